@@ -0,0 +1,398 @@
+//! Allowlist for the generic `admin_api_request` proxy command.
+//!
+//! `validate_admin_api_path` only guards against traversal and keeps calls
+//! under `/api/pos/`; this module decides which of those paths the renderer
+//! may reach through the generic proxy, with which methods, and whether a
+//! body may be sent. The built-in table lives here; the admin dashboard can
+//! extend it with read-only (GET, no body) prefixes through a signed list
+//! delivered with the terminal settings, so a new read-only endpoint does
+//! not need a POS release.
+//!
+//! The extension envelope is `{ "payload": "<json>", "signature": "<b64>" }`
+//! where `signature` is HMAC-SHA256 of the exact `payload` text keyed with
+//! the terminal's POS API key. It is stored as received and verified again
+//! on every load, so a hand-edited local setting is ignored.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use base64::Engine as _;
+use hmac::{Hmac, Mac};
+use rusqlite::Connection;
+use serde_json::Value;
+use sha2::Sha256;
+
+use crate::db;
+
+pub const SETTINGS_CATEGORY: &str = "admin_proxy";
+pub const EXTENSIONS_KEY: &str = "allowlist_extensions";
+
+/// Requests allowed per path within `RATE_LIMIT_WINDOW`.
+const RATE_LIMIT_MAX_REQUESTS: usize = 60;
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+/// Extension entries beyond this are ignored.
+const MAX_EXTENSION_ENTRIES: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllowlistEntry {
+    pub prefix: String,
+    pub methods: Vec<String>,
+    pub body_allowed: bool,
+}
+
+/// Built-in allowlist: `(path prefix, methods, body permitted)`.
+const BUILTIN_ALLOWLIST: &[(&str, &[&str], bool)] = &[
+    ("/api/health", &["GET"], false),
+    ("/api/menu/combos", &["GET"], false),
+    ("/api/pos/analytics", &["GET"], false),
+    (
+        "/api/pos/appointments",
+        &["GET", "POST", "PATCH", "DELETE"],
+        true,
+    ),
+    ("/api/pos/coupons", &["GET"], false),
+    ("/api/pos/customers", &["GET", "POST", "PATCH", "PUT"], true),
+    ("/api/pos/delivery-zones", &["GET"], false),
+    ("/api/pos/drive-through", &["GET", "PATCH"], true),
+    ("/api/pos/housekeeping", &["GET", "PATCH"], true),
+    ("/api/pos/integrations", &["GET"], false),
+    ("/api/pos/inventory", &["GET", "POST", "PATCH"], true),
+    ("/api/pos/orders", &["GET", "POST", "PATCH"], true),
+    ("/api/pos/products", &["GET"], false),
+    (
+        "/api/pos/reservations",
+        &["GET", "POST", "PATCH", "DELETE"],
+        true,
+    ),
+    ("/api/pos/rooms", &["GET", "PATCH"], true),
+    ("/api/pos/services", &["GET"], false),
+    ("/api/pos/staff-directory", &["GET"], false),
+    ("/api/pos/staff-schedule", &["GET"], false),
+    ("/api/pos/suppliers", &["GET"], false),
+    ("/api/pos/table-sessions", &["GET", "POST", "PATCH"], true),
+    ("/api/pos/tables", &["GET", "PATCH"], true),
+    ("/api/pos/z-report/history", &["GET"], false),
+];
+
+pub fn builtin_allowlist() -> Vec<AllowlistEntry> {
+    BUILTIN_ALLOWLIST
+        .iter()
+        .map(|(prefix, methods, body_allowed)| AllowlistEntry {
+            prefix: prefix.to_string(),
+            methods: methods.iter().map(|m| m.to_string()).collect(),
+            body_allowed: *body_allowed,
+        })
+        .collect()
+}
+
+/// A rejection the renderer can branch on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyRejection {
+    pub error_code: &'static str,
+    pub error: String,
+}
+
+impl ProxyRejection {
+    fn new(error_code: &'static str, error: impl Into<String>) -> Self {
+        Self {
+            error_code,
+            error: error.into(),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "success": false,
+            "errorCode": self.error_code,
+            "error": self.error,
+        })
+    }
+}
+
+/// Path without its query string.
+pub fn path_only(path: &str) -> &str {
+    path.split(['?', '#']).next().unwrap_or(path)
+}
+
+/// Segment-aware prefix match: `/api/pos/orders` covers `/api/pos/orders`
+/// and `/api/pos/orders/...` but not `/api/pos/orders-archive`.
+fn prefix_matches(prefix: &str, path: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Check a request against the allowlist. The longest matching prefix wins so
+/// a narrow entry can be stricter than a broader one.
+pub fn check_request(
+    allowlist: &[AllowlistEntry],
+    path: &str,
+    method: &str,
+    has_body: bool,
+) -> Result<(), ProxyRejection> {
+    crate::validate_admin_api_path(path).map_err(|e| ProxyRejection::new("invalid_path", e))?;
+    let bare = path_only(path);
+    let entry = allowlist
+        .iter()
+        .filter(|entry| prefix_matches(&entry.prefix, bare))
+        .max_by_key(|entry| entry.prefix.len())
+        .ok_or_else(|| {
+            ProxyRejection::new(
+                "path_not_allowed",
+                format!("{bare} is not available through the admin proxy"),
+            )
+        })?;
+    if !entry.methods.iter().any(|m| m.eq_ignore_ascii_case(method)) {
+        return Err(ProxyRejection::new(
+            "method_not_allowed",
+            format!("{method} is not allowed for {bare}"),
+        ));
+    }
+    if has_body && !entry.body_allowed {
+        return Err(ProxyRejection::new(
+            "body_not_allowed",
+            format!("{bare} does not accept a request body"),
+        ));
+    }
+    Ok(())
+}
+
+/// Verify an extension envelope and return its read-only entries. Anything
+/// that is not a GET-only prefix under `/api/pos/` is dropped.
+pub fn verify_extensions(envelope: &Value, api_key: &str) -> Result<Vec<AllowlistEntry>, String> {
+    let payload = envelope
+        .get("payload")
+        .and_then(Value::as_str)
+        .ok_or("Allowlist extension has no payload")?;
+    let signature = envelope
+        .get("signature")
+        .and_then(Value::as_str)
+        .and_then(|s| {
+            base64::engine::general_purpose::STANDARD
+                .decode(s.trim())
+                .ok()
+        })
+        .ok_or("Allowlist extension has no valid signature")?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(api_key.as_bytes())
+        .map_err(|e| format!("Allowlist extension key: {e}"))?;
+    mac.update(payload.as_bytes());
+    mac.verify_slice(&signature)
+        .map_err(|_| "Allowlist extension signature mismatch".to_string())?;
+
+    let parsed: Value =
+        serde_json::from_str(payload).map_err(|e| format!("Allowlist extension payload: {e}"))?;
+    let entries = parsed
+        .get("entries")
+        .or(Some(&parsed))
+        .and_then(Value::as_array)
+        .ok_or("Allowlist extension payload has no entries")?;
+    Ok(entries
+        .iter()
+        .filter_map(|entry| entry.as_str().or_else(|| entry.get("prefix")?.as_str()))
+        .map(|prefix| prefix.trim().trim_end_matches('/').to_string())
+        .filter(|prefix| {
+            prefix.starts_with("/api/pos/")
+                && crate::validate_admin_api_path(prefix).is_ok()
+                && !prefix.contains(['?', '#'])
+        })
+        .take(MAX_EXTENSION_ENTRIES)
+        .map(|prefix| AllowlistEntry {
+            prefix,
+            methods: vec!["GET".to_string()],
+            body_allowed: false,
+        })
+        .collect())
+}
+
+/// Store the envelope delivered with the terminal settings once it verifies.
+/// Returns the number of accepted entries.
+pub fn store_extensions(conn: &Connection, envelope: &Value) -> Result<usize, String> {
+    let api_key = crate::storage::get_credential("pos_api_key")
+        .map(|raw| crate::api::extract_api_key_from_connection_string(&raw).unwrap_or(raw))
+        .ok_or("Terminal not configured: missing API key")?;
+    let entries = verify_extensions(envelope, &api_key)?;
+    db::set_setting(
+        conn,
+        SETTINGS_CATEGORY,
+        EXTENSIONS_KEY,
+        &envelope.to_string(),
+    )?;
+    Ok(entries.len())
+}
+
+/// Built-in entries plus any verified extensions.
+pub fn effective_allowlist(conn: &Connection) -> Vec<AllowlistEntry> {
+    let mut allowlist = builtin_allowlist();
+    let stored = db::get_setting(conn, SETTINGS_CATEGORY, EXTENSIONS_KEY)
+        .and_then(|raw| serde_json::from_str::<Value>(&raw).ok());
+    let api_key = crate::storage::get_credential("pos_api_key")
+        .map(|raw| crate::api::extract_api_key_from_connection_string(&raw).unwrap_or(raw));
+    if let (Some(envelope), Some(api_key)) = (stored, api_key) {
+        match verify_extensions(&envelope, &api_key) {
+            Ok(extensions) => allowlist.extend(extensions),
+            Err(e) => tracing::warn!(error = %e, "Ignoring stored admin proxy allowlist extension"),
+        }
+    }
+    allowlist
+}
+
+/// Sliding-window limiter keyed by path (without query).
+#[derive(Default)]
+pub struct PathRateLimiter {
+    hits: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl PathRateLimiter {
+    pub fn check(&self, path: &str, now: Instant) -> Result<(), ProxyRejection> {
+        let mut hits = self.hits.lock().unwrap_or_else(|e| e.into_inner());
+        hits.retain(|_, window| {
+            window
+                .back()
+                .is_some_and(|last| now.saturating_duration_since(*last) < RATE_LIMIT_WINDOW)
+        });
+        let window = hits.entry(path_only(path).to_string()).or_default();
+        while window
+            .front()
+            .is_some_and(|first| now.saturating_duration_since(*first) >= RATE_LIMIT_WINDOW)
+        {
+            window.pop_front();
+        }
+        if window.len() >= RATE_LIMIT_MAX_REQUESTS {
+            let retry_in = window
+                .front()
+                .map(|first| {
+                    RATE_LIMIT_WINDOW.saturating_sub(now.saturating_duration_since(*first))
+                })
+                .unwrap_or(RATE_LIMIT_WINDOW);
+            return Err(ProxyRejection::new(
+                "rate_limited",
+                format!(
+                    "Too many requests to {}; retry in {}s",
+                    path_only(path),
+                    retry_in.as_secs().max(1)
+                ),
+            ));
+        }
+        window.push_back(now);
+        Ok(())
+    }
+}
+
+pub fn rate_limiter() -> &'static PathRateLimiter {
+    static LIMITER: OnceLock<PathRateLimiter> = OnceLock::new();
+    LIMITER.get_or_init(PathRateLimiter::default)
+}
+
+/// HTTP status carried in an `api::fetch_from_admin` error (`"... (HTTP 404)"`).
+pub fn status_from_error(error: &str) -> Option<u16> {
+    let start = error.find("(HTTP ")? + "(HTTP ".len();
+    error.get(start..start + 3)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(payload: &str, key: &str) -> Option<Vec<u8>> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).ok()?;
+        mac.update(payload.as_bytes());
+        Some(mac.finalize().into_bytes().to_vec())
+    }
+
+    fn envelope(payload: &str, key: &str) -> Value {
+        serde_json::json!({
+            "payload": payload,
+            "signature": base64::engine::general_purpose::STANDARD.encode(sign(payload, key).unwrap()),
+        })
+    }
+
+    #[test]
+    fn allowlist_enforces_prefix_method_and_body() {
+        let allowlist = builtin_allowlist();
+        assert!(check_request(&allowlist, "/api/pos/coupons?active=true", "GET", false).is_ok());
+        assert!(check_request(&allowlist, "/api/pos/orders/o-1", "PATCH", true).is_ok());
+        assert_eq!(
+            check_request(&allowlist, "/api/pos/coupons", "POST", false)
+                .unwrap_err()
+                .error_code,
+            "method_not_allowed"
+        );
+        assert_eq!(
+            check_request(&allowlist, "/api/pos/products", "GET", true)
+                .unwrap_err()
+                .error_code,
+            "body_not_allowed"
+        );
+        assert_eq!(
+            check_request(&allowlist, "/api/pos/orders-archive", "GET", false)
+                .unwrap_err()
+                .error_code,
+            "path_not_allowed"
+        );
+        assert_eq!(
+            check_request(&allowlist, "/api/pos/settings/t-1", "GET", false)
+                .unwrap_err()
+                .error_code,
+            "path_not_allowed"
+        );
+        assert_eq!(
+            check_request(&allowlist, "/api/pos/../admin", "GET", false)
+                .unwrap_err()
+                .error_code,
+            "invalid_path"
+        );
+    }
+
+    #[test]
+    fn extensions_require_a_valid_signature_and_stay_read_only() {
+        let payload = r#"{"entries":["/api/pos/gift-cards/","/api/admin/users",{"prefix":"/api/pos/reports"}]}"#;
+        let entries = verify_extensions(&envelope(payload, "k-123"), "k-123").unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|e| e.prefix.as_str())
+                .collect::<Vec<_>>(),
+            vec!["/api/pos/gift-cards", "/api/pos/reports"]
+        );
+        assert!(entries
+            .iter()
+            .all(|e| e.methods == ["GET"] && !e.body_allowed));
+        assert!(verify_extensions(&envelope(payload, "other-key"), "k-123").is_err());
+
+        let mut tampered = envelope(payload, "k-123");
+        tampered["payload"] = Value::String(r#"{"entries":["/api/pos/payments"]}"#.into());
+        assert!(verify_extensions(&tampered, "k-123").is_err());
+    }
+
+    #[test]
+    fn rate_limiter_is_per_path_and_slides() {
+        let limiter = PathRateLimiter::default();
+        let t0 = Instant::now();
+        for _ in 0..RATE_LIMIT_MAX_REQUESTS {
+            assert!(limiter.check("/api/pos/coupons?page=1", t0).is_ok());
+        }
+        assert_eq!(
+            limiter
+                .check("/api/pos/coupons?page=2", t0)
+                .unwrap_err()
+                .error_code,
+            "rate_limited"
+        );
+        assert!(limiter.check("/api/pos/products", t0).is_ok());
+        assert!(limiter
+            .check("/api/pos/coupons", t0 + RATE_LIMIT_WINDOW)
+            .is_ok());
+    }
+
+    #[test]
+    fn status_is_read_from_admin_errors() {
+        assert_eq!(
+            status_from_error("Admin dashboard endpoint not found (HTTP 404)"),
+            Some(404)
+        );
+        assert_eq!(status_from_error("Cannot reach admin dashboard"), None);
+    }
+}
@@ -0,0 +1,476 @@
+//! Allergy flags on orders and item lines.
+//!
+//! Allergy warnings used to be typed into `special_instructions` and got
+//! lost among "no onions" notes. Orders and item lines now carry an
+//! `allergyFlags` array of allergen keys, checked against the
+//! `orders.allergen_list` setting (a JSON array or comma-separated list,
+//! defaulting to the 14 EU allergens). Item flags live in the items JSON;
+//! order flags in `orders.allergy_flags`. The kitchen ticket prints them
+//! emphasised, each item's flags directly under that item.
+//!
+//! Flags added after the kitchen already has its ticket go through [`plan`]
+//! and [`save`] and print as an "ALLERGY UPDATE" amendment naming only the
+//! affected items ([`amendment_payload`]), not a full reprint.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+
+use crate::courses;
+
+pub const SETTING_CATEGORY: &str = "orders";
+pub const SETTING_KEY: &str = "allergen_list";
+
+/// EU Regulation 1169/2011 Annex II allergens.
+pub const DEFAULT_ALLERGENS: &[&str] = &[
+    "gluten",
+    "crustaceans",
+    "eggs",
+    "fish",
+    "peanuts",
+    "soy",
+    "milk",
+    "nuts",
+    "celery",
+    "mustard",
+    "sesame",
+    "sulphites",
+    "lupin",
+    "molluscs",
+];
+
+/// Title of an allergy amendment ticket.
+pub const AMENDMENT_HEADER: &str = "ALLERGY UPDATE";
+
+/// Statuses after which the kitchen is done with the order.
+const CLOSED_STATUSES: &[&str] = &[
+    "ready",
+    "served",
+    "out_for_delivery",
+    "delivered",
+    "completed",
+    "cancelled",
+    "canceled",
+    "refunded",
+    "voided",
+];
+
+fn normalize(flag: &str) -> Option<String> {
+    let flag = flag.trim().to_lowercase();
+    (!flag.is_empty()).then_some(flag)
+}
+
+/// Parse an allergen list setting: a JSON array of strings or a
+/// comma-separated list.
+pub fn parse_list(raw: &str) -> Vec<String> {
+    let entries: Vec<String> = match serde_json::from_str::<Value>(raw) {
+        Ok(Value::Array(values)) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        _ => raw.split(',').map(str::to_string).collect(),
+    };
+    let mut list: Vec<String> = Vec::new();
+    for flag in entries.iter().filter_map(|flag| normalize(flag)) {
+        if !list.contains(&flag) {
+            list.push(flag);
+        }
+    }
+    list
+}
+
+/// Allergens staff may flag. An empty or missing setting falls back to
+/// [`DEFAULT_ALLERGENS`].
+pub fn allowed(conn: &Connection) -> Vec<String> {
+    let configured = crate::db::get_setting(conn, SETTING_CATEGORY, SETTING_KEY)
+        .map(|raw| parse_list(&raw))
+        .unwrap_or_default();
+    if configured.is_empty() {
+        DEFAULT_ALLERGENS
+            .iter()
+            .map(|flag| flag.to_string())
+            .collect()
+    } else {
+        configured
+    }
+}
+
+/// Normalise `flags` and check each against `allowed`. Duplicates are
+/// dropped; any flag not on the list fails the whole call.
+pub fn validate(flags: &[String], allowed: &[String]) -> Result<Vec<String>, String> {
+    let mut valid: Vec<String> = Vec::new();
+    let mut unknown: Vec<String> = Vec::new();
+    for flag in flags.iter().filter_map(|flag| normalize(flag)) {
+        if !allowed.contains(&flag) {
+            unknown.push(flag);
+        } else if !valid.contains(&flag) {
+            valid.push(flag);
+        }
+    }
+    if unknown.is_empty() {
+        Ok(valid)
+    } else {
+        Err(format!(
+            "Unknown allergen(s): {}. Allowed: {}",
+            unknown.join(", "),
+            allowed.join(", ")
+        ))
+    }
+}
+
+/// Flags on an item line or order object, under either spelling.
+pub fn flags(value: &Value) -> Vec<String> {
+    let raw = value
+        .get("allergyFlags")
+        .or_else(|| value.get("allergy_flags"));
+    let mut flags: Vec<String> = Vec::new();
+    for flag in raw
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .filter_map(normalize)
+    {
+        if !flags.contains(&flag) {
+            flags.push(flag);
+        }
+    }
+    flags
+}
+
+/// Order flags as stored in `orders.allergy_flags`.
+pub fn parse_stored(raw: Option<&str>) -> Vec<String> {
+    raw.and_then(|raw| serde_json::from_str::<Value>(raw).ok())
+        .map(|value| flags(&json!({ "allergyFlags": value })))
+        .unwrap_or_default()
+}
+
+/// Plain-text form of a flag list, e.g. `[ALLERGY: GLUTEN, MILK]`.
+pub fn ticket_label(flags: &[String]) -> Option<String> {
+    if flags.is_empty() {
+        return None;
+    }
+    let names: Vec<String> = flags.iter().map(|flag| flag.to_uppercase()).collect();
+    Some(format!("[ALLERGY: {}]", names.join(", ")))
+}
+
+/// Kitchen ticket job payload for an allergy amendment.
+pub fn amendment_payload(item_indexes: &[usize]) -> Value {
+    json!({
+        "allergyUpdate": true,
+        "courseHeader": AMENDMENT_HEADER,
+        "itemIndexes": item_indexes,
+    })
+}
+
+/// Item lines an amendment ticket prints, if the payload is one.
+pub fn amendment_items(payload: Option<&Value>) -> Option<Vec<usize>> {
+    let payload = payload?;
+    if payload.get("allergyUpdate").and_then(Value::as_bool) != Some(true) {
+        return None;
+    }
+    Some(
+        payload
+            .get("itemIndexes")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_u64)
+            .map(|index| index as usize)
+            .collect(),
+    )
+}
+
+/// Flags to add: order-level and per item line (by index into the items
+/// JSON). Already validated against the allergen list.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FlagUpdate {
+    pub order: Vec<String>,
+    pub items: Vec<(usize, Vec<String>)>,
+}
+
+/// Result of [`plan`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Applied {
+    pub items: Vec<Value>,
+    pub order_flags: Vec<String>,
+    /// Order flags that were not set before.
+    pub added_order_flags: Vec<String>,
+    /// Item lines that gained at least one flag.
+    pub changed_items: Vec<usize>,
+    /// Changed item lines the kitchen has already been sent: every line of a
+    /// plain order, or lines of fired courses on a course-aware order.
+    pub sent_items: Vec<usize>,
+}
+
+impl Applied {
+    pub fn changed(&self) -> bool {
+        !self.added_order_flags.is_empty() || !self.changed_items.is_empty()
+    }
+}
+
+/// Reject orders the kitchen is done with: closed statuses, and
+/// course-aware orders whose every course has been fired.
+pub fn check_unfired(conn: &Connection, order_id: &str) -> Result<(), String> {
+    let status: String = conn
+        .query_row(
+            "SELECT LOWER(COALESCE(status, '')) FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("load order status: {e}"))?
+        .ok_or_else(|| format!("Order not found: {order_id}"))?;
+    if CLOSED_STATUSES.contains(&status.as_str()) {
+        return Err(format!(
+            "Order is {status}; allergy flags can only be added before it is ready"
+        ));
+    }
+    let courses = courses::status(conn, order_id)?;
+    let course_aware = courses.iter().any(|state| state.course > 1);
+    if course_aware
+        && courses
+            .iter()
+            .filter(|state| state.item_count > 0)
+            .all(courses::CourseState::is_fired)
+    {
+        return Err("Every course of this order has already been fired".to_string());
+    }
+    Ok(())
+}
+
+/// Merge `update` into the order's current flags without writing anything.
+/// Flags are only ever added, never removed.
+pub fn plan(conn: &Connection, order_id: &str, update: &FlagUpdate) -> Result<Applied, String> {
+    let (items_json, stored_flags): (String, Option<String>) = conn
+        .query_row(
+            "SELECT COALESCE(items, '[]'), allergy_flags FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("load order: {e}"))?
+        .ok_or_else(|| format!("Order not found: {order_id}"))?;
+    let mut items: Vec<Value> = serde_json::from_str::<Value>(&items_json)
+        .ok()
+        .and_then(|value| value.as_array().cloned())
+        .unwrap_or_default();
+
+    let mut order_flags = parse_stored(stored_flags.as_deref());
+    let mut added_order_flags = Vec::new();
+    for flag in &update.order {
+        if !order_flags.contains(flag) {
+            order_flags.push(flag.clone());
+            added_order_flags.push(flag.clone());
+        }
+    }
+
+    let mut changed_items = Vec::new();
+    for (index, new_flags) in &update.items {
+        let item = items
+            .get_mut(*index)
+            .ok_or_else(|| format!("Order has no item line {index}"))?;
+        let mut item_flags = flags(item);
+        let before = item_flags.len();
+        for flag in new_flags {
+            if !item_flags.contains(flag) {
+                item_flags.push(flag.clone());
+            }
+        }
+        if item_flags.len() == before {
+            continue;
+        }
+        if let Some(object) = item.as_object_mut() {
+            object.remove("allergy_flags");
+            object.insert("allergyFlags".to_string(), json!(item_flags));
+        }
+        if !changed_items.contains(index) {
+            changed_items.push(*index);
+        }
+    }
+    changed_items.sort_unstable();
+
+    let courses = courses::status(conn, order_id)?;
+    let course_aware = courses.iter().any(|state| state.course > 1);
+    let sent_items = changed_items
+        .iter()
+        .copied()
+        .filter(|index| {
+            !course_aware || {
+                let course = courses::item_course(&items[*index]);
+                courses
+                    .iter()
+                    .any(|state| state.course == course && state.is_fired())
+            }
+        })
+        .collect();
+
+    Ok(Applied {
+        items,
+        order_flags,
+        added_order_flags,
+        changed_items,
+        sent_items,
+    })
+}
+
+/// Write a [`plan`] back: the items JSON and `allergy_flags`, marking the
+/// order for sync.
+pub fn save(conn: &Connection, order_id: &str, applied: &Applied, now: &str) -> Result<(), String> {
+    let items_json =
+        serde_json::to_string(&applied.items).map_err(|e| format!("serialize items: {e}"))?;
+    let flags_json = serde_json::to_string(&applied.order_flags)
+        .map_err(|e| format!("serialize allergy flags: {e}"))?;
+    conn.execute(
+        "UPDATE orders
+         SET items = ?1, allergy_flags = ?2, sync_status = 'pending', updated_at = ?3
+         WHERE id = ?4",
+        params![items_json, flags_json, now, order_id],
+    )
+    .map_err(|e| format!("update allergy flags: {e}"))?;
+    Ok(())
+}
+
+/// Whether a kitchen ticket for the order has already gone to the printer.
+/// A ticket still queued renders the new flags itself.
+pub fn kitchen_ticket_sent(conn: &Connection, order_id: &str) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM print_jobs
+         WHERE entity_type = 'kitchen_ticket' AND entity_id = ?1
+           AND status IN ('printing', 'printed')
+         LIMIT 1",
+        params![order_id],
+        |_| Ok(()),
+    )
+    .optional()
+    .ok()
+    .flatten()
+    .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn insert_order(conn: &Connection, order_id: &str, status: &str, items: Value) {
+        conn.execute(
+            "INSERT INTO orders (id, items, total_amount, status, order_type, created_at, updated_at)
+             VALUES (?1, ?2, 20.0, ?3, 'dine-in', datetime('now'), datetime('now'))",
+            params![order_id, items.to_string(), status],
+        )
+        .unwrap();
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn allergen_list_setting_and_validation() {
+        let conn = test_conn();
+        assert_eq!(allowed(&conn).len(), DEFAULT_ALLERGENS.len());
+
+        crate::db::set_setting(
+            &conn,
+            SETTING_CATEGORY,
+            SETTING_KEY,
+            " Gluten, milk,,gluten",
+        )
+        .unwrap();
+        assert_eq!(allowed(&conn), strings(&["gluten", "milk"]));
+        crate::db::set_setting(&conn, SETTING_CATEGORY, SETTING_KEY, r#"["Peanuts"]"#).unwrap();
+        assert_eq!(allowed(&conn), strings(&["peanuts"]));
+
+        let list = strings(&["gluten", "milk"]);
+        assert_eq!(
+            validate(&strings(&["MILK ", "milk"]), &list).unwrap(),
+            strings(&["milk"])
+        );
+        let err = validate(&strings(&["milk", "kiwi"]), &list).unwrap_err();
+        assert!(err.contains("kiwi"), "{err}");
+        assert_eq!(
+            ticket_label(&strings(&["gluten", "milk"])).as_deref(),
+            Some("[ALLERGY: GLUTEN, MILK]")
+        );
+        assert_eq!(
+            amendment_items(Some(&amendment_payload(&[0, 2]))),
+            Some(vec![0, 2])
+        );
+        assert_eq!(amendment_items(Some(&json!({ "courses": [2] }))), None);
+    }
+
+    #[test]
+    fn plan_only_adds_flags_and_reports_sent_lines() {
+        let conn = test_conn();
+        insert_order(
+            &conn,
+            "ord-1",
+            "preparing",
+            json!([
+                { "name": "Pasta", "quantity": 1, "price": 9.0, "allergyFlags": ["gluten"] },
+                { "name": "Salad", "quantity": 1, "price": 6.0 }
+            ]),
+        );
+        check_unfired(&conn, "ord-1").unwrap();
+        let update = FlagUpdate {
+            order: strings(&["peanuts"]),
+            items: vec![(0, strings(&["gluten", "milk"])), (1, strings(&["sesame"]))],
+        };
+        let applied = plan(&conn, "ord-1", &update).unwrap();
+        assert_eq!(applied.added_order_flags, strings(&["peanuts"]));
+        assert_eq!(applied.changed_items, vec![0, 1]);
+        assert_eq!(applied.sent_items, vec![0, 1]);
+        assert_eq!(flags(&applied.items[0]), strings(&["gluten", "milk"]));
+        save(&conn, "ord-1", &applied, "2026-10-15T12:00:00Z").unwrap();
+
+        let (items, stored, sync_status): (String, String, String) = conn
+            .query_row(
+                "SELECT items, allergy_flags, sync_status FROM orders WHERE id = 'ord-1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        let items: Value = serde_json::from_str(&items).unwrap();
+        assert_eq!(items[1]["allergyFlags"], json!(["sesame"]));
+        assert_eq!(parse_stored(Some(&stored)), strings(&["peanuts"]));
+        assert_eq!(sync_status, "pending");
+
+        assert!(!plan(&conn, "ord-1", &update).unwrap().changed());
+
+        conn.execute("UPDATE orders SET status = 'ready' WHERE id = 'ord-1'", [])
+            .unwrap();
+        assert!(check_unfired(&conn, "ord-1").is_err());
+    }
+
+    #[test]
+    fn course_aware_orders_only_amend_fired_courses() {
+        let conn = test_conn();
+        insert_order(
+            &conn,
+            "ord-courses",
+            "confirmed",
+            json!([
+                { "name": "Soup", "quantity": 1, "price": 5.0 },
+                { "name": "Steak", "quantity": 1, "price": 15.0, "course": 2 }
+            ]),
+        );
+        courses::record_fire(&conn, "ord-courses", &[1], None, false).unwrap();
+        let update = FlagUpdate {
+            order: Vec::new(),
+            items: vec![(0, strings(&["celery"])), (1, strings(&["mustard"]))],
+        };
+        let applied = plan(&conn, "ord-courses", &update).unwrap();
+        assert_eq!(applied.changed_items, vec![0, 1]);
+        assert_eq!(applied.sent_items, vec![0]);
+
+        courses::record_fire(&conn, "ord-courses", &[2], None, false).unwrap();
+        let err = check_unfired(&conn, "ord-courses").unwrap_err();
+        assert!(err.contains("already been fired"), "{err}");
+    }
+}
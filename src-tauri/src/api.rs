@@ -6,13 +6,20 @@
 use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
 use base64::Engine as _;
 use reqwest::{Client, Method, StatusCode};
+use serde::Serialize;
 use serde_json::Value;
-use std::sync::OnceLock;
+use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, Instant};
+use tauri::Emitter;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
-/// Default timeout for API requests (30 seconds).
-const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Request timeout for health checks and other cheap probes.
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(3);
+/// Request timeout for sync endpoints, which move whole batches.
+const SYNC_TIMEOUT: Duration = Duration::from_secs(30);
+/// Request timeout for everything else.
+const STANDARD_TIMEOUT: Duration = Duration::from_secs(15);
 
 /// Shared reqwest::Client — holds a connection pool, TLS session cache,
 /// and DNS cache. Previously a fresh Client was built on every call to
@@ -22,6 +29,11 @@ const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 /// but referencing the singleton directly avoids even that overhead. Each
 /// caller sets its own timeout via `RequestBuilder::timeout()` rather
 /// than the client-level default.
+///
+/// Every outbound HTTP call in the app (admin API, Supabase reads, parity
+/// queue, fiscal dispatch, updater and geo-IP probes) goes through
+/// [`shared_client`], so a slow link pays the TLS handshake once per host
+/// rather than once per request.
 /// Wave 9 H2: the shared HTTP client's builder can fail — rarely (typically
 /// only when the system TLS certificate store is unavailable on the very
 /// first call), but a `.expect()` here used to panic the entire process
@@ -33,7 +45,7 @@ const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 /// propagate the error instead of panicking.
 static HTTP_CLIENT: OnceLock<Result<Client, String>> = OnceLock::new();
 
-fn shared_client() -> Result<&'static Client, String> {
+pub(crate) fn shared_client() -> Result<&'static Client, String> {
     HTTP_CLIENT
         .get_or_init(|| {
             // Wave 11 L: explicit connect timeout prevents a stalled TCP
@@ -70,9 +82,6 @@ fn is_local_plain_http_url(url: &str) -> bool {
         || lower.starts_with("http://[::1]")
 }
 
-/// Timeout used specifically for the lightweight connectivity test.
-const CONNECTIVITY_TIMEOUT: Duration = Duration::from_secs(10);
-
 // ---------------------------------------------------------------------------
 // URL normalisation
 // ---------------------------------------------------------------------------
@@ -166,6 +175,22 @@ pub fn extract_terminal_id_from_connection_string(raw: &str) -> Option<String> {
         .filter(|s| !s.is_empty())
 }
 
+/// Expiry stamped into a connection string by the admin dashboard, either as
+/// `exp` (unix seconds) or `expiresAt` (RFC 3339). Codes without one never
+/// expire.
+pub fn extract_expiry_from_connection_string(raw: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let payload = decode_connection_string_payload(raw)?;
+    if let Some(secs) = payload.get("exp").and_then(Value::as_i64) {
+        return chrono::DateTime::from_timestamp(secs, 0);
+    }
+    payload
+        .get("expiresAt")
+        .or_else(|| payload.get("expires_at"))
+        .and_then(Value::as_str)
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s.trim()).ok())
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+}
+
 #[cfg(test)]
 fn extract_terminal_id_from_body(body: Option<&Value>) -> Option<String> {
     let body = body?;
@@ -258,8 +283,12 @@ pub async fn test_connectivity(admin_url: &str, api_key: &str) -> ConnectivityRe
 
     let resp = match client
         .get(&health_url)
-        .timeout(CONNECTIVITY_TIMEOUT)
+        .timeout(RouteClass::Health.timeout())
         .header("X-POS-API-Key", resolved_api_key)
+        .header(
+            crate::correlation::HEADER,
+            crate::correlation::current_or_new(),
+        )
         .send()
         .await
     {
@@ -278,6 +307,9 @@ pub async fn test_connectivity(admin_url: &str, api_key: &str) -> ConnectivityRe
 
     if status.is_success() {
         info!(latency_ms = latency, "connectivity test passed");
+        // A passing health check proves the admin is back; close the breaker
+        // instead of waiting for the next half-open probe.
+        publish_breaker_transition(&url, breaker_for(&url).record_success());
         ConnectivityResult {
             success: true,
             latency_ms: Some(latency),
@@ -292,6 +324,307 @@ pub async fn test_connectivity(admin_url: &str, api_key: &str) -> ConnectivityRe
     }
 }
 
+// ---------------------------------------------------------------------------
+// Retry policy and circuit breaker
+// ---------------------------------------------------------------------------
+
+/// Timeout class of an admin route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteClass {
+    Health,
+    Sync,
+    Standard,
+}
+
+impl RouteClass {
+    pub fn for_path(path: &str) -> Self {
+        let path = path.split('?').next().unwrap_or(path).trim_end_matches('/');
+        if path.ends_with("/health") || path.ends_with("/probe") {
+            RouteClass::Health
+        } else if path.contains("/sync") {
+            RouteClass::Sync
+        } else {
+            RouteClass::Standard
+        }
+    }
+
+    pub fn timeout(self) -> Duration {
+        match self {
+            RouteClass::Health => HEALTH_TIMEOUT,
+            RouteClass::Sync => SYNC_TIMEOUT,
+            RouteClass::Standard => STANDARD_TIMEOUT,
+        }
+    }
+}
+
+/// Retries after the first attempt; the delay before retry `n` is
+/// `RETRY_BASE_DELAY_MS * 2^(n-1)` plus up to the same again in jitter.
+const MAX_RETRIES: u32 = 3;
+const RETRY_BASE_DELAY_MS: u64 = 250;
+
+/// Consecutive failed requests (after retries) that open the breaker.
+const BREAKER_FAILURE_THRESHOLD: u32 = 5;
+/// How long an open breaker fails fast before letting one probe through.
+const BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Why a single attempt failed, as far as the retry policy cares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttemptFailure {
+    /// The connection was never established (refused, DNS, connect timeout).
+    Connect,
+    /// The request may have reached the server (read timeout, reset, ...).
+    Transport,
+    Status(StatusCode),
+}
+
+impl AttemptFailure {
+    fn from_error(err: &reqwest::Error) -> Self {
+        if err.is_connect() {
+            AttemptFailure::Connect
+        } else {
+            AttemptFailure::Transport
+        }
+    }
+}
+
+/// GETs are idempotent and retry on any transport failure or gateway error.
+/// Mutations only retry when the connection was never established, so the
+/// admin cannot have applied them already.
+fn should_retry(idempotent: bool, failure: AttemptFailure) -> bool {
+    match failure {
+        AttemptFailure::Connect => true,
+        AttemptFailure::Transport => idempotent,
+        AttemptFailure::Status(status) => idempotent && matches!(status.as_u16(), 502..=504),
+    }
+}
+
+fn retry_delay(retry: u32) -> Duration {
+    let base = RETRY_BASE_DELAY_MS << retry.saturating_sub(1).min(8);
+    // Same nanosecond mix as `sync_queue::compute_next_retry_delay_ms`.
+    let nanos = chrono::Utc::now().timestamp_subsec_nanos() as u64;
+    let jitter = nanos.wrapping_mul(0x9E37_79B9_7F4A_7C15) % base;
+    Duration::from_millis(base + jitter)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+/// Breaker state as reported to the renderer.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreakerSnapshot {
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    pub opened_at: Option<String>,
+    pub retry_in_secs: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+impl BreakerSnapshot {
+    fn closed() -> Self {
+        Self {
+            state: BreakerState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            retry_in_secs: None,
+            last_error: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct BreakerInner {
+    open: bool,
+    half_open: bool,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    opened_at_wall: Option<String>,
+    probe_started_at: Option<Instant>,
+    last_error: Option<String>,
+}
+
+/// Circuit breaker for one admin dashboard. After
+/// `BREAKER_FAILURE_THRESHOLD` consecutive failures it opens and requests
+/// fail immediately with `circuit_open`; once `BREAKER_COOLDOWN` has passed a
+/// single half-open probe is let through, and its outcome closes or reopens
+/// the breaker. Only transport failures and 5xx responses count as failures.
+///
+/// `record_*` return `Some(degraded)` when the breaker crosses between closed
+/// and open, which is what the `admin_api_degraded` / `admin_api_recovered`
+/// events report.
+#[derive(Default)]
+pub struct CircuitBreaker {
+    inner: Mutex<BreakerInner>,
+}
+
+impl CircuitBreaker {
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn retry_in(inner: &BreakerInner, now: Instant) -> Duration {
+        let since = inner.probe_started_at.or(inner.opened_at).unwrap_or(now);
+        BREAKER_COOLDOWN.saturating_sub(now.saturating_duration_since(since))
+    }
+
+    /// Admit a request, or fail fast with a `circuit_open` error. A probe
+    /// that never reports back (e.g. its future was dropped) is replaced by
+    /// a new one after another cooldown.
+    fn admit(&self, now: Instant) -> Result<(), String> {
+        let mut inner = self.lock();
+        if !inner.open {
+            return Ok(());
+        }
+        let wait = Self::retry_in(&inner, now);
+        if wait.is_zero() {
+            inner.half_open = true;
+            inner.probe_started_at = Some(now);
+            return Ok(());
+        }
+        Err(format!(
+            "circuit_open: Cannot reach admin dashboard after {} consecutive failures; retrying in {}s",
+            inner.consecutive_failures,
+            wait.as_secs().max(1)
+        ))
+    }
+
+    fn record_success(&self) -> Option<bool> {
+        let mut inner = self.lock();
+        let was_open = inner.open;
+        *inner = BreakerInner::default();
+        was_open.then_some(false)
+    }
+
+    fn record_failure(&self, error: &str, now: Instant) -> Option<bool> {
+        let mut inner = self.lock();
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        inner.last_error = Some(error.to_string());
+        if inner.open {
+            // A failed half-open probe restarts the cooldown.
+            if inner.half_open {
+                inner.half_open = false;
+                inner.opened_at = Some(now);
+                inner.probe_started_at = None;
+            }
+            return None;
+        }
+        if inner.consecutive_failures < BREAKER_FAILURE_THRESHOLD {
+            return None;
+        }
+        inner.open = true;
+        inner.opened_at = Some(now);
+        inner.opened_at_wall = Some(chrono::Utc::now().to_rfc3339());
+        Some(true)
+    }
+
+    fn snapshot_at(&self, now: Instant) -> BreakerSnapshot {
+        let inner = self.lock();
+        BreakerSnapshot {
+            state: match (inner.open, inner.half_open) {
+                (false, _) => BreakerState::Closed,
+                (true, false) => BreakerState::Open,
+                (true, true) => BreakerState::HalfOpen,
+            },
+            consecutive_failures: inner.consecutive_failures,
+            opened_at: inner.opened_at_wall.clone(),
+            retry_in_secs: inner.open.then(|| Self::retry_in(&inner, now).as_secs()),
+            last_error: inner.last_error.clone(),
+        }
+    }
+}
+
+/// Breaker crossing between healthy and degraded for one admin URL.
+#[derive(Debug, Clone)]
+struct BreakerTransition {
+    admin_url: String,
+    degraded: bool,
+}
+
+struct BreakerRegistry {
+    breakers: Mutex<std::collections::HashMap<String, std::sync::Arc<CircuitBreaker>>>,
+    transitions: tokio::sync::broadcast::Sender<BreakerTransition>,
+}
+
+static ADMIN_BREAKERS: OnceLock<BreakerRegistry> = OnceLock::new();
+
+fn breaker_registry() -> &'static BreakerRegistry {
+    ADMIN_BREAKERS.get_or_init(|| BreakerRegistry {
+        breakers: Mutex::new(std::collections::HashMap::new()),
+        transitions: tokio::sync::broadcast::channel(16).0,
+    })
+}
+
+/// Breaker for an admin base URL (as returned by `normalize_admin_url`).
+fn breaker_for(base: &str) -> std::sync::Arc<CircuitBreaker> {
+    let mut breakers = breaker_registry()
+        .breakers
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    breakers.entry(base.to_string()).or_default().clone()
+}
+
+fn publish_breaker_transition(base: &str, transition: Option<bool>) {
+    let Some(degraded) = transition else {
+        return;
+    };
+    if degraded {
+        warn!(admin_url = %base, "Admin API circuit opened; failing fast");
+    } else {
+        info!(admin_url = %base, "Admin API circuit closed");
+    }
+    // No receiver just means the event forwarder is not running (tests).
+    let _ = breaker_registry().transitions.send(BreakerTransition {
+        admin_url: base.to_string(),
+        degraded,
+    });
+}
+
+/// Breaker state for the configured admin dashboard, for status payloads.
+pub fn admin_breaker_snapshot() -> BreakerSnapshot {
+    let Some(url) = crate::storage::get_credential("admin_dashboard_url") else {
+        return BreakerSnapshot::closed();
+    };
+    let base = normalize_admin_url(&url);
+    let breakers = breaker_registry()
+        .breakers
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    breakers
+        .get(&base)
+        .map(|breaker| breaker.snapshot_at(Instant::now()))
+        .unwrap_or_else(BreakerSnapshot::closed)
+}
+
+/// Forward breaker transitions to the renderer as `admin_api_degraded` /
+/// `admin_api_recovered`, each carrying the breaker snapshot.
+pub fn start_breaker_event_forwarder(app: tauri::AppHandle, cancel: CancellationToken) {
+    let mut transitions = breaker_registry().transitions.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let transition = tokio::select! {
+                _ = cancel.cancelled() => break,
+                received = transitions.recv() => match received {
+                    Ok(transition) => transition,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                },
+            };
+            let event = if transition.degraded {
+                "admin_api_degraded"
+            } else {
+                "admin_api_recovered"
+            };
+            let snapshot = breaker_for(&transition.admin_url).snapshot_at(Instant::now());
+            let _ = app.emit(event, snapshot);
+        }
+    });
+}
+
 // ---------------------------------------------------------------------------
 // Generic authenticated fetch
 // ---------------------------------------------------------------------------
@@ -300,6 +633,7 @@ pub async fn test_connectivity(admin_url: &str, api_key: &str) -> ConnectivityRe
 ///
 /// `path` should include the leading slash, e.g. `/api/pos/menu/sync`.
 /// `method` is an HTTP verb string: "GET", "POST", "PUT", "PATCH", "DELETE".
+/// In training mode nothing is sent and the response is a simulated success.
 pub async fn fetch_from_admin(
     admin_url: &str,
     api_key: &str,
@@ -307,6 +641,9 @@ pub async fn fetch_from_admin(
     method: &str,
     body: Option<Value>,
 ) -> Result<Value, String> {
+    if crate::training::is_active() {
+        return Ok(crate::training::simulated_success(serde_json::json!({})));
+    }
     let base = normalize_admin_url(admin_url);
     if base.starts_with("http://") && !is_local_plain_http_url(&base) {
         return Err(
@@ -349,27 +686,75 @@ pub async fn fetch_from_admin(
         return Err("Terminal not configured: missing terminal_id".to_string());
     }
 
-    let mut req = client
-        .request(http_method, &full_url)
-        .timeout(DEFAULT_TIMEOUT)
-        .header("X-POS-API-Key", resolved_api_key)
-        .header("x-terminal-id", &terminal_id)
-        .header("Content-Type", "application/json");
-
-    if let Some(b) = body {
-        // If the JavaScript frontend pre-serialized the body via JSON.stringify(),
-        // it arrives as Value::String containing JSON. Parse it back to avoid
-        // double-serialization by reqwest's .json() method.
-        let resolved = if let Value::String(ref s) = b {
+    // If the JavaScript frontend pre-serialized the body via JSON.stringify(),
+    // it arrives as Value::String containing JSON. Parse it back to avoid
+    // double-serialization by reqwest's .json() method.
+    let body = body.map(|b| {
+        if let Value::String(ref s) = b {
             serde_json::from_str::<Value>(s).unwrap_or(b)
         } else {
             b
+        }
+    });
+    let route = RouteClass::for_path(path);
+    let idempotent = matches!(http_method, Method::GET | Method::HEAD);
+    let breaker = breaker_for(&base);
+    breaker.admit(Instant::now())?;
+
+    // Retries reuse the id so the server sees them as one logical request.
+    let correlation_id = crate::correlation::current_or_new();
+    let mut retry = 0;
+    let sent = loop {
+        let mut req = client
+            .request(http_method.clone(), &full_url)
+            .timeout(route.timeout())
+            .header("X-POS-API-Key", resolved_api_key.as_str())
+            .header("x-terminal-id", &terminal_id)
+            .header(crate::correlation::HEADER, correlation_id.as_str())
+            .header("Content-Type", "application/json");
+        if let Some(b) = body.as_ref() {
+            req = req.json(b);
+        }
+
+        let result = req.send().await;
+        let failure = match &result {
+            Ok(resp) if resp.status().is_success() => None,
+            Ok(resp) => Some(AttemptFailure::Status(resp.status())),
+            Err(e) => Some(AttemptFailure::from_error(e)),
         };
-        req = req.json(&resolved);
-    }
+        match failure {
+            Some(failure) if retry < MAX_RETRIES && should_retry(idempotent, failure) => {
+                retry += 1;
+                let delay = retry_delay(retry);
+                warn!(
+                    method = %http_method,
+                    path = %path,
+                    retry,
+                    delay_ms = delay.as_millis() as u64,
+                    failure = ?failure,
+                    "Retrying admin request"
+                );
+                tokio::time::sleep(delay).await;
+            }
+            _ => break result,
+        }
+    };
 
-    let mut resp = req.send().await.map_err(|e| friendly_error(&base, &e))?;
+    let mut resp = match sent {
+        Ok(resp) => resp,
+        Err(e) => {
+            let error = friendly_error(&base, &e);
+            publish_breaker_transition(&base, breaker.record_failure(&error, Instant::now()));
+            return Err(error);
+        }
+    };
     let status = resp.status();
+    let transition = if status.is_server_error() {
+        breaker.record_failure(&status_error(status), Instant::now())
+    } else {
+        breaker.record_success()
+    };
+    publish_breaker_transition(&base, transition);
 
     if !status.is_success() {
         // Preserve validation details for diagnostics and sync queue visibility,
@@ -441,6 +826,8 @@ pub async fn fetch_from_admin(
 mod tests {
     use super::*;
     use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
 
     #[test]
     fn extract_terminal_id_from_body_reads_object_payloads() {
@@ -464,4 +851,126 @@ mod tests {
         );
         assert_eq!(extract_terminal_id_from_body(Some(&Value::Null)), None);
     }
+
+    #[test]
+    fn route_class_and_retry_policy() {
+        assert_eq!(RouteClass::for_path("/api/health"), RouteClass::Health);
+        assert_eq!(
+            RouteClass::for_path("/api/pos/orders/sync?since=1"),
+            RouteClass::Sync
+        );
+        assert_eq!(
+            RouteClass::for_path("/api/pos/coupons"),
+            RouteClass::Standard
+        );
+        assert_eq!(RouteClass::Health.timeout(), Duration::from_secs(3));
+
+        let bad_gateway = AttemptFailure::Status(StatusCode::BAD_GATEWAY);
+        assert!(should_retry(true, bad_gateway));
+        assert!(should_retry(true, AttemptFailure::Transport));
+        assert!(!should_retry(
+            true,
+            AttemptFailure::Status(StatusCode::BAD_REQUEST)
+        ));
+        // Mutations only retry when the request never left the terminal.
+        assert!(should_retry(false, AttemptFailure::Connect));
+        assert!(!should_retry(false, AttemptFailure::Transport));
+        assert!(!should_retry(false, bad_gateway));
+
+        let first = retry_delay(1).as_millis() as u64;
+        assert!((RETRY_BASE_DELAY_MS..2 * RETRY_BASE_DELAY_MS).contains(&first));
+    }
+
+    #[test]
+    fn breaker_opens_fails_fast_and_recovers_through_probe() {
+        let breaker = CircuitBreaker::default();
+        let t0 = Instant::now();
+        for _ in 1..BREAKER_FAILURE_THRESHOLD {
+            assert_eq!(breaker.record_failure("HTTP 502", t0), None);
+            assert!(breaker.admit(t0).is_ok());
+        }
+        assert_eq!(breaker.record_failure("HTTP 502", t0), Some(true));
+        let err = breaker.admit(t0).unwrap_err();
+        assert!(err.starts_with("circuit_open"), "{err}");
+        assert_eq!(breaker.snapshot_at(t0).state, BreakerState::Open);
+
+        // After the cooldown one probe goes through; others still fail fast.
+        let t1 = t0 + BREAKER_COOLDOWN;
+        assert!(breaker.admit(t1).is_ok());
+        assert_eq!(breaker.snapshot_at(t1).state, BreakerState::HalfOpen);
+        assert!(breaker.admit(t1).is_err());
+
+        // A failed probe reopens without a second degraded transition.
+        assert_eq!(breaker.record_failure("HTTP 503", t1), None);
+        assert_eq!(breaker.snapshot_at(t1).state, BreakerState::Open);
+        assert!(breaker.admit(t1 + Duration::from_secs(1)).is_err());
+
+        let t2 = t1 + BREAKER_COOLDOWN;
+        assert!(breaker.admit(t2).is_ok());
+        assert_eq!(breaker.record_success(), Some(false));
+        assert_eq!(breaker.snapshot_at(t2).state, BreakerState::Closed);
+        assert_eq!(breaker.record_success(), None);
+    }
+
+    /// Serve keep-alive `200 {}` responses and count accepted connections.
+    async fn spawn_counting_keep_alive_server() -> (String, Arc<AtomicUsize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock server");
+        let address = listener.local_addr().expect("mock server address");
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = Arc::clone(&connections);
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                accepted.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buffer = [0_u8; 4096];
+                    // Requests in this test carry no body, so one read per
+                    // request is enough to see its headers.
+                    while let Ok(read) = stream.read(&mut buffer).await {
+                        if read == 0 {
+                            break;
+                        }
+                        let response =
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n{}";
+                        if stream.write_all(response.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        (format!("http://{address}/api/health"), connections)
+    }
+
+    #[tokio::test]
+    async fn shared_client_reuses_pooled_connections() {
+        const REQUESTS: usize = 5;
+
+        let (fresh_url, fresh_connections) = spawn_counting_keep_alive_server().await;
+        for _ in 0..REQUESTS {
+            let client = Client::new();
+            let resp = client.get(&fresh_url).send().await.expect("fresh request");
+            assert!(resp.status().is_success());
+            resp.bytes().await.expect("fresh body");
+        }
+
+        let (shared_url, shared_connections) = spawn_counting_keep_alive_server().await;
+        let client = shared_client().expect("shared client");
+        for _ in 0..REQUESTS {
+            let resp = client
+                .get(&shared_url)
+                .timeout(RouteClass::Health.timeout())
+                .send()
+                .await
+                .expect("shared request");
+            assert!(resp.status().is_success());
+            resp.bytes().await.expect("shared body");
+        }
+
+        assert_eq!(fresh_connections.load(Ordering::SeqCst), REQUESTS);
+        assert_eq!(shared_connections.load(Ordering::SeqCst), 1);
+    }
 }
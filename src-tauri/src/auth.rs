@@ -5,6 +5,11 @@
 //! `local_settings` table (category "staff", keys "admin_pin_hash" /
 //! "staff_pin_hash"). Sessions are kept in-memory; the `staff_sessions`
 //! table is used only for audit/persistence across restarts.
+//!
+//! Several staff can share a terminal: locking or switching user parks the
+//! active session instead of ending it, and up to [`MAX_SESSIONS`] parked
+//! sessions can be resumed with just their PIN until they expire. Only the
+//! active session counts for activity, idle timeout and permissions.
 
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
@@ -14,6 +19,7 @@ use std::sync::Mutex;
 use tracing::{info, warn};
 use uuid::Uuid;
 
+use crate::error::PosError;
 use crate::{api, db, storage};
 
 // ---------------------------------------------------------------------------
@@ -24,6 +30,9 @@ const MAX_FAILED_ATTEMPTS: u32 = 5;
 const LOCKOUT_MINUTES: i64 = 15;
 const SESSION_INACTIVITY_MINUTES: i64 = 30;
 const SESSION_MAX_DURATION_HOURS: i64 = 2;
+/// Sessions kept per terminal, the active one included. The least recently
+/// active parked session is dropped beyond this.
+pub(crate) const MAX_SESSIONS: usize = 5;
 pub(crate) const PRIVILEGED_ACTION_TTL_SECONDS: i64 = 300;
 const LOCKOUT_ATTEMPTS_KEY: &str = "lockout_attempts";
 const LOCKOUT_LAST_ATTEMPT_KEY: &str = "lockout_last_attempt";
@@ -244,6 +253,12 @@ impl From<String> for GuardedCommandError {
     }
 }
 
+impl From<crate::error::PosError> for GuardedCommandError {
+    fn from(value: crate::error::PosError) -> Self {
+        Self::Message(value.to_string())
+    }
+}
+
 /// Tauri managed state for authentication.
 pub struct AuthState {
     sessions: Mutex<HashMap<String, StaffSession>>,
@@ -317,6 +332,70 @@ fn load_staff_auth_cache(
     parse_staff_auth_cache(&raw)
 }
 
+/// Staff id → display name from the cached staff directory. An empty
+/// `branch_id` reads every cached branch. Entries without a usable name
+/// are left out so callers can fall back to the raw id.
+pub(crate) fn cached_staff_display_names(
+    conn: &rusqlite::Connection,
+    branch_id: &str,
+) -> HashMap<String, String> {
+    let raws: Vec<String> = if branch_id.trim().is_empty() {
+        conn.prepare("SELECT setting_value FROM local_settings WHERE setting_category = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_map([STAFF_AUTH_CACHE_CATEGORY], |row| row.get(0))?
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else {
+        db::get_setting(
+            conn,
+            STAFF_AUTH_CACHE_CATEGORY,
+            &staff_auth_cache_key(branch_id),
+        )
+        .into_iter()
+        .collect()
+    };
+
+    let mut names = HashMap::new();
+    for raw in raws {
+        let Ok(cache) = serde_json::from_str::<Value>(&raw) else {
+            continue;
+        };
+        let Some(entries) = cache.get("staff").and_then(Value::as_array) else {
+            continue;
+        };
+        for entry in entries {
+            let Some(id) = value_string_alias(entry, &["id"]) else {
+                continue;
+            };
+            let name = value_string_alias(
+                entry,
+                &[
+                    "name",
+                    "fullName",
+                    "full_name",
+                    "displayName",
+                    "display_name",
+                ],
+            )
+            .or_else(|| {
+                let parts: Vec<String> = [
+                    value_string_alias(entry, &["firstName", "first_name"]),
+                    value_string_alias(entry, &["lastName", "last_name"]),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                (!parts.is_empty()).then(|| parts.join(" "))
+            });
+            if let Some(name) = name {
+                names.insert(id, name);
+            }
+        }
+    }
+    names
+}
+
 fn value_string_alias(value: &Value, keys: &[&str]) -> Option<String> {
     keys.iter()
         .find_map(|key| value.get(*key).and_then(Value::as_str))
@@ -712,7 +791,7 @@ fn current_terminal_has_cash_drawer_role(db: &db::DbState) -> Result<bool, Strin
     Ok(matches!(role.as_deref(), Some("cashier" | "manager")))
 }
 
-fn verify_privileged_pin_with_lockout(
+pub(crate) fn verify_privileged_pin_with_lockout(
     pin: &str,
     role: &str,
     db: &db::DbState,
@@ -885,8 +964,9 @@ fn create_session(auth: &AuthState, role: &str, staff_id: &str) -> Value {
         sessions.insert(sid.clone(), session);
     }
     if let Ok(mut current) = auth.current_session_id.lock() {
-        *current = Some(sid);
+        *current = Some(sid.clone());
     }
+    prune_parked_sessions(auth, &sid);
 
     serde_json::json!({
         "success": true,
@@ -894,6 +974,39 @@ fn create_session(auth: &AuthState, role: &str, staff_id: &str) -> Value {
     })
 }
 
+/// Drop expired parked sessions, then the least recently active ones beyond
+/// [`MAX_SESSIONS`]. `keep` (the session just activated) always stays.
+fn prune_parked_sessions(auth: &AuthState, keep: &str) {
+    let dropped: Vec<String> = {
+        let Ok(mut sessions) = auth.sessions.lock() else {
+            return;
+        };
+        let mut dropped: Vec<String> = sessions
+            .values()
+            .filter(|session| session.session_id != keep && session.is_expired())
+            .map(|session| session.session_id.clone())
+            .collect();
+        for sid in &dropped {
+            sessions.remove(sid);
+        }
+        let mut parked: Vec<(DateTime<Utc>, String)> = sessions
+            .values()
+            .filter(|session| session.session_id != keep)
+            .map(|session| (session.last_activity, session.session_id.clone()))
+            .collect();
+        parked.sort();
+        let excess = sessions.len().saturating_sub(MAX_SESSIONS);
+        for (_, sid) in parked.into_iter().take(excess) {
+            sessions.remove(&sid);
+            dropped.push(sid);
+        }
+        dropped
+    };
+    for sid in dropped {
+        clear_privileged_grants_for_session(auth, &sid);
+    }
+}
+
 /// Get the current active session (if it exists and is not expired).
 fn get_current_session(auth: &AuthState) -> Option<StaffSession> {
     let current_id = auth.current_session_id.lock().ok()?.clone()?;
@@ -910,13 +1023,15 @@ fn get_current_session(auth: &AuthState) -> Option<StaffSession> {
 // ---------------------------------------------------------------------------
 
 /// Handle auth:login — verify PIN against stored hashes, create a session.
-pub fn login(arg0: Option<Value>, db: &db::DbState, auth: &AuthState) -> Result<Value, String> {
+pub fn login(arg0: Option<Value>, db: &db::DbState, auth: &AuthState) -> Result<Value, PosError> {
     // Extract PIN
-    let pin_val = arg0.ok_or("Missing login argument")?;
-    let pin = extract_pin(&pin_val).ok_or("Invalid login payload: expected a PIN string")?;
+    let pin_val = arg0.ok_or_else(|| PosError::validation("pin", "Missing login argument"))?;
+    let pin = extract_pin(&pin_val).ok_or_else(|| {
+        PosError::validation("pin", "Invalid login payload: expected a PIN string")
+    })?;
 
     if pin.is_empty() {
-        return Err("PIN is required".into());
+        return Err(PosError::validation("pin", "PIN is required"));
     }
 
     // Read PIN hashes and synchronize lockout state from durable storage.
@@ -968,7 +1083,7 @@ pub fn login(arg0: Option<Value>, db: &db::DbState, auth: &AuthState) -> Result<
         if let Err(e) = check_lockout(&persisted_lockout) {
             let _ = conn.execute_batch("ROLLBACK");
             *lockout = persisted_lockout;
-            return Err(e);
+            return Err(PosError::Unauthorized(e));
         }
 
         let admin_hash = db::get_setting(&conn, "staff", "admin_pin_hash");
@@ -976,7 +1091,9 @@ pub fn login(arg0: Option<Value>, db: &db::DbState, auth: &AuthState) -> Result<
 
         if let Err(e) = conn.execute_batch("COMMIT") {
             let _ = conn.execute_batch("ROLLBACK");
-            return Err(format!("commit auth phase-1 transaction: {e}"));
+            return Err(PosError::Database(format!(
+                "commit auth phase-1 transaction: {e}"
+            )));
         }
         *lockout = persisted_lockout;
         (admin_hash, staff_hash)
@@ -1030,7 +1147,7 @@ pub fn login(arg0: Option<Value>, db: &db::DbState, auth: &AuthState) -> Result<
     } else {
         record_failure(&mut lockout);
         persist_lockout_to_db(&conn, &lockout);
-        Err("Invalid PIN".to_string())
+        Err(PosError::Unauthorized("Invalid PIN".to_string()))
     };
 
     // Wave 1 C1: if COMMIT fails, the Phase-3 transaction has to be explicitly
@@ -1044,7 +1161,7 @@ pub fn login(arg0: Option<Value>, db: &db::DbState, auth: &AuthState) -> Result<
     // line 751.
     conn.execute_batch("COMMIT").map_err(|e| {
         let _ = conn.execute_batch("ROLLBACK");
-        format!("commit auth phase-3 transaction: {e}")
+        PosError::Database(format!("commit auth phase-3 transaction: {e}"))
     })?;
     // Release the lockout mutex before creating the session
     drop(lockout);
@@ -1258,18 +1375,231 @@ pub fn has_any_permission(auth: &AuthState, permissions: Option<&[String]>) -> b
     }
 }
 
-/// Handle auth:get-session-stats.
+/// Handle auth:get-session-stats. Parked sessions are listed with their
+/// idle time whether or not a session is active.
 pub fn get_session_stats(auth: &AuthState) -> Value {
-    match get_current_session(auth) {
+    let current = get_current_session(auth);
+    let now = Utc::now();
+    let mut parked: Vec<StaffSession> = auth
+        .sessions
+        .lock()
+        .map(|sessions| {
+            sessions
+                .values()
+                .filter(|session| {
+                    current.as_ref().map(|active| active.session_id.as_str())
+                        != Some(session.session_id.as_str())
+                })
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    parked.sort_by_key(|session| std::cmp::Reverse(session.last_activity));
+    let parked_json: Vec<Value> = parked
+        .iter()
+        .map(|session| {
+            serde_json::json!({
+                "sessionId": session.session_id,
+                "staffId": session.staff_id,
+                "databaseStaffId": session.database_staff_id(),
+                "role": session.role,
+                "lastActivity": session.last_activity.to_rfc3339(),
+                "idleSeconds": (now - session.last_activity).num_seconds().max(0),
+                "expired": session.is_expired(),
+            })
+        })
+        .collect();
+    let locked = current.is_none() && parked.iter().any(|session| !session.is_expired());
+
+    let mut stats = match current {
         Some(s) => serde_json::json!({
             "sessionId": s.session_id,
+            "staffId": s.staff_id,
             "role": s.role,
             "loginTime": s.login_time.to_rfc3339(),
             "lastActivity": s.last_activity.to_rfc3339(),
             "expiresAt": s.expires_at.to_rfc3339(),
         }),
         None => serde_json::json!({}),
+    };
+    if let Some(obj) = stats.as_object_mut() {
+        obj.insert("locked".to_string(), Value::Bool(locked));
+        obj.insert("parkedSessions".to_string(), Value::Array(parked_json));
+    }
+    stats
+}
+
+/// Handle auth:lock-terminal — park the active session without ending it.
+/// Its privileged grants are dropped, so a resumed session re-confirms.
+pub fn lock_terminal(auth: &AuthState) -> Value {
+    let parked = auth
+        .current_session_id
+        .lock()
+        .ok()
+        .and_then(|mut current| current.take());
+    let staff_id = parked.as_deref().and_then(|sid| {
+        auth.sessions
+            .lock()
+            .ok()
+            .and_then(|sessions| sessions.get(sid).map(|s| s.staff_id.clone()))
+    });
+    if let Some(sid) = parked.as_deref() {
+        clear_privileged_grants_for_session(auth, sid);
+        info!(session_id = %sid, "terminal locked, session parked");
     }
+    serde_json::json!({
+        "success": true,
+        "sessionId": parked,
+        "staffId": staff_id,
+    })
+}
+
+#[derive(Debug, Default)]
+struct SwitchUserRequest {
+    pin: String,
+    session_id: Option<String>,
+    staff_id: Option<String>,
+    branch_id: Option<String>,
+}
+
+fn parse_switch_user_request(arg0: Option<Value>) -> Result<SwitchUserRequest, PosError> {
+    let payload = arg0.ok_or_else(|| PosError::validation("pin", "Missing switch-user payload"))?;
+    let pin = extract_pin(&payload)
+        .map(|pin| pin.trim().to_string())
+        .filter(|pin| !pin.is_empty())
+        .ok_or_else(|| PosError::validation("pin", "PIN is required"))?;
+    let text = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| payload.get(*key).and_then(Value::as_str))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(ToString::to_string)
+    };
+    Ok(SwitchUserRequest {
+        pin,
+        session_id: text(&["sessionId", "session_id"]),
+        staff_id: text(&["staffId", "staff_id"]),
+        branch_id: text(&["branchId", "branch_id"]),
+    })
+}
+
+/// Check a PIN against a staff member's entry in the cached auth directory.
+fn verify_directory_pin(
+    db: &db::DbState,
+    staff_id: &str,
+    branch_id: Option<&str>,
+    pin: &str,
+) -> Result<(), PosError> {
+    let branch_id = branch_id
+        .map(ToString::to_string)
+        .or_else(|| storage::get_credential("branch_id"))
+        .unwrap_or_default();
+    let result = verify_staff_check_in_pin(
+        Some(serde_json::json!({
+            "staffId": staff_id,
+            "branchId": branch_id,
+            "pin": pin,
+        })),
+        db,
+    )?;
+    if result.get("success").and_then(Value::as_bool) == Some(true) {
+        return Ok(());
+    }
+    Err(PosError::Unauthorized(
+        result
+            .get("error")
+            .and_then(Value::as_str)
+            .unwrap_or("Invalid PIN")
+            .to_string(),
+    ))
+}
+
+/// Handle auth:switch-user — resume a parked session with its PIN, or sign
+/// the staff member in afresh when their session expired or never existed.
+/// Any active session is parked, not ended.
+pub fn switch_user(
+    arg0: Option<Value>,
+    db: &db::DbState,
+    auth: &AuthState,
+) -> Result<Value, PosError> {
+    let request = parse_switch_user_request(arg0)?;
+    let target = {
+        let sessions = auth
+            .sessions
+            .lock()
+            .map_err(|e| format!("sessions mutex poisoned: {e}"))?;
+        match (&request.session_id, &request.staff_id) {
+            (Some(sid), _) => sessions.get(sid).cloned(),
+            (None, Some(staff_id)) => sessions
+                .values()
+                .filter(|session| &session.staff_id == staff_id)
+                .max_by_key(|session| session.last_activity)
+                .cloned(),
+            (None, None) => None,
+        }
+    };
+    if request.session_id.is_some() && target.is_none() {
+        return Err(PosError::NotFound("Session not found".to_string()));
+    }
+
+    if let Some(session) = target.as_ref().filter(|session| !session.is_expired()) {
+        match session.database_staff_id() {
+            Some(staff_id) => {
+                verify_directory_pin(db, staff_id, request.branch_id.as_deref(), &request.pin)?
+            }
+            None => {
+                if !verify_privileged_pin_with_lockout(&request.pin, &session.role, db, auth)? {
+                    return Err(PosError::Unauthorized("Invalid PIN".to_string()));
+                }
+            }
+        }
+        let resumed = {
+            let mut sessions = auth
+                .sessions
+                .lock()
+                .map_err(|e| format!("sessions mutex poisoned: {e}"))?;
+            let Some(stored) = sessions.get_mut(&session.session_id) else {
+                return Err(PosError::NotFound("Session not found".to_string()));
+            };
+            stored.last_activity = Utc::now();
+            stored.to_user_json()
+        };
+        if let Ok(mut current) = auth.current_session_id.lock() {
+            *current = Some(session.session_id.clone());
+        }
+        info!(session_id = %session.session_id, "switched to parked session");
+        return Ok(serde_json::json!({
+            "success": true,
+            "resumed": true,
+            "user": resumed,
+        }));
+    }
+
+    // Expired or unknown: full re-auth. A directory staff member gets a
+    // session of their own; the shared admin/staff PINs go through login.
+    if let Some(expired) = target.as_ref() {
+        if let Ok(mut sessions) = auth.sessions.lock() {
+            sessions.remove(&expired.session_id);
+        }
+        clear_privileged_grants_for_session(auth, &expired.session_id);
+    }
+    let directory_staff_id = target
+        .as_ref()
+        .map(|session| session.staff_id.clone())
+        .or(request.staff_id.clone())
+        .filter(|staff_id| Uuid::parse_str(staff_id).is_ok());
+    let mut response = match directory_staff_id {
+        Some(staff_id) => {
+            verify_directory_pin(db, &staff_id, request.branch_id.as_deref(), &request.pin)?;
+            info!("directory staff signed in by switch-user");
+            create_session(auth, "staff", &staff_id)
+        }
+        None => login(Some(Value::String(request.pin)), db, auth)?,
+    };
+    if let Some(obj) = response.as_object_mut() {
+        obj.insert("resumed".to_string(), Value::Bool(false));
+    }
+    Ok(response)
 }
 
 /// Handle auth:setup-pin — validate, hash, and store admin/staff PINs.
@@ -1338,6 +1668,18 @@ pub fn get_current_user(auth: &AuthState) -> Value {
     get_session_json(auth)
 }
 
+/// Staff id of the current unexpired session, for attributing changes.
+pub fn current_staff_id(auth: &AuthState) -> Option<String> {
+    get_current_session(auth).map(|session| session.staff_id)
+}
+
+/// Database staff id of the current session, when it belongs to a staff
+/// member rather than a shared admin/staff PIN.
+pub fn current_database_staff_id(auth: &AuthState) -> Option<String> {
+    get_current_session(auth)
+        .and_then(|session| session.database_staff_id().map(ToString::to_string))
+}
+
 fn authorize_privileged_action_at(
     scope: PrivilegedActionScope,
     db: &db::DbState,
@@ -1483,10 +1825,7 @@ mod tests {
     fn test_db_state() -> db::DbState {
         let conn = Connection::open_in_memory().expect("open in-memory db");
         db::run_migrations_for_test(&conn);
-        db::DbState {
-            conn: Mutex::new(conn),
-            db_path: PathBuf::from(":memory:"),
-        }
+        db::DbState::new(conn, PathBuf::from(":memory:"))
     }
 
     fn lockout_attempts(db_state: &db::DbState) -> u32 {
@@ -1584,7 +1923,7 @@ mod tests {
                 &auth_before_restart,
             )
             .expect_err("invalid login should fail");
-            assert_eq!(err, "Invalid PIN");
+            assert_eq!(err, PosError::Unauthorized("Invalid PIN".into()));
         }
 
         assert_eq!(lockout_attempts(&db_state), MAX_FAILED_ATTEMPTS);
@@ -1597,8 +1936,9 @@ mod tests {
         )
         .expect_err("lockout should remain active after restart");
 
+        assert_eq!(err.code(), "UNAUTHORIZED");
         assert!(
-            err.contains("Too many failed attempts"),
+            err.to_string().contains("Too many failed attempts"),
             "unexpected lockout error message: {err}"
         );
         assert_eq!(
@@ -1626,7 +1966,7 @@ mod tests {
                 &auth_before_restart,
             )
             .expect_err("invalid login should fail");
-            assert_eq!(err, "Invalid PIN");
+            assert_eq!(err, PosError::Unauthorized("Invalid PIN".into()));
         }
         assert_eq!(lockout_attempts(&db_state), 2);
 
@@ -1651,7 +1991,7 @@ mod tests {
             &auth_after_second_restart,
         )
         .expect_err("invalid login should fail after reset");
-        assert_eq!(err, "Invalid PIN");
+        assert_eq!(err, PosError::Unauthorized("Invalid PIN".into()));
         assert_eq!(lockout_attempts(&db_state), 1);
     }
 
@@ -2218,4 +2558,104 @@ mod tests {
         };
         assert!(placeholder_session.to_user_json()["databaseStaffId"].is_null());
     }
+
+    #[test]
+    fn lock_parks_session_and_switch_resumes_it_with_pin() {
+        let db_state = test_db_state();
+        let auth = AuthState::new();
+        login_as_staff(&db_state, &auth);
+        let staff_session = current_session_id(&auth);
+
+        let locked = lock_terminal(&auth);
+        assert_eq!(locked["sessionId"].as_str(), Some(staff_session.as_str()));
+        assert!(!has_permission(&auth, Some("create_order")));
+        assert_eq!(get_session_stats(&auth)["locked"], Value::Bool(true));
+
+        login_as_admin(&db_state, &auth);
+        let admin_session = current_session_id(&auth);
+        assert_ne!(admin_session, staff_session);
+
+        let wrong = switch_user(
+            Some(serde_json::json!({ "sessionId": staff_session, "pin": "1234" })),
+            &db_state,
+            &auth,
+        );
+        assert!(matches!(wrong, Err(PosError::Unauthorized(_))));
+
+        let resumed = switch_user(
+            Some(serde_json::json!({ "sessionId": staff_session, "pin": "4321" })),
+            &db_state,
+            &auth,
+        )
+        .expect("switch back to parked staff session");
+        assert_eq!(resumed["resumed"], Value::Bool(true));
+        assert_eq!(current_session_id(&auth), staff_session);
+        assert!(!has_permission(&auth, Some("system_settings")));
+
+        let stats = get_session_stats(&auth);
+        assert_eq!(stats["sessionId"].as_str(), Some(staff_session.as_str()));
+        let parked = stats["parkedSessions"].as_array().expect("parked list");
+        assert_eq!(parked.len(), 1);
+        assert_eq!(
+            parked[0]["sessionId"].as_str(),
+            Some(admin_session.as_str())
+        );
+    }
+
+    #[test]
+    fn switch_user_signs_in_directory_staff_under_their_own_id() {
+        let db_state = test_db_state();
+        let auth = AuthState::new();
+        let staff_id = "22222222-2222-4222-8222-222222222222";
+        let hash = bcrypt::hash("5678", 4).expect("hash test pin");
+        set_staff_auth_cache(
+            &db_state,
+            "branch-1",
+            serde_json::json!([{
+                "id": staff_id,
+                "can_login_pos": true,
+                "has_pin": true,
+                "pin_hash": hash,
+                "is_active": true
+            }]),
+        );
+        login_as_admin(&db_state, &auth);
+
+        let response = switch_user(
+            Some(serde_json::json!({
+                "staffId": staff_id,
+                "branchId": "branch-1",
+                "pin": "5678"
+            })),
+            &db_state,
+            &auth,
+        )
+        .expect("directory staff switch");
+        assert_eq!(response["resumed"], Value::Bool(false));
+        assert_eq!(current_database_staff_id(&auth).as_deref(), Some(staff_id));
+        assert_eq!(
+            get_session_stats(&auth)["parkedSessions"]
+                .as_array()
+                .map(Vec::len),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn parked_sessions_are_capped_by_least_recent_activity() {
+        let auth = AuthState::new();
+        let first = create_session(&auth, "staff", "staff-user")["user"]["sessionId"]
+            .as_str()
+            .map(ToString::to_string)
+            .expect("first session id");
+        if let Some(session) = auth.sessions.lock().expect("sessions lock").get_mut(&first) {
+            session.last_activity -= Duration::minutes(1);
+        }
+        for _ in 0..MAX_SESSIONS {
+            create_session(&auth, "staff", "staff-user");
+        }
+        let sessions = auth.sessions.lock().expect("sessions lock");
+        assert_eq!(sessions.len(), MAX_SESSIONS);
+        assert!(!sessions.contains_key(&first));
+    }
 }
@@ -0,0 +1,412 @@
+//! Automatic acceptance of remote orders.
+//!
+//! Remote orders land in `pending` and wait for someone to approve them.
+//! When a rule matches, `commands::orders::persist_remote_order` approves
+//! the order on the spot through the same path as `order_approve` and
+//! records `auto_approved` with the rule id on the order timeline. The
+//! estimated time comes from `prep_time` once it has history for the order,
+//! otherwise it is `base_minutes + per_item_minutes * items`.
+//!
+//! Everything lives in `local_settings` under category `auto_accept`:
+//! `rules` is a JSON array of [`AutoAcceptRule`] evaluated in order (first
+//! match wins), `base_minutes` / `per_item_minutes` drive the estimate, and
+//! `enabled` is the kill switch — `false` stops the engine without touching
+//! the rules.
+
+use std::collections::HashSet;
+
+use chrono::NaiveTime;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::db;
+
+const SETTINGS_CATEGORY: &str = "auto_accept";
+const RULES_KEY: &str = "rules";
+const ENABLED_KEY: &str = "enabled";
+const BASE_MINUTES_KEY: &str = "base_minutes";
+const PER_ITEM_MINUTES_KEY: &str = "per_item_minutes";
+
+pub const DEFAULT_BASE_MINUTES: i64 = 20;
+pub const DEFAULT_PER_ITEM_MINUTES: i64 = 1;
+
+/// Source of an order with neither a `plugin` nor a `source`, i.e. one
+/// placed through the restaurant's own online ordering.
+pub const DIRECT_SOURCE: &str = "direct";
+
+const TIME_FORMAT: &str = "%H:%M";
+
+/// Local time window, `HH:MM` on both ends. A window whose end is before
+/// its start runs past midnight.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HoursWindow {
+    pub from: String,
+    pub to: String,
+}
+
+impl HoursWindow {
+    fn bounds(&self) -> Result<(NaiveTime, NaiveTime), String> {
+        let parse = |raw: &str| {
+            NaiveTime::parse_from_str(raw.trim(), TIME_FORMAT)
+                .map_err(|_| format!("Invalid time '{}', expected HH:MM", raw.trim()))
+        };
+        Ok((parse(&self.from)?, parse(&self.to)?))
+    }
+
+    fn contains(&self, now: NaiveTime) -> bool {
+        match self.bounds() {
+            Ok((from, to)) if from <= to => from <= now && now < to,
+            Ok((from, to)) => now >= from || now < to,
+            Err(_) => false,
+        }
+    }
+}
+
+/// One acceptance rule. Every condition that is set must hold; an empty
+/// list or absent bound matches anything.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoAcceptRule {
+    pub id: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default, alias = "order_types")]
+    pub order_types: Vec<String>,
+    /// Platform plugins (`wolt`, `efood`, ...) or order sources; see
+    /// [`DIRECT_SOURCE`].
+    #[serde(default, alias = "plugins")]
+    pub sources: Vec<String>,
+    /// Orders must total strictly less than this.
+    #[serde(default, alias = "max_total")]
+    pub max_total: Option<f64>,
+    #[serde(default, alias = "min_items")]
+    pub min_items: Option<i64>,
+    #[serde(default, alias = "max_items")]
+    pub max_items: Option<i64>,
+    #[serde(default)]
+    pub hours: Option<HoursWindow>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn normalize(raw: &str) -> String {
+    raw.trim().to_ascii_lowercase().replace(['-', ' '], "_")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoAcceptConfig {
+    pub enabled: bool,
+    pub rules: Vec<AutoAcceptRule>,
+    pub base_minutes: i64,
+    pub per_item_minutes: i64,
+}
+
+impl AutoAcceptConfig {
+    pub fn load(conn: &Connection) -> Self {
+        let minutes = |key: &str, default: i64| {
+            db::get_setting(conn, SETTINGS_CATEGORY, key)
+                .and_then(|raw| raw.trim().parse::<i64>().ok())
+                .filter(|value| *value >= 0)
+                .unwrap_or(default)
+        };
+        Self {
+            enabled: db::get_setting(conn, SETTINGS_CATEGORY, ENABLED_KEY)
+                .map_or(true, |raw| !matches!(raw.trim(), "false" | "0")),
+            rules: db::get_setting(conn, SETTINGS_CATEGORY, RULES_KEY)
+                .and_then(|raw| serde_json::from_str(&raw).ok())
+                .unwrap_or_default(),
+            base_minutes: minutes(BASE_MINUTES_KEY, DEFAULT_BASE_MINUTES),
+            per_item_minutes: minutes(PER_ITEM_MINUTES_KEY, DEFAULT_PER_ITEM_MINUTES),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "enabled": self.enabled,
+            "rules": self.rules,
+            "baseMinutes": self.base_minutes,
+            "perItemMinutes": self.per_item_minutes,
+        })
+    }
+
+    /// Check and normalize a config before saving: rule ids unique and
+    /// non-empty, order types and sources in snake_case, windows parseable,
+    /// bounds non-negative.
+    pub fn validate(mut self) -> Result<Self, String> {
+        if self.base_minutes < 0 || self.per_item_minutes < 0 {
+            return Err("Estimated time minutes cannot be negative".into());
+        }
+        let mut seen = HashSet::new();
+        for rule in &mut self.rules {
+            rule.id = rule.id.trim().to_string();
+            if rule.id.is_empty() {
+                return Err("Auto-accept rules need an id".into());
+            }
+            if !seen.insert(rule.id.clone()) {
+                return Err(format!("Duplicate auto-accept rule id '{}'", rule.id));
+            }
+            rule.order_types = rule.order_types.iter().map(|v| normalize(v)).collect();
+            rule.sources = rule.sources.iter().map(|v| normalize(v)).collect();
+            if rule
+                .max_total
+                .is_some_and(|max| !max.is_finite() || max < 0.0)
+            {
+                return Err(format!("Rule '{}': maxTotal cannot be negative", rule.id));
+            }
+            if rule.min_items.is_some_and(|min| min < 0)
+                || rule.max_items.is_some_and(|max| max < 0)
+            {
+                return Err(format!(
+                    "Rule '{}': item counts cannot be negative",
+                    rule.id
+                ));
+            }
+            if let Some(hours) = &rule.hours {
+                hours
+                    .bounds()
+                    .map_err(|e| format!("Rule '{}': {e}", rule.id))?;
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn save(&self, conn: &Connection, actor_staff_id: Option<&str>) -> Result<(), String> {
+        let rules = serde_json::to_string(&self.rules).map_err(|e| e.to_string())?;
+        for (key, value) in [
+            (ENABLED_KEY, self.enabled.to_string()),
+            (RULES_KEY, rules),
+            (BASE_MINUTES_KEY, self.base_minutes.to_string()),
+            (PER_ITEM_MINUTES_KEY, self.per_item_minutes.to_string()),
+        ] {
+            db::set_setting_with_source(
+                conn,
+                SETTINGS_CATEGORY,
+                key,
+                &value,
+                "autoaccept_set_rules",
+                actor_staff_id,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// What the rules look at on a freshly stored order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderFacts {
+    pub status: String,
+    pub order_type: String,
+    pub source: String,
+    pub total: f64,
+    pub item_count: i64,
+}
+
+/// Sum of item quantities; an item without one counts once.
+fn item_count(items: &Value) -> i64 {
+    items.as_array().map_or(0, |items| {
+        items
+            .iter()
+            .map(|item| {
+                item.get("quantity")
+                    .and_then(|q| q.as_i64().or_else(|| q.as_f64().map(|q| q.ceil() as i64)))
+                    .filter(|q| *q > 0)
+                    .unwrap_or(1)
+            })
+            .sum()
+    })
+}
+
+pub fn load_order_facts(conn: &Connection, order_id: &str) -> Result<Option<OrderFacts>, String> {
+    conn.query_row(
+        "SELECT LOWER(COALESCE(status, '')), COALESCE(order_type, ''),
+                COALESCE(NULLIF(TRIM(plugin), ''), NULLIF(TRIM(source), '')),
+                COALESCE(total_amount, 0), COALESCE(items, '[]'), COALESCE(is_ghost, 0)
+         FROM orders WHERE id = ?1",
+        params![order_id],
+        |row| {
+            let items: String = row.get(4)?;
+            let is_ghost: i64 = row.get(5)?;
+            Ok((
+                OrderFacts {
+                    status: row.get(0)?,
+                    order_type: normalize(&row.get::<_, String>(1)?),
+                    source: row
+                        .get::<_, Option<String>>(2)?
+                        .map(|source| normalize(&source))
+                        .unwrap_or_else(|| DIRECT_SOURCE.to_string()),
+                    total: row.get(3)?,
+                    item_count: item_count(&serde_json::from_str(&items).unwrap_or(Value::Null)),
+                },
+                is_ghost != 0,
+            ))
+        },
+    )
+    .optional()
+    .map(|row| row.and_then(|(facts, is_ghost)| (!is_ghost).then_some(facts)))
+    .map_err(|e| format!("load order for auto-accept: {e}"))
+}
+
+impl AutoAcceptRule {
+    pub fn matches(&self, order: &OrderFacts, now: NaiveTime) -> bool {
+        self.enabled
+            && (self.order_types.is_empty() || self.order_types.contains(&order.order_type))
+            && (self.sources.is_empty() || self.sources.contains(&order.source))
+            && self.max_total.map_or(true, |max| order.total < max)
+            && self.min_items.map_or(true, |min| order.item_count >= min)
+            && self.max_items.map_or(true, |max| order.item_count <= max)
+            && self
+                .hours
+                .as_ref()
+                .map_or(true, |hours| hours.contains(now))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoAcceptMatch {
+    pub rule_id: String,
+    pub estimated_minutes: i64,
+}
+
+/// First rule that accepts the order, if the engine is on and the order is
+/// still pending. `now` is the terminal's local time of day.
+pub fn evaluate(
+    conn: &Connection,
+    order_id: &str,
+    now: NaiveTime,
+) -> Result<Option<AutoAcceptMatch>, String> {
+    let config = AutoAcceptConfig::load(conn);
+    if !config.enabled || config.rules.is_empty() {
+        return Ok(None);
+    }
+    let Some(order) = load_order_facts(conn, order_id)? else {
+        return Ok(None);
+    };
+    if order.status != "pending" {
+        return Ok(None);
+    }
+    Ok(config
+        .rules
+        .iter()
+        .find(|rule| rule.matches(&order, now))
+        .map(|rule| AutoAcceptMatch {
+            rule_id: rule.id.clone(),
+            estimated_minutes: config.base_minutes + config.per_item_minutes * order.item_count,
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn time(raw: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(raw, TIME_FORMAT).unwrap()
+    }
+
+    fn insert_order(conn: &Connection, id: &str, plugin: Option<&str>, total: f64, items: &str) {
+        conn.execute(
+            "INSERT INTO orders (
+                 id, items, total_amount, status, order_type, plugin, sync_status,
+                 created_at, updated_at
+             ) VALUES (?1, ?2, ?3, 'pending', 'delivery', ?4, 'synced',
+                       '2026-05-04T12:00:00Z', '2026-05-04T12:00:00Z')",
+            params![id, items, total, plugin],
+        )
+        .expect("insert order");
+    }
+
+    fn rules(value: Value) -> Vec<AutoAcceptRule> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn hours_window_wraps_past_midnight() {
+        let lunch = HoursWindow {
+            from: "11:30".into(),
+            to: "15:00".into(),
+        };
+        assert!(lunch.contains(time("11:30")));
+        assert!(!lunch.contains(time("15:00")));
+        let late = HoursWindow {
+            from: "22:00".into(),
+            to: "02:00".into(),
+        };
+        assert!(late.contains(time("23:15")));
+        assert!(late.contains(time("01:00")));
+        assert!(!late.contains(time("12:00")));
+    }
+
+    #[test]
+    fn validate_rejects_duplicate_ids_and_bad_windows() {
+        let config = |rules| AutoAcceptConfig {
+            enabled: true,
+            rules,
+            base_minutes: 15,
+            per_item_minutes: 2,
+        };
+        let valid = config(self::rules(
+            json!([{ "id": " wolt ", "sources": ["Wolt"] }]),
+        ))
+        .validate()
+        .unwrap();
+        assert_eq!(valid.rules[0].id, "wolt");
+        assert_eq!(valid.rules[0].sources, vec!["wolt".to_string()]);
+        assert!(config(self::rules(json!([{ "id": "a" }, { "id": "a" }])))
+            .validate()
+            .is_err());
+        assert!(config(self::rules(
+            json!([{ "id": "a", "hours": { "from": "25:00", "to": "02:00" } }])
+        ))
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn evaluate_picks_first_matching_rule_and_honours_kill_switch() {
+        let conn = test_conn();
+        insert_order(
+            &conn,
+            "o-1",
+            Some("Wolt"),
+            18.0,
+            r#"[{"name":"Pita","quantity":2},{"name":"Cola"}]"#,
+        );
+        insert_order(
+            &conn,
+            "o-2",
+            None,
+            45.0,
+            r#"[{"name":"Tray","quantity":1}]"#,
+        );
+        AutoAcceptConfig {
+            enabled: true,
+            rules: rules(json!([
+                { "id": "big", "maxTotal": 10 },
+                { "id": "lunch-wolt", "sources": ["wolt"], "maxTotal": 30, "maxItems": 5,
+                  "hours": { "from": "11:00", "to": "15:00" } },
+                { "id": "direct", "sources": ["direct"], "orderTypes": ["pickup"] },
+            ])),
+            base_minutes: 15,
+            per_item_minutes: 2,
+        }
+        .save(&conn, None)
+        .unwrap();
+
+        let matched = evaluate(&conn, "o-1", time("12:30")).unwrap().unwrap();
+        assert_eq!(matched.rule_id, "lunch-wolt");
+        assert_eq!(matched.estimated_minutes, 21);
+        assert_eq!(evaluate(&conn, "o-1", time("18:00")).unwrap(), None);
+        assert_eq!(evaluate(&conn, "o-2", time("12:30")).unwrap(), None);
+
+        db::set_setting(&conn, SETTINGS_CATEGORY, ENABLED_KEY, "false").unwrap();
+        assert_eq!(evaluate(&conn, "o-1", time("12:30")).unwrap(), None);
+    }
+}
@@ -0,0 +1,349 @@
+//! Branch registry and active-branch switching.
+//!
+//! The `branch_id` credential is the terminal's *active* branch: new orders
+//! and shifts are stamped with it and reports scope to it. The admin
+//! settings response assigns the terminal a home branch and, for keys that
+//! oversee several branches, lists the others the key may access.
+//! [`record_settings_response`] mirrors that list into the `branches` table
+//! (v83) so `terminal_switch_branch` can move the active branch without a
+//! factory reset. A later settings refresh keeps the switched branch for as
+//! long as the admin still lists it, and falls back to the home branch once
+//! it does not.
+//!
+//! Switching never rewrites existing rows: orders, shifts and payments keep
+//! the `branch_id` they were created with.
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+
+use crate::{db, storage};
+
+const BRANCH_LIST_POINTERS: &[&str] = &[
+    "/branches",
+    "/accessible_branches",
+    "/settings/terminal/accessible_branches",
+    "/organization/branches",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BranchRecord {
+    id: String,
+    name: Option<String>,
+    organization_id: Option<String>,
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// The branch the terminal currently operates as.
+pub fn active_branch_id() -> Option<String> {
+    non_empty(storage::get_credential("branch_id").as_deref())
+}
+
+/// Branch filter for report and shift queries: the caller's explicit branch,
+/// else the active branch. An unconfigured terminal resolves to `''`, which
+/// matches only rows recorded without a branch.
+pub fn report_scope(explicit: Option<String>) -> String {
+    non_empty(explicit.as_deref())
+        .or_else(active_branch_id)
+        .unwrap_or_default()
+}
+
+fn branch_record(value: &Value) -> Option<BranchRecord> {
+    if let Some(id) = non_empty(value.as_str()) {
+        return Some(BranchRecord {
+            id,
+            name: None,
+            organization_id: None,
+        });
+    }
+    let field = |keys: &[&str]| keys.iter().find_map(|k| non_empty(value.get(*k)?.as_str()));
+    Some(BranchRecord {
+        id: field(&["id", "branch_id", "branchId"])?,
+        name: field(&["name", "branch_name", "branchName"]),
+        organization_id: field(&["organization_id", "organizationId"]),
+    })
+}
+
+fn branch_list(resp: &Value) -> Vec<BranchRecord> {
+    BRANCH_LIST_POINTERS
+        .iter()
+        .find_map(|pointer| resp.pointer(pointer)?.as_array())
+        .map(|items| items.iter().filter_map(branch_record).collect())
+        .unwrap_or_default()
+}
+
+fn upsert(
+    conn: &Connection,
+    branch: &BranchRecord,
+    is_home: bool,
+    now: &str,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO branches (id, name, organization_id, is_home, last_seen_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(id) DO UPDATE SET
+            name = COALESCE(excluded.name, branches.name),
+            organization_id = COALESCE(excluded.organization_id, branches.organization_id),
+            is_home = excluded.is_home,
+            last_seen_at = excluded.last_seen_at",
+        params![
+            branch.id,
+            branch.name,
+            branch.organization_id,
+            is_home as i32,
+            now
+        ],
+    )
+    .map_err(|e| format!("upsert branch {}: {e}", branch.id))?;
+    Ok(())
+}
+
+/// Refresh the registry from an admin settings response. `assigned` is the
+/// terminal's home branch from the same response. When the response lists
+/// branches, any branch no longer listed is dropped so a revoked branch
+/// cannot be switched to.
+pub fn record_settings_response(
+    conn: &Connection,
+    resp: &Value,
+    assigned: &str,
+) -> Result<(), String> {
+    let now = Utc::now().to_rfc3339();
+    let organization_id = crate::extract_org_id_from_terminal_settings_response(resp);
+    let listed = branch_list(resp);
+    let listed_home = listed.iter().find(|branch| branch.id == assigned);
+    let home = BranchRecord {
+        id: assigned.to_string(),
+        name: listed_home
+            .and_then(|branch| branch.name.clone())
+            .or_else(|| non_empty(resp.pointer("/branch/name").and_then(Value::as_str))),
+        organization_id: listed_home
+            .and_then(|branch| branch.organization_id.clone())
+            .or_else(|| organization_id.clone()),
+    };
+
+    if !listed.is_empty() {
+        let keep: Vec<&str> = listed
+            .iter()
+            .map(|b| b.id.as_str())
+            .chain([assigned])
+            .collect();
+        let placeholders = vec!["?"; keep.len()].join(", ");
+        conn.execute(
+            &format!("DELETE FROM branches WHERE id NOT IN ({placeholders})"),
+            rusqlite::params_from_iter(keep.iter()),
+        )
+        .map_err(|e| format!("prune branches: {e}"))?;
+    }
+    for branch in listed.iter().filter(|b| b.id != assigned) {
+        let branch = BranchRecord {
+            organization_id: branch
+                .organization_id
+                .clone()
+                .or_else(|| organization_id.clone()),
+            ..branch.clone()
+        };
+        upsert(conn, &branch, false, &now)?;
+    }
+    upsert(conn, &home, true, &now)?;
+    db::set_setting(conn, "terminal", "home_branch_id", assigned)?;
+    Ok(())
+}
+
+pub fn is_known(conn: &Connection, branch_id: &str) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM branches WHERE id = ?1",
+        params![branch_id],
+        |_| Ok(()),
+    )
+    .optional()
+    .ok()
+    .flatten()
+    .is_some()
+}
+
+/// Decide the active branch after a settings refresh assigned `assigned`:
+/// a switched-to branch that is still registered stays active, anything
+/// else falls back to the assignment. Returns the active branch.
+pub fn apply_assigned_branch(conn: &Connection, assigned: &str) -> Result<String, String> {
+    let active = active_branch_id()
+        .filter(|current| current != assigned && is_known(conn, current))
+        .unwrap_or_else(|| assigned.to_string());
+    write_active(conn, &active, "system")?;
+    Ok(active)
+}
+
+fn write_active(conn: &Connection, branch_id: &str, source: &str) -> Result<(), String> {
+    storage::set_credential("branch_id", branch_id)?;
+    // Keep the plaintext mirror in step, otherwise the next hydration would
+    // copy a stale value back over the credential.
+    db::set_setting_with_source(conn, "terminal", "branch_id", branch_id, source, None)
+}
+
+/// Make `branch_id` the active branch. Callers check [`switch_blocker`] and
+/// [`is_known`] first.
+pub fn set_active(conn: &Connection, branch_id: &str) -> Result<(), String> {
+    write_active(conn, branch_id, "terminal_switch_branch")
+}
+
+/// Why a branch switch is not allowed right now, if it is not.
+pub fn switch_blocker(conn: &Connection) -> Result<Option<String>, String> {
+    let open_shift: Option<String> = conn
+        .query_row(
+            "SELECT id FROM staff_shifts WHERE status = 'active' LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("check open shifts: {e}"))?;
+    if open_shift.is_some() {
+        return Ok(Some("Close all open shifts before switching branch".into()));
+    }
+    let open_drawer: Option<String> = conn
+        .query_row(
+            "SELECT id FROM cash_drawer_sessions WHERE closed_at IS NULL LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("check open drawer sessions: {e}"))?;
+    if open_drawer.is_some() {
+        return Ok(Some(
+            "Close the open cash drawer session before switching branch".into(),
+        ));
+    }
+    Ok(None)
+}
+
+/// Registered branches, home branch first.
+pub fn list(conn: &Connection) -> Result<Vec<Value>, String> {
+    let active = active_branch_id();
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, organization_id, is_home, last_seen_at
+             FROM branches ORDER BY is_home DESC, COALESCE(name, id), id",
+        )
+        .map_err(|e| format!("prepare branches: {e}"))?;
+    let rows = stmt
+        .query_map([], |row| {
+            let id: String = row.get(0)?;
+            Ok(json!({
+                "isActive": active.as_deref() == Some(id.as_str()),
+                "id": id,
+                "name": row.get::<_, Option<String>>(1)?,
+                "organizationId": row.get::<_, Option<String>>(2)?,
+                "isHome": row.get::<_, i64>(3)? != 0,
+                "lastSeenAt": row.get::<_, String>(4)?,
+            }))
+        })
+        .map_err(|e| format!("query branches: {e}"))?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("read branches: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::fake_keyring;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn known_ids(conn: &Connection) -> Vec<String> {
+        list(conn)
+            .unwrap()
+            .into_iter()
+            .map(|b| b["id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn settings_response_populates_registry_and_prunes_revoked_branches() {
+        let _keyring = fake_keyring::install_empty();
+        let conn = test_conn();
+        let resp = json!({
+            "branch_id": "b-home",
+            "organization_id": "org-1",
+            "branch": { "id": "b-home", "name": "Harbour" },
+            "branches": [
+                { "id": "b-home", "name": "Harbour" },
+                { "id": "b-2", "name": "Old Town" },
+                "b-3"
+            ]
+        });
+        record_settings_response(&conn, &resp, "b-home").unwrap();
+        assert_eq!(known_ids(&conn), vec!["b-home", "b-2", "b-3"]);
+        let home = &list(&conn).unwrap()[0];
+        assert_eq!(home["isHome"], true);
+        assert_eq!(home["organizationId"], "org-1");
+        assert_eq!(
+            db::get_setting(&conn, "terminal", "home_branch_id").as_deref(),
+            Some("b-home")
+        );
+
+        let revoked = json!({ "branch_id": "b-home", "branches": [{ "id": "b-2" }] });
+        record_settings_response(&conn, &revoked, "b-home").unwrap();
+        assert_eq!(known_ids(&conn), vec!["b-home", "b-2"]);
+    }
+
+    #[test]
+    fn refresh_keeps_a_switched_branch_while_it_is_still_registered() {
+        let _keyring = fake_keyring::install_empty();
+        let conn = test_conn();
+        let resp = json!({ "branches": ["b-home", "b-2"] });
+        record_settings_response(&conn, &resp, "b-home").unwrap();
+        assert_eq!(apply_assigned_branch(&conn, "b-home").unwrap(), "b-home");
+
+        set_active(&conn, "b-2").unwrap();
+        assert_eq!(apply_assigned_branch(&conn, "b-home").unwrap(), "b-2");
+        assert_eq!(active_branch_id().as_deref(), Some("b-2"));
+        assert_eq!(
+            db::get_setting(&conn, "terminal", "branch_id").as_deref(),
+            Some("b-2")
+        );
+
+        record_settings_response(&conn, &json!({ "branches": ["b-home"] }), "b-home").unwrap();
+        assert_eq!(apply_assigned_branch(&conn, "b-home").unwrap(), "b-home");
+    }
+
+    #[test]
+    fn switch_is_blocked_by_open_shift_or_drawer() {
+        let conn = test_conn();
+        assert_eq!(switch_blocker(&conn).unwrap(), None);
+        conn.execute(
+            "INSERT INTO staff_shifts (id, staff_id, role_type, check_in_time, status, created_at, updated_at)
+             VALUES ('s-1', 'staff-1', 'cashier', datetime('now'), 'active', datetime('now'), datetime('now'))",
+            [],
+        )
+        .unwrap();
+        assert!(switch_blocker(&conn).unwrap().unwrap().contains("shift"));
+        conn.execute("UPDATE staff_shifts SET status = 'closed'", [])
+            .unwrap();
+        conn.execute(
+            "INSERT INTO cash_drawer_sessions (
+                id, staff_shift_id, cashier_id, branch_id, terminal_id, opening_amount,
+                opened_at, created_at, updated_at
+             ) VALUES ('d-1', 's-1', 'staff-1', 'b-1', 't-1', 100.0,
+                datetime('now'), datetime('now'), datetime('now'))",
+            [],
+        )
+        .unwrap();
+        assert!(switch_blocker(&conn).unwrap().unwrap().contains("drawer"));
+    }
+
+    #[test]
+    fn report_scope_defaults_to_active_branch() {
+        let _keyring = fake_keyring::install_seeded([("branch_id", "b-active")]);
+        assert_eq!(report_scope(None), "b-active");
+        assert_eq!(report_scope(Some("  ".into())), "b-active");
+        assert_eq!(report_scope(Some("b-other".into())), "b-other");
+    }
+}
@@ -1,4 +1,6 @@
-use chrono::{DateTime, Days, Local, Timelike};
+use chrono::{
+    DateTime, Days, Duration, Local, NaiveDate, NaiveTime, SecondsFormat, TimeZone, Timelike, Utc,
+};
 use rusqlite::{params, Connection, OptionalExtension};
 
 use crate::db;
@@ -90,6 +92,29 @@ pub(crate) fn open_unsettled_table_tab_expr(order_alias: &str) -> String {
     )
 }
 
+/// Unpaid balance, in cents, of a deposit order (`payment_status =
+/// 'partially_paid'`): the order total minus every completed payment.
+///
+/// Z-report gross subtracts this so a deposit order contributes only the
+/// money actually taken, not its full total. Refunds are deliberately not
+/// netted here — the refund line already subtracts them from net sales.
+pub(crate) fn deposit_balance_due_cents_expr(order_alias: &str) -> String {
+    format!(
+        "(CASE WHEN LOWER(TRIM(COALESCE({order_alias}.payment_status, ''))) = 'partially_paid'
+            THEN MAX(
+                COALESCE({order_alias}.total_amount_cents, CAST(ROUND({order_alias}.total_amount * 100) AS INTEGER), 0)
+                - COALESCE((
+                    SELECT SUM(COALESCE(op_dep.amount_cents, CAST(ROUND(op_dep.amount * 100) AS INTEGER), 0))
+                    FROM order_payments op_dep
+                    WHERE op_dep.order_id = {order_alias}.id
+                      AND op_dep.status = 'completed'
+                ), 0),
+                0)
+            ELSE 0
+        END)"
+    )
+}
+
 pub(crate) fn resolve_order_financial_effective_at(
     conn: &Connection,
     order_id: &str,
@@ -182,6 +207,87 @@ pub(crate) fn current_business_day_report_date_at(
     business_day_report_date_at_minutes(now, resolve_business_day_start_minutes(conn))
 }
 
+/// UTC instant at which business day `date` starts in `tz`: local `date` at
+/// the configured start time. A start time that falls into a DST gap moves
+/// forward to the first local minute that exists; an ambiguous one (DST
+/// fall-back) uses the earlier instant.
+pub(crate) fn business_day_start_in<Tz: TimeZone>(
+    tz: &Tz,
+    date: NaiveDate,
+    business_day_start_minutes: u32,
+) -> DateTime<Utc> {
+    let start =
+        date.and_time(NaiveTime::MIN) + Duration::minutes(i64::from(business_day_start_minutes));
+    (0..=180)
+        .find_map(|offset| {
+            tz.from_local_datetime(&(start + Duration::minutes(offset)))
+                .earliest()
+        })
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|| start.and_utc())
+}
+
+/// Business day an instant belongs to in `tz`: its local date, or the day
+/// before when the local time is earlier than the configured start.
+pub(crate) fn business_date_in<Tz: TimeZone>(
+    tz: &Tz,
+    instant: DateTime<Utc>,
+    business_day_start_minutes: u32,
+) -> NaiveDate {
+    let date = instant.with_timezone(tz).date_naive();
+    if instant < business_day_start_in(tz, date, business_day_start_minutes) {
+        date.pred_opt().unwrap_or(date)
+    } else {
+        date
+    }
+}
+
+fn parse_report_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|e| format!("invalid report date {value:?}: {e}"))
+}
+
+/// Half-open UTC range `[start, end)` covering business days `date_from`
+/// through `date_to` in `tz`, as RFC3339 strings for SQL comparison.
+pub(crate) fn business_day_bounds_in<Tz: TimeZone>(
+    tz: &Tz,
+    date_from: &str,
+    date_to: &str,
+    business_day_start_minutes: u32,
+) -> Result<(String, String), String> {
+    let from = parse_report_date(date_from)?;
+    let to = parse_report_date(date_to)?;
+    let end_day = to.succ_opt().ok_or("report date out of range")?;
+    let start = business_day_start_in(tz, from, business_day_start_minutes);
+    let end = business_day_start_in(tz, end_day, business_day_start_minutes);
+    Ok((
+        start.to_rfc3339_opts(SecondsFormat::Secs, true),
+        end.to_rfc3339_opts(SecondsFormat::Secs, true),
+    ))
+}
+
+/// [`business_day_bounds_in`] for the local timezone and the configured
+/// business-day start.
+pub(crate) fn business_day_bounds(
+    conn: &Connection,
+    date_from: &str,
+    date_to: &str,
+) -> Result<(String, String), String> {
+    business_day_bounds_in(
+        &Local,
+        date_from,
+        date_to,
+        resolve_business_day_start_minutes(conn),
+    )
+}
+
+/// SQL predicate keeping `column` inside the half-open range bound to the
+/// `start` and `end` parameters. Compares instants rather than date
+/// prefixes so offsets in stored timestamps are honoured.
+pub(crate) fn timestamp_in_range_sql(column: &str, start: &str, end: &str) -> String {
+    format!("julianday({column}) >= julianday({start}) AND julianday({column}) < julianday({end})")
+}
+
 pub(crate) fn local_report_date_from_timestamp(value: &str) -> String {
     DateTime::parse_from_rfc3339(value)
         .map(|dt| dt.with_timezone(&Local).format("%Y-%m-%d").to_string())
@@ -242,14 +348,14 @@ fn infer_branch_period_start(
          FROM (
             SELECT MIN(check_in_time) AS ts
             FROM staff_shifts
-            WHERE (?1 = '' OR branch_id = ?1 OR branch_id IS NULL)
+            WHERE (branch_id = ?1 OR branch_id IS NULL)
               AND (?2 IS NULL OR check_in_time <= ?2)
 
             UNION ALL
 
             SELECT MIN(created_at) AS ts
             FROM orders
-            WHERE (?1 = '' OR branch_id = ?1 OR branch_id IS NULL)
+            WHERE (branch_id = ?1 OR branch_id IS NULL)
               AND COALESCE(is_ghost, 0) = 0
               AND (?2 IS NULL OR created_at <= ?2)
 
@@ -258,7 +364,7 @@ fn infer_branch_period_start(
             SELECT MIN(op.created_at) AS ts
             FROM order_payments op
             JOIN orders o ON o.id = op.order_id
-            WHERE (?1 = '' OR o.branch_id = ?1 OR o.branch_id IS NULL)
+            WHERE (o.branch_id = ?1 OR o.branch_id IS NULL)
               AND op.status = 'completed'
               AND COALESCE(o.is_ghost, 0) = 0
               AND (?2 IS NULL OR op.created_at <= ?2)
@@ -267,7 +373,7 @@ fn infer_branch_period_start(
 
             SELECT MIN(opened_at) AS ts
             FROM cash_drawer_sessions
-            WHERE (?1 = '' OR branch_id = ?1 OR branch_id IS NULL)
+            WHERE (branch_id = ?1 OR branch_id IS NULL)
               AND (?2 IS NULL OR opened_at <= ?2)
 
             UNION ALL
@@ -275,7 +381,7 @@ fn infer_branch_period_start(
             SELECT MIN(se.created_at) AS ts
             FROM shift_expenses se
             JOIN staff_shifts ss ON ss.id = se.staff_shift_id
-            WHERE (?1 = '' OR ss.branch_id = ?1 OR ss.branch_id IS NULL)
+            WHERE (ss.branch_id = ?1 OR ss.branch_id IS NULL)
               AND (?2 IS NULL OR se.created_at <= ?2)
          )
          WHERE ts IS NOT NULL",
@@ -310,7 +416,7 @@ pub(crate) fn find_cashier_owner_for_timestamp(
         "SELECT id, staff_id
          FROM staff_shifts
          WHERE role_type IN ('cashier', 'manager')
-           AND (?1 = '' OR branch_id = ?1 OR branch_id IS NULL)
+           AND (branch_id = ?1 OR branch_id IS NULL)
            AND check_in_time <= ?2
            AND (check_out_time IS NULL OR check_out_time >= ?2)
          ORDER BY check_in_time DESC
@@ -420,4 +526,137 @@ mod tests {
             "2026-02-17"
         );
     }
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .expect("valid timestamp")
+            .with_timezone(&Utc)
+    }
+
+    fn athens_business_date(value: &str, start_minutes: u32) -> String {
+        business_date_in(&chrono_tz::Europe::Athens, utc(value), start_minutes)
+            .format("%Y-%m-%d")
+            .to_string()
+    }
+
+    #[test]
+    fn business_date_keeps_late_night_orders_on_the_previous_day() {
+        // Athens springs forward 03:00 -> 04:00 local on 2026-03-29.
+        let start = DEFAULT_BUSINESS_DAY_START_MINUTES;
+        // 23:50 local (EET, UTC+2) on the 28th.
+        assert_eq!(
+            athens_business_date("2026-03-28T21:50:00Z", start),
+            "2026-03-28"
+        );
+        // 00:10 local on the 29th is still the 28th's business day, even
+        // though its UTC date prefix is the 28th and its local date the 29th.
+        assert_eq!(
+            athens_business_date("2026-03-28T22:10:00Z", start),
+            "2026-03-28"
+        );
+        // 07:00 local (EEST, UTC+3) opens the 29th; a second earlier does not.
+        assert_eq!(
+            athens_business_date("2026-03-29T03:59:59Z", start),
+            "2026-03-28"
+        );
+        assert_eq!(
+            athens_business_date("2026-03-29T04:00:00Z", start),
+            "2026-03-29"
+        );
+    }
+
+    #[test]
+    fn business_day_start_inside_dst_gap_moves_to_first_valid_minute() {
+        let athens = chrono_tz::Europe::Athens;
+        let start = 3 * 60 + 30;
+        let date = NaiveDate::from_ymd_opt(2026, 3, 29).unwrap();
+        // 03:30 does not exist on the 29th; the day opens at 04:00 EEST.
+        assert_eq!(
+            business_day_start_in(&athens, date, start),
+            utc("2026-03-29T01:00:00Z")
+        );
+        assert_eq!(
+            athens_business_date("2026-03-29T00:59:59Z", start),
+            "2026-03-28"
+        );
+        assert_eq!(
+            athens_business_date("2026-03-29T01:00:00Z", start),
+            "2026-03-29"
+        );
+    }
+
+    #[test]
+    fn business_day_start_inside_repeated_hour_uses_first_occurrence() {
+        // Athens falls back 04:00 -> 03:00 local on 2026-10-25, so 03:30
+        // happens twice: 00:30Z (EEST) and 01:30Z (EET).
+        let start = 3 * 60 + 30;
+        assert_eq!(
+            athens_business_date("2026-10-25T00:29:59Z", start),
+            "2026-10-24"
+        );
+        assert_eq!(
+            athens_business_date("2026-10-25T00:30:00Z", start),
+            "2026-10-25"
+        );
+        assert_eq!(
+            athens_business_date("2026-10-25T01:30:00Z", start),
+            "2026-10-25"
+        );
+    }
+
+    #[test]
+    fn business_day_bounds_select_orders_by_instant_across_dst() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch("CREATE TABLE orders (id TEXT, created_at TEXT);")
+            .expect("create orders");
+        for (id, created_at) in [
+            ("late-28th", "2026-03-28T21:50:00.123456789+00:00"),
+            ("after-midnight", "2026-03-28T22:10:00+00:00"),
+            ("before-open", "2026-03-29T06:59:59+03:00"),
+            ("at-open", "2026-03-29T07:00:00+03:00"),
+            ("evening-29th", "2026-03-29T20:00:00Z"),
+        ] {
+            conn.execute(
+                "INSERT INTO orders (id, created_at) VALUES (?1, ?2)",
+                params![id, created_at],
+            )
+            .expect("insert order");
+        }
+
+        let (start, end) = business_day_bounds_in(
+            &chrono_tz::Europe::Athens,
+            "2026-03-29",
+            "2026-03-29",
+            DEFAULT_BUSINESS_DAY_START_MINUTES,
+        )
+        .expect("bounds");
+        assert_eq!(start, "2026-03-29T04:00:00Z");
+        assert_eq!(end, "2026-03-30T04:00:00Z");
+
+        let sql = format!(
+            "SELECT id FROM orders WHERE {} ORDER BY created_at",
+            timestamp_in_range_sql("created_at", "?1", "?2")
+        );
+        let mut stmt = conn.prepare(&sql).expect("prepare");
+        let ids: Vec<String> = stmt
+            .query_map(params![start, end], |row| row.get(0))
+            .expect("query")
+            .collect::<Result<_, _>>()
+            .expect("rows");
+        assert_eq!(ids, vec!["at-open", "evening-29th"]);
+
+        let (start, end) = business_day_bounds_in(
+            &chrono_tz::Europe::Athens,
+            "2026-03-28",
+            "2026-03-28",
+            DEFAULT_BUSINESS_DAY_START_MINUTES,
+        )
+        .expect("bounds");
+        let ids: Vec<String> = stmt
+            .query_map(params![start, end], |row| row.get(0))
+            .expect("query")
+            .collect::<Result<_, _>>()
+            .expect("rows");
+        assert_eq!(ids, vec!["late-28th", "after-midnight", "before-open"]);
+    }
 }
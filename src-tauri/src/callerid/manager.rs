@@ -1,12 +1,14 @@
-//! Caller ID Manager — lifecycle orchestrator for the SIP listener.
+//! Caller ID Manager — lifecycle orchestrator for the SIP and modem listeners.
 //!
 //! Thread-safe singleton registered as Tauri managed state. Holds the
-//! listener configuration, status, and a handle to the background task.
+//! listener configuration, status, and handles to the background tasks.
 
 use std::sync::Mutex;
 use tracing::info;
 
-use super::types::{CallerIdConfig, CallerIdStatus, CallerIdStatusReason, ListenerStatus};
+use super::types::{
+    CallerIdConfig, CallerIdStatus, CallerIdStatusReason, ListenerStatus, ModemStatus,
+};
 
 // ---------------------------------------------------------------------------
 // Inner state
@@ -21,6 +23,9 @@ struct Inner {
     calls_detected: u64,
     /// Handle to cancel the background listener task
     task_cancel: Option<tokio_util::sync::CancellationToken>,
+    modem: ModemStatus,
+    /// Handle to cancel the background modem task
+    modem_cancel: Option<tokio_util::sync::CancellationToken>,
 }
 
 impl Default for Inner {
@@ -33,6 +38,8 @@ impl Default for Inner {
             registered: false,
             calls_detected: 0,
             task_cancel: None,
+            modem: ModemStatus::default(),
+            modem_cancel: None,
         }
     }
 }
@@ -94,6 +101,7 @@ impl CallerIdManager {
                 reason: i.reason,
                 registered: i.registered,
                 calls_detected: i.calls_detected,
+                modem: i.modem.clone(),
             })
             .unwrap_or(CallerIdStatus {
                 status: ListenerStatus::Error,
@@ -101,6 +109,7 @@ impl CallerIdManager {
                 reason: Some(CallerIdStatusReason::Unknown),
                 registered: false,
                 calls_detected: 0,
+                modem: ModemStatus::default(),
             })
     }
 
@@ -184,10 +193,52 @@ impl CallerIdManager {
             .unwrap_or(false)
     }
 
-    /// Graceful shutdown — stop listener and clear state.
+    /// Store the cancellation token for a newly started modem task,
+    /// cancelling the previous one.
+    pub fn set_modem_task(&self, port: &str, token: tokio_util::sync::CancellationToken) {
+        if let Ok(mut inner) = self.inner.lock() {
+            if let Some(previous) = inner.modem_cancel.replace(token) {
+                previous.cancel();
+            }
+            inner.modem = ModemStatus {
+                port: Some(port.to_string()),
+                ..ModemStatus::default()
+            };
+        }
+    }
+
+    /// Record the modem listener state.
+    pub fn set_modem_status(&self, status: ListenerStatus, error: Option<String>) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.modem.status = status;
+            inner.modem.error = error;
+        }
+    }
+
+    /// Record that the modem port was reopened after the device dropped.
+    pub fn record_modem_reconnect(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.modem.reconnects += 1;
+        }
+    }
+
+    /// Stop the background modem task (if running).
+    pub fn stop_modem(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            if let Some(cancel) = inner.modem_cancel.take() {
+                info!("CallerIdManager: stopping modem listener");
+                cancel.cancel();
+            }
+            inner.modem.status = ListenerStatus::Stopped;
+            inner.modem.error = None;
+        }
+    }
+
+    /// Graceful shutdown — stop listeners and clear state.
     #[allow(dead_code)]
     pub fn shutdown(&self) {
         self.stop();
+        self.stop_modem();
         info!("CallerIdManager shutdown complete");
     }
 }
@@ -293,6 +344,31 @@ mod tests {
         assert_eq!(mgr.get_status().status, ListenerStatus::Stopped);
     }
 
+    #[test]
+    fn test_modem_status_is_tracked_apart_from_sip() {
+        let mgr = CallerIdManager::new();
+        let first = tokio_util::sync::CancellationToken::new();
+        mgr.set_modem_task("COM4", first.clone());
+        mgr.set_modem_status(ListenerStatus::Listening, None);
+        assert_eq!(mgr.get_status().status, ListenerStatus::Stopped);
+        assert_eq!(mgr.get_status().modem.status, ListenerStatus::Listening);
+        assert_eq!(mgr.get_status().modem.port.as_deref(), Some("COM4"));
+
+        mgr.set_modem_status(ListenerStatus::Reconnecting, Some("unplugged".into()));
+        mgr.record_modem_reconnect();
+        assert_eq!(mgr.get_status().modem.reconnects, 1);
+
+        // Restarting cancels the previous task.
+        let second = tokio_util::sync::CancellationToken::new();
+        mgr.set_modem_task("COM5", second.clone());
+        assert!(first.is_cancelled());
+        assert_eq!(mgr.get_status().modem.reconnects, 0);
+
+        mgr.stop_modem();
+        assert!(second.is_cancelled());
+        assert_eq!(mgr.get_status().modem.status, ListenerStatus::Stopped);
+    }
+
     #[test]
     fn test_shutdown() {
         let mgr = CallerIdManager::new();
@@ -2,15 +2,20 @@
 //!
 //! Provides SIP-based caller ID recognition for VoIP phone lines. When a
 //! phone rings, the POS terminal detects the caller's number via SIP INVITE
-//! parsing and shows a notification popup with customer lookup.
+//! parsing and shows a notification popup with customer lookup. Analogue
+//! lines are covered by a USB caller ID modem on a serial port.
 //!
 //! Architecture mirrors the ECR module pattern:
 //! - `types.rs`        — Config, event, and status types
 //! - `sip_parser.rs`   — Manual SIP message parser (~250 LOC, no external SIP crate)
 //! - `sip_listener.rs` — Background UDP listener (tokio::spawn + CancellationToken)
+//! - `modem_parser.rs` — FSK (SDMF/MDMF, formatted) and DTMF modem frame parser
+//! - `modem_listener.rs` — Background serial listener with automatic reconnect
 //! - `manager.rs`      — CallerIdManager singleton (Mutex + Tauri managed state)
 
 pub mod manager;
+pub mod modem_listener;
+pub mod modem_parser;
 pub mod sip_listener;
 pub mod sip_parser;
 pub mod types;
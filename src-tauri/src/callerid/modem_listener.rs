@@ -0,0 +1,406 @@
+//! Background listener for serial caller ID modems.
+//!
+//! Opens the configured port through the shared serial pool, sends the init
+//! command, and feeds everything read into a [`ModemFrameParser`]. Each
+//! decoded call is looked up (customer and recent orders) and emitted as an
+//! `incoming_call` event. When the device is unplugged the read fails, the
+//! port is closed, and the task keeps reopening it with backoff until the
+//! device is back or the listener is stopped.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+use tauri::{Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use super::manager::CallerIdManager;
+use super::modem_parser::{ModemCall, ModemFrameParser};
+use super::types::{ListenerStatus, ModemConfig};
+use crate::{db, normalize_phone};
+
+/// Event carrying a caller, their customer record and recent orders.
+pub const INCOMING_CALL_EVENT: &str = "incoming_call";
+const STATUS_EVENT: &str = "caller_id_modem_status_changed";
+/// Serial read timeout; a read returning nothing counts as the line going
+/// quiet.
+const READ_TIMEOUT_MS: u64 = 250;
+const READ_CHUNK: usize = 256;
+const RECONNECT_INITIAL: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(30);
+const RECENT_ORDERS_LIMIT: i64 = 5;
+/// Shorter numbers (internal extensions, garbled frames) are not looked up.
+const MIN_LOOKUP_DIGITS: usize = 5;
+
+pub fn start_modem_listener(
+    config: ModemConfig,
+    manager: Arc<CallerIdManager>,
+    app_handle: tauri::AppHandle,
+    cancel: CancellationToken,
+) {
+    let listener_cancel = cancel.child_token();
+    manager.set_modem_task(&config.port, listener_cancel.clone());
+
+    tokio::spawn(async move {
+        run_modem_listener(config, manager, app_handle, listener_cancel).await;
+    });
+}
+
+async fn run_modem_listener(
+    config: ModemConfig,
+    manager: Arc<CallerIdManager>,
+    app_handle: tauri::AppHandle,
+    cancel: CancellationToken,
+) {
+    let mut backoff = RECONNECT_INITIAL;
+    let mut opened_before = false;
+
+    while !cancel.is_cancelled() {
+        match open_modem(&config).await {
+            Ok(handle) => {
+                if opened_before {
+                    manager.record_modem_reconnect();
+                }
+                opened_before = true;
+                backoff = RECONNECT_INITIAL;
+                info!(port = %config.port, "Caller ID modem listening");
+                set_status(
+                    &manager,
+                    &app_handle,
+                    &cancel,
+                    ListenerStatus::Listening,
+                    None,
+                );
+
+                let error = read_until_error(&handle, &manager, &app_handle, &cancel).await;
+                let _ = crate::serial::close_port(&handle);
+                let Some(error) = error else {
+                    break;
+                };
+                warn!(port = %config.port, error = %error, "Caller ID modem disconnected");
+                set_status(
+                    &manager,
+                    &app_handle,
+                    &cancel,
+                    ListenerStatus::Reconnecting,
+                    Some(error),
+                );
+            }
+            Err(error) => {
+                warn!(port = %config.port, error = %error, "Caller ID modem unavailable");
+                set_status(
+                    &manager,
+                    &app_handle,
+                    &cancel,
+                    ListenerStatus::Reconnecting,
+                    Some(error),
+                );
+            }
+        }
+
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = tokio::time::sleep(backoff) => {}
+        }
+        backoff = (backoff * 2).min(RECONNECT_MAX);
+    }
+
+    info!(port = %config.port, "Caller ID modem listener stopped");
+}
+
+/// Open the port and send the init command. Returns the serial pool handle.
+async fn open_modem(config: &ModemConfig) -> Result<String, String> {
+    let config = config.clone();
+    tokio::task::spawn_blocking(move || {
+        let opened =
+            crate::serial::open_port(&config.port, config.baud_rate, Some(READ_TIMEOUT_MS))?;
+        let handle = opened["handle"]
+            .as_str()
+            .ok_or("No handle returned")?
+            .to_string();
+        let init = config.init_command.trim();
+        if init.is_empty() {
+            return Ok(handle);
+        }
+        match crate::serial::write_port(&handle, format!("{init}\r").as_bytes()) {
+            Ok(_) => Ok(handle),
+            Err(error) => {
+                let _ = crate::serial::close_port(&handle);
+                Err(error)
+            }
+        }
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("modem open join error: {e}")))
+}
+
+/// Read and dispatch calls until the port fails (`Some(error)`) or the
+/// listener is cancelled (`None`).
+async fn read_until_error(
+    handle: &str,
+    manager: &Arc<CallerIdManager>,
+    app_handle: &tauri::AppHandle,
+    cancel: &CancellationToken,
+) -> Option<String> {
+    let mut parser = ModemFrameParser::new();
+    while !cancel.is_cancelled() {
+        let handle_for_read = handle.to_string();
+        let read = tokio::task::spawn_blocking(move || {
+            crate::serial::read_port(&handle_for_read, READ_CHUNK)
+        })
+        .await
+        .unwrap_or_else(|e| Err(format!("modem read join error: {e}")));
+        let bytes = match read {
+            Ok(result) => raw_bytes(&result),
+            Err(error) => return Some(error),
+        };
+
+        let calls = if bytes.is_empty() {
+            parser.flush_idle()
+        } else {
+            parser.push(&bytes)
+        };
+        for call in calls {
+            handle_modem_call(app_handle, manager, call).await;
+        }
+    }
+    None
+}
+
+fn raw_bytes(read: &Value) -> Vec<u8> {
+    read["raw"]
+        .as_array()
+        .map(|bytes| {
+            bytes
+                .iter()
+                .filter_map(Value::as_u64)
+                .filter_map(|byte| u8::try_from(byte).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn set_status(
+    manager: &CallerIdManager,
+    app_handle: &tauri::AppHandle,
+    cancel: &CancellationToken,
+    status: ListenerStatus,
+    error: Option<String>,
+) {
+    // A stopped or replaced task must not overwrite its successor's state.
+    if cancel.is_cancelled() {
+        return;
+    }
+    manager.set_modem_status(status, error.clone());
+    let _ = app_handle.emit(
+        STATUS_EVENT,
+        json!({
+            "status": status,
+            "error": error,
+        }),
+    );
+}
+
+/// Look up the caller and emit `incoming_call`. Returns the event payload.
+pub async fn handle_modem_call(
+    app_handle: &tauri::AppHandle,
+    manager: &CallerIdManager,
+    call: ModemCall,
+) -> Value {
+    manager.increment_calls();
+    let db_state = app_handle.state::<db::DbState>();
+    let number = normalize_phone(call.number.as_deref().unwrap_or_default());
+
+    let (customer, recent_orders) = if number.len() >= MIN_LOOKUP_DIGITS {
+        let raw_number = call.number.as_deref().unwrap_or_default();
+        let customer = crate::commands::customers::lookup_customer_by_phone(&db_state, raw_number)
+            .await
+            .unwrap_or_else(|error| {
+                warn!(error = %error, "Caller ID customer lookup failed");
+                Value::Null
+            });
+        let recent_orders = match db_state.conn.lock() {
+            Ok(conn) => recent_orders_summary(&conn, &number).unwrap_or_else(|error| {
+                warn!(error = %error, "Caller ID recent orders lookup failed");
+                empty_orders_summary()
+            }),
+            Err(_) => empty_orders_summary(),
+        };
+        (customer, recent_orders)
+    } else {
+        (Value::Null, empty_orders_summary())
+    };
+
+    let call_id = format!("modem-{}", uuid::Uuid::new_v4());
+    let payload = incoming_call_payload(
+        &call,
+        &number,
+        customer,
+        recent_orders,
+        &call_id,
+        &Utc::now().to_rfc3339(),
+    );
+    let _ = app_handle.emit(INCOMING_CALL_EVENT, payload.clone());
+
+    if let Ok(conn) = db_state.conn.lock() {
+        if let Err(error) = db::upsert_caller_id_log(
+            &conn,
+            call.number.as_deref().unwrap_or_default(),
+            call.name.as_deref(),
+            payload["customer"].get("id").and_then(Value::as_str),
+            payload["customer"].get("name").and_then(Value::as_str),
+            &call_id,
+            "detected",
+        ) {
+            warn!(error = %error, "Failed to persist caller_id_log row");
+        }
+    }
+    payload
+}
+
+fn incoming_call_payload(
+    call: &ModemCall,
+    number: &str,
+    customer: Value,
+    recent_orders: Value,
+    call_id: &str,
+    timestamp: &str,
+) -> Value {
+    json!({
+        "source": "modem",
+        "callId": call_id,
+        "number": (!number.is_empty()).then_some(number),
+        "callerNumber": call.number,
+        "callerName": call.name,
+        "numberAbsent": call.number_absent,
+        "format": call.format,
+        "customer": customer,
+        "recentOrders": recent_orders,
+        "timestamp": timestamp,
+    })
+}
+
+fn empty_orders_summary() -> Value {
+    json!({ "count": 0, "lastOrderAt": null, "orders": [] })
+}
+
+/// Order count and the latest orders whose phone ends with `number`
+/// (digits only), so a number sent without its country code still matches
+/// one stored with it.
+fn recent_orders_summary(conn: &Connection, number: &str) -> Result<Value, String> {
+    const PHONE_DIGITS: &str = "replace(replace(replace(replace(replace(customer_phone, '-', ''), ' ', ''), '(', ''), ')', ''), '+', '')";
+    let pattern = format!("%{number}");
+    let (count, last_order_at): (i64, Option<String>) = conn
+        .query_row(
+            &format!(
+                "SELECT COUNT(*), MAX(created_at) FROM orders
+                 WHERE customer_phone IS NOT NULL
+                   AND COALESCE(is_ghost, 0) = 0
+                   AND {PHONE_DIGITS} LIKE ?1"
+            ),
+            params![pattern],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("count caller orders: {e}"))?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, order_number, status, order_type, total_amount, created_at
+             FROM orders
+             WHERE customer_phone IS NOT NULL
+               AND COALESCE(is_ghost, 0) = 0
+               AND {PHONE_DIGITS} LIKE ?1
+             ORDER BY created_at DESC
+             LIMIT ?2"
+        ))
+        .map_err(|e| format!("prepare caller orders: {e}"))?;
+    let orders = stmt
+        .query_map(params![pattern, RECENT_ORDERS_LIMIT], |row| {
+            Ok(json!({
+                "id": row.get::<_, String>(0)?,
+                "orderNumber": row.get::<_, Option<String>>(1)?,
+                "status": row.get::<_, Option<String>>(2)?,
+                "orderType": row.get::<_, Option<String>>(3)?,
+                "totalAmount": row.get::<_, Option<f64>>(4)?,
+                "createdAt": row.get::<_, Option<String>>(5)?,
+            }))
+        })
+        .map_err(|e| format!("query caller orders: {e}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read caller orders: {e}"))?;
+
+    Ok(json!({
+        "count": count,
+        "lastOrderAt": last_order_at,
+        "orders": orders,
+    }))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callerid::modem_parser::ModemFrameParser;
+
+    #[test]
+    fn test_incoming_call_payload_carries_customer_and_recent_orders() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        for (id, phone, created_at, ghost) in [
+            ("o1", "+30 691-234-5678", "2026-03-01T10:00:00Z", 0),
+            ("o2", "(691) 234 5678", "2026-03-02T10:00:00Z", 0),
+            ("o3", "6912345678", "2026-03-03T10:00:00Z", 1),
+            ("o4", "2101234567", "2026-03-04T10:00:00Z", 0),
+        ] {
+            conn.execute(
+                "INSERT INTO orders (id, order_number, items, total_amount, status, order_type,
+                                     customer_phone, is_ghost, sync_status, created_at, updated_at)
+                 VALUES (?1, ?1, '[]', 12.5, 'completed', 'delivery', ?2, ?3, 'synced', ?4, ?4)",
+                params![id, phone, ghost, created_at],
+            )
+            .unwrap();
+        }
+
+        let summary = recent_orders_summary(&conn, "6912345678").unwrap();
+        assert_eq!(summary["count"], 2);
+        assert_eq!(summary["lastOrderAt"], "2026-03-02T10:00:00Z");
+        assert_eq!(summary["orders"][0]["id"], "o2");
+        assert_eq!(summary["orders"][1]["id"], "o1");
+
+        let call = ModemFrameParser::new()
+            .push(b"NMBR = 691 234 5678\r\nNAME = MARIA\r\n")
+            .remove(0);
+        let payload = incoming_call_payload(
+            &call,
+            "6912345678",
+            json!({ "id": "cust-1", "name": "Maria" }),
+            summary,
+            "modem-1",
+            "2026-03-05T12:00:00Z",
+        );
+        assert_eq!(payload["number"], "6912345678");
+        assert_eq!(payload["callerNumber"], "691 234 5678");
+        assert_eq!(payload["customer"]["id"], "cust-1");
+        assert_eq!(payload["recentOrders"]["count"], 2);
+        assert_eq!(payload["format"], "formatted");
+
+        let withheld = ModemFrameParser::new().push(b"B10C\r\n").remove(0);
+        let payload = incoming_call_payload(
+            &withheld,
+            "",
+            Value::Null,
+            empty_orders_summary(),
+            "modem-2",
+            "2026-03-05T12:00:00Z",
+        );
+        assert!(payload["number"].is_null());
+        assert!(payload["customer"].is_null());
+        assert_eq!(payload["numberAbsent"], "private");
+    }
+}
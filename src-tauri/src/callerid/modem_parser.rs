@@ -0,0 +1,510 @@
+//! Caller ID frame parser for serial caller ID modems.
+//!
+//! A USB caller ID modem reports the calling number in one of these forms:
+//!
+//! - **Formatted FSK** (`AT+VCID=1`): text lines such as `DATE = 0321`,
+//!   `TIME = 1405`, `NMBR = 5551234`, `NAME = JOHN DOE`, with `RING`
+//!   lines between rings.
+//! - **Raw FSK** (Bellcore / ETSI): binary SDMF (`0x04`) or MDMF (`0x80`)
+//!   messages closed by a two's-complement checksum, either as bytes or,
+//!   with `AT+VCID=2`, as a line of hex digits.
+//! - **DTMF** (ETSI EN 300 659-1 Annex B): `A<digits>C` or `D<digits>C`,
+//!   with `B<code>C` when the number is withheld.
+//!
+//! [`ModemFrameParser`] takes bytes as they arrive, in chunks of any size,
+//! and yields one [`ModemCall`] per complete frame. Bad checksums, truncated
+//! frames, line noise and unknown lines are dropped; the parser never
+//! panics and its buffers are bounded.
+
+use serde::Serialize;
+
+/// SDMF message type.
+const SDMF_TYPE: u8 = 0x04;
+/// MDMF message type.
+const MDMF_TYPE: u8 = 0x80;
+/// MDMF parameter types.
+const PARAM_DATE_TIME: u8 = 0x01;
+const PARAM_NUMBER: u8 = 0x02;
+const PARAM_NUMBER_ABSENT: u8 = 0x04;
+const PARAM_NAME: u8 = 0x07;
+const PARAM_NAME_ABSENT: u8 = 0x08;
+/// Longest text line kept while waiting for its newline.
+const MAX_LINE_LEN: usize = 256;
+/// Bytes buffered before the oldest are dropped as noise.
+const MAX_BUFFER_LEN: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModemFrameFormat {
+    Sdmf,
+    Mdmf,
+    Formatted,
+    Dtmf,
+}
+
+/// Why a frame carries no number (or no name).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AbsenceReason {
+    Private,
+    Unavailable,
+}
+
+/// One caller ID report decoded from the modem.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModemCall {
+    pub format: ModemFrameFormat,
+    /// Number as sent; `None` when withheld or unavailable.
+    pub number: Option<String>,
+    pub name: Option<String>,
+    pub number_absent: Option<AbsenceReason>,
+    /// `MMDDHHMM` from the frame, when present.
+    pub date_time: Option<String>,
+}
+
+impl ModemCall {
+    fn new(format: ModemFrameFormat) -> Self {
+        Self {
+            format,
+            number: None,
+            name: None,
+            number_absent: None,
+            date_time: None,
+        }
+    }
+
+    fn has_caller(&self) -> bool {
+        self.number.is_some() || self.number_absent.is_some()
+    }
+}
+
+/// Streaming parser for the modem byte stream.
+#[derive(Debug, Default)]
+pub struct ModemFrameParser {
+    buffer: Vec<u8>,
+    /// Formatted fields seen since the last emitted call.
+    pending: Option<ModemCall>,
+}
+
+impl ModemFrameParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed bytes read from the port; returns the calls they complete.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<ModemCall> {
+        self.buffer.extend_from_slice(bytes);
+        if self.buffer.len() > MAX_BUFFER_LEN {
+            let excess = self.buffer.len() - MAX_BUFFER_LEN;
+            self.buffer.drain(..excess);
+        }
+
+        let mut calls = Vec::new();
+        loop {
+            let Some(&first) = self.buffer.first() else {
+                break;
+            };
+            match first {
+                SDMF_TYPE | MDMF_TYPE if !plausible_header(&self.buffer) => {
+                    self.buffer.remove(0);
+                }
+                SDMF_TYPE | MDMF_TYPE => match binary_frame_len(&self.buffer) {
+                    None => break,
+                    Some(len) => match decode_binary_frame(&self.buffer[..len]) {
+                        Some(call) => {
+                            self.buffer.drain(..len);
+                            calls.extend(self.flush());
+                            calls.push(call);
+                        }
+                        // Not a valid frame after all: skip the byte and
+                        // resynchronise on whatever follows.
+                        None => {
+                            self.buffer.remove(0);
+                        }
+                    },
+                },
+                b'\r' | b'\n' => {
+                    self.buffer.remove(0);
+                }
+                byte if is_text(byte) => {
+                    let Some(end) = self.buffer.iter().position(|b| !is_text(*b)) else {
+                        if self.buffer.len() > MAX_LINE_LEN {
+                            self.buffer.clear();
+                        }
+                        break;
+                    };
+                    let line: Vec<u8> = self.buffer.drain(..end).collect();
+                    // Text running into binary is noise, not a line.
+                    if matches!(self.buffer[0], b'\r' | b'\n') {
+                        calls.extend(self.handle_line(&String::from_utf8_lossy(&line)));
+                    }
+                }
+                _ => {
+                    self.buffer.remove(0);
+                }
+            }
+        }
+        calls
+    }
+
+    /// Called once the line goes quiet: nothing more is coming for what is
+    /// buffered, so a stalled frame header is dropped as noise, a text tail
+    /// is read as a line without its newline, and formatted fields still
+    /// waiting for NAME or RING are emitted.
+    pub fn flush_idle(&mut self) -> Vec<ModemCall> {
+        let mut calls = Vec::new();
+        while let Some(&first) = self.buffer.first() {
+            if matches!(first, SDMF_TYPE | MDMF_TYPE) {
+                self.buffer.remove(0);
+            } else {
+                self.buffer.push(b'\n');
+            }
+            calls.extend(self.push(&[]));
+        }
+        calls.extend(self.flush());
+        calls
+    }
+
+    fn flush(&mut self) -> Option<ModemCall> {
+        self.pending.take().filter(ModemCall::has_caller)
+    }
+
+    fn handle_line(&mut self, line: &str) -> Vec<ModemCall> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Vec::new();
+        }
+        if let Some(call) = parse_dtmf(line) {
+            return self.flush().into_iter().chain([call]).collect();
+        }
+        if let Some(call) = parse_hex_frame(line) {
+            return self.flush().into_iter().chain([call]).collect();
+        }
+        if line.eq_ignore_ascii_case("RING") {
+            return self.flush().into_iter().collect();
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            return Vec::new();
+        };
+        let key = key.trim().to_ascii_uppercase();
+        let value = value.trim();
+        let mut emitted = Vec::new();
+        // A new DATE starts the next report.
+        if key == "DATE" && self.pending.as_ref().is_some_and(ModemCall::has_caller) {
+            emitted.extend(self.flush());
+        }
+        let pending = self
+            .pending
+            .get_or_insert_with(|| ModemCall::new(ModemFrameFormat::Formatted));
+        match key.as_str() {
+            "DATE" => pending.date_time = Some(value.to_string()),
+            "TIME" => {
+                let date = pending.date_time.take().unwrap_or_default();
+                pending.date_time = Some(format!("{date}{value}"));
+            }
+            "NMBR" | "DDN_NMBR" => match absence_code(value) {
+                Some(reason) => pending.number_absent = Some(reason),
+                None => pending.number = clean_number(value),
+            },
+            "NAME" => {
+                pending.name = clean_name(value);
+                // NAME closes the report.
+                emitted.extend(self.flush());
+            }
+            _ => {}
+        }
+        emitted
+    }
+}
+
+fn is_text(byte: u8) -> bool {
+    byte.is_ascii_graphic() || byte == b' '
+}
+
+/// Whether the bytes seen so far can still start a frame: an SDMF body
+/// opens with its date digits, an MDMF body with a known parameter type.
+fn plausible_header(buffer: &[u8]) -> bool {
+    let (Some(&len), Some(&next)) = (buffer.get(1), buffer.get(2)) else {
+        return true;
+    };
+    match buffer[0] {
+        SDMF_TYPE => len >= 9 && next.is_ascii_digit(),
+        MDMF_TYPE => {
+            len >= 2
+                && matches!(
+                    next,
+                    PARAM_DATE_TIME
+                        | PARAM_NUMBER
+                        | PARAM_NUMBER_ABSENT
+                        | PARAM_NAME
+                        | PARAM_NAME_ABSENT
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Total length of the binary frame at the start of `buffer`, or `None`
+/// while it is incomplete.
+fn binary_frame_len(buffer: &[u8]) -> Option<usize> {
+    let body_len = usize::from(*buffer.get(1)?);
+    let total = 2 + body_len + 1;
+    (buffer.len() >= total).then_some(total)
+}
+
+/// Decode a complete SDMF/MDMF frame (type, length, body, checksum).
+fn decode_binary_frame(frame: &[u8]) -> Option<ModemCall> {
+    if frame.len() < 3 || frame.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) != 0 {
+        return None;
+    }
+    let body = &frame[2..frame.len() - 1];
+    let call = match frame[0] {
+        SDMF_TYPE => decode_sdmf(body)?,
+        MDMF_TYPE => decode_mdmf(body)?,
+        _ => return None,
+    };
+    call.has_caller().then_some(call)
+}
+
+fn decode_sdmf(body: &[u8]) -> Option<ModemCall> {
+    if body.len() < 8 {
+        return None;
+    }
+    let mut call = ModemCall::new(ModemFrameFormat::Sdmf);
+    call.date_time = ascii_field(&body[..8]);
+    let number = ascii_field(&body[8..])?;
+    match absence_code(&number) {
+        Some(reason) => call.number_absent = Some(reason),
+        None => call.number = clean_number(&number),
+    }
+    Some(call)
+}
+
+fn decode_mdmf(body: &[u8]) -> Option<ModemCall> {
+    let mut call = ModemCall::new(ModemFrameFormat::Mdmf);
+    let mut rest = body;
+    while !rest.is_empty() {
+        let (&kind, tail) = rest.split_first()?;
+        let (&len, tail) = tail.split_first()?;
+        let len = usize::from(len);
+        if tail.len() < len {
+            return None;
+        }
+        let (value, tail) = tail.split_at(len);
+        rest = tail;
+        match kind {
+            PARAM_DATE_TIME => call.date_time = ascii_field(value),
+            PARAM_NUMBER => call.number = ascii_field(value).and_then(|v| clean_number(&v)),
+            PARAM_NUMBER_ABSENT => {
+                call.number_absent = ascii_field(value).and_then(|v| absence_code(&v));
+            }
+            PARAM_NAME => call.name = ascii_field(value).and_then(|v| clean_name(&v)),
+            PARAM_NAME_ABSENT => {}
+            _ => {}
+        }
+    }
+    Some(call)
+}
+
+/// `A5551234C`, `D5551234C` (number) or `B00C` / `B10C` (withheld).
+fn parse_dtmf(line: &str) -> Option<ModemCall> {
+    let bytes = line.as_bytes();
+    if bytes.len() < 3 {
+        return None;
+    }
+    let (start, end) = (bytes[0].to_ascii_uppercase(), bytes[bytes.len() - 1]);
+    if !matches!(end.to_ascii_uppercase(), b'C' | b'#') {
+        return None;
+    }
+    let digits = &line[1..line.len() - 1];
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut call = ModemCall::new(ModemFrameFormat::Dtmf);
+    match start {
+        b'A' | b'D' => call.number = clean_number(digits),
+        b'B' => {
+            call.number_absent = Some(match digits {
+                "10" => AbsenceReason::Private,
+                _ => AbsenceReason::Unavailable,
+            })
+        }
+        _ => return None,
+    }
+    call.has_caller().then_some(call)
+}
+
+/// A raw frame printed as hex digits (`AT+VCID=2`).
+fn parse_hex_frame(line: &str) -> Option<ModemCall> {
+    let hex: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    if hex.len() < 6 || hex.len() % 2 != 0 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let bytes: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<_, _>>()
+        .ok()?;
+    if !matches!(bytes[0], SDMF_TYPE | MDMF_TYPE) || binary_frame_len(&bytes) != Some(bytes.len()) {
+        return None;
+    }
+    decode_binary_frame(&bytes)
+}
+
+fn ascii_field(bytes: &[u8]) -> Option<String> {
+    let text: String = bytes
+        .iter()
+        .filter(|b| b.is_ascii_graphic() || **b == b' ')
+        .map(|b| char::from(*b))
+        .collect();
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// `O` (out of area) and `P` (private) stand in for a number.
+fn absence_code(value: &str) -> Option<AbsenceReason> {
+    match value.trim().to_ascii_uppercase().as_str() {
+        "P" | "PRIVATE" => Some(AbsenceReason::Private),
+        "O" | "OUT OF AREA" | "UNAVAILABLE" => Some(AbsenceReason::Unavailable),
+        _ => None,
+    }
+}
+
+/// Keep a number only if it carries digits and nothing but dialling
+/// characters.
+fn clean_number(value: &str) -> Option<String> {
+    let value = value.trim();
+    let dialable = value
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | ' ' | '(' | ')' | '.'));
+    (dialable && value.chars().any(|c| c.is_ascii_digit())).then(|| value.to_string())
+}
+
+fn clean_name(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty() && absence_code(value).is_none()).then(|| value.to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a frame with its checksum.
+    fn frame(kind: u8, body: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![kind, body.len() as u8];
+        bytes.extend_from_slice(body);
+        let sum = bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+        bytes.push(sum.wrapping_neg());
+        bytes
+    }
+
+    fn mdmf(number: &str, name: &str) -> Vec<u8> {
+        let mut body = vec![PARAM_DATE_TIME, 8];
+        body.extend_from_slice(b"03211405");
+        body.extend_from_slice(&[PARAM_NUMBER, number.len() as u8]);
+        body.extend_from_slice(number.as_bytes());
+        body.extend_from_slice(&[PARAM_NAME, name.len() as u8]);
+        body.extend_from_slice(name.as_bytes());
+        frame(MDMF_TYPE, &body)
+    }
+
+    #[test]
+    fn test_parses_fsk_dtmf_and_formatted_frames() {
+        let mut parser = ModemFrameParser::new();
+
+        let calls = parser.push(&mdmf("2101234567", "JOHN DOE"));
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].format, ModemFrameFormat::Mdmf);
+        assert_eq!(calls[0].number.as_deref(), Some("2101234567"));
+        assert_eq!(calls[0].name.as_deref(), Some("JOHN DOE"));
+        assert_eq!(calls[0].date_time.as_deref(), Some("03211405"));
+
+        let calls = parser.push(&frame(SDMF_TYPE, b"03211405P"));
+        assert_eq!(calls[0].format, ModemFrameFormat::Sdmf);
+        assert_eq!(calls[0].number, None);
+        assert_eq!(calls[0].number_absent, Some(AbsenceReason::Private));
+
+        let calls = parser.push(b"A6912345678C\r\nB10C\r\n");
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].format, ModemFrameFormat::Dtmf);
+        assert_eq!(calls[0].number.as_deref(), Some("6912345678"));
+        assert_eq!(calls[1].number_absent, Some(AbsenceReason::Private));
+
+        // Formatted output split mid-line; NAME closes the report.
+        assert!(parser
+            .push(b"RING\r\n\r\nDATE = 0321\r\nTIME = 14")
+            .is_empty());
+        assert!(parser.push(b"05\r\nNMBR = 210-123-4567\r\n").is_empty());
+        let calls = parser.push(b"NAME = JANE\r\n");
+        assert_eq!(calls[0].format, ModemFrameFormat::Formatted);
+        assert_eq!(calls[0].number.as_deref(), Some("210-123-4567"));
+        assert_eq!(calls[0].date_time.as_deref(), Some("03211405"));
+
+        // Without NAME, the next RING (or an idle flush) closes it.
+        assert!(parser.push(b"NMBR = 5551234\r\n").is_empty());
+        let calls = parser.push(b"RING\r\n");
+        assert_eq!(calls[0].number.as_deref(), Some("5551234"));
+        assert!(parser.push(b"NMBR = 5559876\r\n").is_empty());
+        let calls = parser.flush_idle();
+        assert_eq!(calls[0].number.as_deref(), Some("5559876"));
+
+        let hex: String = mdmf("5551234", "A B")
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect();
+        let calls = parser.push(format!("{hex}\r\n").as_bytes());
+        assert_eq!(calls[0].format, ModemFrameFormat::Mdmf);
+        assert_eq!(calls[0].number.as_deref(), Some("5551234"));
+    }
+
+    #[test]
+    fn test_garbled_and_partial_frames_are_dropped_without_losing_the_next_call() {
+        let mut parser = ModemFrameParser::new();
+
+        // Bad checksum, then line noise, then a good frame.
+        let mut corrupt = mdmf("2101234567", "JOHN");
+        *corrupt.last_mut().unwrap() ^= 0xFF;
+        corrupt.extend_from_slice(&[0x00, 0xFF, 0x13, b'\r']);
+        corrupt.extend_from_slice(&mdmf("2107654321", "ANNA"));
+        let calls = parser.push(&corrupt);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].number.as_deref(), Some("2107654321"));
+
+        // An MDMF whose parameter overruns the body is rejected.
+        let calls = parser.push(&frame(MDMF_TYPE, &[PARAM_NUMBER, 20, b'1', b'2']));
+        assert!(calls.is_empty());
+
+        // A frame delivered one byte at a time still decodes.
+        let frame = mdmf("6900000000", "BYTE");
+        let calls: Vec<ModemCall> = frame.iter().flat_map(|b| parser.push(&[*b])).collect();
+        assert_eq!(calls.len(), 1);
+
+        // Every byte value, truncated frames and endless text never panic
+        // and leave the buffer bounded.
+        let noise: Vec<u8> = (0..=255u8).cycle().take(4096).collect();
+        parser.push(&noise);
+        for cut in 0..frame.len() {
+            parser.push(&frame[..cut]);
+        }
+        parser.push(&[b'x'; 2048]);
+        assert!(parser.buffer.len() <= MAX_BUFFER_LEN);
+        parser.flush_idle();
+        assert!(parser.buffer.is_empty());
+        assert!(parser
+            .push(b"NMBR = not-a-number\r\nNAME = X\r\n")
+            .is_empty());
+
+        // A stray frame header stalls only until the line goes quiet.
+        assert!(parser.push(&[MDMF_TYPE, 0xF0, b'D']).is_empty());
+        assert!(parser.push(b"2101112222C").is_empty());
+        let calls = parser.flush_idle();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].number.as_deref(), Some("2101112222"));
+    }
+}
@@ -97,6 +97,43 @@ impl CallerIdConfig {
     }
 }
 
+/// Serial caller ID modem configuration, stored next to the SIP settings
+/// under `modem_*` keys.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModemConfig {
+    /// Whether the modem listener is enabled
+    #[serde(default)]
+    pub enabled: bool,
+    /// Serial port (e.g. "COM4", "/dev/ttyACM0")
+    #[serde(default)]
+    pub port: String,
+    #[serde(default = "default_modem_baud_rate")]
+    pub baud_rate: u32,
+    /// AT command sent after opening the port to turn caller ID reporting on
+    #[serde(default = "default_modem_init_command")]
+    pub init_command: String,
+}
+
+pub fn default_modem_baud_rate() -> u32 {
+    9600
+}
+
+pub fn default_modem_init_command() -> String {
+    "AT+VCID=1".into()
+}
+
+impl Default for ModemConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: String::new(),
+            baud_rate: default_modem_baud_rate(),
+            init_command: default_modem_init_command(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ResolvedCallerIdConfig {
     pub config: CallerIdConfig,
@@ -129,6 +166,30 @@ pub enum ListenerStatus {
     Registering,
     /// An error occurred (check logs)
     Error,
+    /// The device went away; retrying until it comes back
+    Reconnecting,
+}
+
+/// Status of the serial modem listener.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModemStatus {
+    pub status: ListenerStatus,
+    pub port: Option<String>,
+    pub error: Option<String>,
+    /// Times the port was reopened after the device dropped
+    pub reconnects: u64,
+}
+
+impl Default for ModemStatus {
+    fn default() -> Self {
+        Self {
+            status: ListenerStatus::Stopped,
+            port: None,
+            error: None,
+            reconnects: 0,
+        }
+    }
 }
 
 /// Status response for the frontend.
@@ -140,6 +201,8 @@ pub struct CallerIdStatus {
     pub reason: Option<CallerIdStatusReason>,
     pub registered: bool,
     pub calls_detected: u64,
+    #[serde(default)]
+    pub modem: ModemStatus,
 }
 
 // ---------------------------------------------------------------------------
@@ -208,6 +271,19 @@ mod tests {
         assert_eq!(json, "\"listening\"");
     }
 
+    #[test]
+    fn test_modem_config_defaults_fill_missing_fields() {
+        let cfg: ModemConfig = serde_json::from_str(r#"{"enabled":true,"port":"COM4"}"#).unwrap();
+        assert!(cfg.enabled);
+        assert_eq!(cfg.port, "COM4");
+        assert_eq!(cfg.baud_rate, 9600);
+        assert_eq!(cfg.init_command, "AT+VCID=1");
+        assert_eq!(
+            serde_json::to_string(&ListenerStatus::Reconnecting).unwrap(),
+            "\"reconnecting\""
+        );
+    }
+
     #[test]
     fn test_effective_auth_username_defaults_to_sip_username() {
         let cfg = CallerIdConfig {
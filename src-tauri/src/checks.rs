@@ -0,0 +1,663 @@
+//! Split bills: one order divided into separately payable checks.
+//!
+//! The order itself is untouched — the kitchen keeps seeing one ticket — and
+//! each `order_checks` row is a share of it. A split either assigns every
+//! item line (by its index in the order's items JSON) to exactly one check,
+//! or divides the order evenly into N checks.
+//!
+//! Check subtotals and taxes come from the same per-line totals and tax
+//! breakdown the order uses ([`money::items_total_cents`],
+//! [`tax::compute_breakdown`]). Whatever the order total holds beyond its
+//! items (discounts, delivery fee, tip) is spread over the checks in
+//! proportion to their items, so the check totals always add up to the
+//! order total. Leftover cents from any division go to the first checks, one
+//! each, so the same split always produces the same amounts.
+//!
+//! Payments name their check with `check_id`. A check is paid once its
+//! payments cover its total, and a split order only becomes paid when every
+//! check is. An order can be re-split until the first payment is taken.
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::money::{self, Cents, RoundingRule};
+use crate::order_events;
+use crate::payments;
+use crate::tax::{self, MenuTaxLookup, TaxBreakdownLine, TaxMode};
+
+pub const MAX_CHECKS: usize = 20;
+
+/// How an order is divided.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SplitSpec {
+    /// Item line indexes per check, in check order.
+    Items(Vec<Vec<usize>>),
+    /// N equal checks over the whole order.
+    Even(usize),
+}
+
+impl SplitSpec {
+    fn mode(&self) -> &'static str {
+        match self {
+            SplitSpec::Items(_) => "items",
+            SplitSpec::Even(_) => "even",
+        }
+    }
+}
+
+fn parse_index_list(value: &Value) -> Result<Vec<usize>, String> {
+    let list = value
+        .as_array()
+        .or_else(|| {
+            value
+                .get("items")
+                .or_else(|| value.get("itemIndexes"))
+                .or_else(|| value.get("item_indexes"))
+                .and_then(Value::as_array)
+        })
+        .ok_or("Each check must list its item indexes")?;
+    list.iter()
+        .map(|index| {
+            index
+                .as_u64()
+                .map(|index| index as usize)
+                .ok_or_else(|| format!("Invalid item index: {index}"))
+        })
+        .collect()
+}
+
+/// Parse `{ even: N }` or `{ checks: [[0, 2], [1]] }` (each check may also
+/// be `{ items: [...] }`).
+pub fn parse_spec(payload: &Value) -> Result<SplitSpec, String> {
+    if let Some(even) = payload.get("even").filter(|value| !value.is_null()) {
+        let count = even
+            .as_u64()
+            .ok_or("even must be a whole number of checks")? as usize;
+        if !(2..=MAX_CHECKS).contains(&count) {
+            return Err(format!(
+                "An even split needs between 2 and {MAX_CHECKS} checks"
+            ));
+        }
+        return Ok(SplitSpec::Even(count));
+    }
+    let checks = payload
+        .get("checks")
+        .and_then(Value::as_array)
+        .ok_or("Missing split specification: pass even or checks")?;
+    if !(2..=MAX_CHECKS).contains(&checks.len()) {
+        return Err(format!("A split needs between 2 and {MAX_CHECKS} checks"));
+    }
+    checks
+        .iter()
+        .map(parse_index_list)
+        .collect::<Result<Vec<_>, _>>()
+        .map(SplitSpec::Items)
+}
+
+/// Divide `total` cents over `weights`: each share is proportional to its
+/// weight, rounded toward zero, and the cents that rounding leaves over go
+/// to the first shares one at a time. With no positive weight the total is
+/// split evenly.
+pub fn allocate_cents(total: i64, weights: &[i64]) -> Vec<i64> {
+    if weights.is_empty() {
+        return Vec::new();
+    }
+    let weight_sum: i64 = weights.iter().map(|weight| (*weight).max(0)).sum();
+    let weights: Vec<i64> = if weight_sum > 0 {
+        weights.iter().map(|weight| (*weight).max(0)).collect()
+    } else {
+        vec![1; weights.len()]
+    };
+    let weight_sum: i128 = weights.iter().map(|weight| i128::from(*weight)).sum();
+    let sign = total.signum();
+    let magnitude = i128::from(total.abs());
+    let mut shares: Vec<i64> = weights
+        .iter()
+        .map(|weight| (magnitude * i128::from(*weight) / weight_sum) as i64)
+        .collect();
+    let mut leftover = total.abs() - shares.iter().sum::<i64>();
+    for share in shares.iter_mut() {
+        if leftover == 0 {
+            break;
+        }
+        *share += 1;
+        leftover -= 1;
+    }
+    shares.into_iter().map(|share| share * sign).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderCheck {
+    pub id: String,
+    pub order_id: String,
+    pub check_number: i64,
+    pub check_count: i64,
+    pub split_mode: String,
+    pub item_indexes: Vec<usize>,
+    pub subtotal_cents: i64,
+    pub tax_cents: i64,
+    /// Share of the order's discounts, fees and tip.
+    pub adjustment_cents: i64,
+    pub total_cents: i64,
+    pub tax_breakdown: Vec<TaxBreakdownLine>,
+    pub status: String,
+}
+
+impl OrderCheck {
+    pub fn is_paid(&self) -> bool {
+        self.status == "paid"
+    }
+
+    fn to_json(&self, paid: Cents) -> Value {
+        let total = Cents::new(self.total_cents);
+        let outstanding = if paid >= total {
+            Cents::ZERO
+        } else {
+            total - paid
+        };
+        json!({
+            "id": self.id,
+            "orderId": self.order_id,
+            "checkNumber": self.check_number,
+            "checkCount": self.check_count,
+            "splitMode": self.split_mode,
+            "itemIndexes": self.item_indexes,
+            "subtotal": Cents::new(self.subtotal_cents).to_f64_dp2(),
+            "subtotal_cents": self.subtotal_cents,
+            "taxAmount": Cents::new(self.tax_cents).to_f64_dp2(),
+            "tax_amount_cents": self.tax_cents,
+            "adjustment": Cents::new(self.adjustment_cents).to_f64_dp2(),
+            "adjustment_cents": self.adjustment_cents,
+            "totalAmount": total.to_f64_dp2(),
+            "total_amount_cents": self.total_cents,
+            "taxBreakdown": tax::breakdown_json(&self.tax_breakdown),
+            "paid": paid.to_f64_dp2(),
+            "outstanding": outstanding.to_f64_dp2(),
+            "status": self.status,
+        })
+    }
+}
+
+const CHECK_COLUMNS: &str = "id, order_id, check_number, check_count, split_mode, item_indexes,
+    subtotal_cents, tax_amount_cents, adjustment_cents, total_amount_cents, tax_breakdown, status";
+
+fn check_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<OrderCheck> {
+    let item_indexes: String = row.get(5)?;
+    let tax_breakdown: Option<String> = row.get(10)?;
+    Ok(OrderCheck {
+        id: row.get(0)?,
+        order_id: row.get(1)?,
+        check_number: row.get(2)?,
+        check_count: row.get(3)?,
+        split_mode: row.get(4)?,
+        item_indexes: serde_json::from_str(&item_indexes).unwrap_or_default(),
+        subtotal_cents: row.get(6)?,
+        tax_cents: row.get(7)?,
+        adjustment_cents: row.get(8)?,
+        total_cents: row.get(9)?,
+        tax_breakdown: tax::parse_breakdown(tax_breakdown.as_deref()),
+        status: row.get(11)?,
+    })
+}
+
+/// The order's checks in check order; empty when it was never split.
+pub fn load_for_order(conn: &Connection, order_id: &str) -> Result<Vec<OrderCheck>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {CHECK_COLUMNS} FROM order_checks WHERE order_id = ?1 ORDER BY check_number"
+        ))
+        .map_err(|e| format!("read order checks: {e}"))?;
+    let rows = stmt
+        .query_map(params![order_id], check_from_row)
+        .map_err(|e| format!("read order checks: {e}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read order checks: {e}"))
+}
+
+pub fn load(conn: &Connection, check_id: &str) -> Result<Option<OrderCheck>, String> {
+    conn.query_row(
+        &format!("SELECT {CHECK_COLUMNS} FROM order_checks WHERE id = ?1"),
+        params![check_id],
+        check_from_row,
+    )
+    .optional()
+    .map_err(|e| format!("read order check: {e}"))
+}
+
+/// Total, net paid and outstanding of one check, shaped like the order
+/// snapshot the payment guard uses.
+pub fn balance_snapshot(
+    conn: &Connection,
+    order_id: &str,
+    check_id: &str,
+) -> Result<payments::OrderPaymentBalanceSnapshot, String> {
+    let check = load(conn, check_id)?
+        .filter(|check| check.order_id == order_id)
+        .ok_or_else(|| format!("Check {check_id} does not belong to order {order_id}"))?;
+    let total = Cents::new(check.total_cents);
+    let paid = payments::load_net_paid_for_check(conn, check_id)?;
+    let outstanding = if paid >= total {
+        Cents::ZERO
+    } else {
+        total - paid
+    };
+    Ok(payments::OrderPaymentBalanceSnapshot {
+        order_total: total.to_f64_dp2(),
+        net_paid: paid.to_f64_dp2(),
+        outstanding_amount: outstanding.to_f64_dp2(),
+    })
+}
+
+/// Re-derive each check's `status` from its payments. Returns `None` for an
+/// order that was never split, otherwise whether every check is paid.
+pub fn refresh_statuses(
+    conn: &Connection,
+    order_id: &str,
+    now: &str,
+) -> Result<Option<bool>, String> {
+    let checks = load_for_order(conn, order_id)?;
+    if checks.is_empty() {
+        return Ok(None);
+    }
+    let mut all_paid = true;
+    for check in &checks {
+        let paid = payments::load_net_paid_for_check(conn, &check.id)?;
+        let status = if paid.as_i64() >= check.total_cents {
+            "paid"
+        } else {
+            "open"
+        };
+        all_paid &= status == "paid";
+        if status != check.status {
+            conn.execute(
+                "UPDATE order_checks SET status = ?1, updated_at = ?2 WHERE id = ?3",
+                params![status, now, check.id],
+            )
+            .map_err(|e| format!("update check status: {e}"))?;
+        }
+    }
+    Ok(Some(all_paid))
+}
+
+struct SplitShare {
+    item_indexes: Vec<usize>,
+    subtotal: Cents,
+    tax: Cents,
+    breakdown: Vec<TaxBreakdownLine>,
+}
+
+fn breakdown_tax(lines: &[TaxBreakdownLine]) -> Cents {
+    Cents::new(lines.iter().map(|line| line.tax_cents).sum())
+}
+
+/// Even shares of each breakdown line, so per-check tax lines add back up to
+/// the order's.
+fn split_breakdown_evenly(lines: &[TaxBreakdownLine], count: usize) -> Vec<Vec<TaxBreakdownLine>> {
+    let mut shares = vec![Vec::with_capacity(lines.len()); count];
+    for line in lines {
+        let net = allocate_cents(line.net_cents, &vec![1; count]);
+        let tax = allocate_cents(line.tax_cents, &vec![1; count]);
+        for (index, share) in shares.iter_mut().enumerate() {
+            share.push(TaxBreakdownLine {
+                net_cents: net[index],
+                tax_cents: tax[index],
+                gross_cents: net[index] + tax[index],
+                ..line.clone()
+            });
+        }
+    }
+    shares
+}
+
+fn build_shares(
+    conn: &Connection,
+    items: &[Value],
+    spec: &SplitSpec,
+) -> Result<Vec<SplitShare>, String> {
+    let rule = RoundingRule::from_settings(conn);
+    let config = tax::load_config(conn);
+    let menu = MenuTaxLookup::load(conn);
+    match spec {
+        SplitSpec::Even(count) => {
+            let breakdown = tax::compute_breakdown(&config, &menu, items, rule);
+            let subtotals = allocate_cents(
+                money::items_total_cents(items, rule).as_i64(),
+                &vec![1; *count],
+            );
+            let taxes = allocate_cents(breakdown_tax(&breakdown).as_i64(), &vec![1; *count]);
+            Ok(split_breakdown_evenly(&breakdown, *count)
+                .into_iter()
+                .enumerate()
+                .map(|(index, breakdown)| SplitShare {
+                    item_indexes: (0..items.len()).collect(),
+                    subtotal: Cents::new(subtotals[index]),
+                    tax: Cents::new(taxes[index]),
+                    breakdown,
+                })
+                .collect())
+        }
+        SplitSpec::Items(checks) => {
+            let mut assigned = vec![false; items.len()];
+            for (check_index, indexes) in checks.iter().enumerate() {
+                if indexes.is_empty() {
+                    return Err(format!("Check {} has no items", check_index + 1));
+                }
+                for index in indexes {
+                    let slot = assigned
+                        .get_mut(*index)
+                        .ok_or_else(|| format!("Order has no item at index {index}"))?;
+                    if *slot {
+                        return Err(format!("Item {index} is assigned to more than one check"));
+                    }
+                    *slot = true;
+                }
+            }
+            if let Some(index) = assigned.iter().position(|assigned| !assigned) {
+                return Err(format!("Item {index} is not assigned to a check"));
+            }
+            Ok(checks
+                .iter()
+                .map(|indexes| {
+                    let lines: Vec<Value> =
+                        indexes.iter().map(|index| items[*index].clone()).collect();
+                    let breakdown = tax::compute_breakdown(&config, &menu, &lines, rule);
+                    SplitShare {
+                        item_indexes: indexes.clone(),
+                        subtotal: money::items_total_cents(&lines, rule),
+                        tax: breakdown_tax(&breakdown),
+                        breakdown,
+                    }
+                })
+                .collect())
+        }
+    }
+}
+
+/// Split `order_id` into checks, replacing any earlier split. Rejected once
+/// a payment has been taken on the order.
+pub fn split(
+    conn: &Connection,
+    order_id: &str,
+    spec: &SplitSpec,
+    actor_staff_id: Option<&str>,
+) -> Result<Vec<OrderCheck>, String> {
+    let (items_json, status, total_cents): (String, String, i64) = conn
+        .query_row(
+            "SELECT COALESCE(items, '[]'), COALESCE(status, ''),
+                    COALESCE(total_amount_cents, CAST(ROUND(total_amount * 100) AS INTEGER), 0)
+             FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| format!("load order for split: {e}"))?
+        .ok_or_else(|| format!("Order not found: {order_id}"))?;
+    if matches!(status.as_str(), "cancelled" | "canceled") {
+        return Err(format!("Cannot split cancelled order {order_id}"));
+    }
+
+    let existing = load_for_order(conn, order_id)?;
+    if let Some(paid) = existing.iter().find(|check| check.is_paid()) {
+        return Err(format!(
+            "Cannot re-split order {order_id}: check {} is already paid",
+            paid.check_number
+        ));
+    }
+    if Cents::round_half_even(payments::load_net_paid_for_order(conn, order_id)?).is_positive() {
+        return Err(format!(
+            "Cannot split order {order_id}: it already has payments"
+        ));
+    }
+
+    let items: Vec<Value> = serde_json::from_str::<Value>(&items_json)
+        .ok()
+        .and_then(|value| value.as_array().cloned())
+        .unwrap_or_default();
+    if items.is_empty() {
+        return Err(format!("Order {order_id} has no items to split"));
+    }
+    let shares = build_shares(conn, &items, spec)?;
+
+    // Exclusive tax is charged on top of the items; inclusive tax is already
+    // in them. The rest of the order total is spread by item value.
+    let exclusive = tax::load_config(conn).mode == TaxMode::Exclusive;
+    let bases: Vec<i64> = shares
+        .iter()
+        .map(|share| {
+            let mut base = share.subtotal;
+            if exclusive {
+                base += share.tax;
+            }
+            base.as_i64()
+        })
+        .collect();
+    let adjustments = allocate_cents(total_cents - bases.iter().sum::<i64>(), &bases);
+
+    conn.execute(
+        "DELETE FROM order_checks WHERE order_id = ?1",
+        params![order_id],
+    )
+    .map_err(|e| format!("clear previous split: {e}"))?;
+    let now = Utc::now().to_rfc3339();
+    let count = shares.len() as i64;
+    for (index, share) in shares.iter().enumerate() {
+        let total = Cents::new(bases[index] + adjustments[index]);
+        let adjustment = Cents::new(adjustments[index]);
+        let breakdown = serde_json::to_string(&share.breakdown)
+            .map_err(|e| format!("serialize check tax breakdown: {e}"))?;
+        let item_indexes = serde_json::to_string(&share.item_indexes).map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO order_checks (
+                id, order_id, check_number, check_count, split_mode, item_indexes,
+                subtotal, subtotal_cents, tax_amount, tax_amount_cents,
+                adjustment, adjustment_cents, total_amount, total_amount_cents,
+                tax_breakdown, status, created_by, created_at, updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15,
+                      'open', ?16, ?17, ?17)",
+            params![
+                Uuid::new_v4().to_string(),
+                order_id,
+                index as i64 + 1,
+                count,
+                spec.mode(),
+                item_indexes,
+                share.subtotal.to_f64_dp2(),
+                share.subtotal.as_i64(),
+                share.tax.to_f64_dp2(),
+                share.tax.as_i64(),
+                adjustment.to_f64_dp2(),
+                adjustment.as_i64(),
+                total.to_f64_dp2(),
+                total.as_i64(),
+                breakdown,
+                actor_staff_id,
+                now,
+            ],
+        )
+        .map_err(|e| format!("insert order check: {e}"))?;
+    }
+
+    let checks = load_for_order(conn, order_id)?;
+    order_events::append(
+        conn,
+        order_id,
+        order_events::BILL_SPLIT,
+        actor_staff_id,
+        json!({
+            "mode": spec.mode(),
+            "checks": checks.iter().map(|check| json!({
+                "checkNumber": check.check_number,
+                "total": Cents::new(check.total_cents).to_f64_dp2(),
+            })).collect::<Vec<_>>(),
+            "resplit": !existing.is_empty(),
+        }),
+    );
+    Ok(checks)
+}
+
+/// The order's checks with what has been paid on each.
+pub fn checks_json(conn: &Connection, order_id: &str) -> Result<Value, String> {
+    let checks = load_for_order(conn, order_id)?;
+    let mut entries = Vec::with_capacity(checks.len());
+    for check in &checks {
+        let paid = payments::load_net_paid_for_check(conn, &check.id)?;
+        entries.push(check.to_json(paid));
+    }
+    Ok(json!({
+        "success": true,
+        "orderId": order_id,
+        "split": !checks.is_empty(),
+        "checks": entries,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn insert_order(conn: &Connection, order_id: &str, items: Value, total: f64) {
+        conn.execute(
+            "INSERT INTO orders (id, items, total_amount, total_amount_cents, status, order_type,
+                                 created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, 'pending', 'dine-in', datetime('now'), datetime('now'))",
+            params![
+                order_id,
+                items.to_string(),
+                total,
+                Cents::round_half_even(total).as_i64()
+            ],
+        )
+        .unwrap();
+    }
+
+    fn insert_payment(conn: &Connection, order_id: &str, check_id: &str, amount_cents: i64) {
+        conn.execute(
+            "INSERT INTO order_payments (id, order_id, method, amount, amount_cents, status,
+                                         check_id, created_at, updated_at)
+             VALUES (?1, ?2, 'cash', ?3, ?4, 'completed', ?5, datetime('now'), datetime('now'))",
+            params![
+                Uuid::new_v4().to_string(),
+                order_id,
+                Cents::new(amount_cents).to_f64_dp2(),
+                amount_cents,
+                check_id
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn allocate_gives_leftover_cents_to_first_checks() {
+        assert_eq!(allocate_cents(1000, &[1, 1, 1]), vec![334, 333, 333]);
+        assert_eq!(allocate_cents(1001, &[1, 1, 1]), vec![334, 334, 333]);
+        assert_eq!(allocate_cents(-100, &[1, 1, 1]), vec![-34, -33, -33]);
+        assert_eq!(allocate_cents(100, &[300, 100]), vec![75, 25]);
+        assert_eq!(allocate_cents(7, &[0, 0]), vec![4, 3]);
+        assert_eq!(
+            parse_spec(&json!({ "even": 3 })).unwrap(),
+            SplitSpec::Even(3)
+        );
+        assert_eq!(
+            parse_spec(&json!({ "checks": [[0, 2], { "items": [1] }] })).unwrap(),
+            SplitSpec::Items(vec![vec![0, 2], vec![1]])
+        );
+        assert!(parse_spec(&json!({ "even": 1 })).is_err());
+    }
+
+    #[test]
+    fn item_split_sums_to_order_total_and_even_split_is_deterministic() {
+        let conn = test_conn();
+        // 2.00 delivery-style surcharge on top of 18.00 of items.
+        insert_order(
+            &conn,
+            "ord-split",
+            json!([
+                { "name": "Burger", "quantity": 1, "price": 12.0 },
+                { "name": "Fries", "quantity": 2, "price": 1.5 },
+                { "name": "Soda", "quantity": 1, "price": 3.0 }
+            ]),
+            20.0,
+        );
+        let checks = split(
+            &conn,
+            "ord-split",
+            &SplitSpec::Items(vec![vec![0], vec![1, 2]]),
+            Some("staff-1"),
+        )
+        .unwrap();
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[0].subtotal_cents, 1200);
+        assert_eq!(checks[1].subtotal_cents, 600);
+        assert_eq!(checks[0].total_cents + checks[1].total_cents, 2000);
+        assert_eq!(checks[0].total_cents, 1334);
+
+        let err = split(
+            &conn,
+            "ord-split",
+            &SplitSpec::Items(vec![vec![0, 1], vec![1, 2]]),
+            None,
+        )
+        .unwrap_err();
+        assert!(err.contains("more than one check"), "{err}");
+        assert!(split(
+            &conn,
+            "ord-split",
+            &SplitSpec::Items(vec![vec![0], vec![1]]),
+            None
+        )
+        .is_err());
+
+        let even = split(&conn, "ord-split", &SplitSpec::Even(3), None).unwrap();
+        let totals: Vec<i64> = even.iter().map(|check| check.total_cents).collect();
+        assert_eq!(totals, vec![667, 667, 666]);
+        assert!(even.iter().all(|check| check.item_indexes == vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn order_is_paid_only_when_every_check_is_and_paid_checks_block_resplit() {
+        let conn = test_conn();
+        insert_order(
+            &conn,
+            "ord-pay",
+            json!([{ "name": "Pizza", "quantity": 1, "price": 10.0 }]),
+            10.0,
+        );
+        let checks = split(&conn, "ord-pay", &SplitSpec::Even(2), None).unwrap();
+        insert_payment(&conn, "ord-pay", &checks[0].id, 500);
+        payments::recompute_order_payment_state(&conn, "ord-pay", "now", "p1").unwrap();
+        let status: String = conn
+            .query_row(
+                "SELECT payment_status FROM orders WHERE id = 'ord-pay'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(status, "partially_paid");
+        assert!(load(&conn, &checks[0].id).unwrap().unwrap().is_paid());
+
+        let err = split(&conn, "ord-pay", &SplitSpec::Even(3), None).unwrap_err();
+        assert!(err.contains("check 1 is already paid"), "{err}");
+
+        let snapshot = balance_snapshot(&conn, "ord-pay", &checks[1].id).unwrap();
+        assert_eq!(snapshot.outstanding_amount, 5.0);
+
+        insert_payment(&conn, "ord-pay", &checks[1].id, 500);
+        payments::recompute_order_payment_state(&conn, "ord-pay", "now", "p2").unwrap();
+        let status: String = conn
+            .query_row(
+                "SELECT payment_status FROM orders WHERE id = 'ord-pay'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(status, "paid");
+    }
+}
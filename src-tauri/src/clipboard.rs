@@ -0,0 +1,149 @@
+//! System clipboard access for text and PNG images on Windows, macOS and
+//! Linux.
+//!
+//! One `arboard::Clipboard` is kept for the life of the process: on X11 the
+//! owning process serves the selection, so a handle dropped right after a
+//! write can take the copied content with it. Failures are split into
+//! [`ClipboardError::Unsupported`] (no clipboard here — headless Linux, a
+//! Wayland compositor without data-control, an unknown platform), where the
+//! caller falls back to the text stored in `local_settings`, and
+//! [`ClipboardError::Unavailable`] (another app holds the clipboard, a
+//! conversion failed), which is worth retrying.
+
+use std::borrow::Cow;
+use std::sync::Mutex;
+
+use base64::Engine;
+
+use crate::error::PosError;
+
+/// Longest side accepted by [`write_png_base64`].
+const MAX_IMAGE_SIDE: u32 = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ClipboardError {
+    #[error("Clipboard is not supported on this system: {0}")]
+    Unsupported(String),
+    #[error("Clipboard is temporarily unavailable: {0}")]
+    Unavailable(String),
+}
+
+impl From<arboard::Error> for ClipboardError {
+    fn from(error: arboard::Error) -> Self {
+        match error {
+            arboard::Error::ClipboardNotSupported => Self::Unsupported(error.to_string()),
+            other => Self::Unavailable(other.to_string()),
+        }
+    }
+}
+
+impl From<ClipboardError> for PosError {
+    fn from(error: ClipboardError) -> Self {
+        match error {
+            ClipboardError::Unsupported(_) => PosError::Unsupported(error.to_string()),
+            ClipboardError::Unavailable(_) => PosError::Unavailable(error.to_string()),
+        }
+    }
+}
+
+static CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+/// Run `f` on the shared clipboard, opening it on first use. A handle that
+/// could not be opened is retried on the next call.
+fn with_clipboard<T>(
+    f: impl FnOnce(&mut arboard::Clipboard) -> Result<T, arboard::Error>,
+) -> Result<T, ClipboardError> {
+    let mut slot = CLIPBOARD
+        .lock()
+        .map_err(|e| ClipboardError::Unavailable(e.to_string()))?;
+    if slot.is_none() {
+        // On a display-less Linux box opening fails with a generic error,
+        // which is as unsupported as it gets.
+        let clipboard = arboard::Clipboard::new().map_err(|error| match error {
+            arboard::Error::ClipboardOccupied => ClipboardError::Unavailable(error.to_string()),
+            other => ClipboardError::Unsupported(other.to_string()),
+        })?;
+        *slot = Some(clipboard);
+    }
+    let clipboard = slot.as_mut().expect("clipboard initialised above");
+    f(clipboard).map_err(ClipboardError::from)
+}
+
+/// Current clipboard text; empty when the clipboard holds something else.
+pub fn read_text() -> Result<String, ClipboardError> {
+    with_clipboard(|clipboard| match clipboard.get_text() {
+        Err(arboard::Error::ContentNotAvailable) => Ok(String::new()),
+        result => result,
+    })
+}
+
+pub fn write_text(text: &str) -> Result<(), ClipboardError> {
+    with_clipboard(|clipboard| clipboard.set_text(text))
+}
+
+/// Decode a base64 PNG (optionally a `data:image/png;base64,` URL) into RGBA
+/// pixels. `Err` carries a message suitable for a validation error.
+fn decode_png_base64(encoded: &str) -> Result<image::RgbaImage, String> {
+    let body = match encoded.trim().split_once(";base64,") {
+        Some((prefix, body)) if prefix.starts_with("data:") => body,
+        _ => encoded.trim(),
+    };
+    let compact: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(compact)
+        .map_err(|e| format!("Invalid base64 image: {e}"))?;
+    let image = image::load_from_memory_with_format(&bytes, image::ImageFormat::Png)
+        .map_err(|e| format!("Invalid PNG image: {e}"))?;
+    if image.width() > MAX_IMAGE_SIDE || image.height() > MAX_IMAGE_SIDE {
+        return Err(format!(
+            "Image is larger than {MAX_IMAGE_SIDE}x{MAX_IMAGE_SIDE}"
+        ));
+    }
+    Ok(image.to_rgba8())
+}
+
+/// Put a base64 PNG on the clipboard as an image.
+pub fn write_png_base64(encoded: &str) -> Result<(u32, u32), PosError> {
+    let rgba =
+        decode_png_base64(encoded).map_err(|reason| PosError::validation("image", reason))?;
+    let (width, height) = rgba.dimensions();
+    let image = arboard::ImageData {
+        width: width as usize,
+        height: height as usize,
+        bytes: Cow::Owned(rgba.into_raw()),
+    };
+    with_clipboard(|clipboard| clipboard.set_image(image))?;
+    Ok((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png_base64(width: u32, height: u32) -> String {
+        let image = image::RgbaImage::from_pixel(width, height, image::Rgba([0, 0, 0, 255]));
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image
+            .write_to(&mut bytes, image::ImageFormat::Png)
+            .expect("encode png");
+        base64::engine::general_purpose::STANDARD.encode(bytes.into_inner())
+    }
+
+    #[test]
+    fn decodes_plain_and_data_url_png() {
+        let encoded = png_base64(3, 2);
+        assert_eq!(decode_png_base64(&encoded).unwrap().dimensions(), (3, 2));
+        let data_url = format!("data:image/png;base64,{encoded}");
+        assert_eq!(decode_png_base64(&data_url).unwrap().dimensions(), (3, 2));
+        assert!(decode_png_base64("not an image").is_err());
+        assert!(decode_png_base64(&png_base64(MAX_IMAGE_SIDE + 1, 1)).is_err());
+    }
+
+    #[test]
+    fn errors_map_to_distinct_codes() {
+        let unsupported = ClipboardError::from(arboard::Error::ClipboardNotSupported);
+        assert_eq!(PosError::from(unsupported).code(), "UNSUPPORTED");
+        let busy = ClipboardError::from(arboard::Error::ClipboardOccupied);
+        assert_eq!(PosError::from(busy).code(), "UNAVAILABLE");
+    }
+}
@@ -0,0 +1,623 @@
+//! Combo line validation for order create / item edits.
+//!
+//! An order line with `combo_id` claims to be a combo from the cached menu.
+//! [`validate_line`] expands that combo definition, checks the line's
+//! `combo_items` against its slots (`specific` items and `category_choice`
+//! picks), refuses components that are unavailable — the cache already
+//! carries local availability overrides — and recomputes the combo price
+//! for the order type plus paid upgrades, less any pricing-rule discount
+//! the line was locked with. A price mismatch is fixed up in place and
+//! marked `normalized: true`; anything structural is returned as a list of
+//! violations so the caller can reject the order.
+//!
+//! BOGO combos are a discount rule rather than a fixed bundle, so only their
+//! availability is checked.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Local};
+use rusqlite::{params, Connection};
+use serde_json::Value;
+
+use crate::money::{self, Cents, RoundingRule};
+use crate::{value_f64, value_str};
+
+/// Menu sections a combo line is checked against.
+pub struct ComboMenu {
+    combos: HashMap<String, Value>,
+    subcategories: HashMap<String, Value>,
+    categories: HashMap<String, Value>,
+}
+
+/// A cached menu section keyed by entry id.
+pub(crate) fn read_section(conn: &Connection, key: &str) -> HashMap<String, Value> {
+    let raw: Option<String> = conn
+        .query_row(
+            "SELECT data FROM menu_cache WHERE cache_key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .ok();
+    raw.and_then(|data| serde_json::from_str::<Value>(&data).ok())
+        .and_then(|value| value.as_array().cloned())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|entry| {
+            value_str(&entry, &["id"])
+                .map(|id| (id.trim().to_string(), entry))
+                .filter(|(id, _)| !id.is_empty())
+        })
+        .collect()
+}
+
+impl ComboMenu {
+    pub fn load(conn: &Connection) -> Self {
+        Self {
+            combos: read_section(conn, "combos"),
+            subcategories: read_section(conn, "subcategories"),
+            categories: read_section(conn, "categories"),
+        }
+    }
+}
+
+pub fn is_combo_line(item: &Value) -> bool {
+    combo_id(item).is_some()
+}
+
+pub(crate) fn combo_id(item: &Value) -> Option<String> {
+    value_str(item, &["combo_id", "comboId"])
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn display_name(entry: &Value) -> String {
+    value_str(entry, &["name", "name_en", "name_el"]).unwrap_or_else(|| "Item".to_string())
+}
+
+fn flag_off(entry: &Value, key: &str) -> bool {
+    matches!(entry.get(key), Some(Value::Bool(false)))
+        || matches!(entry.get(key).and_then(Value::as_i64), Some(0))
+}
+
+fn violation(code: &str, message: String) -> Value {
+    serde_json::json!({ "code": code, "message": message })
+}
+
+fn with_fields(mut violation: Value, fields: Value) -> Value {
+    if let (Some(target), Value::Object(extra)) = (violation.as_object_mut(), fields) {
+        target.extend(extra);
+    }
+    violation
+}
+
+/// Combo price for the order type: specific price, then pickup, then base.
+fn combo_price_for_order_type(combo: &Value, order_type: &str) -> f64 {
+    let base = value_f64(combo, &["base_price"]).unwrap_or(0.0);
+    let pickup = value_f64(combo, &["pickup_price"]).unwrap_or(base);
+    match order_type.trim().to_ascii_lowercase().as_str() {
+        "delivery" => value_f64(combo, &["delivery_price"]).unwrap_or(pickup),
+        "dine-in" | "dine_in" | "dinein" => value_f64(combo, &["dine_in_price"]).unwrap_or(pickup),
+        _ => pickup,
+    }
+}
+
+fn time_restriction_violation(combo: &Value, now: DateTime<Local>) -> Option<String> {
+    if !combo
+        .get("has_time_restriction")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+    {
+        return None;
+    }
+    let now_utc = now.with_timezone(&chrono::Utc);
+    let parse = |key: &str| {
+        value_str(combo, &[key])
+            .and_then(|raw| DateTime::parse_from_rfc3339(raw.trim()).ok())
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+    };
+    if parse("valid_from").is_some_and(|from| from > now_utc) {
+        return Some("not valid yet".to_string());
+    }
+    if parse("valid_until").is_some_and(|until| until < now_utc) {
+        return Some("expired".to_string());
+    }
+    if let Some(days) = combo.get("available_days").and_then(Value::as_array) {
+        let today = i64::from(now.weekday().num_days_from_sunday());
+        if !days.is_empty() && !days.iter().any(|day| day.as_i64() == Some(today)) {
+            return Some("not available today".to_string());
+        }
+    }
+    let current = now.format("%H:%M").to_string();
+    if value_str(combo, &["start_time"])
+        .is_some_and(|start| current.as_str() < start.get(..5).unwrap_or(&start))
+    {
+        return Some("not available yet at this time".to_string());
+    }
+    if value_str(combo, &["end_time"])
+        .is_some_and(|end| current.as_str() > end.get(..5).unwrap_or(&end))
+    {
+        return Some("no longer available at this time".to_string());
+    }
+    None
+}
+
+/// Paid upgrades chosen inside the combo (`add` / `extra` customizations on
+/// its components), per combo unit.
+fn upgrades_total(selections: &[Value]) -> f64 {
+    selections
+        .iter()
+        .map(|selection| {
+            let quantity = value_f64(selection, &["quantity"]).unwrap_or(1.0).max(0.0);
+            let per_unit: f64 = selection
+                .get("customizations")
+                .and_then(Value::as_array)
+                .map(|customizations| {
+                    customizations
+                        .iter()
+                        .filter(|c| value_str(c, &["action"]).as_deref() != Some("remove"))
+                        .filter_map(|c| value_f64(c, &["price"]))
+                        .filter(|price| *price > 0.0)
+                        .sum()
+                })
+                .unwrap_or(0.0);
+            per_unit * quantity
+        })
+        .sum()
+}
+
+fn line_selections(line: &Value) -> Vec<Value> {
+    line.get("combo_items")
+        .or_else(|| line.get("comboItems"))
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn combo_unit_price(
+    combo: &Value,
+    line: &Value,
+    selections: &[Value],
+    order_type: &str,
+    rule: RoundingRule,
+) -> Cents {
+    let unit = Cents::round_with(
+        combo_price_for_order_type(combo, order_type) + upgrades_total(selections),
+        rule,
+    );
+    crate::pricing_rules::locked_unit_price(line, unit, rule)
+}
+
+/// Unit price a combo line gets from `combo` as cached now, paid upgrades
+/// and a locked pricing-rule adjustment included. `None` for BOGO combos,
+/// which are priced by their components.
+pub fn menu_unit_price(
+    combo: &Value,
+    line: &Value,
+    order_type: &str,
+    rule: RoundingRule,
+) -> Option<Cents> {
+    if value_str(combo, &["combo_type"]).as_deref() == Some("bogo") {
+        return None;
+    }
+    Some(combo_unit_price(
+        combo,
+        line,
+        &line_selections(line),
+        order_type,
+        rule,
+    ))
+}
+
+/// Validate one combo line. `Ok(true)` means the line was fixed up in place.
+pub fn validate_line(
+    menu: &ComboMenu,
+    line: &mut Value,
+    order_type: &str,
+    now: DateTime<Local>,
+    rule: RoundingRule,
+) -> Result<bool, Vec<Value>> {
+    let Some(combo_id) = combo_id(line) else {
+        return Ok(false);
+    };
+    // Same policy as the menu-item check: an unsynced menu cannot validate.
+    if menu.combos.is_empty() {
+        return Ok(false);
+    }
+    let Some(combo) = menu.combos.get(&combo_id) else {
+        return Err(vec![with_fields(
+            violation(
+                "unknown_combo",
+                format!("Combo {combo_id} is not on the menu"),
+            ),
+            serde_json::json!({ "comboId": combo_id }),
+        )]);
+    };
+    let combo_name = display_name(combo);
+    let mut violations = Vec::new();
+
+    if flag_off(combo, "is_active") {
+        violations.push(violation(
+            "combo_inactive",
+            format!("Combo '{combo_name}' is not active"),
+        ));
+    }
+    if let Some(reason) = time_restriction_violation(combo, now) {
+        violations.push(violation(
+            "combo_unavailable",
+            format!("Combo '{combo_name}' is {reason}"),
+        ));
+    }
+
+    let selections = line_selections(line);
+
+    // Every chosen component must exist and be sellable right now.
+    let mut remaining: Vec<(String, f64, Option<String>)> = Vec::new();
+    for selection in &selections {
+        let Some(component_id) = value_str(selection, &["subcategory_id", "subcategoryId"])
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+        else {
+            violations.push(violation(
+                "unknown_component",
+                format!("Combo '{combo_name}' has a component without an item id"),
+            ));
+            continue;
+        };
+        let quantity = value_f64(selection, &["quantity"]).unwrap_or(1.0).max(0.0);
+        match menu.subcategories.get(&component_id) {
+            None => violations.push(with_fields(
+                violation(
+                    "unknown_component",
+                    format!(
+                        "'{}' in combo '{combo_name}' is not on the menu",
+                        display_name(selection)
+                    ),
+                ),
+                serde_json::json!({ "componentId": component_id }),
+            )),
+            Some(component) => {
+                let category_id = value_str(component, &["category_id", "categoryId"]);
+                let category_off = category_id
+                    .as_deref()
+                    .and_then(|id| menu.categories.get(id))
+                    .is_some_and(|category| flag_off(category, "is_active"));
+                if flag_off(component, "is_available")
+                    || flag_off(component, "is_active")
+                    || category_off
+                {
+                    let name = display_name(component);
+                    violations.push(with_fields(
+                        violation(
+                            "component_unavailable",
+                            format!("'{name}' in combo '{combo_name}' is unavailable"),
+                        ),
+                        serde_json::json!({ "componentId": component_id, "componentName": name }),
+                    ));
+                }
+                remaining.push((component_id, quantity, category_id));
+            }
+        }
+    }
+
+    let is_bogo = value_str(combo, &["combo_type"]).as_deref() == Some("bogo");
+    if !is_bogo {
+        let mut slots: Vec<Value> = combo
+            .get("items")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        // Fill fixed slots before category choices so a specific item is
+        // never consumed by a looser "any from category" slot.
+        slots.sort_by_key(|slot| {
+            value_str(slot, &["selection_type"]).as_deref() == Some("category_choice")
+        });
+
+        for slot in &slots {
+            let required = value_f64(slot, &["quantity"]).unwrap_or(1.0).max(0.0);
+            let choice = value_str(slot, &["selection_type"]).as_deref() == Some("category_choice");
+            let target = if choice {
+                value_str(slot, &["category_id"])
+            } else {
+                value_str(slot, &["subcategory_id"])
+            };
+            let Some(target) = target else {
+                continue;
+            };
+            let mut provided = 0.0;
+            for (component_id, quantity, category_id) in remaining.iter_mut() {
+                let matches = if choice {
+                    category_id.as_deref() == Some(target.as_str())
+                } else {
+                    *component_id == target
+                };
+                if !matches || *quantity <= 0.0 {
+                    continue;
+                }
+                let take = quantity.min(required - provided);
+                *quantity -= take;
+                provided += take;
+                if provided >= required {
+                    break;
+                }
+            }
+            if provided < required {
+                let (code, label) = if choice {
+                    let name = slot
+                        .get("category")
+                        .map(display_name)
+                        .or_else(|| menu.categories.get(&target).map(display_name))
+                        .unwrap_or_else(|| target.clone());
+                    ("missing_choice", format!("a choice from '{name}'"))
+                } else {
+                    let name = slot
+                        .get("subcategory")
+                        .map(display_name)
+                        .or_else(|| menu.subcategories.get(&target).map(display_name))
+                        .unwrap_or_else(|| target.clone());
+                    ("missing_component", format!("'{name}'"))
+                };
+                violations.push(with_fields(
+                    violation(
+                        code,
+                        format!("Combo '{combo_name}' needs {required} x {label}, got {provided}"),
+                    ),
+                    serde_json::json!({ "target": target, "required": required, "provided": provided }),
+                ));
+            }
+        }
+        for (component_id, quantity, _) in &remaining {
+            if *quantity > 0.0 {
+                let name = menu
+                    .subcategories
+                    .get(component_id)
+                    .map(display_name)
+                    .unwrap_or_else(|| component_id.clone());
+                violations.push(with_fields(
+                    violation(
+                        "unexpected_component",
+                        format!("'{name}' is not part of combo '{combo_name}'"),
+                    ),
+                    serde_json::json!({ "componentId": component_id, "quantity": quantity }),
+                ));
+            }
+        }
+        if flag_off(combo, "allow_customization") && upgrades_total(&selections) > 0.0 {
+            violations.push(violation(
+                "customization_not_allowed",
+                format!("Combo '{combo_name}' does not allow paid upgrades"),
+            ));
+        }
+    }
+
+    if !violations.is_empty() {
+        return Err(violations);
+    }
+    if is_bogo {
+        return Ok(false);
+    }
+
+    let line_quantity = value_f64(line, &["quantity"]).unwrap_or(1.0).max(0.0);
+    let unit = combo_unit_price(combo, line, &selections, order_type, rule);
+    let expected_total = Cents::round_with(unit.to_f64_dp2() * line_quantity, rule);
+    if money::item_line_cents(line, rule) == expected_total {
+        return Ok(false);
+    }
+
+    let claimed = money::item_line_cents(line, rule).to_f64_dp2();
+    if let Some(object) = line.as_object_mut() {
+        for key in ["price", "unitPrice", "unit_price", "basePrice"] {
+            object.insert(key.to_string(), Value::from(unit.to_f64_dp2()));
+        }
+        for key in ["totalPrice", "total_price"] {
+            object.insert(key.to_string(), Value::from(expected_total.to_f64_dp2()));
+        }
+        object.insert("normalized".to_string(), Value::Bool(true));
+        object.insert("normalizedFromTotal".to_string(), Value::from(claimed));
+    }
+    Ok(true)
+}
+
+/// Result of checking every combo line of an order.
+#[derive(Debug, Default)]
+pub struct ItemsValidation {
+    pub violations: Vec<Value>,
+    pub normalized_lines: usize,
+    /// Change in the items total caused by price fix-ups.
+    pub total_delta: Cents,
+}
+
+pub fn validate_items(
+    conn: &Connection,
+    items: &mut [Value],
+    order_type: &str,
+    now: DateTime<Local>,
+) -> ItemsValidation {
+    let mut outcome = ItemsValidation::default();
+    if !items.iter().any(is_combo_line) {
+        return outcome;
+    }
+    let menu = ComboMenu::load(conn);
+    let rule = RoundingRule::from_settings(conn);
+    for (index, line) in items.iter_mut().enumerate() {
+        let before = money::item_line_cents(line, rule);
+        match validate_line(&menu, line, order_type, now, rule) {
+            Ok(true) => {
+                outcome.normalized_lines += 1;
+                outcome.total_delta += money::item_line_cents(line, rule) - before;
+            }
+            Ok(false) => {}
+            Err(line_violations) => {
+                let combo_id = combo_id(line);
+                outcome
+                    .violations
+                    .extend(line_violations.into_iter().map(|v| {
+                        with_fields(
+                            v,
+                            serde_json::json!({ "lineIndex": index, "comboId": combo_id }),
+                        )
+                    }));
+            }
+        }
+    }
+    outcome
+}
+
+/// Structured rejection returned by order commands when a combo line fails.
+pub fn rejection_response(violations: &[Value]) -> Value {
+    let first = violations
+        .first()
+        .and_then(|v| value_str(v, &["message"]))
+        .unwrap_or_else(|| "Invalid combo".to_string());
+    let error = if violations.len() > 1 {
+        format!("{first} (+{} more)", violations.len() - 1)
+    } else {
+        first
+    };
+    serde_json::json!({
+        "success": false,
+        "errorCode": "invalid_combo",
+        "error": error,
+        "violations": violations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn menu() -> ComboMenu {
+        let mut combos = HashMap::new();
+        combos.insert(
+            "combo-1".to_string(),
+            serde_json::json!({
+                "id": "combo-1",
+                "name_en": "Burger Menu",
+                "combo_type": "choice",
+                "base_price": 9.5,
+                "delivery_price": 10.5,
+                "is_active": true,
+                "allow_customization": true,
+                "has_time_restriction": false,
+                "items": [
+                    { "selection_type": "specific", "subcategory_id": "burger", "quantity": 1 },
+                    { "selection_type": "category_choice", "category_id": "drinks", "quantity": 1 }
+                ]
+            }),
+        );
+        let mut subcategories = HashMap::new();
+        for (id, name, category, available) in [
+            ("burger", "Burger", "mains", true),
+            ("cola", "Cola", "drinks", true),
+            ("juice", "Juice", "drinks", false),
+        ] {
+            subcategories.insert(
+                id.to_string(),
+                serde_json::json!({ "id": id, "name": name, "category_id": category, "is_available": available }),
+            );
+        }
+        ComboMenu {
+            combos,
+            subcategories,
+            categories: HashMap::new(),
+        }
+    }
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 5, 4, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn valid_combo_with_wrong_price_is_normalized_including_upgrades() {
+        let mut line = serde_json::json!({
+            "combo_id": "combo-1",
+            "quantity": 2,
+            "unitPrice": 9.5,
+            "totalPrice": 19.0,
+            "combo_items": [
+                { "subcategory_id": "burger", "quantity": 1,
+                  "customizations": [{ "ingredient_id": "bacon", "price": 1.0, "action": "extra" }] },
+                { "subcategory_id": "cola", "quantity": 1 }
+            ]
+        });
+        let fixed = validate_line(
+            &menu(),
+            &mut line,
+            "delivery",
+            now(),
+            RoundingRule::default(),
+        )
+        .expect("combo should be valid");
+        assert!(fixed);
+        assert_eq!(line["normalized"], true);
+        assert_eq!(line["unitPrice"], 11.5, "delivery price + paid upgrade");
+        assert_eq!(line["totalPrice"], 23.0);
+        assert_eq!(line["normalizedFromTotal"], 19.0);
+    }
+
+    #[test]
+    fn combo_price_is_rounded_with_the_given_rule() {
+        for (rule, unit_price) in [
+            (RoundingRule::HalfEven, 10.62),
+            (RoundingRule::HalfUp, 10.63),
+        ] {
+            let mut line = serde_json::json!({
+                "combo_id": "combo-1",
+                "quantity": 1,
+                "totalPrice": 10.5,
+                "combo_items": [
+                    { "subcategory_id": "burger", "quantity": 1,
+                      "customizations": [{ "ingredient_id": "sauce", "price": 0.125, "action": "extra" }] },
+                    { "subcategory_id": "cola", "quantity": 1 }
+                ]
+            });
+            validate_line(&menu(), &mut line, "delivery", now(), rule)
+                .expect("combo should be valid");
+            assert_eq!(line["unitPrice"], unit_price);
+        }
+    }
+
+    #[test]
+    fn invalid_combo_lists_missing_choice_and_unavailable_component() {
+        let mut missing = serde_json::json!({
+            "combo_id": "combo-1",
+            "quantity": 1,
+            "totalPrice": 9.5,
+            "combo_items": [{ "subcategory_id": "burger", "quantity": 1 }]
+        });
+        let violations = validate_line(
+            &menu(),
+            &mut missing,
+            "pickup",
+            now(),
+            RoundingRule::default(),
+        )
+        .unwrap_err();
+        assert_eq!(violations[0]["code"], "missing_choice");
+
+        let mut unavailable = serde_json::json!({
+            "combo_id": "combo-1",
+            "quantity": 1,
+            "totalPrice": 9.5,
+            "combo_items": [
+                { "subcategory_id": "burger", "quantity": 1 },
+                { "subcategory_id": "juice", "quantity": 1 }
+            ]
+        });
+        let violations = validate_line(
+            &menu(),
+            &mut unavailable,
+            "pickup",
+            now(),
+            RoundingRule::default(),
+        )
+        .unwrap_err();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0]["code"], "component_unavailable");
+        assert_eq!(violations[0]["componentName"], "Juice");
+        assert!(rejection_response(&violations)["error"]
+            .as_str()
+            .unwrap()
+            .contains("Juice"));
+    }
+}
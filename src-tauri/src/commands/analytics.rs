@@ -6,7 +6,11 @@ use std::cmp::Ordering;
 use tauri::Emitter;
 use tracing::{info, warn};
 
-use crate::{db, order_ownership, payment_integrity, payments, print, value_str, zreport};
+use crate::reports_export::{Column, ExportFormat, ExportName, ExportOptions, ReportTable};
+use crate::{
+    business_day, db, idempotency, order_ownership, payment_integrity, payments, print,
+    reports_export, schedule, value_str, zreport,
+};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -81,6 +85,40 @@ struct ReportDailyStaffPerformancePayload {
     date: Option<String>,
 }
 
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ReportHourlyHeatmapPayload {
+    #[serde(default, alias = "branch_id")]
+    branch_id: Option<String>,
+    #[serde(
+        default,
+        alias = "date_from",
+        alias = "startDate",
+        alias = "start_date"
+    )]
+    date_from: Option<String>,
+    #[serde(default, alias = "date_to", alias = "endDate", alias = "end_date")]
+    date_to: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ReportStaffPerformancePayload {
+    #[serde(default, alias = "branch_id")]
+    branch_id: Option<String>,
+    #[serde(
+        default,
+        alias = "date_from",
+        alias = "startDate",
+        alias = "start_date"
+    )]
+    date_from: Option<String>,
+    #[serde(default, alias = "date_to", alias = "endDate", alias = "end_date")]
+    date_to: Option<String>,
+    #[serde(default)]
+    format: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct ResolvePaymentBlockerPayload {
@@ -213,11 +251,67 @@ fn parse_report_daily_staff_performance_payload(
     serde_json::from_value(payload).unwrap_or_default()
 }
 
-fn resolve_report_date(optional_date: Option<String>) -> String {
+fn parse_report_staff_performance_payload(
+    arg0: Option<serde_json::Value>,
+) -> ReportStaffPerformancePayload {
+    let payload = normalize_payload_with_branch(arg0);
+    serde_json::from_value(payload).unwrap_or_default()
+}
+
+fn parse_report_hourly_heatmap_payload(
+    arg0: Option<serde_json::Value>,
+) -> ReportHourlyHeatmapPayload {
+    let payload = normalize_payload_with_branch(arg0);
+    serde_json::from_value(payload).unwrap_or_default()
+}
+
+/// Requested report date, defaulting to the current business day so a
+/// report opened after midnight but before the day-start still shows the
+/// shift in progress.
+fn resolve_report_date(conn: &rusqlite::Connection, optional_date: Option<String>) -> String {
     optional_date
         .map(|v| v.trim().to_string())
         .filter(|v| !v.is_empty())
-        .unwrap_or_else(|| Local::now().format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| business_day::current_business_day_report_date_at(conn, Local::now()))
+}
+
+/// Current business day as a date, for reports that count back from it.
+fn business_today(conn: &rusqlite::Connection) -> chrono::NaiveDate {
+    business_day::business_date_in(
+        &Local,
+        Utc::now(),
+        business_day::resolve_business_day_start_minutes(conn),
+    )
+}
+
+/// UTC bounds of business day `date` in `tz`.
+fn report_day_bounds<Tz: chrono::TimeZone>(
+    conn: &rusqlite::Connection,
+    date: &str,
+    tz: &Tz,
+) -> Result<(String, String), String> {
+    business_day::business_day_bounds_in(
+        tz,
+        date,
+        date,
+        business_day::resolve_business_day_start_minutes(conn),
+    )
+}
+
+/// Hour of day of a stored UTC timestamp in `tz`; unparseable values fall
+/// back to the hour digits of the raw string.
+fn local_hour<Tz: chrono::TimeZone>(created_at: &str, tz: &Tz) -> usize {
+    use chrono::Timelike;
+    chrono::DateTime::parse_from_rfc3339(created_at)
+        .map(|dt| dt.with_timezone(tz).hour() as usize)
+        .ok()
+        .or_else(|| {
+            created_at
+                .get(11..13)
+                .and_then(|raw| raw.parse::<usize>().ok())
+        })
+        .filter(|h| *h < 24)
+        .unwrap_or(0)
 }
 
 fn is_cancelled_status(status: &str) -> bool {
@@ -620,7 +714,7 @@ fn load_daily_top_items(
                     SUM(total_quantity),
                     SUM(total_revenue)
              FROM top_sellers_daily
-             WHERE (?1 = '' OR branch_id = ?1)
+             WHERE COALESCE(branch_id, '') = ?1
                AND sale_date >= ?2
                AND sale_date <= ?3
              GROUP BY menu_item_id",
@@ -697,31 +791,935 @@ fn load_report_rows_for_day(
     branch_id: &str,
     date: &str,
 ) -> Result<Vec<(String, String, Option<String>, Option<String>, f64, String)>, String> {
+    let (start_at, end_at) = report_day_bounds(conn, date, &Local)?;
+    // W4b: cents-with-real-fallback shim (removed in 4e).
+    let sql = format!(
+        "SELECT status, created_at, payment_method, order_type,
+                COALESCE(total_amount_cents, CAST(ROUND(total_amount * 100) AS INTEGER), 0),
+                items
+         FROM orders
+         WHERE COALESCE(branch_id, '') = ?1
+           AND COALESCE(is_ghost, 0) = 0
+           AND {}",
+        business_day::timestamp_in_range_sql("created_at", "?2", "?3")
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![branch_id, start_at, end_at], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                crate::money::Cents::new(row.get::<_, i64>(4)?).to_f64_dp2(),
+                row.get::<_, String>(5)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+// =====================================================================
+// Local daily sales summary
+// =====================================================================
+//
+// The dashboard widgets used to call `sync_fetch_analytics`, which needs the
+// admin API and goes blank offline. `reports_get_daily_summary` computes the
+// same figures from the local `orders` / `order_payments` /
+// `payment_adjustments` tables. Closed past days are cached in
+// `daily_summaries` keyed by a fingerprint of the day's rows, so repeated
+// dashboard loads reuse the stored JSON until an order for that date changes.
+
+const DAILY_SUMMARY_TOP_ITEMS_LIMIT: usize = 10;
+
+/// Cheap change detector for one business date: row counts plus the latest
+/// `updated_at` across the day's orders, their payments and adjustments. Any
+/// insert, edit, status change, payment or refund moves at least one part.
+fn daily_summary_fingerprint(
+    conn: &rusqlite::Connection,
+    branch_id: &str,
+    (start_at, end_at): (&str, &str),
+) -> Result<(i64, String), String> {
+    let day_filter = business_day::timestamp_in_range_sql("created_at", "?2", "?3");
+    conn.query_row(
+        &format!(
+            "WITH day_orders AS (
+             SELECT id, updated_at
+             FROM orders
+             WHERE COALESCE(branch_id, '') = ?1
+               AND COALESCE(is_ghost, 0) = 0
+               AND {day_filter}
+         )
+         SELECT
+             (SELECT COUNT(*) FROM day_orders),
+             (SELECT COUNT(*) FROM day_orders) || ':' ||
+             COALESCE((SELECT MAX(updated_at) FROM day_orders), '') || '|' ||
+             (SELECT COUNT(*) FROM order_payments
+              WHERE order_id IN (SELECT id FROM day_orders)) || ':' ||
+             COALESCE((SELECT MAX(updated_at) FROM order_payments
+                       WHERE order_id IN (SELECT id FROM day_orders)), '') || '|' ||
+             (SELECT COUNT(*) FROM payment_adjustments
+              WHERE order_id IN (SELECT id FROM day_orders)) || ':' ||
+             COALESCE((SELECT MAX(updated_at) FROM payment_adjustments
+                       WHERE order_id IN (SELECT id FROM day_orders)), '')"
+        ),
+        params![branch_id, start_at, end_at],
+        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+    )
+    .map_err(|e| format!("daily summary fingerprint: {e}"))
+}
+
+fn compute_daily_summary<Tz: chrono::TimeZone>(
+    conn: &rusqlite::Connection,
+    branch_id: &str,
+    date: &str,
+    (start_at, end_at): (&str, &str),
+    tz: &Tz,
+) -> Result<Value, String> {
+    let day_filter = business_day::timestamp_in_range_sql("created_at", "?2", "?3");
+    let order_day_filter = business_day::timestamp_in_range_sql("o.created_at", "?2", "?3");
     let mut stmt = conn
-        .prepare(
+        .prepare(&format!(
             // W4b: cents-with-real-fallback shim (removed in 4e).
-            "SELECT status, created_at, payment_method, order_type,
+            "SELECT status, created_at, order_type,
                     COALESCE(total_amount_cents, CAST(ROUND(total_amount * 100) AS INTEGER), 0),
-                    items
+                    COALESCE(tax_amount_cents, CAST(ROUND(tax_amount * 100) AS INTEGER), 0),
+                    COALESCE(discount_amount_cents, CAST(ROUND(discount_amount * 100) AS INTEGER), 0),
+                    items, COALESCE(is_return, 0)
              FROM orders
-             WHERE (?1 = '' OR branch_id = ?1)
+             WHERE COALESCE(branch_id, '') = ?1
                AND COALESCE(is_ghost, 0) = 0
-               AND substr(created_at, 1, 10) = ?2",
-        )
-        .map_err(|e| e.to_string())?;
+               AND {day_filter}",
+        ))
+        .map_err(|e| format!("daily summary prepare orders: {e}"))?;
     let rows = stmt
-        .query_map(params![branch_id, date], |row| {
+        .query_map(params![branch_id, start_at, end_at], |row| {
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, String>(1)?,
                 row.get::<_, Option<String>>(2)?,
-                row.get::<_, Option<String>>(3)?,
-                crate::money::Cents::new(row.get::<_, i64>(4)?).to_f64_dp2(),
-                row.get::<_, String>(5)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, i64>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, i64>(7)? != 0,
+            ))
+        })
+        .map_err(|e| format!("daily summary query orders: {e}"))?
+        .filter_map(|r| r.ok())
+        .collect::<Vec<_>>();
+
+    let rounding = crate::money::RoundingRule::from_settings(conn);
+    let mut order_count = 0i64;
+    let mut sales_cents = 0i64;
+    let mut tax_cents = 0i64;
+    let mut discount_cents = 0i64;
+    let mut cancelled_count = 0i64;
+    let mut cancelled_cents = 0i64;
+    let mut returns = (0i64, 0i64);
+    let mut hourly_orders = [0i64; 24];
+    let mut hourly_cents = [0i64; 24];
+    let mut by_order_type: std::collections::BTreeMap<String, (i64, i64)> =
+        std::collections::BTreeMap::new();
+    let mut item_rows: Vec<(String, String)> = Vec::new();
+
+    for (
+        status,
+        created_at,
+        order_type,
+        total_cents,
+        order_tax_cents,
+        order_discount_cents,
+        items,
+        is_return,
+    ) in rows
+    {
+        let revenue_cents = if total_cents != 0 {
+            total_cents
+        } else {
+            crate::money::Cents::round_half_even(crate::parse_item_totals(&items, rounding).0)
+                .as_i64()
+        };
+        if is_cancelled_status(&status) {
+            cancelled_count += 1;
+            cancelled_cents += revenue_cents;
+            continue;
+        }
+        // A return's negative total nets the day's sales; it is not another
+        // ticket and its lines are not items sold.
+        if is_return {
+            returns.0 += 1;
+            returns.1 -= revenue_cents;
+            sales_cents += revenue_cents;
+            tax_cents += order_tax_cents;
+            continue;
+        }
+
+        order_count += 1;
+        sales_cents += revenue_cents;
+        tax_cents += order_tax_cents;
+        discount_cents += order_discount_cents;
+
+        let hour = local_hour(&created_at, tz);
+        hourly_orders[hour] += 1;
+        hourly_cents[hour] += revenue_cents;
+
+        let order_type = order_type
+            .map(|v| v.trim().to_ascii_lowercase())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "unknown".to_string());
+        let entry = by_order_type.entry(order_type).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += revenue_cents;
+
+        item_rows.push((status, items));
+    }
+
+    let mut method_stmt = conn
+        .prepare(&format!(
+            // W4b-iii: cents-with-real-fallback shim (removed in 4e).
+            "SELECT LOWER(TRIM(op.method)),
+                    COUNT(*),
+                    COALESCE(SUM(COALESCE(op.amount_cents, CAST(ROUND(op.amount * 100) AS INTEGER))), 0)
+             FROM order_payments op
+             JOIN orders o ON o.id = op.order_id
+             WHERE COALESCE(o.branch_id, '') = ?1
+               AND COALESCE(o.is_ghost, 0) = 0
+               AND {order_day_filter}
+               AND o.status NOT IN ('cancelled', 'canceled')
+               AND op.status = 'completed'
+             GROUP BY LOWER(TRIM(op.method))
+             ORDER BY LOWER(TRIM(op.method))",
+        ))
+        .map_err(|e| format!("daily summary prepare payments: {e}"))?;
+    let by_payment_method: Vec<Value> = method_stmt
+        .query_map(params![branch_id, start_at, end_at], |row| {
+            Ok(serde_json::json!({
+                "method": row.get::<_, String>(0)?,
+                "count": row.get::<_, i64>(1)?,
+                "total": crate::money::Cents::new(row.get::<_, i64>(2)?).to_f64_dp2(),
+            }))
+        })
+        .map_err(|e| format!("daily summary query payments: {e}"))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    // Same scope as the Z report: adjustments on cancelled orders belong to
+    // the cancellation, not to the day's recognised revenue.
+    let mut adj_stmt = conn
+        .prepare(&format!(
+            // W4b-iii: cents-with-real-fallback shim (removed in 4e).
+            "SELECT pa.adjustment_type,
+                    COUNT(*),
+                    COALESCE(SUM(COALESCE(pa.amount_cents, CAST(ROUND(pa.amount * 100) AS INTEGER))), 0)
+             FROM payment_adjustments pa
+             JOIN orders o ON o.id = pa.order_id
+             WHERE COALESCE(o.branch_id, '') = ?1
+               AND COALESCE(o.is_ghost, 0) = 0
+               AND {order_day_filter}
+               AND o.status NOT IN ('cancelled', 'canceled')
+               AND pa.return_order_id IS NULL
+             GROUP BY pa.adjustment_type",
+        ))
+        .map_err(|e| format!("daily summary prepare adjustments: {e}"))?;
+    let mut refunds = (0i64, 0i64);
+    let mut voids = (0i64, 0i64);
+    let adj_rows = adj_stmt
+        .query_map(params![branch_id, start_at, end_at], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })
+        .map_err(|e| format!("daily summary query adjustments: {e}"))?;
+    for (adj_type, count, cents) in adj_rows.flatten() {
+        match adj_type.as_str() {
+            "refund" => refunds = (count, cents),
+            "void" => voids = (count, cents),
+            _ => {}
+        }
+    }
+
+    // Wave 6 H16 convention: `total_amount` is post-discount, so gross adds
+    // the discount back and net takes it, refunds and voids off again.
+    let gross_cents = sales_cents + discount_cents;
+    let net_cents = gross_cents - discount_cents - refunds.1 - voids.1;
+    let average_ticket_cents = if order_count > 0 {
+        crate::money::Cents::round_half_even(sales_cents as f64 / order_count as f64 / 100.0)
+            .as_i64()
+    } else {
+        0
+    };
+    let to_major = |cents: i64| crate::money::Cents::new(cents).to_f64_dp2();
+
+    let top_items = aggregate_top_items_from_order_rows(item_rows);
+    let mut top_by_revenue = top_items.clone();
+    top_by_revenue.sort_by(|left, right| {
+        right
+            .revenue
+            .partial_cmp(&left.revenue)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| left.name.cmp(&right.name))
+    });
+
+    Ok(serde_json::json!({
+        "date": date,
+        "branchId": branch_id,
+        "grossSales": to_major(gross_cents),
+        "netSales": to_major(net_cents),
+        "tax": to_major(tax_cents),
+        "discounts": to_major(discount_cents),
+        "orderCount": order_count,
+        "averageTicket": to_major(average_ticket_cents),
+        "byOrderType": by_order_type
+            .into_iter()
+            .map(|(order_type, (count, cents))| serde_json::json!({
+                "orderType": order_type,
+                "count": count,
+                "total": to_major(cents),
+            }))
+            .collect::<Vec<_>>(),
+        "byPaymentMethod": by_payment_method,
+        "hourly": (0..24)
+            .map(|hour| serde_json::json!({
+                "hour": hour,
+                "orders": hourly_orders[hour],
+                "revenue": to_major(hourly_cents[hour]),
+            }))
+            .collect::<Vec<_>>(),
+        "topItemsByQuantity": top_items_to_json(top_items, DAILY_SUMMARY_TOP_ITEMS_LIMIT),
+        "topItemsByRevenue": top_items_to_json(top_by_revenue, DAILY_SUMMARY_TOP_ITEMS_LIMIT),
+        "refunds": { "count": refunds.0, "total": to_major(refunds.1) },
+        "returns": { "count": returns.0, "total": to_major(returns.1) },
+        "voids": { "count": voids.0, "total": to_major(voids.1) },
+        "cancelled": { "count": cancelled_count, "total": to_major(cancelled_cents) },
+    }))
+}
+
+/// Returns the summary for `date`, served from `daily_summaries` when the
+/// day is closed (strictly before `today`) and its fingerprint still
+/// matches. Today's figures are always recomputed and never cached.
+///
+/// A closed day whose order rows were purged by the Z-report rollover keeps
+/// its cached summary: an empty day after a cached non-empty one means the
+/// rows were archived, not that the sales disappeared.
+///
+/// `date` is a business day in `tz`: orders from after midnight up to the
+/// configured day-start count towards the previous date.
+fn load_or_compute_daily_summary<Tz: chrono::TimeZone>(
+    conn: &rusqlite::Connection,
+    branch_id: &str,
+    date: &str,
+    today: &str,
+    tz: &Tz,
+) -> Result<(Value, bool), String> {
+    let cacheable = date < today;
+    let (start_at, end_at) = report_day_bounds(conn, date, tz)?;
+    let bounds = (start_at.as_str(), end_at.as_str());
+    let (live_orders, fingerprint) = daily_summary_fingerprint(conn, branch_id, bounds)?;
+
+    if cacheable {
+        let cached = conn
+            .query_row(
+                "SELECT fingerprint, summary FROM daily_summaries
+                 WHERE branch_id = ?1 AND report_date = ?2",
+                params![branch_id, date],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )
+            .ok();
+        if let Some((cached_fingerprint, summary)) = cached {
+            if cached_fingerprint == fingerprint || live_orders == 0 {
+                if let Ok(value) = serde_json::from_str::<Value>(&summary) {
+                    return Ok((value, true));
+                }
+            }
+        }
+    }
+
+    let summary = compute_daily_summary(conn, branch_id, date, bounds, tz)?;
+    if cacheable {
+        conn.execute(
+            "INSERT INTO daily_summaries (branch_id, report_date, fingerprint, summary, computed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(branch_id, report_date) DO UPDATE SET
+                fingerprint = excluded.fingerprint,
+                summary = excluded.summary,
+                computed_at = excluded.computed_at",
+            params![
+                branch_id,
+                date,
+                fingerprint,
+                summary.to_string(),
+                Utc::now().to_rfc3339()
+            ],
+        )
+        .map_err(|e| format!("daily summary cache write: {e}"))?;
+    }
+    Ok((summary, false))
+}
+
+// =====================================================================
+// Hourly sales heatmap
+// =====================================================================
+//
+// Owners staff according to busy hours, so `reports_get_hourly_heatmap`
+// buckets orders into a weekday x hour grid in the terminal's timezone and
+// compares it with the same weekdays one week earlier.
+//
+// Bucketing converts each stored UTC instant to local time rather than
+// stepping through local wall-clock hours, so every order lands in exactly
+// one cell: the skipped spring-forward hour simply stays empty and the
+// repeated fall-back hour collects both passes once each.
+
+const HEATMAP_MAX_RANGE_DAYS: i64 = 366;
+
+struct HourlyHeatmap {
+    orders: [[i64; 24]; 7],
+    revenue_cents: [[i64; 24]; 7],
+}
+
+fn heatmap_order_instant(raw: &str) -> Option<chrono::DateTime<Utc>> {
+    let trimmed = raw.trim();
+    if let Ok(parsed) = chrono::DateTime::parse_from_rfc3339(trimmed) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    // SQLite `datetime('now')` writes naive UTC.
+    chrono::NaiveDateTime::parse_from_str(trimmed, "%Y-%m-%d %H:%M:%S%.f")
+        .ok()
+        .map(|naive| chrono::TimeZone::from_utc_datetime(&Utc, &naive))
+}
+
+/// Streams the range's orders through a prepared statement and folds them
+/// into the grid. The SQL window is widened by a day on each side because
+/// `created_at` is UTC; the exact local-date filter happens per row.
+fn accumulate_hourly_heatmap<Tz: chrono::TimeZone>(
+    conn: &rusqlite::Connection,
+    branch_id: &str,
+    date_from: chrono::NaiveDate,
+    date_to: chrono::NaiveDate,
+    tz: &Tz,
+) -> Result<HourlyHeatmap, String> {
+    use chrono::{Datelike, Timelike};
+
+    let window_from = (date_from - chrono::Duration::days(1))
+        .format("%Y-%m-%d")
+        .to_string();
+    let window_to = (date_to + chrono::Duration::days(1))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let mut stmt = conn
+        .prepare(
+            // W4b: cents-with-real-fallback shim (removed in 4e).
+            "SELECT created_at,
+                    COALESCE(total_amount_cents, CAST(ROUND(total_amount * 100) AS INTEGER), 0)
+             FROM orders
+             WHERE COALESCE(branch_id, '') = ?1
+               AND COALESCE(is_ghost, 0) = 0
+               AND LOWER(COALESCE(status, '')) NOT IN ('cancelled', 'canceled', 'declined')
+               AND substr(created_at, 1, 10) >= ?2
+               AND substr(created_at, 1, 10) <= ?3",
+        )
+        .map_err(|e| format!("hourly heatmap prepare: {e}"))?;
+    let mut rows = stmt
+        .query(params![branch_id, window_from, window_to])
+        .map_err(|e| format!("hourly heatmap query: {e}"))?;
+
+    let mut heatmap = HourlyHeatmap {
+        orders: [[0; 24]; 7],
+        revenue_cents: [[0; 24]; 7],
+    };
+    while let Some(row) = rows
+        .next()
+        .map_err(|e| format!("hourly heatmap row: {e}"))?
+    {
+        let created_at: String = row
+            .get(0)
+            .map_err(|e| format!("hourly heatmap created_at: {e}"))?;
+        let total_cents: i64 = row
+            .get(1)
+            .map_err(|e| format!("hourly heatmap total: {e}"))?;
+        let Some(instant) = heatmap_order_instant(&created_at) else {
+            continue;
+        };
+        let local = instant.with_timezone(tz);
+        let local_date = local.date_naive();
+        if local_date < date_from || local_date > date_to {
+            continue;
+        }
+        let weekday = local.weekday().num_days_from_monday() as usize;
+        let hour = local.hour() as usize;
+        heatmap.orders[weekday][hour] += 1;
+        heatmap.revenue_cents[weekday][hour] += total_cents;
+    }
+    Ok(heatmap)
+}
+
+fn heatmap_pct_delta(current: f64, previous: f64) -> Value {
+    if previous == 0.0 {
+        return Value::Null;
+    }
+    serde_json::json!(((current - previous) / previous * 1000.0).round() / 10.0)
+}
+
+fn hourly_heatmap_to_json(
+    heatmap: &HourlyHeatmap,
+    date_from: chrono::NaiveDate,
+    date_to: chrono::NaiveDate,
+) -> Value {
+    let to_major = |cents: i64| crate::money::Cents::new(cents).to_f64_dp2();
+    let total_orders: i64 = heatmap.orders.iter().flatten().sum();
+    let total_revenue_cents: i64 = heatmap.revenue_cents.iter().flatten().sum();
+    serde_json::json!({
+        "dateFrom": date_from.format("%Y-%m-%d").to_string(),
+        "dateTo": date_to.format("%Y-%m-%d").to_string(),
+        "orders": heatmap.orders,
+        "revenue": heatmap
+            .revenue_cents
+            .iter()
+            .map(|day| day.iter().map(|cents| to_major(*cents)).collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+        "totalOrders": total_orders,
+        "totalRevenue": to_major(total_revenue_cents),
+    })
+}
+
+/// Builds the current-period grid plus the prior period shifted back by
+/// whole weeks, so every cell compares like weekdays.
+fn build_hourly_heatmap_report<Tz: chrono::TimeZone>(
+    conn: &rusqlite::Connection,
+    branch_id: &str,
+    date_from: chrono::NaiveDate,
+    date_to: chrono::NaiveDate,
+    tz: &Tz,
+) -> Result<Value, String> {
+    let span_days = (date_to - date_from).num_days() + 1;
+    let shift = chrono::Duration::weeks((span_days + 6) / 7);
+    let previous_from = date_from - shift;
+    let previous_to = date_to - shift;
+
+    let current = accumulate_hourly_heatmap(conn, branch_id, date_from, date_to, tz)?;
+    let previous = accumulate_hourly_heatmap(conn, branch_id, previous_from, previous_to, tz)?;
+
+    let cell_deltas = |current: &[[i64; 24]; 7], previous: &[[i64; 24]; 7]| {
+        (0..7)
+            .map(|day| {
+                (0..24)
+                    .map(|hour| {
+                        heatmap_pct_delta(current[day][hour] as f64, previous[day][hour] as f64)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+    let current_json = hourly_heatmap_to_json(&current, date_from, date_to);
+    let previous_json = hourly_heatmap_to_json(&previous, previous_from, previous_to);
+    let orders_delta = heatmap_pct_delta(
+        current_json["totalOrders"].as_f64().unwrap_or(0.0),
+        previous_json["totalOrders"].as_f64().unwrap_or(0.0),
+    );
+    let revenue_delta = heatmap_pct_delta(
+        current_json["totalRevenue"].as_f64().unwrap_or(0.0),
+        previous_json["totalRevenue"].as_f64().unwrap_or(0.0),
+    );
+
+    Ok(serde_json::json!({
+        "branchId": branch_id,
+        "weekdays": ["mon", "tue", "wed", "thu", "fri", "sat", "sun"],
+        "current": current_json,
+        "previous": previous_json,
+        "comparison": {
+            "ordersDeltaPct": orders_delta,
+            "revenueDeltaPct": revenue_delta,
+            "orders": cell_deltas(&current.orders, &previous.orders),
+            "revenue": cell_deltas(&current.revenue_cents, &previous.revenue_cents),
+        },
+    }))
+}
+
+fn resolve_heatmap_range(
+    payload: &ReportHourlyHeatmapPayload,
+    today: chrono::NaiveDate,
+) -> Result<(chrono::NaiveDate, chrono::NaiveDate), String> {
+    let parse = |raw: &Option<String>, label: &str| -> Result<Option<chrono::NaiveDate>, String> {
+        match raw.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            Some(value) => chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(Some)
+                .map_err(|_| format!("Invalid {label}: {value}")),
+            None => Ok(None),
+        }
+    };
+    let date_to = parse(&payload.date_to, "dateTo")?.unwrap_or(today);
+    let date_from =
+        parse(&payload.date_from, "dateFrom")?.unwrap_or(date_to - chrono::Duration::days(6));
+    if date_from > date_to {
+        return Err("dateFrom must not be after dateTo".into());
+    }
+    if (date_to - date_from).num_days() >= HEATMAP_MAX_RANGE_DAYS {
+        return Err(format!(
+            "Heatmap range is limited to {HEATMAP_MAX_RANGE_DAYS} days"
+        ));
+    }
+    Ok((date_from, date_to))
+}
+
+/// Per-staff totals for the staff performance report. `staff_id` is `None`
+/// for the unattributed bucket (orders, adjustments or tips with no staff).
+#[derive(Debug, Default, Clone, PartialEq)]
+struct StaffPerformance {
+    staff_id: Option<String>,
+    order_count: i64,
+    /// Post-discount order totals (`orders.total_amount`).
+    net_sales_cents: i64,
+    discount_cents: i64,
+    cancelled_orders: i64,
+    void_count: i64,
+    void_cents: i64,
+    refund_count: i64,
+    refund_cents: i64,
+    tips_cents: i64,
+    /// `(adjustment_type, reason_code)` -> `(count, cents)`; adjustments
+    /// without a code are keyed `uncoded`.
+    adjustments_by_reason: std::collections::BTreeMap<(String, String), (i64, i64)>,
+}
+
+fn cents_sql(column: &str) -> String {
+    format!("COALESCE({column}_cents, CAST(ROUND(COALESCE({column}, 0) * 100) AS INTEGER), 0)")
+}
+
+fn staff_performance_entry(
+    by_staff: &mut std::collections::BTreeMap<Option<String>, StaffPerformance>,
+    staff_id: Option<String>,
+) -> &mut StaffPerformance {
+    by_staff
+        .entry(staff_id.clone())
+        .or_insert_with(|| StaffPerformance {
+            staff_id,
+            ..Default::default()
+        })
+}
+
+/// Aggregates orders, cancellations, payment voids/refunds and tips per
+/// staff member for the business days `date_from..=date_to`. Sales follow
+/// the Z-report rules: cancelled orders and open table tabs are excluded.
+fn accumulate_staff_performance(
+    conn: &rusqlite::Connection,
+    branch_id: &str,
+    date_from: &str,
+    date_to: &str,
+) -> Result<Vec<StaffPerformance>, String> {
+    let (start_at, end_at) = business_day::business_day_bounds(conn, date_from, date_to)?;
+    let mut by_staff: std::collections::BTreeMap<Option<String>, StaffPerformance> =
+        std::collections::BTreeMap::new();
+
+    let order_range = business_day::timestamp_in_range_sql("o.created_at", "?2", "?3");
+    let open_tab = business_day::open_unsettled_table_tab_expr("o");
+    let total = cents_sql("o.total_amount");
+    let discount = cents_sql("o.discount_amount");
+    let sales_sql = format!(
+        "SELECT NULLIF(TRIM(o.staff_id), ''), COUNT(*), COALESCE(SUM({total}), 0),
+                COALESCE(SUM({discount}), 0)
+         FROM orders o
+         WHERE COALESCE(o.branch_id, '') = ?1
+           AND COALESCE(o.is_ghost, 0) = 0
+           AND {order_range}
+           AND o.status NOT IN ('cancelled', 'canceled')
+           AND NOT {open_tab}
+         GROUP BY 1"
+    );
+    let mut stmt = conn.prepare(&sales_sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![branch_id, start_at, end_at], |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
             ))
         })
         .map_err(|e| e.to_string())?;
-    Ok(rows.filter_map(|r| r.ok()).collect())
+    for row in rows {
+        let (staff_id, count, net, discount) = row.map_err(|e| e.to_string())?;
+        let e = staff_performance_entry(&mut by_staff, staff_id);
+        e.order_count += count;
+        e.net_sales_cents += net;
+        e.discount_cents += discount;
+    }
+
+    let cancelled_sql = format!(
+        "SELECT NULLIF(TRIM(o.cancelled_by), ''), COUNT(*)
+         FROM orders o
+         WHERE COALESCE(o.branch_id, '') = ?1
+           AND COALESCE(o.is_ghost, 0) = 0
+           AND {order_range}
+           AND o.status IN ('cancelled', 'canceled')
+         GROUP BY 1"
+    );
+    let mut stmt = conn.prepare(&cancelled_sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![branch_id, start_at, end_at], |row| {
+            Ok((row.get::<_, Option<String>>(0)?, row.get::<_, i64>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        let (staff_id, count) = row.map_err(|e| e.to_string())?;
+        staff_performance_entry(&mut by_staff, staff_id).cancelled_orders += count;
+    }
+
+    let adjustment_range = business_day::timestamp_in_range_sql("pa.created_at", "?2", "?3");
+    let amount = cents_sql("pa.amount");
+    let adjustments_sql = format!(
+        "SELECT NULLIF(TRIM(pa.staff_id), ''), pa.adjustment_type,
+                COALESCE(pa.reason_code, 'uncoded'), COUNT(*), COALESCE(SUM({amount}), 0)
+         FROM payment_adjustments pa
+         JOIN orders o ON o.id = pa.order_id
+         WHERE COALESCE(o.branch_id, '') = ?1
+           AND COALESCE(o.is_ghost, 0) = 0
+           AND {adjustment_range}
+         GROUP BY 1, 2, 3"
+    );
+    let mut stmt = conn.prepare(&adjustments_sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![branch_id, start_at, end_at], |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, i64>(4)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        let (staff_id, kind, reason_code, count, cents) = row.map_err(|e| e.to_string())?;
+        let e = staff_performance_entry(&mut by_staff, staff_id);
+        let by_reason = e
+            .adjustments_by_reason
+            .entry((kind.clone(), reason_code))
+            .or_default();
+        by_reason.0 += count;
+        by_reason.1 += cents;
+        if kind == "void" {
+            e.void_count += count;
+            e.void_cents += cents;
+        } else {
+            e.refund_count += count;
+            e.refund_cents += cents;
+        }
+    }
+
+    let tips_sql = format!(
+        "SELECT COALESCE(NULLIF(TRIM(op.tip_recipient_staff_id), ''),
+                         NULLIF(TRIM(op.staff_id), ''),
+                         NULLIF(TRIM(o.staff_id), '')),
+                COALESCE(SUM(op.tip_amount_cents), 0)
+         FROM order_payments op
+         JOIN orders o ON o.id = op.order_id
+         WHERE COALESCE(o.branch_id, '') = ?1
+           AND COALESCE(o.is_ghost, 0) = 0
+           AND {order_range}
+           AND op.status = 'completed'
+           AND COALESCE(op.tip_amount_cents, 0) > 0
+         GROUP BY 1"
+    );
+    let mut stmt = conn.prepare(&tips_sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![branch_id, start_at, end_at], |row| {
+            Ok((row.get::<_, Option<String>>(0)?, row.get::<_, i64>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        let (staff_id, cents) = row.map_err(|e| e.to_string())?;
+        staff_performance_entry(&mut by_staff, staff_id).tips_cents += cents;
+    }
+
+    let mut staff: Vec<StaffPerformance> = by_staff.into_values().collect();
+    // Highest sellers first; the unattributed bucket always goes last.
+    staff.sort_by(|a, b| {
+        a.staff_id
+            .is_none()
+            .cmp(&b.staff_id.is_none())
+            .then(b.net_sales_cents.cmp(&a.net_sales_cents))
+            .then(a.staff_id.cmp(&b.staff_id))
+    });
+    Ok(staff)
+}
+
+/// Display names from the cached staff directory, then from local shift
+/// rows; ids with neither keep the raw id as their name.
+fn staff_display_names(
+    conn: &rusqlite::Connection,
+    branch_id: &str,
+) -> std::collections::HashMap<String, String> {
+    let mut names = crate::auth::cached_staff_display_names(conn, branch_id);
+    let shift_names: Vec<(String, String)> = conn
+        .prepare(
+            "SELECT staff_id, staff_name FROM staff_shifts
+             WHERE TRIM(COALESCE(staff_name, '')) <> ''
+             ORDER BY check_in_time ASC",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut from_shifts = std::collections::HashMap::new();
+    for (staff_id, staff_name) in shift_names {
+        from_shifts.insert(staff_id, staff_name);
+    }
+    for (staff_id, staff_name) in from_shifts {
+        names.entry(staff_id).or_insert(staff_name);
+    }
+    names
+}
+
+const STAFF_PERFORMANCE_CSV_HEADER: &str = "staff_id,staff_name,orders,gross_sales,net_sales,\
+average_ticket,discount_total,discount_percent,cancelled_orders,void_count,void_total,\
+refund_count,refund_total,tips";
+
+const STAFF_PERFORMANCE_COLUMNS: &[Column] = &[
+    Column::text("staffId", "staff_id"),
+    Column::text("staffName", "staff_name"),
+    Column::integer("orders", "orders"),
+    Column::money("grossSales", "gross_sales"),
+    Column::money("netSales", "net_sales"),
+    Column::money("averageTicket", "average_ticket"),
+    Column::money("discountTotal", "discount_total"),
+    Column::decimal("discountPercent", "discount_percent"),
+    Column::integer("cancelledOrders", "cancelled_orders"),
+    Column::integer("voidCount", "void_count"),
+    Column::money("voidTotal", "void_total"),
+    Column::integer("refundCount", "refund_count"),
+    Column::money("refundTotal", "refund_total"),
+    Column::money("tips", "tips"),
+];
+
+fn staff_performance_to_json(
+    staff: &[StaffPerformance],
+    names: &std::collections::HashMap<String, String>,
+    date_from: &str,
+    date_to: &str,
+) -> Value {
+    let to_major = |cents: i64| crate::money::Cents::new(cents).to_f64_dp2();
+    let rows: Vec<Value> = staff
+        .iter()
+        .map(|row| {
+            let gross_cents = row.net_sales_cents + row.discount_cents;
+            let average_ticket_cents = if row.order_count > 0 {
+                (row.net_sales_cents as f64 / row.order_count as f64).round() as i64
+            } else {
+                0
+            };
+            let discount_percent = if gross_cents > 0 {
+                (row.discount_cents as f64 * 10_000.0 / gross_cents as f64).round() / 100.0
+            } else {
+                0.0
+            };
+            let staff_name = match row.staff_id.as_deref() {
+                Some(id) => names.get(id).cloned().unwrap_or_else(|| id.to_string()),
+                None => "Unattributed".to_string(),
+            };
+            serde_json::json!({
+                "staffId": row.staff_id,
+                "staffName": staff_name,
+                "unattributed": row.staff_id.is_none(),
+                "orders": row.order_count,
+                "grossSales": to_major(gross_cents),
+                "netSales": to_major(row.net_sales_cents),
+                "averageTicket": to_major(average_ticket_cents),
+                "discountTotal": to_major(row.discount_cents),
+                "discountPercent": discount_percent,
+                "cancelledOrders": row.cancelled_orders,
+                "voidCount": row.void_count,
+                "voidTotal": to_major(row.void_cents),
+                "refundCount": row.refund_count,
+                "refundTotal": to_major(row.refund_cents),
+                "tips": to_major(row.tips_cents),
+                "adjustmentsByReason": row
+                    .adjustments_by_reason
+                    .iter()
+                    .map(|((kind, reason_code), (count, cents))| {
+                        serde_json::json!({
+                            "type": kind,
+                            "reasonCode": reason_code,
+                            "count": count,
+                            "total": to_major(*cents),
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "dateFrom": date_from,
+        "dateTo": date_to,
+        "staff": rows,
+    })
+}
+
+fn staff_performance_to_csv(report: &Value) -> String {
+    let mut out = String::from(STAFF_PERFORMANCE_CSV_HEADER);
+    out.push('\n');
+    let cell = |row: &Value, key: &str| match row.get(key) {
+        Some(Value::String(text)) => crate::recovery::csv_escape(text),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    };
+    for row in report["staff"].as_array().into_iter().flatten() {
+        let cells: Vec<String> = [
+            "staffId",
+            "staffName",
+            "orders",
+            "grossSales",
+            "netSales",
+            "averageTicket",
+            "discountTotal",
+            "discountPercent",
+            "cancelledOrders",
+            "voidCount",
+            "voidTotal",
+            "refundCount",
+            "refundTotal",
+            "tips",
+        ]
+        .iter()
+        .map(|key| cell(row, key))
+        .collect();
+        out.push_str(&cells.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn staff_performance_table(report: &Value) -> ReportTable {
+    ReportTable::from_objects(
+        STAFF_PERFORMANCE_COLUMNS,
+        report["staff"].as_array().into_iter().flatten(),
+    )
+}
+
+fn resolve_staff_performance_range(
+    conn: &rusqlite::Connection,
+    payload: &ReportStaffPerformancePayload,
+) -> Result<(String, String), String> {
+    let parse = |raw: &Option<String>, label: &str| -> Result<Option<String>, String> {
+        match raw.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            Some(value) => chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|date| Some(date.format("%Y-%m-%d").to_string()))
+                .map_err(|_| format!("Invalid {label}: {value}")),
+            None => Ok(None),
+        }
+    };
+    let date_to = match parse(&payload.date_to, "dateTo")? {
+        Some(date) => date,
+        None => resolve_report_date(conn, None),
+    };
+    let date_from = parse(&payload.date_from, "dateFrom")?.unwrap_or_else(|| date_to.clone());
+    if date_from > date_to {
+        return Err("dateFrom must not be after dateTo".into());
+    }
+    Ok((date_from, date_to))
 }
 
 fn extract_z_report_id_from_payload(payload: &serde_json::Value) -> Option<String> {
@@ -902,13 +1900,24 @@ pub async fn driver_record_earning(
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
     let payload = arg0.unwrap_or(serde_json::json!({}));
+    let key = idempotency::request_key(&payload);
+    idempotency::run_once(&db, "driver_record_earning", key, || async {
+        record_driver_earning(&db, &payload)
+    })
+    .await
+}
+
+fn record_driver_earning(
+    db: &db::DbState,
+    payload: &serde_json::Value,
+) -> Result<serde_json::Value, String> {
     let driver_id =
-        crate::value_str(&payload, &["driverId", "driver_id"]).ok_or("Missing driverId")?;
+        crate::value_str(payload, &["driverId", "driver_id"]).ok_or("Missing driverId")?;
     let shift_id = crate::value_str(
-        &payload,
+        payload,
         &["shiftId", "shift_id", "staffShiftId", "staff_shift_id"],
     );
-    let order_id = crate::value_str(&payload, &["orderId", "order_id"]).ok_or("Missing orderId")?;
+    let order_id = crate::value_str(payload, &["orderId", "order_id"]).ok_or("Missing orderId")?;
     let now = Utc::now().to_rfc3339();
 
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
@@ -1076,7 +2085,7 @@ pub async fn driver_get_active(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
-    let branch_id = parse_driver_branch_payload(arg0);
+    let branch_id = crate::branches::report_scope(Some(parse_driver_branch_payload(arg0)));
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let mut stmt = conn
         .prepare(
@@ -1100,7 +2109,7 @@ pub async fn driver_get_active(
                  GROUP BY driver_id
              ) oc ON oc.driver_id = ss.staff_id
              WHERE ss.role_type = 'driver' AND ss.status = 'active'
-               AND (?1 = '' OR ss.branch_id = ?1)
+               AND COALESCE(ss.branch_id, '') = ?1
              ORDER BY ss.check_in_time ASC",
         )
         .map_err(|e| e.to_string())?;
@@ -1226,49 +2235,43 @@ pub async fn report_get_today_statistics(
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
     let payload = parse_report_today_statistics_payload(arg0);
-    let branch_id = payload
-        .branch_id
-        .map(|v| v.trim().to_string())
-        .filter(|v| !v.is_empty())
-        .unwrap_or_default();
-    let date = payload
-        .date
-        .map(|v| v.trim().to_string())
-        .filter(|v| !v.is_empty())
-        .unwrap_or_else(|| Local::now().format("%Y-%m-%d").to_string());
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let orders = crate::load_orders_for_period(&conn, &branch_id, &date, &date)?;
-    let mut total_sales = 0.0f64;
-    let mut completed = 0i64;
-    let mut cancelled = 0i64;
-    for (_id, status, _created_at, items_json, _staff, _payment_method) in &orders {
-        let (order_total, _) = crate::parse_item_totals(items_json);
-        total_sales += order_total;
-        let st = status.to_lowercase();
-        if matches!(
-            st.as_str(),
-            "completed" | "delivered" | "approved" | "ready"
-        ) {
-            completed += 1;
-        }
-        if matches!(st.as_str(), "cancelled" | "canceled" | "declined") {
-            cancelled += 1;
+    let branch_id = crate::branches::report_scope(payload.branch_id);
+    db.read(|conn| {
+        let rounding = crate::money::RoundingRule::from_settings(conn);
+        let date = resolve_report_date(conn, payload.date);
+        let orders = crate::load_orders_for_period(conn, &branch_id, &date, &date)?;
+        let mut total_sales = 0.0f64;
+        let mut completed = 0i64;
+        let mut cancelled = 0i64;
+        for (_id, status, _created_at, items_json, _staff, _payment_method) in &orders {
+            let (order_total, _) = crate::parse_item_totals(items_json, rounding);
+            total_sales += order_total;
+            let st = status.to_lowercase();
+            if matches!(
+                st.as_str(),
+                "completed" | "delivered" | "approved" | "ready"
+            ) {
+                completed += 1;
+            }
+            if matches!(st.as_str(), "cancelled" | "canceled" | "declined") {
+                cancelled += 1;
+            }
         }
-    }
-    let total_orders = orders.len() as i64;
-    let avg = if total_orders > 0 {
-        total_sales / (total_orders as f64)
-    } else {
-        0.0
-    };
-    Ok(serde_json::json!({
-        "success": true,
-        "totalOrders": total_orders,
-        "completedOrders": completed,
-        "cancelledOrders": cancelled,
-        "totalSales": total_sales,
-        "averageOrderValue": avg
-    }))
+        let total_orders = orders.len() as i64;
+        let avg = if total_orders > 0 {
+            total_sales / (total_orders as f64)
+        } else {
+            0.0
+        };
+        Ok(serde_json::json!({
+            "success": true,
+            "totalOrders": total_orders,
+            "completedOrders": completed,
+            "cancelledOrders": cancelled,
+            "totalSales": total_sales,
+            "averageOrderValue": avg
+        }))
+    })
 }
 
 #[tauri::command]
@@ -1277,31 +2280,30 @@ pub async fn report_get_sales_trend(
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
     let payload = parse_report_sales_trend_payload(arg0);
-    let branch_id = payload
-        .branch_id
-        .map(|v| v.trim().to_string())
-        .filter(|v| !v.is_empty())
-        .unwrap_or_default();
+    let branch_id = crate::branches::report_scope(payload.branch_id);
     let days = payload.days.unwrap_or(7).clamp(1, 60);
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let mut points: Vec<serde_json::Value> = Vec::new();
-    for i in (0..days).rev() {
-        let date = (Local::now() - chrono::Duration::days(i))
-            .format("%Y-%m-%d")
-            .to_string();
-        let orders = crate::load_orders_for_period(&conn, &branch_id, &date, &date)?;
-        let mut total = 0.0f64;
-        for (_id, _status, _created, items, _staff, _payment_method) in orders.iter() {
-            let (order_total, _) = crate::parse_item_totals(items);
-            total += order_total;
+    db.read(|conn| {
+        let rounding = crate::money::RoundingRule::from_settings(conn);
+        let today = business_today(conn);
+        let mut points: Vec<serde_json::Value> = Vec::new();
+        for i in (0..days).rev() {
+            let date = (today - chrono::Duration::days(i))
+                .format("%Y-%m-%d")
+                .to_string();
+            let orders = crate::load_orders_for_period(conn, &branch_id, &date, &date)?;
+            let mut total = 0.0f64;
+            for (_id, _status, _created, items, _staff, _payment_method) in orders.iter() {
+                let (order_total, _) = crate::parse_item_totals(items, rounding);
+                total += order_total;
+            }
+            points.push(serde_json::json!({
+                "date": date,
+                "sales": total,
+                "orders": orders.len()
+            }));
         }
-        points.push(serde_json::json!({
-            "date": date,
-            "sales": total,
-            "orders": orders.len()
-        }));
-    }
-    Ok(serde_json::json!({ "success": true, "data": points }))
+        Ok(serde_json::json!({ "success": true, "data": points }))
+    })
 }
 
 #[tauri::command]
@@ -1310,30 +2312,23 @@ pub async fn report_get_top_items(
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
     let payload = parse_report_top_items_payload(arg0);
-    let branch_id = payload
-        .branch_id
-        .map(|v| v.trim().to_string())
-        .filter(|v| !v.is_empty())
-        .unwrap_or_default();
-    let date = payload
-        .date
-        .map(|v| v.trim().to_string())
-        .filter(|v| !v.is_empty())
-        .unwrap_or_else(|| Local::now().format("%Y-%m-%d").to_string());
+    let branch_id = crate::branches::report_scope(payload.branch_id);
     let limit = payload.limit.unwrap_or(10).clamp(1, 50) as usize;
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let orders = crate::load_orders_for_period(&conn, &branch_id, &date, &date)?;
-    let live = aggregate_top_items_from_order_rows(
-        orders
-            .into_iter()
-            .map(|(_id, status, _created, items, _staff, _payment_method)| (status, items)),
-    );
-    // Merge only archived sales from the requested day. Lifetime totals must
-    // not leak into this daily ranking.
-    let archived = load_daily_top_items(&conn, &branch_id, &date, &date).unwrap_or_default();
-    let merged = merge_aggregated_top_items(live, archived);
-    let top = top_items_to_json(merged, limit);
-    Ok(serde_json::json!({ "success": true, "data": top }))
+    db.read(|conn| {
+        let date = resolve_report_date(conn, payload.date);
+        let orders = crate::load_orders_for_period(conn, &branch_id, &date, &date)?;
+        let live = aggregate_top_items_from_order_rows(
+            orders
+                .into_iter()
+                .map(|(_id, status, _created, items, _staff, _payment_method)| (status, items)),
+        );
+        // Merge only archived sales from the requested day. Lifetime totals must
+        // not leak into this daily ranking.
+        let archived = load_daily_top_items(conn, &branch_id, &date, &date).unwrap_or_default();
+        let merged = merge_aggregated_top_items(live, archived);
+        let top = top_items_to_json(merged, limit);
+        Ok(serde_json::json!({ "success": true, "data": top }))
+    })
 }
 
 #[tauri::command]
@@ -1342,30 +2337,39 @@ pub async fn report_get_weekly_top_items(
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
     let payload = parse_report_weekly_top_items_payload(arg0);
-    let branch_id = payload
-        .branch_id
-        .map(|v| v.trim().to_string())
-        .filter(|v| !v.is_empty())
-        .unwrap_or_default();
+    let branch_id = crate::branches::report_scope(payload.branch_id);
     let limit = payload.limit.unwrap_or(10).clamp(1, 50) as usize;
-    let today = Local::now().format("%Y-%m-%d").to_string();
-    let from = (Local::now() - chrono::Duration::days(6))
+    db.read(|conn| {
+        let top = top_items_for_recent_days(conn, &branch_id, 7, limit)?;
+        Ok(serde_json::json!({ "success": true, "data": top }))
+    })
+}
+
+/// Top items over the last `days` business days including today, live
+/// orders plus the archived daily buckets in that window.
+pub(crate) fn top_items_for_recent_days(
+    conn: &rusqlite::Connection,
+    branch_id: &str,
+    days: i64,
+    limit: usize,
+) -> Result<Vec<serde_json::Value>, String> {
+    let business_today = business_today(conn);
+    let today = business_today.format("%Y-%m-%d").to_string();
+    let from = (business_today - chrono::Duration::days(days.max(1) - 1))
         .format("%Y-%m-%d")
         .to_string();
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let orders = crate::load_orders_for_period(&conn, &branch_id, &from, &today)?;
+    let orders = crate::load_orders_for_period(conn, branch_id, &from, &today)?;
     let live = aggregate_top_items_from_order_rows(
         orders
             .into_iter()
             .map(|(_id, status, _created, items, _staff, _payment_method)| (status, items)),
     );
-    // Merge only the archived daily buckets inside this exact seven-day
-    // window. The former lifetime aggregate made old favorites outrank
-    // what customers actually bought this week.
-    let archived = load_daily_top_items(&conn, &branch_id, &from, &today).unwrap_or_default();
+    // Merge only the archived daily buckets inside this exact window. The
+    // former lifetime aggregate made old favorites outrank what customers
+    // actually bought recently.
+    let archived = load_daily_top_items(conn, branch_id, &from, &today).unwrap_or_default();
     let merged = merge_aggregated_top_items(live, archived);
-    let top = top_items_to_json(merged, limit);
-    Ok(serde_json::json!({ "success": true, "data": top }))
+    Ok(top_items_to_json(merged, limit))
 }
 
 #[tauri::command]
@@ -1374,198 +2378,690 @@ pub async fn report_get_daily_staff_performance(
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
     let payload = parse_report_daily_staff_performance_payload(arg0);
-    let branch_id = payload
-        .branch_id
-        .map(|v| v.trim().to_string())
-        .filter(|v| !v.is_empty())
-        .unwrap_or_default();
-    let date = payload
-        .date
-        .map(|v| v.trim().to_string())
-        .filter(|v| !v.is_empty())
-        .unwrap_or_else(|| Local::now().format("%Y-%m-%d").to_string());
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let orders = crate::load_orders_for_period(&conn, &branch_id, &date, &date)?;
-    let mut perf: std::collections::HashMap<String, (i64, f64)> = std::collections::HashMap::new();
-    for (_id, _status, _created, items, staff, _payment_method) in orders {
-        let staff_id = staff.unwrap_or_else(|| "unknown".to_string());
-        let (total, _) = crate::parse_item_totals(&items);
-        let entry = perf.entry(staff_id).or_insert((0, 0.0));
-        entry.0 += 1;
-        entry.1 += total;
-    }
-    let data: Vec<serde_json::Value> = perf
-        .into_iter()
-        .map(|(staff_id, (orders_count, sales_total))| {
-            serde_json::json!({
-                "staffId": staff_id,
-                "orders": orders_count,
-                "sales": sales_total
+    let branch_id = crate::branches::report_scope(payload.branch_id);
+    db.read(|conn| {
+        let rounding = crate::money::RoundingRule::from_settings(conn);
+        let date = resolve_report_date(conn, payload.date);
+        let orders = crate::load_orders_for_period(conn, &branch_id, &date, &date)?;
+        let mut perf: std::collections::HashMap<String, (i64, f64)> =
+            std::collections::HashMap::new();
+        for (_id, _status, _created, items, staff, _payment_method) in orders {
+            let staff_id = staff.unwrap_or_else(|| "unknown".to_string());
+            let (total, _) = crate::parse_item_totals(&items, rounding);
+            let entry = perf.entry(staff_id).or_insert((0, 0.0));
+            entry.0 += 1;
+            entry.1 += total;
+        }
+        let data: Vec<serde_json::Value> = perf
+            .into_iter()
+            .map(|(staff_id, (orders_count, sales_total))| {
+                serde_json::json!({
+                    "staffId": staff_id,
+                    "orders": orders_count,
+                    "sales": sales_total
+                })
             })
-        })
-        .collect();
-    Ok(serde_json::json!({ "success": true, "data": data }))
+            .collect();
+        Ok(serde_json::json!({ "success": true, "data": data }))
+    })
+}
+
+#[tauri::command]
+pub async fn report_get_hourly_sales(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let payload = parse_report_today_statistics_payload(arg0);
+    let branch_id = crate::branches::report_scope(payload.branch_id);
+    db.read(|conn| {
+        let rounding = crate::money::RoundingRule::from_settings(conn);
+        let date = resolve_report_date(conn, payload.date);
+        let rows = load_report_rows_for_day(conn, &branch_id, &date)?;
+
+        let mut hourly_orders = [0i64; 24];
+        let mut hourly_revenue = [0.0f64; 24];
+
+        for (status, created_at, _payment_method, _order_type, total_amount, items) in rows {
+            if is_cancelled_status(&status) {
+                continue;
+            }
+            let hour = local_hour(&created_at, &Local);
+            let revenue = if total_amount > 0.0 {
+                total_amount
+            } else {
+                crate::parse_item_totals(&items, rounding).0
+            };
+            hourly_orders[hour] += 1;
+            hourly_revenue[hour] += revenue;
+        }
+
+        let data: Vec<serde_json::Value> = (0..24)
+            .map(|hour| {
+                serde_json::json!({
+                    "hour": hour,
+                    "orders": hourly_orders[hour],
+                    "revenue": hourly_revenue[hour],
+                })
+            })
+            .collect();
+
+        Ok(serde_json::json!({ "success": true, "data": data }))
+    })
+}
+
+#[tauri::command]
+pub async fn report_get_payment_method_breakdown(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let payload = parse_report_today_statistics_payload(arg0);
+    let branch_id = crate::branches::report_scope(payload.branch_id);
+    db.read(|conn| {
+        let rounding = crate::money::RoundingRule::from_settings(conn);
+        let date = resolve_report_date(conn, payload.date);
+        let rows = load_report_rows_for_day(conn, &branch_id, &date)?;
+
+        let mut cash_count = 0i64;
+        let mut cash_total = 0.0f64;
+        let mut card_count = 0i64;
+        let mut card_total = 0.0f64;
+
+        for (status, _created_at, payment_method, _order_type, total_amount, items) in rows {
+            if is_cancelled_status(&status) {
+                continue;
+            }
+            let method = payment_method.unwrap_or_default().to_ascii_lowercase();
+            let revenue = if total_amount > 0.0 {
+                total_amount
+            } else {
+                crate::parse_item_totals(&items, rounding).0
+            };
+
+            if method.contains("cash") {
+                cash_count += 1;
+                cash_total += revenue;
+            } else if method.contains("card") {
+                card_count += 1;
+                card_total += revenue;
+            }
+        }
+
+        Ok(serde_json::json!({
+            "success": true,
+            "data": {
+                "cash": {
+                    "count": cash_count,
+                    "total": cash_total,
+                },
+                "card": {
+                    "count": card_count,
+                    "total": card_total,
+                }
+            }
+        }))
+    })
+}
+
+#[tauri::command]
+pub async fn report_get_order_type_breakdown(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let payload = parse_report_today_statistics_payload(arg0);
+    let branch_id = crate::branches::report_scope(payload.branch_id);
+    db.read(|conn| {
+        let rounding = crate::money::RoundingRule::from_settings(conn);
+        let date = resolve_report_date(conn, payload.date);
+        let rows = load_report_rows_for_day(conn, &branch_id, &date)?;
+
+        let mut delivery_count = 0i64;
+        let mut delivery_total = 0.0f64;
+        let mut instore_count = 0i64;
+        let mut instore_total = 0.0f64;
+
+        for (status, _created_at, _payment_method, order_type, total_amount, items) in rows {
+            if is_cancelled_status(&status) {
+                continue;
+            }
+            let order_type = order_type.unwrap_or_default().to_ascii_lowercase();
+            let revenue = if total_amount > 0.0 {
+                total_amount
+            } else {
+                crate::parse_item_totals(&items, rounding).0
+            };
+
+            if order_type == "delivery" {
+                delivery_count += 1;
+                delivery_total += revenue;
+            } else if matches!(
+                order_type.as_str(),
+                "dine-in" | "dinein" | "takeaway" | "pickup" | "instore" | "in-store"
+            ) {
+                instore_count += 1;
+                instore_total += revenue;
+            }
+        }
+
+        Ok(serde_json::json!({
+            "success": true,
+            "data": {
+                "delivery": {
+                    "count": delivery_count,
+                    "total": delivery_total,
+                },
+                "instore": {
+                    "count": instore_count,
+                    "total": instore_total,
+                }
+            }
+        }))
+    })
+}
+
+#[tauri::command]
+pub async fn reports_get_daily_summary(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    crate::diagnostics::metrics::track("reports_get_daily_summary", async move {
+        let payload = parse_report_today_statistics_payload(arg0);
+        let branch_id = crate::branches::report_scope(payload.branch_id);
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let today = business_day::current_business_day_report_date_at(&conn, Local::now());
+        let date = resolve_report_date(&conn, payload.date);
+        let (data, cached) =
+            load_or_compute_daily_summary(&conn, &branch_id, &date, &today, &Local)?;
+        Ok(serde_json::json!({ "success": true, "data": data, "cached": cached }))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn reports_get_hourly_heatmap(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let payload = parse_report_hourly_heatmap_payload(arg0);
+    let branch_id = crate::branches::report_scope(payload.branch_id.clone());
+    let (date_from, date_to) = resolve_heatmap_range(&payload, Local::now().date_naive())?;
+    db.read(|conn| {
+        let data = build_hourly_heatmap_report(conn, &branch_id, date_from, date_to, &Local)?;
+        Ok(serde_json::json!({ "success": true, "data": data }))
+    })
+}
+
+/// Sales, discounts, voids, refunds and tips per staff member over a date
+/// range. `format: "csv"` also returns the table as CSV in `content`.
+#[tauri::command]
+pub async fn reports_get_staff_performance(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let payload = parse_report_staff_performance_payload(arg0);
+    let csv = match payload.format.as_deref().map(str::trim) {
+        None | Some("") | Some("json") => false,
+        Some("csv") => true,
+        Some(other) => return Err(format!("Unsupported export format: {other}")),
+    };
+    let branch_id = crate::branches::report_scope(payload.branch_id.clone());
+    db.read(|conn| {
+        let (date_from, date_to) = resolve_staff_performance_range(conn, &payload)?;
+        let staff = accumulate_staff_performance(conn, &branch_id, &date_from, &date_to)?;
+        let names = staff_display_names(conn, &branch_id);
+        let data = staff_performance_to_json(&staff, &names, &date_from, &date_to);
+        if csv {
+            let content = staff_performance_to_csv(&data);
+            return Ok(serde_json::json!({
+                "success": true,
+                "format": "csv",
+                "filename": format!("staff-performance_{date_from}_{date_to}.csv"),
+                "data": data,
+                "content": content,
+            }));
+        }
+        Ok(serde_json::json!({ "success": true, "format": "json", "data": data }))
+    })
+}
+
+/// Delivery platform orders for a date range grouped by `plugin`: gross,
+/// service charges, commissions and the payout the platform still owes.
+#[tauri::command]
+pub async fn reports_get_platform_reconciliation(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let payload = parse_report_staff_performance_payload(arg0);
+    let branch_id = crate::branches::report_scope(payload.branch_id.clone());
+    db.read(|conn| {
+        let (date_from, date_to) = resolve_staff_performance_range(conn, &payload)?;
+        let (start_at, end_at) = business_day::business_day_bounds(conn, &date_from, &date_to)?;
+        let platforms = crate::platform_fees::reconciliation(conn, &branch_id, &start_at, &end_at)?;
+        let data = crate::platform_fees::reconciliation_to_json(&platforms, &date_from, &date_to);
+        Ok(serde_json::json!({ "success": true, "data": data }))
+    })
 }
 
+/// Gross margin per menu item for a date range: revenue against recipe
+/// cost, items without a recipe listed apart, and ingredients whose ledger
+/// usage strays from theoretical usage by more than
+/// `varianceThresholdPercent` (default `inventory.margin_variance_threshold_percent`).
 #[tauri::command]
-pub async fn report_get_hourly_sales(
+pub async fn reports_get_margin(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
-    let payload = parse_report_today_statistics_payload(arg0);
-    let branch_id = payload
-        .branch_id
-        .map(|v| v.trim().to_string())
-        .filter(|v| !v.is_empty())
-        .unwrap_or_default();
-    let date = resolve_report_date(payload.date);
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let rows = load_report_rows_for_day(&conn, &branch_id, &date)?;
+    let threshold = arg0.as_ref().and_then(|payload| {
+        crate::value_f64(
+            payload,
+            &[
+                "varianceThresholdPercent",
+                "variance_threshold_percent",
+                "threshold",
+            ],
+        )
+        .filter(|value| value.is_finite() && *value >= 0.0)
+    });
+    let payload = parse_report_staff_performance_payload(arg0);
+    let branch_id = crate::branches::report_scope(payload.branch_id.clone());
+    db.read(|conn| {
+        let (date_from, date_to) = resolve_staff_performance_range(conn, &payload)?;
+        let (start_at, end_at) = business_day::business_day_bounds(conn, &date_from, &date_to)?;
+        let threshold =
+            threshold.unwrap_or_else(|| crate::inventory::margin_variance_threshold(conn));
+        let report =
+            crate::inventory::margin_report(conn, &branch_id, &start_at, &end_at, threshold)?;
+        let data = report.to_json(&date_from, &date_to);
+        Ok(serde_json::json!({ "success": true, "data": data }))
+    })
+}
 
-    let mut hourly_orders = [0i64; 24];
-    let mut hourly_revenue = [0.0f64; 24];
+// =====================================================================
+// Report exports
+// =====================================================================
+//
+// `reports_export` renders any report below through the shared exporter in
+// `crate::reports_export`. Each report contributes a column list and a table
+// builder over its JSON payload, so exporting another report is one more
+// arm in `build_report_export`.
+
+const EXPORTABLE_REPORTS: &[&str] = &[
+    "daily_summary",
+    "hourly_heatmap",
+    "staff_performance",
+    "labor",
+    "platform_reconciliation",
+    "zreport",
+];
+
+const DAILY_SUMMARY_COLUMNS: &[Column] = &[
+    Column::text("section", "section"),
+    Column::text("name", "name"),
+    Column::integer("count", "count"),
+    Column::money("amount", "amount"),
+];
+
+const HOURLY_HEATMAP_COLUMNS: &[Column] = &[
+    Column::text("weekday", "weekday"),
+    Column::integer("hour", "hour"),
+    Column::integer("orders", "orders"),
+    Column::money("revenue", "revenue"),
+    Column::integer("previousOrders", "previous_orders"),
+    Column::money("previousRevenue", "previous_revenue"),
+    Column::decimal("ordersDeltaPct", "orders_delta_pct"),
+    Column::decimal("revenueDeltaPct", "revenue_delta_pct"),
+];
+
+const LABOR_COLUMNS: &[Column] = &[
+    Column::text("staffId", "staff_id"),
+    Column::text("staffName", "staff_name"),
+    Column::integer("scheduledShifts", "scheduled_shifts"),
+    Column::integer("workedShifts", "worked_shifts"),
+    Column::integer("noShows", "no_shows"),
+    Column::integer("unscheduledShifts", "unscheduled_shifts"),
+    Column::integer("earlyClockIns", "early_clock_ins"),
+    Column::integer("lateClockIns", "late_clock_ins"),
+    Column::integer("scheduledMinutes", "scheduled_minutes"),
+    Column::integer("actualMinutes", "actual_minutes"),
+];
+
+const PLATFORM_RECONCILIATION_COLUMNS: &[Column] = &[
+    Column::text("plugin", "platform"),
+    Column::integer("orderCount", "orders"),
+    Column::money("gross", "gross"),
+    Column::money("serviceCharges", "service_charges"),
+    Column::money("commissions", "commissions"),
+    Column::money("netExpectedPayout", "net_expected_payout"),
+];
+
+const Z_REPORT_COLUMNS: &[Column] = &[
+    Column::text("reportDate", "report_date"),
+    Column::text("branchId", "branch_id"),
+    Column::text("terminalId", "terminal_id"),
+    Column::text("generatedAt", "generated_at"),
+    Column::integer("totalOrders", "total_orders"),
+    Column::money("grossSales", "gross_sales"),
+    Column::money("netSales", "net_sales"),
+    Column::money("cashSales", "cash_sales"),
+    Column::money("cardSales", "card_sales"),
+    Column::money("refundsTotal", "refunds_total"),
+    Column::money("voidsTotal", "voids_total"),
+    Column::money("discountsTotal", "discounts_total"),
+    Column::money("tipsTotal", "tips_total"),
+    Column::money("expensesTotal", "expenses_total"),
+    Column::money("openingCash", "opening_cash"),
+    Column::money("closingCash", "closing_cash"),
+    Column::money("expectedCash", "expected_cash"),
+    Column::money("cashVariance", "cash_variance"),
+];
+
+/// Headline figures first, then one line per order type, payment method
+/// and adjustment kind.
+fn daily_summary_table(report: &Value) -> ReportTable {
+    let mut table = ReportTable::new(DAILY_SUMMARY_COLUMNS);
+    let mut line = |section: &str, name: &Value, count: &Value, amount: &Value| {
+        table.push(vec![
+            serde_json::json!(section),
+            name.clone(),
+            count.clone(),
+            amount.clone(),
+        ]);
+    };
+    let none = Value::Null;
+    line("totals", &"orders".into(), &report["orderCount"], &none);
+    for (name, key) in [
+        ("gross_sales", "grossSales"),
+        ("net_sales", "netSales"),
+        ("tax", "tax"),
+        ("discounts", "discounts"),
+        ("average_ticket", "averageTicket"),
+    ] {
+        line("totals", &name.into(), &none, &report[key]);
+    }
+    for row in report["byOrderType"].as_array().into_iter().flatten() {
+        line(
+            "order_type",
+            &row["orderType"],
+            &row["count"],
+            &row["total"],
+        );
+    }
+    for row in report["byPaymentMethod"].as_array().into_iter().flatten() {
+        line(
+            "payment_method",
+            &row["method"],
+            &row["count"],
+            &row["total"],
+        );
+    }
+    for key in ["refunds", "returns", "voids", "cancelled"] {
+        let row = &report[key];
+        line("adjustments", &key.into(), &row["count"], &row["total"]);
+    }
+    table
+}
 
-    for (status, created_at, _payment_method, _order_type, total_amount, items) in rows {
-        if is_cancelled_status(&status) {
-            continue;
+/// One line per weekday and hour of the current period, next to the same
+/// cell of the comparison period.
+fn hourly_heatmap_table(report: &Value) -> ReportTable {
+    let mut table = ReportTable::new(HOURLY_HEATMAP_COLUMNS);
+    let weekdays = report["weekdays"].as_array().cloned().unwrap_or_default();
+    for (day, weekday) in weekdays.iter().enumerate() {
+        for hour in 0..24 {
+            table.push(vec![
+                weekday.clone(),
+                serde_json::json!(hour),
+                report["current"]["orders"][day][hour].clone(),
+                report["current"]["revenue"][day][hour].clone(),
+                report["previous"]["orders"][day][hour].clone(),
+                report["previous"]["revenue"][day][hour].clone(),
+                report["comparison"]["orders"][day][hour].clone(),
+                report["comparison"]["revenue"][day][hour].clone(),
+            ]);
         }
-        let hour = created_at
-            .get(11..13)
-            .and_then(|raw| raw.parse::<usize>().ok())
-            .filter(|h| *h < 24)
-            .unwrap_or(0);
-        let revenue = if total_amount > 0.0 {
-            total_amount
-        } else {
-            crate::parse_item_totals(&items).0
-        };
-        hourly_orders[hour] += 1;
-        hourly_revenue[hour] += revenue;
     }
+    table
+}
 
-    let data: Vec<serde_json::Value> = (0..24)
-        .map(|hour| {
-            serde_json::json!({
-                "hour": hour,
-                "orders": hourly_orders[hour],
-                "revenue": hourly_revenue[hour],
-            })
-        })
-        .collect();
+fn labor_table(report: &Value) -> ReportTable {
+    ReportTable::from_objects(
+        LABOR_COLUMNS,
+        report["staff"].as_array().into_iter().flatten(),
+    )
+}
 
-    Ok(serde_json::json!({ "success": true, "data": data }))
+fn platform_reconciliation_table(report: &Value) -> ReportTable {
+    let platforms = report["platforms"].as_array().into_iter().flatten();
+    ReportTable::from_objects(
+        PLATFORM_RECONCILIATION_COLUMNS,
+        platforms.chain(std::iter::once(&report["totals"])),
+    )
 }
 
-#[tauri::command]
-pub async fn report_get_payment_method_breakdown(
-    arg0: Option<serde_json::Value>,
-    db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
-    let payload = parse_report_today_statistics_payload(arg0);
-    let branch_id = payload
-        .branch_id
-        .map(|v| v.trim().to_string())
-        .filter(|v| !v.is_empty())
-        .unwrap_or_default();
-    let date = resolve_report_date(payload.date);
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let rows = load_report_rows_for_day(&conn, &branch_id, &date)?;
+fn z_report_table(reports: &[Value]) -> ReportTable {
+    ReportTable::from_objects(Z_REPORT_COLUMNS, reports)
+}
 
-    let mut cash_count = 0i64;
-    let mut cash_total = 0.0f64;
-    let mut card_count = 0i64;
-    let mut card_total = 0.0f64;
+struct ReportExport {
+    table: ReportTable,
+    branch_id: String,
+    range: String,
+}
 
-    for (status, _created_at, payment_method, _order_type, total_amount, items) in rows {
-        if is_cancelled_status(&status) {
-            continue;
+/// Runs `report` with its usual parameters and reduces the result to a
+/// table.
+fn build_report_export(
+    db: &db::DbState,
+    report: &str,
+    params: Value,
+) -> Result<ReportExport, String> {
+    match report {
+        "daily_summary" => {
+            let payload = parse_report_today_statistics_payload(Some(params));
+            let branch_id = crate::branches::report_scope(payload.branch_id);
+            let (data, _) = db.write(|conn| {
+                let today = business_day::current_business_day_report_date_at(conn, Local::now());
+                let date = resolve_report_date(conn, payload.date);
+                load_or_compute_daily_summary(conn, &branch_id, &date, &today, &Local)
+            })?;
+            Ok(ReportExport {
+                table: daily_summary_table(&data),
+                range: data["date"].as_str().unwrap_or_default().to_string(),
+                branch_id,
+            })
         }
-        let method = payment_method.unwrap_or_default().to_ascii_lowercase();
-        let revenue = if total_amount > 0.0 {
-            total_amount
-        } else {
-            crate::parse_item_totals(&items).0
-        };
-
-        if method.contains("cash") {
-            cash_count += 1;
-            cash_total += revenue;
-        } else if method.contains("card") {
-            card_count += 1;
-            card_total += revenue;
+        "hourly_heatmap" => {
+            let payload = parse_report_hourly_heatmap_payload(Some(params));
+            let branch_id = crate::branches::report_scope(payload.branch_id.clone());
+            let (date_from, date_to) = resolve_heatmap_range(&payload, Local::now().date_naive())?;
+            let data = db.read(|conn| {
+                build_hourly_heatmap_report(conn, &branch_id, date_from, date_to, &Local)
+            })?;
+            Ok(ReportExport {
+                table: hourly_heatmap_table(&data),
+                range: reports_export::range_label(
+                    &date_from.format("%Y-%m-%d").to_string(),
+                    &date_to.format("%Y-%m-%d").to_string(),
+                ),
+                branch_id,
+            })
+        }
+        "staff_performance" => {
+            let payload = parse_report_staff_performance_payload(Some(params));
+            let branch_id = crate::branches::report_scope(payload.branch_id.clone());
+            let (data, range) = db.read(|conn| {
+                let (date_from, date_to) = resolve_staff_performance_range(conn, &payload)?;
+                let staff = accumulate_staff_performance(conn, &branch_id, &date_from, &date_to)?;
+                let names = staff_display_names(conn, &branch_id);
+                Ok((
+                    staff_performance_to_json(&staff, &names, &date_from, &date_to),
+                    reports_export::range_label(&date_from, &date_to),
+                ))
+            })?;
+            Ok(ReportExport {
+                table: staff_performance_table(&data),
+                branch_id,
+                range,
+            })
+        }
+        "labor" => {
+            let staff_id = value_str(&params, &["staffId", "staff_id"]);
+            let payload = parse_report_staff_performance_payload(Some(params));
+            let branch_id = crate::branches::report_scope(payload.branch_id.clone());
+            let (data, range) = db.read(|conn| {
+                let (date_from, date_to) = resolve_staff_performance_range(conn, &payload)?;
+                let day = |raw: &str| {
+                    chrono::NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+                        .map_err(|_| format!("Invalid date: {raw}"))
+                };
+                let (from, _) = schedule::local_day_bounds(day(&date_from)?)?;
+                let (_, to) = schedule::local_day_bounds(day(&date_to)?)?;
+                let data = schedule::adherence(
+                    conn,
+                    Some(branch_id.as_str()).filter(|id| !id.is_empty()),
+                    staff_id.as_deref(),
+                    from,
+                    to,
+                    Utc::now(),
+                )?;
+                Ok((data, reports_export::range_label(&date_from, &date_to)))
+            })?;
+            Ok(ReportExport {
+                table: labor_table(&data),
+                branch_id,
+                range,
+            })
+        }
+        "platform_reconciliation" => {
+            let payload = parse_report_staff_performance_payload(Some(params));
+            let branch_id = crate::branches::report_scope(payload.branch_id.clone());
+            let (data, range) = db.read(|conn| {
+                let (date_from, date_to) = resolve_staff_performance_range(conn, &payload)?;
+                let (start_at, end_at) =
+                    business_day::business_day_bounds(conn, &date_from, &date_to)?;
+                let platforms =
+                    crate::platform_fees::reconciliation(conn, &branch_id, &start_at, &end_at)?;
+                Ok((
+                    crate::platform_fees::reconciliation_to_json(&platforms, &date_from, &date_to),
+                    reports_export::range_label(&date_from, &date_to),
+                ))
+            })?;
+            Ok(ReportExport {
+                table: platform_reconciliation_table(&data),
+                branch_id,
+                range,
+            })
         }
+        "zreport" => {
+            let payload = parse_report_staff_performance_payload(Some(params));
+            let branch_id = crate::branches::report_scope(payload.branch_id.clone());
+            let (date_from, date_to) =
+                db.read(|conn| resolve_staff_performance_range(conn, &payload))?;
+            let listed = zreport::list_z_reports(
+                db,
+                &serde_json::json!({ "startDate": date_from, "endDate": date_to }),
+            )?;
+            let mut reports: Vec<Value> = listed["reports"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|report| {
+                    branch_id.is_empty() || report["branchId"].as_str() == Some(branch_id.as_str())
+                })
+                .cloned()
+                .collect();
+            reports.sort_by(|a, b| {
+                a["reportDate"]
+                    .as_str()
+                    .cmp(&b["reportDate"].as_str())
+                    .then_with(|| a["generatedAt"].as_str().cmp(&b["generatedAt"].as_str()))
+            });
+            Ok(ReportExport {
+                table: z_report_table(&reports),
+                range: reports_export::range_label(&date_from, &date_to),
+                branch_id,
+            })
+        }
+        other => Err(format!(
+            "Unknown report type: {other} (expected one of {})",
+            EXPORTABLE_REPORTS.join(", ")
+        )),
     }
+}
 
-    Ok(serde_json::json!({
-        "success": true,
-        "data": {
-            "cash": {
-                "count": cash_count,
-                "total": cash_total,
-            },
-            "card": {
-                "count": card_count,
-                "total": card_total,
-            }
-        }
-    }))
+#[derive(Debug)]
+struct ReportsExportPayload {
+    report: String,
+    format: ExportFormat,
+    language: Option<String>,
+    params: Value,
+}
+
+/// `{ report, format?, params?, language? }`. Without `params` the report
+/// parameters are read from the payload itself.
+fn parse_reports_export_payload(arg0: Option<Value>) -> Result<ReportsExportPayload, String> {
+    let payload = arg0.unwrap_or(serde_json::json!({}));
+    let report = value_str(&payload, &["report", "reportType", "report_type", "type"])
+        .map(|report| report.to_ascii_lowercase())
+        .ok_or("Missing report type")?;
+    let format = ExportFormat::parse(value_str(&payload, &["format"]).as_deref())?;
+    let language = value_str(&payload, &["language", "locale"]);
+    let params = payload
+        .get("params")
+        .filter(|params| params.is_object())
+        .cloned()
+        .unwrap_or(payload);
+    Ok(ReportsExportPayload {
+        report,
+        format,
+        language,
+        params,
+    })
 }
 
+/// Export a report (`daily_summary`, `hourly_heatmap`, `staff_performance`,
+/// `labor`, `platform_reconciliation` or `zreport`) as CSV, JSON or XLSX
+/// into the exports folder.
 #[tauri::command]
-pub async fn report_get_order_type_breakdown(
+pub async fn reports_export(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
-    let payload = parse_report_today_statistics_payload(arg0);
-    let branch_id = payload
-        .branch_id
-        .map(|v| v.trim().to_string())
-        .filter(|v| !v.is_empty())
-        .unwrap_or_default();
-    let date = resolve_report_date(payload.date);
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let rows = load_report_rows_for_day(&conn, &branch_id, &date)?;
-
-    let mut delivery_count = 0i64;
-    let mut delivery_total = 0.0f64;
-    let mut instore_count = 0i64;
-    let mut instore_total = 0.0f64;
-
-    for (status, _created_at, _payment_method, order_type, total_amount, items) in rows {
-        if is_cancelled_status(&status) {
-            continue;
-        }
-        let order_type = order_type.unwrap_or_default().to_ascii_lowercase();
-        let revenue = if total_amount > 0.0 {
-            total_amount
-        } else {
-            crate::parse_item_totals(&items).0
+    let payload = parse_reports_export_payload(arg0)?;
+    db.run_blocking(move |db| {
+        let export = build_report_export(db, &payload.report, payload.params)?;
+        let language =
+            db.read(|conn| Ok(print::print_language(conn, payload.language.as_deref())))?;
+        let name = ExportName {
+            report: &payload.report,
+            branch_id: &export.branch_id,
+            range: &export.range,
         };
+        let file = reports_export::write_export(
+            &reports_export::exports_dir(db)?,
+            &export.table,
+            &name,
+            payload.format,
+            &ExportOptions::for_language(&language),
+        )?;
+        info!(
+            report = %payload.report,
+            file = file["fileName"].as_str().unwrap_or_default(),
+            "Report exported"
+        );
+        Ok(serde_json::json!({ "success": true, "report": payload.report, "file": file }))
+    })
+    .await
+}
 
-        if order_type == "delivery" {
-            delivery_count += 1;
-            delivery_total += revenue;
-        } else if matches!(
-            order_type.as_str(),
-            "dine-in" | "dinein" | "takeaway" | "pickup" | "instore" | "in-store"
-        ) {
-            instore_count += 1;
-            instore_total += revenue;
-        }
-    }
-
+/// Files previously written by `reports_export`, newest first.
+#[tauri::command]
+pub async fn exports_list(db: tauri::State<'_, db::DbState>) -> Result<serde_json::Value, String> {
+    let dir = reports_export::exports_dir(&db)?;
+    let files = reports_export::list_exports(&dir)?;
     Ok(serde_json::json!({
         "success": true,
-        "data": {
-            "delivery": {
-                "count": delivery_count,
-                "total": delivery_total,
-            },
-            "instore": {
-                "count": instore_count,
-                "total": instore_total,
-            }
-        }
+        "directory": dir.to_string_lossy(),
+        "files": files,
     }))
 }
 
@@ -1799,15 +3295,11 @@ pub async fn report_resolve_payment_blocker(
 }
 
 #[tauri::command]
-pub async fn inventory_get_stock_metrics() -> Result<serde_json::Value, String> {
-    Ok(serde_json::json!({
-        "success": false,
-        "notImplemented": true,
-        "message": "Inventory service not yet implemented",
-        "inStock": 0,
-        "lowStock": 0,
-        "outOfStock": 0,
-    }))
+pub async fn inventory_get_stock_metrics(
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    crate::inventory::stock_metrics(&conn)
 }
 
 #[tauri::command]
@@ -2350,4 +3842,422 @@ mod dto_tests {
         assert_eq!(merged[0].quantity, 10.0);
         assert_eq!(merged[0].revenue, 50.0);
     }
+
+    fn insert_summary_order(
+        conn: &rusqlite::Connection,
+        id: &str,
+        status: &str,
+        order_type: &str,
+        created_at: &str,
+        total_cents: i64,
+        items: &str,
+    ) {
+        conn.execute(
+            "INSERT INTO orders (id, order_number, items, total_amount, total_amount_cents,
+                                 tax_amount, tax_amount_cents, discount_amount, discount_amount_cents,
+                                 status, order_type, branch_id, sync_status, created_at, updated_at)
+             VALUES (?1, ?1, ?2, ?3, ?4, 0, 100, 0, 50, ?5, ?6, 'branch-A', 'synced', ?7, ?7)",
+            rusqlite::params![
+                id,
+                items,
+                total_cents as f64 / 100.0,
+                total_cents,
+                status,
+                order_type,
+                created_at
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn daily_summary_excludes_cancelled_and_caches_closed_days_until_orders_change() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        insert_summary_order(
+            &conn,
+            "ord-1",
+            "completed",
+            "delivery",
+            "2026-05-03T09:15:00Z",
+            1000,
+            r#"[{"menu_item_id": "m1", "name": "Burger", "quantity": 2, "total_price": 10.0}]"#,
+        );
+        insert_summary_order(
+            &conn,
+            "ord-2",
+            "completed",
+            "takeaway",
+            "2026-05-03T18:40:00Z",
+            2000,
+            r#"[{"menu_item_id": "m2", "name": "Pizza", "quantity": 1, "total_price": 20.0}]"#,
+        );
+        insert_summary_order(
+            &conn,
+            "ord-3",
+            "cancelled",
+            "delivery",
+            "2026-05-03T19:00:00Z",
+            700,
+            r#"[{"menu_item_id": "m1", "name": "Burger", "quantity": 1, "total_price": 7.0}]"#,
+        );
+        conn.execute(
+            "INSERT INTO order_payments (id, order_id, method, amount, amount_cents, status, created_at, updated_at)
+             VALUES ('pay-2', 'ord-2', 'card', 20.0, 2000, 'completed', '2026-05-03T18:41:00Z', '2026-05-03T18:41:00Z')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO payment_adjustments (id, payment_id, order_id, adjustment_type, amount, amount_cents, reason, created_at, updated_at)
+             VALUES ('adj-1', 'pay-2', 'ord-2', 'refund', 5.0, 500, 'cold', '2026-05-03T19:00:00Z', '2026-05-03T19:00:00Z')",
+            [],
+        )
+        .unwrap();
+
+        let (summary, cached) =
+            load_or_compute_daily_summary(&conn, "branch-A", "2026-05-03", "2026-05-04", &Utc)
+                .unwrap();
+        assert!(!cached);
+        assert_eq!(summary["orderCount"], 2);
+        assert_eq!(summary["grossSales"], 31.0, "30.00 sales + 1.00 discounts");
+        assert_eq!(summary["netSales"], 25.0, "gross - discounts - refund");
+        assert_eq!(summary["tax"], 2.0);
+        assert_eq!(summary["averageTicket"], 15.0);
+        assert_eq!(summary["cancelled"]["count"], 1);
+        assert_eq!(summary["cancelled"]["total"], 7.0);
+        assert_eq!(summary["refunds"]["total"], 5.0);
+        assert_eq!(summary["hourly"][9]["revenue"], 10.0);
+        assert_eq!(summary["byPaymentMethod"][0]["method"], "card");
+        assert_eq!(summary["topItemsByQuantity"][0]["menuItemId"], "m1");
+        assert_eq!(summary["topItemsByQuantity"][0]["quantity"], 2.0);
+        assert_eq!(summary["topItemsByRevenue"][0]["menuItemId"], "m2");
+
+        let (_, cached) =
+            load_or_compute_daily_summary(&conn, "branch-A", "2026-05-03", "2026-05-04", &Utc)
+                .unwrap();
+        assert!(cached, "closed day should be served from daily_summaries");
+
+        conn.execute(
+            "UPDATE orders SET status = 'cancelled', updated_at = '2026-05-04T08:00:00Z' WHERE id = 'ord-1'",
+            [],
+        )
+        .unwrap();
+        let (summary, cached) =
+            load_or_compute_daily_summary(&conn, "branch-A", "2026-05-03", "2026-05-04", &Utc)
+                .unwrap();
+        assert!(!cached, "order change must invalidate the cached day");
+        assert_eq!(summary["orderCount"], 1);
+        assert_eq!(summary["cancelled"]["count"], 2);
+
+        let (_, cached) =
+            load_or_compute_daily_summary(&conn, "branch-A", "2026-05-04", "2026-05-04", &Utc)
+                .unwrap();
+        assert!(!cached, "today is never cached");
+        let today_rows: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM daily_summaries WHERE report_date = '2026-05-04'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(today_rows, 0);
+    }
+
+    #[test]
+    fn daily_summary_counts_after_midnight_orders_on_the_business_day() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        let athens = chrono_tz::Europe::Athens;
+        // 23:50 and 00:10 local belong to the 28th; 07:00 EEST (after the
+        // 29th's spring-forward) opens the next business day.
+        for (id, created_at) in [
+            ("ord-2350", "2026-03-28T21:50:00Z"),
+            ("ord-0010", "2026-03-28T22:10:00Z"),
+            ("ord-0700", "2026-03-29T04:00:00Z"),
+        ] {
+            insert_summary_order(
+                &conn,
+                id,
+                "completed",
+                "takeaway",
+                created_at,
+                1000,
+                r#"[{"menu_item_id": "m1", "name": "Burger", "quantity": 1, "total_price": 10.0}]"#,
+            );
+        }
+
+        let (day, _) =
+            load_or_compute_daily_summary(&conn, "branch-A", "2026-03-28", "2026-03-30", &athens)
+                .unwrap();
+        assert_eq!(day["orderCount"], 2);
+        assert_eq!(day["hourly"][23]["orders"], 1);
+        assert_eq!(day["hourly"][0]["orders"], 1);
+
+        let (next, _) =
+            load_or_compute_daily_summary(&conn, "branch-A", "2026-03-29", "2026-03-30", &athens)
+                .unwrap();
+        assert_eq!(next["orderCount"], 1);
+        assert_eq!(next["hourly"][7]["orders"], 1);
+    }
+
+    #[test]
+    fn hourly_heatmap_buckets_in_local_time_and_compares_previous_week() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        // 22:30Z on Sunday 2026-05-03 is 01:30 on Monday in UTC+3.
+        insert_summary_order(
+            &conn,
+            "cur-1",
+            "completed",
+            "delivery",
+            "2026-05-03T22:30:00Z",
+            1200,
+            "[]",
+        );
+        insert_summary_order(
+            &conn,
+            "cur-2",
+            "completed",
+            "delivery",
+            "2026-05-03T22:45:00Z",
+            800,
+            "[]",
+        );
+        insert_summary_order(
+            &conn,
+            "cur-3",
+            "declined",
+            "delivery",
+            "2026-05-03T22:50:00Z",
+            900,
+            "[]",
+        );
+        insert_summary_order(
+            &conn,
+            "cur-4",
+            "canceled",
+            "delivery",
+            "2026-05-03T22:55:00Z",
+            900,
+            "[]",
+        );
+        // Previous Monday, same local hour.
+        insert_summary_order(
+            &conn,
+            "prev-1",
+            "completed",
+            "delivery",
+            "2026-04-26T22:10:00Z",
+            1000,
+            "[]",
+        );
+        // Outside the local range (Sunday 2026-05-03 local).
+        insert_summary_order(
+            &conn,
+            "out-1",
+            "completed",
+            "delivery",
+            "2026-05-03T20:00:00Z",
+            500,
+            "[]",
+        );
+
+        let tz = chrono::FixedOffset::east_opt(3 * 3600).unwrap();
+        let monday = chrono::NaiveDate::from_ymd_opt(2026, 5, 4).unwrap();
+        let report = build_hourly_heatmap_report(&conn, "branch-A", monday, monday, &tz).unwrap();
+
+        assert_eq!(report["current"]["orders"][0][1], 2);
+        assert_eq!(report["current"]["revenue"][0][1], 20.0);
+        assert_eq!(
+            report["current"]["totalOrders"], 2,
+            "declined/cancelled excluded"
+        );
+        assert_eq!(report["previous"]["dateFrom"], "2026-04-27");
+        assert_eq!(report["previous"]["orders"][0][1], 1);
+        assert_eq!(report["comparison"]["ordersDeltaPct"], 100.0);
+        assert_eq!(report["comparison"]["revenueDeltaPct"], 100.0);
+        assert_eq!(report["comparison"]["orders"][0][1], 100.0);
+        assert!(report["comparison"]["orders"][0][2].is_null());
+
+        let payload = parse_report_hourly_heatmap_payload(Some(serde_json::json!({
+            "branchId": "branch-A",
+            "dateFrom": "2026-05-10",
+            "dateTo": "2026-05-04"
+        })));
+        assert!(resolve_heatmap_range(&payload, monday).is_err());
+        let (from, to) =
+            resolve_heatmap_range(&parse_report_hourly_heatmap_payload(None), monday).unwrap();
+        assert_eq!((to - from).num_days(), 6);
+    }
+
+    #[test]
+    fn staff_performance_attributes_sales_adjustments_and_tips() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        for (id, staff, status, total_cents, discount_cents, cancelled_by) in [
+            ("ord-a1", Some("staff-a"), "completed", 900, 100, None),
+            ("ord-a2", Some("staff-a"), "completed", 2100, 0, None),
+            ("ord-b1", Some("staff-b"), "completed", 1000, 0, None),
+            ("ord-x", None, "completed", 500, 0, None),
+            (
+                "ord-c",
+                Some("staff-a"),
+                "cancelled",
+                700,
+                0,
+                Some("staff-b"),
+            ),
+        ] {
+            conn.execute(
+                "INSERT INTO orders (id, order_number, items, total_amount, total_amount_cents,
+                                     discount_amount, discount_amount_cents, status, staff_id,
+                                     cancelled_by, branch_id, created_at, updated_at)
+                 VALUES (?1, ?1, '[]', ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'branch-A',
+                         '2026-05-03T12:00:00Z', '2026-05-03T12:00:00Z')",
+                params![
+                    id,
+                    total_cents as f64 / 100.0,
+                    total_cents,
+                    discount_cents as f64 / 100.0,
+                    discount_cents,
+                    status,
+                    staff,
+                    cancelled_by
+                ],
+            )
+            .unwrap();
+        }
+        conn.execute_batch(
+            "INSERT INTO order_payments (id, order_id, method, amount, amount_cents, status,
+                                         staff_id, tip_amount, tip_amount_cents,
+                                         tip_recipient_staff_id, created_at, updated_at)
+             VALUES ('pay-a1', 'ord-a1', 'card', 10.0, 1000, 'completed', 'staff-a', 1.0, 100,
+                     'staff-b', '2026-05-03T12:01:00Z', '2026-05-03T12:01:00Z');
+             INSERT INTO payment_adjustments (id, payment_id, order_id, adjustment_type, amount,
+                                              amount_cents, reason, reason_code, staff_id,
+                                              created_at, updated_at)
+             VALUES ('adj-1', 'pay-a1', 'ord-a1', 'refund', 2.0, 200, 'cold', 'quality_issue',
+                     'staff-a', '2026-05-03T13:00:00Z', '2026-05-03T13:00:00Z');
+             INSERT INTO staff_shifts (id, staff_id, staff_name, role_type, check_in_time,
+                                       status, created_at, updated_at)
+             VALUES ('shift-a', 'staff-a', 'Anna', 'cashier', '2026-05-03T08:00:00Z',
+                     'closed', '2026-05-03T08:00:00Z', '2026-05-03T08:00:00Z');",
+        )
+        .unwrap();
+
+        let staff =
+            accumulate_staff_performance(&conn, "branch-A", "2026-05-03", "2026-05-03").unwrap();
+        let ids: Vec<Option<&str>> = staff.iter().map(|row| row.staff_id.as_deref()).collect();
+        assert_eq!(ids, vec![Some("staff-a"), Some("staff-b"), None]);
+        let a = &staff[0];
+        assert_eq!(
+            (a.order_count, a.net_sales_cents, a.discount_cents),
+            (2, 3000, 100)
+        );
+        assert_eq!((a.refund_count, a.refund_cents, a.tips_cents), (1, 200, 0));
+        let b = &staff[1];
+        assert_eq!(
+            (b.order_count, b.cancelled_orders, b.tips_cents),
+            (1, 1, 100)
+        );
+        assert_eq!(staff[2].net_sales_cents, 500);
+
+        let names = staff_display_names(&conn, "branch-A");
+        let report = staff_performance_to_json(&staff, &names, "2026-05-03", "2026-05-03");
+        let rows = report["staff"].as_array().unwrap();
+        assert_eq!(rows[0]["staffName"], "Anna");
+        assert_eq!(rows[0]["averageTicket"], 15.0);
+        assert_eq!(rows[0]["discountPercent"], 3.23);
+        assert_eq!(
+            rows[0]["adjustmentsByReason"],
+            serde_json::json!([
+                { "type": "refund", "reasonCode": "quality_issue", "count": 1, "total": 2.0 }
+            ])
+        );
+        assert_eq!(
+            rows[1]["staffName"], "staff-b",
+            "raw id without a cached name"
+        );
+        assert_eq!(rows[2]["staffName"], "Unattributed");
+        assert_eq!(rows[2]["unattributed"], true);
+
+        let csv = staff_performance_to_csv(&report);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(STAFF_PERFORMANCE_CSV_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some("staff-a,Anna,2,31.0,30.0,15.0,1.0,3.23,0,0,0.0,1,2.0,0.0")
+        );
+        assert_eq!(
+            lines.nth(1),
+            Some(",Unattributed,1,5.0,5.0,5.0,0.0,0.0,0,0,0.0,0,0.0,0.0")
+        );
+    }
+
+    #[test]
+    fn report_exports_reduce_reports_to_tables() {
+        let nested = parse_reports_export_payload(Some(serde_json::json!({
+            "report": "Daily_Summary",
+            "format": "xlsx",
+            "params": { "date": "2026-05-03" },
+        })))
+        .unwrap();
+        assert_eq!(nested.report, "daily_summary");
+        assert_eq!(nested.format, ExportFormat::Xlsx);
+        assert_eq!(nested.params, serde_json::json!({ "date": "2026-05-03" }));
+        let flat = parse_reports_export_payload(Some(serde_json::json!({
+            "reportType": "labor",
+            "dateFrom": "2026-05-01",
+        })))
+        .unwrap();
+        assert_eq!(flat.format, ExportFormat::Csv);
+        assert_eq!(flat.params["dateFrom"], "2026-05-01");
+        assert!(
+            parse_reports_export_payload(Some(serde_json::json!({ "format": "csv" }))).is_err()
+        );
+
+        let summary = daily_summary_table(&serde_json::json!({
+            "orderCount": 3,
+            "grossSales": 40.0,
+            "netSales": 35.0,
+            "tax": 4.2,
+            "discounts": 5.0,
+            "averageTicket": 11.67,
+            "byOrderType": [{ "orderType": "pickup", "count": 3, "total": 35.0 }],
+            "byPaymentMethod": [{ "method": "cash", "count": 2, "total": 20.0 }],
+            "refunds": { "count": 1, "total": 2.0 },
+            "returns": { "count": 0, "total": 0.0 },
+            "voids": { "count": 0, "total": 0.0 },
+            "cancelled": { "count": 0, "total": 0.0 },
+        }));
+        assert_eq!(summary.rows.len(), 12);
+        assert_eq!(
+            summary.rows[0],
+            vec![
+                serde_json::json!("totals"),
+                serde_json::json!("orders"),
+                serde_json::json!(3),
+                Value::Null
+            ]
+        );
+        assert_eq!(summary.rows[6][1], "pickup");
+        assert_eq!(summary.rows[8][3], 2.0);
+
+        let platforms = platform_reconciliation_table(&serde_json::json!({
+            "platforms": [{ "plugin": "wolt", "orderCount": 2, "gross": 30.0 }],
+            "totals": { "plugin": "total", "orderCount": 2, "gross": 30.0 },
+        }));
+        assert_eq!(platforms.rows.len(), 2);
+        assert_eq!(platforms.rows[1][0], "total");
+
+        let staff = staff_performance_table(&serde_json::json!({
+            "staff": [{ "staffId": "staff-a", "staffName": "Anna", "orders": 2, "grossSales": 31.0 }],
+        }));
+        let csv = crate::reports_export::render_csv(&staff, &ExportOptions::default());
+        assert_eq!(
+            csv.lines().nth(1),
+            Some("staff-a,Anna,2,31.00,,,,,,,,,,"),
+            "the exporter pads money columns to two decimals"
+        );
+    }
 }
@@ -201,80 +201,95 @@ pub async fn admin_sync_terminal_config(
     db: tauri::State<'_, db::DbState>,
     app: tauri::AppHandle,
 ) -> Result<serde_json::Value, String> {
-    crate::hydrate_terminal_credentials_from_local_settings(&db);
-
-    let terminal_id = storage::get_credential("terminal_id")
-        .or_else(|| crate::read_local_setting(&db, "terminal", "terminal_id"))
-        .ok_or("Terminal not configured: missing terminal ID")?;
-
-    // Wave 1 C2: `terminal_id` comes from the OS keyring (or a local DB
-    // mirror) and is interpolated into an admin-API path. Any `/`, `..`,
-    // `?`, `#`, or control byte in that value would bypass
-    // `validate_admin_api_path`'s allowlist. Strict UUID validation is
-    // the only shape the onboarding pipeline ever writes, so we enforce
-    // it here before formatting the path.
-    let terminal_id = core_helpers::validate_terminal_id_path_safe(&terminal_id)?;
-    let path = format!("/api/pos/settings/{terminal_id}");
-    let resp = crate::admin_fetch(Some(&db), &path, "GET", None).await?;
-
-    let mut updated: Vec<String> = Vec::new();
-    if let Some(bid) = crate::extract_branch_id_from_terminal_settings_response(&resp) {
-        storage::set_credential("branch_id", &bid)?;
-        if let Ok(conn) = db.conn.lock() {
-            let _ = db::set_setting(&conn, "terminal", "branch_id", &bid);
+    let requested = None;
+    crate::correlation::command(requested, async {
+        crate::hydrate_terminal_credentials_from_local_settings(&db);
+
+        let terminal_id = storage::get_credential("terminal_id")
+            .or_else(|| crate::read_local_setting(&db, "terminal", "terminal_id"))
+            .ok_or("Terminal not configured: missing terminal ID")?;
+
+        // Wave 1 C2: `terminal_id` comes from the OS keyring (or a local DB
+        // mirror) and is interpolated into an admin-API path. Any `/`, `..`,
+        // `?`, `#`, or control byte in that value would bypass
+        // `validate_admin_api_path`'s allowlist. Strict UUID validation is
+        // the only shape the onboarding pipeline ever writes, so we enforce
+        // it here before formatting the path.
+        let terminal_id = core_helpers::validate_terminal_id_path_safe(&terminal_id)?;
+        let path = format!("/api/pos/settings/{terminal_id}");
+        let resp = crate::admin_fetch(Some(&db), &path, "GET", None).await?;
+
+        let mut updated: Vec<String> = Vec::new();
+        if let Some(bid) = crate::extract_branch_id_from_terminal_settings_response(&resp) {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            if let Err(e) = crate::branches::record_settings_response(&conn, &resp, &bid) {
+                tracing::warn!(error = %e, "Failed to refresh branch registry");
+            }
+            crate::branches::apply_assigned_branch(&conn, &bid)?;
+            updated.push("branch_id".into());
         }
-        updated.push("branch_id".into());
-    }
-    if let Some(oid) = crate::extract_org_id_from_terminal_settings_response(&resp) {
-        storage::set_credential("organization_id", &oid)?;
-        if let Ok(conn) = db.conn.lock() {
-            let _ = db::set_setting(&conn, "terminal", "organization_id", &oid);
+        let mut organization_change = None;
+        if let Some(oid) = crate::extract_org_id_from_terminal_settings_response(&resp) {
+            organization_change = crate::commands::organization_change::store_organization_id(
+                &db,
+                &oid,
+                "admin_sync_terminal_config",
+            )?;
+            if organization_change.is_none() {
+                updated.push("organization_id".into());
+            }
         }
-        updated.push("organization_id".into());
-    }
-    if let Some(ghost_enabled) =
-        crate::extract_ghost_mode_feature_from_terminal_settings_response(&resp)
-    {
-        let ghost_value = if ghost_enabled { "true" } else { "false" };
-        storage::set_credential("ghost_mode_feature_enabled", ghost_value)?;
-        if let Ok(conn) = db.conn.lock() {
-            let _ = db::set_setting(&conn, "terminal", "ghost_mode_feature_enabled", ghost_value);
+        if let Some(ghost_enabled) =
+            crate::extract_ghost_mode_feature_from_terminal_settings_response(&resp)
+        {
+            let ghost_value = if ghost_enabled { "true" } else { "false" };
+            storage::set_credential("ghost_mode_feature_enabled", ghost_value)?;
+            if let Ok(conn) = db.conn.lock() {
+                let _ =
+                    db::set_setting(&conn, "terminal", "ghost_mode_feature_enabled", ghost_value);
+            }
+            updated.push("ghost_mode_feature_enabled".into());
         }
-        updated.push("ghost_mode_feature_enabled".into());
-    }
-    if let Some(supa) = resp.get("supabase") {
-        if let Some(url) = supa.get("url").and_then(|v| v.as_str()) {
-            if !url.is_empty() {
-                storage::set_credential("supabase_url", url)?;
-                if let Ok(conn) = db.conn.lock() {
-                    let _ = db::set_setting(&conn, "terminal", "supabase_url", url);
+        if let Some(supa) = resp.get("supabase") {
+            if let Some(url) = supa.get("url").and_then(|v| v.as_str()) {
+                if !url.is_empty() {
+                    storage::set_credential("supabase_url", url)?;
+                    if let Ok(conn) = db.conn.lock() {
+                        let _ = db::set_setting(&conn, "terminal", "supabase_url", url);
+                    }
+                    updated.push("supabase_url".into());
                 }
-                updated.push("supabase_url".into());
             }
-        }
-        if let Some(key) = supa.get("anon_key").and_then(|v| v.as_str()) {
-            if !key.is_empty() {
-                storage::set_credential("supabase_anon_key", key)?;
-                updated.push("supabase_anon_key".into());
+            if let Some(key) = supa.get("anon_key").and_then(|v| v.as_str()) {
+                if !key.is_empty() {
+                    storage::set_credential("supabase_anon_key", key)?;
+                    updated.push("supabase_anon_key".into());
+                }
             }
         }
-    }
-    if let Ok(snapshot_updates) = crate::cache_terminal_settings_snapshot(&db, &resp) {
-        if !snapshot_updates.is_empty() {
-            updated.extend(snapshot_updates);
+        if let Ok(snapshot_updates) = crate::cache_terminal_settings_snapshot(&db, &resp) {
+            if !snapshot_updates.is_empty() {
+                updated.extend(snapshot_updates);
+            }
         }
-    }
-    tracing::info!("admin_sync_terminal_config: updated {:?}", updated);
-    crate::scrub_sensitive_local_settings(&db);
-    let _ = app.emit(
-        "terminal_config_updated",
-        serde_json::json!({ "updated": updated.clone() }),
-    );
-    let _ = app.emit(
-        "terminal_settings_updated",
-        serde_json::json!({ "updated": updated.clone() }),
-    );
-    Ok(serde_json::json!({ "success": true, "updated": updated }))
+        tracing::info!("admin_sync_terminal_config: updated {:?}", updated);
+        crate::scrub_sensitive_local_settings(&db);
+        let _ = app.emit(
+            "terminal_config_updated",
+            serde_json::json!({ "updated": updated.clone() }),
+        );
+        let _ = app.emit(
+            "terminal_settings_updated",
+            serde_json::json!({ "updated": updated.clone() }),
+        );
+        crate::commands::organization_change::announce_if_pending(&app, &db);
+        Ok(serde_json::json!({
+            "success": true,
+            "updated": updated,
+            "organizationChangePending": organization_change.is_some(),
+        }))
+    })
+    .await
 }
 
 #[tauri::command]
@@ -283,84 +298,189 @@ pub async fn api_fetch_from_admin(
     arg1: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
-    crate::hydrate_terminal_credentials_from_local_settings(&db);
-
-    let parsed = parse_admin_fetch_payload(arg0, arg1)?;
-    let path = parsed.path;
-    let opts = parsed.options;
-    let method = opts
-        .get("method")
-        .and_then(|v| v.as_str())
-        .unwrap_or("GET")
-        .trim()
-        .to_uppercase();
-    let body = opts.get("body").cloned();
-    let query = opts.get("query").or_else(|| opts.get("params"));
-    let final_path = if let Some(q) = query {
-        crate::build_admin_query(&path, Some(q))
-    } else {
-        path.clone()
-    };
+    let requested = crate::correlation::from_payload(arg0.as_ref());
+    crate::correlation::command(requested, async {
+        crate::hydrate_terminal_credentials_from_local_settings(&db);
+
+        let parsed = parse_admin_fetch_payload(arg0, arg1)?;
+        let path = parsed.path;
+        let opts = parsed.options;
+        let method = opts
+            .get("method")
+            .and_then(|v| v.as_str())
+            .unwrap_or("GET")
+            .trim()
+            .to_uppercase();
+        let body = opts.get("body").cloned();
+        let query = opts.get("query").or_else(|| opts.get("params"));
+        let final_path = if let Some(q) = query {
+            crate::build_admin_query(&path, Some(q))
+        } else {
+            path.clone()
+        };
+
+        if let Err(e) = crate::validate_admin_api_path(&final_path) {
+            return Ok(serde_json::json!({
+                "success": false,
+                "error": e
+            }));
+        }
+        if !matches!(method.as_str(), "GET" | "POST" | "PATCH" | "PUT" | "DELETE") {
+            return Ok(serde_json::json!({
+                "success": false,
+                "error": "Unsupported HTTP method"
+            }));
+        }
 
-    if let Err(e) = crate::validate_admin_api_path(&final_path) {
-        return Ok(serde_json::json!({
-            "success": false,
-            "error": e
-        }));
-    }
-    if !matches!(method.as_str(), "GET" | "POST" | "PATCH" | "PUT" | "DELETE") {
-        return Ok(serde_json::json!({
-            "success": false,
-            "error": "Unsupported HTTP method"
-        }));
-    }
+        let cacheable_get = is_cacheable_admin_get(&method, &final_path);
 
-    let cacheable_get = is_cacheable_admin_get(&method, &final_path);
+        match crate::admin_fetch(Some(&db), &final_path, &method, body).await {
+            Ok(v) => {
+                if cacheable_get {
+                    let _ = cache_admin_get_response(&db, &final_path, &v);
+                }
 
-    match crate::admin_fetch(Some(&db), &final_path, &method, body).await {
-        Ok(v) => {
-            if cacheable_get {
-                let _ = cache_admin_get_response(&db, &final_path, &v);
+                Ok(serde_json::json!({
+                    "success": true,
+                    "data": v,
+                    "status": 200,
+                    "meta": {
+                        "source": "remote"
+                    }
+                }))
             }
-
-            Ok(serde_json::json!({
-                "success": true,
-                "data": v,
-                "status": 200,
-                "meta": {
-                    "source": "remote"
-                }
-            }))
-        }
-        Err(e) => {
-            if cacheable_get {
-                if let Some((cached_data, cached_at)) =
-                    read_cached_admin_get_response(&db, &final_path)
-                {
-                    return Ok(serde_json::json!({
-                        "success": true,
-                        "data": cached_data,
-                        "status": 200,
-                        "meta": {
-                            "source": "cache",
-                            "cachedAt": cached_at,
-                            "offlineFallback": true,
-                            "path": final_path,
-                        }
-                    }));
+            Err(e) => {
+                if cacheable_get {
+                    if let Some((cached_data, cached_at)) =
+                        read_cached_admin_get_response(&db, &final_path)
+                    {
+                        return Ok(serde_json::json!({
+                            "success": true,
+                            "data": cached_data,
+                            "status": 200,
+                            "meta": {
+                                "source": "cache",
+                                "cachedAt": cached_at,
+                                "offlineFallback": true,
+                                "path": final_path,
+                            }
+                        }));
+                    }
                 }
-            }
 
-            Ok(serde_json::json!({
-                "success": false,
-                "error": if cacheable_get {
-                    format!("{e}. No cached local copy is available yet for offline use.")
-                } else {
-                    e
-                }
-            }))
+                Ok(serde_json::json!({
+                    "success": false,
+                    "error": if cacheable_get {
+                        format!("{e}. No cached local copy is available yet for offline use.")
+                    } else {
+                        e
+                    }
+                }))
+            }
         }
+    })
+    .await
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct AdminApiRequestPayload {
+    #[serde(alias = "apiPath", alias = "endpoint")]
+    path: String,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    body: Option<serde_json::Value>,
+    /// GET options, appended with `build_admin_query`.
+    #[serde(default, alias = "query", alias = "params")]
+    options: Option<serde_json::Value>,
+}
+
+fn parse_admin_api_request_payload(
+    arg0: Option<serde_json::Value>,
+) -> Result<AdminApiRequestPayload, String> {
+    let payload = arg0.ok_or("Missing admin API request payload")?;
+    let mut parsed: AdminApiRequestPayload =
+        serde_json::from_value(payload).map_err(|e| format!("Invalid admin API request: {e}"))?;
+    parsed.path = parsed.path.trim().to_string();
+    if parsed.path.is_empty() {
+        return Err("Missing API path".into());
     }
+    parsed.body = parsed.body.filter(|body| !body.is_null());
+    Ok(parsed)
+}
+
+/// Generic admin proxy for endpoints the backend has no dedicated command
+/// for. Calls are checked against `admin_proxy`'s allowlist, rate-limited per
+/// path and logged with path and status only.
+#[tauri::command]
+pub async fn admin_api_request(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let requested = crate::correlation::from_payload(arg0.as_ref());
+    crate::correlation::command(requested, async {
+        use crate::admin_proxy;
+
+        let request = parse_admin_api_request_payload(arg0)?;
+        let method = request
+            .method
+            .as_deref()
+            .unwrap_or("GET")
+            .trim()
+            .to_uppercase();
+        let path = if method == "GET" {
+            crate::build_admin_query(&request.path, request.options.as_ref())
+        } else {
+            request.path.clone()
+        };
+
+        let allowlist = {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            admin_proxy::effective_allowlist(&conn)
+        };
+        let checked =
+            admin_proxy::check_request(&allowlist, &path, &method, request.body.is_some())
+                .and_then(|()| admin_proxy::rate_limiter().check(&path, std::time::Instant::now()));
+        if let Err(rejection) = checked {
+            tracing::warn!(
+                path = %admin_proxy::path_only(&path),
+                method = %method,
+                error_code = rejection.error_code,
+                "Admin proxy request rejected"
+            );
+            return Ok(rejection.to_json());
+        }
+
+        let result = crate::admin_fetch(Some(&db), &path, &method, request.body).await;
+        let status = match &result {
+            Ok(_) => Some(200),
+            Err(e) => admin_proxy::status_from_error(e),
+        };
+        tracing::info!(
+            path = %admin_proxy::path_only(&path),
+            method = %method,
+            status = status.unwrap_or(0),
+            "Admin proxy request"
+        );
+        Ok(match result {
+            Ok(data) => serde_json::json!({ "success": true, "data": data, "status": 200 }),
+            Err(error) => {
+                let mut response = serde_json::json!({
+                    "success": false,
+                    "errorCode": if error.starts_with("circuit_open") {
+                        "circuit_open"
+                    } else {
+                        "admin_request_failed"
+                    },
+                    "error": error,
+                    "status": status,
+                });
+                crate::remediation::attach(&mut response, &error);
+                response
+            }
+        })
+    })
+    .await
 }
 
 #[tauri::command]
@@ -384,19 +504,24 @@ pub fn api_list_cached_paths(
 pub async fn sync_test_parent_connection(
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
-    crate::hydrate_terminal_credentials_from_local_settings(&db);
-    let admin_url = storage::get_credential("admin_dashboard_url")
-        .ok_or("Terminal not configured: missing admin URL")?;
-    let raw_api_key = Zeroizing::new(
-        storage::get_credential("pos_api_key").ok_or("Terminal not configured: missing API key")?,
-    );
-    let api_key = Zeroizing::new(
-        api::extract_api_key_from_connection_string(&raw_api_key)
-            .unwrap_or_else(|| (*raw_api_key).clone()),
-    );
-
-    let result = api::test_connectivity(&admin_url, &api_key).await;
-    serde_json::to_value(&result).map_err(|e| e.to_string())
+    let requested = None;
+    crate::correlation::command(requested, async {
+        crate::hydrate_terminal_credentials_from_local_settings(&db);
+        let admin_url = storage::get_credential("admin_dashboard_url")
+            .ok_or("Terminal not configured: missing admin URL")?;
+        let raw_api_key = Zeroizing::new(
+            storage::get_credential("pos_api_key")
+                .ok_or("Terminal not configured: missing API key")?,
+        );
+        let api_key = Zeroizing::new(
+            api::extract_api_key_from_connection_string(&raw_api_key)
+                .unwrap_or_else(|| (*raw_api_key).clone()),
+        );
+
+        let result = api::test_connectivity(&admin_url, &api_key).await;
+        serde_json::to_value(&result).map_err(|e| e.to_string())
+    })
+    .await
 }
 
 #[cfg(test)]
@@ -420,10 +545,7 @@ mod dto_tests {
             );",
         )
         .expect("create local_settings");
-        db::DbState {
-            conn: Mutex::new(conn),
-            db_path: PathBuf::from(":memory:"),
-        }
+        db::DbState::new(conn, PathBuf::from(":memory:"))
     }
 
     #[test]
@@ -523,6 +645,24 @@ mod dto_tests {
         assert!(err.contains("Missing API path"));
     }
 
+    #[test]
+    fn parse_admin_api_request_payload_reads_aliases() {
+        let parsed = parse_admin_api_request_payload(Some(serde_json::json!({
+            "endpoint": " /api/pos/coupons ",
+            "query": { "active": true },
+            "body": null,
+        })))
+        .expect("payload should parse");
+        assert_eq!(parsed.path, "/api/pos/coupons");
+        assert_eq!(parsed.method, None);
+        assert!(parsed.body.is_none());
+        assert_eq!(
+            crate::build_admin_query(&parsed.path, parsed.options.as_ref()),
+            "/api/pos/coupons?active=true"
+        );
+        assert!(parse_admin_api_request_payload(Some(serde_json::json!({ "path": " " }))).is_err());
+    }
+
     #[test]
     fn cacheable_admin_get_only_applies_to_pos_get_routes() {
         assert!(is_cacheable_admin_get("GET", "/api/pos/suppliers"));
@@ -1,6 +1,7 @@
 use serde_json::Value;
 use tauri::Emitter;
 
+use crate::error::PosError;
 use crate::{api, auth, core_helpers, db, storage};
 
 fn parse_permission_payload(arg0: Option<Value>) -> Option<String> {
@@ -74,7 +75,15 @@ pub async fn auth_login(
     arg0: Option<Value>,
     db: tauri::State<'_, db::DbState>,
     auth_state: tauri::State<'_, auth::AuthState>,
-) -> Result<Value, String> {
+) -> Result<Value, PosError> {
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        if crate::organization_change::pending(&conn).is_some() {
+            return Err(PosError::Conflict(
+                crate::organization_change::LOGIN_BLOCKED_ERROR.into(),
+            ));
+        }
+    }
     auth::login(arg0, &db, &auth_state)
 }
 
@@ -82,7 +91,7 @@ pub async fn auth_login(
 pub async fn auth_logout(
     auth_state: tauri::State<'_, auth::AuthState>,
     app: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<(), PosError> {
     auth::logout(&auth_state);
     let _ = app.emit("session_timeout", serde_json::json!({ "reason": "logout" }));
     Ok(())
@@ -103,13 +112,13 @@ pub async fn auth_logout(
 // at-rest protection.
 
 #[tauri::command]
-pub async fn auth_secure_session_get() -> Result<Option<String>, String> {
+pub async fn auth_secure_session_get() -> Result<Option<String>, PosError> {
     Ok(storage::session_get())
 }
 
 #[tauri::command]
-pub async fn auth_secure_session_set(arg0: Option<Value>) -> Result<(), String> {
-    let payload = arg0.ok_or_else(|| "Missing session payload".to_string())?;
+pub async fn auth_secure_session_set(arg0: Option<Value>) -> Result<(), PosError> {
+    let payload = arg0.ok_or_else(|| PosError::validation("payload", "Missing session payload"))?;
     let raw = match payload {
         Value::String(s) => s,
         // Allow callers that forgot to stringify — serialise on the Rust
@@ -118,25 +127,25 @@ pub async fn auth_secure_session_set(arg0: Option<Value>) -> Result<(), String>
         other => serde_json::to_string(&other)
             .map_err(|e| format!("session payload serialisation failed: {e}"))?,
     };
-    storage::session_set(&raw)
+    Ok(storage::session_set(&raw)?)
 }
 
 #[tauri::command]
-pub async fn auth_secure_session_clear() -> Result<(), String> {
-    storage::session_clear()
+pub async fn auth_secure_session_clear() -> Result<(), PosError> {
+    Ok(storage::session_clear()?)
 }
 
 #[tauri::command]
 pub async fn auth_get_current_session(
     auth_state: tauri::State<'_, auth::AuthState>,
-) -> Result<Value, String> {
+) -> Result<Value, PosError> {
     Ok(auth::get_session_json(&auth_state))
 }
 
 #[tauri::command]
 pub async fn auth_validate_session(
     auth_state: tauri::State<'_, auth::AuthState>,
-) -> Result<Value, String> {
+) -> Result<Value, PosError> {
     Ok(auth::validate_session(&auth_state))
 }
 
@@ -144,7 +153,7 @@ pub async fn auth_validate_session(
 pub async fn auth_has_permission(
     arg0: Option<Value>,
     auth_state: tauri::State<'_, auth::AuthState>,
-) -> Result<bool, String> {
+) -> Result<bool, PosError> {
     let permission = parse_permission_payload(arg0);
     Ok(auth::has_permission(&auth_state, permission.as_deref()))
 }
@@ -152,10 +161,29 @@ pub async fn auth_has_permission(
 #[tauri::command]
 pub async fn auth_get_session_stats(
     auth_state: tauri::State<'_, auth::AuthState>,
-) -> Result<Value, String> {
+) -> Result<Value, PosError> {
     Ok(auth::get_session_stats(&auth_state))
 }
 
+#[tauri::command]
+pub async fn auth_lock_terminal(
+    auth_state: tauri::State<'_, auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<Value, PosError> {
+    let locked = auth::lock_terminal(&auth_state);
+    let _ = app.emit("terminal_locked", &locked);
+    Ok(locked)
+}
+
+#[tauri::command]
+pub async fn auth_switch_user(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<Value, PosError> {
+    auth::switch_user(arg0, &db, &auth_state)
+}
+
 #[tauri::command]
 pub async fn auth_confirm_privileged_action(
     arg0: Option<Value>,
@@ -170,7 +198,7 @@ pub async fn auth_setup_pin(
     arg0: Option<Value>,
     db: tauri::State<'_, db::DbState>,
     auth_state: tauri::State<'_, auth::AuthState>,
-) -> Result<Value, String> {
+) -> Result<Value, PosError> {
     // Security hardening: once an admin PIN is set, require an active admin
     // session before allowing PIN reset/overwrite — UNLESS the admin has
     // remotely triggered a PIN reset (pin_reset_required flag). In that case
@@ -192,7 +220,9 @@ pub async fn auth_setup_pin(
             .and_then(|v| v.as_str())
             .unwrap_or_default();
         if role_name != "admin" {
-            return Err("Unauthorized: active admin session required to change PIN".into());
+            return Err(PosError::Unauthorized(
+                "Unauthorized: active admin session required to change PIN".into(),
+            ));
         }
     }
     let result = auth::setup_pin(arg0, &db)?;
@@ -238,7 +268,7 @@ pub async fn staff_auth_authenticate_pin(
     arg0: Option<Value>,
     db: tauri::State<'_, db::DbState>,
     auth_state: tauri::State<'_, auth::AuthState>,
-) -> Result<Value, String> {
+) -> Result<Value, PosError> {
     // staff_auth:authenticate-pin uses the same login logic
     auth::login(arg0, &db, &auth_state)
 }
@@ -247,8 +277,8 @@ pub async fn staff_auth_authenticate_pin(
 pub async fn staff_auth_verify_check_in_pin(
     arg0: Option<Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<Value, String> {
-    auth::verify_staff_check_in_pin(arg0, &db)
+) -> Result<Value, PosError> {
+    Ok(auth::verify_staff_check_in_pin(arg0, &db)?)
 }
 
 /// staff-auth:refresh-directory — fetch the staff directory (with
@@ -259,27 +289,27 @@ pub async fn staff_auth_verify_check_in_pin(
 pub async fn staff_auth_refresh_directory(
     arg0: Option<Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<Value, String> {
+) -> Result<Value, PosError> {
     let branch_override = arg0
         .as_ref()
         .and_then(|v| v.get("branchId").or_else(|| v.get("branch_id")))
         .and_then(Value::as_str)
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty());
-    auth::refresh_staff_auth_directory(&db, branch_override.as_deref()).await
+    Ok(auth::refresh_staff_auth_directory(&db, branch_override.as_deref()).await?)
 }
 
 #[tauri::command]
 pub async fn staff_auth_get_session(
     auth_state: tauri::State<'_, auth::AuthState>,
-) -> Result<Value, String> {
+) -> Result<Value, PosError> {
     Ok(auth::get_session_json(&auth_state))
 }
 
 #[tauri::command]
 pub async fn staff_auth_get_current(
     auth_state: tauri::State<'_, auth::AuthState>,
-) -> Result<Value, String> {
+) -> Result<Value, PosError> {
     Ok(auth::get_current_user(&auth_state))
 }
 
@@ -287,7 +317,7 @@ pub async fn staff_auth_get_current(
 pub async fn staff_auth_has_permission(
     arg0: Option<Value>,
     auth_state: tauri::State<'_, auth::AuthState>,
-) -> Result<bool, String> {
+) -> Result<bool, PosError> {
     let permission = parse_permission_payload(arg0);
     Ok(auth::has_permission(&auth_state, permission.as_deref()))
 }
@@ -296,7 +326,7 @@ pub async fn staff_auth_has_permission(
 pub async fn staff_auth_has_any_permission(
     arg0: Option<Value>,
     auth_state: tauri::State<'_, auth::AuthState>,
-) -> Result<bool, String> {
+) -> Result<bool, PosError> {
     let permissions = parse_permissions_payload(arg0);
     let permissions_ref = if permissions.is_empty() {
         None
@@ -309,7 +339,7 @@ pub async fn staff_auth_has_any_permission(
 #[tauri::command]
 pub async fn staff_auth_logout(
     auth_state: tauri::State<'_, auth::AuthState>,
-) -> Result<(), String> {
+) -> Result<(), PosError> {
     auth::logout(&auth_state);
     Ok(())
 }
@@ -317,14 +347,14 @@ pub async fn staff_auth_logout(
 #[tauri::command]
 pub async fn staff_auth_validate_session(
     auth_state: tauri::State<'_, auth::AuthState>,
-) -> Result<Value, String> {
+) -> Result<Value, PosError> {
     Ok(auth::validate_session(&auth_state))
 }
 
 #[tauri::command]
 pub async fn staff_auth_track_activity(
     auth_state: tauri::State<'_, auth::AuthState>,
-) -> Result<(), String> {
+) -> Result<(), PosError> {
     auth::track_activity(&auth_state);
     Ok(())
 }
@@ -0,0 +1,96 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::auto_accept::{AutoAcceptConfig, AutoAcceptRule};
+use crate::{auth, db};
+
+/// Fields left out keep their stored value, so the kill switch can be
+/// flipped with `{ enabled: false }` alone.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AutoAcceptSetPayload {
+    #[serde(default)]
+    enabled: Option<bool>,
+    #[serde(default)]
+    rules: Option<Vec<AutoAcceptRule>>,
+    #[serde(default, alias = "base_minutes")]
+    base_minutes: Option<i64>,
+    #[serde(default, alias = "per_item_minutes")]
+    per_item_minutes: Option<i64>,
+}
+
+fn parse_set_payload(arg0: Option<Value>) -> Result<AutoAcceptSetPayload, String> {
+    let payload = arg0.ok_or("Missing auto-accept payload")?;
+    serde_json::from_value(payload).map_err(|e| format!("Invalid auto-accept payload: {e}"))
+}
+
+fn apply_payload(mut config: AutoAcceptConfig, payload: AutoAcceptSetPayload) -> AutoAcceptConfig {
+    if let Some(enabled) = payload.enabled {
+        config.enabled = enabled;
+    }
+    if let Some(rules) = payload.rules {
+        config.rules = rules;
+    }
+    if let Some(base_minutes) = payload.base_minutes {
+        config.base_minutes = base_minutes;
+    }
+    if let Some(per_item_minutes) = payload.per_item_minutes {
+        config.per_item_minutes = per_item_minutes;
+    }
+    config
+}
+
+#[tauri::command]
+pub async fn autoaccept_get_rules(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(json!({
+        "success": true,
+        "config": AutoAcceptConfig::load(&conn).to_json(),
+    }))
+}
+
+#[tauri::command]
+pub async fn autoaccept_set_rules(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<Value, auth::GuardedCommandError> {
+    auth::authorize_privileged_action(
+        auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let payload = parse_set_payload(arg0)?;
+    let actor = auth::current_staff_id(&auth_state);
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let config = apply_payload(AutoAcceptConfig::load(&conn), payload).validate()?;
+    config.save(&conn, actor.as_deref())?;
+    Ok(json!({
+        "success": true,
+        "config": config.to_json(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_payload_only_overrides_given_fields() {
+        let stored = AutoAcceptConfig {
+            enabled: true,
+            rules: serde_json::from_value(json!([{ "id": "lunch" }])).unwrap(),
+            base_minutes: 20,
+            per_item_minutes: 1,
+        };
+        let config = apply_payload(
+            stored,
+            parse_set_payload(Some(json!({ "enabled": false }))).unwrap(),
+        );
+        assert!(!config.enabled);
+        assert_eq!(config.rules.len(), 1);
+        assert_eq!(config.base_minutes, 20);
+        assert!(parse_set_payload(None).is_err());
+        assert!(parse_set_payload(Some(json!({ "rules": [{ "enabled": true }] }))).is_err());
+    }
+}
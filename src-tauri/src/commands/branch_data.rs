@@ -93,7 +93,10 @@ fn trimmed(value: Option<String>) -> Option<String> {
         .filter(|value| !value.is_empty())
 }
 
-fn resolve_branch_id(db: &db::DbState, explicit: Option<String>) -> Result<String, String> {
+pub(crate) fn resolve_branch_id(
+    db: &db::DbState,
+    explicit: Option<String>,
+) -> Result<String, String> {
     trimmed(explicit)
         .or_else(|| storage::get_credential("branch_id"))
         .or_else(|| read_local_setting(db, "terminal", "branch_id"))
@@ -110,7 +113,7 @@ fn resolve_terminal_id(db: &db::DbState) -> Option<String> {
     )
 }
 
-fn resolve_organization_id(db: &db::DbState) -> String {
+pub(crate) fn resolve_organization_id(db: &db::DbState) -> String {
     storage::get_credential("organization_id")
         .or_else(|| read_local_setting(db, "terminal", "organization_id"))
         .unwrap_or_else(|| "pending-org".to_string())
@@ -206,6 +209,53 @@ fn update_tables_cached_payload(
     Err("Table not found in local cache".into())
 }
 
+/// Set a table's status in the local tables cache and queue the change for
+/// the admin. The caller owns the transaction.
+pub(crate) fn set_cached_table_status(
+    conn: &rusqlite::Connection,
+    branch_id: &str,
+    organization_id: &str,
+    table_id: &str,
+    status: &str,
+    now: &str,
+) -> Result<Value, String> {
+    let mut cached_tables = read_cache_entry(conn, branch_id, CACHE_KEY_TABLES, "all")?
+        .ok_or_else(|| {
+            "Local tables cache is missing. Connect once while online before updating tables offline."
+                .to_string()
+        })?;
+    let updated_table =
+        update_tables_cached_payload(&mut cached_tables.payload, table_id, status, now)?;
+    cache_payload(
+        conn,
+        branch_id,
+        CACHE_KEY_TABLES,
+        "all",
+        &cached_tables.payload,
+    )?;
+
+    crate::sync_queue::enqueue(
+        conn,
+        &crate::sync_queue::EnqueueInput {
+            table_name: "restaurant_tables".to_string(),
+            record_id: table_id.to_string(),
+            operation: "UPDATE".to_string(),
+            data: json!({
+                "status": status,
+                "updated_at": now,
+            })
+            .to_string(),
+            organization_id: organization_id.to_string(),
+            priority: Some(0),
+            module_type: Some("operations".to_string()),
+            conflict_strategy: Some("server-wins".to_string()),
+            version: Some(1),
+        },
+    )?;
+
+    Ok(updated_table)
+}
+
 fn cache_payload(
     conn: &rusqlite::Connection,
     branch_id: &str,
@@ -617,43 +667,14 @@ pub async fn branch_data_update_table_status(
         conn.execute_batch("BEGIN IMMEDIATE")
             .map_err(|error| format!("begin table status update: {error}"))?;
 
-        let result = (|| -> Result<Value, String> {
-            let mut cached_tables = read_cache_entry(&conn, &branch_id, CACHE_KEY_TABLES, "all")?
-                .ok_or_else(|| {
-                    "Local tables cache is missing. Connect once while online before updating tables offline."
-                        .to_string()
-                })?;
-            let updated_table =
-                update_tables_cached_payload(&mut cached_tables.payload, &table_id, &status, &now)?;
-            cache_payload(
-                &conn,
-                &branch_id,
-                CACHE_KEY_TABLES,
-                "all",
-                &cached_tables.payload,
-            )?;
-
-            crate::sync_queue::enqueue(
-                &conn,
-                &crate::sync_queue::EnqueueInput {
-                    table_name: "restaurant_tables".to_string(),
-                    record_id: table_id.clone(),
-                    operation: "UPDATE".to_string(),
-                    data: json!({
-                        "status": status,
-                        "updated_at": now,
-                    })
-                    .to_string(),
-                    organization_id: organization_id.clone(),
-                    priority: Some(0),
-                    module_type: Some("operations".to_string()),
-                    conflict_strategy: Some("server-wins".to_string()),
-                    version: Some(1),
-                },
-            )?;
-
-            Ok(updated_table)
-        })();
+        let result = set_cached_table_status(
+            &conn,
+            &branch_id,
+            &organization_id,
+            &table_id,
+            &status,
+            &now,
+        );
 
         match result {
             Ok(updated_table) => {
@@ -8,8 +8,9 @@ use tracing::{info, warn};
 use crate::{
     callerid::{
         self,
+        modem_parser::ModemFrameParser,
         types::{
-            CallerIdConfig, CallerIdMode, CallerIdStatusReason, CallerIdTransport,
+            CallerIdConfig, CallerIdMode, CallerIdStatusReason, CallerIdTransport, ModemConfig,
             ResolvedCallerIdConfig,
         },
     },
@@ -22,6 +23,9 @@ const LEGACY_KEY_SIP_PASSWORD: &str = "sip_password";
 /// Settings category for caller ID config in local_settings table.
 const CALLERID_CATEGORY: &str = "callerid";
 
+/// Baud rates offered by USB caller ID modems.
+const MODEM_BAUD_RATES: [u32; 8] = [1200, 2400, 4800, 9600, 19200, 38400, 57600, 115200];
+
 fn parse_mode(value: Option<&str>, default: CallerIdMode) -> CallerIdMode {
     match value.unwrap_or_default().trim() {
         "pbx_ip_trust_legacy" => CallerIdMode::PbxIpTrustLegacy,
@@ -305,6 +309,151 @@ fn merge_config_from_payload(base: &CallerIdConfig, payload: &Value) -> CallerId
     })
 }
 
+fn load_modem_config(db_state: &db::DbState) -> ModemConfig {
+    let conn = match db_state.conn.lock() {
+        Ok(c) => c,
+        Err(_) => return ModemConfig::default(),
+    };
+
+    let get = |key: &str| -> Option<String> {
+        db::get_setting(&conn, CALLERID_CATEGORY, key).filter(|v| !v.is_empty())
+    };
+
+    let defaults = ModemConfig::default();
+    ModemConfig {
+        enabled: get("modem_enabled")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false),
+        port: get("modem_port").unwrap_or_default(),
+        baud_rate: get("modem_baud_rate")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.baud_rate),
+        init_command: get("modem_init_command").unwrap_or(defaults.init_command),
+    }
+}
+
+fn save_modem_config(db_state: &db::DbState, config: &ModemConfig) -> Result<(), String> {
+    let conn = db_state.conn.lock().map_err(|e| e.to_string())?;
+
+    db::set_setting(
+        &conn,
+        CALLERID_CATEGORY,
+        "modem_enabled",
+        if config.enabled { "true" } else { "false" },
+    )?;
+    db::set_setting(&conn, CALLERID_CATEGORY, "modem_port", &config.port)?;
+    db::set_setting(
+        &conn,
+        CALLERID_CATEGORY,
+        "modem_baud_rate",
+        &config.baud_rate.to_string(),
+    )?;
+    db::set_setting(
+        &conn,
+        CALLERID_CATEGORY,
+        "modem_init_command",
+        &config.init_command,
+    )?;
+
+    Ok(())
+}
+
+fn merge_modem_config_from_payload(base: &ModemConfig, payload: &Value) -> ModemConfig {
+    let baud_rate = ["baudRate", "baud_rate"]
+        .iter()
+        .find_map(|key| payload.get(*key).and_then(Value::as_u64))
+        .and_then(|value| u32::try_from(value).ok())
+        .unwrap_or(base.baud_rate);
+
+    ModemConfig {
+        enabled: parse_bool(payload, &["enabled"], base.enabled),
+        port: value_str(payload, &["port", "modemPort", "modem_port"])
+            .map(|value| value.trim().to_string())
+            .unwrap_or_else(|| base.port.clone()),
+        baud_rate,
+        init_command: payload
+            .get("initCommand")
+            .or_else(|| payload.get("init_command"))
+            .and_then(Value::as_str)
+            .map(|value| value.trim().to_string())
+            .unwrap_or_else(|| base.init_command.clone()),
+    }
+}
+
+fn validate_modem_config(config: &ModemConfig) -> Result<(), String> {
+    if config.enabled && config.port.is_empty() {
+        return Err("A serial port is required to enable the caller ID modem".into());
+    }
+    if !MODEM_BAUD_RATES.contains(&config.baud_rate) {
+        return Err(format!(
+            "Unsupported modem baud rate {}; expected one of {MODEM_BAUD_RATES:?}",
+            config.baud_rate
+        ));
+    }
+    if config.init_command.len() > 64
+        || !config
+            .init_command
+            .chars()
+            .all(|c| c.is_ascii_graphic() || c == ' ')
+    {
+        return Err("The modem init command must be a single line of at most 64 characters".into());
+    }
+    Ok(())
+}
+
+/// (Re)start the modem listener for `config`, or stop it when disabled.
+fn apply_modem_config(
+    app: &tauri::AppHandle,
+    mgr: &Arc<callerid::CallerIdManager>,
+    cancel_token: &tokio_util::sync::CancellationToken,
+    config: &ModemConfig,
+) {
+    mgr.stop_modem();
+    if !config.enabled {
+        return;
+    }
+
+    callerid::modem_listener::start_modem_listener(
+        config.clone(),
+        Arc::clone(mgr),
+        app.clone(),
+        cancel_token.child_token(),
+    );
+    info!(port = %config.port, baud = config.baud_rate, "Caller ID modem listener started");
+}
+
+/// Build a frame from the simulate payload: `frame` (text as the modem
+/// would send it), `frameHex` (raw bytes), or `number` and `name`.
+fn simulated_frame(payload: &Value) -> Result<Vec<u8>, String> {
+    if let Some(frame) = value_str(payload, &["frame"]) {
+        return Ok(frame.into_bytes());
+    }
+    if let Some(hex) = value_str(payload, &["frameHex", "frame_hex"]) {
+        let hex: String = hex.chars().filter(|c| !c.is_whitespace()).collect();
+        if hex.len() % 2 != 0 {
+            return Err("frameHex must have an even number of hex digits".into());
+        }
+        return (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(|| format!("Invalid hex in frameHex at offset {i}"))
+            })
+            .collect();
+    }
+
+    let number = value_str(payload, &["number", "callerNumber", "phone"])
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .ok_or("Missing number or frame")?;
+    let mut frame = format!("NMBR = {number}\r\n");
+    if let Some(name) = value_str(payload, &["name", "callerName"]) {
+        frame.push_str(&format!("NAME = {}\r\n", name.trim()));
+    }
+    Ok(frame.into_bytes())
+}
+
 fn resolve_runtime_config(
     db_state: &db::DbState,
     payload: Option<&Value>,
@@ -408,6 +557,11 @@ pub fn autostart_if_enabled(
     mgr: &Arc<callerid::CallerIdManager>,
     cancel_token: &tokio_util::sync::CancellationToken,
 ) {
+    let modem = load_modem_config(db_state);
+    if modem.enabled {
+        apply_modem_config(app, mgr, cancel_token, &modem);
+    }
+
     let resolved = match resolve_runtime_config(db_state, None) {
         Ok(config) => config,
         Err(error) => {
@@ -505,6 +659,52 @@ pub async fn callerid_get_config(db: tauri::State<'_, db::DbState>) -> Result<Va
     }))
 }
 
+/// Configure the serial caller ID modem and restart its listener.
+#[tauri::command]
+pub async fn callerid_configure(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, db::DbState>,
+    mgr: tauri::State<'_, Arc<callerid::CallerIdManager>>,
+    cancel_token: tauri::State<'_, tokio_util::sync::CancellationToken>,
+    arg0: Option<Value>,
+    arg1: Option<Value>,
+) -> Result<Value, String> {
+    let payload = crate::parse_channel_payload(arg0, arg1);
+    let config = merge_modem_config_from_payload(&load_modem_config(&db), &payload);
+    validate_modem_config(&config)?;
+    save_modem_config(&db, &config)?;
+    apply_modem_config(&app, mgr.inner(), &cancel_token, &config);
+
+    info!(enabled = config.enabled, port = %config.port, "Caller ID modem config saved");
+    Ok(serde_json::json!({
+        "success": true,
+        "config": config,
+        "status": mgr.get_status().modem,
+    }))
+}
+
+/// Run a call through the modem parser and lookup without hardware and
+/// emit `incoming_call` exactly as a real ring would.
+#[tauri::command]
+pub async fn callerid_simulate_call(
+    app: tauri::AppHandle,
+    mgr: tauri::State<'_, Arc<callerid::CallerIdManager>>,
+    arg0: Option<Value>,
+    arg1: Option<Value>,
+) -> Result<Value, String> {
+    let payload = crate::parse_channel_payload(arg0, arg1);
+    let frame = simulated_frame(&payload)?;
+    let mut parser = ModemFrameParser::new();
+    let mut calls = parser.push(&frame);
+    calls.extend(parser.flush_idle());
+    let call = calls
+        .into_iter()
+        .next()
+        .ok_or("No caller ID found in the simulated frame")?;
+
+    Ok(callerid::modem_listener::handle_modem_call(&app, mgr.inner(), call).await)
+}
+
 /// Test SIP connection — sends a REGISTER and waits for acceptance.
 #[tauri::command]
 pub async fn callerid_test_connection(
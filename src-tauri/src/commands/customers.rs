@@ -2,9 +2,13 @@ use chrono::Utc;
 use serde::Deserialize;
 use tauri::Emitter;
 
+use crate::auth;
+use crate::customer_import::{self, DuplicateStrategy, ImportOptions};
+use crate::customer_privacy;
+use crate::supabase::SupabaseQuery;
 use crate::{
-    db, fetch_supabase_rows, normalize_phone, payload_arg0_as_string, read_local_json_array,
-    read_local_setting, storage, sync_queue, value_i64, value_str, write_local_json,
+    db, normalize_phone, payload_arg0_as_string, read_local_json_array, read_local_setting,
+    storage, sync_queue, value_i64, value_str, write_local_json,
 };
 
 #[derive(Debug, Deserialize)]
@@ -70,6 +74,31 @@ struct CustomerResolveConflictPayload {
     data: serde_json::Value,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CustomerImportCsvPayload {
+    #[serde(default, alias = "filePath", alias = "file_path")]
+    path: Option<String>,
+    #[serde(default, alias = "text", alias = "content")]
+    csv: Option<String>,
+    #[serde(default)]
+    columns: std::collections::HashMap<String, String>,
+    #[serde(default, alias = "duplicate_strategy", alias = "onDuplicate")]
+    duplicate_strategy: Option<String>,
+    #[serde(default, alias = "dry_run")]
+    dry_run: bool,
+    #[serde(default)]
+    delimiter: Option<String>,
+    #[serde(default, alias = "branch_id")]
+    branch_id: Option<String>,
+}
+
+#[derive(Debug)]
+enum CustomerImportSource {
+    Path(String),
+    Text(String),
+}
+
 fn parse_lookup_payload(
     arg0: Option<serde_json::Value>,
     err_msg: &str,
@@ -352,6 +381,45 @@ fn parse_customer_resolve_conflict_payload(
     })
 }
 
+fn parse_customer_import_csv_payload(
+    arg0: Option<serde_json::Value>,
+) -> Result<(CustomerImportSource, ImportOptions), String> {
+    let payload: CustomerImportCsvPayload =
+        serde_json::from_value(arg0.unwrap_or(serde_json::json!({})))
+            .map_err(|e| format!("Invalid payload: {e}"))?;
+    let source = match (trim_to_option(payload.path), payload.csv) {
+        (Some(path), _) => CustomerImportSource::Path(path),
+        (None, Some(text)) if !text.trim().is_empty() => CustomerImportSource::Text(text),
+        _ => return Err("Missing path or csv".into()),
+    };
+    let strategy = match trim_to_option(payload.duplicate_strategy) {
+        Some(raw) => DuplicateStrategy::parse(&raw)
+            .ok_or_else(|| format!("Unknown duplicate strategy: {raw}"))?,
+        None => DuplicateStrategy::default(),
+    };
+    let delimiter = match payload.delimiter.as_deref() {
+        None | Some("") => None,
+        Some("\\t") | Some("tab") => Some('\t'),
+        Some(raw) => {
+            let mut chars = raw.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c != '"' => Some(c),
+                _ => return Err(format!("Invalid delimiter: {raw}")),
+            }
+        }
+    };
+    Ok((
+        source,
+        ImportOptions {
+            columns: payload.columns,
+            strategy,
+            dry_run: payload.dry_run,
+            delimiter,
+            branch_id: trim_to_option(payload.branch_id),
+        },
+    ))
+}
+
 fn trim_to_option(value: Option<String>) -> Option<String> {
     value.and_then(|raw| {
         let trimmed = raw.trim().to_string();
@@ -426,7 +494,7 @@ fn customer_body_field(
     })
 }
 
-fn build_remote_customer_create_body(source: &serde_json::Value) -> serde_json::Value {
+pub(crate) fn build_remote_customer_create_body(source: &serde_json::Value) -> serde_json::Value {
     let mut body = serde_json::Map::new();
 
     if let Some(name) = customer_body_field(source, &["name", "fullName"], &["name", "fullName"]) {
@@ -521,7 +589,7 @@ fn build_remote_customer_create_body(source: &serde_json::Value) -> serde_json::
     serde_json::Value::Object(body)
 }
 
-fn build_remote_customer_update_body(source: &serde_json::Value) -> serde_json::Value {
+pub(crate) fn build_remote_customer_update_body(source: &serde_json::Value) -> serde_json::Value {
     let mut body = serde_json::Map::new();
 
     if let Some(name) = string_field(source, &["name", "fullName"]) {
@@ -816,7 +884,7 @@ fn enqueue_customer_sync_item(
     )
 }
 
-fn build_local_customer_from_source(source: &serde_json::Value) -> serde_json::Value {
+pub(crate) fn build_local_customer_from_source(source: &serde_json::Value) -> serde_json::Value {
     let body = build_remote_customer_create_body(source);
     let customer_id = value_str(source, &["id", "customerId"])
         .unwrap_or_else(|| format!("cust-{}", uuid::Uuid::new_v4()));
@@ -1135,35 +1203,17 @@ fn resolved_customer_sync_org_id(db: &db::DbState) -> Option<String> {
         .filter(|value| !value.is_empty())
 }
 
-async fn fetch_supabase_table_pages(
-    table: &str,
-    params: &[(&str, String)],
-    page_size: usize,
-) -> Result<Vec<serde_json::Value>, String> {
-    let mut rows = Vec::new();
-    let mut offset = 0usize;
-
-    loop {
-        let mut page_params = params.to_vec();
-        page_params.push(("limit", page_size.to_string()));
-        page_params.push(("offset", offset.to_string()));
-
-        let response = fetch_supabase_rows(table, &page_params).await?;
-        let page = response.as_array().cloned().unwrap_or_default();
-        let page_len = page.len();
-        rows.extend(page);
+const CUSTOMER_SYNC_PAGE_SIZE: usize = 1_000;
+/// The fallback sync used to stop at a million rows; keep that ceiling.
+const CUSTOMER_SYNC_MAX_ROWS: usize = 1_000_000;
 
-        if page_len < page_size {
-            break;
-        }
-
-        offset += page_size;
-        if offset > 1_000_000 {
-            return Err(format!("{table} pagination exceeded safety limit"));
-        }
-    }
-
-    Ok(rows)
+fn customer_sync_query(table: &str, org_id: &str) -> SupabaseQuery {
+    SupabaseQuery::new(table)
+        .select("*")
+        .filter("organization_id", format!("eq.{org_id}"))
+        .order("updated_at.desc.nullslast")
+        .page_size(CUSTOMER_SYNC_PAGE_SIZE)
+        .max_rows(CUSTOMER_SYNC_MAX_ROWS)
 }
 
 async fn sync_customer_fetch_all_from_supabase(
@@ -1173,28 +1223,13 @@ async fn sync_customer_fetch_all_from_supabase(
 
     let org_id = resolved_customer_sync_org_id(db)
         .ok_or("Terminal not configured: missing organization_id")?;
-    let page_size = 1000usize;
-    let mut customers = fetch_supabase_table_pages(
-        "customers",
-        &[
-            ("select", "*".to_string()),
-            ("organization_id", format!("eq.{org_id}")),
-            ("order", "updated_at.desc.nullslast".to_string()),
-        ],
-        page_size,
-    )
-    .await?;
+    let mut customers = customer_sync_query("customers", &org_id)
+        .fetch_all()
+        .await?;
 
-    let addresses = fetch_supabase_table_pages(
-        "customer_addresses",
-        &[
-            ("select", "*".to_string()),
-            ("organization_id", format!("eq.{org_id}")),
-            ("order", "updated_at.desc.nullslast".to_string()),
-        ],
-        page_size,
-    )
-    .await
+    let addresses = customer_sync_query("customer_addresses", &org_id)
+        .fetch_all()
+        .await
     .unwrap_or_else(|error| {
         tracing::warn!(error = %error, "Unable to fetch customer addresses from Supabase fallback");
         Vec::new()
@@ -1501,10 +1536,19 @@ pub async fn customer_lookup_by_phone(
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
     let payload = parse_phone_payload(arg0)?;
-    let phone = payload.phone;
-    let phone_norm = normalize_phone(&phone);
-    let _ = sync_customer_privacy_tombstones(&db).await;
-    let cache = read_local_json_array(&db, "customer_cache_v1")?;
+    lookup_customer_by_phone(&db, &payload.phone).await
+}
+
+/// Customer for `phone`: the local cache first, then the admin API, then
+/// the most recent local order with a matching phone. `Null` when nothing
+/// matches. Shared by `customer_lookup_by_phone` and caller ID.
+pub(crate) async fn lookup_customer_by_phone(
+    db: &db::DbState,
+    phone: &str,
+) -> Result<serde_json::Value, String> {
+    let phone_norm = normalize_phone(phone);
+    let _ = sync_customer_privacy_tombstones(db).await;
+    let cache = read_local_json_array(db, "customer_cache_v1")?;
     if let Some(found) = cache.into_iter().find(|entry| {
         value_str(entry, &["phone", "customerPhone", "mobile", "telephone"])
             .map(|s| normalize_phone(&s))
@@ -1514,10 +1558,10 @@ pub async fn customer_lookup_by_phone(
         return Ok(found);
     }
 
-    if let Some(remote_customer) = sync_customer_fetch_remote_by_phone(&db, &phone).await? {
-        let mut cache = read_local_json_array(&db, "customer_cache_v1")?;
+    if let Some(remote_customer) = sync_customer_fetch_remote_by_phone(db, phone).await? {
+        let mut cache = read_local_json_array(db, "customer_cache_v1")?;
         let customer = upsert_customer_cache_entry(&mut cache, remote_customer);
-        write_local_json(&db, "customer_cache_v1", &serde_json::Value::Array(cache))?;
+        write_local_json(db, "customer_cache_v1", &serde_json::Value::Array(cache))?;
         return Ok(customer);
     }
 
@@ -2265,10 +2309,126 @@ pub async fn customer_resolve_conflict(
     Ok(serde_json::json!({ "success": false, "error": "Conflict not found" }))
 }
 
+/// Import customers from a CSV file (`path`) or pasted text (`csv`).
+/// Emits `customer_import_progress` after every batch and
+/// `customer_import_completed` with the summary; see [`customer_import`].
+#[tauri::command]
+pub async fn customer_import_csv(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let (source, options) = parse_customer_import_csv_payload(arg0)?;
+    let organization_id = resolve_customer_queue_organization_id(&db);
+    let progress_app = app.clone();
+    let summary = db
+        .run_blocking(move |db| {
+            let progress = |summary: &customer_import::ImportSummary| {
+                let _ = progress_app.emit(customer_import::PROGRESS_EVENT, summary.to_json());
+            };
+            match source {
+                CustomerImportSource::Path(path) => {
+                    let file = std::fs::File::open(&path)
+                        .map_err(|e| format!("Cannot open {path}: {e}"))?;
+                    customer_import::import_customers(
+                        db,
+                        std::io::BufReader::new(file),
+                        &options,
+                        &organization_id,
+                        progress,
+                    )
+                }
+                CustomerImportSource::Text(text) => customer_import::import_customers(
+                    db,
+                    std::io::Cursor::new(text),
+                    &options,
+                    &organization_id,
+                    progress,
+                ),
+            }
+        })
+        .await?;
+
+    let summary = summary.to_json();
+    let _ = app.emit(customer_import::COMPLETED_EVENT, summary.clone());
+    Ok(serde_json::json!({ "success": true, "data": summary }))
+}
+
+/// Write everything held about a customer (by id or phone) to a JSON file
+/// under `exports/`; see [`customer_privacy::export_customer_data`].
+#[tauri::command]
+pub async fn customer_export_data(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let target = customer_privacy::CustomerRef::parse(&arg0.unwrap_or(serde_json::Value::Null))?;
+    let export = db
+        .run_blocking(move |db| customer_privacy::export_customer_data(db, &target))
+        .await?;
+    Ok(serde_json::json!({ "success": true, "data": export }))
+}
+
+/// Anonymise a customer on this terminal and queue the erasure for the
+/// cloud. Refused while they have an open unpaid order; see
+/// [`customer_privacy::erase_customer`].
+#[tauri::command]
+pub async fn customer_erase(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, auth::GuardedCommandError> {
+    auth::authorize_privileged_action(
+        auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let target = customer_privacy::CustomerRef::parse(&arg0.unwrap_or(serde_json::Value::Null))?;
+    let organization_id = resolve_customer_queue_organization_id(&db);
+    let actor = auth::current_staff_id(&auth_state);
+    let summary = db
+        .run_blocking(move |db| {
+            customer_privacy::erase_customer(db, &target, &organization_id, actor.as_deref())
+        })
+        .await?;
+    let removed = summary["cacheEntriesRemoved"].as_u64().unwrap_or(0);
+    if removed > 0 {
+        let _ = app.emit(
+            "customer_deleted",
+            serde_json::json!({ "removed": removed }),
+        );
+    }
+    Ok(serde_json::json!({ "success": true, "data": summary }))
+}
+
 #[cfg(test)]
 mod dto_tests {
     use super::*;
 
+    #[test]
+    fn parse_customer_import_csv_payload_reads_source_and_options() {
+        let (source, options) = parse_customer_import_csv_payload(Some(serde_json::json!({
+            "filePath": " /tmp/customers.csv ",
+            "columns": { "phone": "Mobile No" },
+            "onDuplicate": "update",
+            "dryRun": true,
+            "delimiter": "tab"
+        })))
+        .expect("import payload should parse");
+        assert!(matches!(source, CustomerImportSource::Path(path) if path == "/tmp/customers.csv"));
+        assert_eq!(options.strategy, DuplicateStrategy::Update);
+        assert!(options.dry_run);
+        assert_eq!(options.delimiter, Some('\t'));
+        assert_eq!(options.columns["phone"], "Mobile No");
+
+        assert!(parse_customer_import_csv_payload(Some(serde_json::json!({}))).is_err());
+        assert!(parse_customer_import_csv_payload(Some(serde_json::json!({
+            "csv": "name,phone",
+            "duplicateStrategy": "ignore"
+        })))
+        .is_err());
+    }
+
     #[test]
     fn parse_phone_payload_supports_string_and_alias() {
         let from_string = parse_phone_payload(Some(serde_json::json!("2101234567")))
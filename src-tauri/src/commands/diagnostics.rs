@@ -5,8 +5,23 @@ use std::sync::Arc;
 use std::time::Duration;
 use tauri::Emitter;
 use tracing::{info, warn};
-
-use crate::{db, diagnostics, incident_reporting, sync};
+use zeroize::Zeroizing;
+
+use crate::{
+    api, db, diagnostics, heartbeat, incident_reporting, kiosk, logs, storage, support_session,
+    sync,
+};
+
+fn parse_log_lines_payload(arg0: Option<&Value>) -> usize {
+    arg0.and_then(|v| {
+        v.get("logLines")
+            .or_else(|| v.get("log_lines"))
+            .or_else(|| v.get("lines"))
+    })
+    .and_then(Value::as_u64)
+    .map(|n| (n as usize).clamp(1, 20_000))
+    .unwrap_or(diagnostics::SUPPORT_BUNDLE_LOG_LINES)
+}
 
 fn parse_diagnostics_export_payload(arg0: Option<Value>) -> diagnostics::DiagnosticsExportOptions {
     let mut options = diagnostics::DiagnosticsExportOptions::default();
@@ -283,6 +298,14 @@ pub async fn database_health_check(
     Ok(payload)
 }
 
+/// Applied and pending schema migrations. Read-only: migrations themselves
+/// only run at startup.
+#[tauri::command]
+pub async fn db_get_migration_status(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
+    let status = db.read(db::migration_status)?;
+    Ok(serde_json::json!({ "success": true, "data": status }))
+}
+
 #[tauri::command]
 pub async fn database_get_stats(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
@@ -630,6 +653,219 @@ pub async fn diagnostics_export(
     }))
 }
 
+async fn check_network_health() -> Value {
+    if let Some(cached) = diagnostics::health_cache_get("network") {
+        return cached;
+    }
+    let admin_url = storage::get_credential("admin_dashboard_url");
+    let api_key = storage::get_credential("pos_api_key").map(Zeroizing::new);
+    let entry = match (admin_url, api_key) {
+        (Some(admin_url), Some(api_key)) => {
+            let result = api::test_connectivity(&admin_url, api_key.as_str()).await;
+            let latency = result.latency_ms;
+            if !result.success {
+                diagnostics::health_entry(
+                    diagnostics::HealthStatus::Error,
+                    result
+                        .error
+                        .unwrap_or_else(|| "Admin dashboard unreachable".to_string()),
+                    serde_json::json!({ "reachable": false, "latencyMs": latency }),
+                )
+            } else if latency.unwrap_or(0) > 2_000 {
+                diagnostics::health_entry(
+                    diagnostics::HealthStatus::Warn,
+                    format!("Slow connection ({} ms)", latency.unwrap_or(0)),
+                    serde_json::json!({ "reachable": true, "latencyMs": latency }),
+                )
+            } else {
+                diagnostics::health_entry(
+                    diagnostics::HealthStatus::Ok,
+                    "Admin dashboard reachable",
+                    serde_json::json!({ "reachable": true, "latencyMs": latency }),
+                )
+            }
+        }
+        _ => diagnostics::health_entry(
+            diagnostics::HealthStatus::Warn,
+            "Admin connection not configured",
+            serde_json::json!({ "reachable": false, "latencyMs": null }),
+        ),
+    };
+    diagnostics::health_cache_put("network", entry.clone());
+    entry
+}
+
+/// Single aggregated health check for dashboard badges. Each subsystem
+/// carries `status: ok|warn|error` and a `detail` string; `status` at the
+/// top level is the worst of them. The integrity check and network probe
+/// are cached for `diagnostics::HEALTH_CHECK_CACHE_TTL`. `slowestCommands`
+/// lists the tracked commands with the highest p95 latency.
+#[tauri::command]
+pub async fn system_health_check(
+    db: tauri::State<'_, db::DbState>,
+    sync_state: tauri::State<'_, std::sync::Arc<sync::SyncState>>,
+) -> Result<Value, String> {
+    let last_sync = sync_state.last_sync.lock().ok().and_then(|g| g.clone());
+    let update_state = crate::read_update_state(&db).unwrap_or(Value::Null);
+
+    let database = diagnostics::check_database_health(&db);
+    let sync = diagnostics::check_sync_health(&db, last_sync);
+    let network = check_network_health().await;
+    let printing = diagnostics::check_printing_health(&db);
+    let credentials = diagnostics::check_credentials_health();
+    let updater = diagnostics::check_updater_health(&update_state);
+    let heartbeat_health = heartbeat::check_heartbeat_health(&db);
+    let kiosk_health = kiosk::check_kiosk_health(&db);
+
+    let overall = diagnostics::rollup_health_status([
+        &database,
+        &sync,
+        &network,
+        &printing,
+        &credentials,
+        &updater,
+        &heartbeat_health,
+        &kiosk_health,
+    ]);
+    Ok(serde_json::json!({
+        "status": overall.as_str(),
+        "checkedAt": Utc::now().to_rfc3339(),
+        "adminApiCircuit": api::admin_breaker_snapshot(),
+        "slowestCommands": diagnostics::metrics::slowest_commands(),
+        "subsystems": {
+            "database": database,
+            "sync": sync,
+            "network": network,
+            "printing": printing,
+            "credentials": credentials,
+            "updater": updater,
+            "heartbeat": heartbeat_health,
+            "kiosk": kiosk_health,
+        },
+    }))
+}
+
+/// Send a terminal heartbeat immediately (support tooling) and return the
+/// recorded result.
+#[tauri::command]
+pub async fn heartbeat_send_now(
+    db: tauri::State<'_, db::DbState>,
+    sync_state: tauri::State<'_, std::sync::Arc<sync::SyncState>>,
+) -> Result<Value, String> {
+    heartbeat::send(&db, sync_state.as_ref()).await
+}
+
+#[tauri::command]
+pub async fn diagnostics_export_bundle(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    use tauri::Manager;
+    let log_lines = parse_log_lines_payload(arg0.as_ref());
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("app data dir: {e}"))?;
+    let zip_path = diagnostics::export_support_bundle(&db, &data_dir, log_lines)?;
+    Ok(serde_json::json!({
+        "success": true,
+        "path": zip_path,
+        "logLines": log_lines,
+    }))
+}
+
+#[tauri::command]
+pub async fn diagnostics_get_recent_errors(arg0: Option<Value>) -> Result<Value, String> {
+    let limit = arg0
+        .as_ref()
+        .and_then(|v| v.get("limit").or(Some(v)))
+        .and_then(Value::as_u64)
+        .map(|n| n as usize)
+        .unwrap_or(diagnostics::RECENT_ERROR_CAPACITY);
+    Ok(serde_json::json!({
+        "success": true,
+        "errors": diagnostics::get_recent_errors(limit),
+    }))
+}
+
+/// Per-command latency and error counters and `db.read` / `db.write`
+/// counters since startup or the last reset.
+#[tauri::command]
+pub async fn diagnostics_get_metrics() -> Result<Value, String> {
+    Ok(serde_json::json!({
+        "success": true,
+        "metrics": diagnostics::metrics::registry().snapshot(),
+    }))
+}
+
+#[tauri::command]
+pub async fn diagnostics_reset_metrics() -> Result<Value, String> {
+    diagnostics::metrics::registry().reset();
+    info!("Command metrics reset");
+    Ok(serde_json::json!({ "success": true }))
+}
+
+#[tauri::command]
+pub async fn diagnostics_find_by_correlation(arg0: Option<Value>) -> Result<Value, String> {
+    let correlation_id = arg0
+        .as_ref()
+        .and_then(|v| v.get("correlationId").or(Some(v)))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .ok_or("Missing correlationId")?;
+    let mut found = diagnostics::find_by_correlation(&diagnostics::get_log_dir(), correlation_id);
+    found["success"] = Value::Bool(true);
+    Ok(found)
+}
+
+#[tauri::command]
+pub async fn logs_query(arg0: Option<Value>) -> Result<Value, String> {
+    let query = logs::LogQuery::from_payload(&arg0.unwrap_or(Value::Null))?;
+    let mut result =
+        tokio::task::spawn_blocking(move || logs::query(&diagnostics::get_log_dir(), &query))
+            .await
+            .map_err(|e| format!("log query task join error: {e}"))??;
+    result["success"] = Value::Bool(true);
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn logs_get_files() -> Result<Value, String> {
+    let log_dir = diagnostics::get_log_dir();
+    Ok(serde_json::json!({
+        "success": true,
+        "directory": log_dir.to_string_lossy(),
+        "files": logs::list_files(&log_dir),
+    }))
+}
+
+#[tauri::command]
+pub async fn logs_get_level() -> Result<Value, String> {
+    Ok(serde_json::json!({
+        "success": true,
+        "filter": logs::current_filter(),
+        "default": logs::DEFAULT_LOG_FILTER,
+    }))
+}
+
+/// Change the log filter until the next restart. Accepts a bare level
+/// (`debug`), `default`, or full `EnvFilter` directives.
+#[tauri::command]
+pub async fn logs_set_level(arg0: Option<Value>) -> Result<Value, String> {
+    let level = arg0
+        .as_ref()
+        .and_then(|v| v.get("level").or(Some(v)))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|level| !level.is_empty())
+        .ok_or("Missing level")?;
+    let filter = logs::set_level(level)?;
+    info!(filter = %filter, "Log level changed");
+    Ok(serde_json::json!({ "success": true, "filter": filter }))
+}
+
 #[tauri::command]
 pub async fn diagnostics_send_remote_incident(
     db: tauri::State<'_, db::DbState>,
@@ -693,6 +929,185 @@ pub async fn diagnostics_open_export_dir(
     }))
 }
 
+/// Open a read-only support session (admin, SystemControl confirmation).
+/// Returns the first one-time token for `support_get_snapshot`; the session
+/// ends after `durationMinutes` (default 30) with `support_session_ended`.
+#[tauri::command]
+pub async fn support_session_start(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<Value, crate::auth::GuardedCommandError> {
+    crate::auth::authorize_privileged_action(
+        crate::auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let actor = crate::auth::current_staff_id(&auth_state);
+    let duration = support_session::duration_from_payload(arg0.as_ref());
+    let (session, token, replaced) =
+        support_session::start(actor.as_deref(), duration, Utc::now())?;
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        if let Some(replaced) = replaced {
+            let ended = support_session::record_end(
+                &conn,
+                &replaced,
+                support_session::EndReason::Replaced,
+                actor.as_deref(),
+            )?;
+            let _ = app.emit(support_session::ENDED_EVENT, ended);
+        }
+        support_session::record(
+            &conn,
+            "start",
+            Some(&session.id),
+            true,
+            "Support session started",
+            actor.as_deref(),
+            session.to_json(),
+        )?;
+    }
+    info!(session_id = %session.id, "Support session started");
+
+    let session_id = session.id.clone();
+    let db_for_expiry = db.inner().clone();
+    let wait = duration.to_std().unwrap_or(Duration::ZERO);
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(wait).await;
+        let Ok(Some(expired)) = support_session::expire(&session_id, Utc::now()) else {
+            return;
+        };
+        let ended = db_for_expiry
+            .conn
+            .lock()
+            .map_err(|e| e.to_string())
+            .and_then(|conn| {
+                support_session::record_end(
+                    &conn,
+                    &expired,
+                    support_session::EndReason::Expired,
+                    None,
+                )
+            });
+        match ended {
+            Ok(payload) => {
+                let _ = app.emit(support_session::ENDED_EVENT, payload);
+            }
+            Err(e) => warn!(error = %e, "Failed to record support session expiry"),
+        }
+    });
+
+    let mut response = session.to_json();
+    response["success"] = Value::Bool(true);
+    response["token"] = Value::String(token);
+    response["durationMinutes"] = duration.num_minutes().into();
+    Ok(response)
+}
+
+/// Redacted diagnostics snapshot for the open support session. The token is
+/// used up; the response carries `nextToken` for the following call.
+#[tauri::command]
+pub async fn support_get_snapshot(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    let token = crate::payload_arg0_as_string(arg0, &["token", "supportToken", "support_token"]);
+    let (session, next_token) = match support_session::consume(token.as_deref(), Utc::now()) {
+        Ok(granted) => granted,
+        Err(rejection) => {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            let session_id = match &rejection {
+                support_session::TokenError::Expired(session) => Some(session.id.as_str()),
+                _ => None,
+            };
+            support_session::record(
+                &conn,
+                "snapshot_rejected",
+                session_id,
+                false,
+                rejection.message(),
+                None,
+                Value::Null,
+            )?;
+            if let support_session::TokenError::Expired(session) = &rejection {
+                let ended = support_session::record_end(
+                    &conn,
+                    session,
+                    support_session::EndReason::Expired,
+                    None,
+                )?;
+                let _ = app.emit(support_session::ENDED_EVENT, ended);
+            }
+            return Err(rejection.message().into());
+        }
+    };
+
+    // Built on a pooled read connection off the async runtime, so the
+    // writer and the command threads are never held up by a snapshot.
+    let snapshot = db
+        .run_blocking(|db| db.read(|conn| Ok(diagnostics::support_snapshot(conn))))
+        .await?;
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        support_session::record(
+            &conn,
+            "snapshot",
+            Some(&session.id),
+            true,
+            &format!("Support snapshot {} served", session.snapshots),
+            None,
+            session.to_json(),
+        )?;
+    }
+
+    Ok(serde_json::json!({
+        "success": true,
+        "sessionId": session.id,
+        "expiresAt": session.expires_at.to_rfc3339(),
+        "nextToken": next_token,
+        "snapshot": snapshot,
+    }))
+}
+
+/// The open support session, without its token, so the terminal can show
+/// that support access is live.
+#[tauri::command]
+pub async fn support_session_status() -> Result<Value, String> {
+    Ok(match support_session::current() {
+        Some(session) => {
+            serde_json::json!({ "success": true, "active": true, "session": session.to_json() })
+        }
+        None => serde_json::json!({ "success": true, "active": false }),
+    })
+}
+
+/// End the support session early. `sessionId` limits it to that session.
+#[tauri::command]
+pub async fn support_session_end(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    let session_id = crate::payload_arg0_as_string(arg0, &["sessionId", "session_id"]);
+    let Some(session) = support_session::end(session_id.as_deref())? else {
+        return Ok(serde_json::json!({ "success": true, "ended": false }));
+    };
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let ended = support_session::record_end(
+        &conn,
+        &session,
+        support_session::EndReason::Ended,
+        crate::auth::current_staff_id(&auth_state).as_deref(),
+    )?;
+    let _ = app.emit(support_session::ENDED_EVENT, ended.clone());
+    info!(session_id = %session.id, "Support session ended");
+    Ok(serde_json::json!({ "success": true, "ended": true, "session": ended }))
+}
+
 /// Gap review 2026-07-10 P0: static audit that every renderer-callable
 /// full-wipe command carries the SystemControl privileged-action gate. These
 /// commands DELETE the entire sync_queue / parity_sync_queue / orders from the
@@ -744,14 +1159,36 @@ mod destructive_command_auth_audit {
         let sync_queue = include_str!("sync_queue.rs");
         assert_gated(sync_queue, "pub fn sync_queue_clear(");
     }
+
+    #[test]
+    fn clear_all_and_factory_reset_require_a_confirmation_token() {
+        for (source, signature) in [
+            (include_str!("orders.rs"), "pub async fn orders_clear_all("),
+            (
+                include_str!("sync.rs"),
+                "pub async fn sync_clear_all_orders(",
+            ),
+            (
+                include_str!("settings.rs"),
+                "pub async fn settings_factory_reset(",
+            ),
+        ] {
+            let body = command_body(source, signature);
+            assert!(
+                body.contains("destructive_ops::confirm"),
+                "`{signature}` must check its destructive_op_prepare token before deleting",
+            );
+        }
+    }
 }
 
 #[cfg(test)]
 mod dto_tests {
     use super::{
         parse_diagnostic_fix_driver_payload, parse_diagnostics_export_payload,
-        parse_diagnostics_open_export_dir_payload,
+        parse_diagnostics_open_export_dir_payload, parse_log_lines_payload,
     };
+    use crate::diagnostics;
 
     #[test]
     fn parse_diagnostics_export_payload_supports_defaults_and_bool_legacy_form() {
@@ -775,6 +1212,22 @@ mod dto_tests {
         assert!(parsed.redact_sensitive);
     }
 
+    #[test]
+    fn parse_log_lines_payload_defaults_and_clamps() {
+        assert_eq!(
+            parse_log_lines_payload(None),
+            diagnostics::SUPPORT_BUNDLE_LOG_LINES
+        );
+        assert_eq!(
+            parse_log_lines_payload(Some(&serde_json::json!({ "logLines": 500 }))),
+            500
+        );
+        assert_eq!(
+            parse_log_lines_payload(Some(&serde_json::json!({ "lines": 1_000_000 }))),
+            20_000
+        );
+    }
+
     #[test]
     fn parse_diagnostics_open_export_dir_payload_supports_string_and_object() {
         let from_string = parse_diagnostics_open_export_dir_payload(Some(serde_json::json!(
@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use serde_json::{json, Value};
+use tauri::Emitter;
+
+use crate::commands::orders::{
+    enqueue_order_sync_payload, resolve_driver_display_name, settle_driver_delivery, DriverDelivery,
+};
+use crate::dispatch::{self, NewRun};
+use crate::{auth, db, order_events, order_ownership, resolve_order_id, value_f64, value_str};
+
+#[derive(Debug, Default, PartialEq)]
+struct StartRunPayload {
+    driver_id: String,
+    order_ids: Vec<String>,
+}
+
+fn parse_start_run_payload(arg0: Option<Value>) -> Result<StartRunPayload, String> {
+    let payload = arg0.unwrap_or_else(|| json!({}));
+    let driver_id = value_str(&payload, &["driverId", "driver_id"]).ok_or("Missing driverId")?;
+    let order_ids = ["orderIds", "order_ids", "orders"]
+        .iter()
+        .find_map(|key| payload.get(*key).and_then(Value::as_array))
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|id| match id {
+                    Value::String(id) => Some(id.trim().to_string()),
+                    Value::Object(_) => value_str(id, &["orderId", "order_id", "id"]),
+                    _ => None,
+                })
+                .filter(|id| !id.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    if order_ids.is_empty() {
+        return Err("Missing orderIds".into());
+    }
+    Ok(StartRunPayload {
+        driver_id,
+        order_ids,
+    })
+}
+
+/// `{ runId, orders: [{ orderId, cashCollected }] }`, or the shorthand
+/// `{ runId, cash: { <orderId>: amount } }`. Orders left out keep the cash
+/// side of their recorded payments.
+fn parse_complete_run_payload(
+    arg0: Option<Value>,
+) -> Result<(String, HashMap<String, f64>), String> {
+    let payload = arg0.unwrap_or_else(|| json!({}));
+    let run_id = value_str(&payload, &["runId", "run_id", "id"]).ok_or("Missing runId")?;
+    let mut cash = HashMap::new();
+    if let Some(orders) = payload.get("orders").and_then(Value::as_array) {
+        for order in orders {
+            let order_id = value_str(order, &["orderId", "order_id", "id"]);
+            let amount = value_f64(order, &["cashCollected", "cash_collected", "cash"]);
+            if let Some((order_id, amount)) = order_id.zip(amount) {
+                cash.insert(order_id, amount);
+            }
+        }
+    }
+    if let Some(map) = payload.get("cash").and_then(Value::as_object) {
+        for (order_id, amount) in map {
+            if let Some(amount) = amount.as_f64() {
+                cash.insert(order_id.trim().to_string(), amount);
+            }
+        }
+    }
+    if let Some((order_id, amount)) = cash
+        .iter()
+        .find(|(_, amount)| !amount.is_finite() || **amount < 0.0)
+    {
+        return Err(format!("Invalid cash amount {amount} for order {order_id}"));
+    }
+    Ok((run_id, cash))
+}
+
+fn emit_order_updates(app: &tauri::AppHandle, run: &dispatch::DriverRun, status: &str) {
+    for order_id in &run.order_ids {
+        let payload = json!({
+            "orderId": order_id,
+            "status": status,
+            "driverId": run.driver_id,
+            "driverName": run.driver_name,
+            "runId": run.id,
+        });
+        let _ = app.emit(
+            "order_status_updated",
+            json!({ "orderId": order_id, "status": status }),
+        );
+        let _ = app.emit("order_realtime_update", payload);
+    }
+}
+
+/// Send a driver out with a batch of ready delivery orders.
+#[tauri::command]
+pub async fn driver_start_run(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    let parsed = parse_start_run_payload(arg0)?;
+    let actor = auth::current_staff_id(&auth_state);
+    let now = Utc::now().to_rfc3339();
+    let run = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let order_ids = parsed
+            .order_ids
+            .iter()
+            .map(|id| resolve_order_id(&conn, id).ok_or_else(|| format!("Order {id} not found")))
+            .collect::<Result<Vec<_>, _>>()?;
+        let shift_id = order_ownership::resolve_driver_shift_id(&conn, &parsed.driver_id, None)?
+            .ok_or("Driver must have an active shift before starting a run")?;
+        let branch_id: Option<String> = conn
+            .query_row(
+                "SELECT branch_id FROM staff_shifts WHERE id = ?1",
+                rusqlite::params![shift_id],
+                |row| row.get(0),
+            )
+            .ok()
+            .flatten();
+        let driver_name = resolve_driver_display_name(&conn, &parsed.driver_id);
+        let run = dispatch::start_run(
+            &conn,
+            &NewRun {
+                driver_id: &parsed.driver_id,
+                driver_name: driver_name.as_deref(),
+                driver_shift_id: Some(&shift_id),
+                branch_id: branch_id.as_deref(),
+                order_ids: &order_ids,
+                actor: actor.as_deref(),
+            },
+            &now,
+        )?;
+        for order_id in &run.order_ids {
+            let sync_payload = json!({
+                "orderId": order_id,
+                "orderType": "delivery",
+                "status": "out_for_delivery",
+                "driverId": run.driver_id,
+                "driverName": run.driver_name,
+            });
+            let _ = enqueue_order_sync_payload(&conn, order_id, &sync_payload);
+            order_events::append(
+                &conn,
+                order_id,
+                order_events::STATUS_CHANGED,
+                actor.as_deref(),
+                json!({
+                    "from": "ready",
+                    "to": "out_for_delivery",
+                    "driverId": run.driver_id,
+                    "runId": run.id,
+                }),
+            );
+        }
+        run
+    };
+
+    tracing::info!(
+        run_id = %run.id,
+        driver_id = %run.driver_id,
+        orders = run.order_ids.len(),
+        "Driver run started"
+    );
+    emit_order_updates(&app, &run, "out_for_delivery");
+    let data = run.to_json();
+    let _ = app.emit(
+        dispatch::UPDATED_EVENT,
+        json!({ "action": "run_started", "run": data }),
+    );
+    Ok(json!({ "success": true, "data": data }))
+}
+
+/// Close a run when the driver is back: each order is marked delivered and
+/// its driver earning written with the cash the driver declared for it.
+#[tauri::command]
+pub async fn driver_complete_run(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    let (run_id, cash) = parse_complete_run_payload(arg0)?;
+    let actor = auth::current_staff_id(&auth_state);
+    let now = Utc::now().to_rfc3339();
+    let (run, delivered) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let run = dispatch::load_open_run(&conn, &run_id)?;
+        let mut cash_by_order = HashMap::new();
+        for (order_id, amount) in cash {
+            let resolved = resolve_order_id(&conn, &order_id)
+                .filter(|id| run.order_ids.contains(id))
+                .ok_or_else(|| format!("Order {order_id} is not on run {run_id}"))?;
+            cash_by_order.insert(resolved, amount);
+        }
+        let shift_id = match run.driver_shift_id.clone() {
+            Some(shift_id) => shift_id,
+            None => order_ownership::resolve_driver_shift_id(&conn, &run.driver_id, None)?
+                .ok_or("Driver has no active shift to settle the run against")?,
+        };
+
+        let mut delivered = Vec::with_capacity(run.order_ids.len());
+        for order_id in &run.order_ids {
+            let status: String = conn
+                .query_row(
+                    "SELECT LOWER(COALESCE(status, '')) FROM orders WHERE id = ?1",
+                    rusqlite::params![order_id],
+                    |row| row.get(0),
+                )
+                .unwrap_or_default();
+            // An order cancelled while the driver was out is not delivered
+            // and earns nothing.
+            if matches!(status.as_str(), "cancelled" | "canceled" | "") {
+                continue;
+            }
+            let cash_collected = cash_by_order.get(order_id).copied();
+            settle_driver_delivery(
+                &conn,
+                &DriverDelivery {
+                    order_id,
+                    driver_id: &run.driver_id,
+                    driver_name: run.driver_name.as_deref(),
+                    shift_id: &shift_id,
+                    notes: None,
+                    cash_collected,
+                    actor: actor.as_deref(),
+                },
+                &now,
+            )?;
+            dispatch::record_delivery(&conn, &run.id, order_id, cash_collected, &now)?;
+            delivered.push(order_id.clone());
+        }
+        dispatch::mark_returned(&conn, &run.id, actor.as_deref(), &now)?;
+        (dispatch::load_run(&conn, &run.id)?, delivered)
+    };
+
+    tracing::info!(
+        run_id = %run.id,
+        driver_id = %run.driver_id,
+        delivered = delivered.len(),
+        "Driver run completed"
+    );
+    emit_order_updates(
+        &app,
+        &dispatch::DriverRun {
+            order_ids: delivered.clone(),
+            ..run.clone()
+        },
+        "delivered",
+    );
+    let data = run.to_json();
+    let _ = app.emit(
+        dispatch::UPDATED_EVENT,
+        json!({ "action": "run_completed", "run": data, "deliveredOrderIds": delivered }),
+    );
+    Ok(json!({ "success": true, "data": data, "deliveredOrderIds": delivered }))
+}
+
+#[tauri::command]
+pub async fn dispatch_get_board(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<Value, String> {
+    let branch_id = crate::branches::report_scope(crate::payload_arg0_as_string(
+        arg0,
+        &["branchId", "branch_id"],
+    ));
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let board = dispatch::board(&conn, &branch_id)?;
+    Ok(json!({ "success": true, "data": board }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_run_payload_accepts_ids_and_order_objects() {
+        let parsed = parse_start_run_payload(Some(json!({
+            "driverId": "d-1",
+            "orderIds": ["o-1", { "orderId": "o-2" }, " "],
+        })))
+        .unwrap();
+        assert_eq!(parsed.order_ids, vec!["o-1".to_string(), "o-2".to_string()]);
+        assert!(parse_start_run_payload(Some(json!({ "driverId": "d-1" }))).is_err());
+        assert!(parse_start_run_payload(Some(json!({ "orderIds": ["o-1"] }))).is_err());
+    }
+
+    #[test]
+    fn complete_run_payload_merges_both_cash_forms() {
+        let (run_id, cash) = parse_complete_run_payload(Some(json!({
+            "runId": "r-1",
+            "orders": [{ "orderId": "o-1", "cashCollected": 12.5 }, { "orderId": "o-2" }],
+            "cash": { "o-3": 4 },
+        })))
+        .unwrap();
+        assert_eq!(run_id, "r-1");
+        assert_eq!(cash.len(), 2);
+        assert_eq!(cash["o-1"], 12.5);
+        assert_eq!(cash["o-3"], 4.0);
+        assert!(parse_complete_run_payload(Some(json!({
+            "runId": "r-1",
+            "cash": { "o-1": -1 },
+        })))
+        .is_err());
+    }
+}
@@ -6,7 +6,7 @@ use std::time::Duration;
 use tauri::Emitter;
 use tracing::{info, warn};
 
-use crate::{db, ecr, payload_arg0_as_string, value_str};
+use crate::{db, ecr, payload_arg0_as_string, training, value_str};
 
 #[derive(Debug, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -1177,6 +1177,23 @@ fn parse_amount_and_options_payload(
     AmountOptionsCompatPayload { amount, options }
 }
 
+/// Approved stand-in for a sale or refund attempted in training mode. The
+/// terminal is never contacted and nothing is logged to `ecr_transactions`.
+fn training_transaction_response(amount: f64, options: serde_json::Value) -> serde_json::Value {
+    let now = chrono::Utc::now().to_rfc3339();
+    training::simulated_success(serde_json::json!({
+        "transaction": {
+            "id": format!("training-{}", uuid::Uuid::new_v4()),
+            "amount": amount,
+            "status": "approved",
+            "authorizationCode": "TRAINING",
+            "startedAt": now,
+            "completedAt": now,
+        },
+        "options": options,
+    }))
+}
+
 fn validate_ecr_amount(amount: f64) -> Result<i64, String> {
     if !amount.is_finite() {
         return Err("Invalid ECR amount: amount must be finite".to_string());
@@ -1650,6 +1667,9 @@ pub async fn ecr_process_payment(
     let amount = parsed.amount;
     let amount_cents = validate_ecr_amount(amount)?;
     let options = parsed.options;
+    if training::is_active() {
+        return Ok(training_transaction_response(amount, options));
+    }
     let device_id = options
         .get("deviceId")
         .and_then(|v| v.as_str())
@@ -1803,6 +1823,9 @@ pub async fn ecr_process_refund(
     let amount = parsed.amount;
     let amount_cents = validate_ecr_amount(amount)?;
     let options = parsed.options;
+    if training::is_active() {
+        return Ok(training_transaction_response(amount, options));
+    }
     let device_id = options
         .get("deviceId")
         .and_then(|v| v.as_str())
@@ -1942,6 +1965,12 @@ pub async fn ecr_void_transaction(
         );
         return Err("Missing transactionId".into());
     }
+    if training::is_active() {
+        return Ok(training::simulated_success(serde_json::json!({
+            "transactionId": txid,
+            "deviceId": parsed.device_id
+        })));
+    }
     // If a device is specified and connected, try to void through protocol
     if let Some(ref did) = parsed.device_id {
         if mgr.is_connected(did) {
@@ -2003,6 +2032,11 @@ pub async fn ecr_settlement(
     app: tauri::AppHandle,
 ) -> Result<serde_json::Value, String> {
     let device_id = parse_optional_device_id(arg0);
+    if training::is_active() {
+        return Ok(training::simulated_success(
+            serde_json::json!({ "deviceId": device_id }),
+        ));
+    }
     let _ = app.emit(
         "ecr_event_display_message",
         serde_json::json!({ "message": "Settlement started", "deviceId": device_id.clone() }),
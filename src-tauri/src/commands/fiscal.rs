@@ -0,0 +1,174 @@
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+
+use crate::fiscal::documents;
+use crate::{db, resolve_order_id, storage};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JournalExportPayload {
+    #[serde(default, alias = "date")]
+    from: Option<String>,
+    #[serde(default)]
+    to: Option<String>,
+    #[serde(default)]
+    format: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelDocumentPayload {
+    #[serde(alias = "document_id", alias = "id")]
+    document_id: String,
+}
+
+fn parse_journal_export_payload(
+    arg0: Option<serde_json::Value>,
+) -> Result<(String, String, String), String> {
+    let payload: JournalExportPayload = match arg0 {
+        Some(serde_json::Value::String(date)) => JournalExportPayload {
+            from: Some(date),
+            to: None,
+            format: None,
+        },
+        Some(value) => {
+            serde_json::from_value(value).map_err(|e| format!("Invalid export payload: {e}"))?
+        }
+        None => JournalExportPayload {
+            from: None,
+            to: None,
+            format: None,
+        },
+    };
+    let parse_date = |raw: &str| {
+        NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {raw}"))
+    };
+    let from = match payload.from.as_deref().filter(|v| !v.trim().is_empty()) {
+        Some(raw) => parse_date(raw)?,
+        None => Utc::now().date_naive(),
+    };
+    let to = match payload.to.as_deref().filter(|v| !v.trim().is_empty()) {
+        Some(raw) => parse_date(raw)?,
+        None => from,
+    };
+    if to < from {
+        return Err("Export range end is before its start".into());
+    }
+    let format = match payload.format.as_deref().map(str::trim) {
+        None | Some("") | Some("json") => "json",
+        Some("xml") => "xml",
+        Some(other) => return Err(format!("Unsupported export format: {other}")),
+    };
+    Ok((from.to_string(), to.to_string(), format.to_string()))
+}
+
+#[tauri::command]
+pub async fn fiscal_export_daily_journal(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let (from, to, format) = parse_journal_export_payload(arg0)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    documents::export_journal(&conn, &from, &to, &format)
+}
+
+#[tauri::command]
+pub async fn fiscal_list_order_documents(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let raw = match arg0 {
+        Some(serde_json::Value::String(id)) => id,
+        Some(value) => value
+            .get("orderId")
+            .or_else(|| value.get("order_id"))
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string)
+            .ok_or("Missing orderId")?,
+        None => return Err("Missing orderId".into()),
+    };
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let order_id = resolve_order_id(&conn, &raw).unwrap_or(raw);
+    let documents = documents::list_order_documents(&conn, &order_id)?;
+    Ok(serde_json::json!({
+        "success": true,
+        "orderId": order_id,
+        "documents": documents,
+    }))
+}
+
+#[tauri::command]
+pub async fn fiscal_cancel_document(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let payload: CancelDocumentPayload = match arg0 {
+        Some(serde_json::Value::String(document_id)) => CancelDocumentPayload { document_id },
+        Some(value) => {
+            serde_json::from_value(value).map_err(|e| format!("Invalid cancel payload: {e}"))?
+        }
+        None => return Err("Missing documentId".into()),
+    };
+    let terminal_id = storage::get_credential("terminal_id").unwrap_or_default();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute_batch("BEGIN IMMEDIATE")
+        .map_err(|e| format!("begin transaction: {e}"))?;
+    let terminal_id = if terminal_id.trim().is_empty() {
+        conn.query_row(
+            "SELECT terminal_id FROM fiscal_documents WHERE id = ?1",
+            rusqlite::params![payload.document_id],
+            |row| row.get::<_, String>(0),
+        )
+        .unwrap_or_default()
+    } else {
+        terminal_id
+    };
+    match documents::cancel_document(&conn, &payload.document_id, &terminal_id) {
+        Ok(document) => {
+            conn.execute_batch("COMMIT")
+                .map_err(|e| format!("commit: {e}"))?;
+            Ok(serde_json::json!({ "success": true, "document": document }))
+        }
+        Err(error) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            Ok(serde_json::json!({
+                "success": false,
+                "errorCode": "fiscal_cancel_rejected",
+                "error": error,
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn journal_payload_defaults_and_validation() {
+        let (from, to, format) =
+            parse_journal_export_payload(Some(serde_json::json!("2026-03-01"))).unwrap();
+        assert_eq!(
+            (from.as_str(), to.as_str(), format.as_str()),
+            ("2026-03-01", "2026-03-01", "json")
+        );
+
+        let (from, to, format) = parse_journal_export_payload(Some(serde_json::json!({
+            "from": "2026-03-01", "to": "2026-03-07", "format": "xml"
+        })))
+        .unwrap();
+        assert_eq!(
+            (from.as_str(), to.as_str(), format.as_str()),
+            ("2026-03-01", "2026-03-07", "xml")
+        );
+
+        assert!(parse_journal_export_payload(Some(serde_json::json!({
+            "from": "2026-03-07", "to": "2026-03-01"
+        })))
+        .is_err());
+        assert!(
+            parse_journal_export_payload(Some(serde_json::json!({ "format": "csv" }))).is_err()
+        );
+    }
+}
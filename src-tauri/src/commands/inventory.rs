@@ -0,0 +1,312 @@
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{db, inventory};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecipeLinePayload {
+    #[serde(alias = "inventory_item_id", alias = "ingredientId", alias = "id")]
+    inventory_item_id: String,
+    quantity: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetRecipePayload {
+    #[serde(
+        alias = "menu_item_id",
+        alias = "subcategoryId",
+        alias = "subcategory_id"
+    )]
+    menu_item_id: String,
+    #[serde(default)]
+    ingredients: Vec<RecipeLinePayload>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetModifierRecipePayload {
+    #[serde(alias = "modifier_id", alias = "ingredientId", alias = "ingredient_id")]
+    modifier_id: String,
+    #[serde(default, alias = "stock")]
+    ingredients: Vec<RecipeLinePayload>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InventoryAdjustPayload {
+    #[serde(alias = "inventory_item_id", alias = "itemId", alias = "id")]
+    inventory_item_id: String,
+    #[serde(
+        alias = "adjustment",
+        alias = "quantityDelta",
+        alias = "quantity_delta"
+    )]
+    delta: f64,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InventoryReceivePayload {
+    #[serde(alias = "inventory_item_id", alias = "itemId", alias = "id")]
+    inventory_item_id: String,
+    quantity: f64,
+    #[serde(
+        default,
+        alias = "supplier",
+        alias = "invoiceNumber",
+        alias = "invoice_number"
+    )]
+    reference: Option<String>,
+    #[serde(default, alias = "notes")]
+    reason: Option<String>,
+    #[serde(default, alias = "unit_cost", alias = "cost")]
+    unit_cost: Option<f64>,
+}
+
+fn parse_set_recipe_payload(arg0: Option<Value>) -> Result<SetRecipePayload, String> {
+    let payload = arg0.ok_or("Missing recipe payload")?;
+    let mut parsed: SetRecipePayload =
+        serde_json::from_value(payload).map_err(|e| format!("Invalid recipe payload: {e}"))?;
+    parsed.menu_item_id = parsed.menu_item_id.trim().to_string();
+    if parsed.menu_item_id.is_empty() {
+        return Err("Missing menuItemId".into());
+    }
+    Ok(parsed)
+}
+
+fn parse_set_modifier_recipe_payload(
+    arg0: Option<Value>,
+) -> Result<SetModifierRecipePayload, String> {
+    let payload = arg0.ok_or("Missing modifier recipe payload")?;
+    let mut parsed: SetModifierRecipePayload = serde_json::from_value(payload)
+        .map_err(|e| format!("Invalid modifier recipe payload: {e}"))?;
+    parsed.modifier_id = parsed.modifier_id.trim().to_string();
+    if parsed.modifier_id.is_empty() {
+        return Err("Missing modifierId".into());
+    }
+    Ok(parsed)
+}
+
+fn parse_inventory_adjust_payload(arg0: Option<Value>) -> Result<InventoryAdjustPayload, String> {
+    let payload = arg0.ok_or("Missing inventory adjustment payload")?;
+    let parsed: InventoryAdjustPayload = serde_json::from_value(payload)
+        .map_err(|e| format!("Invalid inventory adjustment payload: {e}"))?;
+    if parsed.delta == 0.0 || !parsed.delta.is_finite() {
+        return Err("Adjustment must be a non-zero quantity".into());
+    }
+    if parsed
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .unwrap_or_default()
+        .is_empty()
+    {
+        return Err("Missing adjustment reason".into());
+    }
+    Ok(parsed)
+}
+
+fn parse_inventory_receive_payload(arg0: Option<Value>) -> Result<InventoryReceivePayload, String> {
+    let payload = arg0.ok_or("Missing inventory receive payload")?;
+    let parsed: InventoryReceivePayload = serde_json::from_value(payload)
+        .map_err(|e| format!("Invalid inventory receive payload: {e}"))?;
+    if parsed.quantity <= 0.0 || !parsed.quantity.is_finite() {
+        return Err("Received quantity must be positive".into());
+    }
+    if parsed
+        .unit_cost
+        .is_some_and(|cost| cost < 0.0 || !cost.is_finite())
+    {
+        return Err("Unit cost must be zero or more".into());
+    }
+    Ok(parsed)
+}
+
+#[tauri::command]
+pub async fn inventory_list_items(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let items = inventory::list_items(&conn)?;
+    Ok(serde_json::json!({ "success": true, "items": items }))
+}
+
+#[tauri::command]
+pub async fn inventory_upsert_item(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<Value, String> {
+    let payload = arg0.ok_or("Missing inventory item payload")?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let item = inventory::upsert_local_item(&conn, &payload)?;
+    Ok(serde_json::json!({ "success": true, "item": item }))
+}
+
+#[tauri::command]
+pub async fn inventory_set_recipe(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<Value, String> {
+    let payload = parse_set_recipe_payload(arg0)?;
+    let ingredients: Vec<(String, f64)> = payload
+        .ingredients
+        .into_iter()
+        .map(|line| (line.inventory_item_id.trim().to_string(), line.quantity))
+        .collect();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    inventory::set_recipe(&conn, &payload.menu_item_id, &ingredients)?;
+    Ok(serde_json::json!({
+        "success": true,
+        "menuItemId": payload.menu_item_id,
+        "ingredients": ingredients.len(),
+    }))
+}
+
+/// Map a modifier (menu ingredient) to the stock one portion of it uses.
+#[tauri::command]
+pub async fn inventory_set_modifier_recipe(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<Value, String> {
+    let payload = parse_set_modifier_recipe_payload(arg0)?;
+    let ingredients: Vec<(String, f64)> = payload
+        .ingredients
+        .into_iter()
+        .map(|line| (line.inventory_item_id.trim().to_string(), line.quantity))
+        .collect();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    inventory::set_modifier_recipe(&conn, &payload.modifier_id, &ingredients)?;
+    Ok(serde_json::json!({
+        "success": true,
+        "modifierId": payload.modifier_id,
+        "ingredients": ingredients.len(),
+    }))
+}
+
+#[tauri::command]
+pub async fn inventory_adjust(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    let payload = parse_inventory_adjust_payload(arg0)?;
+    let staff_id = crate::auth::current_staff_id(&auth_state);
+    let (item, crossed) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        inventory::record_movement(
+            &conn,
+            &inventory::MovementInput {
+                inventory_item_id: payload.inventory_item_id.trim(),
+                movement_type: inventory::MOVEMENT_ADJUSTMENT,
+                quantity_delta: payload.delta,
+                reason: payload.reason.as_deref().map(str::trim),
+                order_id: None,
+                reference_id: None,
+                staff_id: staff_id.as_deref(),
+                unit_cost: None,
+            },
+        )?
+    };
+    if crossed {
+        inventory::emit_low_stock(&app, std::slice::from_ref(&item));
+    }
+    Ok(serde_json::json!({ "success": true, "item": item }))
+}
+
+#[tauri::command]
+pub async fn inventory_receive(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+) -> Result<Value, String> {
+    let payload = parse_inventory_receive_payload(arg0)?;
+    let staff_id = crate::auth::current_staff_id(&auth_state);
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let (item, _) = inventory::record_movement(
+        &conn,
+        &inventory::MovementInput {
+            inventory_item_id: payload.inventory_item_id.trim(),
+            movement_type: inventory::MOVEMENT_RECEIVE,
+            quantity_delta: payload.quantity,
+            reason: payload.reason.as_deref().map(str::trim),
+            order_id: None,
+            reference_id: payload.reference.as_deref().map(str::trim),
+            staff_id: staff_id.as_deref(),
+            unit_cost: payload.unit_cost,
+        },
+    )?;
+    Ok(serde_json::json!({ "success": true, "item": item }))
+}
+
+/// Pull inventory items from the admin API into `inventory_items`.
+#[tauri::command]
+pub async fn inventory_sync_items(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
+    let resp = crate::admin_fetch(
+        Some(&db),
+        "/api/pos/sync/inventory_items?limit=2000",
+        "GET",
+        None,
+    )
+    .await?;
+    let items = ["inventory_items", "items", "data"]
+        .iter()
+        .find_map(|key| resp.get(*key).and_then(Value::as_array))
+        .cloned()
+        .unwrap_or_default();
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let count = inventory::import_admin_items(&conn, &items)?;
+    info!(count = count, "Synced inventory items from admin");
+    Ok(serde_json::json!({ "success": true, "count": count }))
+}
+
+#[cfg(test)]
+mod dto_tests {
+    use super::*;
+
+    #[test]
+    fn parse_inventory_adjust_requires_reason_and_non_zero_delta() {
+        let parsed = parse_inventory_adjust_payload(Some(serde_json::json!({
+            "inventory_item_id": "bun",
+            "adjustment": -2,
+            "reason": "spoiled"
+        })))
+        .expect("adjust payload should parse");
+        assert_eq!(parsed.inventory_item_id, "bun");
+        assert_eq!(parsed.delta, -2.0);
+
+        assert!(parse_inventory_adjust_payload(Some(serde_json::json!({
+            "inventoryItemId": "bun",
+            "delta": -2
+        })))
+        .is_err());
+        assert!(parse_inventory_adjust_payload(Some(serde_json::json!({
+            "inventoryItemId": "bun",
+            "delta": 0,
+            "reason": "count"
+        })))
+        .is_err());
+        assert!(parse_inventory_receive_payload(Some(serde_json::json!({
+            "inventoryItemId": "bun",
+            "quantity": -1
+        })))
+        .is_err());
+        let received = parse_inventory_receive_payload(Some(serde_json::json!({
+            "inventoryItemId": "bun",
+            "quantity": 10,
+            "unitCost": 0.35
+        })))
+        .expect("receive payload should parse");
+        assert_eq!(received.unit_cost, Some(0.35));
+        assert!(parse_inventory_receive_payload(Some(serde_json::json!({
+            "inventoryItemId": "bun",
+            "quantity": 10,
+            "cost": -1
+        })))
+        .is_err());
+    }
+}
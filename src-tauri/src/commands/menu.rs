@@ -8,10 +8,11 @@ use tauri::Emitter;
 use tracing::{debug, info, warn};
 
 use super::offline_mutations::patch_menu_flag;
+use crate::menu::availability::{self, MenuEntity};
 use crate::{
-    db, handle_invalid_terminal_credentials, hydrate_terminal_credentials_from_local_settings,
-    is_terminal_auth_failure, mask_terminal_id, maybe_lazy_warm_menu_cache, menu,
-    read_local_setting, storage, sync_queue, value_str,
+    auth, db, favorites, handle_invalid_terminal_credentials,
+    hydrate_terminal_credentials_from_local_settings, is_terminal_auth_failure, mask_terminal_id,
+    maybe_lazy_warm_menu_cache, menu, read_local_setting, storage, sync_queue, value_str,
 };
 
 #[derive(Debug, Deserialize)]
@@ -57,9 +58,24 @@ struct MenuComboUpdatePayload {
     is_active: bool,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MenuBulkAvailabilityPayload {
+    #[serde(alias = "entity_type", alias = "type")]
+    entity_type: String,
+    #[serde(default)]
+    ids: Vec<String>,
+    #[serde(default, alias = "parent_category_id")]
+    parent_category_id: Option<String>,
+    #[serde(alias = "isAvailable", alias = "is_available", alias = "available")]
+    is_available: bool,
+}
+
 const MENU_VERSION_MONITOR_MIN_INTERVAL_SECS: u64 = 10;
 const MENU_MONITOR_WARN_THROTTLE_SECS: u64 = 300;
 const MENU_MONITOR_OFFLINE_LOG_THROTTLE_SECS: u64 = 120;
+/// Concurrent admin PATCH calls per bulk availability batch.
+const MENU_BULK_REMOTE_BATCH: usize = 8;
 static MENU_PAYLOAD_WARN_STATE: OnceLock<Mutex<Option<Instant>>> = OnceLock::new();
 static MENU_OFFLINE_INFO_STATE: OnceLock<Mutex<Option<Instant>>> = OnceLock::new();
 
@@ -171,6 +187,23 @@ fn emit_menu_sync_event(
     );
 }
 
+/// Tell the renderer which open orders a sync left with stale line prices.
+pub(crate) fn emit_order_prices_stale_event(app: &tauri::AppHandle, result: &serde_json::Value) {
+    let order_ids = result
+        .get("staleOrderIds")
+        .and_then(|v| v.as_array())
+        .filter(|ids| !ids.is_empty());
+    if let Some(order_ids) = order_ids {
+        let _ = app.emit(
+            "order_prices_stale",
+            serde_json::json!({
+                "orderIds": order_ids,
+                "priceChanges": result.get("priceChanges").cloned().unwrap_or_default(),
+            }),
+        );
+    }
+}
+
 fn emit_menu_version_checked_event(
     app: &tauri::AppHandle,
     source: &str,
@@ -232,6 +265,24 @@ fn parse_menu_subcategory_payload(arg0: Option<serde_json::Value>) -> Result<Str
     Ok(parsed.subcategory_id)
 }
 
+fn parse_menu_bulk_availability_payload(
+    arg0: Option<serde_json::Value>,
+) -> Result<(MenuEntity, MenuBulkAvailabilityPayload), String> {
+    let payload = arg0.ok_or("Missing bulk availability payload")?;
+    let parsed: MenuBulkAvailabilityPayload = serde_json::from_value(payload)
+        .map_err(|e| format!("Invalid bulk availability payload: {e}"))?;
+    let entity = MenuEntity::parse(&parsed.entity_type)
+        .ok_or_else(|| format!("Unknown menu entity type: {}", parsed.entity_type))?;
+    let has_parent = parsed
+        .parent_category_id
+        .as_deref()
+        .is_some_and(|parent| !parent.trim().is_empty());
+    if parsed.ids.is_empty() && !has_parent {
+        return Err("Provide ids or parentCategoryId".into());
+    }
+    Ok((entity, parsed))
+}
+
 fn parse_menu_category_update_payload(
     arg0: Option<serde_json::Value>,
     arg1: Option<serde_json::Value>,
@@ -402,6 +453,7 @@ pub fn start_menu_version_monitor(
                                 Ok(result) => {
                                     let (updated, version, counts, timestamp) =
                                         menu_sync_snapshot(&result);
+                                    emit_order_prices_stale_event(&app, &result);
                                     emit_menu_version_checked_event(
                                         &app,
                                         "menu_version_monitor",
@@ -449,6 +501,7 @@ pub fn start_menu_version_monitor(
                             Ok(result) => {
                                 let (updated, version, counts, timestamp) =
                                     menu_sync_snapshot(&result);
+                                emit_order_prices_stale_event(&app, &result);
                                 emit_menu_version_checked_event(
                                     &app,
                                     "menu_version_monitor",
@@ -500,20 +553,23 @@ pub async fn menu_get_categories(
     db: tauri::State<'_, db::DbState>,
     app: tauri::AppHandle,
 ) -> Result<Vec<serde_json::Value>, String> {
-    let mut categories = menu::get_categories(&db);
-    let source = if categories.is_empty() {
-        maybe_lazy_warm_menu_cache(&db, &app, "menu_get_categories").await;
-        categories = menu::get_categories(&db);
-        if categories.is_empty() {
-            "empty_after_warmup"
+    crate::diagnostics::metrics::track("menu_get_categories", async move {
+        let mut categories = menu::get_categories(&db);
+        let source = if categories.is_empty() {
+            maybe_lazy_warm_menu_cache(&db, &app, "menu_get_categories").await;
+            categories = menu::get_categories(&db);
+            if categories.is_empty() {
+                "empty_after_warmup"
+            } else {
+                "lazy_sync"
+            }
         } else {
-            "lazy_sync"
-        }
-    } else {
-        "cache"
-    };
-    info!(source = %source, count = categories.len(), "menu_get_categories");
-    Ok(categories)
+            "cache"
+        };
+        info!(source = %source, count = categories.len(), "menu_get_categories");
+        Ok(categories)
+    })
+    .await
 }
 
 #[tauri::command]
@@ -652,6 +708,7 @@ pub async fn menu_sync(
         Ok(result) => {
             sync_state.clear_remote_auth_pause();
             let (updated, version, counts, timestamp) = menu_sync_snapshot(&result);
+            emit_order_prices_stale_event(&app, &result);
 
             emit_menu_sync_event(
                 &app,
@@ -684,6 +741,7 @@ pub async fn menu_sync(
                 "updated": updated,
                 "version": version,
                 "counts": counts,
+                "images": result.get("images"),
                 "timestamp": timestamp
             }))
         }
@@ -776,6 +834,31 @@ pub async fn menu_sync(
     }
 }
 
+/// Cached photo for a category, subcategory or combo: its local path, plus
+/// the bytes as base64 for small images.
+#[tauri::command]
+pub async fn menu_get_image(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let item_id = crate::payload_arg0_as_string(arg0, &["itemId", "item_id", "id"])
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .ok_or("Missing itemId")?;
+    let mut image = menu::images::get_image(&db, &item_id)?;
+    image["success"] = serde_json::Value::Bool(true);
+    Ok(image)
+}
+
+#[tauri::command]
+pub async fn menu_clear_image_cache(
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let mut cleared = menu::images::clear_cache(&db)?;
+    cleared["success"] = serde_json::Value::Bool(true);
+    Ok(cleared)
+}
+
 #[tauri::command]
 pub async fn menu_update_category(
     arg0: Option<serde_json::Value>,
@@ -1000,6 +1083,287 @@ pub async fn menu_update_combo(
     }))
 }
 
+/// Apply one entity type's availability change: local cache and overrides
+/// first, then the admin PATCH calls in batches. A PATCH that fails is queued
+/// for the sync engine like a single-item update, so the change still lands
+/// once the terminal is back online. Returns per-id results and whether any
+/// PATCH reached the admin.
+async fn apply_bulk_availability(
+    db: &db::DbState,
+    entity: MenuEntity,
+    ids: &[String],
+    is_available: bool,
+    operation_id: &str,
+    actor_staff_id: Option<&str>,
+) -> Result<(Vec<serde_json::Value>, bool), String> {
+    let outcomes = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        availability::apply_local(
+            &conn,
+            entity,
+            ids,
+            is_available,
+            operation_id,
+            actor_staff_id,
+        )?
+    };
+
+    let field = entity.flag_field();
+    let mut results = Vec::with_capacity(outcomes.len());
+    let mut local_ok = Vec::new();
+    for (id, outcome) in outcomes {
+        match outcome {
+            Ok(_) => local_ok.push(id),
+            Err(error) => results.push(serde_json::json!({
+                "entityType": entity.as_str(),
+                "id": id,
+                "success": false,
+                "error": error,
+            })),
+        }
+    }
+
+    let mut any_remote = false;
+    let mut offline = false;
+    for batch in local_ok.chunks(MENU_BULK_REMOTE_BATCH) {
+        let remote: Vec<Result<serde_json::Value, String>> = if offline {
+            batch
+                .iter()
+                .map(|_| Err("Skipped: admin unreachable".to_string()))
+                .collect()
+        } else {
+            futures_util::future::join_all(batch.iter().map(|id| {
+                let body = serde_json::json!({ "id": id, field: is_available });
+                let path = entity.remote_path(id);
+                async move { crate::admin_fetch(Some(db), &path, "PATCH", Some(body)).await }
+            }))
+            .await
+        };
+
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        for (id, response) in batch.iter().zip(remote) {
+            match response {
+                Ok(_) => {
+                    any_remote = true;
+                    availability::mark_applied(&conn, entity, id)?;
+                    results.push(serde_json::json!({
+                        "entityType": entity.as_str(),
+                        "id": id,
+                        "success": true,
+                        "remote": "applied",
+                    }));
+                }
+                Err(error) => {
+                    offline = offline || is_menu_connectivity_error(&error);
+                    let queue_payload = serde_json::json!({ "id": id, field: is_available });
+                    let queued = sync_queue::enqueue_payload_item(
+                        &conn,
+                        entity.queue_table(),
+                        id,
+                        "UPDATE",
+                        &queue_payload,
+                        Some(0),
+                        Some("catalog"),
+                        Some("manual"),
+                        Some(1),
+                    );
+                    results.push(match queued {
+                        Ok(queue_id) => serde_json::json!({
+                            "entityType": entity.as_str(),
+                            "id": id,
+                            "success": true,
+                            "remote": "queued",
+                            "queueId": queue_id,
+                            "remoteError": error,
+                        }),
+                        Err(queue_error) => serde_json::json!({
+                            "entityType": entity.as_str(),
+                            "id": id,
+                            "success": false,
+                            "remote": "failed",
+                            "error": format!("{error}; queue: {queue_error}"),
+                        }),
+                    });
+                }
+            }
+        }
+    }
+    Ok((results, any_remote))
+}
+
+/// One menu re-sync after a bulk change, instead of one per item.
+async fn resync_after_bulk_update(
+    db: &db::DbState,
+    app: &tauri::AppHandle,
+    any_remote: bool,
+) -> serde_json::Value {
+    if !any_remote {
+        return serde_json::json!({ "skipped": true });
+    }
+    match menu::sync_menu(db).await {
+        Ok(result) => {
+            let (updated, version, counts, timestamp) = menu_sync_snapshot(&result);
+            emit_order_prices_stale_event(app, &result);
+            emit_menu_sync_event(
+                app,
+                "menu_bulk_availability",
+                updated,
+                &version,
+                &counts,
+                &timestamp,
+            );
+            serde_json::json!({ "success": true, "updated": updated, "version": version })
+        }
+        Err(error) => {
+            warn!(error = %error, "menu bulk availability: re-sync failed");
+            serde_json::json!({ "success": false, "error": error })
+        }
+    }
+}
+
+fn bulk_availability_response(
+    operation_id: &str,
+    is_available: bool,
+    results: Vec<serde_json::Value>,
+    resync: serde_json::Value,
+) -> serde_json::Value {
+    let failed = results
+        .iter()
+        .filter(|result| result["success"] != true)
+        .count();
+    let queued = results
+        .iter()
+        .filter(|result| result["remote"] == "queued")
+        .count();
+    serde_json::json!({
+        "success": failed == 0,
+        "operationId": operation_id,
+        "isAvailable": is_available,
+        "updated": results.len() - failed,
+        "failed": failed,
+        "queued": queued,
+        "results": results,
+        "resync": resync,
+    })
+}
+
+fn emit_bulk_availability_events(app: &tauri::AppHandle, response: &serde_json::Value) {
+    let _ = app.emit(
+        "menu_sync",
+        serde_json::json!({
+            "action": "bulk_availability",
+            "operationId": response.get("operationId"),
+            "isAvailable": response.get("isAvailable"),
+        }),
+    );
+    let queued = response["queued"].as_u64().unwrap_or(0);
+    if queued > 0 {
+        let _ = app.emit(
+            "sync:status",
+            serde_json::json!({ "queuedRemote": queued, "moduleType": "catalog" }),
+        );
+    }
+}
+
+/// Switch many categories, subcategories or ingredients on or off at once,
+/// e.g. "no more fried items" at close. Takes explicit `ids`, a
+/// `parentCategoryId` meaning all of that category's children, or both.
+#[tauri::command]
+pub async fn menu_bulk_update_availability(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let (entity, payload) = parse_menu_bulk_availability_payload(arg0)?;
+    let ids = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        availability::resolve_targets(
+            &conn,
+            entity,
+            &payload.ids,
+            payload.parent_category_id.as_deref(),
+        )?
+    };
+    let operation_id = uuid::Uuid::new_v4().to_string();
+    let actor = auth::current_staff_id(&auth_state);
+    let (results, any_remote) = apply_bulk_availability(
+        &db,
+        entity,
+        &ids,
+        payload.is_available,
+        &operation_id,
+        actor.as_deref(),
+    )
+    .await?;
+    let resync = resync_after_bulk_update(&db, &app, any_remote).await;
+
+    info!(
+        operation_id = %operation_id,
+        entity_type = entity.as_str(),
+        count = ids.len(),
+        is_available = payload.is_available,
+        "menu bulk availability applied"
+    );
+    let mut response =
+        bulk_availability_response(&operation_id, payload.is_available, results, resync);
+    response["entityType"] = serde_json::json!(entity.as_str());
+    emit_bulk_availability_events(&app, &response);
+    Ok(response)
+}
+
+/// Re-enable everything a bulk operation disabled, except entries a later
+/// operation has changed since.
+#[tauri::command]
+pub async fn menu_bulk_restore_availability(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let source_operation_id =
+        crate::payload_arg0_as_string(arg0, &["operationId", "operation_id", "id"])
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+            .ok_or("Missing operationId")?;
+    let targets = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        availability::disabled_by_operation(&conn, &source_operation_id)?
+    };
+
+    let operation_id = uuid::Uuid::new_v4().to_string();
+    let actor = auth::current_staff_id(&auth_state);
+    let mut results = Vec::new();
+    let mut any_remote = false;
+    for entity in MenuEntity::ALL {
+        let ids: Vec<String> = targets
+            .iter()
+            .filter(|(target, _)| *target == entity)
+            .map(|(_, id)| id.clone())
+            .collect();
+        if ids.is_empty() {
+            continue;
+        }
+        let (entity_results, entity_remote) =
+            apply_bulk_availability(&db, entity, &ids, true, &operation_id, actor.as_deref())
+                .await?;
+        results.extend(entity_results);
+        any_remote |= entity_remote;
+    }
+    let resync = resync_after_bulk_update(&db, &app, any_remote).await;
+
+    info!(
+        operation_id = %operation_id,
+        restored_operation_id = %source_operation_id,
+        count = targets.len(),
+        "menu bulk availability restored"
+    );
+    let mut response = bulk_availability_response(&operation_id, true, results, resync);
+    response["restoredOperationId"] = serde_json::json!(source_operation_id);
+    emit_bulk_availability_events(&app, &response);
+    Ok(response)
+}
+
 #[tauri::command]
 pub async fn menu_trigger_check_for_updates(
     app: tauri::AppHandle,
@@ -1011,10 +1375,155 @@ pub async fn menu_trigger_check_for_updates(
     Ok(serde_json::json!({ "success": true }))
 }
 
+#[derive(Debug, PartialEq)]
+struct FavoritesScope {
+    terminal_id: String,
+    staff_id: Option<String>,
+}
+
+fn parse_favorites_scope(payload: &serde_json::Value) -> Result<FavoritesScope, String> {
+    let terminal_id = value_str(payload, &["terminalId", "terminal_id"])
+        .unwrap_or_else(crate::order_locks::local_terminal_id);
+    if terminal_id.trim().is_empty() {
+        return Err("Terminal is not configured".into());
+    }
+    Ok(FavoritesScope {
+        terminal_id: terminal_id.trim().to_string(),
+        staff_id: value_str(payload, &["staffId", "staff_id"])
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty()),
+    })
+}
+
+fn favorites_grid_json(
+    terminal_id: &str,
+    loaded: Option<(favorites::Grid, String, String)>,
+) -> serde_json::Value {
+    match loaded {
+        Some((grid, owner, updated_at)) => serde_json::json!({
+            "terminalId": terminal_id,
+            "staffId": (!owner.is_empty()).then_some(owner),
+            "grid": grid,
+            "updatedAt": updated_at,
+        }),
+        None => serde_json::Value::Null,
+    }
+}
+
+/// The favorites grid for this terminal: the staff member's own when
+/// `staffId` has one, otherwise the terminal default. `data` is null when
+/// nothing has been saved.
+#[tauri::command]
+pub async fn favorites_get_grid(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let scope = parse_favorites_scope(&arg0.unwrap_or(serde_json::Value::Null))?;
+    let loaded =
+        db.read(|conn| favorites::load(conn, &scope.terminal_id, scope.staff_id.as_deref()))?;
+    Ok(serde_json::json!({
+        "success": true,
+        "data": favorites_grid_json(&scope.terminal_id, loaded),
+    }))
+}
+
+/// Save the favorites grid for this terminal, or for `staffId` on it.
+#[tauri::command]
+pub async fn favorites_set_grid(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let payload = arg0.ok_or("Missing favorites payload")?;
+    let scope = parse_favorites_scope(&payload)?;
+    let grid = favorites::parse(
+        payload
+            .get("grid")
+            .or_else(|| payload.get("layout"))
+            .ok_or("Missing grid")?,
+    )?;
+    let loaded = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        favorites::save(
+            &conn,
+            &scope.terminal_id,
+            scope.staff_id.as_deref(),
+            &grid,
+            &Utc::now().to_rfc3339(),
+        )?;
+        favorites::load(&conn, &scope.terminal_id, scope.staff_id.as_deref())?
+    };
+    let _ = app.emit(
+        favorites::UPDATED_EVENT,
+        serde_json::json!({
+            "terminalId": scope.terminal_id,
+            "staffId": scope.staff_id,
+        }),
+    );
+    Ok(serde_json::json!({
+        "success": true,
+        "data": favorites_grid_json(&scope.terminal_id, loaded),
+    }))
+}
+
+/// Suggest a one-page grid from the best sellers of the last 30 days. The
+/// suggestion is not saved.
+#[tauri::command]
+pub async fn favorites_autofill(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let payload = arg0.unwrap_or(serde_json::Value::Null);
+    let rows = crate::value_i64(&payload, &["rows"]).map_or(favorites::AUTOFILL_ROWS, |v| {
+        v.clamp(1, favorites::MAX_ROWS as i64) as u32
+    });
+    let cols = crate::value_i64(&payload, &["cols", "columns"])
+        .map_or(favorites::AUTOFILL_COLS, |v| {
+            v.clamp(1, favorites::MAX_COLS as i64) as u32
+        });
+    let branch_id = crate::branches::report_scope(value_str(&payload, &["branchId", "branch_id"]));
+    let grid = db.read(|conn| {
+        let top = super::analytics::top_items_for_recent_days(
+            conn,
+            &branch_id,
+            favorites::AUTOFILL_DAYS,
+            (rows * cols) as usize * 2,
+        )?;
+        let items = crate::combos::read_section(conn, "subcategories");
+        Ok(favorites::autofill(&top, &items, rows, cols))
+    })?;
+    Ok(serde_json::json!({
+        "success": true,
+        "data": { "grid": grid, "days": favorites::AUTOFILL_DAYS },
+    }))
+}
+
 #[cfg(test)]
 mod dto_tests {
     use super::*;
 
+    #[test]
+    fn parse_favorites_scope_reads_terminal_and_staff() {
+        let scope = parse_favorites_scope(&serde_json::json!({
+            "terminalId": " term-1 ",
+            "staffId": "",
+        }))
+        .unwrap();
+        assert_eq!(
+            scope,
+            FavoritesScope {
+                terminal_id: "term-1".to_string(),
+                staff_id: None,
+            }
+        );
+        let scope = parse_favorites_scope(&serde_json::json!({
+            "terminal_id": "term-1",
+            "staff_id": "staff-7",
+        }))
+        .unwrap();
+        assert_eq!(scope.staff_id.as_deref(), Some("staff-7"));
+    }
+
     #[test]
     fn parse_menu_subcategory_payload_supports_string_and_object() {
         let from_string = parse_menu_subcategory_payload(Some(serde_json::json!("sub-1")))
@@ -1027,6 +1536,34 @@ mod dto_tests {
         assert_eq!(from_object, "sub-2");
     }
 
+    #[test]
+    fn parse_menu_bulk_availability_payload_requires_targets() {
+        let (entity, parsed) = parse_menu_bulk_availability_payload(Some(serde_json::json!({
+            "entityType": "subcategory",
+            "parentCategoryId": "fried",
+            "isAvailable": false
+        })))
+        .expect("parent category payload should parse");
+        assert_eq!(entity, MenuEntity::Subcategory);
+        assert_eq!(parsed.parent_category_id.as_deref(), Some("fried"));
+        assert!(!parsed.is_available);
+
+        let err = parse_menu_bulk_availability_payload(Some(serde_json::json!({
+            "entityType": "ingredient",
+            "isAvailable": false
+        })))
+        .expect_err("no ids or parent should fail");
+        assert!(err.contains("parentCategoryId"));
+        assert!(
+            parse_menu_bulk_availability_payload(Some(serde_json::json!({
+                "entityType": "combo",
+                "ids": ["c1"],
+                "isAvailable": false
+            })))
+            .is_err()
+        );
+    }
+
     #[test]
     fn parse_menu_category_update_payload_supports_legacy_tuple() {
         let parsed = parse_menu_category_update_payload(
@@ -2,25 +2,36 @@ pub mod address_offline;
 pub mod analytics;
 pub mod api_bridge;
 pub mod auth;
+pub mod auto_accept;
 pub mod branch_data;
 pub mod callerid;
 pub mod customers;
 pub mod diagnostics;
+pub mod dispatch;
 pub mod ecr;
+pub mod fiscal;
 pub mod hardware;
+pub mod inventory;
 pub mod loyalty;
 pub mod menu;
 pub mod modules;
 pub mod offline_mutations;
+pub mod onboarding;
 pub mod orders;
+pub mod organization_change;
 pub mod payments;
+pub mod pricing_rules;
 pub mod print;
+pub mod reasons;
 pub mod recovery;
+pub mod reservations;
+pub mod retention;
 pub mod runtime;
 pub mod settings;
 pub mod shifts;
 pub mod sync;
 pub mod sync_queue;
 pub mod system_ui;
+pub mod terminal_repair;
 pub mod updates;
 pub mod zreports;
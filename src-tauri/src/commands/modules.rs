@@ -2,7 +2,7 @@ use chrono::Utc;
 use serde::Deserialize;
 use tauri::Emitter;
 
-use crate::{db, storage};
+use crate::{auth, db, features, storage};
 
 #[derive(Debug, Default)]
 struct ModulesSaveCachePayload {
@@ -250,7 +250,10 @@ pub async fn modules_fetch_from_admin(
                 "timestamp": Utc::now().timestamp_millis(),
                 "apiTimestamp": api_timestamp,
             });
-            let _ = crate::write_module_cache(&db, &cache_payload);
+            let cache_to_write = cache_payload.clone();
+            let _ = db
+                .run_blocking(move |db| crate::write_module_cache(db, &cache_to_write))
+                .await;
             let _ = app.emit(
                 "modules_sync_complete",
                 serde_json::json!({
@@ -305,7 +308,7 @@ pub async fn modules_fetch_from_admin(
             emit_modules_sync_error(&app, &payload);
             Ok(payload)
         }
-        Err(fetch_err) => match crate::read_module_cache(&db) {
+        Err(fetch_err) => match db.run_blocking(crate::read_module_cache).await {
             Ok(cache) => {
                 let (current_org, current_terminal, current_admin_url) =
                     current_module_identity(&db);
@@ -391,7 +394,7 @@ pub async fn modules_fetch_from_admin(
 pub async fn modules_get_cached(
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
-    let cache = match crate::read_module_cache(&db) {
+    let cache = match db.run_blocking(crate::read_module_cache).await {
         Ok(c) => c,
         Err(_) => {
             return Ok(serde_json::json!({
@@ -486,7 +489,9 @@ pub async fn modules_save_cache(
         "timestamp": Utc::now().timestamp_millis(),
         "apiTimestamp": api_timestamp,
     });
-    crate::write_module_cache(&db, &cache_payload)?;
+    let cache_to_write = cache_payload.clone();
+    db.run_blocking(move |db| crate::write_module_cache(db, &cache_to_write))
+        .await?;
     let _ = app.emit(
         "modules_refresh_needed",
         serde_json::json!({
@@ -501,10 +506,159 @@ pub async fn modules_save_cache(
     Ok(serde_json::json!({ "success": true }))
 }
 
+fn parse_feature_override_payload(
+    arg0: Option<serde_json::Value>,
+    require_enabled: bool,
+) -> Result<(String, Option<bool>), String> {
+    let payload = match arg0 {
+        Some(serde_json::Value::String(id)) => serde_json::json!({ "featureId": id }),
+        Some(value) => value,
+        None => return Err("Missing featureId".into()),
+    };
+    let feature_id = payload
+        .get("featureId")
+        .or_else(|| payload.get("feature_id"))
+        .or_else(|| payload.get("moduleId"))
+        .or_else(|| payload.get("module_id"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .ok_or("Missing featureId")?;
+    let enabled = payload.get("enabled").and_then(|v| v.as_bool());
+    if require_enabled && enabled.is_none() {
+        return Err("Missing enabled flag".into());
+    }
+    Ok((feature_id, enabled))
+}
+
+fn emit_feature_overrides_changed(app: &tauri::AppHandle, feature_id: &str) {
+    let _ = app.emit(
+        "modules_refresh_needed",
+        serde_json::json!({ "source": "feature_override", "featureId": feature_id }),
+    );
+}
+
+#[tauri::command]
+pub async fn features_get_all(
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let cache = db.run_blocking(crate::read_module_cache).await.ok();
+    let (current_org, current_terminal, current_admin_url) = current_module_identity(&db);
+    let identity_match = cache.as_ref().is_some_and(|c| {
+        cache_identity_matches(c, &current_org, &current_terminal, &current_admin_url)
+    });
+    let cache_age = cache.as_ref().map(cache_age_ms);
+    let stale = cache.as_ref().map_or(true, cache_is_stale) || !identity_match;
+    let overrides = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        features::load_overrides(&conn)
+    };
+    Ok(serde_json::json!({
+        "success": true,
+        "features": features::resolve(cache.as_ref(), &overrides, stale),
+        "overrides": overrides,
+        "hasModuleCache": cache.is_some(),
+        "cacheAgeMs": cache_age,
+        "stale": stale,
+        "identityMatch": identity_match,
+    }))
+}
+
+#[tauri::command]
+pub async fn features_set_override(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<serde_json::Value, auth::GuardedCommandError> {
+    auth::authorize_privileged_action(
+        auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let (feature_id, enabled) = parse_feature_override_payload(arg0, true)?;
+    let actor = auth::current_staff_id(&auth_state);
+    let changed = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        features::write_override(
+            &conn,
+            &feature_id,
+            enabled,
+            "features_set_override",
+            actor.as_deref(),
+        )?
+    };
+    if changed {
+        emit_feature_overrides_changed(&app, &feature_id);
+    }
+    Ok(serde_json::json!({
+        "success": true,
+        "featureId": feature_id,
+        "enabled": enabled,
+        "changed": changed,
+    }))
+}
+
+#[tauri::command]
+pub async fn features_clear_override(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<serde_json::Value, auth::GuardedCommandError> {
+    auth::authorize_privileged_action(
+        auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let (feature_id, _) = parse_feature_override_payload(arg0, false)?;
+    let actor = auth::current_staff_id(&auth_state);
+    let changed = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        features::write_override(
+            &conn,
+            &feature_id,
+            None,
+            "features_clear_override",
+            actor.as_deref(),
+        )?
+    };
+    if changed {
+        emit_feature_overrides_changed(&app, &feature_id);
+    }
+    Ok(serde_json::json!({
+        "success": true,
+        "featureId": feature_id,
+        "changed": changed,
+    }))
+}
+
 #[cfg(test)]
 mod dto_tests {
     use super::*;
 
+    #[test]
+    fn parse_feature_override_payload_requires_id_and_flag() {
+        assert_eq!(
+            parse_feature_override_payload(
+                Some(serde_json::json!({ "featureId": " kiosk ", "enabled": true })),
+                true
+            )
+            .unwrap(),
+            ("kiosk".to_string(), Some(true))
+        );
+        assert_eq!(
+            parse_feature_override_payload(Some(serde_json::json!("kiosk")), false).unwrap(),
+            ("kiosk".to_string(), None)
+        );
+        assert!(parse_feature_override_payload(
+            Some(serde_json::json!({ "featureId": "kiosk" })),
+            true
+        )
+        .is_err());
+        assert!(parse_feature_override_payload(Some(serde_json::json!({})), false).is_err());
+    }
+
     #[test]
     fn parse_modules_save_cache_payload_supports_array_payload() {
         let parsed = parse_modules_save_cache_payload(Some(serde_json::json!([
@@ -213,6 +213,11 @@ where
         }
     }
 
+    // The read-through proxy cache is not patched in place; drop its copies
+    // so the next read refetches.
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    crate::remote_cache::invalidate_prefix(&conn, prefix)?;
+
     Ok(())
 }
 
@@ -653,6 +658,9 @@ pub async fn offline_reservation_create(
 
     let queue_id = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        if let Err(e) = crate::reservations::store_local(&conn, &reservation) {
+            tracing::warn!(error = %e, "Could not store reservation in the local cache");
+        }
         enqueue_parity_item(
             &conn,
             "reservations",
@@ -705,6 +713,9 @@ pub async fn offline_reservation_update(
     let queue_payload = Value::Object(queue_object);
     let queue_id = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        if let Err(e) = crate::reservations::store_local(&conn, &reservation) {
+            tracing::warn!(error = %e, "Could not store reservation in the local cache");
+        }
         enqueue_parity_item(
             &conn,
             "reservations",
@@ -1395,10 +1406,7 @@ mod offline_room_checkin_tests {
         let conn = Connection::open_in_memory().expect("open in-memory db");
         db::run_migrations_for_test(&conn);
         sync_queue::create_tables(&conn).expect("create parity queue tables");
-        db::DbState {
-            conn: Mutex::new(conn),
-            db_path: PathBuf::from(":memory:"),
-        }
+        db::DbState::new(conn, PathBuf::from(":memory:"))
     }
 
     fn seed_rooms_cache(db: &db::DbState, path: &str) {
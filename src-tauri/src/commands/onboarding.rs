@@ -0,0 +1,165 @@
+use serde_json::Value;
+
+use crate::onboarding::{self, StepResult};
+use crate::{api, db, payload_arg0_as_string};
+
+pub(crate) fn parse_connection_code_payload(arg0: Option<Value>) -> Result<String, String> {
+    payload_arg0_as_string(
+        arg0,
+        &["connectionCode", "connection_code", "code", "apiKey"],
+    )
+    .map(|code| code.trim().to_string())
+    .filter(|code| !code.is_empty())
+    .ok_or("Missing connectionCode".into())
+}
+
+pub(crate) async fn admin_reachable_step(endpoint: Option<&(String, String)>) -> StepResult {
+    let Some((admin_url, api_key)) = endpoint else {
+        return StepResult::failed(
+            onboarding::STEP_ADMIN_REACHABLE,
+            "not_configured",
+            "Apply a connection code first",
+        );
+    };
+    let result = api::test_connectivity(admin_url, api_key).await;
+    if result.success {
+        StepResult::ok(onboarding::STEP_ADMIN_REACHABLE)
+    } else {
+        onboarding::admin_step(
+            onboarding::STEP_ADMIN_REACHABLE,
+            Err(result
+                .error
+                .unwrap_or_else(|| "Admin dashboard unreachable".into())),
+        )
+    }
+}
+
+/// Every required step, with a live reachability probe of the admin.
+async fn collect_steps(db: &db::DbState) -> Result<Vec<StepResult>, String> {
+    crate::hydrate_terminal_credentials_from_local_settings(db);
+    let (connection, endpoint) = onboarding::stored_connection_step();
+    let admin = admin_reachable_step(endpoint.as_ref()).await;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(vec![
+        connection,
+        admin,
+        onboarding::terminal_settings_step(&conn),
+        onboarding::printer_step(&conn),
+        onboarding::admin_pin_step(&conn),
+    ])
+}
+
+#[tauri::command]
+pub async fn onboarding_get_state(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
+    let steps = collect_steps(&db).await?;
+    let completed = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        onboarding::is_completed(&conn)
+    };
+    Ok(onboarding::state_json(&steps, completed))
+}
+
+/// Decode a connection code, store its credentials, check the admin is
+/// reachable and pull this terminal's settings. Stops at the first failing
+/// step and reports the results so far.
+#[tauri::command]
+pub async fn onboarding_apply_connection_code(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+    sync_state: tauri::State<'_, std::sync::Arc<crate::sync::SyncState>>,
+) -> Result<Value, String> {
+    let code = zeroize::Zeroizing::new(parse_connection_code_payload(arg0)?);
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        if onboarding::is_completed(&conn) {
+            return Ok(serde_json::json!({
+                "success": false,
+                "errorCode": "onboarding_already_completed",
+                "error": "This terminal is already set up; change its connection from settings",
+            }));
+        }
+    }
+
+    let decoded = onboarding::decode_connection_code(&code);
+    let mut steps = vec![onboarding::validate_connection_code(&decoded)];
+    let respond = |steps: &[StepResult]| {
+        serde_json::json!({
+            "success": onboarding::all_complete(steps),
+            "steps": steps,
+        })
+    };
+    if !steps[0].complete {
+        return Ok(respond(&steps));
+    }
+
+    crate::commands::settings::store_terminal_credentials(
+        &db,
+        &serde_json::json!({ "apiKey": code.as_str() }),
+    )?;
+
+    let endpoint = decoded.admin_url.clone().zip(decoded.api_key.clone());
+    let admin = admin_reachable_step(endpoint.as_ref()).await;
+    let reachable = admin.complete;
+    steps.push(admin);
+    if !reachable {
+        return Ok(respond(&steps));
+    }
+
+    let fetched = crate::commands::settings::refresh_terminal_context_from_admin(&db).await;
+    let fetched_ok = fetched.is_ok();
+    let mut settings_step = onboarding::admin_step(onboarding::STEP_TERMINAL_SETTINGS, fetched);
+    if fetched_ok {
+        sync_state.clear_remote_auth_pause();
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        settings_step = onboarding::terminal_settings_step(&conn);
+    }
+    steps.push(settings_step);
+
+    crate::commands::settings::announce_terminal_credentials_updated(
+        &app,
+        &db,
+        "onboarding_apply_connection_code",
+    );
+    crate::scrub_sensitive_local_settings(&db);
+    Ok(respond(&steps))
+}
+
+/// Persist `onboarding.completed` once every required step passes.
+#[tauri::command]
+pub async fn onboarding_complete(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
+    let steps = collect_steps(&db).await?;
+    if !onboarding::all_complete(&steps) {
+        return Ok(serde_json::json!({
+            "success": false,
+            "errorCode": "onboarding_incomplete",
+            "error": "Some required setup steps have not passed",
+            "steps": steps,
+        }));
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    onboarding::mark_completed(&conn)?;
+    tracing::info!("Onboarding completed");
+    Ok(serde_json::json!({ "success": true, "completed": true, "steps": steps }))
+}
+
+#[cfg(test)]
+mod dto_tests {
+    use super::*;
+
+    #[test]
+    fn parse_connection_code_accepts_object_and_string() {
+        assert_eq!(
+            parse_connection_code_payload(Some(serde_json::json!({
+                "connectionCode": "  abc  "
+            })))
+            .unwrap(),
+            "abc"
+        );
+        assert_eq!(
+            parse_connection_code_payload(Some(serde_json::json!("xyz"))).unwrap(),
+            "xyz"
+        );
+        assert!(parse_connection_code_payload(Some(serde_json::json!({}))).is_err());
+    }
+}
@@ -3,13 +3,18 @@ use rusqlite::OptionalExtension;
 use serde::Deserialize;
 use serde_json::Value;
 use std::time::Duration;
-use tauri::Emitter;
+use tauri::{Emitter, Manager};
 
-use crate::money::Cents;
+use crate::money::{self, Cents, RoundingRule};
+use crate::supabase::{self, SupabaseQuery};
+use crate::sync::order_schema;
 use crate::{
-    can_transition_locally, db, fetch_supabase_rows, normalize_status_for_storage, order_ownership,
-    payload_arg0_as_string, payment_integrity, payments, print, read_local_json_array, refunds,
-    resolve_order_id, storage, sync, value_f64, value_i64, value_str, write_local_json,
+    allergens, auto_accept, can_transition_locally, combos, db, idempotency, inventory, kiosk,
+    normalize_status_for_storage, order_aging, order_duplicate, order_eta, order_events,
+    order_locks, order_ownership, order_plugins, payload_arg0_as_string, payment_integrity,
+    payments, prep_time, pricing_rules, print, read_local_json_array, refunds, resolve_order_id,
+    resolve_order_row, returns, stale_prices, storage, sync, value_f64, value_i64, value_str,
+    write_local_json,
 };
 
 #[derive(Debug, Deserialize)]
@@ -35,6 +40,10 @@ struct OrderUpdateStatusPayload {
         alias = "reason"
     )]
     cancellation_reason: Option<String>,
+    /// Version the caller last read. When present the update only applies if
+    /// the row is still at this version.
+    #[serde(default, alias = "expected_version")]
+    expected_version: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -54,6 +63,8 @@ struct OrderUpdateItemsRawPayload {
         alias = "special_instructions"
     )]
     order_notes: Option<serde_json::Value>,
+    #[serde(default, alias = "expected_version")]
+    expected_version: Option<i64>,
 }
 
 #[derive(Debug)]
@@ -61,6 +72,7 @@ struct OrderUpdateItemsPayload {
     order_id: String,
     items: Vec<serde_json::Value>,
     order_notes: Option<String>,
+    expected_version: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -85,6 +97,12 @@ struct OrderUpdateFinancialsPayload {
     delivery_fee: Option<f64>,
     #[serde(default, alias = "tip_amount")]
     tip_amount: Option<f64>,
+    #[serde(default, alias = "expected_version")]
+    expected_version: Option<i64>,
+    #[serde(default, alias = "discount_reason_code")]
+    discount_reason_code: Option<String>,
+    #[serde(default, alias = "discount_reason")]
+    discount_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -560,38 +578,100 @@ fn enqueue_or_refresh_driver_earning_sync_row(
     Ok(())
 }
 
-fn enqueue_order_sync_payload(
+pub(crate) fn enqueue_order_sync_payload(
     conn: &rusqlite::Connection,
     order_id: &str,
     payload: &Value,
 ) -> Result<(), String> {
+    // The push carries the row version so server-side conflict detection can
+    // tell a stale edit from a fresh one.
+    let version = current_order_version(conn, order_id).unwrap_or(1);
+    let mut payload = payload.clone();
+    if let Some(obj) = payload.as_object_mut() {
+        obj.insert("version".to_string(), Value::from(version));
+    }
     crate::sync_queue::enqueue_payload_item(
         conn,
         "orders",
         order_id,
         "UPDATE",
-        payload,
+        &payload,
         Some(0),
         Some("orders"),
         Some("server-wins"),
-        Some(1),
+        Some(version),
     )
     .map(|_| ())
 }
 
+fn current_order_version(conn: &rusqlite::Connection, order_id: &str) -> Result<i64, String> {
+    conn.prepare_cached("SELECT COALESCE(version, 1) FROM orders WHERE id = ?1")
+        .and_then(|mut stmt| stmt.query_row(rusqlite::params![order_id], |row| row.get(0)))
+        .map_err(|e| format!("load order version: {e}"))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum VersionClaim {
+    Claimed(i64),
+    Conflict { current_version: i64 },
+}
+
+/// Bump `orders.version`, but only when the row is still at `expected` (any
+/// version is accepted when the caller did not send one). Run this inside the
+/// mutation's `BEGIN IMMEDIATE` so a failed write rolls the bump back too.
+fn claim_order_version(
+    conn: &rusqlite::Connection,
+    order_id: &str,
+    expected: Option<i64>,
+) -> Result<VersionClaim, String> {
+    let changed = conn
+        .prepare_cached(
+            "UPDATE orders
+             SET version = COALESCE(version, 1) + 1
+             WHERE id = ?1 AND (?2 IS NULL OR COALESCE(version, 1) = ?2)",
+        )
+        .and_then(|mut stmt| stmt.execute(rusqlite::params![order_id, expected]))
+        .map_err(|e| format!("bump order version: {e}"))?;
+    let current_version = current_order_version(conn, order_id)?;
+    if changed == 0 {
+        return Ok(VersionClaim::Conflict { current_version });
+    }
+    Ok(VersionClaim::Claimed(current_version))
+}
+
+/// Structured rejection for a stale `expectedVersion`. Call after releasing
+/// the connection lock; the response carries the current row so the UI can
+/// refresh and retry.
+fn version_conflict_response(
+    db: &db::DbState,
+    order_id: &str,
+    expected: Option<i64>,
+    current_version: i64,
+) -> Value {
+    let message = "Order was changed by another terminal. Reload it and try again.";
+    serde_json::json!({
+        "success": false,
+        "errorCode": "version_conflict",
+        "error": message,
+        "message": message,
+        "orderId": order_id,
+        "expectedVersion": expected,
+        "currentVersion": current_version,
+        "current": sync::get_order_by_id(db, order_id).ok(),
+    })
+}
+
 fn resolve_order_id_with_remote(
     conn: &rusqlite::Connection,
     order_id_raw: &str,
 ) -> Result<(String, Option<String>), String> {
-    conn.query_row(
-        "SELECT id, NULLIF(TRIM(COALESCE(supabase_id, '')), '')
-         FROM orders
-         WHERE id = ?1 OR supabase_id = ?1
-         LIMIT 1",
-        rusqlite::params![order_id_raw],
+    resolve_order_row(
+        conn,
+        order_id_raw,
+        "id, NULLIF(TRIM(COALESCE(supabase_id, '')), '')",
         |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)),
     )
-    .map_err(|_| "Order not found".to_string())
+    .ok_or_else(|| "Order not found".to_string())
 }
 
 fn push_unique_identity(candidates: &mut Vec<String>, value: Option<String>) {
@@ -915,15 +995,12 @@ fn spawn_immediate_order_status_patch(db: &db::DbState, body: Value) {
         .to_string();
 
     tauri::async_runtime::spawn(async move {
-        let client = match reqwest::Client::builder()
-            .timeout(Duration::from_secs(8))
-            .build()
-        {
+        let client = match crate::api::shared_client() {
             Ok(client) => client,
             Err(error) => {
                 tracing::warn!(
                     error = %error,
-                    "Immediate kiosk status sync could not build HTTP client"
+                    "Immediate kiosk status sync could not get HTTP client"
                 );
                 return;
             }
@@ -934,6 +1011,7 @@ fn spawn_immediate_order_status_patch(db: &db::DbState, body: Value) {
         );
         let response = client
             .patch(url)
+            .timeout(Duration::from_secs(8))
             .header("x-pos-api-key", context.api_key)
             .header("x-terminal-id", context.terminal_id)
             .header("Content-Type", "application/json")
@@ -1030,6 +1108,7 @@ fn parse_order_update_items_payload(
         order_id,
         items: raw.items,
         order_notes,
+        expected_version: raw.expected_version,
     })
 }
 
@@ -1142,24 +1221,47 @@ fn parse_order_update_financials_payload(
     Ok(parsed)
 }
 
+/// A discount that grows past what the order already had is a comp and needs
+/// a comp reason code. A code sent with any other edit is still validated.
+fn resolve_discount_reason(
+    conn: &rusqlite::Connection,
+    order_id: &str,
+    payload: &OrderUpdateFinancialsPayload,
+    discount_amount_cents: i64,
+    discount_percentage: f64,
+) -> Result<Option<crate::reasons::ResolvedReason>, String> {
+    let (current_amount_cents, current_percentage): (i64, f64) = conn
+        .query_row(
+            "SELECT COALESCE(discount_amount_cents, CAST(ROUND(COALESCE(discount_amount, 0) * 100) AS INTEGER)),
+                    COALESCE(discount_percentage, 0)
+             FROM orders WHERE id = ?1",
+            rusqlite::params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("load order discount: {e}"))?;
+    let discount_increased =
+        discount_amount_cents > current_amount_cents || discount_percentage > current_percentage;
+    let code = normalize_optional_text(payload.discount_reason_code.clone());
+    if !discount_increased && code.is_none() {
+        return Ok(None);
+    }
+    crate::reasons::require(
+        conn,
+        crate::reasons::ReasonAction::Comp,
+        code.as_deref(),
+        payload.discount_reason.as_deref(),
+    )
+    .map(Some)
+}
+
 fn normalize_optional_text(value: Option<String>) -> Option<String> {
     value
         .map(|raw| raw.trim().to_string())
         .filter(|raw| !raw.is_empty())
 }
 
-fn compute_order_items_total(items: &[serde_json::Value]) -> f64 {
-    items
-        .iter()
-        .map(|item| {
-            let qty = value_f64(item, &["quantity"]).unwrap_or(1.0);
-            if let Some(tp) = value_f64(item, &["total_price", "totalPrice"]) {
-                tp
-            } else {
-                value_f64(item, &["unit_price", "unitPrice", "price"]).unwrap_or(0.0) * qty
-            }
-        })
-        .sum::<f64>()
+fn compute_order_items_total(items: &[serde_json::Value], rule: RoundingRule) -> Cents {
+    money::items_total_cents(items, rule)
 }
 
 fn item_text_value<'a>(item: &'a serde_json::Value, keys: &[&str]) -> Option<&'a str> {
@@ -1345,6 +1447,33 @@ fn merge_existing_order_item_customizations(
     Ok(merged)
 }
 
+/// Pricing rules are evaluated once, at the order's creation time. Lines
+/// an edit resends keep the rule they were priced with, even without the
+/// marker; lines added later are priced with the rules active when the
+/// order was created. Returns the order type and that creation time.
+fn carry_over_pricing_rules(
+    conn: &rusqlite::Connection,
+    order_id: &str,
+    items: &mut [serde_json::Value],
+) -> Result<(String, chrono::DateTime<chrono::Local>), String> {
+    let (order_type, created_at, current_items_json): (String, Option<String>, String) = conn
+        .query_row(
+            "SELECT COALESCE(order_type, 'dine-in'), created_at, COALESCE(items, '[]')
+             FROM orders WHERE id = ?1",
+            rusqlite::params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| format!("load order pricing context: {e}"))?;
+    let current_items: Vec<serde_json::Value> =
+        serde_json::from_str(&current_items_json).unwrap_or_default();
+    pricing_rules::carry_over_markers(&current_items, items, order_item_identity_matches);
+    let created_at = created_at
+        .and_then(|raw| chrono::DateTime::parse_from_rfc3339(raw.trim()).ok())
+        .map(|at| at.with_timezone(&chrono::Local))
+        .unwrap_or_else(chrono::Local::now);
+    Ok((order_type, created_at))
+}
+
 fn derive_next_order_totals(
     conn: &rusqlite::Connection,
     order_id: &str,
@@ -1365,15 +1494,25 @@ fn derive_next_order_totals(
 
     let current_items: Vec<serde_json::Value> =
         serde_json::from_str(&current_items_json).unwrap_or_default();
-    let current_items_total = compute_order_items_total(&current_items);
-    let next_items_total = compute_order_items_total(next_items);
-
-    let total_offset = current_total - current_items_total;
-    let subtotal_offset = current_subtotal - current_items_total;
+    let rule = RoundingRule::from_settings(conn);
+    let current_items_total = compute_order_items_total(&current_items, rule);
+    let next_items_total = compute_order_items_total(next_items, rule);
+
+    // Offsets (fees, tax, discounts) carried over from the stored totals.
+    // An automatic service charge is re-priced for the new items instead.
+    let total_offset = Cents::round_half_even(current_total) - current_items_total;
+    let subtotal_offset = Cents::round_half_even(current_subtotal) - current_items_total;
+    let service_charge_delta =
+        crate::service_charge::reprice(conn, order_id, next_items_total, None)?
+            .map_or(Cents::ZERO, |repriced| repriced.total_delta);
 
     Ok((
-        (next_items_total + total_offset).max(0.0),
-        (next_items_total + subtotal_offset).max(0.0),
+        (next_items_total + total_offset + service_charge_delta)
+            .max(Cents::ZERO)
+            .to_f64_dp2(),
+        (next_items_total + subtotal_offset)
+            .max(Cents::ZERO)
+            .to_f64_dp2(),
     ))
 }
 
@@ -1526,7 +1665,7 @@ fn update_order_items_in_connection(
     total_amount: f64,
     subtotal_amount: f64,
     now: &str,
-) -> Result<(), String> {
+) -> Result<f64, String> {
     let items_json = serde_json::to_string(items).map_err(|e| format!("serialize items: {e}"))?;
     // W4c dual-write: order edit total_amount + subtotal mirror onto cents.
     let total_amount_cents = Cents::round_half_even(total_amount).as_i64();
@@ -1574,8 +1713,13 @@ fn update_order_items_in_connection(
         )
         .map_err(|e| format!("update order items: {e}"))?;
     }
+    let items_total = compute_order_items_total(items, RoundingRule::from_settings(conn));
+    if let Some(repriced) = crate::service_charge::reprice(conn, order_id, items_total, None)? {
+        repriced.store(conn, order_id)?;
+    }
+    let captured = crate::tax::capture_order_breakdown(conn, order_id, items)?;
 
-    Ok(())
+    Ok(captured.total.to_f64_dp2())
 }
 
 fn net_paid_amount_from_edit_payment(payment: &serde_json::Value) -> f64 {
@@ -1605,12 +1749,15 @@ fn determine_edit_settlement_required_action(paid_total: f64, next_total: f64) -
     // (the live "no-op edit opens Extra Payment for the full unpaid total" defect).
     // Only orders that already have money applied (paid / partially paid) settle a
     // delta against what was paid.
-    if paid_total <= 0.01 {
+    let one_cent = Cents::new(1);
+    let paid_total = Cents::round_half_even(paid_total);
+    let next_total = Cents::round_half_even(next_total);
+    if paid_total <= one_cent {
         return "none";
     }
-    if paid_total + 0.01 < next_total {
+    if paid_total + one_cent < next_total {
         "collect"
-    } else if paid_total > next_total + 0.01 {
+    } else if paid_total > next_total + one_cent {
         "refund"
     } else {
         "none"
@@ -2175,7 +2322,10 @@ fn parse_pickup_to_delivery_conversion_payload(
     Ok(parsed)
 }
 
-fn resolve_driver_display_name(conn: &rusqlite::Connection, driver_id: &str) -> Option<String> {
+pub(crate) fn resolve_driver_display_name(
+    conn: &rusqlite::Connection,
+    driver_id: &str,
+) -> Option<String> {
     let driver_id = driver_id.trim();
     if driver_id.is_empty() {
         return None;
@@ -2200,7 +2350,10 @@ fn resolve_driver_display_name(conn: &rusqlite::Connection, driver_id: &str) ->
 pub async fn order_get_all(
     db: tauri::State<'_, db::DbState>,
 ) -> Result<Vec<serde_json::Value>, String> {
-    sync::get_all_orders(&db)
+    crate::diagnostics::metrics::track("order_get_all", async move {
+        db.run_blocking(sync::get_all_orders).await
+    })
+    .await
 }
 
 #[tauri::command]
@@ -2209,33 +2362,107 @@ pub async fn order_get_by_id(
     arg1: Option<String>,
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
-    let id = payload_arg0_as_string(
-        arg0,
-        &["orderId", "order_id", "id", "supabaseId", "supabase_id"],
-    )
-    .or(arg1)
-    .ok_or("Missing order ID")?;
-    let resolved_id = {
-        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    crate::diagnostics::metrics::track("order_get_by_id", async move {
+        let include_timeline = arg0
+            .as_ref()
+            .and_then(|v| {
+                v.get("includeTimeline")
+                    .or_else(|| v.get("include_timeline"))
+            })
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let id = payload_arg0_as_string(
+            arg0,
+            &["orderId", "order_id", "id", "supabaseId", "supabase_id"],
+        )
+        .or(arg1)
+        .ok_or("Missing order ID")?;
+        db.run_blocking(move |db| load_order_for_ipc(db, &id, include_timeline))
+            .await
+    })
+    .await
+}
+
+/// Open orders bucketed by age for the kitchen dashboard. Items are only
+/// parsed when `includeItems` is set; see `order_aging`.
+#[tauri::command]
+pub async fn orders_get_open_with_aging(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    crate::diagnostics::metrics::track("orders_get_open_with_aging", async move {
+        let include_items = arg0
+            .as_ref()
+            .and_then(|v| v.get("includeItems").or_else(|| v.get("include_items")))
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        db.run_blocking(move |db| {
+            db.read(|conn| order_aging::open_orders_snapshot(conn, include_items, Utc::now()))
+        })
+        .await
+    })
+    .await
+}
+
+/// Resolve a local or Supabase order id and load the order, optionally
+/// with its activity timeline.
+pub(crate) fn load_order_for_ipc(
+    db: &db::DbState,
+    id: &str,
+    include_timeline: bool,
+) -> Result<serde_json::Value, String> {
+    let resolved_id = db.read(|conn| {
         let by_local: Option<String> = conn
             .query_row(
                 "SELECT id FROM orders WHERE id = ?1 LIMIT 1",
-                rusqlite::params![id.clone()],
+                rusqlite::params![id],
                 |row| row.get(0),
             )
             .ok();
         if let Some(v) = by_local {
-            v
+            Ok(v)
         } else {
             conn.query_row(
                 "SELECT id FROM orders WHERE supabase_id = ?1 LIMIT 1",
                 rusqlite::params![id],
                 |row| row.get(0),
             )
-            .map_err(|_| "Order not found")?
+            .map_err(|_| "Order not found".to_string())
         }
-    };
-    sync::get_order_by_id(&db, &resolved_id)
+    })?;
+    let mut order = sync::get_order_by_id(db, &resolved_id)?;
+    if include_timeline {
+        let timeline = db.read(|conn| order_events::timeline(conn, &resolved_id))?;
+        if let Some(obj) = order.as_object_mut() {
+            obj.insert("timeline".to_string(), Value::Array(timeline));
+        }
+    }
+    Ok(order)
+}
+
+/// Activity timeline for one order, oldest first. Works for deleted orders
+/// too, since events are kept after the order row is gone.
+#[tauri::command]
+pub async fn order_get_timeline(
+    arg0: Option<serde_json::Value>,
+    arg1: Option<String>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let id = payload_arg0_as_string(
+        arg0,
+        &["orderId", "order_id", "id", "supabaseId", "supabase_id"],
+    )
+    .or(arg1)
+    .ok_or("Missing order ID")?;
+    db.read(|conn| {
+        let order_id = resolve_order_id(conn, &id).unwrap_or(id);
+        let events = order_events::timeline(conn, &order_id)?;
+        Ok(serde_json::json!({
+            "success": true,
+            "orderId": order_id,
+            "events": events
+        }))
+    })
 }
 
 #[tauri::command]
@@ -2252,7 +2479,7 @@ pub async fn order_get_by_customer_phone(
         .chars()
         .filter(|c| !matches!(c, ' ' | '-' | '(' | ')'))
         .collect::<String>();
-    let all_orders = sync::get_all_orders(&db)?;
+    let all_orders = db.run_blocking(sync::get_all_orders).await?;
     let filtered: Vec<serde_json::Value> = all_orders
         .into_iter()
         .filter(|o| {
@@ -2279,169 +2506,260 @@ pub async fn order_update_status(
     arg0: Option<serde_json::Value>,
     arg1: Option<String>,
     db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
     app: tauri::AppHandle,
 ) -> Result<serde_json::Value, String> {
-    let payload = parse_order_update_status_payload(arg0, arg1)?;
-    let order_id_raw = payload.order_id;
-    let status = normalize_status_for_storage(&payload.status);
-    let estimated_time = payload.estimated_time;
-    // Only honor cancellation_reason when the transition is actually to
-    // cancelled. For other transitions (e.g. complete -> delivered) we never
-    // overwrite the existing reason column.
-    let cancellation_reason: Option<String> = if status == "cancelled" {
-        payload
-            .cancellation_reason
-            .as_deref()
-            .map(str::trim)
-            .filter(|s| !s.is_empty())
-            .map(|s| s.to_string())
-    } else {
-        None
-    };
-    let now = Utc::now().to_rfc3339();
+    crate::diagnostics::metrics::track("order_update_status", async move {
+        let payload = parse_order_update_status_payload(arg0, arg1)?;
+        let actor = crate::auth::current_staff_id(&auth_state);
+        let order_id_raw = payload.order_id;
+        let status = normalize_status_for_storage(&payload.status);
+        let estimated_time = payload.estimated_time;
+        let expected_version = payload.expected_version;
+        // Only honor cancellation_reason when the transition is actually to
+        // cancelled. For other transitions (e.g. complete -> delivered) we never
+        // overwrite the existing reason column.
+        let cancellation_reason: Option<String> = if status == "cancelled" {
+            payload
+                .cancellation_reason
+                .as_deref()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+        } else {
+            None
+        };
+        let now = Utc::now().to_rfc3339();
 
-    let (actual_order_id, remote_order_id) = {
-        let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        resolve_order_id_with_remote(&conn, &order_id_raw)?
-    };
+        let (actual_order_id, remote_order_id) = {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            resolve_order_id_with_remote(&conn, &order_id_raw)?
+        };
 
-    {
-        let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        let previous_status =
-            ensure_order_status_transition_allowed(&conn, &actual_order_id, &status)?;
-        if status_requires_payment_integrity_guard(&status) {
-            let blockers = payment_integrity::load_order_payment_blockers(&conn, &actual_order_id)?;
-            if !blockers.is_empty() {
-                let action_label = if status == "delivered" {
-                    "Cannot mark order as delivered"
+        let (new_version, low_stock) = {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            if let Some(locked) = order_locks::guard_order_unlocked(&conn, &actual_order_id)? {
+                return Ok(locked);
+            }
+            let previous_status =
+                ensure_order_status_transition_allowed(&conn, &actual_order_id, &status)?;
+            if status_requires_payment_integrity_guard(&status) {
+                let blockers = payment_integrity::load_order_payment_blockers(&conn, &actual_order_id)?;
+                if !blockers.is_empty() {
+                    let action_label = if status == "delivered" {
+                        "Cannot mark order as delivered"
+                    } else {
+                        "Cannot mark order as completed"
+                    };
+                    return Ok(payment_integrity::build_unsettled_payment_blocker_response(
+                        action_label,
+                        &blockers,
+                    ));
+                }
+            }
+            conn.execute_batch("BEGIN IMMEDIATE")
+                .map_err(|e| format!("begin transaction: {e}"))?;
+            let result = (|| -> Result<Result<(i64, Vec<Value>), i64>, String> {
+                let new_version =
+                    match claim_order_version(&conn, &actual_order_id, expected_version)? {
+                        VersionClaim::Claimed(version) => version,
+                        VersionClaim::Conflict { current_version } => {
+                            return Ok(Err(current_version))
+                        }
+                    };
+                let was_cancelled = previous_status == "cancelled";
+                let next_is_cancelled = status == "cancelled";
+                let is_cancellation_reactivation = was_cancelled && status == "pending";
+
+                if !was_cancelled && next_is_cancelled {
+                    order_ownership::reverse_order_drawer_attribution(&conn, &actual_order_id, &now)?;
+                    conn.execute(
+                        "UPDATE orders SET cancelled_by = ?1 WHERE id = ?2",
+                        rusqlite::params![actor, actual_order_id],
+                    )
+                    .map_err(|e| format!("record order canceller: {e}"))?;
+                }
+
+                if let Some(reason) = cancellation_reason.as_deref() {
+                    conn.execute(
+                        "UPDATE orders
+                         SET status = ?1,
+                             cancellation_reason = ?2,
+                             sync_status = 'pending',
+                             updated_at = ?3
+                         WHERE id = ?4",
+                        rusqlite::params![status, reason, now, actual_order_id],
+                    )
+                    .map_err(|e| format!("update order status: {e}"))?;
+                } else if is_cancellation_reactivation {
+                    conn.execute(
+                        "UPDATE orders
+                         SET status = ?1,
+                             cancellation_reason = NULL,
+                             cancelled_by = NULL,
+                             sync_status = 'pending',
+                             updated_at = ?2
+                         WHERE id = ?3",
+                        rusqlite::params![status, now, actual_order_id],
+                    )
+                    .map_err(|e| format!("update order status: {e}"))?;
                 } else {
-                    "Cannot mark order as completed"
+                    conn.prepare_cached(
+                        "UPDATE orders
+                         SET status = ?1, sync_status = 'pending', updated_at = ?2
+                         WHERE id = ?3",
+                    )
+                    .and_then(|mut stmt| stmt.execute(rusqlite::params![status, now, actual_order_id]))
+                    .map_err(|e| format!("update order status: {e}"))?;
+                }
+                if let Some(eta) = estimated_time {
+                    let _ = conn.execute(
+                        "UPDATE orders SET estimated_time = ?1, updated_at = ?2 WHERE id = ?3",
+                        rusqlite::params![eta, now, actual_order_id],
+                    );
+                }
+                let prep_stamp = match status.as_str() {
+                    "confirmed" | "preparing" => {
+                        prep_time::mark_confirmed(&conn, &actual_order_id, &now)
+                    }
+                    "ready" => prep_time::record_ready(&conn, &actual_order_id, Utc::now()).map(|_| ()),
+                    _ => Ok(()),
                 };
-                return Ok(payment_integrity::build_unsettled_payment_blocker_response(
-                    action_label,
-                    &blockers,
-                ));
+                if let Err(e) = prep_stamp {
+                    tracing::warn!(order_id = %actual_order_id, error = %e, "Failed to record prep time");
+                }
+                let mut sync_payload = serde_json::json!({
+                    "orderId": actual_order_id,
+                    "status": status,
+                    "estimatedTime": estimated_time
+                });
+                if let Some(reason) = cancellation_reason.as_deref() {
+                    // Send under both keys so whichever convention the server reads is
+                    // satisfied (admin-dashboard inspects both shapes).
+                    if let Some(obj) = sync_payload.as_object_mut() {
+                        obj.insert(
+                            "cancellation_reason".to_string(),
+                            serde_json::Value::String(reason.to_string()),
+                        );
+                        obj.insert(
+                            "cancellationReason".to_string(),
+                            serde_json::Value::String(reason.to_string()),
+                        );
+                        obj.insert(
+                            "cancelled_at".to_string(),
+                            serde_json::Value::String(now.clone()),
+                        );
+                    }
+                } else if is_cancellation_reactivation {
+                    if let Some(obj) = sync_payload.as_object_mut() {
+                        obj.insert("cancellation_reason".to_string(), serde_json::Value::Null);
+                        obj.insert("cancellationReason".to_string(), serde_json::Value::Null);
+                        obj.insert("cancelled_at".to_string(), serde_json::Value::Null);
+                        obj.insert("cancelledAt".to_string(), serde_json::Value::Null);
+                    }
+                }
+                let _ = enqueue_order_sync_payload(&conn, &actual_order_id, &sync_payload);
+                if let Err(e) = order_plugins::enqueue_status_ack(
+                    &conn,
+                    &actual_order_id,
+                    &status,
+                    cancellation_reason.as_deref(),
+                    estimated_time,
+                ) {
+                    tracing::warn!(order_id = %actual_order_id, error = %e, "Failed to queue platform status callback");
+                }
+                order_events::append(
+                    &conn,
+                    &actual_order_id,
+                    order_events::STATUS_CHANGED,
+                    actor.as_deref(),
+                    serde_json::json!({
+                        "from": previous_status,
+                        "to": status,
+                        "cancellationReason": cancellation_reason,
+                        "version": new_version
+                    }),
+                );
+                let low_stock = if inventory::status_reaches_confirmed(&status) {
+                    inventory::deduct_for_order_logged(
+                        &conn,
+                        &actual_order_id,
+                        inventory::TRIGGER_CONFIRMED,
+                        actor.as_deref(),
+                    )
+                } else {
+                    Vec::new()
+                };
+                Ok(Ok((new_version, low_stock)))
+            })();
+            match result {
+                Ok(Ok(outcome)) => {
+                    conn.execute_batch("COMMIT")
+                        .map_err(|e| format!("commit: {e}"))?;
+                    outcome
+                }
+                Ok(Err(current_version)) => {
+                    let _ = conn.execute_batch("ROLLBACK");
+                    drop(conn);
+                    return Ok(version_conflict_response(
+                        &db,
+                        &actual_order_id,
+                        expected_version,
+                        current_version,
+                    ));
+                }
+                Err(error) => {
+                    let _ = conn.execute_batch("ROLLBACK");
+                    return Err(error);
+                }
             }
-        }
-        let was_cancelled = previous_status == "cancelled";
-        let next_is_cancelled = status == "cancelled";
-        let is_cancellation_reactivation = was_cancelled && status == "pending";
-
-        if !was_cancelled && next_is_cancelled {
-            order_ownership::reverse_order_drawer_attribution(&conn, &actual_order_id, &now)?;
-        }
+        };
+        inventory::emit_low_stock(&app, &low_stock);
 
-        if let Some(reason) = cancellation_reason.as_deref() {
-            conn.execute(
-                "UPDATE orders
-                 SET status = ?1,
-                     cancellation_reason = ?2,
-                     sync_status = 'pending',
-                     updated_at = ?3
-                 WHERE id = ?4",
-                rusqlite::params![status, reason, now, actual_order_id],
-            )
-            .map_err(|e| format!("update order status: {e}"))?;
-        } else if is_cancellation_reactivation {
-            conn.execute(
-                "UPDATE orders
-                 SET status = ?1,
-                     cancellation_reason = NULL,
-                     sync_status = 'pending',
-                     updated_at = ?2
-                 WHERE id = ?3",
-                rusqlite::params![status, now, actual_order_id],
-            )
-            .map_err(|e| format!("update order status: {e}"))?;
-        } else {
-            conn.execute(
-                "UPDATE orders
-                 SET status = ?1, sync_status = 'pending', updated_at = ?2
-                 WHERE id = ?3",
-                rusqlite::params![status, now, actual_order_id],
-            )
-            .map_err(|e| format!("update order status: {e}"))?;
-        }
-        if let Some(eta) = estimated_time {
-            let _ = conn.execute(
-                "UPDATE orders SET estimated_time = ?1, updated_at = ?2 WHERE id = ?3",
-                rusqlite::params![eta, now, actual_order_id],
-            );
-        }
-        let mut sync_payload = serde_json::json!({
+        let mut event_payload = serde_json::json!({
             "orderId": actual_order_id,
             "status": status,
             "estimatedTime": estimated_time
         });
-        if let Some(reason) = cancellation_reason.as_deref() {
-            // Send under both keys so whichever convention the server reads is
-            // satisfied (admin-dashboard inspects both shapes).
-            if let Some(obj) = sync_payload.as_object_mut() {
-                obj.insert(
-                    "cancellation_reason".to_string(),
-                    serde_json::Value::String(reason.to_string()),
-                );
+        if let Some(reason) = cancellation_reason.as_ref() {
+            if let Some(obj) = event_payload.as_object_mut() {
                 obj.insert(
                     "cancellationReason".to_string(),
-                    serde_json::Value::String(reason.to_string()),
-                );
-                obj.insert(
-                    "cancelled_at".to_string(),
-                    serde_json::Value::String(now.clone()),
+                    serde_json::Value::String(reason.clone()),
                 );
             }
-        } else if is_cancellation_reactivation {
-            if let Some(obj) = sync_payload.as_object_mut() {
-                obj.insert("cancellation_reason".to_string(), serde_json::Value::Null);
+        } else if status == "pending" {
+            if let Some(obj) = event_payload.as_object_mut() {
                 obj.insert("cancellationReason".to_string(), serde_json::Value::Null);
-                obj.insert("cancelled_at".to_string(), serde_json::Value::Null);
-                obj.insert("cancelledAt".to_string(), serde_json::Value::Null);
             }
         }
-        let _ = enqueue_order_sync_payload(&conn, &actual_order_id, &sync_payload);
-    }
-
-    let mut event_payload = serde_json::json!({
-        "orderId": actual_order_id,
-        "status": status,
-        "estimatedTime": estimated_time
-    });
-    if let Some(reason) = cancellation_reason.as_ref() {
-        if let Some(obj) = event_payload.as_object_mut() {
-            obj.insert(
-                "cancellationReason".to_string(),
-                serde_json::Value::String(reason.clone()),
+        let _ = app.emit("order_status_updated", event_payload.clone());
+        let _ = app.emit("order_realtime_update", event_payload);
+
+        if let Some(remote_order_id) = remote_order_id.as_deref() {
+            spawn_immediate_order_status_patch(
+                &db,
+                build_order_status_patch_body(
+                    remote_order_id,
+                    &status,
+                    estimated_time,
+                    cancellation_reason.as_deref(),
+                    if status == "cancelled" {
+                        Some(now.as_str())
+                    } else {
+                        None
+                    },
+                ),
             );
         }
-    } else if status == "pending" {
-        if let Some(obj) = event_payload.as_object_mut() {
-            obj.insert("cancellationReason".to_string(), serde_json::Value::Null);
-        }
-    }
-    let _ = app.emit("order_status_updated", event_payload.clone());
-    let _ = app.emit("order_realtime_update", event_payload);
-
-    if let Some(remote_order_id) = remote_order_id.as_deref() {
-        spawn_immediate_order_status_patch(
-            &db,
-            build_order_status_patch_body(
-                remote_order_id,
-                &status,
-                estimated_time,
-                cancellation_reason.as_deref(),
-                if status == "cancelled" {
-                    Some(now.as_str())
-                } else {
-                    None
-                },
-            ),
-        );
-    }
 
-    Ok(serde_json::json!({
-        "success": true,
-        "orderId": actual_order_id
-    }))
+        Ok(serde_json::json!({
+            "success": true,
+            "orderId": actual_order_id,
+            "version": new_version
+        }))
+    })
+    .await
 }
 
 fn convert_pickup_order_to_delivery_inner(
@@ -2706,72 +3024,160 @@ pub async fn order_update_items(
     arg0: Option<serde_json::Value>,
     arg1: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
     app: tauri::AppHandle,
 ) -> Result<serde_json::Value, String> {
     let payload = parse_order_update_items_payload(arg0, arg1)?;
+    let actor = crate::auth::current_staff_id(&auth_state);
     let order_id_raw = payload.order_id;
-    let items = payload.items;
+    let mut items = payload.items;
+    let (schema_mode, allergen_list) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        (
+            order_schema::ValidationMode::for_ipc(&conn),
+            allergens::allowed(&conn),
+        )
+    };
+    let schema_warnings = match order_schema::validate_items(&mut items, schema_mode) {
+        Ok(validated) => validated.warnings,
+        Err(issues) => return Ok(order_schema::rejection_response(&issues)),
+    };
+    let allergen_issues = order_schema::check_item_allergy_flags(&mut items, &allergen_list);
+    if !allergen_issues.is_empty() {
+        return Ok(order_schema::rejection_response(&allergen_issues));
+    }
     let notes = payload.order_notes;
+    let expected_version = payload.expected_version;
     let now = Utc::now().to_rfc3339();
 
     let actual_order_id = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        conn.query_row(
-            "SELECT id FROM orders WHERE id = ?1 OR supabase_id = ?1 LIMIT 1",
-            rusqlite::params![order_id_raw],
-            |row| row.get::<_, String>(0),
-        )
-        .map_err(|_| "Order not found")?
+        resolve_order_id(&conn, &order_id_raw).ok_or("Order not found")?
     };
 
-    {
+    let new_version = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        let merged_items =
+        if let Some(locked) = order_locks::guard_order_unlocked(&conn, &actual_order_id)? {
+            return Ok(locked);
+        }
+        let mut merged_items =
             merge_existing_order_item_customizations(&conn, &actual_order_id, &items)?;
-        let total = compute_order_items_total(&merged_items);
-        let items_json =
-            serde_json::to_string(&merged_items).map_err(|e| format!("serialize items: {e}"))?;
-        // W4c dual-write: the post-edit total_amount must propagate to
-        // total_amount_cents too — otherwise downstream COALESCE reads
-        // get the pre-edit cents value instead of the new real.
-        let total_cents = Cents::round_half_even(total).as_i64();
-        if let Some(order_notes) = notes.clone() {
-            conn.execute(
-                "UPDATE orders
-                 SET items = ?1, total_amount = ?2, total_amount_cents = ?3, special_instructions = ?4, sync_status = 'pending', updated_at = ?5
-                 WHERE id = ?6",
-                rusqlite::params![items_json, total, total_cents, order_notes, now, actual_order_id],
-            )
-            .map_err(|e| format!("update order items: {e}"))?;
-        } else {
-            conn.execute(
-                "UPDATE orders
-                 SET items = ?1, total_amount = ?2, total_amount_cents = ?3, sync_status = 'pending', updated_at = ?4
-                 WHERE id = ?5",
-                rusqlite::params![items_json, total, total_cents, now, actual_order_id],
-            )
-            .map_err(|e| format!("update order items: {e}"))?;
+        let (order_type, created_at) =
+            carry_over_pricing_rules(&conn, &actual_order_id, &mut merged_items)?;
+        let combo_check =
+            combos::validate_items(&conn, &mut merged_items, &order_type, chrono::Local::now());
+        if !combo_check.violations.is_empty() {
+            return Ok(combos::rejection_response(&combo_check.violations));
         }
-        let sync_payload = serde_json::json!({
-            "orderId": actual_order_id,
-            "items": merged_items,
-            "orderNotes": notes
-        });
-        let _ = enqueue_order_sync_payload(&conn, &actual_order_id, &sync_payload);
-    }
-
-    if let Ok(order_json) = sync::get_order_by_id(&db, &actual_order_id) {
-        let _ = app.emit("order_realtime_update", order_json);
-    }
-
-    Ok(serde_json::json!({
-        "success": true,
-        "orderId": actual_order_id
-    }))
-}
-
-#[tauri::command]
-pub async fn orders_preview_edit_settlement(
+        pricing_rules::apply_to_items(&conn, &mut merged_items, created_at)?;
+        conn.execute_batch("BEGIN IMMEDIATE")
+            .map_err(|e| format!("begin transaction: {e}"))?;
+        let result = (|| -> Result<Result<i64, i64>, String> {
+            let new_version = match claim_order_version(&conn, &actual_order_id, expected_version)?
+            {
+                VersionClaim::Claimed(version) => version,
+                VersionClaim::Conflict { current_version } => return Ok(Err(current_version)),
+            };
+            let previous_total: Option<f64> = conn
+                .query_row(
+                    "SELECT total_amount FROM orders WHERE id = ?1",
+                    rusqlite::params![actual_order_id],
+                    |row| row.get(0),
+                )
+                .ok();
+            let items_total =
+                compute_order_items_total(&merged_items, RoundingRule::from_settings(&conn));
+            let service_charge =
+                crate::service_charge::reprice(&conn, &actual_order_id, items_total, None)?;
+            let total_cents =
+                items_total + service_charge.map_or(Cents::ZERO, |repriced| repriced.effect);
+            let items_json = serde_json::to_string(&merged_items)
+                .map_err(|e| format!("serialize items: {e}"))?;
+            // W4c dual-write: the post-edit total_amount must propagate to
+            // total_amount_cents too — otherwise downstream COALESCE reads
+            // get the pre-edit cents value instead of the new real.
+            let total = total_cents.to_f64_dp2();
+            let total_cents = total_cents.as_i64();
+            if let Some(order_notes) = notes.clone() {
+                conn.execute(
+                    "UPDATE orders
+                     SET items = ?1, total_amount = ?2, total_amount_cents = ?3, special_instructions = ?4, sync_status = 'pending', updated_at = ?5
+                     WHERE id = ?6",
+                    rusqlite::params![items_json, total, total_cents, order_notes, now, actual_order_id],
+                )
+                .map_err(|e| format!("update order items: {e}"))?;
+            } else {
+                conn.execute(
+                    "UPDATE orders
+                     SET items = ?1, total_amount = ?2, total_amount_cents = ?3, sync_status = 'pending', updated_at = ?4
+                     WHERE id = ?5",
+                    rusqlite::params![items_json, total, total_cents, now, actual_order_id],
+                )
+                .map_err(|e| format!("update order items: {e}"))?;
+            }
+            if let Some(repriced) = service_charge {
+                repriced.store(&conn, &actual_order_id)?;
+            }
+            crate::tax::capture_order_breakdown(&conn, &actual_order_id, &merged_items)?;
+            let sync_payload = serde_json::json!({
+                "orderId": actual_order_id,
+                "items": merged_items,
+                "orderNotes": notes
+            });
+            let _ = enqueue_order_sync_payload(&conn, &actual_order_id, &sync_payload);
+            order_events::append(
+                &conn,
+                &actual_order_id,
+                order_events::ITEMS_EDITED,
+                actor.as_deref(),
+                serde_json::json!({
+                    "previousTotal": previous_total,
+                    "itemCount": merged_items.len(),
+                    "total": total,
+                    "notesChanged": notes.is_some(),
+                    "version": new_version
+                }),
+            );
+            Ok(Ok(new_version))
+        })();
+        match result {
+            Ok(Ok(new_version)) => {
+                conn.execute_batch("COMMIT")
+                    .map_err(|e| format!("commit: {e}"))?;
+                new_version
+            }
+            Ok(Err(current_version)) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                drop(conn);
+                return Ok(version_conflict_response(
+                    &db,
+                    &actual_order_id,
+                    expected_version,
+                    current_version,
+                ));
+            }
+            Err(error) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                return Err(error);
+            }
+        }
+    };
+
+    if let Ok(order_json) = sync::get_order_by_id(&db, &actual_order_id) {
+        let _ = app.emit("order_realtime_update", order_json);
+    }
+
+    let mut resp = serde_json::json!({
+        "success": true,
+        "orderId": actual_order_id,
+        "version": new_version
+    });
+    attach_schema_warnings(&mut resp, &schema_warnings);
+    Ok(resp)
+}
+
+#[tauri::command]
+pub async fn orders_preview_edit_settlement(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
@@ -2823,8 +3229,9 @@ pub async fn orders_preview_edit_settlement(
     let completed_payments = list_completed_payments_for_edit(&conn, &actual_order_id)?;
     let paid_total = completed_payments
         .iter()
-        .map(net_paid_amount_from_edit_payment)
-        .sum::<f64>();
+        .map(|payment| Cents::round_half_even(net_paid_amount_from_edit_payment(payment)))
+        .sum::<Cents>()
+        .to_f64_dp2();
     let delta = next_total - current_total;
     let required_action = determine_edit_settlement_required_action(paid_total, next_total);
     let driver_settlement = load_active_driver_settlement(&conn, &actual_order_id)?;
@@ -2896,19 +3303,21 @@ pub async fn orders_apply_edit_settlement(
             None => serde_json::Map::new(),
         };
 
-        update_order_items_in_connection(
+        // Financials first: the tax capture in the items update then
+        // replaces any tax the payload carried with the per-rate tax.
+        apply_edit_settlement_financial_adjustments(
             &conn,
             &actual_order_id,
-            &merged_items,
-            payload.order_notes.as_deref(),
-            next_total,
-            next_subtotal,
+            payload.financials.as_ref(),
             &now,
         )?;
-        apply_edit_settlement_financial_adjustments(
+        let next_total = update_order_items_in_connection(
             &conn,
             &actual_order_id,
-            payload.financials.as_ref(),
+            &merged_items,
+            payload.order_notes.as_deref(),
+            next_total,
+            next_subtotal,
             &now,
         )?;
 
@@ -3091,6 +3500,9 @@ pub async fn order_update_financials(
         .max(0.0);
 
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    if let Some(locked) = order_locks::guard_order_unlocked(&conn, &actual_order_id)? {
+        return Ok(locked);
+    }
     conn.execute_batch("BEGIN IMMEDIATE")
         .map_err(|e| format!("begin transaction: {e}"))?;
 
@@ -3101,7 +3513,20 @@ pub async fn order_update_financials(
     let edit_tax_amount_cents = Cents::round_half_even(tax_amount).as_i64();
     let edit_delivery_fee_cents = Cents::round_half_even(delivery_fee).as_i64();
     let edit_tip_amount_cents = Cents::round_half_even(tip_amount).as_i64();
-    let result = (|| -> Result<serde_json::Value, String> {
+    let result = (|| -> Result<Result<serde_json::Value, i64>, String> {
+        let new_version =
+            match claim_order_version(&conn, &actual_order_id, payload.expected_version)? {
+                VersionClaim::Claimed(version) => version,
+                VersionClaim::Conflict { current_version } => return Ok(Err(current_version)),
+            };
+        let discount_reason = resolve_discount_reason(
+            &conn,
+            &actual_order_id,
+            &payload,
+            edit_discount_amount_cents,
+            discount_percentage,
+        )?;
+        let has_discount = edit_discount_amount_cents > 0 || discount_percentage > 0.0;
         conn.execute(
             "UPDATE orders
              SET total_amount = ?1, total_amount_cents = ?2,
@@ -3111,6 +3536,10 @@ pub async fn order_update_financials(
                  tax_amount = ?8, tax_amount_cents = ?9,
                  delivery_fee = ?10, delivery_fee_cents = ?11,
                  tip_amount = ?12, tip_amount_cents = ?13,
+                 discount_reason_code = CASE
+                    WHEN ?17 = 0 THEN NULL
+                    ELSE COALESCE(?16, discount_reason_code)
+                 END,
                  sync_status = 'pending',
                  updated_at = ?14
              WHERE id = ?15",
@@ -3130,9 +3559,18 @@ pub async fn order_update_financials(
                 edit_tip_amount_cents,
                 now,
                 actual_order_id,
+                discount_reason.as_ref().map(|reason| reason.code.as_str()),
+                has_discount,
             ],
         )
         .map_err(|e| format!("update order financials: {e}"))?;
+        let discount_reason_code: Option<String> = conn
+            .query_row(
+                "SELECT discount_reason_code FROM orders WHERE id = ?1",
+                rusqlite::params![actual_order_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("load discount reason code: {e}"))?;
 
         let stale_payment_ids =
             resolve_stale_unsynced_overpay_payments_for_order(&conn, &actual_order_id, &now)?;
@@ -3150,6 +3588,8 @@ pub async fn order_update_financials(
             "discountAmount": discount_amount,
             "discount_amount_cents": Cents::round_half_even(discount_amount).as_i64(),
             "discountPercentage": discount_percentage,
+            "discountReasonCode": discount_reason_code,
+            "discountReason": discount_reason.as_ref().map(|reason| reason.text.clone()),
             "taxAmount": tax_amount,
             "tax_amount_cents": Cents::round_half_even(tax_amount).as_i64(),
             "deliveryFee": delivery_fee,
@@ -3162,22 +3602,34 @@ pub async fn order_update_financials(
         enqueue_order_sync_payload(&conn, &actual_order_id, &sync_payload)
             .map_err(|e| format!("enqueue order financial sync: {e}"))?;
 
-        Ok(serde_json::json!({
+        Ok(Ok(serde_json::json!({
             "success": true,
             "orderId": actual_order_id.clone(),
+            "version": new_version,
             "paymentStatus": payment_status,
             "paymentMethod": payment_method,
             "paidTotal": paid_total,
             "stalePaymentIdsVoided": stale_payment_ids,
-        }))
+            "discountReasonCode": discount_reason_code,
+        })))
     })();
 
     let response = match result {
-        Ok(value) => {
+        Ok(Ok(value)) => {
             conn.execute_batch("COMMIT")
                 .map_err(|e| format!("commit: {e}"))?;
             Ok(value)
         }
+        Ok(Err(current_version)) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            drop(conn);
+            return Ok(version_conflict_response(
+                &db,
+                &actual_order_id,
+                payload.expected_version,
+                current_version,
+            ));
+        }
         Err(error) => {
             let _ = conn.execute_batch("ROLLBACK");
             Err(error)
@@ -3205,12 +3657,7 @@ pub async fn order_delete(
 
     let actual_order_id = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        conn.query_row(
-            "SELECT id FROM orders WHERE id = ?1 OR supabase_id = ?1 LIMIT 1",
-            rusqlite::params![order_id_raw],
-            |row| row.get::<_, String>(0),
-        )
-        .ok()
+        resolve_order_id(&conn, &order_id_raw)
     };
 
     if let Some(actual_id) = actual_order_id.clone() {
@@ -3254,68 +3701,259 @@ pub async fn order_save_from_remote(
     app: tauri::AppHandle,
 ) -> Result<serde_json::Value, String> {
     let payload = arg0.ok_or("Missing order payload")?;
-    let order_data = payload
-        .get("orderData")
-        .cloned()
-        .unwrap_or_else(|| payload.clone());
-    let suppress_auto_print = value_bool_any(
-        &payload,
-        &[
-            "suppressAutoPrint",
-            "suppress_auto_print",
-            "skipAutoPrint",
-            "skip_auto_print",
-        ],
-    )
-    .or_else(|| {
-        value_bool_any(
-            &order_data,
-            &[
-                "suppressAutoPrint",
-                "suppress_auto_print",
-                "skipAutoPrint",
-                "skip_auto_print",
-            ],
-        )
-    })
-    .unwrap_or(false);
-    let remote_id = value_str(&order_data, &["id", "supabase_id", "supabaseId"])
-        .ok_or("Missing remote order id")?;
+    persist_remote_order(&db, &app, &payload)
+}
 
-    {
-        let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        if !sync::remote_order_visible_to_current_terminal(&conn, &order_data)? {
-            tracing::debug!(
-                remote_id = %remote_id,
-                "Ignoring remote order outside current isolated terminal scope"
-            );
-            return Ok(serde_json::json!({
-                "success": true,
-                "ignored": true,
-                "reason": "outside_terminal_scope"
-            }));
-        }
+/// Page size for `orders_import_from_admin`; each page is one transaction.
+const REMOTE_IMPORT_BATCH_SIZE: usize = 200;
+/// Hard stop so a misbehaving endpoint cannot page forever.
+const REMOTE_IMPORT_MAX_PAGES: usize = 250;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct RemoteImportTally {
+    imported: usize,
+    updated: usize,
+    skipped: usize,
+    failed: usize,
+}
+
+impl RemoteImportTally {
+    fn add(&mut self, other: RemoteImportTally) {
+        self.imported += other.imported;
+        self.updated += other.updated;
+        self.skipped += other.skipped;
+        self.failed += other.failed;
     }
+}
 
-    let existing_local_id = {
-        let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        resolve_existing_local_order_for_remote(&conn, &remote_id, &order_data)?
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OrdersImportFromAdminPayload {
+    #[serde(default, alias = "date_from", alias = "since")]
+    date_from: Option<String>,
+    #[serde(default, alias = "date_to")]
+    date_to: Option<String>,
+}
+
+fn parse_orders_import_from_admin_payload(
+    arg0: Option<Value>,
+) -> Result<OrdersImportFromAdminPayload, String> {
+    let mut parsed: OrdersImportFromAdminPayload = match arg0 {
+        None | Some(Value::Null) => OrdersImportFromAdminPayload::default(),
+        Some(Value::String(date_from)) => OrdersImportFromAdminPayload {
+            date_from: Some(date_from),
+            date_to: None,
+        },
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| format!("Invalid order import payload: {e}"))?,
     };
-    if let Some(local_id) = existing_local_id {
-        let now = Utc::now().to_rfc3339();
-        {
+    parsed.date_from = parsed
+        .date_from
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    parsed.date_to = parsed
+        .date_to
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    Ok(parsed)
+}
+
+/// Save one page of remote orders as `synced` rows in a single
+/// transaction, through the same [`save_remote_order`] upsert as the
+/// realtime path. Orders outside this terminal's scope, or already stored
+/// at least as new, are skipped, so re-running an import is a no-op. A row
+/// that fails to save is rolled back on its own and counted, without
+/// aborting the rest of the page. Returns the tally and the local ids of
+/// the newly inserted rows.
+fn import_remote_orders_batch(
+    conn: &rusqlite::Connection,
+    orders: &[Value],
+    now: &str,
+) -> Result<(RemoteImportTally, Vec<String>), String> {
+    let mut tally = RemoteImportTally::default();
+    let mut imported_ids = Vec::new();
+    conn.execute_batch("SAVEPOINT remote_order_import")
+        .map_err(|e| format!("begin order import batch: {e}"))?;
+    for order in orders {
+        let order_data = order.get("orderData").unwrap_or(order);
+        let Some(remote_id) = value_str(order_data, &["id", "supabase_id", "supabaseId"]) else {
+            tally.failed += 1;
+            continue;
+        };
+        match sync::remote_order_visible_to_current_terminal(conn, order_data) {
+            Ok(true) => {}
+            Ok(false) => {
+                tally.skipped += 1;
+                continue;
+            }
+            Err(error) => {
+                tracing::warn!(remote_id = %remote_id, error = %error, "Order import check failed");
+                tally.failed += 1;
+                continue;
+            }
+        }
+
+        let saved = conn
+            .execute_batch("SAVEPOINT remote_order_import_row")
+            .map_err(|e| e.to_string())
+            .and_then(|_| save_remote_order(conn, order_data, &remote_id, now));
+        match saved {
+            Ok(saved) => {
+                let _ = conn.execute_batch("RELEASE remote_order_import_row");
+                match saved.outcome {
+                    RemoteOrderUpsert::Inserted => {
+                        tally.imported += 1;
+                        imported_ids.push(saved.local_id);
+                    }
+                    RemoteOrderUpsert::Updated => tally.updated += 1,
+                    RemoteOrderUpsert::Skipped => tally.skipped += 1,
+                }
+            }
+            Err(error) => {
+                let _ = conn.execute_batch(
+                    "ROLLBACK TO remote_order_import_row; RELEASE remote_order_import_row",
+                );
+                tracing::warn!(remote_id = %remote_id, error = %error, "Failed to import remote order");
+                tally.failed += 1;
+            }
+        }
+    }
+    if let Err(error) = conn.execute_batch("RELEASE remote_order_import") {
+        let _ = conn.execute_batch("ROLLBACK TO remote_order_import; RELEASE remote_order_import");
+        return Err(format!("commit order import batch: {error}"));
+    }
+    Ok((tally, imported_ids))
+}
+
+/// Seed the local `orders` table from the admin API, e.g. when a
+/// replacement terminal is provisioned mid-day. Imported rows are stored as
+/// `synced` and never touch the sync queue; running it again only picks up
+/// orders that are still missing or have changed since.
+#[tauri::command]
+pub async fn orders_import_from_admin(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    let payload = parse_orders_import_from_admin_payload(arg0)?;
+    let date_from = payload
+        .date_from
+        .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+
+    let mut totals = RemoteImportTally::default();
+    let mut pages = 0usize;
+    for page in 0..REMOTE_IMPORT_MAX_PAGES {
+        let options = serde_json::json!({
+            "date_from": date_from,
+            "date_to": payload.date_to,
+            "limit": REMOTE_IMPORT_BATCH_SIZE,
+            "offset": page * REMOTE_IMPORT_BATCH_SIZE,
+        });
+        let path = crate::build_admin_query("/api/pos/orders", Some(&options));
+        let response = crate::admin_fetch(Some(&db), &path, "GET", None).await?;
+        let orders = ["orders", "data"]
+            .iter()
+            .find_map(|key| response.get(*key).and_then(Value::as_array))
+            .cloned()
+            .unwrap_or_default();
+        if orders.is_empty() {
+            break;
+        }
+        pages += 1;
+
+        let (batch, imported_ids) = {
             let conn = db.conn.lock().map_err(|e| e.to_string())?;
-            attach_remote_order_identity_to_local(&conn, &local_id, &remote_id, &order_data, &now)?;
+            import_remote_orders_batch(&conn, &orders, &Utc::now().to_rfc3339())?
+        };
+        totals.add(batch);
+        for order_id in imported_ids {
+            crate::event_batcher::emit(
+                &app,
+                "order_created",
+                serde_json::json!({ "orderId": order_id }),
+            );
+        }
+        let _ = app.emit(
+            "orders_import_progress",
+            serde_json::json!({
+                "page": pages,
+                "imported": totals.imported,
+                "updated": totals.updated,
+                "skipped": totals.skipped,
+                "failed": totals.failed,
+            }),
+        );
+        if orders.len() < REMOTE_IMPORT_BATCH_SIZE {
+            break;
         }
-        return Ok(serde_json::json!({
-            "success": true,
-            "orderId": local_id,
-            "alreadyExists": true
-        }));
     }
 
-    let local_id = uuid::Uuid::new_v4().to_string();
-    let now = Utc::now().to_rfc3339();
+    tracing::info!(
+        imported = totals.imported,
+        updated = totals.updated,
+        skipped = totals.skipped,
+        failed = totals.failed,
+        pages = pages,
+        "Imported remote orders from admin"
+    );
+    Ok(serde_json::json!({
+        "success": true,
+        "dateFrom": date_from,
+        "imported": totals.imported,
+        "updated": totals.updated,
+        "skipped": totals.skipped,
+        "failed": totals.failed,
+    }))
+}
+
+/// How [`upsert_remote_order_row`] treated a remote snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RemoteOrderUpsert {
+    Inserted,
+    /// The row already existed and the snapshot was newer.
+    Updated,
+    /// The row already existed and was at least as new as the snapshot.
+    Skipped,
+}
+
+impl RemoteOrderUpsert {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Inserted => "inserted",
+            Self::Updated => "updated",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// What the caller of [`upsert_remote_order_row`] still needs after the
+/// write (events, auto-print decisions).
+pub(crate) struct RemoteOrderSaved {
+    pub outcome: RemoteOrderUpsert,
+    pub local_id: String,
+    pub order_type: String,
+    pub is_ghost: bool,
+    pub payment_method: Option<String>,
+}
+
+/// Map a remote order snapshot onto the local `orders` columns and upsert
+/// it on `supabase_id`. A new remote id is inserted as `synced` under
+/// `local_id`. An existing row is only overwritten when the snapshot's
+/// `updated_at` is newer than the stored one (last write wins); a snapshot
+/// without `updated_at` never overwrites, and neither does any snapshot of
+/// a row with unsynced local edits (pending `sync_status` or outstanding
+/// queue work), the same guard `sync::apply_remote_order_snapshot` applies.
+/// Ownership, ghost and sync columns
+/// of an existing row are left alone, as are its items when the snapshot
+/// carries none. Pure row mapping: no visibility checks, events, print jobs
+/// or sync-queue writes — callers own those.
+pub(crate) fn upsert_remote_order_row(
+    conn: &rusqlite::Connection,
+    order_data: &serde_json::Value,
+    remote_id: &str,
+    local_id: &str,
+    now: &str,
+) -> Result<RemoteOrderSaved, String> {
     let items = order_data
         .get("items")
         .or_else(|| order_data.get("order_items"))
@@ -3324,70 +3962,70 @@ pub async fn order_save_from_remote(
         .unwrap_or_else(|| serde_json::json!([]));
     let items_json = serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string());
 
-    let order_number = value_str(&order_data, &["order_number", "orderNumber"]);
+    let order_number = value_str(order_data, &["order_number", "orderNumber"]);
     let display_order_number =
-        value_str(&order_data, &["display_order_number", "displayOrderNumber"])
+        value_str(order_data, &["display_order_number", "displayOrderNumber"])
             .or_else(|| order_number.clone());
-    let customer_name = value_str(&order_data, &["customer_name", "customerName"]);
-    let customer_phone = value_str(&order_data, &["customer_phone", "customerPhone"]);
-    let customer_email = value_str(&order_data, &["customer_email", "customerEmail"]);
-    let total_amount = value_f64(&order_data, &["total_amount", "totalAmount"]).unwrap_or(0.0);
-    let tax_amount = value_f64(&order_data, &["tax_amount", "taxAmount"]).unwrap_or(0.0);
-    let subtotal = value_f64(&order_data, &["subtotal"]).unwrap_or(0.0);
+    let customer_name = value_str(order_data, &["customer_name", "customerName"]);
+    let customer_phone = value_str(order_data, &["customer_phone", "customerPhone"]);
+    let customer_email = value_str(order_data, &["customer_email", "customerEmail"]);
+    let total_amount = value_f64(order_data, &["total_amount", "totalAmount"]).unwrap_or(0.0);
+    let tax_amount = value_f64(order_data, &["tax_amount", "taxAmount"]).unwrap_or(0.0);
+    let subtotal = value_f64(order_data, &["subtotal"]).unwrap_or(0.0);
     let status = normalize_status_for_storage(
-        &value_str(&order_data, &["status"]).unwrap_or_else(|| "pending".to_string()),
+        &value_str(order_data, &["status"]).unwrap_or_else(|| "pending".to_string()),
     );
     let order_type =
-        value_str(&order_data, &["order_type", "orderType"]).unwrap_or_else(|| "pickup".into());
-    let table_number = value_str(&order_data, &["table_number", "tableNumber"]);
-    let table_id = value_str(&order_data, &["table_id", "tableId"]);
-    let table_session_id = value_str(&order_data, &["table_session_id", "tableSessionId"]);
-    let guest_count = value_i64(&order_data, &["guest_count", "guestCount"]);
+        value_str(order_data, &["order_type", "orderType"]).unwrap_or_else(|| "pickup".into());
+    let table_number = value_str(order_data, &["table_number", "tableNumber"]);
+    let table_id = value_str(order_data, &["table_id", "tableId"]);
+    let table_session_id = value_str(order_data, &["table_session_id", "tableSessionId"]);
+    let guest_count = value_i64(order_data, &["guest_count", "guestCount"]);
     let delivery_address = value_str(
-        &order_data,
+        order_data,
         &["delivery_address", "deliveryAddress", "address"],
     );
-    let delivery_city = value_str(&order_data, &["delivery_city", "deliveryCity"]);
+    let delivery_city = value_str(order_data, &["delivery_city", "deliveryCity"]);
     let delivery_postal_code =
-        value_str(&order_data, &["delivery_postal_code", "deliveryPostalCode"]);
-    let delivery_floor = value_str(&order_data, &["delivery_floor", "deliveryFloor"]);
-    let delivery_notes = value_str(&order_data, &["delivery_notes", "deliveryNotes"]);
-    let name_on_ringer = value_str(&order_data, &["name_on_ringer", "nameOnRinger"]);
-    let special_instructions = value_str(&order_data, &["special_instructions", "notes"]);
-    let estimated_time = value_i64(&order_data, &["estimated_time", "estimatedTime"]);
-    let payment_status = value_str(&order_data, &["payment_status", "paymentStatus"])
+        value_str(order_data, &["delivery_postal_code", "deliveryPostalCode"]);
+    let delivery_floor = value_str(order_data, &["delivery_floor", "deliveryFloor"]);
+    let delivery_notes = value_str(order_data, &["delivery_notes", "deliveryNotes"]);
+    let name_on_ringer = value_str(order_data, &["name_on_ringer", "nameOnRinger"]);
+    let special_instructions = value_str(order_data, &["special_instructions", "notes"]);
+    let estimated_time = value_i64(order_data, &["estimated_time", "estimatedTime"]);
+    let payment_status = value_str(order_data, &["payment_status", "paymentStatus"])
         .unwrap_or_else(|| "pending".into());
-    let payment_method = value_str(&order_data, &["payment_method", "paymentMethod"]);
+    let payment_method = value_str(order_data, &["payment_method", "paymentMethod"]);
     let payment_tx_id = value_str(
-        &order_data,
+        order_data,
         &["payment_transaction_id", "paymentTransactionId"],
     );
-    let staff_shift_id = value_str(&order_data, &["staff_shift_id", "staffShiftId"]);
-    let staff_id = value_str(&order_data, &["staff_id", "staffId"]);
-    let driver_id = value_str(&order_data, &["driver_id", "driverId"]);
-    let driver_name = value_str(&order_data, &["driver_name", "driverName"]);
+    let staff_shift_id = value_str(order_data, &["staff_shift_id", "staffShiftId"]);
+    let staff_id = value_str(order_data, &["staff_id", "staffId"]);
+    let driver_id = value_str(order_data, &["driver_id", "driverId"]);
+    let driver_name = value_str(order_data, &["driver_name", "driverName"]);
     let discount_pct =
-        value_f64(&order_data, &["discount_percentage", "discountPercentage"]).unwrap_or(0.0);
+        value_f64(order_data, &["discount_percentage", "discountPercentage"]).unwrap_or(0.0);
     let discount_amount =
-        value_f64(&order_data, &["discount_amount", "discountAmount"]).unwrap_or(0.0);
-    let tip_amount = value_f64(&order_data, &["tip_amount", "tipAmount"]).unwrap_or(0.0);
-    let tax_rate = value_f64(&order_data, &["tax_rate", "taxRate"]);
-    let delivery_fee = value_f64(&order_data, &["delivery_fee", "deliveryFee"]).unwrap_or(0.0);
-    let branch_id = value_str(&order_data, &["branch_id", "branchId"])
+        value_f64(order_data, &["discount_amount", "discountAmount"]).unwrap_or(0.0);
+    let tip_amount = value_f64(order_data, &["tip_amount", "tipAmount"]).unwrap_or(0.0);
+    let tax_rate = value_f64(order_data, &["tax_rate", "taxRate"]);
+    let delivery_fee = value_f64(order_data, &["delivery_fee", "deliveryFee"]).unwrap_or(0.0);
+    let branch_id = value_str(order_data, &["branch_id", "branchId"])
         .or_else(|| storage::get_credential("branch_id"));
-    let terminal_id = value_str(&order_data, &["terminal_id", "terminalId"])
+    let terminal_id = value_str(order_data, &["terminal_id", "terminalId"])
         .or_else(|| storage::get_credential("terminal_id"));
-    let owner_terminal_id = value_str(&order_data, &["owner_terminal_id", "ownerTerminalId"]);
-    let source_terminal_id = value_str(&order_data, &["source_terminal_id", "sourceTerminalId"]);
-    let client_request_id = remote_order_client_identity_candidates(&order_data)
+    let owner_terminal_id = value_str(order_data, &["owner_terminal_id", "ownerTerminalId"]);
+    let source_terminal_id = value_str(order_data, &["source_terminal_id", "sourceTerminalId"]);
+    let client_request_id = remote_order_client_identity_candidates(order_data)
         .into_iter()
         .next();
     let plugin = value_str(
-        &order_data,
+        order_data,
         &["plugin", "platform", "order_plugin", "orderPlatform"],
     );
     let external_plugin_order_id = value_str(
-        &order_data,
+        order_data,
         &[
             "external_plugin_order_id",
             "externalPluginOrderId",
@@ -3417,18 +4055,42 @@ pub async fn order_save_from_remote(
             })
         })
         .unwrap_or(false);
-    let ghost_source = value_str(&order_data, &["ghost_source", "ghostSource"]);
+    let ghost_source = value_str(order_data, &["ghost_source", "ghostSource"]);
     let ghost_metadata = attach_kiosk_payment_method_to_metadata(
         order_data
             .get("ghost_metadata")
             .or_else(|| order_data.get("ghostMetadata")),
         payment_method.as_deref(),
     );
-    let created_at = value_str(&order_data, &["created_at", "createdAt"]).unwrap_or(now.clone());
-    let updated_at = value_str(&order_data, &["updated_at", "updatedAt"]).unwrap_or(now.clone());
+    let created_at =
+        value_str(order_data, &["created_at", "createdAt"]).unwrap_or_else(|| now.to_string());
+    let incoming_updated_at = value_str(order_data, &["updated_at", "updatedAt"]);
+    let updated_at = incoming_updated_at
+        .clone()
+        .unwrap_or_else(|| now.to_string());
+
+    let existing: Option<(String, Option<String>)> = conn
+        .query_row(
+            "SELECT id, sync_status FROM orders WHERE supabase_id = ?1",
+            rusqlite::params![remote_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("read local order for remote snapshot: {e}"))?;
+    if let Some((existing_id, sync_status)) = existing {
+        let pending_sync = matches!(sync_status.as_deref(), Some("pending" | "queued"));
+        if pending_sync || crate::sync::has_outstanding_local_order_queue(conn, &existing_id) {
+            return Ok(RemoteOrderSaved {
+                outcome: RemoteOrderUpsert::Skipped,
+                local_id: existing_id,
+                order_type,
+                is_ghost,
+                payment_method,
+            });
+        }
+    }
 
-    {
-        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let saved_id: Option<String> = {
         // W4c dual-write: 6 monetary REAL columns mirror onto cents siblings.
         let total_amount_cents = Cents::round_half_even(total_amount).as_i64();
         let tax_amount_cents = Cents::round_half_even(tax_amount).as_i64();
@@ -3436,7 +4098,7 @@ pub async fn order_save_from_remote(
         let discount_amount_cents = Cents::round_half_even(discount_amount).as_i64();
         let tip_amount_cents = Cents::round_half_even(tip_amount).as_i64();
         let delivery_fee_cents = Cents::round_half_even(delivery_fee).as_i64();
-        conn.execute(
+        conn.query_row(
             "INSERT INTO orders (
                 id, order_number, display_order_number, customer_name, customer_phone, customer_email,
                 items,
@@ -3477,7 +4139,52 @@ pub async fn order_save_from_remote(
                 ?49,
                 ?50, ?51,
                 ?52, ?53, ?54
-            )",
+            )
+            ON CONFLICT(supabase_id) WHERE supabase_id IS NOT NULL AND supabase_id <> ''
+            DO UPDATE SET
+                order_number = COALESCE(excluded.order_number, orders.order_number),
+                display_order_number = COALESCE(excluded.display_order_number, orders.display_order_number),
+                customer_name = COALESCE(excluded.customer_name, orders.customer_name),
+                customer_phone = COALESCE(excluded.customer_phone, orders.customer_phone),
+                customer_email = COALESCE(excluded.customer_email, orders.customer_email),
+                items = CASE WHEN excluded.items <> '[]' THEN excluded.items ELSE orders.items END,
+                total_amount = excluded.total_amount,
+                total_amount_cents = excluded.total_amount_cents,
+                tax_amount = excluded.tax_amount,
+                tax_amount_cents = excluded.tax_amount_cents,
+                subtotal = excluded.subtotal,
+                subtotal_cents = excluded.subtotal_cents,
+                status = excluded.status,
+                order_type = excluded.order_type,
+                table_number = COALESCE(excluded.table_number, orders.table_number),
+                table_id = COALESCE(excluded.table_id, orders.table_id),
+                table_session_id = COALESCE(excluded.table_session_id, orders.table_session_id),
+                guest_count = COALESCE(excluded.guest_count, orders.guest_count),
+                delivery_address = COALESCE(excluded.delivery_address, orders.delivery_address),
+                delivery_city = COALESCE(excluded.delivery_city, orders.delivery_city),
+                delivery_postal_code = COALESCE(excluded.delivery_postal_code, orders.delivery_postal_code),
+                delivery_floor = COALESCE(excluded.delivery_floor, orders.delivery_floor),
+                delivery_notes = COALESCE(excluded.delivery_notes, orders.delivery_notes),
+                name_on_ringer = COALESCE(excluded.name_on_ringer, orders.name_on_ringer),
+                special_instructions = COALESCE(excluded.special_instructions, orders.special_instructions),
+                estimated_time = COALESCE(excluded.estimated_time, orders.estimated_time),
+                payment_status = excluded.payment_status,
+                payment_transaction_id = COALESCE(excluded.payment_transaction_id, orders.payment_transaction_id),
+                driver_id = COALESCE(excluded.driver_id, orders.driver_id),
+                driver_name = COALESCE(excluded.driver_name, orders.driver_name),
+                discount_percentage = excluded.discount_percentage,
+                discount_amount = excluded.discount_amount,
+                discount_amount_cents = excluded.discount_amount_cents,
+                tip_amount = excluded.tip_amount,
+                tip_amount_cents = excluded.tip_amount_cents,
+                tax_rate = COALESCE(excluded.tax_rate, orders.tax_rate),
+                delivery_fee = excluded.delivery_fee,
+                delivery_fee_cents = excluded.delivery_fee_cents,
+                updated_at = excluded.updated_at,
+                version = COALESCE(orders.version, 0) + 1
+            WHERE ?55 IS NOT NULL
+              AND (orders.updated_at IS NULL OR julianday(?55) > julianday(orders.updated_at))
+            RETURNING id",
             rusqlite::params![
                 local_id,
                 order_number,
@@ -3533,64 +4240,271 @@ pub async fn order_save_from_remote(
                 if is_ghost { 1_i64 } else { 0_i64 },
                 ghost_source,
                 ghost_metadata,
+                incoming_updated_at,
             ],
+            |row| row.get(0),
         )
-        .map_err(|e| format!("save remote order: {e}"))?;
+        .optional()
+        .map_err(|e| format!("save remote order: {e}"))?
+    };
+    let (outcome, local_id) = match saved_id {
+        Some(id) if id == local_id => (RemoteOrderUpsert::Inserted, id),
+        Some(id) => (RemoteOrderUpsert::Updated, id),
+        None => {
+            let id = conn
+                .query_row(
+                    "SELECT id FROM orders WHERE supabase_id = ?1",
+                    rusqlite::params![remote_id],
+                    |row| row.get::<_, String>(0),
+                )
+                .map_err(|e| format!("resolve skipped remote order: {e}"))?;
+            (RemoteOrderUpsert::Skipped, id)
+        }
+    };
+    if outcome != RemoteOrderUpsert::Skipped {
+        if let Some(charges) = crate::platform_fees::OrderCharges::from_payload(order_data) {
+            charges.store(conn, &local_id)?;
+        }
     }
 
-    if let Ok(order_json) = sync::get_order_by_id(&db, &local_id) {
-        let _ = app.emit("order_created", order_json);
-    }
+    Ok(RemoteOrderSaved {
+        outcome,
+        local_id,
+        order_type,
+        is_ghost,
+        payment_method,
+    })
+}
 
-    // Skip auto-print for ghost orders and pending/split payment orders (receipt
-    // will be printed after split payments are individually recorded).
-    let skip_auto_print =
-        suppress_auto_print || is_ghost || payment_method.as_deref() == Some("pending");
-    if !skip_auto_print && crate::print::is_print_action_enabled(&db, "after_order") {
-        for entity_type in print::auto_print_entity_types_for_order_type(&order_type) {
-            if let Err(error) = print::enqueue_print_job(&db, entity_type, &local_id, None) {
-                tracing::warn!(
-                    order_id = %local_id,
-                    entity_type = %entity_type,
-                    error = %error,
-                    "Failed to enqueue remote order auto-print job"
-                );
-            }
-        }
+/// Store one remote order snapshot. A local order it already matches by
+/// client identity or order number first gets the remote id attached, so
+/// the upsert on `supabase_id` lands on that row instead of a duplicate.
+/// Every remote-ingestion path goes through here.
+pub(crate) fn save_remote_order(
+    conn: &rusqlite::Connection,
+    order_data: &Value,
+    remote_id: &str,
+    now: &str,
+) -> Result<RemoteOrderSaved, String> {
+    if let Some(local_id) = resolve_existing_local_order_for_remote(conn, remote_id, order_data)? {
+        attach_remote_order_identity_to_local(conn, &local_id, remote_id, order_data, now)?;
     }
-
-    Ok(serde_json::json!({
-        "success": true,
-        "orderId": local_id
-    }))
+    let local_id = uuid::Uuid::new_v4().to_string();
+    upsert_remote_order_row(conn, order_data, remote_id, &local_id, now)
 }
 
-#[tauri::command]
-pub async fn order_fetch_items_from_supabase(
-    arg0: Option<serde_json::Value>,
-    arg1: Option<String>,
-    db: tauri::State<'_, db::DbState>,
+/// Persist a remote (admin/Supabase) order snapshot into the local `orders`
+/// table through [`save_remote_order`]. A new order emits `order_created`
+/// and enqueues the usual auto-print jobs; a newer snapshot of a known order
+/// only emits `order_realtime_update`. The response's `outcome` says whether
+/// the row was inserted, updated or skipped.
+///
+/// Shared by `order_save_from_remote` and the realtime subscription so both
+/// ingestion paths apply the exact same column mapping and visibility rules.
+/// `payload` may wrap the row under `orderData` (IPC shape) or be the row
+/// itself (realtime shape).
+pub(crate) fn persist_remote_order(
+    db: &db::DbState,
+    app: &tauri::AppHandle,
+    payload: &serde_json::Value,
 ) -> Result<serde_json::Value, String> {
-    let order_id = payload_arg0_as_string(
-        arg0,
-        &["orderId", "order_id", "id", "supabaseId", "supabase_id"],
-    )
-    .or(arg1)
-    .ok_or("Missing orderId")?;
-
-    if let Ok(items_json) = fetch_supabase_rows(
-        "order_items",
+    let order_data = payload
+        .get("orderData")
+        .cloned()
+        .unwrap_or_else(|| payload.clone());
+    let suppress_auto_print = value_bool_any(
+        payload,
         &[
-            (
-                "select",
-                "id,menu_item_id,menu_item_name,quantity,unit_price,total_price,notes,customizations".to_string(),
-            ),
-            ("order_id", format!("eq.{}", order_id)),
-        ],
-    )
-    .await
+            "suppressAutoPrint",
+            "suppress_auto_print",
+            "skipAutoPrint",
+            "skip_auto_print",
+        ],
+    )
+    .or_else(|| {
+        value_bool_any(
+            &order_data,
+            &[
+                "suppressAutoPrint",
+                "suppress_auto_print",
+                "skipAutoPrint",
+                "skip_auto_print",
+            ],
+        )
+    })
+    .unwrap_or(false);
+    let remote_id = value_str(&order_data, &["id", "supabase_id", "supabaseId"])
+        .ok_or("Missing remote order id")?;
+
     {
-        let rows = items_json.as_array().cloned().unwrap_or_default();
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        if !sync::remote_order_visible_to_current_terminal(&conn, &order_data)? {
+            tracing::debug!(
+                remote_id = %remote_id,
+                "Ignoring remote order outside current isolated terminal scope"
+            );
+            return Ok(serde_json::json!({
+                "success": true,
+                "ignored": true,
+                "reason": "outside_terminal_scope"
+            }));
+        }
+    }
+
+    let now = Utc::now().to_rfc3339();
+    let RemoteOrderSaved {
+        outcome,
+        local_id,
+        order_type,
+        is_ghost,
+        payment_method,
+    } = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        save_remote_order(&conn, &order_data, &remote_id, &now)?
+    };
+    if outcome != RemoteOrderUpsert::Inserted {
+        if outcome == RemoteOrderUpsert::Updated {
+            let _ = app.emit(
+                "order_realtime_update",
+                serde_json::json!({ "orderId": local_id, "source": "remote_save" }),
+            );
+        }
+        return Ok(serde_json::json!({
+            "success": true,
+            "orderId": local_id,
+            "alreadyExists": true,
+            "outcome": outcome.as_str(),
+        }));
+    }
+
+    if let Ok(order_json) = sync::get_order_by_id(db, &local_id) {
+        let _ = app.emit("order_created", order_json);
+    }
+    let auto_accepted = if is_ghost {
+        None
+    } else {
+        auto_accept_remote_order(db, app, &local_id, &remote_id)
+    };
+
+    // Skip auto-print for ghost orders and pending/split payment orders (receipt
+    // will be printed after split payments are individually recorded).
+    let skip_auto_print =
+        suppress_auto_print || is_ghost || payment_method.as_deref() == Some("pending");
+    if !skip_auto_print && crate::print::is_print_action_enabled(db, "after_order") {
+        for entity_type in print::auto_print_entity_types_for_order_type(&order_type) {
+            if let Err(error) = print::enqueue_print_job(db, entity_type, &local_id, None) {
+                tracing::warn!(
+                    order_id = %local_id,
+                    entity_type = %entity_type,
+                    error = %error,
+                    "Failed to enqueue remote order auto-print job"
+                );
+            }
+        }
+    }
+
+    Ok(serde_json::json!({
+        "success": true,
+        "orderId": local_id,
+        "outcome": outcome.as_str(),
+        "autoAccepted": auto_accepted.map(|matched| serde_json::json!({
+            "ruleId": matched.rule_id,
+            "estimatedTime": matched.estimated_minutes,
+        })),
+    }))
+}
+
+/// Run the auto-accept rules on a remote order that was just stored and,
+/// on a match, approve it as `order_approve` would and send a kitchen
+/// ticket. Failures are logged; the order then simply waits in `pending`.
+fn auto_accept_remote_order(
+    db: &db::DbState,
+    app: &tauri::AppHandle,
+    order_id: &str,
+    remote_order_id: &str,
+) -> Option<auto_accept::AutoAcceptMatch> {
+    let outcome = (|| -> Result<Option<(auto_accept::AutoAcceptMatch, ApprovedOrder)>, String> {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let Some(mut matched) =
+            auto_accept::evaluate(&conn, order_id, chrono::Local::now().time())?
+        else {
+            return Ok(None);
+        };
+        if let Some(estimate) =
+            suggest_prep_time(&conn, order_id).filter(prep_time::PrepEstimate::is_learned)
+        {
+            matched.estimated_minutes = estimate.minutes;
+        }
+        match apply_order_approval(
+            &conn,
+            order_id,
+            Some(matched.estimated_minutes),
+            None,
+            None,
+            order_events::AUTO_APPROVED,
+            serde_json::json!({ "ruleId": matched.rule_id }),
+        )? {
+            OrderApproval::Approved(approved) => Ok(Some((matched, approved))),
+            OrderApproval::Conflict { .. } => Ok(None),
+        }
+    })();
+    let (matched, approved) = match outcome {
+        Ok(Some(accepted)) => accepted,
+        Ok(None) => return None,
+        Err(error) => {
+            tracing::warn!(order_id = %order_id, error = %error, "Auto-accept failed");
+            return None;
+        }
+    };
+    tracing::info!(
+        order_id = %order_id,
+        rule_id = %matched.rule_id,
+        estimated_time = matched.estimated_minutes,
+        "Remote order auto-approved"
+    );
+    announce_order_approval(db, app, &approved, Some(remote_order_id));
+    if crate::print::is_print_action_enabled(db, "kitchen_ticket") {
+        if let Err(error) = print::enqueue_print_job(db, "kitchen_ticket", order_id, None) {
+            tracing::warn!(
+                order_id = %order_id,
+                error = %error,
+                "Failed to enqueue kitchen ticket for auto-approved order"
+            );
+        }
+    }
+    Some(matched)
+}
+
+#[tauri::command]
+pub async fn order_fetch_items_from_supabase(
+    arg0: Option<serde_json::Value>,
+    arg1: Option<String>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let order_id = payload_arg0_as_string(
+        arg0,
+        &["orderId", "order_id", "id", "supabaseId", "supabase_id"],
+    )
+    .or(arg1)
+    .ok_or("Missing orderId")?;
+
+    let items = SupabaseQuery::new("order_items")
+        .select(
+            "id,menu_item_id,menu_item_name,quantity,unit_price,total_price,notes,customizations",
+        )
+        .filter("order_id", format!("eq.{order_id}"))
+        .fetch_all()
+        .await
+        .inspect_err(|error| {
+            supabase::handle_auth_failure(
+                Some(&db),
+                &app,
+                "order_fetch_items_from_supabase",
+                error,
+            );
+        });
+    if let Ok(rows) = items {
         if !rows.is_empty() {
             let ids: Vec<String> = rows
                 .iter()
@@ -3608,27 +4522,22 @@ pub async fn order_fetch_items_from_supabase(
             let mut category_names_by_id: std::collections::HashMap<String, String> =
                 std::collections::HashMap::new();
             if !ids.is_empty() {
-                if let Ok(menu_items) = fetch_supabase_rows(
-                    "menu_items",
-                    &[
-                        ("select", "id,name,name_en,name_el,category_id".to_string()),
-                        ("id", format!("in.({})", ids.join(","))),
-                    ],
-                )
-                .await
+                if let Ok(menu_items) = SupabaseQuery::new("menu_items")
+                    .select("id,name,name_en,name_el,category_id")
+                    .filter("id", format!("in.({})", ids.join(",")))
+                    .fetch_all()
+                    .await
                 {
-                    if let Some(arr) = menu_items.as_array() {
-                        for row in arr {
-                            if let Some(id) = row.get("id").and_then(|v| v.as_str()) {
-                                let name = value_str(row, &["name", "name_en", "name_el"])
-                                    .unwrap_or_else(|| "Item".to_string());
-                                names.insert(id.to_string(), name);
-                                if let Some(category_id) =
-                                    row.get("category_id").and_then(|v| v.as_str())
-                                {
-                                    category_ids_by_item
-                                        .insert(id.to_string(), category_id.to_string());
-                                }
+                    for row in &menu_items {
+                        if let Some(id) = row.get("id").and_then(|v| v.as_str()) {
+                            let name = value_str(row, &["name", "name_en", "name_el"])
+                                .unwrap_or_else(|| "Item".to_string());
+                            names.insert(id.to_string(), name);
+                            if let Some(category_id) =
+                                row.get("category_id").and_then(|v| v.as_str())
+                            {
+                                category_ids_by_item
+                                    .insert(id.to_string(), category_id.to_string());
                             }
                         }
                     }
@@ -3641,22 +4550,17 @@ pub async fn order_fetch_items_from_supabase(
                     .into_iter()
                     .collect();
                 if !category_ids.is_empty() {
-                    if let Ok(categories) = fetch_supabase_rows(
-                        "categories",
-                        &[
-                            ("select", "id,name,name_en,name_el".to_string()),
-                            ("id", format!("in.({})", category_ids.join(","))),
-                        ],
-                    )
-                    .await
+                    if let Ok(categories) = SupabaseQuery::new("categories")
+                        .select("id,name,name_en,name_el")
+                        .filter("id", format!("in.({})", category_ids.join(",")))
+                        .fetch_all()
+                        .await
                     {
-                        if let Some(arr) = categories.as_array() {
-                            for row in arr {
-                                if let Some(id) = row.get("id").and_then(|v| v.as_str()) {
-                                    let name = value_str(row, &["name", "name_en", "name_el"])
-                                        .unwrap_or_else(|| "Category".to_string());
-                                    category_names_by_id.insert(id.to_string(), name);
-                                }
+                        for row in &categories {
+                            if let Some(id) = row.get("id").and_then(|v| v.as_str()) {
+                                let name = value_str(row, &["name", "name_en", "name_el"])
+                                    .unwrap_or_else(|| "Category".to_string());
+                                category_names_by_id.insert(id.to_string(), name);
                             }
                         }
                     }
@@ -3708,34 +4612,738 @@ pub async fn order_fetch_items_from_supabase(
         }
     }
 
-    // Fallback: use local order cache (by local ID or Supabase ID).
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let items_str: Option<String> = conn
-        .query_row(
-            "SELECT items FROM orders WHERE id = ?1 OR supabase_id = ?1 LIMIT 1",
-            rusqlite::params![order_id],
-            |row| row.get(0),
-        )
-        .ok();
-    if let Some(s) = items_str {
-        if let Ok(v) = serde_json::from_str::<serde_json::Value>(&s) {
-            if v.is_array() {
-                return Ok(v);
-            }
+    // Fallback: use local order cache (by local ID or Supabase ID).
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let items_str: Option<String> = conn
+        .query_row(
+            "SELECT items FROM orders WHERE id = ?1 OR supabase_id = ?1 LIMIT 1",
+            rusqlite::params![order_id],
+            |row| row.get(0),
+        )
+        .ok();
+    if let Some(s) = items_str {
+        if let Ok(v) = serde_json::from_str::<serde_json::Value>(&s) {
+            if v.is_array() {
+                return Ok(v);
+            }
+        }
+    }
+    Ok(serde_json::json!([]))
+}
+
+/// Validate combo lines of a create payload in place. Price fix-ups shift
+/// the renderer-sent totals by the same cents so they still add up; a
+/// structural problem returns the `invalid_combo` rejection instead.
+/// Check an incoming order against `sync::order_schema`. The outer `Result`
+/// is a lock failure; the inner one is the warnings or the structured
+/// rejection to return to the renderer.
+fn validate_create_payload_schema(
+    db: &db::DbState,
+    payload: &mut serde_json::Value,
+) -> Result<Result<Vec<order_schema::FieldIssue>, serde_json::Value>, String> {
+    let (mode, allergens) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        (
+            order_schema::ValidationMode::for_ipc(&conn),
+            allergens::allowed(&conn),
+        )
+    };
+    let validated = order_schema::validate_order(payload, mode).and_then(|validated| {
+        let issues = order_schema::check_allergy_flags(payload, &allergens);
+        if issues.is_empty() {
+            Ok(validated)
+        } else {
+            Err(issues)
+        }
+    });
+    Ok(match validated {
+        Ok(validated) => Ok(validated.warnings),
+        Err(issues) => {
+            tracing::warn!(
+                fields = ?issues.iter().map(|issue| issue.field.as_str()).collect::<Vec<_>>(),
+                "Rejected invalid order payload"
+            );
+            Err(order_schema::rejection_response(&issues))
+        }
+    })
+}
+
+fn attach_schema_warnings(resp: &mut serde_json::Value, warnings: &[order_schema::FieldIssue]) {
+    if warnings.is_empty() {
+        return;
+    }
+    if let Some(obj) = resp.as_object_mut() {
+        obj.insert(
+            "validationWarnings".to_string(),
+            serde_json::to_value(warnings).unwrap_or_default(),
+        );
+    }
+}
+
+fn validate_create_payload_combos(
+    db: &db::DbState,
+    payload: &mut serde_json::Value,
+) -> Result<Option<serde_json::Value>, String> {
+    let order_type = payload
+        .get("orderType")
+        .or_else(|| payload.get("order_type"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("dine-in")
+        .to_string();
+    let Some(items) = payload.get_mut("items").and_then(|v| v.as_array_mut()) else {
+        return Ok(None);
+    };
+    let outcome = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        combos::validate_items(&conn, items, &order_type, chrono::Local::now())
+    };
+    if !outcome.violations.is_empty() {
+        return Ok(Some(combos::rejection_response(&outcome.violations)));
+    }
+    shift_create_payload_totals(payload, outcome.total_delta);
+    Ok(None)
+}
+
+/// Move the payload's totals by a change in the items total.
+fn shift_create_payload_totals(payload: &mut serde_json::Value, delta: Cents) {
+    if delta.is_zero() {
+        return;
+    }
+    if let Some(obj) = payload.as_object_mut() {
+        for key in ["totalAmount", "total_amount", "subtotal"] {
+            if let Some(current) = obj.get(key).and_then(|v| v.as_f64()) {
+                let adjusted = Cents::round_half_even(current) + delta;
+                obj.insert(key.to_string(), serde_json::json!(adjusted.to_f64_dp2()));
+            }
+        }
+    }
+}
+
+/// Price a new order's lines with the pricing rules active now. Markers
+/// sent by the client are dropped: rules are only ever applied here.
+fn apply_create_payload_pricing(
+    db: &db::DbState,
+    payload: &mut serde_json::Value,
+) -> Result<(), String> {
+    let Some(items) = payload.get_mut("items").and_then(|v| v.as_array_mut()) else {
+        return Ok(());
+    };
+    for item in items.iter_mut() {
+        if let Some(obj) = item.as_object_mut() {
+            obj.remove(pricing_rules::LINE_MARKER_KEY);
+        }
+    }
+    let delta = db.read(|conn| pricing_rules::apply_to_items(conn, items, chrono::Local::now()))?;
+    shift_create_payload_totals(payload, delta);
+    Ok(())
+}
+
+/// Check a single combo line against the cached menu without saving
+/// anything, so the cart can surface problems before checkout.
+#[tauri::command]
+pub async fn order_validate_combo(
+    arg0: Option<serde_json::Value>,
+    arg1: Option<String>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let payload = arg0.ok_or("Missing combo line")?;
+    let mut line = payload.get("item").cloned().unwrap_or(payload.clone());
+    let order_type = arg1
+        .or_else(|| {
+            payload
+                .get("orderType")
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| "dine-in".to_string());
+    if !combos::is_combo_line(&line) {
+        return Err("Item is not a combo line".into());
+    }
+    let outcome = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        combos::validate_items(
+            &conn,
+            std::slice::from_mut(&mut line),
+            &order_type,
+            chrono::Local::now(),
+        )
+    };
+    if !outcome.violations.is_empty() {
+        return Ok(combos::rejection_response(&outcome.violations));
+    }
+    Ok(serde_json::json!({
+        "success": true,
+        "normalized": outcome.normalized_lines > 0,
+        "item": line,
+    }))
+}
+
+#[derive(Debug, PartialEq)]
+struct PrepEstimatePayload {
+    order_type: String,
+    items: Vec<serde_json::Value>,
+    load: Option<i64>,
+}
+
+fn parse_prep_estimate_payload(
+    arg0: Option<serde_json::Value>,
+) -> Result<PrepEstimatePayload, String> {
+    let payload = arg0.ok_or("Missing prep-time payload")?;
+    let items = payload
+        .get("items")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .ok_or("Missing items")?;
+    Ok(PrepEstimatePayload {
+        order_type: value_str(&payload, &["orderType", "order_type"])
+            .unwrap_or_else(|| "pickup".to_string()),
+        items,
+        load: value_i64(
+            &payload,
+            &["openOrders", "open_orders", "preparingCount", "load"],
+        ),
+    })
+}
+
+/// Suggested prep minutes for a cart, with a confidence band. The load
+/// defaults to the number of orders currently preparing.
+#[tauri::command]
+pub async fn order_estimate_prep_time(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let payload = parse_prep_estimate_payload(arg0)?;
+    let estimate = db.read(|conn| {
+        let load = match payload.load {
+            Some(load) => load,
+            None => prep_time::preparing_load(conn, None)?,
+        };
+        prep_time::estimate(conn, &payload.order_type, &payload.items, load)
+    })?;
+    Ok(serde_json::json!({
+        "success": true,
+        "data": estimate.to_json(),
+    }))
+}
+
+#[derive(Debug)]
+struct RefreshPricesPayload {
+    order_id: String,
+    confirm: bool,
+    expected_version: Option<i64>,
+}
+
+fn parse_refresh_prices_payload(
+    arg0: Option<serde_json::Value>,
+) -> Result<RefreshPricesPayload, String> {
+    let confirm = arg0
+        .as_ref()
+        .and_then(|payload| payload.get("confirm"))
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let expected_version = arg0
+        .as_ref()
+        .and_then(|payload| value_i64(payload, &["expectedVersion", "expected_version"]));
+    let order_id =
+        payload_arg0_as_string(arg0, &["orderId", "order_id", "id"]).ok_or("Missing orderId")?;
+    Ok(RefreshPricesPayload {
+        order_id,
+        confirm,
+        expected_version,
+    })
+}
+
+/// Re-price an open order's lines from the current menu. Without `confirm`
+/// only the before/after diff is returned so the cashier can check it; with
+/// it the new prices are saved and the order total moves by the difference,
+/// a percentage discount scaling with it.
+#[tauri::command]
+pub async fn order_refresh_prices(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let payload = parse_refresh_prices_payload(arg0)?;
+    let actor = crate::auth::current_staff_id(&auth_state);
+    let committed = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let order_id = resolve_order_id(&conn, &payload.order_id).ok_or("Order not found")?;
+        let refresh = stale_prices::reprice_order(&conn, &order_id)?;
+        if !payload.confirm {
+            let mut resp = refresh.to_json();
+            if let Some(obj) = resp.as_object_mut() {
+                obj.insert("success".to_string(), serde_json::json!(true));
+                obj.insert("orderId".to_string(), serde_json::json!(order_id));
+                obj.insert("confirmRequired".to_string(), serde_json::json!(true));
+            }
+            return Ok(resp);
+        }
+        if refresh.changes.is_empty() && !refresh.had_flags {
+            return Ok(serde_json::json!({
+                "success": true,
+                "orderId": order_id,
+                "changes": [],
+                "delta": 0.0
+            }));
+        }
+        if let Some(locked) = order_locks::guard_order_unlocked(&conn, &order_id)? {
+            return Ok(locked);
+        }
+        let new_version = match claim_order_version(&conn, &order_id, payload.expected_version)? {
+            VersionClaim::Claimed(version) => version,
+            VersionClaim::Conflict { current_version } => {
+                drop(conn);
+                return Ok(version_conflict_response(
+                    &db,
+                    &order_id,
+                    payload.expected_version,
+                    current_version,
+                ));
+            }
+        };
+        let (total_cents, subtotal_cents, discount_cents, discount_percentage): (
+            i64,
+            i64,
+            i64,
+            f64,
+        ) = conn
+            .query_row(
+                "SELECT COALESCE(total_amount_cents, CAST(ROUND(COALESCE(total_amount, 0) * 100) AS INTEGER)),
+                        COALESCE(subtotal_cents, CAST(ROUND(COALESCE(subtotal, 0) * 100) AS INTEGER)),
+                        COALESCE(discount_amount_cents, CAST(ROUND(COALESCE(discount_amount, 0) * 100) AS INTEGER)),
+                        COALESCE(discount_percentage, 0)
+                 FROM orders WHERE id = ?1",
+                rusqlite::params![order_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .map_err(|e| format!("load order totals: {e}"))?;
+        let delta = refresh.delta();
+        let discount_delta = if discount_percentage > 0.0 {
+            Cents::round_half_even(delta.to_f64_dp2() * discount_percentage / 100.0)
+        } else {
+            Cents::ZERO
+        };
+        let subtotal = Cents::new(subtotal_cents) + delta;
+        let discount = Cents::new(discount_cents) + discount_delta;
+        let service_charge =
+            crate::service_charge::reprice(&conn, &order_id, refresh.items_after, Some(discount))?;
+        let total = Cents::new(total_cents) + delta - discount_delta
+            + service_charge.map_or(Cents::ZERO, |repriced| repriced.total_delta);
+        let items_json =
+            serde_json::to_string(&refresh.items).map_err(|e| format!("serialize items: {e}"))?;
+        conn.execute(
+            "UPDATE orders
+             SET items = ?1,
+                 subtotal = ?2, subtotal_cents = ?3,
+                 discount_amount = ?4, discount_amount_cents = ?5,
+                 total_amount = ?6, total_amount_cents = ?7,
+                 sync_status = 'pending', updated_at = ?8
+             WHERE id = ?9",
+            rusqlite::params![
+                items_json,
+                subtotal.to_f64_dp2(),
+                subtotal.as_i64(),
+                discount.to_f64_dp2(),
+                discount.as_i64(),
+                total.to_f64_dp2(),
+                total.as_i64(),
+                Utc::now().to_rfc3339(),
+                order_id
+            ],
+        )
+        .map_err(|e| format!("save refreshed prices: {e}"))?;
+        if let Some(repriced) = service_charge {
+            repriced.store(&conn, &order_id)?;
+        }
+        crate::tax::capture_order_breakdown(&conn, &order_id, &refresh.items)?;
+        let _ = enqueue_order_sync_payload(
+            &conn,
+            &order_id,
+            &serde_json::json!({ "orderId": order_id, "items": refresh.items }),
+        );
+        order_events::append(
+            &conn,
+            &order_id,
+            order_events::PRICES_REFRESHED,
+            actor.as_deref(),
+            serde_json::json!({
+                "linesChanged": refresh.changes.len(),
+                "delta": delta.to_f64_dp2(),
+                "total": total.to_f64_dp2(),
+                "version": new_version
+            }),
+        );
+        let mut resp = refresh.to_json();
+        if let Some(obj) = resp.as_object_mut() {
+            obj.insert("success".to_string(), serde_json::json!(true));
+            obj.insert("orderId".to_string(), serde_json::json!(order_id));
+            obj.insert(
+                "totalAmount".to_string(),
+                serde_json::json!(total.to_f64_dp2()),
+            );
+            obj.insert("version".to_string(), serde_json::json!(new_version));
+        }
+        (order_id, resp)
+    };
+
+    let (order_id, resp) = committed;
+    if let Ok(order_json) = sync::get_order_by_id(&db, &order_id) {
+        let _ = app.emit("order_realtime_update", order_json);
+    }
+    Ok(resp)
+}
+
+#[derive(Debug, PartialEq)]
+struct AllergyFlagsPayload {
+    order_id: String,
+    flags: Vec<String>,
+    items: Vec<(usize, Vec<String>)>,
+    expected_version: Option<i64>,
+    printer_profile_id: Option<String>,
+}
+
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_allergy_flags_payload(
+    arg0: Option<serde_json::Value>,
+) -> Result<AllergyFlagsPayload, String> {
+    let payload = arg0.clone().unwrap_or(Value::Null);
+    let flags = string_list(payload.get("allergyFlags").or_else(|| payload.get("flags")));
+    let mut items = Vec::new();
+    for entry in payload
+        .get("items")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+    {
+        let index = value_i64(entry, &["index", "itemIndex", "item_index"])
+            .filter(|index| *index >= 0)
+            .ok_or("Each item needs a non-negative index")?;
+        let item_flags = string_list(entry.get("allergyFlags").or_else(|| entry.get("flags")));
+        items.push((index as usize, item_flags));
+    }
+    let expected_version = value_i64(&payload, &["expectedVersion", "expected_version"]);
+    let printer_profile_id = value_str(&payload, &["printerProfileId", "printer_profile_id"]);
+    let order_id =
+        payload_arg0_as_string(arg0, &["orderId", "order_id", "id"]).ok_or("Missing orderId")?;
+    if flags.is_empty() && items.iter().all(|(_, flags)| flags.is_empty()) {
+        return Err("No allergy flags given".into());
+    }
+    Ok(AllergyFlagsPayload {
+        order_id,
+        flags,
+        items,
+        expected_version,
+        printer_profile_id,
+    })
+}
+
+/// Add allergy flags to an order the kitchen has not finished. Flags are only
+/// ever added. If the kitchen already has its ticket, an "ALLERGY UPDATE"
+/// amendment naming just the affected lines is printed instead of a full
+/// reprint.
+#[tauri::command]
+pub async fn order_set_allergy_flags(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let payload = parse_allergy_flags_payload(arg0)?;
+    let actor = crate::auth::current_staff_id(&auth_state);
+    let (order_id, applied, new_version, amend) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let order_id = resolve_order_id(&conn, &payload.order_id).ok_or("Order not found")?;
+        let allowed = allergens::allowed(&conn);
+        let mut update = allergens::FlagUpdate {
+            order: allergens::validate(&payload.flags, &allowed)?,
+            items: Vec::with_capacity(payload.items.len()),
+        };
+        for (index, flags) in &payload.items {
+            update
+                .items
+                .push((*index, allergens::validate(flags, &allowed)?));
+        }
+        allergens::check_unfired(&conn, &order_id)?;
+        let applied = allergens::plan(&conn, &order_id, &update)?;
+        if !applied.changed() {
+            return Ok(serde_json::json!({
+                "success": true,
+                "orderId": order_id,
+                "allergyFlags": applied.order_flags,
+                "items": [],
+                "amendmentPrinted": false
+            }));
+        }
+        if let Some(locked) = order_locks::guard_order_unlocked(&conn, &order_id)? {
+            return Ok(locked);
+        }
+        let new_version = match claim_order_version(&conn, &order_id, payload.expected_version)? {
+            VersionClaim::Claimed(version) => version,
+            VersionClaim::Conflict { current_version } => {
+                drop(conn);
+                return Ok(version_conflict_response(
+                    &db,
+                    &order_id,
+                    payload.expected_version,
+                    current_version,
+                ));
+            }
+        };
+        allergens::save(&conn, &order_id, &applied, &Utc::now().to_rfc3339())?;
+        let _ = enqueue_order_sync_payload(
+            &conn,
+            &order_id,
+            &serde_json::json!({
+                "orderId": order_id,
+                "items": applied.items,
+                "allergyFlags": applied.order_flags,
+                "allergy_flags": applied.order_flags
+            }),
+        );
+        order_events::append(
+            &conn,
+            &order_id,
+            order_events::ALLERGY_FLAGS_UPDATED,
+            actor.as_deref(),
+            serde_json::json!({
+                "addedOrderFlags": applied.added_order_flags,
+                "items": applied.changed_items,
+                "version": new_version
+            }),
+        );
+        let amend = allergens::kitchen_ticket_sent(&conn, &order_id)
+            && (!applied.added_order_flags.is_empty() || !applied.sent_items.is_empty());
+        (order_id, applied, new_version, amend)
+    };
+
+    let mut job_id: Option<String> = None;
+    if amend && crate::print::is_print_action_enabled(&db, "kitchen_ticket") {
+        let job = print::enqueue_print_job_with_payload(
+            &db,
+            "kitchen_ticket",
+            &order_id,
+            payload.printer_profile_id.as_deref(),
+            Some(&allergens::amendment_payload(&applied.sent_items)),
+        )?;
+        job_id = job.get("jobId").and_then(Value::as_str).map(str::to_string);
+        let data_dir = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("app data dir: {e}"))?;
+        print::spawn_pending_job_processing(
+            app.clone(),
+            data_dir,
+            format!("allergy update for order {order_id}"),
+        );
+    }
+    if let Ok(order_json) = sync::get_order_by_id(&db, &order_id) {
+        let _ = app.emit("order_realtime_update", order_json);
+    }
+    Ok(serde_json::json!({
+        "success": true,
+        "orderId": order_id,
+        "version": new_version,
+        "allergyFlags": applied.order_flags,
+        "items": applied.changed_items,
+        "amendmentPrinted": job_id.is_some(),
+        "jobId": job_id
+    }))
+}
+
+/// Waive the automatic service charge on an unpaid order. Needs a manager
+/// PIN (checked against the admin PIN) and a `service_charge_waiver` reason
+/// code; the waiver is recorded on the order timeline.
+#[tauri::command]
+pub async fn order_waive_service_charge(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let payload = arg0.clone().unwrap_or(Value::Null);
+    let order_id_raw =
+        payload_arg0_as_string(arg0, &["orderId", "order_id", "id"]).ok_or("Missing orderId")?;
+    let pin = value_str(&payload, &["managerPin", "manager_pin"])
+        .ok_or("Waiving the service charge needs a manager PIN")?;
+    if !crate::auth::verify_privileged_pin_with_lockout(&pin, "admin", &db, &auth_state)? {
+        return Err("Invalid manager PIN".into());
+    }
+    let manager = crate::auth::current_staff_id(&auth_state);
+    let now = Utc::now().to_rfc3339();
+    let (order_id, waived) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let order_id = resolve_order_id(&conn, &order_id_raw).ok_or("Order not found")?;
+        if let Some(locked) = order_locks::guard_order_unlocked(&conn, &order_id)? {
+            return Ok(locked);
+        }
+        let reason = crate::reasons::require(
+            &conn,
+            crate::reasons::ReasonAction::ServiceChargeWaiver,
+            value_str(&payload, &["reasonCode", "reason_code"]).as_deref(),
+            value_str(&payload, &["reason", "notes"]).as_deref(),
+        )?;
+        conn.execute_batch("BEGIN IMMEDIATE")
+            .map_err(|e| format!("begin transaction: {e}"))?;
+        let waived =
+            match crate::service_charge::waive(&conn, &order_id, &reason, manager.as_deref(), &now)
+            {
+                Ok(waived) => waived,
+                Err(e) => {
+                    let _ = conn.execute_batch("ROLLBACK");
+                    return Err(e);
+                }
+            };
+        conn.execute_batch("COMMIT")
+            .map_err(|e| format!("commit service charge waiver: {e}"))?;
+        let _ = enqueue_order_sync_payload(
+            &conn,
+            &order_id,
+            &serde_json::json!({
+                "orderId": order_id,
+                "totalAmount": waived["totalAmount"],
+                "taxAmount": waived["taxAmount"],
+                "serviceCharge": 0,
+                "service_charge": 0,
+                "service_charge_cents": 0,
+                "serviceChargeWaived": waived["waived"],
+                "service_charge_waived_cents": waived["waived_cents"],
+                "serviceChargeWaiverReason": reason.code,
+                "serviceChargeWaivedAt": now
+            }),
+        );
+        (order_id, waived)
+    };
+    if let Ok(order_json) = sync::get_order_by_id(&db, &order_id) {
+        let _ = app.emit("order_realtime_update", order_json);
+    }
+    Ok(serde_json::json!({
+        "success": true,
+        "orderId": order_id,
+        "serviceChargeWaiver": waived
+    }))
+}
+
+/// Order ids targeted by an ETA change: `orderIds` for a bulk change,
+/// otherwise the single `orderId`.
+fn parse_order_eta_ids(arg0: Option<Value>) -> Result<Vec<String>, String> {
+    if let Some(ids) = arg0
+        .as_ref()
+        .and_then(|payload| payload.get("orderIds").or_else(|| payload.get("order_ids")))
+        .and_then(Value::as_array)
+    {
+        let ids: Vec<String> = ids
+            .iter()
+            .filter_map(|id| normalize_optional_text(id.as_str().map(str::to_string)))
+            .fold(Vec::new(), |mut unique, id| {
+                if !unique.contains(&id) {
+                    unique.push(id);
+                }
+                unique
+            });
+        if ids.is_empty() {
+            return Err("orderIds must not be empty".into());
         }
+        return Ok(ids);
     }
-    Ok(serde_json::json!([]))
+    payload_arg0_as_string(arg0, &["orderId", "order_id", "id"])
+        .map(|id| vec![id])
+        .ok_or_else(|| "Missing orderId".into())
 }
 
+/// Move the ETA of one or more approved orders, either to `estimatedTime`
+/// or by `deltaMinutes`. Needs an `eta_change` reason code. A bulk change
+/// runs in one transaction: if any order would be rejected, none change.
 #[tauri::command]
-pub async fn order_create(
+pub async fn order_update_eta(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-    _app: tauri::AppHandle,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+    app: tauri::AppHandle,
 ) -> Result<serde_json::Value, String> {
-    let payload = arg0.ok_or("Missing order payload")?;
-    let normalized = payload.get("orderData").cloned().unwrap_or(payload);
-    let mut resp = sync::create_order(&db, &normalized)?;
+    let payload = arg0.clone().unwrap_or(Value::Null);
+    let order_ids_raw = parse_order_eta_ids(arg0)?;
+    let adjustment = order_eta::Adjustment::from_payload(&payload)?;
+    let actor = crate::auth::current_staff_id(&auth_state);
+    let updates = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let mut order_ids = Vec::with_capacity(order_ids_raw.len());
+        for raw in &order_ids_raw {
+            let order_id =
+                resolve_order_id(&conn, raw).ok_or_else(|| format!("Order not found: {raw}"))?;
+            if let Some(locked) = order_locks::guard_order_unlocked(&conn, &order_id)? {
+                return Ok(locked);
+            }
+            order_ids.push(order_id);
+        }
+        let reason = order_eta::reason(&conn, &payload)?;
+        conn.execute_batch("BEGIN IMMEDIATE")
+            .map_err(|e| format!("begin transaction: {e}"))?;
+        let updates = match order_eta::update_many(
+            &conn,
+            &order_ids,
+            adjustment,
+            &reason,
+            actor.as_deref(),
+            Utc::now(),
+        ) {
+            Ok(updates) => updates,
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                return Err(e);
+            }
+        };
+        conn.execute_batch("COMMIT")
+            .map_err(|e| format!("commit eta update: {e}"))?;
+        updates
+    };
+    for update in &updates {
+        let _ = app.emit("order_eta_updated", update);
+        if let Some(order_id) = update["orderId"].as_str() {
+            if let Ok(order_json) = sync::get_order_by_id(&db, order_id) {
+                let _ = app.emit("order_realtime_update", order_json);
+            }
+        }
+    }
+    Ok(serde_json::json!({
+        "success": true,
+        "updates": updates
+    }))
+}
+
+/// Validate and persist a create-order payload. Shared by both create
+/// commands; `enqueue_fiscal` hands the new order to the fiscal dispatcher.
+pub(crate) fn create_order_from_payload(
+    db: &db::DbState,
+    payload: serde_json::Value,
+    enqueue_fiscal: bool,
+) -> Result<serde_json::Value, String> {
+    let mut normalized = payload.get("orderData").cloned().unwrap_or(payload);
+    if kiosk::is_enabled() {
+        let config = db.read(|conn| Ok(kiosk::KioskConfig::load(conn)))?;
+        kiosk::apply_to_order_payload(&config, &mut normalized);
+    }
+    let schema_warnings = match validate_create_payload_schema(db, &mut normalized)? {
+        Ok(warnings) => warnings,
+        Err(rejection) => return Ok(rejection),
+    };
+    if let Some(rejection) = validate_create_payload_combos(db, &mut normalized)? {
+        return Ok(rejection);
+    }
+    apply_create_payload_pricing(db, &mut normalized)?;
+    let mut resp = sync::create_order(db, &normalized)?;
+    attach_schema_warnings(&mut resp, &schema_warnings);
     let order_id = resp
         .get("orderId")
         .and_then(|v| v.as_str())
@@ -3755,6 +5363,10 @@ pub async fn order_create(
                 .or_insert_with(|| serde_json::json!({ "orderId": order_id.clone() }));
         }
 
+        if !enqueue_fiscal {
+            return Ok(resp);
+        }
+
         // T22 (fiscalization-core / Req 4.2 + Req 12): best-effort fire-and-forget
         // handoff to the fiscal dispatcher. The order itself is already persisted;
         // a fiscal enqueue failure here MUST NOT fail the order_create command —
@@ -3774,46 +5386,391 @@ pub async fn order_create(
         }
     }
 
-    // NOTE: We intentionally do NOT emit order_created/order_realtime_update here.
-    // Self-created orders are added to state directly in the frontend store.
-    // Only order_save_from_remote() emits these events (for orders from other terminals).
     Ok(resp)
 }
 
+/// Record the staff member of the active terminal session on a create
+/// payload, so orders are attributed to whoever is signed in rather than
+/// whoever the renderer last remembered.
+fn stamp_session_staff(payload: &mut serde_json::Value, staff_id: Option<String>) {
+    let Some(staff_id) = staff_id else {
+        return;
+    };
+    let target = if payload.get("orderData").is_some_and(|v| v.is_object()) {
+        &mut payload["orderData"]
+    } else {
+        payload
+    };
+    if let Some(obj) = target.as_object_mut() {
+        obj.insert("sessionStaffId".into(), serde_json::json!(staff_id));
+    }
+}
+
+#[tauri::command]
+pub async fn order_create(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+    _app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let session_staff_id = crate::auth::current_database_staff_id(&auth_state);
+    crate::diagnostics::metrics::track("order_create", async move {
+        let mut payload = arg0.ok_or("Missing order payload")?;
+        stamp_session_staff(&mut payload, session_staff_id);
+        // NOTE: We intentionally do NOT emit order_created/order_realtime_update here.
+        // Self-created orders are added to state directly in the frontend store.
+        // Only order_save_from_remote() emits these events (for orders from other terminals).
+        let key = idempotency::request_key(&payload);
+        idempotency::run_once(&db, "order_create", key, || {
+            db.run_blocking(move |db| create_order_from_payload(db, payload, true))
+        })
+        .await
+    })
+    .await
+}
+
 #[tauri::command]
 pub async fn order_create_with_initial_payment(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
     _app: tauri::AppHandle,
 ) -> Result<serde_json::Value, String> {
-    let payload = arg0.ok_or("Missing order payload")?;
-    let normalized = payload.get("orderData").cloned().unwrap_or(payload);
-    let mut resp = sync::create_order(&db, &normalized)?;
-    let order_id = resp
-        .get("orderId")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string())
-        .or_else(|| {
-            resp.get("order")
-                .and_then(|v| v.get("id"))
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-        });
+    let session_staff_id = crate::auth::current_database_staff_id(&auth_state);
+    crate::diagnostics::metrics::track("order_create_with_initial_payment", async move {
+        let mut payload = arg0.ok_or("Missing order payload")?;
+        stamp_session_staff(&mut payload, session_staff_id);
+        db.run_blocking(move |db| create_order_from_payload(db, payload, false))
+            .await
+    })
+    .await
+}
 
-    if let Some(order_id) = order_id.clone() {
+fn parse_order_duplicate_payload(arg0: Option<serde_json::Value>) -> Result<String, String> {
+    payload_arg0_as_string(
+        arg0,
+        &[
+            "sourceOrderId",
+            "source_order_id",
+            "orderId",
+            "order_id",
+            "id",
+            "supabaseId",
+            "supabase_id",
+        ],
+    )
+    .ok_or_else(|| "Missing source orderId".to_string())
+}
+
+fn duplicate_order(
+    db: &db::DbState,
+    source_id_raw: &str,
+    actor: Option<&str>,
+) -> Result<serde_json::Value, String> {
+    let plan = db.read(|conn| {
+        let source_id = resolve_order_id(conn, source_id_raw)
+            .ok_or_else(|| format!("Order not found: {source_id_raw}"))?;
+        order_duplicate::build_payload(conn, &source_id, chrono::Local::now())
+    })?;
+    let mut resp = create_order_from_payload(db, plan.payload, true)?;
+    let Some(order_id) = value_str(&resp, &["orderId"]) else {
+        // Schema or combo rejection; hand it back with what was dropped.
         if let Some(obj) = resp.as_object_mut() {
-            obj.entry("orderId".to_string())
-                .or_insert_with(|| serde_json::Value::String(order_id.clone()));
-            obj.entry("data".to_string())
-                .or_insert_with(|| serde_json::json!({ "orderId": order_id.clone() }));
+            obj.insert("skippedItems".into(), serde_json::json!(plan.skipped_items));
+        }
+        return Ok(resp);
+    };
+    db.write(|conn| {
+        order_events::append(
+            conn,
+            &order_id,
+            order_events::DUPLICATED_FROM,
+            actor,
+            serde_json::json!({
+                "sourceOrderId": plan.source_order_id,
+                "sourceOrderNumber": plan.source_order_number,
+                "skippedItems": plan.skipped_items.len(),
+            }),
+        );
+        Ok(())
+    })?;
+    let order = sync::get_order_by_id(db, &order_id)?;
+    if let Some(obj) = resp.as_object_mut() {
+        obj.insert("order".into(), order);
+        obj.insert(
+            "sourceOrderId".into(),
+            serde_json::json!(plan.source_order_id),
+        );
+        obj.insert("skippedItems".into(), serde_json::json!(plan.skipped_items));
+    }
+    Ok(resp)
+}
+
+/// "Same as last time": create a new order from a past one's items and
+/// customer, priced from the current menu. Lines that can no longer be sold
+/// are left out and listed in `skippedItems`.
+#[tauri::command]
+pub async fn order_duplicate(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+) -> Result<serde_json::Value, String> {
+    let source_id = parse_order_duplicate_payload(arg0)?;
+    let actor = crate::auth::current_staff_id(&auth_state);
+    db.run_blocking(move |db| duplicate_order(db, &source_id, actor.as_deref()))
+        .await
+}
+
+#[derive(Debug)]
+struct OrderReturnPayload {
+    original_order_id: String,
+    lines: Vec<returns::ReturnLine>,
+    reason_code: Option<String>,
+    reason: Option<String>,
+    staff_shift_id: Option<String>,
+    restock: bool,
+    /// `order_create` payload for the replacement order of an exchange.
+    exchange: Option<Value>,
+}
+
+fn parse_order_return_payload(arg0: Option<Value>) -> Result<OrderReturnPayload, String> {
+    let payload = arg0.ok_or("Missing return payload")?;
+    let original_order_id = value_str(
+        &payload,
+        &[
+            "originalOrderId",
+            "original_order_id",
+            "orderId",
+            "order_id",
+        ],
+    )
+    .ok_or("Missing originalOrderId")?;
+    let exchange = match (
+        payload.get("exchange").filter(|value| value.is_object()),
+        payload
+            .get("exchangeItems")
+            .or_else(|| payload.get("exchange_items"))
+            .and_then(Value::as_array),
+    ) {
+        (Some(order), _) => Some(order.clone()),
+        (None, Some(items)) if !items.is_empty() => Some(serde_json::json!({ "items": items })),
+        _ => None,
+    };
+    if exchange
+        .as_ref()
+        .and_then(|order| order.get("items"))
+        .is_some_and(|items| items.as_array().map_or(true, Vec::is_empty))
+    {
+        return Err("Exchange order has no items".into());
+    }
+    Ok(OrderReturnPayload {
+        original_order_id,
+        lines: returns::parse_lines(&payload)?,
+        reason_code: value_str(&payload, &["reasonCode", "reason_code"]),
+        reason: value_str(&payload, &["reason"]),
+        staff_shift_id: value_str(&payload, &["staffShiftId", "staff_shift_id"]),
+        restock: payload.get("restock").and_then(Value::as_bool) == Some(true),
+        exchange,
+    })
+}
+
+/// Open the replacement order of an exchange: the caller's payload with the
+/// sale's order type and customer filled in where left out, linked to the
+/// return order.
+fn open_exchange_order(
+    db: &db::DbState,
+    original_order_id: &str,
+    return_order_id: &str,
+    exchange: Value,
+) -> Result<Value, String> {
+    let fields = db.read(|conn| {
+        conn.query_row(
+            "SELECT order_type, customer_name, customer_phone, customer_email, customer_id
+             FROM orders WHERE id = ?1",
+            rusqlite::params![original_order_id],
+            |row| {
+                Ok([
+                    ("orderType", row.get::<_, Option<String>>(0)?),
+                    ("customerName", row.get::<_, Option<String>>(1)?),
+                    ("customerPhone", row.get::<_, Option<String>>(2)?),
+                    ("customerEmail", row.get::<_, Option<String>>(3)?),
+                    ("customerId", row.get::<_, Option<String>>(4)?),
+                ])
+            },
+        )
+        .map_err(|e| format!("load sale for exchange: {e}"))
+    })?;
+    let mut payload = exchange;
+    if let Some(obj) = payload.as_object_mut() {
+        for (key, value) in fields {
+            if let Some(value) = value.filter(|value| !value.trim().is_empty()) {
+                obj.entry(key.to_string())
+                    .or_insert_with(|| Value::String(value));
+            }
         }
+        obj.insert(
+            "relatedOrderId".into(),
+            Value::String(return_order_id.into()),
+        );
+    }
+    let mut resp = create_order_from_payload(db, payload, true)?;
+    let Some(order_id) = value_str(&resp, &["orderId"]) else {
+        // Schema or combo rejection; the return itself stands.
+        return Ok(resp);
+    };
+    db.write(|conn| {
+        conn.execute(
+            "UPDATE orders SET related_order_id = ?1 WHERE id = ?2",
+            rusqlite::params![return_order_id, order_id],
+        )
+        .map_err(|e| format!("link exchange order: {e}"))
+    })?;
+    if let Some(obj) = resp.as_object_mut() {
+        obj.insert("order".into(), sync::get_order_by_id(db, &order_id)?);
+    }
+    Ok(resp)
+}
+
+fn create_order_return(
+    db: &db::DbState,
+    parsed: OrderReturnPayload,
+    actor: Option<&str>,
+) -> Result<Value, String> {
+    let created = db.write(|conn| {
+        let original_order_id = resolve_order_id(conn, &parsed.original_order_id)
+            .ok_or_else(|| format!("Order not found: {}", parsed.original_order_id))?;
+        returns::create_return(
+            conn,
+            &returns::ReturnRequest {
+                original_order_id: &original_order_id,
+                lines: &parsed.lines,
+                reason_code: parsed.reason_code.as_deref(),
+                reason: parsed.reason.as_deref(),
+                staff_id: actor,
+                staff_shift_id: parsed.staff_shift_id.as_deref(),
+                restock: parsed.restock,
+            },
+        )
+    })?;
+    tracing::info!(
+        return_order_id = %created.return_order_id,
+        original_order_id = %created.original_order_id,
+        refund_cents = created.refund_cents,
+        "Return order created"
+    );
+
+    let exchange = match parsed.exchange {
+        Some(exchange) => Some(
+            open_exchange_order(
+                db,
+                &created.original_order_id,
+                &created.return_order_id,
+                exchange,
+            )
+            .unwrap_or_else(|error| {
+                tracing::warn!(
+                    return_order_id = %created.return_order_id,
+                    error = %error,
+                    "Exchange order could not be opened"
+                );
+                serde_json::json!({ "success": false, "error": error })
+            }),
+        ),
+        None => None,
+    };
+    Ok(serde_json::json!({
+        "success": true,
+        "data": created.to_json(),
+        "order": sync::get_order_by_id(db, &created.return_order_id)?,
+        "exchange": exchange,
+    }))
+}
+
+/// Take items of a past sale back as a linked return order, refunding them
+/// on the sale's payments, and optionally open the replacement order of an
+/// exchange.
+#[tauri::command]
+pub async fn order_create_return(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+) -> Result<Value, String> {
+    let parsed = parse_order_return_payload(arg0)?;
+    let actor = crate::auth::current_staff_id(&auth_state);
+    db.run_blocking(move |db| create_order_return(db, parsed, actor.as_deref()))
+        .await
+}
+
+/// Normalize a delivery-platform order and create it locally. A platform
+/// order that was already ingested returns the existing id.
+fn ingest_external_order(
+    db: &db::DbState,
+    app: &tauri::AppHandle,
+    platform: &str,
+    raw: &serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let normalized = order_plugins::normalize(platform, raw)?;
+    let existing = db.read(|conn| {
+        order_plugins::find_existing(conn, &normalized.plugin, &normalized.external_id)
+    })?;
+    if let Some(order_id) = existing {
+        return Ok(serde_json::json!({
+            "success": true,
+            "orderId": order_id,
+            "deduplicated": true
+        }));
     }
 
+    let resp = create_order_from_payload(db, normalized.payload, true)?;
+    if resp.get("deduplicated").and_then(Value::as_bool) == Some(true) {
+        return Ok(resp);
+    }
+    let Some(order_id) = value_str(&resp, &["orderId"]) else {
+        // Schema or combo rejection; hand the structured response back.
+        return Ok(resp);
+    };
+    db.write(|conn| order_plugins::store_raw_payload(conn, &order_id, raw))?;
+    tracing::info!(
+        order_id = %order_id,
+        plugin = %normalized.plugin,
+        external_id = %normalized.external_id,
+        "Ingested external platform order"
+    );
+    if let Ok(order_json) = sync::get_order_by_id(db, &order_id) {
+        let _ = app.emit("order_created", order_json);
+    }
     Ok(resp)
 }
 
+#[tauri::command]
+pub async fn order_ingest_external(
+    arg0: Option<serde_json::Value>,
+    arg1: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    // `{ platform, payload }`, or the platform id and the raw payload as two
+    // arguments.
+    let (platform, raw) = match (arg0, arg1) {
+        (Some(serde_json::Value::String(platform)), Some(raw)) => (platform, raw),
+        (Some(mut wrapper @ serde_json::Value::Object(_)), _) => {
+            let platform = value_str(&wrapper, &["platform", "plugin"])
+                .ok_or("Missing platform identifier")?;
+            let raw = wrapper
+                .as_object_mut()
+                .and_then(|obj| obj.remove("payload").or_else(|| obj.remove("order")))
+                .ok_or("Missing external order payload")?;
+            (platform, raw)
+        }
+        _ => return Err("Missing external order payload".into()),
+    };
+    db.run_blocking(move |db| ingest_external_order(db, &app, &platform, &raw))
+        .await
+}
+
 #[tauri::command]
 pub async fn orders_clear_all(
+    arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
     auth_state: tauri::State<'_, crate::auth::AuthState>,
     app: tauri::AppHandle,
@@ -3825,6 +5782,12 @@ pub async fn orders_clear_all(
         &db,
         &auth_state,
     )?;
+    let archive_files = crate::destructive_ops::confirm(
+        &db,
+        crate::destructive_ops::DestructiveOp::OrdersClearAll,
+        arg0.as_ref(),
+        crate::auth::current_staff_id(&auth_state).as_deref(),
+    )?;
     crate::recovery::snapshot_before_destructive_action(
         &db,
         crate::recovery::RecoveryPointKind::PreClearOperationalData,
@@ -3837,7 +5800,8 @@ pub async fn orders_clear_all(
     let _ = app.emit("orders_cleared", serde_json::json!({ "count": count }));
     Ok(serde_json::json!({
         "success": true,
-        "cleared": count
+        "cleared": count,
+        "archiveFiles": archive_files
     }))
 }
 
@@ -3873,15 +5837,112 @@ pub async fn orders_resolve_conflict(
 pub async fn order_approve(
     arg0: Option<String>,
     arg1: Option<i64>,
+    arg2: Option<i64>,
     db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
     app: tauri::AppHandle,
 ) -> Result<serde_json::Value, String> {
     let order_id_raw = arg0.ok_or("Missing orderId")?;
+    let actor = crate::auth::current_staff_id(&auth_state);
     let estimated_time = arg1;
-    let now = Utc::now().to_rfc3339();
+    let expected_version = arg2;
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let (order_id, remote_order_id) = resolve_order_id_with_remote(&conn, &order_id_raw)?;
-    ensure_order_status_transition_allowed(&conn, &order_id, "confirmed")?;
+    let suggested = match estimated_time {
+        Some(_) => None,
+        None => suggest_prep_time(&conn, &order_id),
+    };
+    let estimated_time = estimated_time.or(suggested.as_ref().map(|estimate| estimate.minutes));
+    let approved = match apply_order_approval(
+        &conn,
+        &order_id,
+        estimated_time,
+        expected_version,
+        actor.as_deref(),
+        order_events::APPROVED,
+        serde_json::json!({}),
+    )? {
+        OrderApproval::Approved(approved) => approved,
+        OrderApproval::Conflict { current_version } => {
+            drop(conn);
+            return Ok(version_conflict_response(
+                &db,
+                &order_id,
+                expected_version,
+                current_version,
+            ));
+        }
+    };
+    drop(conn);
+
+    announce_order_approval(&db, &app, &approved, remote_order_id.as_deref());
+    Ok(serde_json::json!({
+        "success": true,
+        "orderId": order_id_raw,
+        "estimatedTime": estimated_time,
+        "prepEstimate": suggested.map(|estimate| estimate.to_json()),
+        "version": approved.version
+    }))
+}
+
+/// Prep-time suggestion for an approval that came without an estimate.
+/// `None` when the order already carries one (e.g. from the platform) or
+/// the estimate cannot be computed.
+fn suggest_prep_time(
+    conn: &rusqlite::Connection,
+    order_id: &str,
+) -> Option<prep_time::PrepEstimate> {
+    let stored: Option<i64> = conn
+        .query_row(
+            "SELECT estimated_time FROM orders WHERE id = ?1",
+            rusqlite::params![order_id],
+            |row| row.get(0),
+        )
+        .ok()
+        .flatten();
+    if stored.is_some() {
+        return None;
+    }
+    prep_time::estimate_for_order(conn, order_id)
+        .map_err(|error| {
+            tracing::warn!(order_id = %order_id, error = %error, "Prep-time estimate failed");
+        })
+        .ok()
+}
+
+struct ApprovedOrder {
+    payload: serde_json::Value,
+    estimated_time: Option<i64>,
+    version: i64,
+    low_stock: Vec<serde_json::Value>,
+}
+
+enum OrderApproval {
+    Approved(ApprovedOrder),
+    Conflict { current_version: i64 },
+}
+
+/// The database half of `order_approve`: move the order to `confirmed`,
+/// queue it for sync, record `event_type` on the timeline (with `details`
+/// merged into the summary) and deduct stock. Call
+/// [`announce_order_approval`] once the lock is released.
+fn apply_order_approval(
+    conn: &rusqlite::Connection,
+    order_id: &str,
+    estimated_time: Option<i64>,
+    expected_version: Option<i64>,
+    actor: Option<&str>,
+    event_type: &str,
+    details: serde_json::Value,
+) -> Result<OrderApproval, String> {
+    let now = Utc::now().to_rfc3339();
+    let previous_status = ensure_order_status_transition_allowed(conn, order_id, "confirmed")?;
+    let new_version = match claim_order_version(conn, order_id, expected_version)? {
+        VersionClaim::Claimed(version) => version,
+        VersionClaim::Conflict { current_version } => {
+            return Ok(OrderApproval::Conflict { current_version })
+        }
+    };
     conn.execute(
         "UPDATE orders
          SET status = 'confirmed',
@@ -3892,41 +5953,96 @@ pub async fn order_approve(
         rusqlite::params![estimated_time, now, order_id],
     )
     .map_err(|e| format!("approve order: {e}"))?;
+    prep_time::mark_confirmed(conn, order_id, &now)?;
 
     let payload = serde_json::json!({
         "orderId": order_id,
         "status": "confirmed",
         "estimatedTime": estimated_time
     });
-    let _ = enqueue_order_sync_payload(&conn, &order_id, &payload);
-    drop(conn);
+    let _ = enqueue_order_sync_payload(conn, order_id, &payload);
+    let mut summary = serde_json::json!({
+        "from": previous_status,
+        "to": "confirmed",
+        "estimatedTime": estimated_time,
+        "version": new_version
+    });
+    if let (Some(summary), Some(details)) = (summary.as_object_mut(), details.as_object()) {
+        summary.extend(details.clone());
+    }
+    order_events::append(conn, order_id, event_type, actor, summary);
+    let low_stock =
+        inventory::deduct_for_order_logged(conn, order_id, inventory::TRIGGER_CONFIRMED, actor);
+    Ok(OrderApproval::Approved(ApprovedOrder {
+        payload,
+        estimated_time,
+        version: new_version,
+        low_stock,
+    }))
+}
 
-    let _ = app.emit("order_status_updated", payload.clone());
-    let _ = app.emit("order_realtime_update", payload.clone());
-    if let Some(remote_order_id) = remote_order_id.as_deref() {
+/// Tell the renderer and the admin about an approval written by
+/// [`apply_order_approval`].
+fn announce_order_approval(
+    db: &db::DbState,
+    app: &tauri::AppHandle,
+    approved: &ApprovedOrder,
+    remote_order_id: Option<&str>,
+) {
+    inventory::emit_low_stock(app, &approved.low_stock);
+    let _ = app.emit("order_status_updated", approved.payload.clone());
+    let _ = app.emit("order_realtime_update", approved.payload.clone());
+    if let Some(remote_order_id) = remote_order_id {
         spawn_immediate_order_status_patch(
-            &db,
-            build_order_status_patch_body(remote_order_id, "confirmed", estimated_time, None, None),
+            db,
+            build_order_status_patch_body(
+                remote_order_id,
+                "confirmed",
+                approved.estimated_time,
+                None,
+                None,
+            ),
         );
     }
-    Ok(
-        serde_json::json!({ "success": true, "orderId": order_id_raw, "estimatedTime": estimated_time }),
-    )
 }
 
 #[tauri::command]
 pub async fn order_decline(
     arg0: Option<String>,
     arg1: Option<String>,
+    arg2: Option<i64>,
+    arg3: Option<String>,
     db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
     app: tauri::AppHandle,
 ) -> Result<serde_json::Value, String> {
     let order_id_raw = arg0.ok_or("Missing orderId")?;
-    let reason = arg1.unwrap_or_else(|| "Declined".to_string());
+    let actor = crate::auth::current_staff_id(&auth_state);
+    let expected_version = arg2;
     let now = Utc::now().to_rfc3339();
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let resolved_reason = crate::reasons::require(
+        &conn,
+        crate::reasons::ReasonAction::OrderDecline,
+        arg3.as_deref(),
+        arg1.as_deref(),
+    )?;
+    let reason = resolved_reason.text;
+    let reason_code = resolved_reason.code;
     let (order_id, remote_order_id) = resolve_order_id_with_remote(&conn, &order_id_raw)?;
     let previous_status = ensure_order_status_transition_allowed(&conn, &order_id, "cancelled")?;
+    let new_version = match claim_order_version(&conn, &order_id, expected_version)? {
+        VersionClaim::Claimed(version) => version,
+        VersionClaim::Conflict { current_version } => {
+            drop(conn);
+            return Ok(version_conflict_response(
+                &db,
+                &order_id,
+                expected_version,
+                current_version,
+            ));
+        }
+    };
     if previous_status != "cancelled" {
         order_ownership::reverse_order_drawer_attribution(&conn, &order_id, &now)?;
     }
@@ -3934,10 +6050,12 @@ pub async fn order_decline(
         "UPDATE orders
          SET status = 'cancelled',
              cancellation_reason = ?1,
+             cancellation_reason_code = ?5,
+             cancelled_by = COALESCE(?4, cancelled_by),
              sync_status = 'pending',
              updated_at = ?2
          WHERE id = ?3",
-        rusqlite::params![reason, now, order_id],
+        rusqlite::params![reason, now, order_id, actor, reason_code],
     )
     .map_err(|e| format!("decline order: {e}"))?;
 
@@ -3945,11 +6063,26 @@ pub async fn order_decline(
         "orderId": order_id,
         "status": "cancelled",
         "reason": reason.clone(),
+        "reasonCode": reason_code.clone(),
         "cancellationReason": reason.clone(),
         "cancellation_reason": reason.clone(),
+        "cancellation_reason_code": reason_code.clone(),
         "cancelled_at": now
     });
     let _ = enqueue_order_sync_payload(&conn, &order_id, &payload);
+    order_events::append(
+        &conn,
+        &order_id,
+        order_events::DECLINED,
+        actor.as_deref(),
+        serde_json::json!({
+            "from": previous_status,
+            "to": "cancelled",
+            "reason": reason,
+            "reasonCode": reason_code,
+            "version": new_version
+        }),
+    );
     drop(conn);
 
     let _ = app.emit("order_status_updated", payload.clone());
@@ -3966,7 +6099,11 @@ pub async fn order_decline(
             ),
         );
     }
-    Ok(serde_json::json!({ "success": true, "orderId": order_id_raw }))
+    Ok(serde_json::json!({
+        "success": true,
+        "orderId": order_id_raw,
+        "version": new_version
+    }))
 }
 
 #[tauri::command]
@@ -3975,9 +6112,11 @@ pub async fn order_assign_driver(
     arg1: Option<String>,
     arg2: Option<String>,
     db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
     app: tauri::AppHandle,
 ) -> Result<serde_json::Value, String> {
     let order_id_raw = arg0.ok_or("Missing orderId")?;
+    let actor = crate::auth::current_staff_id(&auth_state);
     let driver_id = arg1.ok_or("Missing driverId")?;
     let notes = arg2;
     let now = Utc::now().to_rfc3339();
@@ -4009,6 +6148,13 @@ pub async fn order_assign_driver(
         return Err("Driver assignment is only supported for delivery orders".into());
     }
 
+    if let Some(run_id) = crate::dispatch::active_run_for_order(&conn, &order_id)? {
+        let run = crate::dispatch::load_run(&conn, &run_id)?;
+        if run.driver_id != driver_id.trim() {
+            return Err(format!("Order is already on active run {run_id}"));
+        }
+    }
+
     let driver_shift_id = if is_delivery {
         order_ownership::resolve_driver_shift_id(&conn, &driver_id, None)?
     } else {
@@ -4019,90 +6165,22 @@ pub async fn order_assign_driver(
         .as_deref()
         .ok_or_else(|| "Driver must have an active shift before assignment".to_string())?;
 
-    let assignment = order_ownership::assign_order_to_driver_shift(
+    let settled = settle_driver_delivery(
         &conn,
-        &order_id,
-        &driver_id,
-        driver_name.as_deref(),
-        shift_id,
+        &DriverDelivery {
+            order_id: &order_id,
+            driver_id: &driver_id,
+            driver_name: driver_name.as_deref(),
+            shift_id,
+            notes: notes.as_deref(),
+            cash_collected: None,
+            actor: actor.as_deref(),
+        },
         &now,
     )?;
-
-    let earning_id =
-        order_ownership::upsert_driver_earning(&conn, &order_id, &driver_id, &assignment, &now)?;
+    let assigned_status = settled.status.unwrap_or_else(|| current_status.clone());
     let earning_created = true;
 
-    // A delivery tip can be collected before dispatch. Resolve every pending
-    // driver allocation to the actual driver/shift at the same point that the
-    // canonical driver earning is created, then rebuild its payment sync row
-    // so an already-offline payment cannot retain a stale pending recipient.
-    resolve_delivery_tip_recipients_for_assignment(
-        &conn,
-        &order_id,
-        &driver_id,
-        &assignment.driver_shift_id,
-        &now,
-    )?;
-
-    let assigned_status: String = conn
-        .query_row(
-            "SELECT COALESCE(status, 'pending') FROM orders WHERE id = ?1",
-            rusqlite::params![order_id],
-            |row| row.get(0),
-        )
-        .unwrap_or_else(|_| current_status.clone());
-
-    let _ = conn.execute(
-        "UPDATE orders
-         SET delivery_notes = COALESCE(?1, delivery_notes),
-             sync_status = 'pending',
-             updated_at = ?2
-         WHERE id = ?3",
-        rusqlite::params![notes, now, order_id],
-    );
-
-    // W4d-iv additive emission: driver-earning sync payload now ships
-    // every monetary float key alongside its `_cents` integer sibling.
-    let total_earning = assignment.delivery_fee + assignment.tip_amount;
-    let driver_earning_sync_payload = serde_json::json!({
-        "id": earning_id,
-        "driver_id": driver_id,
-        "staff_shift_id": shift_id,
-        "order_id": order_id,
-        "branch_id": assignment.branch_id,
-        "delivery_fee": assignment.delivery_fee,
-        "delivery_fee_cents": Cents::round_half_even(assignment.delivery_fee).as_i64(),
-        "tip_amount": assignment.tip_amount,
-        "tip_amount_cents": Cents::round_half_even(assignment.tip_amount).as_i64(),
-        "total_earning": total_earning,
-        "total_earning_cents": Cents::round_half_even(total_earning).as_i64(),
-        "payment_method": assignment.payment_method,
-        "cash_collected": assignment.cash_collected,
-        "cash_collected_cents": Cents::round_half_even(assignment.cash_collected).as_i64(),
-        "card_amount": assignment.card_amount,
-        "card_amount_cents": Cents::round_half_even(assignment.card_amount).as_i64(),
-        "cash_to_return": assignment.cash_collected,
-        "cash_to_return_cents": Cents::round_half_even(assignment.cash_collected).as_i64(),
-        "createdAt": now,
-        "updatedAt": now,
-    });
-    enqueue_or_refresh_driver_earning_sync_row(
-        &conn,
-        &earning_id,
-        &driver_earning_sync_payload,
-        &now,
-    )?;
-
-    let order_sync_payload = serde_json::json!({
-        "orderId": order_id,
-        "orderType": "delivery",
-        "status": assigned_status,
-        "driverId": driver_id,
-        "driverName": driver_name,
-        "deliveryNotes": notes,
-    });
-    let _ = enqueue_order_sync_payload(&conn, &order_id, &order_sync_payload);
-
     drop(conn);
 
     // Use is_print_action_enabled (not setting_bool) so the default-true behaviour
@@ -4234,14 +6312,28 @@ pub async fn order_update_preparation(
 pub async fn order_update_type(
     arg0: Option<String>,
     arg1: Option<String>,
+    arg2: Option<i64>,
     db: tauri::State<'_, db::DbState>,
     app: tauri::AppHandle,
 ) -> Result<serde_json::Value, String> {
     let order_id_raw = arg0.ok_or("Missing orderId")?;
     let order_type = arg1.ok_or("Missing orderType")?.trim().to_ascii_lowercase();
+    let expected_version = arg2;
     let now = Utc::now().to_rfc3339();
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let order_id = resolve_order_id(&conn, &order_id_raw).ok_or("Order not found")?;
+    let new_version = match claim_order_version(&conn, &order_id, expected_version)? {
+        VersionClaim::Claimed(version) => version,
+        VersionClaim::Conflict { current_version } => {
+            drop(conn);
+            return Ok(version_conflict_response(
+                &db,
+                &order_id,
+                expected_version,
+                current_version,
+            ));
+        }
+    };
     let mut emitted_status: Option<String> = None;
     if order_type == "pickup" {
         // Keyring-first; plaintext `local_settings` is backward-compat fallback.
@@ -4335,6 +6427,7 @@ pub async fn order_update_type(
     Ok(serde_json::json!({
         "success": true,
         "orderId": order_id_raw,
+        "version": new_version,
         "data": {
             "orderId": order_id_raw,
             "orderType": order_type,
@@ -4345,6 +6438,145 @@ pub async fn order_update_type(
     }))
 }
 
+/// One delivery handed to a driver shift.
+pub(crate) struct DriverDelivery<'a> {
+    pub order_id: &'a str,
+    pub driver_id: &'a str,
+    pub driver_name: Option<&'a str>,
+    pub shift_id: &'a str,
+    pub notes: Option<&'a str>,
+    /// Cash the driver declared for this order; when absent the earning
+    /// takes the cash side of the order's payments.
+    pub cash_collected: Option<f64>,
+    pub actor: Option<&'a str>,
+}
+
+pub(crate) struct SettledDriverDelivery {
+    /// Order status after the hand-over, if it could be read back.
+    pub status: Option<String>,
+    pub earning_id: String,
+}
+
+/// Attribute a delivery order to the driver's shift (which marks it
+/// delivered), write its driver earning and queue both for sync. Shared by
+/// `order_assign_driver` and dispatch runs.
+pub(crate) fn settle_driver_delivery(
+    conn: &rusqlite::Connection,
+    delivery: &DriverDelivery<'_>,
+    now: &str,
+) -> Result<SettledDriverDelivery, String> {
+    let DriverDelivery {
+        order_id,
+        driver_id,
+        driver_name,
+        shift_id,
+        notes,
+        cash_collected,
+        actor,
+    } = *delivery;
+    let mut assignment = order_ownership::assign_order_to_driver_shift(
+        conn,
+        order_id,
+        driver_id,
+        driver_name,
+        shift_id,
+        now,
+    )?;
+    if let Some(cash) = cash_collected {
+        assignment.cash_collected = cash;
+    }
+
+    let earning_id =
+        order_ownership::upsert_driver_earning(conn, order_id, driver_id, &assignment, now)?;
+
+    // A delivery tip can be collected before dispatch. Resolve every pending
+    // driver allocation to the actual driver/shift at the same point that the
+    // canonical driver earning is created, then rebuild its payment sync row
+    // so an already-offline payment cannot retain a stale pending recipient.
+    resolve_delivery_tip_recipients_for_assignment(
+        conn,
+        order_id,
+        driver_id,
+        &assignment.driver_shift_id,
+        now,
+    )?;
+
+    let assigned_status: Option<String> = conn
+        .query_row(
+            "SELECT COALESCE(status, 'pending') FROM orders WHERE id = ?1",
+            rusqlite::params![order_id],
+            |row| row.get(0),
+        )
+        .ok();
+
+    let _ = conn.execute(
+        "UPDATE orders
+         SET delivery_notes = COALESCE(?1, delivery_notes),
+             sync_status = 'pending',
+             updated_at = ?2
+         WHERE id = ?3",
+        rusqlite::params![notes, now, order_id],
+    );
+
+    // W4d-iv additive emission: driver-earning sync payload now ships
+    // every monetary float key alongside its `_cents` integer sibling.
+    let total_earning = assignment.delivery_fee + assignment.tip_amount;
+    let driver_earning_sync_payload = serde_json::json!({
+        "id": earning_id,
+        "driver_id": driver_id,
+        "staff_shift_id": shift_id,
+        "order_id": order_id,
+        "branch_id": assignment.branch_id,
+        "delivery_fee": assignment.delivery_fee,
+        "delivery_fee_cents": Cents::round_half_even(assignment.delivery_fee).as_i64(),
+        "tip_amount": assignment.tip_amount,
+        "tip_amount_cents": Cents::round_half_even(assignment.tip_amount).as_i64(),
+        "total_earning": total_earning,
+        "total_earning_cents": Cents::round_half_even(total_earning).as_i64(),
+        "payment_method": assignment.payment_method,
+        "cash_collected": assignment.cash_collected,
+        "cash_collected_cents": Cents::round_half_even(assignment.cash_collected).as_i64(),
+        "card_amount": assignment.card_amount,
+        "card_amount_cents": Cents::round_half_even(assignment.card_amount).as_i64(),
+        "cash_to_return": assignment.cash_collected,
+        "cash_to_return_cents": Cents::round_half_even(assignment.cash_collected).as_i64(),
+        "createdAt": now,
+        "updatedAt": now,
+    });
+    enqueue_or_refresh_driver_earning_sync_row(
+        conn,
+        &earning_id,
+        &driver_earning_sync_payload,
+        now,
+    )?;
+
+    let order_sync_payload = serde_json::json!({
+        "orderId": order_id,
+        "orderType": "delivery",
+        "status": assigned_status,
+        "driverId": driver_id,
+        "driverName": driver_name,
+        "deliveryNotes": notes,
+    });
+    let _ = enqueue_order_sync_payload(conn, order_id, &order_sync_payload);
+    order_events::append(
+        conn,
+        order_id,
+        order_events::DRIVER_ASSIGNED,
+        actor,
+        serde_json::json!({
+            "driverId": driver_id,
+            "driverName": driver_name,
+            "driverShiftId": shift_id,
+        }),
+    );
+
+    Ok(SettledDriverDelivery {
+        status: assigned_status,
+        earning_id,
+    })
+}
+
 fn resolve_delivery_tip_recipients_for_assignment(
     conn: &rusqlite::Connection,
     order_id: &str,
@@ -4565,9 +6797,142 @@ pub async fn orders_get_retry_info(
     let entries: Vec<serde_json::Value> = rows.filter_map(|r| r.ok()).collect();
     Ok(serde_json::json!({
         "success": true,
-        "orderId": order_id_raw,
-        "entries": entries,
-        "hasRetries": !entries.is_empty()
+        "orderId": order_id_raw,
+        "entries": entries,
+        "hasRetries": !entries.is_empty()
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OrderLockPayload {
+    #[serde(alias = "order_id", alias = "id")]
+    order_id: String,
+    #[serde(default, alias = "staff_id")]
+    staff_id: Option<String>,
+    #[serde(default, alias = "terminal_id")]
+    terminal_id: Option<String>,
+}
+
+fn parse_order_lock_payload(arg0: Option<serde_json::Value>) -> Result<OrderLockPayload, String> {
+    let payload = match arg0 {
+        Some(serde_json::Value::String(order_id)) => serde_json::json!({ "orderId": order_id }),
+        Some(value) => value,
+        None => return Err("Missing orderId".into()),
+    };
+    let mut parsed: OrderLockPayload =
+        serde_json::from_value(payload).map_err(|e| format!("Invalid order lock payload: {e}"))?;
+    parsed.order_id = parsed.order_id.trim().to_string();
+    if parsed.order_id.is_empty() {
+        return Err("Missing orderId".into());
+    }
+    let non_empty = |value: Option<String>| {
+        value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    parsed.staff_id = non_empty(parsed.staff_id);
+    parsed.terminal_id = non_empty(parsed.terminal_id);
+    Ok(parsed)
+}
+
+fn emit_order_lock_changed(
+    app: &tauri::AppHandle,
+    order_id: &str,
+    lock: Option<&order_locks::OrderLock>,
+) {
+    let _ = app.emit(
+        "order_lock_changed",
+        serde_json::json!({
+            "orderId": order_id,
+            "locked": lock.is_some(),
+            "lock": lock,
+        }),
+    );
+}
+
+#[tauri::command]
+pub async fn order_acquire_lock(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let payload = parse_order_lock_payload(arg0)?;
+    let terminal_id = payload
+        .terminal_id
+        .unwrap_or_else(order_locks::local_terminal_id);
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let order_id = resolve_order_id(&conn, &payload.order_id).ok_or("Order not found")?;
+    let ttl = order_locks::lock_ttl(&conn);
+
+    match order_locks::acquire_lock(
+        &conn,
+        &order_id,
+        payload.staff_id.as_deref(),
+        &terminal_id,
+        ttl,
+        Utc::now(),
+    )? {
+        order_locks::AcquireOutcome::Held(lock) => Ok(order_locks::build_locked_response(&lock)),
+        order_locks::AcquireOutcome::Acquired(lock) => {
+            if let Err(error) =
+                order_locks::enqueue_lock_sync(&conn, &order_id, Some(&lock), &terminal_id)
+            {
+                tracing::warn!(order_id = %order_id, error = %error, "Failed to queue order lock sync");
+            }
+            drop(conn);
+            emit_order_lock_changed(&app, &order_id, Some(&lock));
+            Ok(serde_json::json!({ "success": true, "lock": lock }))
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn order_release_lock(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let payload = parse_order_lock_payload(arg0)?;
+    let terminal_id = payload
+        .terminal_id
+        .unwrap_or_else(order_locks::local_terminal_id);
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let order_id = resolve_order_id(&conn, &payload.order_id).ok_or("Order not found")?;
+
+    let released = order_locks::release_lock(&conn, &order_id, &terminal_id)?;
+    if released {
+        if let Err(error) = order_locks::enqueue_lock_sync(&conn, &order_id, None, &terminal_id) {
+            tracing::warn!(order_id = %order_id, error = %error, "Failed to queue order unlock sync");
+        }
+        drop(conn);
+        emit_order_lock_changed(&app, &order_id, None);
+    }
+    Ok(serde_json::json!({ "success": true, "released": released }))
+}
+
+#[tauri::command]
+pub async fn order_get_lock_status(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let payload = parse_order_lock_payload(arg0)?;
+    let terminal_id = payload
+        .terminal_id
+        .unwrap_or_else(order_locks::local_terminal_id);
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let order_id = resolve_order_id(&conn, &payload.order_id).ok_or("Order not found")?;
+    let lock = order_locks::current_lock(&conn, &order_id, Utc::now())?;
+    let held_by_other = lock
+        .as_ref()
+        .is_some_and(|lock| lock.terminal_id != terminal_id);
+    Ok(serde_json::json!({
+        "success": true,
+        "orderId": order_id,
+        "locked": lock.is_some(),
+        "heldByThisTerminal": lock.is_some() && !held_by_other,
+        "lockedBy": if held_by_other { lock.clone() } else { None },
+        "lock": lock,
     }))
 }
 
@@ -4575,6 +6940,171 @@ pub async fn orders_get_retry_info(
 mod dto_tests {
     use super::*;
 
+    #[test]
+    fn parse_order_lock_payload_supports_string_and_object() {
+        let from_string = parse_order_lock_payload(Some(serde_json::json!("order-1")))
+            .expect("string payload should parse");
+        assert_eq!(from_string.order_id, "order-1");
+        assert!(from_string.staff_id.is_none());
+
+        let from_object = parse_order_lock_payload(Some(serde_json::json!({
+            "order_id": " order-2 ",
+            "staffId": "staff-1",
+            "terminalId": "  ",
+        })))
+        .expect("object payload should parse");
+        assert_eq!(from_object.order_id, "order-2");
+        assert_eq!(from_object.staff_id.as_deref(), Some("staff-1"));
+        assert!(from_object.terminal_id.is_none());
+
+        assert!(parse_order_lock_payload(None).is_err());
+        assert!(parse_order_lock_payload(Some(serde_json::json!({ "orderId": "" }))).is_err());
+    }
+
+    #[test]
+    fn parse_order_duplicate_payload_prefers_source_order_id() {
+        assert_eq!(
+            parse_order_duplicate_payload(Some(serde_json::json!(" order-1 "))).unwrap(),
+            "order-1"
+        );
+        assert_eq!(
+            parse_order_duplicate_payload(Some(serde_json::json!({
+                "sourceOrderId": "order-2",
+                "orderId": "order-3",
+            })))
+            .unwrap(),
+            "order-2"
+        );
+        assert!(parse_order_duplicate_payload(Some(serde_json::json!({}))).is_err());
+    }
+
+    #[test]
+    fn parse_order_eta_ids_reads_bulk_ids_or_a_single_order() {
+        assert_eq!(
+            parse_order_eta_ids(Some(serde_json::json!({
+                "orderIds": ["order-1", " order-2 ", "order-1", ""],
+                "orderId": "order-3",
+            })))
+            .unwrap(),
+            vec!["order-1".to_string(), "order-2".to_string()]
+        );
+        assert_eq!(
+            parse_order_eta_ids(Some(serde_json::json!({ "orderId": "order-3" }))).unwrap(),
+            vec!["order-3".to_string()]
+        );
+        assert!(parse_order_eta_ids(Some(serde_json::json!({ "orderIds": [] }))).is_err());
+    }
+
+    #[test]
+    fn stamp_session_staff_targets_order_data_when_wrapped() {
+        let mut wrapped = serde_json::json!({ "orderData": { "items": [] } });
+        stamp_session_staff(&mut wrapped, Some("staff-2".into()));
+        assert_eq!(wrapped["orderData"]["sessionStaffId"], "staff-2");
+        assert!(wrapped.get("sessionStaffId").is_none());
+
+        let mut flat = serde_json::json!({ "items": [] });
+        stamp_session_staff(&mut flat, None);
+        assert!(flat.get("sessionStaffId").is_none());
+        stamp_session_staff(&mut flat, Some("staff-3".into()));
+        assert_eq!(flat["sessionStaffId"], "staff-3");
+    }
+
+    #[test]
+    fn parse_refresh_prices_payload_defaults_to_preview() {
+        let preview = parse_refresh_prices_payload(Some(serde_json::json!("order-1"))).unwrap();
+        assert_eq!(preview.order_id, "order-1");
+        assert!(!preview.confirm);
+        let confirmed = parse_refresh_prices_payload(Some(serde_json::json!({
+            "orderId": "order-2",
+            "confirm": true,
+            "expectedVersion": 3,
+        })))
+        .unwrap();
+        assert!(confirmed.confirm);
+        assert_eq!(confirmed.expected_version, Some(3));
+        assert!(parse_refresh_prices_payload(None).is_err());
+    }
+
+    #[test]
+    fn parse_allergy_flags_payload_reads_order_and_item_flags() {
+        let parsed = parse_allergy_flags_payload(Some(serde_json::json!({
+            "orderId": "order-1",
+            "allergyFlags": ["Peanuts"],
+            "items": [{ "index": 1, "flags": ["milk", 3] }],
+            "expectedVersion": 4,
+            "printerProfileId": "kitchen",
+        })))
+        .unwrap();
+        assert_eq!(
+            parsed,
+            AllergyFlagsPayload {
+                order_id: "order-1".to_string(),
+                flags: vec!["Peanuts".to_string()],
+                items: vec![(1, vec!["milk".to_string()])],
+                expected_version: Some(4),
+                printer_profile_id: Some("kitchen".to_string()),
+            }
+        );
+        assert!(parse_allergy_flags_payload(Some(serde_json::json!({
+            "orderId": "order-1",
+            "items": [{ "index": -1, "flags": ["milk"] }],
+        })))
+        .is_err());
+        assert!(
+            parse_allergy_flags_payload(Some(serde_json::json!({ "orderId": "order-1" }))).is_err()
+        );
+    }
+
+    #[test]
+    fn parse_prep_estimate_payload_reads_items_and_load() {
+        let parsed = parse_prep_estimate_payload(Some(serde_json::json!({
+            "orderType": "delivery",
+            "items": [{ "menu_item_id": "pizza" }],
+            "openOrders": 4,
+        })))
+        .unwrap();
+        assert_eq!(parsed.order_type, "delivery");
+        assert_eq!(parsed.items.len(), 1);
+        assert_eq!(parsed.load, Some(4));
+        assert_eq!(
+            parse_prep_estimate_payload(Some(serde_json::json!({ "items": [] })))
+                .unwrap()
+                .load,
+            None
+        );
+        assert!(parse_prep_estimate_payload(Some(serde_json::json!({}))).is_err());
+    }
+
+    #[test]
+    fn parse_order_return_payload_reads_lines_and_exchange_items() {
+        let parsed = parse_order_return_payload(Some(serde_json::json!({
+            "originalOrderId": "order-1",
+            "items": [{ "lineIndex": 1, "quantity": 2 }],
+            "reasonCode": "damaged",
+            "restock": true,
+            "exchangeItems": [{ "menuItemId": "shirt-l", "quantity": 1 }],
+        })))
+        .unwrap();
+        assert_eq!(parsed.original_order_id, "order-1");
+        assert_eq!(parsed.lines.len(), 1);
+        assert_eq!(parsed.reason_code.as_deref(), Some("damaged"));
+        assert!(parsed.restock);
+        assert_eq!(
+            parsed.exchange.unwrap()["items"][0]["menuItemId"],
+            "shirt-l"
+        );
+        assert!(parse_order_return_payload(Some(serde_json::json!({
+            "items": [{ "lineIndex": 0 }],
+        })))
+        .is_err());
+        assert!(parse_order_return_payload(Some(serde_json::json!({
+            "originalOrderId": "order-1",
+            "items": [{ "lineIndex": 0 }],
+            "exchange": { "items": [] },
+        })))
+        .is_err());
+    }
+
     #[test]
     fn parse_status_payload_supports_legacy_shape() {
         let parsed = parse_order_update_status_payload(
@@ -4602,6 +7132,30 @@ mod dto_tests {
         assert_eq!(parsed.estimated_time, Some(18));
     }
 
+    #[test]
+    fn parse_payloads_accept_expected_version() {
+        let status = parse_order_update_status_payload(
+            Some(serde_json::json!({ "orderId": "order-1", "expectedVersion": 3 })),
+            Some("ready".to_string()),
+        )
+        .expect("status payload should parse");
+        assert_eq!(status.expected_version, Some(3));
+
+        let items = parse_order_update_items_payload(
+            Some(serde_json::json!({ "orderId": "order-1", "items": [], "expected_version": 5 })),
+            None,
+        )
+        .expect("items payload should parse");
+        assert_eq!(items.expected_version, Some(5));
+
+        let financials = parse_order_update_financials_payload(Some(serde_json::json!({
+            "orderId": "order-1",
+            "totalAmount": 10.0,
+        })))
+        .expect("financials payload should parse");
+        assert_eq!(financials.expected_version, None);
+    }
+
     #[test]
     fn parse_status_payload_supports_cancellation_reason_aliases() {
         let parsed = parse_order_update_status_payload(
@@ -5103,10 +7657,7 @@ mod transition_tests {
         )
         .expect("pragma setup");
         db::run_migrations_for_test(&conn);
-        db::DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        }
+        db::DbState::new(conn, std::path::PathBuf::from(":memory:"))
     }
 
     fn insert_order(db: &db::DbState, order_id: &str, status: &str) {
@@ -5120,6 +7671,195 @@ mod transition_tests {
         .unwrap();
     }
 
+    /// Same path as `order_create`: claim the key, then create on the
+    /// blocking pool.
+    async fn submit_order(
+        db: &db::DbState,
+        payload: serde_json::Value,
+    ) -> Result<serde_json::Value, String> {
+        let key = idempotency::request_key(&payload);
+        idempotency::run_once(db, "order_create", key, || {
+            db.run_blocking(move |db| create_order_from_payload(db, payload, true))
+        })
+        .await
+    }
+
+    #[test]
+    fn growing_discount_requires_comp_reason_code() {
+        let db = test_db();
+        insert_order(&db, "ord-comp", "pending");
+        let conn = db.conn.lock().unwrap();
+        let financials = |extra: serde_json::Value| {
+            let mut payload = serde_json::json!({ "orderId": "ord-comp", "totalAmount": 8.0 });
+            payload
+                .as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            parse_order_update_financials_payload(Some(payload)).unwrap()
+        };
+
+        let err = resolve_discount_reason(
+            &conn,
+            "ord-comp",
+            &financials(serde_json::json!({})),
+            200,
+            0.0,
+        )
+        .unwrap_err();
+        assert!(err.contains("comp reason code is required"), "{err}");
+        let err = resolve_discount_reason(
+            &conn,
+            "ord-comp",
+            &financials(serde_json::json!({ "discountReasonCode": "asdf" })),
+            200,
+            0.0,
+        )
+        .unwrap_err();
+        assert!(err.contains("Unknown comp reason code"), "{err}");
+
+        let resolved = resolve_discount_reason(
+            &conn,
+            "ord-comp",
+            &financials(serde_json::json!({ "discountReasonCode": "staff_meal" })),
+            200,
+            0.0,
+        )
+        .unwrap()
+        .expect("comp reason");
+        assert_eq!(resolved.code, "staff_meal");
+
+        // Edits that keep or shrink the discount need no code.
+        conn.execute(
+            "UPDATE orders SET discount_amount = 2.0, discount_amount_cents = 200 WHERE id = 'ord-comp'",
+            [],
+        )
+        .unwrap();
+        assert!(resolve_discount_reason(
+            &conn,
+            "ord-comp",
+            &financials(serde_json::json!({})),
+            100,
+            0.0
+        )
+        .unwrap()
+        .is_none());
+    }
+
+    #[tokio::test]
+    async fn order_create_double_submit_replays_first_response() {
+        let db = test_db();
+        {
+            let conn = db.conn.lock().unwrap();
+            db::set_setting(&conn, "terminal", "__ignore_keyring", "1").unwrap();
+            conn.execute(
+                "INSERT INTO staff_shifts (
+                    id, staff_id, staff_name, branch_id, terminal_id, role_type,
+                    check_in_time, opening_cash_amount, opening_cash_amount_cents,
+                    status, sync_status, created_at, updated_at
+                ) VALUES (
+                    'shift-dup', 'staff-dup', 'Cashier', 'branch-dup', 'terminal-dup', 'cashier',
+                    datetime('now'), 100.0, 10000,
+                    'active', 'pending', datetime('now'), datetime('now')
+                )",
+                [],
+            )
+            .unwrap();
+        }
+        let payload = serde_json::json!({
+            "idempotencyKey": "webview-retry-1",
+            "branchId": "branch-dup",
+            "terminalId": "terminal-dup",
+            "items": [{ "name": "Coffee", "quantity": 1, "price": 2.5 }],
+            "totalAmount": 2.5,
+            "subtotal": 2.5,
+            "status": "pending",
+            "orderType": "pickup"
+        });
+
+        let first = submit_order(&db, payload.clone())
+            .await
+            .expect("first submit");
+        let retry = submit_order(&db, payload.clone()).await.expect("retry");
+
+        assert_eq!(retry, first, "retry must replay the first response");
+        let conn = db.conn.lock().unwrap();
+        let (orders, queued): (i64, i64) = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM orders),
+                        (SELECT COUNT(*) FROM parity_sync_queue WHERE table_name = 'orders')",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(orders, 1, "double submit must create a single order");
+        assert_eq!(queued, 1, "double submit must enqueue a single sync");
+        drop(conn);
+
+        let mut other = payload;
+        other["idempotencyKey"] = serde_json::json!("webview-retry-2");
+        submit_order(&db, other).await.expect("new key submit");
+        let orders: i64 = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM orders", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(orders, 2, "a different key executes again");
+    }
+
+    #[test]
+    fn claim_order_version_bumps_and_detects_stale_expectation() {
+        let db = test_db();
+        insert_order(&db, "order-v", "pending");
+        let conn = db.conn.lock().unwrap();
+        let start = current_order_version(&conn, "order-v").unwrap();
+
+        assert_eq!(
+            claim_order_version(&conn, "order-v", None).unwrap(),
+            VersionClaim::Claimed(start + 1)
+        );
+        assert_eq!(
+            claim_order_version(&conn, "order-v", Some(start + 1)).unwrap(),
+            VersionClaim::Claimed(start + 2)
+        );
+        assert_eq!(
+            claim_order_version(&conn, "order-v", Some(start)).unwrap(),
+            VersionClaim::Conflict {
+                current_version: start + 2
+            }
+        );
+        assert_eq!(current_order_version(&conn, "order-v").unwrap(), start + 2);
+    }
+
+    #[test]
+    fn enqueued_order_update_carries_row_version() {
+        let db = test_db();
+        insert_order(&db, "order-sync-v", "pending");
+        let conn = db.conn.lock().unwrap();
+        let VersionClaim::Claimed(version) =
+            claim_order_version(&conn, "order-sync-v", None).unwrap()
+        else {
+            panic!("claim should succeed without an expected version");
+        };
+        enqueue_order_sync_payload(
+            &conn,
+            "order-sync-v",
+            &serde_json::json!({ "orderId": "order-sync-v", "status": "ready" }),
+        )
+        .unwrap();
+        let (data, queued_version): (String, i64) = conn
+            .query_row(
+                "SELECT data, version FROM parity_sync_queue
+                 WHERE table_name = 'orders' AND record_id = 'order-sync-v'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        let data: serde_json::Value = serde_json::from_str(&data).unwrap();
+        assert_eq!(data["version"], serde_json::json!(version));
+        assert_eq!(queued_version, version);
+    }
+
     #[test]
     fn delivery_driver_assignment_resolves_and_requeues_pending_tip_recipient() {
         let db = test_db();
@@ -5689,6 +8429,73 @@ mod transition_tests {
         assert!((total_paid - 10.0).abs() < 0.001);
     }
 
+    #[test]
+    fn item_totals_agree_to_the_cent_across_paths() {
+        // Prices like 0.10 × 3 drift when summed as f64, and half-cent lines
+        // only agree when every path uses the configured rule; every path
+        // must round per line with it and add cents.
+        let db = test_db();
+        let rule = {
+            let conn = db.conn.lock().unwrap();
+            db::set_setting(&conn, "general", "money_rounding", "half_even").unwrap();
+            RoundingRule::from_settings(&conn)
+        };
+        assert_eq!(rule, RoundingRule::HalfEven);
+
+        let mut state = 0x2545_F491_4F6C_DD1D_u64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let mut expected = 0_i64;
+        let items: Vec<serde_json::Value> = (0..1000)
+            .map(|index| {
+                let unit_cents = (next() % 2500) as i64;
+                let quantity = (next() % 4 + 1) as i64;
+                if index % 5 == 0 {
+                    // An odd number of eighths lands exactly on a half cent.
+                    let eighths = 2 * (next() % 1000) as i64 + 1;
+                    let half_cents = eighths * 25;
+                    let floor = half_cents / 2;
+                    expected += if floor % 2 == 0 { floor } else { floor + 1 };
+                    return serde_json::json!({
+                        "name": "Line",
+                        "totalPrice": eighths as f64 / 8.0,
+                        "quantity": 1,
+                    });
+                }
+                expected += unit_cents * quantity;
+                let unit_price = unit_cents as f64 / 100.0;
+                if index % 3 == 0 {
+                    serde_json::json!({
+                        "name": "Line",
+                        "totalPrice": (unit_cents * quantity) as f64 / 100.0,
+                        "quantity": quantity,
+                    })
+                } else {
+                    serde_json::json!({
+                        "name": "Line",
+                        "unit_price": unit_price,
+                        "quantity": quantity,
+                    })
+                }
+            })
+            .collect();
+        let items_json = serde_json::to_string(&items).unwrap();
+
+        let ipc_total = crate::parse_item_totals(&items_json, rule).0;
+        let edit_total = compute_order_items_total(&items, rule);
+        assert_eq!(edit_total, Cents::new(expected));
+        assert_eq!(Cents::round_half_even(ipc_total), Cents::new(expected));
+        assert_eq!(ipc_total, Cents::new(expected).to_f64_dp2());
+        assert_ne!(
+            compute_order_items_total(&items, RoundingRule::default()),
+            Cents::new(expected)
+        );
+    }
+
     #[test]
     fn determine_edit_settlement_required_action_selects_collect_refund_and_none() {
         assert_eq!(
@@ -6379,4 +9186,204 @@ mod transition_tests {
         assert!((total_amount - 15.0).abs() < 0.001);
         assert_eq!(queue_count, 0);
     }
+
+    #[test]
+    fn racing_remote_saves_keep_one_row_with_the_newer_snapshot() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        let older = serde_json::json!({
+            "id": "remote-race",
+            "order_number": "ORD-9",
+            "total_amount": 10.0,
+            "status": "pending",
+            "customer_name": "Old",
+            "updated_at": "2026-05-04T10:00:00Z"
+        });
+        let newer = serde_json::json!({
+            "id": "remote-race",
+            "order_number": "ORD-9",
+            "total_amount": 14.5,
+            "status": "confirmed",
+            "customer_name": "New",
+            "updated_at": "2026-05-04T10:00:30+00:00"
+        });
+
+        // Both saves passed the existence check before either inserted.
+        let first = upsert_remote_order_row(
+            &conn,
+            &newer,
+            "remote-race",
+            "local-a",
+            "2026-05-04T10:01:00Z",
+        )
+        .unwrap();
+        let second = upsert_remote_order_row(
+            &conn,
+            &older,
+            "remote-race",
+            "local-b",
+            "2026-05-04T10:01:00Z",
+        )
+        .unwrap();
+        assert_eq!(first.outcome, RemoteOrderUpsert::Inserted);
+        assert_eq!(second.outcome, RemoteOrderUpsert::Skipped);
+        assert_eq!(second.local_id, "local-a");
+
+        let replay =
+            save_remote_order(&conn, &newer, "remote-race", "2026-05-04T10:02:00Z").unwrap();
+        assert_eq!(replay.outcome, RemoteOrderUpsert::Skipped);
+        let mut newest = newer.clone();
+        newest["customer_name"] = serde_json::json!("Newest");
+        newest["updated_at"] = serde_json::json!("2026-05-04T10:05:00Z");
+        let updated =
+            save_remote_order(&conn, &newest, "remote-race", "2026-05-04T10:06:00Z").unwrap();
+        assert_eq!(updated.outcome, RemoteOrderUpsert::Updated);
+
+        let rows: Vec<(String, String, i64, String)> = conn
+            .prepare(
+                "SELECT id, customer_name, total_amount_cents, status
+                 FROM orders WHERE supabase_id = 'remote-race'",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![(
+                "local-a".to_string(),
+                "Newest".to_string(),
+                1450,
+                "confirmed".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn newer_remote_snapshot_never_overwrites_pending_local_edits() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO orders (id, supabase_id, items, total_amount, total_amount_cents,
+                                 status, payment_status, sync_status, created_at, updated_at)
+             VALUES ('local-edit', 'remote-edit', '[]', 18.0, 1800, 'ready', 'paid',
+                     'pending', '2026-05-04T10:00:00Z', '2026-05-04T10:00:00Z')",
+            [],
+        )
+        .unwrap();
+        let remote = serde_json::json!({
+            "id": "remote-edit",
+            "total_amount": 12.0,
+            "status": "preparing",
+            "payment_status": "pending",
+            "updated_at": "2026-05-04T10:30:00Z"
+        });
+
+        let realtime =
+            save_remote_order(&conn, &remote, "remote-edit", "2026-05-04T10:31:00Z").unwrap();
+        assert_eq!(realtime.outcome, RemoteOrderUpsert::Skipped);
+        assert_eq!(realtime.local_id, "local-edit");
+
+        // Pushed but not yet acknowledged: the queue row still guards it.
+        conn.execute(
+            "UPDATE orders SET sync_status = 'synced' WHERE id = 'local-edit'",
+            [],
+        )
+        .unwrap();
+        crate::sync_queue::enqueue(
+            &conn,
+            &crate::sync_queue::EnqueueInput {
+                table_name: "orders".to_string(),
+                record_id: "local-edit".to_string(),
+                operation: "UPDATE".to_string(),
+                data: "{}".to_string(),
+                organization_id: "org-test".to_string(),
+                priority: None,
+                module_type: None,
+                conflict_strategy: None,
+                version: None,
+            },
+        )
+        .unwrap();
+        let (bulk, _) =
+            import_remote_orders_batch(&conn, &[remote], "2026-05-04T10:32:00Z").unwrap();
+        assert_eq!(bulk.updated, 0);
+        assert_eq!(bulk.skipped, 1);
+
+        let (status, payment_status, total_cents): (String, String, i64) = conn
+            .query_row(
+                "SELECT status, payment_status, total_amount_cents FROM orders
+                 WHERE id = 'local-edit'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            (status.as_str(), payment_status.as_str(), total_cents),
+            ("ready", "paid", 1800)
+        );
+    }
+
+    #[test]
+    fn import_remote_orders_batch_is_idempotent_and_never_queues_sync() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO orders (id, supabase_id, items, total_amount, status, sync_status, created_at, updated_at)
+             VALUES ('local-1', 'remote-existing', '[]', 5.0, 'completed', 'synced', datetime('now'), datetime('now'))",
+            [],
+        )
+        .unwrap();
+        let orders = vec![
+            serde_json::json!({
+                "id": "remote-new",
+                "order_number": "ORD-7",
+                "total_amount": 12.5,
+                "status": "completed",
+                "order_type": "pickup",
+                "items": [{ "name": "Burger", "quantity": 1, "total_price": 12.5 }]
+            }),
+            serde_json::json!({ "id": "remote-existing", "total_amount": 5.0 }),
+            serde_json::json!({ "total_amount": 1.0 }),
+        ];
+
+        let (first, first_ids) =
+            import_remote_orders_batch(&conn, &orders, "2026-05-04T10:00:00Z").unwrap();
+        assert_eq!(
+            first,
+            RemoteImportTally {
+                imported: 1,
+                updated: 0,
+                skipped: 1,
+                failed: 1
+            }
+        );
+        assert_eq!(first_ids.len(), 1);
+        let (again, again_ids) =
+            import_remote_orders_batch(&conn, &orders, "2026-05-04T10:05:00Z").unwrap();
+        assert!(again_ids.is_empty());
+        assert_eq!(again.imported, 0);
+        assert_eq!(again.skipped, 2);
+
+        let (sync_status, total_cents): (String, i64) = conn
+            .query_row(
+                "SELECT sync_status, total_amount_cents FROM orders WHERE supabase_id = 'remote-new'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(sync_status, "synced");
+        assert_eq!(total_cents, 1250);
+        let queued: i64 = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM sync_queue) + (SELECT COUNT(*) FROM parity_sync_queue)",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(queued, 0);
+    }
 }
@@ -0,0 +1,157 @@
+use chrono::{Local, Utc};
+use serde_json::{json, Value};
+use tauri::Emitter;
+
+use crate::organization_change::{self, Observation, PendingChange, Resolution};
+use crate::{auth, db, storage, value_str};
+
+/// Store an organization id reported by the admin dashboard, unless it would
+/// hand the local data to a different organization. Returns the held change
+/// in that case; nothing is stored until it is confirmed.
+pub(crate) fn store_organization_id(
+    db: &db::DbState,
+    organization_id: &str,
+    source: &str,
+) -> Result<Option<PendingChange>, String> {
+    let observation = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        organization_change::observe(&conn, organization_id, source, Utc::now())?
+    };
+    if let Observation::Pending(change) = observation {
+        return Ok(Some(change));
+    }
+    storage::set_credential("organization_id", organization_id)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    db::set_setting(&conn, "terminal", "organization_id", organization_id)?;
+    Ok(None)
+}
+
+/// Remove the organization id from a credentials payload when it names a
+/// different organization than the one owning the local data. Returns the
+/// stripped payload, or `None` when the payload can be stored as is.
+pub(crate) fn hold_payload_organization(
+    db: &db::DbState,
+    payload: &Value,
+    source: &str,
+) -> Result<Option<Value>, String> {
+    let Some(organization_id) = value_str(payload, &["organizationId", "organization_id"]) else {
+        return Ok(None);
+    };
+    let observation = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        organization_change::observe(&conn, &organization_id, source, Utc::now())?
+    };
+    if !matches!(observation, Observation::Pending(_)) {
+        return Ok(None);
+    }
+    let mut stripped = payload.clone();
+    if let Some(map) = stripped.as_object_mut() {
+        map.remove("organizationId");
+        map.remove("organization_id");
+    }
+    Ok(Some(stripped))
+}
+
+/// Tell the renderer to show the organization change screen when a change
+/// is waiting for confirmation.
+pub(crate) fn announce_if_pending(app: &tauri::AppHandle, db: &db::DbState) {
+    let change = match db.conn.lock() {
+        Ok(conn) => organization_change::pending(&conn),
+        Err(_) => None,
+    };
+    if let Some(change) = change {
+        let _ = app.emit(organization_change::REQUIRED_EVENT, change.to_json());
+    }
+}
+
+#[tauri::command]
+pub async fn organization_change_status(
+    db: tauri::State<'_, db::DbState>,
+) -> Result<Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(match organization_change::pending(&conn) {
+        Some(change) => json!({ "pending": true, "change": change.to_json() }),
+        None => json!({ "pending": false }),
+    })
+}
+
+/// Resolve a pending organization change: `action: "wipe"` archives the
+/// previous organization's data under the recovery root and deletes it;
+/// `action: "retag"` moves it to the new organization and needs `adminPin`.
+#[tauri::command]
+pub async fn organization_change_confirm(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<Value, String> {
+    let payload = arg0.unwrap_or(Value::Null);
+    let resolution = value_str(&payload, &["action", "resolution", "mode"])
+        .as_deref()
+        .and_then(Resolution::parse)
+        .ok_or("organization_change_confirm requires action \"wipe\" or \"retag\"")?;
+    let change = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        organization_change::pending(&conn)
+    }
+    .ok_or("No organization change is waiting for confirmation")?;
+    let actor = auth::current_staff_id(&auth_state);
+
+    let details = match resolution {
+        Resolution::Wipe => wipe_previous_organization(&db)?,
+        Resolution::Retag => {
+            let pin = value_str(&payload, &["adminPin", "admin_pin", "pin"])
+                .ok_or("Re-tagging the existing data requires the admin PIN")?;
+            if !auth::verify_privileged_pin_with_lockout(&pin, "admin", &db, &auth_state)? {
+                return Err("Invalid admin PIN".into());
+            }
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            organization_change::retag(&conn, &change)?
+        }
+    };
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        organization_change::finish(
+            &conn,
+            &change,
+            resolution,
+            actor.as_deref(),
+            details.clone(),
+        )?;
+    }
+    storage::set_credential("organization_id", &change.new_organization_id)?;
+    tracing::warn!(
+        previous_organization_id = %crate::mask_terminal_id(&change.previous_organization_id),
+        new_organization_id = %crate::mask_terminal_id(&change.new_organization_id),
+        action = resolution.as_str(),
+        "Organization change confirmed"
+    );
+
+    let result = json!({
+        "success": true,
+        "action": resolution.as_str(),
+        "change": change.to_json(),
+        "details": details,
+    });
+    let _ = app.emit(organization_change::RESOLVED_EVENT, &result);
+    Ok(result)
+}
+
+/// Snapshot, archive and delete the previous organization's data.
+fn wipe_previous_organization(db: &db::DbState) -> Result<Value, String> {
+    crate::recovery::snapshot_before_destructive_action(
+        db,
+        crate::recovery::RecoveryPointKind::PreClearOperationalData,
+    )?;
+    let archive_files = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let archive_dir =
+            crate::recovery::recovery_root_for_db(db).join(organization_change::ARCHIVE_DIR);
+        let date = Local::now().format("%Y-%m-%d").to_string();
+        organization_change::archive(&conn, &archive_dir, &date)?
+    };
+    crate::clear_operational_data_inner(db)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    organization_change::wipe(&conn)?;
+    Ok(json!({ "archiveFiles": archive_files }))
+}
@@ -1,8 +1,16 @@
 use chrono::Utc;
 use serde::Deserialize;
 use tauri::{Emitter, Manager};
+use tracing::{error, info, warn};
 
-use crate::{db, payload_arg0_as_string, payments, refunds, resolve_order_id};
+use crate::ecr::protocol::{TransactionRequest, TransactionStatus, TransactionType};
+use crate::error::PosError;
+use crate::money::Cents;
+use crate::tabs::{self, CaptureStep, Tab};
+use crate::{
+    checks, db, ecr, idempotency, inventory, kiosk, order_locks, payload_arg0_as_string, payments,
+    receipt_copies, receipt_delivery, refunds, resolve_order_id, training, value_str,
+};
 
 #[derive(Debug)]
 struct PaymentUpdateStatusPayload {
@@ -22,6 +30,9 @@ struct PaymentMethodUpdatePayload {
 struct PaymentVoidPayload {
     #[serde(alias = "payment_id")]
     payment_id: String,
+    #[serde(default, alias = "reason_code")]
+    reason_code: String,
+    #[serde(default)]
     reason: String,
     #[serde(default, alias = "voided_by")]
     voided_by: Option<String>,
@@ -34,6 +45,9 @@ struct PaymentVoidPayload {
 struct RefundVoidPayload {
     #[serde(alias = "payment_id")]
     payment_id: String,
+    #[serde(default, alias = "reason_code")]
+    reason_code: String,
+    #[serde(default)]
     reason: String,
     #[serde(default, alias = "staff_id")]
     staff_id: Option<String>,
@@ -41,11 +55,68 @@ struct RefundVoidPayload {
     staff_shift_id: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReceiptSendPayload {
+    #[serde(alias = "order_id", alias = "id")]
+    order_id: String,
+    #[serde(alias = "email", alias = "phone", alias = "to")]
+    destination: String,
+    #[serde(default)]
+    channel: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReceiptDeliveriesQuery {
+    #[serde(default, alias = "order_id")]
+    order_id: Option<String>,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TabOpenPayload {
+    #[serde(alias = "order_id")]
+    order_id: String,
+    #[serde(default, alias = "hold_amount")]
+    hold_amount: Option<f64>,
+    #[serde(default, alias = "device_id")]
+    device_id: Option<String>,
+    #[serde(default, alias = "card_reference")]
+    card_reference: Option<String>,
+    #[serde(default, alias = "card_last_four")]
+    card_last_four: Option<String>,
+    /// Record the card reference even when a pre-auth device is connected.
+    #[serde(default)]
+    manual: bool,
+    #[serde(default)]
+    currency: Option<String>,
+    #[serde(default, alias = "staff_id")]
+    staff_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TabClosePayload {
+    #[serde(alias = "order_id")]
+    order_id: String,
+    #[serde(default, alias = "tip_amount")]
+    tip_amount: Option<f64>,
+    #[serde(default)]
+    currency: Option<String>,
+    #[serde(default, alias = "staff_id")]
+    staff_id: Option<String>,
+}
+
 fn parse_payment_update_status_payload(
     arg0: Option<serde_json::Value>,
     arg1: Option<String>,
     arg2: Option<String>,
-) -> Result<PaymentUpdateStatusPayload, String> {
+) -> Result<PaymentUpdateStatusPayload, PosError> {
     let payload = match arg0 {
         Some(serde_json::Value::Object(obj)) => serde_json::Value::Object(obj),
         Some(serde_json::Value::String(order_id)) => serde_json::json!({
@@ -64,7 +135,7 @@ fn parse_payment_update_status_payload(
         Some(payload.clone()),
         &["orderId", "order_id", "id", "supabaseId", "supabase_id"],
     )
-    .ok_or("Missing orderId")?;
+    .ok_or_else(|| PosError::validation("orderId", "Missing orderId"))?;
     let payment_status = payload
         .get("paymentStatus")
         .or_else(|| payload.get("payment_status"))
@@ -73,7 +144,7 @@ fn parse_payment_update_status_payload(
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty())
         .or_else(|| arg1.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()))
-        .ok_or("Missing payment status")?;
+        .ok_or_else(|| PosError::validation("paymentStatus", "Missing payment status"))?;
     let payment_status = match payment_status.to_ascii_lowercase().as_str() {
         "pending" => "pending".to_string(),
         "paid" => "paid".to_string(),
@@ -81,10 +152,10 @@ fn parse_payment_update_status_payload(
         "refunded" => "refunded".to_string(),
         "failed" => "failed".to_string(),
         _ => {
-            return Err(
-                "payment_update_payment_status only supports reconciliation states; use payment_record to capture funds"
-                    .into(),
-            )
+            return Err(PosError::validation(
+                "paymentStatus",
+                "payment_update_payment_status only supports reconciliation states; use payment_record to capture funds",
+            ))
         }
     };
     let payment_method = payload
@@ -105,7 +176,7 @@ fn parse_payment_update_status_payload(
 fn parse_payment_method_update_payload(
     arg0: Option<serde_json::Value>,
     arg1: Option<String>,
-) -> Result<PaymentMethodUpdatePayload, String> {
+) -> Result<PaymentMethodUpdatePayload, PosError> {
     let payload = match arg0 {
         Some(serde_json::Value::Object(obj)) => serde_json::Value::Object(obj),
         Some(serde_json::Value::String(order_id)) => serde_json::json!({
@@ -122,7 +193,7 @@ fn parse_payment_method_update_payload(
         Some(payload.clone()),
         &["orderId", "order_id", "id", "supabaseId", "supabase_id"],
     )
-    .ok_or("Missing orderId")?;
+    .ok_or_else(|| PosError::validation("orderId", "Missing orderId"))?;
     let payment_method = payload
         .get("paymentMethod")
         .or_else(|| payload.get("payment_method"))
@@ -135,12 +206,17 @@ fn parse_payment_method_update_payload(
                 .map(|value| value.trim().to_ascii_lowercase())
                 .filter(|value| !value.is_empty())
         })
-        .ok_or("Missing payment method")?;
+        .ok_or_else(|| PosError::validation("paymentMethod", "Missing payment method"))?;
 
     let payment_method = match payment_method.as_str() {
         "cash" => "cash".to_string(),
         "card" => "card".to_string(),
-        _ => return Err("Payment method edits only support cash or card".into()),
+        _ => {
+            return Err(PosError::validation(
+                "paymentMethod",
+                "Payment method edits only support cash or card",
+            ))
+        }
     };
 
     Ok(PaymentMethodUpdatePayload {
@@ -151,51 +227,121 @@ fn parse_payment_method_update_payload(
 
 fn parse_payment_void_payload(
     payload: Option<serde_json::Value>,
-) -> Result<PaymentVoidPayload, String> {
-    let mut parsed: PaymentVoidPayload =
-        serde_json::from_value(payload.ok_or("Missing void payment payload")?)
-            .map_err(|e| format!("Invalid void payment payload: {e}"))?;
+) -> Result<PaymentVoidPayload, PosError> {
+    let mut parsed: PaymentVoidPayload = serde_json::from_value(
+        payload.ok_or_else(|| PosError::validation("payload", "Missing void payment payload"))?,
+    )
+    .map_err(|e| PosError::validation("payload", format!("Invalid void payment payload: {e}")))?;
 
     parsed.payment_id = parsed.payment_id.trim().to_string();
+    parsed.reason_code = parsed.reason_code.trim().to_string();
     parsed.reason = parsed.reason.trim().to_string();
     if parsed.payment_id.is_empty() {
-        return Err("Missing paymentId".into());
+        return Err(PosError::validation("paymentId", "Missing paymentId"));
     }
-    if parsed.reason.is_empty() {
-        return Err("Missing reason".into());
+    if parsed.reason_code.is_empty() {
+        return Err(PosError::validation("reasonCode", "Missing reasonCode"));
     }
     Ok(parsed)
 }
 
 fn parse_refund_void_payload(
     payload: Option<serde_json::Value>,
-) -> Result<RefundVoidPayload, String> {
-    let mut parsed: RefundVoidPayload =
-        serde_json::from_value(payload.ok_or("Missing void payment payload")?)
-            .map_err(|e| format!("Invalid refund void payload: {e}"))?;
+) -> Result<RefundVoidPayload, PosError> {
+    let mut parsed: RefundVoidPayload = serde_json::from_value(
+        payload.ok_or_else(|| PosError::validation("payload", "Missing void payment payload"))?,
+    )
+    .map_err(|e| PosError::validation("payload", format!("Invalid refund void payload: {e}")))?;
 
     parsed.payment_id = parsed.payment_id.trim().to_string();
+    parsed.reason_code = parsed.reason_code.trim().to_string();
     parsed.reason = parsed.reason.trim().to_string();
     if parsed.payment_id.is_empty() {
-        return Err("Missing paymentId".into());
+        return Err(PosError::validation("paymentId", "Missing paymentId"));
     }
-    if parsed.reason.is_empty() {
-        return Err("Missing reason".into());
+    if parsed.reason_code.is_empty() {
+        return Err(PosError::validation("reasonCode", "Missing reasonCode"));
     }
     Ok(parsed)
 }
 
-fn parse_order_id_payload(arg0: Option<serde_json::Value>) -> Result<String, String> {
+fn parse_order_id_payload(arg0: Option<serde_json::Value>) -> Result<String, PosError> {
     payload_arg0_as_string(
         arg0,
         &["orderId", "order_id", "id", "supabaseId", "supabase_id"],
     )
-    .ok_or("Missing orderId".into())
+    .ok_or_else(|| PosError::validation("orderId", "Missing orderId"))
 }
 
-fn parse_payment_id_payload(arg0: Option<serde_json::Value>) -> Result<String, String> {
+fn parse_payment_id_payload(arg0: Option<serde_json::Value>) -> Result<String, PosError> {
     payload_arg0_as_string(arg0, &["paymentId", "payment_id", "id"])
-        .ok_or("Missing paymentId".into())
+        .ok_or_else(|| PosError::validation("paymentId", "Missing paymentId"))
+}
+
+fn parse_check_id_payload(arg0: Option<serde_json::Value>) -> Result<String, PosError> {
+    payload_arg0_as_string(arg0, &["checkId", "check_id", "id"])
+        .ok_or_else(|| PosError::validation("checkId", "Missing checkId"))
+}
+
+fn parse_split_bill_payload(
+    arg0: Option<serde_json::Value>,
+) -> Result<(String, checks::SplitSpec), PosError> {
+    let payload = arg0.ok_or_else(|| PosError::validation("payload", "Missing split payload"))?;
+    let spec = checks::parse_spec(&payload).map_err(|e| PosError::validation("checks", e))?;
+    let order_id = parse_order_id_payload(Some(payload))?;
+    Ok((order_id, spec))
+}
+
+fn parse_tab_open_payload(payload: &serde_json::Value) -> Result<TabOpenPayload, PosError> {
+    let mut parsed: TabOpenPayload = serde_json::from_value(payload.clone())
+        .map_err(|e| PosError::validation("payload", format!("Invalid tab payload: {e}")))?;
+    parsed.order_id = parsed.order_id.trim().to_string();
+    if parsed.order_id.is_empty() {
+        return Err(PosError::validation("orderId", "Missing orderId"));
+    }
+    if parsed
+        .hold_amount
+        .is_some_and(|amount| !amount.is_finite() || amount <= 0.0)
+    {
+        return Err(PosError::validation(
+            "holdAmount",
+            "Hold amount must be positive",
+        ));
+    }
+    parsed.device_id = parsed
+        .device_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty());
+    parsed.card_reference = parsed
+        .card_reference
+        .map(|reference| reference.trim().to_string())
+        .filter(|reference| !reference.is_empty());
+    if parsed.manual && parsed.card_reference.is_none() {
+        return Err(PosError::validation(
+            "cardReference",
+            "A manual tab needs a card reference",
+        ));
+    }
+    Ok(parsed)
+}
+
+fn parse_tab_close_payload(payload: &serde_json::Value) -> Result<TabClosePayload, PosError> {
+    let mut parsed: TabClosePayload = serde_json::from_value(payload.clone())
+        .map_err(|e| PosError::validation("payload", format!("Invalid tab payload: {e}")))?;
+    parsed.order_id = parsed.order_id.trim().to_string();
+    if parsed.order_id.is_empty() {
+        return Err(PosError::validation("orderId", "Missing orderId"));
+    }
+    if parsed
+        .tip_amount
+        .is_some_and(|amount| !amount.is_finite() || amount < 0.0)
+    {
+        return Err(PosError::validation(
+            "tipAmount",
+            "Tip amount cannot be negative",
+        ));
+    }
+    Ok(parsed)
 }
 
 #[tauri::command]
@@ -205,14 +351,15 @@ pub async fn payment_update_payment_status(
     arg2: Option<String>,
     db: tauri::State<'_, db::DbState>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_payment_update_status_payload(arg0, arg1, arg2)?;
     let order_id_raw = payload.order_id;
     let payment_status = payload.payment_status;
     let payment_method = payload.payment_method;
     let now = Utc::now().to_rfc3339();
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let order_id = resolve_order_id(&conn, &order_id_raw).ok_or("Order not found")?;
+    let order_id = resolve_order_id(&conn, &order_id_raw)
+        .ok_or_else(|| PosError::not_found("Order not found"))?;
 
     // Wave 6 H15: the SELECT of `current_payment_status` +
     // `completed_payment_rows` followed by the UPDATE used to run on the
@@ -235,7 +382,7 @@ pub async fn payment_update_payment_status(
     conn.execute_batch("BEGIN IMMEDIATE")
         .map_err(|e| format!("begin payment-status transaction: {e}"))?;
 
-    let result = (|| -> Result<(), String> {
+    let result = (|| -> Result<(), PosError> {
         let (current_payment_status, completed_payment_rows): (String, i64) = conn
             .query_row(
                 "SELECT COALESCE(payment_status, 'pending'),
@@ -258,10 +405,10 @@ pub async fn payment_update_payment_status(
                 "paid" | "partially_paid" | "refunded"
             )
         {
-            return Err(
+            return Err(PosError::Conflict(
                 "Cannot promote payment status without completed payment rows; use payment_record instead"
                     .into(),
-            );
+            ));
         }
         conn.execute(
             "UPDATE orders
@@ -307,7 +454,7 @@ pub async fn payment_update_payment_method(
     arg1: Option<String>,
     db: tauri::State<'_, db::DbState>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_payment_method_update_payload(arg0, arg1)?;
     let result = payments::update_payment_method(&db, &payload.order_id, &payload.payment_method)?;
     if let Some(event_payload) = result.get("data").cloned() {
@@ -320,51 +467,299 @@ pub async fn payment_update_payment_method(
 pub async fn payment_record(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
-    let payload = arg0.ok_or("Missing payment payload")?;
-    payments::record_payment(&db, &payload)
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, PosError> {
+    crate::diagnostics::metrics::track("payment_record", async move {
+        let payload =
+            arg0.ok_or_else(|| PosError::validation("payload", "Missing payment payload"))?;
+        kiosk::ensure_card_payment(&payload)?;
+        let key = idempotency::request_key(&payload);
+        idempotency::run_once(&db, "payment_record", key, || async {
+            record_payment_and_deduct_stock(&db, &app, &payload)
+        })
+        .await
+    })
+    .await
+}
+
+/// Record a payment and, once it is captured, deduct stock for its order.
+fn record_payment_and_deduct_stock(
+    db: &db::DbState,
+    app: &tauri::AppHandle,
+    payload: &serde_json::Value,
+) -> Result<serde_json::Value, PosError> {
+    let mut local_order_id = None;
+    if let Some(order_id) = payload
+        .get("orderId")
+        .or_else(|| payload.get("order_id"))
+        .and_then(serde_json::Value::as_str)
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        if let Some(local_id) = resolve_order_id(&conn, order_id) {
+            if let Some(locked) = order_locks::guard_order_unlocked(&conn, &local_id)? {
+                return Ok(locked);
+            }
+            local_order_id = Some(local_id);
+        }
+    }
+    let mut result = payments::record_payment(db, payload)?;
+    let recorded = result.get("success").and_then(serde_json::Value::as_bool) == Some(true);
+    if let Some(order_id) = local_order_id.as_deref().filter(|_| recorded) {
+        let low_stock = {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            inventory::deduct_for_order_logged(
+                &conn,
+                order_id,
+                inventory::TRIGGER_PAYMENT,
+                crate::value_str(payload, &["staffId", "staff_id"]).as_deref(),
+            )
+        };
+        inventory::emit_low_stock(app, &low_stock);
+    }
+    if let Some(order_id) = local_order_id.filter(|_| recorded && !suppresses_auto_print(payload)) {
+        let payment_id = result["paymentId"].as_str().unwrap_or_default().to_string();
+        let method = value_str(payload, &["method"]).unwrap_or_default();
+        let amount = crate::value_f64(payload, &["amount"]).unwrap_or(0.0);
+        match receipt_copies::enqueue_after_payment(db, &order_id, &payment_id, &method, amount) {
+            Ok(jobs) if !jobs.is_empty() => {
+                if let Ok(data_dir) = app.path().app_data_dir() {
+                    crate::print::spawn_pending_job_processing(
+                        app.clone(),
+                        data_dir,
+                        format!("receipt copies for payment {payment_id}"),
+                    );
+                }
+                result["receiptCopies"] = serde_json::Value::from(jobs);
+            }
+            Ok(_) => {}
+            Err(e) => warn!(order_id = %order_id, error = %e, "Failed to enqueue receipt copies"),
+        }
+    }
+    Ok(result)
+}
+
+/// `suppressAutoPrint` on a `payment_record` payload skips the receipt copy
+/// policy for that payment.
+fn suppresses_auto_print(payload: &serde_json::Value) -> bool {
+    ["suppressAutoPrint", "suppress_auto_print"]
+        .iter()
+        .find_map(|key| payload.get(*key).and_then(serde_json::Value::as_bool))
+        .unwrap_or(false)
 }
 
 #[tauri::command]
 pub async fn payment_void(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_payment_void_payload(arg0)?;
-    payments::void_payment(
+    Ok(payments::void_payment(
         &db,
         &payload.payment_id,
+        &payload.reason_code,
         &payload.reason,
         payload.voided_by.as_deref(),
         payload.staff_shift_id.as_deref(),
-    )
+    )?)
 }
 
 #[tauri::command]
 pub async fn payment_get_order_payments(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
+    let order_id = parse_order_id_payload(arg0)?;
+    Ok(payments::get_order_payments(&db, &order_id)?)
+}
+
+/// Total, paid, refunded and remaining amounts for an order — the deposit
+/// view used when an order is settled in instalments.
+#[tauri::command]
+pub async fn order_get_balance(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, PosError> {
+    let order_id = parse_order_id_payload(arg0)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let order_id = resolve_order_id(&conn, &order_id)
+        .ok_or_else(|| PosError::not_found(format!("Order not found: {order_id}")))?;
+    let balance = payments::load_order_balance(&conn, &order_id)?;
+    Ok(serde_json::json!({ "success": true, "balance": balance }))
+}
+
+/// Split an order into separately payable checks, either by item
+/// (`checks: [[0, 2], [1]]`) or evenly (`even: 3`). The order itself stays
+/// whole for the kitchen.
+#[tauri::command]
+pub async fn order_split_bill(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+) -> Result<serde_json::Value, PosError> {
+    let (order_id, spec) = parse_split_bill_payload(arg0)?;
+    let actor = crate::auth::current_staff_id(&auth_state);
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let order_id = resolve_order_id(&conn, &order_id)
+        .ok_or_else(|| PosError::not_found(format!("Order not found: {order_id}")))?;
+    if let Some(locked) = order_locks::guard_order_unlocked(&conn, &order_id)? {
+        return Ok(locked);
+    }
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("begin split bill transaction: {e}"))?;
+    checks::split(&tx, &order_id, &spec, actor.as_deref())?;
+    tx.commit().map_err(|e| format!("commit split bill: {e}"))?;
+    Ok(checks::checks_json(&conn, &order_id)?)
+}
+
+/// The order's checks with what is paid and still owed on each.
+#[tauri::command]
+pub async fn order_get_checks(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, PosError> {
     let order_id = parse_order_id_payload(arg0)?;
-    payments::get_order_payments(&db, &order_id)
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let order_id = resolve_order_id(&conn, &order_id)
+        .ok_or_else(|| PosError::not_found(format!("Order not found: {order_id}")))?;
+    Ok(checks::checks_json(&conn, &order_id)?)
 }
 
 #[tauri::command]
 pub async fn payment_get_receipt_preview(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
+    let order_id = parse_order_id_payload(arg0)?;
+    Ok(payments::get_receipt_preview(&db, &order_id)?)
+}
+
+/// Print a "DUPLICATE #n" copy of the order's latest receipt. Copies of
+/// receipts from a previous day need manager approval when
+/// `receipt.reprint_requires_approval_for_past_days` is enabled.
+#[tauri::command]
+pub async fn payment_reprint_receipt(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, crate::auth::GuardedCommandError> {
     let order_id = parse_order_id_payload(arg0)?;
-    payments::get_receipt_preview(&db, &order_id)
+    let (order_id, needs_approval) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let order_id = resolve_order_id(&conn, &order_id)
+            .ok_or_else(|| format!("Order not found: {order_id}"))?;
+        let latest = crate::print::latest_receipt_job(&conn, &order_id)?
+            .ok_or("No receipt has been printed for this order")?;
+        let needs_approval = crate::print::receipt_reprint_needs_approval(&conn, &latest);
+        (order_id, needs_approval)
+    };
+    if needs_approval {
+        crate::auth::authorize_privileged_action(
+            crate::auth::PrivilegedActionScope::CashDrawerControl,
+            &db,
+            &auth_state,
+        )?;
+    }
+
+    let staff_id = crate::auth::current_staff_id(&auth_state);
+    let result = crate::print::enqueue_receipt_reprint(&db, &order_id, staff_id.as_deref())?;
+
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("app data dir: {e}"))?;
+    crate::print::spawn_pending_job_processing(
+        app.clone(),
+        data_dir,
+        format!("receipt reprint for order {order_id}"),
+    );
+
+    Ok(result)
+}
+
+/// Email or text the order's receipt through the admin API. The delivery is
+/// queued first, so it is retried in the background when the terminal is
+/// offline; a repeat request for the same destination on the same day
+/// returns the existing delivery.
+#[tauri::command]
+pub async fn receipt_send(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+) -> Result<serde_json::Value, PosError> {
+    let payload: ReceiptSendPayload = serde_json::from_value(
+        arg0.ok_or_else(|| PosError::validation("payload", "Missing receipt send payload"))?,
+    )
+    .map_err(|e| PosError::validation("payload", format!("Invalid receipt send payload: {e}")))?;
+    let (channel, destination) =
+        receipt_delivery::parse_destination(payload.channel.as_deref(), &payload.destination)?;
+    let order_id = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        resolve_order_id(&conn, &payload.order_id)
+            .ok_or_else(|| PosError::not_found(format!("Order not found: {}", payload.order_id)))?
+    };
+
+    let staff_id = crate::auth::current_staff_id(&auth_state);
+    let (delivery, created) = receipt_delivery::enqueue_delivery(
+        &db,
+        &order_id,
+        channel,
+        &destination,
+        staff_id.as_deref(),
+    )?;
+    if !created {
+        return Ok(serde_json::json!({
+            "success": true,
+            "duplicate": true,
+            "delivery": delivery,
+        }));
+    }
+
+    let delivery_id = delivery["id"].as_str().unwrap_or_default().to_string();
+    let delivery = receipt_delivery::attempt_delivery(&db, &delivery_id).await?;
+    Ok(serde_json::json!({
+        "success": true,
+        "queued": delivery["status"] == receipt_delivery::STATUS_PENDING,
+        "delivery": delivery,
+    }))
+}
+
+#[tauri::command]
+pub async fn receipt_list_deliveries(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, PosError> {
+    let query: ReceiptDeliveriesQuery = match arg0 {
+        Some(serde_json::Value::String(order_id)) => ReceiptDeliveriesQuery {
+            order_id: Some(order_id),
+            ..Default::default()
+        },
+        Some(value @ serde_json::Value::Object(_)) => serde_json::from_value(value)
+            .map_err(|e| format!("Invalid receipt deliveries query: {e}"))?,
+        _ => ReceiptDeliveriesQuery::default(),
+    };
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let order_id = query
+        .order_id
+        .as_deref()
+        .map(|raw| resolve_order_id(&conn, raw).unwrap_or_else(|| raw.to_string()));
+    let deliveries = receipt_delivery::list_deliveries(
+        &conn,
+        order_id.as_deref(),
+        query.status.as_deref(),
+        query.limit.unwrap_or(100),
+    )?;
+    Ok(serde_json::json!({ "success": true, "deliveries": deliveries }))
 }
 
 #[tauri::command]
 pub async fn payment_get_paid_items(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let order_id = parse_order_id_payload(arg0)?;
-    payments::get_paid_items(&db, &order_id)
+    Ok(payments::get_paid_items(&db, &order_id)?)
 }
 
 #[tauri::command]
@@ -372,7 +767,7 @@ pub async fn payment_print_split_receipt(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payment_id = parse_payment_id_payload(arg0)?;
     if !crate::print::is_print_action_enabled(&db, "split_receipt") {
         return Ok(serde_json::json!({ "success": true, "skipped": true }));
@@ -393,46 +788,545 @@ pub async fn payment_print_split_receipt(
     Ok(enqueue_result)
 }
 
+/// Receipt for one check of a split bill: its items, totals and payments.
+#[tauri::command]
+pub async fn payment_print_check_receipt(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, PosError> {
+    let check_id = parse_check_id_payload(arg0)?;
+    if !crate::print::is_print_action_enabled(&db, "split_receipt") {
+        return Ok(serde_json::json!({ "success": true, "skipped": true }));
+    }
+    let enqueue_result = crate::print::enqueue_print_job(&db, "check_receipt", &check_id, None)?;
+
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("app data dir: {e}"))?;
+    crate::print::spawn_pending_job_processing(
+        app.clone(),
+        data_dir,
+        format!("receipt for check {check_id}"),
+    );
+
+    Ok(enqueue_result)
+}
+
 #[tauri::command]
 pub async fn refund_payment(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
-    let payload = arg0.ok_or("Missing refund payload")?;
-    refunds::refund_payment(&db, &payload)
+) -> Result<serde_json::Value, PosError> {
+    let payload = arg0.ok_or_else(|| PosError::validation("payload", "Missing refund payload"))?;
+    let key = idempotency::request_key(&payload);
+    idempotency::run_once(&db, "refund_payment", key, || async {
+        refunds::refund_payment(&db, &payload).map_err(PosError::from)
+    })
+    .await
 }
 
 #[tauri::command]
 pub async fn refund_void_payment(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_refund_void_payload(arg0)?;
-    refunds::void_payment_with_adjustment(
+    Ok(refunds::void_payment_with_adjustment(
         &db,
         &payload.payment_id,
+        &payload.reason_code,
         &payload.reason,
         payload.staff_id.as_deref(),
         payload.staff_shift_id.as_deref(),
-    )
+    )?)
 }
 
 #[tauri::command]
 pub async fn refund_list_order_adjustments(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let order_id = parse_order_id_payload(arg0)?;
-    refunds::list_order_adjustments(&db, &order_id)
+    Ok(refunds::list_order_adjustments(&db, &order_id)?)
 }
 
 #[tauri::command]
 pub async fn refund_get_payment_balance(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payment_id = parse_payment_id_payload(arg0)?;
-    refunds::get_payment_balance(&db, &payment_id)
+    Ok(refunds::get_payment_balance(&db, &payment_id)?)
+}
+
+/// Build the ECR request for one step of a tab's card flow.
+fn tab_ecr_request(
+    transaction_type: TransactionType,
+    amount_cents: i64,
+    tip_cents: Option<i64>,
+    currency: &str,
+    order_id: &str,
+    original_transaction_id: Option<String>,
+) -> TransactionRequest {
+    TransactionRequest {
+        transaction_id: format!("txn-{}", uuid::Uuid::new_v4()),
+        transaction_type,
+        amount: amount_cents,
+        currency: currency.to_string(),
+        order_id: Some(order_id.to_string()),
+        tip_amount: tip_cents,
+        original_transaction_id,
+        fiscal_data: None,
+    }
+}
+
+/// Run a tab's ECR step on `device_id` and log it to `ecr_transactions`.
+/// Training mode approves without touching the device.
+async fn run_tab_ecr_step(
+    db: &db::DbState,
+    mgr: &ecr::DeviceManager,
+    device_id: &str,
+    request: TransactionRequest,
+) -> Result<ecr::protocol::TransactionResponse, String> {
+    let now = Utc::now().to_rfc3339();
+    if training::is_active() {
+        return Ok(ecr::protocol::TransactionResponse {
+            transaction_id: request.transaction_id,
+            status: TransactionStatus::Approved,
+            authorization_code: Some("TRAINING".to_string()),
+            terminal_reference: None,
+            fiscal_receipt_number: None,
+            fiscal_z_number: None,
+            card_type: None,
+            card_last_four: None,
+            entry_method: None,
+            customer_receipt_lines: None,
+            merchant_receipt_lines: None,
+            error_message: None,
+            error_code: None,
+            raw_response: None,
+            started_at: now.clone(),
+            completed_at: now,
+        });
+    }
+    let transaction_type = serde_json::to_value(request.transaction_type)
+        .ok()
+        .and_then(|value| value.as_str().map(ToString::to_string))
+        .unwrap_or_default();
+    let mut log = serde_json::json!({
+        "id": request.transaction_id,
+        "deviceId": device_id,
+        "orderId": request.order_id,
+        "transactionType": transaction_type,
+        "amount": request.amount,
+        "currency": request.currency,
+    });
+    let result = mgr.process_transaction_offloaded(device_id, request).await;
+    if let Some(obj) = log.as_object_mut() {
+        match &result {
+            Ok(resp) => {
+                let status = format!("{:?}", resp.status).to_lowercase();
+                obj.insert("id".into(), serde_json::json!(resp.transaction_id));
+                obj.insert("status".into(), serde_json::json!(status));
+                obj.insert(
+                    "authorizationCode".into(),
+                    serde_json::json!(resp.authorization_code),
+                );
+                obj.insert(
+                    "terminalReference".into(),
+                    serde_json::json!(resp.terminal_reference),
+                );
+                obj.insert("cardType".into(), serde_json::json!(resp.card_type));
+                obj.insert(
+                    "cardLastFour".into(),
+                    serde_json::json!(resp.card_last_four),
+                );
+                obj.insert("entryMethod".into(), serde_json::json!(resp.entry_method));
+                obj.insert("errorMessage".into(), serde_json::json!(resp.error_message));
+                obj.insert("rawResponse".into(), serde_json::json!(resp.raw_response));
+                obj.insert("startedAt".into(), serde_json::json!(resp.started_at));
+                obj.insert("completedAt".into(), serde_json::json!(resp.completed_at));
+            }
+            Err(e) => {
+                obj.insert("status".into(), serde_json::json!("error"));
+                obj.insert("errorMessage".into(), serde_json::json!(e));
+                obj.insert("startedAt".into(), serde_json::json!(now));
+                obj.insert(
+                    "completedAt".into(),
+                    serde_json::json!(Utc::now().to_rfc3339()),
+                );
+            }
+        }
+    }
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let _ = db::ecr_insert_transaction(&conn, &log);
+    }
+    result
+}
+
+/// The error a declined ECR step reports to the caller.
+fn declined_message(resp: &ecr::protocol::TransactionResponse, fallback: &str) -> String {
+    resp.error_message
+        .clone()
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+/// Open a bar tab on an order. With an ECR device that can pre-authorize,
+/// a hold of `holdAmount` (default `payments.tab_hold_amount`) is placed on
+/// the card; otherwise, or with `manual: true`, the operator's
+/// `cardReference` is recorded instead.
+#[tauri::command]
+pub async fn tab_open(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    mgr: tauri::State<'_, ecr::DeviceManager>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, PosError> {
+    let payload = arg0.ok_or_else(|| PosError::validation("payload", "Missing tab payload"))?;
+    let request = parse_tab_open_payload(&payload)?;
+    let actor = request
+        .staff_id
+        .clone()
+        .or_else(|| crate::auth::current_staff_id(&auth_state));
+    let key = idempotency::request_key(&payload);
+    idempotency::run_once(&db, "tab_open", key, || async {
+        open_tab(&db, &mgr, &app, &request, actor.as_deref()).await
+    })
+    .await
+}
+
+async fn open_tab(
+    db: &db::DbState,
+    mgr: &ecr::DeviceManager,
+    app: &tauri::AppHandle,
+    request: &TabOpenPayload,
+    actor: Option<&str>,
+) -> Result<serde_json::Value, PosError> {
+    let (order_id, hold_amount) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let order_id = resolve_order_id(&conn, &request.order_id)
+            .ok_or_else(|| PosError::not_found(format!("Order not found: {}", request.order_id)))?;
+        if let Some(locked) = order_locks::guard_order_unlocked(&conn, &order_id)? {
+            return Ok(locked);
+        }
+        tabs::ensure_can_open(&conn, &order_id).map_err(|e| PosError::validation("orderId", e))?;
+        let hold_amount = request
+            .hold_amount
+            .unwrap_or_else(|| tabs::hold_amount(&conn));
+        (order_id, hold_amount)
+    };
+    let hold_amount_cents = Cents::round_half_even(hold_amount).as_i64();
+    let currency = request.currency.as_deref().unwrap_or("EUR");
+    let device_id = if request.manual {
+        None
+    } else {
+        request
+            .device_id
+            .clone()
+            .or_else(|| mgr.connected_device_ids().into_iter().next())
+    };
+    let capabilities = device_id.as_deref().and_then(|did| mgr.capabilities(did));
+
+    let mut tab = Tab {
+        id: uuid::Uuid::new_v4().to_string(),
+        order_id: order_id.clone(),
+        mode: tabs::MODE_MANUAL.to_string(),
+        device_id: None,
+        hold_amount_cents,
+        card_reference: request.card_reference.clone(),
+        card_last_four: request.card_last_four.clone(),
+        authorization_code: None,
+        terminal_reference: None,
+        ecr_transaction_id: None,
+        opened_at: Utc::now().to_rfc3339(),
+    };
+    match (device_id, capabilities) {
+        (Some(did), Some(caps)) if caps.pre_auth => {
+            let ecr_request = tab_ecr_request(
+                TransactionType::PreAuth,
+                hold_amount_cents,
+                None,
+                currency,
+                &order_id,
+                None,
+            );
+            let resp = run_tab_ecr_step(db, mgr, &did, ecr_request).await?;
+            if resp.status != TransactionStatus::Approved {
+                return Ok(serde_json::json!({
+                    "success": false,
+                    "error": declined_message(&resp, "Pre-authorization declined"),
+                }));
+            }
+            tab.mode = tabs::MODE_PRE_AUTH.to_string();
+            tab.device_id = Some(did);
+            tab.card_last_four = resp.card_last_four.or(tab.card_last_four);
+            tab.authorization_code = resp.authorization_code;
+            tab.terminal_reference = resp.terminal_reference;
+            tab.ecr_transaction_id = Some(resp.transaction_id);
+        }
+        _ if tab.card_reference.is_some() => {}
+        (Some(did), Some(_)) => {
+            return Err(PosError::validation(
+                "cardReference",
+                format!(
+                    "ECR device '{did}' does not support pre-authorization; enter a card reference to open a manual tab"
+                ),
+            ));
+        }
+        (Some(did), None) => {
+            return Err(PosError::validation(
+                "cardReference",
+                format!(
+                    "ECR device '{did}' is not connected; enter a card reference to open a manual tab"
+                ),
+            ));
+        }
+        (None, _) => {
+            return Err(PosError::validation(
+                "cardReference",
+                "No ECR device connected; enter a card reference to open a manual tab",
+            ));
+        }
+    }
+
+    let stored = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        tabs::open(&conn, &tab, actor)
+    };
+    if let Err(e) = stored {
+        // Don't leave a hold on the card for a tab that doesn't exist.
+        if let Some(did) = tab.device_id.as_deref() {
+            if mgr.capabilities(did).is_some_and(|caps| caps.void) {
+                let release = tab_ecr_request(
+                    TransactionType::Void,
+                    hold_amount_cents,
+                    None,
+                    currency,
+                    &order_id,
+                    tab.original_transaction_id(),
+                );
+                let _ = run_tab_ecr_step(db, mgr, did, release).await;
+            } else {
+                warn!(order_id = %order_id, device_id = %did, "Tab not stored; pre-authorization hold left on the card");
+            }
+        }
+        return Err(e.into());
+    }
+    info!(order_id = %order_id, tab_id = %tab.id, mode = %tab.mode, "Tab opened");
+    let tab_json = tab.to_json();
+    let _ = app.emit(
+        tabs::UPDATED_EVENT,
+        serde_json::json!({ "action": "open", "tab": tab_json }),
+    );
+    Ok(serde_json::json!({ "success": true, "tab": tab_json }))
+}
+
+/// Close an order's tab: capture the outstanding balance (plus
+/// `tipAmount`) against the pre-authorization, or charge the recorded card
+/// reference for a manual tab, and record the card payment. The acquirer
+/// releases whatever the capture leaves of the hold.
+#[tauri::command]
+pub async fn tab_close(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    mgr: tauri::State<'_, ecr::DeviceManager>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, PosError> {
+    let payload = arg0.ok_or_else(|| PosError::validation("payload", "Missing tab payload"))?;
+    let request = parse_tab_close_payload(&payload)?;
+    let actor = request
+        .staff_id
+        .clone()
+        .or_else(|| crate::auth::current_staff_id(&auth_state));
+    let key = idempotency::request_key(&payload);
+    idempotency::run_once(&db, "tab_close", key, || async {
+        close_tab(&db, &mgr, &app, &payload, &request, actor.as_deref()).await
+    })
+    .await
+}
+
+async fn close_tab(
+    db: &db::DbState,
+    mgr: &ecr::DeviceManager,
+    app: &tauri::AppHandle,
+    payload: &serde_json::Value,
+    request: &TabClosePayload,
+    actor: Option<&str>,
+) -> Result<serde_json::Value, PosError> {
+    let (order_id, tab, outstanding_cents) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let order_id = resolve_order_id(&conn, &request.order_id)
+            .ok_or_else(|| PosError::not_found(format!("Order not found: {}", request.order_id)))?;
+        if let Some(locked) = order_locks::guard_order_unlocked(&conn, &order_id)? {
+            return Ok(locked);
+        }
+        let tab = tabs::load_open(&conn, &order_id)?
+            .ok_or_else(|| PosError::not_found(format!("No open tab on order {order_id}")))?;
+        let balance = payments::load_order_payment_balance_snapshot(&conn, &order_id)?;
+        let outstanding_cents = Cents::round_half_even(balance.outstanding_amount)
+            .as_i64()
+            .max(0);
+        (order_id, tab, outstanding_cents)
+    };
+    let tip_cents = request
+        .tip_amount
+        .map(|tip| Cents::round_half_even(tip).as_i64())
+        .filter(|tip| *tip > 0 && outstanding_cents > 0);
+    let captured_cents = outstanding_cents + tip_cents.unwrap_or(0);
+    let currency = request.currency.as_deref().unwrap_or("EUR");
+
+    let mut transaction_ref = tab.card_reference.clone();
+    if tab.mode == tabs::MODE_PRE_AUTH {
+        let did = tab
+            .device_id
+            .clone()
+            .ok_or("Pre-authorized tab has no ECR device")?;
+        let capabilities = mgr.capabilities(&did).ok_or_else(|| {
+            PosError::validation(
+                "deviceId",
+                format!("ECR device '{did}' holding the pre-authorization is not connected"),
+            )
+        })?;
+        let plan = tabs::capture_plan(tab.hold_amount_cents, captured_cents, capabilities, &did)
+            .map_err(|e| PosError::validation("amount", e))?;
+        for step in plan {
+            let ecr_request = match step {
+                CaptureStep::Increment(cents) => tab_ecr_request(
+                    TransactionType::IncrementalAuth,
+                    cents,
+                    None,
+                    currency,
+                    &order_id,
+                    tab.original_transaction_id(),
+                ),
+                CaptureStep::Complete(_) => tab_ecr_request(
+                    TransactionType::PreAuthCompletion,
+                    outstanding_cents,
+                    tip_cents,
+                    currency,
+                    &order_id,
+                    tab.original_transaction_id(),
+                ),
+                CaptureStep::Release => tab_ecr_request(
+                    TransactionType::Void,
+                    tab.hold_amount_cents,
+                    None,
+                    currency,
+                    &order_id,
+                    tab.original_transaction_id(),
+                ),
+            };
+            let resp = run_tab_ecr_step(db, mgr, &did, ecr_request).await?;
+            if resp.status != TransactionStatus::Approved {
+                return Ok(serde_json::json!({
+                    "success": false,
+                    "error": declined_message(&resp, "Tab capture declined"),
+                    "tab": tab.to_json(),
+                }));
+            }
+            if matches!(step, CaptureStep::Complete(_)) {
+                transaction_ref = resp
+                    .terminal_reference
+                    .or(resp.authorization_code)
+                    .or_else(|| tab.original_transaction_id());
+            }
+        }
+    }
+
+    let mut payment = serde_json::Value::Null;
+    if outstanding_cents > 0 {
+        let mut payment_payload = payload.clone();
+        if let Some(obj) = payment_payload.as_object_mut() {
+            obj.remove("idempotencyKey");
+            obj.remove("idempotency_key");
+            obj.insert("orderId".into(), serde_json::json!(order_id));
+            obj.insert("method".into(), serde_json::json!("card"));
+            obj.insert(
+                "amount".into(),
+                serde_json::json!(Cents::new(outstanding_cents).to_f64_dp2()),
+            );
+            obj.insert(
+                "tipAmount".into(),
+                serde_json::json!(Cents::new(tip_cents.unwrap_or(0)).to_f64_dp2()),
+            );
+            obj.insert("currency".into(), serde_json::json!(currency));
+            obj.insert("transactionRef".into(), serde_json::json!(transaction_ref));
+            obj.insert("staffId".into(), serde_json::json!(actor));
+            let from_terminal = tab.mode == tabs::MODE_PRE_AUTH;
+            obj.insert("terminalApproved".into(), serde_json::json!(from_terminal));
+            obj.insert(
+                "paymentOrigin".into(),
+                serde_json::json!(if from_terminal { "terminal" } else { "manual" }),
+            );
+            obj.insert("terminalDeviceId".into(), serde_json::json!(tab.device_id));
+        }
+        payment = record_payment_and_deduct_stock(db, app, &payment_payload).map_err(|e| {
+            error!(order_id = %order_id, tab_id = %tab.id, error = %e, "Tab captured but the payment was not recorded");
+            PosError::from(format!(
+                "Captured {:.2} on the card but recording the payment failed: {e}",
+                Cents::new(captured_cents).to_f64_dp2()
+            ))
+        })?;
+        if payment.get("success").and_then(serde_json::Value::as_bool) != Some(true) {
+            return Ok(payment);
+        }
+    }
+    let payment_id = value_str(&payment, &["paymentId"]);
+
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        tabs::close(
+            &conn,
+            &tab,
+            captured_cents,
+            payment_id.as_deref(),
+            actor,
+            &Utc::now().to_rfc3339(),
+        )?;
+    }
+    info!(order_id = %order_id, tab_id = %tab.id, captured_cents, "Tab closed");
+    let released_cents = if tab.mode == tabs::MODE_PRE_AUTH {
+        (tab.hold_amount_cents - captured_cents).max(0)
+    } else {
+        0
+    };
+    let _ = app.emit(
+        tabs::UPDATED_EVENT,
+        serde_json::json!({ "action": "close", "orderId": order_id, "tabId": tab.id }),
+    );
+    Ok(serde_json::json!({
+        "success": true,
+        "orderId": order_id,
+        "tabId": tab.id,
+        "paymentId": payment_id,
+        "capturedAmount": Cents::new(captured_cents).to_f64_dp2(),
+        "releasedAmount": Cents::new(released_cents).to_f64_dp2(),
+        "payment": payment,
+    }))
+}
+
+/// Open tabs, oldest first, with their age in minutes. `branchId`
+/// narrows the list to one branch.
+#[tauri::command]
+pub async fn tab_list_open(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, PosError> {
+    let branch_id = arg0
+        .as_ref()
+        .and_then(|payload| value_str(payload, &["branchId", "branch_id"]));
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let open_tabs = tabs::list_open(&conn, branch_id.as_deref(), Utc::now())?;
+    Ok(serde_json::json!({
+        "success": true,
+        "count": open_tabs.len(),
+        "tabs": open_tabs,
+    }))
 }
 
 #[cfg(test)]
@@ -481,24 +1375,36 @@ mod dto_tests {
     }
 
     #[test]
-    fn parse_payment_void_payload_requires_reason() {
+    fn parse_payment_void_payload_requires_reason_code() {
         let err = parse_payment_void_payload(Some(serde_json::json!({
-            "paymentId": "pay-1"
+            "paymentId": "pay-1",
+            "reason": "asdf"
         })))
-        .expect_err("missing reason should fail");
-        assert!(err.contains("Invalid void payment payload") || err.contains("Missing reason"));
+        .expect_err("missing reason code should fail");
+        assert_eq!(err.code(), "VALIDATION");
+        assert!(err.to_string().contains("Missing reasonCode"));
+
+        let parsed = parse_payment_void_payload(Some(serde_json::json!({
+            "paymentId": "pay-1",
+            "reasonCode": "wrong_item"
+        })))
+        .expect("free text is optional");
+        assert_eq!(parsed.reason_code, "wrong_item");
+        assert_eq!(parsed.reason, "");
     }
 
     #[test]
     fn parse_refund_void_payload_supports_aliases() {
         let parsed = parse_refund_void_payload(Some(serde_json::json!({
             "payment_id": "pay-2",
+            "reason_code": "other",
             "reason": "operator correction",
             "staff_id": "staff-1",
             "staff_shift_id": "shift-1"
         })))
         .expect("alias payload should parse");
         assert_eq!(parsed.payment_id, "pay-2");
+        assert_eq!(parsed.reason_code, "other");
         assert_eq!(parsed.reason, "operator correction");
         assert_eq!(parsed.staff_id.as_deref(), Some("staff-1"));
         assert_eq!(parsed.staff_shift_id.as_deref(), Some("shift-1"));
@@ -515,4 +1421,58 @@ mod dto_tests {
         assert_eq!(from_obj, "order-3");
         assert_eq!(from_str, "order-4");
     }
+
+    #[test]
+    fn parse_split_bill_payload_accepts_even_and_item_checks() {
+        let (order_id, spec) = parse_split_bill_payload(Some(serde_json::json!({
+            "orderId": "order-5",
+            "even": 2
+        })))
+        .expect("even split should parse");
+        assert_eq!(order_id, "order-5");
+        assert_eq!(spec, checks::SplitSpec::Even(2));
+
+        let (_, spec) = parse_split_bill_payload(Some(serde_json::json!({
+            "order_id": "order-5",
+            "checks": [[1], [0, 2]]
+        })))
+        .expect("item split should parse");
+        assert_eq!(spec, checks::SplitSpec::Items(vec![vec![1], vec![0, 2]]));
+        assert!(
+            parse_split_bill_payload(Some(serde_json::json!({ "orderId": "order-5" }))).is_err()
+        );
+    }
+
+    #[test]
+    fn parse_tab_payloads_validate_hold_reference_and_tip() {
+        let parsed = parse_tab_open_payload(&serde_json::json!({
+            "order_id": " order-6 ",
+            "holdAmount": 80.0,
+            "cardReference": "  ",
+        }))
+        .expect("tab open should parse");
+        assert_eq!(parsed.order_id, "order-6");
+        assert_eq!(parsed.hold_amount, Some(80.0));
+        assert!(parsed.card_reference.is_none());
+
+        for bad in [
+            serde_json::json!({ "orderId": "order-6", "holdAmount": 0 }),
+            serde_json::json!({ "orderId": "order-6", "manual": true }),
+            serde_json::json!({ "orderId": "" }),
+        ] {
+            assert!(parse_tab_open_payload(&bad).is_err(), "{bad}");
+        }
+
+        let parsed = parse_tab_close_payload(&serde_json::json!({
+            "orderId": "order-6",
+            "tip_amount": 2.5,
+        }))
+        .expect("tab close should parse");
+        assert_eq!(parsed.tip_amount, Some(2.5));
+        assert!(parse_tab_close_payload(&serde_json::json!({
+            "orderId": "order-6",
+            "tipAmount": -1,
+        }))
+        .is_err());
+    }
 }
@@ -0,0 +1,68 @@
+use serde_json::{json, Value};
+
+use crate::pricing_rules::{self, PricingRule};
+use crate::{auth, db};
+
+/// `{ rules: [...] }` or a bare array. Replaces the terminal's own rules;
+/// rules synced from admin are read-only here.
+fn parse_set_payload(arg0: Option<Value>) -> Result<Vec<PricingRule>, String> {
+    let payload = arg0.ok_or("Missing pricing rules payload")?;
+    let rules = match payload.get("rules") {
+        Some(rules) => rules.clone(),
+        None if payload.is_array() => payload,
+        None => return Err("Missing rules".into()),
+    };
+    serde_json::from_value(rules).map_err(|e| format!("Invalid pricing rules: {e}"))
+}
+
+#[tauri::command]
+pub async fn pricing_rules_get(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(json!({
+        "success": true,
+        "rules": pricing_rules::list(&conn)?,
+    }))
+}
+
+#[tauri::command]
+pub async fn pricing_rules_set(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<Value, auth::GuardedCommandError> {
+    auth::authorize_privileged_action(
+        auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let rules = parse_set_payload(arg0)?;
+    let actor = auth::current_staff_id(&auth_state);
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    pricing_rules::set_local(&conn, rules, actor.as_deref())?;
+    Ok(json!({
+        "success": true,
+        "rules": pricing_rules::list(&conn)?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_payload_accepts_wrapped_and_bare_arrays() {
+        let rule = json!({
+            "id": "hh", "name": "Happy Hour", "categoryIds": ["coffee"],
+            "adjustmentType": "percent", "adjustmentValue": 20,
+        });
+        assert_eq!(
+            parse_set_payload(Some(json!({ "rules": [rule.clone()] })))
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(parse_set_payload(Some(json!([rule]))).unwrap().len(), 1);
+        assert!(parse_set_payload(Some(json!({}))).is_err());
+        assert!(parse_set_payload(None).is_err());
+    }
+}
@@ -7,8 +7,9 @@ use tauri::{Emitter, Manager};
 use tracing::{info, warn};
 
 use crate::{
-    auth, db, drawer, escpos, payload_arg0_as_string, print, printers, read_local_json_array,
-    receipt_renderer, resolve_order_id, value_str, write_local_json,
+    auth, courses, db, drawer, escpos, labels, payload_arg0_as_string, print, print_schedule,
+    printer_watchdog, printers, read_local_json_array, receipt_renderer, resolve_order_id,
+    value_i64, value_str, write_local_json,
 };
 
 // -- Print -------------------------------------------------------------------
@@ -25,6 +26,21 @@ struct LabelPrintArgs {
     printer_id: Option<String>,
 }
 
+#[derive(Debug)]
+struct OrderLabelPrintArgs {
+    order_id: String,
+    options: labels::LabelOptions,
+    printer_profile_id: Option<String>,
+}
+
+#[derive(Debug)]
+struct CourseFireArgs {
+    order_id: String,
+    course: u32,
+    reprint: bool,
+    printer_profile_id: Option<String>,
+}
+
 #[derive(Debug)]
 struct LabelPrintBatchArgs {
     items: serde_json::Value,
@@ -111,6 +127,107 @@ fn parse_printer_profile_id_payload(
         })
 }
 
+/// Per-print language override (`el`, `en`, ...), stored in the job payload.
+fn parse_print_language_payload(
+    arg0: Option<&serde_json::Value>,
+    arg1: Option<&serde_json::Value>,
+) -> Option<String> {
+    arg0.and_then(|value| value_str(value, &["language", "lang"]))
+        .or_else(|| arg1.and_then(|value| value_str(value, &["language", "lang"])))
+}
+
+/// Add the language override to a job payload, creating one if needed.
+fn with_print_language(
+    payload: Option<serde_json::Value>,
+    language: Option<String>,
+) -> Option<serde_json::Value> {
+    let Some(language) = language else {
+        return payload;
+    };
+    let mut payload = payload.unwrap_or_else(|| serde_json::json!({}));
+    if let Some(object) = payload.as_object_mut() {
+        object.insert("language".to_string(), serde_json::json!(language));
+    }
+    Some(payload)
+}
+
+fn parse_course_fire_payload(arg0: Option<serde_json::Value>) -> Result<CourseFireArgs, String> {
+    let payload = arg0.ok_or("Missing course fire payload")?;
+    let printer_profile_id = parse_printer_profile_id_payload(Some(&payload), None);
+    let course_keys = ["course", "courseNumber", "course_number"];
+    let course = value_i64(&payload, &course_keys)
+        .or_else(|| value_str(&payload, &course_keys).and_then(|raw| raw.parse().ok()))
+        .ok_or("Missing course")?;
+    if !(1..=i64::from(crate::sync::order_schema::MAX_COURSE)).contains(&course) {
+        return Err(format!(
+            "Course must be between 1 and {}",
+            crate::sync::order_schema::MAX_COURSE
+        ));
+    }
+    let reprint = payload
+        .get("reprint")
+        .and_then(serde_json::Value::as_bool)
+        .unwrap_or(false);
+    let order_id = parse_order_id_payload(Some(payload))?;
+    Ok(CourseFireArgs {
+        order_id,
+        course: course as u32,
+        reprint,
+        printer_profile_id,
+    })
+}
+
+/// `print_label` accepts `(orderId, copies)` or a single object; option
+/// keys may sit on either argument, `arg1` winning.
+fn parse_order_label_payload(
+    arg0: Option<serde_json::Value>,
+    arg1: Option<serde_json::Value>,
+) -> Result<OrderLabelPrintArgs, String> {
+    let printer_profile_id = parse_printer_profile_id_payload(arg0.as_ref(), arg1.as_ref());
+    let sources: Vec<&serde_json::Value> = [arg1.as_ref(), arg0.as_ref()]
+        .into_iter()
+        .flatten()
+        .filter(|value| value.is_object())
+        .collect();
+    let count = |keys: &[&str]| {
+        sources
+            .iter()
+            .find_map(|value| value_i64(value, keys))
+            .map(|n| n.clamp(0, u32::MAX as i64) as u32)
+    };
+    let text = |keys: &[&str]| sources.iter().find_map(|value| value_str(value, keys));
+
+    let copies = arg1
+        .as_ref()
+        .and_then(serde_json::Value::as_i64)
+        .map(|n| n.clamp(0, u32::MAX as i64) as u32)
+        .or_else(|| count(&["copies"]))
+        .unwrap_or(1);
+    if copies == 0 || copies > labels::MAX_COPIES {
+        return Err(format!(
+            "copies must be between 1 and {}",
+            labels::MAX_COPIES
+        ));
+    }
+    let bags = count(&["bags", "bagCount", "bag_count"]);
+    if bags.is_some_and(|bags| bags == 0 || bags > labels::MAX_BAGS) {
+        return Err(format!("bags must be between 1 and {}", labels::MAX_BAGS));
+    }
+    let options = labels::LabelOptions {
+        copies,
+        bags,
+        items_per_bag: count(&["itemsPerBag", "items_per_bag"]).filter(|n| *n > 0),
+        allergen_note: text(&["allergenNote", "allergen_note"]),
+        pickup_time: text(&["pickupTime", "pickup_time"]),
+    };
+    let order_id = parse_order_id_payload(arg0)?;
+    Ok(OrderLabelPrintArgs {
+        order_id,
+        options,
+        printer_profile_id,
+    })
+}
+
 fn parse_profile_id_payload(arg0: Option<serde_json::Value>) -> Result<String, String> {
     payload_arg0_as_string(arg0, &["profileId", "profile_id", "id"])
         .ok_or("Missing profileId".into())
@@ -614,6 +731,7 @@ pub async fn payment_print_receipt(
 ) -> Result<serde_json::Value, String> {
     let entity_type = parse_requested_receipt_entity_type(arg0.as_ref(), arg1.as_ref());
     let printer_profile_id = parse_printer_profile_id_payload(arg0.as_ref(), arg1.as_ref());
+    let language = parse_print_language_payload(arg0.as_ref(), arg1.as_ref());
     let order_id_raw = parse_order_id_payload(arg0)?;
     // Wave 11 Item 8: scope the `MutexGuard` to a block so the borrow
     // checker can prove the (non-Send) guard is dropped before the
@@ -629,8 +747,14 @@ pub async fn payment_print_receipt(
         return Ok(serde_json::json!({ "success": true, "skipped": true }));
     }
 
-    let enqueue_result =
-        print::enqueue_print_job(&db, entity_type, &order_id, printer_profile_id.as_deref())?;
+    let job_payload = with_print_language(None, language);
+    let enqueue_result = print::enqueue_print_job_with_payload(
+        &db,
+        entity_type,
+        &order_id,
+        printer_profile_id.as_deref(),
+        job_payload.as_ref(),
+    )?;
 
     // Process the job immediately instead of waiting for the background worker.
     // Wave 11 Item 8 deferred follow-up: offload to `spawn_blocking` so the
@@ -649,23 +773,52 @@ pub async fn payment_print_receipt(
     Ok(enqueue_result)
 }
 
+/// Kitchen ticket for an order. On a course-aware order only the unfired
+/// courses up to the first held one are printed, and they are marked fired.
 #[tauri::command]
 pub async fn kitchen_print_ticket(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
     app: tauri::AppHandle,
 ) -> Result<serde_json::Value, String> {
     let printer_profile_id = parse_printer_profile_id_payload(arg0.as_ref(), None);
+    let language = parse_print_language_payload(arg0.as_ref(), None);
     let order_id = parse_order_id_payload(arg0)?;
     if !crate::print::is_print_action_enabled(&db, "kitchen_ticket") {
         return Ok(serde_json::json!({ "success": true, "skipped": true }));
     }
-    let enqueue_result = print::enqueue_print_job(
+    let (order_id, ticket_courses) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let order_id = resolve_order_id(&conn, &order_id).unwrap_or(order_id);
+        let ticket_courses = courses::default_ticket_courses(&conn, &order_id).unwrap_or(None);
+        (order_id, ticket_courses)
+    };
+    if ticket_courses.as_ref().is_some_and(Vec::is_empty) {
+        return Ok(serde_json::json!({
+            "success": true,
+            "skipped": true,
+            "reason": "no_unfired_course",
+        }));
+    }
+    let ticket_payload = with_print_language(
+        ticket_courses
+            .as_deref()
+            .map(|fired| courses::ticket_payload(fired, false)),
+        language,
+    );
+    let enqueue_result = print::enqueue_print_job_with_payload(
         &db,
         "kitchen_ticket",
         &order_id,
         printer_profile_id.as_deref(),
+        ticket_payload.as_ref(),
     )?;
+    if let Some(fired) = ticket_courses.as_deref() {
+        let actor = auth::current_staff_id(&auth_state);
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        courses::record_fire(&conn, &order_id, fired, actor.as_deref(), false)?;
+    }
 
     // Process the job immediately instead of waiting for the background worker.
     // Wave 11 Item 8 deferred follow-up: offload to `spawn_blocking` so the
@@ -684,6 +837,180 @@ pub async fn kitchen_print_ticket(
     Ok(enqueue_result)
 }
 
+/// Fire one course: mark it fired and print a kitchen ticket with only that
+/// course's items under a "FIRE: MAINS" style header. A course that was
+/// already fired needs `reprint: true`.
+#[tauri::command]
+pub async fn order_fire_course(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let args = parse_course_fire_payload(arg0)?;
+    let order_id = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let order_id = resolve_order_id(&conn, &args.order_id).ok_or("Order not found")?;
+        courses::check_fire(&conn, &order_id, args.course, args.reprint)?;
+        order_id
+    };
+
+    let print_enabled = crate::print::is_print_action_enabled(&db, "kitchen_ticket");
+    let enqueue_result = if print_enabled {
+        Some(print::enqueue_print_job_with_payload(
+            &db,
+            "kitchen_ticket",
+            &order_id,
+            args.printer_profile_id.as_deref(),
+            Some(&courses::ticket_payload(&[args.course], args.reprint)),
+        )?)
+    } else {
+        None
+    };
+
+    let actor = auth::current_staff_id(&auth_state);
+    let status = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        courses::record_fire(
+            &conn,
+            &order_id,
+            &[args.course],
+            actor.as_deref(),
+            args.reprint,
+        )?;
+        courses::status_json(&conn, &order_id)?
+    };
+
+    if print_enabled {
+        let data_dir = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("app data dir: {e}"))?;
+        print::spawn_pending_job_processing(
+            app.clone(),
+            data_dir,
+            format!("course {} fire ticket for order {order_id}", args.course),
+        );
+    }
+
+    Ok(serde_json::json!({
+        "success": true,
+        "orderId": order_id,
+        "course": args.course,
+        "header": courses::fire_header(&[args.course]),
+        "reprint": args.reprint,
+        "printed": print_enabled,
+        "jobId": enqueue_result.as_ref().and_then(|result| result.get("jobId")).cloned(),
+        "courses": status["courses"].clone(),
+    }))
+}
+
+#[tauri::command]
+pub async fn order_get_course_status(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let order_id = parse_order_id_payload(arg0)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let order_id = resolve_order_id(&conn, &order_id).ok_or("Order not found")?;
+    courses::status_json(&conn, &order_id)
+}
+
+fn preview_order_document(
+    db: &db::DbState,
+    entity_type: &str,
+    order_id_raw: &str,
+    printer_profile_id: Option<&str>,
+) -> Result<serde_json::Value, String> {
+    let order_id = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        resolve_order_id(&conn, order_id_raw).ok_or("Order not found")?
+    };
+    print::preview_document(db, entity_type, &order_id, printer_profile_id)
+}
+
+/// Render the customer receipt for an order as text and HTML without
+/// printing it.
+#[tauri::command]
+pub async fn print_preview_receipt(
+    arg0: Option<serde_json::Value>,
+    arg1: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let entity_type = parse_requested_receipt_entity_type(arg0.as_ref(), arg1.as_ref());
+    let printer_profile_id = parse_printer_profile_id_payload(arg0.as_ref(), arg1.as_ref());
+    let order_id = parse_order_id_payload(arg0)?;
+    db.run_blocking(move |db| {
+        preview_order_document(db, entity_type, &order_id, printer_profile_id.as_deref())
+    })
+    .await
+}
+
+/// Render the kitchen ticket for an order as text and HTML without
+/// printing it.
+#[tauri::command]
+pub async fn print_preview_kitchen_ticket(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let printer_profile_id = parse_printer_profile_id_payload(arg0.as_ref(), None);
+    let order_id = parse_order_id_payload(arg0)?;
+    db.run_blocking(move |db| {
+        preview_order_document(
+            db,
+            "kitchen_ticket",
+            &order_id,
+            printer_profile_id.as_deref(),
+        )
+    })
+    .await
+}
+
+/// Queue bag labels for an order on the `label` printer profile.
+#[tauri::command]
+pub async fn print_label(
+    arg0: Option<serde_json::Value>,
+    arg1: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let parsed = parse_order_label_payload(arg0, arg1)?;
+    let order_id = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        resolve_order_id(&conn, &parsed.order_id).ok_or("Order not found")?
+    };
+    if printers::resolve_printer_profile_for_type(
+        &db,
+        parsed.printer_profile_id.as_deref(),
+        labels::LABEL_PROFILE_TYPE,
+    )?
+    .is_none()
+    {
+        return Err("No label printer profile configured".into());
+    }
+
+    let payload = serde_json::to_value(&parsed.options).map_err(|e| e.to_string())?;
+    let enqueue_result = print::enqueue_print_job_with_payload(
+        &db,
+        labels::LABEL_ENTITY_TYPE,
+        &order_id,
+        parsed.printer_profile_id.as_deref(),
+        Some(&payload),
+    )?;
+
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("app data dir: {e}"))?;
+    print::spawn_pending_job_processing(
+        app.clone(),
+        data_dir,
+        format!("bag labels for order {order_id}"),
+    );
+
+    Ok(enqueue_result)
+}
+
 #[tauri::command]
 pub async fn print_list_jobs(
     arg0: Option<serde_json::Value>,
@@ -713,7 +1040,11 @@ pub async fn print_get_receipt_file(
         .path()
         .app_data_dir()
         .map_err(|e| format!("app data dir: {e}"))?;
-    let path = print::generate_receipt_file(&db, &order_id, &data_dir)?;
+    // Rendering and writing the receipt HTML is file I/O; keep it off the
+    // async executor.
+    let path = db
+        .run_blocking(move |db| print::generate_receipt_file(db, &order_id, &data_dir))
+        .await?;
     Ok(serde_json::json!({
         "success": true,
         "path": path,
@@ -785,9 +1116,15 @@ pub async fn printer_set_default_profile(
 
 #[tauri::command]
 pub async fn printer_get_default_profile(
+    arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
-    printers::get_default_printer_profile(&db)
+    let profile_type = payload_arg0_as_string(arg0, &["profileType", "profile_type", "type"])
+        .unwrap_or_else(|| "receipt".to_string());
+    if !printers::PROFILE_TYPES.contains(&profile_type.as_str()) {
+        return Err(format!("Invalid profile_type: {profile_type}"));
+    }
+    printers::get_default_printer_profile_for_type(&db, &profile_type)
 }
 
 #[tauri::command]
@@ -872,6 +1209,7 @@ fn profile_to_electron_format(profile: &serde_json::Value) -> serde_json::Value
         "headerEmphasis": value_str(profile, &["headerEmphasis", "header_emphasis"]).unwrap_or_else(|| "strong".to_string()),
         "escposCodePage": profile.get("escposCodePage").or_else(|| profile.get("escpos_code_page")).and_then(|v| v.as_i64()),
         "role": value_str(profile, &["role"]).unwrap_or_else(|| "receipt".to_string()),
+        "profileType": value_str(profile, &["profileType", "profile_type"]).unwrap_or_else(|| "receipt".to_string()),
         "isDefault": is_default,
         "fallbackPrinterId": value_str(profile, &["fallbackPrinterId", "fallback_printer_id"]),
         "enabled": enabled,
@@ -960,6 +1298,7 @@ fn electron_to_profile_input(id: Option<String>, payload: serde_json::Value) ->
     // Direct pass-through fields
     let pass_fields = [
         ("role", "role"),
+        ("profileType", "profileType"),
         ("characterSet", "characterSet"),
         ("greekRenderMode", "greekRenderMode"),
         ("receiptTemplate", "receiptTemplate"),
@@ -1942,40 +2281,75 @@ fn collect_printer_status_map(
     if let Some(arr) = profiles.as_array() {
         for profile in arr {
             let printer_id = value_str(profile, &["id"]).unwrap_or_default();
-            let (target, connected, state) = resolve_profile_connection_state(profile);
-            let capabilities = printers::read_capability_snapshot(profile);
-
-            let queue_len: i64 = conn
-                .query_row(
-                    "SELECT COUNT(*) FROM print_jobs WHERE status IN ('pending', 'printing') AND printer_profile_id = ?1",
-                    rusqlite::params![printer_id],
-                    |row| row.get(0),
-                )
-                .unwrap_or(0);
-
-            status_map.insert(
-                printer_id.clone(),
-                serde_json::json!({
-                    "printerId": printer_id,
-                    "state": state,
-                    "connected": connected,
-                    "transportReachable": connected,
-                    "verificationStatus": printers::capability_verification_status(profile),
-                    "resolvedTransport": target.as_ref().map(resolved_transport_name),
-                    "resolvedAddress": target.as_ref().map(|value| value.label()),
-                    "supportsLogo": capabilities.supports_logo,
-                    "supportsCut": capabilities.supports_cut,
-                    "lastVerifiedAt": capabilities.last_verified_at,
-                    "queueLength": queue_len,
-                    "lastSeen": chrono::Utc::now().to_rfc3339()
-                }),
-            );
+            status_map.insert(printer_id.clone(), printer_status_entry(&conn, profile));
         }
     }
 
     Ok(status_map)
 }
 
+/// Connection probe, capabilities and queue length for one profile, with
+/// the watchdog's last hardware reading folded in.
+fn printer_status_entry(
+    conn: &rusqlite::Connection,
+    profile: &serde_json::Value,
+) -> serde_json::Value {
+    let printer_id = value_str(profile, &["id"]).unwrap_or_default();
+    let (target, connected, state) = resolve_profile_connection_state(profile);
+    let capabilities = printers::read_capability_snapshot(profile);
+
+    let queue_len: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM print_jobs WHERE status IN ('pending', 'printing', 'deferred') AND printer_profile_id = ?1",
+            rusqlite::params![printer_id],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+
+    let mut entry = serde_json::json!({
+        "printerId": printer_id,
+        "state": state,
+        "connected": connected,
+        "transportReachable": connected,
+        "verificationStatus": printers::capability_verification_status(profile),
+        "resolvedTransport": target.as_ref().map(resolved_transport_name),
+        "resolvedAddress": target.as_ref().map(|value| value.label()),
+        "supportsLogo": capabilities.supports_logo,
+        "supportsCut": capabilities.supports_cut,
+        "lastVerifiedAt": capabilities.last_verified_at,
+        "queueLength": queue_len,
+        "lastSeen": chrono::Utc::now().to_rfc3339()
+    });
+    apply_hardware_status(&mut entry, &printer_id, connected);
+    entry
+}
+
+/// Paper and cover state from the watchdog. A reachable printer that is
+/// out of paper or open is reported as `error` so the settings screen
+/// stops showing it as ready.
+fn apply_hardware_status(entry: &mut serde_json::Value, printer_id: &str, connected: bool) {
+    let health = printer_watchdog::last_status(printer_id);
+    if let Some(health) = &health {
+        let error_code = health.error_code().filter(|code| *code != "offline");
+        if connected {
+            if let Some(code) = error_code {
+                entry["state"] = serde_json::json!("error");
+                entry["errorCode"] = serde_json::json!(code);
+                entry["errorMessage"] = serde_json::json!(match code {
+                    "paper_out" => "Printer is out of paper",
+                    _ => "Printer cover is open",
+                });
+            }
+        }
+        entry["paperOut"] = serde_json::json!(health.paper_out);
+        entry["paperNearEnd"] = serde_json::json!(health.paper_near_end);
+        entry["coverOpen"] = serde_json::json!(health.cover_open);
+    }
+    entry["hardware"] = health
+        .map(|health| health.to_json())
+        .unwrap_or(serde_json::Value::Null);
+}
+
 fn hash_status_map(status_map: &serde_json::Map<String, serde_json::Value>) -> u64 {
     let mut hasher = DefaultHasher::new();
     // JSON object key order is deterministic for Map insertion sequence,
@@ -2031,6 +2405,92 @@ pub fn start_printer_status_monitor(
     );
 }
 
+/// Poll printer hardware status (paper, cover) while
+/// `printing.status_poll_enabled` is on and emit `printer_status_changed`
+/// for every profile whose state changed. The setting and interval are
+/// re-read each round so changes apply without a restart.
+pub fn start_printer_watchdog(
+    app: tauri::AppHandle,
+    db: Arc<db::DbState>,
+    cancel: tokio_util::sync::CancellationToken,
+) {
+    tauri::async_runtime::spawn(async move {
+        info!("Printer watchdog started");
+        loop {
+            let (enabled, interval) = match db.conn.lock() {
+                Ok(conn) => (
+                    printer_watchdog::poll_enabled(&conn),
+                    printer_watchdog::poll_interval(&conn),
+                ),
+                Err(_) => (
+                    false,
+                    std::time::Duration::from_secs(printer_watchdog::DEFAULT_POLL_INTERVAL_SECS),
+                ),
+            };
+
+            if enabled {
+                let db_for_poll = Arc::clone(&db);
+                let polled = tokio::task::spawn_blocking(move || {
+                    let transitions = printer_watchdog::poll_once(
+                        db_for_poll.as_ref(),
+                        &printer_watchdog::HardwareTransport,
+                    )?;
+                    let mut events = Vec::new();
+                    for transition in transitions {
+                        let Ok(profile) = printers::get_printer_profile(
+                            db_for_poll.as_ref(),
+                            &transition.profile_id,
+                        ) else {
+                            continue;
+                        };
+                        let status = match db_for_poll.conn.lock() {
+                            Ok(conn) => printer_status_entry(&conn, &profile),
+                            Err(_) => continue,
+                        };
+                        events.push((transition, status));
+                    }
+                    Ok::<_, String>(events)
+                })
+                .await;
+                match polled {
+                    Ok(Ok(events)) => {
+                        for (transition, status) in events {
+                            info!(
+                                printer_profile_id = %transition.profile_id,
+                                online = transition.current.online,
+                                paper_out = transition.current.paper_out,
+                                cover_open = transition.current.cover_open,
+                                "Printer hardware status changed"
+                            );
+                            let _ = app.emit(
+                                "printer_status_changed",
+                                serde_json::json!({
+                                    "printerId": transition.profile_id,
+                                    "status": status,
+                                    "previous": transition.previous.map(|health| health.to_json()),
+                                    "updatedAt": chrono::Utc::now().to_rfc3339()
+                                }),
+                            );
+                        }
+                    }
+                    Ok(Err(error)) => warn!(error = %error, "Printer watchdog poll failed"),
+                    Err(error) => warn!(error = %error, "Printer watchdog poll panicked"),
+                }
+            } else if let Err(error) = printer_watchdog::clear(db.as_ref()) {
+                warn!(error = %error, "Printer watchdog reset failed");
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = cancel.cancelled() => {
+                    tracing::info!("Printer watchdog cancelled");
+                    break;
+                }
+            }
+        }
+    });
+}
+
 #[cfg(target_os = "windows")]
 fn discover_bluetooth_printers_native(
     configured: &ConfiguredPrinterLookup,
@@ -2404,34 +2864,12 @@ pub async fn printer_get_status(
     let printer_id = parse_printer_id_payload(arg0)?;
     let profile = printers::get_printer_profile(&db, &printer_id)?;
     let printer_name = value_str(&profile, &["printerName", "printer_name"]).unwrap_or_default();
-    let (target, connected, state) = resolve_profile_connection_state(&profile);
-    let capabilities = printers::read_capability_snapshot(&profile);
 
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let queue_len: i64 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM print_jobs WHERE status IN ('pending', 'printing') AND printer_profile_id = ?1",
-            rusqlite::params![printer_id],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
-
-    Ok(serde_json::json!({
-        "success": true,
-        "printerId": printer_id,
-        "state": state,
-        "connected": connected,
-        "transportReachable": connected,
-        "verificationStatus": printers::capability_verification_status(&profile),
-        "resolvedTransport": target.as_ref().map(resolved_transport_name),
-        "resolvedAddress": target.as_ref().map(|value| value.label()),
-        "supportsLogo": capabilities.supports_logo,
-        "supportsCut": capabilities.supports_cut,
-        "lastVerifiedAt": capabilities.last_verified_at,
-        "queueLength": queue_len,
-        "printerName": printer_name,
-        "lastSeen": chrono::Utc::now().to_rfc3339()
-    }))
+    let mut status = printer_status_entry(&conn, &profile);
+    status["success"] = serde_json::json!(true);
+    status["printerName"] = serde_json::json!(printer_name);
+    Ok(status)
 }
 
 #[tauri::command]
@@ -2459,11 +2897,14 @@ pub async fn printer_submit_job(
         "order_receipt" | "kitchen_ticket" | "z_report" | "shift_checkout"
     );
     if allowed {
-        return print::enqueue_print_job(
+        let not_before = print_schedule::from_payload(&payload, Utc::now())?;
+        return print::enqueue_print_job_at(
             &db,
             &entity_type,
             &entity_id,
             printer_profile_id.as_deref(),
+            None,
+            not_before,
         );
     }
 
@@ -2492,6 +2933,40 @@ pub async fn printer_cancel_job(
     print::cancel_print_job(&db, &job_id)
 }
 
+/// Cancel a pending or deferred print job before it reaches the printer.
+#[tauri::command]
+pub async fn print_cancel_job(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<serde_json::Value, String> {
+    let reason = arg0
+        .as_ref()
+        .and_then(|payload| value_str(payload, &["reason", "cancelReason", "cancel_reason"]));
+    let job_id = parse_job_id_payload(arg0)?;
+    let actor = auth::current_staff_id(&auth_state);
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let result = print_schedule::cancel(
+        &conn,
+        &job_id,
+        actor.as_deref(),
+        reason.as_deref(),
+        Utc::now(),
+    )?;
+    info!(job_id = %job_id, actor = ?actor, "Print job cancelled");
+    Ok(result)
+}
+
+/// Print jobs waiting for their `not_before` time, soonest first.
+#[tauri::command]
+pub async fn print_list_scheduled(
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let jobs = print_schedule::list_scheduled(&conn, Utc::now())?;
+    Ok(serde_json::json!({ "success": true, "jobs": jobs }))
+}
+
 #[tauri::command]
 pub async fn printer_pause_queue(
     arg0: Option<serde_json::Value>,
@@ -3450,6 +3925,8 @@ fn build_sample_receipt_doc() -> receipt_renderer::OrderReceiptDoc {
         }],
         order_notes: vec![],
         adjustments: vec![],
+        tax_breakdown: vec![],
+        fiscal_document_numbers: vec![],
         masked_card: None,
         customer_phone: None,
         delivery_address: None,
@@ -3462,6 +3939,10 @@ fn build_sample_receipt_doc() -> receipt_renderer::OrderReceiptDoc {
         delivery_slip_mode: Default::default(),
         status_label: None,
         cancellation_reason: None,
+        reprint_count: 0,
+        reprinted_at: None,
+        merchant_copy: false,
+        return_of_order_number: None,
     }
 }
 
@@ -3717,16 +4198,44 @@ mod dto_tests {
     fn test_db() -> db::DbState {
         let conn = Connection::open_in_memory().expect("open in-memory db");
         db::run_migrations_for_test(&conn);
-        db::DbState {
-            conn: Mutex::new(conn),
-            db_path: std::env::temp_dir().join("receipt-sample-preview-tests.sqlite"),
-        }
+        db::DbState::new(
+            conn,
+            std::env::temp_dir().join("receipt-sample-preview-tests.sqlite"),
+        )
     }
 
     fn preview_profile_from_frontend(payload: serde_json::Value) -> serde_json::Value {
         normalize_draft_profile_payload(payload).expect("frontend profile payload should normalize")
     }
 
+    #[test]
+    fn parse_course_fire_payload_validates_course_and_defaults_reprint() {
+        let args = parse_course_fire_payload(Some(serde_json::json!({
+            "orderId": "order-1",
+            "course": 2,
+        })))
+        .expect("course fire payload");
+        assert_eq!(args.order_id, "order-1");
+        assert_eq!(args.course, 2);
+        assert!(!args.reprint);
+
+        let reprint = parse_course_fire_payload(Some(serde_json::json!({
+            "orderId": "order-1",
+            "course": "3",
+            "reprint": true,
+        })))
+        .expect("reprint payload");
+        assert!(reprint.reprint);
+        assert_eq!(reprint.course, 3);
+
+        assert!(parse_course_fire_payload(Some(serde_json::json!({
+            "orderId": "order-1",
+            "course": 0,
+        })))
+        .is_err());
+        assert!(parse_course_fire_payload(Some(serde_json::json!({ "course": 2 }))).is_err());
+    }
+
     #[test]
     fn parse_order_id_payload_accepts_string_and_object() {
         let from_string = parse_order_id_payload(Some(serde_json::json!("order-1")))
@@ -3782,6 +4291,21 @@ mod dto_tests {
         assert_eq!(profile_id.as_deref(), Some("receipt-profile-1"));
     }
 
+    #[test]
+    fn print_language_override_is_merged_into_job_payload() {
+        let language = parse_print_language_payload(
+            Some(&serde_json::json!({ "orderId": "order-1", "language": "el" })),
+            None,
+        );
+        assert_eq!(language.as_deref(), Some("el"));
+        let payload = with_print_language(Some(serde_json::json!({ "courses": [2] })), language);
+        assert_eq!(
+            payload,
+            Some(serde_json::json!({ "courses": [2], "language": "el" }))
+        );
+        assert_eq!(with_print_language(None, None), None);
+    }
+
     #[test]
     fn parse_print_list_jobs_status_accepts_string_and_object() {
         let from_string = parse_print_list_jobs_status(Some(serde_json::json!("pending")));
@@ -4121,6 +4645,50 @@ mod dto_tests {
         assert_eq!(parsed.printer_id.as_deref(), Some("printer-7"));
     }
 
+    #[test]
+    fn parse_order_label_payload_supports_tuple_and_object_shapes() {
+        let tuple = parse_order_label_payload(
+            Some(serde_json::json!("order-1")),
+            Some(serde_json::json!(3)),
+        )
+        .unwrap();
+        assert_eq!(tuple.order_id, "order-1");
+        assert_eq!(tuple.options.copies, 3);
+        assert_eq!(tuple.options.bags, None);
+
+        let object = parse_order_label_payload(
+            Some(serde_json::json!({
+                "orderId": "order-2",
+                "copies": 2,
+                "itemsPerBag": 4,
+                "allergenNote": "Contains nuts",
+                "printerProfileId": "label-1"
+            })),
+            Some(serde_json::json!({ "bags": 2 })),
+        )
+        .unwrap();
+        assert_eq!(object.order_id, "order-2");
+        assert_eq!(object.options.copies, 2);
+        assert_eq!(object.options.bags, Some(2));
+        assert_eq!(object.options.items_per_bag, Some(4));
+        assert_eq!(
+            object.options.allergen_note.as_deref(),
+            Some("Contains nuts")
+        );
+        assert_eq!(object.printer_profile_id.as_deref(), Some("label-1"));
+
+        assert!(parse_order_label_payload(
+            Some(serde_json::json!("order-1")),
+            Some(serde_json::json!(0))
+        )
+        .is_err());
+        assert!(parse_order_label_payload(
+            Some(serde_json::json!({ "orderId": "o", "bags": 99 })),
+            None
+        )
+        .is_err());
+    }
+
     #[test]
     fn receipt_sample_preview_prefers_profile_draft_over_saved_default_profile() {
         let db = test_db();
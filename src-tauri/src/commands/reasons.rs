@@ -0,0 +1,86 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::reasons::{self, ReasonAction, ReasonCode};
+use crate::{auth, db};
+
+#[derive(Debug, Deserialize)]
+struct ReasonsSetPayload {
+    action: String,
+    #[serde(alias = "reasons")]
+    codes: Vec<ReasonCode>,
+}
+
+fn parse_action(raw: &str) -> Result<ReasonAction, String> {
+    ReasonAction::parse(raw).ok_or_else(|| format!("Unknown reason action: {raw}"))
+}
+
+fn parse_set_payload(arg0: Option<Value>) -> Result<(ReasonAction, Vec<ReasonCode>), String> {
+    let payload = arg0.ok_or("Missing reasons payload")?;
+    let parsed: ReasonsSetPayload =
+        serde_json::from_value(payload).map_err(|e| format!("Invalid reasons payload: {e}"))?;
+    Ok((parse_action(&parsed.action)?, parsed.codes))
+}
+
+/// Reason-code lists. With an action, only that list is returned.
+#[tauri::command]
+pub async fn reasons_get(
+    arg0: Option<String>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    match arg0.as_deref().map(str::trim).filter(|raw| !raw.is_empty()) {
+        Some(raw) => {
+            let action = parse_action(raw)?;
+            Ok(json!({
+                "success": true,
+                "action": action.as_str(),
+                "codes": reasons::list(&conn, action),
+            }))
+        }
+        None => Ok(json!({
+            "success": true,
+            "reasons": reasons::all_to_json(&conn),
+        })),
+    }
+}
+
+#[tauri::command]
+pub async fn reasons_set(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<Value, auth::GuardedCommandError> {
+    auth::authorize_privileged_action(
+        auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let (action, codes) = parse_set_payload(arg0)?;
+    let actor = auth::current_staff_id(&auth_state);
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let saved = reasons::set(&conn, action, codes, actor.as_deref())?;
+    Ok(json!({
+        "success": true,
+        "action": action.as_str(),
+        "codes": saved,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_payload_requires_known_action_and_defaults_active() {
+        let (action, codes) = parse_set_payload(Some(json!({
+            "action": "order-decline",
+            "reasons": [{ "code": "rain", "label": "Bad weather" }],
+        })))
+        .expect("payload");
+        assert_eq!(action, ReasonAction::OrderDecline);
+        assert!(codes[0].active);
+        assert!(parse_set_payload(Some(json!({ "action": "tip", "codes": [] }))).is_err());
+        assert!(parse_set_payload(None).is_err());
+    }
+}
@@ -1527,10 +1527,7 @@ mod tests {
         )
         .expect("pragma setup");
         db::run_migrations_for_test(&conn);
-        db::DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        }
+        db::DbState::new(conn, std::path::PathBuf::from(":memory:"))
     }
 
     #[test]
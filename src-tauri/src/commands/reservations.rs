@@ -0,0 +1,182 @@
+use chrono::Utc;
+use serde_json::{json, Value};
+use tauri::Emitter;
+
+use crate::{db, reservations, value_i64, value_str};
+
+use super::branch_data;
+
+fn emit_reservation_updated(app: &tauri::AppHandle, reservation: &Value, queue_id: &str) {
+    let _ = app.emit(
+        reservations::UPDATED_EVENT,
+        json!({
+            "reservation": reservation,
+            "queued": true,
+            "queueId": queue_id,
+        }),
+    );
+    let _ = app.emit(
+        "sync:status",
+        json!({ "queuedRemote": 1, "moduleType": reservations::MODULE_TYPE }),
+    );
+}
+
+fn reservation_id(payload: &Value) -> Result<String, String> {
+    value_str(payload, &["reservationId", "reservation_id", "id"])
+        .ok_or_else(|| "Missing reservation id".to_string())
+}
+
+fn flag(payload: &Value, keys: &[&str]) -> bool {
+    keys.iter()
+        .any(|key| payload.get(*key).and_then(Value::as_bool) == Some(true))
+}
+
+/// Today's reservations from the local cache, with expected covers per hour.
+/// Optional `date` (`YYYY-MM-DD`), `fromTime` / `toTime` (`HH:MM`) narrow the
+/// window.
+#[tauri::command]
+pub async fn reservation_list_today(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<Value, String> {
+    let payload = arg0.unwrap_or(Value::Null);
+    let (from, to) = reservations::day_window(
+        value_str(&payload, &["date"]).as_deref(),
+        value_str(&payload, &["fromTime", "from_time"]).as_deref(),
+        value_str(&payload, &["toTime", "to_time"]).as_deref(),
+    )?;
+    let branch_id = branch_data::resolve_branch_id(&db, None).ok();
+    let list = db.read(|conn| reservations::list_window(conn, branch_id.as_deref(), &from, &to))?;
+    let covers = reservations::covers_by_hour(&list);
+    let total_covers: i64 = covers
+        .iter()
+        .filter_map(|hour| hour["covers"].as_i64())
+        .sum();
+    Ok(json!({
+        "success": true,
+        "reservations": list,
+        "coversByHour": covers,
+        "totalCovers": total_covers,
+        "window": { "from": from, "to": to },
+    }))
+}
+
+/// Pull reservations from the admin now instead of waiting for the sync
+/// cycle.
+#[tauri::command]
+pub async fn reservation_refresh(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
+    let summary = reservations::refresh(&db).await?;
+    Ok(json!({ "success": true, "summary": summary.to_json() }))
+}
+
+/// Mark a reservation as arrived. `tableId` seats the party at that table
+/// (the table is marked occupied through the tables cache); `orderId` links an
+/// existing order, or `createOrder: true` opens a dine-in order for the party.
+#[tauri::command]
+pub async fn reservation_check_in(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    let payload = arg0.ok_or("Missing reservation payload")?;
+    let id = reservation_id(&payload)?;
+    let reservation = db
+        .read(|conn| reservations::get(conn, &id))?
+        .ok_or_else(|| format!("Reservation not found: {id}"))?;
+    let table_id = value_str(&payload, &["tableId", "table_id"])
+        .or_else(|| value_str(&reservation, &["table_id"]));
+    let mut order_id = value_str(&payload, &["orderId", "order_id"]);
+
+    if order_id.is_none() && flag(&payload, &["createOrder", "create_order"]) {
+        let order_payload = json!({
+            "orderType": "dine-in",
+            "tableId": table_id,
+            "tableNumber": value_str(&payload, &["tableNumber", "table_number"]),
+            "guestCount": value_i64(&reservation, &["party_size"]),
+            "customerName": reservation["customer_name"],
+            "customerPhone": reservation["customer_phone"],
+            "customerId": reservation["customer_id"],
+            "specialInstructions": reservation["special_requests"],
+            "items": [],
+        });
+        let created = db
+            .run_blocking(move |db| {
+                super::orders::create_order_from_payload(db, order_payload, true)
+            })
+            .await?;
+        match value_str(&created, &["orderId"]) {
+            Some(created_id) => order_id = Some(created_id),
+            // Schema rejection: nothing was checked in yet.
+            None => return Ok(created),
+        }
+    }
+
+    let now = Utc::now().to_rfc3339();
+    let (reservation, queue_id) = db.write(|conn| {
+        reservations::check_in(conn, &id, table_id.as_deref(), order_id.as_deref(), &now)
+    })?;
+    emit_reservation_updated(&app, &reservation, &queue_id);
+
+    // The check-in stands even when the table cannot be marked (no tables
+    // cache yet); the warning tells the hostess to seat it by hand.
+    let mut table_warning = None;
+    if let Some(table_id) = table_id.as_deref() {
+        let result = branch_data::resolve_branch_id(&db, None).and_then(|branch_id| {
+            let organization_id = branch_data::resolve_organization_id(&db);
+            db.write(|conn| {
+                branch_data::set_cached_table_status(
+                    conn,
+                    &branch_id,
+                    &organization_id,
+                    table_id,
+                    "occupied",
+                    &now,
+                )
+            })
+        });
+        match result {
+            Ok(table) => {
+                let _ = app.emit(
+                    "table_status_updated",
+                    json!({
+                        "tableId": table_id,
+                        "status": "occupied",
+                        "updatedAt": now,
+                        "queued": true,
+                        "table": table,
+                    }),
+                );
+            }
+            Err(e) => {
+                tracing::warn!(reservation_id = %id, table_id = %table_id, error = %e, "Could not assign table on check-in");
+                table_warning = Some(e);
+            }
+        }
+    }
+
+    Ok(json!({
+        "success": true,
+        "reservation": reservation,
+        "orderId": order_id,
+        "queueId": queue_id,
+        "tableWarning": table_warning,
+    }))
+}
+
+#[tauri::command]
+pub async fn reservation_mark_no_show(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    let payload = arg0.ok_or("Missing reservation payload")?;
+    let id = reservation_id(&payload)?;
+    let now = Utc::now().to_rfc3339();
+    let (reservation, queue_id) = db.write(|conn| reservations::mark_no_show(conn, &id, &now))?;
+    emit_reservation_updated(&app, &reservation, &queue_id);
+    Ok(json!({
+        "success": true,
+        "reservation": reservation,
+        "queueId": queue_id,
+    }))
+}
@@ -0,0 +1,89 @@
+use chrono::Local;
+use serde_json::{json, Value};
+use tauri::Emitter;
+
+use crate::{auth, db, retention};
+
+fn parse_policy_update(arg0: Option<Value>) -> Result<retention::RetentionPolicyUpdate, String> {
+    let payload = arg0.unwrap_or_else(|| json!({}));
+    let payload = payload.get("policy").cloned().unwrap_or(payload);
+    serde_json::from_value(payload).map_err(|e| format!("Invalid retention policy: {e}"))
+}
+
+fn policy_response(conn: &rusqlite::Connection, policy: retention::RetentionPolicy) -> Value {
+    json!({
+        "success": true,
+        "policy": policy,
+        "lastRunDate": retention::last_run_date(conn).map(|date| date.to_string()),
+        "minFreeBytes": retention::MIN_FREE_BYTES,
+    })
+}
+
+#[tauri::command]
+pub async fn retention_get_policy(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(policy_response(&conn, retention::load_policy(&conn)))
+}
+
+#[tauri::command]
+pub async fn retention_set_policy(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<Value, auth::GuardedCommandError> {
+    auth::authorize_privileged_action(
+        auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let update = parse_policy_update(arg0)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let policy = retention::update_policy(&conn, &update)?;
+    Ok(policy_response(&conn, policy))
+}
+
+#[tauri::command]
+pub async fn retention_run_now(
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<Value, auth::GuardedCommandError> {
+    auth::authorize_privileged_action(
+        auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let emit = |payload: &Value| {
+        let _ = app.emit(retention::PROGRESS_EVENT, payload);
+    };
+    retention::run(&db, retention::TRIGGER_MANUAL, &emit).map_err(Into::into)
+}
+
+#[tauri::command]
+pub async fn archives_list(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
+    let archive_dir = retention::archives_dir(&db)?;
+    let archives = retention::list_archives(&archive_dir)?;
+    Ok(json!({
+        "success": true,
+        "archiveDir": archive_dir.to_string_lossy(),
+        "archives": archives,
+        "checkedAt": Local::now().to_rfc3339(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy_update_accepts_wrapped_and_snake_case_payloads() {
+        let wrapped = parse_policy_update(Some(json!({ "policy": { "ordersDays": 30 } })))
+            .expect("wrapped payload");
+        assert_eq!(wrapped.orders_days, Some(30));
+        let snake = parse_policy_update(Some(json!({ "print_jobs_days": 2, "enabled": false })))
+            .expect("snake_case payload");
+        assert_eq!(snake.print_jobs_days, Some(2));
+        assert_eq!(snake.enabled, Some(false));
+        assert!(parse_policy_update(Some(json!({ "ordersDays": "lots" }))).is_err());
+    }
+}
@@ -6,7 +6,8 @@ use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::{
-    auth, db, ecr, payload_arg0_as_string, storage, validate_external_url, APP_START_EPOCH,
+    auth, db, ecr, kiosk, payload_arg0_as_string, shutdown, storage, training,
+    validate_external_url, APP_START_EPOCH,
 };
 
 #[derive(Debug, Deserialize, Default)]
@@ -37,8 +38,25 @@ pub struct ScreenCaptureSignalPollingState {
     active: std::sync::Mutex<Option<ScreenCaptureSignalPollingHandle>>,
 }
 
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ShutdownPayload {
+    #[serde(default)]
+    force: bool,
+}
+
 const SCREEN_CAPTURE_AFTER_MAX_LEN: usize = 128;
 
+fn parse_shutdown_payload(arg0: Option<serde_json::Value>) -> ShutdownPayload {
+    match arg0 {
+        Some(serde_json::Value::Bool(force)) => ShutdownPayload { force },
+        Some(value @ serde_json::Value::Object(_)) => {
+            serde_json::from_value(value).unwrap_or_default()
+        }
+        _ => ShutdownPayload::default(),
+    }
+}
+
 fn parse_external_url_payload(arg0: Option<serde_json::Value>) -> Result<String, String> {
     payload_arg0_as_string(arg0, &["url", "href", "target", "value"])
         .ok_or("Missing external URL payload".into())
@@ -130,6 +148,7 @@ fn parse_screen_capture_signal_polling_payload(
 
 #[tauri::command]
 pub async fn app_shutdown(
+    arg0: Option<serde_json::Value>,
     app: tauri::AppHandle,
     mgr: tauri::State<'_, ecr::DeviceManager>,
     db: tauri::State<'_, db::DbState>,
@@ -140,7 +159,8 @@ pub async fn app_shutdown(
         &db,
         &auth_state,
     )?;
-    info!("app:shutdown requested");
+    let payload = parse_shutdown_payload(arg0);
+    info!(force = payload.force, "app:shutdown requested");
     let _ = app.emit(
         "control_command_received",
         serde_json::json!({ "command": "shutdown" }),
@@ -149,6 +169,9 @@ pub async fn app_shutdown(
         "app_shutdown_initiated",
         serde_json::json!({ "source": "ipc" }),
     );
+    if !shutdown::run_shutdown_sequence(&app, payload.force).await {
+        return Ok(());
+    }
     let _ = app.emit("app_close", serde_json::json!({ "reason": "shutdown" }));
     mgr.shutdown();
     app.exit(0);
@@ -186,8 +209,10 @@ pub async fn app_get_version() -> Result<serde_json::Value, String> {
 }
 
 #[tauri::command]
-pub async fn app_get_shutdown_status() -> Result<serde_json::Value, String> {
-    Ok(serde_json::json!({ "shuttingDown": false }))
+pub async fn app_get_shutdown_status(
+    shutdown_state: tauri::State<'_, shutdown::ShutdownState>,
+) -> Result<serde_json::Value, String> {
+    Ok(shutdown_state.status_json())
 }
 
 #[tauri::command]
@@ -215,9 +240,92 @@ pub async fn system_get_info(
         "db_size_bytes": db_size,
         "is_configured": is_configured,
         "uptime_seconds": uptime,
+        "training_mode": training::is_active(),
+        "kiosk_mode": kiosk::is_enabled(),
+    }))
+}
+
+#[tauri::command]
+pub async fn training_mode_get_status(
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    Ok(training::status(&db))
+}
+
+#[tauri::command]
+pub async fn training_mode_enter(
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let path = db.run_blocking(training::enter).await?;
+    let status = training::status(&db);
+    let _ = app.emit(training::MODE_CHANGED_EVENT, status.clone());
+    Ok(serde_json::json!({
+        "success": true,
+        "training": true,
+        "dbPath": path.to_string_lossy(),
+        "status": status,
+    }))
+}
+
+#[tauri::command]
+pub async fn training_mode_exit(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let wipe = arg0
+        .as_ref()
+        .and_then(|payload| payload.get("wipe"))
+        .and_then(serde_json::Value::as_bool)
+        .unwrap_or(false);
+    db.run_blocking(move |db| training::exit(db, wipe)).await?;
+    let status = training::status(&db);
+    let _ = app.emit(training::MODE_CHANGED_EVENT, status.clone());
+    Ok(serde_json::json!({
+        "success": true,
+        "training": false,
+        "wiped": wipe,
+        "status": status,
     }))
 }
 
+#[tauri::command]
+pub async fn kiosk_get_status(
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    db.read(|conn| Ok(kiosk::KioskConfig::load(conn).to_json()))
+}
+
+#[tauri::command]
+pub async fn kiosk_enable(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let payload = arg0.unwrap_or(serde_json::Value::Null);
+    let status = kiosk::enable(&db, &payload)?;
+    let _ = app.emit(kiosk::MODE_CHANGED_EVENT, status.clone());
+    Ok(serde_json::json!({ "success": true, "status": status }))
+}
+
+#[tauri::command]
+pub async fn kiosk_disable(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, crate::error::PosError> {
+    let pin = arg0
+        .as_ref()
+        .and_then(|payload| payload.get("pin"))
+        .and_then(serde_json::Value::as_str)
+        .ok_or_else(|| crate::error::PosError::validation("pin", "Missing admin PIN"))?;
+    let status = kiosk::disable(&db, &auth_state, pin)?;
+    let _ = app.emit(kiosk::MODE_CHANGED_EVENT, status.clone());
+    Ok(serde_json::json!({ "success": true, "status": status }))
+}
+
 #[tauri::command]
 pub async fn system_open_external_url(
     arg0: Option<serde_json::Value>,
@@ -486,13 +594,16 @@ pub async fn screen_capture_stop_signal_polling(
 
 #[tauri::command]
 pub async fn geo_ip() -> Result<serde_json::Value, String> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(8))
-        .build()
-        .map_err(|e| format!("HTTP client error: {e}"))?;
+    let client = crate::api::shared_client()?;
+    let timeout = std::time::Duration::from_secs(8);
 
     // Primary provider
-    if let Ok(resp) = client.get("https://ipapi.co/json/").send().await {
+    if let Ok(resp) = client
+        .get("https://ipapi.co/json/")
+        .timeout(timeout)
+        .send()
+        .await
+    {
         if resp.status().is_success() {
             if let Ok(v) = resp.json::<serde_json::Value>().await {
                 if let (Some(lat), Some(lng)) = (
@@ -510,7 +621,12 @@ pub async fn geo_ip() -> Result<serde_json::Value, String> {
     }
 
     // Fallback provider
-    if let Ok(resp) = client.get("https://ipwho.is/").send().await {
+    if let Ok(resp) = client
+        .get("https://ipwho.is/")
+        .timeout(timeout)
+        .send()
+        .await
+    {
         if resp.status().is_success() {
             if let Ok(v) = resp.json::<serde_json::Value>().await {
                 if let (Some(lat), Some(lng)) = (
@@ -534,6 +650,14 @@ pub async fn geo_ip() -> Result<serde_json::Value, String> {
 mod dto_tests {
     use super::*;
 
+    #[test]
+    fn parse_shutdown_payload_reads_force_flag() {
+        assert!(!parse_shutdown_payload(None).force);
+        assert!(parse_shutdown_payload(Some(serde_json::json!({ "force": true }))).force);
+        assert!(parse_shutdown_payload(Some(serde_json::json!(true))).force);
+        assert!(!parse_shutdown_payload(Some(serde_json::json!("now"))).force);
+    }
+
     #[test]
     fn parse_external_url_payload_supports_string_and_object() {
         let from_string =
@@ -5,6 +5,7 @@ use std::sync::{Mutex, OnceLock};
 use tauri::Emitter;
 use zeroize::Zeroizing;
 
+use crate::destructive_ops::{self, DestructiveOp};
 use crate::terminal_helpers::{
     extract_enabled_features_from_terminal_settings_response,
     extract_owner_terminal_db_id_from_terminal_settings_response,
@@ -16,7 +17,7 @@ use crate::terminal_helpers::{
     extract_terminal_type_from_terminal_settings_response, persist_terminal_identity,
     reconcile_terminal_identity_from_local_sources, resolve_managed_terminal_identity,
 };
-use crate::{api, auth, db, menu, reset, storage};
+use crate::{api, auth, db, menu, provisioning, reset, storage};
 
 const TERMINAL_RUNTIME_STALE_AFTER_MS: i64 = 15 * 60 * 1000;
 static LAST_TERMINAL_RUNTIME_EMIT_SIGNATURE: OnceLock<Mutex<Option<Value>>> = OnceLock::new();
@@ -529,18 +530,30 @@ pub(crate) async fn refresh_terminal_context_from_admin(db: &db::DbState) -> Res
     let resp = api::fetch_from_admin(&normalized_admin_url, &api_key, &path, "GET", None).await?;
 
     if let Some(bid) = crate::extract_branch_id_from_terminal_settings_response(&resp) {
-        let _ = storage::set_credential("branch_id", &bid);
         if let Ok(conn) = db.conn.lock() {
-            let _ = db::set_setting(&conn, "terminal", "branch_id", &bid);
+            if let Err(e) = crate::branches::record_settings_response(&conn, &resp, &bid) {
+                tracing::warn!(error = %e, "Failed to refresh branch registry");
+            }
+            match crate::branches::apply_assigned_branch(&conn, &bid) {
+                Ok(active) => tracing::info!(
+                    branch_id = %bid,
+                    active_branch_id = %active,
+                    "Stored branch_id from admin settings"
+                ),
+                Err(e) => tracing::warn!(error = %e, "Failed to store branch_id"),
+            }
         }
-        tracing::info!(branch_id = %bid, "Stored branch_id from admin settings");
     }
     if let Some(oid) = crate::extract_org_id_from_terminal_settings_response(&resp) {
-        let _ = storage::set_credential("organization_id", &oid);
-        if let Ok(conn) = db.conn.lock() {
-            let _ = db::set_setting(&conn, "terminal", "organization_id", &oid);
+        match crate::commands::organization_change::store_organization_id(
+            db,
+            &oid,
+            "admin_settings_refresh",
+        ) {
+            Ok(None) => tracing::info!("Stored organization_id from admin settings"),
+            Ok(Some(_)) => {}
+            Err(e) => tracing::warn!(error = %e, "Failed to store organization_id"),
         }
-        tracing::info!("Stored organization_id from admin settings");
     }
     if let Some(ghost_enabled) =
         crate::extract_ghost_mode_feature_from_terminal_settings_response(&resp)
@@ -865,8 +878,39 @@ pub async fn settings_get_local(
     get_settings(db).await
 }
 
+/// First step of a destructive operation: report what it would delete and
+/// issue the confirmation token the operation itself requires.
+#[tauri::command]
+pub async fn destructive_op_prepare(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<Value, auth::GuardedCommandError> {
+    auth::authorize_privileged_action(
+        auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let raw =
+        crate::payload_arg0_as_string(arg0, &["operation", "op"]).ok_or("Missing operation")?;
+    let op = DestructiveOp::parse(&raw).ok_or_else(|| format!("Unknown operation: {raw}"))?;
+    let impact = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        destructive_ops::impact(&conn)
+    };
+    let (token, expires_at) = destructive_ops::issue_token(op, Utc::now())?;
+    Ok(serde_json::json!({
+        "success": true,
+        "operation": op.as_str(),
+        "confirmationToken": token,
+        "expiresAt": expires_at,
+        "impact": impact,
+    }))
+}
+
 #[tauri::command]
 pub async fn settings_factory_reset(
+    arg0: Option<Value>,
     db: tauri::State<'_, db::DbState>,
     app: tauri::AppHandle,
     auth_state: tauri::State<'_, auth::AuthState>,
@@ -878,6 +922,12 @@ pub async fn settings_factory_reset(
         &db,
         &auth_state,
     )?;
+    destructive_ops::confirm(
+        &db,
+        DestructiveOp::FactoryReset,
+        arg0.as_ref(),
+        auth::current_staff_id(&auth_state).as_deref(),
+    )?;
     crate::recovery::snapshot_before_destructive_action(
         &db,
         crate::recovery::RecoveryPointKind::PreFactoryReset,
@@ -917,18 +967,82 @@ pub async fn settings_emergency_reset(
     )
 }
 
-#[tauri::command]
-pub async fn settings_update_terminal_credentials(
-    arg0: Option<Value>,
-    db: tauri::State<'_, db::DbState>,
-    app: tauri::AppHandle,
-    sync_state: tauri::State<'_, std::sync::Arc<crate::sync::SyncState>>,
+/// Mirror non-sensitive terminal metadata into local_settings for
+/// compatibility paths. Sensitive credentials stay in OS keyring only.
+fn mirror_terminal_credentials_to_settings(
+    db: &db::DbState,
+    payload: &Value,
+) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    if let Some(v) = storage::get_credential("terminal_id")
+        .or_else(|| crate::value_str(payload, &["terminalId", "terminal_id"]))
+    {
+        db::set_setting(&conn, "terminal", "terminal_id", &v)?;
+    }
+    if let Some(v) = storage::get_credential("admin_dashboard_url").or_else(|| {
+        crate::value_str(
+            payload,
+            &["adminDashboardUrl", "adminUrl", "admin_dashboard_url"],
+        )
+    }) {
+        db::set_setting(&conn, "terminal", "admin_dashboard_url", &v)?;
+    }
+    if let Some(v) = storage::get_credential("branch_id")
+        .or_else(|| crate::value_str(payload, &["branchId", "branch_id"]))
+    {
+        db::set_setting(&conn, "terminal", "branch_id", &v)?;
+    }
+    if let Some(v) = storage::get_credential("organization_id")
+        .or_else(|| crate::value_str(payload, &["organizationId", "organization_id"]))
+    {
+        db::set_setting(&conn, "terminal", "organization_id", &v)?;
+    }
+    if let Some(v) = storage::get_credential("supabase_url")
+        .or_else(|| crate::value_str(payload, &["supabaseUrl", "supabase_url"]))
+    {
+        db::set_setting(&conn, "terminal", "supabase_url", &v)?;
+    }
+    let ghost_mode_feature = storage::get_credential("ghost_mode_feature_enabled").or_else(|| {
+        payload
+            .get("ghostModeFeatureEnabled")
+            .or_else(|| payload.get("ghost_mode_feature_enabled"))
+            .and_then(value_to_bool_string)
+    });
+    if let Some(v) = ghost_mode_feature {
+        db::set_setting(&conn, "terminal", "ghost_mode_feature_enabled", &v)?;
+    }
+    Ok(())
+}
+
+/// Store credentials from a credentials payload (or connection code in
+/// `apiKey`). Switching to a different terminal or admin clears the old
+/// terminal's operational data first, after a recovery snapshot.
+pub(crate) fn store_terminal_credentials(
+    db: &db::DbState,
+    payload: &Value,
 ) -> Result<Value, String> {
-    let payload = arg0.ok_or("Missing credentials payload")?;
-    let previous_terminal_id = current_terminal_id_for_switch(&db);
-    let previous_admin_url = current_admin_url_for_switch(&db);
-    let next_terminal_id = payload_terminal_id_for_switch(&payload);
-    let next_admin_url = payload_admin_url_for_switch(&payload);
+    store_credentials(db, payload, true)
+}
+
+/// Store credentials for a terminal re-pairing. The old terminal's orders
+/// and queue rows are held by the re-pairing quarantine, so a terminal
+/// switch only drops derived context instead of clearing operational data.
+pub(crate) fn store_terminal_credentials_for_repair(
+    db: &db::DbState,
+    payload: &Value,
+) -> Result<Value, String> {
+    store_credentials(db, payload, false)
+}
+
+fn store_credentials(
+    db: &db::DbState,
+    payload: &Value,
+    clear_operational_on_switch: bool,
+) -> Result<Value, String> {
+    let previous_terminal_id = current_terminal_id_for_switch(db);
+    let previous_admin_url = current_admin_url_for_switch(db);
+    let next_terminal_id = payload_terminal_id_for_switch(payload);
+    let next_admin_url = payload_admin_url_for_switch(payload);
     let connection_changed = terminal_connection_changed(
         previous_terminal_id.as_deref(),
         next_terminal_id.as_deref(),
@@ -937,12 +1051,10 @@ pub async fn settings_update_terminal_credentials(
     );
 
     if connection_changed {
-        crate::clear_derived_terminal_context(&db);
+        crate::clear_derived_terminal_context(db);
     }
 
-    let result = storage::update_terminal_credentials(&payload)?;
-
-    if connection_changed {
+    if connection_changed && clear_operational_on_switch {
         tracing::warn!(
             previous_terminal_id = previous_terminal_id
                 .as_deref()
@@ -957,55 +1069,56 @@ pub async fn settings_update_terminal_credentials(
             "Terminal connection changed; clearing old operational data before bootstrap"
         );
         crate::recovery::snapshot_before_destructive_action(
-            &db,
+            db,
             crate::recovery::RecoveryPointKind::PreClearOperationalData,
         )?;
-        crate::clear_operational_data_inner(&db)?;
+        crate::clear_operational_data_inner(db)?;
     }
 
-    // Mirror non-sensitive terminal metadata into local_settings for
-    // compatibility paths. Sensitive credentials stay in OS keyring only.
+    // Checked after the clear above, which also empties the audit log the
+    // organization check writes to.
+    let held_payload = crate::commands::organization_change::hold_payload_organization(
+        db,
+        payload,
+        "terminal_credentials",
+    )?;
+    let payload = held_payload.as_ref().unwrap_or(payload);
+    let result = storage::update_terminal_credentials(payload)?;
+    mirror_terminal_credentials_to_settings(db, payload)?;
+    Ok(result)
+}
+
+/// Tell the renderer and realtime socket that terminal credentials changed.
+pub(crate) fn announce_terminal_credentials_updated(
+    app: &tauri::AppHandle,
+    db: &db::DbState,
+    source: &str,
+) {
+    let mut credentials_payload = build_terminal_runtime_config(db);
+    if let Some(map) = credentials_payload.as_object_mut() {
+        map.insert("success".to_string(), serde_json::json!(true));
+    }
+    let _ = app.emit("terminal_credentials_updated", credentials_payload);
+    let _ = app.emit("terminal_enabled", serde_json::json!({ "success": true }));
+    // Reconnect the realtime socket so it picks up the new branch / keys.
+    if let Some(realtime_state) =
+        tauri::Manager::try_state::<std::sync::Arc<crate::realtime::RealtimeState>>(app)
     {
-        let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        if let Some(v) = storage::get_credential("terminal_id")
-            .or_else(|| crate::value_str(&payload, &["terminalId", "terminal_id"]))
-        {
-            db::set_setting(&conn, "terminal", "terminal_id", &v)?;
-        }
-        if let Some(v) = storage::get_credential("admin_dashboard_url").or_else(|| {
-            crate::value_str(
-                &payload,
-                &["adminDashboardUrl", "adminUrl", "admin_dashboard_url"],
-            )
-        }) {
-            db::set_setting(&conn, "terminal", "admin_dashboard_url", &v)?;
-        }
-        if let Some(v) = storage::get_credential("branch_id")
-            .or_else(|| crate::value_str(&payload, &["branchId", "branch_id"]))
-        {
-            db::set_setting(&conn, "terminal", "branch_id", &v)?;
-        }
-        if let Some(v) = storage::get_credential("organization_id")
-            .or_else(|| crate::value_str(&payload, &["organizationId", "organization_id"]))
-        {
-            db::set_setting(&conn, "terminal", "organization_id", &v)?;
-        }
-        if let Some(v) = storage::get_credential("supabase_url")
-            .or_else(|| crate::value_str(&payload, &["supabaseUrl", "supabase_url"]))
-        {
-            db::set_setting(&conn, "terminal", "supabase_url", &v)?;
-        }
-        let ghost_mode_feature =
-            storage::get_credential("ghost_mode_feature_enabled").or_else(|| {
-                payload
-                    .get("ghostModeFeatureEnabled")
-                    .or_else(|| payload.get("ghost_mode_feature_enabled"))
-                    .and_then(value_to_bool_string)
-            });
-        if let Some(v) = ghost_mode_feature {
-            db::set_setting(&conn, "terminal", "ghost_mode_feature_enabled", &v)?;
-        }
+        realtime_state.request_restart();
     }
+    emit_terminal_runtime_update(app, db, source, None);
+    crate::commands::organization_change::announce_if_pending(app, db);
+}
+
+#[tauri::command]
+pub async fn settings_update_terminal_credentials(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+    sync_state: tauri::State<'_, std::sync::Arc<crate::sync::SyncState>>,
+) -> Result<Value, String> {
+    let payload = arg0.ok_or("Missing credentials payload")?;
+    let result = store_terminal_credentials(&db, &payload)?;
 
     // After saving credentials, fetch terminal config from admin API
     // to populate branch_id, organization_id, and feature flags.
@@ -1033,16 +1146,199 @@ pub async fn settings_update_terminal_credentials(
         }
     }
 
+    announce_terminal_credentials_updated(&app, &db, "settings_update_terminal_credentials");
+    crate::scrub_sensitive_local_settings(&db);
+
+    Ok(result)
+}
+
+/// Apply a connection code scanned from the admin dashboard's QR. Nothing is
+/// stored until the admin dashboard accepts the decoded credentials.
+#[tauri::command]
+pub async fn settings_apply_connection_qr(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+    sync_state: tauri::State<'_, std::sync::Arc<crate::sync::SyncState>>,
+) -> Result<Value, String> {
+    use crate::connection_qr::{self, QrInput};
+
+    let text = match connection_qr::parse_input(arg0).and_then(|input| match input {
+        QrInput::Text(text) => Ok(text),
+        QrInput::Image(encoded) => connection_qr::decode_qr_image(&encoded),
+    }) {
+        Ok(text) => Zeroizing::new(text),
+        Err(rejection) => return Ok(rejection.to_json()),
+    };
+    let code = Zeroizing::new(connection_qr::connection_string_from_qr_text(&text));
+    let decoded = match connection_qr::check_connection_string(&code, Utc::now()) {
+        Ok(decoded) => decoded,
+        Err(rejection) => return Ok(rejection.to_json()),
+    };
+    let (Some(admin_url), Some(api_key), Some(terminal_id)) =
+        (decoded.admin_url, decoded.api_key, decoded.terminal_id)
+    else {
+        return Err("Validated connection code is missing fields".into());
+    };
+    let api_key = Zeroizing::new(api_key);
+
+    let connectivity = api::test_connectivity(&admin_url, &api_key).await;
+    if !connectivity.success {
+        let error = connectivity
+            .error
+            .unwrap_or_else(|| "Admin dashboard unreachable".into());
+        return Ok(serde_json::json!({
+            "success": false,
+            "errorCode": crate::onboarding::classify_admin_error(&error),
+            "error": error,
+        }));
+    }
+
+    store_terminal_credentials(&db, &serde_json::json!({ "apiKey": code.as_str() }))?;
+    match refresh_terminal_context_from_admin(&db).await {
+        Ok(()) => sync_state.clear_remote_auth_pause(),
+        Err(e) => tracing::warn!(error = %e, "Failed to fetch terminal config from admin"),
+    }
+    announce_terminal_credentials_updated(&app, &db, "settings_apply_connection_qr");
+    crate::scrub_sensitive_local_settings(&db);
+
+    Ok(serde_json::json!({
+        "success": true,
+        "terminalId": crate::mask_terminal_id(&terminal_id),
+        "adminHost": connection_qr::admin_host(&admin_url),
+        "latencyMs": connectivity.latency_ms,
+    }))
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProvisioningPayload {
+    #[serde(alias = "filePath", alias = "file_path")]
+    path: String,
+    passphrase: String,
+}
+
+fn parse_provisioning_payload(
+    arg0: Option<Value>,
+) -> Result<(std::path::PathBuf, Zeroizing<String>), String> {
+    let payload: ProvisioningPayload = serde_json::from_value(arg0.ok_or("Missing payload")?)
+        .map_err(|_| "Provisioning payload requires path and passphrase".to_string())?;
+    let passphrase = Zeroizing::new(payload.passphrase);
+    let path = payload.path.trim();
+    if path.is_empty() {
+        return Err("Missing provisioning file path".into());
+    }
+    Ok((std::path::PathBuf::from(path), passphrase))
+}
+
+#[tauri::command]
+pub async fn config_export_provisioning(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<Value, auth::GuardedCommandError> {
+    // The file carries the terminal API key, so exporting is a privileged
+    // action even though the contents are encrypted.
+    auth::authorize_privileged_action(
+        auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let (path, passphrase) = parse_provisioning_payload(arg0)?;
+    let bundle = provisioning::collect_bundle(&db, provisioning::credentials_from_keyring())?;
+    let sealed = provisioning::seal(&bundle, &passphrase)?;
+    std::fs::write(&path, sealed).map_err(|e| format!("write provisioning file: {e}"))?;
+    tracing::info!(path = %path.display(), "provisioning file exported");
+    Ok(serde_json::json!({
+        "success": true,
+        "path": path.to_string_lossy(),
+        "credentials": bundle.credentials.len(),
+        "settings": bundle.settings.values().map(|v| v.len()).sum::<usize>(),
+        "printerProfiles": bundle.printer_profiles.len(),
+        "ecrDevices": bundle.ecr_devices.len(),
+    }))
+}
+
+#[tauri::command]
+pub async fn config_import_provisioning(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<Value, auth::GuardedCommandError> {
+    // A fresh terminal has no staff to authorize against; once configured,
+    // overwriting its credentials needs the same privilege as a reset.
+    if storage::is_configured() {
+        auth::authorize_privileged_action(
+            auth::PrivilegedActionScope::SystemControl,
+            &db,
+            &auth_state,
+        )?;
+    }
+    let (path, passphrase) = parse_provisioning_payload(arg0)?;
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        if provisioning::has_open_shift(&conn) {
+            return Ok(serde_json::json!({
+                "success": false,
+                "errorCode": "shift_open",
+                "error": "Close the open shift before importing a provisioning file",
+            }));
+        }
+    }
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("read provisioning file: {e}"))?;
+    let bundle = match provisioning::open(&contents, &passphrase) {
+        Ok(bundle) => bundle,
+        Err(error) => {
+            return Ok(serde_json::json!({
+                "success": false,
+                "errorCode": "invalid_provisioning_file",
+                "error": error,
+            }));
+        }
+    };
+
+    let payload = bundle.credentials_payload();
+    let previous_terminal_id = current_terminal_id_for_switch(&db);
+    let previous_admin_url = current_admin_url_for_switch(&db);
+    if terminal_connection_changed(
+        previous_terminal_id.as_deref(),
+        payload_terminal_id_for_switch(&payload).as_deref(),
+        previous_admin_url.as_deref(),
+        payload_admin_url_for_switch(&payload).as_deref(),
+    ) {
+        crate::clear_derived_terminal_context(&db);
+        crate::recovery::snapshot_before_destructive_action(
+            &db,
+            crate::recovery::RecoveryPointKind::PreClearOperationalData,
+        )?;
+        crate::clear_operational_data_inner(&db)?;
+    }
+    storage::update_terminal_credentials(&payload)?;
+    for key in ["business_type", storage::KEY_CALLERID_SIP_PASSWORD] {
+        if let Some(value) = bundle.credentials.get(key) {
+            storage::set_credential(key, value)?;
+        }
+    }
+    mirror_terminal_credentials_to_settings(&db, &payload)?;
+    let summary = provisioning::apply_bundle(&db, &bundle)?;
+    crate::scrub_sensitive_local_settings(&db);
+    tracing::info!(path = %path.display(), "provisioning file imported");
+
     let mut credentials_payload = build_terminal_runtime_config(&db);
     if let Some(map) = credentials_payload.as_object_mut() {
         map.insert("success".to_string(), serde_json::json!(true));
     }
     let _ = app.emit("terminal_credentials_updated", credentials_payload);
-    let _ = app.emit("terminal_enabled", serde_json::json!({ "success": true }));
-    emit_terminal_runtime_update(&app, &db, "settings_update_terminal_credentials", None);
-    crate::scrub_sensitive_local_settings(&db);
+    if let Some(realtime_state) =
+        tauri::Manager::try_state::<std::sync::Arc<crate::realtime::RealtimeState>>(&app)
+    {
+        realtime_state.request_restart();
+    }
+    emit_terminal_runtime_update(&app, &db, "config_import_provisioning", None);
 
-    Ok(result)
+    Ok(serde_json::json!({ "success": true, "imported": summary }))
 }
 
 #[tauri::command]
@@ -1100,6 +1396,128 @@ pub async fn settings_get_credential_status(
     }))
 }
 
+/// Compare keyring and `local_settings` credentials and report mismatches.
+/// `{ "repair": true }` reconciles them, keyring winning unless empty; see
+/// `credential_validation` for the precedence rules.
+#[tauri::command]
+pub async fn credentials_validate(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    let repair = arg0
+        .as_ref()
+        .and_then(|payload| payload.get("repair"))
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    if !repair {
+        return crate::credential_validation::validate(&db);
+    }
+    let report = crate::credential_validation::repair(&db)?;
+    if report["repaired"]
+        .as_array()
+        .is_some_and(|repaired| !repaired.is_empty())
+    {
+        announce_terminal_credentials_updated(&app, &db, "credentials_validate");
+    }
+    Ok(report)
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SwitchBranchPayload {
+    #[serde(alias = "branch_id", alias = "id")]
+    branch_id: String,
+}
+
+fn parse_switch_branch_payload(arg0: Option<Value>) -> Result<String, String> {
+    let payload = match arg0 {
+        Some(Value::String(branch_id)) => serde_json::json!({ "branchId": branch_id }),
+        Some(value) => value,
+        None => return Err("Missing branchId".into()),
+    };
+    let parsed: SwitchBranchPayload = serde_json::from_value(payload)
+        .map_err(|e| format!("Invalid branch switch payload: {e}"))?;
+    let branch_id = parsed.branch_id.trim().to_string();
+    if branch_id.is_empty() {
+        return Err("Missing branchId".into());
+    }
+    Ok(branch_id)
+}
+
+/// Switch the terminal's active branch to another branch its API key can
+/// access. Blocked while a shift or cash drawer session is open; existing
+/// rows keep their branch. See `branches`.
+#[tauri::command]
+pub async fn terminal_switch_branch(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<Value, String> {
+    let branch_id = parse_switch_branch_payload(arg0)?;
+    let previous = crate::branches::active_branch_id();
+    if previous.as_deref() == Some(branch_id.as_str()) {
+        return Ok(serde_json::json!({
+            "success": true,
+            "branchId": branch_id,
+            "previousBranchId": previous,
+            "changed": false,
+        }));
+    }
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        if let Some(reason) = crate::branches::switch_blocker(&conn)? {
+            return Err(reason);
+        }
+    }
+
+    // Re-read the key's branch list so a revoked branch is refused. Offline,
+    // the registry from the last successful refresh is used instead.
+    if let Err(e) = refresh_terminal_context_from_admin(&db).await {
+        if crate::is_terminal_auth_failure(&e) {
+            return Err(e);
+        }
+        tracing::warn!(error = %e, "Branch switch using cached branch registry");
+    }
+
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        if !crate::branches::is_known(&conn, &branch_id) {
+            return Err(format!(
+                "Branch {branch_id} is not available to this terminal"
+            ));
+        }
+        if let Some(reason) = crate::branches::switch_blocker(&conn)? {
+            return Err(reason);
+        }
+        crate::branches::set_active(&conn, &branch_id)?;
+    }
+    tracing::info!(
+        branch_id = %branch_id,
+        previous_branch_id = ?previous,
+        "Switched active branch"
+    );
+
+    if let Some(realtime_state) =
+        tauri::Manager::try_state::<std::sync::Arc<crate::realtime::RealtimeState>>(&app)
+    {
+        realtime_state.request_restart();
+    }
+    emit_terminal_runtime_update(
+        &app,
+        &db,
+        "terminal_switch_branch",
+        Some(vec!["branch_id".into()]),
+    );
+
+    Ok(serde_json::json!({
+        "success": true,
+        "branchId": branch_id,
+        "previousBranchId": previous,
+        "changed": true,
+    }))
+}
+
 /// Returns all settings merged: local_settings DB + terminal credential store.
 /// The StaffShiftModal uses this to look up `terminal.branch_id`.
 #[tauri::command]
@@ -1169,6 +1587,18 @@ pub async fn get_settings(db: tauri::State<'_, db::DbState>) -> Result<Value, St
         );
     }
 
+    // Active branch plus every branch the terminal may switch to.
+    map.insert(
+        "terminal.active_branch_id".into(),
+        crate::branches::active_branch_id()
+            .map(Value::String)
+            .unwrap_or(Value::Null),
+    );
+    map.insert(
+        "known_branches".into(),
+        Value::Array(crate::branches::list(&conn).unwrap_or_default()),
+    );
+
     Ok(all)
 }
 
@@ -1189,8 +1619,23 @@ pub async fn settings_set(
     arg1: Option<Value>,
     db: tauri::State<'_, db::DbState>,
     app: tauri::AppHandle,
+    auth_state: tauri::State<'_, auth::AuthState>,
 ) -> Result<Value, String> {
     let parsed = parse_settings_set_payload(arg0, arg1)?;
+    let actor = auth::current_staff_id(&auth_state);
+    apply_settings_set(&db, &app, parsed, "settings_set", actor.as_deref())
+}
+
+/// Shared write path for `settings_set` and `settings_revert`: normalizes
+/// terminal values, mirrors credentials into the keyring, records history and
+/// emits the settings events.
+fn apply_settings_set(
+    db: &db::DbState,
+    app: &tauri::AppHandle,
+    parsed: SettingsSetPayload,
+    source: &str,
+    actor: Option<&str>,
+) -> Result<Value, String> {
     let category = parsed.category;
     let key = parsed.key;
     let mut value = match parsed.value_node {
@@ -1223,6 +1668,8 @@ pub async fn settings_set(
         }
     }
 
+    crate::receipt_copies::validate_setting(&category, &key, &value)?;
+
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     if category == "terminal" && crate::is_sensitive_terminal_setting(&key) {
         let _ = conn.execute(
@@ -1230,11 +1677,26 @@ pub async fn settings_set(
              WHERE setting_category = 'terminal' AND setting_key = ?1",
             params![&key],
         );
+        // The value lives in the keyring, so history records it here
+        // (masked) rather than through `set_setting`.
+        let previous =
+            crate::credential_key_for_terminal_setting(&key).and_then(storage::get_credential);
+        if previous.as_deref() != Some(value.trim()) {
+            db::record_setting_change(
+                &conn,
+                &category,
+                &key,
+                previous.as_deref(),
+                Some(value.trim()),
+                source,
+                actor,
+            )?;
+        }
     } else {
-        db::set_setting(&conn, &category, &key, &value)?;
+        db::set_setting_with_source(&conn, &category, &key, &value, source, actor)?;
     }
     for (ekey, evalue) in &extra_terminal_updates {
-        db::set_setting(&conn, "terminal", ekey, evalue)?;
+        db::set_setting_with_source(&conn, "terminal", ekey, evalue, source, actor)?;
     }
     drop(conn);
 
@@ -1261,11 +1723,132 @@ pub async fn settings_set(
         serde_json::json!({ "key": full_key }),
     );
     if category == "terminal" && crate::is_sensitive_terminal_setting(&key) {
-        crate::scrub_sensitive_local_settings(&db);
+        crate::scrub_sensitive_local_settings(db);
     }
     Ok(serde_json::json!({ "success": true }))
 }
 
+#[derive(Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SettingsHistoryQuery {
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    key: Option<String>,
+    #[serde(default)]
+    limit: Option<i64>,
+    #[serde(default)]
+    offset: Option<i64>,
+}
+
+fn parse_settings_history_query(arg0: Option<Value>) -> Result<SettingsHistoryQuery, String> {
+    let mut query = match arg0 {
+        None | Some(Value::Null) => SettingsHistoryQuery::default(),
+        Some(Value::String(raw)) => match raw.trim().split_once('.') {
+            Some((category, key)) => SettingsHistoryQuery {
+                category: Some(category.to_string()),
+                key: Some(key.to_string()),
+                ..Default::default()
+            },
+            None => SettingsHistoryQuery {
+                category: Some(raw.trim().to_string()),
+                ..Default::default()
+            },
+        },
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| format!("Invalid settings history query: {e}"))?,
+    };
+    query.category = query.category.filter(|v| !v.trim().is_empty());
+    query.key = query.key.filter(|v| !v.trim().is_empty());
+    Ok(query)
+}
+
+#[tauri::command]
+pub async fn settings_get_history(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<Value, String> {
+    let query = parse_settings_history_query(arg0)?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let (entries, total) = db::list_settings_history(
+        &conn,
+        query.category.as_deref(),
+        query.key.as_deref(),
+        limit,
+        offset,
+    )?;
+    Ok(serde_json::json!({
+        "success": true,
+        "entries": entries,
+        "total": total,
+        "limit": limit,
+        "offset": offset,
+    }))
+}
+
+#[tauri::command]
+pub async fn settings_revert(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<Value, String> {
+    let history_id = match arg0.as_ref() {
+        Some(Value::Number(n)) => n.as_i64(),
+        Some(Value::String(raw)) => raw.trim().parse::<i64>().ok(),
+        Some(value) => value
+            .get("historyId")
+            .or_else(|| value.get("history_id"))
+            .or_else(|| value.get("id"))
+            .and_then(|v| v.as_i64().or_else(|| v.as_str()?.trim().parse().ok())),
+        None => None,
+    }
+    .ok_or("Missing historyId")?;
+
+    let entry = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        db::get_settings_history_entry(&conn, history_id)
+    };
+    let Some(entry) = entry else {
+        return Ok(serde_json::json!({
+            "success": false,
+            "errorCode": "settings_history_not_found",
+            "error": format!("No settings history entry {history_id}"),
+        }));
+    };
+    if entry["masked"].as_bool().unwrap_or(false) {
+        return Ok(serde_json::json!({
+            "success": false,
+            "errorCode": "settings_revert_secret",
+            "error": "Secret settings cannot be restored from history",
+        }));
+    }
+    let Some(previous) = entry["oldValue"].as_str().map(str::to_string) else {
+        return Ok(serde_json::json!({
+            "success": false,
+            "errorCode": "settings_revert_no_previous_value",
+            "error": "The setting had no previous value to restore",
+        }));
+    };
+
+    let parsed = SettingsSetPayload {
+        category: entry["category"].as_str().unwrap_or_default().to_string(),
+        key: entry["key"].as_str().unwrap_or_default().to_string(),
+        value_node: Value::String(previous.clone()),
+    };
+    let actor = auth::current_staff_id(&auth_state);
+    apply_settings_set(&db, &app, parsed, "settings_revert", actor.as_deref())?;
+    Ok(serde_json::json!({
+        "success": true,
+        "historyId": history_id,
+        "category": entry["category"],
+        "key": entry["key"],
+        "value": previous,
+    }))
+}
+
 #[tauri::command]
 pub async fn settings_update_local(
     arg0: Option<Value>,
@@ -1323,6 +1906,10 @@ pub async fn settings_update_local(
         }
     }
 
+    for (category, key, value) in &normalized_updates {
+        crate::receipt_copies::validate_setting(category, key, value)?;
+    }
+
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     for (category, key, value) in &normalized_updates {
         let is_sensitive_terminal =
@@ -1432,6 +2019,59 @@ pub async fn settings_set_tax_rate(
     Ok(serde_json::json!({ "success": true }))
 }
 
+#[tauri::command]
+pub async fn tax_get_config(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let config = crate::tax::load_config(&conn);
+    serde_json::to_value(config).map_err(|e| format!("serialize tax config: {e}"))
+}
+
+#[tauri::command]
+pub async fn tax_set_config(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<Value, String> {
+    let config: crate::tax::TaxConfig = serde_json::from_value(arg0.unwrap_or(Value::Null))
+        .map_err(|e| format!("Invalid tax config: {e}"))?;
+    if let Err(error) = config.validate() {
+        return Ok(serde_json::json!({
+            "success": false,
+            "errorCode": "invalid_tax_config",
+            "error": error,
+        }));
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    crate::tax::save_config(&conn, &config)?;
+    Ok(serde_json::json!({ "success": true }))
+}
+
+#[tauri::command]
+pub async fn service_charge_get_config(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let config = crate::service_charge::load_config(&conn);
+    serde_json::to_value(config).map_err(|e| format!("serialize service charge config: {e}"))
+}
+
+#[tauri::command]
+pub async fn service_charge_set_config(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<Value, String> {
+    let config: crate::service_charge::ServiceChargeConfig =
+        serde_json::from_value(arg0.unwrap_or(Value::Null))
+            .map_err(|e| format!("Invalid service charge config: {e}"))?;
+    if let Err(error) = config.validate() {
+        return Ok(serde_json::json!({
+            "success": false,
+            "errorCode": "invalid_service_charge_config",
+            "error": error,
+        }));
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    crate::service_charge::save_config(&conn, &config)?;
+    Ok(serde_json::json!({ "success": true }))
+}
+
 #[tauri::command]
 pub async fn settings_get_language(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
@@ -1632,6 +2272,7 @@ pub async fn terminal_config_refresh(
     }
 
     emit_terminal_runtime_update(&app, &db, "terminal_config_refresh", None);
+    super::menu::emit_order_prices_stale_event(&app, &result);
     let _ = app.emit(
         "hardware_config_update",
         serde_json::json!({ "source": "terminal_config_refresh" }),
@@ -1642,10 +2283,10 @@ pub async fn terminal_config_refresh(
 #[cfg(test)]
 mod dto_tests {
     use super::{
-        parse_settings_set_payload, parse_settings_update_local_payload,
-        parse_terminal_config_get_setting_payload, payload_admin_url_for_switch,
-        payload_terminal_id_for_switch, terminal_connection_changed,
-        terminal_runtime_emit_signature, SettingsSetPayload,
+        parse_settings_history_query, parse_settings_set_payload,
+        parse_settings_update_local_payload, parse_terminal_config_get_setting_payload,
+        payload_admin_url_for_switch, payload_terminal_id_for_switch, terminal_connection_changed,
+        terminal_runtime_emit_signature, SettingsHistoryQuery, SettingsSetPayload,
     };
 
     #[test]
@@ -1702,6 +2343,35 @@ mod dto_tests {
         );
     }
 
+    #[test]
+    fn parse_settings_history_query_accepts_flat_key_and_object() {
+        let flat = parse_settings_history_query(Some(serde_json::json!("general.tax_rate")))
+            .expect("flat key");
+        assert_eq!(flat.category.as_deref(), Some("general"));
+        assert_eq!(flat.key.as_deref(), Some("tax_rate"));
+
+        let object = parse_settings_history_query(Some(serde_json::json!({
+            "category": "printer",
+            "key": "",
+            "limit": 20,
+            "offset": 40
+        })))
+        .expect("object");
+        assert_eq!(
+            object,
+            SettingsHistoryQuery {
+                category: Some("printer".into()),
+                key: None,
+                limit: Some(20),
+                offset: Some(40),
+            }
+        );
+        assert_eq!(
+            parse_settings_history_query(None).expect("empty"),
+            SettingsHistoryQuery::default()
+        );
+    }
+
     #[test]
     fn parse_settings_set_payload_supports_object_and_flat_key() {
         let object_payload = parse_settings_set_payload(
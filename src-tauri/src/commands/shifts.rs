@@ -1,11 +1,14 @@
-use chrono::{Datelike, Local, TimeZone, Utc};
-use rusqlite::params;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension};
 use serde::Deserialize;
 use tauri::{Emitter, Manager};
 use tracing::{info, warn};
 
+use crate::drawer_transactions::{self, Direction};
+use crate::error::PosError;
 use crate::shifts as shift_service;
-use crate::{db, fetch_supabase_rows, print, value_f64, value_str};
+use crate::supabase;
+use crate::{db, idempotency, opening_float, print, schedule, tabs, value_f64, value_str};
 
 async fn emit_sync_status_snapshot(
     app: &tauri::AppHandle,
@@ -77,13 +80,16 @@ fn schedule_immediate_sync(app: tauri::AppHandle, entity_type: &'static str, ent
         };
 
         match crate::sync::force_sync(&background_db, sync_state.as_ref(), &app).await {
-            Ok(()) => {
+            Ok(stats) => {
                 let _ = app.emit(
                     "sync_complete",
                     serde_json::json!({
                         "trigger": "auto",
                         "entityType": entity_type,
                         "entityId": entity_id,
+                        "batchesSent": stats.batches_sent,
+                        "itemsSynced": stats.items_synced,
+                        "itemsFailed": stats.items_failed,
                     }),
                 );
             }
@@ -225,7 +231,9 @@ struct ShiftBranchPayload {
     branch_id: String,
 }
 
-fn parse_shift_staff_payload(arg0: Option<serde_json::Value>) -> Result<ShiftStaffPayload, String> {
+fn parse_shift_staff_payload(
+    arg0: Option<serde_json::Value>,
+) -> Result<ShiftStaffPayload, PosError> {
     let payload = match arg0 {
         Some(serde_json::Value::String(staff_id)) => serde_json::json!({
             "staffId": staff_id
@@ -234,18 +242,18 @@ fn parse_shift_staff_payload(arg0: Option<serde_json::Value>) -> Result<ShiftSta
         Some(v) => v,
         None => serde_json::json!({}),
     };
-    let mut parsed: ShiftStaffPayload =
-        serde_json::from_value(payload).map_err(|e| format!("Invalid staff payload: {e}"))?;
+    let mut parsed: ShiftStaffPayload = serde_json::from_value(payload)
+        .map_err(|e| PosError::validation("payload", format!("Invalid staff payload: {e}")))?;
     parsed.staff_id = parsed.staff_id.trim().to_string();
     if parsed.staff_id.is_empty() {
-        return Err("Missing staffId".into());
+        return Err(PosError::validation("staffId", "Missing staffId"));
     }
     Ok(parsed)
 }
 
 fn parse_shift_terminal_payload(
     arg0: Option<serde_json::Value>,
-) -> Result<ShiftTerminalPayload, String> {
+) -> Result<ShiftTerminalPayload, PosError> {
     let payload = match arg0 {
         Some(serde_json::Value::String(terminal_id)) => serde_json::json!({
             "terminalId": terminal_id
@@ -254,11 +262,11 @@ fn parse_shift_terminal_payload(
         Some(v) => v,
         None => serde_json::json!({}),
     };
-    let mut parsed: ShiftTerminalPayload =
-        serde_json::from_value(payload).map_err(|e| format!("Invalid terminal payload: {e}"))?;
+    let mut parsed: ShiftTerminalPayload = serde_json::from_value(payload)
+        .map_err(|e| PosError::validation("payload", format!("Invalid terminal payload: {e}")))?;
     parsed.terminal_id = parsed.terminal_id.trim().to_string();
     if parsed.terminal_id.is_empty() {
-        return Err("Missing terminalId".into());
+        return Err(PosError::validation("terminalId", "Missing terminalId"));
     }
     Ok(parsed)
 }
@@ -284,7 +292,7 @@ fn merge_payload_args(
 fn parse_shift_branch_terminal_payload(
     arg0: Option<serde_json::Value>,
     arg1: Option<serde_json::Value>,
-) -> Result<ShiftBranchTerminalPayload, String> {
+) -> Result<ShiftBranchTerminalPayload, PosError> {
     let payload = match (arg0, arg1) {
         (
             Some(serde_json::Value::String(branch_id)),
@@ -313,15 +321,16 @@ fn parse_shift_branch_terminal_payload(
         (lhs, rhs) => merge_payload_args(lhs, rhs),
     };
 
-    let mut parsed: ShiftBranchTerminalPayload = serde_json::from_value(payload)
-        .map_err(|e| format!("Invalid branch/terminal payload: {e}"))?;
+    let mut parsed: ShiftBranchTerminalPayload = serde_json::from_value(payload).map_err(|e| {
+        PosError::validation("payload", format!("Invalid branch/terminal payload: {e}"))
+    })?;
     parsed.branch_id = parsed.branch_id.trim().to_string();
     parsed.terminal_id = parsed.terminal_id.trim().to_string();
     if parsed.branch_id.is_empty() {
-        return Err("Missing branchId".into());
+        return Err(PosError::validation("branchId", "Missing branchId"));
     }
     if parsed.terminal_id.is_empty() {
-        return Err("Missing terminalId".into());
+        return Err(PosError::validation("terminalId", "Missing terminalId"));
     }
     Ok(parsed)
 }
@@ -329,7 +338,7 @@ fn parse_shift_branch_terminal_payload(
 fn parse_shift_summary_payload(
     arg0: Option<serde_json::Value>,
     arg1: Option<serde_json::Value>,
-) -> Result<ShiftSummaryPayload, String> {
+) -> Result<ShiftSummaryPayload, PosError> {
     let payload = match (arg0, arg1) {
         (Some(serde_json::Value::String(shift_id)), Some(serde_json::Value::Object(mut obj))) => {
             obj.entry("shiftId".to_string())
@@ -351,11 +360,12 @@ fn parse_shift_summary_payload(
         (lhs, rhs) => merge_payload_args(lhs, rhs),
     };
 
-    let mut parsed: ShiftSummaryPayload = serde_json::from_value(payload)
-        .map_err(|e| format!("Invalid shift summary payload: {e}"))?;
+    let mut parsed: ShiftSummaryPayload = serde_json::from_value(payload).map_err(|e| {
+        PosError::validation("payload", format!("Invalid shift summary payload: {e}"))
+    })?;
     parsed.shift_id = parsed.shift_id.trim().to_string();
     if parsed.shift_id.is_empty() {
-        return Err("Missing shiftId".into());
+        return Err(PosError::validation("shiftId", "Missing shiftId"));
     }
     Ok(parsed)
 }
@@ -363,7 +373,7 @@ fn parse_shift_summary_payload(
 fn parse_shift_expense_delete_payload(
     arg0: Option<serde_json::Value>,
     arg1: Option<serde_json::Value>,
-) -> Result<ShiftExpenseDeletePayload, String> {
+) -> Result<ShiftExpenseDeletePayload, PosError> {
     let payload = match (arg0, arg1) {
         (
             Some(serde_json::Value::String(expense_id)),
@@ -385,22 +395,23 @@ fn parse_shift_expense_delete_payload(
         (lhs, rhs) => merge_payload_args(lhs, rhs),
     };
 
-    let mut parsed: ShiftExpenseDeletePayload = serde_json::from_value(payload)
-        .map_err(|e| format!("Invalid expense delete payload: {e}"))?;
+    let mut parsed: ShiftExpenseDeletePayload = serde_json::from_value(payload).map_err(|e| {
+        PosError::validation("payload", format!("Invalid expense delete payload: {e}"))
+    })?;
     parsed.expense_id = parsed.expense_id.trim().to_string();
     parsed.shift_id = parsed.shift_id.trim().to_string();
     if parsed.expense_id.is_empty() {
-        return Err("Missing expenseId".into());
+        return Err(PosError::validation("expenseId", "Missing expenseId"));
     }
     if parsed.shift_id.is_empty() {
-        return Err("Missing shiftId".into());
+        return Err(PosError::validation("shiftId", "Missing shiftId"));
     }
     Ok(parsed)
 }
 
 fn parse_shift_print_checkout_payload(
     arg0: Option<serde_json::Value>,
-) -> Result<ShiftPrintCheckoutPayload, String> {
+) -> Result<ShiftPrintCheckoutPayload, PosError> {
     let payload = match arg0 {
         Some(serde_json::Value::String(shift_id)) => serde_json::json!({
             "shiftId": shift_id
@@ -410,8 +421,12 @@ fn parse_shift_print_checkout_payload(
         None => serde_json::json!({}),
     };
 
-    let mut parsed: ShiftPrintCheckoutPayload = serde_json::from_value(payload)
-        .map_err(|e| format!("Invalid shift checkout print payload: {e}"))?;
+    let mut parsed: ShiftPrintCheckoutPayload = serde_json::from_value(payload).map_err(|e| {
+        PosError::validation(
+            "payload",
+            format!("Invalid shift checkout print payload: {e}"),
+        )
+    })?;
     parsed.shift_id = parsed.shift_id.trim().to_string();
     parsed.role_type = parsed.role_type.and_then(|value| match value.trim() {
         "" => None,
@@ -430,7 +445,7 @@ fn parse_shift_print_checkout_payload(
             });
 
     if parsed.shift_id.is_empty() {
-        return Err("Missing shiftId".into());
+        return Err(PosError::validation("shiftId", "Missing shiftId"));
     }
 
     Ok(parsed)
@@ -438,7 +453,7 @@ fn parse_shift_print_checkout_payload(
 
 fn parse_cashier_shift_payload(
     arg0: Option<serde_json::Value>,
-) -> Result<CashierShiftPayload, String> {
+) -> Result<CashierShiftPayload, PosError> {
     let payload = match arg0 {
         Some(serde_json::Value::String(cashier_shift_id)) => serde_json::json!({
             "cashierShiftId": cashier_shift_id
@@ -447,26 +462,32 @@ fn parse_cashier_shift_payload(
         Some(v) => v,
         None => serde_json::json!({}),
     };
-    let mut parsed: CashierShiftPayload = serde_json::from_value(payload)
-        .map_err(|e| format!("Invalid cashier shift payload: {e}"))?;
+    let mut parsed: CashierShiftPayload = serde_json::from_value(payload).map_err(|e| {
+        PosError::validation("payload", format!("Invalid cashier shift payload: {e}"))
+    })?;
     parsed.cashier_shift_id = parsed.cashier_shift_id.trim().to_string();
     if parsed.cashier_shift_id.is_empty() {
-        return Err("Missing cashierShiftId".into());
+        return Err(PosError::validation(
+            "cashierShiftId",
+            "Missing cashierShiftId",
+        ));
     }
     Ok(parsed)
 }
 
 fn parse_staff_payment_mutation_payload(
     arg0: Option<serde_json::Value>,
-) -> Result<ShiftStaffPaymentMutationPayload, String> {
+) -> Result<ShiftStaffPaymentMutationPayload, PosError> {
     let payload = match arg0 {
         Some(serde_json::Value::Object(obj)) => serde_json::Value::Object(obj),
         Some(v) => v,
         None => serde_json::json!({}),
     };
 
-    let mut parsed: ShiftStaffPaymentMutationPayload = serde_json::from_value(payload)
-        .map_err(|e| format!("Invalid staff payment payload: {e}"))?;
+    let mut parsed: ShiftStaffPaymentMutationPayload =
+        serde_json::from_value(payload).map_err(|e| {
+            PosError::validation("payload", format!("Invalid staff payment payload: {e}"))
+        })?;
     parsed.payment_id = parsed.payment_id.and_then(|value| {
         let trimmed = value.trim().to_string();
         if trimmed.is_empty() {
@@ -495,13 +516,19 @@ fn parse_staff_payment_mutation_payload(
     });
 
     if parsed.cashier_shift_id.is_empty() {
-        return Err("Missing cashierShiftId".into());
+        return Err(PosError::validation(
+            "cashierShiftId",
+            "Missing cashierShiftId",
+        ));
     }
     if parsed.paid_to_staff_id.is_empty() {
-        return Err("Missing paidToStaffId".into());
+        return Err(PosError::validation(
+            "paidToStaffId",
+            "Missing paidToStaffId",
+        ));
     }
     if parsed.amount <= 0.0 {
-        return Err("Amount must be positive".into());
+        return Err(PosError::validation("amount", "Amount must be positive"));
     }
 
     Ok(parsed)
@@ -509,22 +536,30 @@ fn parse_staff_payment_mutation_payload(
 
 fn parse_staff_payment_delete_payload(
     arg0: Option<serde_json::Value>,
-) -> Result<ShiftStaffPaymentDeletePayload, String> {
+) -> Result<ShiftStaffPaymentDeletePayload, PosError> {
     let payload = match arg0 {
         Some(serde_json::Value::Object(obj)) => serde_json::Value::Object(obj),
         Some(v) => v,
         None => serde_json::json!({}),
     };
 
-    let mut parsed: ShiftStaffPaymentDeletePayload = serde_json::from_value(payload)
-        .map_err(|e| format!("Invalid staff payment delete payload: {e}"))?;
+    let mut parsed: ShiftStaffPaymentDeletePayload =
+        serde_json::from_value(payload).map_err(|e| {
+            PosError::validation(
+                "payload",
+                format!("Invalid staff payment delete payload: {e}"),
+            )
+        })?;
     parsed.payment_id = parsed.payment_id.trim().to_string();
     parsed.cashier_shift_id = parsed.cashier_shift_id.trim().to_string();
     if parsed.payment_id.is_empty() {
-        return Err("Missing paymentId".into());
+        return Err(PosError::validation("paymentId", "Missing paymentId"));
     }
     if parsed.cashier_shift_id.is_empty() {
-        return Err("Missing cashierShiftId".into());
+        return Err(PosError::validation(
+            "cashierShiftId",
+            "Missing cashierShiftId",
+        ));
     }
 
     Ok(parsed)
@@ -532,7 +567,7 @@ fn parse_staff_payment_delete_payload(
 
 fn parse_staff_payments_by_staff_payload(
     arg0: Option<serde_json::Value>,
-) -> Result<ShiftStaffPaymentsByStaffPayload, String> {
+) -> Result<ShiftStaffPaymentsByStaffPayload, PosError> {
     let payload = match arg0 {
         Some(serde_json::Value::String(staff_id)) => serde_json::json!({
             "staffId": staff_id
@@ -541,8 +576,10 @@ fn parse_staff_payments_by_staff_payload(
         Some(v) => v,
         None => serde_json::json!({}),
     };
-    let mut parsed: ShiftStaffPaymentsByStaffPayload = serde_json::from_value(payload)
-        .map_err(|e| format!("Invalid staff payments payload: {e}"))?;
+    let mut parsed: ShiftStaffPaymentsByStaffPayload =
+        serde_json::from_value(payload).map_err(|e| {
+            PosError::validation("payload", format!("Invalid staff payments payload: {e}"))
+        })?;
     parsed.staff_id = parsed.staff_id.trim().to_string();
     parsed.date_from = parsed
         .date_from
@@ -551,7 +588,7 @@ fn parse_staff_payments_by_staff_payload(
         .date_to
         .and_then(|v| if v.trim().is_empty() { None } else { Some(v) });
     if parsed.staff_id.is_empty() {
-        return Err("Missing staffId".into());
+        return Err(PosError::validation("staffId", "Missing staffId"));
     }
     Ok(parsed)
 }
@@ -559,7 +596,7 @@ fn parse_staff_payments_by_staff_payload(
 fn parse_staff_date_payload(
     arg0: Option<serde_json::Value>,
     arg1: Option<serde_json::Value>,
-) -> Result<ShiftStaffDatePayload, String> {
+) -> Result<ShiftStaffDatePayload, PosError> {
     let payload = match (arg0, arg1) {
         (Some(serde_json::Value::String(staff_id)), Some(serde_json::Value::String(date))) => {
             serde_json::json!({
@@ -580,20 +617,20 @@ fn parse_staff_date_payload(
         (lhs, rhs) => merge_payload_args(lhs, rhs),
     };
 
-    let mut parsed: ShiftStaffDatePayload =
-        serde_json::from_value(payload).map_err(|e| format!("Invalid staff/date payload: {e}"))?;
+    let mut parsed: ShiftStaffDatePayload = serde_json::from_value(payload)
+        .map_err(|e| PosError::validation("payload", format!("Invalid staff/date payload: {e}")))?;
     parsed.staff_id = parsed.staff_id.trim().to_string();
     parsed.date = parsed.date.trim().to_string();
     if parsed.staff_id.is_empty() {
-        return Err("Missing staffId".into());
+        return Err(PosError::validation("staffId", "Missing staffId"));
     }
     if parsed.date.is_empty() {
-        return Err("Missing date".into());
+        return Err(PosError::validation("date", "Missing date"));
     }
     Ok(parsed)
 }
 
-fn parse_branch_payload(arg0: Option<serde_json::Value>) -> Result<ShiftBranchPayload, String> {
+fn parse_branch_payload(arg0: Option<serde_json::Value>) -> Result<ShiftBranchPayload, PosError> {
     let payload = match arg0 {
         Some(serde_json::Value::String(branch_id)) => serde_json::json!({
             "branchId": branch_id
@@ -602,88 +639,131 @@ fn parse_branch_payload(arg0: Option<serde_json::Value>) -> Result<ShiftBranchPa
         Some(v) => v,
         None => serde_json::json!({}),
     };
-    let mut parsed: ShiftBranchPayload =
-        serde_json::from_value(payload).map_err(|e| format!("Invalid branch payload: {e}"))?;
+    let mut parsed: ShiftBranchPayload = serde_json::from_value(payload)
+        .map_err(|e| PosError::validation("payload", format!("Invalid branch payload: {e}")))?;
     parsed.branch_id = parsed.branch_id.trim().to_string();
     if parsed.branch_id.is_empty() {
-        return Err("Missing branchId".into());
+        return Err(PosError::validation("branchId", "Missing branchId"));
     }
     Ok(parsed)
 }
 
-fn ensure_staff_payments_table(conn: &rusqlite::Connection) -> Result<(), String> {
-    shift_service::ensure_staff_payments_table(conn)
-}
-
-fn map_scheduled_shift_row(row: &serde_json::Value) -> serde_json::Value {
-    let staff_node = row.get("staff");
-    let staff_obj = match staff_node {
-        Some(serde_json::Value::Object(obj)) => Some(serde_json::Value::Object(obj.clone())),
-        Some(serde_json::Value::Array(arr)) => arr
-            .first()
-            .and_then(|v| v.as_object().cloned())
-            .map(serde_json::Value::Object),
-        _ => None,
-    };
-    let staff_first = staff_obj
-        .as_ref()
-        .and_then(|s| s.get("first_name"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-    let staff_last = staff_obj
-        .as_ref()
-        .and_then(|s| s.get("last_name"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-    let staff_name = format!("{} {}", staff_first, staff_last).trim().to_string();
-
-    serde_json::json!({
-        "id": row.get("id").cloned().unwrap_or(serde_json::Value::Null),
-        "staffId": row.get("staff_id").cloned().unwrap_or(serde_json::Value::Null),
-        "branchId": row.get("branch_id").cloned().unwrap_or(serde_json::Value::Null),
-        "startTime": row.get("start_time").cloned().unwrap_or(serde_json::Value::Null),
-        "endTime": row.get("end_time").cloned().unwrap_or(serde_json::Value::Null),
-        "breakStart": row.get("break_start").cloned().unwrap_or(serde_json::Value::Null),
-        "breakEnd": row.get("break_end").cloned().unwrap_or(serde_json::Value::Null),
-        "status": row.get("status").cloned().unwrap_or(serde_json::Value::Null),
-        "notes": row.get("notes").cloned().unwrap_or(serde_json::Value::Null),
-        "staffName": if staff_name.is_empty() { "Unknown".to_string() } else { staff_name },
-        "staffCode": staff_obj
-            .as_ref()
-            .and_then(|s| s.get("staff_code"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-    })
-}
-
+/// Open a shift. A clock-in that the schedule check flags (too early, or
+/// no scheduled shift) or an opening float outside the tolerance comes back
+/// with `approvalRequired`; the retry carries `managerPin`, checked against
+/// the admin PIN, to approve it, plus `openingFloatNote` for a float.
 #[tauri::command]
 pub async fn shift_open(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
-    let payload = arg0.ok_or("Missing shift payload")?;
-    let result = shift_service::open_shift(&db, &payload)?;
-    if let Some(shift_id) = result.get("shiftId").and_then(serde_json::Value::as_str) {
-        schedule_immediate_sync(app.clone(), "shift", shift_id.to_string());
+) -> Result<serde_json::Value, PosError> {
+    let payload = arg0.ok_or_else(|| PosError::validation("payload", "Missing shift payload"))?;
+    let manager_approved = match value_str(&payload, &["managerPin", "manager_pin"]) {
+        Some(pin) => {
+            if !crate::auth::verify_privileged_pin_with_lockout(&pin, "admin", &db, &auth_state)
+                .map_err(PosError::Unauthorized)?
+            {
+                return Err(PosError::Unauthorized("Invalid manager PIN".into()));
+            }
+            true
+        }
+        None => false,
+    };
+    let key = idempotency::request_key(&payload);
+    idempotency::run_once(&db, "shift_open", key, || async {
+        let result =
+            shift_service::open_shift_with_manager_approval(&db, &payload, manager_approved)?;
+        if let Some(shift_id) = result.get("shiftId").and_then(serde_json::Value::as_str) {
+            schedule_immediate_sync(app.clone(), "shift", shift_id.to_string());
+        }
+        let _ = app.emit(
+            "shift_updated",
+            serde_json::json!({
+                "action": "open",
+                "shift": result
+            }),
+        );
+        Ok::<_, PosError>(result)
+    })
+    .await
+}
+
+/// Tabs still open in the branch block a cashier or manager shift close.
+/// A `managerPin` (checked against the admin PIN) overrides, recorded on
+/// each open tab's order timeline.
+fn guard_open_tabs(
+    db: &db::DbState,
+    auth_state: &crate::auth::AuthState,
+    payload: &serde_json::Value,
+) -> Result<Option<serde_json::Value>, PosError> {
+    let Some(shift_id) = value_str(payload, &["shiftId", "shift_id"]) else {
+        return Ok(None);
+    };
+    let open_tabs = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let shift: Option<(String, String)> = conn
+            .query_row(
+                "SELECT COALESCE(branch_id, ''), role_type
+                 FROM staff_shifts WHERE id = ?1 AND status = 'active'",
+                params![shift_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| format!("load shift for tab check: {e}"))?;
+        // A missing shift is reported by the close itself.
+        let Some((branch_id, role_type)) = shift else {
+            return Ok(None);
+        };
+        if role_type != "cashier" && role_type != "manager" {
+            return Ok(None);
+        }
+        let branch_id = Some(branch_id.as_str()).filter(|id| !id.is_empty());
+        tabs::list_open(&conn, branch_id, Utc::now())?
+    };
+    if open_tabs.is_empty() {
+        return Ok(None);
     }
-    let _ = app.emit(
-        "shift_updated",
-        serde_json::json!({
-            "action": "open",
-            "shift": result
-        }),
+    let Some(pin) = value_str(payload, &["managerPin", "manager_pin"]) else {
+        return Ok(Some(serde_json::json!({
+            "success": false,
+            "error": format!(
+                "Cannot close shift: {} tab(s) still open. Close them or have a manager override.",
+                open_tabs.len()
+            ),
+            "overrideRequired": true,
+            "openTabs": open_tabs,
+        })));
+    };
+    if !crate::auth::verify_privileged_pin_with_lockout(&pin, "admin", db, auth_state)
+        .map_err(PosError::Unauthorized)?
+    {
+        return Err(PosError::Unauthorized("Invalid manager PIN".into()));
+    }
+    let manager = crate::auth::current_staff_id(auth_state);
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    tabs::record_shift_close_override(&conn, &open_tabs, &shift_id, manager.as_deref());
+    warn!(
+        shift_id = %shift_id,
+        open_tabs = open_tabs.len(),
+        "Shift closed over open tabs by manager override"
     );
-    Ok(result)
+    Ok(None)
 }
 
 #[tauri::command]
 pub async fn shift_close(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
-    let payload = arg0.ok_or("Missing shift close payload")?;
+) -> Result<serde_json::Value, PosError> {
+    let payload =
+        arg0.ok_or_else(|| PosError::validation("payload", "Missing shift close payload"))?;
+    if let Some(blocked) = guard_open_tabs(&db, &auth_state, &payload)? {
+        return Ok(blocked);
+    }
     let requested_shift_id = value_str(&payload, &["shiftId", "shift_id"]);
     let mut result = shift_service::close_shift(&db, &payload)?;
     let success = result
@@ -735,27 +815,33 @@ pub async fn shift_close(
 pub async fn shift_get_active(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_shift_staff_payload(arg0)?;
-    shift_service::get_active(&db, &payload.staff_id)
+    Ok(shift_service::get_active(&db, &payload.staff_id)?)
 }
 
 #[tauri::command]
 pub async fn shift_get_by_id(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_cashier_shift_payload(arg0)?;
-    shift_service::get_shift_by_id(&db, &payload.cashier_shift_id)
+    Ok(shift_service::get_shift_by_id(
+        &db,
+        &payload.cashier_shift_id,
+    )?)
 }
 
 #[tauri::command]
 pub async fn shift_get_sync_state(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_cashier_shift_payload(arg0)?;
-    shift_service::get_shift_sync_state(&db, &payload.cashier_shift_id)
+    Ok(shift_service::get_shift_sync_state(
+        &db,
+        &payload.cashier_shift_id,
+    )?)
 }
 
 #[tauri::command]
@@ -763,18 +849,25 @@ pub async fn shift_get_active_by_terminal(
     arg0: Option<serde_json::Value>,
     arg1: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_shift_branch_terminal_payload(arg0, arg1)?;
-    shift_service::get_active_by_terminal(&db, &payload.branch_id, &payload.terminal_id)
+    Ok(shift_service::get_active_by_terminal(
+        &db,
+        &payload.branch_id,
+        &payload.terminal_id,
+    )?)
 }
 
 #[tauri::command]
 pub async fn shift_get_active_by_terminal_loose(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_shift_terminal_payload(arg0)?;
-    shift_service::get_active_by_terminal_loose(&db, &payload.terminal_id)
+    Ok(shift_service::get_active_by_terminal_loose(
+        &db,
+        &payload.terminal_id,
+    )?)
 }
 
 #[tauri::command]
@@ -782,9 +875,13 @@ pub async fn shift_get_active_cashier_by_terminal(
     arg0: Option<serde_json::Value>,
     arg1: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_shift_branch_terminal_payload(arg0, arg1)?;
-    shift_service::get_active_cashier_by_terminal(&db, &payload.branch_id, &payload.terminal_id)
+    Ok(shift_service::get_active_cashier_by_terminal(
+        &db,
+        &payload.branch_id,
+        &payload.terminal_id,
+    )?)
 }
 
 #[tauri::command]
@@ -792,18 +889,25 @@ pub async fn shift_get_check_in_eligibility(
     arg0: Option<serde_json::Value>,
     arg1: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_shift_branch_terminal_payload(arg0, arg1)?;
-    shift_service::get_check_in_eligibility(&db, &payload.branch_id, &payload.terminal_id)
+    Ok(shift_service::get_check_in_eligibility(
+        &db,
+        &payload.branch_id,
+        &payload.terminal_id,
+    )?)
 }
 
 #[tauri::command]
 pub async fn shift_get_active_cashier_by_terminal_loose(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_shift_terminal_payload(arg0)?;
-    shift_service::get_active_cashier_by_terminal_loose(&db, &payload.terminal_id)
+    Ok(shift_service::get_active_cashier_by_terminal_loose(
+        &db,
+        &payload.terminal_id,
+    )?)
 }
 
 #[tauri::command]
@@ -811,17 +915,17 @@ pub async fn shift_get_summary(
     arg0: Option<serde_json::Value>,
     arg1: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_shift_summary_payload(arg0, arg1)?;
     let _skip_backfill = payload.skip_backfill;
-    shift_service::get_shift_summary(&db, &payload.shift_id)
+    Ok(shift_service::get_shift_summary(&db, &payload.shift_id)?)
 }
 
 #[tauri::command]
 pub async fn shift_print_checkout(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_shift_print_checkout_payload(arg0)?;
     let summary = match shift_service::get_shift_summary(&db, &payload.shift_id) {
         Ok(summary) => summary,
@@ -904,8 +1008,8 @@ pub async fn shift_record_expense(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
-    let payload = arg0.ok_or("Missing expense payload")?;
+) -> Result<serde_json::Value, PosError> {
+    let payload = arg0.ok_or_else(|| PosError::validation("payload", "Missing expense payload"))?;
     let result = shift_service::record_expense(&db, &payload)?;
     if let Some(expense_id) = result.get("expenseId").and_then(serde_json::Value::as_str) {
         schedule_immediate_sync(app, "shift_expense", expense_id.to_string());
@@ -919,7 +1023,7 @@ pub async fn shift_delete_expense(
     arg1: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let parsed = parse_shift_expense_delete_payload(arg0, arg1)?;
     let payload = serde_json::json!({
         "expenseId": parsed.expense_id,
@@ -936,9 +1040,133 @@ pub async fn shift_delete_expense(
 pub async fn shift_get_expenses(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
+    let payload = parse_shift_summary_payload(arg0, None)?;
+    Ok(shift_service::get_expenses(&db, &payload.shift_id)?)
+}
+
+/// Record a cash-in or cash-out against the shift's open drawer and print
+/// its chit. A cash-out above `cash.cash_out_approval_threshold` comes back
+/// with `approvalRequired`; the retry carries `managerPin`.
+fn record_drawer_transaction(
+    direction: Direction,
+    payload: &serde_json::Value,
+    db: &db::DbState,
+    auth_state: &crate::auth::AuthState,
+    app: &tauri::AppHandle,
+) -> Result<serde_json::Value, PosError> {
+    let request = drawer_transactions::Request::from_payload(payload)
+        .map_err(|e| PosError::validation("payload", e))?;
+    let needs_approval = db.read(|conn| {
+        Ok(drawer_transactions::needs_approval(
+            conn,
+            direction,
+            request.amount,
+        ))
+    })?;
+    let staff_id = crate::auth::current_staff_id(auth_state);
+    let approved_by = if needs_approval {
+        let Some(pin) = value_str(payload, &["managerPin", "manager_pin"]) else {
+            let threshold = db.read(|conn| Ok(drawer_transactions::approval_threshold(conn)))?;
+            return Ok(serde_json::json!({
+                "success": false,
+                "error": "Cash-out above the approval threshold needs a manager",
+                "approvalRequired": true,
+                "threshold": threshold.map(|cents| cents.to_f64_dp2()),
+            }));
+        };
+        if !crate::auth::verify_privileged_pin_with_lockout(&pin, "admin", db, auth_state)
+            .map_err(PosError::Unauthorized)?
+        {
+            return Err(PosError::Unauthorized("Invalid manager PIN".into()));
+        }
+        staff_id.clone()
+    } else {
+        None
+    };
+
+    let now = Utc::now().to_rfc3339();
+    let mut transaction = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute_batch("BEGIN IMMEDIATE")
+            .map_err(|e| format!("begin transaction: {e}"))?;
+        match drawer_transactions::record(
+            &conn,
+            direction,
+            &request,
+            staff_id.as_deref(),
+            approved_by.as_deref(),
+            &now,
+        ) {
+            Ok(transaction) => {
+                conn.execute_batch("COMMIT")
+                    .map_err(|e| format!("commit drawer transaction: {e}"))?;
+                transaction
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                return Err(e.into());
+            }
+        }
+    };
+
+    let id = value_str(&transaction, &["id"]).unwrap_or_default();
+    info!(
+        transaction_id = %id,
+        shift_id = %request.shift_id,
+        direction = direction.as_str(),
+        amount_cents = request.amount.as_i64(),
+        "Drawer transaction recorded"
+    );
+    match print::enqueue_print_job(db, drawer_transactions::PRINT_ENTITY_TYPE, &id, None) {
+        Ok(job) => transaction["printJob"] = job,
+        Err(error) => {
+            warn!(transaction_id = %id, error = %error, "Failed to enqueue drawer chit print job");
+        }
+    }
+    schedule_immediate_sync(app.clone(), "drawer_transaction", id);
+    let _ = app.emit(
+        "shift_updated",
+        serde_json::json!({
+            "action": direction.as_str(),
+            "shiftId": request.shift_id,
+        }),
+    );
+    Ok(serde_json::json!({ "success": true, "transaction": transaction }))
+}
+
+#[tauri::command]
+pub async fn drawer_cash_in(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, PosError> {
+    let payload = arg0.ok_or_else(|| PosError::validation("payload", "Missing cash-in payload"))?;
+    record_drawer_transaction(Direction::In, &payload, &db, &auth_state, &app)
+}
+
+#[tauri::command]
+pub async fn drawer_cash_out(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    auth_state: tauri::State<'_, crate::auth::AuthState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, PosError> {
+    let payload =
+        arg0.ok_or_else(|| PosError::validation("payload", "Missing cash-out payload"))?;
+    record_drawer_transaction(Direction::Out, &payload, &db, &auth_state, &app)
+}
+
+#[tauri::command]
+pub async fn drawer_get_transactions(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_shift_summary_payload(arg0, None)?;
-    shift_service::get_expenses(&db, &payload.shift_id)
+    let transactions =
+        db.read(|conn| drawer_transactions::list_for_shift(conn, &payload.shift_id))?;
+    Ok(serde_json::json!({ "success": true, "transactions": transactions }))
 }
 
 #[tauri::command]
@@ -946,7 +1174,7 @@ pub async fn shift_record_staff_payment(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let parsed = parse_staff_payment_mutation_payload(arg0)?;
     let payload = serde_json::json!({
         "cashierShiftId": parsed.cashier_shift_id,
@@ -967,9 +1195,11 @@ pub async fn shift_update_staff_payment(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let parsed = parse_staff_payment_mutation_payload(arg0)?;
-    let payment_id = parsed.payment_id.ok_or("Missing paymentId")?;
+    let payment_id = parsed
+        .payment_id
+        .ok_or_else(|| PosError::validation("paymentId", "Missing paymentId"))?;
     let payload = serde_json::json!({
         "paymentId": payment_id,
         "cashierShiftId": parsed.cashier_shift_id,
@@ -990,7 +1220,7 @@ pub async fn shift_delete_staff_payment(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let parsed = parse_staff_payment_delete_payload(arg0)?;
     let payload = serde_json::json!({
         "paymentId": parsed.payment_id,
@@ -1007,12 +1237,10 @@ pub async fn shift_delete_staff_payment(
 pub async fn shift_get_staff_payments(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_cashier_shift_payload(arg0)?;
     let cashier_shift_id = payload.cashier_shift_id;
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    ensure_staff_payments_table(&conn)?;
-
     let mut stmt = conn
         .prepare(
             "SELECT sp.id, sp.cashier_shift_id, sp.paid_to_staff_id, sp.amount, sp.payment_type, sp.notes, sp.created_at, sp.updated_at,
@@ -1055,15 +1283,13 @@ pub async fn shift_get_staff_payments(
 pub async fn shift_get_staff_payments_by_staff(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_staff_payments_by_staff_payload(arg0)?;
     let staff_id = payload.staff_id;
     let date_from = payload.date_from;
     let date_to = payload.date_to;
 
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    ensure_staff_payments_table(&conn)?;
-
     let query =
         "SELECT sp.id, sp.cashier_shift_id, sp.paid_to_staff_id, sp.amount, sp.payment_type, sp.notes,
                 sp.created_at, sp.updated_at,
@@ -1101,12 +1327,11 @@ pub async fn shift_get_staff_payment_total_for_date(
     arg0: Option<serde_json::Value>,
     arg1: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<f64, String> {
+) -> Result<f64, PosError> {
     let payload = parse_staff_date_payload(arg0, arg1)?;
     let staff_id = payload.staff_id;
     let date = payload.date;
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    ensure_staff_payments_table(&conn)?;
     let total: f64 = conn
         .query_row(
             "SELECT COALESCE(SUM(amount), 0) FROM staff_payments
@@ -1122,7 +1347,7 @@ pub async fn shift_get_staff_payment_total_for_date(
 pub async fn shift_backfill_driver_earnings(
     _arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
     // Read legacy JSON array from local_settings
@@ -1221,73 +1446,184 @@ pub async fn shift_backfill_driver_earnings(
     }))
 }
 
+/// Scheduled shifts starting in `[startDate, endDate]`, fetched from
+/// Supabase into the local schedule cache. When the fetch fails the cached
+/// copy is returned, as long as the schedule has been refreshed before.
 #[tauri::command]
 pub async fn shift_get_scheduled_shifts(
     arg0: Option<serde_json::Value>,
-) -> Result<serde_json::Value, String> {
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, PosError> {
     let payload = arg0.unwrap_or(serde_json::json!({}));
-    let branch_id = value_str(&payload, &["branchId", "branch_id"]).ok_or("Missing branchId")?;
-    let start_date =
-        value_str(&payload, &["startDate", "start_date"]).ok_or("Missing startDate")?;
-    let end_date = value_str(&payload, &["endDate", "end_date"]).ok_or("Missing endDate")?;
+    let branch_id = value_str(&payload, &["branchId", "branch_id"])
+        .ok_or_else(|| PosError::validation("branchId", "Missing branchId"))?;
+    let from = value_str(&payload, &["startDate", "start_date"])
+        .ok_or_else(|| PosError::validation("startDate", "Missing startDate"))?;
+    let to = value_str(&payload, &["endDate", "end_date"])
+        .ok_or_else(|| PosError::validation("endDate", "Missing endDate"))?;
+    let from = schedule::parse_timestamp(&from)
+        .ok_or_else(|| PosError::validation("startDate", format!("Invalid startDate: {from}")))?;
+    let to = schedule::parse_timestamp(&to)
+        .ok_or_else(|| PosError::validation("endDate", format!("Invalid endDate: {to}")))?;
     let staff_id = value_str(&payload, &["staffId", "staff_id"]);
 
-    let mut params = vec![
-        (
-            "select",
-            "id,staff_id,branch_id,start_time,end_time,break_start,break_end,status,notes,staff(id,first_name,last_name,staff_code)"
-                .to_string(),
-        ),
-        ("branch_id", format!("eq.{branch_id}")),
-        ("start_time", format!("gte.{start_date}")),
-        ("start_time", format!("lte.{end_date}")),
-        ("order", "start_time.asc".to_string()),
-    ];
-    if let Some(sid) = staff_id {
-        params.push(("staff_id", format!("eq.{sid}")));
-    }
-
-    let raw = fetch_supabase_rows("salon_staff_shifts", &params).await?;
-    let arr = raw.as_array().cloned().unwrap_or_default();
-    let mapped: Vec<serde_json::Value> = arr.iter().map(map_scheduled_shift_row).collect();
-    Ok(serde_json::json!(mapped))
+    fetch_scheduled_shifts(
+        ScheduleWindow {
+            branch_id: &branch_id,
+            staff_id: staff_id.as_deref(),
+            from,
+            to,
+        },
+        &db,
+        &app,
+        "shift_get_scheduled_shifts",
+    )
+    .await
 }
 
 #[tauri::command]
 pub async fn shift_get_today_scheduled_shifts(
     arg0: Option<serde_json::Value>,
-) -> Result<serde_json::Value, String> {
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_branch_payload(arg0)?;
-    let branch_id = payload.branch_id;
-    let now_local = Local::now();
-    let y = now_local.year();
-    let m = now_local.month();
-    let d = now_local.day();
-    let start_local = Local
-        .with_ymd_and_hms(y, m, d, 0, 0, 0)
-        .single()
-        .ok_or("Failed to compute local start of day")?;
-    let end_local = Local
-        .with_ymd_and_hms(y, m, d, 23, 59, 59)
-        .single()
-        .ok_or("Failed to compute local end of day")?;
-
-    let params = vec![
-        (
-            "select",
-            "id,staff_id,branch_id,start_time,end_time,break_start,break_end,status,notes,staff(id,first_name,last_name,staff_code)"
-                .to_string(),
-        ),
-        ("branch_id", format!("eq.{branch_id}")),
-        ("start_time", format!("gte.{}", start_local.to_rfc3339())),
-        ("start_time", format!("lte.{}", end_local.to_rfc3339())),
-        ("order", "start_time.asc".to_string()),
-    ];
-
-    let raw = fetch_supabase_rows("salon_staff_shifts", &params).await?;
-    let arr = raw.as_array().cloned().unwrap_or_default();
-    let mapped: Vec<serde_json::Value> = arr.iter().map(map_scheduled_shift_row).collect();
-    Ok(serde_json::json!(mapped))
+    let (from, to) = schedule::local_day_bounds(Local::now().date_naive())?;
+
+    fetch_scheduled_shifts(
+        ScheduleWindow {
+            branch_id: &payload.branch_id,
+            staff_id: None,
+            from,
+            to,
+        },
+        &db,
+        &app,
+        "shift_get_today_scheduled_shifts",
+    )
+    .await
+}
+
+/// Per-staff scheduled vs actual clock-in/out times for scheduled shifts
+/// starting in `[startDate, endDate]` (local dates, inclusive), read from the
+/// local schedule cache. `branchId` and `staffId` narrow the report.
+#[tauri::command]
+pub async fn shift_get_schedule_adherence(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, PosError> {
+    let payload = arg0.unwrap_or(serde_json::json!({}));
+    let parse_date = |keys: &[&str], field: &str| -> Result<NaiveDate, PosError> {
+        let raw = value_str(&payload, keys)
+            .ok_or_else(|| PosError::validation(field, format!("Missing {field}")))?;
+        NaiveDate::parse_from_str(&raw, "%Y-%m-%d")
+            .map_err(|_| PosError::validation(field, format!("Invalid {field}: {raw}")))
+    };
+    let start_date = parse_date(&["startDate", "start_date"], "startDate")?;
+    let end_date = parse_date(&["endDate", "end_date"], "endDate")?;
+    if end_date < start_date {
+        return Err(PosError::validation(
+            "endDate",
+            "endDate must not be before startDate",
+        ));
+    }
+    let (from, _) = schedule::local_day_bounds(start_date)?;
+    let (_, to) = schedule::local_day_bounds(end_date)?;
+    let branch_id = value_str(&payload, &["branchId", "branch_id"]);
+    let staff_id = value_str(&payload, &["staffId", "staff_id"]);
+
+    let mut report = db.read(|conn| {
+        schedule::adherence(
+            conn,
+            branch_id.as_deref(),
+            staff_id.as_deref(),
+            from,
+            to,
+            Utc::now(),
+        )
+    })?;
+    report["success"] = serde_json::json!(true);
+    Ok(report)
+}
+
+/// Opening floats of the last 30 days with their deviation from the
+/// standing float, per terminal. `terminalId` narrows the report; the
+/// current float settings come back as `config` for pre-filling.
+#[tauri::command]
+pub async fn shift_get_float_history(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, PosError> {
+    let payload = match arg0 {
+        Some(serde_json::Value::String(terminal_id)) => serde_json::json!({
+            "terminalId": terminal_id
+        }),
+        Some(value) => value,
+        None => serde_json::json!({}),
+    };
+    let terminal_id = value_str(&payload, &["terminalId", "terminal_id"]);
+    let mut report =
+        db.read(|conn| opening_float::history(conn, terminal_id.as_deref(), Utc::now()))?;
+    report["success"] = serde_json::json!(true);
+    Ok(report)
+}
+
+struct ScheduleWindow<'a> {
+    branch_id: &'a str,
+    staff_id: Option<&'a str>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+}
+
+async fn fetch_scheduled_shifts(
+    window: ScheduleWindow<'_>,
+    db: &db::DbState,
+    app: &tauri::AppHandle,
+    source: &str,
+) -> Result<serde_json::Value, PosError> {
+    let mut query = schedule::remote_query(window.branch_id)
+        .filter("start_time", format!("gte.{}", window.from.to_rfc3339()))
+        .filter("start_time", format!("lte.{}", window.to.to_rfc3339()));
+    if let Some(staff_id) = window.staff_id {
+        query = query.filter("staff_id", format!("eq.{staff_id}"));
+    }
+
+    match query.fetch_all().await {
+        Ok(rows) => {
+            let now = Utc::now().to_rfc3339();
+            db.write(|conn| {
+                schedule::store_remote(
+                    conn,
+                    window.branch_id,
+                    window.staff_id,
+                    window.from,
+                    window.to,
+                    &rows,
+                    &now,
+                )
+            })?;
+        }
+        Err(error) => {
+            supabase::handle_auth_failure(Some(db), app, source, &error);
+            let has_cache = db.read(|conn| Ok(schedule::synced_at(conn).is_some()))?;
+            if !has_cache {
+                return Err(error.into());
+            }
+            warn!(source, error = %error, "Scheduled shifts fetch failed; serving cached schedule");
+        }
+    }
+
+    let cached = db.read(|conn| {
+        schedule::list(
+            conn,
+            Some(window.branch_id),
+            window.staff_id,
+            window.from,
+            window.to,
+        )
+    })?;
+    Ok(serde_json::json!(cached))
 }
 
 #[cfg(test)]
@@ -1,9 +1,10 @@
-use chrono::Local;
+use chrono::{Local, Utc};
 use serde::Deserialize;
 use tauri::Emitter;
 use zeroize::Zeroizing;
 
-use crate::{api, db, storage, sync, value_i64};
+use crate::error::PosError;
+use crate::{api, connectivity, db, realtime, remote_cache, storage, sync, value_i64};
 
 #[derive(Debug, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -137,7 +138,7 @@ fn calculate_appointment_metrics(
 
 fn parse_remove_invalid_orders_payload(
     arg0: Option<serde_json::Value>,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, PosError> {
     let payload = match arg0 {
         Some(serde_json::Value::Array(order_ids)) => serde_json::json!({
             "orderIds": order_ids
@@ -150,8 +151,12 @@ fn parse_remove_invalid_orders_payload(
         None => serde_json::json!({}),
     };
 
-    let parsed: SyncRemoveInvalidOrdersPayload = serde_json::from_value(payload)
-        .map_err(|e| format!("Invalid remove-invalid-orders payload: {e}"))?;
+    let parsed: SyncRemoveInvalidOrdersPayload = serde_json::from_value(payload).map_err(|e| {
+        PosError::validation(
+            "payload",
+            format!("Invalid remove-invalid-orders payload: {e}"),
+        )
+    })?;
 
     let mut seen = std::collections::HashSet::new();
     let mut order_ids = Vec::new();
@@ -166,7 +171,7 @@ fn parse_remove_invalid_orders_payload(
     }
 
     if order_ids.is_empty() {
-        return Err("Missing orderIds".into());
+        return Err(PosError::validation("orderIds", "Missing orderIds"));
     }
     Ok(order_ids)
 }
@@ -219,7 +224,7 @@ fn load_zeroized_pos_api_key_optional() -> Option<Zeroizing<String>> {
     ))
 }
 
-fn parse_retry_financial_item_payload(arg0: Option<serde_json::Value>) -> Result<i64, String> {
+fn parse_retry_financial_item_payload(arg0: Option<serde_json::Value>) -> Result<i64, PosError> {
     let payload = match arg0 {
         Some(serde_json::Value::String(sync_id)) => serde_json::json!({
             "syncId": sync_id
@@ -229,13 +234,15 @@ fn parse_retry_financial_item_payload(arg0: Option<serde_json::Value>) -> Result
         None => serde_json::json!({}),
     };
 
-    let parsed: SyncRetryFinancialItemPayload = serde_json::from_value(payload.clone())
-        .map_err(|e| format!("Invalid retry financial payload: {e}"))?;
+    let parsed: SyncRetryFinancialItemPayload =
+        serde_json::from_value(payload.clone()).map_err(|e| {
+            PosError::validation("payload", format!("Invalid retry financial payload: {e}"))
+        })?;
 
     parse_retry_financial_queue_id(&parsed.sync_id)
         .or_else(|| value_i64(&payload, &["syncId", "sync_id", "id"]))
         .filter(|id| *id > 0)
-        .ok_or_else(|| "Missing sync item id".into())
+        .ok_or_else(|| PosError::validation("syncId", "Missing sync item id"))
 }
 
 pub(crate) fn query_financial_queue_items(
@@ -762,7 +769,7 @@ pub(crate) fn collect_financial_integrity(db: &db::DbState) -> Result<serde_json
 fn parse_update_room_status_payload(
     arg0: Option<serde_json::Value>,
     arg1: Option<String>,
-) -> Result<SyncUpdateRoomStatusPayload, String> {
+) -> Result<SyncUpdateRoomStatusPayload, PosError> {
     let payload = match arg0 {
         Some(serde_json::Value::Object(mut obj)) => {
             if obj.get("status").is_none() {
@@ -782,15 +789,16 @@ fn parse_update_room_status_payload(
         }),
     };
 
-    let mut parsed: SyncUpdateRoomStatusPayload =
-        serde_json::from_value(payload).map_err(|e| format!("Invalid room status payload: {e}"))?;
+    let mut parsed: SyncUpdateRoomStatusPayload = serde_json::from_value(payload).map_err(|e| {
+        PosError::validation("payload", format!("Invalid room status payload: {e}"))
+    })?;
     parsed.room_id = parsed.room_id.trim().to_string();
     parsed.status = parsed.status.trim().to_string();
     if parsed.room_id.is_empty() {
-        return Err("Missing roomId".into());
+        return Err(PosError::validation("roomId", "Missing roomId"));
     }
     if parsed.status.is_empty() {
-        return Err("Missing status".into());
+        return Err(PosError::validation("status", "Missing status"));
     }
     Ok(parsed)
 }
@@ -798,7 +806,7 @@ fn parse_update_room_status_payload(
 fn parse_update_drive_thru_order_status_payload(
     arg0: Option<serde_json::Value>,
     arg1: Option<String>,
-) -> Result<SyncUpdateDriveThruOrderStatusPayload, String> {
+) -> Result<SyncUpdateDriveThruOrderStatusPayload, PosError> {
     let payload = match arg0 {
         Some(serde_json::Value::Object(mut obj)) => {
             if obj.get("status").is_none() {
@@ -819,14 +827,22 @@ fn parse_update_drive_thru_order_status_payload(
     };
 
     let mut parsed: SyncUpdateDriveThruOrderStatusPayload = serde_json::from_value(payload)
-        .map_err(|e| format!("Invalid drive-through status payload: {e}"))?;
+        .map_err(|e| {
+            PosError::validation(
+                "payload",
+                format!("Invalid drive-through status payload: {e}"),
+            )
+        })?;
     parsed.drive_thru_order_id = parsed.drive_thru_order_id.trim().to_string();
     parsed.status = parsed.status.trim().to_string();
     if parsed.drive_thru_order_id.is_empty() {
-        return Err("Missing drive-through order ID".into());
+        return Err(PosError::validation(
+            "driveThruOrderId",
+            "Missing drive-through order ID",
+        ));
     }
     if parsed.status.is_empty() {
-        return Err("Missing status".into());
+        return Err(PosError::validation("status", "Missing status"));
     }
     Ok(parsed)
 }
@@ -857,40 +873,73 @@ async fn emit_sync_status_snapshot(
 pub async fn sync_get_status(
     db: tauri::State<'_, db::DbState>,
     sync_state: tauri::State<'_, std::sync::Arc<sync::SyncState>>,
-) -> Result<serde_json::Value, String> {
-    sync::get_sync_status(&db, &sync_state)
+) -> Result<serde_json::Value, PosError> {
+    Ok(sync::get_sync_status(&db, &sync_state)?)
 }
 
 #[tauri::command]
-pub async fn sync_get_network_status(app: tauri::AppHandle) -> Result<serde_json::Value, String> {
+pub async fn sync_get_network_status(app: tauri::AppHandle) -> Result<serde_json::Value, PosError> {
     let status = sync::check_network_status().await;
     let _ = app.emit("network_status", status.clone());
     Ok(status)
 }
 
+#[tauri::command]
+pub async fn network_get_history(
+    connectivity: tauri::State<'_, std::sync::Arc<connectivity::ConnectivityState>>,
+) -> Result<serde_json::Value, PosError> {
+    Ok(serde_json::json!({
+        "isOnline": connectivity.is_online(),
+        "transitions": connectivity.history(),
+    }))
+}
+
+#[tauri::command]
+pub async fn realtime_get_status(
+    realtime_state: tauri::State<'_, std::sync::Arc<realtime::RealtimeState>>,
+) -> Result<serde_json::Value, PosError> {
+    Ok(realtime_state.status_json())
+}
+
 #[tauri::command]
 pub async fn sync_force(
     db: tauri::State<'_, db::DbState>,
     sync_state: tauri::State<'_, std::sync::Arc<sync::SyncState>>,
     app: tauri::AppHandle,
-) -> Result<(), String> {
-    match sync::force_sync(&db, &sync_state, &app).await {
-        Ok(()) => {
-            let _ = app.emit("sync_complete", serde_json::json!({ "trigger": "manual" }));
-            Ok(())
-        }
-        Err(e) => {
-            let _ = app.emit("sync_error", serde_json::json!({ "error": e }));
-            Err(e)
+) -> Result<(), PosError> {
+    crate::diagnostics::metrics::track("sync_force", async move {
+        let forced = crate::correlation::scope(
+            crate::correlation::new_id(),
+            sync::force_sync(&db, &sync_state, &app),
+        )
+        .await;
+        match forced {
+            Ok(stats) => {
+                let _ = app.emit(
+                    "sync_complete",
+                    serde_json::json!({
+                        "trigger": "manual",
+                        "batchesSent": stats.batches_sent,
+                        "itemsSynced": stats.items_synced,
+                        "itemsFailed": stats.items_failed,
+                    }),
+                );
+                Ok(())
+            }
+            Err(e) => {
+                let _ = app.emit("sync_error", serde_json::json!({ "error": e }));
+                Err(e.into())
+            }
         }
-    }
+    })
+    .await
 }
 
 #[tauri::command]
 pub async fn sync_validate_pending_orders(
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
-    sync::validate_pending_orders(&db)
+) -> Result<serde_json::Value, PosError> {
+    Ok(sync::validate_pending_orders(&db)?)
 }
 
 #[tauri::command]
@@ -899,38 +948,38 @@ pub async fn sync_remove_invalid_orders(
     db: tauri::State<'_, db::DbState>,
     sync_state: tauri::State<'_, std::sync::Arc<sync::SyncState>>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let order_ids = parse_remove_invalid_orders_payload(arg0)?;
     let result = sync::remove_invalid_orders(&db, order_ids);
     if result.is_ok() {
         emit_sync_status_snapshot(&app, &db, &sync_state).await;
     }
-    result
+    Ok(result?)
 }
 
 #[tauri::command]
 pub async fn sync_get_financial_stats(
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
-    sync::get_financial_stats(&db)
+) -> Result<serde_json::Value, PosError> {
+    Ok(sync::get_financial_stats(&db)?)
 }
 
 #[tauri::command]
 pub async fn sync_get_failed_financial_items(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let limit = parse_failed_financial_items_limit(arg0);
-    query_financial_queue_items(limit, &db)
+    Ok(query_financial_queue_items(limit, &db)?)
 }
 
 #[tauri::command]
 pub async fn sync_get_financial_queue_items(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let limit = parse_failed_financial_items_limit(arg0);
-    query_financial_queue_items(limit, &db)
+    Ok(query_financial_queue_items(limit, &db)?)
 }
 
 #[tauri::command]
@@ -939,7 +988,7 @@ pub async fn sync_retry_financial_item(
     db: tauri::State<'_, db::DbState>,
     sync_state: tauri::State<'_, std::sync::Arc<sync::SyncState>>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let id = parse_retry_financial_item_payload(arg0)?;
     sync::retry_financial_queue_item(&db, id)?;
 
@@ -953,7 +1002,7 @@ pub async fn sync_retry_all_failed_financial(
     db: tauri::State<'_, db::DbState>,
     sync_state: tauri::State<'_, std::sync::Arc<sync::SyncState>>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     // Wave 2a C1: the previous implementation correlated the two UPDATEs
     // via `updated_at = ?1` (where `?1` is a freshly-computed RFC3339
     // timestamp). When any other writer happened to stamp the same
@@ -1103,7 +1152,7 @@ pub async fn sync_retry_all_failed_financial(
             }
             Err(e) => {
                 let _ = conn.execute_batch("ROLLBACK");
-                return Err(e);
+                return Err(e.into());
             }
         }
     };
@@ -1119,7 +1168,7 @@ pub async fn sync_retry_all_failed_financial(
 #[tauri::command]
 pub async fn sync_get_unsynced_financial_summary(
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let total: i64 = conn
         .query_row(
@@ -1136,8 +1185,8 @@ pub async fn sync_get_unsynced_financial_summary(
 #[tauri::command]
 pub async fn sync_validate_financial_integrity(
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
-    collect_financial_integrity(&db)
+) -> Result<serde_json::Value, PosError> {
+    Ok(collect_financial_integrity(&db)?)
 }
 
 #[tauri::command]
@@ -1145,7 +1194,7 @@ pub async fn sync_requeue_orphaned_financial(
     db: tauri::State<'_, db::DbState>,
     sync_state: tauri::State<'_, std::sync::Arc<sync::SyncState>>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let admin_url = storage::get_credential("admin_url")
         .ok_or_else(|| "Admin URL not configured".to_string())?;
     let api_key = load_zeroized_pos_api_key()?;
@@ -1172,6 +1221,7 @@ pub async fn sync_requeue_orphaned_financial(
 
 #[tauri::command]
 pub async fn sync_clear_all_orders(
+    arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
     sync_state: tauri::State<'_, std::sync::Arc<sync::SyncState>>,
     auth_state: tauri::State<'_, crate::auth::AuthState>,
@@ -1183,6 +1233,12 @@ pub async fn sync_clear_all_orders(
         &db,
         &auth_state,
     )?;
+    let archive_files = crate::destructive_ops::confirm(
+        &db,
+        crate::destructive_ops::DestructiveOp::SyncClearAllOrders,
+        arg0.as_ref(),
+        crate::auth::current_staff_id(&auth_state).as_deref(),
+    )?;
     crate::recovery::snapshot_before_destructive_action(
         &db,
         crate::recovery::RecoveryPointKind::PreClearOperationalData,
@@ -1198,7 +1254,11 @@ pub async fn sync_clear_all_orders(
     };
     let _ = app.emit("orders_cleared", serde_json::json!({ "count": cleared }));
     emit_sync_status_snapshot(&app, &db, &sync_state).await;
-    Ok(serde_json::json!({ "success": true, "cleared": cleared }))
+    Ok(serde_json::json!({
+        "success": true,
+        "cleared": cleared,
+        "archiveFiles": archive_files
+    }))
 }
 
 #[tauri::command]
@@ -1206,7 +1266,7 @@ pub async fn sync_cleanup_deleted_orders(
     db: tauri::State<'_, db::DbState>,
     sync_state: tauri::State<'_, std::sync::Arc<sync::SyncState>>,
     app: tauri::AppHandle,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     crate::hydrate_terminal_credentials_from_local_settings(&db);
     let admin_url =
         storage::get_credential("admin_dashboard_url").ok_or("Admin URL not configured")?;
@@ -1278,7 +1338,7 @@ async fn sync_fetch_with_options(
     path: &str,
     arg0: Option<serde_json::Value>,
     db: &db::DbState,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let full_path = crate::build_admin_query(path, arg0.as_ref());
     match crate::admin_fetch(Some(db), &full_path, "GET", None).await {
         Ok(v) => Ok(v),
@@ -1289,6 +1349,114 @@ async fn sync_fetch_with_options(
     }
 }
 
+/// Admin GET proxy backed by [`crate::remote_cache`]: fresh entries are
+/// served without a request, stale ones are served while a background
+/// refresh runs, and any entry is served when the admin API is unreachable.
+async fn sync_fetch_cached(
+    path: &str,
+    arg0: Option<serde_json::Value>,
+    db: &db::DbState,
+) -> Result<serde_json::Value, PosError> {
+    let full_path = crate::build_admin_query(path, arg0.as_ref());
+    let key = remote_cache::cache_key(&full_path);
+    let cached = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        remote_cache::lookup(&conn, &key, Utc::now())?
+    };
+    match cached {
+        Some(entry) if entry.fresh => Ok(remote_cache::mark(
+            entry.body,
+            Some(&entry.cached_at),
+            false,
+        )),
+        Some(entry) => {
+            spawn_remote_cache_refresh(db.clone(), full_path, key);
+            Ok(remote_cache::mark(entry.body, Some(&entry.cached_at), true))
+        }
+        None => match fetch_into_remote_cache(db, &full_path, &key).await {
+            Ok(v) => Ok(remote_cache::mark(v, None, false)),
+            Err(e) => Ok(serde_json::json!({
+                "success": false,
+                "error": e
+            })),
+        },
+    }
+}
+
+/// Fetch `full_path` and cache the response unless the API reported a
+/// failure.
+async fn fetch_into_remote_cache(
+    db: &db::DbState,
+    full_path: &str,
+    key: &str,
+) -> Result<serde_json::Value, String> {
+    let v = crate::admin_fetch(Some(db), full_path, "GET", None).await?;
+    if v.get("success").and_then(serde_json::Value::as_bool) != Some(false) {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        remote_cache::store(&conn, key, &v, Utc::now())?;
+    }
+    Ok(v)
+}
+
+/// Keys with a background refresh in flight, so repeated reads of a stale
+/// entry start one request.
+fn remote_cache_refreshes() -> &'static std::sync::Mutex<std::collections::HashSet<String>> {
+    static REFRESHES: std::sync::OnceLock<std::sync::Mutex<std::collections::HashSet<String>>> =
+        std::sync::OnceLock::new();
+    REFRESHES.get_or_init(Default::default)
+}
+
+fn spawn_remote_cache_refresh(db: db::DbState, full_path: String, key: String) {
+    match remote_cache_refreshes().lock() {
+        Ok(mut in_flight) => {
+            if !in_flight.insert(key.clone()) {
+                return;
+            }
+        }
+        Err(_) => return,
+    }
+    tauri::async_runtime::spawn(async move {
+        if let Err(error) = fetch_into_remote_cache(&db, &full_path, &key).await {
+            tracing::debug!(path = %full_path, error = %error, "Remote cache refresh failed");
+        }
+        if let Ok(mut in_flight) = remote_cache_refreshes().lock() {
+            in_flight.remove(&key);
+        }
+    });
+}
+
+/// Drop cached proxy responses after a mutation of the same resource.
+fn invalidate_remote_cache(db: &db::DbState, prefix: &str) {
+    match db.conn.lock() {
+        Ok(conn) => {
+            if let Err(error) = remote_cache::invalidate_prefix(&conn, prefix) {
+                tracing::warn!(prefix, error = %error, "Remote cache invalidation failed");
+            }
+        }
+        Err(error) => {
+            tracing::warn!(prefix, error = %error, "Remote cache invalidation failed");
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn remote_cache_invalidate(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, PosError> {
+    let prefix = crate::payload_arg0_as_string(arg0, &["prefix", "pathPrefix", "path_prefix"])
+        .ok_or_else(|| PosError::validation("prefix", "Missing cache path prefix"))?;
+    if !prefix.starts_with('/') {
+        return Err(PosError::validation(
+            "prefix",
+            "Cache path prefix must start with '/'",
+        ));
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let invalidated = remote_cache::invalidate_prefix(&conn, &prefix)?;
+    Ok(serde_json::json!({ "success": true, "prefix": prefix, "invalidated": invalidated }))
+}
+
 #[tauri::command]
 pub async fn sync_clear_all(
     db: tauri::State<'_, db::DbState>,
@@ -1364,16 +1532,22 @@ pub async fn sync_clear_old_orders(
         &db,
         crate::recovery::RecoveryPointKind::PreClearOperationalData,
     )?;
-    let today = Local::now().format("%Y-%m-%d").to_string();
+    let archive_dir = crate::retention::archives_dir(&db)?;
+    crate::retention::ensure_archive_space(&archive_dir)?;
     let cleared = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        clear_old_orders_before(&conn, &today)?
+        let today = crate::business_day::current_business_day_report_date_at(&conn, Local::now());
+        let (today_start, _) = crate::business_day::business_day_bounds(&conn, &today, &today)?;
+        clear_old_orders_before(&conn, &archive_dir, &today_start)?
     };
     emit_sync_status_snapshot(&app, &db, &sync_state).await;
     Ok(serde_json::json!({ "success": true, "cleared": cleared }))
 }
 
-/// Delete pre-`today` orders (and their orphaned legacy sync_queue rows).
+/// Archive, then delete, orders created before `cutoff_at` — the UTC start
+/// of the current business day — and their orphaned legacy sync_queue rows.
+/// Nothing is deleted unless the archive under `archive_dir` was written
+/// and verified.
 ///
 /// Gap review P0-03: a live table tab legitimately spans business days — the
 /// end-of-day rollover preserves it, so this maintenance path must too, or
@@ -1381,37 +1555,30 @@ pub async fn sync_clear_old_orders(
 /// it was opened.
 pub(crate) fn clear_old_orders_before(
     conn: &rusqlite::Connection,
-    today: &str,
+    archive_dir: &std::path::Path,
+    cutoff_at: &str,
 ) -> Result<usize, String> {
     let open_table_tab = crate::business_day::open_unsettled_table_tab_expr("o");
     let _ = conn.execute(
         &format!(
             "DELETE FROM sync_queue WHERE entity_type = 'order' AND entity_id IN (
                 SELECT o.id FROM orders o
-                WHERE substr(o.created_at, 1, 10) < ?1
+                WHERE julianday(o.created_at) < julianday(?1)
                   AND NOT {open_table_tab}
             )"
         ),
-        rusqlite::params![today],
+        rusqlite::params![cutoff_at],
     );
-    conn.execute(
-        &format!(
-            "DELETE FROM orders
-             WHERE id IN (
-                SELECT o.id FROM orders o
-                WHERE substr(o.created_at, 1, 10) < ?1
-                  AND NOT {open_table_tab}
-             )"
-        ),
-        rusqlite::params![today],
-    )
-    .map_err(|e| e.to_string())
+    let archive_date = Local::now().format("%Y-%m-%d").to_string();
+    let target = crate::retention::orders_target("1 = 1");
+    crate::retention::purge_with_archive(conn, archive_dir, &target, cutoff_at, &archive_date)
+        .map(|(_, cleared)| cleared)
 }
 
 #[tauri::command]
 pub async fn sync_get_inter_terminal_status(
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     crate::hydrate_terminal_credentials_from_local_settings(&db);
 
     let admin_url = storage::get_credential("admin_dashboard_url");
@@ -1451,7 +1618,7 @@ pub async fn sync_get_inter_terminal_status(
 }
 
 #[tauri::command]
-pub async fn sync_rediscover_parent() -> Result<serde_json::Value, String> {
+pub async fn sync_rediscover_parent() -> Result<serde_json::Value, PosError> {
     Ok(serde_json::json!({ "success": true }))
 }
 
@@ -1459,23 +1626,39 @@ pub async fn sync_rediscover_parent() -> Result<serde_json::Value, String> {
 pub async fn sync_fetch_suppliers(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
-    sync_fetch_with_options("/api/pos/suppliers", arg0, &db).await
+) -> Result<serde_json::Value, PosError> {
+    sync_fetch_cached("/api/pos/suppliers", arg0, &db).await
+}
+
+#[tauri::command]
+pub async fn sync_fetch_tables(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, PosError> {
+    sync_fetch_cached("/api/pos/tables", arg0, &db).await
+}
+
+#[tauri::command]
+pub async fn sync_fetch_reservations(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, PosError> {
+    sync_fetch_cached("/api/pos/reservations", arg0, &db).await
 }
 
 #[tauri::command]
 pub async fn sync_fetch_analytics(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
-    sync_fetch_with_options("/api/pos/analytics", arg0, &db).await
+) -> Result<serde_json::Value, PosError> {
+    sync_fetch_cached("/api/pos/analytics", arg0, &db).await
 }
 
 #[tauri::command]
 pub async fn sync_fetch_orders(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     sync_fetch_with_options("/api/pos/orders", arg0, &db).await
 }
 
@@ -1483,8 +1666,8 @@ pub async fn sync_fetch_orders(
 pub async fn sync_fetch_rooms(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
-    sync_fetch_with_options("/api/pos/rooms", arg0, &db).await
+) -> Result<serde_json::Value, PosError> {
+    sync_fetch_cached("/api/pos/rooms", arg0, &db).await
 }
 
 #[tauri::command]
@@ -1492,7 +1675,7 @@ pub async fn sync_update_room_status(
     arg0: Option<serde_json::Value>,
     arg1: Option<String>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_update_room_status_payload(arg0, arg1)?;
     let room_id = payload.room_id;
     let status = payload.status;
@@ -1500,7 +1683,10 @@ pub async fn sync_update_room_status(
     let body = serde_json::json!({ "status": status });
 
     match crate::admin_fetch(Some(&db), &path, "PATCH", Some(body)).await {
-        Ok(v) => Ok(v),
+        Ok(v) => {
+            invalidate_remote_cache(&db, "/api/pos/rooms");
+            Ok(v)
+        }
         Err(e) => Ok(serde_json::json!({
             "success": false,
             "error": e
@@ -1512,7 +1698,7 @@ pub async fn sync_update_room_status(
 pub async fn sync_fetch_drive_thru(
     arg0: Option<serde_json::Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     sync_fetch_with_options("/api/pos/drive-through", arg0, &db).await
 }
 
@@ -1521,7 +1707,7 @@ pub async fn sync_update_drive_thru_order_status(
     arg0: Option<serde_json::Value>,
     arg1: Option<String>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let payload = parse_update_drive_thru_order_status_payload(arg0, arg1)?;
     let order_id = payload.drive_thru_order_id;
     let status = payload.status;
@@ -1551,7 +1737,7 @@ pub async fn sync_update_drive_thru_order_status(
 #[tauri::command]
 pub async fn rooms_get_availability(
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     match crate::admin_fetch(Some(&db), "/api/pos/rooms", "GET", None).await {
         Ok(resp) => {
             let metrics = calculate_room_availability(&resp);
@@ -1592,7 +1778,7 @@ pub async fn rooms_get_availability(
 #[tauri::command]
 pub async fn appointments_get_today_metrics(
     db: tauri::State<'_, db::DbState>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, PosError> {
     let today = Local::now().date_naive().to_string();
     let path = format!("/api/pos/appointments?date={today}&include_services=true");
 
@@ -1679,7 +1865,9 @@ mod dto_tests {
         )
         .expect("insert old settled order");
 
-        let cleared = clear_old_orders_before(&conn, "2026-02-16").expect("clear");
+        let archive_dir = crate::tests::harness::TempDir::new();
+        let cleared = clear_old_orders_before(&conn, archive_dir.path(), "2026-02-16T07:00:00Z")
+            .expect("clear");
 
         assert_eq!(cleared, 1, "only the settled pre-today order is cleared");
         let remaining_id: String = conn
@@ -1688,6 +1876,48 @@ mod dto_tests {
         assert_eq!(remaining_id, "ord-overnight-tab");
     }
 
+    #[test]
+    fn clear_old_orders_keeps_orders_from_the_current_business_day() {
+        let conn = rusqlite::Connection::open_in_memory().expect("open db");
+        db::run_migrations_for_test(&conn);
+        // Business day 2026-03-29 in Athens opens at 07:00 EEST (04:00Z),
+        // the morning after the spring-forward.
+        let (cutoff_at, _) = crate::business_day::business_day_bounds_in(
+            &chrono_tz::Europe::Athens,
+            "2026-03-29",
+            "2026-03-29",
+            7 * 60,
+        )
+        .expect("bounds");
+        for (id, created_at) in [
+            ("ord-2350", "2026-03-28T21:50:00Z"),
+            ("ord-0010", "2026-03-28T22:10:00Z"),
+            ("ord-open", "2026-03-29T07:00:00+03:00"),
+        ] {
+            conn.execute(
+                "INSERT INTO orders (
+                    id, order_number, items, total_amount, total_amount_cents, status,
+                    order_type, payment_status, sync_status, created_at, updated_at
+                 ) VALUES (?1, ?1, '[]', 10.0, 1000, 'completed', 'takeaway', 'paid',
+                           'synced', ?2, ?2)",
+                params![id, created_at],
+            )
+            .expect("insert order");
+        }
+
+        let archive_dir = crate::tests::harness::TempDir::new();
+        let cleared =
+            clear_old_orders_before(&conn, archive_dir.path(), &cutoff_at).expect("clear");
+        let archived = crate::retention::list_archives(archive_dir.path()).expect("archives");
+        assert_eq!(archived.len(), 1, "cleared orders are archived first");
+
+        assert_eq!(cleared, 2, "both orders of the previous business day go");
+        let remaining_id: String = conn
+            .query_row("SELECT id FROM orders", [], |row| row.get(0))
+            .expect("one order remains");
+        assert_eq!(remaining_id, "ord-open");
+    }
+
     #[test]
     fn parse_remove_invalid_orders_supports_array_payload() {
         let parsed = parse_remove_invalid_orders_payload(Some(serde_json::json!([
@@ -1743,10 +1973,7 @@ mod dto_tests {
             params![],
         )
         .expect("insert financial queue row");
-        let db = db::DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        };
+        let db = db::DbState::new(conn, std::path::PathBuf::from(":memory:"));
 
         let response = query_financial_queue_items(10, &db).expect("query financial queue");
         let items = response
@@ -1787,10 +2014,7 @@ mod dto_tests {
             [],
         )
         .expect("insert failed payment adjustment");
-        let db = db::DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        };
+        let db = db::DbState::new(conn, std::path::PathBuf::from(":memory:"));
 
         let response = query_financial_queue_items(10, &db).expect("query financial queue");
         let items = response
@@ -1852,10 +2076,7 @@ mod dto_tests {
             [],
         )
         .expect("insert failed payment queue row");
-        let db = db::DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        };
+        let db = db::DbState::new(conn, std::path::PathBuf::from(":memory:"));
 
         let response = query_financial_queue_items(10, &db).expect("query financial queue");
         let items = response
@@ -1917,10 +2138,7 @@ mod dto_tests {
         )
         .expect("insert financial queue row");
 
-        let db = db::DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        };
+        let db = db::DbState::new(conn, std::path::PathBuf::from(":memory:"));
 
         let response = query_financial_queue_items(10, &db).expect("query financial queue");
         let items = response
@@ -1982,10 +2200,7 @@ mod dto_tests {
             [],
         )
         .expect("insert orphaned legacy payment parity row");
-        let db = db::DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        };
+        let db = db::DbState::new(conn, std::path::PathBuf::from(":memory:"));
 
         let response = collect_financial_integrity(&db).expect("collect integrity");
         let issues = response
@@ -2061,10 +2276,7 @@ mod dto_tests {
         )
         .expect("insert financial queue row");
 
-        let db = db::DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        };
+        let db = db::DbState::new(conn, std::path::PathBuf::from(":memory:"));
 
         let child_queue_id = {
             let conn = db.conn.lock().expect("lock db");
@@ -5,9 +5,15 @@ use serde_json::Value;
 use tauri::{Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
 use tracing::info;
 
+use crate::clipboard::{self, ClipboardError};
 use crate::db;
+use crate::error::PosError;
+use crate::notify::{self, NotificationKind, NotificationSettings};
 
 const MAX_CLIPBOARD_TEXT_LEN: usize = 1_000_000;
+/// Base64 length, roughly 12 MB of PNG.
+const MAX_CLIPBOARD_IMAGE_LEN: usize = 16_000_000;
+const CLIPBOARD_FALLBACK_KEY: &str = "clipboard_fallback_text";
 const DISPLAY_WINDOW_PREFIX: &str = "external-display";
 const WINDOW_ZOOM_DEFAULT: f64 = 1.0;
 const WINDOW_ZOOM_STEP: f64 = 0.1;
@@ -48,17 +54,37 @@ fn parse_clipboard_text_payload(arg0: Option<Value>) -> Result<String, String> {
     Ok(text)
 }
 
+fn parse_clipboard_image_payload(arg0: Option<Value>) -> Result<String, PosError> {
+    let encoded = match arg0 {
+        Some(Value::String(encoded)) => encoded,
+        Some(payload @ Value::Object(_)) => {
+            crate::value_str(&payload, &["image", "png", "data", "base64"]).unwrap_or_default()
+        }
+        _ => String::new(),
+    };
+    if encoded.trim().is_empty() {
+        return Err(PosError::validation("image", "Missing base64 PNG image"));
+    }
+    if encoded.len() > MAX_CLIPBOARD_IMAGE_LEN {
+        return Err(PosError::validation(
+            "image",
+            format!("Clipboard image too large (max {MAX_CLIPBOARD_IMAGE_LEN} bytes)"),
+        ));
+    }
+    Ok(encoded)
+}
+
 fn parse_notification_payload(arg0: Option<Value>) -> (String, String) {
     match arg0 {
-        Some(Value::String(message)) => ("The Small POS".to_string(), message),
+        Some(Value::String(message)) => (notify::APP_TITLE.to_string(), message),
         Some(Value::Object(obj)) => {
             let payload = Value::Object(obj);
             let title = crate::value_str(&payload, &["title"])
-                .unwrap_or_else(|| "The Small POS".to_string());
+                .unwrap_or_else(|| notify::APP_TITLE.to_string());
             let body = crate::value_str(&payload, &["body", "message", "text"]).unwrap_or_default();
             (title, body)
         }
-        _ => ("The Small POS".to_string(), String::new()),
+        _ => (notify::APP_TITLE.to_string(), String::new()),
     }
 }
 
@@ -306,39 +332,121 @@ pub async fn display_close_window(
 }
 
 #[tauri::command]
-pub async fn clipboard_read_text(db: tauri::State<'_, db::DbState>) -> Result<Value, String> {
-    match crate::read_system_clipboard_text() {
-        Ok(text) => {
-            let _ =
-                crate::write_local_json(&db, "clipboard_fallback_text", &serde_json::json!(text));
-            Ok(serde_json::json!(text))
-        }
-        Err(_) => {
-            let fallback = crate::read_local_json(&db, "clipboard_fallback_text")?;
+pub async fn clipboard_read_text(db: tauri::State<'_, db::DbState>) -> Result<Value, PosError> {
+    // Clipboard calls can block on the display server.
+    let result = tokio::task::spawn_blocking(clipboard::read_text)
+        .await
+        .map_err(|e| PosError::Internal(format!("clipboard task join error: {e}")))?;
+    match result {
+        Ok(text) => Ok(serde_json::json!(text)),
+        // Headless: hand back what this app last wrote.
+        Err(ClipboardError::Unsupported(_)) => {
+            let fallback = db
+                .run_blocking(|db| crate::read_local_json(db, CLIPBOARD_FALLBACK_KEY))
+                .await?;
             Ok(serde_json::json!(fallback
                 .as_str()
                 .unwrap_or_default()
                 .to_string()))
         }
+        Err(error) => Err(error.into()),
     }
 }
 
+/// Write text to the system clipboard. The stored fallback only changes when
+/// the write succeeded, or when there is no system clipboard and the fallback
+/// is the clipboard; a transient failure leaves it as it was.
 #[tauri::command]
 pub async fn clipboard_write_text(
     arg0: Option<Value>,
     db: tauri::State<'_, db::DbState>,
-) -> Result<Value, String> {
-    let text = parse_clipboard_text_payload(arg0)?;
-    let _ = crate::write_local_json(&db, "clipboard_fallback_text", &serde_json::json!(text));
-    let _ = crate::write_system_clipboard_text(&text);
-    Ok(serde_json::json!({ "success": true }))
+) -> Result<Value, PosError> {
+    let text = parse_clipboard_text_payload(arg0)
+        .map_err(|reason| PosError::validation("text", reason))?;
+    let system_text = text.clone();
+    let result = tokio::task::spawn_blocking(move || clipboard::write_text(&system_text))
+        .await
+        .map_err(|e| PosError::Internal(format!("clipboard task join error: {e}")))?;
+    let fallback_only = match result {
+        Ok(()) => false,
+        Err(ClipboardError::Unsupported(_)) => true,
+        Err(error) => return Err(error.into()),
+    };
+    db.run_blocking(move |db| {
+        crate::write_local_json(db, CLIPBOARD_FALLBACK_KEY, &serde_json::json!(text))
+    })
+    .await?;
+    Ok(serde_json::json!({ "success": true, "fallback": fallback_only }))
 }
 
+/// Copy a base64 PNG (e.g. a rendered receipt preview) to the clipboard as an
+/// image. There is no fallback: images fail with `UNSUPPORTED` when the
+/// system has no clipboard.
 #[tauri::command]
-pub async fn show_notification(arg0: Option<Value>) -> Result<Value, String> {
+pub async fn clipboard_write_image(arg0: Option<Value>) -> Result<Value, PosError> {
+    let encoded = parse_clipboard_image_payload(arg0)?;
+    let (width, height) =
+        tokio::task::spawn_blocking(move || clipboard::write_png_base64(&encoded))
+            .await
+            .map_err(|e| PosError::Internal(format!("clipboard task join error: {e}")))??;
+    Ok(serde_json::json!({ "success": true, "width": width, "height": height }))
+}
+
+#[tauri::command]
+pub async fn show_notification(
+    arg0: Option<Value>,
+    app: tauri::AppHandle,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<Value, String> {
     let (title, body) = parse_notification_payload(arg0);
     info!(title = %title, body = %body, "show-notification requested");
-    Ok(serde_json::json!({ "success": true }))
+    let outcome = notify::send(&app, &db, NotificationKind::General, &title, &body);
+    Ok(serde_json::json!({ "success": true, "delivered": outcome["delivered"] }))
+}
+
+#[tauri::command]
+pub async fn notifications_get_settings(
+    db: tauri::State<'_, db::DbState>,
+) -> Result<Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(NotificationSettings::load(&conn).to_json())
+}
+
+#[tauri::command]
+pub async fn notifications_set_settings(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<Value, PosError> {
+    let payload = arg0.unwrap_or(Value::Null);
+    if !payload.is_object() {
+        return Err(PosError::validation(
+            "settings",
+            "Expected a notification settings object",
+        ));
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let settings = notify::update_settings(&conn, &payload)
+        .map_err(|reason| PosError::validation("settings", reason))?;
+    Ok(settings.to_json())
+}
+
+/// Send a sample notification regardless of toggles and do-not-disturb, so
+/// staff can check the OS permission and sound.
+#[tauri::command]
+pub async fn notifications_test(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, db::DbState>,
+) -> Result<Value, String> {
+    let outcome = notify::send(
+        &app,
+        &db,
+        NotificationKind::Test,
+        notify::APP_TITLE,
+        "Notifications are working",
+    );
+    Ok(
+        serde_json::json!({ "success": true, "delivered": outcome["delivered"], "reason": outcome.get("reason") }),
+    )
 }
 
 #[tauri::command]
@@ -478,6 +586,19 @@ mod dto_tests {
         assert!(err.contains("Clipboard payload too large"));
     }
 
+    #[test]
+    fn parse_clipboard_image_payload_requires_an_image() {
+        let from_object =
+            parse_clipboard_image_payload(Some(serde_json::json!({ "image": "iVBORw0KGgo=" })))
+                .expect("object payload should parse");
+        assert_eq!(from_object, "iVBORw0KGgo=");
+        let missing = parse_clipboard_image_payload(Some(serde_json::json!({})))
+            .expect_err("empty payload should fail");
+        assert_eq!(missing.code(), "VALIDATION");
+        let oversized = "A".repeat(MAX_CLIPBOARD_IMAGE_LEN + 1);
+        assert!(parse_clipboard_image_payload(Some(serde_json::json!(oversized))).is_err());
+    }
+
     #[test]
     fn parse_notification_payload_supports_string_and_object() {
         let from_string = parse_notification_payload(Some(serde_json::json!("Sync complete")));
@@ -0,0 +1,331 @@
+use chrono::Utc;
+use serde_json::{json, Value};
+use tauri::Emitter;
+
+use crate::commands::onboarding::{admin_reachable_step, parse_connection_code_payload};
+use crate::onboarding::{self, StepResult};
+use crate::terminal_repair::{self, RepairSession};
+use crate::{auth, db, storage};
+
+fn parse_repair_id(arg0: Option<Value>) -> Option<String> {
+    crate::payload_arg0_as_string(arg0, &["repairId", "repair_id", "id"])
+}
+
+fn current_identity(db: &db::DbState, key: &str) -> Option<String> {
+    crate::terminal_helpers::normalize_terminal_identity(
+        storage::get_credential(key).or_else(|| crate::read_local_setting(db, "terminal", key)),
+    )
+}
+
+/// Audit one step and tell the renderer about it. An audit write failure is
+/// logged rather than failing a step that already happened.
+#[allow(clippy::too_many_arguments)]
+fn report(
+    db: &db::DbState,
+    app: &tauri::AppHandle,
+    session: Option<&RepairSession>,
+    step: &str,
+    success: bool,
+    message: &str,
+    actor_staff_id: Option<&str>,
+    details: Value,
+) {
+    if let Ok(conn) = db.conn.lock() {
+        if let Err(error) = terminal_repair::record(
+            &conn,
+            session.map(|session| session.id.as_str()),
+            step,
+            success,
+            message,
+            actor_staff_id,
+            details.clone(),
+        ) {
+            tracing::warn!(step = %step, error = %error, "Failed to audit terminal repair step");
+        }
+    }
+    let mut payload = terminal_repair::progress(step, session, details);
+    if let Some(map) = payload.as_object_mut() {
+        map.insert("success".into(), json!(success));
+        map.insert("message".into(), json!(message));
+    }
+    let _ = app.emit(terminal_repair::PROGRESS_EVENT, payload);
+}
+
+/// Quarantine the old terminal's unsynced work, then apply and validate a
+/// new connection code. Stops at the first failing validation step; the
+/// quarantine stays in place so the code can be retried.
+#[tauri::command]
+pub async fn terminal_repair_begin(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+    auth_state: tauri::State<'_, auth::AuthState>,
+    sync_state: tauri::State<'_, std::sync::Arc<crate::sync::SyncState>>,
+) -> Result<Value, auth::GuardedCommandError> {
+    auth::authorize_privileged_action(
+        auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let actor = auth::current_staff_id(&auth_state);
+    let code = zeroize::Zeroizing::new(parse_connection_code_payload(arg0)?);
+
+    let old_terminal_id = current_identity(&db, "terminal_id");
+    let old_branch_id = current_identity(&db, "branch_id");
+    let session = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        terminal_repair::quarantine(
+            &conn,
+            old_terminal_id.as_deref(),
+            old_branch_id.as_deref(),
+            Utc::now(),
+        )?
+    };
+    tracing::info!(
+        repair_id = %session.id,
+        old_terminal_id = session
+            .old_terminal_id
+            .as_deref()
+            .map(crate::mask_terminal_id)
+            .unwrap_or_else(|| "none".to_string()),
+        orders = session.order_count,
+        queue_items = session.queue_count,
+        "Quarantined old terminal data for re-pairing"
+    );
+    report(
+        &db,
+        &app,
+        Some(&session),
+        "quarantined",
+        true,
+        "Quarantined open and unsynced work of the old terminal",
+        actor.as_deref(),
+        json!({ "orders": session.order_count, "queueItems": session.queue_count }),
+    );
+
+    let decoded = onboarding::decode_connection_code(&code);
+    let mut steps = vec![onboarding::validate_connection_code(&decoded)];
+    let fail = |steps: &[StepResult], session: &RepairSession| {
+        let failed = steps.iter().find(|step| !step.complete);
+        report(
+            &db,
+            &app,
+            Some(session),
+            "validation_failed",
+            false,
+            failed
+                .and_then(|step| step.error.as_deref())
+                .unwrap_or("Connection code was not accepted"),
+            actor.as_deref(),
+            json!({ "failedStep": failed.map(|step| step.id), "errorCode": failed.and_then(|step| step.error_code) }),
+        );
+        crate::scrub_sensitive_local_settings(&db);
+        json!({
+            "success": false,
+            "steps": steps,
+            "repair": session.to_json(),
+        })
+    };
+    if !steps[0].complete {
+        return Ok(fail(&steps, &session));
+    }
+
+    crate::commands::settings::store_terminal_credentials_for_repair(
+        &db,
+        &json!({ "apiKey": code.as_str() }),
+    )?;
+    let endpoint = decoded.admin_url.clone().zip(decoded.api_key.clone());
+    let admin = admin_reachable_step(endpoint.as_ref()).await;
+    let reachable = admin.complete;
+    steps.push(admin);
+    if !reachable {
+        return Ok(fail(&steps, &session));
+    }
+
+    let fetched = crate::commands::settings::refresh_terminal_context_from_admin(&db).await;
+    let settings_step = onboarding::admin_step(onboarding::STEP_TERMINAL_SETTINGS, fetched);
+    let fetched_ok = settings_step.complete;
+    steps.push(settings_step);
+    if !fetched_ok {
+        return Ok(fail(&steps, &session));
+    }
+
+    let new_terminal_id =
+        crate::terminal_helpers::normalize_terminal_identity(decoded.terminal_id.clone())
+            .or_else(|| current_identity(&db, "terminal_id"))
+            .ok_or("Connection code did not resolve a terminal id")?;
+    let new_branch_id = current_identity(&db, "branch_id");
+    let session = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        terminal_repair::mark_validated(
+            &conn,
+            &session.id,
+            &new_terminal_id,
+            new_branch_id.as_deref(),
+            Utc::now(),
+        )?
+    };
+    report(
+        &db,
+        &app,
+        Some(&session),
+        "validated",
+        true,
+        "New connection code validated against the admin dashboard",
+        actor.as_deref(),
+        json!({ "newTerminalId": session.new_terminal_id, "newBranchId": session.new_branch_id }),
+    );
+
+    sync_state.clear_remote_auth_pause();
+    crate::commands::settings::announce_terminal_credentials_updated(
+        &app,
+        &db,
+        "terminal_repair_begin",
+    );
+    crate::scrub_sensitive_local_settings(&db);
+    Ok(json!({
+        "success": true,
+        "steps": steps,
+        "repair": session.to_json(),
+    }))
+}
+
+/// Re-associate the quarantined orders with the new terminal and branch and
+/// requeue their sync rows.
+#[tauri::command]
+pub async fn terminal_repair_migrate(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<Value, auth::GuardedCommandError> {
+    auth::authorize_privileged_action(
+        auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let actor = auth::current_staff_id(&auth_state);
+    let repair_id = parse_repair_id(arg0);
+    let (session, migrated) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let session = terminal_repair::resolve_session(&conn, repair_id.as_deref())?;
+        let organization_id = db::get_setting(&conn, "terminal", "organization_id");
+        let migrated =
+            terminal_repair::migrate(&conn, &session.id, organization_id.as_deref(), Utc::now());
+        (session, migrated)
+    };
+    let (orders, queue_items) = match migrated {
+        Ok(counts) => counts,
+        Err(error) => {
+            report(
+                &db,
+                &app,
+                Some(&session),
+                "migrate_failed",
+                false,
+                &error,
+                actor.as_deref(),
+                json!({}),
+            );
+            return Err(error.into());
+        }
+    };
+    let session = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        terminal_repair::load_session(&conn, &session.id)?
+    };
+    report(
+        &db,
+        &app,
+        Some(&session),
+        "migrated",
+        true,
+        "Quarantined orders moved to the new terminal and requeued",
+        actor.as_deref(),
+        json!({ "orders": orders, "queueItems": queue_items }),
+    );
+    Ok(json!({
+        "success": true,
+        "orders": orders,
+        "queueItems": queue_items,
+        "repair": session.to_json(),
+    }))
+}
+
+/// Archive the quarantined orders and queue rows under the recovery root,
+/// then delete them.
+#[tauri::command]
+pub async fn terminal_repair_discard(
+    arg0: Option<Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<Value, auth::GuardedCommandError> {
+    auth::authorize_privileged_action(
+        auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let actor = auth::current_staff_id(&auth_state);
+    let repair_id = parse_repair_id(arg0);
+    let (session, discarded) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let session = terminal_repair::resolve_session(&conn, repair_id.as_deref())?;
+        let archive_dir = crate::recovery::recovery_root_for_db(&db)
+            .join(terminal_repair::ARCHIVE_DIR)
+            .join(&session.id);
+        let discarded = terminal_repair::discard(&conn, &session.id, &archive_dir, Utc::now());
+        (session, discarded)
+    };
+    let (archive_files, orders) = match discarded {
+        Ok(result) => result,
+        Err(error) => {
+            report(
+                &db,
+                &app,
+                Some(&session),
+                "discard_failed",
+                false,
+                &error,
+                actor.as_deref(),
+                json!({}),
+            );
+            return Err(error.into());
+        }
+    };
+    let session = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        terminal_repair::load_session(&conn, &session.id)?
+    };
+    report(
+        &db,
+        &app,
+        Some(&session),
+        "discarded",
+        true,
+        "Quarantined orders archived and dropped",
+        actor.as_deref(),
+        json!({ "orders": orders, "archiveFiles": archive_files }),
+    );
+    Ok(json!({
+        "success": true,
+        "orders": orders,
+        "archiveFiles": archive_files,
+        "repair": session.to_json(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repair_id_accepts_object_and_string() {
+        assert_eq!(
+            parse_repair_id(Some(json!({ "repairId": "r-1" }))).as_deref(),
+            Some("r-1")
+        );
+        assert_eq!(parse_repair_id(Some(json!("r-2"))).as_deref(), Some("r-2"));
+        assert_eq!(parse_repair_id(None), None);
+    }
+}
@@ -15,6 +15,15 @@ const UPDATER_PUBKEY_PLACEHOLDER: &str = "__TAURI_UPDATER_PUBKEY__";
 const GITHUB_RELEASE_API_BASE: &str =
     "https://api.github.com/repos/EpsylonBita/The-Small-POS/releases/tags/";
 const GITHUB_RELEASE_NOTES_TIMEOUT_SECS: u64 = 5;
+/// Non-stable channels publish their manifest on a rolling release tag named
+/// after the channel (e.g. `.../releases/download/beta/latest.json`).
+const UPDATER_CHANNEL_RELEASE_BASE: &str =
+    "https://github.com/EpsylonBita/The-Small-POS/releases/download/";
+/// Written right before handing the payload to the installer and removed
+/// once the new version boots; a leftover marker means the install failed.
+const UPDATER_INSTALL_MARKER_FILE: &str = "install_pending.json";
+/// Persist download progress into `updater_state` at most every N percent.
+const UPDATER_PROGRESS_PERSIST_STEP: u64 = 5;
 
 fn parse_update_channel_payload(arg0: Option<serde_json::Value>) -> String {
     let raw = match arg0 {
@@ -39,6 +48,43 @@ fn parse_update_channel_payload(arg0: Option<serde_json::Value>) -> String {
     raw.unwrap_or_else(|| "stable".to_string()).to_lowercase()
 }
 
+fn normalize_update_channel(raw: Option<String>) -> String {
+    match raw.map(|value| value.trim().to_lowercase()).as_deref() {
+        Some("beta") => "beta".to_string(),
+        _ => "stable".to_string(),
+    }
+}
+
+/// Channel from the `updater.channel` setting, falling back to the legacy
+/// `general.update_channel` key written by older builds.
+fn configured_update_channel(db: &db::DbState) -> String {
+    let raw = db.conn.lock().ok().and_then(|conn| {
+        db::get_setting(&conn, "updater", "channel")
+            .or_else(|| db::get_setting(&conn, "general", "update_channel"))
+    });
+    normalize_update_channel(raw)
+}
+
+fn manifest_url_for_channel(channel: &str) -> String {
+    if channel == "stable" {
+        crate::UPDATER_MANIFEST_URL.to_string()
+    } else {
+        format!("{UPDATER_CHANNEL_RELEASE_BASE}{channel}/latest.json")
+    }
+}
+
+fn channel_updater(
+    app: &AppHandle,
+    manifest_url: &str,
+) -> Result<tauri_plugin_updater::Updater, String> {
+    let endpoint = url::Url::parse(manifest_url)
+        .map_err(|error| format!("Invalid updater manifest URL: {error}"))?;
+    app.updater_builder()
+        .endpoints(vec![endpoint])
+        .and_then(|builder| builder.build())
+        .map_err(|error| format!("Failed to initialize updater: {error}"))
+}
+
 fn configured_updater_pubkey() -> Option<String> {
     let config: serde_json::Value =
         serde_json::from_str(include_str!("../../tauri.conf.json")).ok()?;
@@ -204,14 +250,10 @@ fn release_notes_from_github_payload(payload: &serde_json::Value) -> Option<Stri
 }
 
 async fn fetch_github_release_notes(version: &str) -> Option<String> {
-    let client = match reqwest::Client::builder()
-        .timeout(Duration::from_secs(GITHUB_RELEASE_NOTES_TIMEOUT_SECS))
-        .user_agent(format!("The-Small-POS/{}", env!("CARGO_PKG_VERSION")))
-        .build()
-    {
+    let client = match crate::api::shared_client() {
         Ok(client) => client,
         Err(error) => {
-            tracing::warn!("Failed to build release-notes HTTP client: {error}");
+            tracing::warn!("Failed to get release-notes HTTP client: {error}");
             return None;
         }
     };
@@ -220,6 +262,11 @@ async fn fetch_github_release_notes(version: &str) -> Option<String> {
         let url = github_release_api_url(&tag);
         let response = match client
             .get(&url)
+            .timeout(Duration::from_secs(GITHUB_RELEASE_NOTES_TIMEOUT_SECS))
+            .header(
+                header::USER_AGENT,
+                format!("The-Small-POS/{}", env!("CARGO_PKG_VERSION")),
+            )
             .header(header::ACCEPT, "application/vnd.github+json")
             .send()
             .await
@@ -340,6 +387,10 @@ fn reset_update_state(state: &mut serde_json::Value) {
     set_state_value(state, "downloadedArtifactPath", serde_json::Value::Null);
     set_state_value(state, "installPending", serde_json::json!(false));
     set_state_value(state, "installingVersion", serde_json::Value::Null);
+    set_state_value(state, "downloadedBytes", serde_json::json!(0));
+    set_state_value(state, "totalBytes", serde_json::Value::Null);
+    set_state_value(state, "artifactSize", serde_json::Value::Null);
+    set_state_value(state, "artifactMd5", serde_json::Value::Null);
 }
 
 fn clear_runtime_update_state(updater_runtime: &UpdaterRuntimeState) -> Result<(), String> {
@@ -364,6 +415,156 @@ fn remove_artifact(path: Option<&Path>) {
     }
 }
 
+fn artifact_md5_hex(bytes: &[u8]) -> String {
+    format!("{:x}", md5::compute(bytes))
+}
+
+/// Check the payload about to be installed against the size/checksum
+/// recorded when it was downloaded, so a truncated or tampered artifact on
+/// disk is never handed to the installer.
+fn verify_downloaded_artifact(bytes: &[u8], state: &serde_json::Value) -> Result<(), String> {
+    if bytes.is_empty() {
+        return Err("Downloaded update is empty".to_string());
+    }
+    if let Some(expected_size) = state.get("artifactSize").and_then(|value| value.as_u64()) {
+        if bytes.len() as u64 != expected_size {
+            return Err(format!(
+                "Downloaded update size mismatch (expected {expected_size} bytes, found {})",
+                bytes.len()
+            ));
+        }
+    }
+    if let Some(expected_md5) = update_state_string(state, "artifactMd5") {
+        if !artifact_md5_hex(bytes).eq_ignore_ascii_case(&expected_md5) {
+            return Err("Downloaded update checksum mismatch".to_string());
+        }
+    }
+    Ok(())
+}
+
+fn persist_download_progress(app: &AppHandle, percent: u64, transferred: u64, total: Option<u64>) {
+    let db = app.state::<db::DbState>();
+    if let Ok(mut state) = crate::read_update_state(&db) {
+        set_state_value(&mut state, "progress", serde_json::json!(percent));
+        set_state_value(
+            &mut state,
+            "downloadedBytes",
+            serde_json::json!(transferred),
+        );
+        set_state_value(&mut state, "totalBytes", serde_json::json!(total));
+        let _ = crate::write_update_state(&db, &state);
+    }
+}
+
+fn install_marker_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
+    Ok(app_data_dir
+        .join(UPDATER_ARTIFACT_DIR)
+        .join(UPDATER_INSTALL_MARKER_FILE))
+}
+
+fn write_install_marker(app: &AppHandle, target_version: &str) -> Result<(), String> {
+    let path = install_marker_path(app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create updater artifact dir: {e}"))?;
+    }
+    let marker = serde_json::json!({
+        "targetVersion": target_version,
+        "fromVersion": env!("CARGO_PKG_VERSION"),
+        "startedAt": chrono::Utc::now().to_rfc3339(),
+    });
+    std::fs::write(&path, marker.to_string())
+        .map_err(|e| format!("Failed to write install marker: {e}"))
+}
+
+fn remove_install_marker(app: &AppHandle) {
+    if let Ok(path) = install_marker_path(app) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum InstallMarkerOutcome {
+    /// The new version booted; the marker is stale.
+    Completed,
+    /// The previous run tried to install `target_version` but we are still
+    /// on the old build.
+    Failed {
+        target_version: String,
+        started_at: Option<String>,
+    },
+}
+
+fn classify_install_marker(
+    marker: &serde_json::Value,
+    current_version: &str,
+) -> InstallMarkerOutcome {
+    let target_version = update_state_string(marker, "targetVersion").unwrap_or_default();
+    let normalize = |version: &str| version.trim().trim_start_matches(['v', 'V']).to_string();
+    if !target_version.is_empty() && normalize(&target_version) == normalize(current_version) {
+        InstallMarkerOutcome::Completed
+    } else {
+        InstallMarkerOutcome::Failed {
+            target_version,
+            started_at: update_state_string(marker, "startedAt"),
+        }
+    }
+}
+
+/// Inspect the pending-install marker left by a previous run. Returns
+/// `true` when a failed install was detected and the persisted state was
+/// reset to an error.
+fn reconcile_install_marker(app: &AppHandle) -> bool {
+    let Ok(path) = install_marker_path(app) else {
+        return false;
+    };
+    let Ok(raw) = std::fs::read_to_string(&path) else {
+        return false;
+    };
+    let _ = std::fs::remove_file(&path);
+    let marker: serde_json::Value = serde_json::from_str(&raw).unwrap_or(serde_json::Value::Null);
+    let current_version = env!("CARGO_PKG_VERSION");
+
+    match classify_install_marker(&marker, current_version) {
+        InstallMarkerOutcome::Completed => {
+            tracing::info!(version = current_version, "Update install completed");
+            false
+        }
+        InstallMarkerOutcome::Failed {
+            target_version,
+            started_at,
+        } => {
+            let message = format!(
+                "Update to {target_version} did not complete; still running {current_version}"
+            );
+            tracing::warn!("{message}");
+            let db = app.state::<db::DbState>();
+            if let Ok(mut state) = crate::read_update_state(&db) {
+                remove_artifact(update_state_path(&state, "downloadedArtifactPath").as_deref());
+                reset_update_state(&mut state);
+                set_state_value(&mut state, "error", serde_json::json!(message.clone()));
+                let _ = crate::write_update_state(&db, &state);
+            }
+            let updater_runtime = app.state::<UpdaterRuntimeState>();
+            let _ = clear_runtime_update_state(&updater_runtime);
+            let _ = app.emit(
+                "update_failed",
+                serde_json::json!({
+                    "message": message,
+                    "targetVersion": target_version,
+                    "currentVersion": current_version,
+                    "startedAt": started_at,
+                }),
+            );
+            true
+        }
+    }
+}
+
 fn sanitize_filename_component(value: &str) -> String {
     value
         .chars()
@@ -485,9 +686,8 @@ async fn fetch_matching_remote_update(
     app: &AppHandle,
     target_version: &str,
 ) -> Result<Option<tauri_plugin_updater::Update>, String> {
-    let updater = app
-        .updater()
-        .map_err(|error| format!("Failed to initialize updater: {error}"))?;
+    let channel = configured_update_channel(&app.state::<db::DbState>());
+    let updater = channel_updater(app, &manifest_url_for_channel(&channel))?;
 
     match updater.check().await {
         Ok(Some(update)) if update.version == target_version => Ok(Some(update)),
@@ -526,6 +726,10 @@ async fn ensure_pending_update_loaded(
 }
 
 pub async fn reconcile_update_state_on_startup(app: AppHandle) {
+    if reconcile_install_marker(&app) {
+        return;
+    }
+
     let mut state = {
         let db = app.state::<db::DbState>();
         match reconcile_persisted_update_state(&db) {
@@ -574,20 +778,36 @@ pub async fn reconcile_update_state_on_startup(app: AppHandle) {
         let updater_runtime = app.state::<UpdaterRuntimeState>();
         if let Ok(bytes) = rehydrate_downloaded_bytes(&updater_runtime, &artifact_path) {
             if install_pending {
-                set_installing_state(&mut state, &version);
                 let db = app.state::<db::DbState>();
-                let _ = crate::write_update_state(&db, &state);
-                if let Err(error) = update.install(bytes) {
-                    let message = format!("Failed to install update: {error}");
-                    set_download_ready_state(
-                        &mut state,
-                        update_info.clone(),
-                        &version,
-                        &artifact_path,
-                    );
+                if let Err(message) = verify_downloaded_artifact(&bytes, &state) {
+                    remove_artifact(Some(&artifact_path));
+                    reset_update_state(&mut state);
                     set_state_value(&mut state, "error", serde_json::json!(message.clone()));
                     let _ = crate::write_update_state(&db, &state);
+                    let _ = clear_runtime_update_state(&updater_runtime);
                     let _ = app.emit("update_error", serde_json::json!({ "message": message }));
+                    return;
+                }
+                set_installing_state(&mut state, &version);
+                let _ = crate::write_update_state(&db, &state);
+                if let Err(error) = write_install_marker(&app, &version) {
+                    tracing::warn!("Failed to write update install marker: {error}");
+                }
+                match update.install(bytes) {
+                    Ok(()) => app.restart(),
+                    Err(error) => {
+                        remove_install_marker(&app);
+                        let message = format!("Failed to install update: {error}");
+                        set_download_ready_state(
+                            &mut state,
+                            update_info.clone(),
+                            &version,
+                            &artifact_path,
+                        );
+                        set_state_value(&mut state, "error", serde_json::json!(message.clone()));
+                        let _ = crate::write_update_state(&db, &state);
+                        let _ = app.emit("update_error", serde_json::json!({ "message": message }));
+                    }
                 }
             } else {
                 set_download_ready_state(&mut state, update_info, &version, &artifact_path);
@@ -630,7 +850,8 @@ pub async fn update_check(
     crate::write_update_state(&db, &state)?;
     let _ = app.emit("update_checking", serde_json::json!({}));
 
-    match crate::updater_manifest_is_reachable().await {
+    let manifest_url = manifest_url_for_channel(&configured_update_channel(&db));
+    match crate::updater_manifest_is_reachable(&manifest_url).await {
         Ok(true) => {}
         Ok(false) => {
             set_state_value(
@@ -654,10 +875,9 @@ pub async fn update_check(
         }
     }
 
-    let updater = match app.updater() {
+    let updater = match channel_updater(&app, &manifest_url) {
         Ok(updater) => updater,
-        Err(error) => {
-            let message = format!("Failed to initialize updater: {error}");
+        Err(message) => {
             set_state_value(&mut state, "checking", serde_json::json!(false));
             set_state_value(&mut state, "error", serde_json::json!(message.clone()));
             crate::write_update_state(&db, &state)?;
@@ -764,6 +984,7 @@ pub async fn update_download(
 
     let transferred = std::sync::Arc::new(AtomicU64::new(0));
     let transferred_for_event = transferred.clone();
+    let last_persisted_percent = std::sync::Arc::new(AtomicU64::new(0));
     let app_for_event = app.clone();
 
     match update
@@ -787,6 +1008,30 @@ pub async fn update_download(
                         "total": total_bytes
                     }),
                 );
+                let _ = app_for_event.emit(
+                    "update_download_progress",
+                    serde_json::json!({
+                        "bytes": transferred_now,
+                        "total": total,
+                        "percent": percent,
+                    }),
+                );
+                // Persisting on every chunk would hammer SQLite; only write
+                // the state when progress crosses the next step.
+                let whole_percent = percent as u64;
+                let last = last_persisted_percent.load(Ordering::Relaxed);
+                if whole_percent >= last + UPDATER_PROGRESS_PERSIST_STEP
+                    && last_persisted_percent
+                        .compare_exchange(last, whole_percent, Ordering::Relaxed, Ordering::Relaxed)
+                        .is_ok()
+                {
+                    persist_download_progress(
+                        &app_for_event,
+                        whole_percent,
+                        transferred_now,
+                        total,
+                    );
+                }
             },
             || {},
         )
@@ -817,6 +1062,8 @@ pub async fn update_download(
                 return Ok(serde_json::json!({ "success": false, "error": message }));
             }
 
+            let artifact_size = bytes.len() as u64;
+            let artifact_md5 = artifact_md5_hex(&bytes);
             {
                 let mut downloaded = updater_runtime
                     .downloaded_bytes
@@ -843,6 +1090,14 @@ pub async fn update_download(
                 &artifact_version,
                 &artifact_path,
             );
+            set_state_value(
+                &mut state,
+                "downloadedBytes",
+                serde_json::json!(artifact_size),
+            );
+            set_state_value(&mut state, "totalBytes", serde_json::json!(artifact_size));
+            set_state_value(&mut state, "artifactSize", serde_json::json!(artifact_size));
+            set_state_value(&mut state, "artifactMd5", serde_json::json!(artifact_md5));
             crate::write_update_state(&db, &state)?;
 
             let transferred_final = transferred.load(Ordering::Relaxed);
@@ -965,13 +1220,27 @@ pub async fn update_install(
     };
 
     let bytes = rehydrate_downloaded_bytes(&updater_runtime, &artifact_path)?;
+    if let Err(message) = verify_downloaded_artifact(&bytes, &state) {
+        let _ = clear_runtime_update_state(&updater_runtime);
+        remove_artifact(Some(&artifact_path));
+        reset_update_state(&mut state);
+        set_state_value(&mut state, "error", serde_json::json!(message.clone()));
+        crate::write_update_state(&db, &state)?;
+        let _ = app.emit(
+            "update_error",
+            serde_json::json!({ "message": message.clone() }),
+        );
+        return Ok(serde_json::json!({ "success": false, "error": message }));
+    }
 
     set_installing_state(&mut state, &version);
     crate::write_update_state(&db, &state)?;
+    write_install_marker(&app, &version)?;
 
     match update.install(bytes) {
-        Ok(_) => Ok(serde_json::json!({ "success": true })),
+        Ok(_) => app.restart(),
         Err(error) => {
+            remove_install_marker(&app);
             let message = format!("Failed to install update: {error}");
             let update_info = match state
                 .get("updateInfo")
@@ -1007,7 +1276,7 @@ pub async fn update_set_channel(
         return Err("Invalid update channel".into());
     }
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    db::set_setting(&conn, "general", "update_channel", &channel)?;
+    db::set_setting(&conn, "updater", "channel", &channel)?;
     Ok(serde_json::json!({ "success": true, "channel": channel }))
 }
 
@@ -1103,4 +1372,62 @@ mod dto_tests {
         );
         assert!(release_notes_from_github_payload(&empty_payload).is_none());
     }
+
+    #[test]
+    fn update_channel_resolves_manifest_url() {
+        assert_eq!(normalize_update_channel(Some(" Beta ".into())), "beta");
+        assert_eq!(normalize_update_channel(Some("nightly".into())), "stable");
+        assert_eq!(normalize_update_channel(None), "stable");
+        assert_eq!(
+            manifest_url_for_channel("stable"),
+            crate::UPDATER_MANIFEST_URL
+        );
+        assert_eq!(
+            manifest_url_for_channel("beta"),
+            format!("{UPDATER_CHANNEL_RELEASE_BASE}beta/latest.json")
+        );
+    }
+
+    #[test]
+    fn verify_downloaded_artifact_checks_size_and_checksum() {
+        let bytes = b"installer-payload";
+        let state = serde_json::json!({
+            "artifactSize": bytes.len(),
+            "artifactMd5": artifact_md5_hex(bytes),
+        });
+        assert!(verify_downloaded_artifact(bytes, &state).is_ok());
+        assert!(verify_downloaded_artifact(b"installer-paylo", &state)
+            .unwrap_err()
+            .contains("size mismatch"));
+        assert!(verify_downloaded_artifact(b"installer-PAYLOAD", &state)
+            .unwrap_err()
+            .contains("checksum mismatch"));
+        assert!(verify_downloaded_artifact(b"", &serde_json::json!({})).is_err());
+        // States written before checksums were recorded still install.
+        assert!(verify_downloaded_artifact(bytes, &serde_json::json!({})).is_ok());
+    }
+
+    #[test]
+    fn install_marker_detects_failed_install() {
+        let marker = serde_json::json!({
+            "targetVersion": "v2.4.0",
+            "fromVersion": "2.3.9",
+            "startedAt": "2026-01-01T00:00:00Z",
+        });
+        assert_eq!(
+            classify_install_marker(&marker, "2.4.0"),
+            InstallMarkerOutcome::Completed
+        );
+        assert_eq!(
+            classify_install_marker(&marker, "2.3.9"),
+            InstallMarkerOutcome::Failed {
+                target_version: "v2.4.0".to_string(),
+                started_at: Some("2026-01-01T00:00:00Z".to_string()),
+            }
+        );
+        assert!(matches!(
+            classify_install_marker(&serde_json::Value::Null, "2.3.9"),
+            InstallMarkerOutcome::Failed { .. }
+        ));
+    }
 }
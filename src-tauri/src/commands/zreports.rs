@@ -1,9 +1,9 @@
-use chrono::Utc;
+use chrono::{Local, Utc};
 use serde::Deserialize;
 use tracing::warn;
 
 use crate::fiscal::close_day_guard::{ensure_no_queued_fiscal_for_day, CloseBlockedError};
-use crate::{db, payload_arg0_as_string, zreport};
+use crate::{auth, db, eod, payload_arg0_as_string, zreport};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -77,6 +77,18 @@ fn parse_zreport_id_payload(arg0: Option<serde_json::Value>) -> Result<String, S
         .ok_or("Missing zReportId".into())
 }
 
+/// `autoClose` overrides the `eod.auto_close` setting for a manual run.
+fn parse_eod_run_now_payload(arg0: Option<serde_json::Value>) -> Option<bool> {
+    match arg0 {
+        Some(serde_json::Value::Bool(flag)) => Some(flag),
+        Some(v) => v
+            .get("autoClose")
+            .or_else(|| v.get("auto_close"))
+            .and_then(serde_json::Value::as_bool),
+        None => None,
+    }
+}
+
 fn parse_zreport_list_payload(arg0: Option<serde_json::Value>) -> serde_json::Value {
     match arg0 {
         Some(serde_json::Value::String(shift_id)) => serde_json::json!({
@@ -93,7 +105,18 @@ pub async fn zreport_generate(
     db: tauri::State<'_, db::DbState>,
 ) -> Result<serde_json::Value, String> {
     let payload = parse_zreport_generate_payload(arg0);
+    // The shift rollover aggregates a full day of orders and payments; keep
+    // it off the async executor.
+    db.run_blocking(move |db| generate_or_preview_z_report(db, &payload))
+        .await
+}
 
+/// Generate the shift Z-report (after the local fiscal-queue guard), or
+/// preview a branch/date report without persisting it.
+fn generate_or_preview_z_report(
+    db: &db::DbState,
+    payload: &serde_json::Value,
+) -> Result<serde_json::Value, String> {
     let has_shift_id = payload.get("shiftId").and_then(|v| v.as_str()).is_some()
         || payload.get("shift_id").and_then(|v| v.as_str()).is_some();
     let has_branch_date = payload.get("branchId").and_then(|v| v.as_str()).is_some()
@@ -119,7 +142,7 @@ pub async fn zreport_generate(
                 Ok(g) => g,
                 Err(e) => {
                     warn!("[zreports.fiscal-guard] DB mutex poisoned: {e}");
-                    return zreport::generate_z_report(&db, &payload);
+                    return zreport::generate_z_report(db, payload);
                 }
             };
             match ensure_no_queued_fiscal_for_day(&conn_guard, &branch_id, &business_day_iso) {
@@ -159,9 +182,9 @@ pub async fn zreport_generate(
         // discard in the success path — the caller keeps whatever the
         // generator returns. The preview-and-discard path lives under the
         // `else` arm below (`preview_z_report_for_date`), not here.
-        zreport::generate_z_report(&db, &payload)
+        zreport::generate_z_report(db, payload)
     } else {
-        zreport::preview_z_report_for_date(&db, &payload)
+        zreport::preview_z_report_for_date(db, payload)
     }
 }
 
@@ -196,6 +219,50 @@ pub async fn zreport_print(
     zreport::print_z_report(&db, &payload)
 }
 
+/// Run the end-of-day pass now: remind about open shifts and drawers and,
+/// when auto-close applies, close them and generate the Z-report.
+#[tauri::command]
+pub async fn eod_run_now(
+    arg0: Option<serde_json::Value>,
+    db: tauri::State<'_, db::DbState>,
+    app: tauri::AppHandle,
+    auth_state: tauri::State<'_, auth::AuthState>,
+) -> Result<serde_json::Value, auth::GuardedCommandError> {
+    auth::authorize_privileged_action(
+        auth::PrivilegedActionScope::SystemControl,
+        &db,
+        &auth_state,
+    )?;
+    let auto_close = match parse_eod_run_now_payload(arg0) {
+        Some(flag) => flag,
+        None => {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            eod::load_config(&conn).auto_close
+        }
+    };
+    let actor = auth::current_staff_id(&auth_state);
+    let outcome = eod::run(
+        &db,
+        eod::RunRequest {
+            trigger: eod::TRIGGER_MANUAL,
+            scheduled_for: None,
+            auto_close,
+            actor_staff_id: actor.as_deref(),
+            ecr_busy: eod::ecr_transaction_in_progress(&app),
+            now: Local::now().naive_local(),
+        },
+    )?;
+    eod::emit_run_events(&app, &outcome);
+    Ok(outcome)
+}
+
+#[tauri::command]
+pub async fn eod_get_status(
+    db: tauri::State<'_, db::DbState>,
+) -> Result<serde_json::Value, String> {
+    eod::status(&db, Local::now().naive_local())
+}
+
 #[cfg(test)]
 mod dto_tests {
     use super::*;
@@ -246,6 +313,20 @@ mod dto_tests {
         );
     }
 
+    #[test]
+    fn parse_eod_run_now_payload_reads_auto_close_override() {
+        assert_eq!(parse_eod_run_now_payload(None), None);
+        assert_eq!(
+            parse_eod_run_now_payload(Some(serde_json::json!(true))),
+            Some(true)
+        );
+        assert_eq!(
+            parse_eod_run_now_payload(Some(serde_json::json!({ "auto_close": false }))),
+            Some(false)
+        );
+        assert_eq!(parse_eod_run_now_payload(Some(serde_json::json!({}))), None);
+    }
+
     #[test]
     fn parse_zreport_id_payload_rejects_missing() {
         let err = parse_zreport_id_payload(Some(serde_json::json!({})))
@@ -0,0 +1,282 @@
+//! Connection code QR provisioning.
+//!
+//! The admin dashboard renders a terminal's connection string as a QR code.
+//! The renderer may hand us either the text a camera scanner already decoded
+//! or a base64 PNG of the code; both end up as the same connection string
+//! that `settings_update_terminal_credentials` accepts when pasted.
+//!
+//! Every rejection carries an `errorCode` so the UI can tell an unreadable
+//! image apart from an incomplete or expired code.
+
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use crate::onboarding::{self, DecodedConnectionCode};
+
+/// Images larger than this on either side are rejected before decoding.
+const MAX_IMAGE_SIDE: u32 = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrRejection {
+    pub error_code: &'static str,
+    pub error: String,
+}
+
+impl QrRejection {
+    fn new(error_code: &'static str, error: impl Into<String>) -> Self {
+        Self {
+            error_code,
+            error: error.into(),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "success": false,
+            "errorCode": self.error_code,
+            "error": self.error,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QrInput {
+    /// Text already decoded from the QR by the scanner.
+    Text(String),
+    /// Base64 PNG (optionally a `data:` URL) still to be decoded here.
+    Image(String),
+}
+
+/// Accepts `{ qrText }` / `{ imageBase64 }` objects, or a bare string that is
+/// treated as an image when it is a `data:image/` URL and as text otherwise.
+pub fn parse_input(arg0: Option<Value>) -> Result<QrInput, QrRejection> {
+    let missing = || QrRejection::new("missing_qr_payload", "No QR code text or image provided");
+    let non_empty = |v: &Value| {
+        v.as_str()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+    match arg0.ok_or_else(missing)? {
+        Value::String(s) => {
+            let s = s.trim().to_string();
+            if s.is_empty() {
+                Err(missing())
+            } else if s.starts_with("data:image/") {
+                Ok(QrInput::Image(s))
+            } else {
+                Ok(QrInput::Text(s))
+            }
+        }
+        Value::Object(map) => {
+            let text = ["qrText", "qr_text", "text", "connectionCode"]
+                .iter()
+                .find_map(|k| map.get(*k).and_then(non_empty));
+            if let Some(text) = text {
+                return Ok(QrInput::Text(text));
+            }
+            ["imageBase64", "image_base64", "pngBase64", "image"]
+                .iter()
+                .find_map(|k| map.get(*k).and_then(non_empty))
+                .map(QrInput::Image)
+                .ok_or_else(missing)
+        }
+        _ => Err(missing()),
+    }
+}
+
+/// Decode the first readable QR code in a base64 PNG.
+pub fn decode_qr_image(encoded: &str) -> Result<String, QrRejection> {
+    let invalid = |detail: String| {
+        QrRejection::new(
+            "invalid_qr_image",
+            format!("The QR image could not be read: {detail}"),
+        )
+    };
+    let body = match encoded.trim().split_once(";base64,") {
+        Some((prefix, body)) if prefix.starts_with("data:") => body,
+        _ => encoded.trim(),
+    };
+    let compact: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(compact)
+        .map_err(|e| invalid(e.to_string()))?;
+    let image = image::load_from_memory_with_format(&bytes, image::ImageFormat::Png)
+        .map_err(|e| invalid(e.to_string()))?;
+    if image.width() > MAX_IMAGE_SIDE || image.height() > MAX_IMAGE_SIDE {
+        return Err(invalid(format!(
+            "image is larger than {MAX_IMAGE_SIDE}x{MAX_IMAGE_SIDE}"
+        )));
+    }
+
+    let luma = image.to_luma8();
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        luma.width() as usize,
+        luma.height() as usize,
+        |x, y| luma.get_pixel(x as u32, y as u32).0[0],
+    );
+    let grids = prepared.detect_grids();
+    if grids.is_empty() {
+        return Err(QrRejection::new(
+            "qr_not_found",
+            "No QR code was found in the image",
+        ));
+    }
+    grids
+        .iter()
+        .find_map(|grid| grid.decode().ok().map(|(_, content)| content))
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty())
+        .ok_or_else(|| {
+            QrRejection::new(
+                "qr_unreadable",
+                "A QR code was found but could not be decoded",
+            )
+        })
+}
+
+/// QR codes may wrap the connection string in a link such as
+/// `https://admin.example.com/pos/connect?code=...`; unwrap it when present.
+pub fn connection_string_from_qr_text(text: &str) -> String {
+    let trimmed = text.trim();
+    if let Ok(url) = reqwest::Url::parse(trimmed) {
+        if let Some((_, code)) = url
+            .query_pairs()
+            .find(|(key, _)| key == "code" || key == "connectionCode" || key == "connection")
+        {
+            // An unescaped base64 `+` arrives form-decoded as a space.
+            return code.trim().replace(' ', "+");
+        }
+    }
+    trimmed.to_string()
+}
+
+/// Decode and validate a connection string, rejecting incomplete or expired
+/// codes.
+pub fn check_connection_string(
+    raw: &str,
+    now: DateTime<Utc>,
+) -> Result<DecodedConnectionCode, QrRejection> {
+    let decoded = onboarding::decode_connection_code(raw);
+    let step = onboarding::validate_connection_code(&decoded);
+    if !step.complete {
+        return Err(QrRejection::new(
+            step.error_code.unwrap_or("invalid_connection_code"),
+            step.error.unwrap_or_default(),
+        ));
+    }
+    if let Some(expires_at) = crate::api::extract_expiry_from_connection_string(raw) {
+        if expires_at <= now {
+            return Err(QrRejection::new(
+                "connection_code_expired",
+                format!(
+                    "This connection code expired at {}; generate a new one in the admin dashboard",
+                    expires_at.to_rfc3339()
+                ),
+            ));
+        }
+    }
+    Ok(decoded)
+}
+
+/// Host part of the admin URL, shown to the operator for confirmation.
+pub fn admin_host(admin_url: &str) -> String {
+    reqwest::Url::parse(admin_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| admin_url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection_string(payload: Value) -> String {
+        base64::engine::general_purpose::STANDARD.encode(payload.to_string())
+    }
+
+    fn png_base64(image: image::GrayImage) -> String {
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut bytes, image::ImageFormat::Png).unwrap();
+        base64::engine::general_purpose::STANDARD.encode(bytes.into_inner())
+    }
+
+    #[test]
+    fn parse_input_distinguishes_text_and_images() {
+        assert_eq!(
+            parse_input(Some(serde_json::json!({ "qrText": " abc " }))).unwrap(),
+            QrInput::Text("abc".into())
+        );
+        assert_eq!(
+            parse_input(Some(serde_json::json!({ "imageBase64": "iVBOR" }))).unwrap(),
+            QrInput::Image("iVBOR".into())
+        );
+        assert_eq!(
+            parse_input(Some(serde_json::json!("data:image/png;base64,iVBOR"))).unwrap(),
+            QrInput::Image("data:image/png;base64,iVBOR".into())
+        );
+        assert_eq!(
+            parse_input(Some(serde_json::json!({})))
+                .unwrap_err()
+                .error_code,
+            "missing_qr_payload"
+        );
+    }
+
+    #[test]
+    fn image_errors_have_distinct_codes() {
+        assert_eq!(
+            decode_qr_image("not base64!").unwrap_err().error_code,
+            "invalid_qr_image"
+        );
+        let blank = image::GrayImage::from_pixel(64, 64, image::Luma([255]));
+        assert_eq!(
+            decode_qr_image(&format!("data:image/png;base64,{}", png_base64(blank)))
+                .unwrap_err()
+                .error_code,
+            "qr_not_found"
+        );
+    }
+
+    #[test]
+    fn connection_string_checks_fields_and_expiry() {
+        let now = Utc::now();
+        let code = connection_string(serde_json::json!({
+            "key": "k-123",
+            "url": "https://admin.example.com/api",
+            "tid": "term-1",
+            "exp": (now + chrono::Duration::hours(1)).timestamp(),
+        }));
+        let wrapped = format!("https://admin.example.com/pos/connect?code={code}");
+        let decoded = check_connection_string(&connection_string_from_qr_text(&wrapped), now)
+            .expect("valid code");
+        assert_eq!(decoded.terminal_id.as_deref(), Some("term-1"));
+        assert_eq!(
+            admin_host(decoded.admin_url.as_deref().unwrap()),
+            "admin.example.com"
+        );
+
+        let expired = connection_string(serde_json::json!({
+            "key": "k-123",
+            "url": "https://admin.example.com",
+            "tid": "term-1",
+            "expiresAt": "2020-01-01T00:00:00Z",
+        }));
+        assert_eq!(
+            check_connection_string(&expired, now)
+                .unwrap_err()
+                .error_code,
+            "connection_code_expired"
+        );
+        let no_tid = connection_string(serde_json::json!({
+            "key": "k-123",
+            "url": "https://admin.example.com",
+        }));
+        assert_eq!(
+            check_connection_string(&no_tid, now)
+                .unwrap_err()
+                .error_code,
+            "missing_terminal_id"
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! Connectivity watchdog.
+//!
+//! Probes the admin `/api/health` endpoint on an interval (falling back to a
+//! DNS + TCP reachability check against the admin host) and emits
+//! `network_status` only when the online/offline state actually changes.
+//! Each transition carries the number of rows still waiting in `sync_queue`
+//! so the offline banner can show how much work is queued, and a transition
+//! back online wakes the sync loop immediately.
+//!
+//! Probe cadence and timeout are read from `local_settings` on every cycle
+//! (`network.probe_interval_secs`, `network.probe_timeout_secs`) so changes
+//! take effect without a restart.
+
+use chrono::Utc;
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::db::{self, DbState};
+use crate::storage;
+use crate::sync::{self, SyncState};
+
+const HISTORY_LIMIT: usize = 50;
+const DEFAULT_PROBE_INTERVAL_SECS: u64 = 10;
+const DEFAULT_PROBE_TIMEOUT_SECS: u64 = 5;
+const MIN_PROBE_INTERVAL_SECS: u64 = 2;
+const MAX_PROBE_INTERVAL_SECS: u64 = 300;
+const MIN_PROBE_TIMEOUT_SECS: u64 = 1;
+const MAX_PROBE_TIMEOUT_SECS: u64 = 30;
+/// A single failed probe is treated as transient; only this many consecutive
+/// failures flip the state to offline (mirrors the sync loop's hysteresis).
+const OFFLINE_FLIP_THRESHOLD: u32 = 2;
+
+/// One recorded online/offline transition.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkTransition {
+    pub at: String,
+    pub is_online: bool,
+    /// Which probe decided the state: `http`, `tcp`, or `none` (unconfigured).
+    pub probe: &'static str,
+    pub pending_sync_count: i64,
+    pub latency_ms: Option<u64>,
+}
+
+#[derive(Default)]
+struct Inner {
+    online: Option<bool>,
+    consecutive_failures: u32,
+    last_probe_at: Option<String>,
+    history: VecDeque<NetworkTransition>,
+}
+
+/// Tauri managed state holding the watchdog's current view and transition
+/// history.
+#[derive(Default)]
+pub struct ConnectivityState {
+    inner: Mutex<Inner>,
+}
+
+impl ConnectivityState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Transitions, most recent first.
+    pub fn history(&self) -> Vec<NetworkTransition> {
+        self.inner
+            .lock()
+            .map(|inner| inner.history.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn is_online(&self) -> Option<bool> {
+        self.inner.lock().ok().and_then(|inner| inner.online)
+    }
+
+    /// Fold one probe result into the state. Returns the transition when the
+    /// (debounced) online state changed.
+    fn record_probe(
+        &self,
+        probe: &ProbeResult,
+        pending_sync_count: i64,
+        now: &str,
+    ) -> Option<NetworkTransition> {
+        let mut inner = self.inner.lock().ok()?;
+        inner.last_probe_at = Some(now.to_string());
+        if probe.online {
+            inner.consecutive_failures = 0;
+        } else {
+            inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        }
+
+        let next_online = match inner.online {
+            // First probe decides the initial state without debouncing.
+            None => probe.online,
+            Some(_) if probe.online => true,
+            Some(previous) => previous && inner.consecutive_failures < OFFLINE_FLIP_THRESHOLD,
+        };
+        if inner.online == Some(next_online) {
+            return None;
+        }
+
+        inner.online = Some(next_online);
+        let transition = NetworkTransition {
+            at: now.to_string(),
+            is_online: next_online,
+            probe: probe.method,
+            pending_sync_count,
+            latency_ms: probe.latency_ms,
+        };
+        inner.history.push_back(transition.clone());
+        while inner.history.len() > HISTORY_LIMIT {
+            inner.history.pop_front();
+        }
+        Some(transition)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProbeResult {
+    online: bool,
+    method: &'static str,
+    latency_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ProbeConfig {
+    interval: Duration,
+    timeout: Duration,
+}
+
+fn parse_secs(raw: Option<String>, default: u64, min: u64, max: u64) -> u64 {
+    raw.and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(default)
+        .clamp(min, max)
+}
+
+fn load_probe_config(conn: &Connection) -> ProbeConfig {
+    let interval = parse_secs(
+        db::get_setting(conn, "network", "probe_interval_secs"),
+        DEFAULT_PROBE_INTERVAL_SECS,
+        MIN_PROBE_INTERVAL_SECS,
+        MAX_PROBE_INTERVAL_SECS,
+    );
+    let timeout = parse_secs(
+        db::get_setting(conn, "network", "probe_timeout_secs"),
+        DEFAULT_PROBE_TIMEOUT_SECS,
+        MIN_PROBE_TIMEOUT_SECS,
+        MAX_PROBE_TIMEOUT_SECS,
+    );
+    ProbeConfig {
+        interval: Duration::from_secs(interval),
+        timeout: Duration::from_secs(timeout),
+    }
+}
+
+fn pending_sync_count(conn: &Connection) -> i64 {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sync_queue
+         WHERE status IN ('pending', 'in_progress', 'queued_remote')",
+        [],
+        |row| row.get(0),
+    )
+    .unwrap_or(0)
+}
+
+/// `host:port` for the TCP fallback, derived from the admin dashboard URL.
+fn admin_socket_target(admin_url: &str) -> Option<String> {
+    let parsed = url::Url::parse(admin_url.trim()).ok()?;
+    let host = parsed.host_str()?;
+    let port = parsed.port_or_known_default()?;
+    Some(format!("{host}:{port}"))
+}
+
+async fn tcp_reachable(target: &str, timeout: Duration) -> bool {
+    let lookup = tokio::time::timeout(timeout, tokio::net::lookup_host(target)).await;
+    let Ok(Ok(mut addrs)) = lookup else {
+        return false;
+    };
+    let Some(addr) = addrs.next() else {
+        return false;
+    };
+    matches!(
+        tokio::time::timeout(timeout, tokio::net::TcpStream::connect(addr)).await,
+        Ok(Ok(_))
+    )
+}
+
+async fn probe(timeout: Duration) -> ProbeResult {
+    let Some(admin_url) = storage::get_credential("admin_dashboard_url") else {
+        return ProbeResult {
+            online: false,
+            method: "none",
+            latency_ms: None,
+        };
+    };
+
+    let started = Instant::now();
+    let status = sync::check_network_status_with_timeout(timeout).await;
+    if status.get("isOnline").and_then(Value::as_bool) == Some(true) {
+        return ProbeResult {
+            online: true,
+            method: "http",
+            latency_ms: Some(started.elapsed().as_millis() as u64),
+        };
+    }
+
+    let target = admin_socket_target(&crate::api::normalize_admin_url(&admin_url));
+    let started = Instant::now();
+    let reachable = match target {
+        Some(target) => tcp_reachable(&target, timeout).await,
+        None => false,
+    };
+    ProbeResult {
+        online: reachable,
+        method: "tcp",
+        latency_ms: reachable.then(|| started.elapsed().as_millis() as u64),
+    }
+}
+
+fn transition_payload(transition: &NetworkTransition) -> Value {
+    json!({
+        "isOnline": transition.is_online,
+        "probe": transition.probe,
+        "pendingSyncCount": transition.pending_sync_count,
+        "latencyMs": transition.latency_ms,
+        "changedAt": transition.at,
+    })
+}
+
+/// Start the connectivity watchdog loop.
+pub fn start_connectivity_watchdog(
+    app: AppHandle,
+    db: Arc<DbState>,
+    sync_state: Arc<SyncState>,
+    state: Arc<ConnectivityState>,
+    cancel: CancellationToken,
+) {
+    tauri::async_runtime::spawn(async move {
+        info!("Connectivity watchdog started");
+        loop {
+            if cancel.is_cancelled() {
+                break;
+            }
+
+            let config = match db.conn.lock() {
+                Ok(conn) => load_probe_config(&conn),
+                Err(_) => ProbeConfig {
+                    interval: Duration::from_secs(DEFAULT_PROBE_INTERVAL_SECS),
+                    timeout: Duration::from_secs(DEFAULT_PROBE_TIMEOUT_SECS),
+                },
+            };
+
+            let result = probe(config.timeout).await;
+            let pending = db
+                .conn
+                .lock()
+                .map(|conn| pending_sync_count(&conn))
+                .unwrap_or(0);
+            let now = Utc::now().to_rfc3339();
+
+            if let Some(transition) = state.record_probe(&result, pending, &now) {
+                if transition.is_online {
+                    info!(
+                        probe = transition.probe,
+                        pending_sync_count = pending,
+                        "Connectivity restored"
+                    );
+                    sync_state.request_sync_now();
+                } else {
+                    warn!(pending_sync_count = pending, "Connectivity lost");
+                }
+                let _ = app.emit("network_status", transition_payload(&transition));
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(config.interval) => {}
+                _ = cancel.cancelled() => break,
+            }
+        }
+        info!("Connectivity watchdog stopped");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(online: bool) -> ProbeResult {
+        ProbeResult {
+            online,
+            method: "http",
+            latency_ms: online.then_some(12),
+        }
+    }
+
+    #[test]
+    fn records_only_transitions_with_debounced_offline() {
+        let state = ConnectivityState::new();
+        let first = state.record_probe(&result(true), 0, "t0").expect("initial");
+        assert!(first.is_online);
+        assert!(state.record_probe(&result(true), 0, "t1").is_none());
+        // One failed probe is transient.
+        assert!(state.record_probe(&result(false), 3, "t2").is_none());
+        assert_eq!(state.is_online(), Some(true));
+        let offline = state
+            .record_probe(&result(false), 14, "t3")
+            .expect("offline");
+        assert!(!offline.is_online);
+        assert_eq!(offline.pending_sync_count, 14);
+        let online = state.record_probe(&result(true), 14, "t4").expect("online");
+        assert!(online.is_online);
+
+        let history = state.history();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].at, "t4");
+        assert_eq!(history[2].at, "t0");
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let state = ConnectivityState::new();
+        for i in 0..(HISTORY_LIMIT * 3) {
+            let online = i % 4 < 2;
+            state.record_probe(&result(online), 0, &format!("t{i}"));
+            state.record_probe(&result(online), 0, &format!("t{i}b"));
+        }
+        assert_eq!(state.history().len(), HISTORY_LIMIT);
+    }
+
+    #[test]
+    fn probe_config_reads_and_clamps_settings() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE local_settings (
+                setting_category TEXT, setting_key TEXT, setting_value TEXT
+             );",
+        )
+        .unwrap();
+        assert_eq!(
+            load_probe_config(&conn),
+            ProbeConfig {
+                interval: Duration::from_secs(DEFAULT_PROBE_INTERVAL_SECS),
+                timeout: Duration::from_secs(DEFAULT_PROBE_TIMEOUT_SECS),
+            }
+        );
+        conn.execute_batch(
+            "INSERT INTO local_settings VALUES ('network', 'probe_interval_secs', '30');
+             INSERT INTO local_settings VALUES ('network', 'probe_timeout_secs', '999');",
+        )
+        .unwrap();
+        let config = load_probe_config(&conn);
+        assert_eq!(config.interval, Duration::from_secs(30));
+        assert_eq!(config.timeout, Duration::from_secs(MAX_PROBE_TIMEOUT_SECS));
+    }
+
+    #[test]
+    fn admin_socket_target_uses_default_ports() {
+        assert_eq!(
+            admin_socket_target("https://admin.example.com").as_deref(),
+            Some("admin.example.com:443")
+        );
+        assert_eq!(
+            admin_socket_target("http://10.0.0.5:3001").as_deref(),
+            Some("10.0.0.5:3001")
+        );
+        assert_eq!(admin_socket_target("not a url"), None);
+    }
+}
@@ -1,8 +1,7 @@
 use chrono::{TimeZone, Utc};
-use reqwest::Url;
 use std::path::PathBuf;
 
-use crate::{db, storage, MODULE_CACHE_FILE};
+use crate::{db, MODULE_CACHE_FILE};
 
 pub(crate) fn payload_arg0_as_string(
     arg0: Option<serde_json::Value>,
@@ -38,20 +37,28 @@ pub(crate) fn build_admin_query(path: &str, options: Option<&serde_json::Value>)
     // RFC-compliant server (the admin dashboard uses Next.js URL parsing,
     // which handles both). Reserved chars `& = + ?` are still percent-
     // encoded to `%26 %3D %2B %3F` exactly as before.
-    let mut query: Vec<(String, String)> = Vec::new();
+    //
+    // Array values expand to one pair per element, in array order, so
+    // Supabase-style range filters can repeat a key:
+    // `{"start_time": ["gte.X", "lte.Y"]}` → `start_time=gte.X&start_time=lte.Y`.
+    // Keys follow the JSON map's iteration order; null and empty values are
+    // omitted so optional filters can be passed through unconditionally.
+    let mut query: Vec<(&str, String)> = Vec::new();
     if let Some(serde_json::Value::Object(map)) = options {
         for (k, v) in map {
-            if v.is_null() {
-                continue;
-            }
-            let sval = match v {
-                serde_json::Value::String(s) => s.clone(),
-                serde_json::Value::Bool(b) => b.to_string(),
-                serde_json::Value::Number(n) => n.to_string(),
-                _ => v.to_string(),
-            };
-            if !sval.is_empty() {
-                query.push((k.clone(), sval));
+            match v {
+                serde_json::Value::Array(values) => {
+                    for value in values {
+                        if let Some(sval) = admin_query_value(value) {
+                            query.push((k.as_str(), sval));
+                        }
+                    }
+                }
+                other => {
+                    if let Some(sval) = admin_query_value(other) {
+                        query.push((k.as_str(), sval));
+                    }
+                }
             }
         }
     }
@@ -60,15 +67,28 @@ pub(crate) fn build_admin_query(path: &str, options: Option<&serde_json::Value>)
     }
 
     let serialized = url::form_urlencoded::Serializer::new(String::new())
-        .extend_pairs(query.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+        .extend_pairs(query.iter().map(|(k, v)| (*k, v.as_str())))
         .finish();
 
     let mut out = String::from(path);
-    out.push('?');
+    if !out.ends_with('?') && !out.ends_with('&') {
+        out.push(if out.contains('?') { '&' } else { '?' });
+    }
     out.push_str(&serialized);
     out
 }
 
+fn admin_query_value(value: &serde_json::Value) -> Option<String> {
+    let sval = match value {
+        serde_json::Value::Null => return None,
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Bool(b) => b.to_string(),
+        serde_json::Value::Number(n) => n.to_string(),
+        other => other.to_string(),
+    };
+    (!sval.is_empty()).then_some(sval)
+}
+
 /// Reject any `terminal_id` value that cannot be safely interpolated into
 /// a URL path segment.
 ///
@@ -263,6 +283,8 @@ pub(crate) fn write_module_cache(
 }
 
 pub(crate) fn clear_operational_data_inner(db: &db::DbState) -> Result<serde_json::Value, String> {
+    // `idempotency_cache` is kept on purpose: wiping it while a webview
+    // retry is still pending would let that retry execute a second time.
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     conn.execute_batch(
         "
@@ -292,70 +314,6 @@ pub(crate) fn clear_operational_data_inner(db: &db::DbState) -> Result<serde_jso
     }))
 }
 
-pub(crate) async fn fetch_supabase_rows(
-    path: &str,
-    params: &[(&str, String)],
-) -> Result<serde_json::Value, String> {
-    let supabase_url =
-        storage::get_credential("supabase_url").ok_or("Supabase not configured: missing URL")?;
-    let supabase_key = storage::get_credential("supabase_anon_key")
-        .ok_or("Supabase not configured: missing anon key")?;
-
-    let base = supabase_url.trim_end_matches('/');
-    let mut url = Url::parse(&format!("{base}/rest/v1/{path}"))
-        .map_err(|e| format!("Invalid Supabase URL: {e}"))?;
-    {
-        let mut qp = url.query_pairs_mut();
-        for (k, v) in params {
-            qp.append_pair(k, v);
-        }
-    }
-
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(20))
-        .build()
-        .map_err(|e| format!("HTTP client error: {e}"))?;
-    let mut request = client
-        .get(url)
-        .header("apikey", &supabase_key)
-        .header("Authorization", format!("Bearer {supabase_key}"))
-        .header("Content-Type", "application/json");
-
-    if let Some(terminal_id) = storage::get_credential("terminal_id")
-        .map(|value| value.trim().to_string())
-        .filter(|value| !value.is_empty())
-    {
-        request = request.header("x-terminal-id", terminal_id);
-    }
-    if let Some(api_key) = storage::get_credential("pos_api_key")
-        .map(|value| value.trim().to_string())
-        .filter(|value| !value.is_empty())
-    {
-        request = request.header("x-pos-api-key", api_key);
-    }
-
-    let resp = request
-        .send()
-        .await
-        .map_err(|e| format!("Supabase request failed: {e}"))?;
-    if !resp.status().is_success() {
-        let status = resp.status();
-        // Wave 9 H4: surface body-read errors instead of collapsing them to
-        // an empty string. A truncated body (e.g. network dropped mid-read)
-        // used to be indistinguishable from a legitimately empty error
-        // body, hiding the real cause. The fallback message keeps the
-        // status code visible so the caller still has actionable info.
-        let body = resp
-            .text()
-            .await
-            .unwrap_or_else(|e| format!("<failed to read body: {e}>"));
-        return Err(format!("Supabase error ({status}): {body}"));
-    }
-    resp.json::<serde_json::Value>()
-        .await
-        .map_err(|e| format!("Supabase JSON parse error: {e}"))
-}
-
 fn default_update_state() -> serde_json::Value {
     serde_json::json!({
         "checking": false,
@@ -369,6 +327,10 @@ fn default_update_state() -> serde_json::Value {
         "downloadedArtifactPath": serde_json::Value::Null,
         "installPending": false,
         "installingVersion": serde_json::Value::Null,
+        "downloadedBytes": 0,
+        "totalBytes": serde_json::Value::Null,
+        "artifactSize": serde_json::Value::Null,
+        "artifactMd5": serde_json::Value::Null,
     })
 }
 
@@ -507,10 +469,7 @@ mod tests {
             [],
         )
         .expect("seed recovery log");
-        let db = crate::db::DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        };
+        let db = crate::db::DbState::new(conn, std::path::PathBuf::from(":memory:"));
 
         clear_operational_data_inner(&db).expect("clear operational data");
 
@@ -637,6 +596,74 @@ mod tests {
         assert!(actual.starts_with("/api/pos/probe?"));
     }
 
+    #[test]
+    fn build_admin_query_encodes_percent_and_already_encoded_sequences() {
+        let options = serde_json::json!({
+            "search": "50% off",
+            "pre": "a%20b",
+            "path": "/api/x:y",
+        });
+        assert_eq!(
+            build_admin_query("/api/pos/probe", Some(&options)),
+            "/api/pos/probe?path=%2Fapi%2Fx%3Ay&pre=a%2520b&search=50%25+off"
+        );
+    }
+
+    #[test]
+    fn build_admin_query_encodes_greek_search_terms() {
+        let options = serde_json::json!({ "search": "Γιώργος Παπ" });
+        let actual = build_admin_query("/api/pos/customers", Some(&options));
+        let decoded: Vec<(String, String)> =
+            url::form_urlencoded::parse(actual.split_once('?').unwrap().1.as_bytes())
+                .into_owned()
+                .collect();
+        assert_eq!(
+            decoded,
+            vec![("search".to_string(), "Γιώργος Παπ".to_string())]
+        );
+        assert!(
+            actual.is_ascii(),
+            "query must be fully percent-encoded: {actual}"
+        );
+    }
+
+    #[test]
+    fn build_admin_query_repeats_keys_for_array_values_in_order() {
+        let options = serde_json::json!({
+            "start_time": ["gte.2026-01-01T00:00:00Z", "lte.2026-01-31T23:59:59Z"],
+            "status": "eq.closed",
+        });
+        assert_eq!(
+            build_admin_query("/api/pos/shifts", Some(&options)),
+            "/api/pos/shifts?start_time=gte.2026-01-01T00%3A00%3A00Z\
+             &start_time=lte.2026-01-31T23%3A59%3A59Z&status=eq.closed"
+        );
+    }
+
+    #[test]
+    fn build_admin_query_skips_null_and_empty_values() {
+        let options = serde_json::json!({
+            "date_to": null,
+            "search": "",
+            "tags": ["", null, "vip"],
+            "limit": 50,
+            "active": false,
+        });
+        assert_eq!(
+            build_admin_query("/api/pos/orders", Some(&options)),
+            "/api/pos/orders?active=false&limit=50&tags=vip"
+        );
+    }
+
+    #[test]
+    fn build_admin_query_appends_to_existing_query_string() {
+        let options = serde_json::json!({ "limit": 10 });
+        assert_eq!(
+            build_admin_query("/api/pos/orders?status=open", Some(&options)),
+            "/api/pos/orders?status=open&limit=10"
+        );
+    }
+
     #[test]
     fn build_admin_query_returns_path_unchanged_when_no_options() {
         assert_eq!(
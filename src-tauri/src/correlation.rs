@@ -0,0 +1,192 @@
+//! Request correlation ids.
+//!
+//! Every IPC command gets an id (the frontend may supply one through the
+//! `x-correlation-id` invoke header or a `correlationId` payload field).
+//! The id is carried in a tracing span, sent as `X-Correlation-Id` on admin
+//! and Supabase requests made while the command runs, stored on sync queue
+//! rows it enqueues, and echoed back under `_meta.correlationId` alongside
+//! `_meta.training`, the current training-mode flag.
+//!
+//! Synchronous commands run inline inside the invoke handler and pick the
+//! id up from [`enter_sync`]. Async commands are spawned by Tauri on a fresh
+//! task, so commands that talk to the admin API wrap their body in
+//! [`command`] to establish the same scope.
+
+use std::cell::RefCell;
+use std::future::Future;
+
+use serde_json::Value;
+use tracing::Instrument;
+
+/// Header name used on outbound admin/Supabase requests.
+pub const HEADER: &str = "X-Correlation-Id";
+
+/// Longest frontend-supplied id we accept verbatim.
+const MAX_ID_LEN: usize = 64;
+
+tokio::task_local! {
+    static TASK_ID: String;
+}
+
+thread_local! {
+    static SYNC_ID: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+pub fn new_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Accept a caller-supplied id only when it is a short token of
+/// alphanumerics, `-` and `_`; anything else is replaced by a fresh id.
+pub fn sanitize(raw: &str) -> Option<String> {
+    let trimmed = raw.trim();
+    let valid = !trimmed.is_empty()
+        && trimmed.len() <= MAX_ID_LEN
+        && trimmed
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| trimmed.to_string())
+}
+
+/// Id supplied in a command payload, either at the top level or inside
+/// `arg0`.
+pub fn from_payload(payload: Option<&Value>) -> Option<String> {
+    let payload = payload?;
+    ["correlationId", "_correlationId"]
+        .iter()
+        .find_map(|key| payload.get(*key).and_then(Value::as_str))
+        .and_then(sanitize)
+        .or_else(|| {
+            payload
+                .get("arg0")
+                .filter(|inner| inner.is_object())
+                .and_then(|inner| from_payload(Some(inner)))
+        })
+}
+
+/// Id for an incoming IPC call: the `x-correlation-id` header, then the
+/// payload, then a fresh id.
+pub fn for_invoke<R: tauri::Runtime>(message: &tauri::ipc::InvokeMessage<R>) -> String {
+    message
+        .headers()
+        .get("x-correlation-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(sanitize)
+        .or_else(|| match message.payload() {
+            tauri::ipc::InvokeBody::Json(payload) => from_payload(Some(payload)),
+            tauri::ipc::InvokeBody::Raw(_) => None,
+        })
+        .unwrap_or_else(new_id)
+}
+
+/// Id of the command currently running on this task or thread, if any.
+pub fn current() -> Option<String> {
+    TASK_ID
+        .try_with(Clone::clone)
+        .ok()
+        .or_else(|| SYNC_ID.with(|id| id.borrow().clone()))
+}
+
+/// Id for an outbound request: the current command's, or a fresh one for
+/// background work outside any command.
+pub fn current_or_new() -> String {
+    current().unwrap_or_else(new_id)
+}
+
+/// Run `fut` with `id` as the current correlation id and inside a span
+/// carrying it.
+pub async fn scope<F: Future>(id: String, fut: F) -> F::Output {
+    let span = tracing::info_span!("correlated", correlation_id = %id);
+    TASK_ID.scope(id, fut.instrument(span)).await
+}
+
+/// Restores the previous thread-local id when a synchronous command returns.
+pub struct SyncGuard {
+    previous: Option<String>,
+    _span: tracing::span::EnteredSpan,
+}
+
+impl Drop for SyncGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        SYNC_ID.with(|id| *id.borrow_mut() = previous);
+    }
+}
+
+/// Make `id` current on this thread until the guard is dropped.
+pub fn enter_sync(id: String) -> SyncGuard {
+    let span = tracing::info_span!("correlated", correlation_id = %id).entered();
+    let previous = SYNC_ID.with(|current| current.borrow_mut().replace(id));
+    SyncGuard {
+        previous,
+        _span: span,
+    }
+}
+
+/// Add `_meta.correlationId` and `_meta.training` to an object response;
+/// other shapes are left untouched.
+pub fn attach(value: &mut Value, id: &str) {
+    if let Value::Object(map) = value {
+        let meta = map
+            .entry("_meta")
+            .or_insert_with(|| Value::Object(serde_json::Map::new()));
+        if let Value::Object(meta) = meta {
+            meta.insert("correlationId".into(), Value::String(id.to_string()));
+            meta.insert("training".into(), Value::Bool(crate::training::is_active()));
+        }
+    }
+}
+
+/// Run an async command body under a correlation scope and tag its JSON
+/// response. `requested` is the id the frontend asked for, if any.
+pub async fn command<F>(requested: Option<String>, fut: F) -> Result<Value, String>
+where
+    F: Future<Output = Result<Value, String>>,
+{
+    let id = requested.or_else(current).unwrap_or_else(new_id);
+    let mut result = scope(id.clone(), fut).await;
+    if let Ok(value) = result.as_mut() {
+        attach(value, &id);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_ids_are_sanitized() {
+        let payload = serde_json::json!({ "arg0": { "correlationId": "abc-123" } });
+        assert_eq!(from_payload(Some(&payload)).as_deref(), Some("abc-123"));
+        let hostile = serde_json::json!({ "correlationId": "x\ny: injected" });
+        assert_eq!(from_payload(Some(&hostile)), None);
+        assert_eq!(sanitize(&"a".repeat(MAX_ID_LEN + 1)), None);
+    }
+
+    #[tokio::test]
+    async fn command_scopes_id_and_tags_response() {
+        assert_eq!(current(), None);
+        let response = command(Some("req-1".into()), async {
+            assert_eq!(current().as_deref(), Some("req-1"));
+            Ok(serde_json::json!({ "success": true }))
+        })
+        .await
+        .unwrap();
+        assert_eq!(response["_meta"]["correlationId"], "req-1");
+        assert_eq!(response["_meta"]["training"], false);
+        assert_eq!(current(), None);
+    }
+
+    #[test]
+    fn sync_guard_restores_previous_id() {
+        let outer = enter_sync("outer".into());
+        {
+            let _inner = enter_sync("inner".into());
+            assert_eq!(current().as_deref(), Some("inner"));
+        }
+        assert_eq!(current().as_deref(), Some("outer"));
+        drop(outer);
+        assert_eq!(current(), None);
+    }
+}
@@ -0,0 +1,359 @@
+//! Fire-by-course kitchen tickets for dine-in orders.
+//!
+//! Item lines carry a `course` number (1..=`order_schema::MAX_COURSE`,
+//! default 1) in the order's items JSON. Course 1 goes to the kitchen with
+//! the first ticket; every later course is held until the server fires it
+//! with `order_fire_course`. Fire state lives in `order_courses`, one row per
+//! fired course.
+//!
+//! Fire tickets are ordinary `kitchen_ticket` print jobs whose payload names
+//! the courses to print and the "FIRE: MAINS" header; the ticket renders
+//! only those courses' items. Orders whose items are all in course 1 are not
+//! course-aware and print exactly as before.
+
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+
+use crate::order_events;
+use crate::sync::order_schema::MAX_COURSE;
+
+/// Course of an item line. Missing or invalid values fall back to course 1,
+/// as the order schema does.
+pub fn item_course(item: &Value) -> u32 {
+    let raw = match item.get("course") {
+        Some(Value::Number(n)) => n.as_f64(),
+        Some(Value::String(s)) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+    raw.filter(|n| n.fract() == 0.0 && (1.0..=f64::from(MAX_COURSE)).contains(n))
+        .map(|n| n as u32)
+        .unwrap_or(1)
+}
+
+pub fn course_name(course: u32) -> String {
+    match course {
+        1 => "STARTERS".to_string(),
+        2 => "MAINS".to_string(),
+        3 => "DESSERTS".to_string(),
+        n => format!("COURSE {n}"),
+    }
+}
+
+/// Ticket title for a fire ticket, e.g. `FIRE: MAINS`.
+pub fn fire_header(courses: &[u32]) -> String {
+    let names: Vec<String> = courses.iter().map(|course| course_name(*course)).collect();
+    format!("FIRE: {}", names.join(" + "))
+}
+
+fn load_items(conn: &Connection, order_id: &str) -> Result<Vec<Value>, String> {
+    let items_json: String = conn
+        .query_row(
+            "SELECT COALESCE(items, '[]') FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("load order items: {e}"))?
+        .ok_or_else(|| format!("Order not found: {order_id}"))?;
+    Ok(serde_json::from_str::<Value>(&items_json)
+        .ok()
+        .and_then(|value| value.as_array().cloned())
+        .unwrap_or_default())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CourseState {
+    pub course: u32,
+    pub item_count: usize,
+    pub fired_at: Option<String>,
+    pub fired_by: Option<String>,
+    pub fire_count: i64,
+}
+
+impl CourseState {
+    pub fn is_fired(&self) -> bool {
+        self.fired_at.is_some()
+    }
+
+    /// Later courses wait for an explicit fire.
+    pub fn is_held(&self) -> bool {
+        !self.is_fired() && self.course > 1
+    }
+
+    fn state(&self) -> &'static str {
+        if self.is_fired() {
+            "fired"
+        } else if self.is_held() {
+            "held"
+        } else {
+            "pending"
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "course": self.course,
+            "name": course_name(self.course),
+            "itemCount": self.item_count,
+            "state": self.state(),
+            "firedAt": self.fired_at,
+            "firedBy": self.fired_by,
+            "fireCount": self.fire_count,
+        })
+    }
+}
+
+/// Every course on the order, lowest first, with its fire state. Courses
+/// that were fired and later emptied by an item edit are kept.
+pub fn status(conn: &Connection, order_id: &str) -> Result<Vec<CourseState>, String> {
+    let mut courses: BTreeMap<u32, CourseState> = BTreeMap::new();
+    for item in load_items(conn, order_id)? {
+        let course = item_course(&item);
+        courses
+            .entry(course)
+            .or_insert_with(|| CourseState {
+                course,
+                item_count: 0,
+                fired_at: None,
+                fired_by: None,
+                fire_count: 0,
+            })
+            .item_count += 1;
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT course, fired_at, fired_by, fire_count
+             FROM order_courses WHERE order_id = ?1",
+        )
+        .map_err(|e| format!("read order courses: {e}"))?;
+    let rows = stmt
+        .query_map(params![order_id], |row| {
+            Ok((
+                row.get::<_, u32>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })
+        .map_err(|e| format!("read order courses: {e}"))?;
+    for row in rows {
+        let (course, fired_at, fired_by, fire_count) =
+            row.map_err(|e| format!("read order courses: {e}"))?;
+        let state = courses.entry(course).or_insert_with(|| CourseState {
+            course,
+            item_count: 0,
+            fired_at: None,
+            fired_by: None,
+            fire_count: 0,
+        });
+        state.fired_at = Some(fired_at);
+        state.fired_by = fired_by;
+        state.fire_count = fire_count;
+    }
+    Ok(courses.into_values().collect())
+}
+
+pub fn status_json(conn: &Connection, order_id: &str) -> Result<Value, String> {
+    let courses = status(conn, order_id)?;
+    let course_aware = courses.iter().any(|state| state.course > 1);
+    Ok(json!({
+        "success": true,
+        "orderId": order_id,
+        "courseAware": course_aware,
+        "courses": courses.iter().map(CourseState::to_json).collect::<Vec<_>>(),
+    }))
+}
+
+/// Courses a plain `kitchen_print_ticket` prints: the unfired courses before
+/// the first held one. `None` when the order is not course-aware.
+pub fn default_ticket_courses(
+    conn: &Connection,
+    order_id: &str,
+) -> Result<Option<Vec<u32>>, String> {
+    let courses = status(conn, order_id)?;
+    if !courses.iter().any(|state| state.course > 1) {
+        return Ok(None);
+    }
+    Ok(Some(
+        courses
+            .iter()
+            .take_while(|state| !state.is_held())
+            .filter(|state| !state.is_fired() && state.item_count > 0)
+            .map(|state| state.course)
+            .collect(),
+    ))
+}
+
+/// Check that `course` can be fired: it must have items, and a course that
+/// was already fired only prints again as an explicit reprint.
+pub fn check_fire(
+    conn: &Connection,
+    order_id: &str,
+    course: u32,
+    reprint: bool,
+) -> Result<(), String> {
+    let courses = status(conn, order_id)?;
+    let state = courses
+        .iter()
+        .find(|state| state.course == course && state.item_count > 0)
+        .ok_or_else(|| format!("Order has no items in course {course}"))?;
+    if state.is_fired() && !reprint {
+        return Err(format!(
+            "Course {course} ({}) was already fired; pass reprint: true to print it again",
+            course_name(course)
+        ));
+    }
+    Ok(())
+}
+
+/// Print job payload for a fire ticket.
+pub fn ticket_payload(courses: &[u32], reprint: bool) -> Value {
+    json!({
+        "courses": courses,
+        "courseHeader": fire_header(courses),
+        "reprint": reprint,
+    })
+}
+
+/// Courses named by a kitchen ticket job payload, if any.
+pub fn ticket_courses(payload: Option<&Value>) -> Option<Vec<u32>> {
+    let courses = payload?.get("courses")?.as_array()?;
+    Some(
+        courses
+            .iter()
+            .filter_map(Value::as_u64)
+            .map(|course| course as u32)
+            .collect(),
+    )
+}
+
+/// Mark `courses` fired and add a timeline event per course with the staff
+/// member who fired it. Fired courses keep their first fire time; reprints
+/// bump `fire_count`.
+pub fn record_fire(
+    conn: &Connection,
+    order_id: &str,
+    courses: &[u32],
+    actor_staff_id: Option<&str>,
+    reprint: bool,
+) -> Result<(), String> {
+    let now = Utc::now().to_rfc3339();
+    for course in courses {
+        conn.execute(
+            "INSERT INTO order_courses (order_id, course, fired_at, fired_by, fire_count, last_fired_at)
+             VALUES (?1, ?2, ?3, ?4, 1, ?3)
+             ON CONFLICT(order_id, course) DO UPDATE SET
+                fire_count = fire_count + 1,
+                last_fired_at = excluded.last_fired_at",
+            params![order_id, course, now, actor_staff_id],
+        )
+        .map_err(|e| format!("record course fire: {e}"))?;
+        order_events::append(
+            conn,
+            order_id,
+            order_events::COURSE_FIRED,
+            actor_staff_id,
+            json!({
+                "course": course,
+                "name": course_name(*course),
+                "reprint": reprint,
+            }),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn insert_order(conn: &Connection, order_id: &str, items: Value) {
+        conn.execute(
+            "INSERT INTO orders (id, items, total_amount, status, order_type, created_at, updated_at)
+             VALUES (?1, ?2, 30.0, 'pending', 'dine-in', datetime('now'), datetime('now'))",
+            params![order_id, items.to_string()],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn item_course_defaults_and_names() {
+        assert_eq!(item_course(&json!({ "name": "Soup" })), 1);
+        assert_eq!(item_course(&json!({ "course": 2 })), 2);
+        assert_eq!(item_course(&json!({ "course": "3" })), 3);
+        assert_eq!(item_course(&json!({ "course": 0 })), 1);
+        assert_eq!(fire_header(&[2]), "FIRE: MAINS");
+        assert_eq!(fire_header(&[1, 4]), "FIRE: STARTERS + COURSE 4");
+    }
+
+    #[test]
+    fn default_ticket_fires_starters_and_holds_later_courses() {
+        let conn = test_conn();
+        insert_order(
+            &conn,
+            "ord-plain",
+            json!([{ "name": "Coffee", "quantity": 1, "price": 2.5 }]),
+        );
+        assert_eq!(default_ticket_courses(&conn, "ord-plain").unwrap(), None);
+
+        insert_order(
+            &conn,
+            "ord-courses",
+            json!([
+                { "name": "Soup", "quantity": 1, "price": 5.0 },
+                { "name": "Steak", "quantity": 1, "price": 20.0, "course": 2 },
+                { "name": "Cake", "quantity": 1, "price": 5.0, "course": 3 }
+            ]),
+        );
+        assert_eq!(
+            default_ticket_courses(&conn, "ord-courses").unwrap(),
+            Some(vec![1])
+        );
+        record_fire(&conn, "ord-courses", &[1], Some("staff-1"), false).unwrap();
+        assert_eq!(
+            default_ticket_courses(&conn, "ord-courses").unwrap(),
+            Some(vec![])
+        );
+
+        let err = check_fire(&conn, "ord-courses", 1, false).unwrap_err();
+        assert!(err.contains("reprint: true"), "{err}");
+        assert!(check_fire(&conn, "ord-courses", 1, true).is_ok());
+        assert!(check_fire(&conn, "ord-courses", 2, false).is_ok());
+        assert!(check_fire(&conn, "ord-courses", 5, false).is_err());
+
+        record_fire(&conn, "ord-courses", &[2], Some("staff-2"), false).unwrap();
+        record_fire(&conn, "ord-courses", &[2], Some("staff-3"), true).unwrap();
+        let status = status_json(&conn, "ord-courses").unwrap();
+        assert_eq!(status["courseAware"], true);
+        let courses = status["courses"].as_array().unwrap();
+        assert_eq!(courses[1]["state"], "fired");
+        assert_eq!(courses[1]["firedBy"], "staff-2");
+        assert_eq!(courses[1]["fireCount"], 2);
+        assert_eq!(courses[2]["state"], "held");
+
+        let events = order_events::timeline(&conn, "ord-courses").unwrap();
+        let fires: Vec<(&Value, &Value)> = events
+            .iter()
+            .filter(|event| event["eventType"] == order_events::COURSE_FIRED)
+            .map(|event| (&event["actorStaffId"], &event["summary"]["course"]))
+            .collect();
+        assert_eq!(
+            fires,
+            vec![
+                (&json!("staff-1"), &json!(1)),
+                (&json!("staff-2"), &json!(2)),
+                (&json!("staff-3"), &json!(2)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,427 @@
+//! Keyring vs `local_settings` credential consistency report.
+//!
+//! Terminal credentials live in the OS keyring, with legacy plaintext copies
+//! in `local_settings` (category `terminal`) that
+//! `hydrate_terminal_credentials_from_local_settings` copies over the keyring
+//! on every call. When the two disagree (an old API key in one, a new one in
+//! the other) hydration silently picks `local_settings`. [`validate`]
+//! compares both sources for every key in `HYDRATED_CREDENTIAL_KEYS` and
+//! reports, per key, the masked values, a status and which source hydration
+//! would apply:
+//!
+//! | status         | meaning                                   | winner           |
+//! |----------------|-------------------------------------------|------------------|
+//! | `match`        | both set and equal after normalization    | either           |
+//! | `mismatch`     | both set, different values                | `local_settings` |
+//! | `keyring_only` | only the keyring has a value              | `keyring`        |
+//! | `local_only`   | only `local_settings` has a value         | `local_settings` |
+//! | `missing`      | neither source has a value                | —                |
+//!
+//! Local values are normalized the way hydration does: a connection string
+//! in `pos_api_key` contributes its decoded key, terminal id and admin URL,
+//! the legacy `admin_url` row overrides `admin_dashboard_url`, and the
+//! `terminal-001` placeholder counts as unset.
+//!
+//! [`repair`] reconciles with the keyring winning unless it is empty: for a
+//! `mismatch` the stale `local_settings` row is removed so hydration stops
+//! overwriting the keyring, and for `local_only` the local value is written
+//! to the keyring. Every reconciliation is recorded in `settings_history`
+//! with source `credentials_repair` and masked values.
+
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::db::{self, DbState};
+use crate::terminal_helpers::HYDRATED_CREDENTIAL_KEYS;
+use crate::{api, storage};
+
+pub const INCONSISTENT_EVENT: &str = "credentials_inconsistent";
+const REPAIR_SOURCE: &str = "credentials_repair";
+const PLACEHOLDER_TERMINAL_ID: &str = "terminal-001";
+const REPAIR_PASSES: usize = 3;
+const REQUIRED_KEYS: &[&str] = &["terminal_id", "pos_api_key", "admin_dashboard_url"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Match,
+    Mismatch,
+    KeyringOnly,
+    LocalOnly,
+    Missing,
+}
+
+impl Status {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Match => "match",
+            Self::Mismatch => "mismatch",
+            Self::KeyringOnly => "keyring_only",
+            Self::LocalOnly => "local_only",
+            Self::Missing => "missing",
+        }
+    }
+
+    /// Source hydration applies for this status.
+    fn hydration_winner(self) -> Option<&'static str> {
+        match self {
+            Self::Match | Self::Mismatch | Self::LocalOnly => Some("local_settings"),
+            Self::KeyringOnly => Some("keyring"),
+            Self::Missing => None,
+        }
+    }
+}
+
+/// A local value together with the `local_settings` row it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LocalValue {
+    value: String,
+    setting_key: &'static str,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    key: &'static str,
+    keyring: Option<String>,
+    local: Option<LocalValue>,
+    status: Status,
+}
+
+impl Entry {
+    fn to_json(&self) -> Value {
+        json!({
+            "key": self.key,
+            "status": self.status.as_str(),
+            "required": REQUIRED_KEYS.contains(&self.key),
+            "keyring": self.keyring.as_deref().map(api::redact),
+            "localSettings": self.local.as_ref().map(|local| api::redact(&local.value)),
+            "localSettingKey": self.local.as_ref().map(|local| local.setting_key),
+            "winner": self.status.hydration_winner(),
+        })
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn normalize(key: &str, value: String) -> Option<String> {
+    let value = non_empty(Some(value))?;
+    match key {
+        "admin_dashboard_url" => non_empty(Some(api::normalize_admin_url(&value))),
+        "terminal_id" if value == PLACEHOLDER_TERMINAL_ID => None,
+        _ => Some(value),
+    }
+}
+
+/// The value hydration would copy into the keyring for `key`, mirroring the
+/// order in which it applies overrides.
+fn effective_local_value(conn: &Connection, key: &'static str) -> Option<LocalValue> {
+    let local = |setting_key: &'static str| {
+        normalize(key, db::get_setting(conn, "terminal", setting_key)?)
+            .map(|value| LocalValue { value, setting_key })
+    };
+    let connection_string = non_empty(db::get_setting(conn, "terminal", "pos_api_key"));
+    let decoded = |extract: fn(&str) -> Option<String>| {
+        let raw = connection_string.as_deref()?;
+        api::extract_api_key_from_connection_string(raw)?;
+        normalize(key, extract(raw)?).map(|value| LocalValue {
+            value,
+            setting_key: "pos_api_key",
+        })
+    };
+    match key {
+        "pos_api_key" => {
+            let raw = connection_string.as_deref()?;
+            let value =
+                api::extract_api_key_from_connection_string(raw).unwrap_or_else(|| raw.to_string());
+            normalize(key, value).map(|value| LocalValue {
+                value,
+                setting_key: "pos_api_key",
+            })
+        }
+        "terminal_id" => {
+            decoded(api::extract_terminal_id_from_connection_string).or_else(|| local(key))
+        }
+        "admin_dashboard_url" => local("admin_url")
+            .or_else(|| local("admin_dashboard_url"))
+            .or_else(|| decoded(api::extract_admin_url_from_connection_string)),
+        _ => local(key),
+    }
+}
+
+fn collect(conn: &Connection) -> Vec<Entry> {
+    HYDRATED_CREDENTIAL_KEYS
+        .iter()
+        .map(|&key| {
+            let keyring = storage::get_credential(key).and_then(|value| normalize(key, value));
+            let local = effective_local_value(conn, key);
+            let status = match (&keyring, &local) {
+                (Some(keyring), Some(local)) if *keyring == local.value => Status::Match,
+                (Some(_), Some(_)) => Status::Mismatch,
+                (Some(_), None) => Status::KeyringOnly,
+                (None, Some(_)) => Status::LocalOnly,
+                (None, None) => Status::Missing,
+            };
+            Entry {
+                key,
+                keyring,
+                local,
+                status,
+            }
+        })
+        .collect()
+}
+
+fn report(entries: &[Entry], repaired: Vec<Value>) -> Value {
+    let mismatches: Vec<&str> = entries
+        .iter()
+        .filter(|entry| entry.status == Status::Mismatch)
+        .map(|entry| entry.key)
+        .collect();
+    let missing: Vec<&str> = entries
+        .iter()
+        .filter(|entry| entry.status == Status::Missing && REQUIRED_KEYS.contains(&entry.key))
+        .map(|entry| entry.key)
+        .collect();
+    json!({
+        "success": true,
+        "consistent": mismatches.is_empty(),
+        "mismatches": mismatches,
+        "missing": missing,
+        "entries": entries.iter().map(Entry::to_json).collect::<Vec<_>>(),
+        "repaired": repaired,
+    })
+}
+
+/// Compare keyring and `local_settings` without changing either.
+pub fn validate(db: &DbState) -> Result<Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(report(&collect(&conn), Vec::new()))
+}
+
+/// Reconcile disagreements (keyring wins unless empty) and return the report
+/// as it stands afterwards, with a `repaired` list of the actions taken.
+pub fn repair(db: &DbState) -> Result<Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut repaired = Vec::new();
+    // Removing one stale row can expose the next source in line (e.g. the
+    // legacy `admin_url` shadowing `admin_dashboard_url`), so re-check.
+    for _ in 0..REPAIR_PASSES {
+        let before = repaired.len();
+        repair_pass(&conn, &mut repaired)?;
+        if repaired.len() == before {
+            break;
+        }
+    }
+    Ok(report(&collect(&conn), repaired))
+}
+
+fn repair_pass(conn: &Connection, repaired: &mut Vec<Value>) -> Result<(), String> {
+    for entry in collect(conn) {
+        match (entry.status, &entry.keyring, &entry.local) {
+            (Status::Mismatch, Some(keyring), Some(local)) => {
+                remove_stale_local_row(conn, entry.key, local, keyring)?;
+                repaired.push(json!({
+                    "key": entry.key,
+                    "action": "removed_local_setting",
+                    "localSettingKey": local.setting_key,
+                }));
+            }
+            (Status::LocalOnly, None, Some(local)) => {
+                storage::set_credential(entry.key, &local.value)?;
+                db::record_setting_change(
+                    conn,
+                    "terminal",
+                    entry.key,
+                    None,
+                    Some(&api::redact(&local.value)),
+                    REPAIR_SOURCE,
+                    None,
+                )?;
+                info!(key = entry.key, "Copied local credential into keyring");
+                repaired.push(json!({
+                    "key": entry.key,
+                    "action": "wrote_keyring",
+                    "localSettingKey": local.setting_key,
+                }));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Drop the `local_settings` row that disagrees with the keyring. A
+/// connection string in `pos_api_key` also feeds `terminal_id` and the admin
+/// URL; when it is only stale for one of those and the keyring has no API
+/// key to fall back on, the row is rewritten to the bare API key instead.
+fn remove_stale_local_row(
+    conn: &Connection,
+    key: &str,
+    local: &LocalValue,
+    keyring: &str,
+) -> Result<(), String> {
+    let previous = db::get_setting(conn, "terminal", local.setting_key);
+    let replacement = if local.setting_key == "pos_api_key"
+        && key != "pos_api_key"
+        && storage::get_credential("pos_api_key")
+            .map(|v| v.trim().is_empty())
+            .unwrap_or(true)
+    {
+        previous
+            .as_deref()
+            .and_then(api::extract_api_key_from_connection_string)
+    } else {
+        None
+    };
+
+    match &replacement {
+        Some(api_key) => conn.execute(
+            "UPDATE local_settings SET setting_value = ?3, updated_at = datetime('now')
+             WHERE setting_category = ?1 AND setting_key = ?2",
+            params!["terminal", local.setting_key, api_key],
+        ),
+        None => conn.execute(
+            "DELETE FROM local_settings WHERE setting_category = ?1 AND setting_key = ?2",
+            params!["terminal", local.setting_key],
+        ),
+    }
+    .map_err(|e| format!("repair {key} credential: {e}"))?;
+
+    db::record_setting_change(
+        conn,
+        "terminal",
+        local.setting_key,
+        previous.as_deref().map(api::redact).as_deref(),
+        replacement.as_deref().map(api::redact).as_deref(),
+        REPAIR_SOURCE,
+        None,
+    )?;
+    warn!(
+        key,
+        setting_key = local.setting_key,
+        keyring = %api::redact(keyring),
+        local = %api::redact(&local.value),
+        "Removed stale local credential in favour of keyring"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::fake_keyring;
+    use std::path::PathBuf;
+
+    fn test_db() -> DbState {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        db::run_migrations_for_test(&conn);
+        DbState::new(conn, PathBuf::from(":memory:"))
+    }
+
+    fn entry<'a>(report: &'a Value, key: &str) -> &'a Value {
+        report["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entry| entry["key"] == key)
+            .unwrap()
+    }
+
+    fn set_local(db: &DbState, key: &str, value: &str) {
+        let conn = db.conn.lock().unwrap();
+        db::set_setting(&conn, "terminal", key, value).unwrap();
+    }
+
+    #[test]
+    fn validate_reports_status_and_hydration_winner_with_masked_values() {
+        let _keyring = fake_keyring::install_seeded([
+            ("pos_api_key", "new-api-key-1234"),
+            ("terminal_id", "term-7"),
+            ("branch_id", "branch-1"),
+        ]);
+        let db = test_db();
+        set_local(&db, "pos_api_key", "old-api-key-9999");
+        set_local(&db, "terminal_id", "term-7");
+        set_local(&db, "organization_id", "org-1");
+        set_local(&db, "admin_url", "admin.example.com/api/");
+
+        let report = validate(&db).unwrap();
+        assert_eq!(report["consistent"], false);
+        assert_eq!(report["mismatches"], json!(["pos_api_key"]));
+        assert_eq!(report["missing"], json!([]));
+
+        let api_key = entry(&report, "pos_api_key");
+        assert_eq!(api_key["status"], "mismatch");
+        assert_eq!(api_key["winner"], "local_settings");
+        assert_eq!(api_key["keyring"], "...1234");
+        assert_eq!(api_key["localSettings"], "...9999");
+
+        assert_eq!(entry(&report, "terminal_id")["status"], "match");
+        assert_eq!(entry(&report, "branch_id")["status"], "keyring_only");
+        assert_eq!(entry(&report, "branch_id")["winner"], "keyring");
+        assert_eq!(entry(&report, "organization_id")["status"], "local_only");
+        let url = entry(&report, "admin_dashboard_url");
+        assert_eq!(url["status"], "local_only");
+        assert_eq!(url["localSettingKey"], "admin_url");
+        assert_eq!(entry(&report, "supabase_url")["status"], "missing");
+    }
+
+    #[test]
+    fn placeholder_terminal_id_counts_as_missing() {
+        let _keyring = fake_keyring::install_empty();
+        let db = test_db();
+        set_local(&db, "terminal_id", PLACEHOLDER_TERMINAL_ID);
+        let report = validate(&db).unwrap();
+        assert_eq!(entry(&report, "terminal_id")["status"], "missing");
+        assert_eq!(
+            report["missing"],
+            json!(["terminal_id", "pos_api_key", "admin_dashboard_url"])
+        );
+    }
+
+    #[test]
+    fn repair_prefers_keyring_unless_empty_and_audits_masked_values() {
+        let _keyring = fake_keyring::install_seeded([("pos_api_key", "new-api-key-1234")]);
+        let db = test_db();
+        set_local(&db, "pos_api_key", "old-api-key-9999");
+        set_local(&db, "branch_id", "branch-42");
+
+        let report = repair(&db).unwrap();
+        assert_eq!(report["consistent"], true);
+        assert_eq!(report["repaired"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            storage::get_credential("pos_api_key").as_deref(),
+            Some("new-api-key-1234")
+        );
+        assert_eq!(
+            storage::get_credential("branch_id").as_deref(),
+            Some("branch-42")
+        );
+        assert_eq!(entry(&report, "pos_api_key")["status"], "keyring_only");
+        assert_eq!(entry(&report, "branch_id")["status"], "match");
+
+        let conn = db.conn.lock().unwrap();
+        assert_eq!(db::get_setting(&conn, "terminal", "pos_api_key"), None);
+        let audit: Vec<(String, Option<String>, Option<String>)> = conn
+            .prepare(
+                "SELECT setting_key, old_value, new_value FROM settings_history
+                 WHERE source = 'credentials_repair' ORDER BY id",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            audit,
+            vec![
+                ("pos_api_key".to_string(), Some("...9999".to_string()), None),
+                ("branch_id".to_string(), None, Some("...h-42".to_string())),
+            ]
+        );
+    }
+}
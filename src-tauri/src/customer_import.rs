@@ -0,0 +1,702 @@
+//! Bulk customer import from CSV.
+//!
+//! Customers live in the `local.customer_cache_v1` JSON cache and reach the
+//! admin through `customers` rows in the sync queue, exactly like an
+//! offline `customer_create`. The import streams the file record by
+//! record, maps configurable columns onto the customer-create payload,
+//! dedupes on the normalized phone against the cache (and earlier rows of
+//! the same file), and writes every [`BATCH_SIZE`] rows inside one
+//! savepoint: the cache plus that batch's queue rows. A bad row is
+//! reported with its line number and never stops the import; a dry run
+//! goes through the same steps without writing.
+
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use chrono::Utc;
+use serde_json::{json, Value};
+
+use crate::commands::customers::{
+    build_local_customer_from_source, build_remote_customer_create_body,
+    build_remote_customer_update_body,
+};
+use crate::{db, normalize_phone, sync_queue, value_str};
+
+pub const PROGRESS_EVENT: &str = "customer_import_progress";
+pub const COMPLETED_EVENT: &str = "customer_import_completed";
+pub const BATCH_SIZE: usize = 200;
+
+const CACHE_KEY: &str = "customer_cache_v1";
+/// A quoted field still open after this many lines is treated as a stray
+/// quote, so one bad row cannot swallow the rest of the file.
+const MAX_RECORD_LINES: usize = 50;
+const PHONE_KEYS: &[&str] = &["phone", "customerPhone", "mobile", "telephone"];
+
+/// What to do when an imported phone already belongs to a customer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateStrategy {
+    #[default]
+    Skip,
+    Update,
+    CreateDuplicate,
+}
+
+impl DuplicateStrategy {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "skip" => Some(Self::Skip),
+            "update" | "merge" => Some(Self::Update),
+            "create_duplicate" | "create" | "duplicate" => Some(Self::CreateDuplicate),
+            _ => None,
+        }
+    }
+}
+
+/// Customer fields a CSV column can map to, with the header names
+/// recognised without configuration (compared lowercase, letters and
+/// digits only).
+const FIELDS: &[(&str, &[&str])] = &[
+    ("name", &["name", "fullname", "customer", "customername"]),
+    ("first_name", &["firstname", "first", "givenname"]),
+    ("last_name", &["lastname", "last", "surname", "familyname"]),
+    (
+        "phone",
+        &["phone", "phonenumber", "mobile", "telephone", "tel", "cell"],
+    ),
+    ("email", &["email", "emailaddress", "mail"]),
+    (
+        "address",
+        &["address", "street", "streetaddress", "address1"],
+    ),
+    ("city", &["city", "town"]),
+    ("postal_code", &["postalcode", "postcode", "zip", "zipcode"]),
+    ("floor_number", &["floor", "floornumber"]),
+    ("name_on_ringer", &["nameonringer", "ringer", "doorbell"]),
+    ("notes", &["notes", "note", "comments", "comment"]),
+];
+
+fn header_key(header: &str) -> String {
+    header
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Field → CSV header overrides, e.g. `{"phone": "Mobile No"}`.
+    pub columns: HashMap<String, String>,
+    pub strategy: DuplicateStrategy,
+    pub dry_run: bool,
+    /// Field separator; detected from the header line when `None`.
+    pub delimiter: Option<char>,
+    pub branch_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowFailure {
+    /// Line of the file the record starts on; the header is line 1.
+    pub row: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportSummary {
+    pub rows: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub failed: Vec<RowFailure>,
+    pub dry_run: bool,
+}
+
+impl ImportSummary {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "rows": self.rows,
+            "created": self.created,
+            "updated": self.updated,
+            "skipped": self.skipped,
+            "failedCount": self.failed.len(),
+            "failed": self
+                .failed
+                .iter()
+                .map(|failure| json!({ "row": failure.row, "reason": failure.reason }))
+                .collect::<Vec<_>>(),
+            "dryRun": self.dry_run,
+        })
+    }
+}
+
+/// One CSV record and the line it starts on.
+pub type CsvRecord = (usize, Vec<String>);
+
+/// RFC 4180 reader over a [`BufRead`]: quoted fields may hold the
+/// delimiter, doubled quotes and line breaks. Reads one record at a time.
+pub struct CsvReader<R> {
+    reader: R,
+    delimiter: Option<char>,
+    line: usize,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    pub fn new(reader: R, delimiter: Option<char>) -> Self {
+        Self {
+            reader,
+            delimiter,
+            line: 0,
+        }
+    }
+
+    fn read_line(&mut self) -> Result<Option<String>, String> {
+        let mut buf = String::new();
+        let read = self
+            .reader
+            .read_line(&mut buf)
+            .map_err(|e| format!("read CSV line {}: {e}", self.line + 1))?;
+        if read == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        if self.line == 1 {
+            if let Some(stripped) = buf.strip_prefix('\u{feff}') {
+                buf = stripped.to_string();
+            }
+        }
+        while buf.ends_with('\n') || buf.ends_with('\r') {
+            buf.pop();
+        }
+        Ok(Some(buf))
+    }
+
+    /// The next record, `Ok(None)` at end of input, or `Err((line, reason))`
+    /// for a record that could not be read; reading can continue after it.
+    pub fn next_record(&mut self) -> Result<Option<CsvRecord>, (usize, String)> {
+        let first = loop {
+            match self.read_line() {
+                Ok(Some(line)) if line.trim().is_empty() => continue,
+                Ok(Some(line)) => break line,
+                Ok(None) => return Ok(None),
+                Err(e) => return Err((self.line + 1, e)),
+            }
+        };
+        let start = self.line;
+        let delimiter = *self
+            .delimiter
+            .get_or_insert_with(|| detect_delimiter(&first));
+
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut line = first;
+        let mut lines = 1;
+        loop {
+            let mut chars = line.chars().peekable();
+            while let Some(c) = chars.next() {
+                if in_quotes {
+                    if c == '"' {
+                        if chars.peek() == Some(&'"') {
+                            chars.next();
+                            field.push('"');
+                        } else {
+                            in_quotes = false;
+                        }
+                    } else {
+                        field.push(c);
+                    }
+                } else if c == '"' && field.trim().is_empty() {
+                    field.clear();
+                    in_quotes = true;
+                } else if c == delimiter {
+                    fields.push(std::mem::take(&mut field));
+                } else {
+                    field.push(c);
+                }
+            }
+            if !in_quotes {
+                break;
+            }
+            if lines >= MAX_RECORD_LINES {
+                return Err((start, "Unterminated quoted field".into()));
+            }
+            match self.read_line() {
+                Ok(Some(next)) => {
+                    field.push('\n');
+                    line = next;
+                    lines += 1;
+                }
+                Ok(None) => return Err((start, "Unterminated quoted field".into())),
+                Err(e) => return Err((start, e)),
+            }
+        }
+        fields.push(field);
+        Ok(Some((start, fields)))
+    }
+}
+
+/// The separator used most often outside quotes in the header line.
+fn detect_delimiter(header: &str) -> char {
+    let mut counts = [(',', 0usize), (';', 0), ('\t', 0), ('|', 0)];
+    let mut in_quotes = false;
+    for c in header.chars() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes {
+            if let Some(entry) = counts.iter_mut().find(|(d, _)| *d == c) {
+                entry.1 += 1;
+            }
+        }
+    }
+    counts
+        .iter()
+        .max_by_key(|(_, count)| *count)
+        .filter(|(_, count)| *count > 0)
+        .map(|(d, _)| *d)
+        .unwrap_or(',')
+}
+
+/// Column index of each field, from the configured overrides or the
+/// default header names.
+fn resolve_columns(
+    headers: &[String],
+    overrides: &HashMap<String, String>,
+) -> Result<HashMap<&'static str, usize>, String> {
+    if let Some(unknown) = overrides
+        .keys()
+        .find(|field| !FIELDS.iter().any(|(known, _)| known == &field.as_str()))
+    {
+        return Err(format!("Unknown customer field: {unknown}"));
+    }
+    let keys: Vec<String> = headers.iter().map(|h| header_key(h)).collect();
+    let mut columns = HashMap::new();
+    for (field, aliases) in FIELDS {
+        let index = match overrides.get(*field) {
+            Some(header) => {
+                let wanted = header_key(header);
+                let index = keys.iter().position(|key| *key == wanted);
+                Some(index.ok_or_else(|| format!("Column \"{header}\" is not in the file"))?)
+            }
+            None => keys.iter().position(|key| aliases.contains(&key.as_str())),
+        };
+        if let Some(index) = index {
+            columns.insert(*field, index);
+        }
+    }
+    if !columns.contains_key("phone") {
+        return Err("The file has no phone column".into());
+    }
+    if !["name", "first_name", "last_name"]
+        .iter()
+        .any(|field| columns.contains_key(field))
+    {
+        return Err("The file has no name column".into());
+    }
+    Ok(columns)
+}
+
+/// The `customer_create` payload for one record, or why it cannot be
+/// imported.
+fn map_record(
+    record: &[String],
+    columns: &HashMap<&'static str, usize>,
+    branch_id: Option<&str>,
+) -> Result<Value, String> {
+    let cell = |field: &str| {
+        columns
+            .get(field)
+            .and_then(|index| record.get(*index))
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    };
+    let name = cell("name").map(str::to_string).or_else(|| {
+        let parts: Vec<&str> = [cell("first_name"), cell("last_name")]
+            .into_iter()
+            .flatten()
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    });
+    let name = name.ok_or("Missing name")?;
+    let phone = cell("phone").ok_or("Missing phone")?;
+    if normalize_phone(phone).len() < 5 {
+        return Err(format!("Invalid phone: {phone}"));
+    }
+    if let Some(email) = cell("email") {
+        if !email.contains('@') || email.contains(char::is_whitespace) {
+            return Err(format!("Invalid email: {email}"));
+        }
+    }
+
+    let mut source = serde_json::Map::new();
+    source.insert("name".into(), json!(name));
+    source.insert("phone".into(), json!(phone));
+    for field in [
+        "email",
+        "address",
+        "city",
+        "postal_code",
+        "floor_number",
+        "name_on_ringer",
+        "notes",
+    ] {
+        if let Some(value) = cell(field) {
+            source.insert(field.into(), json!(value));
+        }
+    }
+    if let Some(branch_id) = branch_id {
+        source.insert("branch_id".into(), json!(branch_id));
+    }
+    Ok(Value::Object(source))
+}
+
+enum PendingWrite {
+    Insert {
+        id: String,
+        body: Value,
+    },
+    Update {
+        id: String,
+        body: Value,
+        version: i64,
+    },
+}
+
+struct Importer<'a> {
+    options: &'a ImportOptions,
+    organization_id: String,
+    cache: Vec<Value>,
+    by_phone: HashMap<String, usize>,
+    pending: Vec<PendingWrite>,
+    summary: ImportSummary,
+}
+
+impl Importer<'_> {
+    fn apply(&mut self, source: Value) {
+        let phone = normalize_phone(&value_str(&source, &["phone"]).unwrap_or_default());
+        let existing = self.by_phone.get(&phone).copied();
+        match (existing, self.options.strategy) {
+            (Some(_), DuplicateStrategy::Skip) => self.summary.skipped += 1,
+            (Some(index), DuplicateStrategy::Update) => self.update(index, &source),
+            _ => {
+                let customer = build_local_customer_from_source(&source);
+                let id = value_str(&customer, &["id"]).unwrap_or_default();
+                self.pending.push(PendingWrite::Insert {
+                    id,
+                    body: build_remote_customer_create_body(&source),
+                });
+                self.cache.push(customer);
+                self.by_phone.entry(phone).or_insert(self.cache.len() - 1);
+                self.summary.created += 1;
+            }
+        }
+    }
+
+    /// Overwrite the imported fields on an existing customer, adding the
+    /// imported address only when the customer has none.
+    fn update(&mut self, index: usize, source: &Value) {
+        let mut updates = source.clone();
+        if let Some(map) = updates.as_object_mut() {
+            map.remove("phone");
+            map.remove("branch_id");
+        }
+        let imported = build_local_customer_from_source(source);
+        let entry = &mut self.cache[index];
+        let id = value_str(entry, &["id", "customerId"]).unwrap_or_default();
+        let version = entry.get("version").and_then(Value::as_i64).unwrap_or(1) + 1;
+        if let (Some(dst), Some(src)) = (entry.as_object_mut(), updates.as_object()) {
+            for key in ["name", "email", "notes"] {
+                if let Some(value) = src.get(key) {
+                    dst.insert(key.to_string(), value.clone());
+                }
+            }
+            let has_address = dst
+                .get("addresses")
+                .and_then(Value::as_array)
+                .is_some_and(|addresses| !addresses.is_empty());
+            if !has_address {
+                if let Some(addresses) = imported.get("addresses") {
+                    dst.insert("addresses".to_string(), addresses.clone());
+                }
+            }
+            dst.insert("version".to_string(), json!(version));
+            dst.insert("updatedAt".to_string(), json!(Utc::now().to_rfc3339()));
+        }
+        let body = build_remote_customer_update_body(&updates);
+        if body.as_object().is_some_and(|map| !map.is_empty()) {
+            self.pending
+                .push(PendingWrite::Update { id, body, version });
+        }
+        self.summary.updated += 1;
+    }
+
+    /// Write the cache and this batch's queue rows in one savepoint.
+    fn flush(&mut self, db: &db::DbState) -> Result<(), String> {
+        if self.options.dry_run || self.pending.is_empty() {
+            self.pending.clear();
+            return Ok(());
+        }
+        let pending = std::mem::take(&mut self.pending);
+        let cache = Value::Array(self.cache.clone()).to_string();
+        db.write(|conn| {
+            conn.execute_batch("SAVEPOINT customer_import_batch")
+                .map_err(|e| format!("savepoint customer_import_batch: {e}"))?;
+            let result = (|| -> Result<(), String> {
+                db::set_setting(conn, "local", CACHE_KEY, &cache)?;
+                for write in &pending {
+                    let (id, operation, body, version) = match write {
+                        PendingWrite::Insert { id, body } => (id, "INSERT", body, 1),
+                        PendingWrite::Update { id, body, version } => {
+                            (id, "UPDATE", body, *version)
+                        }
+                    };
+                    sync_queue::enqueue(
+                        conn,
+                        &sync_queue::EnqueueInput {
+                            table_name: "customers".to_string(),
+                            record_id: id.clone(),
+                            operation: operation.to_string(),
+                            data: body.to_string(),
+                            organization_id: self.organization_id.clone(),
+                            priority: Some(0),
+                            module_type: Some("customers".to_string()),
+                            conflict_strategy: Some("manual".to_string()),
+                            version: Some(version.max(1)),
+                        },
+                    )?;
+                }
+                Ok(())
+            })();
+            match result {
+                Ok(()) => conn
+                    .execute_batch("RELEASE customer_import_batch")
+                    .map_err(|e| format!("release customer_import_batch: {e}")),
+                Err(e) => {
+                    let _ = conn.execute_batch(
+                        "ROLLBACK TO customer_import_batch; RELEASE customer_import_batch",
+                    );
+                    Err(e)
+                }
+            }
+        })
+    }
+}
+
+/// Import every record of `reader`. `progress` is called with the running
+/// summary after each batch of [`BATCH_SIZE`] rows. Fails only when the
+/// header is unusable or a batch cannot be written; earlier batches stay
+/// imported in that case.
+pub fn import_customers<R: BufRead>(
+    db: &db::DbState,
+    reader: R,
+    options: &ImportOptions,
+    organization_id: &str,
+    mut progress: impl FnMut(&ImportSummary),
+) -> Result<ImportSummary, String> {
+    let mut csv = CsvReader::new(reader, options.delimiter);
+    let headers = match csv.next_record() {
+        Ok(Some((_, headers))) => headers,
+        Ok(None) => return Err("The file is empty".into()),
+        Err((_, reason)) => return Err(format!("Unreadable header: {reason}")),
+    };
+    let columns = resolve_columns(&headers, &options.columns)?;
+
+    let cache = db.read(|conn| {
+        Ok(db::get_setting(conn, "local", CACHE_KEY)
+            .and_then(|raw| serde_json::from_str::<Vec<Value>>(&raw).ok())
+            .unwrap_or_default())
+    })?;
+    let mut by_phone = HashMap::new();
+    for (index, entry) in cache.iter().enumerate() {
+        let phone = value_str(entry, PHONE_KEYS)
+            .map(|phone| normalize_phone(&phone))
+            .unwrap_or_default();
+        if !phone.is_empty() {
+            by_phone.entry(phone).or_insert(index);
+        }
+    }
+    let mut importer = Importer {
+        options,
+        organization_id: organization_id.to_string(),
+        cache,
+        by_phone,
+        pending: Vec::new(),
+        summary: ImportSummary {
+            dry_run: options.dry_run,
+            ..ImportSummary::default()
+        },
+    };
+
+    let mut next_flush = BATCH_SIZE;
+    loop {
+        let mapped = match csv.next_record() {
+            Ok(Some((row, fields))) => map_record(&fields, &columns, options.branch_id.as_deref())
+                .map_err(|reason| (row, reason)),
+            Ok(None) => break,
+            Err(failure) => Err(failure),
+        };
+        importer.summary.rows += 1;
+        match mapped {
+            Ok(source) => importer.apply(source),
+            Err((row, reason)) => importer.summary.failed.push(RowFailure { row, reason }),
+        }
+        if importer.summary.rows >= next_flush {
+            next_flush += BATCH_SIZE;
+            importer.flush(db)?;
+            progress(&importer.summary);
+        }
+    }
+    importer.flush(db)?;
+    progress(&importer.summary);
+    Ok(importer.summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    fn test_db() -> db::DbState {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        db::DbState::new(conn, PathBuf::from(":memory:"))
+    }
+
+    fn queued(db: &db::DbState, operation: &str) -> i64 {
+        db.read(|conn| {
+            conn.query_row(
+                "SELECT COUNT(*) FROM parity_sync_queue WHERE table_name = 'customers' AND operation = ?1",
+                [operation],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn csv_reader_handles_quotes_line_breaks_and_bad_rows() {
+        let text = "\u{feff}Name;Phone;Notes\r\n\"Doe; Jane\";210 1234567;\"Ring \"\"twice\"\"\nthen wait\"\r\n\r\nBob;6900000000;ok\n";
+        let mut csv = CsvReader::new(Cursor::new(text), None);
+        assert_eq!(
+            csv.next_record().unwrap(),
+            Some((1, vec!["Name".into(), "Phone".into(), "Notes".into()]))
+        );
+        assert_eq!(
+            csv.next_record().unwrap(),
+            Some((
+                2,
+                vec![
+                    "Doe; Jane".into(),
+                    "210 1234567".into(),
+                    "Ring \"twice\"\nthen wait".into()
+                ]
+            ))
+        );
+        assert_eq!(csv.next_record().unwrap().unwrap().0, 5);
+        assert_eq!(csv.next_record().unwrap(), None);
+
+        let mut broken = CsvReader::new(Cursor::new("a,b\n\"open,1\n"), None);
+        broken.next_record().unwrap();
+        assert_eq!(broken.next_record().unwrap_err().0, 2);
+    }
+
+    #[test]
+    fn import_dedupes_by_phone_and_reports_failed_rows() {
+        let db = test_db();
+        db.write(|conn| {
+            db::set_setting(
+                conn,
+                "local",
+                CACHE_KEY,
+                &json!([{ "id": "cust-1", "name": "Old", "phone": "210-111-2222", "version": 3 }])
+                    .to_string(),
+            )
+        })
+        .unwrap();
+        let text = "Full Name,Mobile,E-mail,Street\n\
+                    Maria,2101112222,maria@example.com,Main 1\n\
+                    ,6900000001,,\n\
+                    Nikos,6900000002,not-an-email,\n\
+                    Eleni,6900000003,,Side 2\n\
+                    Eleni Again,690 000 0003,,\n";
+
+        let dry = ImportOptions {
+            strategy: DuplicateStrategy::Update,
+            dry_run: true,
+            ..ImportOptions::default()
+        };
+        let summary = import_customers(&db, Cursor::new(text), &dry, "org-1", |_| {}).unwrap();
+        assert_eq!((summary.created, summary.updated), (1, 2));
+        assert_eq!(queued(&db, "INSERT"), 0, "dry run writes nothing");
+
+        let options = ImportOptions {
+            strategy: DuplicateStrategy::Update,
+            ..ImportOptions::default()
+        };
+        let mut batches = 0;
+        let summary =
+            import_customers(&db, Cursor::new(text), &options, "org-1", |_| batches += 1).unwrap();
+        assert_eq!(summary.rows, 5);
+        assert_eq!(summary.created, 1);
+        assert_eq!(
+            summary.updated, 2,
+            "the cached customer and the repeated phone"
+        );
+        assert_eq!(
+            summary.failed,
+            vec![
+                RowFailure {
+                    row: 3,
+                    reason: "Missing name".into()
+                },
+                RowFailure {
+                    row: 4,
+                    reason: "Invalid email: not-an-email".into()
+                },
+            ]
+        );
+        assert_eq!(batches, 1);
+        assert_eq!(queued(&db, "INSERT"), 1);
+        assert_eq!(queued(&db, "UPDATE"), 2);
+
+        let cache: Vec<Value> = db
+            .read(|conn| {
+                Ok(
+                    serde_json::from_str(&db::get_setting(conn, "local", CACHE_KEY).unwrap())
+                        .unwrap(),
+                )
+            })
+            .unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache[0]["name"], "Maria");
+        assert_eq!(cache[0]["version"], 4);
+        assert_eq!(cache[1]["name"], "Eleni Again");
+
+        let skip = import_customers(
+            &db,
+            Cursor::new(text),
+            &ImportOptions::default(),
+            "org-1",
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!((skip.created, skip.skipped), (0, 3));
+    }
+
+    #[test]
+    fn column_overrides_must_name_existing_headers() {
+        let headers: Vec<String> = vec!["Customer".into(), "Mobile No".into()];
+        let mut overrides = HashMap::new();
+        overrides.insert("phone".to_string(), "Mobile No".to_string());
+        let columns = resolve_columns(&headers, &overrides).unwrap();
+        assert_eq!(columns["phone"], 1);
+        overrides.insert("email".to_string(), "Mail".to_string());
+        assert!(resolve_columns(&headers, &overrides).is_err());
+        assert_eq!(
+            DuplicateStrategy::parse("create-duplicate"),
+            Some(DuplicateStrategy::CreateDuplicate)
+        );
+    }
+}
@@ -0,0 +1,743 @@
+//! Data subject requests for one customer: export what the terminal holds
+//! about them, and erase it.
+//!
+//! A customer is named by id, phone, or both. Their orders are the ones
+//! carrying that customer id or, digits only, exactly that phone — never a
+//! partial match, so another customer's orders are never exported or
+//! touched.
+//!
+//! [`export_customer_data`] writes the customer record, their addresses,
+//! loyalty entry and the identifying fields of their orders (number, date,
+//! status, total) to a JSON file in `exports/`.
+//!
+//! [`erase_customer`] anonymises rather than deletes. Orders and
+//! reservations keep every financial figure but lose name, phone, email and
+//! address; the customer leaves the customer cache, conflicts, loyalty
+//! cache and caller ID log. A `customer_erasures` row in the sync queue asks
+//! the cloud to do the same, and `recovery_action_log` records the erasure
+//! with a SHA-256 of the phone instead of the phone. Erasure is refused
+//! while the customer has an open, unpaid order.
+
+use std::fs;
+
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::db::{self, DbState};
+use crate::{normalize_phone, reports_export, retention, sync_queue, value_str};
+
+pub const ERASURE_SYNC_ENTITY: &str = "customer_erasures";
+const CACHE_KEY: &str = "customer_cache_v1";
+const CONFLICTS_KEY: &str = "customer_conflicts_v1";
+const AUDIT_ACTION_ID: &str = "customer_privacy";
+const ERASE_ISSUE_CODE: &str = "customer_erase";
+const EXPORT_FILE_PREFIX: &str = "customer_data";
+const PHONE_KEYS: &[&str] = &["phone", "customerPhone", "mobile", "telephone"];
+/// Order statuses after which an order is no longer open.
+const CLOSED_ORDER_STATUSES: &str =
+    "'completed', 'delivered', 'cancelled', 'canceled', 'refunded', 'declined'";
+/// Order columns that identify the customer, blanked on erasure. Totals,
+/// taxes and payments are left alone for the books.
+const ORDER_PERSONAL_COLUMNS: &[&str] = &[
+    "customer_name",
+    "customer_phone",
+    "customer_email",
+    "customer_id",
+    "delivery_address",
+    "delivery_notes",
+    "delivery_latitude",
+    "delivery_longitude",
+    "delivery_address_id",
+    "delivery_address_fingerprint",
+];
+const RESERVATION_PERSONAL_COLUMNS: &[&str] = &[
+    "customer_id",
+    "customer_name",
+    "customer_phone",
+    "customer_email",
+];
+
+/// The customer a request is about.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CustomerRef {
+    pub customer_id: Option<String>,
+    pub phone: Option<String>,
+}
+
+impl CustomerRef {
+    /// `{ customerId?, phone? }`, or a bare string: a phone when it has no
+    /// letters, otherwise a customer id.
+    pub fn parse(payload: &Value) -> Result<Self, String> {
+        let target = match payload {
+            Value::String(raw) if raw.chars().any(|c| c.is_ascii_alphabetic()) => Self {
+                customer_id: Some(raw.trim().to_string()),
+                phone: None,
+            },
+            Value::String(raw) => Self {
+                customer_id: None,
+                phone: Some(raw.trim().to_string()),
+            },
+            _ => Self {
+                customer_id: value_str(payload, &["customerId", "customer_id", "id"]),
+                phone: value_str(payload, PHONE_KEYS),
+            },
+        };
+        let has_phone = target
+            .phone
+            .as_deref()
+            .is_some_and(|phone| !normalize_phone(phone).is_empty());
+        if target.customer_id.is_none() && !has_phone {
+            return Err("Missing customerId or phone".into());
+        }
+        Ok(target)
+    }
+}
+
+/// The customer as found locally.
+struct Subject {
+    customer_id: Option<String>,
+    /// Digits only; empty when no phone is known.
+    phone: String,
+    record: Option<Value>,
+}
+
+impl Subject {
+    fn matches(&self, customer_id: Option<&str>, phone: Option<&str>) -> bool {
+        let id_matches = self
+            .customer_id
+            .as_deref()
+            .is_some_and(|id| customer_id == Some(id));
+        let phone_matches = !self.phone.is_empty()
+            && phone.is_some_and(|phone| normalize_phone(phone) == self.phone);
+        id_matches || phone_matches
+    }
+
+    fn matches_entry(&self, entry: &Value) -> bool {
+        self.matches(
+            value_str(entry, &["id", "customerId", "customer_id"]).as_deref(),
+            value_str(entry, PHONE_KEYS).as_deref(),
+        )
+    }
+
+    fn phone_hash(&self) -> Option<String> {
+        (!self.phone.is_empty()).then(|| sha256_hex(self.phone.as_bytes()))
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn read_json_array(conn: &Connection, key: &str) -> Vec<Value> {
+    db::get_setting(conn, "local", key)
+        .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+        .and_then(|parsed| parsed.as_array().cloned())
+        .unwrap_or_default()
+}
+
+fn resolve_subject(conn: &Connection, target: &CustomerRef) -> Subject {
+    let wanted = Subject {
+        customer_id: target.customer_id.clone(),
+        phone: target
+            .phone
+            .as_deref()
+            .map(normalize_phone)
+            .unwrap_or_default(),
+        record: None,
+    };
+    let record = read_json_array(conn, CACHE_KEY)
+        .into_iter()
+        .find(|entry| wanted.matches_entry(entry));
+    let Some(record) = record else {
+        return wanted;
+    };
+    Subject {
+        customer_id: wanted
+            .customer_id
+            .or_else(|| value_str(&record, &["id", "customerId"])),
+        phone: if wanted.phone.is_empty() {
+            value_str(&record, PHONE_KEYS)
+                .map(|phone| normalize_phone(&phone))
+                .unwrap_or_default()
+        } else {
+            wanted.phone
+        },
+        record: Some(record),
+    }
+}
+
+/// Ids of `table` rows belonging to the subject, by customer id or exact
+/// phone digits.
+fn matching_row_ids(
+    conn: &Connection,
+    table: &str,
+    id_column: &str,
+    phone_column: &str,
+    subject: &Subject,
+) -> Result<Vec<String>, String> {
+    if !retention::table_exists(conn, table) {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, {id_column}, {phone_column} FROM {table}
+             WHERE {id_column} IS NOT NULL OR {phone_column} IS NOT NULL"
+        ))
+        .map_err(|e| format!("prepare {table} lookup: {e}"))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })
+        .map_err(|e| format!("query {table}: {e}"))?;
+    let mut ids = Vec::new();
+    for row in rows {
+        let (id, customer_id, phone) = row.map_err(|e| format!("read {table}: {e}"))?;
+        if subject.matches(customer_id.as_deref(), phone.as_deref()) {
+            ids.push(id);
+        }
+    }
+    Ok(ids)
+}
+
+fn order_ids(conn: &Connection, subject: &Subject) -> Result<Vec<String>, String> {
+    matching_row_ids(conn, "orders", "customer_id", "customer_phone", subject)
+}
+
+fn export_orders(conn: &Connection, ids: &[String]) -> Result<Vec<Value>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, order_number, created_at, status, order_type, total_amount,
+                    customer_name, customer_phone, customer_email, delivery_address
+             FROM orders WHERE id = ?1",
+        )
+        .map_err(|e| format!("prepare order export: {e}"))?;
+    let mut orders = Vec::with_capacity(ids.len());
+    for id in ids {
+        let order = stmt
+            .query_row(params![id], |row| {
+                Ok(json!({
+                    "id": row.get::<_, String>(0)?,
+                    "orderNumber": row.get::<_, Option<String>>(1)?,
+                    "createdAt": row.get::<_, Option<String>>(2)?,
+                    "status": row.get::<_, Option<String>>(3)?,
+                    "orderType": row.get::<_, Option<String>>(4)?,
+                    "totalAmount": row.get::<_, Option<f64>>(5)?,
+                    "customerName": row.get::<_, Option<String>>(6)?,
+                    "customerPhone": row.get::<_, Option<String>>(7)?,
+                    "customerEmail": row.get::<_, Option<String>>(8)?,
+                    "deliveryAddress": row.get::<_, Option<String>>(9)?,
+                }))
+            })
+            .map_err(|e| format!("read order {id} for export: {e}"))?;
+        orders.push(order);
+    }
+    orders.sort_by(|a, b| a["createdAt"].as_str().cmp(&b["createdAt"].as_str()));
+    Ok(orders)
+}
+
+fn export_loyalty(conn: &Connection, subject: &Subject) -> Result<Vec<Value>, String> {
+    let ids = matching_row_ids(
+        conn,
+        "loyalty_customers",
+        "user_profile_id",
+        "customer_phone",
+        subject,
+    )?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT customer_name, customer_email, customer_phone, points_balance,
+                    total_earned, total_redeemed, tier, loyalty_card_uid
+             FROM loyalty_customers WHERE id = ?1",
+        )
+        .map_err(|e| format!("prepare loyalty export: {e}"))?;
+    ids.iter()
+        .map(|id| {
+            stmt.query_row(params![id], |row| {
+                Ok(json!({
+                    "name": row.get::<_, Option<String>>(0)?,
+                    "email": row.get::<_, Option<String>>(1)?,
+                    "phone": row.get::<_, Option<String>>(2)?,
+                    "pointsBalance": row.get::<_, i64>(3)?,
+                    "totalEarned": row.get::<_, i64>(4)?,
+                    "totalRedeemed": row.get::<_, i64>(5)?,
+                    "tier": row.get::<_, String>(6)?,
+                    "loyaltyCardUid": row.get::<_, Option<String>>(7)?,
+                }))
+            })
+            .map_err(|e| format!("read loyalty entry {id} for export: {e}"))
+        })
+        .collect()
+}
+
+/// Name of the export file. Never contains the phone itself.
+fn export_file_name(subject: &Subject, now: chrono::DateTime<Utc>) -> String {
+    let who = match (&subject.customer_id, subject.phone_hash()) {
+        (Some(id), _) => id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect(),
+        (None, Some(hash)) => format!("phone-{}", &hash[..12]),
+        (None, None) => "unknown".to_string(),
+    };
+    format!(
+        "{EXPORT_FILE_PREFIX}_{who}_{}.json",
+        now.format("%Y%m%dT%H%M%S")
+    )
+}
+
+/// Gather everything held about the customer into `exports/`. Returns the
+/// file's name, path and size, and how many orders it lists.
+pub fn export_customer_data(db: &DbState, target: &CustomerRef) -> Result<Value, String> {
+    let now = Utc::now();
+    let (subject, orders, loyalty) = db.read(|conn| {
+        let subject = resolve_subject(conn, target);
+        let orders = export_orders(conn, &order_ids(conn, &subject)?)?;
+        let loyalty = export_loyalty(conn, &subject)?;
+        Ok((subject, orders, loyalty))
+    })?;
+    if subject.record.is_none() && orders.is_empty() && loyalty.is_empty() {
+        return Err("No data is held for this customer".into());
+    }
+
+    let addresses = subject
+        .record
+        .as_ref()
+        .and_then(|record| record.get("addresses"))
+        .cloned()
+        .unwrap_or_else(|| json!([]));
+    let document = json!({
+        "exportedAt": now.to_rfc3339(),
+        "customer": subject.record,
+        "addresses": addresses,
+        "loyalty": loyalty,
+        "orders": orders,
+    });
+    let bytes =
+        serde_json::to_vec_pretty(&document).map_err(|e| format!("serialize export: {e}"))?;
+
+    let dir = reports_export::exports_dir(db)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("create exports directory: {e}"))?;
+    let file_name = export_file_name(&subject, now);
+    let path = dir.join(&file_name);
+    let temp_path = dir.join(format!(".{file_name}.tmp"));
+    fs::write(&temp_path, &bytes).map_err(|e| format!("write export: {e}"))?;
+    fs::rename(&temp_path, &path).map_err(|e| format!("finalize export: {e}"))?;
+
+    Ok(json!({
+        "fileName": file_name,
+        "path": path.to_string_lossy(),
+        "sizeBytes": bytes.len(),
+        "orderCount": orders.len(),
+    }))
+}
+
+/// Order numbers of the subject's orders that are still open and unpaid.
+fn open_unpaid_orders(conn: &Connection, ids: &[String]) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT COALESCE(order_number, id) FROM orders
+             WHERE id = ?1
+               AND status NOT IN ({CLOSED_ORDER_STATUSES})
+               AND COALESCE(payment_status, 'pending') != 'paid'"
+        ))
+        .map_err(|e| format!("prepare open order check: {e}"))?;
+    let mut open = Vec::new();
+    for id in ids {
+        match stmt.query_row(params![id], |row| row.get::<_, String>(0)) {
+            Ok(number) => open.push(number),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {}
+            Err(e) => return Err(format!("check order {id}: {e}")),
+        }
+    }
+    Ok(open)
+}
+
+/// Set `columns` to NULL on each row in `ids`.
+fn blank_columns(
+    conn: &Connection,
+    table: &str,
+    columns: &[&str],
+    ids: &[String],
+    now: &str,
+) -> Result<usize, String> {
+    if ids.is_empty() {
+        return Ok(0);
+    }
+    let assignments = columns
+        .iter()
+        .map(|column| format!("{column} = NULL"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut stmt = conn
+        .prepare(&format!(
+            "UPDATE {table} SET {assignments}, updated_at = ?2 WHERE id = ?1"
+        ))
+        .map_err(|e| format!("prepare {table} anonymisation: {e}"))?;
+    for id in ids {
+        stmt.execute(params![id, now])
+            .map_err(|e| format!("anonymise {table} {id}: {e}"))?;
+    }
+    Ok(ids.len())
+}
+
+fn delete_rows(conn: &Connection, table: &str, ids: &[String]) -> Result<usize, String> {
+    let mut stmt = conn
+        .prepare(&format!("DELETE FROM {table} WHERE id = ?1"))
+        .map_err(|e| format!("prepare {table} delete: {e}"))?;
+    for id in ids {
+        stmt.execute(params![id])
+            .map_err(|e| format!("delete {table} {id}: {e}"))?;
+    }
+    Ok(ids.len())
+}
+
+/// Drop the subject from a `local` JSON array setting. Returns how many
+/// entries went.
+fn remove_from_local_array(
+    conn: &Connection,
+    key: &str,
+    subject: &Subject,
+) -> Result<usize, String> {
+    let entries = read_json_array(conn, key);
+    let before = entries.len();
+    let kept: Vec<Value> = entries
+        .into_iter()
+        .filter(|entry| !subject.matches_entry(entry))
+        .collect();
+    let removed = before - kept.len();
+    if removed > 0 {
+        db::set_setting(conn, "local", key, &Value::Array(kept).to_string())?;
+    }
+    Ok(removed)
+}
+
+fn erase_in_transaction(
+    conn: &Connection,
+    subject: &Subject,
+    order_ids: &[String],
+    organization_id: &str,
+    actor_staff_id: Option<&str>,
+) -> Result<Value, String> {
+    let now = Utc::now().to_rfc3339();
+    let erasure_id = Uuid::new_v4().to_string();
+    let phone_hash = subject.phone_hash();
+
+    let orders = blank_columns(conn, "orders", ORDER_PERSONAL_COLUMNS, order_ids, &now)?;
+    let reservation_ids = matching_row_ids(
+        conn,
+        "reservations",
+        "customer_id",
+        "customer_phone",
+        subject,
+    )?;
+    let reservations = blank_columns(
+        conn,
+        "reservations",
+        RESERVATION_PERSONAL_COLUMNS,
+        &reservation_ids,
+        &now,
+    )?;
+    let loyalty_ids = matching_row_ids(
+        conn,
+        "loyalty_customers",
+        "user_profile_id",
+        "customer_phone",
+        subject,
+    )?;
+    let loyalty_entries = delete_rows(conn, "loyalty_customers", &loyalty_ids)?;
+    let call_ids = matching_row_ids(
+        conn,
+        "caller_id_log",
+        "customer_id",
+        "caller_number",
+        subject,
+    )?;
+    let caller_id_entries = delete_rows(conn, "caller_id_log", &call_ids)?;
+    let cache_entries = remove_from_local_array(conn, CACHE_KEY, subject)?;
+    let conflicts = remove_from_local_array(conn, CONFLICTS_KEY, subject)?;
+
+    let request = json!({
+        "erasureId": erasure_id,
+        "customerId": subject.customer_id,
+        "phoneHash": phone_hash,
+        "orderIds": order_ids,
+        "requestedAt": now,
+    });
+    sync_queue::enqueue(
+        conn,
+        &sync_queue::EnqueueInput {
+            table_name: ERASURE_SYNC_ENTITY.to_string(),
+            record_id: erasure_id.clone(),
+            operation: "INSERT".to_string(),
+            data: request.to_string(),
+            organization_id: organization_id.to_string(),
+            priority: Some(0),
+            module_type: Some("customers".to_string()),
+            conflict_strategy: None,
+            version: None,
+        },
+    )?;
+
+    let summary = json!({
+        "erasureId": erasure_id,
+        "customerId": subject.customer_id,
+        "phoneHash": phone_hash,
+        "ordersAnonymized": orders,
+        "reservationsAnonymized": reservations,
+        "loyaltyEntriesRemoved": loyalty_entries,
+        "callerIdEntriesRemoved": caller_id_entries,
+        "cacheEntriesRemoved": cache_entries,
+        "conflictsRemoved": conflicts,
+    });
+    conn.execute(
+        "INSERT INTO recovery_action_log (
+            id, action_id, issue_code, entity_type, entity_id, success, message,
+            actor_staff_id, payload_json, created_at
+         ) VALUES (?1, ?2, ?3, 'customer', ?4, 1, ?5, ?6, ?7, ?8)",
+        params![
+            Uuid::new_v4().to_string(),
+            AUDIT_ACTION_ID,
+            ERASE_ISSUE_CODE,
+            subject.customer_id,
+            format!("Customer erased; {orders} order(s) anonymised"),
+            actor_staff_id,
+            summary.to_string(),
+            now,
+        ],
+    )
+    .map_err(|e| format!("record customer erasure in audit log: {e}"))?;
+
+    Ok(summary)
+}
+
+/// Anonymise the customer everywhere on this terminal and queue the same
+/// request for the cloud. Returns what was changed.
+pub fn erase_customer(
+    db: &DbState,
+    target: &CustomerRef,
+    organization_id: &str,
+    actor_staff_id: Option<&str>,
+) -> Result<Value, String> {
+    db.write(|conn| {
+        conn.execute_batch("BEGIN IMMEDIATE")
+            .map_err(|e| format!("begin customer erasure: {e}"))?;
+        let result = (|| {
+            let subject = resolve_subject(conn, target);
+            let order_ids = order_ids(conn, &subject)?;
+            if subject.record.is_none()
+                && order_ids.is_empty()
+                && subject.customer_id.is_none()
+                && subject.phone.is_empty()
+            {
+                return Err("No data is held for this customer".to_string());
+            }
+            let open = open_unpaid_orders(conn, &order_ids)?;
+            if !open.is_empty() {
+                return Err(format!(
+                    "Cannot erase this customer while they have {} open unpaid order(s) ({}); settle or cancel them first",
+                    open.len(),
+                    open.join(", ")
+                ));
+            }
+            erase_in_transaction(conn, &subject, &order_ids, organization_id, actor_staff_id)
+        })();
+        match result {
+            Ok(summary) => {
+                conn.execute_batch("COMMIT")
+                    .map_err(|e| format!("commit customer erasure: {e}"))?;
+                Ok(summary)
+            }
+            Err(error) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(error)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn test_db() -> (DbState, PathBuf) {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        for (id, number, phone, customer_id, status, payment_status) in [
+            ("o1", "101", "691 234-5678", None, "completed", "paid"),
+            (
+                "o2",
+                "102",
+                "6912345678",
+                Some("cust-1"),
+                "delivered",
+                "paid",
+            ),
+            ("o3", "103", "2101111111", None, "completed", "paid"),
+            ("o4", "104", "691234567", None, "completed", "paid"),
+        ] {
+            conn.execute(
+                "INSERT INTO orders (id, order_number, items, total_amount, status, order_type,
+                                     customer_name, customer_phone, customer_email, customer_id,
+                                     delivery_address, payment_status, sync_status,
+                                     created_at, updated_at)
+                 VALUES (?1, ?2, '[]', 18.4, ?5, 'delivery', 'Maria', ?3, 'maria@example.com',
+                         ?4, 'Main St 1', ?6, 'synced', datetime('now'), datetime('now'))",
+                params![id, number, phone, customer_id, status, payment_status],
+            )
+            .unwrap();
+        }
+        db::set_setting(
+            &conn,
+            "local",
+            CACHE_KEY,
+            &json!([
+                { "id": "cust-1", "name": "Maria", "phone": "6912345678",
+                  "addresses": [{ "id": "addr-1", "street": "Main St 1" }] },
+                { "id": "cust-2", "name": "Nikos", "phone": "2101111111" },
+            ])
+            .to_string(),
+        )
+        .unwrap();
+        let dir = std::env::temp_dir().join(format!("customer-privacy-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        (DbState::new(conn, dir.join("pos.db")), dir)
+    }
+
+    #[test]
+    fn export_collects_only_this_customers_record_and_orders() {
+        let (db, dir) = test_db();
+        let target = CustomerRef::parse(&json!({ "customerId": "cust-1" })).unwrap();
+
+        let result = export_customer_data(&db, &target).unwrap();
+        assert_eq!(result["orderCount"], 2);
+        let file_name = result["fileName"].as_str().unwrap();
+        assert!(file_name.starts_with("customer_data_cust-1_"));
+        let document: Value =
+            serde_json::from_slice(&fs::read(dir.join("exports").join(file_name)).unwrap())
+                .unwrap();
+        assert_eq!(document["customer"]["name"], "Maria");
+        assert_eq!(document["addresses"][0]["id"], "addr-1");
+        let ids: Vec<&str> = document["orders"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|order| order["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&"o1") && ids.contains(&"o2"));
+
+        assert!(CustomerRef::parse(&json!({})).is_err());
+        let unknown = CustomerRef::parse(&json!("6900000000")).unwrap();
+        assert!(export_customer_data(&db, &unknown).is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn erasure_is_blocked_by_open_unpaid_orders_then_anonymises_and_audits() {
+        let (db, dir) = test_db();
+        let target = CustomerRef::parse(&json!("691 234 5678")).unwrap();
+        db.write(|conn| {
+            conn.execute(
+                "UPDATE orders SET status = 'preparing', payment_status = 'pending'
+                 WHERE id = 'o2'",
+                [],
+            )
+            .map_err(|e| e.to_string())
+        })
+        .unwrap();
+
+        let error = erase_customer(&db, &target, "org-1", Some("staff-1")).unwrap_err();
+        assert!(error.contains("open unpaid order"), "{error}");
+        assert!(error.contains("102"), "{error}");
+        db.write(|conn| {
+            conn.execute(
+                "UPDATE orders SET status = 'completed', payment_status = 'paid'
+                 WHERE id = 'o2'",
+                [],
+            )
+            .map_err(|e| e.to_string())
+        })
+        .unwrap();
+
+        let summary = erase_customer(&db, &target, "org-1", Some("staff-1")).unwrap();
+        assert_eq!(summary["ordersAnonymized"], 2);
+        assert_eq!(summary["cacheEntriesRemoved"], 1);
+        let phone_hash = sha256_hex(b"6912345678");
+        assert_eq!(summary["phoneHash"], phone_hash.as_str());
+
+        db.read(|conn| {
+            let (name, phone, address, total): (
+                Option<String>,
+                Option<String>,
+                Option<String>,
+                f64,
+            ) = conn
+                .query_row(
+                    "SELECT customer_name, customer_phone, delivery_address, total_amount
+                     FROM orders WHERE id = 'o1'",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )
+                .unwrap();
+            assert_eq!((name, phone, address), (None, None, None));
+            assert_eq!(total, 18.4);
+            // A different number sharing a prefix is another customer.
+            let other: Option<String> = conn
+                .query_row(
+                    "SELECT customer_phone FROM orders WHERE id = 'o4'",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(other.as_deref(), Some("691234567"));
+
+            let cache = read_json_array(conn, CACHE_KEY);
+            assert_eq!(cache.len(), 1);
+            assert_eq!(cache[0]["id"], "cust-2");
+
+            let (table, data): (String, String) = conn
+                .query_row(
+                    "SELECT table_name, data FROM parity_sync_queue
+                     WHERE table_name = ?1",
+                    params![ERASURE_SYNC_ENTITY],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap();
+            assert_eq!(table, ERASURE_SYNC_ENTITY);
+            assert!(!data.contains("6912345678"));
+            assert!(data.contains(&phone_hash));
+
+            let (actor, payload): (String, String) = conn
+                .query_row(
+                    "SELECT actor_staff_id, payload_json FROM recovery_action_log
+                     WHERE issue_code = ?1",
+                    params![ERASE_ISSUE_CODE],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap();
+            assert_eq!(actor, "staff-1");
+            assert!(!payload.contains("6912345678"));
+            assert!(payload.contains(&phone_hash));
+            Ok(())
+        })
+        .unwrap();
+        let _ = fs::remove_dir_all(dir);
+    }
+}
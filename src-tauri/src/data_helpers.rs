@@ -1,5 +1,6 @@
 use reqwest::Url;
 
+use crate::money::{self, Cents, RoundingRule};
 use crate::{
     db, value_f64, value_str, ALLOWED_EXTERNAL_HOSTS, ALLOWED_EXTERNAL_HOST_SUFFIXES,
     EXTERNAL_URL_MAX_LEN,
@@ -40,13 +41,30 @@ pub(crate) fn normalize_phone(value: &str) -> String {
         .collect::<String>()
 }
 
+/// Local order id for a local or Supabase id. Two indexed point lookups,
+/// local id first, instead of `id = ?1 OR supabase_id = ?1`: most callers
+/// pass a local id, so the second probe rarely runs.
 pub(crate) fn resolve_order_id(conn: &rusqlite::Connection, order_id: &str) -> Option<String> {
-    conn.query_row(
-        "SELECT id FROM orders WHERE id = ?1 OR supabase_id = ?1 LIMIT 1",
-        rusqlite::params![order_id],
-        |row| row.get::<_, String>(0),
-    )
-    .ok()
+    resolve_order_row(conn, order_id, "id", |row| row.get::<_, String>(0))
+}
+
+/// Look up `columns` of the order whose local id, or failing that Supabase
+/// id, is `order_id`. Both statements go through the prepared-statement
+/// cache.
+pub(crate) fn resolve_order_row<T>(
+    conn: &rusqlite::Connection,
+    order_id: &str,
+    columns: &str,
+    map: impl Fn(&rusqlite::Row<'_>) -> rusqlite::Result<T>,
+) -> Option<T> {
+    ["id = ?1", "supabase_id = ?1"].iter().find_map(|filter| {
+        conn.prepare_cached(&format!(
+            "SELECT {columns} FROM orders WHERE {filter} LIMIT 1"
+        ))
+        .ok()?
+        .query_row(rusqlite::params![order_id], &map)
+        .ok()
+    })
 }
 
 #[allow(clippy::type_complexity)]
@@ -66,18 +84,18 @@ pub(crate) fn load_orders_for_period(
     )>,
     String,
 > {
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, status, created_at, items, staff_id, payment_method
-             FROM orders
-             WHERE (?1 = '' OR branch_id = ?1)
-               AND COALESCE(is_ghost, 0) = 0
-               AND substr(created_at, 1, 10) >= ?2
-               AND substr(created_at, 1, 10) <= ?3",
-        )
-        .map_err(|e| e.to_string())?;
+    let (start_at, end_at) = crate::business_day::business_day_bounds(conn, date_from, date_to)?;
+    let sql = format!(
+        "SELECT id, status, created_at, items, staff_id, payment_method
+         FROM orders
+         WHERE COALESCE(branch_id, '') = ?1
+           AND COALESCE(is_ghost, 0) = 0
+           AND {}",
+        crate::business_day::timestamp_in_range_sql("created_at", "?2", "?3")
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map(rusqlite::params![branch_id, date_from, date_to], |row| {
+        .query_map(rusqlite::params![branch_id, start_at, end_at], |row| {
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, String>(1)?,
@@ -91,24 +109,24 @@ pub(crate) fn load_orders_for_period(
     Ok(rows.filter_map(|r| r.ok()).collect())
 }
 
-pub(crate) fn parse_item_totals(items_json: &str) -> (f64, std::collections::HashMap<String, f64>) {
-    let mut total = 0.0;
+pub(crate) fn parse_item_totals(
+    items_json: &str,
+    rule: RoundingRule,
+) -> (f64, std::collections::HashMap<String, f64>) {
+    let mut total = Cents::ZERO;
     let mut by_name: std::collections::HashMap<String, f64> = std::collections::HashMap::new();
     let parsed =
         serde_json::from_str::<serde_json::Value>(items_json).unwrap_or(serde_json::json!([]));
     if let Some(items) = parsed.as_array() {
         for item in items {
             let qty = value_f64(item, &["quantity"]).unwrap_or(1.0).max(0.0);
-            let line_total = value_f64(item, &["total_price", "totalPrice"]).unwrap_or_else(|| {
-                value_f64(item, &["unit_price", "unitPrice", "price"]).unwrap_or(0.0) * qty
-            });
-            total += line_total;
+            total += money::item_line_cents(item, rule);
             let name = value_str(item, &["name", "item_name", "title"])
                 .unwrap_or_else(|| "Item".to_string());
             *by_name.entry(name).or_insert(0.0) += qty.max(1.0);
         }
     }
-    (total, by_name)
+    (total.to_f64_dp2(), by_name)
 }
 
 pub(crate) fn validate_external_url(
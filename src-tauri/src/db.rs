@@ -4,22 +4,34 @@
 //! configuration. Provides schema migrations, settings helpers, and managed
 //! state for use across Tauri commands.
 
-use rusqlite::{params, Connection};
+use crate::diagnostics::metrics::{self, DbAccess};
+use rusqlite::{params, Connection, OpenFlags};
 use serde_json::Value;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tracing::{error, info, warn};
 
-/// Tauri managed state holding the database connection.
+/// Tauri managed state holding the database connections.
 ///
-/// # Single-Mutex Design
+/// # Write Connection
 ///
-/// SQLite enforces a single-writer constraint: only one thread may write at a
-/// time, and concurrent readers are only possible when WAL mode is used with
-/// separate connections. Because this POS application uses a single
-/// `rusqlite::Connection` (matching the Electron POS's `better-sqlite3`
-/// pattern), a single `Mutex<Connection>` serializes all access.
+/// SQLite enforces a single-writer constraint, so every write goes through
+/// the one read-write `conn` behind a `Mutex` (matching the Electron POS's
+/// `better-sqlite3` pattern). Use [`DbState::write`] or lock `conn`
+/// directly.
+///
+/// # Read Pool
+///
+/// With WAL mode, readers on separate connections see the last committed
+/// snapshot and never block on the writer. [`init`] therefore also opens a
+/// small pool of `SQLITE_OPEN_READ_ONLY` connections; [`DbState::read`]
+/// borrows one so listings, menu getters and reports do not queue behind a
+/// long write such as a Z-report rollover. A read closure must not write —
+/// the connection rejects it. In-memory databases (tests) have no pool and
+/// `read` falls back to the write connection.
 ///
 /// # Deadlock Prevention
 ///
@@ -32,22 +44,145 @@ use tracing::{error, info, warn};
 ///    their own lock.
 ///
 /// See `diagnostics::get_system_health` for an example of this drop-and-reacquire
-/// pattern.
+/// pattern. The same applies to calling `write` from inside a `read` closure
+/// when the pool is empty.
 ///
-/// # Performance Considerations
+/// # Blocking Work
 ///
-/// A single mutex is adequate for the POS workload (low concurrency, small
-/// transactions). If contention becomes measurable — e.g. background sync
-/// blocking UI reads — consider migrating to an `r2d2` connection pool with
-/// separate read-only and read-write connections, or switching to
-/// `tokio::sync::Mutex` with `spawn_blocking` for DB calls.
+/// Every rusqlite call blocks. Async command handlers that run long queries
+/// or file I/O should go through [`DbState::run_blocking`], which moves a
+/// clone onto Tokio's blocking pool. Clones share the same connections.
+#[derive(Clone)]
 pub struct DbState {
-    pub conn: Mutex<Connection>,
+    pub conn: Arc<Mutex<Connection>>,
     pub db_path: PathBuf,
+    readers: Arc<[Mutex<Connection>]>,
+    next_reader: Arc<AtomicUsize>,
+}
+
+/// Read-only connections opened alongside the write connection.
+const READ_POOL_SIZE: usize = 4;
+
+/// Connections detached from a [`DbState`] by [`DbState::swap_connections`].
+pub(crate) struct DbConnections {
+    pub writer: Connection,
+    readers: Vec<Connection>,
+}
+
+impl DbState {
+    /// State around an already-configured write connection, without a read
+    /// pool.
+    pub fn new(conn: Connection, db_path: PathBuf) -> Self {
+        Self {
+            conn: Arc::new(Mutex::new(conn)),
+            db_path,
+            readers: Arc::new([]),
+            next_reader: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn with_readers(mut self, readers: Vec<Connection>) -> Self {
+        self.readers = readers.into_iter().map(Mutex::new).collect();
+        self
+    }
+
+    /// Open a fully migrated database at `path` with as many readers as this
+    /// state has, ready for [`DbState::swap_connections`].
+    pub(crate) fn open_sibling(&self, path: &Path) -> Result<DbConnections, String> {
+        let writer = open_and_configure(path)?;
+        run_migrations(&writer)?;
+        let readers = (0..self.readers.len())
+            .map(|_| open_reader(path))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(DbConnections { writer, readers })
+    }
+
+    /// Put `next` behind every clone of this state and hand back the
+    /// connections it replaces. Takes the write lock first, so no write is in
+    /// flight while the readers change over.
+    pub(crate) fn swap_connections(&self, next: DbConnections) -> Result<DbConnections, String> {
+        if next.readers.len() != self.readers.len() {
+            return Err("Read pool size mismatch".into());
+        }
+        let mut writer = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut previous_readers = Vec::with_capacity(next.readers.len());
+        for (slot, reader) in self.readers.iter().zip(next.readers) {
+            let mut current = slot.lock().map_err(|e| e.to_string())?;
+            previous_readers.push(std::mem::replace(&mut *current, reader));
+        }
+        let previous_writer = std::mem::replace(&mut *writer, next.writer);
+        Ok(DbConnections {
+            writer: previous_writer,
+            readers: previous_readers,
+        })
+    }
+
+    /// Run `f` on a read-only pooled connection: the first idle one,
+    /// otherwise the next in turn. Never touches the write mutex unless the
+    /// pool is empty. Counted in the `db.read` metrics.
+    pub fn read<T>(&self, f: impl FnOnce(&Connection) -> Result<T, String>) -> Result<T, String> {
+        let started = Instant::now();
+        let result = self.read_unmetered(f);
+        metrics::record_db(DbAccess::Read, started.elapsed(), result.is_ok());
+        result
+    }
+
+    fn read_unmetered<T>(
+        &self,
+        f: impl FnOnce(&Connection) -> Result<T, String>,
+    ) -> Result<T, String> {
+        if self.readers.is_empty() {
+            return self.write_unmetered(f);
+        }
+        let start = self.next_reader.fetch_add(1, Ordering::Relaxed);
+        let count = self.readers.len();
+        let idle =
+            (0..count).find_map(|offset| self.readers[(start + offset) % count].try_lock().ok());
+        let conn = match idle {
+            Some(conn) => conn,
+            None => self.readers[start % count]
+                .lock()
+                .map_err(|e| e.to_string())?,
+        };
+        f(&conn)
+    }
+
+    /// Run `f` on the write connection. Counted in the `db.write` metrics.
+    pub fn write<T>(&self, f: impl FnOnce(&Connection) -> Result<T, String>) -> Result<T, String> {
+        let started = Instant::now();
+        let result = self.write_unmetered(f);
+        metrics::record_db(DbAccess::Write, started.elapsed(), result.is_ok());
+        result
+    }
+
+    fn write_unmetered<T>(
+        &self,
+        f: impl FnOnce(&Connection) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        f(&conn)
+    }
+
+    /// Run `f` on Tokio's blocking pool with a shared handle to this state,
+    /// so long queries and file I/O do not stall the async executor. `f`
+    /// picks `read` or `write` itself, exactly as a synchronous caller would.
+    pub async fn run_blocking<T, F>(&self, f: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&DbState) -> Result<T, String> + Send + 'static,
+    {
+        let db = self.clone();
+        tokio::task::spawn_blocking(move || f(&db))
+            .await
+            .map_err(|e| format!("db blocking task join error: {e}"))?
+    }
 }
 
 /// Current schema version. Bump when adding new migrations.
-const CURRENT_SCHEMA_VERSION: i32 = 70;
+const CURRENT_SCHEMA_VERSION: i32 = 117;
+
+/// Statements kept by the write connection's prepared-statement cache.
+const PREPARED_STATEMENT_CACHE_CAPACITY: usize = 64;
 
 /// Initialize the database at `{app_data_dir}/pos.db`.
 ///
@@ -74,14 +209,29 @@ pub fn init(app_data_dir: &Path) -> Result<DbState, String> {
         }
     };
 
-    run_migrations(&conn)?;
+    // A failed migration has rolled back, so the schema is still the last
+    // good version; refuse to start rather than run new code against it.
+    run_migrations(&conn).map_err(|e| {
+        error!("Database migration failed, refusing to start: {e}");
+        format!("Database migration failed; the app cannot start until it succeeds: {e}")
+    })?;
 
-    info!("Database initialized (schema v{CURRENT_SCHEMA_VERSION})");
+    // Readers open after migrations so they never see a half-built schema.
+    // A pool that fails to open only costs concurrency, not correctness.
+    let readers = (0..READ_POOL_SIZE)
+        .map(|_| open_reader(&db_path))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| {
+            warn!("Read connection pool disabled: {e}");
+            Vec::new()
+        });
 
-    Ok(DbState {
-        conn: Mutex::new(conn),
-        db_path,
-    })
+    info!(
+        readers = readers.len(),
+        "Database initialized (schema v{CURRENT_SCHEMA_VERSION})"
+    );
+
+    Ok(DbState::new(conn, db_path).with_readers(readers))
 }
 
 /// Open the database file and apply pragmas.
@@ -96,7 +246,26 @@ fn open_and_configure(path: &Path) -> Result<Connection, String> {
          PRAGMA synchronous = NORMAL;",
     )
     .map_err(|e| format!("pragma setup: {e}"))?;
+    // The order and sync hot paths use `prepare_cached`; the default of 16
+    // statements is evicted by a single order update.
+    conn.set_prepared_statement_cache_capacity(PREPARED_STATEMENT_CACHE_CAPACITY);
+
+    Ok(conn)
+}
 
+/// Open a read-only connection for the read pool. WAL mode and the shared
+/// memory file already exist because the write connection is opened first.
+fn open_reader(path: &Path) -> Result<Connection, String> {
+    let conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| format!("sqlite open reader: {e}"))?;
+    conn.execute_batch(
+        "PRAGMA busy_timeout = 5000;
+         PRAGMA query_only = ON;",
+    )
+    .map_err(|e| format!("reader pragma setup: {e}"))?;
     Ok(conn)
 }
 
@@ -176,6 +345,13 @@ where
 
 /// Run all pending migrations up to `CURRENT_SCHEMA_VERSION`.
 fn run_migrations(conn: &Connection) -> Result<(), String> {
+    run_migrations_to(conn, CURRENT_SCHEMA_VERSION)
+}
+
+/// Run pending migrations up to and including `target`. Only tests stop
+/// short of `CURRENT_SCHEMA_VERSION`, to build databases as older releases
+/// left them.
+fn run_migrations_to(conn: &Connection, target: i32) -> Result<(), String> {
     // Ensure schema_version table exists first
     conn.execute_batch(
         "CREATE TABLE IF NOT EXISTS schema_version (
@@ -185,13 +361,7 @@ fn run_migrations(conn: &Connection) -> Result<(), String> {
     )
     .map_err(|e| format!("create schema_version: {e}"))?;
 
-    let current: i32 = conn
-        .query_row(
-            "SELECT COALESCE(MAX(version), 0) FROM schema_version",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
+    let current = current_schema_version(conn);
 
     if current > CURRENT_SCHEMA_VERSION {
         return Err(format!(
@@ -200,7 +370,7 @@ fn run_migrations(conn: &Connection) -> Result<(), String> {
         ));
     }
     let needs_v56_backfill = needs_v56_claim_generation_backfill(conn, current)?;
-    if current == CURRENT_SCHEMA_VERSION && !needs_v56_backfill {
+    if current >= target && !needs_v56_backfill {
         info!("Database schema up to date (v{current})");
         return Ok(());
     }
@@ -220,7 +390,8 @@ fn run_migrations(conn: &Connection) -> Result<(), String> {
         }
     }
 
-    info!("Migrating database from v{current} to v{CURRENT_SCHEMA_VERSION}");
+    info!("Migrating database from v{current} to v{target}");
+    let pending = |version: i32| current < version && version <= target;
 
     // Each migration runs inside a transaction so a crash mid-migration
     // (power loss, process kill) cannot leave the schema in a half-applied
@@ -230,235 +401,428 @@ fn run_migrations(conn: &Connection) -> Result<(), String> {
     // an open transaction. Every other migration goes through
     // `run_migration_tx`, which wraps the call in `BEGIN IMMEDIATE` and
     // commits on success / rolls back on error.
-    if current < 1 {
+    if pending(1) {
         run_migration_tx(conn, 1, migrate_v1)?;
     }
-    if current < 2 {
+    if pending(2) {
         run_migration_tx(conn, 2, migrate_v2)?;
     }
-    if current < 3 {
+    if pending(3) {
         run_migration_tx(conn, 3, migrate_v3)?;
     }
-    if current < 4 {
+    if pending(4) {
         run_migration_tx(conn, 4, migrate_v4)?;
     }
-    if current < 5 {
+    if pending(5) {
         run_migration_tx(conn, 5, migrate_v5)?;
     }
-    if current < 6 {
+    if pending(6) {
         run_migration_tx(conn, 6, migrate_v6)?;
     }
-    if current < 7 {
+    if pending(7) {
         run_migration_tx(conn, 7, migrate_v7)?;
     }
-    if current < 8 {
+    if pending(8) {
         run_migration_tx(conn, 8, migrate_v8)?;
     }
-    if current < 9 {
+    if pending(9) {
         run_migration_tx(conn, 9, migrate_v9)?;
     }
-    if current < 10 {
+    if pending(10) {
         run_migration_tx(conn, 10, migrate_v10)?;
     }
-    if current < 11 {
+    if pending(11) {
         // v11 self-wraps (inline BEGIN;/COMMIT;).
         migrate_v11(conn)?;
     }
-    if current < 12 {
+    if pending(12) {
         run_migration_tx(conn, 12, migrate_v12)?;
     }
-    if current < 13 {
+    if pending(13) {
         run_migration_tx(conn, 13, migrate_v13)?;
     }
-    if current < 14 {
+    if pending(14) {
         run_migration_tx(conn, 14, migrate_v14)?;
     }
-    if current < 15 {
+    if pending(15) {
         run_migration_tx(conn, 15, migrate_v15)?;
     }
-    if current < 16 {
+    if pending(16) {
         // v16 self-wraps (inline BEGIN;/COMMIT;).
         migrate_v16(conn)?;
     }
-    if current < 17 {
+    if pending(17) {
         run_migration_tx(conn, 17, migrate_v17)?;
     }
-    if current < 18 {
+    if pending(18) {
         // v18 self-wraps (inline BEGIN;/COMMIT;).
         migrate_v18(conn)?;
     }
-    if current < 19 {
+    if pending(19) {
         run_migration_tx(conn, 19, migrate_v19)?;
     }
-    if current < 20 {
+    if pending(20) {
         // v20 self-wraps (inline BEGIN;/COMMIT;).
         migrate_v20(conn)?;
     }
-    if current < 21 {
+    if pending(21) {
         run_migration_tx(conn, 21, migrate_v21)?;
     }
-    if current < 22 {
+    if pending(22) {
         run_migration_tx(conn, 22, migrate_v22)?;
     }
-    if current < 23 {
+    if pending(23) {
         run_migration_tx(conn, 23, migrate_v23)?;
     }
-    if current < 24 {
+    if pending(24) {
         // v24 self-wraps (inline BEGIN;/COMMIT;).
         migrate_v24(conn)?;
     }
-    if current < 25 {
+    if pending(25) {
         run_migration_tx(conn, 25, migrate_v25)?;
     }
-    if current < 26 {
+    if pending(26) {
         run_migration_tx(conn, 26, migrate_v26)?;
     }
-    if current < 27 {
+    if pending(27) {
         run_migration_tx(conn, 27, migrate_v27)?;
     }
-    if current < 28 {
+    if pending(28) {
         // v28 self-wraps (inline BEGIN;/COMMIT;).
         migrate_v28(conn)?;
     }
-    if current < 29 {
+    if pending(29) {
         run_migration_tx(conn, 29, migrate_v29)?;
     }
-    if current < 30 {
+    if pending(30) {
         // v30 self-wraps (inline BEGIN;/COMMIT;).
         migrate_v30(conn)?;
     }
-    if current < 31 {
+    if pending(31) {
         // v31 self-wraps (inline BEGIN;/COMMIT;).
         migrate_v31(conn)?;
     }
-    if current < 32 {
+    if pending(32) {
         run_migration_tx(conn, 32, migrate_v32)?;
     }
-    if current < 33 {
+    if pending(33) {
         run_migration_tx(conn, 33, migrate_v33)?;
     }
-    if current < 34 {
+    if pending(34) {
         // v34 self-wraps (inline BEGIN;/COMMIT;).
         migrate_v34(conn)?;
     }
-    if current < 35 {
+    if pending(35) {
         run_migration_tx(conn, 35, migrate_v35)?;
     }
-    if current < 36 {
+    if pending(36) {
         // v36 self-wraps (inline BEGIN;/COMMIT;).
         migrate_v36(conn)?;
     }
-    if current < 37 {
+    if pending(37) {
         run_migration_tx(conn, 37, migrate_v37)?;
     }
-    if current < 38 {
+    if pending(38) {
         run_migration_tx(conn, 38, migrate_v38)?;
     }
-    if current < 39 {
+    if pending(39) {
         run_migration_tx(conn, 39, migrate_v39)?;
     }
-    if current < 40 {
+    if pending(40) {
         // v40 self-wraps (inline BEGIN;/COMMIT;).
         migrate_v40(conn)?;
     }
-    if current < 41 {
+    if pending(41) {
         run_migration_tx(conn, 41, migrate_v41)?;
     }
-    if current < 42 {
+    if pending(42) {
         run_migration_tx(conn, 42, migrate_v42)?;
     }
-    if current < 43 {
+    if pending(43) {
         run_migration_tx(conn, 43, migrate_v43)?;
     }
-    if current < 44 {
+    if pending(44) {
         run_migration_tx(conn, 44, migrate_v44)?;
     }
-    if current < 45 {
+    if pending(45) {
         run_migration_tx(conn, 45, migrate_v45)?;
     }
-    if current < 46 {
+    if pending(46) {
         run_migration_tx(conn, 46, migrate_v46)?;
     }
-    if current < 47 {
+    if pending(47) {
         run_migration_tx(conn, 47, migrate_v47)?;
     }
-    if current < 48 {
+    if pending(48) {
         run_migration_tx(conn, 48, migrate_v48)?;
     }
-    if current < 49 {
+    if pending(49) {
         run_migration_tx(conn, 49, migrate_v49)?;
     }
-    if current < 50 {
+    if pending(50) {
         run_migration_tx(conn, 50, migrate_v50)?;
     }
-    if current < 51 {
+    if pending(51) {
         run_migration_tx(conn, 51, migrate_v51)?;
     }
-    if current < 52 {
+    if pending(52) {
         run_migration_tx(conn, 52, migrate_v52)?;
     }
-    if current < 53 {
+    if pending(53) {
         run_migration_tx(conn, 53, migrate_v53)?;
     }
-    if current < 54 {
+    if pending(54) {
         run_migration_tx(conn, 54, migrate_v54)?;
     }
-    if current < 55 {
+    if pending(55) {
         run_migration_tx(conn, 55, migrate_v55)?;
     }
     // Wave 10 H8: `claim_generation` column on `parity_sync_queue`.
     // Reserved as v56 by the W11 cleanup sprint (which jumped to v57
     // to leave 56 free for this work). See
     // `project_w10_h8_claim_generation_deferred.md`.
-    if current < 56 || needs_v56_backfill {
+    if pending(56) || (needs_v56_backfill && 56 <= target) {
         run_migration_tx(conn, 56, migrate_v56)?;
     }
-    if current < 57 {
+    if pending(57) {
         run_migration_tx(conn, 57, migrate_v57)?;
     }
-    if current < 58 {
+    if pending(58) {
         run_migration_tx(conn, 58, migrate_v58)?;
     }
-    if current < 59 {
+    if pending(59) {
         run_migration_tx(conn, 59, migrate_v59)?;
     }
-    if current < 60 {
+    if pending(60) {
         run_migration_tx(conn, 60, migrate_v60)?;
     }
-    if current < 61 {
+    if pending(61) {
         run_migration_tx(conn, 61, migrate_v61)?;
     }
-    if current < 62 {
+    if pending(62) {
         run_migration_tx(conn, 62, migrate_v62)?;
     }
-    if current < 63 {
+    if pending(63) {
         run_migration_tx(conn, 63, migrate_v63)?;
     }
-    if current < 64 {
+    if pending(64) {
         run_migration_tx(conn, 64, migrate_v64)?;
     }
-    if current < 65 {
+    if pending(65) {
         run_migration_tx(conn, 65, migrate_v65)?;
     }
-    if current < 66 {
+    if pending(66) {
         run_migration_tx(conn, 66, migrate_v66)?;
     }
-    if current < 67 {
+    if pending(67) {
         run_migration_tx(conn, 67, migrate_v67)?;
     }
-    if current < 68 {
+    if pending(68) {
         run_migration_tx(conn, 68, migrate_v68)?;
     }
-    if current < 69 {
+    if pending(69) {
         run_migration_tx(conn, 69, migrate_v69)?;
     }
-    if current < 70 {
+    if pending(70) {
         run_migration_tx(conn, 70, migrate_v70)?;
     }
+    if pending(71) {
+        run_migration_tx(conn, 71, migrate_v71)?;
+    }
+    if pending(72) {
+        run_migration_tx(conn, 72, migrate_v72)?;
+    }
+    if pending(73) {
+        run_migration_tx(conn, 73, migrate_v73)?;
+    }
+    if pending(74) {
+        run_migration_tx(conn, 74, migrate_v74)?;
+    }
+    if pending(75) {
+        run_migration_tx(conn, 75, migrate_v75)?;
+    }
+    if pending(76) {
+        run_migration_tx(conn, 76, migrate_v76)?;
+    }
+    if pending(77) {
+        run_migration_tx(conn, 77, migrate_v77)?;
+    }
+    if pending(78) {
+        run_migration_tx(conn, 78, migrate_v78)?;
+    }
+    if pending(79) {
+        run_migration_tx(conn, 79, migrate_v79)?;
+    }
+    if pending(80) {
+        run_migration_tx(conn, 80, migrate_v80)?;
+    }
+    if pending(81) {
+        run_migration_tx(conn, 81, migrate_v81)?;
+    }
+    if pending(82) {
+        run_migration_tx(conn, 82, migrate_v82)?;
+    }
+    if pending(83) {
+        run_migration_tx(conn, 83, migrate_v83)?;
+    }
+    if pending(84) {
+        run_migration_tx(conn, 84, migrate_v84)?;
+    }
+    if pending(85) {
+        run_migration_tx(conn, 85, migrate_v85)?;
+    }
+    if pending(86) {
+        run_migration_tx(conn, 86, migrate_v86)?;
+    }
+    if pending(87) {
+        run_migration_tx(conn, 87, migrate_v87)?;
+    }
+    if pending(88) {
+        run_migration_tx(conn, 88, migrate_v88)?;
+    }
+    if pending(89) {
+        run_migration_tx(conn, 89, migrate_v89)?;
+    }
+    if pending(90) {
+        run_migration_tx(conn, 90, migrate_v90)?;
+    }
+    if pending(91) {
+        run_migration_tx(conn, 91, migrate_v91)?;
+    }
+    if pending(92) {
+        run_migration_tx(conn, 92, migrate_v92)?;
+    }
+    if pending(93) {
+        run_migration_tx(conn, 93, migrate_v93)?;
+    }
+    if pending(94) {
+        run_migration_tx(conn, 94, migrate_v94)?;
+    }
+    if pending(95) {
+        run_migration_tx(conn, 95, migrate_v95)?;
+    }
+    if pending(96) {
+        run_migration_tx(conn, 96, migrate_v96)?;
+    }
+    if pending(97) {
+        run_migration_tx(conn, 97, migrate_v97)?;
+    }
+    if pending(98) {
+        run_migration_tx(conn, 98, migrate_v98)?;
+    }
+    if pending(99) {
+        run_migration_tx(conn, 99, migrate_v99)?;
+    }
+    if pending(100) {
+        run_migration_tx(conn, 100, migrate_v100)?;
+    }
+    if pending(101) {
+        run_migration_tx(conn, 101, migrate_v101)?;
+    }
+    if pending(102) {
+        run_migration_tx(conn, 102, migrate_v102)?;
+    }
+    if pending(103) {
+        run_migration_tx(conn, 103, migrate_v103)?;
+    }
+    if pending(104) {
+        run_migration_tx(conn, 104, migrate_v104)?;
+    }
+    if pending(105) {
+        run_migration_tx(conn, 105, migrate_v105)?;
+    }
+    if pending(106) {
+        run_migration_tx(conn, 106, migrate_v106)?;
+    }
+    if pending(107) {
+        run_migration_tx(conn, 107, migrate_v107)?;
+    }
+    if pending(108) {
+        run_migration_tx(conn, 108, migrate_v108)?;
+    }
+    if pending(109) {
+        run_migration_tx(conn, 109, migrate_v109)?;
+    }
+    if pending(110) {
+        run_migration_tx(conn, 110, migrate_v110)?;
+    }
+    if pending(111) {
+        run_migration_tx(conn, 111, migrate_v111)?;
+    }
+    if pending(112) {
+        run_migration_tx(conn, 112, migrate_v112)?;
+    }
+
+    if pending(113) {
+        run_migration_tx(conn, 113, migrate_v113)?;
+    }
+    if pending(114) {
+        run_migration_tx(conn, 114, migrate_v114)?;
+    }
+    if pending(115) {
+        run_migration_tx(conn, 115, migrate_v115)?;
+    }
+    if pending(116) {
+        run_migration_tx(conn, 116, migrate_v116)?;
+    }
+    if pending(117) {
+        run_migration_tx(conn, 117, migrate_v117)?;
+    }
 
     Ok(())
 }
 
+fn current_schema_version(conn: &Connection) -> i32 {
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )
+    .unwrap_or(0)
+}
+
+/// What [`run_migrations`] would do on this database, without doing it:
+/// the applied and target versions, the versions still pending, and when
+/// each applied version ran.
+pub fn migration_status(conn: &Connection) -> Result<Value, String> {
+    let has_table = table_exists(conn, "schema_version")?;
+    let current = if has_table {
+        current_schema_version(conn)
+    } else {
+        0
+    };
+    let mut applied = Vec::new();
+    if has_table {
+        let mut stmt = conn
+            .prepare("SELECT version, applied_at FROM schema_version ORDER BY version")
+            .map_err(|e| format!("prepare schema_version: {e}"))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(serde_json::json!({
+                    "version": row.get::<_, i32>(0)?,
+                    "appliedAt": row.get::<_, Option<String>>(1)?,
+                }))
+            })
+            .map_err(|e| format!("query schema_version: {e}"))?;
+        for row in rows {
+            applied.push(row.map_err(|e| format!("read schema_version: {e}"))?);
+        }
+    }
+    let needs_v56_backfill = has_table && needs_v56_claim_generation_backfill(conn, current)?;
+    let mut pending: Vec<i32> = ((current + 1)..=CURRENT_SCHEMA_VERSION).collect();
+    if needs_v56_backfill {
+        pending.insert(0, 56);
+    }
+    Ok(serde_json::json!({
+        "currentVersion": current,
+        "targetVersion": CURRENT_SCHEMA_VERSION,
+        "pending": pending,
+        "upToDate": pending.is_empty() && current <= CURRENT_SCHEMA_VERSION,
+        "newerThanSupported": current > CURRENT_SCHEMA_VERSION,
+        "applied": applied,
+    }))
+}
+
 fn schema_version_exists(conn: &Connection, version: i32) -> Result<bool, String> {
     conn.query_row(
         "SELECT EXISTS(SELECT 1 FROM schema_version WHERE version = ?1)",
@@ -1259,6 +1623,18 @@ fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool, S
     Ok(false)
 }
 
+/// Whether `table` exists. Later migrations use this to skip columns on
+/// tables a partially-applied schema never created; the repair path brings
+/// such databases forward without them.
+fn table_exists(conn: &Connection, table: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+        params![table],
+        |row| row.get::<_, bool>(0),
+    )
+    .map_err(|e| format!("inspect {table} table: {e}"))
+}
+
 /// Return true iff `s` looks like a plain SQL identifier: `[A-Za-z_][A-Za-z0-9_]*`.
 /// Used by `debug_assert!` in `column_exists` to catch accidental interpolation
 /// of runtime-derived table names during development. Not a substitute for
@@ -4217,7 +4593,9 @@ fn migrate_v63(conn: &Connection) -> Result<(), String> {
 /// configured for the branch, the local dispatcher MUST not enqueue
 /// anything — see `crate::fiscal::active_cache`. This migration must NOT
 /// add behavior that breaks POS for un-configured deployments.
-fn migrate_v64(_conn: &Connection) -> Result<(), String> {
+fn migrate_v64(conn: &Connection) -> Result<(), String> {
+    conn.execute("INSERT INTO schema_version (version) VALUES (64)", [])
+        .map_err(|e| format!("v64 record schema_version: {e}"))?;
     info!("Applied migration v64 (fiscalization-core: module_type='fiscal' is now a recognized parity_sync_queue discriminator)");
     Ok(())
 }
@@ -4522,137 +4900,1879 @@ fn migrate_v70(conn: &Connection) -> Result<(), String> {
     Ok(())
 }
 
-/// Read the persisted `idempotency_key` from an entity table.
-///
-/// Wave 4 architectural contract:
-///
-/// > Every `sync_queue` row that dispatches an entity MUST carry the
-/// > SAME `idempotency_key` that was persisted on the entity row at
-/// > creation time. A second dispatch (retry, requeue, manual replay)
-/// > reads the same entity row and copies the same key, so the server
-/// > sees ONE operation regardless of how many times the client
-/// > re-sends it.
+/// Migration v71: cross-terminal order edit locks.
 ///
-/// Use this helper to fetch the key before constructing an enqueue.
-/// Rows created under v47+ always have a value (nullable on-disk, but
-/// the v49 trigger backfills via SQLite random on INSERT). If the key
-/// is missing for any reason — a pre-v47 row that was never touched,
-/// or a trigger that failed silently — this returns `None` and the
-/// caller may fall back to a deterministic synthetic
-/// (`entity_type:entity_id:operation`) so the sync_queue INSERT still
-/// succeeds.
-///
-/// `table` must be one of the five entity-sync-queue tables covered
-/// by v47 (`order_payments`, `payment_adjustments`, `staff_shifts`,
-/// `shift_expenses`, `driver_earnings`). The function validates that
-/// at compile time via a debug_assert; production builds accept any
-/// plain identifier and simply return `None` on lookup miss.
-// Wave 5 C17: consumer wired in `sync_queue.rs::prepare_financial_request`
-// via the `idempotency::make_entity_key` facade; `#[allow(dead_code)]`
-// gate removed.
-pub fn get_entity_idempotency_key(
-    conn: &Connection,
-    table: &str,
-    entity_id: &str,
-) -> Option<String> {
-    debug_assert!(
-        matches!(
-            table,
-            "order_payments"
-                | "payment_adjustments"
-                | "staff_shifts"
-                | "shift_expenses"
-                | "driver_earnings"
-                | "staff_payments"
-        ),
-        "get_entity_idempotency_key: unexpected table '{table}'"
-    );
-    debug_assert!(
-        is_safe_sql_identifier(table),
-        "get_entity_idempotency_key: table '{table}' must be a plain identifier"
-    );
-    let sql = format!("SELECT idempotency_key FROM {table} WHERE id = ?1");
-    conn.query_row(&sql, params![entity_id], |row| {
-        row.get::<_, Option<String>>(0)
-    })
-    .ok()
-    .flatten()
-}
-
-// ---------------------------------------------------------------------------
-// ECR device helpers
-// ---------------------------------------------------------------------------
+/// One row per locked order. `source = 'local'` rows were taken by this
+/// terminal; `source = 'remote'` rows mirror locks other terminals announced
+/// via realtime. Rows are never trusted past `expires_at`, so a terminal that
+/// crashes while holding a lock releases it implicitly after the TTL.
+fn migrate_v71(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS order_locks (
+            order_id TEXT PRIMARY KEY,
+            terminal_id TEXT NOT NULL,
+            staff_id TEXT,
+            acquired_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            source TEXT NOT NULL DEFAULT 'local' CHECK (source IN ('local', 'remote'))
+        );
 
-/// Insert a new ECR device.
-pub fn ecr_insert_device(conn: &Connection, dev: &serde_json::Value) -> Result<(), String> {
-    conn.execute(
-        "INSERT INTO ecr_devices
-            (id, name, device_type, brand, protocol, connection_type, connection_details,
-             terminal_id, merchant_id, operator_id, print_mode, tax_rates,
-             is_default, enabled, settings)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
-        params![
-            dev.get("id").and_then(|v| v.as_str()).unwrap_or_default(),
-            dev.get("name").and_then(|v| v.as_str()).unwrap_or("Device"),
-            dev.get("deviceType")
-                .and_then(|v| v.as_str())
-                .unwrap_or("payment_terminal"),
-            dev.get("brand")
-                .and_then(|v| v.as_str())
-                .unwrap_or("generic"),
-            dev.get("protocol")
-                .and_then(|v| v.as_str())
-                .unwrap_or("generic"),
-            dev.get("connectionType")
-                .and_then(|v| v.as_str())
-                .unwrap_or("network"),
-            dev.get("connectionDetails")
-                .map(|v| v.to_string())
-                .unwrap_or_else(|| "{}".into()),
-            dev.get("terminalId").and_then(|v| v.as_str()),
-            dev.get("merchantId").and_then(|v| v.as_str()),
-            dev.get("operatorId").and_then(|v| v.as_str()),
-            dev.get("printMode")
-                .and_then(|v| v.as_str())
-                .unwrap_or("register_prints"),
-            dev.get("taxRates")
-                .map(|v| v.to_string())
-                .unwrap_or_else(|| "[]".into()),
-            dev.get("isDefault")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false) as i32,
-            dev.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true) as i32,
-            dev.get("settings")
-                .map(|v| v.to_string())
-                .unwrap_or_else(|| "{}".into()),
-        ],
+        CREATE INDEX IF NOT EXISTS idx_order_locks_expires_at
+          ON order_locks (expires_at);
+        ",
     )
-    .map_err(|e| format!("ecr_insert_device: {e}"))?;
+    .map_err(|e| format!("v71 create order_locks: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (71)", [])
+        .map_err(|e| format!("v71 record schema_version: {e}"))?;
+
+    info!("Applied migration v71 (order edit locks)");
     Ok(())
 }
 
-/// Update an existing ECR device.
-pub fn ecr_update_device(
-    conn: &Connection,
-    id: &str,
-    updates: &serde_json::Value,
-) -> Result<(), String> {
-    // Build SET clauses dynamically for provided fields
-    let mut sets = Vec::new();
-    let mut values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+/// Migration v72: per-rate tax configuration.
+///
+/// Adds `orders.tax_breakdown` (JSON, captured when the order's items are
+/// written) and seeds `tax.config` from the legacy single `general.tax_rate`
+/// so existing terminals keep charging the same rate in inclusive mode.
+fn migrate_v72(conn: &Connection) -> Result<(), String> {
+    if !column_exists(conn, "orders", "tax_breakdown")? {
+        conn.execute("ALTER TABLE orders ADD COLUMN tax_breakdown TEXT", [])
+            .map_err(|e| format!("v72 add orders.tax_breakdown: {e}"))?;
+    }
+
+    // Partial restores of historical backups can lack `local_settings`;
+    // `tax::load_config` falls back to the legacy rate in that case.
+    let settings_exist: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master
+             WHERE type = 'table' AND name = 'local_settings'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
 
-    macro_rules! maybe_set {
-        ($field:expr, $col:expr) => {
-            if let Some(v) = updates.get($field) {
-                if let Some(s) = v.as_str() {
-                    sets.push(format!("{} = ?", $col));
-                    values.push(Box::new(s.to_string()));
-                }
-            }
-        };
+    if settings_exist > 0 && get_setting(conn, "tax", "config").is_none() {
+        let legacy =
+            get_setting(conn, "general", "tax_rate").and_then(|raw| raw.trim().parse::<f64>().ok());
+        let config = crate::tax::TaxConfig::from_legacy_rate(legacy);
+        let raw =
+            serde_json::to_string(&config).map_err(|e| format!("v72 serialize tax config: {e}"))?;
+        set_setting(conn, "tax", "config", &raw)?;
+    }
+    if settings_exist > 0 && get_setting(conn, "general", "tax_mode").is_none() {
+        set_setting(conn, "general", "tax_mode", "inclusive")?;
     }
 
-    macro_rules! maybe_set_json {
+    conn.execute("INSERT INTO schema_version (version) VALUES (72)", [])
+        .map_err(|e| format!("v72 record schema_version: {e}"))?;
+
+    info!("Applied migration v72 (per-rate tax breakdown)");
+    Ok(())
+}
+
+/// Migration v73: Greek fiscal documents.
+///
+/// `fiscal_receipt_series` holds one never-resetting counter per
+/// (terminal, series). `fiscal_documents` rows are immutable — triggers
+/// reject UPDATE and DELETE, so a cancellation is always a new linked
+/// document. There are no foreign keys to `orders`/`order_payments`:
+/// fiscal records must outlive the retention purge of operational rows.
+fn migrate_v73(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS fiscal_receipt_series (
+            terminal_id TEXT NOT NULL,
+            series TEXT NOT NULL CHECK (series IN ('receipt', 'refund')),
+            last_number INTEGER NOT NULL DEFAULT 0,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (terminal_id, series)
+        );
+
+        CREATE TABLE IF NOT EXISTS fiscal_documents (
+            id TEXT PRIMARY KEY,
+            document_type TEXT NOT NULL
+                CHECK (document_type IN ('receipt', 'refund', 'cancellation')),
+            series TEXT NOT NULL,
+            number INTEGER NOT NULL,
+            document_number TEXT NOT NULL,
+            terminal_id TEXT NOT NULL,
+            branch_id TEXT NOT NULL DEFAULT '',
+            order_id TEXT NOT NULL,
+            payment_id TEXT,
+            adjustment_id TEXT,
+            cancels_document_id TEXT REFERENCES fiscal_documents(id),
+            payment_method TEXT,
+            net_cents INTEGER NOT NULL,
+            vat_cents INTEGER NOT NULL,
+            gross_cents INTEGER NOT NULL,
+            vat_breakdown TEXT NOT NULL DEFAULT '[]',
+            issued_at TEXT NOT NULL,
+            UNIQUE (terminal_id, series, number)
+        );
+
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_fiscal_documents_cancels
+          ON fiscal_documents (cancels_document_id)
+          WHERE cancels_document_id IS NOT NULL;
+        CREATE INDEX IF NOT EXISTS idx_fiscal_documents_order
+          ON fiscal_documents (order_id);
+        CREATE INDEX IF NOT EXISTS idx_fiscal_documents_issued_at
+          ON fiscal_documents (issued_at);
+
+        CREATE TRIGGER IF NOT EXISTS fiscal_documents_no_update
+        BEFORE UPDATE ON fiscal_documents
+        BEGIN
+            SELECT RAISE(ABORT, 'fiscal documents are immutable');
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS fiscal_documents_no_delete
+        BEFORE DELETE ON fiscal_documents
+        BEGIN
+            SELECT RAISE(ABORT, 'fiscal documents cannot be deleted');
+        END;
+        ",
+    )
+    .map_err(|e| format!("v73 create fiscal documents: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (73)", [])
+        .map_err(|e| format!("v73 record schema_version: {e}"))?;
+
+    info!("Applied migration v73 (fiscal documents and receipt series)");
+    Ok(())
+}
+
+/// Migration v74: settings change history.
+///
+/// One row per effective change to `local_settings` (or to a keyring-backed
+/// terminal setting written through `settings_set`). Secret-bearing keys are
+/// stored masked and flagged so they can never be reverted from history.
+fn migrate_v74(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS settings_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            setting_category TEXT NOT NULL,
+            setting_key TEXT NOT NULL,
+            old_value TEXT,
+            new_value TEXT,
+            masked INTEGER NOT NULL DEFAULT 0,
+            actor_staff_id TEXT,
+            source TEXT NOT NULL DEFAULT 'system',
+            changed_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_settings_history_key
+          ON settings_history (setting_category, setting_key, id);
+        ",
+    )
+    .map_err(|e| format!("v74 create settings_history: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (74)", [])
+        .map_err(|e| format!("v74 record schema_version: {e}"))?;
+
+    info!("Applied migration v74 (settings change history)");
+    Ok(())
+}
+
+/// v75: per-order activity timeline. No foreign key to `orders` so events
+/// outlive a deleted order.
+fn migrate_v75(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS order_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            order_id TEXT NOT NULL,
+            event_type TEXT NOT NULL,
+            actor_staff_id TEXT,
+            terminal_id TEXT,
+            summary TEXT NOT NULL DEFAULT '{}',
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_order_events_order
+          ON order_events (order_id, id);
+        ",
+    )
+    .map_err(|e| format!("v75 create order_events: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (75)", [])
+        .map_err(|e| format!("v75 record schema_version: {e}"))?;
+
+    info!("Applied migration v75 (order events)");
+    Ok(())
+}
+
+/// v76: end-of-day run log. Every scheduled or manual end-of-day pass
+/// records what it found and every action it took, so an automatic close
+/// can be audited after the fact.
+fn migrate_v76(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS eod_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            trigger TEXT NOT NULL,
+            scheduled_for TEXT,
+            status TEXT NOT NULL,
+            auto_close INTEGER NOT NULL DEFAULT 0,
+            open_shift_count INTEGER NOT NULL DEFAULT 0,
+            open_drawer_count INTEGER NOT NULL DEFAULT 0,
+            blockers TEXT NOT NULL DEFAULT '[]',
+            actions TEXT NOT NULL DEFAULT '[]',
+            z_report_id TEXT,
+            error TEXT,
+            actor_staff_id TEXT,
+            started_at TEXT NOT NULL,
+            finished_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_eod_runs_trigger_scheduled
+          ON eod_runs (trigger, scheduled_for);
+        ",
+    )
+    .map_err(|e| format!("v76 create eod_runs: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (76)", [])
+        .map_err(|e| format!("v76 record schema_version: {e}"))?;
+
+    info!("Applied migration v76 (end-of-day runs)");
+    Ok(())
+}
+
+/// v77: cache of locally computed daily sales summaries for closed
+/// business dates. `fingerprint` captures the day's order, payment
+/// and adjustment rows at compute time; a mismatch forces a recompute.
+fn migrate_v77(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS daily_summaries (
+            branch_id TEXT NOT NULL,
+            report_date TEXT NOT NULL,
+            fingerprint TEXT NOT NULL,
+            summary TEXT NOT NULL,
+            computed_at TEXT NOT NULL,
+            PRIMARY KEY (branch_id, report_date)
+        );
+        ",
+    )
+    .map_err(|e| format!("v77 create daily_summaries: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (77)", [])
+        .map_err(|e| format!("v77 record schema_version: {e}"))?;
+
+    info!("Applied migration v77 (daily sales summary cache)");
+    Ok(())
+}
+
+/// v78: ingredient stock. `inventory_items` is the current level per
+/// ingredient, `inventory_recipes` maps a menu item to the ingredients one
+/// unit consumes, and `inventory_movements` is the append-only ledger every
+/// stock change writes (and queues for sync).
+fn migrate_v78(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS inventory_items (
+            id TEXT PRIMARY KEY,
+            remote_id TEXT,
+            name TEXT NOT NULL,
+            unit TEXT,
+            quantity REAL NOT NULL DEFAULT 0,
+            low_stock_threshold REAL NOT NULL DEFAULT 0,
+            source TEXT NOT NULL DEFAULT 'local',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS inventory_recipes (
+            menu_item_id TEXT NOT NULL,
+            inventory_item_id TEXT NOT NULL,
+            quantity REAL NOT NULL,
+            PRIMARY KEY (menu_item_id, inventory_item_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS inventory_movements (
+            id TEXT PRIMARY KEY,
+            inventory_item_id TEXT NOT NULL,
+            movement_type TEXT NOT NULL
+                CHECK (movement_type IN ('sale', 'adjustment', 'receive', 'refund_restock')),
+            quantity_delta REAL NOT NULL,
+            quantity_after REAL NOT NULL,
+            reason TEXT,
+            order_id TEXT,
+            reference_id TEXT,
+            staff_id TEXT,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_inventory_movements_order
+          ON inventory_movements (order_id, movement_type);
+        CREATE INDEX IF NOT EXISTS idx_inventory_movements_item
+          ON inventory_movements (inventory_item_id, created_at);
+        ",
+    )
+    .map_err(|e| format!("v78 create inventory tables: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (78)", [])
+        .map_err(|e| format!("v78 record schema_version: {e}"))?;
+
+    info!("Applied migration v78 (ingredient inventory)");
+    Ok(())
+}
+
+/// v79: `print_jobs.reprint_count` — 0 for an original print, n for the
+/// n-th duplicate of an order's receipt, so copies can be told apart.
+fn migrate_v79(conn: &Connection) -> Result<(), String> {
+    let has_print_jobs = conn
+        .query_row(
+            "SELECT EXISTS(
+                 SELECT 1
+                 FROM sqlite_master
+                 WHERE type = 'table' AND name = 'print_jobs'
+             )",
+            [],
+            |row| row.get::<_, bool>(0),
+        )
+        .map_err(|e| format!("v79 inspect print_jobs table: {e}"))?;
+
+    // Same partial-schema allowance as v70.
+    if has_print_jobs && !column_exists(conn, "print_jobs", "reprint_count")? {
+        conn.execute(
+            "ALTER TABLE print_jobs ADD COLUMN reprint_count INTEGER NOT NULL DEFAULT 0",
+            [],
+        )
+        .map_err(|e| format!("v79 add print_jobs.reprint_count: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (79)", [])
+        .map_err(|e| format!("v79 record schema_version: {e}"))?;
+
+    info!("Applied migration v79 (print job reprint counter)");
+    Ok(())
+}
+
+/// v80: `receipt_deliveries` — e-receipts (email/SMS) waiting to be handed to
+/// the admin API, with retry backoff and a per-order/destination/day
+/// idempotency key so double-taps send once.
+fn migrate_v80(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS receipt_deliveries (
+            id TEXT PRIMARY KEY,
+            order_id TEXT NOT NULL,
+            channel TEXT NOT NULL CHECK (channel IN ('email', 'sms')),
+            destination TEXT NOT NULL,
+            idempotency_key TEXT NOT NULL UNIQUE,
+            payload_json TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending'
+                CHECK (status IN ('pending', 'sent', 'failed')),
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_retry_at TEXT,
+            last_error TEXT,
+            requested_by TEXT,
+            sent_at TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_receipt_deliveries_due
+            ON receipt_deliveries(status, next_retry_at);
+        CREATE INDEX IF NOT EXISTS idx_receipt_deliveries_order
+            ON receipt_deliveries(order_id, created_at);
+        ",
+    )
+    .map_err(|e| format!("v80 create receipt_deliveries: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (80)", [])
+        .map_err(|e| format!("v80 record schema_version: {e}"))?;
+
+    info!("Applied migration v80 (receipt deliveries)");
+    Ok(())
+}
+
+/// v81: `parity_sync_queue.correlation_id` — id of the command that enqueued
+/// the row, reused as `X-Correlation-Id` when the row is pushed.
+fn migrate_v81(conn: &Connection) -> Result<(), String> {
+    let has_queue = conn
+        .query_row(
+            "SELECT EXISTS(
+                 SELECT 1
+                 FROM sqlite_master
+                 WHERE type = 'table' AND name = 'parity_sync_queue'
+             )",
+            [],
+            |row| row.get::<_, bool>(0),
+        )
+        .map_err(|e| format!("v81 inspect parity_sync_queue table: {e}"))?;
+
+    if has_queue && !column_exists(conn, "parity_sync_queue", "correlation_id")? {
+        conn.execute(
+            "ALTER TABLE parity_sync_queue ADD COLUMN correlation_id TEXT",
+            [],
+        )
+        .map_err(|e| format!("v81 add parity_sync_queue.correlation_id: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (81)", [])
+        .map_err(|e| format!("v81 record schema_version: {e}"))?;
+
+    info!("Applied migration v81 (sync queue correlation ids)");
+    Ok(())
+}
+
+/// v82: `idempotency_cache` — responses of mutation commands keyed by the
+/// caller's `idempotencyKey`, so a webview retry replays instead of
+/// re-executing. See `idempotency::run_once`.
+fn migrate_v82(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS idempotency_cache (
+            command TEXT NOT NULL,
+            idempotency_key TEXT NOT NULL,
+            status TEXT NOT NULL CHECK (status IN ('pending', 'completed')),
+            response_json TEXT,
+            created_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            PRIMARY KEY (command, idempotency_key)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_idempotency_cache_expires
+            ON idempotency_cache(expires_at);
+        ",
+    )
+    .map_err(|e| format!("v82 create idempotency_cache: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (82)", [])
+        .map_err(|e| format!("v82 record schema_version: {e}"))?;
+
+    info!("Applied migration v82 (command idempotency cache)");
+    Ok(())
+}
+
+/// v83: `branches` — registry of the branches this terminal's API key may
+/// switch between, refreshed from the admin settings response. See
+/// `branches`.
+fn migrate_v83(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS branches (
+            id TEXT PRIMARY KEY,
+            name TEXT,
+            organization_id TEXT,
+            is_home INTEGER NOT NULL DEFAULT 0,
+            last_seen_at TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        ",
+    )
+    .map_err(|e| format!("v83 create branches: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (83)", [])
+        .map_err(|e| format!("v83 record schema_version: {e}"))?;
+
+    info!("Applied migration v83 (branch registry)");
+    Ok(())
+}
+
+/// v84: `printer_profiles.profile_type` (`receipt`, `kitchen`, `label`).
+/// The default flag is now tracked per type; every existing profile is a
+/// receipt profile, so the single pre-v84 default keeps its meaning.
+fn migrate_v84(conn: &Connection) -> Result<(), String> {
+    if table_exists(conn, "printer_profiles")?
+        && !column_exists(conn, "printer_profiles", "profile_type")?
+    {
+        conn.execute_batch(
+            "ALTER TABLE printer_profiles ADD COLUMN profile_type TEXT NOT NULL DEFAULT 'receipt';",
+        )
+        .map_err(|e| format!("v84 add printer_profiles.profile_type: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (84)", [])
+        .map_err(|e| format!("v84 record schema_version: {e}"))?;
+
+    info!("Applied migration v84 (printer profile types)");
+    Ok(())
+}
+
+/// v85: `orders.sla_breached_at`, set once by the SLA monitor so a restart
+/// does not re-alert, plus a `(status, created_at)` index for the open-order
+/// aging dashboard, which is polled every few seconds. See `order_aging`.
+fn migrate_v85(conn: &Connection) -> Result<(), String> {
+    if !column_exists(conn, "orders", "sla_breached_at")? {
+        conn.execute("ALTER TABLE orders ADD COLUMN sla_breached_at TEXT", [])
+            .map_err(|e| format!("v85 add orders.sla_breached_at: {e}"))?;
+    }
+    if column_exists(conn, "orders", "status")? && column_exists(conn, "orders", "created_at")? {
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_orders_status_created_at
+                 ON orders(status, created_at);",
+        )
+        .map_err(|e| format!("v85 create idx_orders_status_created_at: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (85)", [])
+        .map_err(|e| format!("v85 record schema_version: {e}"))?;
+
+    info!("Applied migration v85 (order SLA tracking)");
+    Ok(())
+}
+
+/// v86: `orders.cancelled_by`, the staff member who cancelled or declined
+/// the order. Payment voids and refunds already record their actor in
+/// `payment_adjustments.staff_id`; this covers whole-order voids for the
+/// staff performance report.
+fn migrate_v86(conn: &Connection) -> Result<(), String> {
+    if !column_exists(conn, "orders", "cancelled_by")? {
+        conn.execute("ALTER TABLE orders ADD COLUMN cancelled_by TEXT", [])
+            .map_err(|e| format!("v86 add orders.cancelled_by: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (86)", [])
+        .map_err(|e| format!("v86 record schema_version: {e}"))?;
+
+    info!("Applied migration v86 (order cancellation actor)");
+    Ok(())
+}
+
+/// v87: where an order was placed (`kiosk` for self-order kiosk orders; NULL
+/// for the regular till) so reports can separate them.
+fn migrate_v87(conn: &Connection) -> Result<(), String> {
+    if !column_exists(conn, "orders", "source")? {
+        conn.execute("ALTER TABLE orders ADD COLUMN source TEXT", [])
+            .map_err(|e| format!("v87 add orders.source: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (87)", [])
+        .map_err(|e| format!("v87 record schema_version: {e}"))?;
+
+    info!("Applied migration v87 (order source)");
+    Ok(())
+}
+
+/// v88: `menu_image_cache` — menu photos downloaded into `menu_images/`,
+/// keyed by source URL, with the ETag and content hash used to skip
+/// unchanged images and `last_accessed_at` for LRU eviction. See
+/// `menu::images`.
+fn migrate_v88(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS menu_image_cache (
+            url TEXT PRIMARY KEY,
+            file_name TEXT NOT NULL,
+            content_type TEXT,
+            etag TEXT,
+            content_hash TEXT NOT NULL,
+            size_bytes INTEGER NOT NULL,
+            fetched_at TEXT NOT NULL,
+            last_accessed_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_menu_image_cache_last_accessed
+            ON menu_image_cache(last_accessed_at);
+        ",
+    )
+    .map_err(|e| format!("v88 create menu_image_cache: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (88)", [])
+        .map_err(|e| format!("v88 record schema_version: {e}"))?;
+
+    info!("Applied migration v88 (menu image cache)");
+    Ok(())
+}
+
+/// v89: `orders.external_raw_payload` — the platform's original JSON for
+/// orders ingested directly from a delivery platform, kept for audit — and
+/// an index for deduplicating on `(plugin, external_plugin_order_id)`. See
+/// `order_plugins`.
+fn migrate_v89(conn: &Connection) -> Result<(), String> {
+    if !column_exists(conn, "orders", "external_raw_payload")? {
+        conn.execute(
+            "ALTER TABLE orders ADD COLUMN external_raw_payload TEXT",
+            [],
+        )
+        .map_err(|e| format!("v89 add orders.external_raw_payload: {e}"))?;
+    }
+
+    if column_exists(conn, "orders", "plugin")?
+        && column_exists(conn, "orders", "external_plugin_order_id")?
+    {
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_orders_plugin_external_id
+                ON orders(plugin, external_plugin_order_id);",
+        )
+        .map_err(|e| format!("v89 create plugin order index: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (89)", [])
+        .map_err(|e| format!("v89 record schema_version: {e}"))?;
+
+    info!("Applied migration v89 (external order payload)");
+    Ok(())
+}
+
+/// v90: `reservations` — local copy of the branch's reservations so the
+/// hostess screen keeps working offline. `remote_updated_at` is the server's
+/// `updated_at` as of the last applied pull; `conflict_remote_updated_at`
+/// remembers the last remote edit already logged as a conflict so a refresh
+/// does not log it again. See `reservations`.
+fn migrate_v90(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS reservations (
+            id TEXT PRIMARY KEY,
+            organization_id TEXT,
+            branch_id TEXT,
+            reservation_number TEXT,
+            customer_id TEXT,
+            customer_name TEXT,
+            customer_phone TEXT,
+            customer_email TEXT,
+            party_size INTEGER NOT NULL DEFAULT 2,
+            reservation_datetime TEXT NOT NULL,
+            duration_minutes INTEGER,
+            table_id TEXT,
+            status TEXT NOT NULL DEFAULT 'pending',
+            special_requests TEXT,
+            notes TEXT,
+            order_id TEXT,
+            seated_at TEXT,
+            no_show_at TEXT,
+            remote_updated_at TEXT,
+            conflict_remote_updated_at TEXT,
+            version INTEGER NOT NULL DEFAULT 1,
+            payload_json TEXT NOT NULL DEFAULT '{}',
+            synced_at TEXT,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_reservations_datetime
+            ON reservations(reservation_datetime);
+        CREATE INDEX IF NOT EXISTS idx_reservations_branch_datetime
+            ON reservations(branch_id, reservation_datetime);
+        ",
+    )
+    .map_err(|e| format!("v90 create reservations: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (90)", [])
+        .map_err(|e| format!("v90 record schema_version: {e}"))?;
+
+    info!("Applied migration v90 (reservations cache)");
+    Ok(())
+}
+
+/// v91: cash rounding (`payments.cash_rounding`). `order_payments.amount`
+/// stays the exact amount settled against the order; cash tenders also record
+/// what was physically collected (`rounded_amount`) and the difference
+/// (`rounding_delta`, rounded minus exact). Cash refunds carry their own
+/// delta, and `cash_drawer_sessions.total_cash_rounding` keeps the drawer's
+/// running net so expected cash matches the coins in the till.
+fn migrate_v91(conn: &Connection) -> Result<(), String> {
+    for (table, column, column_type) in [
+        ("order_payments", "rounded_amount", "REAL"),
+        ("order_payments", "rounded_amount_cents", "INTEGER"),
+        ("order_payments", "rounding_delta", "REAL"),
+        ("order_payments", "rounding_delta_cents", "INTEGER"),
+        ("payment_adjustments", "rounding_delta", "REAL"),
+        ("payment_adjustments", "rounding_delta_cents", "INTEGER"),
+        (
+            "cash_drawer_sessions",
+            "total_cash_rounding",
+            "REAL DEFAULT 0",
+        ),
+        (
+            "cash_drawer_sessions",
+            "total_cash_rounding_cents",
+            "INTEGER DEFAULT 0",
+        ),
+    ] {
+        if table_exists(conn, table)? && !column_exists(conn, table, column)? {
+            let sql = format!("ALTER TABLE {table} ADD COLUMN {column} {column_type}");
+            conn.execute(&sql, [])
+                .map_err(|e| format!("v91 add {table}.{column}: {e}"))?;
+        }
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (91)", [])
+        .map_err(|e| format!("v91 record schema_version: {e}"))?;
+
+    info!("Applied migration v91 (cash rounding)");
+    Ok(())
+}
+
+/// v92: `scheduled_shifts` — local copy of the branch's staff schedule, so
+/// clock-in can be checked against it offline. Start/end times are stored as
+/// UTC RFC 3339 strings. `staff_shifts` records how the clock-in compared to
+/// the schedule: the matched scheduled shift (its times go into the existing
+/// `scheduled_start` / `scheduled_end`), minutes early/late, whether the
+/// staff member had no scheduled shift, and when a manager approved the
+/// deviation. See `schedule`.
+fn migrate_v92(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS scheduled_shifts (
+            id TEXT PRIMARY KEY,
+            staff_id TEXT NOT NULL,
+            branch_id TEXT NOT NULL,
+            start_time TEXT NOT NULL,
+            end_time TEXT,
+            break_start TEXT,
+            break_end TEXT,
+            status TEXT,
+            notes TEXT,
+            staff_name TEXT,
+            staff_code TEXT,
+            synced_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_scheduled_shifts_branch_start
+            ON scheduled_shifts(branch_id, start_time);
+        CREATE INDEX IF NOT EXISTS idx_scheduled_shifts_staff_start
+            ON scheduled_shifts(staff_id, start_time);
+        ",
+    )
+    .map_err(|e| format!("v92 create scheduled_shifts: {e}"))?;
+
+    let has_staff_shifts = table_exists(conn, "staff_shifts")?;
+    for (column, column_type) in [
+        ("scheduled_shift_id", "TEXT"),
+        ("clock_in_early_minutes", "INTEGER"),
+        ("clock_in_late_minutes", "INTEGER"),
+        ("is_unscheduled", "INTEGER NOT NULL DEFAULT 0"),
+        ("schedule_check_status", "TEXT"),
+        ("schedule_approved_at", "TEXT"),
+    ] {
+        if has_staff_shifts && !column_exists(conn, "staff_shifts", column)? {
+            let sql = format!("ALTER TABLE staff_shifts ADD COLUMN {column} {column_type}");
+            conn.execute(&sql, [])
+                .map_err(|e| format!("v92 add staff_shifts.{column}: {e}"))?;
+        }
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (92)", [])
+        .map_err(|e| format!("v92 record schema_version: {e}"))?;
+
+    info!("Applied migration v92 (scheduled shifts cache)");
+    Ok(())
+}
+
+/// v93: reason codes for voids, refunds, comps and declines. Each affected
+/// row keeps the code next to its existing free-text reason, and the default
+/// code lists are seeded so upgraded terminals can keep voiding straight away.
+fn migrate_v93(conn: &Connection) -> Result<(), String> {
+    for (table, column) in [
+        ("order_payments", "void_reason_code"),
+        ("payment_adjustments", "reason_code"),
+        ("orders", "cancellation_reason_code"),
+        ("orders", "discount_reason_code"),
+    ] {
+        if table_exists(conn, table)? && !column_exists(conn, table, column)? {
+            let sql = format!("ALTER TABLE {table} ADD COLUMN {column} TEXT");
+            conn.execute(&sql, [])
+                .map_err(|e| format!("v93 add {table}.{column}: {e}"))?;
+        }
+    }
+
+    if table_exists(conn, "local_settings")? {
+        crate::reasons::seed_defaults(conn).map_err(|e| format!("v93 seed reason codes: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (93)", [])
+        .map_err(|e| format!("v93 record schema_version: {e}"))?;
+
+    info!("Applied migration v93 (reason codes)");
+    Ok(())
+}
+
+/// v94: fire state per course for dine-in orders. Item lines carry their
+/// course number in the order's items JSON; a row here means the course has
+/// been sent to the kitchen.
+fn migrate_v94(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS order_courses (
+            order_id TEXT NOT NULL,
+            course INTEGER NOT NULL,
+            fired_at TEXT NOT NULL,
+            fired_by TEXT,
+            fire_count INTEGER NOT NULL DEFAULT 1,
+            last_fired_at TEXT NOT NULL,
+            PRIMARY KEY (order_id, course)
+        );
+        ",
+    )
+    .map_err(|e| format!("v94 create order_courses: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (94)", [])
+        .map_err(|e| format!("v94 record schema_version: {e}"))?;
+
+    info!("Applied migration v94 (order courses)");
+    Ok(())
+}
+
+/// v95: local menu availability overrides written by bulk 86ing, tagged with
+/// the bulk operation that set them so the operation can be undone.
+fn migrate_v95(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS menu_availability_overrides (
+            entity_type TEXT NOT NULL,
+            entity_id TEXT NOT NULL,
+            is_available INTEGER NOT NULL,
+            operation_id TEXT NOT NULL,
+            remote_status TEXT NOT NULL DEFAULT 'pending'
+                CHECK (remote_status IN ('pending', 'applied')),
+            created_by TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (entity_type, entity_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_menu_availability_overrides_operation
+            ON menu_availability_overrides(operation_id);
+        ",
+    )
+    .map_err(|e| format!("v95 create menu_availability_overrides: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (95)", [])
+        .map_err(|e| format!("v95 record schema_version: {e}"))?;
+
+    info!("Applied migration v95 (menu availability overrides)");
+    Ok(())
+}
+
+/// v96: split bills. An order split into checks keeps its items for the
+/// kitchen; each `order_checks` row is one payable share of it, and
+/// payments taken against a check carry its id.
+fn migrate_v96(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS order_checks (
+            id TEXT PRIMARY KEY,
+            order_id TEXT NOT NULL,
+            check_number INTEGER NOT NULL,
+            check_count INTEGER NOT NULL,
+            split_mode TEXT NOT NULL CHECK (split_mode IN ('items', 'even')),
+            item_indexes TEXT NOT NULL DEFAULT '[]',
+            subtotal REAL NOT NULL DEFAULT 0,
+            subtotal_cents INTEGER NOT NULL DEFAULT 0,
+            tax_amount REAL NOT NULL DEFAULT 0,
+            tax_amount_cents INTEGER NOT NULL DEFAULT 0,
+            adjustment REAL NOT NULL DEFAULT 0,
+            adjustment_cents INTEGER NOT NULL DEFAULT 0,
+            total_amount REAL NOT NULL DEFAULT 0,
+            total_amount_cents INTEGER NOT NULL DEFAULT 0,
+            tax_breakdown TEXT,
+            status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'paid')),
+            created_by TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE (order_id, check_number)
+        );
+        CREATE INDEX IF NOT EXISTS idx_order_checks_order ON order_checks(order_id);
+        ",
+    )
+    .map_err(|e| format!("v96 create order_checks: {e}"))?;
+
+    if table_exists(conn, "order_payments")? && !column_exists(conn, "order_payments", "check_id")?
+    {
+        conn.execute("ALTER TABLE order_payments ADD COLUMN check_id TEXT", [])
+            .map_err(|e| format!("v96 add order_payments.check_id: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (96)", [])
+        .map_err(|e| format!("v96 record schema_version: {e}"))?;
+
+    info!("Applied migration v96 (order checks)");
+    Ok(())
+}
+
+/// v97: claim leases on `sync_queue`. A cycle that moves a row to
+/// `in_progress` leases it until `lease_expires_at`; once that passes the
+/// row is claimable again, so a crash between claim and completion no longer
+/// strands it.
+fn migrate_v97(conn: &Connection) -> Result<(), String> {
+    if table_exists(conn, "sync_queue")? && !column_exists(conn, "sync_queue", "lease_expires_at")?
+    {
+        conn.execute(
+            "ALTER TABLE sync_queue ADD COLUMN lease_expires_at TEXT",
+            [],
+        )
+        .map_err(|e| format!("v97 add sync_queue.lease_expires_at: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (97)", [])
+        .map_err(|e| format!("v97 record schema_version: {e}"))?;
+
+    info!("Applied migration v97 (sync queue claim leases)");
+    Ok(())
+}
+
+/// v98: `remote_cache`, the read-through cache behind the admin GET proxies
+/// (suppliers, tables, reservations, analytics, rooms). One row per path +
+/// normalized query; `last_accessed_at` drives LRU eviction.
+fn migrate_v98(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS remote_cache (
+            cache_key TEXT PRIMARY KEY,
+            path TEXT NOT NULL,
+            resource TEXT NOT NULL,
+            body TEXT NOT NULL,
+            size_bytes INTEGER NOT NULL,
+            cached_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            last_accessed_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_remote_cache_last_accessed
+            ON remote_cache(last_accessed_at);
+        ",
+    )
+    .map_err(|e| format!("v98 create remote_cache: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (98)", [])
+        .map_err(|e| format!("v98 record schema_version: {e}"))?;
+
+    info!("Applied migration v98 (remote read-through cache)");
+    Ok(())
+}
+
+/// v99: terminal re-pairing quarantine. `terminal_repair_sessions` tracks
+/// one re-pairing run from quarantine through migrate or discard;
+/// `terminal_repair_quarantine` lists the orders it holds and carries the
+/// queue rows it pulled out of `sync_queue` / `parity_sync_queue`.
+fn migrate_v99(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS terminal_repair_sessions (
+            id TEXT PRIMARY KEY,
+            status TEXT NOT NULL DEFAULT 'quarantined'
+                CHECK (status IN ('quarantined', 'validated', 'migrated', 'discarded')),
+            old_terminal_id TEXT,
+            old_branch_id TEXT,
+            new_terminal_id TEXT,
+            new_branch_id TEXT,
+            order_count INTEGER NOT NULL DEFAULT 0,
+            queue_count INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            completed_at TEXT
+        );
+        CREATE TABLE IF NOT EXISTS terminal_repair_quarantine (
+            repair_id TEXT NOT NULL,
+            source_table TEXT NOT NULL,
+            row_id TEXT NOT NULL,
+            row_json TEXT,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (repair_id, source_table, row_id),
+            FOREIGN KEY(repair_id) REFERENCES terminal_repair_sessions(id) ON DELETE CASCADE
+        );
+        ",
+    )
+    .map_err(|e| format!("v99 create terminal repair tables: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (99)", [])
+        .map_err(|e| format!("v99 record schema_version: {e}"))?;
+
+    info!("Applied migration v99 (terminal re-pairing quarantine)");
+    Ok(())
+}
+
+/// v100: per-order `service_charge` (printed on the receipt and part of the
+/// total) and `platform_commission` (what a delivery platform keeps; never
+/// printed). Both default to zero. See `platform_fees`.
+fn migrate_v100(conn: &Connection) -> Result<(), String> {
+    for (column, ddl) in [
+        ("service_charge", "REAL DEFAULT 0"),
+        ("service_charge_cents", "INTEGER DEFAULT 0"),
+        ("platform_commission", "REAL DEFAULT 0"),
+        ("platform_commission_cents", "INTEGER DEFAULT 0"),
+    ] {
+        if !column_exists(conn, "orders", column)? {
+            conn.execute(&format!("ALTER TABLE orders ADD COLUMN {column} {ddl}"), [])
+                .map_err(|e| format!("v100 add orders.{column}: {e}"))?;
+        }
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (100)", [])
+        .map_err(|e| format!("v100 record schema_version: {e}"))?;
+
+    info!("Applied migration v100 (order service charge and platform commission)");
+    Ok(())
+}
+
+/// v101: dispatch runs. `driver_runs` is one trip of a driver (`out` until
+/// they come back, then `returned`); `driver_run_orders` links the orders
+/// carried on it, in drop-off order, with the cash declared for each on
+/// return. See `dispatch`.
+fn migrate_v101(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS driver_runs (
+            id TEXT PRIMARY KEY,
+            driver_id TEXT NOT NULL,
+            driver_name TEXT,
+            driver_shift_id TEXT,
+            branch_id TEXT,
+            status TEXT NOT NULL DEFAULT 'out'
+                CHECK (status IN ('out', 'returned')),
+            started_at TEXT NOT NULL,
+            returned_at TEXT,
+            started_by TEXT,
+            completed_by TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_driver_runs_status
+            ON driver_runs(status, driver_id);
+
+        CREATE TABLE IF NOT EXISTS driver_run_orders (
+            run_id TEXT NOT NULL REFERENCES driver_runs(id) ON DELETE CASCADE,
+            order_id TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            cash_collected REAL,
+            cash_collected_cents INTEGER,
+            delivered_at TEXT,
+            PRIMARY KEY (run_id, order_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_driver_run_orders_order
+            ON driver_run_orders(order_id);
+        ",
+    )
+    .map_err(|e| format!("v101 create dispatch tables: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (101)", [])
+        .map_err(|e| format!("v101 record schema_version: {e}"))?;
+
+    info!("Applied migration v101 (driver dispatch runs)");
+    Ok(())
+}
+
+/// v102: returns. A return order (`is_return = 1`) carries negative lines and
+/// points at the sale it refunds through `related_order_id`; an exchange
+/// order points at its return the same way. Refunds issued by a return are
+/// tagged with `payment_adjustments.return_order_id` so reports count the
+/// money once, on the return order, instead of again as a refund.
+fn migrate_v102(conn: &Connection) -> Result<(), String> {
+    for (column, ddl) in [
+        ("related_order_id", "TEXT"),
+        ("is_return", "INTEGER DEFAULT 0"),
+    ] {
+        if !column_exists(conn, "orders", column)? {
+            conn.execute(&format!("ALTER TABLE orders ADD COLUMN {column} {ddl}"), [])
+                .map_err(|e| format!("v102 add orders.{column}: {e}"))?;
+        }
+    }
+    if table_exists(conn, "payment_adjustments")?
+        && !column_exists(conn, "payment_adjustments", "return_order_id")?
+    {
+        conn.execute(
+            "ALTER TABLE payment_adjustments ADD COLUMN return_order_id TEXT",
+            [],
+        )
+        .map_err(|e| format!("v102 add payment_adjustments.return_order_id: {e}"))?;
+    }
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_orders_related_order
+             ON orders(related_order_id);",
+    )
+    .map_err(|e| format!("v102 create related order index: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (102)", [])
+        .map_err(|e| format!("v102 record schema_version: {e}"))?;
+
+    info!("Applied migration v102 (linked return orders)");
+    Ok(())
+}
+
+/// v103: time-based pricing rules (happy hour). Rules come from the admin
+/// menu sync (`source = 'admin'`) or are managed on the terminal
+/// (`source = 'local'`); list columns hold JSON arrays. See `pricing_rules`.
+fn migrate_v103(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS pricing_rules (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            label TEXT,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            days_of_week TEXT NOT NULL DEFAULT '[]',
+            start_time TEXT,
+            end_time TEXT,
+            category_ids TEXT NOT NULL DEFAULT '[]',
+            subcategory_ids TEXT NOT NULL DEFAULT '[]',
+            item_ids TEXT NOT NULL DEFAULT '[]',
+            adjustment_type TEXT NOT NULL
+                CHECK (adjustment_type IN ('percent', 'fixed_price')),
+            adjustment_value REAL NOT NULL,
+            adjustment_value_cents INTEGER,
+            source TEXT NOT NULL DEFAULT 'local'
+                CHECK (source IN ('local', 'admin')),
+            updated_by TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_pricing_rules_source
+            ON pricing_rules(source);
+        ",
+    )
+    .map_err(|e| format!("v103 create pricing_rules: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (103)", [])
+        .map_err(|e| format!("v103 record schema_version: {e}"))?;
+
+    info!("Applied migration v103 (pricing rules)");
+    Ok(())
+}
+
+/// v104: prep-time statistics. `orders.confirmed_at` / `ready_at` stamp the
+/// first time an order reaches each status; `prep_time_stats` keeps one
+/// rolling mean and variance per scope (`all`, `order_type`, `category`).
+/// See `prep_time`.
+fn migrate_v104(conn: &Connection) -> Result<(), String> {
+    for column in ["confirmed_at", "ready_at"] {
+        if !column_exists(conn, "orders", column)? {
+            conn.execute(&format!("ALTER TABLE orders ADD COLUMN {column} TEXT"), [])
+                .map_err(|e| format!("v104 add orders.{column}: {e}"))?;
+        }
+    }
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS prep_time_stats (
+            scope TEXT NOT NULL,
+            scope_key TEXT NOT NULL,
+            samples INTEGER NOT NULL DEFAULT 0,
+            mean_minutes REAL NOT NULL DEFAULT 0,
+            variance REAL NOT NULL DEFAULT 0,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (scope, scope_key)
+        );
+        ",
+    )
+    .map_err(|e| format!("v104 create prep_time_stats: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (104)", [])
+        .map_err(|e| format!("v104 record schema_version: {e}"))?;
+
+    info!("Applied migration v104 (prep-time statistics)");
+    Ok(())
+}
+
+/// v105: order-level kitchen notes and allergy flags. Item-level notes and
+/// flags stay in the items JSON; `allergy_flags` holds a JSON array. See
+/// `allergens`.
+fn migrate_v105(conn: &Connection) -> Result<(), String> {
+    for column in ["kitchen_notes", "allergy_flags"] {
+        if !column_exists(conn, "orders", column)? {
+            conn.execute(&format!("ALTER TABLE orders ADD COLUMN {column} TEXT"), [])
+                .map_err(|e| format!("v105 add orders.{column}: {e}"))?;
+        }
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (105)", [])
+        .map_err(|e| format!("v105 record schema_version: {e}"))?;
+
+    info!("Applied migration v105 (kitchen notes and allergy flags)");
+    Ok(())
+}
+
+/// v106: `staff_payments`, until now created on first use by
+/// `ensure_staff_payments_table` in the shift commands. Terminals that
+/// already have the table keep their rows and gain any missing column. As
+/// v47 and v54 asked of this table when it got a real migration, it carries
+/// `amount_cents` and an `idempotency_key` stamped by the v49 trigger.
+fn migrate_v106(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS staff_payments (
+            id TEXT PRIMARY KEY,
+            cashier_shift_id TEXT NOT NULL,
+            paid_to_staff_id TEXT NOT NULL,
+            amount REAL NOT NULL,
+            amount_cents INTEGER,
+            payment_type TEXT NOT NULL DEFAULT 'wage',
+            notes TEXT,
+            idempotency_key TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        ",
+    )
+    .map_err(|e| format!("v106 create staff_payments: {e}"))?;
+
+    for (column, column_type) in [
+        ("amount_cents", "INTEGER"),
+        ("idempotency_key", "TEXT"),
+        ("updated_at", "TEXT"),
+    ] {
+        if !column_exists(conn, "staff_payments", column)? {
+            conn.execute(
+                &format!("ALTER TABLE staff_payments ADD COLUMN {column} {column_type}"),
+                [],
+            )
+            .map_err(|e| format!("v106 add staff_payments.{column}: {e}"))?;
+        }
+    }
+
+    conn.execute_batch(
+        "
+        UPDATE staff_payments
+        SET amount_cents = CAST(ROUND(amount * 100) AS INTEGER)
+        WHERE amount_cents IS NULL AND amount IS NOT NULL;
+
+        UPDATE staff_payments
+        SET updated_at = created_at
+        WHERE updated_at IS NULL OR trim(updated_at) = '';
+
+        UPDATE staff_payments
+        SET idempotency_key = lower(hex(randomblob(16)))
+        WHERE idempotency_key IS NULL;
+
+        CREATE INDEX IF NOT EXISTS idx_staff_payments_cashier_shift_id
+            ON staff_payments(cashier_shift_id);
+        CREATE INDEX IF NOT EXISTS idx_staff_payments_paid_to_staff_id
+            ON staff_payments(paid_to_staff_id);
+        CREATE INDEX IF NOT EXISTS idx_staff_payments_created_at
+            ON staff_payments(created_at);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_staff_payments_idempotency_key
+            ON staff_payments(idempotency_key)
+            WHERE idempotency_key IS NOT NULL;
+
+        DROP TRIGGER IF EXISTS trg_staff_payments_idempotency_key;
+        CREATE TRIGGER trg_staff_payments_idempotency_key
+            AFTER INSERT ON staff_payments
+            WHEN NEW.idempotency_key IS NULL
+        BEGIN
+            UPDATE staff_payments
+            SET idempotency_key = lower(hex(randomblob(16)))
+            WHERE id = NEW.id;
+        END;
+        ",
+    )
+    .map_err(|e| format!("v106 backfill staff_payments: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (106)", [])
+        .map_err(|e| format!("v106 record schema_version: {e}"))?;
+
+    info!("Applied migration v106 (staff payments)");
+    Ok(())
+}
+
+/// v107: `favorite_grids` — the quick-sale favorites layout, one row per
+/// terminal plus optional per-staff rows (`staff_id = ''` is the terminal
+/// default). `layout` is the grid JSON. See `favorites`.
+fn migrate_v107(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS favorite_grids (
+            terminal_id TEXT NOT NULL,
+            staff_id TEXT NOT NULL DEFAULT '',
+            layout TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (terminal_id, staff_id)
+        );
+        ",
+    )
+    .map_err(|e| format!("v107 create favorite_grids: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (107)", [])
+        .map_err(|e| format!("v107 record schema_version: {e}"))?;
+
+    info!("Applied migration v107 (favorites grid)");
+    Ok(())
+}
+
+/// v108: `order_tabs` — bar tabs, one row per tab opened against an order,
+/// holding the card pre-authorization (or a manual card reference) until
+/// the tab is captured at close. At most one tab per order is open. See
+/// `tabs`.
+fn migrate_v108(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS order_tabs (
+            id TEXT PRIMARY KEY,
+            order_id TEXT NOT NULL,
+            branch_id TEXT,
+            terminal_id TEXT,
+            mode TEXT NOT NULL CHECK (mode IN ('pre_auth', 'manual')),
+            device_id TEXT,
+            hold_amount REAL NOT NULL DEFAULT 0,
+            hold_amount_cents INTEGER NOT NULL DEFAULT 0,
+            card_reference TEXT,
+            card_last_four TEXT,
+            authorization_code TEXT,
+            terminal_reference TEXT,
+            ecr_transaction_id TEXT,
+            status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'closed')),
+            opened_by TEXT,
+            opened_at TEXT NOT NULL,
+            captured_amount REAL,
+            captured_amount_cents INTEGER,
+            payment_id TEXT,
+            closed_by TEXT,
+            closed_at TEXT,
+            updated_at TEXT NOT NULL
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_order_tabs_open_order
+            ON order_tabs(order_id) WHERE status = 'open';
+        CREATE INDEX IF NOT EXISTS idx_order_tabs_status_branch
+            ON order_tabs(status, branch_id);
+        ",
+    )
+    .map_err(|e| format!("v108 create order_tabs: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (108)", [])
+        .map_err(|e| format!("v108 record schema_version: {e}"))?;
+
+    info!("Applied migration v108 (order tabs)");
+    Ok(())
+}
+
+/// v109: ingredient costing. `unit_cost` on `inventory_items` is the last
+/// received cost per unit and on `inventory_movements` the cost a delivery
+/// was received at, so a period can be costed at its weighted average.
+/// `inventory_modifier_recipes` maps a menu ingredient offered as a
+/// modifier to the stock it adds (or, when left out, saves). Costs are per
+/// stock unit and often below a cent, so they carry no `_cents` twin. See
+/// `inventory`.
+fn migrate_v109(conn: &Connection) -> Result<(), String> {
+    for table in ["inventory_items", "inventory_movements"] {
+        if !column_exists(conn, table, "unit_cost")? {
+            conn.execute(
+                &format!("ALTER TABLE {table} ADD COLUMN unit_cost REAL"),
+                [],
+            )
+            .map_err(|e| format!("v109 add {table}.unit_cost: {e}"))?;
+        }
+    }
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS inventory_modifier_recipes (
+            modifier_id TEXT NOT NULL,
+            inventory_item_id TEXT NOT NULL,
+            quantity REAL NOT NULL,
+            PRIMARY KEY (modifier_id, inventory_item_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_inventory_movements_type_created
+            ON inventory_movements (movement_type, created_at);
+        ",
+    )
+    .map_err(|e| format!("v109 create inventory_modifier_recipes: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (109)", [])
+        .map_err(|e| format!("v109 record schema_version: {e}"))?;
+
+    info!("Applied migration v109 (inventory costing)");
+    Ok(())
+}
+
+/// Migration v110: allow `deferred` print jobs (printer reported paper-out).
+///
+/// Columns have been added to `print_jobs` by `ALTER TABLE` since the last
+/// rebuild (v40), so the new table is created from the stored definition
+/// with only the status CHECK widened rather than from a copied column
+/// list.
+fn migrate_v110(conn: &Connection) -> Result<(), String> {
+    let table_sql: Option<String> = match conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'print_jobs'",
+        [],
+        |row| row.get(0),
+    ) {
+        Ok(sql) => Some(sql),
+        Err(rusqlite::Error::QueryReturnedNoRows) => None,
+        Err(e) => return Err(format!("v110 inspect print_jobs table: {e}")),
+    };
+
+    // Same partial-schema allowance as v79.
+    if let Some(table_sql) = table_sql.filter(|sql| !sql.contains("'deferred'")) {
+        const OLD_STATUSES: &str = "'failed', 'cancelled')";
+        let columns_start = table_sql
+            .find('(')
+            .ok_or_else(|| "v110 unexpected print_jobs definition".to_string())?;
+        if !table_sql.contains(OLD_STATUSES) {
+            return Err("v110 print_jobs status CHECK not found".to_string());
+        }
+        let rebuilt = format!(
+            "CREATE TABLE print_jobs_v110 {}",
+            table_sql[columns_start..].replacen(
+                OLD_STATUSES,
+                "'failed', 'cancelled', 'deferred')",
+                1
+            )
+        );
+        conn.execute_batch(&format!(
+            "
+            DROP TABLE IF EXISTS print_jobs_v110;
+            {rebuilt};
+            INSERT INTO print_jobs_v110 SELECT * FROM print_jobs;
+            DROP TABLE print_jobs;
+            ALTER TABLE print_jobs_v110 RENAME TO print_jobs;
+
+            CREATE INDEX IF NOT EXISTS idx_print_jobs_status
+                ON print_jobs(status);
+            CREATE INDEX IF NOT EXISTS idx_print_jobs_created_at
+                ON print_jobs(created_at);
+            CREATE INDEX IF NOT EXISTS idx_print_jobs_entity
+                ON print_jobs(entity_type, entity_id);
+            "
+        ))
+        .map_err(|e| format!("v110 rebuild print_jobs: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (110)", [])
+        .map_err(|e| format!("v110 record schema_version: {e}"))?;
+
+    info!("Applied migration v110 (deferred print_jobs status)");
+    Ok(())
+}
+
+/// Migration v111: expected opening float and its deviation on shifts.
+fn migrate_v111(conn: &Connection) -> Result<(), String> {
+    for (column, definition) in [
+        ("opening_float_expected", "REAL"),
+        ("opening_float_expected_cents", "INTEGER"),
+        ("opening_float_deviation", "REAL"),
+        ("opening_float_deviation_cents", "INTEGER"),
+        ("opening_float_note", "TEXT"),
+        ("opening_float_approved_at", "TEXT"),
+    ] {
+        if table_exists(conn, "staff_shifts")? && !column_exists(conn, "staff_shifts", column)? {
+            conn.execute(
+                &format!("ALTER TABLE staff_shifts ADD COLUMN {column} {definition}"),
+                [],
+            )
+            .map_err(|e| format!("v111 add staff_shifts.{column}: {e}"))?;
+        }
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (111)", [])
+        .map_err(|e| format!("v111 record schema_version: {e}"))?;
+
+    info!("Applied migration v111 (opening float deviation on shifts)");
+    Ok(())
+}
+
+/// v112: automatic service charge. The rate, basis and taxability are
+/// captured on the order when the charge is applied so later configuration
+/// changes never reprice it; waivers and the refunded share of the charge
+/// are recorded for the Z-report.
+fn migrate_v112(conn: &Connection) -> Result<(), String> {
+    for (table, column, definition) in [
+        ("orders", "service_charge_rate", "REAL"),
+        ("orders", "service_charge_basis", "TEXT"),
+        (
+            "orders",
+            "service_charge_taxable",
+            "INTEGER NOT NULL DEFAULT 0",
+        ),
+        ("orders", "service_charge_waived", "REAL"),
+        ("orders", "service_charge_waived_cents", "INTEGER"),
+        ("orders", "service_charge_waived_at", "TEXT"),
+        ("orders", "service_charge_waiver_reason", "TEXT"),
+        ("orders", "service_charge_waived_by", "TEXT"),
+        ("payment_adjustments", "service_charge", "REAL"),
+        ("payment_adjustments", "service_charge_cents", "INTEGER"),
+    ] {
+        if table_exists(conn, table)? && !column_exists(conn, table, column)? {
+            conn.execute(
+                &format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+                [],
+            )
+            .map_err(|e| format!("v112 add {table}.{column}: {e}"))?;
+        }
+    }
+
+    if table_exists(conn, "local_settings")? {
+        crate::reasons::seed_defaults(conn)
+            .map_err(|e| format!("v112 seed waiver reason codes: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (112)", [])
+        .map_err(|e| format!("v112 record schema_version: {e}"))?;
+
+    info!("Applied migration v112 (automatic service charge)");
+    Ok(())
+}
+
+/// v113: deferred print jobs. `not_before` holds back a job until its time
+/// (the epoch for jobs that print straight away); the worker reads pending
+/// jobs through `(status, not_before)`. Cancelling an order cancels its
+/// jobs that have not reached their time yet.
+fn migrate_v113(conn: &Connection) -> Result<(), String> {
+    if table_exists(conn, "print_jobs")? {
+        for (column, definition) in [
+            (
+                "not_before",
+                "TEXT NOT NULL DEFAULT '1970-01-01T00:00:00.000Z'",
+            ),
+            ("cancelled_at", "TEXT"),
+            ("cancelled_by", "TEXT"),
+            ("cancel_reason", "TEXT"),
+        ] {
+            if !column_exists(conn, "print_jobs", column)? {
+                conn.execute(
+                    &format!("ALTER TABLE print_jobs ADD COLUMN {column} {definition}"),
+                    [],
+                )
+                .map_err(|e| format!("v113 add print_jobs.{column}: {e}"))?;
+            }
+        }
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_print_jobs_status_not_before
+                ON print_jobs(status, not_before)",
+            [],
+        )
+        .map_err(|e| format!("v113 create print_jobs not_before index: {e}"))?;
+
+        if table_exists(conn, "orders")? {
+            conn.execute_batch(
+                "
+                CREATE TRIGGER IF NOT EXISTS trg_orders_cancel_scheduled_print_jobs
+                AFTER UPDATE OF status ON orders
+                WHEN NEW.status IN ('cancelled', 'canceled')
+                 AND OLD.status NOT IN ('cancelled', 'canceled')
+                BEGIN
+                    UPDATE print_jobs
+                    SET status = 'cancelled',
+                        warning_code = 'order_cancelled',
+                        warning_message = 'Order cancelled before the scheduled print',
+                        cancel_reason = 'order_cancelled',
+                        cancelled_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+                        updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+                    WHERE entity_id = NEW.id
+                      AND status IN ('pending', 'deferred')
+                      AND not_before > strftime('%Y-%m-%dT%H:%M:%fZ', 'now');
+                END;
+                ",
+            )
+            .map_err(|e| format!("v113 create order cancel print trigger: {e}"))?;
+        }
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (113)", [])
+        .map_err(|e| format!("v113 record schema_version: {e}"))?;
+
+    info!("Applied migration v113 (deferred print jobs)");
+    Ok(())
+}
+
+/// v114: cash-in / cash-out drawer transactions. Each movement is its own
+/// row against the drawer session; the session keeps running totals in
+/// `cash_in` and the existing `cash_drops`, which the expected-cash formula
+/// already subtracts.
+fn migrate_v114(conn: &Connection) -> Result<(), String> {
+    if table_exists(conn, "cash_drawer_sessions")? {
+        for (column, definition) in [("cash_in", "REAL DEFAULT 0"), ("cash_in_cents", "INTEGER")] {
+            if !column_exists(conn, "cash_drawer_sessions", column)? {
+                conn.execute(
+                    &format!("ALTER TABLE cash_drawer_sessions ADD COLUMN {column} {definition}"),
+                    [],
+                )
+                .map_err(|e| format!("v114 add cash_drawer_sessions.{column}: {e}"))?;
+            }
+        }
+
+        conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS drawer_transactions (
+                id TEXT PRIMARY KEY,
+                cash_drawer_session_id TEXT NOT NULL,
+                staff_shift_id TEXT NOT NULL,
+                transaction_type TEXT NOT NULL CHECK (transaction_type IN ('cash_in', 'cash_out')),
+                amount REAL NOT NULL,
+                amount_cents INTEGER NOT NULL,
+                reason_code TEXT NOT NULL,
+                reason_label TEXT,
+                note TEXT,
+                staff_id TEXT,
+                approved_by TEXT,
+                branch_id TEXT,
+                terminal_id TEXT,
+                idempotency_key TEXT,
+                sync_status TEXT NOT NULL DEFAULT 'pending',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY(cash_drawer_session_id) REFERENCES cash_drawer_sessions(id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_drawer_transactions_session
+                ON drawer_transactions(cash_drawer_session_id, created_at);
+            CREATE INDEX IF NOT EXISTS idx_drawer_transactions_created_at
+                ON drawer_transactions(created_at);
+            ",
+        )
+        .map_err(|e| format!("v114 create drawer_transactions: {e}"))?;
+    }
+
+    if table_exists(conn, "local_settings")? {
+        crate::reasons::seed_defaults(conn)
+            .map_err(|e| format!("v114 seed drawer reason codes: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (114)", [])
+        .map_err(|e| format!("v114 record schema_version: {e}"))?;
+
+    info!("Applied migration v114 (drawer cash-in / cash-out transactions)");
+    Ok(())
+}
+
+/// v115: one local order per remote id. Remote-order ingestion upserts on
+/// `supabase_id`, which needs a unique index. Terminals that raced a sync
+/// pull against a remote save may already hold two rows for one remote
+/// order; the most recently updated row keeps the remote id and the rest
+/// are detached from it, logged at WARN.
+fn migrate_v115(conn: &Connection) -> Result<(), String> {
+    if !table_exists(conn, "orders")? || !column_exists(conn, "orders", "supabase_id")? {
+        conn.execute("INSERT INTO schema_version (version) VALUES (115)", [])
+            .map_err(|e| format!("v115 record schema_version: {e}"))?;
+        return Ok(());
+    }
+
+    let duplicates: Vec<(String, i64)> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT supabase_id, COUNT(*)
+                 FROM orders
+                 WHERE supabase_id IS NOT NULL AND supabase_id <> ''
+                 GROUP BY supabase_id
+                 HAVING COUNT(*) > 1",
+            )
+            .map_err(|e| format!("v115 scan duplicate remote ids: {e}"))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("v115 map duplicate remote ids: {e}"))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("v115 collect duplicate remote ids: {e}"))?;
+        rows
+    };
+    for (supabase_id, count) in &duplicates {
+        warn!(
+            supabase_id = %supabase_id,
+            duplicate_orders = count,
+            "migration v115: detaching older duplicate orders from their remote id"
+        );
+        conn.execute(
+            "UPDATE orders
+             SET supabase_id = NULL
+             WHERE supabase_id = ?1
+               AND id NOT IN (
+                   SELECT id FROM orders
+                   WHERE supabase_id = ?1
+                   ORDER BY updated_at DESC, id DESC
+                   LIMIT 1
+               )",
+            params![supabase_id],
+        )
+        .map_err(|e| format!("v115 dedup remote order {supabase_id}: {e}"))?;
+    }
+
+    conn.execute_batch(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_orders_supabase_id_unique
+             ON orders(supabase_id)
+             WHERE supabase_id IS NOT NULL AND supabase_id <> '';",
+    )
+    .map_err(|e| format!("v115 create unique supabase_id index: {e}"))?;
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (115)", [])
+        .map_err(|e| format!("v115 record schema_version: {e}"))?;
+
+    info!(
+        duplicates_cleaned = duplicates.len(),
+        "Applied migration v115 (orders supabase_id unique index)"
+    );
+    Ok(())
+}
+
+/// v116: seed the `eta_change` reason codes that `order_update_eta`
+/// requires.
+fn migrate_v116(conn: &Connection) -> Result<(), String> {
+    if table_exists(conn, "local_settings")? {
+        crate::reasons::seed_defaults(conn)
+            .map_err(|e| format!("v116 seed eta reason codes: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (116)", [])
+        .map_err(|e| format!("v116 record schema_version: {e}"))?;
+
+    info!("Applied migration v116 (ETA change reason codes)");
+    Ok(())
+}
+
+/// v117: indexes for the hot order and sync-queue queries.
+///
+/// `parity_sync_queue(table_name, status)` serves the per-entity pending
+/// counts and claims, as `idx_sync_queue_entity_status` (v30) does for the
+/// legacy `sync_queue`. The v30 lookups by Supabase id and payment order id
+/// are re-asserted for databases restored from older backups. The
+/// status-filtered order lists already search `idx_orders_status_created_at`
+/// (v85).
+fn migrate_v117(conn: &Connection) -> Result<(), String> {
+    let indexes = [
+        ("idx_orders_supabase_id", "orders", &["supabase_id"][..]),
+        (
+            "idx_order_payments_order_id",
+            "order_payments",
+            &["order_id"][..],
+        ),
+        (
+            "idx_parity_sq_table_status",
+            "parity_sync_queue",
+            &["table_name", "status"][..],
+        ),
+    ];
+    for (index, table, columns) in indexes {
+        if !table_exists(conn, table)? {
+            continue;
+        }
+        let mut present = true;
+        for column in columns {
+            present &= column_exists(conn, table, column)?;
+        }
+        if !present {
+            continue;
+        }
+        conn.execute_batch(&format!(
+            "CREATE INDEX IF NOT EXISTS {index} ON {table}({});",
+            columns.join(", ")
+        ))
+        .map_err(|e| format!("v117 create {index}: {e}"))?;
+    }
+
+    conn.execute("INSERT INTO schema_version (version) VALUES (117)", [])
+        .map_err(|e| format!("v117 record schema_version: {e}"))?;
+
+    info!("Applied migration v117 (hot-path order and sync queue indexes)");
+    Ok(())
+}
+
+/// Read the persisted `idempotency_key` from an entity table.
+///
+/// Wave 4 architectural contract:
+///
+/// > Every `sync_queue` row that dispatches an entity MUST carry the
+/// > SAME `idempotency_key` that was persisted on the entity row at
+/// > creation time. A second dispatch (retry, requeue, manual replay)
+/// > reads the same entity row and copies the same key, so the server
+/// > sees ONE operation regardless of how many times the client
+/// > re-sends it.
+///
+/// Use this helper to fetch the key before constructing an enqueue.
+/// Rows created under v47+ always have a value (nullable on-disk, but
+/// the v49 trigger backfills via SQLite random on INSERT). If the key
+/// is missing for any reason — a pre-v47 row that was never touched,
+/// or a trigger that failed silently — this returns `None` and the
+/// caller may fall back to a deterministic synthetic
+/// (`entity_type:entity_id:operation`) so the sync_queue INSERT still
+/// succeeds.
+///
+/// `table` must be one of the five entity-sync-queue tables covered
+/// by v47 (`order_payments`, `payment_adjustments`, `staff_shifts`,
+/// `shift_expenses`, `driver_earnings`). The function validates that
+/// at compile time via a debug_assert; production builds accept any
+/// plain identifier and simply return `None` on lookup miss.
+// Wave 5 C17: consumer wired in `sync_queue.rs::prepare_financial_request`
+// via the `idempotency::make_entity_key` facade; `#[allow(dead_code)]`
+// gate removed.
+pub fn get_entity_idempotency_key(
+    conn: &Connection,
+    table: &str,
+    entity_id: &str,
+) -> Option<String> {
+    debug_assert!(
+        matches!(
+            table,
+            "order_payments"
+                | "payment_adjustments"
+                | "staff_shifts"
+                | "shift_expenses"
+                | "driver_earnings"
+                | "staff_payments"
+                | "drawer_transactions"
+        ),
+        "get_entity_idempotency_key: unexpected table '{table}'"
+    );
+    debug_assert!(
+        is_safe_sql_identifier(table),
+        "get_entity_idempotency_key: table '{table}' must be a plain identifier"
+    );
+    let sql = format!("SELECT idempotency_key FROM {table} WHERE id = ?1");
+    conn.query_row(&sql, params![entity_id], |row| {
+        row.get::<_, Option<String>>(0)
+    })
+    .ok()
+    .flatten()
+}
+
+// ---------------------------------------------------------------------------
+// ECR device helpers
+// ---------------------------------------------------------------------------
+
+/// Insert a new ECR device.
+pub fn ecr_insert_device(conn: &Connection, dev: &serde_json::Value) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO ecr_devices
+            (id, name, device_type, brand, protocol, connection_type, connection_details,
+             terminal_id, merchant_id, operator_id, print_mode, tax_rates,
+             is_default, enabled, settings)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+        params![
+            dev.get("id").and_then(|v| v.as_str()).unwrap_or_default(),
+            dev.get("name").and_then(|v| v.as_str()).unwrap_or("Device"),
+            dev.get("deviceType")
+                .and_then(|v| v.as_str())
+                .unwrap_or("payment_terminal"),
+            dev.get("brand")
+                .and_then(|v| v.as_str())
+                .unwrap_or("generic"),
+            dev.get("protocol")
+                .and_then(|v| v.as_str())
+                .unwrap_or("generic"),
+            dev.get("connectionType")
+                .and_then(|v| v.as_str())
+                .unwrap_or("network"),
+            dev.get("connectionDetails")
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "{}".into()),
+            dev.get("terminalId").and_then(|v| v.as_str()),
+            dev.get("merchantId").and_then(|v| v.as_str()),
+            dev.get("operatorId").and_then(|v| v.as_str()),
+            dev.get("printMode")
+                .and_then(|v| v.as_str())
+                .unwrap_or("register_prints"),
+            dev.get("taxRates")
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "[]".into()),
+            dev.get("isDefault")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false) as i32,
+            dev.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true) as i32,
+            dev.get("settings")
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "{}".into()),
+        ],
+    )
+    .map_err(|e| format!("ecr_insert_device: {e}"))?;
+    Ok(())
+}
+
+/// Update an existing ECR device.
+pub fn ecr_update_device(
+    conn: &Connection,
+    id: &str,
+    updates: &serde_json::Value,
+) -> Result<(), String> {
+    // Build SET clauses dynamically for provided fields
+    let mut sets = Vec::new();
+    let mut values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+
+    macro_rules! maybe_set {
+        ($field:expr, $col:expr) => {
+            if let Some(v) = updates.get($field) {
+                if let Some(s) = v.as_str() {
+                    sets.push(format!("{} = ?", $col));
+                    values.push(Box::new(s.to_string()));
+                }
+            }
+        };
+    }
+
+    macro_rules! maybe_set_json {
         ($field:expr, $col:expr) => {
             if let Some(v) = updates.get($field) {
                 sets.push(format!("{} = ?", $col));
@@ -4959,20 +7079,50 @@ pub fn get_setting(conn: &Connection, category: &str, key: &str) -> Option<Strin
 /// does not exist. Used by the post-hydration purge to remove plaintext
 /// credentials from `local_settings` once they're safely in the OS keyring.
 pub fn delete_setting(conn: &Connection, category: &str, key: &str) -> Result<usize, String> {
-    conn.execute(
-        "DELETE FROM local_settings WHERE setting_category = ?1 AND setting_key = ?2",
-        params![category, key],
-    )
-    .map_err(|e| format!("delete_setting: {e}"))
+    let previous = get_setting(conn, category, key);
+    let deleted = conn
+        .execute(
+            "DELETE FROM local_settings WHERE setting_category = ?1 AND setting_key = ?2",
+            params![category, key],
+        )
+        .map_err(|e| format!("delete_setting: {e}"))?;
+    if deleted > 0 {
+        record_setting_change(
+            conn,
+            category,
+            key,
+            previous.as_deref(),
+            None,
+            "system",
+            None,
+        )?;
+    }
+    Ok(deleted)
 }
 
-/// Insert or update a setting.
+/// Insert or update a setting. Effective changes are appended to
+/// `settings_history` with source `system`; commands acting for a staff
+/// member use [`set_setting_with_source`] instead.
 pub fn set_setting(
     conn: &Connection,
     category: &str,
     key: &str,
     value: &str,
 ) -> Result<(), String> {
+    set_setting_with_source(conn, category, key, value, "system", None)
+}
+
+/// Insert or update a setting, recording the change (if the value actually
+/// changed) against `source` and the acting staff member.
+pub fn set_setting_with_source(
+    conn: &Connection,
+    category: &str,
+    key: &str,
+    value: &str,
+    source: &str,
+    actor_staff_id: Option<&str>,
+) -> Result<(), String> {
+    let previous = get_setting(conn, category, key);
     conn.execute(
         "INSERT INTO local_settings (setting_category, setting_key, setting_value, updated_at)
          VALUES (?1, ?2, ?3, datetime('now'))
@@ -4982,9 +7132,144 @@ pub fn set_setting(
         params![category, key, value],
     )
     .map_err(|e| format!("set_setting: {e}"))?;
+    if previous.as_deref() != Some(value) {
+        record_setting_change(
+            conn,
+            category,
+            key,
+            previous.as_deref(),
+            Some(value),
+            source,
+            actor_staff_id,
+        )?;
+    }
+    Ok(())
+}
+
+/// Default cap for `settings_history`; override with
+/// `system.settings_history_max_rows`.
+pub const DEFAULT_SETTINGS_HISTORY_MAX_ROWS: i64 = 5000;
+
+/// Keys whose values must never be stored in clear in the history.
+fn is_secret_setting_key(key: &str) -> bool {
+    crate::terminal_helpers::is_sensitive_terminal_setting(key)
+        || key.to_ascii_lowercase().contains("password")
+}
+
+/// Append one entry to `settings_history` and prune the oldest rows beyond
+/// the configured cap. Secret-bearing keys are stored masked. Databases that
+/// predate v74 (migrations seeding settings) are skipped silently.
+pub fn record_setting_change(
+    conn: &Connection,
+    category: &str,
+    key: &str,
+    old_value: Option<&str>,
+    new_value: Option<&str>,
+    source: &str,
+    actor_staff_id: Option<&str>,
+) -> Result<(), String> {
+    let masked = is_secret_setting_key(key);
+    let mask = |value: Option<&str>| {
+        value.map(|v| {
+            if masked {
+                crate::api::redact(v)
+            } else {
+                v.to_string()
+            }
+        })
+    };
+    let inserted = conn.execute(
+        "INSERT INTO settings_history
+            (setting_category, setting_key, old_value, new_value, masked,
+             actor_staff_id, source, changed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))",
+        params![
+            category,
+            key,
+            mask(old_value),
+            mask(new_value),
+            masked as i32,
+            actor_staff_id,
+            source,
+        ],
+    );
+    match inserted {
+        Ok(_) => {}
+        Err(e) if e.to_string().contains("no such table") => return Ok(()),
+        Err(e) => return Err(format!("record setting change: {e}")),
+    }
+    let max_rows = get_setting(conn, "system", "settings_history_max_rows")
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_SETTINGS_HISTORY_MAX_ROWS);
+    conn.execute(
+        "DELETE FROM settings_history
+         WHERE id <= (SELECT id FROM settings_history ORDER BY id DESC LIMIT 1 OFFSET ?1)",
+        params![max_rows],
+    )
+    .map_err(|e| format!("prune settings history: {e}"))?;
     Ok(())
 }
 
+fn settings_history_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<serde_json::Value> {
+    Ok(serde_json::json!({
+        "id": row.get::<_, i64>(0)?,
+        "category": row.get::<_, String>(1)?,
+        "key": row.get::<_, String>(2)?,
+        "oldValue": row.get::<_, Option<String>>(3)?,
+        "newValue": row.get::<_, Option<String>>(4)?,
+        "masked": row.get::<_, i64>(5)? != 0,
+        "actorStaffId": row.get::<_, Option<String>>(6)?,
+        "source": row.get::<_, String>(7)?,
+        "changedAt": row.get::<_, String>(8)?,
+    }))
+}
+
+/// Page through settings history, newest first, optionally filtered by
+/// category and key. Returns `(entries, total)`.
+pub fn list_settings_history(
+    conn: &Connection,
+    category: Option<&str>,
+    key: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<serde_json::Value>, i64), String> {
+    let filter = "(?1 IS NULL OR setting_category = ?1) AND (?2 IS NULL OR setting_key = ?2)";
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM settings_history WHERE {filter}"),
+            params![category, key],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("count settings history: {e}"))?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, setting_category, setting_key, old_value, new_value, masked,
+                    actor_staff_id, source, changed_at
+             FROM settings_history WHERE {filter}
+             ORDER BY id DESC LIMIT ?3 OFFSET ?4"
+        ))
+        .map_err(|e| format!("read settings history: {e}"))?;
+    let entries = stmt
+        .query_map(params![category, key, limit, offset], settings_history_row)
+        .map_err(|e| format!("read settings history: {e}"))?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("read settings history: {e}"))?;
+    Ok((entries, total))
+}
+
+/// Load one settings history entry by id.
+pub fn get_settings_history_entry(conn: &Connection, id: i64) -> Option<serde_json::Value> {
+    conn.query_row(
+        "SELECT id, setting_category, setting_key, old_value, new_value, masked,
+                actor_staff_id, source, changed_at
+         FROM settings_history WHERE id = ?1",
+        params![id],
+        settings_history_row,
+    )
+    .ok()
+}
+
 pub fn upsert_caller_id_log(
     conn: &Connection,
     caller_number: &str,
@@ -5307,6 +7592,104 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn test_read_pool_does_not_wait_on_writer() {
+        use std::sync::mpsc;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let dir = std::env::temp_dir().join(format!(
+            "pos_tauri_test_read_pool_{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let db = Arc::new(init(&dir).expect("init file db"));
+        db.write(|conn| {
+            conn.execute_batch(
+                "CREATE TABLE pool_probe (n INTEGER NOT NULL);
+                 INSERT INTO pool_probe (n) VALUES (1);",
+            )
+            .map_err(|e| e.to_string())
+        })
+        .expect("seed probe table");
+
+        // Slow read: hold a snapshot open until the writer says it is done.
+        let (started_tx, started_rx) = mpsc::channel();
+        let (writes_done_tx, writes_done_rx) = mpsc::channel::<()>();
+        let reader_db = Arc::clone(&db);
+        let slow_read = std::thread::spawn(move || {
+            reader_db.read(|conn| {
+                let count = |conn: &Connection| {
+                    conn.query_row("SELECT COUNT(*) FROM pool_probe", [], |row| {
+                        row.get::<_, i64>(0)
+                    })
+                    .map_err(|e| e.to_string())
+                };
+                conn.execute_batch("BEGIN").map_err(|e| e.to_string())?;
+                let before = count(conn)?;
+                started_tx.send(()).map_err(|e| e.to_string())?;
+                writes_done_rx
+                    .recv_timeout(Duration::from_secs(10))
+                    .map_err(|e| format!("writer never finished: {e}"))?;
+                let after = count(conn)?;
+                conn.execute_batch("COMMIT").map_err(|e| e.to_string())?;
+                Ok((before, after))
+            })
+        });
+
+        started_rx
+            .recv_timeout(Duration::from_secs(10))
+            .expect("slow read started");
+        for n in 2..=5 {
+            db.write(|conn| {
+                conn.execute("INSERT INTO pool_probe (n) VALUES (?1)", params![n])
+                    .map_err(|e| e.to_string())
+            })
+            .expect("write proceeds during slow read");
+        }
+
+        // A fresh read completes while the write mutex is held.
+        let held = db.conn.lock().expect("hold write lock");
+        let (fresh_tx, fresh_rx) = mpsc::channel();
+        let fresh_db = Arc::clone(&db);
+        std::thread::spawn(move || {
+            let _ = fresh_tx.send(fresh_db.read(|conn| {
+                conn.query_row("SELECT COUNT(*) FROM pool_probe", [], |row| {
+                    row.get::<_, i64>(0)
+                })
+                .map_err(|e| e.to_string())
+            }));
+        });
+        let fresh = fresh_rx
+            .recv_timeout(Duration::from_secs(10))
+            .expect("read must not wait on the write mutex")
+            .expect("fresh read");
+        assert_eq!(fresh, 5, "pooled reads see committed writes");
+        drop(held);
+
+        writes_done_tx.send(()).expect("signal writes done");
+        let (before, after) = slow_read
+            .join()
+            .expect("slow read thread")
+            .expect("slow read");
+        assert_eq!(
+            (before, after),
+            (1, 1),
+            "slow read keeps its snapshot while writes commit"
+        );
+
+        let rejected = db.read(|conn| {
+            conn.execute("INSERT INTO pool_probe (n) VALUES (6)", [])
+                .map_err(|e| e.to_string())
+        });
+        assert!(rejected.is_err(), "pooled connections are read-only");
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_migrate_v55_drops_payment_method_column() {
         let conn = test_db();
@@ -5707,6 +8090,91 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_every_historical_version_migrates_to_head() {
+        for version in 1..CURRENT_SCHEMA_VERSION {
+            let conn = test_db();
+            run_migrations_to(&conn, version)
+                .unwrap_or_else(|e| panic!("build v{version} database: {e}"));
+            assert_eq!(current_schema_version(&conn), version);
+
+            let status = migration_status(&conn).expect("status");
+            assert_eq!(status["currentVersion"], version);
+            assert_eq!(
+                status["pending"].as_array().map(Vec::len),
+                Some((CURRENT_SCHEMA_VERSION - version) as usize),
+                "v{version} pending list"
+            );
+
+            run_migrations(&conn).unwrap_or_else(|e| panic!("migrate v{version} to head: {e}"));
+            assert_eq!(current_schema_version(&conn), CURRENT_SCHEMA_VERSION);
+            assert_eq!(migration_status(&conn).expect("status")["upToDate"], true);
+
+            for sql in [
+                "SELECT id, status, total_amount, total_amount_cents, version, items,
+                        kitchen_notes, allergy_flags, sync_status
+                 FROM orders",
+                "SELECT id, order_id, amount, amount_cents, status FROM order_payments",
+                "SELECT id, staff_id, role_type, status FROM staff_shifts",
+                "SELECT id, cashier_shift_id, amount, amount_cents, idempotency_key
+                 FROM staff_payments",
+                "SELECT id, status, claim_generation FROM parity_sync_queue",
+                "SELECT setting_category, setting_key, setting_value FROM local_settings",
+            ] {
+                conn.prepare(sql)
+                    .unwrap_or_else(|e| panic!("from v{version}: {sql}: {e}"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_v106_adopts_staff_payments_created_on_first_use() {
+        let conn = test_db();
+        run_migrations_to(&conn, 105).expect("build v105 database");
+        conn.execute_batch(
+            "CREATE TABLE staff_payments (
+                id TEXT PRIMARY KEY,
+                cashier_shift_id TEXT NOT NULL,
+                paid_to_staff_id TEXT NOT NULL,
+                amount REAL NOT NULL,
+                payment_type TEXT NOT NULL DEFAULT 'wage',
+                notes TEXT,
+                created_at TEXT NOT NULL
+            );
+            INSERT INTO staff_payments (id, cashier_shift_id, paid_to_staff_id, amount, created_at)
+            VALUES ('sp-1', 'shift-1', 'staff-1', 12.5, '2026-10-01T09:00:00Z');",
+        )
+        .expect("seed legacy staff_payments");
+
+        run_migrations(&conn).expect("migrate to head");
+
+        let (cents, updated_at, key): (i64, String, Option<String>) = conn
+            .query_row(
+                "SELECT amount_cents, updated_at, idempotency_key FROM staff_payments WHERE id = 'sp-1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .expect("legacy row kept");
+        assert_eq!(cents, 1250);
+        assert_eq!(updated_at, "2026-10-01T09:00:00Z");
+        assert!(key.is_some());
+
+        conn.execute(
+            "INSERT INTO staff_payments (id, cashier_shift_id, paid_to_staff_id, amount, created_at)
+             VALUES ('sp-2', 'shift-1', 'staff-2', 5.0, '2026-10-01T10:00:00Z')",
+            [],
+        )
+        .expect("insert after migration");
+        let key: Option<String> = conn
+            .query_row(
+                "SELECT idempotency_key FROM staff_payments WHERE id = 'sp-2'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(key.is_some(), "v106 trigger stamps new rows");
+    }
+
     #[test]
     fn test_migrations_repair_missing_v56_after_later_versions_applied() {
         let conn = test_db();
@@ -5956,6 +8424,15 @@ mod tests {
         )
         .expect("insert shift checkout print job");
 
+        conn.execute(
+            "INSERT INTO print_jobs (id, entity_type, entity_id, status, reprint_count,
+                                     created_at, updated_at)
+             VALUES ('pj-deferred', 'order_receipt', 'ord-2', 'deferred', 1,
+                     datetime('now'), datetime('now'))",
+            [],
+        )
+        .expect("insert deferred print job");
+
         // Verify CHECK constraint rejects invalid status
         let bad = conn.execute(
             "INSERT INTO print_jobs (id, entity_type, entity_id, status, created_at, updated_at)
@@ -6901,6 +9378,63 @@ mod tests {
         assert!(val.is_none());
     }
 
+    #[test]
+    fn settings_history_records_changes_masks_secrets_and_prunes() {
+        let conn = test_db();
+        run_migrations(&conn).expect("migrations");
+
+        set_setting_with_source(
+            &conn,
+            "general",
+            "tax_rate",
+            "24",
+            "settings_set",
+            Some("s1"),
+        )
+        .expect("set");
+        // Rewriting the same value is not a change.
+        set_setting(&conn, "general", "tax_rate", "24").expect("same value");
+        set_setting_with_source(
+            &conn,
+            "general",
+            "tax_rate",
+            "13",
+            "settings_set",
+            Some("s2"),
+        )
+        .expect("update");
+        set_setting(&conn, "terminal", "pos_api_key", "sk-live-abcdef123456").expect("secret");
+
+        let (entries, total) =
+            list_settings_history(&conn, Some("general"), Some("tax_rate"), 10, 0).expect("list");
+        assert_eq!(total, 2);
+        assert_eq!(entries[0]["oldValue"], "24");
+        assert_eq!(entries[0]["newValue"], "13");
+        assert_eq!(entries[0]["actorStaffId"], "s2");
+        assert_eq!(entries[0]["source"], "settings_set");
+        assert!(entries[1]["oldValue"].is_null());
+
+        let (secret, _) =
+            list_settings_history(&conn, Some("terminal"), Some("pos_api_key"), 10, 0)
+                .expect("list secret");
+        assert_eq!(secret[0]["newValue"], "...3456");
+        assert_eq!(secret[0]["masked"], true);
+
+        delete_setting(&conn, "general", "tax_rate").expect("delete");
+        let (entries, _) = list_settings_history(&conn, None, None, 1, 0).expect("latest");
+        assert_eq!(entries[0]["oldValue"], "13");
+        assert!(entries[0]["newValue"].is_null());
+
+        set_setting(&conn, "system", "settings_history_max_rows", "3").expect("cap");
+        for i in 0..5 {
+            set_setting(&conn, "ui", "theme", &format!("t{i}")).expect("churn");
+        }
+        let (entries, total) = list_settings_history(&conn, None, None, 10, 0).expect("pruned");
+        assert_eq!(total, 3);
+        assert_eq!(entries[0]["newValue"], "t4");
+        assert_eq!(entries[2]["newValue"], "t2");
+    }
+
     // ----------------------------------------------------------------------
     // Wave 4a — migration v51: *_cents shadow columns
     // ----------------------------------------------------------------------
@@ -7391,4 +9925,161 @@ mod tests {
             "after the helper returns, synchronous must be NORMAL (1); got {observed_after}"
         );
     }
+
+    /// `EXPLAIN QUERY PLAN` detail lines for `sql`.
+    fn query_plan(conn: &Connection, sql: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(&format!("EXPLAIN QUERY PLAN {sql}"))
+            .expect("prepare query plan");
+        // `raw_query` leaves the `?N` placeholders unbound (NULL), which
+        // is all the planner needs.
+        let mut rows = stmt.raw_query();
+        let mut plan = Vec::new();
+        while let Some(row) = rows.next().expect("query plan row") {
+            plan.push(row.get::<_, String>(3).expect("query plan detail"));
+        }
+        plan
+    }
+
+    #[test]
+    fn hot_order_queries_search_indexes_instead_of_scanning() {
+        let conn = test_db();
+        run_migrations(&conn).expect("migrations");
+
+        let cases = [
+            ("SELECT id FROM orders WHERE id = ?1 LIMIT 1", "orders"),
+            (
+                "SELECT id FROM orders WHERE supabase_id = ?1 LIMIT 1",
+                "idx_orders_supabase_id",
+            ),
+            (
+                "UPDATE orders SET status = ?1, sync_status = 'pending', updated_at = ?2
+                 WHERE id = ?3",
+                "orders",
+            ),
+            (
+                "SELECT id FROM orders WHERE status = ?1 ORDER BY created_at DESC LIMIT 50",
+                "idx_orders_status_created_at",
+            ),
+            (
+                "SELECT id FROM order_payments WHERE order_id = ?1",
+                "idx_order_payments",
+            ),
+            (
+                "SELECT COUNT(*) FROM parity_sync_queue
+                 WHERE table_name = ?1 AND status = 'pending'",
+                "parity_sync_queue",
+            ),
+        ];
+        for (sql, expected) in cases {
+            let plan = query_plan(&conn, sql);
+            assert!(
+                plan.iter().all(|line| !line.starts_with("SCAN")),
+                "{sql} scans a table: {plan:?}"
+            );
+            assert!(
+                plan.iter()
+                    .any(|line| line.starts_with("SEARCH") && line.contains(expected)),
+                "{sql} does not search {expected}: {plan:?}"
+            );
+        }
+    }
+
+    /// Hot-path bench — 1,000 order creates and 1,000 status updates, each
+    /// with its sync-queue row, through the old statements (`execute`,
+    /// `id OR supabase_id` lookup) and through the cached, split ones.
+    ///
+    ///     cargo test --release --lib hot_order_path_bench -- --ignored --nocapture
+    ///
+    /// Uses a tempfile WAL database like the H32 bench above, one
+    /// transaction per write, and prints both timings.
+    #[test]
+    #[ignore = "bench harness — run with `cargo test --release hot_order_path_bench -- --ignored --nocapture`"]
+    fn hot_order_path_bench_before_after() {
+        use std::time::{Duration, Instant};
+
+        const ORDERS: usize = 1000;
+
+        fn run(conn: &Connection, prefix: &str, cached: bool) -> (Duration, Duration) {
+            let enqueue = |order_id: &str, op: &str| {
+                let sql = "INSERT INTO parity_sync_queue
+                    (id, table_name, record_id, operation, data, organization_id,
+                     created_at, attempts, retry_delay_ms, priority, module_type,
+                     conflict_strategy, version, status)
+                 VALUES (?1, 'orders', ?2, ?3, '{}', 'org-bench', datetime('now'), 0,
+                         1000, 0, 'orders', 'server-wins', 1, 'pending')";
+                let params = rusqlite::params![uuid::Uuid::new_v4().to_string(), order_id, op];
+                if cached {
+                    conn.prepare_cached(sql).unwrap().execute(params).unwrap();
+                } else {
+                    conn.execute(sql, params).unwrap();
+                }
+            };
+
+            let started = Instant::now();
+            for i in 0..ORDERS {
+                let id = format!("{prefix}-{i}");
+                conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+                let sql = "INSERT INTO orders (id, items, total_amount, status, sync_status,
+                                               created_at, updated_at)
+                           VALUES (?1, '[]', 10.0, 'pending', 'pending',
+                                   datetime('now'), datetime('now'))";
+                if cached {
+                    conn.prepare_cached(sql).unwrap().execute([&id]).unwrap();
+                } else {
+                    conn.execute(sql, [&id]).unwrap();
+                }
+                enqueue(&id, "INSERT");
+                conn.execute_batch("COMMIT").unwrap();
+            }
+            let create = started.elapsed();
+
+            let started = Instant::now();
+            for i in 0..ORDERS {
+                let raw = format!("{prefix}-{i}");
+                conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+                let id = if cached {
+                    crate::resolve_order_id(conn, &raw).unwrap()
+                } else {
+                    conn.query_row(
+                        "SELECT id FROM orders WHERE id = ?1 OR supabase_id = ?1 LIMIT 1",
+                        [&raw],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .unwrap()
+                };
+                let sql = "UPDATE orders SET status = 'preparing', sync_status = 'pending',
+                                             updated_at = datetime('now')
+                           WHERE id = ?1";
+                if cached {
+                    conn.prepare_cached(sql).unwrap().execute([&id]).unwrap();
+                } else {
+                    conn.execute(sql, [&id]).unwrap();
+                }
+                enqueue(&id, "UPDATE");
+                conn.execute_batch("COMMIT").unwrap();
+            }
+            (create, started.elapsed())
+        }
+
+        let dir = std::env::temp_dir().join(format!(
+            "pos_hot_path_bench_{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).expect("create bench tempdir");
+        let conn = open_and_configure(&dir.join("bench.db")).expect("open bench db");
+        run_migrations(&conn).expect("run migrations on bench db");
+
+        let (before_create, before_update) = run(&conn, "before", false);
+        let (after_create, after_update) = run(&conn, "after", true);
+        eprintln!("hot order path bench ({ORDERS} orders):");
+        eprintln!("  create  before {before_create:?}  after {after_create:?}");
+        eprintln!("  update  before {before_update:?}  after {after_update:?}");
+
+        drop(conn);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,363 @@
+//! Two-step confirmation for commands that irreversibly delete data.
+//!
+//! `destructive_op_prepare` reports what an operation would lose (unsynced
+//! orders, pending payments, open shifts) and issues a one-time confirmation
+//! token valid for [`TOKEN_TTL_SECS`]. `orders_clear_all`,
+//! `sync_clear_all_orders` and `settings_factory_reset` refuse to run
+//! without a token issued for that same operation.
+//!
+//! Before deleting, each operation exports the affected tables in the
+//! retention archive format. Clear-all archives go to `<app_data>/archives/`;
+//! a factory reset wipes that directory, so its archive goes under the
+//! recovery root, which the reset preserves. `skip_backup: true` bypasses
+//! the export. Every run, with or without a backup, is recorded in
+//! `recovery_action_log` with the acting staff member.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use chrono::{DateTime, Duration, Local, Utc};
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::db::DbState;
+use crate::retention;
+
+pub const TOKEN_TTL_SECS: i64 = 120;
+const AUDIT_ACTION_ID: &str = "destructive_op";
+const RESET_ARCHIVES_DIR: &str = "reset_archives";
+
+/// Operational tables exported before a factory reset, on top of orders and
+/// their payment rows.
+const RESET_EXTRA_TABLES: &[&str] = &[
+    "staff_shifts",
+    "cash_drawer_sessions",
+    "shift_expenses",
+    "drawer_transactions",
+    "z_reports",
+    "sync_queue",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DestructiveOp {
+    OrdersClearAll,
+    SyncClearAllOrders,
+    FactoryReset,
+}
+
+impl DestructiveOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::OrdersClearAll => "orders_clear_all",
+            Self::SyncClearAllOrders => "sync_clear_all_orders",
+            Self::FactoryReset => "settings_factory_reset",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "orders_clear_all" => Some(Self::OrdersClearAll),
+            "sync_clear_all_orders" => Some(Self::SyncClearAllOrders),
+            "settings_factory_reset" | "factory_reset" => Some(Self::FactoryReset),
+            _ => None,
+        }
+    }
+
+    fn archived_tables(self) -> Vec<&'static str> {
+        let mut tables = vec!["orders"];
+        tables.extend_from_slice(retention::ORDER_CASCADED_TABLES);
+        if self == Self::FactoryReset {
+            tables.extend_from_slice(RESET_EXTRA_TABLES);
+        }
+        tables
+    }
+}
+
+/// What an operation would destroy.
+pub fn impact(conn: &Connection) -> Value {
+    let count = |sql: &str| -> i64 { conn.query_row(sql, [], |row| row.get(0)).unwrap_or(0) };
+    json!({
+        "orders": count("SELECT COUNT(*) FROM orders"),
+        "unsyncedOrders": count(
+            "SELECT COUNT(*) FROM orders WHERE COALESCE(sync_status, 'pending') != 'synced'"
+        ),
+        "pendingPayments": count(
+            "SELECT COUNT(*) FROM order_payments WHERE COALESCE(sync_state, 'pending') != 'applied'"
+        ),
+        "openShifts": count("SELECT COUNT(*) FROM staff_shifts WHERE status = 'active'"),
+        "pendingSyncItems": count(
+            "SELECT COUNT(*) FROM sync_queue WHERE status IN ('pending', 'in_progress', 'queued_remote')"
+        ),
+    })
+}
+
+struct PendingToken {
+    op: DestructiveOp,
+    expires_at: DateTime<Utc>,
+}
+
+fn tokens() -> &'static Mutex<HashMap<String, PendingToken>> {
+    static TOKENS: OnceLock<Mutex<HashMap<String, PendingToken>>> = OnceLock::new();
+    TOKENS.get_or_init(Default::default)
+}
+
+/// Issue a one-time confirmation token for `op`. Returns the token and its
+/// expiry.
+pub fn issue_token(op: DestructiveOp, now: DateTime<Utc>) -> Result<(String, String), String> {
+    let token = Uuid::new_v4().simple().to_string();
+    let expires_at = now + Duration::seconds(TOKEN_TTL_SECS);
+    let mut pending = tokens().lock().map_err(|e| e.to_string())?;
+    pending.retain(|_, entry| entry.expires_at > now);
+    pending.insert(token.clone(), PendingToken { op, expires_at });
+    Ok((token, expires_at.to_rfc3339()))
+}
+
+/// Use up `token` for `op`. A token works once, only for the operation it
+/// was issued for, and only before it expires.
+pub fn consume_token(
+    op: DestructiveOp,
+    token: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    let token = token
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| {
+            format!(
+                "{} requires a confirmation token from destructive_op_prepare",
+                op.as_str()
+            )
+        })?;
+    let mut pending = tokens().lock().map_err(|e| e.to_string())?;
+    match pending.get(token) {
+        Some(entry) if entry.op != op => {
+            return Err(format!(
+                "Confirmation token was issued for {}, not {}",
+                entry.op.as_str(),
+                op.as_str()
+            ))
+        }
+        Some(entry) if entry.expires_at <= now => {
+            pending.remove(token);
+            return Err("Confirmation token has expired; prepare the operation again".into());
+        }
+        Some(_) => {}
+        None => return Err("Unknown or already used confirmation token".into()),
+    }
+    pending.remove(token);
+    Ok(())
+}
+
+/// Confirmation fields accepted by the destructive commands.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Confirmation {
+    pub token: Option<String>,
+    pub skip_backup: bool,
+}
+
+pub fn parse_confirmation(arg0: Option<&Value>) -> Confirmation {
+    match arg0 {
+        Some(Value::String(token)) => Confirmation {
+            token: Some(token.clone()),
+            skip_backup: false,
+        },
+        Some(payload @ Value::Object(_)) => Confirmation {
+            token: crate::value_str(
+                payload,
+                &["confirmationToken", "confirmation_token", "token"],
+            ),
+            skip_backup: payload
+                .get("skipBackup")
+                .or_else(|| payload.get("skip_backup"))
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        },
+        _ => Confirmation::default(),
+    }
+}
+
+fn archive_dir(db: &DbState, op: DestructiveOp) -> Result<PathBuf, String> {
+    match op {
+        DestructiveOp::FactoryReset => {
+            Ok(crate::recovery::recovery_root_for_db(db).join(RESET_ARCHIVES_DIR))
+        }
+        DestructiveOp::OrdersClearAll | DestructiveOp::SyncClearAllOrders => {
+            retention::archives_dir(db)
+        }
+    }
+}
+
+/// Export what `op` is about to delete. Returns the archive files.
+pub fn archive_before(db: &DbState, op: DestructiveOp) -> Result<Vec<String>, String> {
+    let dir = archive_dir(db, op)?;
+    let date = Local::now().format("%Y-%m-%d").to_string();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    retention::export_tables(&conn, &dir, &op.archived_tables(), &date)
+}
+
+/// Record a destructive operation in `recovery_action_log`.
+pub fn record(
+    conn: &Connection,
+    op: DestructiveOp,
+    actor_staff_id: Option<&str>,
+    archive_files: &[String],
+    skip_backup: bool,
+    impact: &Value,
+) -> Result<(), String> {
+    let message = if skip_backup {
+        format!(
+            "{} ran with skip_backup; no archive was written",
+            op.as_str()
+        )
+    } else {
+        format!(
+            "{} archived {} file(s) before deleting",
+            op.as_str(),
+            archive_files.len()
+        )
+    };
+    let payload = json!({
+        "operation": op.as_str(),
+        "skipBackup": skip_backup,
+        "archiveFiles": archive_files,
+        "impact": impact,
+    });
+    conn.execute(
+        "INSERT INTO recovery_action_log (
+            id, action_id, issue_code, export_path, success, message,
+            actor_staff_id, payload_json, created_at
+         ) VALUES (?1, ?2, ?3, ?4, 1, ?5, ?6, ?7, ?8)",
+        params![
+            Uuid::new_v4().to_string(),
+            AUDIT_ACTION_ID,
+            op.as_str(),
+            archive_files.first(),
+            message,
+            actor_staff_id,
+            payload.to_string(),
+            Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(|e| format!("record {} in audit log: {e}", op.as_str()))?;
+    Ok(())
+}
+
+/// Second step shared by the destructive commands: check the token, archive
+/// unless told to skip, and write the audit entry. Returns the archive files.
+pub fn confirm(
+    db: &DbState,
+    op: DestructiveOp,
+    arg0: Option<&Value>,
+    actor_staff_id: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let confirmation = parse_confirmation(arg0);
+    consume_token(op, confirmation.token.as_deref(), Utc::now())?;
+    let files = if confirmation.skip_backup {
+        tracing::warn!(
+            operation = op.as_str(),
+            "Destructive operation skipping archive backup"
+        );
+        Vec::new()
+    } else {
+        archive_before(db, op)?
+    };
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    record(
+        &conn,
+        op,
+        actor_staff_id,
+        &files,
+        confirmation.skip_backup,
+        &impact(&conn),
+    )?;
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    #[test]
+    fn tokens_are_single_use_bound_to_their_operation_and_expire() {
+        let now = Utc::now();
+        assert!(consume_token(DestructiveOp::FactoryReset, None, now)
+            .unwrap_err()
+            .contains("destructive_op_prepare"));
+
+        let (token, _) = issue_token(DestructiveOp::OrdersClearAll, now).unwrap();
+        let err = consume_token(DestructiveOp::FactoryReset, Some(&token), now).unwrap_err();
+        assert!(err.contains("issued for orders_clear_all"), "{err}");
+        consume_token(DestructiveOp::OrdersClearAll, Some(&token), now).unwrap();
+        assert!(consume_token(DestructiveOp::OrdersClearAll, Some(&token), now).is_err());
+
+        let (token, _) = issue_token(DestructiveOp::SyncClearAllOrders, now).unwrap();
+        let later = now + Duration::seconds(TOKEN_TTL_SECS + 1);
+        let err =
+            consume_token(DestructiveOp::SyncClearAllOrders, Some(&token), later).unwrap_err();
+        assert!(err.contains("expired"), "{err}");
+    }
+
+    #[test]
+    fn impact_counts_unsynced_work_and_record_writes_audit_entry() {
+        let conn = test_conn();
+        conn.execute_batch(
+            "INSERT INTO orders (id, items, total_amount, status, order_type, sync_status, created_at, updated_at)
+             VALUES ('ord-synced', '[]', 10.0, 'completed', 'takeaway', 'synced', datetime('now'), datetime('now')),
+                    ('ord-local', '[]', 12.0, 'completed', 'takeaway', 'pending', datetime('now'), datetime('now'));
+             INSERT INTO order_payments (id, order_id, method, amount, status, created_at, updated_at)
+             VALUES ('pay-local', 'ord-local', 'cash', 12.0, 'completed', datetime('now'), datetime('now'));
+             INSERT INTO staff_shifts (id, staff_id, role_type, check_in_time, status, created_at, updated_at)
+             VALUES ('shift-1', 'staff-1', 'cashier', datetime('now'), 'active', datetime('now'), datetime('now'));",
+        )
+        .unwrap();
+
+        let summary = impact(&conn);
+        assert_eq!(summary["orders"], 2);
+        assert_eq!(summary["unsyncedOrders"], 1);
+        assert_eq!(summary["pendingPayments"], 1);
+        assert_eq!(summary["openShifts"], 1);
+
+        record(
+            &conn,
+            DestructiveOp::OrdersClearAll,
+            Some("admin-1"),
+            &[],
+            true,
+            &summary,
+        )
+        .unwrap();
+        let (issue_code, actor, message): (String, String, String) = conn
+            .query_row(
+                "SELECT issue_code, actor_staff_id, message FROM recovery_action_log
+                 WHERE action_id = 'destructive_op'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(issue_code, "orders_clear_all");
+        assert_eq!(actor, "admin-1");
+        assert!(message.contains("skip_backup"), "{message}");
+    }
+
+    #[test]
+    fn parse_confirmation_reads_token_and_skip_backup() {
+        assert_eq!(
+            parse_confirmation(Some(
+                &json!({ "confirmationToken": "abc", "skip_backup": true })
+            )),
+            Confirmation {
+                token: Some("abc".into()),
+                skip_backup: true,
+            }
+        );
+        assert_eq!(parse_confirmation(None), Confirmation::default());
+    }
+}
@@ -5,6 +5,12 @@
 //! - **System health**: online/offline, sync backlog, printer status, last z-report
 //! - **Diagnostics export**: packages logs, DB schema version, sync counts,
 //!   last 20 sync errors, and printer profiles into a zip bundle.
+//! - **Health check**: per-subsystem ok/warn/error entries with a worst-of
+//!   rollup, backing the `system_health_check` command.
+//! - **Support bundle**: always-redacted zip (settings, queue summary, DB
+//!   integrity, log tail) for attaching to support tickets.
+//! - **Recent errors**: WARN/ERROR ring buffer fed by a tracing layer.
+//! - **Metrics**: per-command latency and error counters (see [`metrics`]).
 //! - **Log rotation helpers**: used by `lib.rs` to configure rolling log files.
 
 use crate::db::DbState;
@@ -12,11 +18,15 @@ use crate::sync::normalize_optional_uuid_str;
 use crate::sync::SyncBlockerDetail;
 use rusqlite::{params, OptionalExtension};
 use serde_json::{json, Value};
+use std::collections::VecDeque;
 use std::fs;
-use std::io::{Read as _, Write as _};
+use std::io::{Read as _, Seek as _, SeekFrom, Write as _};
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 use tracing::warn;
 
+pub mod metrics;
+
 // ---------------------------------------------------------------------------
 // Constants
 // ---------------------------------------------------------------------------
@@ -125,6 +135,7 @@ pub fn get_system_health(db: &DbState) -> Result<Value, String> {
             .map(|value| value.to_string());
         printer_status["defaultProfile"] = json!(display_name);
     }
+    printer_status["hardwareStatus"] = crate::printer_watchdog::snapshot_json();
 
     // Validate pending orders against menu cache (acquires its own lock)
     let invalid_orders = crate::sync::validate_pending_orders(db)
@@ -667,11 +678,20 @@ fn get_printer_status(conn: &rusqlite::Connection) -> Value {
         }
     }
 
+    let deferred_jobs: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM print_jobs WHERE status = 'deferred'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+
     json!({
         "configured": profile_count > 0,
         "profileCount": profile_count,
         "defaultProfile": serde_json::Value::Null,
         "recentJobs": recent_jobs,
+        "deferredJobs": deferred_jobs,
     })
 }
 
@@ -996,6 +1016,26 @@ fn redact_sensitive_fields(value: Value) -> Value {
     }
 }
 
+/// Redact the recorded fields of a tracing event: the message like a log
+/// line, every other field by key.
+pub(crate) fn redact_event_fields(fields: Value) -> Value {
+    let Value::Object(mut map) = fields else {
+        return redact_sensitive_fields(fields);
+    };
+    let message = map.remove("message");
+    let mut redacted = match redact_sensitive_fields(Value::Object(map)) {
+        Value::Object(map) => map,
+        _ => serde_json::Map::new(),
+    };
+    if let Some(Value::String(message)) = message {
+        redacted.insert(
+            "message".into(),
+            Value::String(redact_log_line(&message, &[])),
+        );
+    }
+    Value::Object(redacted)
+}
+
 fn should_redact_key(key: &str) -> bool {
     let normalized = key.to_ascii_lowercase();
     let non_secret_presence_markers = ["hasapikey", "hasadminurl"];
@@ -1143,6 +1183,843 @@ fn get_printer_diagnostics(conn: &rusqlite::Connection) -> Value {
     })
 }
 
+// ---------------------------------------------------------------------------
+// Structured health check
+// ---------------------------------------------------------------------------
+
+/// How long expensive sub-checks (integrity check, connectivity probe) are
+/// reused before being recomputed.
+pub const HEALTH_CHECK_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Database files above this size raise a `warn`.
+const HEALTH_DB_SIZE_WARN_BYTES: u64 = 512 * 1024 * 1024;
+/// Pending sync rows above this count raise a `warn`.
+const HEALTH_SYNC_PENDING_WARN: i64 = 100;
+
+static HEALTH_CHECK_CACHE: OnceLock<
+    Mutex<std::collections::HashMap<&'static str, (std::time::Instant, Value)>>,
+> = OnceLock::new();
+
+fn health_check_cache(
+) -> &'static Mutex<std::collections::HashMap<&'static str, (std::time::Instant, Value)>> {
+    HEALTH_CHECK_CACHE.get_or_init(|| Mutex::new(std::collections::HashMap::new()))
+}
+
+/// Cached sub-check result if it is younger than `HEALTH_CHECK_CACHE_TTL`.
+pub fn health_cache_get(key: &'static str) -> Option<Value> {
+    let cache = health_check_cache().lock().ok()?;
+    cache
+        .get(key)
+        .filter(|(at, _)| at.elapsed() < HEALTH_CHECK_CACHE_TTL)
+        .map(|(_, value)| value.clone())
+}
+
+pub fn health_cache_put(key: &'static str, value: Value) {
+    if let Ok(mut cache) = health_check_cache().lock() {
+        cache.insert(key, (std::time::Instant::now(), value));
+    }
+}
+
+/// Severity of one subsystem entry; ordered so `max` gives the worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HealthStatus {
+    Ok,
+    Warn,
+    Error,
+}
+
+impl HealthStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        }
+    }
+
+    fn parse(value: &Value) -> Self {
+        match value.get("status").and_then(Value::as_str) {
+            Some("error") => Self::Error,
+            Some("warn") => Self::Warn,
+            _ => Self::Ok,
+        }
+    }
+}
+
+/// Build a subsystem entry: `status`, `detail`, plus subsystem fields.
+pub fn health_entry(status: HealthStatus, detail: impl Into<String>, extra: Value) -> Value {
+    let mut entry = match extra {
+        Value::Object(map) => map,
+        _ => serde_json::Map::new(),
+    };
+    entry.insert("status".into(), json!(status.as_str()));
+    entry.insert("detail".into(), json!(detail.into()));
+    Value::Object(entry)
+}
+
+/// Worst-of rollup across subsystem entries.
+pub fn rollup_health_status<'a>(entries: impl IntoIterator<Item = &'a Value>) -> HealthStatus {
+    entries
+        .into_iter()
+        .map(HealthStatus::parse)
+        .max()
+        .unwrap_or(HealthStatus::Ok)
+}
+
+fn database_file_size(db_path: &Path) -> u64 {
+    let wal = PathBuf::from(format!("{}-wal", db_path.display()));
+    [db_path.to_path_buf(), wal]
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+pub fn check_database_health(db: &DbState) -> Value {
+    let size_bytes = database_file_size(&db.db_path);
+    let conn = match db.conn.lock() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return health_entry(
+                HealthStatus::Error,
+                format!("Database lock poisoned: {e}"),
+                json!({ "reachable": false, "sizeBytes": size_bytes }),
+            )
+        }
+    };
+    if let Err(e) = conn.query_row("SELECT 1", [], |row| row.get::<_, i64>(0)) {
+        return health_entry(
+            HealthStatus::Error,
+            format!("Database query failed: {e}"),
+            json!({ "reachable": false, "sizeBytes": size_bytes }),
+        );
+    }
+
+    let integrity = match health_cache_get("database_integrity") {
+        Some(cached) => cached,
+        None => {
+            let result: Result<String, _> =
+                conn.query_row("PRAGMA quick_check", [], |row| row.get(0));
+            let ok = matches!(result.as_deref(), Ok("ok"));
+            let previous_ok_at = health_check_cache()
+                .lock()
+                .ok()
+                .and_then(|cache| cache.get("database_integrity").cloned())
+                .and_then(|(_, value)| value.get("integrityOkAt").cloned())
+                .unwrap_or(Value::Null);
+            let value = json!({
+                "integrityOk": ok,
+                "integrityOkAt": if ok { json!(chrono::Utc::now().to_rfc3339()) } else { previous_ok_at },
+                "integrityMessage": result.unwrap_or_else(|e| e.to_string()),
+            });
+            health_cache_put("database_integrity", value.clone());
+            value
+        }
+    };
+    drop(conn);
+
+    let integrity_ok = integrity["integrityOk"].as_bool().unwrap_or(false);
+    let size_mb = size_bytes / (1024 * 1024);
+    let (status, detail) = if !integrity_ok {
+        (
+            HealthStatus::Error,
+            format!(
+                "Integrity check failed: {}",
+                integrity["integrityMessage"].as_str().unwrap_or("unknown")
+            ),
+        )
+    } else if size_bytes > HEALTH_DB_SIZE_WARN_BYTES {
+        (
+            HealthStatus::Warn,
+            format!("Database is large ({size_mb} MB)"),
+        )
+    } else {
+        (HealthStatus::Ok, format!("Database healthy ({size_mb} MB)"))
+    };
+    health_entry(
+        status,
+        detail,
+        json!({
+            "reachable": true,
+            "sizeBytes": size_bytes,
+            "integrityOk": integrity_ok,
+            "integrityOkAt": integrity["integrityOkAt"],
+        }),
+    )
+}
+
+pub fn check_sync_health(db: &DbState, last_success: Option<String>) -> Value {
+    let conn = match db.conn.lock() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return health_entry(
+                HealthStatus::Error,
+                format!("Database lock poisoned: {e}"),
+                json!({}),
+            )
+        }
+    };
+    let count = |sql: &str| -> i64 { conn.query_row(sql, [], |row| row.get(0)).unwrap_or(0) };
+    let pending = count(
+        "SELECT COUNT(*) FROM sync_queue WHERE status IN ('pending', 'in_progress', 'queued_remote')",
+    );
+    let failed = count("SELECT COUNT(*) FROM sync_queue WHERE status = 'failed'");
+    // Rows that exhausted their retry budget will not be retried without
+    // operator action.
+    let dead = count(
+        "SELECT COUNT(*) FROM sync_queue
+         WHERE status = 'failed' AND retry_count >= max_retries",
+    );
+    drop(conn);
+
+    let (status, detail) = if dead > 0 {
+        (
+            HealthStatus::Error,
+            format!("{dead} sync item(s) exhausted retries"),
+        )
+    } else if failed > 0 {
+        (HealthStatus::Warn, format!("{failed} sync item(s) failed"))
+    } else if pending >= HEALTH_SYNC_PENDING_WARN {
+        (
+            HealthStatus::Warn,
+            format!("{pending} item(s) waiting to sync"),
+        )
+    } else {
+        (HealthStatus::Ok, format!("{pending} item(s) pending"))
+    };
+    health_entry(
+        status,
+        detail,
+        json!({
+            "pending": pending,
+            "failed": failed,
+            "dead": dead,
+            "lastSuccessAt": last_success,
+        }),
+    )
+}
+
+pub fn check_printing_health(db: &DbState) -> Value {
+    let conn = match db.conn.lock() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return health_entry(
+                HealthStatus::Error,
+                format!("Database lock poisoned: {e}"),
+                json!({}),
+            )
+        }
+    };
+    let profiles: i64 = conn
+        .query_row("SELECT COUNT(*) FROM printer_profiles", [], |row| {
+            row.get(0)
+        })
+        .unwrap_or(0);
+    let has_default = conn
+        .query_row(
+            "SELECT COUNT(*) FROM printer_profiles WHERE is_default = 1",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .unwrap_or(0)
+        > 0;
+    let failed_jobs: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM print_jobs
+             WHERE status = 'failed' AND julianday(created_at) > julianday('now', '-1 day')",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+    drop(conn);
+
+    let (status, detail) = if profiles == 0 {
+        (
+            HealthStatus::Warn,
+            "No printer profiles configured".to_string(),
+        )
+    } else if !has_default {
+        (HealthStatus::Warn, "No default printer profile".to_string())
+    } else if failed_jobs > 0 {
+        (
+            HealthStatus::Warn,
+            format!("{failed_jobs} print job(s) failed in the last 24h"),
+        )
+    } else {
+        (HealthStatus::Ok, "Printing ready".to_string())
+    };
+    health_entry(
+        status,
+        detail,
+        json!({
+            "profileCount": profiles,
+            "defaultProfilePresent": has_default,
+            "failedJobs": failed_jobs,
+        }),
+    )
+}
+
+/// Credentials required to talk to the admin dashboard at all.
+const REQUIRED_CREDENTIAL_KEYS: &[&str] = &["admin_dashboard_url", "pos_api_key", "terminal_id"];
+/// Credentials needed for branch-scoped features (realtime, menu sync).
+const RECOMMENDED_CREDENTIAL_KEYS: &[&str] = &["branch_id", "organization_id"];
+
+pub fn check_credentials_health() -> Value {
+    check_credentials_health_with(crate::storage::get_credential)
+}
+
+fn check_credentials_health_with(lookup: impl Fn(&str) -> Option<String>) -> Value {
+    let is_missing = |key: &str| !lookup(key).is_some_and(|value| !value.trim().is_empty());
+    let missing_required: Vec<&str> = REQUIRED_CREDENTIAL_KEYS
+        .iter()
+        .copied()
+        .filter(|key| is_missing(key))
+        .collect();
+    let missing_recommended: Vec<&str> = RECOMMENDED_CREDENTIAL_KEYS
+        .iter()
+        .copied()
+        .filter(|key| is_missing(key))
+        .collect();
+
+    let (status, detail) = if !missing_required.is_empty() {
+        (
+            HealthStatus::Error,
+            format!(
+                "Terminal not configured (missing {})",
+                missing_required.join(", ")
+            ),
+        )
+    } else if !missing_recommended.is_empty() {
+        (
+            HealthStatus::Warn,
+            format!("Missing {}", missing_recommended.join(", ")),
+        )
+    } else {
+        (HealthStatus::Ok, "Terminal configured".to_string())
+    };
+    let mut missing = missing_required;
+    missing.extend(missing_recommended);
+    health_entry(
+        status,
+        detail,
+        json!({
+            "configured": status != HealthStatus::Error,
+            "missingKeys": missing,
+        }),
+    )
+}
+
+pub fn check_updater_health(update_state: &Value) -> Value {
+    let flag = |key: &str| {
+        update_state
+            .get(key)
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    };
+    let error = update_state
+        .get("error")
+        .and_then(Value::as_str)
+        .filter(|e| !e.trim().is_empty());
+    let version = update_state
+        .pointer("/updateInfo/version")
+        .and_then(Value::as_str);
+
+    let (state, status, detail) = if let Some(error) = error {
+        (
+            "error",
+            HealthStatus::Warn,
+            format!("Updater error: {error}"),
+        )
+    } else if flag("installPending") {
+        (
+            "installing",
+            HealthStatus::Ok,
+            "Update install pending".to_string(),
+        )
+    } else if flag("ready") {
+        (
+            "ready",
+            HealthStatus::Ok,
+            format!("Update {} ready to install", version.unwrap_or("")),
+        )
+    } else if flag("downloading") {
+        (
+            "downloading",
+            HealthStatus::Ok,
+            "Downloading update".to_string(),
+        )
+    } else if flag("available") {
+        (
+            "available",
+            HealthStatus::Ok,
+            format!("Update {} available", version.unwrap_or("")),
+        )
+    } else if flag("checking") {
+        (
+            "checking",
+            HealthStatus::Ok,
+            "Checking for updates".to_string(),
+        )
+    } else {
+        ("idle", HealthStatus::Ok, "Up to date".to_string())
+    };
+    health_entry(
+        status,
+        detail,
+        json!({
+            "state": state,
+            "currentVersion": env!("CARGO_PKG_VERSION"),
+            "availableVersion": version,
+            "progress": update_state.get("progress").cloned().unwrap_or(json!(0)),
+        }),
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Recent error ring buffer
+// ---------------------------------------------------------------------------
+
+/// Maximum number of WARN/ERROR events retained for the support screen.
+pub const RECENT_ERROR_CAPACITY: usize = 200;
+
+/// One WARN/ERROR tracing event, already scrubbed for display/export.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentErrorEntry {
+    pub at: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    pub fields: serde_json::Map<String, Value>,
+}
+
+static RECENT_ERRORS: OnceLock<Mutex<VecDeque<RecentErrorEntry>>> = OnceLock::new();
+
+fn recent_error_buffer() -> &'static Mutex<VecDeque<RecentErrorEntry>> {
+    RECENT_ERRORS.get_or_init(|| Mutex::new(VecDeque::with_capacity(RECENT_ERROR_CAPACITY)))
+}
+
+fn push_recent_error(entry: RecentErrorEntry) {
+    if let Ok(mut buffer) = recent_error_buffer().lock() {
+        if buffer.len() >= RECENT_ERROR_CAPACITY {
+            buffer.pop_front();
+        }
+        buffer.push_back(entry);
+    }
+}
+
+/// Recent WARN/ERROR events, newest first.
+pub fn get_recent_errors(limit: usize) -> Vec<RecentErrorEntry> {
+    recent_error_buffer()
+        .lock()
+        .map(|buffer| buffer.iter().rev().take(limit).cloned().collect())
+        .unwrap_or_default()
+}
+
+#[derive(Default)]
+struct RecentErrorVisitor {
+    message: String,
+    fields: serde_json::Map<String, Value>,
+}
+
+impl tracing::field::Visit for RecentErrorVisitor {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields
+                .insert(field.name().to_string(), Value::String(value.to_string()));
+        }
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        let rendered = format!("{value:?}");
+        if field.name() == "message" {
+            self.message = rendered;
+        } else {
+            self.fields
+                .insert(field.name().to_string(), Value::String(rendered));
+        }
+    }
+}
+
+/// Tracing layer that mirrors WARN/ERROR events into an in-memory ring
+/// buffer (see `get_recent_errors`). Messages and fields are redacted at
+/// capture time so the buffer never holds secrets or customer contact data.
+pub struct RecentErrorLayer;
+
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for RecentErrorLayer {
+    fn on_event(
+        &self,
+        event: &tracing::Event<'_>,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let metadata = event.metadata();
+        if *metadata.level() > tracing::Level::WARN {
+            return;
+        }
+        let mut visitor = RecentErrorVisitor::default();
+        event.record(&mut visitor);
+        let mut fields = match redact_sensitive_fields(Value::Object(visitor.fields)) {
+            Value::Object(map) => map,
+            _ => serde_json::Map::new(),
+        };
+        if let Some(id) = crate::correlation::current() {
+            fields.insert("correlationId".into(), Value::String(id));
+        }
+        push_recent_error(RecentErrorEntry {
+            at: chrono::Utc::now().to_rfc3339(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: redact_log_line(&visitor.message, &[]),
+            fields,
+        });
+    }
+}
+
+/// Trailing log lines scanned by `find_by_correlation`.
+pub const CORRELATION_SEARCH_LOG_LINES: usize = 5000;
+
+/// Recent WARN/ERROR events and trailing log lines that mention
+/// `correlation_id`, oldest log line first. Log lines are redacted like the
+/// support bundle's.
+pub fn find_by_correlation(log_dir: &Path, correlation_id: &str) -> Value {
+    let errors: Vec<RecentErrorEntry> = get_recent_errors(RECENT_ERROR_CAPACITY)
+        .into_iter()
+        .filter(|entry| {
+            entry.fields.get("correlationId").and_then(Value::as_str) == Some(correlation_id)
+                || entry.message.contains(correlation_id)
+        })
+        .collect();
+    let secrets = known_secret_values();
+    let log_lines: Vec<String> = tail_log_lines(log_dir, CORRELATION_SEARCH_LOG_LINES)
+        .into_iter()
+        .filter(|line| line.contains(correlation_id))
+        .map(|line| redact_log_line(&line, &secrets))
+        .collect();
+    json!({
+        "correlationId": correlation_id,
+        "errors": errors,
+        "logLines": log_lines,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Support bundle
+// ---------------------------------------------------------------------------
+
+/// Default number of trailing log lines included in a support bundle.
+pub const SUPPORT_BUNDLE_LOG_LINES: usize = 2000;
+
+/// Keyring entries whose literal values are masked wherever they appear in
+/// exported log lines.
+const SECRET_CREDENTIAL_KEYS: &[&str] = &["pos_api_key", "api_key", "supabase_anon_key"];
+
+fn known_secret_values() -> Vec<String> {
+    SECRET_CREDENTIAL_KEYS
+        .iter()
+        .filter_map(|key| crate::storage::get_credential(key))
+        .map(|value| value.trim().to_string())
+        .filter(|value| value.len() >= 8)
+        .collect()
+}
+
+/// Redact one log line: literal known secrets, JWT-shaped tokens (Supabase
+/// keys), `key=value` pairs with sensitive keys, then emails/phone numbers.
+fn redact_log_line(line: &str, secrets: &[String]) -> String {
+    let mut line = line.to_string();
+    for secret in secrets {
+        if line.contains(secret.as_str()) {
+            line = line.replace(secret.as_str(), "[REDACTED]");
+        }
+    }
+    let words: Vec<String> = line
+        .split(' ')
+        .map(|word| {
+            let bare = word.trim_matches(|c: char| matches!(c, '"' | '\'' | ',' | ';'));
+            if bare.starts_with("eyJ") && bare.len() > 20 && bare.contains('.') {
+                return word.replace(bare, "[REDACTED_TOKEN]");
+            }
+            if let Some((key, value)) = bare.split_once(['=', ':']) {
+                if !value.is_empty() && should_redact_key(key.trim_matches('"')) {
+                    return word.replace(value, "[REDACTED]");
+                }
+            }
+            word.to_string()
+        })
+        .collect();
+    scrub_sensitive_string(&words.join(" "))
+}
+
+/// Last `max_lines` lines across the newest `pos.*` log files, oldest first.
+fn tail_log_lines(log_dir: &Path, max_lines: usize) -> Vec<String> {
+    let mut files: Vec<(PathBuf, std::time::SystemTime)> = fs::read_dir(log_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| {
+                    entry
+                        .file_name()
+                        .to_str()
+                        .is_some_and(|name| name.starts_with("pos."))
+                })
+                .map(|entry| {
+                    let modified = entry
+                        .metadata()
+                        .ok()
+                        .and_then(|m| m.modified().ok())
+                        .unwrap_or(std::time::UNIX_EPOCH);
+                    (entry.path(), modified)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort_by_key(|file| std::cmp::Reverse(file.1));
+
+    let mut collected: VecDeque<String> = VecDeque::new();
+    for (path, _) in files {
+        if collected.len() >= max_lines {
+            break;
+        }
+        let Ok(mut file) = fs::File::open(&path) else {
+            continue;
+        };
+        // Only the tail of very large files matters.
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        if len > MAX_LOG_SIZE {
+            let _ = file.seek(SeekFrom::Start(len - MAX_LOG_SIZE));
+        }
+        let mut buf = Vec::new();
+        if file.read_to_end(&mut buf).is_err() {
+            continue;
+        }
+        let text = String::from_utf8_lossy(&buf);
+        let remaining = max_lines - collected.len();
+        let lines: Vec<&str> = text.lines().collect();
+        for line in lines.iter().rev().take(remaining) {
+            collected.push_front((*line).to_string());
+        }
+    }
+    collected.into_iter().collect()
+}
+
+fn get_redacted_local_settings(conn: &rusqlite::Connection) -> Value {
+    let mut settings = serde_json::Map::new();
+    if let Ok(mut stmt) = conn.prepare(
+        "SELECT setting_category, setting_key, setting_value
+         FROM local_settings ORDER BY setting_category, setting_key",
+    ) {
+        if let Ok(rows) = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        }) {
+            for (category, key, value) in rows.flatten() {
+                let value = if should_redact_key(&key) || should_redact_key(&category) {
+                    Value::String("[REDACTED]".to_string())
+                } else {
+                    redact_sensitive_fields(
+                        value
+                            .as_deref()
+                            .map(parse_local_setting_value)
+                            .unwrap_or(Value::Null),
+                    )
+                };
+                let entry = settings
+                    .entry(category)
+                    .or_insert_with(|| Value::Object(serde_json::Map::new()));
+                if let Some(map) = entry.as_object_mut() {
+                    map.insert(key, value);
+                }
+            }
+        }
+    }
+    Value::Object(settings)
+}
+
+fn get_sync_queue_summary(conn: &rusqlite::Connection) -> Value {
+    let mut by_status = serde_json::Map::new();
+    let mut by_entity = serde_json::Map::new();
+    if let Ok(mut stmt) = conn.prepare(
+        "SELECT entity_type, status, COUNT(*) FROM sync_queue GROUP BY entity_type, status",
+    ) {
+        if let Ok(rows) = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        }) {
+            for (entity_type, status, count) in rows.flatten() {
+                let total = by_status.get(&status).and_then(Value::as_i64).unwrap_or(0);
+                by_status.insert(status.clone(), json!(total + count));
+                let entity = by_entity.entry(entity_type).or_insert_with(|| json!({}));
+                entity[status] = json!(count);
+            }
+        }
+    }
+    json!({
+        "byStatus": by_status,
+        "byEntityType": by_entity,
+    })
+}
+
+fn get_db_integrity(conn: &rusqlite::Connection) -> Value {
+    let mut messages = Vec::new();
+    let result = conn.prepare("PRAGMA integrity_check").and_then(|mut stmt| {
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        for row in rows {
+            messages.push(row?);
+        }
+        Ok(())
+    });
+    match result {
+        Ok(()) => json!({
+            "ok": messages.len() == 1 && messages[0] == "ok",
+            "messages": messages,
+        }),
+        Err(e) => json!({ "ok": false, "error": e.to_string() }),
+    }
+}
+
+fn get_system_info() -> Value {
+    json!({
+        "os": std::env::consts::OS,
+        "family": std::env::consts::FAMILY,
+        "arch": std::env::consts::ARCH,
+        "cpuCount": std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        "generatedAt": chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Build a support bundle at `<app_data_dir>/diagnostics/bundle_<ts>.zip`.
+///
+/// Unlike `export_diagnostics`, everything in this bundle is always
+/// redacted: settings and structured data go through `redact_sensitive_fields`
+/// and log lines are scrubbed line-by-line before being written.
+pub fn export_support_bundle(
+    db: &DbState,
+    app_data_dir: &Path,
+    log_lines: usize,
+) -> Result<String, String> {
+    export_support_bundle_from(db, app_data_dir, &get_log_dir(), log_lines)
+}
+
+fn export_support_bundle_from(
+    db: &DbState,
+    app_data_dir: &Path,
+    log_dir: &Path,
+    log_lines: usize,
+) -> Result<String, String> {
+    let bundle_dir = app_data_dir.join("diagnostics");
+    fs::create_dir_all(&bundle_dir)
+        .map_err(|e| format!("Failed to create diagnostics directory: {e}"))?;
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
+    let zip_path = bundle_dir.join(format!("bundle_{timestamp}.zip"));
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let settings = get_redacted_local_settings(&conn);
+    let queue_summary = get_sync_queue_summary(&conn);
+    let sync_errors = redact_sensitive_fields(json!(get_recent_sync_errors(&conn, 50)));
+    let integrity = get_db_integrity(&conn);
+    let printers = redact_sensitive_fields(get_printer_diagnostics(&conn));
+    drop(conn);
+
+    let secrets = known_secret_values();
+    let mut log_tail = String::new();
+    for line in tail_log_lines(log_dir, log_lines) {
+        log_tail.push_str(&redact_log_line(&line, &secrets));
+        log_tail.push('\n');
+    }
+
+    let file =
+        fs::File::create(&zip_path).map_err(|e| format!("Failed to create support bundle: {e}"))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let zip_options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    let about = redact_sensitive_fields(json!({
+        "app": get_about_info(),
+        "system": get_system_info(),
+    }));
+    write_json_to_zip(&mut zip, &zip_options, "about.json", &about)?;
+    write_json_to_zip(&mut zip, &zip_options, "settings.json", &settings)?;
+    write_json_to_zip(
+        &mut zip,
+        &zip_options,
+        "sync_queue_summary.json",
+        &queue_summary,
+    )?;
+    write_json_to_zip(&mut zip, &zip_options, "sync_errors.json", &sync_errors)?;
+    write_json_to_zip(
+        &mut zip,
+        &zip_options,
+        "recent_errors.json",
+        &json!(get_recent_errors(RECENT_ERROR_CAPACITY)),
+    )?;
+    write_json_to_zip(&mut zip, &zip_options, "db_integrity.json", &integrity)?;
+    write_json_to_zip(&mut zip, &zip_options, "printer_profiles.json", &printers)?;
+    zip.start_file("logs/tail.log", zip_options)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(log_tail.as_bytes())
+        .map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+
+    Ok(zip_path.to_string_lossy().to_string())
+}
+
+/// Orders listed in a support snapshot.
+pub const SUPPORT_SNAPSHOT_ORDER_LIMIT: i64 = 50;
+
+/// The support bundle's data as one JSON value, for a live support session.
+/// Orders carry metadata only: no customer, address or note columns are read.
+pub fn support_snapshot(conn: &rusqlite::Connection) -> Value {
+    redact_sensitive_fields(json!({
+        "generatedAt": chrono::Utc::now().to_rfc3339(),
+        "about": {
+            "app": get_about_info(),
+            "system": get_system_info(),
+        },
+        "recentOrders": get_recent_order_metadata(conn, SUPPORT_SNAPSHOT_ORDER_LIMIT),
+        "syncQueue": get_sync_queue_summary(conn),
+        "syncErrors": get_recent_sync_errors(conn, 50),
+        "recentErrors": get_recent_errors(RECENT_ERROR_CAPACITY),
+        "settings": get_redacted_local_settings(conn),
+        "printers": get_printer_diagnostics(conn),
+    }))
+}
+
+fn get_recent_order_metadata(conn: &rusqlite::Connection, limit: i64) -> Vec<Value> {
+    let mut orders = Vec::new();
+    if let Ok(mut stmt) = conn.prepare(
+        "SELECT id, order_number, status, order_type, total_amount, payment_status,
+                sync_status, terminal_id, created_at, updated_at
+         FROM orders ORDER BY created_at DESC LIMIT ?1",
+    ) {
+        if let Ok(rows) = stmt.query_map(params![limit], |row| {
+            Ok(json!({
+                "id": row.get::<_, String>(0)?,
+                "orderNumber": row.get::<_, Option<String>>(1)?,
+                "status": row.get::<_, String>(2)?,
+                "orderType": row.get::<_, Option<String>>(3)?,
+                "totalAmount": row.get::<_, f64>(4)?,
+                "paymentStatus": row.get::<_, Option<String>>(5)?,
+                "syncStatus": row.get::<_, String>(6)?,
+                "terminalId": row.get::<_, Option<String>>(7)?,
+                "createdAt": row.get::<_, Option<String>>(8)?,
+                "updatedAt": row.get::<_, Option<String>>(9)?,
+            }))
+        }) {
+            for row in rows.flatten() {
+                orders.push(row);
+            }
+        }
+    }
+    orders
+}
+
 // ---------------------------------------------------------------------------
 // Log rotation
 // ---------------------------------------------------------------------------
@@ -1589,4 +2466,189 @@ mod tests {
         assert_eq!(redacted["items"][0]["password"], json!("[REDACTED]"));
         assert_eq!(redacted["items"][1]["name"], json!("safe"));
     }
+
+    #[test]
+    fn test_export_support_bundle_is_always_redacted() {
+        let dir = std::env::temp_dir().join(format!("diag_support_{}", uuid::Uuid::new_v4()));
+        let log_dir = dir.join("logs");
+        std::fs::create_dir_all(&log_dir).unwrap();
+        let db_state = crate::db::init(&dir).unwrap();
+        {
+            let conn = db_state.conn.lock().unwrap();
+            crate::db::set_setting(&conn, "terminal", "pos_api_key", "sk-live-1234567890").unwrap();
+            crate::db::set_setting(&conn, "restaurant", "name", "Corner Cafe").unwrap();
+        }
+        std::fs::write(
+            log_dir.join("pos.2026-10-15"),
+            "INFO first line\nWARN lookup failed for +30 6912345678\nINFO apikey=abcdef123456 sent\nINFO last line\n",
+        )
+        .unwrap();
+
+        let zip_path = export_support_bundle_from(&db_state, &dir, &log_dir, 3).unwrap();
+        assert!(zip_path.contains("diagnostics"));
+        assert!(std::path::Path::new(&zip_path)
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("bundle_"));
+
+        let file = std::fs::File::open(&zip_path).unwrap();
+        let mut archive = zip::ZipArchive::new(file).unwrap();
+        let settings = read_zip_json(&mut archive, "settings.json");
+        assert_eq!(settings["terminal"]["pos_api_key"], json!("[REDACTED]"));
+        assert_eq!(settings["restaurant"]["name"], json!("Corner Cafe"));
+        let integrity = read_zip_json(&mut archive, "db_integrity.json");
+        assert_eq!(integrity["ok"], json!(true));
+        for name in [
+            "about.json",
+            "sync_queue_summary.json",
+            "sync_errors.json",
+            "recent_errors.json",
+            "printer_profiles.json",
+        ] {
+            assert!(archive.by_name(name).is_ok(), "missing {name}");
+        }
+
+        let mut tail = String::new();
+        archive
+            .by_name("logs/tail.log")
+            .unwrap()
+            .read_to_string(&mut tail)
+            .unwrap();
+        assert!(
+            !tail.contains("first line"),
+            "only the last 3 lines are kept"
+        );
+        assert!(tail.contains("last line"));
+        assert!(!tail.contains("6912345678"));
+        assert!(!tail.contains("abcdef123456"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_redact_log_line_masks_secrets_and_tokens() {
+        let secrets = vec!["super-secret-value".to_string()];
+        let line = redact_log_line(
+            "auth key=super-secret-value bearer eyJhbGciOiJIUzI1NiJ9.eyJyb2xlIjoiYW5vbiJ9.sig status=ok",
+            &secrets,
+        );
+        assert!(!line.contains("super-secret-value"));
+        assert!(!line.contains("eyJhbGciOiJIUzI1NiJ9"));
+        assert!(line.contains("status=ok"));
+    }
+
+    #[test]
+    fn test_recent_error_buffer_is_bounded_and_newest_first() {
+        for i in 0..(RECENT_ERROR_CAPACITY + 5) {
+            push_recent_error(RecentErrorEntry {
+                at: chrono::Utc::now().to_rfc3339(),
+                level: "ERROR".to_string(),
+                target: "diag_test".to_string(),
+                message: format!("ring-test-{i}"),
+                fields: serde_json::Map::new(),
+            });
+        }
+        let recent = get_recent_errors(usize::MAX);
+        assert!(recent.len() <= RECENT_ERROR_CAPACITY);
+        assert_eq!(
+            recent[0].message,
+            format!("ring-test-{}", RECENT_ERROR_CAPACITY + 4)
+        );
+    }
+
+    #[test]
+    fn test_find_by_correlation_matches_errors_and_log_lines() {
+        let dir = std::env::temp_dir().join(format!("diag_correlation_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("create log dir");
+        fs::write(
+            dir.join("pos.2026-10-15"),
+            "INFO correlated{correlation_id=corr-find-1}: Enqueued sync item\n\
+             INFO correlated{correlation_id=corr-other}: unrelated\n",
+        )
+        .expect("write log");
+        let mut fields = serde_json::Map::new();
+        fields.insert("correlationId".into(), json!("corr-find-1"));
+        push_recent_error(RecentErrorEntry {
+            at: chrono::Utc::now().to_rfc3339(),
+            level: "WARN".to_string(),
+            target: "diag_test".to_string(),
+            message: "admin request failed".to_string(),
+            fields,
+        });
+
+        let found = find_by_correlation(&dir, "corr-find-1");
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(found["logLines"].as_array().unwrap().len(), 1);
+        assert!(found["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|entry| entry["message"] == "admin request failed"));
+    }
+
+    #[test]
+    fn test_health_rollup_is_worst_of() {
+        let ok = health_entry(HealthStatus::Ok, "fine", json!({}));
+        let warn = health_entry(HealthStatus::Warn, "meh", json!({ "x": 1 }));
+        let error = health_entry(HealthStatus::Error, "bad", json!({}));
+        assert_eq!(warn["x"], json!(1));
+        assert_eq!(warn["status"], json!("warn"));
+        assert_eq!(rollup_health_status([&ok]), HealthStatus::Ok);
+        assert_eq!(rollup_health_status([&ok, &warn]), HealthStatus::Warn);
+        assert_eq!(
+            rollup_health_status([&warn, &error, &ok]),
+            HealthStatus::Error
+        );
+    }
+
+    #[test]
+    fn test_database_and_sync_health_on_fresh_db() {
+        let dir = std::env::temp_dir().join(format!("diag_health_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_state = crate::db::init(&dir).unwrap();
+
+        let database = check_database_health(&db_state);
+        assert_eq!(database["reachable"], json!(true));
+        assert_eq!(database["integrityOk"], json!(true));
+        assert!(database["sizeBytes"].as_u64().unwrap() > 0);
+
+        let sync = check_sync_health(&db_state, Some("2026-10-15T10:00:00Z".into()));
+        assert_eq!(sync["status"], json!("ok"));
+        assert_eq!(sync["pending"], json!(0));
+        assert_eq!(sync["lastSuccessAt"], json!("2026-10-15T10:00:00Z"));
+
+        let printing = check_printing_health(&db_state);
+        assert_eq!(printing["status"], json!("warn"));
+        assert_eq!(printing["defaultProfilePresent"], json!(false));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_credentials_health_reports_missing_keys() {
+        let none = check_credentials_health_with(|_| None);
+        assert_eq!(none["status"], json!("error"));
+        assert_eq!(none["configured"], json!(false));
+        assert_eq!(none["missingKeys"].as_array().unwrap().len(), 5);
+
+        let partial =
+            check_credentials_health_with(|key| (key != "branch_id").then(|| "value".to_string()));
+        assert_eq!(partial["status"], json!("warn"));
+        assert_eq!(partial["missingKeys"], json!(["branch_id"]));
+    }
+
+    #[test]
+    fn test_updater_health_maps_state_flags() {
+        let idle = check_updater_health(&json!({}));
+        assert_eq!(idle["state"], json!("idle"));
+        let ready = check_updater_health(&json!({
+            "ready": true,
+            "updateInfo": { "version": "9.9.9" }
+        }));
+        assert_eq!(ready["state"], json!("ready"));
+        assert_eq!(ready["availableVersion"], json!("9.9.9"));
+        let failed = check_updater_health(&json!({ "error": "signature mismatch" }));
+        assert_eq!(failed["status"], json!("warn"));
+    }
 }
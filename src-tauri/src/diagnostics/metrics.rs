@@ -0,0 +1,400 @@
+//! Per-command latency and error counters, plus counters for the
+//! `DbState::read` / `DbState::write` helpers.
+//!
+//! Recording is lock-free: every entry is a fixed block of atomics and the
+//! registry is a fixed-size open-addressed table claimed with
+//! compare-and-swap. Names beyond [`MAX_ENTRIES`] are folded into a single
+//! overflow entry so dynamically named commands cannot grow it without
+//! limit. Latencies go into log-linear buckets (four per power of two, in
+//! microseconds), so percentiles are accurate to within 25%.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+pub const MAX_ENTRIES: usize = 256;
+pub const OVERFLOW_NAME: &str = "(other)";
+/// Commands listed in the health check.
+pub const SLOWEST_COMMANDS: usize = 5;
+
+const SUB_BUCKET_BITS: u32 = 2;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+/// Values below this get a bucket each.
+const LINEAR_BUCKETS: usize = 2 * SUB_BUCKETS;
+const BUCKETS: usize = 128;
+
+fn bucket_index(micros: u64) -> usize {
+    if micros < LINEAR_BUCKETS as u64 {
+        return micros as usize;
+    }
+    let exponent = 63 - micros.leading_zeros();
+    let sub = (micros >> (exponent - SUB_BUCKET_BITS)) as usize & (SUB_BUCKETS - 1);
+    let index = LINEAR_BUCKETS + (exponent - SUB_BUCKET_BITS - 1) as usize * SUB_BUCKETS + sub;
+    index.min(BUCKETS - 1)
+}
+
+/// Exclusive upper bound of a bucket, in microseconds.
+fn bucket_upper_micros(index: usize) -> u64 {
+    if index < LINEAR_BUCKETS {
+        return index as u64 + 1;
+    }
+    let offset = index - LINEAR_BUCKETS;
+    let exponent = (offset / SUB_BUCKETS) as u32 + SUB_BUCKET_BITS + 1;
+    let sub = (offset % SUB_BUCKETS) as u64;
+    let width = 1u64 << (exponent - SUB_BUCKET_BITS);
+    (SUB_BUCKETS as u64 + sub) * width + width
+}
+
+struct Entry {
+    /// Name hash; zero while the slot is free.
+    key: AtomicU64,
+    name: OnceLock<String>,
+    errors: AtomicU64,
+    total_micros: AtomicU64,
+    max_micros: AtomicU64,
+    buckets: [AtomicU64; BUCKETS],
+}
+
+impl Entry {
+    fn new() -> Self {
+        Self {
+            key: AtomicU64::new(0),
+            name: OnceLock::new(),
+            errors: AtomicU64::new(0),
+            total_micros: AtomicU64::new(0),
+            max_micros: AtomicU64::new(0),
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+
+    fn named(name: &str) -> Self {
+        let entry = Self::new();
+        let _ = entry.name.set(name.to_string());
+        entry
+    }
+
+    fn record(&self, elapsed: Duration, ok: bool) {
+        let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        if !ok {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        self.total_micros.fetch_add(micros, Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, Ordering::Relaxed);
+        self.buckets[bucket_index(micros)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn reset(&self) {
+        for counter in [&self.errors, &self.total_micros, &self.max_micros]
+            .into_iter()
+            .chain(self.buckets.iter())
+        {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn snapshot(&self) -> Option<EntrySnapshot> {
+        let name = self.name.get()?;
+        let buckets: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .collect();
+        // The bucket total is the call count.
+        let count: u64 = buckets.iter().sum();
+        if count == 0 {
+            return None;
+        }
+        let percentile = |p: f64| {
+            let rank = ((count as f64) * p).ceil().max(1.0) as u64;
+            let mut seen = 0;
+            for (index, bucket) in buckets.iter().enumerate() {
+                seen += bucket;
+                if seen >= rank {
+                    return bucket_upper_micros(index);
+                }
+            }
+            bucket_upper_micros(BUCKETS - 1)
+        };
+        let max_micros = self.max_micros.load(Ordering::Relaxed);
+        Some(EntrySnapshot {
+            name: name.clone(),
+            count,
+            errors: self.errors.load(Ordering::Relaxed).min(count),
+            mean_micros: self.total_micros.load(Ordering::Relaxed) / count,
+            // A bucket bound can overshoot the slowest call actually seen.
+            p50_micros: percentile(0.50).min(max_micros),
+            p95_micros: percentile(0.95).min(max_micros),
+            p99_micros: percentile(0.99).min(max_micros),
+            max_micros,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntrySnapshot {
+    pub name: String,
+    pub count: u64,
+    pub errors: u64,
+    pub mean_micros: u64,
+    pub p50_micros: u64,
+    pub p95_micros: u64,
+    pub p99_micros: u64,
+    pub max_micros: u64,
+}
+
+fn millis(micros: u64) -> f64 {
+    (micros as f64 / 10.0).round() / 100.0
+}
+
+impl EntrySnapshot {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "count": self.count,
+            "errors": self.errors,
+            "meanMs": millis(self.mean_micros),
+            "p50Ms": millis(self.p50_micros),
+            "p95Ms": millis(self.p95_micros),
+            "p99Ms": millis(self.p99_micros),
+            "maxMs": millis(self.max_micros),
+        })
+    }
+}
+
+/// Which `DbState` helper a database access went through.
+#[derive(Debug, Clone, Copy)]
+pub enum DbAccess {
+    Read,
+    Write,
+}
+
+pub struct Registry {
+    entries: Box<[Entry]>,
+    overflow: Entry,
+    db_read: Entry,
+    db_write: Entry,
+    since_ms: AtomicI64,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self {
+            entries: (0..MAX_ENTRIES).map(|_| Entry::new()).collect(),
+            overflow: Entry::named(OVERFLOW_NAME),
+            db_read: Entry::named("read"),
+            db_write: Entry::named("write"),
+            since_ms: AtomicI64::new(chrono::Utc::now().timestamp_millis()),
+        }
+    }
+
+    fn entry(&self, name: &str) -> &Entry {
+        // FNV-1a; zero marks a free slot so it is never a valid key.
+        let key = name
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            })
+            .max(1);
+        let start = (key % MAX_ENTRIES as u64) as usize;
+        for offset in 0..MAX_ENTRIES {
+            let entry = &self.entries[(start + offset) % MAX_ENTRIES];
+            let current =
+                match entry
+                    .key
+                    .compare_exchange(0, key, Ordering::AcqRel, Ordering::Acquire)
+                {
+                    Ok(_) => {
+                        let _ = entry.name.set(name.to_string());
+                        return entry;
+                    }
+                    Err(current) => current,
+                };
+            // The name may not be set yet by the thread that just claimed
+            // the slot; the hash alone is good enough then.
+            if current == key && entry.name.get().map_or(true, |stored| stored == name) {
+                return entry;
+            }
+        }
+        &self.overflow
+    }
+
+    pub fn record(&self, name: &str, elapsed: Duration, ok: bool) {
+        self.entry(name).record(elapsed, ok);
+    }
+
+    pub fn record_db(&self, access: DbAccess, elapsed: Duration, ok: bool) {
+        match access {
+            DbAccess::Read => &self.db_read,
+            DbAccess::Write => &self.db_write,
+        }
+        .record(elapsed, ok);
+    }
+
+    /// Zero every counter. Registered names keep their slots.
+    pub fn reset(&self) {
+        for entry in self.entries.iter() {
+            entry.reset();
+        }
+        self.overflow.reset();
+        self.db_read.reset();
+        self.db_write.reset();
+        self.since_ms
+            .store(chrono::Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    /// Commands with at least one call, slowest p95 first.
+    pub fn commands(&self) -> Vec<EntrySnapshot> {
+        let mut commands: Vec<EntrySnapshot> = self
+            .entries
+            .iter()
+            .chain(std::iter::once(&self.overflow))
+            .filter_map(Entry::snapshot)
+            .collect();
+        commands.sort_by(|a, b| {
+            b.p95_micros
+                .cmp(&a.p95_micros)
+                .then(b.max_micros.cmp(&a.max_micros))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        commands
+    }
+
+    pub fn snapshot(&self) -> Value {
+        let since = chrono::DateTime::from_timestamp_millis(self.since_ms.load(Ordering::Relaxed))
+            .map(|at| at.to_rfc3339())
+            .unwrap_or_default();
+        let db = |entry: &Entry| {
+            entry
+                .snapshot()
+                .map(|snapshot| snapshot.to_json())
+                .unwrap_or_else(|| json!({ "count": 0, "errors": 0 }))
+        };
+        json!({
+            "since": since,
+            "capacity": MAX_ENTRIES,
+            "commands": self.commands().iter().map(EntrySnapshot::to_json).collect::<Vec<_>>(),
+            "db": {
+                "read": db(&self.db_read),
+                "write": db(&self.db_write),
+            },
+        })
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+pub fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(Registry::new)
+}
+
+/// Run a command body and record its latency under `name`; an `Err`
+/// result counts as an error.
+pub async fn track<T, E, F>(name: &str, fut: F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let started = Instant::now();
+    let result = fut.await;
+    registry().record(name, started.elapsed(), result.is_ok());
+    result
+}
+
+pub fn record_db(access: DbAccess, elapsed: Duration, ok: bool) {
+    registry().record_db(access, elapsed, ok);
+}
+
+/// The slowest commands by p95, for the health check.
+pub fn slowest_commands() -> Value {
+    Value::Array(
+        registry()
+            .commands()
+            .iter()
+            .take(SLOWEST_COMMANDS)
+            .map(EntrySnapshot::to_json)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_are_monotonic_and_cover_their_values() {
+        let mut previous = 0;
+        for micros in (0..5_000).chain([65_535, 1_000_000, 3_600_000_000]) {
+            let index = bucket_index(micros);
+            assert!(index >= previous, "bucket for {micros} went backwards");
+            assert!(
+                micros < bucket_upper_micros(index),
+                "{micros} above its bucket"
+            );
+            if index > 0 && index < BUCKETS - 1 {
+                assert!(micros >= bucket_upper_micros(index - 1));
+            }
+            previous = index;
+        }
+        assert_eq!(bucket_index(u64::MAX), BUCKETS - 1);
+    }
+
+    #[test]
+    fn snapshot_reports_counts_errors_and_percentiles() {
+        let registry = Registry::new();
+        for millis in 1..=100u64 {
+            registry.record(
+                "order_create",
+                Duration::from_millis(millis),
+                millis % 10 != 0,
+            );
+        }
+        registry.record("menu_get_categories", Duration::from_micros(300), true);
+        registry.record_db(DbAccess::Read, Duration::from_micros(40), true);
+
+        let commands = registry.commands();
+        assert_eq!(commands[0].name, "order_create");
+        assert_eq!(commands[0].count, 100);
+        assert_eq!(commands[0].errors, 10);
+        assert_eq!(commands[0].max_micros, 100_000);
+        let within = |actual: u64, expected: u64| {
+            actual >= expected && (actual as f64) <= expected as f64 * 1.25
+        };
+        assert!(within(commands[0].p50_micros, 50_000), "{commands:?}");
+        assert!(within(commands[0].p95_micros, 95_000), "{commands:?}");
+        assert_eq!(commands[1].name, "menu_get_categories");
+
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot["db"]["read"]["count"], 1);
+        assert_eq!(snapshot["db"]["write"]["count"], 0);
+
+        registry.reset();
+        assert!(registry.commands().is_empty());
+    }
+
+    #[test]
+    fn registry_is_bounded_by_an_overflow_entry() {
+        let registry = Registry::new();
+        for index in 0..MAX_ENTRIES + 10 {
+            registry.record(&format!("dynamic_{index}"), Duration::from_micros(5), true);
+        }
+        registry.record("dynamic_0", Duration::from_micros(5), true);
+        let commands = registry.commands();
+        assert_eq!(commands.len(), MAX_ENTRIES + 1);
+        let count = |name: &str| {
+            commands
+                .iter()
+                .find(|entry| entry.name == name)
+                .map(|entry| entry.count)
+        };
+        assert_eq!(count(OVERFLOW_NAME), Some(10));
+        assert_eq!(count("dynamic_0"), Some(2));
+    }
+}
@@ -0,0 +1,569 @@
+//! Driver dispatch runs.
+//!
+//! A run is one trip of a driver: the dispatcher picks a batch of ready
+//! delivery orders, `start_run` hands them to the driver and moves them to
+//! `out_for_delivery`, and `mark_returned` closes the run when the driver is
+//! back with the cash declared per order. The per-order delivered/earning
+//! bookkeeping on return is done by the caller through
+//! `commands::orders::settle_driver_delivery`, the same path a single
+//! `order_assign_driver` takes.
+
+use std::collections::{BTreeMap, HashSet};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::money::Cents;
+
+pub const UPDATED_EVENT: &str = "dispatch_updated";
+
+/// Optional cap on orders per run (`local_settings` dispatch /
+/// max_orders_per_run). Absent or zero means no cap.
+const CAPACITY_SETTING: (&str, &str) = ("dispatch", "max_orders_per_run");
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriverRun {
+    pub id: String,
+    pub driver_id: String,
+    pub driver_name: Option<String>,
+    pub driver_shift_id: Option<String>,
+    pub status: String,
+    pub started_at: String,
+    pub returned_at: Option<String>,
+    pub order_ids: Vec<String>,
+}
+
+impl DriverRun {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "driverId": self.driver_id,
+            "driverName": self.driver_name,
+            "driverShiftId": self.driver_shift_id,
+            "status": self.status,
+            "startedAt": self.started_at,
+            "returnedAt": self.returned_at,
+            "orderIds": self.order_ids,
+            "orderCount": self.order_ids.len(),
+        })
+    }
+}
+
+pub struct NewRun<'a> {
+    pub driver_id: &'a str,
+    pub driver_name: Option<&'a str>,
+    pub driver_shift_id: Option<&'a str>,
+    pub branch_id: Option<&'a str>,
+    pub order_ids: &'a [String],
+    pub actor: Option<&'a str>,
+}
+
+/// The `out` run currently carrying `order_id`, if any.
+pub fn active_run_for_order(conn: &Connection, order_id: &str) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT r.id
+         FROM driver_run_orders ro
+         JOIN driver_runs r ON r.id = ro.run_id
+         WHERE ro.order_id = ?1 AND r.status = 'out'
+         LIMIT 1",
+        params![order_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("load active run for order: {e}"))
+}
+
+fn active_run_for_driver(conn: &Connection, driver_id: &str) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT id FROM driver_runs WHERE driver_id = ?1 AND status = 'out' LIMIT 1",
+        params![driver_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("load active run for driver: {e}"))
+}
+
+fn run_capacity(conn: &Connection) -> Option<usize> {
+    crate::db::get_setting(conn, CAPACITY_SETTING.0, CAPACITY_SETTING.1)
+        .and_then(|raw| raw.trim().parse::<usize>().ok())
+        .filter(|cap| *cap > 0)
+}
+
+/// Check that `order_id` can go out on a new run: a ready delivery order
+/// with no driver and not already carried by another run.
+fn ensure_dispatchable(conn: &Connection, order_id: &str) -> Result<(), String> {
+    if let Some(run_id) = active_run_for_order(conn, order_id)? {
+        return Err(format!(
+            "Order {order_id} is already on active run {run_id}"
+        ));
+    }
+    let (order_type, status, driver_id): (String, String, String) = conn
+        .query_row(
+            "SELECT COALESCE(order_type, ''), LOWER(COALESCE(status, '')),
+                    TRIM(COALESCE(driver_id, ''))
+             FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| format!("load order {order_id}: {e}"))?
+        .ok_or_else(|| format!("Order {order_id} not found"))?;
+    if order_type != "delivery" {
+        return Err(format!("Order {order_id} is not a delivery order"));
+    }
+    if status != "ready" {
+        return Err(format!(
+            "Order {order_id} is {status}, only ready orders can be dispatched"
+        ));
+    }
+    if !driver_id.is_empty() {
+        return Err(format!("Order {order_id} is already assigned to a driver"));
+    }
+    Ok(())
+}
+
+/// Open a run for the driver and move its orders to `out_for_delivery`.
+/// Validation covers every order before anything is written, so a rejected
+/// batch leaves no partial run behind.
+pub fn start_run(conn: &Connection, run: &NewRun<'_>, now: &str) -> Result<DriverRun, String> {
+    let mut seen = HashSet::new();
+    let order_ids: Vec<String> = run
+        .order_ids
+        .iter()
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty() && seen.insert(id.clone()))
+        .collect();
+    if order_ids.is_empty() {
+        return Err("A run needs at least one order".into());
+    }
+    if let Some(capacity) = run_capacity(conn) {
+        if order_ids.len() > capacity {
+            return Err(format!(
+                "A run can carry at most {capacity} orders ({} selected)",
+                order_ids.len()
+            ));
+        }
+    }
+    if let Some(run_id) = active_run_for_driver(conn, run.driver_id)? {
+        return Err(format!("Driver is already out on run {run_id}"));
+    }
+    for order_id in &order_ids {
+        ensure_dispatchable(conn, order_id)?;
+    }
+
+    let run_id = Uuid::new_v4().to_string();
+    conn.execute_batch("BEGIN IMMEDIATE")
+        .map_err(|e| format!("begin driver run: {e}"))?;
+    let written = (|| -> Result<(), String> {
+        conn.execute(
+            "INSERT INTO driver_runs (
+                 id, driver_id, driver_name, driver_shift_id, branch_id, status,
+                 started_at, started_by, created_at, updated_at
+             ) VALUES (?1, ?2, ?3, ?4, ?5, 'out', ?6, ?7, ?6, ?6)",
+            params![
+                run_id,
+                run.driver_id,
+                run.driver_name,
+                run.driver_shift_id,
+                run.branch_id,
+                now,
+                run.actor,
+            ],
+        )
+        .map_err(|e| format!("insert driver run: {e}"))?;
+        for (position, order_id) in order_ids.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO driver_run_orders (run_id, order_id, position)
+                 VALUES (?1, ?2, ?3)",
+                params![run_id, order_id, position as i64],
+            )
+            .map_err(|e| format!("link order to run: {e}"))?;
+            conn.execute(
+                "UPDATE orders
+                 SET status = 'out_for_delivery', driver_id = ?1, driver_name = ?2,
+                     sync_status = 'pending', updated_at = ?3
+                 WHERE id = ?4",
+                params![run.driver_id, run.driver_name, now, order_id],
+            )
+            .map_err(|e| format!("mark order out for delivery: {e}"))?;
+        }
+        Ok(())
+    })();
+    match written {
+        Ok(()) => conn
+            .execute_batch("COMMIT")
+            .map_err(|e| format!("commit driver run: {e}"))?,
+        Err(error) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            return Err(error);
+        }
+    }
+    load_run(conn, &run_id)
+}
+
+pub fn load_run(conn: &Connection, run_id: &str) -> Result<DriverRun, String> {
+    let mut run = conn
+        .query_row(
+            "SELECT id, driver_id, driver_name, driver_shift_id, status, started_at, returned_at
+             FROM driver_runs WHERE id = ?1",
+            params![run_id],
+            |row| {
+                Ok(DriverRun {
+                    id: row.get(0)?,
+                    driver_id: row.get(1)?,
+                    driver_name: row.get(2)?,
+                    driver_shift_id: row.get(3)?,
+                    status: row.get(4)?,
+                    started_at: row.get(5)?,
+                    returned_at: row.get(6)?,
+                    order_ids: Vec::new(),
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| format!("load driver run: {e}"))?
+        .ok_or_else(|| format!("Run {run_id} not found"))?;
+    let mut stmt = conn
+        .prepare("SELECT order_id FROM driver_run_orders WHERE run_id = ?1 ORDER BY position")
+        .map_err(|e| format!("prepare run orders: {e}"))?;
+    run.order_ids = stmt
+        .query_map(params![run_id], |row| row.get(0))
+        .map_err(|e| format!("query run orders: {e}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read run orders: {e}"))?;
+    Ok(run)
+}
+
+/// Load a run that is still `out`, for completion.
+pub fn load_open_run(conn: &Connection, run_id: &str) -> Result<DriverRun, String> {
+    let run = load_run(conn, run_id)?;
+    if run.status != "out" {
+        return Err(format!("Run {run_id} has already returned"));
+    }
+    Ok(run)
+}
+
+/// Record the cash declared for one order of the run as delivered.
+pub fn record_delivery(
+    conn: &Connection,
+    run_id: &str,
+    order_id: &str,
+    cash_collected: Option<f64>,
+    now: &str,
+) -> Result<(), String> {
+    let cash = cash_collected.map(Cents::round_half_even);
+    conn.execute(
+        "UPDATE driver_run_orders
+         SET cash_collected = ?3, cash_collected_cents = ?4, delivered_at = ?5
+         WHERE run_id = ?1 AND order_id = ?2",
+        params![
+            run_id,
+            order_id,
+            cash.map(|cents| cents.to_f64_dp2()),
+            cash.map(|cents| cents.as_i64()),
+            now,
+        ],
+    )
+    .map_err(|e| format!("record run delivery: {e}"))?;
+    Ok(())
+}
+
+/// Close the run; the driver is back and free for the next one.
+pub fn mark_returned(
+    conn: &Connection,
+    run_id: &str,
+    actor: Option<&str>,
+    now: &str,
+) -> Result<(), String> {
+    let updated = conn
+        .execute(
+            "UPDATE driver_runs
+             SET status = 'returned', returned_at = ?2, completed_by = ?3, updated_at = ?2
+             WHERE id = ?1 AND status = 'out'",
+            params![run_id, now, actor],
+        )
+        .map_err(|e| format!("mark run returned: {e}"))?;
+    if updated == 0 {
+        return Err(format!("Run {run_id} has already returned"));
+    }
+    Ok(())
+}
+
+fn order_summary(row: &rusqlite::Row<'_>) -> rusqlite::Result<Value> {
+    Ok(json!({
+        "id": row.get::<_, String>(0)?,
+        "orderNumber": row.get::<_, Option<String>>(1)?,
+        "status": row.get::<_, Option<String>>(2)?,
+        "customerName": row.get::<_, Option<String>>(3)?,
+        "deliveryAddress": row.get::<_, Option<String>>(4)?,
+        "deliveryPostalCode": row.get::<_, Option<String>>(5)?,
+        "deliveryZoneId": row.get::<_, Option<String>>(6)?,
+        "totalAmount": row.get::<_, Option<f64>>(7)?.unwrap_or(0.0),
+        "readyAt": row.get::<_, Option<String>>(8)?,
+    }))
+}
+
+const ORDER_SUMMARY_COLUMNS: &str = "o.id, COALESCE(o.display_order_number, o.order_number),
+    o.status, o.customer_name, o.delivery_address, o.delivery_postal_code,
+    o.delivery_zone_id, o.total_amount, o.updated_at";
+
+/// Route key for grouping unassigned orders: the delivery zone when the
+/// order has one, else its postal code.
+fn route_key(order: &Value) -> String {
+    ["deliveryZoneId", "deliveryPostalCode"]
+        .iter()
+        .filter_map(|key| order[*key].as_str())
+        .map(str::trim)
+        .find(|value| !value.is_empty())
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "unrouted".to_string())
+}
+
+/// Dispatcher view: drivers on shift and whether they are out, runs that
+/// are out with their orders, ready delivery orders nobody carries yet
+/// grouped by route, and the cash each driver still has to hand back.
+/// `branch_id` is the resolved report scope; runs without a branch show
+/// on every branch.
+pub fn board(conn: &Connection, branch_id: &str) -> Result<Value, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id FROM driver_runs
+             WHERE status = 'out' AND COALESCE(branch_id, '') IN ('', ?1)
+             ORDER BY started_at",
+        )
+        .map_err(|e| format!("prepare active runs: {e}"))?;
+    let run_ids = stmt
+        .query_map(params![branch_id], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("query active runs: {e}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read active runs: {e}"))?;
+
+    let order_sql = format!(
+        "SELECT {ORDER_SUMMARY_COLUMNS}
+         FROM driver_run_orders ro
+         JOIN orders o ON o.id = ro.order_id
+         WHERE ro.run_id = ?1
+         ORDER BY ro.position"
+    );
+    let mut order_stmt = conn
+        .prepare(&order_sql)
+        .map_err(|e| format!("prepare run orders: {e}"))?;
+    let mut runs = Vec::with_capacity(run_ids.len());
+    for run_id in &run_ids {
+        let run = load_run(conn, run_id)?;
+        let orders = order_stmt
+            .query_map(params![run_id], order_summary)
+            .map_err(|e| format!("query run orders: {e}"))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("read run orders: {e}"))?;
+        let mut entry = run.to_json();
+        entry["orders"] = json!(orders);
+        runs.push(entry);
+    }
+
+    let unassigned_sql = format!(
+        "SELECT {ORDER_SUMMARY_COLUMNS}
+         FROM orders o
+         WHERE o.order_type = 'delivery'
+           AND LOWER(COALESCE(o.status, '')) = 'ready'
+           AND TRIM(COALESCE(o.driver_id, '')) = ''
+           AND COALESCE(o.is_ghost, 0) = 0
+           AND COALESCE(o.branch_id, '') = ?1
+           AND NOT EXISTS (
+               SELECT 1 FROM driver_run_orders ro
+               JOIN driver_runs r ON r.id = ro.run_id
+               WHERE ro.order_id = o.id AND r.status = 'out'
+           )
+         ORDER BY o.updated_at"
+    );
+    let mut unassigned_stmt = conn
+        .prepare(&unassigned_sql)
+        .map_err(|e| format!("prepare unassigned orders: {e}"))?;
+    let unassigned = unassigned_stmt
+        .query_map(params![branch_id], order_summary)
+        .map_err(|e| format!("query unassigned orders: {e}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read unassigned orders: {e}"))?;
+    let mut routes: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+    for order in &unassigned {
+        routes
+            .entry(route_key(order))
+            .or_default()
+            .push(order.clone());
+    }
+    let routes: Vec<Value> = routes
+        .into_iter()
+        .map(|(route, orders)| json!({ "route": route, "orderCount": orders.len(), "orders": orders }))
+        .collect();
+
+    let mut cash_stmt = conn
+        .prepare(
+            "SELECT driver_id,
+                    COALESCE(SUM(COALESCE(cash_to_return_cents,
+                        CAST(ROUND(COALESCE(cash_to_return, 0) * 100) AS INTEGER))), 0),
+                    COUNT(*)
+             FROM driver_earnings
+             WHERE COALESCE(settled, 0) = 0
+               AND COALESCE(branch_id, '') = ?1
+             GROUP BY driver_id
+             ORDER BY driver_id",
+        )
+        .map_err(|e| format!("prepare open driver cash: {e}"))?;
+    let open_cash = cash_stmt
+        .query_map(params![branch_id], |row| {
+            Ok(json!({
+                "driverId": row.get::<_, String>(0)?,
+                "openCash": Cents::from(row.get::<_, i64>(1)?).to_f64_dp2(),
+                "unsettledEarnings": row.get::<_, i64>(2)?,
+            }))
+        })
+        .map_err(|e| format!("query open driver cash: {e}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read open driver cash: {e}"))?;
+
+    let mut driver_stmt = conn
+        .prepare(
+            "SELECT ss.staff_id, MAX(ss.staff_name), r.id,
+                    (SELECT COUNT(*) FROM driver_run_orders ro WHERE ro.run_id = r.id)
+             FROM staff_shifts ss
+             LEFT JOIN driver_runs r ON r.driver_id = ss.staff_id AND r.status = 'out'
+             WHERE ss.role_type = 'driver' AND ss.status = 'active'
+               AND COALESCE(ss.branch_id, '') = ?1
+             GROUP BY ss.staff_id
+             ORDER BY MIN(ss.check_in_time)",
+        )
+        .map_err(|e| format!("prepare dispatch drivers: {e}"))?;
+    let drivers = driver_stmt
+        .query_map(params![branch_id], |row| {
+            let run_id: Option<String> = row.get(2)?;
+            Ok(json!({
+                "driverId": row.get::<_, String>(0)?,
+                "driverName": row.get::<_, Option<String>>(1)?,
+                "status": if run_id.is_some() { "out" } else { "available" },
+                "runId": run_id,
+                "carrying": row.get::<_, i64>(3)?,
+            }))
+        })
+        .map_err(|e| format!("query dispatch drivers: {e}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read dispatch drivers: {e}"))?;
+
+    Ok(json!({
+        "drivers": drivers,
+        "activeRuns": runs,
+        "unassignedOrders": unassigned,
+        "routes": routes,
+        "driverCash": open_cash,
+        "capacity": run_capacity(conn),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: &str = "2026-05-04T12:00:00Z";
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn insert_order(conn: &Connection, id: &str, status: &str, postal_code: &str) {
+        conn.execute(
+            "INSERT INTO orders (
+                 id, items, total_amount, status, order_type, branch_id,
+                 delivery_postal_code, sync_status, created_at, updated_at
+             ) VALUES (?1, '[]', 10.0, ?2, 'delivery', 'branch-1', ?3, 'pending', ?4, ?4)",
+            params![id, status, postal_code, NOW],
+        )
+        .expect("insert order");
+    }
+
+    fn new_run<'a>(driver_id: &'a str, order_ids: &'a [String]) -> NewRun<'a> {
+        NewRun {
+            driver_id,
+            driver_name: Some("Nikos"),
+            driver_shift_id: None,
+            branch_id: Some("branch-1"),
+            order_ids,
+            actor: None,
+        }
+    }
+
+    #[test]
+    fn start_run_takes_ready_orders_and_rejects_orders_on_another_run() {
+        let conn = test_conn();
+        insert_order(&conn, "o-1", "ready", "10431");
+        insert_order(&conn, "o-2", "ready", "10431");
+        insert_order(&conn, "o-3", "preparing", "10432");
+
+        let ids = vec!["o-1".to_string(), "o-3".to_string()];
+        let err = start_run(&conn, &new_run("d-1", &ids), NOW).unwrap_err();
+        assert!(err.contains("only ready orders"), "{err}");
+        assert_eq!(active_run_for_order(&conn, "o-1").unwrap(), None);
+
+        let ids = vec!["o-1".to_string(), "o-1".to_string()];
+        let run = start_run(&conn, &new_run("d-1", &ids), NOW).unwrap();
+        assert_eq!(run.order_ids, vec!["o-1".to_string()]);
+        let status: String = conn
+            .query_row("SELECT status FROM orders WHERE id = 'o-1'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(status, "out_for_delivery");
+
+        let ids = vec!["o-1".to_string(), "o-2".to_string()];
+        let err = start_run(&conn, &new_run("d-2", &ids), NOW).unwrap_err();
+        assert!(err.contains(&run.id), "{err}");
+        let ids = vec!["o-2".to_string()];
+        let err = start_run(&conn, &new_run("d-1", &ids), NOW).unwrap_err();
+        assert!(err.contains("already out"), "{err}");
+
+        record_delivery(&conn, &run.id, "o-1", Some(12.345), NOW).unwrap();
+        mark_returned(&conn, &run.id, None, NOW).unwrap();
+        assert!(load_open_run(&conn, &run.id).is_err());
+        let cents: i64 = conn
+            .query_row(
+                "SELECT cash_collected_cents FROM driver_run_orders WHERE order_id = 'o-1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(cents, 1234);
+    }
+
+    #[test]
+    fn board_groups_unassigned_orders_by_route_and_sums_open_cash() {
+        let conn = test_conn();
+        insert_order(&conn, "o-1", "ready", "10431");
+        insert_order(&conn, "o-2", "ready", "10431");
+        insert_order(&conn, "o-3", "ready", "");
+        insert_order(&conn, "o-4", "ready", "10555");
+        insert_order(&conn, "old-1", "delivered", "10431");
+        insert_order(&conn, "old-2", "delivered", "10431");
+        conn.execute(
+            "INSERT INTO driver_earnings (
+                 id, driver_id, order_id, branch_id, total_earning, payment_method,
+                 cash_to_return, settled, created_at, updated_at
+             ) VALUES ('e-1', 'd-1', 'old-1', 'branch-1', 2.0, 'cash', 7.5, 0, ?1, ?1),
+                      ('e-2', 'd-1', 'old-2', 'branch-1', 2.0, 'cash', 3.0, 1, ?1, ?1)",
+            params![NOW],
+        )
+        .unwrap();
+        let ids = vec!["o-4".to_string()];
+        start_run(&conn, &new_run("d-1", &ids), NOW).unwrap();
+
+        let board = board(&conn, "branch-1").unwrap();
+        assert_eq!(board["activeRuns"].as_array().unwrap().len(), 1);
+        assert_eq!(board["activeRuns"][0]["orders"][0]["id"], json!("o-4"));
+        assert_eq!(board["unassignedOrders"].as_array().unwrap().len(), 3);
+        assert_eq!(board["routes"][0]["route"], json!("10431"));
+        assert_eq!(board["routes"][0]["orderCount"], json!(2));
+        assert_eq!(board["routes"][1]["route"], json!("unrouted"));
+        assert_eq!(board["driverCash"][0]["openCash"], json!(7.5));
+        assert_eq!(board["driverCash"][0]["unsettledEarnings"], json!(1));
+    }
+}
@@ -284,10 +284,7 @@ mod tests {
         )
         .expect("pragma setup");
         db::run_migrations_for_test(&conn);
-        DbState {
-            conn: StdMutex::new(conn),
-            db_path: PathBuf::from(":memory:"),
-        }
+        DbState::new(conn, PathBuf::from(":memory:"))
     }
 
     #[test]
@@ -0,0 +1,601 @@
+//! Cash-in / cash-out (paid-in / paid-out) drawer transactions.
+//!
+//! Managers add change to the drawer mid-shift or move excess cash to the
+//! safe. These used to be booked as shift expenses, which distorted the
+//! expense report. `drawer_cash_in` and `drawer_cash_out` now write a
+//! `drawer_transactions` row against the active cash drawer session, with a
+//! reason code from the `drawer_cash_in` / `drawer_cash_out` lists, and keep
+//! the session's running totals in `cash_in` and `cash_drops`. The
+//! expected-cash formula adds the first and subtracts the second, and both
+//! show up as their own lines in the drawer reconciliation and Z-report.
+//!
+//! A cash-out above `cash.cash_out_approval_threshold` needs manager
+//! approval. Each transaction prints a chit on the receipt printer and is
+//! queued for sync so head office sees safe drops.
+
+use chrono::{DateTime, Local};
+use rusqlite::{params, Connection, OptionalExtension, ToSql};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::escpos::EscPosBuilder;
+use crate::money::Cents;
+use crate::reasons::{self, ReasonAction};
+use crate::receipt_renderer::{self, EscPosBodyMode, EscPosRender, LayoutConfig};
+use crate::{db, sync_queue, value_f64, value_str};
+
+/// `print_jobs.entity_type` of a drawer transaction chit.
+pub const PRINT_ENTITY_TYPE: &str = "drawer_transaction";
+const SETTINGS_CATEGORY: &str = "cash";
+const APPROVAL_THRESHOLD_KEY: &str = "cash_out_approval_threshold";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    In,
+    Out,
+}
+
+impl Direction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::In => "cash_in",
+            Self::Out => "cash_out",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "cash_in" => Some(Self::In),
+            "cash_out" => Some(Self::Out),
+            _ => None,
+        }
+    }
+
+    fn reason_action(self) -> ReasonAction {
+        match self {
+            Self::In => ReasonAction::DrawerCashIn,
+            Self::Out => ReasonAction::DrawerCashOut,
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::In => "CASH IN",
+            Self::Out => "CASH OUT",
+        }
+    }
+}
+
+/// Cash-outs above this amount need manager approval; `None` when unset.
+pub fn approval_threshold(conn: &Connection) -> Option<Cents> {
+    db::get_setting(conn, SETTINGS_CATEGORY, APPROVAL_THRESHOLD_KEY)
+        .and_then(|raw| raw.trim().parse::<f64>().ok())
+        .filter(|value| value.is_finite() && *value > 0.0)
+        .map(Cents::round_half_even)
+}
+
+pub fn needs_approval(conn: &Connection, direction: Direction, amount: Cents) -> bool {
+    direction == Direction::Out
+        && approval_threshold(conn).is_some_and(|threshold| amount > threshold)
+}
+
+/// A cash-in or cash-out as submitted by the operator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub shift_id: String,
+    pub amount: Cents,
+    pub reason_code: Option<String>,
+    pub note: Option<String>,
+}
+
+impl Request {
+    pub fn from_payload(payload: &Value) -> Result<Self, String> {
+        let shift_id = value_str(payload, &["shiftId", "shift_id"]).ok_or("Missing shiftId")?;
+        let amount = value_f64(payload, &["amount"]).ok_or("Missing amount")?;
+        if !amount.is_finite() || amount <= 0.0 {
+            return Err("Amount must be positive".into());
+        }
+        Ok(Self {
+            shift_id,
+            amount: Cents::round_half_even(amount),
+            reason_code: value_str(payload, &["reasonCode", "reason_code"]),
+            note: value_str(payload, &["note", "notes"]),
+        })
+    }
+}
+
+struct DrawerSession {
+    id: String,
+    branch_id: String,
+    terminal_id: String,
+    shift_staff_id: String,
+}
+
+fn active_session(conn: &Connection, shift_id: &str) -> Result<DrawerSession, String> {
+    conn.query_row(
+        "SELECT cds.id, cds.branch_id, cds.terminal_id, ss.staff_id
+         FROM cash_drawer_sessions cds
+         JOIN staff_shifts ss ON ss.id = cds.staff_shift_id
+         WHERE cds.staff_shift_id = ?1
+           AND ss.status = 'active'
+           AND cds.closed_at IS NULL",
+        params![shift_id],
+        |row| {
+            Ok(DrawerSession {
+                id: row.get(0)?,
+                branch_id: row.get(1)?,
+                terminal_id: row.get(2)?,
+                shift_staff_id: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("load cash drawer session: {e}"))?
+    .ok_or_else(|| format!("No open cash drawer for shift {shift_id}"))
+}
+
+/// Record the transaction, move the session's running total and queue it
+/// for sync. The caller owns the surrounding database transaction.
+pub fn record(
+    conn: &Connection,
+    direction: Direction,
+    request: &Request,
+    staff_id: Option<&str>,
+    approved_by: Option<&str>,
+    now: &str,
+) -> Result<Value, String> {
+    let reason = reasons::require(
+        conn,
+        direction.reason_action(),
+        request.reason_code.as_deref(),
+        None,
+    )?;
+    let session = active_session(conn, &request.shift_id)?;
+    let staff_id = staff_id.unwrap_or(&session.shift_staff_id);
+    let id = Uuid::new_v4().to_string();
+    let amount = request.amount.to_f64_dp2();
+    let amount_cents = request.amount.as_i64();
+
+    conn.execute(
+        "INSERT INTO drawer_transactions (
+            id, cash_drawer_session_id, staff_shift_id, transaction_type,
+            amount, amount_cents, reason_code, reason_label, note, staff_id,
+            approved_by, branch_id, terminal_id, idempotency_key, sync_status,
+            created_at, updated_at
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, 'pending', ?15, ?15)",
+        params![
+            id,
+            session.id,
+            request.shift_id,
+            direction.as_str(),
+            amount,
+            amount_cents,
+            reason.code,
+            reason.label,
+            request.note,
+            staff_id,
+            approved_by,
+            session.branch_id,
+            session.terminal_id,
+            Uuid::new_v4().to_string(),
+            now,
+        ],
+    )
+    .map_err(|e| format!("insert drawer transaction: {e}"))?;
+
+    // W4c dual-write: the running total and its cents sibling.
+    let total_sql = match direction {
+        Direction::In => {
+            "UPDATE cash_drawer_sessions SET
+                cash_in = COALESCE(cash_in, 0) + ?1,
+                cash_in_cents = COALESCE(cash_in_cents, CAST(ROUND(COALESCE(cash_in, 0) * 100) AS INTEGER)) + ?2,
+                updated_at = ?3
+             WHERE id = ?4"
+        }
+        Direction::Out => {
+            "UPDATE cash_drawer_sessions SET
+                cash_drops = COALESCE(cash_drops, 0) + ?1,
+                cash_drops_cents = COALESCE(cash_drops_cents, CAST(ROUND(COALESCE(cash_drops, 0) * 100) AS INTEGER)) + ?2,
+                updated_at = ?3
+             WHERE id = ?4"
+        }
+    };
+    conn.execute(total_sql, params![amount, amount_cents, now, session.id])
+        .map_err(|e| format!("update drawer {} total: {e}", direction.as_str()))?;
+
+    let sync_payload = json!({
+        "id": id,
+        "cashDrawerSessionId": session.id,
+        "shiftId": request.shift_id,
+        "transactionType": direction.as_str(),
+        "amount": amount,
+        "amount_cents": amount_cents,
+        "reasonCode": reason.code,
+        "note": request.note,
+        "staffId": staff_id,
+        "approvedBy": approved_by,
+        "branchId": session.branch_id,
+        "terminalId": session.terminal_id,
+        "createdAt": now,
+    });
+    sync_queue::enqueue_payload_item(
+        conn,
+        "drawer_transactions",
+        &id,
+        "INSERT",
+        &sync_payload,
+        Some(1),
+        Some("financial"),
+        Some("manual"),
+        Some(1),
+    )
+    .map_err(|e| format!("enqueue drawer transaction sync: {e}"))?;
+
+    let mut transaction = load(conn, &id)?.ok_or("Drawer transaction vanished after insert")?;
+    transaction["drawer"] = session_totals(conn, &session.id)?;
+    Ok(transaction)
+}
+
+fn row_to_json(row: &rusqlite::Row<'_>) -> rusqlite::Result<Value> {
+    let amount_cents = row.get::<_, i64>(4)?;
+    Ok(json!({
+        "id": row.get::<_, String>(0)?,
+        "cashDrawerSessionId": row.get::<_, String>(1)?,
+        "shiftId": row.get::<_, String>(2)?,
+        "transactionType": row.get::<_, String>(3)?,
+        "amount": Cents::new(amount_cents).to_f64_dp2(),
+        "amount_cents": amount_cents,
+        "reasonCode": row.get::<_, String>(5)?,
+        "reasonLabel": row.get::<_, Option<String>>(6)?,
+        "note": row.get::<_, Option<String>>(7)?,
+        "staffId": row.get::<_, Option<String>>(8)?,
+        "approvedBy": row.get::<_, Option<String>>(9)?,
+        "createdAt": row.get::<_, String>(10)?,
+    }))
+}
+
+const SELECT_COLUMNS: &str = "id, cash_drawer_session_id, staff_shift_id, transaction_type,
+    amount_cents, reason_code, reason_label, note, staff_id, approved_by, created_at";
+
+pub fn load(conn: &Connection, id: &str) -> Result<Option<Value>, String> {
+    conn.query_row(
+        &format!("SELECT {SELECT_COLUMNS} FROM drawer_transactions WHERE id = ?1"),
+        params![id],
+        row_to_json,
+    )
+    .optional()
+    .map_err(|e| format!("load drawer transaction: {e}"))
+}
+
+/// Transactions of one shift's drawer, oldest first.
+pub fn list_for_shift(conn: &Connection, shift_id: &str) -> Result<Vec<Value>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM drawer_transactions
+             WHERE staff_shift_id = ?1
+             ORDER BY created_at ASC"
+        ))
+        .map_err(|e| format!("prepare drawer transactions: {e}"))?;
+    let rows = stmt
+        .query_map(params![shift_id], row_to_json)
+        .map_err(|e| format!("query drawer transactions: {e}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read drawer transactions: {e}"))?;
+    Ok(rows)
+}
+
+/// Running cash-in / cash-out totals of a drawer session.
+fn session_totals(conn: &Connection, session_id: &str) -> Result<Value, String> {
+    conn.query_row(
+        "SELECT COALESCE(cash_in_cents, CAST(ROUND(cash_in * 100) AS INTEGER), 0),
+                COALESCE(cash_drops_cents, CAST(ROUND(cash_drops * 100) AS INTEGER), 0)
+         FROM cash_drawer_sessions WHERE id = ?1",
+        params![session_id],
+        |row| {
+            let cash_in = row.get::<_, i64>(0)?;
+            let cash_out = row.get::<_, i64>(1)?;
+            Ok(json!({
+                "id": session_id,
+                "cashIn": Cents::new(cash_in).to_f64_dp2(),
+                "cash_in_cents": cash_in,
+                "cashOut": Cents::new(cash_out).to_f64_dp2(),
+                "cash_out_cents": cash_out,
+            }))
+        },
+    )
+    .map_err(|e| format!("load drawer cash movement totals: {e}"))
+}
+
+/// Cash-in / cash-out counts and totals for the Z-report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrawerTransactionTotals {
+    pub cash_in_count: i64,
+    pub cash_in_cents: i64,
+    pub cash_out_count: i64,
+    pub cash_out_cents: i64,
+}
+
+impl DrawerTransactionTotals {
+    /// Sum over the transactions selected by `scope`, a
+    /// `FROM drawer_transactions dt WHERE ...` clause bound to `params`.
+    pub fn collect(conn: &Connection, scope: &str, params: &[&dyn ToSql]) -> Self {
+        let sql = format!(
+            "SELECT COUNT(CASE WHEN dt.transaction_type = 'cash_in' THEN 1 END),
+                    COALESCE(SUM(CASE WHEN dt.transaction_type = 'cash_in' THEN dt.amount_cents END), 0),
+                    COUNT(CASE WHEN dt.transaction_type = 'cash_out' THEN 1 END),
+                    COALESCE(SUM(CASE WHEN dt.transaction_type = 'cash_out' THEN dt.amount_cents END), 0)
+             {scope}"
+        );
+        conn.query_row(&sql, params, |row| {
+            Ok(Self {
+                cash_in_count: row.get(0)?,
+                cash_in_cents: row.get(1)?,
+                cash_out_count: row.get(2)?,
+                cash_out_cents: row.get(3)?,
+            })
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn to_json(self) -> Value {
+        json!({
+            "cashInCount": self.cash_in_count,
+            "cashIn": Cents::new(self.cash_in_cents).to_f64_dp2(),
+            "cashIn_cents": self.cash_in_cents,
+            "cashOutCount": self.cash_out_count,
+            "cashOut": Cents::new(self.cash_out_cents).to_f64_dp2(),
+            "cashOut_cents": self.cash_out_cents,
+            "net": Cents::new(self.cash_in_cents - self.cash_out_cents).to_f64_dp2(),
+            "net_cents": self.cash_in_cents - self.cash_out_cents,
+        })
+    }
+}
+
+/// Text lines of the chit for transaction `id`; the first is the title.
+pub fn chit_lines(conn: &Connection, id: &str, currency: &str) -> Result<Vec<String>, String> {
+    let transaction =
+        load(conn, id)?.ok_or_else(|| format!("Drawer transaction not found: {id}"))?;
+    let direction = transaction["transactionType"]
+        .as_str()
+        .and_then(Direction::parse)
+        .ok_or("Drawer transaction has an unknown type")?;
+    let staff_name: Option<String> = conn
+        .query_row(
+            "SELECT staff_name FROM staff_shifts WHERE id = ?1",
+            params![transaction["shiftId"].as_str()],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("load drawer transaction shift: {e}"))?
+        .flatten();
+    let created_at = transaction["createdAt"].as_str().unwrap_or_default();
+    let printed_at = DateTime::parse_from_rfc3339(created_at)
+        .map(|at| {
+            at.with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|_| created_at.to_string());
+
+    let mut lines = vec![
+        direction.title().to_string(),
+        String::new(),
+        format!(
+            "Amount: {currency}{:.2}",
+            transaction["amount"].as_f64().unwrap_or(0.0)
+        ),
+        format!(
+            "Reason: {}",
+            transaction["reasonLabel"]
+                .as_str()
+                .or(transaction["reasonCode"].as_str())
+                .unwrap_or_default()
+        ),
+    ];
+    if let Some(note) = transaction["note"].as_str() {
+        lines.push(format!("Note: {note}"));
+    }
+    if let Some(name) = staff_name.as_deref() {
+        lines.push(format!("Drawer: {name}"));
+    }
+    if let Some(staff) = transaction["staffId"].as_str() {
+        lines.push(format!("Staff: {staff}"));
+    }
+    if let Some(approver) = transaction["approvedBy"].as_str() {
+        lines.push(format!("Approved by: {approver}"));
+    }
+    lines.push(printed_at);
+    lines.push(String::new());
+    lines.push("Signature: ____________________".to_string());
+    Ok(lines)
+}
+
+pub fn render_chit_escpos(lines: &[String], cfg: &LayoutConfig, cut: bool) -> EscPosRender {
+    let use_star_commands =
+        receipt_renderer::uses_star_commands(cfg.detected_brand, cfg.emulation_mode);
+    let mut builder = if use_star_commands {
+        EscPosBuilder::new()
+            .with_paper(cfg.paper_width)
+            .with_star_line_mode()
+    } else {
+        EscPosBuilder::new().with_paper(cfg.paper_width)
+    };
+    builder.init();
+    let warnings = receipt_renderer::apply_character_set(
+        &mut builder,
+        &cfg.character_set,
+        cfg.greek_render_mode.as_deref(),
+        cfg.escpos_code_page,
+        use_star_commands,
+    );
+
+    for (index, line) in lines.iter().enumerate() {
+        if index == 0 {
+            builder.center().bold(true).text_size(2, 2);
+            builder.text(line).lf();
+            builder.text_size(1, 1).bold(false).left();
+        } else {
+            builder.text(line).lf();
+        }
+    }
+    builder.feed(3);
+    if cut {
+        if use_star_commands {
+            builder.star_cut();
+        } else {
+            builder.cut();
+        }
+    }
+
+    let text = builder.plain_text();
+    EscPosRender {
+        bytes: builder.build(),
+        warnings,
+        body_mode: EscPosBodyMode::Text,
+        text,
+    }
+}
+
+pub fn render_chit_html(lines: &[String]) -> String {
+    let mut body = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        let text = receipt_renderer::esc(line);
+        if index == 0 {
+            body.push_str(&format!("<div class=\"big\">{text}</div>"));
+        } else {
+            body.push_str(&format!("<div>{text}</div>"));
+        }
+    }
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="UTF-8"/>
+<title>Drawer transaction</title>
+<style>
+  body {{ margin: 0; padding: 10px; background: #fff; font-family: monospace; font-size: 13px; white-space: pre; }}
+  .big {{ font-size: 22px; font-weight: bold; text-align: center; }}
+</style>
+</head>
+<body>{body}</body>
+</html>"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        conn.execute(
+            "INSERT INTO staff_shifts (id, staff_id, staff_name, branch_id, terminal_id, role_type,
+                                       check_in_time, status, sync_status, created_at, updated_at)
+             VALUES ('shift-1', 'staff-1', 'Maria', 'branch-1', 'term-1', 'cashier',
+                     '2026-10-16T08:00:00Z', 'active', 'pending', '2026-10-16T08:00:00Z', '2026-10-16T08:00:00Z')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO cash_drawer_sessions (id, staff_shift_id, cashier_id, branch_id, terminal_id,
+                                               opening_amount, opening_amount_cents, opened_at, created_at, updated_at)
+             VALUES ('drawer-1', 'shift-1', 'staff-1', 'branch-1', 'term-1',
+                     100, 10000, '2026-10-16T08:00:00Z', '2026-10-16T08:00:00Z', '2026-10-16T08:00:00Z')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn request(amount: f64, reason: &str) -> Request {
+        Request::from_payload(&json!({
+            "shiftId": "shift-1",
+            "amount": amount,
+            "reasonCode": reason,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn cash_in_and_out_move_the_drawer_totals_and_queue_sync() {
+        let conn = test_conn();
+        let now = "2026-10-16T10:00:00Z";
+        record(
+            &conn,
+            Direction::In,
+            &request(40.0, "change_run"),
+            None,
+            None,
+            now,
+        )
+        .unwrap();
+        let out = record(
+            &conn,
+            Direction::Out,
+            &request(150.0, "safe_drop"),
+            Some("staff-1"),
+            Some("manager-1"),
+            now,
+        )
+        .unwrap();
+        assert_eq!(out["approvedBy"], "manager-1");
+        assert_eq!(out["drawer"]["cash_in_cents"], 4000);
+        assert_eq!(out["drawer"]["cash_out_cents"], 15000);
+
+        let expenses: f64 = conn
+            .query_row(
+                "SELECT COALESCE(total_expenses, 0) FROM cash_drawer_sessions WHERE id = 'drawer-1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(expenses, 0.0);
+        let queued: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM parity_sync_queue WHERE table_name = 'drawer_transactions'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(queued, 2);
+
+        let totals = DrawerTransactionTotals::collect(
+            &conn,
+            "FROM drawer_transactions dt WHERE dt.staff_shift_id = ?1",
+            &[&"shift-1"],
+        );
+        assert_eq!(totals.cash_in_cents, 4000);
+        assert_eq!(totals.cash_out_count, 1);
+        assert_eq!(list_for_shift(&conn, "shift-1").unwrap().len(), 2);
+
+        let lines = chit_lines(&conn, out["id"].as_str().unwrap(), "€").unwrap();
+        assert_eq!(lines[0], "CASH OUT");
+        assert!(lines.contains(&"Amount: €150.00".to_string()));
+        assert!(lines.contains(&"Reason: Safe drop".to_string()));
+
+        assert!(
+            record(
+                &conn,
+                Direction::In,
+                &request(5.0, "safe_drop"),
+                None,
+                None,
+                now
+            )
+            .is_err(),
+            "cash-out reasons are not valid for a cash-in"
+        );
+    }
+
+    #[test]
+    fn only_cash_out_above_the_threshold_needs_approval() {
+        let conn = test_conn();
+        let amount = Cents::new(20_000);
+        assert!(!needs_approval(&conn, Direction::Out, amount));
+        db::set_setting(&conn, SETTINGS_CATEGORY, APPROVAL_THRESHOLD_KEY, "150").unwrap();
+        assert!(needs_approval(&conn, Direction::Out, amount));
+        assert!(!needs_approval(&conn, Direction::Out, Cents::new(15_000)));
+        assert!(!needs_approval(&conn, Direction::In, amount));
+    }
+}
@@ -196,12 +196,16 @@ fn build_initialized_protocol(
 /// ECR traffic through a single lock.
 pub struct DeviceManager {
     devices: Mutex<HashMap<String, DeviceHandle>>,
+    /// Capabilities captured at connect, kept outside the device mutex so
+    /// they can be read while a transaction holds the device.
+    capabilities: Mutex<HashMap<String, EcrCapabilities>>,
 }
 
 impl DeviceManager {
     pub fn new() -> Self {
         Self {
             devices: Mutex::new(HashMap::new()),
+            capabilities: Mutex::new(HashMap::new()),
         }
     }
 
@@ -226,6 +230,7 @@ impl DeviceManager {
             protocol_display_name,
         } = init;
 
+        let capabilities = protocol.capabilities();
         // Store managed device behind its own Mutex
         let handle: DeviceHandle = Arc::new(Mutex::new(ManagedDevice {
             device_id: device_id.to_string(),
@@ -235,6 +240,9 @@ impl DeviceManager {
             let mut devices = self.devices.lock().map_err(|e| e.to_string())?;
             devices.insert(device_id.to_string(), handle);
         }
+        if let Ok(mut caps) = self.capabilities.lock() {
+            caps.insert(device_id.to_string(), capabilities);
+        }
 
         info!(
             "Device {device_id} connected ({protocol_display_name} via {transport_description}, initial transport state: {initial_transport_state:?})"
@@ -328,6 +336,9 @@ impl DeviceManager {
             let mut devices = self.devices.lock().map_err(|e| e.to_string())?;
             devices.remove(device_id)
         };
+        if let Ok(mut caps) = self.capabilities.lock() {
+            caps.remove(device_id);
+        }
         if let Some(handle) = removed {
             abort_removed_device(handle);
         }
@@ -572,6 +583,17 @@ impl DeviceManager {
             .unwrap_or(false)
     }
 
+    /// Capabilities of a connected device, or `None` if it is not managed.
+    pub fn capabilities(&self, device_id: &str) -> Option<EcrCapabilities> {
+        if !self.is_connected(device_id) {
+            return None;
+        }
+        self.capabilities
+            .lock()
+            .ok()
+            .and_then(|caps| caps.get(device_id).copied())
+    }
+
     /// List all connected device IDs.
     pub fn connected_device_ids(&self) -> Vec<String> {
         self.devices
@@ -580,6 +602,18 @@ impl DeviceManager {
             .unwrap_or_default()
     }
 
+    /// Whether any connected device is mid-transaction. Uses the same
+    /// `try_lock` probe as [`get_device_status`](Self::get_device_status),
+    /// so it never waits on an in-flight card exchange.
+    pub fn any_transaction_in_progress(&self) -> bool {
+        self.connected_device_ids()
+            .iter()
+            .any(|id| match self.handle_for(id) {
+                Ok(Some(handle)) => matches!(handle.try_lock(), Err(TryLockError::WouldBlock)),
+                _ => false,
+            })
+    }
+
     /// Gracefully disconnect all devices (app shutdown).
     pub fn shutdown(&self) {
         let ids = self.connected_device_ids();
@@ -681,6 +715,13 @@ mod tests {
                 raw_response: None,
             })
         }
+        fn capabilities(&self) -> EcrCapabilities {
+            EcrCapabilities {
+                pre_auth: true,
+                pre_auth_completion: true,
+                ..EcrCapabilities::default()
+            }
+        }
         fn abort(&mut self) -> Result<(), String> {
             Ok(())
         }
@@ -787,6 +828,27 @@ mod tests {
         assert!(result.unwrap_err().contains("not connected"));
     }
 
+    #[test]
+    fn test_capabilities_follow_connect_and_disconnect() {
+        let mgr = DeviceManager::new();
+        mgr.register_connected_device(
+            "dev-1",
+            InitializedProtocol {
+                protocol: Box::new(StubProtocol),
+                transport_description: "stub".into(),
+                initial_transport_state: transport::TransportState::Connected,
+                protocol_display_name: "Stub".into(),
+            },
+        )
+        .unwrap();
+        let caps = mgr.capabilities("dev-1").expect("capabilities recorded");
+        assert!(caps.pre_auth && caps.pre_auth_completion);
+        assert!(!caps.incremental_auth);
+
+        mgr.disconnect_device("dev-1").unwrap();
+        assert_eq!(mgr.capabilities("dev-1"), None);
+    }
+
     #[test]
     fn test_get_status_disconnected_returns_default() {
         let mgr = DeviceManager::new();
@@ -826,9 +888,11 @@ mod tests {
         assert!(status.busy);
         assert!(!status.ready);
         assert!(status.error.is_none());
+        assert!(mgr.any_transaction_in_progress());
 
         release_tx.send(()).unwrap();
         holder.join().unwrap();
+        assert!(!mgr.any_transaction_in_progress());
 
         // Once the exchange is over, polls reach the protocol again.
         let status = mgr.get_device_status("dev-1").unwrap();
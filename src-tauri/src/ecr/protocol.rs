@@ -19,6 +19,9 @@ pub enum TransactionType {
     Void,
     PreAuth,
     PreAuthCompletion,
+    /// Raise an existing pre-authorization by `amount` (the increment, not
+    /// the new total).
+    IncrementalAuth,
     /// Fiscal receipt (cash register only — sends item-level data).
     FiscalReceipt,
     /// Fiscal Z-close (end-of-day).
@@ -132,6 +135,24 @@ pub struct TransactionResponse {
     pub completed_at: String,
 }
 
+// ---------------------------------------------------------------------------
+// Device capabilities
+// ---------------------------------------------------------------------------
+
+/// Card operations a driver supports beyond plain sales and refunds.
+///
+/// Callers check these before submitting pre-auth traffic so an
+/// unsupported driver fails with a clear error instead of a device
+/// round trip that can only decline.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EcrCapabilities {
+    pub pre_auth: bool,
+    pub pre_auth_completion: bool,
+    pub incremental_auth: bool,
+    pub void: bool,
+}
+
 // ---------------------------------------------------------------------------
 // Device status
 // ---------------------------------------------------------------------------
@@ -238,6 +259,15 @@ pub trait EcrProtocol: Send {
         Err(format!("{}: X-report not supported", self.name()))
     }
 
+    /// Card operations this driver supports beyond sales and refunds.
+    ///
+    /// Static per protocol; the [`DeviceManager`] reads it once at connect
+    /// so it can be queried while a transaction holds the device. The
+    /// default reports none.
+    fn capabilities(&self) -> EcrCapabilities {
+        EcrCapabilities::default()
+    }
+
     /// Abort any ongoing operation immediately.
     ///
     /// Unlike [`cancel_transaction`](EcrProtocol::cancel_transaction), this
@@ -19,6 +19,8 @@ const FS: u8 = 0x1C; // Field separator
 // Transaction types
 const PAX_SALE: &str = "01";
 const PAX_REFUND: &str = "02";
+const PAX_AUTH: &str = "03";
+const PAX_POSTAUTH: &str = "04";
 const PAX_VOID: &str = "16";
 const PAX_SETTLE: &str = "50";
 
@@ -203,6 +205,8 @@ impl EcrProtocol for PaxProtocol {
             TransactionType::Sale => PAX_SALE,
             TransactionType::Refund => PAX_REFUND,
             TransactionType::Void => PAX_VOID,
+            TransactionType::PreAuth => PAX_AUTH,
+            TransactionType::PreAuthCompletion => PAX_POSTAUTH,
             _ => {
                 return Err(format!(
                     "PAX does not support {:?}",
@@ -319,6 +323,17 @@ impl EcrProtocol for PaxProtocol {
         })
     }
 
+    /// T00 carries AUTH / POSTAUTH / VOID as plain transaction types; the
+    /// completion references the auth through `OrigRefNum`.
+    fn capabilities(&self) -> EcrCapabilities {
+        EcrCapabilities {
+            pre_auth: true,
+            pre_auth_completion: true,
+            incremental_auth: false,
+            void: true,
+        }
+    }
+
     fn abort(&mut self) -> Result<(), String> {
         let cancel_result = self.cancel_transaction();
         let disconnect_result = self.transport.disconnect();
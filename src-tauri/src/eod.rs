@@ -0,0 +1,643 @@
+//! End-of-day automation.
+//!
+//! At `eod.close_time` (local `HH:MM`) a background pass looks for open
+//! shifts and cash drawer sessions and emits `eod_reminder`. When
+//! `eod.auto_close` is enabled it also closes them (recording the computed
+//! expected cash, since nobody counted the drawer), generates the daily
+//! Z-report and queues its print. The close is refused — and an `eod_alert`
+//! emitted — while financial rows are still waiting to sync or a payment is
+//! in progress. Every pass, scheduled or manual, is written to `eod_runs`.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use tauri::{Emitter, Manager};
+use tracing::{info, warn};
+
+use crate::db::{self, DbState};
+use crate::fiscal::close_day_guard::{ensure_no_queued_fiscal_for_day, CloseBlockedError};
+use crate::{branches, payment_integrity, shifts, zreport};
+
+pub const SETTINGS_CATEGORY: &str = "eod";
+pub const CLOSE_TIME_KEY: &str = "close_time";
+pub const AUTO_CLOSE_KEY: &str = "auto_close";
+
+pub const TRIGGER_SCHEDULED: &str = "scheduled";
+pub const TRIGGER_MANUAL: &str = "manual";
+
+const SCHEDULER_INTERVAL_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EodConfig {
+    pub close_time: Option<NaiveTime>,
+    pub auto_close: bool,
+}
+
+fn parse_close_time(raw: &str) -> Option<NaiveTime> {
+    let raw = raw.trim();
+    NaiveTime::parse_from_str(raw, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(raw, "%H:%M:%S"))
+        .ok()
+}
+
+pub fn load_config(conn: &Connection) -> EodConfig {
+    EodConfig {
+        close_time: db::get_setting(conn, SETTINGS_CATEGORY, CLOSE_TIME_KEY)
+            .as_deref()
+            .and_then(parse_close_time),
+        auto_close: crate::print::setting_bool(conn, SETTINGS_CATEGORY, AUTO_CLOSE_KEY),
+    }
+}
+
+fn last_scheduled_date(conn: &Connection) -> Option<NaiveDate> {
+    conn.query_row(
+        "SELECT scheduled_for FROM eod_runs
+         WHERE trigger = ?1 AND scheduled_for IS NOT NULL
+         ORDER BY scheduled_for DESC LIMIT 1",
+        params![TRIGGER_SCHEDULED],
+        |row| row.get::<_, String>(0),
+    )
+    .optional()
+    .ok()
+    .flatten()
+    .and_then(|raw| NaiveDate::parse_from_str(&raw, "%Y-%m-%d").ok())
+}
+
+/// The business date whose scheduled pass is due at `now`, if any: the
+/// close time has passed today and today's pass has not run yet.
+pub fn due_scheduled_date(
+    config: &EodConfig,
+    now: NaiveDateTime,
+    last_scheduled: Option<NaiveDate>,
+) -> Option<NaiveDate> {
+    let close_time = config.close_time?;
+    let today = now.date();
+    (now.time() >= close_time && last_scheduled.map_or(true, |last| last < today)).then_some(today)
+}
+
+/// When the next scheduled pass will fire, in local time.
+pub fn next_scheduled_run(
+    config: &EodConfig,
+    now: NaiveDateTime,
+    last_scheduled: Option<NaiveDate>,
+) -> Option<NaiveDateTime> {
+    let close_time = config.close_time?;
+    let today = now.date();
+    let already_ran_today = last_scheduled.is_some_and(|last| last >= today);
+    if already_ran_today {
+        Some(today.succ_opt()?.and_time(close_time))
+    } else if now.time() >= close_time {
+        // Overdue: the scheduler picks it up on its next tick.
+        Some(now)
+    } else {
+        Some(today.and_time(close_time))
+    }
+}
+
+fn local_rfc3339(value: NaiveDateTime) -> String {
+    match Local.from_local_datetime(&value).earliest() {
+        Some(local) => local.to_rfc3339(),
+        None => value.format("%Y-%m-%dT%H:%M:%S").to_string(),
+    }
+}
+
+fn load_open_shifts(conn: &Connection, branch_id: &str) -> Result<Vec<Value>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, staff_id, COALESCE(NULLIF(TRIM(staff_name), ''), staff_id), role_type
+             FROM staff_shifts
+             WHERE status = 'active'
+               AND (branch_id = ?1 OR branch_id IS NULL)
+             ORDER BY check_in_time ASC, id ASC",
+        )
+        .map_err(|e| format!("prepare open shifts: {e}"))?;
+    let rows = stmt
+        .query_map(params![branch_id], |row| {
+            Ok(serde_json::json!({
+                "shiftId": row.get::<_, String>(0)?,
+                "staffId": row.get::<_, String>(1)?,
+                "staffName": row.get::<_, String>(2)?,
+                "roleType": row.get::<_, String>(3)?,
+            }))
+        })
+        .map_err(|e| format!("query open shifts: {e}"))?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("read open shifts: {e}"))
+}
+
+fn count_open_drawers(conn: &Connection, branch_id: &str) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM cash_drawer_sessions
+         WHERE closed_at IS NULL AND COALESCE(branch_id, '') = ?1",
+        params![branch_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("count open drawers: {e}"))
+}
+
+fn count_unsynced_financial_rows(conn: &Connection) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM parity_sync_queue
+         WHERE status NOT IN ('synced', 'applied')
+           AND (module_type = 'financial' OR table_name IN ('payments', 'payment_adjustments'))",
+        [],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("count unsynced financial rows: {e}"))
+}
+
+/// Reasons the day cannot be closed unattended. Empty means clear.
+pub fn close_blockers(
+    db: &DbState,
+    branch_id: &str,
+    today: NaiveDate,
+    ecr_busy: bool,
+) -> Result<Vec<Value>, String> {
+    let mut blockers = Vec::new();
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let unsynced = count_unsynced_financial_rows(&conn)?;
+        if unsynced > 0 {
+            blockers.push(serde_json::json!({
+                "code": "unsynced_financial",
+                "count": unsynced,
+                "message": format!("{unsynced} financial record(s) have not synced yet"),
+            }));
+        }
+        if !branch_id.is_empty() {
+            let business_day = today.format("%Y-%m-%d").to_string();
+            if let Err(CloseBlockedError::FiscalQueueNotEmpty { count, .. }) =
+                ensure_no_queued_fiscal_for_day(&conn, branch_id, &business_day)
+            {
+                blockers.push(serde_json::json!({
+                    "code": "fiscal_queue_pending",
+                    "count": count,
+                    "message": format!("{count} fiscal submission(s) are still pending"),
+                }));
+            }
+        }
+    }
+
+    let payment_blockers =
+        zreport::unsettled_payment_blockers(db, &serde_json::json!({ "branchId": branch_id }))?;
+    if !payment_blockers.is_empty() {
+        blockers.push(serde_json::json!({
+            "code": "payment_in_progress",
+            "count": payment_blockers.len(),
+            "message": payment_integrity::build_unsettled_payment_blocker_message(
+                "Cannot close the day",
+                &payment_blockers,
+            ),
+            "details": payment_blockers,
+        }));
+    }
+    if ecr_busy {
+        blockers.push(serde_json::json!({
+            "code": "payment_in_progress",
+            "count": 1,
+            "message": "A card terminal transaction is in progress",
+        }));
+    }
+    Ok(blockers)
+}
+
+pub struct RunRequest<'a> {
+    pub trigger: &'a str,
+    pub scheduled_for: Option<NaiveDate>,
+    pub auto_close: bool,
+    pub actor_staff_id: Option<&'a str>,
+    pub ecr_busy: bool,
+    pub now: NaiveDateTime,
+}
+
+fn is_cash_owner_role(role_type: &str) -> bool {
+    matches!(role_type, "cashier" | "manager")
+}
+
+/// Close every open shift, cash owners last so they inherit any driver
+/// returns. Stops at the first failure.
+fn close_open_shifts(
+    db: &DbState,
+    open_shifts: &[Value],
+    actions: &mut Vec<Value>,
+) -> Result<(), String> {
+    let mut ordered: Vec<&Value> = open_shifts.iter().collect();
+    ordered.sort_by_key(|shift| is_cash_owner_role(shift["roleType"].as_str().unwrap_or("")));
+    for shift in ordered {
+        let shift_id = shift["shiftId"].as_str().unwrap_or_default();
+        let result = shifts::close_shift(
+            db,
+            &serde_json::json!({ "shiftId": shift_id, "closingCashFromExpected": true }),
+        );
+        let failure = match &result {
+            Ok(value) if value.get("success").and_then(Value::as_bool) == Some(false) => Some(
+                value
+                    .get("error")
+                    .and_then(Value::as_str)
+                    .unwrap_or("Shift close was blocked")
+                    .to_string(),
+            ),
+            Ok(_) => None,
+            Err(error) => Some(error.clone()),
+        };
+        match failure {
+            None => {
+                let value = result.unwrap_or_default();
+                info!(shift_id = %shift_id, "End-of-day closed shift");
+                actions.push(serde_json::json!({
+                    "action": "close_shift",
+                    "shiftId": shift_id,
+                    "roleType": shift["roleType"],
+                    "success": true,
+                    "expected": value.get("expected"),
+                    "variance": value.get("variance"),
+                }));
+            }
+            Some(error) => {
+                warn!(shift_id = %shift_id, error = %error, "End-of-day shift close failed");
+                actions.push(serde_json::json!({
+                    "action": "close_shift",
+                    "shiftId": shift_id,
+                    "roleType": shift["roleType"],
+                    "success": false,
+                    "error": error,
+                }));
+                return Err(format!("Failed to close shift {shift_id}: {error}"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Generate the day's Z-report and queue its print. Returns the report id
+/// when one was persisted.
+fn generate_and_print_z_report(
+    db: &DbState,
+    branch_id: &str,
+    actions: &mut Vec<Value>,
+) -> Result<Option<String>, String> {
+    let result =
+        zreport::generate_z_report_for_date(db, &serde_json::json!({ "branchId": branch_id }))?;
+    let z_report_id = result
+        .get("zReportId")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let existing = result
+        .get("existing")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    info!(z_report_id = ?z_report_id, existing, "End-of-day Z-report generated");
+    actions.push(serde_json::json!({
+        "action": "generate_z_report",
+        "zReportId": z_report_id,
+        "existing": existing,
+    }));
+
+    if let (Some(id), false) = (z_report_id.as_deref(), existing) {
+        match zreport::print_z_report(db, &serde_json::json!({ "zReportId": id })) {
+            Ok(job) => {
+                info!(z_report_id = %id, "End-of-day Z-report print enqueued");
+                actions.push(serde_json::json!({
+                    "action": "enqueue_z_report_print",
+                    "success": true,
+                    "jobId": job.get("jobId"),
+                }));
+            }
+            Err(error) => {
+                // The report exists; a print failure is reported but does
+                // not undo the close.
+                warn!(z_report_id = %id, error = %error, "End-of-day Z-report print failed");
+                actions.push(serde_json::json!({
+                    "action": "enqueue_z_report_print",
+                    "success": false,
+                    "error": error,
+                }));
+            }
+        }
+    }
+    Ok(z_report_id)
+}
+
+/// Run one end-of-day pass and record it in `eod_runs`.
+pub fn run(db: &DbState, request: RunRequest<'_>) -> Result<Value, String> {
+    let started_at = local_rfc3339(request.now);
+    let branch_id = branches::report_scope(None);
+    let (open_shifts, open_drawer_count) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        (
+            load_open_shifts(&conn, &branch_id)?,
+            count_open_drawers(&conn, &branch_id)?,
+        )
+    };
+    let has_open_items = !open_shifts.is_empty() || open_drawer_count > 0;
+
+    let mut blockers = Vec::new();
+    let mut actions = Vec::new();
+    let mut z_report_id = None;
+    let mut error = None;
+    let status = if !request.auto_close {
+        if has_open_items {
+            "reminded"
+        } else {
+            "nothing_open"
+        }
+    } else {
+        blockers = close_blockers(db, &branch_id, request.now.date(), request.ecr_busy)?;
+        if !blockers.is_empty() {
+            warn!(blockers = blockers.len(), "End-of-day auto-close refused");
+            "refused"
+        } else {
+            match close_open_shifts(db, &open_shifts, &mut actions)
+                .and_then(|_| generate_and_print_z_report(db, &branch_id, &mut actions))
+            {
+                Ok(id) => {
+                    z_report_id = id;
+                    "closed"
+                }
+                Err(e) => {
+                    error = Some(e);
+                    "failed"
+                }
+            }
+        }
+    };
+
+    let finished_at = local_rfc3339(Local::now().naive_local().max(request.now));
+    let scheduled_for = request
+        .scheduled_for
+        .map(|date| date.format("%Y-%m-%d").to_string());
+    let run_id = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO eod_runs
+                (trigger, scheduled_for, status, auto_close, open_shift_count,
+                 open_drawer_count, blockers, actions, z_report_id, error,
+                 actor_staff_id, started_at, finished_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                request.trigger,
+                scheduled_for,
+                status,
+                request.auto_close,
+                open_shifts.len() as i64,
+                open_drawer_count,
+                Value::Array(blockers.clone()).to_string(),
+                Value::Array(actions.clone()).to_string(),
+                z_report_id,
+                error,
+                request.actor_staff_id,
+                started_at,
+                finished_at,
+            ],
+        )
+        .map_err(|e| format!("record eod run: {e}"))?;
+        conn.last_insert_rowid()
+    };
+    info!(
+        run_id,
+        trigger = %request.trigger,
+        status,
+        open_shifts = open_shifts.len(),
+        open_drawers = open_drawer_count,
+        "End-of-day pass recorded"
+    );
+
+    Ok(serde_json::json!({
+        "success": status != "failed",
+        "runId": run_id,
+        "trigger": request.trigger,
+        "scheduledFor": scheduled_for,
+        "status": status,
+        "autoClose": request.auto_close,
+        "openShifts": open_shifts,
+        "openDrawerCount": open_drawer_count,
+        "blockers": blockers,
+        "actions": actions,
+        "zReportId": z_report_id,
+        "error": error,
+        "startedAt": started_at,
+        "finishedAt": finished_at,
+    }))
+}
+
+fn run_row_to_json(row: &rusqlite::Row<'_>) -> rusqlite::Result<Value> {
+    let parse = |raw: String| serde_json::from_str::<Value>(&raw).unwrap_or(Value::Null);
+    Ok(serde_json::json!({
+        "runId": row.get::<_, i64>(0)?,
+        "trigger": row.get::<_, String>(1)?,
+        "scheduledFor": row.get::<_, Option<String>>(2)?,
+        "status": row.get::<_, String>(3)?,
+        "autoClose": row.get::<_, bool>(4)?,
+        "openShiftCount": row.get::<_, i64>(5)?,
+        "openDrawerCount": row.get::<_, i64>(6)?,
+        "blockers": parse(row.get(7)?),
+        "actions": parse(row.get(8)?),
+        "zReportId": row.get::<_, Option<String>>(9)?,
+        "error": row.get::<_, Option<String>>(10)?,
+        "actorStaffId": row.get::<_, Option<String>>(11)?,
+        "startedAt": row.get::<_, String>(12)?,
+        "finishedAt": row.get::<_, String>(13)?,
+    }))
+}
+
+/// Configuration, the last recorded pass and the next scheduled one.
+pub fn status(db: &DbState, now: NaiveDateTime) -> Result<Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let config = load_config(&conn);
+    let last_run = conn
+        .query_row(
+            "SELECT id, trigger, scheduled_for, status, auto_close, open_shift_count,
+                    open_drawer_count, blockers, actions, z_report_id, error,
+                    actor_staff_id, started_at, finished_at
+             FROM eod_runs ORDER BY id DESC LIMIT 1",
+            [],
+            run_row_to_json,
+        )
+        .optional()
+        .map_err(|e| format!("load last eod run: {e}"))?;
+    let next_run = next_scheduled_run(&config, now, last_scheduled_date(&conn)).map(local_rfc3339);
+    let branch_id = branches::report_scope(None);
+    let open_shift_count = load_open_shifts(&conn, &branch_id)?.len();
+    let open_drawer_count = count_open_drawers(&conn, &branch_id)?;
+
+    Ok(serde_json::json!({
+        "success": true,
+        "closeTime": config.close_time.map(|t| t.format("%H:%M").to_string()),
+        "autoClose": config.auto_close,
+        "lastRun": last_run,
+        "nextScheduledRun": next_run,
+        "openShiftCount": open_shift_count,
+        "openDrawerCount": open_drawer_count,
+    }))
+}
+
+/// Emit the UI events for a finished pass.
+pub fn emit_run_events(app: &tauri::AppHandle, outcome: &Value) {
+    let open_shifts = outcome["openShifts"].as_array().map_or(0, Vec::len);
+    let open_drawers = outcome["openDrawerCount"].as_i64().unwrap_or(0);
+    if open_shifts > 0 || open_drawers > 0 {
+        let _ = app.emit("eod_reminder", outcome.clone());
+    }
+    if matches!(outcome["status"].as_str(), Some("refused" | "failed")) {
+        let _ = app.emit("eod_alert", outcome.clone());
+    }
+    for action in outcome["actions"].as_array().into_iter().flatten() {
+        if action["action"] == "close_shift" && action["success"] == true {
+            let _ = app.emit(
+                "shift_updated",
+                serde_json::json!({ "action": "close", "shift": action }),
+            );
+        }
+    }
+}
+
+pub fn ecr_transaction_in_progress(app: &tauri::AppHandle) -> bool {
+    app.try_state::<crate::ecr::DeviceManager>()
+        .is_some_and(|manager| manager.any_transaction_in_progress())
+}
+
+/// Background scheduler: checks once a minute whether today's pass is due.
+pub fn start_eod_scheduler(
+    app: tauri::AppHandle,
+    db: Arc<DbState>,
+    cancel: tokio_util::sync::CancellationToken,
+) {
+    let cadence = Duration::from_secs(SCHEDULER_INTERVAL_SECS);
+    tauri::async_runtime::spawn(async move {
+        info!("End-of-day scheduler started");
+        loop {
+            let now: DateTime<Local> = Local::now();
+            let due = db.conn.lock().ok().and_then(|conn| {
+                let config = load_config(&conn);
+                due_scheduled_date(&config, now.naive_local(), last_scheduled_date(&conn))
+                    .map(|date| (date, config))
+            });
+            if let Some((date, config)) = due {
+                let request = RunRequest {
+                    trigger: TRIGGER_SCHEDULED,
+                    scheduled_for: Some(date),
+                    auto_close: config.auto_close,
+                    actor_staff_id: None,
+                    ecr_busy: ecr_transaction_in_progress(&app),
+                    now: now.naive_local(),
+                };
+                match run(db.as_ref(), request) {
+                    Ok(outcome) => emit_run_events(&app, &outcome),
+                    Err(error) => warn!(error = %error, "End-of-day pass failed"),
+                }
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(cadence) => {}
+                _ = cancel.cancelled() => {
+                    info!("End-of-day scheduler cancelled");
+                    break;
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{date} {time}"), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn test_db() -> DbState {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        db::run_migrations_for_test(&conn);
+        DbState::new(conn, std::path::PathBuf::from(":memory:"))
+    }
+
+    #[test]
+    fn schedule_fires_once_per_day_after_close_time() {
+        let config = EodConfig {
+            close_time: parse_close_time("23:30"),
+            auto_close: false,
+        };
+        let today = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+
+        assert_eq!(
+            due_scheduled_date(&config, at("2026-03-14", "23:00"), None),
+            None
+        );
+        assert_eq!(
+            due_scheduled_date(&config, at("2026-03-14", "23:31"), None),
+            Some(today)
+        );
+        assert_eq!(
+            due_scheduled_date(&config, at("2026-03-14", "23:45"), Some(today)),
+            None
+        );
+        assert_eq!(
+            next_scheduled_run(&config, at("2026-03-14", "23:45"), Some(today)),
+            Some(at("2026-03-15", "23:30"))
+        );
+        assert_eq!(
+            next_scheduled_run(&config, at("2026-03-14", "09:00"), None),
+            Some(at("2026-03-14", "23:30"))
+        );
+
+        let unset = EodConfig {
+            close_time: parse_close_time("late"),
+            auto_close: true,
+        };
+        assert_eq!(
+            due_scheduled_date(&unset, at("2026-03-14", "23:59"), None),
+            None
+        );
+        assert_eq!(
+            next_scheduled_run(&unset, at("2026-03-14", "23:59"), None),
+            None
+        );
+    }
+
+    #[test]
+    fn auto_close_refuses_with_unsynced_financial_rows_and_records_the_run() {
+        let db = test_db();
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO parity_sync_queue
+                    (id, table_name, record_id, operation, data, organization_id, module_type, status)
+                 VALUES ('q-1', 'payments', 'pay-1', 'INSERT', '{}', 'org-1', 'financial', 'pending')",
+                [],
+            )
+            .unwrap();
+        }
+
+        let outcome = run(
+            &db,
+            RunRequest {
+                trigger: TRIGGER_MANUAL,
+                scheduled_for: None,
+                auto_close: true,
+                actor_staff_id: Some("staff-1"),
+                ecr_busy: true,
+                now: at("2026-03-14", "23:40"),
+            },
+        )
+        .unwrap();
+        assert_eq!(outcome["status"], "refused");
+        let codes: Vec<&str> = outcome["blockers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|b| b["code"].as_str())
+            .collect();
+        assert!(codes.contains(&"unsynced_financial"));
+        assert!(codes.contains(&"payment_in_progress"));
+        assert!(outcome["actions"].as_array().unwrap().is_empty());
+
+        let status = status(&db, at("2026-03-14", "23:41")).unwrap();
+        assert_eq!(status["lastRun"]["status"], "refused");
+        assert_eq!(status["lastRun"]["actorStaffId"], "staff-1");
+        assert!(status["nextScheduledRun"].is_null());
+    }
+}
@@ -0,0 +1,329 @@
+//! Typed error for IPC commands.
+//!
+//! Commands used to return `Result<_, String>`, so the renderer had to
+//! string-match messages like "Order not found" and any rewording broke it.
+//! `PosError` serializes to a stable `{ code, message, details }` object;
+//! the renderer branches on `code` and shows `message`.
+//!
+//! # Codes
+//!
+//! | code            | variant        | `details`                              |
+//! |-----------------|----------------|----------------------------------------|
+//! | `NOT_FOUND`     | `NotFound`     | `null`                                 |
+//! | `VALIDATION`    | `Validation`   | `{ "field": <payload field> }`         |
+//! | `UNAUTHORIZED`  | `Unauthorized` | `null`                                 |
+//! | `CONFLICT`      | `Conflict`     | `null`                                 |
+//! | `NETWORK_ERROR` | `NetworkError` | `null`                                 |
+//! | `TERMINAL_AUTH` | `TerminalAuth` | `{ "reason": <admin code> }` or `null` |
+//! | `DATABASE`      | `Database`     | `null`                                 |
+//! | `INTERNAL`      | `Internal`     | `null`                                 |
+//! | `KIOSK_MODE`    | `KioskMode`    | `null`                                 |
+//! | `UNSUPPORTED`   | `Unsupported`  | `null`                                 |
+//! | `UNAVAILABLE`   | `Unavailable`  | `null`                                 |
+//!
+//! Codes are part of the IPC contract (`PosErrorPayload` in
+//! `src/lib/ipc-contracts.ts`): add new ones, never rename existing ones.
+//!
+//! Errors with a known fix (missing credentials, terminal auth, an open
+//! admin circuit, a busy database...) add a fourth field, `remediation`,
+//! from the registry in `crate::remediation`; the field is absent
+//! otherwise.
+//!
+//! # Transition
+//!
+//! Most helpers still return `Result<_, String>`. `From<String>` lets `?`
+//! lift those into a `PosError`, classifying terminal-auth, network and
+//! not-found messages and falling back to `Internal`. `From<PosError> for
+//! String` goes the other way so converted helpers can be called from
+//! commands that still return strings. The message text is unchanged in
+//! both directions.
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PosError {
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{reason}")]
+    Validation { field: String, reason: String },
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    NetworkError(String),
+    #[error("{0}")]
+    TerminalAuth(String),
+    #[error("{0}")]
+    Database(String),
+    #[error("{0}")]
+    Internal(String),
+    #[error("{0}")]
+    KioskMode(String),
+    /// The feature cannot work on this machine; retrying will not help.
+    #[error("{0}")]
+    Unsupported(String),
+    /// A transient failure; the same call may succeed shortly.
+    #[error("{0}")]
+    Unavailable(String),
+}
+
+impl PosError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+
+    pub fn validation(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::Validation {
+            field: field.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Stable machine-readable code; see the module docs for the list.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "NOT_FOUND",
+            Self::Validation { .. } => "VALIDATION",
+            Self::Unauthorized(_) => "UNAUTHORIZED",
+            Self::Conflict(_) => "CONFLICT",
+            Self::NetworkError(_) => "NETWORK_ERROR",
+            Self::TerminalAuth(_) => "TERMINAL_AUTH",
+            Self::Database(_) => "DATABASE",
+            Self::Internal(_) => "INTERNAL",
+            Self::KioskMode(_) => "KIOSK_MODE",
+            Self::Unsupported(_) => "UNSUPPORTED",
+            Self::Unavailable(_) => "UNAVAILABLE",
+        }
+    }
+
+    pub fn details(&self) -> Value {
+        match self {
+            Self::Validation { field, .. } => serde_json::json!({ "field": field }),
+            Self::TerminalAuth(message) => crate::terminal_auth_failure_code(message)
+                .map(|reason| serde_json::json!({ "reason": reason }))
+                .unwrap_or(Value::Null),
+            _ => Value::Null,
+        }
+    }
+
+    pub fn is_terminal_auth(&self) -> bool {
+        matches!(self, Self::TerminalAuth(_))
+    }
+
+    /// What staff can do about this error, if the message is a known one.
+    /// Terminal-auth errors always get a hint even when the admin reworded
+    /// the message.
+    pub fn remediation(&self) -> Option<&'static crate::remediation::Remediation> {
+        crate::remediation::for_message(&self.to_string()).or_else(|| match self {
+            Self::TerminalAuth(_) => {
+                crate::remediation::lookup(crate::remediation::TERMINAL_AUTH_FAILED)
+            }
+            _ => None,
+        })
+    }
+}
+
+impl Serialize for PosError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let remediation = self.remediation();
+        let len = if remediation.is_some() { 4 } else { 3 };
+        let mut state = serializer.serialize_struct("PosError", len)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        match remediation {
+            Some(remediation) => state.serialize_field("remediation", remediation)?,
+            None => state.skip_field("remediation")?,
+        }
+        state.end()
+    }
+}
+
+fn is_network_message(lower: &str) -> bool {
+    lower.starts_with("cannot reach admin dashboard")
+        || lower.starts_with("network error communicating with")
+        || lower.starts_with("circuit_open")
+        || (lower.starts_with("connection to ") && lower.ends_with(" timed out"))
+}
+
+impl From<String> for PosError {
+    fn from(message: String) -> Self {
+        if crate::is_terminal_auth_failure(&message) {
+            return Self::TerminalAuth(message);
+        }
+        let lower = message.to_ascii_lowercase();
+        if is_network_message(&lower) {
+            Self::NetworkError(message)
+        } else if lower.contains("not found") {
+            Self::NotFound(message)
+        } else {
+            Self::Internal(message)
+        }
+    }
+}
+
+impl From<&str> for PosError {
+    fn from(message: &str) -> Self {
+        Self::from(message.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for PosError {
+    fn from(error: rusqlite::Error) -> Self {
+        match error {
+            rusqlite::Error::QueryReturnedNoRows => Self::NotFound("Record not found".into()),
+            other => Self::Database(other.to_string()),
+        }
+    }
+}
+
+impl From<PosError> for String {
+    fn from(error: PosError) -> Self {
+        error.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn serialized_shape_is_stable_for_every_code() {
+        let cases = [
+            (
+                PosError::not_found("Order not found: ord-1"),
+                json!({ "code": "NOT_FOUND", "message": "Order not found: ord-1", "details": null }),
+            ),
+            (
+                PosError::validation("orderId", "Missing orderId"),
+                json!({
+                    "code": "VALIDATION",
+                    "message": "Missing orderId",
+                    "details": { "field": "orderId" }
+                }),
+            ),
+            (
+                PosError::Unauthorized("Invalid PIN".into()),
+                json!({ "code": "UNAUTHORIZED", "message": "Invalid PIN", "details": null }),
+            ),
+            (
+                PosError::Conflict("Shift already closed".into()),
+                json!({ "code": "CONFLICT", "message": "Shift already closed", "details": null }),
+            ),
+            (
+                PosError::NetworkError("Cannot reach admin dashboard at https://x".into()),
+                json!({
+                    "code": "NETWORK_ERROR",
+                    "message": "Cannot reach admin dashboard at https://x",
+                    "details": null,
+                    "remediation": {
+                        "code": "ADMIN_UNREACHABLE",
+                        "messageKey": "errors.remediation.adminUnreachable",
+                        "action": "check_network"
+                    }
+                }),
+            ),
+            (
+                PosError::TerminalAuth("Terminal not authorized".into()),
+                json!({
+                    "code": "TERMINAL_AUTH",
+                    "message": "Terminal not authorized",
+                    "details": null,
+                    "remediation": {
+                        "code": "TERMINAL_AUTH_FAILED",
+                        "messageKey": "errors.remediation.terminalAuthFailed",
+                        "action": "open_onboarding"
+                    }
+                }),
+            ),
+            (
+                PosError::Database("disk I/O error".into()),
+                json!({ "code": "DATABASE", "message": "disk I/O error", "details": null }),
+            ),
+            (
+                PosError::Internal("boom".into()),
+                json!({ "code": "INTERNAL", "message": "boom", "details": null }),
+            ),
+            (
+                PosError::KioskMode("refund_payment is not available in kiosk mode".into()),
+                json!({
+                    "code": "KIOSK_MODE",
+                    "message": "refund_payment is not available in kiosk mode",
+                    "details": null
+                }),
+            ),
+            (
+                PosError::Unsupported("No clipboard".into()),
+                json!({ "code": "UNSUPPORTED", "message": "No clipboard", "details": null }),
+            ),
+            (
+                PosError::Unavailable("Clipboard busy".into()),
+                json!({ "code": "UNAVAILABLE", "message": "Clipboard busy", "details": null }),
+            ),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(serde_json::to_value(&error).unwrap(), expected, "{error:?}");
+        }
+    }
+
+    #[test]
+    fn terminal_auth_details_carry_admin_reason_code() {
+        let error = PosError::from(
+            r#"Terminal is inactive (HTTP 401): {"success":false,"code":"terminal_inactive"}"#
+                .to_string(),
+        );
+        assert!(error.is_terminal_auth(), "{error:?}");
+        assert_eq!(error.details(), json!({ "reason": "terminal_inactive" }));
+    }
+
+    #[test]
+    fn string_errors_are_classified_without_changing_the_message() {
+        let cases = [
+            ("API key is invalid or expired", "TERMINAL_AUTH"),
+            (
+                "Cannot reach admin dashboard at https://admin",
+                "NETWORK_ERROR",
+            ),
+            ("Connection to https://admin timed out", "NETWORK_ERROR"),
+            ("Order not found: ord-9", "NOT_FOUND"),
+            ("begin payment-status transaction: locked", "INTERNAL"),
+        ];
+        for (message, code) in cases {
+            let error = PosError::from(message);
+            assert_eq!(error.code(), code, "{message}");
+            assert_eq!(String::from(error), message);
+        }
+    }
+
+    #[test]
+    fn remediation_follows_the_message_and_is_omitted_when_unknown() {
+        let locked = PosError::Database("database is locked".into());
+        assert_eq!(
+            serde_json::to_value(&locked).unwrap()["remediation"]["action"],
+            "contact_support"
+        );
+        let missing = PosError::from("Terminal not configured: missing API key");
+        assert_eq!(
+            missing.remediation().map(|r| r.code),
+            Some(crate::remediation::TERMINAL_NOT_CONFIGURED)
+        );
+        let value = serde_json::to_value(PosError::Internal("boom".into())).unwrap();
+        assert!(value.get("remediation").is_none(), "{value}");
+    }
+
+    #[test]
+    fn rusqlite_no_rows_maps_to_not_found() {
+        assert_eq!(
+            PosError::from(rusqlite::Error::QueryReturnedNoRows).code(),
+            "NOT_FOUND"
+        );
+        assert_eq!(
+            PosError::from(rusqlite::Error::InvalidQuery).code(),
+            "DATABASE"
+        );
+    }
+}
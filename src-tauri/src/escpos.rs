@@ -76,6 +76,50 @@ pub struct EscPosBuilder {
     /// When true, code page commands use Star Line Mode format (ESC GS t n)
     /// instead of standard ESC/POS (ESC t n).
     star_line_mode: bool,
+    /// Plain-text mirror of the printed lines; see [`EscPosBuilder::plain_text`].
+    transcript: Transcript,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Fixed-width text copy of what the printer will print, built from the
+/// same calls that fill the byte buffer. Alignment and character width are
+/// applied when a line ends; styling (bold, reverse, font) is dropped.
+#[derive(Debug, Clone)]
+struct Transcript {
+    lines: Vec<String>,
+    current: String,
+    align: Align,
+    width_scale: usize,
+}
+
+impl Transcript {
+    fn new() -> Self {
+        Self {
+            lines: Vec::new(),
+            current: String::new(),
+            align: Align::Left,
+            width_scale: 1,
+        }
+    }
+
+    fn end_line(&mut self, paper: PaperWidth) {
+        let text = std::mem::take(&mut self.current);
+        let width = paper.chars() / self.width_scale.max(1);
+        let gap = width.saturating_sub(text.chars().count());
+        let indent = match self.align {
+            Align::Left => 0,
+            Align::Center => gap / 2,
+            Align::Right => gap,
+        };
+        let line = format!("{}{}", " ".repeat(indent), text);
+        self.lines.push(line.trim_end().to_string());
+    }
 }
 
 impl EscPosBuilder {
@@ -102,6 +146,7 @@ impl EscPosBuilder {
             greek_mode: false,
             active_code_page: 14, // CP737 (Greek)
             star_line_mode: false,
+            transcript: Transcript::new(),
         }
     }
 
@@ -129,6 +174,8 @@ impl EscPosBuilder {
     /// ESC @ — Initialize printer, reset to defaults.
     pub fn init(&mut self) -> &mut Self {
         self.buffer.extend_from_slice(&[ESC, 0x40]);
+        self.transcript.align = Align::Left;
+        self.transcript.width_scale = 1;
         self
     }
 
@@ -241,6 +288,15 @@ impl EscPosBuilder {
             let h = height.clamp(1, 8) - 1;
             self.buffer.extend_from_slice(&[GS, 0x21, (w << 4) | h]);
         }
+        self.transcript.width_scale = if self.star_line_mode {
+            if width > 1 {
+                2
+            } else {
+                1
+            }
+        } else {
+            usize::from(width.clamp(1, 8))
+        };
         self
     }
 
@@ -266,18 +322,21 @@ impl EscPosBuilder {
     /// ESC a 0 — Left-align.
     pub fn left(&mut self) -> &mut Self {
         self.buffer.extend_from_slice(&[ESC, 0x61, 0]);
+        self.transcript.align = Align::Left;
         self
     }
 
     /// ESC a 1 — Centre-align.
     pub fn center(&mut self) -> &mut Self {
         self.buffer.extend_from_slice(&[ESC, 0x61, 1]);
+        self.transcript.align = Align::Center;
         self
     }
 
     /// ESC a 2 — Right-align.
     pub fn right(&mut self) -> &mut Self {
         self.buffer.extend_from_slice(&[ESC, 0x61, 2]);
+        self.transcript.align = Align::Right;
         self
     }
 
@@ -291,6 +350,7 @@ impl EscPosBuilder {
     /// (page 19) which has € at 0xD5, then restoring the active code page.
     /// Uses Star Line Mode commands (ESC GS t) when `star_line_mode` is set.
     pub fn text(&mut self, s: &str) -> &mut Self {
+        self.transcribe(s);
         // Split on € to handle inline code page switches
         for (i, segment) in s.split('€').enumerate() {
             if i > 0 {
@@ -318,6 +378,32 @@ impl EscPosBuilder {
         self
     }
 
+    /// Mirror `s` into the transcript, substituting `?` wherever the printer
+    /// would, so the preview shows the same glyphs as the paper.
+    fn transcribe(&mut self, s: &str) {
+        for ch in s.chars() {
+            match ch {
+                '\n' => self.transcript.end_line(self.paper),
+                '\r' => {}
+                '€' => self.transcript.current.push(ch),
+                _ if ch.is_ascii() => self.transcript.current.push(ch),
+                _ if !self.greek_mode => self.transcript.current.push('?'),
+                _ => {
+                    let glyph = if box_drawing_to_cp737(ch).is_some() {
+                        ch
+                    } else if let Some(fallback) = unicode_fallback(ch) {
+                        char::from(fallback)
+                    } else if greek_to_cp737(ch).is_some() {
+                        ch
+                    } else {
+                        '?'
+                    };
+                    self.transcript.current.push(glyph);
+                }
+            }
+        }
+    }
+
     /// Emit a code page switch using the correct command for the printer mode.
     fn emit_code_page_cmd(&mut self, page: u8) {
         if self.star_line_mode {
@@ -336,6 +422,7 @@ impl EscPosBuilder {
     /// Append a line-feed.
     pub fn lf(&mut self) -> &mut Self {
         self.buffer.push(LF);
+        self.transcript.end_line(self.paper);
         self
     }
 
@@ -345,8 +432,8 @@ impl EscPosBuilder {
         for _ in 0..width {
             self.buffer.push(b'-');
         }
-        self.buffer.push(LF);
-        self
+        self.transcript.current.push_str(&"-".repeat(width));
+        self.lf()
     }
 
     /// Print a line with left-aligned label and right-aligned value.
@@ -361,6 +448,7 @@ impl EscPosBuilder {
         for _ in 0..gap {
             self.buffer.push(b' ');
         }
+        self.transcript.current.push_str(&" ".repeat(gap));
         self.text(value);
         self.lf()
     }
@@ -401,6 +489,8 @@ impl EscPosBuilder {
         self.raw(payload);
         // Print symbol
         self.raw(&[GS, b'(', b'k', 0x03, 0x00, 0x31, 0x51, 0x30]);
+        self.transcript.current.push_str(&format!("[QR: {data}]"));
+        self.transcript.end_line(self.paper);
         self
     }
 
@@ -495,6 +585,12 @@ impl EscPosBuilder {
     /// ESC d n — Feed n lines.
     pub fn feed(&mut self, lines: u8) -> &mut Self {
         self.buffer.extend_from_slice(&[ESC, 0x64, lines]);
+        if !self.transcript.current.is_empty() {
+            self.transcript.end_line(self.paper);
+        }
+        for _ in 0..lines {
+            self.transcript.end_line(self.paper);
+        }
         self
     }
 
@@ -540,6 +636,25 @@ impl EscPosBuilder {
     // Build
     // -----------------------------------------------------------------------
 
+    /// Fixed-width plain text of everything printed so far, one printed line
+    /// per text line, with trailing blank feed lines dropped. Images and
+    /// control commands have no text form; a QR code shows as `[QR: data]`.
+    pub fn plain_text(&self) -> String {
+        let mut lines: Vec<&str> = self.transcript.lines.iter().map(String::as_str).collect();
+        let pending = self.transcript.current.trim_end();
+        if !pending.is_empty() {
+            lines.push(pending);
+        }
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        let mut text = lines.join("\n");
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text
+    }
+
     /// Consume the builder and return the binary ESC/POS payload.
     pub fn build(self) -> Vec<u8> {
         self.buffer
@@ -941,4 +1056,23 @@ mod tests {
             "EscPosBuilder::new() must default active_code_page to 14 (CP737, Greek)"
         );
     }
+
+    #[test]
+    fn plain_text_mirrors_alignment_width_and_encoding() {
+        let mut b = EscPosBuilder::new().with_paper(PaperWidth::Mm58);
+        b.init().center().text("Καλημέρα\n").left();
+        b.line_pair("Total", "9.00€");
+        b.text_size(2, 2).right().text("BIG").lf();
+        b.normal_size().left().separator();
+        b.set_greek_mode(true).text("Ωραία – ok").lf();
+        b.feed(3).cut();
+        assert_eq!(
+            b.plain_text(),
+            "            ????????\n\
+             Total                      9.00€\n\
+             \x20            BIG\n\
+             --------------------------------\n\
+             Ωραία - ok\n"
+        );
+    }
 }
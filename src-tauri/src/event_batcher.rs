@@ -0,0 +1,310 @@
+//! Coalescing emitter for bursty order events.
+//!
+//! A sync pull that reconciles a few hundred orders used to emit one
+//! `order_realtime_update` (plus `order_created` / `order_status_updated`)
+//! per row, and the renderer re-rendered the order list for each of them.
+//! [`EventBatcher`] buffers the events listed in [`BATCHED_EVENTS`] for a
+//! short window and emits them as one `<event>_batch` event whose payload
+//! is the array of individual payloads, in emission order. A window that
+//! only collected a single payload emits it under the original event name,
+//! so low-frequency updates look exactly as before. Events not in the list
+//! pass straight through.
+//!
+//! The window (`system.event_batch_window_ms`, default 100) and the batch
+//! cap (`system.event_batch_max_size`, default 50) are read from
+//! `local_settings` at startup. A buffer that reaches the cap is emitted
+//! immediately. The shutdown sequence calls [`flush`] so nothing buffered
+//! is lost on exit.
+
+use rusqlite::Connection;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db;
+
+pub const DEFAULT_WINDOW_MS: u64 = 100;
+pub const DEFAULT_MAX_BATCH: usize = 50;
+const MAX_WINDOW_MS: u64 = 2_000;
+const MAX_BATCH_CAP: usize = 500;
+
+/// Events coalesced by the batcher. The renderer's event bridge fans the
+/// matching `<event>_batch` payloads back out to the per-event channel.
+pub const BATCHED_EVENTS: &[&str] = &[
+    "order_realtime_update",
+    "order_created",
+    "order_status_updated",
+    "order_deleted",
+];
+
+pub fn batch_event_name(event: &str) -> String {
+    format!("{event}_batch")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchConfig {
+    pub window: Duration,
+    pub max_batch: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_millis(DEFAULT_WINDOW_MS),
+            max_batch: DEFAULT_MAX_BATCH,
+        }
+    }
+}
+
+impl BatchConfig {
+    pub fn from_settings(conn: &Connection) -> Self {
+        let window_ms = db::get_setting(conn, "system", "event_batch_window_ms")
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_WINDOW_MS)
+            .min(MAX_WINDOW_MS);
+        let max_batch = db::get_setting(conn, "system", "event_batch_max_size")
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_BATCH)
+            .clamp(1, MAX_BATCH_CAP);
+        Self {
+            window: Duration::from_millis(window_ms),
+            max_batch,
+        }
+    }
+}
+
+type Sink = Arc<dyn Fn(&str, Value) + Send + Sync>;
+
+#[derive(Default)]
+struct Pending {
+    items: Vec<Value>,
+    /// Bumped on every flush so a timer armed for an earlier window does not
+    /// cut the next window short.
+    generation: u64,
+}
+
+/// Tauri managed state; cheap to clone.
+#[derive(Clone)]
+pub struct EventBatcher {
+    config: BatchConfig,
+    sink: Sink,
+    /// One slot per entry in [`BATCHED_EVENTS`], same order.
+    pending: Arc<Mutex<Vec<Pending>>>,
+}
+
+impl EventBatcher {
+    pub fn new(config: BatchConfig, sink: impl Fn(&str, Value) + Send + Sync + 'static) -> Self {
+        Self {
+            config,
+            sink: Arc::new(sink),
+            pending: Arc::new(Mutex::new(
+                BATCHED_EVENTS.iter().map(|_| Pending::default()).collect(),
+            )),
+        }
+    }
+
+    pub fn for_app(app: AppHandle, config: BatchConfig) -> Self {
+        Self::new(config, move |event, payload| {
+            let _ = app.emit(event, payload);
+        })
+    }
+
+    pub fn emit(&self, event: &str, payload: Value) {
+        let Some(slot) = BATCHED_EVENTS.iter().position(|name| *name == event) else {
+            (self.sink)(event, payload);
+            return;
+        };
+        if self.config.window.is_zero() {
+            (self.sink)(event, payload);
+            return;
+        }
+
+        let arm_timer = {
+            let Ok(mut pending) = self.pending.lock() else {
+                (self.sink)(event, payload);
+                return;
+            };
+            let entry = &mut pending[slot];
+            entry.items.push(payload);
+            if entry.items.len() >= self.config.max_batch {
+                // Deliver under the lock so a concurrent emit cannot overtake
+                // this batch.
+                self.drain(slot, entry);
+                None
+            } else if entry.items.len() == 1 {
+                Some(entry.generation)
+            } else {
+                None
+            }
+        };
+
+        if let Some(generation) = arm_timer {
+            let batcher = self.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(batcher.config.window).await;
+                batcher.flush_slot(slot, generation);
+            });
+        }
+    }
+
+    /// Emit everything buffered right now.
+    pub fn flush(&self) {
+        let Ok(mut pending) = self.pending.lock() else {
+            return;
+        };
+        for (slot, entry) in pending.iter_mut().enumerate() {
+            self.drain(slot, entry);
+        }
+    }
+
+    fn flush_slot(&self, slot: usize, generation: u64) {
+        let Ok(mut pending) = self.pending.lock() else {
+            return;
+        };
+        let entry = &mut pending[slot];
+        if entry.generation == generation {
+            self.drain(slot, entry);
+        }
+    }
+
+    fn drain(&self, slot: usize, entry: &mut Pending) {
+        if entry.items.is_empty() {
+            return;
+        }
+        entry.generation = entry.generation.wrapping_add(1);
+        let mut items = std::mem::take(&mut entry.items);
+        let event = BATCHED_EVENTS[slot];
+        if items.len() == 1 {
+            (self.sink)(event, items.remove(0));
+        } else {
+            (self.sink)(&batch_event_name(event), Value::Array(items));
+        }
+    }
+}
+
+/// Emit through the managed batcher, or directly when it is not registered
+/// (early startup, tests).
+pub fn emit(app: &AppHandle, event: &str, payload: Value) {
+    match app.try_state::<EventBatcher>() {
+        Some(batcher) => batcher.emit(event, payload),
+        None => {
+            let _ = app.emit(event, payload);
+        }
+    }
+}
+
+pub fn flush(app: &AppHandle) {
+    if let Some(batcher) = app.try_state::<EventBatcher>() {
+        batcher.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    type Captured = Arc<Mutex<Vec<(String, Value)>>>;
+
+    fn capturing(config: BatchConfig) -> (EventBatcher, Captured) {
+        let captured: Captured = Arc::default();
+        let sink = captured.clone();
+        let batcher = EventBatcher::new(config, move |event, payload| {
+            sink.lock().unwrap().push((event.to_string(), payload));
+        });
+        (batcher, captured)
+    }
+
+    fn config(window_ms: u64, max_batch: usize) -> BatchConfig {
+        BatchConfig {
+            window: Duration::from_millis(window_ms),
+            max_batch,
+        }
+    }
+
+    #[test]
+    fn unbatched_events_pass_through_immediately() {
+        let (batcher, captured) = capturing(config(10_000, 50));
+        batcher.emit("sync_status", json!({ "ok": true }));
+        assert_eq!(
+            *captured.lock().unwrap(),
+            vec![("sync_status".to_string(), json!({ "ok": true }))]
+        );
+    }
+
+    #[test]
+    fn burst_is_coalesced_in_emission_order() {
+        let (batcher, captured) = capturing(config(10_000, 50));
+        for i in 0..3 {
+            batcher.emit("order_realtime_update", json!({ "orderId": i }));
+        }
+        assert!(captured.lock().unwrap().is_empty());
+        batcher.flush();
+        assert_eq!(
+            *captured.lock().unwrap(),
+            vec![(
+                "order_realtime_update_batch".to_string(),
+                json!([{ "orderId": 0 }, { "orderId": 1 }, { "orderId": 2 }])
+            )]
+        );
+    }
+
+    #[test]
+    fn single_buffered_event_keeps_its_name() {
+        let (batcher, captured) = capturing(config(10_000, 50));
+        batcher.emit("order_deleted", json!({ "orderId": "o-1" }));
+        batcher.flush();
+        assert_eq!(
+            *captured.lock().unwrap(),
+            vec![("order_deleted".to_string(), json!({ "orderId": "o-1" }))]
+        );
+    }
+
+    #[test]
+    fn full_batch_is_emitted_without_waiting() {
+        let (batcher, captured) = capturing(config(10_000, 2));
+        for i in 0..3 {
+            batcher.emit("order_created", json!(i));
+        }
+        assert_eq!(
+            *captured.lock().unwrap(),
+            vec![("order_created_batch".to_string(), json!([0, 1]))]
+        );
+        batcher.flush();
+        assert_eq!(
+            captured.lock().unwrap()[1],
+            ("order_created".into(), json!(2))
+        );
+    }
+
+    #[tokio::test]
+    async fn window_elapsing_flushes_the_buffer() {
+        let (batcher, captured) = capturing(config(20, 50));
+        batcher.emit("order_status_updated", json!(1));
+        batcher.emit("order_status_updated", json!(2));
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(
+            *captured.lock().unwrap(),
+            vec![("order_status_updated_batch".to_string(), json!([1, 2]))]
+        );
+    }
+
+    #[test]
+    fn settings_override_and_clamp_config() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE local_settings (
+                setting_category TEXT, setting_key TEXT, setting_value TEXT
+             );",
+        )
+        .unwrap();
+        assert_eq!(BatchConfig::from_settings(&conn), BatchConfig::default());
+        conn.execute_batch(
+            "INSERT INTO local_settings VALUES ('system', 'event_batch_window_ms', '99999');
+             INSERT INTO local_settings VALUES ('system', 'event_batch_max_size', '0');",
+        )
+        .unwrap();
+        assert_eq!(BatchConfig::from_settings(&conn), config(MAX_WINDOW_MS, 1));
+    }
+}
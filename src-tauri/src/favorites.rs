@@ -0,0 +1,347 @@
+//! Quick-sale favorites grid, stored per terminal.
+//!
+//! A grid is a list of pages, each `rows × cols`, whose cells point at a
+//! menu item or a combo with an optional label and colour. One grid per
+//! terminal is the default; a staff member can have their own on top of it
+//! (`staff_id` is `''` for the terminal default). Cells are checked against
+//! the menu cache when the grid is read: an item or combo that has since been
+//! deleted stays in its cell with `missing: true`, so the cashier sees the
+//! gap instead of the grid silently reflowing.
+//!
+//! [`autofill`] suggests a single page from the last [`AUTOFILL_DAYS`] days
+//! of local sales; it is not saved until the frontend sends it back through
+//! `favorites_set_grid`.
+
+use std::collections::{HashMap, HashSet};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::combos;
+
+/// Event emitted after a grid is saved so other windows reload it.
+pub const UPDATED_EVENT: &str = "favorites_updated";
+
+pub const MAX_PAGES: usize = 10;
+pub const MAX_ROWS: u32 = 10;
+pub const MAX_COLS: u32 = 10;
+const MAX_LABEL_CHARS: usize = 40;
+
+/// Days of order history [`autofill`] ranks items over.
+pub const AUTOFILL_DAYS: i64 = 30;
+pub const AUTOFILL_ROWS: u32 = 4;
+pub const AUTOFILL_COLS: u32 = 5;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Grid {
+    #[serde(default)]
+    pub pages: Vec<Page>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub rows: u32,
+    pub cols: u32,
+    #[serde(default)]
+    pub cells: Vec<Cell>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cell {
+    pub row: u32,
+    pub col: u32,
+    #[serde(
+        default,
+        alias = "menu_item_id",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub menu_item_id: Option<String>,
+    #[serde(default, alias = "combo_id", skip_serializing_if = "Option::is_none")]
+    pub combo_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Set on read when the item or combo is no longer in the menu cache.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub missing: bool,
+}
+
+fn is_hex_color(color: &str) -> bool {
+    let Some(hex) = color.strip_prefix('#') else {
+        return false;
+    };
+    matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn trimmed(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Parse and check a grid from the frontend. Ids, labels and colours are
+/// trimmed; `missing` markers sent back from a previous read are dropped.
+pub fn parse(value: &Value) -> Result<Grid, String> {
+    let mut grid: Grid =
+        serde_json::from_value(value.clone()).map_err(|e| format!("Invalid grid: {e}"))?;
+    if grid.pages.len() > MAX_PAGES {
+        return Err(format!("A grid has at most {MAX_PAGES} pages"));
+    }
+    for (page_index, page) in grid.pages.iter_mut().enumerate() {
+        let page_no = page_index + 1;
+        if !(1..=MAX_ROWS).contains(&page.rows) || !(1..=MAX_COLS).contains(&page.cols) {
+            return Err(format!(
+                "Page {page_no} must be 1-{MAX_ROWS} rows by 1-{MAX_COLS} columns"
+            ));
+        }
+        page.name = trimmed(page.name.take());
+        let mut taken = HashSet::new();
+        for cell in &mut page.cells {
+            let at = format!("page {page_no}, row {}, column {}", cell.row, cell.col);
+            if cell.row >= page.rows || cell.col >= page.cols {
+                return Err(format!("Cell at {at} is outside the page"));
+            }
+            if !taken.insert((cell.row, cell.col)) {
+                return Err(format!("Two cells share {at}"));
+            }
+            cell.menu_item_id = trimmed(cell.menu_item_id.take());
+            cell.combo_id = trimmed(cell.combo_id.take());
+            if cell.menu_item_id.is_some() == cell.combo_id.is_some() {
+                return Err(format!(
+                    "Cell at {at} needs exactly one of menuItemId or comboId"
+                ));
+            }
+            cell.label = trimmed(cell.label.take());
+            if cell
+                .label
+                .as_ref()
+                .is_some_and(|label| label.chars().count() > MAX_LABEL_CHARS)
+            {
+                return Err(format!(
+                    "Label at {at} is longer than {MAX_LABEL_CHARS} characters"
+                ));
+            }
+            cell.color = trimmed(cell.color.take());
+            if cell
+                .color
+                .as_deref()
+                .is_some_and(|color| !is_hex_color(color))
+            {
+                return Err(format!("Colour at {at} must be #RGB or #RRGGBB"));
+            }
+            cell.missing = false;
+        }
+        page.cells.sort_by_key(|cell| (cell.row, cell.col));
+    }
+    Ok(grid)
+}
+
+/// Mark cells whose item or combo is not in the menu cache. A section that
+/// has not been synced yet marks nothing, since every id would look gone.
+pub fn mark_missing(
+    grid: &mut Grid,
+    items: &HashMap<String, Value>,
+    combos: &HashMap<String, Value>,
+) {
+    for cell in grid.pages.iter_mut().flat_map(|page| page.cells.iter_mut()) {
+        cell.missing = match (&cell.menu_item_id, &cell.combo_id) {
+            (Some(id), _) => !items.is_empty() && !items.contains_key(id),
+            (None, Some(id)) => !combos.is_empty() && !combos.contains_key(id),
+            (None, None) => false,
+        };
+    }
+}
+
+/// The saved grid for `terminal_id`, preferring `staff_id`'s own grid over
+/// the terminal default, with missing cells marked, along with the staff id
+/// it was saved under (`''` for the terminal default) and when. `None` when
+/// neither exists.
+pub fn load(
+    conn: &Connection,
+    terminal_id: &str,
+    staff_id: Option<&str>,
+) -> Result<Option<(Grid, String, String)>, String> {
+    let staff_id = staff_id.unwrap_or("");
+    let row: Option<(String, String, String)> = conn
+        .query_row(
+            "SELECT layout, staff_id, updated_at
+             FROM favorite_grids
+             WHERE terminal_id = ?1 AND staff_id IN (?2, '')
+             ORDER BY staff_id = ?2 DESC
+             LIMIT 1",
+            params![terminal_id, staff_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| format!("load favorites grid: {e}"))?;
+    let Some((layout, owner, updated_at)) = row else {
+        return Ok(None);
+    };
+    let mut grid: Grid =
+        serde_json::from_str(&layout).map_err(|e| format!("parse favorites grid: {e}"))?;
+    mark_missing(
+        &mut grid,
+        &combos::read_section(conn, "subcategories"),
+        &combos::read_section(conn, "combos"),
+    );
+    Ok(Some((grid, owner, updated_at)))
+}
+
+/// Save `grid` for the terminal, or for `staff_id` on it.
+pub fn save(
+    conn: &Connection,
+    terminal_id: &str,
+    staff_id: Option<&str>,
+    grid: &Grid,
+    now: &str,
+) -> Result<(), String> {
+    let layout = serde_json::to_string(grid).map_err(|e| format!("serialize grid: {e}"))?;
+    conn.execute(
+        "INSERT INTO favorite_grids (terminal_id, staff_id, layout, updated_at)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(terminal_id, staff_id) DO UPDATE SET
+             layout = excluded.layout,
+             updated_at = excluded.updated_at",
+        params![terminal_id, staff_id.unwrap_or(""), layout, now],
+    )
+    .map_err(|e| format!("save favorites grid: {e}"))?;
+    Ok(())
+}
+
+/// One page filled in rank order from `top_items` (the analytics top-items
+/// rows: `menuItemId` and `name`), skipping items no longer on the menu.
+pub fn autofill(top_items: &[Value], items: &HashMap<String, Value>, rows: u32, cols: u32) -> Grid {
+    let cols = cols.clamp(1, MAX_COLS);
+    let rows = rows.clamp(1, MAX_ROWS);
+    let cells = top_items
+        .iter()
+        .filter_map(|item| {
+            let id = crate::value_str(item, &["menuItemId", "menu_item_id"])?;
+            (items.is_empty() || items.contains_key(&id))
+                .then(|| (id, crate::value_str(item, &["name"])))
+        })
+        .take((rows * cols) as usize)
+        .enumerate()
+        .map(|(index, (id, name))| Cell {
+            row: index as u32 / cols,
+            col: index as u32 % cols,
+            menu_item_id: Some(id),
+            combo_id: None,
+            label: name,
+            color: None,
+            missing: false,
+        })
+        .collect();
+    Grid {
+        pages: vec![Page {
+            name: None,
+            rows,
+            cols,
+            cells,
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn menu(ids: &[&str]) -> HashMap<String, Value> {
+        ids.iter()
+            .map(|id| (id.to_string(), json!({ "id": id })))
+            .collect()
+    }
+
+    #[test]
+    fn parse_checks_cells_and_save_load_marks_deleted_items() {
+        let grid = parse(&json!({
+            "pages": [{
+                "name": " Drinks ",
+                "rows": 2,
+                "cols": 2,
+                "cells": [
+                    { "row": 1, "col": 0, "comboId": "combo-1", "color": "#ff8800" },
+                    { "row": 0, "col": 0, "menuItemId": "item-1", "label": "Freddo" },
+                    { "row": 0, "col": 1, "menu_item_id": "item-gone", "missing": true }
+                ]
+            }]
+        }))
+        .unwrap();
+        assert_eq!(grid.pages[0].name.as_deref(), Some("Drinks"));
+        assert_eq!(
+            grid.pages[0].cells[0].menu_item_id.as_deref(),
+            Some("item-1")
+        );
+        assert!(!grid.pages[0].cells[1].missing);
+
+        for bad in [
+            json!({ "pages": [{ "rows": 0, "cols": 2 }] }),
+            json!({ "pages": [{ "rows": 1, "cols": 1, "cells": [{ "row": 1, "col": 0, "menuItemId": "a" }] }] }),
+            json!({ "pages": [{ "rows": 1, "cols": 2, "cells": [
+                { "row": 0, "col": 0, "menuItemId": "a" },
+                { "row": 0, "col": 0, "menuItemId": "b" }
+            ] }] }),
+            json!({ "pages": [{ "rows": 1, "cols": 1, "cells": [{ "row": 0, "col": 0, "menuItemId": "a", "comboId": "c" }] }] }),
+            json!({ "pages": [{ "rows": 1, "cols": 1, "cells": [{ "row": 0, "col": 0, "menuItemId": "a", "color": "red" }] }] }),
+        ] {
+            assert!(parse(&bad).is_err(), "{bad}");
+        }
+
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations_for_test(&conn);
+        conn.execute(
+            "INSERT INTO menu_cache (id, cache_key, data, version, updated_at)
+             VALUES ('subcategories', 'subcategories', ?1, 1, datetime('now')),
+                    ('combos', 'combos', ?2, 1, datetime('now'))",
+            params![
+                json!([{ "id": "item-1" }]).to_string(),
+                json!([{ "id": "combo-1" }]).to_string()
+            ],
+        )
+        .unwrap();
+        save(&conn, "term-1", None, &grid, "2026-10-15T10:00:00Z").unwrap();
+        let (loaded, owner, _) = load(&conn, "term-1", Some("staff-1")).unwrap().unwrap();
+        assert_eq!(owner, "");
+        let missing: Vec<bool> = loaded.pages[0].cells.iter().map(|c| c.missing).collect();
+        assert_eq!(missing, vec![false, true, false]);
+
+        let own = parse(&json!({ "pages": [{ "rows": 1, "cols": 1 }] })).unwrap();
+        save(
+            &conn,
+            "term-1",
+            Some("staff-1"),
+            &own,
+            "2026-10-15T11:00:00Z",
+        )
+        .unwrap();
+        let (loaded, owner, _) = load(&conn, "term-1", Some("staff-1")).unwrap().unwrap();
+        assert_eq!((loaded, owner.as_str()), (own, "staff-1"));
+        assert!(load(&conn, "term-2", None).unwrap().is_none());
+    }
+
+    #[test]
+    fn autofill_fills_rows_in_rank_order_and_skips_deleted_items() {
+        let top = vec![
+            json!({ "menuItemId": "a", "name": "Espresso" }),
+            json!({ "menuItemId": "gone", "name": "Old" }),
+            json!({ "menuItemId": "b", "name": "Toast" }),
+            json!({ "menuItemId": "c", "name": "Cake" }),
+        ];
+        let grid = autofill(&top, &menu(&["a", "b", "c"]), 2, 2);
+        let placed: Vec<(u32, u32, &str)> = grid.pages[0]
+            .cells
+            .iter()
+            .map(|c| (c.row, c.col, c.menu_item_id.as_deref().unwrap()))
+            .collect();
+        assert_eq!(placed, vec![(0, 0, "a"), (0, 1, "b"), (1, 0, "c")]);
+        assert_eq!(grid.pages[0].cells[0].label.as_deref(), Some("Espresso"));
+    }
+}
@@ -0,0 +1,221 @@
+//! Per-terminal feature flags.
+//!
+//! Feature availability is derived from the module cache written by
+//! `modules_fetch_from_admin` / `modules_save_cache`, then adjusted by local
+//! overrides stored as a JSON object under `system.local_feature_overrides`.
+//! Overrides exist for testing and support: they let an admin force a
+//! module on or off on one terminal without touching the purchase state.
+
+use std::collections::BTreeMap;
+
+use rusqlite::Connection;
+use serde_json::Value;
+
+use crate::db;
+
+pub const OVERRIDES_CATEGORY: &str = "system";
+pub const OVERRIDES_KEY: &str = "local_feature_overrides";
+
+/// Where a feature's `enabled` value came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureSource {
+    /// Purchase / enablement state from the module cache.
+    Module,
+    /// A local override set on this terminal.
+    Override,
+    /// Core modules, which are on regardless of purchase state.
+    Default,
+}
+
+impl FeatureSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Module => "module",
+            Self::Override => "override",
+            Self::Default => "default",
+        }
+    }
+}
+
+pub fn load_overrides(conn: &Connection) -> BTreeMap<String, bool> {
+    db::get_setting(conn, OVERRIDES_CATEGORY, OVERRIDES_KEY)
+        .and_then(|raw| serde_json::from_str::<BTreeMap<String, bool>>(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// Set (`Some`) or clear (`None`) the override for one feature. Returns
+/// whether the stored overrides changed.
+pub fn write_override(
+    conn: &Connection,
+    feature_id: &str,
+    enabled: Option<bool>,
+    source: &str,
+    actor_staff_id: Option<&str>,
+) -> Result<bool, String> {
+    let feature_id = feature_id.trim();
+    if feature_id.is_empty() {
+        return Err("Missing featureId".into());
+    }
+    let mut overrides = load_overrides(conn);
+    let previous = match enabled {
+        Some(flag) => overrides.insert(feature_id.to_string(), flag),
+        None => overrides.remove(feature_id),
+    };
+    if previous == enabled {
+        return Ok(false);
+    }
+    let raw = serde_json::to_string(&overrides).map_err(|e| e.to_string())?;
+    db::set_setting_with_source(
+        conn,
+        OVERRIDES_CATEGORY,
+        OVERRIDES_KEY,
+        &raw,
+        source,
+        actor_staff_id,
+    )?;
+    Ok(true)
+}
+
+fn module_flag(module: &Value, key: &str) -> Option<bool> {
+    module.get(key).and_then(Value::as_bool)
+}
+
+fn module_id(module: &Value) -> Option<&str> {
+    module
+        .get("module_id")
+        .or_else(|| module.get("moduleId"))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+}
+
+/// Availability of a cached module before overrides: core modules are on by
+/// default; others need to be purchased. Either can be switched off by the
+/// admin (`is_enabled`) or hidden from POS clients (`pos_enabled`).
+fn module_state(module: &Value) -> (bool, FeatureSource) {
+    let switched_off = module_flag(module, "is_enabled") == Some(false)
+        || module_flag(module, "pos_enabled") == Some(false);
+    if module_flag(module, "is_core") == Some(true) {
+        (!switched_off, FeatureSource::Default)
+    } else {
+        let purchased = module_flag(module, "is_purchased") == Some(true);
+        (purchased && !switched_off, FeatureSource::Module)
+    }
+}
+
+/// Merge the module cache with local overrides. `cache` is `None` when no
+/// cache has been written yet; `cache_stale` marks module-derived entries so
+/// the UI can warn without blocking.
+pub fn resolve(
+    cache: Option<&Value>,
+    overrides: &BTreeMap<String, bool>,
+    cache_stale: bool,
+) -> Vec<Value> {
+    let modules = cache
+        .and_then(|c| c.get("apiModules"))
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+
+    let mut features: BTreeMap<String, Value> = BTreeMap::new();
+    for module in modules {
+        let Some(id) = module_id(module) else {
+            continue;
+        };
+        let (module_enabled, module_source) = module_state(module);
+        let (enabled, source) = match overrides.get(id) {
+            Some(flag) => (*flag, FeatureSource::Override),
+            None => (module_enabled, module_source),
+        };
+        features.insert(
+            id.to_string(),
+            serde_json::json!({
+                "featureId": id,
+                "enabled": enabled,
+                "source": source.as_str(),
+                "moduleEnabled": module_enabled,
+                "override": overrides.get(id),
+                "stale": cache_stale && source != FeatureSource::Override,
+                "module": module,
+            }),
+        );
+    }
+    for (id, flag) in overrides {
+        features.entry(id.clone()).or_insert_with(|| {
+            serde_json::json!({
+                "featureId": id,
+                "enabled": flag,
+                "source": FeatureSource::Override.as_str(),
+                "moduleEnabled": false,
+                "override": flag,
+                "stale": false,
+                "module": Value::Null,
+            })
+        });
+    }
+    features.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_merges_module_cache_with_overrides() {
+        let cache = serde_json::json!({
+            "apiModules": [
+                { "module_id": "orders", "is_core": true, "is_purchased": false },
+                { "module_id": "loyalty", "is_purchased": true, "is_enabled": true },
+                { "module_id": "kiosk", "is_purchased": false },
+                { "module_id": "delivery", "is_purchased": true, "pos_enabled": false },
+            ]
+        });
+        let overrides = BTreeMap::from([
+            ("kiosk".to_string(), true),
+            ("loyalty".to_string(), false),
+            ("experimental".to_string(), true),
+        ]);
+        let features = resolve(Some(&cache), &overrides, true);
+        let by_id = |id: &str| {
+            features
+                .iter()
+                .find(|f| f["featureId"] == id)
+                .cloned()
+                .unwrap_or_else(|| panic!("missing {id}"))
+        };
+
+        let orders = by_id("orders");
+        assert_eq!(orders["enabled"], true);
+        assert_eq!(orders["source"], "default");
+        assert_eq!(orders["stale"], true);
+
+        let loyalty = by_id("loyalty");
+        assert_eq!(loyalty["enabled"], false);
+        assert_eq!(loyalty["source"], "override");
+        assert_eq!(loyalty["moduleEnabled"], true);
+        assert_eq!(loyalty["stale"], false);
+
+        assert_eq!(by_id("kiosk")["enabled"], true);
+        assert_eq!(by_id("delivery")["enabled"], false);
+        assert_eq!(by_id("delivery")["source"], "module");
+
+        let experimental = by_id("experimental");
+        assert_eq!(experimental["enabled"], true);
+        assert!(experimental["module"].is_null());
+    }
+
+    #[test]
+    fn overrides_round_trip_through_settings() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        db::run_migrations_for_test(&conn);
+
+        assert!(write_override(&conn, "kiosk", Some(true), "test", None).unwrap());
+        assert!(!write_override(&conn, "kiosk", Some(true), "test", None).unwrap());
+        assert_eq!(load_overrides(&conn).get("kiosk"), Some(&true));
+
+        assert!(write_override(&conn, "kiosk", None, "test", None).unwrap());
+        assert!(!write_override(&conn, "kiosk", None, "test", None).unwrap());
+        assert!(load_overrides(&conn).is_empty());
+        assert!(write_override(&conn, " ", Some(true), "test", None).is_err());
+    }
+}
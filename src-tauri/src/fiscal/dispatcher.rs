@@ -149,15 +149,13 @@ pub(crate) async fn try_post(
     terminal_id: &str,
     payload: &serde_json::Value,
 ) -> Result<DispatchOutcome, String> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
-        .build()
-        .map_err(|e| format!("reqwest client build: {e}"))?;
+    let client = crate::api::shared_client()?;
 
     let url = format!("{}{}", admin_base_url.trim_end_matches('/'), SUBMIT_PATH);
 
     let response = client
         .post(&url)
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
         .header("x-pos-api-key", api_key)
         .header("x-terminal-id", terminal_id)
         .header("Content-Type", "application/json")
@@ -0,0 +1,916 @@
+//! Locally issued fiscal documents (Greek retail receipts for myDATA).
+//!
+//! Every completed local payment gets a `receipt` document and every
+//! refund a `refund` document. Numbers come from two persisted per-terminal
+//! series (`receipt` and `refund`) that never reset. The number is taken
+//! inside the caller's payment/refund transaction, so a rollback also rolls
+//! back the counter and the series stays gap-free.
+//!
+//! Documents are immutable: `fiscal_documents` rejects UPDATE and DELETE
+//! (see `db::migrate_v73`). Cancelling a document issues a `cancellation`
+//! document from the refund series, with negated amounts and
+//! `cancels_document_id` pointing at the original.
+//!
+//! Issuing is enabled per terminal with
+//! `local_settings(fiscalization.gr, documents_enabled) = true`. The issuer
+//! VAT number and myDATA installation (branch) number used by the journal
+//! export are read from `fiscalization.gr/issuer_vat_number` and
+//! `fiscalization.gr/installation_number`.
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::db;
+use crate::money::{Cents, RoundingRule};
+use crate::tax::{self, TaxBreakdownLine};
+
+pub const SETTINGS_CATEGORY: &str = "fiscalization.gr";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Receipt,
+    Refund,
+    Cancellation,
+}
+
+impl DocumentKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Receipt => "receipt",
+            Self::Refund => "refund",
+            Self::Cancellation => "cancellation",
+        }
+    }
+
+    fn parse(raw: &str) -> Self {
+        match raw {
+            "refund" => Self::Refund,
+            "cancellation" => Self::Cancellation,
+            _ => Self::Receipt,
+        }
+    }
+
+    /// Refunds and cancellations are credit documents and share a series.
+    fn series(self) -> &'static str {
+        match self {
+            Self::Receipt => "receipt",
+            Self::Refund | Self::Cancellation => "refund",
+        }
+    }
+
+    fn series_prefix(self) -> &'static str {
+        match self {
+            Self::Receipt => "R",
+            Self::Refund | Self::Cancellation => "C",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FiscalDocument {
+    pub id: String,
+    pub document_type: String,
+    pub series: String,
+    pub number: i64,
+    pub document_number: String,
+    pub terminal_id: String,
+    pub branch_id: String,
+    pub order_id: String,
+    pub payment_id: Option<String>,
+    pub adjustment_id: Option<String>,
+    pub cancels_document_id: Option<String>,
+    pub payment_method: Option<String>,
+    pub net_cents: i64,
+    pub vat_cents: i64,
+    pub gross_cents: i64,
+    pub vat_breakdown: Vec<TaxBreakdownLine>,
+    pub issued_at: String,
+}
+
+pub fn documents_enabled(conn: &Connection) -> bool {
+    db::get_setting(conn, SETTINGS_CATEGORY, "documents_enabled")
+        .map(|raw| matches!(raw.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+        .unwrap_or(false)
+}
+
+/// Take the next number from a terminal's series. Must run inside the
+/// transaction that writes the document.
+fn next_number(conn: &Connection, terminal_id: &str, series: &str) -> Result<i64, String> {
+    if conn.is_autocommit() {
+        return Err("fiscal numbers must be allocated inside a transaction".into());
+    }
+    conn.query_row(
+        "INSERT INTO fiscal_receipt_series (terminal_id, series, last_number, updated_at)
+         VALUES (?1, ?2, 1, datetime('now'))
+         ON CONFLICT (terminal_id, series) DO UPDATE
+           SET last_number = fiscal_receipt_series.last_number + 1,
+               updated_at  = datetime('now')
+         RETURNING last_number",
+        params![terminal_id, series],
+        |row| row.get::<_, i64>(0),
+    )
+    .map_err(|e| format!("allocate fiscal number ({terminal_id}/{series}): {e}"))
+}
+
+/// Split `amount_cents` across the order's stored per-rate breakdown in
+/// proportion to each rate's gross, keeping each rate's tax ratio. The
+/// parts always add up to `amount_cents` exactly (largest remainder).
+fn allocate_breakdown(lines: &[TaxBreakdownLine], amount_cents: i64) -> Vec<TaxBreakdownLine> {
+    let total_gross: i64 = lines.iter().map(|line| line.gross_cents).sum();
+    if total_gross <= 0 {
+        return vec![TaxBreakdownLine {
+            rate_id: "none".to_string(),
+            label: String::new(),
+            rate_bps: 0,
+            net_cents: amount_cents,
+            tax_cents: 0,
+            gross_cents: amount_cents,
+        }];
+    }
+
+    let amount = i128::from(amount_cents);
+    let total = i128::from(total_gross);
+    let mut shares: Vec<(usize, i64, i128)> = lines
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| {
+            let scaled = amount * i128::from(line.gross_cents);
+            (idx, (scaled / total) as i64, scaled % total)
+        })
+        .collect();
+    let mut remainder = amount_cents - shares.iter().map(|(_, share, _)| share).sum::<i64>();
+    shares.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+    for (_, share, _) in shares.iter_mut() {
+        if remainder == 0 {
+            break;
+        }
+        let step = remainder.signum();
+        *share += step;
+        remainder -= step;
+    }
+    shares.sort_by_key(|(idx, _, _)| *idx);
+
+    shares
+        .into_iter()
+        .filter(|(_, share, _)| *share != 0)
+        .map(|(idx, share, _)| {
+            let line = &lines[idx];
+            let tax = if line.gross_cents == 0 {
+                0
+            } else {
+                Cents::round_with(
+                    share as f64 * line.tax_cents as f64 / line.gross_cents as f64 / 100.0,
+                    RoundingRule::HalfUp,
+                )
+                .as_i64()
+            };
+            TaxBreakdownLine {
+                gross_cents: share,
+                tax_cents: tax,
+                net_cents: share - tax,
+                ..line.clone()
+            }
+        })
+        .collect()
+}
+
+fn order_breakdown(conn: &Connection, order_id: &str) -> Result<Vec<TaxBreakdownLine>, String> {
+    let (stored, items): (Option<String>, String) = conn
+        .query_row(
+            "SELECT tax_breakdown, COALESCE(items, '[]') FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("load order tax breakdown: {e}"))?;
+    let lines = tax::parse_breakdown(stored.as_deref());
+    if !lines.is_empty() {
+        return Ok(lines);
+    }
+    let items: Vec<Value> = serde_json::from_str(&items).unwrap_or_default();
+    Ok(tax::compute_breakdown(
+        &tax::load_config(conn),
+        &tax::MenuTaxLookup::load(conn),
+        &items,
+        RoundingRule::from_settings(conn),
+    ))
+}
+
+struct NewDocument<'a> {
+    kind: DocumentKind,
+    terminal_id: &'a str,
+    branch_id: &'a str,
+    order_id: &'a str,
+    payment_id: Option<&'a str>,
+    adjustment_id: Option<&'a str>,
+    cancels_document_id: Option<&'a str>,
+    payment_method: Option<&'a str>,
+    breakdown: Vec<TaxBreakdownLine>,
+}
+
+fn insert_document(conn: &Connection, doc: NewDocument<'_>) -> Result<FiscalDocument, String> {
+    let number = next_number(conn, doc.terminal_id, doc.kind.series())?;
+    let document_number = format!("{}-{number:06}", doc.kind.series_prefix());
+    let net_cents: i64 = doc.breakdown.iter().map(|line| line.net_cents).sum();
+    let vat_cents: i64 = doc.breakdown.iter().map(|line| line.tax_cents).sum();
+    let gross_cents: i64 = doc.breakdown.iter().map(|line| line.gross_cents).sum();
+    let breakdown_json = serde_json::to_string(&doc.breakdown)
+        .map_err(|e| format!("serialize fiscal breakdown: {e}"))?;
+    let document = FiscalDocument {
+        id: Uuid::new_v4().to_string(),
+        document_type: doc.kind.as_str().to_string(),
+        series: doc.kind.series().to_string(),
+        number,
+        document_number,
+        terminal_id: doc.terminal_id.to_string(),
+        branch_id: doc.branch_id.to_string(),
+        order_id: doc.order_id.to_string(),
+        payment_id: doc.payment_id.map(str::to_string),
+        adjustment_id: doc.adjustment_id.map(str::to_string),
+        cancels_document_id: doc.cancels_document_id.map(str::to_string),
+        payment_method: doc.payment_method.map(str::to_string),
+        net_cents,
+        vat_cents,
+        gross_cents,
+        vat_breakdown: doc.breakdown,
+        issued_at: Utc::now().to_rfc3339(),
+    };
+    conn.execute(
+        "INSERT INTO fiscal_documents (
+            id, document_type, series, number, document_number, terminal_id, branch_id,
+            order_id, payment_id, adjustment_id, cancels_document_id, payment_method,
+            net_cents, vat_cents, gross_cents, vat_breakdown, issued_at
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        params![
+            document.id,
+            document.document_type,
+            document.series,
+            document.number,
+            document.document_number,
+            document.terminal_id,
+            document.branch_id,
+            document.order_id,
+            document.payment_id,
+            document.adjustment_id,
+            document.cancels_document_id,
+            document.payment_method,
+            document.net_cents,
+            document.vat_cents,
+            document.gross_cents,
+            breakdown_json,
+            document.issued_at,
+        ],
+    )
+    .map_err(|e| format!("insert fiscal document: {e}"))?;
+    Ok(document)
+}
+
+/// Issue the receipt document for a just-recorded payment. Returns `None`
+/// when fiscal documents are disabled on this terminal.
+pub fn issue_payment_receipt(
+    conn: &Connection,
+    payment_id: &str,
+    terminal_id: &str,
+    branch_id: &str,
+) -> Result<Option<FiscalDocument>, String> {
+    if !documents_enabled(conn) {
+        return Ok(None);
+    }
+    let (order_id, method, amount_cents): (String, String, i64) = conn
+        .query_row(
+            "SELECT order_id, method,
+                    COALESCE(amount_cents, CAST(ROUND(amount * 100) AS INTEGER))
+             FROM order_payments WHERE id = ?1",
+            params![payment_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| format!("load payment for fiscal receipt: {e}"))?;
+    let breakdown = allocate_breakdown(&order_breakdown(conn, &order_id)?, amount_cents);
+    insert_document(
+        conn,
+        NewDocument {
+            kind: DocumentKind::Receipt,
+            terminal_id,
+            branch_id,
+            order_id: &order_id,
+            payment_id: Some(payment_id),
+            adjustment_id: None,
+            cancels_document_id: None,
+            payment_method: Some(&method),
+            breakdown,
+        },
+    )
+    .map(Some)
+}
+
+/// Issue the refund document for a just-recorded refund adjustment.
+pub fn issue_refund(
+    conn: &Connection,
+    adjustment_id: &str,
+    terminal_id: &str,
+    branch_id: &str,
+) -> Result<Option<FiscalDocument>, String> {
+    if !documents_enabled(conn) {
+        return Ok(None);
+    }
+    let (payment_id, order_id, amount_cents, method): (String, String, i64, String) = conn
+        .query_row(
+            "SELECT pa.payment_id, pa.order_id,
+                    COALESCE(pa.amount_cents, CAST(ROUND(pa.amount * 100) AS INTEGER)),
+                    COALESCE(pa.refund_method, op.method, 'cash')
+             FROM payment_adjustments pa
+             LEFT JOIN order_payments op ON op.id = pa.payment_id
+             WHERE pa.id = ?1",
+            params![adjustment_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| format!("load refund for fiscal document: {e}"))?;
+    let breakdown = allocate_breakdown(&order_breakdown(conn, &order_id)?, -amount_cents);
+    insert_document(
+        conn,
+        NewDocument {
+            kind: DocumentKind::Refund,
+            terminal_id,
+            branch_id,
+            order_id: &order_id,
+            payment_id: Some(&payment_id),
+            adjustment_id: Some(adjustment_id),
+            cancels_document_id: None,
+            payment_method: Some(&method),
+            breakdown,
+        },
+    )
+    .map(Some)
+}
+
+fn load_document(conn: &Connection, document_id: &str) -> Result<Option<FiscalDocument>, String> {
+    conn.query_row(
+        "SELECT id, document_type, series, number, document_number, terminal_id, branch_id,
+                order_id, payment_id, adjustment_id, cancels_document_id, payment_method,
+                net_cents, vat_cents, gross_cents, vat_breakdown, issued_at
+         FROM fiscal_documents WHERE id = ?1",
+        params![document_id],
+        row_to_document,
+    )
+    .optional()
+    .map_err(|e| format!("load fiscal document: {e}"))
+}
+
+fn row_to_document(row: &rusqlite::Row<'_>) -> rusqlite::Result<FiscalDocument> {
+    let breakdown: Option<String> = row.get(15)?;
+    Ok(FiscalDocument {
+        id: row.get(0)?,
+        document_type: row.get(1)?,
+        series: row.get(2)?,
+        number: row.get(3)?,
+        document_number: row.get(4)?,
+        terminal_id: row.get(5)?,
+        branch_id: row.get(6)?,
+        order_id: row.get(7)?,
+        payment_id: row.get(8)?,
+        adjustment_id: row.get(9)?,
+        cancels_document_id: row.get(10)?,
+        payment_method: row.get(11)?,
+        net_cents: row.get(12)?,
+        vat_cents: row.get(13)?,
+        gross_cents: row.get(14)?,
+        vat_breakdown: tax::parse_breakdown(breakdown.as_deref()),
+        issued_at: row.get(16)?,
+    })
+}
+
+/// Cancel a document by issuing a linked negative document. The original
+/// row is never touched. Cancelling a cancellation, or cancelling twice,
+/// is rejected.
+pub fn cancel_document(
+    conn: &Connection,
+    document_id: &str,
+    terminal_id: &str,
+) -> Result<FiscalDocument, String> {
+    let original = load_document(conn, document_id)?
+        .ok_or_else(|| format!("Fiscal document not found: {document_id}"))?;
+    if DocumentKind::parse(&original.document_type) == DocumentKind::Cancellation {
+        return Err(format!(
+            "Fiscal document {} is a cancellation and cannot be cancelled",
+            original.document_number
+        ));
+    }
+    let already: Option<String> = conn
+        .query_row(
+            "SELECT document_number FROM fiscal_documents WHERE cancels_document_id = ?1",
+            params![document_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("check fiscal cancellation: {e}"))?;
+    if let Some(number) = already {
+        return Err(format!(
+            "Fiscal document {} is already cancelled by {number}",
+            original.document_number
+        ));
+    }
+    let breakdown = original
+        .vat_breakdown
+        .iter()
+        .map(|line| TaxBreakdownLine {
+            net_cents: -line.net_cents,
+            tax_cents: -line.tax_cents,
+            gross_cents: -line.gross_cents,
+            ..line.clone()
+        })
+        .collect();
+    insert_document(
+        conn,
+        NewDocument {
+            kind: DocumentKind::Cancellation,
+            terminal_id,
+            branch_id: &original.branch_id,
+            order_id: &original.order_id,
+            payment_id: original.payment_id.as_deref(),
+            adjustment_id: original.adjustment_id.as_deref(),
+            cancels_document_id: Some(&original.id),
+            payment_method: original.payment_method.as_deref(),
+            breakdown,
+        },
+    )
+}
+
+/// Cancel the receipt issued for a payment (used when the payment is
+/// voided). No-op when the payment has no live receipt document.
+pub fn cancel_payment_receipt(
+    conn: &Connection,
+    payment_id: &str,
+    terminal_id: &str,
+) -> Result<Option<FiscalDocument>, String> {
+    let receipt_id: Option<String> = conn
+        .query_row(
+            "SELECT d.id FROM fiscal_documents d
+             WHERE d.payment_id = ?1
+               AND d.document_type = 'receipt'
+               AND NOT EXISTS (
+                   SELECT 1 FROM fiscal_documents c WHERE c.cancels_document_id = d.id
+               )",
+            params![payment_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("find payment fiscal receipt: {e}"))?;
+    match receipt_id {
+        Some(id) => cancel_document(conn, &id, terminal_id).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// All documents of an order, oldest first.
+pub fn list_order_documents(
+    conn: &Connection,
+    order_id: &str,
+) -> Result<Vec<FiscalDocument>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, document_type, series, number, document_number, terminal_id, branch_id,
+                    order_id, payment_id, adjustment_id, cancels_document_id, payment_method,
+                    net_cents, vat_cents, gross_cents, vat_breakdown, issued_at
+             FROM fiscal_documents
+             WHERE order_id = ?1
+             ORDER BY issued_at, series, number",
+        )
+        .map_err(|e| format!("prepare order fiscal documents: {e}"))?;
+    let rows = stmt
+        .query_map(params![order_id], row_to_document)
+        .map_err(|e| format!("query order fiscal documents: {e}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read order fiscal documents: {e}"))
+}
+
+fn list_documents_between(
+    conn: &Connection,
+    from_date: &str,
+    to_date: &str,
+) -> Result<Vec<FiscalDocument>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, document_type, series, number, document_number, terminal_id, branch_id,
+                    order_id, payment_id, adjustment_id, cancels_document_id, payment_method,
+                    net_cents, vat_cents, gross_cents, vat_breakdown, issued_at
+             FROM fiscal_documents
+             WHERE substr(issued_at, 1, 10) BETWEEN ?1 AND ?2
+             ORDER BY terminal_id, series, number",
+        )
+        .map_err(|e| format!("prepare fiscal journal: {e}"))?;
+    let rows = stmt
+        .query_map(params![from_date, to_date], row_to_document)
+        .map_err(|e| format!("query fiscal journal: {e}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read fiscal journal: {e}"))
+}
+
+/// myDATA VAT category for a rate in basis points.
+fn mydata_vat_category(rate_bps: i64) -> u8 {
+    match rate_bps {
+        2400 => 1,
+        1300 => 2,
+        600 => 3,
+        1700 => 4,
+        900 => 5,
+        400 => 6,
+        0 => 7,
+        _ => 8,
+    }
+}
+
+/// myDATA payment method type: 3 = cash, 7 = POS / card, 5 = on credit.
+fn mydata_payment_type(method: Option<&str>) -> u8 {
+    match method.unwrap_or_default() {
+        "cash" => 3,
+        "card" => 7,
+        _ => 5,
+    }
+}
+
+fn amount(cents: i64) -> f64 {
+    Cents::new(cents.abs()).to_f64_dp2()
+}
+
+/// One myDATA `invoice`. Retail receipts are type 11.1 and credit
+/// documents (refunds, cancellations) 11.4; myDATA expects positive
+/// amounts on both, the type carries the sign.
+fn mydata_invoice(doc: &FiscalDocument, vat_number: &str, installation: i64) -> Value {
+    let kind = DocumentKind::parse(&doc.document_type);
+    let invoice_type = if kind == DocumentKind::Receipt {
+        "11.1"
+    } else {
+        "11.4"
+    };
+    let details: Vec<Value> = doc
+        .vat_breakdown
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| {
+            json!({
+                "lineNumber": idx + 1,
+                "netValue": amount(line.net_cents),
+                "vatCategory": mydata_vat_category(line.rate_bps),
+                "vatAmount": amount(line.tax_cents),
+                "incomeClassification": [{
+                    "classificationType": "E3_561_003",
+                    "classificationCategory": "category1_3",
+                    "amount": amount(line.net_cents),
+                }],
+            })
+        })
+        .collect();
+    json!({
+        "issuer": {
+            "vatNumber": vat_number,
+            "country": "GR",
+            "branch": installation,
+        },
+        "invoiceHeader": {
+            "series": format!("{}-{}", doc.terminal_id, DocumentKind::parse(&doc.document_type).series_prefix()),
+            "aa": doc.number,
+            "issueDate": doc.issued_at.get(..10).unwrap_or_default(),
+            "invoiceType": invoice_type,
+            "currency": "EUR",
+        },
+        "paymentMethods": {
+            "paymentMethodDetails": [{
+                "type": mydata_payment_type(doc.payment_method.as_deref()),
+                "amount": amount(doc.gross_cents),
+            }],
+        },
+        "invoiceDetails": details,
+        "invoiceSummary": {
+            "totalNetValue": amount(doc.net_cents),
+            "totalVatAmount": amount(doc.vat_cents),
+            "totalWithheldAmount": 0.0,
+            "totalFeesAmount": 0.0,
+            "totalStampDutyAmount": 0.0,
+            "totalOtherTaxesAmount": 0.0,
+            "totalDeductionsAmount": 0.0,
+            "totalGrossValue": amount(doc.gross_cents),
+            "incomeClassification": [{
+                "classificationType": "E3_561_003",
+                "classificationCategory": "category1_3",
+                "amount": amount(doc.net_cents),
+            }],
+        },
+        "localDocument": {
+            "id": doc.id,
+            "documentNumber": doc.document_number,
+            "documentType": doc.document_type,
+            "orderId": doc.order_id,
+            "cancelsDocumentId": doc.cancels_document_id,
+        },
+    })
+}
+
+fn xml_escape(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn push_classification(out: &mut String, net_cents: i64) {
+    out.push_str(&format!(
+        "<incomeClassification>\
+         <icls:classificationType>E3_561_003</icls:classificationType>\
+         <icls:classificationCategory>category1_3</icls:classificationCategory>\
+         <icls:amount>{:.2}</icls:amount>\
+         </incomeClassification>",
+        amount(net_cents)
+    ));
+}
+
+/// Same content as [`mydata_invoice`], written in the element order the
+/// myDATA XSD requires.
+fn push_invoice_xml(out: &mut String, doc: &FiscalDocument, vat_number: &str, installation: i64) {
+    let kind = DocumentKind::parse(&doc.document_type);
+    out.push_str("<invoice>");
+    out.push_str(&format!(
+        "<issuer><vatNumber>{}</vatNumber><country>GR</country><branch>{installation}</branch></issuer>",
+        xml_escape(vat_number)
+    ));
+    out.push_str(&format!(
+        "<invoiceHeader><series>{}</series><aa>{}</aa><issueDate>{}</issueDate>\
+         <invoiceType>{}</invoiceType><currency>EUR</currency></invoiceHeader>",
+        xml_escape(&format!("{}-{}", doc.terminal_id, kind.series_prefix())),
+        doc.number,
+        xml_escape(doc.issued_at.get(..10).unwrap_or_default()),
+        if kind == DocumentKind::Receipt {
+            "11.1"
+        } else {
+            "11.4"
+        },
+    ));
+    out.push_str(&format!(
+        "<paymentMethods><paymentMethodDetails><type>{}</type><amount>{:.2}</amount>\
+         </paymentMethodDetails></paymentMethods>",
+        mydata_payment_type(doc.payment_method.as_deref()),
+        amount(doc.gross_cents)
+    ));
+    for (idx, line) in doc.vat_breakdown.iter().enumerate() {
+        out.push_str(&format!(
+            "<invoiceDetails><lineNumber>{}</lineNumber><netValue>{:.2}</netValue>\
+             <vatCategory>{}</vatCategory><vatAmount>{:.2}</vatAmount>",
+            idx + 1,
+            amount(line.net_cents),
+            mydata_vat_category(line.rate_bps),
+            amount(line.tax_cents)
+        ));
+        push_classification(out, line.net_cents);
+        out.push_str("</invoiceDetails>");
+    }
+    out.push_str(&format!(
+        "<invoiceSummary><totalNetValue>{:.2}</totalNetValue><totalVatAmount>{:.2}</totalVatAmount>\
+         <totalWithheldAmount>0.00</totalWithheldAmount><totalFeesAmount>0.00</totalFeesAmount>\
+         <totalStampDutyAmount>0.00</totalStampDutyAmount><totalOtherTaxesAmount>0.00</totalOtherTaxesAmount>\
+         <totalDeductionsAmount>0.00</totalDeductionsAmount><totalGrossValue>{:.2}</totalGrossValue>",
+        amount(doc.net_cents),
+        amount(doc.vat_cents),
+        amount(doc.gross_cents)
+    ));
+    push_classification(out, doc.net_cents);
+    out.push_str("</invoiceSummary></invoice>");
+}
+
+fn journal_xml(documents: &[FiscalDocument], vat_number: &str, installation: i64) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+         <InvoicesDoc xmlns=\"http://www.aade.gr/myDATA/invoice/v1.0\" \
+         xmlns:icls=\"https://www.aade.gr/myDATA/incomeClassificaton/v1.0\">",
+    );
+    for doc in documents {
+        push_invoice_xml(&mut out, doc, vat_number, installation);
+    }
+    out.push_str("</InvoicesDoc>");
+    out
+}
+
+/// Build the myDATA journal for documents issued between `from_date` and
+/// `to_date` (inclusive, `YYYY-MM-DD`). `format` is `json` or `xml`.
+pub fn export_journal(
+    conn: &Connection,
+    from_date: &str,
+    to_date: &str,
+    format: &str,
+) -> Result<Value, String> {
+    let vat_number = db::get_setting(conn, SETTINGS_CATEGORY, "issuer_vat_number")
+        .map(|raw| raw.trim().to_string())
+        .unwrap_or_default();
+    let installation = db::get_setting(conn, SETTINGS_CATEGORY, "installation_number")
+        .and_then(|raw| raw.trim().parse::<i64>().ok())
+        .unwrap_or(0);
+    let documents = list_documents_between(conn, from_date, to_date)?;
+    let content = match format {
+        "xml" => Value::String(journal_xml(&documents, &vat_number, installation)),
+        _ => {
+            let invoices: Vec<Value> = documents
+                .iter()
+                .map(|doc| mydata_invoice(doc, &vat_number, installation))
+                .collect();
+            json!({ "invoicesDoc": { "invoice": invoices } })
+        }
+    };
+    Ok(json!({
+        "success": true,
+        "format": if format == "xml" { "xml" } else { "json" },
+        "from": from_date,
+        "to": to_date,
+        "documentCount": documents.len(),
+        "issuerVatNumber": vat_number,
+        "content": content,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        db::run_migrations_for_test(&conn);
+        db::set_setting(&conn, SETTINGS_CATEGORY, "documents_enabled", "true").unwrap();
+        db::set_setting(&conn, SETTINGS_CATEGORY, "issuer_vat_number", "EL123456789").unwrap();
+        let breakdown = serde_json::to_string(&vec![
+            TaxBreakdownLine {
+                rate_id: "food".into(),
+                label: "Food".into(),
+                rate_bps: 1300,
+                net_cents: 1000,
+                tax_cents: 130,
+                gross_cents: 1130,
+            },
+            TaxBreakdownLine {
+                rate_id: "drinks".into(),
+                label: "Drinks".into(),
+                rate_bps: 2400,
+                net_cents: 500,
+                tax_cents: 120,
+                gross_cents: 620,
+            },
+        ])
+        .unwrap();
+        conn.execute(
+            "INSERT INTO orders (id, items, total_amount, total_amount_cents, status, tax_breakdown, created_at, updated_at)
+             VALUES ('ord-gr', '[]', 17.5, 1750, 'completed', ?1, datetime('now'), datetime('now'))",
+            params![breakdown],
+        )
+        .unwrap();
+        for (id, cents) in [("pay-1", 1000_i64), ("pay-2", 750)] {
+            conn.execute(
+                "INSERT INTO order_payments (id, order_id, method, amount, amount_cents, created_at, updated_at)
+                 VALUES (?1, 'ord-gr', 'cash', ?2, ?3, datetime('now'), datetime('now'))",
+                params![id, cents as f64 / 100.0, cents],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn in_tx<T>(conn: &Connection, f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+        conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+        match f() {
+            Ok(value) => {
+                conn.execute_batch("COMMIT").unwrap();
+                Ok(value)
+            }
+            Err(e) => {
+                conn.execute_batch("ROLLBACK").unwrap();
+                Err(e)
+            }
+        }
+    }
+
+    #[test]
+    fn allocation_splits_payment_by_rate_and_sums_exactly() {
+        let lines = vec![
+            TaxBreakdownLine {
+                rate_id: "a".into(),
+                label: String::new(),
+                rate_bps: 1300,
+                net_cents: 100,
+                tax_cents: 13,
+                gross_cents: 113,
+            },
+            TaxBreakdownLine {
+                rate_id: "b".into(),
+                label: String::new(),
+                rate_bps: 2400,
+                net_cents: 100,
+                tax_cents: 24,
+                gross_cents: 124,
+            },
+        ];
+        for amount in [1_i64, 99, 100, 237, -50, -237] {
+            let parts = allocate_breakdown(&lines, amount);
+            let gross: i64 = parts.iter().map(|line| line.gross_cents).sum();
+            assert_eq!(gross, amount);
+            for part in parts {
+                assert_eq!(part.net_cents + part.tax_cents, part.gross_cents);
+            }
+        }
+    }
+
+    #[test]
+    fn receipt_numbers_are_sequential_and_rollback_leaves_no_gap() {
+        let conn = test_conn();
+        let first = in_tx(&conn, || issue_payment_receipt(&conn, "pay-1", "T1", "B1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.document_number, "R-000001");
+        assert_eq!(first.gross_cents, 1000);
+        assert_eq!(first.vat_breakdown.len(), 2);
+
+        let rolled_back: Result<(), String> = in_tx(&conn, || {
+            issue_payment_receipt(&conn, "pay-2", "T1", "B1")?;
+            Err("payment insert failed".into())
+        });
+        assert!(rolled_back.is_err());
+
+        let second = in_tx(&conn, || issue_payment_receipt(&conn, "pay-2", "T1", "B1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            second.number, 2,
+            "rolled back allocation must not leave a gap"
+        );
+
+        assert!(
+            issue_payment_receipt(&conn, "pay-1", "T1", "B1").is_err(),
+            "allocation outside a transaction is refused"
+        );
+    }
+
+    #[test]
+    fn cancellation_issues_linked_negative_document_and_never_deletes() {
+        let conn = test_conn();
+        let receipt = in_tx(&conn, || issue_payment_receipt(&conn, "pay-1", "T1", "B1"))
+            .unwrap()
+            .unwrap();
+        let cancel = in_tx(&conn, || cancel_document(&conn, &receipt.id, "T1")).unwrap();
+        assert_eq!(cancel.document_type, "cancellation");
+        assert_eq!(cancel.document_number, "C-000001");
+        assert_eq!(
+            cancel.cancels_document_id.as_deref(),
+            Some(receipt.id.as_str())
+        );
+        assert_eq!(cancel.gross_cents, -receipt.gross_cents);
+        assert_eq!(cancel.vat_cents, -receipt.vat_cents);
+
+        assert!(in_tx(&conn, || cancel_document(&conn, &receipt.id, "T1")).is_err());
+        assert!(conn
+            .execute(
+                "DELETE FROM fiscal_documents WHERE id = ?1",
+                params![receipt.id]
+            )
+            .is_err());
+        assert!(conn
+            .execute(
+                "UPDATE fiscal_documents SET gross_cents = 0 WHERE id = ?1",
+                params![receipt.id]
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn disabled_terminal_issues_nothing() {
+        let conn = test_conn();
+        db::set_setting(&conn, SETTINGS_CATEGORY, "documents_enabled", "false").unwrap();
+        let issued = in_tx(&conn, || issue_payment_receipt(&conn, "pay-1", "T1", "B1")).unwrap();
+        assert!(issued.is_none());
+    }
+
+    #[test]
+    fn journal_export_emits_mydata_invoices() {
+        let conn = test_conn();
+        let receipt = in_tx(&conn, || issue_payment_receipt(&conn, "pay-1", "T1", "B1"))
+            .unwrap()
+            .unwrap();
+        in_tx(&conn, || cancel_document(&conn, &receipt.id, "T1")).unwrap();
+        let day = &receipt.issued_at[..10];
+
+        let json_export = export_journal(&conn, day, day, "json").unwrap();
+        assert_eq!(json_export["documentCount"], 2);
+        let invoices = json_export["content"]["invoicesDoc"]["invoice"]
+            .as_array()
+            .unwrap();
+        assert_eq!(invoices[0]["invoiceHeader"]["invoiceType"], "11.1");
+        assert_eq!(invoices[0]["issuer"]["vatNumber"], "EL123456789");
+        assert_eq!(invoices[0]["invoiceSummary"]["totalGrossValue"], 10.0);
+        assert_eq!(invoices[1]["invoiceHeader"]["invoiceType"], "11.4");
+        assert_eq!(invoices[1]["invoiceSummary"]["totalGrossValue"], 10.0);
+
+        let xml_export = export_journal(&conn, day, day, "xml").unwrap();
+        let xml = xml_export["content"].as_str().unwrap();
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains("<invoiceType>11.1</invoiceType>"));
+        assert!(xml.contains("<vatCategory>2</vatCategory>"));
+        assert!(xml.contains("<icls:classificationType>E3_561_003</icls:classificationType>"));
+        assert!(!xml.contains("localDocument"));
+    }
+}
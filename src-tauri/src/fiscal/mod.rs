@@ -29,6 +29,10 @@
 //!   any fiscal row is `pending`/`processing` for the business day under
 //!   a currently active plugin (stale-plugin rows are auto-marked
 //!   `blocked` and do NOT block close — Req 4.7a).
+//! - `documents`       — Greek retail receipts: gap-free per-terminal
+//!   receipt/refund series, immutable `fiscal_documents` rows and the
+//!   myDATA journal export. Unlike the dispatcher path, issuing runs inside
+//!   the payment transaction and its errors do fail the payment.
 //!
 //! Each submodule is declared with `pub mod` AS IT IS SHIPPED, not
 //! upfront — declaring a `pub mod foo;` for a missing file breaks
@@ -38,6 +42,7 @@
 pub mod active_cache;
 pub mod close_day_guard;
 pub mod dispatcher;
+pub mod documents;
 pub mod payload_builder;
 pub mod replay;
 pub mod sequence_counter;
@@ -0,0 +1,264 @@
+//! Terminal status heartbeat to the admin dashboard.
+//!
+//! Separate from the 30-second liveness ping in `sync` (which feeds the
+//! terminal list): every `diagnostics.heartbeat_interval_minutes` (default 5)
+//! the terminal POSTs a compact status snapshot to [`HEARTBEAT_PATH`] so head
+//! office can see how far behind its sync is, which version it runs and
+//! whether it is running out of disk. A failed send is logged and retried on
+//! the next tick; it is never surfaced to the operator. Nothing is sent while
+//! remote auth is paused. The outcome of the last attempt is stored in
+//! `diagnostics.heartbeat_last_result` and reported by the system health
+//! check.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::db::{self, DbState};
+use crate::diagnostics::{self, HealthStatus};
+use crate::{storage, sync};
+
+pub const HEARTBEAT_PATH: &str = "/api/pos/terminals/heartbeat";
+
+const SETTINGS_CATEGORY: &str = "diagnostics";
+const INTERVAL_KEY: &str = "heartbeat_interval_minutes";
+const LAST_RESULT_KEY: &str = "heartbeat_last_result";
+
+pub const DEFAULT_INTERVAL_MINUTES: u64 = 5;
+const MAX_INTERVAL_MINUTES: u64 = 24 * 60;
+
+pub fn interval_minutes(conn: &Connection) -> u64 {
+    db::get_setting(conn, SETTINGS_CATEGORY, INTERVAL_KEY)
+        .and_then(|raw| raw.trim().parse::<u64>().ok())
+        .filter(|minutes| *minutes > 0)
+        .unwrap_or(DEFAULT_INTERVAL_MINUTES)
+        .min(MAX_INTERVAL_MINUTES)
+}
+
+/// Local counters for the heartbeat body. Queries that fail (e.g. a table
+/// missing on a half-migrated database) count as zero.
+fn collect_local_status(conn: &Connection) -> Value {
+    let count = |sql: &str| -> i64 { conn.query_row(sql, [], |row| row.get(0)).unwrap_or(0) };
+    json!({
+        "pendingSyncCount": count(
+            "SELECT COUNT(*) FROM sync_queue
+             WHERE status IN ('pending', 'in_progress', 'queued_remote')",
+        ),
+        "failedSyncCount": count("SELECT COUNT(*) FROM sync_queue WHERE status = 'failed'"),
+        "openShift": count("SELECT COUNT(*) FROM staff_shifts WHERE status = 'active'") > 0,
+        "printerFailureCount": count(
+            "SELECT COUNT(*) FROM print_jobs
+             WHERE status = 'failed' AND julianday(created_at) > julianday('now', '-1 day')",
+        ),
+    })
+}
+
+pub fn build_payload(db: &DbState, last_sync_at: Option<String>) -> Result<Value, String> {
+    let mut payload = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        collect_local_status(&conn)
+    };
+    let disk_free_bytes = db
+        .db_path
+        .parent()
+        .and_then(|dir| fs4::available_space(dir).ok());
+    if let Some(obj) = payload.as_object_mut() {
+        obj.insert(
+            "terminalId".into(),
+            json!(storage::get_credential("terminal_id")),
+        );
+        obj.insert("appVersion".into(), json!(env!("CARGO_PKG_VERSION")));
+        obj.insert(
+            "uptimeSeconds".into(),
+            json!(sync::compute_uptime_seconds()),
+        );
+        obj.insert("lastSyncAt".into(), json!(last_sync_at));
+        obj.insert("diskFreeBytes".into(), json!(disk_free_bytes));
+        obj.insert("sentAt".into(), json!(Utc::now().to_rfc3339()));
+    }
+    Ok(payload)
+}
+
+fn record_result(db: &DbState, result: &Value) {
+    match db.conn.lock() {
+        Ok(conn) => {
+            if let Err(e) = db::set_setting(
+                &conn,
+                SETTINGS_CATEGORY,
+                LAST_RESULT_KEY,
+                &result.to_string(),
+            ) {
+                warn!(error = %e, "Failed to store heartbeat result");
+            }
+        }
+        Err(e) => warn!(error = %e, "Failed to store heartbeat result"),
+    }
+}
+
+pub fn last_result(conn: &Connection) -> Option<Value> {
+    db::get_setting(conn, SETTINGS_CATEGORY, LAST_RESULT_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+}
+
+/// Send one heartbeat and record the outcome. Returns the recorded result;
+/// `Err` when remote auth is paused or the payload could not be built.
+pub async fn send(db: &DbState, sync_state: &sync::SyncState) -> Result<Value, String> {
+    if sync_state.is_remote_auth_paused() {
+        return Err("Remote auth is paused".into());
+    }
+    let last_sync_at = sync_state.last_sync.lock().ok().and_then(|g| g.clone());
+    let payload = build_payload(db, last_sync_at)?;
+    let attempted_at = Utc::now().to_rfc3339();
+    let outcome = crate::admin_fetch(Some(db), HEARTBEAT_PATH, "POST", Some(payload)).await;
+    let result = match outcome {
+        Ok(_) => json!({
+            "success": true,
+            "attemptedAt": attempted_at,
+            "lastSuccessAt": attempted_at,
+        }),
+        Err(error) => {
+            warn!(error = %error, "Heartbeat send failed");
+            let previous_success_at = db
+                .conn
+                .lock()
+                .ok()
+                .and_then(|conn| last_result(&conn))
+                .and_then(|prev| prev.get("lastSuccessAt").cloned())
+                .unwrap_or(Value::Null);
+            json!({
+                "success": false,
+                "attemptedAt": attempted_at,
+                "lastSuccessAt": previous_success_at,
+                "error": error,
+            })
+        }
+    };
+    record_result(db, &result);
+    Ok(result)
+}
+
+/// Health entry for `system_health_check`: warns when the last heartbeat
+/// failed so on-site staff know head office cannot see the terminal.
+pub fn check_heartbeat_health(db: &DbState) -> Value {
+    let last = db.conn.lock().ok().and_then(|conn| last_result(&conn));
+    let Some(last) = last else {
+        return diagnostics::health_entry(
+            HealthStatus::Ok,
+            "No heartbeat sent yet",
+            json!({ "lastAttemptAt": null, "lastSuccessAt": null }),
+        );
+    };
+    let extra = json!({
+        "lastAttemptAt": last.get("attemptedAt").cloned().unwrap_or(Value::Null),
+        "lastSuccessAt": last.get("lastSuccessAt").cloned().unwrap_or(Value::Null),
+        "lastError": last.get("error").cloned().unwrap_or(Value::Null),
+    });
+    if last.get("success").and_then(Value::as_bool) == Some(true) {
+        diagnostics::health_entry(HealthStatus::Ok, "Head office can see this terminal", extra)
+    } else {
+        diagnostics::health_entry(
+            HealthStatus::Warn,
+            "Last heartbeat to head office failed",
+            extra,
+        )
+    }
+}
+
+pub fn start_heartbeat_task(
+    db: Arc<DbState>,
+    sync_state: Arc<sync::SyncState>,
+    cancel: tokio_util::sync::CancellationToken,
+) {
+    tauri::async_runtime::spawn(async move {
+        info!("Terminal heartbeat started");
+        loop {
+            if let Err(error) = send(db.as_ref(), sync_state.as_ref()).await {
+                warn!(error = %error, "Heartbeat skipped");
+            }
+
+            let minutes = db
+                .conn
+                .lock()
+                .map(|conn| interval_minutes(&conn))
+                .unwrap_or(DEFAULT_INTERVAL_MINUTES);
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(minutes * 60)) => {}
+                _ = cancel.cancelled() => {
+                    info!("Terminal heartbeat cancelled");
+                    break;
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> DbState {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        db::run_migrations_for_test(&conn);
+        DbState::new(conn, std::path::PathBuf::from(":memory:"))
+    }
+
+    #[test]
+    fn interval_defaults_and_clamps() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        assert_eq!(interval_minutes(&conn), DEFAULT_INTERVAL_MINUTES);
+        db::set_setting(&conn, SETTINGS_CATEGORY, INTERVAL_KEY, "0").unwrap();
+        assert_eq!(interval_minutes(&conn), DEFAULT_INTERVAL_MINUTES);
+        db::set_setting(&conn, SETTINGS_CATEGORY, INTERVAL_KEY, "999999").unwrap();
+        assert_eq!(interval_minutes(&conn), MAX_INTERVAL_MINUTES);
+    }
+
+    #[test]
+    fn payload_reports_queue_depth_and_open_shift() {
+        let db = test_db();
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute_batch(
+                "INSERT INTO sync_queue (entity_type, entity_id, operation, payload,
+                                         idempotency_key, status)
+                 VALUES ('order', 'o-1', 'insert', '{}', 'k-1', 'pending'),
+                        ('order', 'o-2', 'insert', '{}', 'k-2', 'failed');
+                 INSERT INTO staff_shifts (id, staff_id, role_type, check_in_time, status,
+                                           created_at, updated_at)
+                 VALUES ('sh-1', 'st-1', 'cashier', '2026-05-03T08:00:00Z', 'active',
+                         '2026-05-03T08:00:00Z', '2026-05-03T08:00:00Z');",
+            )
+            .unwrap();
+        }
+        let payload = build_payload(&db, Some("2026-05-03T09:00:00Z".into())).unwrap();
+        assert_eq!(payload["pendingSyncCount"], 1);
+        assert_eq!(payload["failedSyncCount"], 1);
+        assert_eq!(payload["openShift"], true);
+        assert_eq!(payload["printerFailureCount"], 0);
+        assert_eq!(payload["lastSyncAt"], "2026-05-03T09:00:00Z");
+        assert_eq!(payload["appVersion"], env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn health_reflects_last_result() {
+        let db = test_db();
+        assert_eq!(check_heartbeat_health(&db)["status"], "ok");
+        record_result(
+            &db,
+            &json!({
+                "success": false,
+                "attemptedAt": "2026-05-03T09:05:00Z",
+                "lastSuccessAt": "2026-05-03T09:00:00Z",
+                "error": "HTTP 503",
+            }),
+        );
+        let health = check_heartbeat_health(&db);
+        assert_eq!(health["status"], "warn");
+        assert_eq!(health["lastSuccessAt"], "2026-05-03T09:00:00Z");
+        assert_eq!(health["lastError"], "HTTP 503");
+    }
+}
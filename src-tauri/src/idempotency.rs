@@ -28,10 +28,36 @@
 //! `db::get_entity_idempotency_key` is still `#[allow(dead_code)]`. Wave 5
 //! (`sync_queue.rs::prepare_financial_request` et al) removes that
 //! annotation and replaces the `parity:{row_uuid}` call site.
+//!
+//! # Command response cache
+//!
+//! Sync-queue keys protect the server; they do nothing when the webview
+//! times out on `order_create` and invokes it again — the second call
+//! creates a second local row. Mutation commands therefore accept an
+//! optional `idempotencyKey` in their payload and run through
+//! [`run_once`]: the first call claims `(command, key)` in
+//! `idempotency_cache` and stores its response, and a repeat within
+//! [`RESPONSE_TTL_HOURS`] gets that response back without executing.
+//! The table is deliberately left alone by `clear_operational_data` — a
+//! retry still pending across the clear must not execute twice — and
+//! expired rows are pruned by the retention scheduler.
+
+use std::future::Future;
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use rusqlite::{params, Connection};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::db::{self, DbState};
+use crate::error::PosError;
 
-use rusqlite::Connection;
+/// How long a completed command response stays replayable.
+pub const RESPONSE_TTL_HOURS: i64 = 24;
 
-use crate::db;
+/// A `pending` claim older than this belongs to a call that never finished
+/// (crash, killed process) and may be taken over by the next retry.
+const PENDING_STALE_SECS: i64 = 120;
 
 /// Build a stable idempotency key for an entity-scoped sync-queue row.
 ///
@@ -81,6 +107,194 @@ pub(crate) fn synthetic_key(table: &str, record_id: &str) -> String {
     format!("entity:{table}:{record_id}")
 }
 
+/// Outcome of [`claim`] for one `(command, key)` pair.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Claim {
+    /// First call: execute, then [`complete`] or [`release`].
+    Fresh,
+    /// Already executed: return this response instead.
+    Replay(Value),
+    /// Another call with the same key is still executing.
+    InFlight,
+}
+
+fn cache_timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// The caller-supplied `idempotencyKey` (or `idempotency_key`) of a
+/// command payload, trimmed; `None` when absent or blank.
+pub(crate) fn request_key(payload: &Value) -> Option<String> {
+    ["idempotencyKey", "idempotency_key"]
+        .iter()
+        .find_map(|field| payload.get(*field).and_then(Value::as_str))
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(ToString::to_string)
+}
+
+/// Check for and reserve `(command, key)` in one step. The caller holds
+/// the DB mutex for the duration, so two concurrent calls can never both
+/// see [`Claim::Fresh`].
+pub(crate) fn claim(
+    conn: &Connection,
+    command: &str,
+    key: &str,
+    now: DateTime<Utc>,
+) -> Result<Claim, String> {
+    conn.execute(
+        "DELETE FROM idempotency_cache
+         WHERE command = ?1 AND idempotency_key = ?2
+           AND (expires_at <= ?3 OR (status = 'pending' AND created_at <= ?4))",
+        params![
+            command,
+            key,
+            cache_timestamp(now),
+            cache_timestamp(now - Duration::seconds(PENDING_STALE_SECS))
+        ],
+    )
+    .map_err(|e| format!("expire idempotency claim: {e}"))?;
+
+    let inserted = conn
+        .execute(
+            "INSERT OR IGNORE INTO idempotency_cache
+                (command, idempotency_key, status, created_at, expires_at)
+             VALUES (?1, ?2, 'pending', ?3, ?4)",
+            params![
+                command,
+                key,
+                cache_timestamp(now),
+                cache_timestamp(now + Duration::hours(RESPONSE_TTL_HOURS))
+            ],
+        )
+        .map_err(|e| format!("insert idempotency claim: {e}"))?;
+    if inserted == 1 {
+        return Ok(Claim::Fresh);
+    }
+
+    let (status, response_json): (String, Option<String>) = conn
+        .query_row(
+            "SELECT status, response_json FROM idempotency_cache
+             WHERE command = ?1 AND idempotency_key = ?2",
+            params![command, key],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("read idempotency claim: {e}"))?;
+    match (status.as_str(), response_json) {
+        ("completed", Some(raw)) => serde_json::from_str(&raw)
+            .map(Claim::Replay)
+            .map_err(|e| format!("parse cached response: {e}")),
+        _ => Ok(Claim::InFlight),
+    }
+}
+
+/// Store the response of a [`Claim::Fresh`] call and restart its TTL.
+pub(crate) fn complete(
+    conn: &Connection,
+    command: &str,
+    key: &str,
+    response: &Value,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    let raw = serde_json::to_string(response).map_err(|e| format!("serialize response: {e}"))?;
+    conn.execute(
+        "UPDATE idempotency_cache
+         SET status = 'completed', response_json = ?3, expires_at = ?4
+         WHERE command = ?1 AND idempotency_key = ?2",
+        params![
+            command,
+            key,
+            raw,
+            cache_timestamp(now + Duration::hours(RESPONSE_TTL_HOURS))
+        ],
+    )
+    .map_err(|e| format!("store idempotent response: {e}"))?;
+    Ok(())
+}
+
+/// Drop the claim of a call that failed, so a retry executes again.
+pub(crate) fn release(conn: &Connection, command: &str, key: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM idempotency_cache
+         WHERE command = ?1 AND idempotency_key = ?2 AND status = 'pending'",
+        params![command, key],
+    )
+    .map_err(|e| format!("release idempotency claim: {e}"))?;
+    Ok(())
+}
+
+/// Delete expired responses. Returns the number of rows removed.
+pub fn prune_expired(conn: &Connection, now: DateTime<Utc>) -> Result<usize, String> {
+    conn.execute(
+        "DELETE FROM idempotency_cache WHERE expires_at <= ?1",
+        params![cache_timestamp(now)],
+    )
+    .map_err(|e| format!("prune idempotency cache: {e}"))
+}
+
+/// Run a mutation command at most once per `key`.
+///
+/// Without a key this is just `run().await`. With one, a repeated call
+/// returns the first call's response, and a call that overlaps one still
+/// executing is rejected with [`PosError::Conflict`]. Only successful
+/// responses are cached: an `Err`, or an `Ok` carrying `"success": false`
+/// (lock guards, validation rejections), releases the claim so the retry
+/// runs for real.
+pub(crate) async fn run_once<E, F, Fut>(
+    db: &DbState,
+    command: &str,
+    key: Option<String>,
+    run: F,
+) -> Result<Value, E>
+where
+    E: From<PosError>,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Value, E>>,
+{
+    let Some(key) = key else {
+        return run().await;
+    };
+
+    let claimed = {
+        let conn = db
+            .conn
+            .lock()
+            .map_err(|e| PosError::Internal(e.to_string()))?;
+        claim(&conn, command, &key, Utc::now()).map_err(PosError::from)?
+    };
+    match claimed {
+        Claim::Fresh => {}
+        Claim::Replay(response) => {
+            info!(command, idempotency_key = %key, "Replaying cached command response");
+            return Ok(response);
+        }
+        Claim::InFlight => {
+            return Err(PosError::Conflict(format!(
+                "{command} with this idempotency key is already in progress"
+            ))
+            .into());
+        }
+    }
+
+    let result = run().await;
+    let cacheable = matches!(
+        &result,
+        Ok(response) if response.get("success").and_then(Value::as_bool) != Some(false)
+    );
+    let stored = db
+        .conn
+        .lock()
+        .map_err(|e| e.to_string())
+        .and_then(|conn| match &result {
+            Ok(response) if cacheable => complete(&conn, command, &key, response, Utc::now()),
+            _ => release(&conn, command, &key),
+        });
+    if let Err(error) = stored {
+        warn!(command, idempotency_key = %key, error = %error, "Failed to settle idempotency claim");
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -147,4 +361,101 @@ mod tests {
         let b = make_entity_key(&conn, "order_payments", "same");
         assert_eq!(a, b);
     }
+
+    fn open_with_migrations() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    #[test]
+    fn request_key_reads_either_casing_and_ignores_blank() {
+        assert_eq!(
+            request_key(&serde_json::json!({ "idempotencyKey": " k-1 " })).as_deref(),
+            Some("k-1")
+        );
+        assert_eq!(
+            request_key(&serde_json::json!({ "idempotency_key": "k-2" })).as_deref(),
+            Some("k-2")
+        );
+        assert_eq!(
+            request_key(&serde_json::json!({ "idempotencyKey": "  " })),
+            None
+        );
+        assert_eq!(request_key(&serde_json::json!({})), None);
+    }
+
+    #[test]
+    fn claim_replays_completed_response_and_blocks_overlapping_call() {
+        let conn = open_with_migrations();
+        let now = Utc::now();
+        let response = serde_json::json!({ "success": true, "orderId": "ord-1" });
+
+        assert_eq!(
+            claim(&conn, "order_create", "k", now).unwrap(),
+            Claim::Fresh
+        );
+        assert_eq!(
+            claim(&conn, "order_create", "k", now).unwrap(),
+            Claim::InFlight
+        );
+        assert_eq!(
+            claim(&conn, "payment_record", "k", now).unwrap(),
+            Claim::Fresh,
+            "keys are scoped per command"
+        );
+
+        complete(&conn, "order_create", "k", &response, now).unwrap();
+        assert_eq!(
+            claim(&conn, "order_create", "k", now).unwrap(),
+            Claim::Replay(response)
+        );
+    }
+
+    #[test]
+    fn released_and_abandoned_claims_can_be_retaken() {
+        let conn = open_with_migrations();
+        let now = Utc::now();
+
+        assert_eq!(claim(&conn, "shift_open", "k", now).unwrap(), Claim::Fresh);
+        release(&conn, "shift_open", "k").unwrap();
+        assert_eq!(claim(&conn, "shift_open", "k", now).unwrap(), Claim::Fresh);
+
+        let later = now + Duration::seconds(PENDING_STALE_SECS + 1);
+        assert_eq!(
+            claim(&conn, "shift_open", "k", later).unwrap(),
+            Claim::Fresh
+        );
+    }
+
+    #[test]
+    fn prune_expired_drops_responses_past_ttl() {
+        let conn = open_with_migrations();
+        let now = Utc::now();
+        claim(&conn, "refund_payment", "old", now).unwrap();
+        complete(&conn, "refund_payment", "old", &serde_json::json!({}), now).unwrap();
+        claim(&conn, "refund_payment", "new", now + Duration::hours(12)).unwrap();
+        complete(
+            &conn,
+            "refund_payment",
+            "new",
+            &serde_json::json!({}),
+            now + Duration::hours(12),
+        )
+        .unwrap();
+
+        let pruned = prune_expired(&conn, now + Duration::hours(RESPONSE_TTL_HOURS + 1)).unwrap();
+
+        assert_eq!(pruned, 1);
+        assert_eq!(
+            claim(
+                &conn,
+                "refund_payment",
+                "new",
+                now + Duration::hours(RESPONSE_TTL_HOURS + 1)
+            )
+            .unwrap(),
+            Claim::Replay(serde_json::json!({}))
+        );
+    }
 }
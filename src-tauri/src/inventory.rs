@@ -0,0 +1,1291 @@
+//! Ingredient-level stock tracking driven by sales.
+//!
+//! `inventory_items` holds the current quantity and low-stock threshold of
+//! each ingredient, either pulled from the admin API or maintained on the
+//! terminal. `inventory_recipes` maps a menu item (subcategory) to the
+//! ingredient quantities one unit consumes. When an order reaches
+//! `confirmed` — or is paid, if `inventory.decrement_on = payment` — its
+//! items are deducted once, inside a savepoint, and every item that falls to
+//! or below its threshold is reported so the caller can emit
+//! `inventory_low_stock`.
+//!
+//! Every stock change is recorded in `inventory_movements` and queued to the
+//! parity sync queue as an `inventory_movements` row, so the admin ledger
+//! sees sales, manual corrections, deliveries and refund restocks alike.
+//!
+//! Deliveries may carry a `unit_cost`. `inventory_modifier_recipes` maps a
+//! menu ingredient picked as a modifier to the stock it adds (or, when
+//! left out, saves), and [`margin_report`] prices sold items at the
+//! period's weighted-average cost and compares theoretical usage with the
+//! ledger.
+
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use tauri::Emitter;
+use tracing::{info, warn};
+
+use crate::{db, storage, sync_queue, value_f64, value_str};
+
+pub const SETTINGS_CATEGORY: &str = "inventory";
+pub const DECREMENT_ON_KEY: &str = "decrement_on";
+
+pub const TRIGGER_CONFIRMED: &str = "confirmed";
+pub const TRIGGER_PAYMENT: &str = "payment";
+
+pub const MOVEMENT_SALE: &str = "sale";
+pub const MOVEMENT_ADJUSTMENT: &str = "adjustment";
+pub const MOVEMENT_RECEIVE: &str = "receive";
+pub const MOVEMENT_REFUND_RESTOCK: &str = "refund_restock";
+
+/// Order statuses at or past confirmation. Orders can skip `confirmed`
+/// (e.g. straight to `completed` at the counter); the deduction is
+/// idempotent, so any of these triggers it once.
+const CONFIRMED_OR_LATER_STATUSES: &[&str] = &[
+    "confirmed",
+    "preparing",
+    "ready",
+    "out_for_delivery",
+    "delivered",
+    "completed",
+];
+
+/// Which event deducts stock: `confirmed` (default) or `payment`.
+pub fn decrement_trigger(conn: &Connection) -> &'static str {
+    match db::get_setting(conn, SETTINGS_CATEGORY, DECREMENT_ON_KEY)
+        .map(|raw| raw.trim().to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("payment") | Some("paid") => TRIGGER_PAYMENT,
+        _ => TRIGGER_CONFIRMED,
+    }
+}
+
+pub fn status_reaches_confirmed(status: &str) -> bool {
+    CONFIRMED_OR_LATER_STATUSES.contains(&status.trim().to_ascii_lowercase().as_str())
+}
+
+fn round_qty(value: f64) -> f64 {
+    (value * 10_000.0).round() / 10_000.0
+}
+
+fn item_row_to_json(row: &rusqlite::Row<'_>) -> rusqlite::Result<Value> {
+    let quantity: f64 = row.get("quantity")?;
+    let threshold: f64 = row.get("low_stock_threshold")?;
+    Ok(serde_json::json!({
+        "id": row.get::<_, String>("id")?,
+        "remoteId": row.get::<_, Option<String>>("remote_id")?,
+        "name": row.get::<_, String>("name")?,
+        "unit": row.get::<_, Option<String>>("unit")?,
+        "quantity": quantity,
+        "lowStockThreshold": threshold,
+        "status": stock_status(quantity, threshold),
+        "unitCost": row.get::<_, Option<f64>>("unit_cost")?,
+        "source": row.get::<_, String>("source")?,
+        "updatedAt": row.get::<_, String>("updated_at")?,
+    }))
+}
+
+fn stock_status(quantity: f64, threshold: f64) -> &'static str {
+    if quantity <= 0.0 {
+        "out_of_stock"
+    } else if quantity <= threshold {
+        "low_stock"
+    } else {
+        "in_stock"
+    }
+}
+
+pub fn list_items(conn: &Connection) -> Result<Vec<Value>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, remote_id, name, unit, quantity, low_stock_threshold, unit_cost, source,
+                    updated_at
+             FROM inventory_items
+             ORDER BY name COLLATE NOCASE",
+        )
+        .map_err(|e| format!("prepare inventory items: {e}"))?;
+    let rows = stmt
+        .query_map([], item_row_to_json)
+        .map_err(|e| format!("query inventory items: {e}"))?;
+    Ok(rows.filter_map(Result::ok).collect())
+}
+
+pub fn get_item(conn: &Connection, item_id: &str) -> Result<Option<Value>, String> {
+    conn.query_row(
+        "SELECT id, remote_id, name, unit, quantity, low_stock_threshold, unit_cost, source,
+                updated_at
+         FROM inventory_items
+         WHERE id = ?1 OR remote_id = ?1",
+        params![item_id],
+        item_row_to_json,
+    )
+    .optional()
+    .map_err(|e| format!("load inventory item: {e}"))
+}
+
+/// In / low / out-of-stock counts for the dashboard tile.
+pub fn stock_metrics(conn: &Connection) -> Result<Value, String> {
+    let (total, in_stock, low_stock, out_of_stock) = conn
+        .query_row(
+            "SELECT COUNT(*),
+                    COALESCE(SUM(CASE WHEN quantity > low_stock_threshold AND quantity > 0 THEN 1 ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN quantity > 0 AND quantity <= low_stock_threshold THEN 1 ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN quantity <= 0 THEN 1 ELSE 0 END), 0)
+             FROM inventory_items",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            },
+        )
+        .map_err(|e| format!("inventory metrics: {e}"))?;
+    Ok(serde_json::json!({
+        "success": true,
+        "totalItems": total,
+        "inStock": in_stock,
+        "lowStock": low_stock,
+        "outOfStock": out_of_stock,
+    }))
+}
+
+/// Create or update a locally maintained item. Quantity is only set on
+/// creation; later changes go through [`record_movement`] so they are
+/// ledgered and synced.
+pub fn upsert_local_item(conn: &Connection, payload: &Value) -> Result<Value, String> {
+    let name = value_str(payload, &["name"])
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .ok_or("Missing inventory item name")?;
+    let id = value_str(payload, &["id", "inventoryItemId", "inventory_item_id"])
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let unit = value_str(payload, &["unit"]);
+    let threshold = value_f64(payload, &["lowStockThreshold", "low_stock_threshold"])
+        .unwrap_or(0.0)
+        .max(0.0);
+    let quantity = value_f64(payload, &["quantity"]).unwrap_or(0.0);
+    let now = Utc::now().to_rfc3339();
+
+    conn.execute(
+        "INSERT INTO inventory_items
+            (id, name, unit, quantity, low_stock_threshold, source, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, 'local', ?6, ?6)
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            unit = excluded.unit,
+            low_stock_threshold = excluded.low_stock_threshold,
+            updated_at = excluded.updated_at",
+        params![id, name, unit, round_qty(quantity), threshold, now],
+    )
+    .map_err(|e| format!("upsert inventory item: {e}"))?;
+
+    get_item(conn, &id)?.ok_or_else(|| "Inventory item not found after upsert".to_string())
+}
+
+/// Upsert items pulled from `/api/pos/sync/inventory_items`. The admin
+/// quantity is authoritative for synced items; movements still waiting in
+/// the queue are applied server-side when they drain.
+pub fn import_admin_items(conn: &Connection, items: &[Value]) -> Result<usize, String> {
+    let now = Utc::now().to_rfc3339();
+    let mut count = 0usize;
+    for item in items {
+        let Some(remote_id) = value_str(item, &["id"]).filter(|v| !v.trim().is_empty()) else {
+            continue;
+        };
+        let name = value_str(item, &["name"]).unwrap_or_else(|| remote_id.clone());
+        let quantity = value_f64(
+            item,
+            &[
+                "quantity",
+                "current_stock",
+                "stock_quantity",
+                "currentStock",
+            ],
+        )
+        .unwrap_or(0.0);
+        let threshold = value_f64(
+            item,
+            &[
+                "low_stock_threshold",
+                "lowStockThreshold",
+                "min_stock",
+                "reorder_level",
+            ],
+        )
+        .unwrap_or(0.0)
+        .max(0.0);
+        conn.execute(
+            "INSERT INTO inventory_items
+                (id, remote_id, name, unit, quantity, low_stock_threshold, source, created_at, updated_at)
+             VALUES (?1, ?1, ?2, ?3, ?4, ?5, 'admin', ?6, ?6)
+             ON CONFLICT(id) DO UPDATE SET
+                remote_id = excluded.remote_id,
+                name = excluded.name,
+                unit = excluded.unit,
+                quantity = excluded.quantity,
+                low_stock_threshold = excluded.low_stock_threshold,
+                source = 'admin',
+                updated_at = excluded.updated_at",
+            params![
+                remote_id,
+                name,
+                value_str(item, &["unit"]),
+                round_qty(quantity),
+                threshold,
+                now
+            ],
+        )
+        .map_err(|e| format!("import inventory item: {e}"))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Replace the recipe for one menu item. An empty `ingredients` list
+/// removes the mapping.
+pub fn set_recipe(
+    conn: &Connection,
+    menu_item_id: &str,
+    ingredients: &[(String, f64)],
+) -> Result<(), String> {
+    replace_recipe_lines(
+        conn,
+        "inventory_recipes",
+        "menu_item_id",
+        menu_item_id,
+        ingredients,
+    )
+}
+
+/// Replace the stock one portion of a modifier (a menu ingredient) stands
+/// for. Picking the modifier consumes these quantities; leaving it out of
+/// an item saves them. An empty `ingredients` list removes the mapping.
+pub fn set_modifier_recipe(
+    conn: &Connection,
+    modifier_id: &str,
+    ingredients: &[(String, f64)],
+) -> Result<(), String> {
+    replace_recipe_lines(
+        conn,
+        "inventory_modifier_recipes",
+        "modifier_id",
+        modifier_id,
+        ingredients,
+    )
+}
+
+fn replace_recipe_lines(
+    conn: &Connection,
+    table: &str,
+    key_column: &str,
+    key: &str,
+    ingredients: &[(String, f64)],
+) -> Result<(), String> {
+    conn.execute_batch("SAVEPOINT inventory_set_recipe")
+        .map_err(|e| format!("savepoint inventory_set_recipe: {e}"))?;
+    let result = (|| -> Result<(), String> {
+        conn.execute(
+            &format!("DELETE FROM {table} WHERE {key_column} = ?1"),
+            params![key],
+        )
+        .map_err(|e| format!("clear recipe: {e}"))?;
+        for (inventory_item_id, quantity) in ingredients {
+            if *quantity <= 0.0 {
+                continue;
+            }
+            if get_item(conn, inventory_item_id)?.is_none() {
+                return Err(format!("Unknown inventory item: {inventory_item_id}"));
+            }
+            conn.execute(
+                &format!(
+                    "INSERT INTO {table} ({key_column}, inventory_item_id, quantity)
+                     VALUES (?1, ?2, ?3)"
+                ),
+                params![key, inventory_item_id, quantity],
+            )
+            .map_err(|e| format!("insert recipe line: {e}"))?;
+        }
+        Ok(())
+    })();
+    finish_savepoint(conn, "inventory_set_recipe", result)
+}
+
+fn finish_savepoint<T>(
+    conn: &Connection,
+    name: &str,
+    result: Result<T, String>,
+) -> Result<T, String> {
+    match result {
+        Ok(value) => {
+            conn.execute_batch(&format!("RELEASE {name}"))
+                .map_err(|e| format!("release {name}: {e}"))?;
+            Ok(value)
+        }
+        Err(e) => {
+            let _ = conn.execute_batch(&format!("ROLLBACK TO {name}; RELEASE {name}"));
+            Err(e)
+        }
+    }
+}
+
+pub struct MovementInput<'a> {
+    pub inventory_item_id: &'a str,
+    pub movement_type: &'a str,
+    pub quantity_delta: f64,
+    pub reason: Option<&'a str>,
+    pub order_id: Option<&'a str>,
+    pub reference_id: Option<&'a str>,
+    pub staff_id: Option<&'a str>,
+    /// Cost per stock unit; recorded for deliveries and becomes the item's
+    /// last received cost.
+    pub unit_cost: Option<f64>,
+}
+
+/// Apply one stock change, ledger it and queue it for sync. Returns the
+/// item after the change and whether it crossed into low/out-of-stock.
+pub fn record_movement(
+    conn: &Connection,
+    input: &MovementInput<'_>,
+) -> Result<(Value, bool), String> {
+    let (item_id, remote_id, before, threshold): (String, Option<String>, f64, f64) = conn
+        .query_row(
+            "SELECT id, remote_id, quantity, low_stock_threshold
+             FROM inventory_items
+             WHERE id = ?1 OR remote_id = ?1",
+            params![input.inventory_item_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()
+        .map_err(|e| format!("load inventory item: {e}"))?
+        .ok_or_else(|| format!("Unknown inventory item: {}", input.inventory_item_id))?;
+
+    let after = round_qty(before + input.quantity_delta);
+    let now = Utc::now().to_rfc3339();
+    let movement_id = uuid::Uuid::new_v4().to_string();
+
+    conn.execute(
+        "UPDATE inventory_items SET quantity = ?1, updated_at = ?2 WHERE id = ?3",
+        params![after, now, item_id],
+    )
+    .map_err(|e| format!("update inventory quantity: {e}"))?;
+    let unit_cost = input
+        .unit_cost
+        .filter(|cost| input.movement_type == MOVEMENT_RECEIVE && *cost >= 0.0);
+    if let Some(cost) = unit_cost {
+        conn.execute(
+            "UPDATE inventory_items SET unit_cost = ?1 WHERE id = ?2",
+            params![cost, item_id],
+        )
+        .map_err(|e| format!("update inventory unit cost: {e}"))?;
+    }
+    conn.execute(
+        "INSERT INTO inventory_movements
+            (id, inventory_item_id, movement_type, quantity_delta, quantity_after,
+             reason, order_id, reference_id, staff_id, unit_cost, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            movement_id,
+            item_id,
+            input.movement_type,
+            round_qty(input.quantity_delta),
+            after,
+            input.reason,
+            input.order_id,
+            input.reference_id,
+            input.staff_id,
+            unit_cost,
+            now
+        ],
+    )
+    .map_err(|e| format!("insert inventory movement: {e}"))?;
+
+    let payload = serde_json::json!({
+        "id": movement_id,
+        "inventory_item_id": remote_id.as_deref().unwrap_or(&item_id),
+        "local_inventory_item_id": item_id,
+        "movement_type": input.movement_type,
+        "quantity_delta": round_qty(input.quantity_delta),
+        "quantity_after": after,
+        "reason": input.reason,
+        "order_id": input.order_id,
+        "reference_id": input.reference_id,
+        "staff_id": input.staff_id,
+        "unit_cost": unit_cost,
+        "branch_id": storage::get_credential("branch_id"),
+        "terminal_id": storage::get_credential("terminal_id"),
+        "created_at": now,
+    });
+    sync_queue::enqueue_payload_item(
+        conn,
+        "inventory_movements",
+        &movement_id,
+        "INSERT",
+        &payload,
+        Some(0),
+        Some("inventory"),
+        Some("manual"),
+        Some(1),
+    )
+    .map_err(|e| format!("enqueue inventory movement: {e}"))?;
+
+    let crossed = (before > threshold && after <= threshold) || (before > 0.0 && after <= 0.0);
+    let item = get_item(conn, &item_id)?.unwrap_or(Value::Null);
+    Ok((item, crossed))
+}
+
+fn order_item_menu_id(item: &Value) -> Option<String> {
+    value_str(
+        item,
+        &[
+            "menu_item_id",
+            "menuItemId",
+            "subcategory_id",
+            "subcategoryId",
+        ],
+    )
+    .map(|v| v.trim().to_string())
+    .filter(|v| !v.is_empty() && !v.eq_ignore_ascii_case("manual"))
+}
+
+/// One modifier picked on an order line: the menu ingredient, how many
+/// portions (a "little" counts as half) and whether it was left out.
+struct ModifierUse {
+    modifier_id: String,
+    portions: f64,
+    without: bool,
+}
+
+fn value_flag(value: &Value, keys: &[&str]) -> bool {
+    keys.iter()
+        .any(|key| value.get(*key).and_then(Value::as_bool).unwrap_or(false))
+}
+
+/// Modifiers on an order item. Customizations arrive as an array, an
+/// object keyed by ingredient id, or either of those as a JSON string.
+fn order_item_modifiers(item: &Value) -> Vec<ModifierUse> {
+    let mut uses = Vec::new();
+    for key in [
+        "customizations",
+        "modifiers",
+        "ingredients",
+        "selectedIngredients",
+    ] {
+        let Some(raw) = item.get(key) else {
+            continue;
+        };
+        let parsed;
+        let value = match raw {
+            Value::String(text) => {
+                parsed = serde_json::from_str::<Value>(text).unwrap_or(Value::Null);
+                &parsed
+            }
+            other => other,
+        };
+        let entries: Vec<&Value> = match value {
+            Value::Array(entries) => entries.iter().collect(),
+            Value::Object(map) => map.values().collect(),
+            _ => Vec::new(),
+        };
+        for entry in entries {
+            let modifier_id = entry
+                .get("ingredient")
+                .and_then(|ingredient| value_str(ingredient, &["id"]))
+                .or_else(|| {
+                    value_str(
+                        entry,
+                        &[
+                            "ingredientId",
+                            "ingredient_id",
+                            "modifierId",
+                            "modifier_id",
+                            "id",
+                        ],
+                    )
+                });
+            let Some(modifier_id) = modifier_id else {
+                continue;
+            };
+            let mut portions = value_f64(entry, &["quantity", "qty"])
+                .filter(|qty| *qty > 0.0)
+                .unwrap_or(1.0);
+            if value_flag(entry, &["isLittle", "is_little", "little"]) {
+                portions *= 0.5;
+            }
+            uses.push(ModifierUse {
+                modifier_id,
+                portions,
+                without: value_flag(entry, &["isWithout", "is_without", "without"]),
+            });
+        }
+    }
+    uses
+}
+
+/// Recipe lookups shared by every line of an order or report.
+struct RecipeBook<'conn> {
+    recipes: rusqlite::Statement<'conn>,
+    modifiers: rusqlite::Statement<'conn>,
+}
+
+impl<'conn> RecipeBook<'conn> {
+    fn new(conn: &'conn Connection) -> Result<Self, String> {
+        Ok(Self {
+            recipes: conn
+                .prepare(
+                    "SELECT inventory_item_id, quantity FROM inventory_recipes WHERE menu_item_id = ?1",
+                )
+                .map_err(|e| format!("prepare recipe lookup: {e}"))?,
+            modifiers: conn
+                .prepare(
+                    "SELECT inventory_item_id, quantity FROM inventory_modifier_recipes WHERE modifier_id = ?1",
+                )
+                .map_err(|e| format!("prepare modifier recipe lookup: {e}"))?,
+        })
+    }
+
+    fn lines(stmt: &mut rusqlite::Statement<'_>, key: &str) -> Result<Vec<(String, f64)>, String> {
+        stmt.query_map(params![key], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+        })
+        .map_err(|e| format!("query recipe lookup: {e}"))?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("read recipe lookup: {e}"))
+    }
+
+    /// Ingredients one unit of `item` consumes: its recipe plus added
+    /// modifiers, minus left-out ones, never below zero per ingredient.
+    /// The flag reports whether the menu item has a recipe of its own.
+    fn per_unit(
+        &mut self,
+        menu_item_id: &str,
+        item: &Value,
+    ) -> Result<(BTreeMap<String, f64>, bool), String> {
+        let mut usage: BTreeMap<String, f64> = BTreeMap::new();
+        let base = Self::lines(&mut self.recipes, menu_item_id)?;
+        let has_recipe = !base.is_empty();
+        for (inventory_item_id, quantity) in base {
+            *usage.entry(inventory_item_id).or_insert(0.0) += quantity;
+        }
+        for modifier in order_item_modifiers(item) {
+            let sign = if modifier.without { -1.0 } else { 1.0 };
+            for (inventory_item_id, quantity) in
+                Self::lines(&mut self.modifiers, &modifier.modifier_id)?
+            {
+                *usage.entry(inventory_item_id).or_insert(0.0) +=
+                    sign * quantity * modifier.portions;
+            }
+        }
+        usage.retain(|_, quantity| *quantity > 0.0);
+        Ok((usage, has_recipe))
+    }
+}
+
+/// Ingredient quantities consumed by `items` (order-item JSON shape).
+fn ingredient_requirements(
+    conn: &Connection,
+    items: &[Value],
+) -> Result<BTreeMap<String, f64>, String> {
+    let mut book = RecipeBook::new(conn)?;
+    let mut required: BTreeMap<String, f64> = BTreeMap::new();
+    for item in items {
+        let Some(menu_item_id) = order_item_menu_id(item) else {
+            continue;
+        };
+        let units = value_f64(item, &["quantity"]).unwrap_or(1.0).max(0.0);
+        let (per_unit, _) = book.per_unit(&menu_item_id, item)?;
+        for (inventory_item_id, quantity) in per_unit {
+            *required.entry(inventory_item_id).or_insert(0.0) += quantity * units;
+        }
+    }
+    Ok(required)
+}
+
+fn load_order_items(conn: &Connection, order_id: &str) -> Result<Vec<Value>, String> {
+    let items: Option<String> = conn
+        .query_row(
+            "SELECT items FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("load order items: {e}"))?;
+    Ok(items
+        .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+        .and_then(|v| v.as_array().cloned())
+        .unwrap_or_default())
+}
+
+/// Deduct the order's ingredients if `trigger` is the configured decrement
+/// point and the order has not been deducted yet. Returns the items that
+/// crossed their low-stock threshold.
+pub fn deduct_for_order(
+    conn: &Connection,
+    order_id: &str,
+    trigger: &str,
+    staff_id: Option<&str>,
+) -> Result<Vec<Value>, String> {
+    if decrement_trigger(conn) != trigger {
+        return Ok(Vec::new());
+    }
+    let already: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM inventory_movements WHERE order_id = ?1 AND movement_type = ?2)",
+            params![order_id, MOVEMENT_SALE],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("check inventory deduction: {e}"))?;
+    if already {
+        return Ok(Vec::new());
+    }
+    let required = ingredient_requirements(conn, &load_order_items(conn, order_id)?)?;
+    if required.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    conn.execute_batch("SAVEPOINT inventory_sale")
+        .map_err(|e| format!("savepoint inventory_sale: {e}"))?;
+    let result = (|| -> Result<Vec<Value>, String> {
+        let mut crossed = Vec::new();
+        for (inventory_item_id, quantity) in &required {
+            let (item, hit) = record_movement(
+                conn,
+                &MovementInput {
+                    inventory_item_id,
+                    movement_type: MOVEMENT_SALE,
+                    quantity_delta: -quantity,
+                    reason: Some(trigger),
+                    order_id: Some(order_id),
+                    reference_id: None,
+                    staff_id,
+                    unit_cost: None,
+                },
+            )?;
+            if hit {
+                crossed.push(item);
+            }
+        }
+        Ok(crossed)
+    })();
+    let crossed = finish_savepoint(conn, "inventory_sale", result)?;
+    info!(order_id = %order_id, ingredients = required.len(), trigger = %trigger, "Inventory deducted for order");
+    Ok(crossed)
+}
+
+/// Return refunded ingredients to stock. With `items` the refunded lines
+/// are restocked; without, whatever the order's sale deduction took and no
+/// earlier restock returned. Never restocks more than was deducted.
+pub fn restock_for_refund(
+    conn: &Connection,
+    order_id: &str,
+    items: Option<&[Value]>,
+    adjustment_id: &str,
+    staff_id: Option<&str>,
+) -> Result<usize, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT inventory_item_id,
+                    -SUM(CASE WHEN movement_type = ?2 THEN quantity_delta ELSE 0 END)
+                    - SUM(CASE WHEN movement_type = ?3 THEN quantity_delta ELSE 0 END)
+             FROM inventory_movements
+             WHERE order_id = ?1
+             GROUP BY inventory_item_id",
+        )
+        .map_err(|e| format!("prepare restock outstanding: {e}"))?;
+    let outstanding: BTreeMap<String, f64> = stmt
+        .query_map(
+            params![order_id, MOVEMENT_SALE, MOVEMENT_REFUND_RESTOCK],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)),
+        )
+        .map_err(|e| format!("query restock outstanding: {e}"))?
+        .filter_map(Result::ok)
+        .collect();
+
+    let requested = match items {
+        Some(items) => ingredient_requirements(conn, items)?,
+        None => outstanding.clone(),
+    };
+
+    let mut restocked = 0usize;
+    for (inventory_item_id, quantity) in requested {
+        let available = outstanding.get(&inventory_item_id).copied().unwrap_or(0.0);
+        let quantity = round_qty(quantity.min(available));
+        if quantity <= 0.0 {
+            continue;
+        }
+        record_movement(
+            conn,
+            &MovementInput {
+                inventory_item_id: &inventory_item_id,
+                movement_type: MOVEMENT_REFUND_RESTOCK,
+                quantity_delta: quantity,
+                reason: Some("refund"),
+                order_id: Some(order_id),
+                reference_id: Some(adjustment_id),
+                staff_id,
+                unit_cost: None,
+            },
+        )?;
+        restocked += 1;
+    }
+    Ok(restocked)
+}
+
+/// Emit `inventory_low_stock` for each item that crossed its threshold.
+pub fn emit_low_stock(app: &tauri::AppHandle, crossed: &[Value]) {
+    for item in crossed {
+        let _ = app.emit("inventory_low_stock", item.clone());
+    }
+}
+
+/// Run the sale deduction for an order mutation without failing it: stock
+/// tracking is advisory and must never block taking an order or a payment.
+pub fn deduct_for_order_logged(
+    conn: &Connection,
+    order_id: &str,
+    trigger: &str,
+    staff_id: Option<&str>,
+) -> Vec<Value> {
+    deduct_for_order(conn, order_id, trigger, staff_id).unwrap_or_else(|e| {
+        warn!(order_id = %order_id, trigger = %trigger, error = %e, "inventory deduction failed");
+        Vec::new()
+    })
+}
+
+pub const MARGIN_VARIANCE_THRESHOLD_KEY: &str = "margin_variance_threshold_percent";
+pub const DEFAULT_MARGIN_VARIANCE_THRESHOLD_PERCENT: f64 = 10.0;
+
+/// Allowed gap between ledger depletion and recipe usage before the margin
+/// report flags an ingredient, in percent of the theoretical usage.
+pub fn margin_variance_threshold(conn: &Connection) -> f64 {
+    db::get_setting(conn, SETTINGS_CATEGORY, MARGIN_VARIANCE_THRESHOLD_KEY)
+        .and_then(|raw| raw.trim().parse::<f64>().ok())
+        .filter(|value| value.is_finite() && *value >= 0.0)
+        .unwrap_or(DEFAULT_MARGIN_VARIANCE_THRESHOLD_PERCENT)
+}
+
+/// Cost per stock unit of every ingredient over `[start_at, end_at)`: the
+/// stock on hand at `start_at`, valued at the last cost received before
+/// it, blended with each delivery inside the range by quantity. Items
+/// never received with a cost fall back to their stored `unit_cost` and
+/// are left out when that is unset too.
+pub fn period_unit_costs(
+    conn: &Connection,
+    start_at: &str,
+    end_at: &str,
+) -> Result<BTreeMap<String, f64>, String> {
+    let items: Vec<(String, Option<f64>)> = conn
+        .prepare("SELECT id, unit_cost FROM inventory_items")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .map_err(|e| format!("load inventory costs: {e}"))?;
+    let in_range = crate::business_day::timestamp_in_range_sql("created_at", "?2", "?3");
+    let mut opening_stmt = conn
+        .prepare(
+            "SELECT quantity_after FROM inventory_movements
+             WHERE inventory_item_id = ?1 AND julianday(created_at) < julianday(?2)
+             ORDER BY julianday(created_at) DESC LIMIT 1",
+        )
+        .map_err(|e| format!("prepare opening stock: {e}"))?;
+    let mut opening_cost_stmt = conn
+        .prepare(
+            "SELECT unit_cost FROM inventory_movements
+             WHERE inventory_item_id = ?1 AND movement_type = ?3 AND unit_cost IS NOT NULL
+               AND julianday(created_at) < julianday(?2)
+             ORDER BY julianday(created_at) DESC LIMIT 1",
+        )
+        .map_err(|e| format!("prepare opening cost: {e}"))?;
+    let mut received_stmt = conn
+        .prepare(&format!(
+            "SELECT COALESCE(SUM(quantity_delta * unit_cost), 0), COALESCE(SUM(quantity_delta), 0)
+             FROM inventory_movements
+             WHERE inventory_item_id = ?1 AND movement_type = ?4 AND unit_cost IS NOT NULL
+               AND quantity_delta > 0 AND {in_range}"
+        ))
+        .map_err(|e| format!("prepare received costs: {e}"))?;
+
+    let mut costs = BTreeMap::new();
+    for (item_id, stored_cost) in items {
+        let opening_qty: f64 = opening_stmt
+            .query_row(params![item_id, start_at], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("load opening stock: {e}"))?
+            .unwrap_or(0.0_f64)
+            .max(0.0);
+        let opening_cost: Option<f64> = opening_cost_stmt
+            .query_row(params![item_id, start_at, MOVEMENT_RECEIVE], |row| {
+                row.get(0)
+            })
+            .optional()
+            .map_err(|e| format!("load opening cost: {e}"))?;
+        let (received_value, received_qty): (f64, f64) = received_stmt
+            .query_row(
+                params![item_id, start_at, end_at, MOVEMENT_RECEIVE],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| format!("load received costs: {e}"))?;
+
+        // The stored cost is the latest delivery, which may fall inside the
+        // range; it only values opening stock when nothing arrived since.
+        let opening_cost = opening_cost.or(stored_cost.filter(|_| received_qty <= 0.0));
+        let (mut value, mut quantity) = (received_value, received_qty);
+        if let Some(cost) = opening_cost {
+            value += opening_qty * cost;
+            quantity += opening_qty;
+        }
+        let cost = if quantity > 0.0 {
+            Some(value / quantity)
+        } else {
+            opening_cost.or(stored_cost)
+        };
+        if let Some(cost) = cost {
+            costs.insert(item_id, cost);
+        }
+    }
+    Ok(costs)
+}
+
+/// Sold quantity, revenue and recipe cost of one menu item in a margin
+/// report.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MarginLine {
+    pub menu_item_id: String,
+    pub name: String,
+    pub quantity: f64,
+    pub revenue: crate::money::Cents,
+    /// Theoretical cost at the period's weighted-average ingredient costs.
+    pub cost: f64,
+    /// Some ingredient used by these sales has no known cost.
+    pub missing_costs: bool,
+}
+
+impl MarginLine {
+    pub fn margin_percent(&self) -> Option<f64> {
+        let revenue = self.revenue.to_f64_dp2();
+        (revenue > 0.0).then(|| (revenue - self.cost) / revenue * 100.0)
+    }
+
+    fn to_json(&self) -> Value {
+        let cost = crate::money::Cents::round_half_even(self.cost);
+        let revenue = self.revenue.to_f64_dp2();
+        serde_json::json!({
+            "menuItemId": self.menu_item_id,
+            "name": self.name,
+            "quantity": round_qty(self.quantity),
+            "revenue": revenue,
+            "revenueCents": self.revenue.as_i64(),
+            "theoreticalCost": cost.to_f64_dp2(),
+            "theoreticalCostCents": cost.as_i64(),
+            "margin": (self.revenue - cost).to_f64_dp2(),
+            "marginPercent": self.margin_percent().map(|pct| (pct * 100.0).round() / 100.0),
+            "missingCosts": self.missing_costs,
+        })
+    }
+}
+
+/// An ingredient whose ledger depletion strays from recipe usage by more
+/// than the threshold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageVariance {
+    pub inventory_item_id: String,
+    pub name: String,
+    pub unit: Option<String>,
+    pub theoretical: f64,
+    pub actual: f64,
+    pub unit_cost: Option<f64>,
+}
+
+impl UsageVariance {
+    fn to_json(&self) -> Value {
+        let variance = self.actual - self.theoretical;
+        serde_json::json!({
+            "inventoryItemId": self.inventory_item_id,
+            "name": self.name,
+            "unit": self.unit,
+            "theoreticalUsage": round_qty(self.theoretical),
+            "actualUsage": round_qty(self.actual),
+            "variance": round_qty(variance),
+            "variancePercent": (self.theoretical > 0.0)
+                .then(|| (variance / self.theoretical * 10_000.0).round() / 100.0),
+            "varianceCost": self.unit_cost.map(|cost| {
+                crate::money::Cents::round_half_even(variance * cost).to_f64_dp2()
+            }),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MarginReport {
+    /// Items with a recipe, by revenue.
+    pub lines: Vec<MarginLine>,
+    /// Items sold without a recipe; kept out of the totals.
+    pub without_recipe: Vec<MarginLine>,
+    pub variances: Vec<UsageVariance>,
+    pub threshold_percent: f64,
+}
+
+impl MarginReport {
+    pub fn to_json(&self, date_from: &str, date_to: &str) -> Value {
+        let revenue: crate::money::Cents = self.lines.iter().map(|line| line.revenue).sum();
+        let cost = crate::money::Cents::round_half_even(self.lines.iter().map(|l| l.cost).sum());
+        let margin_percent = (revenue.is_positive()).then(|| {
+            ((revenue - cost).as_i64() as f64 / revenue.as_i64() as f64 * 10_000.0).round() / 100.0
+        });
+        serde_json::json!({
+            "success": true,
+            "dateFrom": date_from,
+            "dateTo": date_to,
+            "items": self.lines.iter().map(MarginLine::to_json).collect::<Vec<_>>(),
+            "itemsWithoutRecipe": self
+                .without_recipe
+                .iter()
+                .map(MarginLine::to_json)
+                .collect::<Vec<_>>(),
+            "variances": self.variances.iter().map(UsageVariance::to_json).collect::<Vec<_>>(),
+            "varianceThresholdPercent": self.threshold_percent,
+            "totals": {
+                "revenue": revenue.to_f64_dp2(),
+                "revenueCents": revenue.as_i64(),
+                "theoreticalCost": cost.to_f64_dp2(),
+                "theoreticalCostCents": cost.as_i64(),
+                "margin": (revenue - cost).to_f64_dp2(),
+                "marginPercent": margin_percent,
+            },
+        })
+    }
+}
+
+/// Gross margin of the items sold in `[start_at, end_at)`: revenue from
+/// order history against recipe cost (modifiers included) at the period's
+/// weighted-average ingredient costs. Theoretical ingredient usage is then
+/// compared with the non-delivery movements in the ledger over the same
+/// range; the ledger is this terminal's, so run it on the terminal that
+/// deducts stock for the branch.
+pub fn margin_report(
+    conn: &Connection,
+    branch_id: &str,
+    start_at: &str,
+    end_at: &str,
+    threshold_percent: f64,
+) -> Result<MarginReport, String> {
+    let costs = period_unit_costs(conn, start_at, end_at)?;
+    let rule = crate::money::RoundingRule::from_settings(conn);
+    let in_range = crate::business_day::timestamp_in_range_sql("o.created_at", "?2", "?3");
+    let mut orders = conn
+        .prepare(&format!(
+            "SELECT o.items FROM orders o
+             WHERE COALESCE(o.branch_id, '') = ?1
+               AND {in_range}
+               AND COALESCE(o.is_ghost, 0) = 0
+               AND LOWER(COALESCE(o.status, '')) NOT IN ('cancelled', 'canceled', 'refunded')"
+        ))
+        .map_err(|e| format!("prepare margin orders: {e}"))?;
+    let order_items: Vec<String> = orders
+        .query_map(params![branch_id, start_at, end_at], |row| {
+            row.get::<_, Option<String>>(0)
+        })
+        .map_err(|e| format!("query margin orders: {e}"))?
+        .filter_map(Result::ok)
+        .flatten()
+        .collect();
+
+    let mut book = RecipeBook::new(conn)?;
+    let mut lines: BTreeMap<String, (MarginLine, bool)> = BTreeMap::new();
+    let mut theoretical: BTreeMap<String, f64> = BTreeMap::new();
+    for raw in order_items {
+        let items = serde_json::from_str::<Value>(&raw)
+            .ok()
+            .and_then(|value| value.as_array().cloned())
+            .unwrap_or_default();
+        for item in items {
+            let Some(menu_item_id) = order_item_menu_id(&item) else {
+                continue;
+            };
+            let units = value_f64(&item, &["quantity"]).unwrap_or(1.0).max(0.0);
+            let (per_unit, has_recipe) = book.per_unit(&menu_item_id, &item)?;
+            let (line, recipe_seen) = lines.entry(menu_item_id.clone()).or_insert_with(|| {
+                let line = MarginLine {
+                    menu_item_id: menu_item_id.clone(),
+                    name: value_str(&item, &["name", "menuItemName", "menu_item_name"])
+                        .unwrap_or_else(|| menu_item_id.clone()),
+                    ..MarginLine::default()
+                };
+                (line, false)
+            });
+            *recipe_seen |= has_recipe;
+            line.quantity += units;
+            line.revenue += crate::money::item_line_cents(&item, rule);
+            for (inventory_item_id, quantity) in per_unit {
+                let used = quantity * units;
+                match costs.get(&inventory_item_id) {
+                    Some(cost) => line.cost += used * cost,
+                    None => line.missing_costs = true,
+                }
+                *theoretical.entry(inventory_item_id).or_insert(0.0) += used;
+            }
+        }
+    }
+
+    let mut report = MarginReport {
+        threshold_percent,
+        ..MarginReport::default()
+    };
+    for (line, has_recipe) in lines.into_values() {
+        if has_recipe {
+            report.lines.push(line);
+        } else {
+            report.without_recipe.push(line);
+        }
+    }
+    report
+        .lines
+        .sort_by_key(|line| std::cmp::Reverse(line.revenue));
+    report
+        .without_recipe
+        .sort_by_key(|line| std::cmp::Reverse(line.revenue));
+
+    let in_range = crate::business_day::timestamp_in_range_sql("m.created_at", "?1", "?2");
+    let mut usage = conn
+        .prepare(&format!(
+            "SELECT i.id, i.name, i.unit, -COALESCE(SUM(m.quantity_delta), 0)
+             FROM inventory_items i
+             LEFT JOIN inventory_movements m
+               ON m.inventory_item_id = i.id AND m.movement_type != ?3 AND {in_range}
+             GROUP BY i.id
+             ORDER BY i.name COLLATE NOCASE"
+        ))
+        .map_err(|e| format!("prepare ledger usage: {e}"))?;
+    let actual = usage
+        .query_map(params![start_at, end_at, MOVEMENT_RECEIVE], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, f64>(3)?,
+            ))
+        })
+        .map_err(|e| format!("query ledger usage: {e}"))?;
+    for row in actual {
+        let (id, name, unit, actual) = row.map_err(|e| format!("read ledger usage: {e}"))?;
+        let expected = theoretical.get(&id).copied().unwrap_or(0.0);
+        let gap = (actual - expected).abs();
+        let flagged = if expected > 0.0 {
+            gap > expected * threshold_percent / 100.0
+        } else {
+            gap > 0.0
+        };
+        if flagged && round_qty(gap) > 0.0 {
+            report.variances.push(UsageVariance {
+                unit_cost: costs.get(&id).copied(),
+                inventory_item_id: id,
+                name,
+                unit,
+                theoretical: expected,
+                actual,
+            });
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn insert_order(conn: &Connection, id: &str, items: &str) {
+        conn.execute(
+            "INSERT INTO orders (id, order_number, items, total_amount, status, order_type, sync_status, created_at, updated_at)
+             VALUES (?1, ?1, ?2, 10.0, 'pending', 'takeaway', 'pending', '2026-05-03T10:00:00Z', '2026-05-03T10:00:00Z')",
+            params![id, items],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn sale_deduction_runs_once_and_reports_threshold_crossing() {
+        let conn = test_conn();
+        upsert_local_item(
+            &conn,
+            &serde_json::json!({ "id": "bun", "name": "Bun", "quantity": 5, "lowStockThreshold": 2 }),
+        )
+        .unwrap();
+        set_recipe(&conn, "burger", &[("bun".to_string(), 1.0)]).unwrap();
+        insert_order(
+            &conn,
+            "ord-1",
+            r#"[{"menu_item_id": "burger", "quantity": 3}]"#,
+        );
+
+        assert!(deduct_for_order(&conn, "ord-1", TRIGGER_PAYMENT, None)
+            .unwrap()
+            .is_empty());
+        let crossed = deduct_for_order(&conn, "ord-1", TRIGGER_CONFIRMED, Some("staff-1")).unwrap();
+        assert_eq!(crossed.len(), 1);
+        assert_eq!(crossed[0]["quantity"], 2.0);
+        assert_eq!(crossed[0]["status"], "low_stock");
+        assert!(deduct_for_order(&conn, "ord-1", TRIGGER_CONFIRMED, None)
+            .unwrap()
+            .is_empty());
+
+        let metrics = stock_metrics(&conn).unwrap();
+        assert_eq!(metrics["lowStock"], 1);
+        let queued: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM parity_sync_queue WHERE table_name = 'inventory_movements'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(queued, 1);
+
+        let restocked = restock_for_refund(
+            &conn,
+            "ord-1",
+            Some(&[serde_json::json!({ "menu_item_id": "burger", "quantity": 5 })]),
+            "adj-1",
+            None,
+        )
+        .unwrap();
+        assert_eq!(restocked, 1);
+        let item = get_item(&conn, "bun").unwrap().unwrap();
+        assert_eq!(
+            item["quantity"], 5.0,
+            "restock is capped at the deducted amount"
+        );
+        assert_eq!(
+            restock_for_refund(&conn, "ord-1", None, "adj-2", None).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn margin_report_costs_modifiers_at_weighted_average_and_flags_variance() {
+        let conn = test_conn();
+        for id in ["bun", "patty", "cheese"] {
+            upsert_local_item(&conn, &serde_json::json!({ "id": id, "name": id })).unwrap();
+        }
+        let receive = |id: &str, quantity: f64, cost: f64| {
+            record_movement(
+                &conn,
+                &MovementInput {
+                    inventory_item_id: id,
+                    movement_type: MOVEMENT_RECEIVE,
+                    quantity_delta: quantity,
+                    reason: None,
+                    order_id: None,
+                    reference_id: None,
+                    staff_id: None,
+                    unit_cost: Some(cost),
+                },
+            )
+            .unwrap();
+        };
+        receive("patty", 10.0, 1.0);
+        let now = Utc::now();
+        let start_at = (now - chrono::Duration::days(1)).to_rfc3339();
+        let end_at = (now + chrono::Duration::days(1)).to_rfc3339();
+        conn.execute(
+            "UPDATE inventory_movements SET created_at = ?1",
+            params![(now - chrono::Duration::days(3)).to_rfc3339()],
+        )
+        .unwrap();
+        receive("patty", 10.0, 2.0);
+        receive("bun", 10.0, 0.5);
+        receive("cheese", 10.0, 0.2);
+        assert_eq!(get_item(&conn, "patty").unwrap().unwrap()["unitCost"], 2.0);
+
+        set_recipe(
+            &conn,
+            "burger",
+            &[("bun".to_string(), 1.0), ("patty".to_string(), 1.0)],
+        )
+        .unwrap();
+        set_modifier_recipe(&conn, "ing-cheese", &[("cheese".to_string(), 1.0)]).unwrap();
+        let without = ingredient_requirements(
+            &conn,
+            &[serde_json::json!({
+                "menu_item_id": "burger",
+                "customizations": r#"[{"ingredient": {"id": "ing-cheese"}, "isWithout": true}]"#
+            })],
+        )
+        .unwrap();
+        assert!(!without.contains_key("cheese"));
+
+        conn.execute(
+            "INSERT INTO orders (id, order_number, items, total_amount, status, order_type, sync_status, created_at, updated_at)
+             VALUES ('ord-m', 'ord-m', ?1, 23.0, 'completed', 'takeaway', 'pending', ?2, ?2)",
+            params![
+                serde_json::json!([
+                    {
+                        "menu_item_id": "burger",
+                        "name": "Burger",
+                        "quantity": 2,
+                        "total_price": 20.0,
+                        "customizations": [{ "ingredient": { "id": "ing-cheese" }, "quantity": 1 }]
+                    },
+                    { "menu_item_id": "fries", "name": "Fries", "quantity": 1, "total_price": 3.0 }
+                ])
+                .to_string(),
+                now.to_rfc3339()
+            ],
+        )
+        .unwrap();
+        deduct_for_order(&conn, "ord-m", TRIGGER_CONFIRMED, None).unwrap();
+        assert_eq!(get_item(&conn, "cheese").unwrap().unwrap()["quantity"], 8.0);
+        record_movement(
+            &conn,
+            &MovementInput {
+                inventory_item_id: "patty",
+                movement_type: MOVEMENT_ADJUSTMENT,
+                quantity_delta: -1.0,
+                reason: Some("dropped"),
+                order_id: None,
+                reference_id: None,
+                staff_id: None,
+                unit_cost: None,
+            },
+        )
+        .unwrap();
+
+        let report = margin_report(&conn, "", &start_at, &end_at, 10.0).unwrap();
+        assert_eq!(report.lines.len(), 1);
+        let burger = &report.lines[0];
+        assert_eq!(burger.revenue.as_i64(), 2000);
+        assert!((burger.cost - 4.4).abs() < 1e-9, "patty at 1.50 average");
+        assert!(!burger.missing_costs);
+        assert_eq!(report.without_recipe.len(), 1);
+        assert_eq!(report.without_recipe[0].menu_item_id, "fries");
+        assert_eq!(report.variances.len(), 1);
+        assert_eq!(report.variances[0].inventory_item_id, "patty");
+        assert_eq!(report.variances[0].actual, 3.0);
+        let json = report.to_json("2026-05-01", "2026-05-02");
+        assert_eq!(json["totals"]["revenueCents"], 2000);
+        assert_eq!(json["totals"]["theoreticalCostCents"], 440);
+        assert_eq!(json["items"][0]["marginPercent"], 78.0);
+    }
+
+    #[test]
+    fn payment_trigger_setting_switches_decrement_point() {
+        let conn = test_conn();
+        assert_eq!(decrement_trigger(&conn), TRIGGER_CONFIRMED);
+        db::set_setting(&conn, SETTINGS_CATEGORY, DECREMENT_ON_KEY, "payment").unwrap();
+        assert_eq!(decrement_trigger(&conn), TRIGGER_PAYMENT);
+        assert!(status_reaches_confirmed("Completed"));
+        assert!(!status_reaches_confirmed("pending"));
+    }
+}
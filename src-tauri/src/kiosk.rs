@@ -0,0 +1,256 @@
+//! Self-order kiosk mode.
+//!
+//! `kiosk_enable` locks the terminal to the customer-facing command surface:
+//! menu getters, order creation and card payment (`ecr_process_payment`, then
+//! `payment_record` with `method: "card"`). Every other IPC command — staff,
+//! shift, settings, refunds — is rejected by the invoke handler with a
+//! `KIOSK_MODE` error before it runs. Orders created in kiosk mode get the
+//! configured order type, `source = "kiosk"` and either `confirmed` or
+//! `pending` status depending on `kiosk.auto_confirm`.
+//!
+//! Unlocking takes the admin PIN, checked against the same bcrypt hash and
+//! lockout as privileged actions. The mode is stored in `local_settings` and
+//! restored at startup, so a restart does not drop the terminal back to the
+//! full POS.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::auth::{self, AuthState};
+use crate::db::{self, DbState};
+use crate::diagnostics::{self, HealthStatus};
+use crate::error::PosError;
+
+const SETTINGS_CATEGORY: &str = "kiosk";
+const ENABLED_KEY: &str = "enabled";
+const ORDER_TYPE_KEY: &str = "order_type";
+const AUTO_CONFIRM_KEY: &str = "auto_confirm";
+
+pub const ORDER_SOURCE: &str = "kiosk";
+pub const MODE_CHANGED_EVENT: &str = "kiosk_mode_changed";
+pub const DEFAULT_ORDER_TYPE: &str = "pickup";
+const KIOSK_ORDER_TYPES: &[&str] = &["pickup", "takeaway", "dine-in"];
+
+/// Commands that keep working while kiosk mode is on.
+const ALLOWED_COMMANDS: &[&str] = &[
+    "menu_get_categories",
+    "menu_get_subcategories",
+    "menu_get_ingredients",
+    "menu_get_subcategory_ingredients",
+    "menu_get_combos",
+    "menu_get_image",
+    "order_create",
+    "order_create_with_initial_payment",
+    "ecr_process_payment",
+    "payment_record",
+    "kiosk_get_status",
+    "kiosk_disable",
+    "app_get_version",
+    "system_get_info",
+    "system_health_check",
+];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+pub fn is_command_allowed(command: &str) -> bool {
+    ALLOWED_COMMANDS.contains(&command)
+}
+
+pub fn command_rejection(command: &str) -> PosError {
+    PosError::KioskMode(format!("{command} is not available in kiosk mode"))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KioskConfig {
+    pub enabled: bool,
+    pub order_type: String,
+    pub auto_confirm: bool,
+}
+
+impl KioskConfig {
+    pub fn load(conn: &Connection) -> Self {
+        let flag = |key: &str| {
+            matches!(
+                db::get_setting(conn, SETTINGS_CATEGORY, key)
+                    .unwrap_or_default()
+                    .trim()
+                    .to_ascii_lowercase()
+                    .as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        };
+        Self {
+            enabled: flag(ENABLED_KEY),
+            order_type: db::get_setting(conn, SETTINGS_CATEGORY, ORDER_TYPE_KEY)
+                .and_then(|raw| normalize_order_type(&raw))
+                .unwrap_or_else(|| DEFAULT_ORDER_TYPE.to_string()),
+            auto_confirm: flag(AUTO_CONFIRM_KEY),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "enabled": self.enabled,
+            "orderType": self.order_type,
+            "autoConfirm": self.auto_confirm,
+        })
+    }
+}
+
+fn normalize_order_type(raw: &str) -> Option<String> {
+    let order_type = raw.trim().to_ascii_lowercase();
+    KIOSK_ORDER_TYPES
+        .contains(&order_type.as_str())
+        .then_some(order_type)
+}
+
+/// Load the persisted mode into the process flag. Called once at startup.
+pub fn restore(db: &DbState) {
+    let config = match db.conn.lock() {
+        Ok(conn) => KioskConfig::load(&conn),
+        Err(_) => return,
+    };
+    ENABLED.store(config.enabled, Ordering::SeqCst);
+    if config.enabled {
+        info!(order_type = %config.order_type, "Kiosk mode restored");
+    }
+}
+
+/// Turn kiosk mode on. `orderType` and `autoConfirm` in `payload` replace the
+/// stored values when present.
+pub fn enable(db: &DbState, payload: &Value) -> Result<Value, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    if let Some(raw) = payload.get("orderType").and_then(Value::as_str) {
+        let order_type = normalize_order_type(raw).ok_or_else(|| {
+            format!(
+                "Unsupported kiosk order type: {raw} (expected one of {})",
+                KIOSK_ORDER_TYPES.join(", ")
+            )
+        })?;
+        db::set_setting(&conn, SETTINGS_CATEGORY, ORDER_TYPE_KEY, &order_type)?;
+    }
+    if let Some(auto_confirm) = payload.get("autoConfirm").and_then(Value::as_bool) {
+        db::set_setting(
+            &conn,
+            SETTINGS_CATEGORY,
+            AUTO_CONFIRM_KEY,
+            if auto_confirm { "true" } else { "false" },
+        )?;
+    }
+    db::set_setting(&conn, SETTINGS_CATEGORY, ENABLED_KEY, "true")?;
+    ENABLED.store(true, Ordering::SeqCst);
+    let config = KioskConfig::load(&conn);
+    info!(order_type = %config.order_type, "Kiosk mode enabled");
+    Ok(config.to_json())
+}
+
+/// Turn kiosk mode off after checking `pin` against the admin PIN.
+pub fn disable(db: &DbState, auth: &AuthState, pin: &str) -> Result<Value, PosError> {
+    if !is_enabled() {
+        return Err(PosError::Conflict("Kiosk mode is not enabled".into()));
+    }
+    if !auth::verify_privileged_pin_with_lockout(pin, "admin", db, auth)
+        .map_err(PosError::Unauthorized)?
+    {
+        return Err(PosError::Unauthorized("Invalid admin PIN".into()));
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    db::set_setting(&conn, SETTINGS_CATEGORY, ENABLED_KEY, "false")?;
+    ENABLED.store(false, Ordering::SeqCst);
+    info!("Kiosk mode disabled");
+    Ok(KioskConfig::load(&conn).to_json())
+}
+
+/// Force the kiosk order type, source tag and initial status onto an
+/// order-create payload.
+pub fn apply_to_order_payload(config: &KioskConfig, payload: &mut Value) {
+    if let Some(obj) = payload.as_object_mut() {
+        obj.remove("order_type");
+        obj.insert("orderType".into(), json!(config.order_type));
+        obj.insert("source".into(), json!(ORDER_SOURCE));
+        let status = if config.auto_confirm {
+            "confirmed"
+        } else {
+            "pending"
+        };
+        obj.insert("status".into(), json!(status));
+    }
+}
+
+/// Kiosk customers can only pay by card.
+pub fn ensure_card_payment(payload: &Value) -> Result<(), PosError> {
+    if !is_enabled() {
+        return Ok(());
+    }
+    let method = payload
+        .get("method")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if method.trim().eq_ignore_ascii_case("card") {
+        Ok(())
+    } else {
+        Err(PosError::KioskMode(
+            "Only card payments are accepted in kiosk mode".into(),
+        ))
+    }
+}
+
+/// Health entry for `system_health_check`. Kiosk mode is a deliberate state,
+/// so it never degrades the overall status.
+pub fn check_kiosk_health(db: &DbState) -> Value {
+    let config = db.conn.lock().map(|conn| KioskConfig::load(&conn)).ok();
+    let detail = if is_enabled() {
+        "Kiosk mode enabled"
+    } else {
+        "Kiosk mode off"
+    };
+    let mut extra = config.map(|config| config.to_json()).unwrap_or(json!({}));
+    if let Some(obj) = extra.as_object_mut() {
+        obj.insert("enabled".into(), json!(is_enabled()));
+    }
+    diagnostics::health_entry(HealthStatus::Ok, detail, extra)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitelist_blocks_staff_and_refund_commands() {
+        assert!(is_command_allowed("menu_get_categories"));
+        assert!(is_command_allowed("order_create"));
+        assert!(is_command_allowed("kiosk_disable"));
+        assert!(!is_command_allowed("refund_payment"));
+        assert!(!is_command_allowed("shift_open"));
+        assert!(!is_command_allowed("settings_set"));
+        assert_eq!(command_rejection("refund_payment").code(), "KIOSK_MODE");
+    }
+
+    #[test]
+    fn order_payload_gets_forced_type_source_and_status() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        db::set_setting(&conn, SETTINGS_CATEGORY, ORDER_TYPE_KEY, "delivery").unwrap();
+        let config = KioskConfig::load(&conn);
+        assert_eq!(config.order_type, DEFAULT_ORDER_TYPE);
+        assert!(!config.auto_confirm);
+
+        let mut payload = json!({ "order_type": "delivery", "status": "completed", "items": [] });
+        apply_to_order_payload(&config, &mut payload);
+        assert_eq!(payload["orderType"], "pickup");
+        assert_eq!(payload["source"], "kiosk");
+        assert_eq!(payload["status"], "pending");
+        assert!(payload.get("order_type").is_none());
+
+        db::set_setting(&conn, SETTINGS_CATEGORY, AUTO_CONFIRM_KEY, "true").unwrap();
+        apply_to_order_payload(&KioskConfig::load(&conn), &mut payload);
+        assert_eq!(payload["status"], "confirmed");
+    }
+}
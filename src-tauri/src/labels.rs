@@ -0,0 +1,668 @@
+//! Adhesive bag labels for takeaway and delivery orders.
+//!
+//! A label carries the order number, customer name, the items in that bag,
+//! an allergen note and the pickup time. Its layout comes from a plain-text
+//! template in `local_settings` (`label` / `template`), kept separate from
+//! the receipt templates so a 58mm label roll can be laid out independently.
+//!
+//! Template lines are copied verbatim after placeholder substitution:
+//!
+//! * `{order_number}`, `{customer_name}`, `{order_type}`, `{allergens}`,
+//!   `{pickup_time}`, `{bag}`, `{bag_count}` are replaced inline;
+//! * a line containing `{items}` is repeated once per item in the bag;
+//! * a line starting with `# ` is printed bold at double size;
+//! * a line that is exactly `---` becomes a rule across the label.
+//!
+//! A line whose placeholders all resolve to nothing is dropped, so an
+//! order without allergens does not print an empty `Allergens:` line.
+//!
+//! Orders are split into bags either by an explicit bag count or by an
+//! items-per-bag limit (`label` / `items_per_bag`, 0 = one bag). Labels are
+//! printed through the regular print queue as `order_label` jobs against a
+//! `label` printer profile; only ESC/POS and Star line mode are emitted.
+
+use chrono::{DateTime, Duration, Local};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::db;
+use crate::escpos::{EscPosBuilder, PaperWidth};
+use crate::receipt_renderer::{self, EscPosBodyMode, EscPosRender, LayoutConfig};
+
+pub const LABEL_ENTITY_TYPE: &str = "order_label";
+pub const LABEL_PROFILE_TYPE: &str = "label";
+
+pub const DEFAULT_TEMPLATE: &str = "# #{order_number}\n\
+{customer_name}\n\
+Bag {bag}/{bag_count}\n\
+---\n\
+{items}\n\
+---\n\
+Allergens: {allergens}\n\
+Pickup: {pickup_time}";
+
+pub const MAX_COPIES: u32 = 20;
+pub const MAX_BAGS: u32 = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelItem {
+    pub name: String,
+    pub quantity: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OrderLabelDoc {
+    pub order_number: String,
+    pub order_type: String,
+    pub customer_name: Option<String>,
+    pub items: Vec<LabelItem>,
+    pub allergens: Vec<String>,
+    pub pickup_time: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BagSplit {
+    /// Exactly this many bags, items spread as evenly as possible.
+    Bags(u32),
+    /// Start a new bag after this many items.
+    ItemsPerBag(u32),
+}
+
+/// Per-job options, stored as the print job payload.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LabelOptions {
+    pub copies: u32,
+    pub bags: Option<u32>,
+    pub items_per_bag: Option<u32>,
+    pub allergen_note: Option<String>,
+    pub pickup_time: Option<String>,
+    /// Print language for item names; the terminal language when unset.
+    pub language: Option<String>,
+}
+
+impl LabelOptions {
+    pub fn from_payload(payload: Option<&Value>) -> Self {
+        payload
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Bag count wins over items-per-bag; `settings_items_per_bag` is the
+    /// terminal default when the job names neither.
+    fn split(&self, settings_items_per_bag: Option<u32>) -> BagSplit {
+        match (self.bags, self.items_per_bag.or(settings_items_per_bag)) {
+            (Some(bags), _) if bags > 0 => BagSplit::Bags(bags.min(MAX_BAGS)),
+            (_, Some(per_bag)) if per_bag > 0 => BagSplit::ItemsPerBag(per_bag),
+            _ => BagSplit::Bags(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelLine {
+    pub text: String,
+    pub emphasis: bool,
+}
+
+/// One physical label.
+pub type LabelPage = Vec<LabelLine>;
+
+pub fn load_template(conn: &Connection) -> String {
+    db::get_setting(conn, "label", "template")
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string())
+}
+
+fn settings_items_per_bag(conn: &Connection) -> Option<u32> {
+    db::get_setting(conn, "label", "items_per_bag").and_then(|value| value.trim().parse().ok())
+}
+
+fn item_quantity(item: &Value) -> u32 {
+    let raw = item.get("quantity").and_then(|value| {
+        value
+            .as_f64()
+            .or_else(|| value.as_str().and_then(|text| text.trim().parse().ok()))
+    });
+    raw.map(|quantity| quantity.round().max(1.0) as u32)
+        .unwrap_or(1)
+}
+
+fn item_allergens(item: &Value) -> Vec<String> {
+    match item.get("allergens") {
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+            .collect(),
+        Some(Value::String(text)) => text
+            .split(',')
+            .map(|part| part.trim().to_string())
+            .filter(|part| !part.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// `created_at` plus `estimated_time` minutes, in local `HH:MM`.
+fn pickup_time(created_at: &str, estimated_minutes: Option<i64>) -> Option<String> {
+    let minutes = estimated_minutes.filter(|minutes| *minutes > 0)?;
+    let created = DateTime::parse_from_rfc3339(created_at).ok()?;
+    Some(
+        (created + Duration::minutes(minutes))
+            .with_timezone(&Local)
+            .format("%H:%M")
+            .to_string(),
+    )
+}
+
+pub fn build_label_doc(
+    conn: &Connection,
+    order_id: &str,
+    language: &str,
+) -> Result<OrderLabelDoc, String> {
+    let (order_number, order_type, customer_name, items_json, created_at, estimated_time) = conn
+        .query_row(
+            "SELECT COALESCE(order_number, ''), COALESCE(order_type, ''),
+                    COALESCE(customer_name, ''), COALESCE(items, '[]'),
+                    COALESCE(created_at, ''), estimated_time
+             FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, Option<i64>>(5)?,
+                ))
+            },
+        )
+        .map_err(|_| format!("Order not found: {order_id}"))?;
+
+    let raw_items: Vec<Value> = serde_json::from_str::<Value>(&items_json)
+        .ok()
+        .and_then(|value| value.as_array().cloned())
+        .unwrap_or_default();
+    let menu_lookup = crate::print::build_menu_category_lookup(conn, language);
+    let mut allergens: Vec<String> = Vec::new();
+    let items = raw_items
+        .iter()
+        .map(|item| {
+            for allergen in item_allergens(item) {
+                if !allergens
+                    .iter()
+                    .any(|known| known.eq_ignore_ascii_case(&allergen))
+                {
+                    allergens.push(allergen);
+                }
+            }
+            LabelItem {
+                name: crate::print::localized_item_name(item, language, &menu_lookup),
+                quantity: item_quantity(item),
+            }
+        })
+        .collect();
+
+    Ok(OrderLabelDoc {
+        order_number: if order_number.is_empty() {
+            order_id.to_string()
+        } else {
+            order_number
+        },
+        order_type,
+        customer_name: Some(customer_name).filter(|name| !name.trim().is_empty()),
+        items,
+        allergens,
+        pickup_time: pickup_time(&created_at, estimated_time),
+    })
+}
+
+/// Split `items` into bags. Quantities are split across bags when needed,
+/// so `3x Burger` with two items per bag becomes `2x` and `1x`.
+pub fn split_into_bags(items: &[LabelItem], split: BagSplit) -> Vec<Vec<LabelItem>> {
+    let total: u32 = items.iter().map(|item| item.quantity).sum();
+    let capacities: Vec<u32> = match split {
+        BagSplit::Bags(bags) => {
+            let bags = bags.max(1);
+            (0..bags)
+                .map(|index| total / bags + u32::from(index < total % bags))
+                .collect()
+        }
+        BagSplit::ItemsPerBag(per_bag) => {
+            let per_bag = per_bag.max(1);
+            let bags = total.div_ceil(per_bag).max(1);
+            (0..bags)
+                .map(|index| per_bag.min(total - index * per_bag))
+                .collect()
+        }
+    };
+
+    let mut remaining = items.iter().cloned().filter(|item| item.quantity > 0);
+    let mut current = remaining.next();
+    capacities
+        .into_iter()
+        .map(|mut capacity| {
+            let mut bag = Vec::new();
+            while capacity > 0 {
+                let Some(item) = current.as_mut() else {
+                    break;
+                };
+                let taken = item.quantity.min(capacity);
+                bag.push(LabelItem {
+                    name: item.name.clone(),
+                    quantity: taken,
+                });
+                item.quantity -= taken;
+                capacity -= taken;
+                if item.quantity == 0 {
+                    current = remaining.next();
+                }
+            }
+            bag
+        })
+        .collect()
+}
+
+fn truncate_chars(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+/// Expand `template` for one bag. `width` is the label width in normal-size
+/// characters; emphasised lines get half of it.
+pub fn render_label_lines(
+    template: &str,
+    doc: &OrderLabelDoc,
+    bag: usize,
+    bag_count: usize,
+    items: &[LabelItem],
+    width: usize,
+) -> LabelPage {
+    let allergens = doc.allergens.join(", ");
+    let bag_text = bag.to_string();
+    let bag_count_text = bag_count.to_string();
+    let values: [(&str, &str); 7] = [
+        ("{order_number}", doc.order_number.as_str()),
+        (
+            "{customer_name}",
+            doc.customer_name.as_deref().unwrap_or(""),
+        ),
+        ("{order_type}", doc.order_type.as_str()),
+        ("{allergens}", allergens.as_str()),
+        ("{pickup_time}", doc.pickup_time.as_deref().unwrap_or("")),
+        ("{bag}", bag_text.as_str()),
+        ("{bag_count}", bag_count_text.as_str()),
+    ];
+
+    let mut lines = Vec::new();
+    for raw_line in template.lines() {
+        let raw_line = raw_line.trim_end();
+        if raw_line == "---" {
+            lines.push(LabelLine {
+                text: "-".repeat(width),
+                emphasis: false,
+            });
+            continue;
+        }
+        let (emphasis, body) = match raw_line.strip_prefix("# ") {
+            Some(rest) => (true, rest),
+            None => (false, raw_line),
+        };
+        let line_width = if emphasis { width / 2 } else { width };
+
+        let expansions: Vec<String> = if body.contains("{items}") {
+            items
+                .iter()
+                .map(|item| body.replace("{items}", &format!("{}x {}", item.quantity, item.name)))
+                .collect()
+        } else {
+            vec![body.to_string()]
+        };
+
+        for expansion in expansions {
+            let mut text = expansion;
+            let mut placeholders = 0;
+            let mut filled = 0;
+            for (placeholder, value) in values {
+                if text.contains(placeholder) {
+                    placeholders += 1;
+                    if !value.trim().is_empty() {
+                        filled += 1;
+                    }
+                    text = text.replace(placeholder, value.trim());
+                }
+            }
+            if placeholders > 0 && filled == 0 {
+                continue;
+            }
+            lines.push(LabelLine {
+                text: truncate_chars(&text, line_width),
+                emphasis,
+            });
+        }
+    }
+    lines
+}
+
+/// Every physical label for a job: each bag, `copies` times, in bag order.
+pub fn render_labels(
+    doc: &OrderLabelDoc,
+    options: &LabelOptions,
+    template: &str,
+    settings_items_per_bag: Option<u32>,
+    width: usize,
+) -> Vec<LabelPage> {
+    let bags = split_into_bags(&doc.items, options.split(settings_items_per_bag));
+    let copies = options.copies.clamp(1, MAX_COPIES) as usize;
+    let bag_count = bags.len();
+    let mut doc = doc.clone();
+    if let Some(note) = options
+        .allergen_note
+        .as_deref()
+        .map(str::trim)
+        .filter(|note| !note.is_empty())
+    {
+        doc.allergens = vec![note.to_string()];
+    }
+    if let Some(pickup) = options
+        .pickup_time
+        .as_deref()
+        .map(str::trim)
+        .filter(|pickup| !pickup.is_empty())
+    {
+        doc.pickup_time = Some(pickup.to_string());
+    }
+
+    let mut pages = Vec::with_capacity(bag_count * copies);
+    for (index, items) in bags.iter().enumerate() {
+        let page = render_label_lines(template, &doc, index + 1, bag_count, items, width);
+        for _ in 0..copies {
+            pages.push(page.clone());
+        }
+    }
+    pages
+}
+
+/// Build the labels for an `order_label` job from the database.
+pub fn labels_for_job(
+    conn: &Connection,
+    order_id: &str,
+    options: &LabelOptions,
+    paper: PaperWidth,
+) -> Result<Vec<LabelPage>, String> {
+    let language = crate::print::print_language(conn, options.language.as_deref());
+    let doc = build_label_doc(conn, order_id, &language)?;
+    let template = load_template(conn);
+    Ok(render_labels(
+        &doc,
+        options,
+        &template,
+        settings_items_per_bag(conn),
+        paper.chars(),
+    ))
+}
+
+pub fn render_label_escpos(labels: &[LabelPage], cfg: &LayoutConfig, cut: bool) -> EscPosRender {
+    let use_star_commands =
+        receipt_renderer::uses_star_commands(cfg.detected_brand, cfg.emulation_mode);
+    let mut builder = if use_star_commands {
+        EscPosBuilder::new()
+            .with_paper(cfg.paper_width)
+            .with_star_line_mode()
+    } else {
+        EscPosBuilder::new().with_paper(cfg.paper_width)
+    };
+    builder.init();
+    let warnings = receipt_renderer::apply_character_set(
+        &mut builder,
+        &cfg.character_set,
+        cfg.greek_render_mode.as_deref(),
+        cfg.escpos_code_page,
+        use_star_commands,
+    );
+
+    for label in labels {
+        for line in label {
+            if line.emphasis {
+                builder.center().bold(true).text_size(2, 2);
+                builder.text(&line.text).lf();
+                builder.text_size(1, 1).bold(false).left();
+            } else {
+                builder.text(&line.text).lf();
+            }
+        }
+        builder.feed(3);
+        if cut {
+            if use_star_commands {
+                builder.star_cut();
+            } else {
+                builder.cut();
+            }
+        }
+    }
+
+    let text = builder.plain_text();
+    EscPosRender {
+        bytes: builder.build(),
+        warnings,
+        body_mode: EscPosBodyMode::Text,
+        text,
+    }
+}
+
+pub fn render_label_html(labels: &[LabelPage]) -> String {
+    let mut body = String::new();
+    for label in labels {
+        body.push_str("<div class=\"label\">");
+        for line in label {
+            let text = receipt_renderer::esc(&line.text);
+            if line.emphasis {
+                body.push_str(&format!("<div class=\"big\">{text}</div>"));
+            } else {
+                body.push_str(&format!("<div>{text}</div>"));
+            }
+        }
+        body.push_str("</div>");
+    }
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="UTF-8"/>
+<title>Bag labels</title>
+<style>
+  body {{ margin: 0; padding: 10px; background: #fff; font-family: monospace; font-size: 13px; }}
+  .label {{ border: 1px dashed #000; padding: 6px; margin-bottom: 10px; white-space: pre; }}
+  .big {{ font-size: 22px; font-weight: bold; text-align: center; }}
+</style>
+</head>
+<body>{body}</body>
+</html>"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, quantity: u32) -> LabelItem {
+        LabelItem {
+            name: name.to_string(),
+            quantity,
+        }
+    }
+
+    fn doc() -> OrderLabelDoc {
+        OrderLabelDoc {
+            order_number: "ORD-0042".to_string(),
+            order_type: "takeaway".to_string(),
+            customer_name: Some("Maria P.".to_string()),
+            items: vec![item("Burger", 3), item("Fries", 1), item("Cola", 1)],
+            allergens: vec!["Gluten".to_string(), "Sesame".to_string()],
+            pickup_time: Some("18:45".to_string()),
+        }
+    }
+
+    fn texts(page: &LabelPage) -> Vec<&str> {
+        page.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn items_per_bag_splits_quantities_across_bags() {
+        let bags = split_into_bags(&doc().items, BagSplit::ItemsPerBag(2));
+        assert_eq!(
+            bags,
+            vec![
+                vec![item("Burger", 2)],
+                vec![item("Burger", 1), item("Fries", 1)],
+                vec![item("Cola", 1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn explicit_bag_count_spreads_items_evenly() {
+        let bags = split_into_bags(&doc().items, BagSplit::Bags(2));
+        assert_eq!(
+            bags,
+            vec![
+                vec![item("Burger", 3)],
+                vec![item("Fries", 1), item("Cola", 1)],
+            ]
+        );
+        // More bags than items still prints the requested number of labels.
+        assert_eq!(
+            split_into_bags(&[item("Cola", 1)], BagSplit::Bags(3)).len(),
+            3
+        );
+        assert_eq!(split_into_bags(&[], BagSplit::ItemsPerBag(4)).len(), 1);
+    }
+
+    #[test]
+    fn default_template_renders_every_field() {
+        let page = render_label_lines(DEFAULT_TEMPLATE, &doc(), 1, 2, &doc().items, 32);
+        assert_eq!(
+            texts(&page),
+            vec![
+                "#ORD-0042",
+                "Maria P.",
+                "Bag 1/2",
+                "--------------------------------",
+                "3x Burger",
+                "1x Fries",
+                "1x Cola",
+                "--------------------------------",
+                "Allergens: Gluten, Sesame",
+                "Pickup: 18:45",
+            ]
+        );
+        assert!(page[0].emphasis);
+        assert!(!page[1].emphasis);
+    }
+
+    #[test]
+    fn lines_with_only_empty_placeholders_are_dropped() {
+        let mut bare = doc();
+        bare.customer_name = None;
+        bare.allergens.clear();
+        bare.pickup_time = None;
+        let page = render_label_lines(DEFAULT_TEMPLATE, &bare, 1, 1, &bare.items, 32);
+        let lines = texts(&page);
+        assert!(!lines.iter().any(|line| line.starts_with("Allergens")));
+        assert!(!lines.iter().any(|line| line.starts_with("Pickup")));
+        assert_eq!(lines[1], "Bag 1/1");
+    }
+
+    #[test]
+    fn copies_repeat_each_bag_and_options_override_order_data() {
+        let options = LabelOptions {
+            copies: 2,
+            bags: Some(2),
+            items_per_bag: Some(1),
+            allergen_note: Some("Contains nuts".to_string()),
+            pickup_time: Some("19:10".to_string()),
+            language: None,
+        };
+        let pages = render_labels(
+            &doc(),
+            &options,
+            "Bag {bag}/{bag_count}\n{allergens}\n{pickup_time}",
+            None,
+            32,
+        );
+        let firsts: Vec<&str> = pages.iter().map(|page| page[0].text.as_str()).collect();
+        assert_eq!(firsts, vec!["Bag 1/2", "Bag 1/2", "Bag 2/2", "Bag 2/2"]);
+        assert_eq!(texts(&pages[0])[1..], ["Contains nuts", "19:10"]);
+    }
+
+    #[test]
+    fn emphasised_lines_fit_half_the_width() {
+        let page = render_label_lines("# {customer_name}", &doc(), 1, 1, &[], 10);
+        assert_eq!(page[0].text, "Maria");
+    }
+
+    #[test]
+    fn escpos_output_cuts_between_labels() {
+        let pages = render_labels(
+            &doc(),
+            &LabelOptions {
+                bags: Some(2),
+                ..LabelOptions::default()
+            },
+            DEFAULT_TEMPLATE,
+            None,
+            PaperWidth::Mm58.chars(),
+        );
+        let cfg = LayoutConfig {
+            paper_width: PaperWidth::Mm58,
+            ..LayoutConfig::default()
+        };
+        let rendered = render_label_escpos(&pages, &cfg, true);
+        let cuts = rendered
+            .bytes
+            .windows(4)
+            .filter(|window| *window == [0x1D, 0x56, 0x41, 0x10])
+            .count();
+        assert_eq!(cuts, 2);
+        assert!(rendered.text.contains("Bag 2/2"));
+        assert!(rendered.text.contains("Allergens: Gluten, Sesame"));
+
+        let html = render_label_html(&pages);
+        assert_eq!(html.matches("class=\"label\"").count(), 2);
+    }
+
+    #[test]
+    fn label_doc_reads_order_items_allergens_and_pickup_time() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        conn.execute(
+            "INSERT INTO orders (id, order_number, order_type, customer_name, items, status,
+                                 total_amount, created_at, updated_at, estimated_time)
+             VALUES ('o-1', 'ORD-7', 'takeaway', 'Nikos', ?1, 'pending', 12.0,
+                     '2026-10-15T10:00:00Z', '2026-10-15T10:00:00Z', 25)",
+            params![serde_json::json!([
+                { "name": "Gyros", "quantity": 2, "allergens": ["Gluten"] },
+                { "name": "Salad", "quantity": "1", "allergens": "gluten, Sesame" }
+            ])
+            .to_string()],
+        )
+        .unwrap();
+
+        let doc = build_label_doc(&conn, "o-1", "en").unwrap();
+        assert_eq!(doc.order_number, "ORD-7");
+        assert_eq!(doc.customer_name.as_deref(), Some("Nikos"));
+        assert_eq!(doc.items, vec![item("Gyros", 2), item("Salad", 1)]);
+        assert_eq!(doc.allergens, vec!["Gluten", "Sesame"]);
+        let expected_pickup = DateTime::parse_from_rfc3339("2026-10-15T10:25:00Z")
+            .unwrap()
+            .with_timezone(&Local)
+            .format("%H:%M")
+            .to_string();
+        assert_eq!(doc.pickup_time.as_deref(), Some(expected_pickup.as_str()));
+
+        assert!(build_label_doc(&conn, "missing", "en").is_err());
+    }
+}
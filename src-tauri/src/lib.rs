@@ -31,44 +31,108 @@ static MENU_WARMUP_LAST_ATTEMPT_MS: AtomicU64 = AtomicU64::new(0);
 
 const MENU_WARMUP_THROTTLE_MS: u64 = 15_000;
 
+mod admin_proxy;
+mod allergens;
 mod api;
 mod auth;
+mod auto_accept;
+mod branches;
 mod business_day;
 mod callerid;
+mod checks;
+mod clipboard;
+mod combos;
 mod commands;
+mod connection_qr;
+mod connectivity;
 mod core_helpers;
+mod correlation;
+mod courses;
+mod credential_validation;
 mod customer_display;
+mod customer_import;
+mod customer_privacy;
 mod data_helpers;
 mod db;
+mod destructive_ops;
 mod diagnostics;
+mod dispatch;
 mod drawer;
+mod drawer_transactions;
 mod ecr;
+mod eod;
+mod error;
 mod escpos;
+mod event_batcher;
+mod favorites;
+mod features;
 pub mod fiscal; // pub so integration tests (tests/*.rs) can exercise enqueue_for_order, active_cache, etc.
 mod hardware_manager;
+mod heartbeat;
 mod idempotency;
 mod incident_reporting;
+mod inventory;
+mod kiosk;
+mod labels;
+mod logs;
 mod loyalty;
 mod menu;
 mod money;
+mod notify;
+mod onboarding;
+mod opening_float;
+mod order_aging;
+mod order_duplicate;
+mod order_eta;
+mod order_events;
+mod order_locks;
 mod order_ownership;
+mod order_plugins;
+mod organization_change;
 mod panic_hook;
 mod payment_integrity;
 mod payments;
+mod platform_fees;
+mod prep_time;
+mod pricing_rules;
 mod print;
+mod print_schedule;
+mod printer_watchdog;
 mod printers;
+mod provisioning;
+mod realtime;
+mod reasons;
+mod receipt_copies;
+mod receipt_delivery;
 mod receipt_renderer;
 mod recovery;
 mod refunds;
+mod remediation;
+mod remote_cache;
+mod reports_export;
+mod reservations;
 mod reset;
+mod retention;
+mod returns;
 mod scale;
 mod scanner;
+mod schedule;
 mod serial;
+mod service_charge;
 mod shifts;
+mod shutdown;
+mod stale_prices;
 mod storage;
+mod supabase;
+mod support_session;
 mod sync;
+mod sync_backlog;
 pub mod sync_queue; // pub so integration tests can call create_tables / enqueue_payload_item
+mod tabs;
+mod tax;
 mod terminal_helpers;
+mod terminal_repair;
+mod training;
 mod zreport;
 
 #[cfg(test)]
@@ -76,7 +140,7 @@ mod tests;
 
 const MODULE_CACHE_FILE: &str = "module-cache.json";
 pub(crate) const MODULE_CACHE_TTL_MS: i64 = 15 * 60 * 1000;
-const UPDATER_MANIFEST_URL: &str =
+pub(crate) const UPDATER_MANIFEST_URL: &str =
     "https://github.com/EpsylonBita/The-Small-POS/releases/latest/download/latest.json";
 const EXTERNAL_URL_MAX_LEN: usize = 2048;
 const ALLOWED_EXTERNAL_HOSTS: &[&str] = &[
@@ -147,14 +211,15 @@ pub(crate) fn value_i64(v: &serde_json::Value, keys: &[&str]) -> Option<i64> {
 }
 
 pub(crate) use core_helpers::{
-    build_admin_query, can_transition_locally, clear_operational_data_inner, fetch_supabase_rows,
+    build_admin_query, can_transition_locally, clear_operational_data_inner,
     normalize_status_for_storage, payload_arg0_as_string, read_module_cache, read_update_state,
     stats_for_modules, update_info_from_release, validate_admin_api_path, write_module_cache,
     write_update_state,
 };
 pub(crate) use data_helpers::{
     load_orders_for_period, normalize_phone, parse_item_totals, read_local_json,
-    read_local_json_array, resolve_order_id, validate_external_url, write_local_json,
+    read_local_json_array, resolve_order_id, resolve_order_row, validate_external_url,
+    write_local_json,
 };
 pub(crate) use terminal_helpers::{
     cache_terminal_settings_snapshot, clear_derived_terminal_context,
@@ -252,6 +317,9 @@ async fn admin_fetch(
     body: Option<serde_json::Value>,
 ) -> Result<serde_json::Value, String> {
     validate_admin_api_path(path)?;
+    if training::is_active() {
+        return Ok(training::simulated_success(serde_json::json!({})));
+    }
 
     if let Some(db_state) = db {
         hydrate_terminal_credentials_from_local_settings(db_state);
@@ -334,20 +402,18 @@ async fn admin_fetch(
     api::fetch_from_admin(&normalized_admin_url, &api_key, path, method, body).await
 }
 
-async fn updater_manifest_is_reachable() -> Result<bool, String> {
+async fn updater_manifest_is_reachable(manifest_url: &str) -> Result<bool, String> {
     // Hard timeout so a stalled GitHub CDN connection cannot hang the
     // updater check indefinitely. 15s is well above a healthy round-trip
     // and below any reasonable user-facing wait tolerance.
-    let client = reqwest::Client::builder()
-        .redirect(reqwest::redirect::Policy::limited(10))
-        .timeout(std::time::Duration::from_secs(15))
-        .build()
-        .map_err(|e| format!("updater manifest client: {e}"))?;
+    let timeout = std::time::Duration::from_secs(15);
+    let client = api::shared_client()?;
 
-    let response = match client.head(UPDATER_MANIFEST_URL).send().await {
+    let response = match client.head(manifest_url).timeout(timeout).send().await {
         Ok(resp) => resp,
         Err(_) => client
-            .get(UPDATER_MANIFEST_URL)
+            .get(manifest_url)
+            .timeout(timeout)
             .send()
             .await
             .map_err(|e| format!("updater manifest request: {e}"))?,
@@ -364,72 +430,6 @@ async fn updater_manifest_is_reachable() -> Result<bool, String> {
 // as additional subsystems are built out.
 // ============================================================================
 
-// -- Modules -----------------------------------------------------------------
-
-pub(crate) fn read_system_clipboard_text() -> Result<String, String> {
-    #[cfg(target_os = "windows")]
-    {
-        let output = std::process::Command::new("powershell")
-            .args([
-                "-NoProfile",
-                "-NonInteractive",
-                "-Command",
-                "Get-Clipboard -Raw",
-            ])
-            .output()
-            .map_err(|e| format!("read clipboard: {e}"))?;
-        if !output.status.success() {
-            let err = String::from_utf8_lossy(&output.stderr).to_string();
-            return Err(format!("read clipboard failed: {err}"));
-        }
-        Ok(String::from_utf8_lossy(&output.stdout)
-            .trim_end_matches(['\r', '\n'])
-            .to_string())
-    }
-    #[cfg(not(target_os = "windows"))]
-    {
-        Err("Clipboard read is not implemented on this platform".into())
-    }
-}
-
-pub(crate) fn write_system_clipboard_text(text: &str) -> Result<(), String> {
-    #[cfg(target_os = "windows")]
-    {
-        use std::io::Write;
-        use std::process::Stdio;
-        let mut child = std::process::Command::new("powershell")
-            .args([
-                "-NoProfile",
-                "-NonInteractive",
-                "-Command",
-                "$text = [Console]::In.ReadToEnd(); Set-Clipboard -Value $text",
-            ])
-            .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| format!("write clipboard spawn: {e}"))?;
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin
-                .write_all(text.as_bytes())
-                .map_err(|e| format!("write clipboard stdin: {e}"))?;
-        }
-        let output = child
-            .wait_with_output()
-            .map_err(|e| format!("write clipboard wait: {e}"))?;
-        if !output.status.success() {
-            let err = String::from_utf8_lossy(&output.stderr).to_string();
-            return Err(format!("write clipboard failed: {err}"));
-        }
-        Ok(())
-    }
-    #[cfg(not(target_os = "windows"))]
-    {
-        let _ = text;
-        Err("Clipboard write is not implemented on this platform".into())
-    }
-}
-
 // -- Update ------------------------------------------------------------------
 
 // -- API proxy ---------------------------------------------------------------
@@ -457,7 +457,7 @@ pub fn run() {
 
     // Initialize structured logging (console + rolling file)
     let env_filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new("info,the_small_pos_lib=info"));
+        .unwrap_or_else(|_| EnvFilter::new(logs::DEFAULT_LOG_FILTER));
 
     // Prune old log files before setting up the appender
     diagnostics::prune_old_logs();
@@ -473,16 +473,26 @@ pub fn run() {
         .with_writer(non_blocking)
         .with_ansi(false)
         .with_target(true);
+    // JSON lines for the in-app log viewer, rotated daily and by size.
+    let (json_writer, json_guard) =
+        tracing_appender::non_blocking(logs::RollingJsonWriter::new(&log_dir));
+    let json_layer = fmt::layer()
+        .json()
+        .with_writer(json_writer)
+        .with_target(true);
     let console_layer = fmt::layer().with_target(true);
     tracing_subscriber::registry()
-        .with(env_filter)
+        .with(logs::reloadable_filter(env_filter))
         .with(console_layer)
         .with(file_layer)
+        .with(json_layer)
+        .with(diagnostics::RecentErrorLayer)
         .init();
 
-    // Keep the guard alive for the lifetime of the app — dropping it flushes logs.
-    // We leak it intentionally since the app runs until process exit.
+    // Keep the guards alive for the lifetime of the app — dropping them flushes logs.
+    // We leak them intentionally since the app runs until process exit.
     std::mem::forget(_guard);
+    std::mem::forget(json_guard);
 
     // Install panic hook now that tracing is ready. Any panic before this point
     // still falls through to Rust's default stderr hook.
@@ -492,6 +502,7 @@ pub fn run() {
 
     tauri::Builder::default()
         .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
         // Prevent the native window menu at the source instead of racing to
         // remove it after the window is realized.
         //
@@ -560,8 +571,22 @@ pub fn run() {
             // into the OS keyring, then purge the plaintext rows that have
             // been successfully migrated. Hydrate must run before purge so a
             // keyring-only failure doesn't wipe the plaintext fallback.
+            // Disagreements are reported first, since hydration resolves
+            // them in favour of `local_settings`.
+            match credential_validation::validate(&db_state) {
+                Ok(report) if report["consistent"] == false => {
+                    warn!(
+                        mismatches = %report["mismatches"],
+                        "Terminal credentials disagree between keyring and local_settings"
+                    );
+                    let _ = app.emit(credential_validation::INCONSISTENT_EVENT, report);
+                }
+                Ok(_) => {}
+                Err(error) => warn!(error = %error, "Startup credential validation failed"),
+            }
             hydrate_terminal_credentials_from_local_settings(&db_state);
             purge_hydrated_terminal_credentials_from_local_settings(&db_state);
+            kiosk::restore(&db_state);
             let caller_id_manager = Arc::new(callerid::CallerIdManager::new());
             app.manage(db_state);
 
@@ -571,6 +596,17 @@ pub fn run() {
             app.manage(ecr::DeviceManager::new());
             app.manage(Arc::clone(&caller_id_manager));
             app.manage(commands::runtime::ScreenCaptureSignalPollingState::default());
+            app.manage(shutdown::ShutdownState::new());
+            let event_batch_config = app
+                .state::<db::DbState>()
+                .conn
+                .lock()
+                .map(|conn| event_batcher::BatchConfig::from_settings(&conn))
+                .unwrap_or_default();
+            app.manage(event_batcher::EventBatcher::for_app(
+                app.handle().clone(),
+                event_batch_config,
+            ));
 
             let updater_app = app.handle().clone();
             tauri::async_runtime::spawn(async move {
@@ -621,6 +657,44 @@ pub fn run() {
                 );
             }
 
+            // admin_api_degraded / admin_api_recovered on breaker transitions
+            api::start_breaker_event_forwarder(app.handle().clone(), cancel_token.clone());
+
+            // Connectivity watchdog: emits network_status on transitions only
+            let connectivity_state = Arc::new(connectivity::ConnectivityState::new());
+            app.manage(connectivity_state.clone());
+            match db::init(&app_data_dir) {
+                Ok(db) => {
+                    connectivity::start_connectivity_watchdog(
+                        app.handle().clone(),
+                        Arc::new(db),
+                        sync_state.clone(),
+                        connectivity_state,
+                        cancel_token.clone(),
+                    );
+                }
+                Err(e) => {
+                    error!("Failed to init connectivity database: {e} — connectivity watchdog disabled");
+                }
+            }
+
+            // Supabase Realtime subscription for remote order inserts/updates
+            let realtime_state = Arc::new(realtime::RealtimeState::new());
+            app.manage(realtime_state.clone());
+            match db::init(&app_data_dir) {
+                Ok(db) => {
+                    realtime::start_realtime_subscription(
+                        app.handle().clone(),
+                        Arc::new(db),
+                        realtime_state,
+                        cancel_token.clone(),
+                    );
+                }
+                Err(e) => {
+                    error!("Failed to init realtime database: {e} — realtime subscription disabled");
+                }
+            }
+
             match db::init(&app_data_dir) {
                 Ok(db) => {
                     sync::start_terminal_heartbeat_loop(
@@ -698,6 +772,20 @@ pub fn run() {
                 }
             }
 
+            // Printer hardware watchdog (printing.status_poll_enabled, off by default)
+            match db::init(&app_data_dir) {
+                Ok(db) => {
+                    commands::print::start_printer_watchdog(
+                        app.handle().clone(),
+                        Arc::new(db),
+                        cancel_token.clone(),
+                    );
+                }
+                Err(e) => {
+                    error!("Failed to init printer watchdog database: {e} — printer watchdog disabled");
+                }
+            }
+
             // Start background system health monitor (30s interval)
             match db::init(&app_data_dir) {
                 Ok(db) => {
@@ -715,6 +803,16 @@ pub fn run() {
                 }
             }
 
+            // Terminal heartbeat to head office (diagnostics.heartbeat_interval_minutes)
+            match db::init(&app_data_dir) {
+                Ok(db) => {
+                    heartbeat::start_heartbeat_task(Arc::new(db), sync_state.clone(), cancel_token.clone());
+                }
+                Err(e) => {
+                    error!("Failed to init heartbeat database: {e} — terminal heartbeat disabled");
+                }
+            }
+
             // Start safe remote incident reporter (45s interval)
             match db::init(&app_data_dir) {
                 Ok(db) => {
@@ -732,6 +830,45 @@ pub fn run() {
                 }
             }
 
+            // End-of-day scheduler (checks eod.close_time once a minute)
+            match db::init(&app_data_dir) {
+                Ok(db) => {
+                    eod::start_eod_scheduler(app.handle().clone(), Arc::new(db), cancel_token.clone());
+                }
+                Err(e) => {
+                    error!("Failed to init end-of-day database: {e} — end-of-day scheduler disabled");
+                }
+            }
+
+            // Order SLA monitor (emits order_sla_breached once per late order)
+            match db::init(&app_data_dir) {
+                Ok(db) => {
+                    order_aging::start_sla_monitor(app.handle().clone(), Arc::new(db), cancel_token.clone());
+                }
+                Err(e) => {
+                    error!("Failed to init order SLA database: {e} — SLA alerts disabled");
+                }
+            }
+
+            match db::init(&app_data_dir) {
+                Ok(db) => {
+                    receipt_delivery::start_retry_worker(Arc::new(db), 60, cancel_token.clone());
+                }
+                Err(e) => {
+                    error!("Failed to init receipt delivery database: {e} — e-receipt retries disabled");
+                }
+            }
+
+            // Nightly retention pass (archives, then deletes, old history)
+            match db::init(&app_data_dir) {
+                Ok(db) => {
+                    retention::start_retention_scheduler(app.handle().clone(), Arc::new(db), cancel_token.clone());
+                }
+                Err(e) => {
+                    error!("Failed to init retention database: {e} — retention scheduler disabled");
+                }
+            }
+
             match db::init(&app_data_dir) {
                 Ok(db) => {
                     recovery::start_snapshot_monitor(Arc::new(db), 15 * 60, cancel_token.clone());
@@ -813,13 +950,21 @@ pub fn run() {
             info!("Database, auth, sync, and print worker registered");
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler({
+            let handler: Box<dyn Fn(tauri::ipc::Invoke) -> bool + Send + Sync> =
+                Box::new(tauri::generate_handler![
             // App lifecycle
             commands::runtime::app_shutdown,
             commands::runtime::app_restart,
             commands::runtime::app_get_version,
             commands::runtime::app_get_shutdown_status,
             commands::runtime::system_get_info,
+            commands::runtime::training_mode_get_status,
+            commands::runtime::training_mode_enter,
+            commands::runtime::training_mode_exit,
+            commands::runtime::kiosk_get_status,
+            commands::runtime::kiosk_enable,
+            commands::runtime::kiosk_disable,
             commands::runtime::system_open_external_url,
             // Auth
             commands::auth::auth_login,
@@ -828,6 +973,8 @@ pub fn run() {
             commands::auth::auth_validate_session,
             commands::auth::auth_has_permission,
             commands::auth::auth_get_session_stats,
+            commands::auth::auth_lock_terminal,
+            commands::auth::auth_switch_user,
             commands::auth::auth_confirm_privileged_action,
             commands::auth::auth_setup_pin,
             commands::auth::auth_secure_session_get,
@@ -851,21 +998,41 @@ pub fn run() {
             commands::settings::settings_get_local,
             commands::settings::settings_get_reset_status,
             commands::settings::settings_set,
+            commands::settings::settings_get_history,
+            commands::settings::settings_revert,
             commands::settings::settings_update_local,
+            commands::settings::destructive_op_prepare,
             commands::settings::settings_factory_reset,
             commands::settings::settings_emergency_reset,
             commands::settings::settings_update_terminal_credentials,
+            commands::settings::settings_apply_connection_qr,
+            commands::onboarding::onboarding_get_state,
+            commands::onboarding::onboarding_apply_connection_code,
+            commands::onboarding::onboarding_complete,
+            commands::terminal_repair::terminal_repair_begin,
+            commands::terminal_repair::terminal_repair_migrate,
+            commands::terminal_repair::terminal_repair_discard,
+            commands::organization_change::organization_change_status,
+            commands::organization_change::organization_change_confirm,
+            commands::settings::config_export_provisioning,
+            commands::settings::config_import_provisioning,
             commands::settings::settings_get_admin_url,
             commands::settings::settings_clear_connection,
             commands::settings::settings_get_discount_max,
             commands::settings::settings_set_discount_max,
             commands::settings::settings_get_tax_rate,
             commands::settings::settings_set_tax_rate,
+            commands::settings::tax_get_config,
+            commands::settings::tax_set_config,
+            commands::settings::service_charge_get_config,
+            commands::settings::service_charge_set_config,
             commands::settings::settings_get_language,
             commands::settings::settings_set_language,
             commands::settings::update_settings,
             commands::settings::settings_get_pos_api_key,
             commands::settings::settings_get_credential_status,
+            commands::settings::credentials_validate,
+            commands::settings::terminal_switch_branch,
             // Terminal config
             commands::settings::terminal_config_get_settings,
             commands::settings::terminal_config_get_setting,
@@ -879,9 +1046,20 @@ pub fn run() {
             // Orders
             commands::orders::order_get_all,
             commands::orders::order_get_by_id,
+            commands::orders::orders_get_open_with_aging,
+            commands::orders::order_get_timeline,
             commands::orders::order_get_by_customer_phone,
             commands::orders::order_create,
+            commands::orders::order_duplicate,
+            commands::orders::order_create_return,
+            commands::orders::order_validate_combo,
+            commands::orders::order_estimate_prep_time,
+            commands::orders::order_refresh_prices,
+            commands::orders::order_set_allergy_flags,
+            commands::orders::order_waive_service_charge,
+            commands::orders::order_update_eta,
             commands::orders::order_create_with_initial_payment,
+            commands::orders::order_ingest_external,
             commands::orders::order_update_status,
             commands::orders::order_update_customer_info,
             commands::orders::order_convert_pickup_to_delivery,
@@ -889,11 +1067,15 @@ pub fn run() {
             commands::orders::orders_preview_edit_settlement,
             commands::orders::orders_apply_edit_settlement,
             commands::orders::order_update_financials,
+            commands::orders::order_acquire_lock,
+            commands::orders::order_release_lock,
+            commands::orders::order_get_lock_status,
             commands::orders::order_approve,
             commands::orders::order_decline,
             commands::orders::order_assign_driver,
             commands::orders::order_delete,
             commands::orders::order_save_from_remote,
+            commands::orders::orders_import_from_admin,
             commands::orders::order_fetch_items_from_supabase,
             commands::orders::order_notify_platform_ready,
             commands::orders::order_update_preparation,
@@ -909,6 +1091,8 @@ pub fn run() {
             // Sync
             commands::sync::sync_get_status,
             commands::sync::sync_get_network_status,
+            commands::sync::network_get_history,
+            commands::sync::realtime_get_status,
             commands::sync::sync_get_inter_terminal_status,
             commands::sync::sync_force,
             commands::sync::sync_validate_pending_orders,
@@ -928,10 +1112,13 @@ pub fn run() {
             commands::sync::sync_cleanup_deleted_orders,
             commands::sync::sync_rediscover_parent,
             commands::sync::sync_fetch_suppliers,
+            commands::sync::sync_fetch_tables,
+            commands::sync::sync_fetch_reservations,
             commands::sync::sync_fetch_analytics,
             commands::sync::sync_fetch_orders,
             commands::sync::sync_fetch_rooms,
             commands::sync::sync_update_room_status,
+            commands::sync::remote_cache_invalidate,
             commands::sync::sync_fetch_drive_thru,
             commands::sync::sync_update_drive_thru_order_status,
             commands::sync::rooms_get_availability,
@@ -954,6 +1141,10 @@ pub fn run() {
             commands::offline_mutations::offline_coupon_set_active,
             commands::offline_mutations::offline_reservation_create,
             commands::offline_mutations::offline_reservation_update,
+            commands::reservations::reservation_list_today,
+            commands::reservations::reservation_refresh,
+            commands::reservations::reservation_check_in,
+            commands::reservations::reservation_mark_no_show,
             commands::offline_mutations::offline_appointment_create,
             commands::offline_mutations::offline_appointment_update_status,
             commands::offline_mutations::offline_staff_shift_create,
@@ -970,11 +1161,18 @@ pub fn run() {
             commands::menu::menu_get_subcategory_ingredients,
             commands::menu::menu_get_combos,
             commands::menu::menu_sync,
+            commands::menu::menu_get_image,
+            commands::menu::menu_clear_image_cache,
             commands::menu::menu_update_category,
             commands::menu::menu_update_subcategory,
             commands::menu::menu_update_ingredient,
             commands::menu::menu_update_combo,
+            commands::menu::menu_bulk_update_availability,
+            commands::menu::menu_bulk_restore_availability,
             commands::menu::menu_trigger_check_for_updates,
+            commands::menu::favorites_get_grid,
+            commands::menu::favorites_set_grid,
+            commands::menu::favorites_autofill,
             // Shifts
             commands::shifts::shift_open,
             commands::shifts::shift_close,
@@ -998,6 +1196,11 @@ pub fn run() {
             commands::shifts::shift_get_staff_payment_total_for_date,
             commands::shifts::shift_get_scheduled_shifts,
             commands::shifts::shift_get_today_scheduled_shifts,
+            commands::shifts::shift_get_schedule_adherence,
+            commands::shifts::shift_get_float_history,
+            commands::shifts::drawer_cash_in,
+            commands::shifts::drawer_cash_out,
+            commands::shifts::drawer_get_transactions,
             commands::shifts::shift_backfill_driver_earnings,
             commands::shifts::shift_print_checkout,
             // Payments
@@ -1006,26 +1209,43 @@ pub fn run() {
             commands::payments::payment_update_payment_status,
             commands::payments::payment_update_payment_method,
             commands::payments::payment_get_order_payments,
+            commands::payments::order_get_balance,
+            commands::payments::order_split_bill,
+            commands::payments::order_get_checks,
             commands::payments::payment_get_receipt_preview,
+            commands::payments::payment_reprint_receipt,
+            commands::payments::receipt_send,
+            commands::payments::receipt_list_deliveries,
             commands::payments::payment_get_paid_items,
             commands::payments::payment_print_split_receipt,
+            commands::payments::payment_print_check_receipt,
             // Refunds / Adjustments
             commands::payments::refund_payment,
             commands::payments::refund_void_payment,
             commands::payments::refund_list_order_adjustments,
             commands::payments::refund_get_payment_balance,
+            commands::payments::tab_open,
+            commands::payments::tab_close,
+            commands::payments::tab_list_open,
             // Z-Reports
             commands::zreports::zreport_generate,
             commands::zreports::zreport_get,
             commands::zreports::zreport_list,
             commands::zreports::zreport_print,
+            commands::zreports::eod_run_now,
+            commands::zreports::eod_get_status,
             // Print
             commands::print::payment_print_receipt,
             commands::print::kitchen_print_ticket,
+            commands::print::order_fire_course,
+            commands::print::order_get_course_status,
             commands::print::print_list_jobs,
             commands::print::print_get_receipt_file,
             commands::print::print_reprint_job,
             commands::print::receipt_sample_preview,
+            commands::print::print_preview_receipt,
+            commands::print::print_preview_kitchen_ticket,
+            commands::print::print_label,
             commands::print::label_print,
             commands::print::label_print_batch,
             // Screen capture / Geo
@@ -1046,6 +1266,8 @@ pub fn run() {
             commands::print::printer_get_all_statuses,
             commands::print::printer_submit_job,
             commands::print::printer_cancel_job,
+            commands::print::print_cancel_job,
+            commands::print::print_list_scheduled,
             commands::print::printer_cancel_all_jobs,
             commands::print::printer_pause_queue,
             commands::print::printer_retry_job,
@@ -1091,6 +1313,9 @@ pub fn run() {
             commands::ecr::ecr_test_connection,
             commands::ecr::ecr_test_print,
             commands::ecr::ecr_fiscal_print,
+            commands::fiscal::fiscal_export_daily_journal,
+            commands::fiscal::fiscal_list_order_documents,
+            commands::fiscal::fiscal_cancel_document,
             // Caller ID / VoIP
             commands::callerid::callerid_start,
             commands::callerid::callerid_stop,
@@ -1098,6 +1323,8 @@ pub fn run() {
             commands::callerid::callerid_save_config,
             commands::callerid::callerid_get_config,
             commands::callerid::callerid_test_connection,
+            commands::callerid::callerid_configure,
+            commands::callerid::callerid_simulate_call,
             // Cash drawer
             commands::hardware::drawer_open,
             // Serial ports
@@ -1145,6 +1372,13 @@ pub fn run() {
             commands::hardware::hardware_reconnect,
             // Dashboard metrics
             commands::analytics::inventory_get_stock_metrics,
+            commands::inventory::inventory_list_items,
+            commands::inventory::inventory_upsert_item,
+            commands::inventory::inventory_set_recipe,
+            commands::inventory::inventory_set_modifier_recipe,
+            commands::inventory::inventory_adjust,
+            commands::inventory::inventory_receive,
+            commands::inventory::inventory_sync_items,
             commands::analytics::products_get_catalog_count,
             // Customers
             commands::customers::customer_invalidate_cache,
@@ -1161,11 +1395,17 @@ pub fn run() {
             commands::customers::customer_delete_address,
             commands::customers::customer_resolve_conflict,
             commands::customers::customer_get_conflicts,
+            commands::customers::customer_import_csv,
+            commands::customers::customer_export_data,
+            commands::customers::customer_erase,
             // Drivers
             commands::analytics::driver_record_earning,
             commands::analytics::driver_get_earnings,
             commands::analytics::driver_get_shift_summary,
             commands::analytics::driver_get_active,
+            commands::dispatch::driver_start_run,
+            commands::dispatch::driver_complete_run,
+            commands::dispatch::dispatch_get_board,
             // Delivery zones
             commands::analytics::delivery_zone_track_validation,
             commands::analytics::delivery_zone_get_analytics,
@@ -1182,6 +1422,13 @@ pub fn run() {
             commands::analytics::report_get_hourly_sales,
             commands::analytics::report_get_payment_method_breakdown,
             commands::analytics::report_get_order_type_breakdown,
+            commands::analytics::reports_get_daily_summary,
+            commands::analytics::reports_get_hourly_heatmap,
+            commands::analytics::reports_get_staff_performance,
+            commands::analytics::reports_get_platform_reconciliation,
+            commands::analytics::reports_get_margin,
+            commands::analytics::reports_export,
+            commands::analytics::exports_list,
             commands::analytics::report_generate_z_report,
             commands::analytics::report_get_end_of_day_status,
             commands::analytics::report_get_daily_staff_performance,
@@ -1192,6 +1439,9 @@ pub fn run() {
             commands::modules::modules_fetch_from_admin,
             commands::modules::modules_get_cached,
             commands::modules::modules_save_cache,
+            commands::modules::features_get_all,
+            commands::modules::features_set_override,
+            commands::modules::features_clear_override,
             commands::branch_data::branch_data_get_bundle_status,
             commands::branch_data::branch_data_get_catalog_offers,
             commands::branch_data::branch_data_get_delivery_zones,
@@ -1202,7 +1452,11 @@ pub fn run() {
             // Utility compatibility
             commands::system_ui::clipboard_read_text,
             commands::system_ui::clipboard_write_text,
+            commands::system_ui::clipboard_write_image,
             commands::system_ui::show_notification,
+            commands::system_ui::notifications_get_settings,
+            commands::system_ui::notifications_set_settings,
+            commands::system_ui::notifications_test,
             // Window
             commands::system_ui::window_start_drag,
             commands::system_ui::window_get_position,
@@ -1224,6 +1478,7 @@ pub fn run() {
             // Database
             commands::diagnostics::database_health_check,
             commands::diagnostics::database_get_stats,
+            commands::diagnostics::db_get_migration_status,
             commands::diagnostics::database_reset,
             commands::diagnostics::database_clear_operational_data,
             commands::diagnostics::diagnostic_check_delivered_orders,
@@ -1232,7 +1487,22 @@ pub fn run() {
             commands::diagnostics::diagnostics_get_about,
             commands::diagnostics::diagnostics_get_system_health,
             commands::diagnostics::diagnostics_export,
+            commands::diagnostics::diagnostics_export_bundle,
+            commands::diagnostics::system_health_check,
+            commands::diagnostics::heartbeat_send_now,
+            commands::diagnostics::diagnostics_get_recent_errors,
+            commands::diagnostics::diagnostics_get_metrics,
+            commands::diagnostics::diagnostics_reset_metrics,
+            commands::diagnostics::diagnostics_find_by_correlation,
+            commands::diagnostics::logs_query,
+            commands::diagnostics::logs_get_files,
+            commands::diagnostics::logs_get_level,
+            commands::diagnostics::logs_set_level,
             commands::diagnostics::diagnostics_open_export_dir,
+            commands::diagnostics::support_session_start,
+            commands::diagnostics::support_get_snapshot,
+            commands::diagnostics::support_session_end,
+            commands::diagnostics::support_session_status,
             commands::diagnostics::diagnostics_send_remote_incident,
             // Recovery
             commands::recovery::recovery_list_points,
@@ -1245,6 +1515,16 @@ pub fn run() {
             commands::recovery::recovery_restore_point,
             commands::recovery::recovery_open_dir,
             commands::recovery::recovery_execute_action,
+            commands::reasons::reasons_get,
+            commands::reasons::reasons_set,
+            commands::auto_accept::autoaccept_get_rules,
+            commands::auto_accept::autoaccept_set_rules,
+            commands::pricing_rules::pricing_rules_get,
+            commands::pricing_rules::pricing_rules_set,
+            commands::retention::retention_get_policy,
+            commands::retention::retention_set_policy,
+            commands::retention::retention_run_now,
+            commands::retention::archives_list,
             // Updates
             commands::updates::update_get_state,
             commands::updates::update_check,
@@ -1255,10 +1535,30 @@ pub fn run() {
             commands::updates::update_set_channel,
             // API proxy
             commands::api_bridge::api_fetch_from_admin,
+            commands::api_bridge::admin_api_request,
             commands::api_bridge::api_list_cached_paths,
             commands::api_bridge::sync_test_parent_connection,
             commands::api_bridge::admin_sync_terminal_config,
-        ])
+            ]);
+            // Synchronous commands run inline here and inherit the id;
+            // async ones re-establish it via `correlation::command`.
+            move |invoke| {
+                let correlation_id = correlation::for_invoke(&invoke.message);
+                tracing::debug!(
+                    command = invoke.message.command(),
+                    correlation_id = %correlation_id,
+                    "IPC command"
+                );
+                let _guard = correlation::enter_sync(correlation_id);
+                if kiosk::is_enabled() && !kiosk::is_command_allowed(invoke.message.command()) {
+                    let command = invoke.message.command().to_string();
+                    tracing::debug!(command = %command, "IPC command rejected in kiosk mode");
+                    invoke.resolver.reject(kiosk::command_rejection(&command));
+                    return true;
+                }
+                handler(invoke)
+            }
+        })
         .build(tauri::generate_context!())
         .expect("error while building The Small POS")
         .run(|app, event| {
@@ -0,0 +1,602 @@
+//! Structured log files and the in-app log viewer.
+//!
+//! Alongside the plain-text `pos.*` files, every event is written as one JSON
+//! line to `logs/pos-json.<YYYY-MM-DD>.<n>.jsonl`. A new file starts at UTC
+//! midnight or once the current one reaches [`MAX_JSON_LOG_FILE_SIZE`], and
+//! only the newest [`MAX_JSON_LOG_FILES`] are kept. Records pass through
+//! [`diagnostics::redact_event_fields`] before they reach disk, so API keys,
+//! PINs, card references and customer contact data never land in these files.
+//!
+//! `logs_query` streams the files line by line and keeps only the requested
+//! page of matches in memory. The global `EnvFilter` sits behind a reload
+//! handle so `logs_set_level` can raise verbosity for a debug session without
+//! a restart; the change is not persisted.
+
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::{json, Value};
+use tracing::Level;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use crate::diagnostics;
+
+pub const JSON_LOG_PREFIX: &str = "pos-json.";
+const JSON_LOG_EXTENSION: &str = "jsonl";
+
+/// Size at which the current JSON log file is closed and the next one opened.
+pub const MAX_JSON_LOG_FILE_SIZE: u64 = diagnostics::MAX_LOG_SIZE;
+
+/// JSON log files retained; older ones are deleted when a new file opens.
+pub const MAX_JSON_LOG_FILES: usize = 30;
+
+/// Filter used when `RUST_LOG` is not set.
+pub const DEFAULT_LOG_FILTER: &str = "info,the_small_pos_lib=info";
+
+const DEFAULT_QUERY_LIMIT: usize = 100;
+const MAX_QUERY_LIMIT: usize = 1000;
+/// Upper bound on `offset + limit`, which is what a query holds in memory.
+const MAX_QUERY_WINDOW: usize = 10_000;
+
+// ---------------------------------------------------------------------------
+// Runtime log level
+// ---------------------------------------------------------------------------
+
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Wrap the startup filter in a reloadable layer and keep its handle.
+pub fn reloadable_filter(filter: EnvFilter) -> reload::Layer<EnvFilter, Registry> {
+    let (layer, handle) = reload::Layer::new(filter);
+    let _ = FILTER_HANDLE.set(handle);
+    layer
+}
+
+pub fn current_filter() -> Option<String> {
+    FILTER_HANDLE
+        .get()?
+        .with_current(|filter| filter.to_string())
+        .ok()
+}
+
+/// Expand a bare level (`debug`) to a directive that raises this crate only,
+/// leaving dependencies at `info`; anything else is taken as directives.
+fn filter_directives(level: &str) -> String {
+    let level = level.trim();
+    if level.eq_ignore_ascii_case("default") {
+        return DEFAULT_LOG_FILTER.to_string();
+    }
+    match level.parse::<Level>() {
+        Ok(parsed) => format!(
+            "info,the_small_pos_lib={}",
+            parsed.as_str().to_ascii_lowercase()
+        ),
+        Err(_) => level.to_string(),
+    }
+}
+
+/// Replace the active filter. Returns the new filter string.
+pub fn set_level(level: &str) -> Result<String, String> {
+    let directives = filter_directives(level);
+    if directives.is_empty() {
+        return Err("Missing log level".into());
+    }
+    let filter =
+        EnvFilter::try_new(&directives).map_err(|e| format!("Invalid log level {level}: {e}"))?;
+    FILTER_HANDLE
+        .get()
+        .ok_or("Logging is not initialised")?
+        .reload(filter)
+        .map_err(|e| format!("Failed to change log level: {e}"))?;
+    Ok(current_filter().unwrap_or(directives))
+}
+
+// ---------------------------------------------------------------------------
+// JSON file writer
+// ---------------------------------------------------------------------------
+
+fn json_log_path(dir: &Path, date: NaiveDate, index: u32) -> PathBuf {
+    dir.join(format!(
+        "{JSON_LOG_PREFIX}{}.{index}.{JSON_LOG_EXTENSION}",
+        date.format("%Y-%m-%d")
+    ))
+}
+
+/// `(date, index)` of a JSON log file name, `None` for any other file.
+fn parse_json_log_name(name: &str) -> Option<(NaiveDate, u32)> {
+    let stem = name
+        .strip_prefix(JSON_LOG_PREFIX)?
+        .strip_suffix(JSON_LOG_EXTENSION)?
+        .strip_suffix('.')?;
+    let (date, index) = stem.rsplit_once('.')?;
+    Some((
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?,
+        index.parse().ok()?,
+    ))
+}
+
+/// JSON log files in `dir`, oldest first.
+fn json_log_files(dir: &Path) -> Vec<(NaiveDate, u32, PathBuf)> {
+    let mut files: Vec<(NaiveDate, u32, PathBuf)> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| {
+                    let (date, index) = parse_json_log_name(entry.file_name().to_str()?)?;
+                    Some((date, index, entry.path()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+fn prune_json_logs(dir: &Path, keep: usize) {
+    let files = json_log_files(dir);
+    let excess = files.len().saturating_sub(keep);
+    for (_, _, path) in files.into_iter().take(excess) {
+        if let Err(e) = fs::remove_file(&path) {
+            tracing::warn!("Failed to prune log file {}: {e}", path.display());
+        }
+    }
+}
+
+/// Redact one formatted JSON record. Lines that are not JSON pass through.
+fn redact_json_line(line: &[u8]) -> Vec<u8> {
+    let Ok(mut record) = serde_json::from_slice::<Value>(line) else {
+        return line.to_vec();
+    };
+    if let Some(obj) = record.as_object_mut() {
+        if let Some(fields) = obj.remove("fields") {
+            obj.insert("fields".into(), diagnostics::redact_event_fields(fields));
+        }
+        for key in ["span", "spans"] {
+            if let Some(spans) = obj.remove(key) {
+                obj.insert(key.into(), diagnostics::redact_event_fields(spans));
+            }
+        }
+    }
+    let mut redacted = serde_json::to_vec(&record).unwrap_or_else(|_| line.to_vec());
+    redacted.push(b'\n');
+    redacted
+}
+
+struct OpenLog {
+    date: NaiveDate,
+    index: u32,
+    file: File,
+    size: u64,
+}
+
+/// Daily, size-capped writer for the JSON log layer. Wrap it in
+/// `tracing_appender::non_blocking`, which hands it one record per write.
+pub struct RollingJsonWriter {
+    dir: PathBuf,
+    max_file_size: u64,
+    max_files: usize,
+    current: Option<OpenLog>,
+}
+
+impl RollingJsonWriter {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            max_file_size: MAX_JSON_LOG_FILE_SIZE,
+            max_files: MAX_JSON_LOG_FILES,
+            current: None,
+        }
+    }
+
+    /// Open the first file for `date` at or after `index` that has room for
+    /// `incoming` bytes. An empty file always has room.
+    fn open(&self, date: NaiveDate, mut index: u32, incoming: u64) -> io::Result<OpenLog> {
+        loop {
+            let path = json_log_path(&self.dir, date, index);
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            if size == 0 || size + incoming <= self.max_file_size {
+                let file = OpenOptions::new().create(true).append(true).open(&path)?;
+                return Ok(OpenLog {
+                    date,
+                    index,
+                    file,
+                    size,
+                });
+            }
+            index += 1;
+        }
+    }
+
+    fn file_for(&mut self, incoming: u64) -> io::Result<&mut OpenLog> {
+        let today = Utc::now().date_naive();
+        let next_index = match &self.current {
+            Some(log) if log.date == today => {
+                (log.size > 0 && log.size + incoming > self.max_file_size).then_some(log.index + 1)
+            }
+            // First write of the process or of a new day: continue the
+            // newest file for today if there is one.
+            _ => Some(
+                json_log_files(&self.dir)
+                    .into_iter()
+                    .filter(|(date, _, _)| *date == today)
+                    .map(|(_, index, _)| index)
+                    .max()
+                    .unwrap_or(0),
+            ),
+        };
+        if let Some(index) = next_index {
+            self.current = Some(self.open(today, index, incoming)?);
+            prune_json_logs(&self.dir, self.max_files);
+        }
+        Ok(self.current.as_mut().expect("log file opened above"))
+    }
+}
+
+impl Write for RollingJsonWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let record = redact_json_line(buf);
+        let log = self.file_for(record.len() as u64)?;
+        log.file.write_all(&record)?;
+        log.size += record.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.current.as_mut() {
+            Some(log) => log.file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Viewer
+// ---------------------------------------------------------------------------
+
+/// Plain-text and JSON log files in `dir`, newest first.
+pub fn list_files(dir: &Path) -> Vec<Value> {
+    let mut files: Vec<(std::time::SystemTime, Value)> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_str()?.to_string();
+                    let kind = if parse_json_log_name(&name).is_some() {
+                        "json"
+                    } else if name.starts_with("pos.") {
+                        "text"
+                    } else {
+                        return None;
+                    };
+                    let metadata = entry.metadata().ok()?;
+                    let modified = metadata.modified().unwrap_or(std::time::UNIX_EPOCH);
+                    Some((
+                        modified,
+                        json!({
+                            "name": name,
+                            "kind": kind,
+                            "sizeBytes": metadata.len(),
+                            "modifiedAt": DateTime::<Utc>::from(modified).to_rfc3339(),
+                        }),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort_by_key(|file| std::cmp::Reverse(file.0));
+    files.into_iter().map(|(_, file)| file).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogQuery {
+    /// Least severe level included (`warn` returns WARN and ERROR).
+    pub level: Option<Level>,
+    /// Module target prefix, e.g. `the_small_pos_lib::sync`.
+    pub target: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Case-insensitive text matched against the whole record.
+    pub search: Option<String>,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+impl LogQuery {
+    pub fn from_payload(payload: &Value) -> Result<Self, String> {
+        let text = |key: &str| crate::value_str(payload, &[key]);
+        let time = |key: &str| -> Result<Option<DateTime<Utc>>, String> {
+            text(key)
+                .map(|raw| {
+                    DateTime::parse_from_rfc3339(&raw)
+                        .map(|at| at.with_timezone(&Utc))
+                        .map_err(|e| format!("Invalid {key} timestamp: {e}"))
+                })
+                .transpose()
+        };
+        let count = |key: &str| payload.get(key).and_then(Value::as_u64).map(|n| n as usize);
+
+        let level = text("level")
+            .map(|raw| {
+                raw.parse::<Level>()
+                    .map_err(|_| format!("Invalid log level: {raw}"))
+            })
+            .transpose()?;
+        let limit = count("limit")
+            .unwrap_or(DEFAULT_QUERY_LIMIT)
+            .clamp(1, MAX_QUERY_LIMIT);
+        let offset = count("offset").unwrap_or(0);
+        if offset + limit > MAX_QUERY_WINDOW {
+            return Err(format!(
+                "offset + limit must not exceed {MAX_QUERY_WINDOW}; narrow the time range instead"
+            ));
+        }
+        Ok(Self {
+            level,
+            target: text("target"),
+            from: time("from")?,
+            to: time("to")?,
+            search: text("search").map(|s| s.to_lowercase()),
+            limit,
+            offset,
+        })
+    }
+
+    /// Whether a file dated `date` can hold records in the time range.
+    fn covers_date(&self, date: NaiveDate) -> bool {
+        self.from.map_or(true, |from| date >= from.date_naive())
+            && self.to.map_or(true, |to| date <= to.date_naive())
+    }
+
+    fn matches(&self, line: &str, record: &Value) -> bool {
+        if let Some(search) = &self.search {
+            if !line.to_lowercase().contains(search.as_str()) {
+                return false;
+            }
+        }
+        if let Some(min) = self.level {
+            let level = record["level"]
+                .as_str()
+                .and_then(|l| l.parse::<Level>().ok());
+            // tracing orders levels by verbosity: ERROR < WARN < ... < TRACE.
+            if !level.is_some_and(|level| level <= min) {
+                return false;
+            }
+        }
+        if let Some(target) = &self.target {
+            if !record["target"]
+                .as_str()
+                .is_some_and(|t| t.starts_with(target.as_str()))
+            {
+                return false;
+            }
+        }
+        if self.from.is_some() || self.to.is_some() {
+            let Some(at) = record["timestamp"]
+                .as_str()
+                .and_then(|raw| DateTime::parse_from_rfc3339(raw).ok())
+            else {
+                return false;
+            };
+            let at = at.with_timezone(&Utc);
+            if self.from.is_some_and(|from| at < from) || self.to.is_some_and(|to| at > to) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn viewer_entry(record: Value, file: &str) -> Value {
+    let mut fields = record.get("fields").cloned().unwrap_or_else(|| json!({}));
+    let message = fields
+        .as_object_mut()
+        .and_then(|fields| fields.remove("message"))
+        .unwrap_or(Value::Null);
+    json!({
+        "timestamp": record.get("timestamp"),
+        "level": record.get("level"),
+        "target": record.get("target"),
+        "message": message,
+        "fields": fields,
+        "file": file,
+    })
+}
+
+/// Matching records across the JSON log files, newest first. Files are read
+/// line by line; at most `offset + limit` matches are held at once.
+pub fn query(dir: &Path, query: &LogQuery) -> Result<Value, String> {
+    let window = query.offset + query.limit;
+    let mut newest: VecDeque<Value> = VecDeque::with_capacity(window.min(1024));
+    let mut total = 0usize;
+
+    for (date, _, path) in json_log_files(dir) {
+        if !query.covers_date(date) {
+            continue;
+        }
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("open {name}: {e}")),
+        };
+        for line in BufReader::new(file).lines() {
+            let Ok(line) = line else {
+                continue;
+            };
+            let Ok(record) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            if !query.matches(&line, &record) {
+                continue;
+            }
+            total += 1;
+            if newest.len() == window {
+                newest.pop_front();
+            }
+            newest.push_back(viewer_entry(record, &name));
+        }
+    }
+
+    let entries: Vec<Value> = newest
+        .into_iter()
+        .rev()
+        .skip(query.offset)
+        .take(query.limit)
+        .collect();
+    Ok(json!({
+        "entries": entries,
+        "total": total,
+        "offset": query.offset,
+        "limit": query.limit,
+        "hasMore": query.offset + query.limit < total,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pos-logs-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn record(timestamp: &str, level: &str, target: &str, message: &str) -> String {
+        json!({
+            "timestamp": timestamp,
+            "level": level,
+            "target": target,
+            "fields": { "message": message },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn writer_redacts_and_rolls_over_at_size_cap() {
+        let dir = temp_dir();
+        let mut writer = RollingJsonWriter::new(&dir);
+        writer.max_file_size = 300;
+        let line = json!({
+            "timestamp": "2026-10-15T10:00:00Z",
+            "level": "INFO",
+            "target": "the_small_pos_lib::auth",
+            "fields": { "message": "pin checked", "pin": "1234", "api_key": "sk_live_abc" },
+        })
+        .to_string()
+            + "\n";
+        for _ in 0..4 {
+            writer.write_all(line.as_bytes()).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let files = json_log_files(&dir);
+        assert!(files.len() > 1, "expected rollover, got {files:?}");
+        for (_, _, path) in &files {
+            assert!(fs::metadata(path).unwrap().len() <= 300);
+            let contents = fs::read_to_string(path).unwrap();
+            assert!(!contents.contains("1234"));
+            assert!(!contents.contains("sk_live_abc"));
+            assert!(contents.contains("pin checked"));
+        }
+
+        writer.max_files = 1;
+        writer.current = None;
+        writer.write_all(line.as_bytes()).unwrap();
+        assert_eq!(json_log_files(&dir).len(), 1);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn query_filters_and_pages_newest_first() {
+        let dir = temp_dir();
+        let day1 = [
+            record(
+                "2026-10-14T09:00:00Z",
+                "INFO",
+                "the_small_pos_lib::sync",
+                "sync ok",
+            ),
+            record(
+                "2026-10-14T09:05:00Z",
+                "WARN",
+                "the_small_pos_lib::sync",
+                "sync slow",
+            ),
+            "not json".to_string(),
+        ];
+        let day2 = [
+            record(
+                "2026-10-15T08:00:00Z",
+                "ERROR",
+                "the_small_pos_lib::print",
+                "printer offline",
+            ),
+            record(
+                "2026-10-15T08:01:00Z",
+                "ERROR",
+                "the_small_pos_lib::sync",
+                "sync failed",
+            ),
+            record(
+                "2026-10-15T08:02:00Z",
+                "DEBUG",
+                "the_small_pos_lib::sync",
+                "sync detail",
+            ),
+        ];
+        let day1_date = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        let day2_date = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+        fs::write(json_log_path(&dir, day1_date, 0), day1.join("\n")).unwrap();
+        fs::write(json_log_path(&dir, day2_date, 0), day2.join("\n")).unwrap();
+
+        let warnings = LogQuery::from_payload(
+            &json!({ "level": "warn", "target": "the_small_pos_lib::sync" }),
+        )
+        .unwrap();
+        let result = query(&dir, &warnings).unwrap();
+        assert_eq!(result["total"], 2);
+        assert_eq!(result["entries"][0]["message"], "sync failed");
+        assert_eq!(result["entries"][1]["message"], "sync slow");
+
+        let paged =
+            LogQuery::from_payload(&json!({ "search": "SYNC", "limit": 2, "offset": 1 })).unwrap();
+        let result = query(&dir, &paged).unwrap();
+        assert_eq!(result["total"], 4);
+        assert_eq!(result["hasMore"], true);
+        let messages: Vec<&str> = result["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["message"].as_str().unwrap())
+            .collect();
+        assert_eq!(messages, ["sync failed", "sync slow"]);
+
+        let ranged = LogQuery::from_payload(&json!({
+            "from": "2026-10-15T08:00:30Z",
+            "to": "2026-10-15T08:01:30Z",
+        }))
+        .unwrap();
+        let result = query(&dir, &ranged).unwrap();
+        assert_eq!(result["total"], 1);
+        assert_eq!(result["entries"][0]["file"], "pos-json.2026-10-15.0.jsonl");
+
+        assert!(LogQuery::from_payload(&json!({ "level": "loud" })).is_err());
+        assert_eq!(list_files(&dir).len(), 2);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn bare_levels_only_raise_this_crate() {
+        assert_eq!(filter_directives("DEBUG"), "info,the_small_pos_lib=debug");
+        assert_eq!(filter_directives("default"), DEFAULT_LOG_FILTER);
+        assert_eq!(filter_directives("warn,hyper=off"), "warn,hyper=off");
+    }
+}
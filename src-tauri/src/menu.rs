@@ -2,7 +2,9 @@
 //!
 //! Reads cached menu data (categories, subcategories, ingredients, combos)
 //! from the local SQLite `menu_cache` table, and provides a sync function
-//! that fetches fresh data from the admin dashboard API.
+//! that fetches fresh data from the admin dashboard API. Menu photos are
+//! cached for offline use by [`images`]; bulk availability changes made on
+//! the POS are kept over synced data by [`availability`].
 
 use chrono::Utc;
 use rusqlite::params;
@@ -16,6 +18,9 @@ use crate::api;
 use crate::db::DbState;
 use crate::storage;
 
+pub mod availability;
+pub mod images;
+
 #[derive(Debug, Clone)]
 pub struct MenuVersionDigest {
     pub token: String,
@@ -38,22 +43,23 @@ struct MenuSyncCredentials {
 
 /// Read a cached menu array by key. Returns an empty array on miss or error.
 fn read_cache(db: &DbState, cache_key: &str) -> Vec<Value> {
-    let conn = match db.conn.lock() {
-        Ok(c) => c,
+    let json_str = db.read(|conn| {
+        Ok(conn
+            .query_row(
+                "SELECT data FROM menu_cache WHERE cache_key = ?1",
+                params![cache_key],
+                |row| row.get::<_, String>(0),
+            )
+            .ok())
+    });
+    let json_str = match json_str {
+        Ok(json_str) => json_str,
         Err(e) => {
             error!("menu cache lock failed: {e}");
             return vec![];
         }
     };
 
-    let json_str: Option<String> = conn
-        .query_row(
-            "SELECT data FROM menu_cache WHERE cache_key = ?1",
-            params![cache_key],
-            |row| row.get(0),
-        )
-        .ok();
-
     match json_str {
         Some(s) => match serde_json::from_str::<Value>(&s) {
             Ok(Value::Array(arr)) => arr,
@@ -452,6 +458,16 @@ pub async fn sync_menu(db: &DbState) -> Result<Value, String> {
         "combos": combo_count
     });
 
+    // Pricing rules ride along with the menu but are not part of its
+    // version: a sync that carries the section always replaces the admin
+    // rules, one that omits it leaves them alone.
+    if let Some(rules) = data.get("pricing_rules").and_then(Value::as_array) {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        if let Err(error) = crate::pricing_rules::store_remote(&conn, rules) {
+            warn!(error = %error, "menu_sync: failed to store pricing rules");
+        }
+    }
+
     let version = compute_menu_payload_version(data);
     let timestamp = resp
         .get("timestamp")
@@ -462,46 +478,51 @@ pub async fn sync_menu(db: &DbState) -> Result<Value, String> {
         .unwrap_or_else(|| Utc::now().to_rfc3339());
 
     // Check if version matches current cache to skip unnecessary writes
-    {
+    let cached_version: Option<String> = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        let cached_version: Option<String> = conn
-            .query_row(
-                "SELECT version FROM menu_cache WHERE cache_key = 'categories'",
-                [],
-                |row| row.get(0),
-            )
-            .ok()
-            .flatten();
-
-        if cached_version.as_deref() == Some(version.as_str()) {
-            trace!(
-                terminal_id = %masked_terminal_id,
-                version = %version,
-                categories = category_count,
-                subcategories = subcategory_count,
-                ingredients = ingredient_count,
-                combos = combo_count,
-                "menu_sync: cache already at latest version"
-            );
-            return Ok(serde_json::json!({
-                "success": true,
-                "updated": false,
-                "version": version,
-                "counts": counts,
-                "timestamp": timestamp
-            }));
-        }
+        conn.query_row(
+            "SELECT version FROM menu_cache WHERE cache_key = 'categories'",
+            [],
+            |row| row.get(0),
+        )
+        .ok()
+        .flatten()
+    };
+    if cached_version.as_deref() == Some(version.as_str()) {
+        // Still fetch images that are missing (first run, eviction).
+        let images = sync_menu_images(db, false).await;
+        trace!(
+            terminal_id = %masked_terminal_id,
+            version = %version,
+            categories = category_count,
+            subcategories = subcategory_count,
+            ingredients = ingredient_count,
+            combos = combo_count,
+            "menu_sync: cache already at latest version"
+        );
+        return Ok(serde_json::json!({
+            "success": true,
+            "updated": false,
+            "version": version,
+            "counts": counts,
+            "images": images,
+            "timestamp": timestamp
+        }));
     }
 
     // Upsert each section
     let sections = ["categories", "subcategories", "ingredients", "combos"];
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let price_delta = crate::stale_prices::PriceDelta::between(&conn, data);
 
     for section in &sections {
-        let empty = Value::Array(vec![]);
-        let section_data = data.get(*section).unwrap_or(&empty);
-        let json_str =
-            serde_json::to_string(section_data).map_err(|e| format!("serialize {section}: {e}"))?;
+        let mut section_data = data
+            .get(*section)
+            .cloned()
+            .unwrap_or_else(|| Value::Array(vec![]));
+        availability::overlay_pending(&conn, section, &mut section_data)?;
+        let json_str = serde_json::to_string(&section_data)
+            .map_err(|e| format!("serialize {section}: {e}"))?;
 
         conn.execute(
             "INSERT INTO menu_cache (id, cache_key, data, version, updated_at)
@@ -514,6 +535,16 @@ pub async fn sync_menu(db: &DbState) -> Result<Value, String> {
         )
         .map_err(|e| format!("upsert menu_cache[{section}]: {e}"))?;
     }
+    // Flagging is advisory: a failure must not fail the sync that already
+    // replaced the cache.
+    let stale_order_ids = crate::stale_prices::flag_open_orders(&conn, &price_delta)
+        .unwrap_or_else(|error| {
+            warn!(error = %error, "menu_sync: failed to flag stale order prices");
+            Vec::new()
+        });
+    drop(conn);
+
+    let images = sync_menu_images(db, true).await;
 
     trace!(
         terminal_id = %masked_terminal_id,
@@ -530,10 +561,25 @@ pub async fn sync_menu(db: &DbState) -> Result<Value, String> {
         "updated": true,
         "version": version,
         "counts": counts,
+        "images": images,
+        "priceChanges": price_delta.len(),
+        "staleOrderIds": stale_order_ids,
         "timestamp": if timestamp.trim().is_empty() { Utc::now().to_rfc3339() } else { timestamp }
     }))
 }
 
+/// Refresh the image cache after a menu sync. Image failures are logged and
+/// reported in the summary; they never fail the sync itself.
+async fn sync_menu_images(db: &DbState, revalidate: bool) -> Value {
+    match images::sync_images(db, revalidate).await {
+        Ok(summary) => summary,
+        Err(error) => {
+            warn!(error = %error, "menu_sync: image cache refresh failed");
+            serde_json::json!({ "error": error })
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
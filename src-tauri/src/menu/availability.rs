@@ -0,0 +1,428 @@
+//! Local availability overrides for bulk 86ing.
+//!
+//! `menu_bulk_update_availability` flips the availability flag of many
+//! categories, subcategories or ingredients at once. The flag is written into
+//! the cached menu right away so the POS reflects it offline, and each change
+//! is recorded in `menu_availability_overrides` under the bulk operation id.
+//!
+//! An override stays `pending` until the admin has the same value — either
+//! the PATCH went through, or a later menu sync came back agreeing. Until
+//! then every menu sync lays pending overrides back over the fresh payload,
+//! so a sync that races a queued PATCH cannot bring an 86'd item back. The
+//! operation id lets `menu_bulk_restore_availability` re-enable exactly what
+//! one operation disabled.
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+
+use crate::value_str;
+
+/// Menu entity types whose availability can be bulk-toggled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuEntity {
+    Category,
+    Subcategory,
+    Ingredient,
+}
+
+impl MenuEntity {
+    pub const ALL: [MenuEntity; 3] = [
+        MenuEntity::Category,
+        MenuEntity::Subcategory,
+        MenuEntity::Ingredient,
+    ];
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "category" | "categories" => Some(MenuEntity::Category),
+            "subcategory" | "subcategories" | "item" | "items" => Some(MenuEntity::Subcategory),
+            "ingredient" | "ingredients" => Some(MenuEntity::Ingredient),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MenuEntity::Category => "category",
+            MenuEntity::Subcategory => "subcategory",
+            MenuEntity::Ingredient => "ingredient",
+        }
+    }
+
+    /// `menu_cache` key holding this entity's list.
+    pub fn section(self) -> &'static str {
+        match self {
+            MenuEntity::Category => "categories",
+            MenuEntity::Subcategory => "subcategories",
+            MenuEntity::Ingredient => "ingredients",
+        }
+    }
+
+    /// Availability flag: categories are switched with `is_active`.
+    pub fn flag_field(self) -> &'static str {
+        match self {
+            MenuEntity::Category => "is_active",
+            MenuEntity::Subcategory | MenuEntity::Ingredient => "is_available",
+        }
+    }
+
+    /// `sync_queue` table name, as used by the single-item menu updates.
+    pub fn queue_table(self) -> &'static str {
+        match self {
+            MenuEntity::Category => "menu_categories",
+            MenuEntity::Subcategory => "menu_subcategories",
+            MenuEntity::Ingredient => "menu_ingredients",
+        }
+    }
+
+    pub fn remote_path(self, id: &str) -> String {
+        match self {
+            MenuEntity::Category => format!("/api/pos/sync/menu_categories/{id}"),
+            MenuEntity::Subcategory => format!("/api/pos/sync/subcategories/{id}"),
+            MenuEntity::Ingredient => format!("/api/pos/sync/ingredients/{id}"),
+        }
+    }
+
+    fn from_section(section: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|entity| entity.section() == section)
+    }
+}
+
+fn item_id(item: &Value) -> Option<String> {
+    value_str(item, &["id"])
+}
+
+fn read_section(conn: &Connection, section: &str) -> Result<Vec<Value>, String> {
+    let raw: Option<String> = conn
+        .query_row(
+            "SELECT data FROM menu_cache WHERE cache_key = ?1",
+            params![section],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("read menu_cache[{section}]: {e}"))?;
+    Ok(match raw.map(|raw| serde_json::from_str::<Value>(&raw)) {
+        Some(Ok(Value::Array(items))) => items,
+        _ => Vec::new(),
+    })
+}
+
+/// Ids a bulk operation applies to: the explicit `ids`, plus every
+/// subcategory or ingredient under `parent_category_id`. Duplicates are
+/// dropped, first occurrence wins.
+pub fn resolve_targets(
+    conn: &Connection,
+    entity: MenuEntity,
+    ids: &[String],
+    parent_category_id: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let mut targets: Vec<String> = Vec::new();
+    for id in ids.iter().map(|id| id.trim()).filter(|id| !id.is_empty()) {
+        if !targets.iter().any(|existing| existing == id) {
+            targets.push(id.to_string());
+        }
+    }
+    if let Some(parent) = parent_category_id
+        .map(str::trim)
+        .filter(|parent| !parent.is_empty())
+    {
+        if entity == MenuEntity::Category {
+            return Err(
+                "parentCategoryId applies to subcategories and ingredients, not categories"
+                    .to_string(),
+            );
+        }
+        for item in read_section(conn, entity.section())? {
+            if value_str(&item, &["category_id", "categoryId"]).as_deref() != Some(parent) {
+                continue;
+            }
+            if let Some(id) = item_id(&item) {
+                if !targets.contains(&id) {
+                    targets.push(id);
+                }
+            }
+        }
+    }
+    if targets.is_empty() {
+        return Err(format!("No {} ids to update", entity.as_str()));
+    }
+    Ok(targets)
+}
+
+/// An id and its updated cache entry, or why it was skipped.
+pub type LocalOutcome = (String, Result<Value, String>);
+
+/// Write the flag for `ids` into the cached menu in one pass and record an
+/// override per id under `operation_id`. Returns the updated item, or why it
+/// was skipped, per id in input order.
+pub fn apply_local(
+    conn: &Connection,
+    entity: MenuEntity,
+    ids: &[String],
+    is_available: bool,
+    operation_id: &str,
+    actor_staff_id: Option<&str>,
+) -> Result<Vec<LocalOutcome>, String> {
+    let section = entity.section();
+    let field = entity.flag_field();
+    let mut items = read_section(conn, section)?;
+    let now = Utc::now().to_rfc3339();
+
+    let mut outcomes = Vec::with_capacity(ids.len());
+    for id in ids {
+        let updated = items.iter_mut().find_map(|item| {
+            if item_id(item).as_deref() != Some(id.as_str()) {
+                return None;
+            }
+            let object = item.as_object_mut()?;
+            object.insert(field.to_string(), Value::Bool(is_available));
+            object.insert("updated_at".to_string(), Value::String(now.clone()));
+            object.insert("updatedAt".to_string(), Value::String(now.clone()));
+            Some(Value::Object(object.clone()))
+        });
+        outcomes.push((
+            id.clone(),
+            updated.ok_or_else(|| "Menu cache item not found locally".to_string()),
+        ));
+    }
+
+    if outcomes.iter().any(|(_, outcome)| outcome.is_ok()) {
+        let data =
+            serde_json::to_string(&items).map_err(|e| format!("serialize {section}: {e}"))?;
+        conn.execute(
+            "UPDATE menu_cache SET data = ?1, updated_at = datetime('now') WHERE cache_key = ?2",
+            params![data, section],
+        )
+        .map_err(|e| format!("update menu_cache[{section}]: {e}"))?;
+    }
+    for (id, _) in outcomes.iter().filter(|(_, outcome)| outcome.is_ok()) {
+        conn.execute(
+            "INSERT INTO menu_availability_overrides
+                (entity_type, entity_id, is_available, operation_id, remote_status,
+                 created_by, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, 'pending', ?5, ?6, ?6)
+             ON CONFLICT(entity_type, entity_id) DO UPDATE SET
+                is_available = excluded.is_available,
+                operation_id = excluded.operation_id,
+                remote_status = 'pending',
+                created_by = excluded.created_by,
+                updated_at = excluded.updated_at",
+            params![
+                entity.as_str(),
+                id,
+                is_available,
+                operation_id,
+                actor_staff_id,
+                now
+            ],
+        )
+        .map_err(|e| format!("record availability override: {e}"))?;
+    }
+    Ok(outcomes)
+}
+
+/// The admin has the override's value; stop re-applying it after syncs.
+pub fn mark_applied(conn: &Connection, entity: MenuEntity, id: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE menu_availability_overrides
+         SET remote_status = 'applied', updated_at = ?3
+         WHERE entity_type = ?1 AND entity_id = ?2",
+        params![entity.as_str(), id, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| format!("mark availability override applied: {e}"))?;
+    Ok(())
+}
+
+/// Lay pending overrides over a freshly synced `section` payload. Entries the
+/// admin already agrees with are marked applied. Returns how many items were
+/// changed.
+pub fn overlay_pending(
+    conn: &Connection,
+    section: &str,
+    section_data: &mut Value,
+) -> Result<usize, String> {
+    let Some(entity) = MenuEntity::from_section(section) else {
+        return Ok(0);
+    };
+    let Some(items) = section_data.as_array_mut() else {
+        return Ok(0);
+    };
+    let pending: Vec<(String, bool)> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT entity_id, is_available FROM menu_availability_overrides
+                 WHERE entity_type = ?1 AND remote_status = 'pending'",
+            )
+            .map_err(|e| format!("read availability overrides: {e}"))?;
+        let rows = stmt
+            .query_map(params![entity.as_str()], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?))
+            })
+            .map_err(|e| format!("read availability overrides: {e}"))?;
+        rows.collect::<rusqlite::Result<_>>()
+            .map_err(|e| format!("read availability overrides: {e}"))?
+    };
+
+    let field = entity.flag_field();
+    let mut changed = 0;
+    for (id, is_available) in pending {
+        let Some(object) = items
+            .iter_mut()
+            .find(|item| item_id(item).as_deref() == Some(id.as_str()))
+            .and_then(Value::as_object_mut)
+        else {
+            continue;
+        };
+        if object.get(field).and_then(Value::as_bool) == Some(is_available) {
+            mark_applied(conn, entity, &id)?;
+        } else {
+            object.insert(field.to_string(), Value::Bool(is_available));
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+/// Everything `operation_id` disabled that no later operation has touched.
+pub fn disabled_by_operation(
+    conn: &Connection,
+    operation_id: &str,
+) -> Result<Vec<(MenuEntity, String)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT entity_type, entity_id FROM menu_availability_overrides
+             WHERE operation_id = ?1 AND is_available = 0
+             ORDER BY entity_type, entity_id",
+        )
+        .map_err(|e| format!("read availability overrides: {e}"))?;
+    let rows = stmt
+        .query_map(params![operation_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| format!("read availability overrides: {e}"))?;
+    let mut targets = Vec::new();
+    for row in rows {
+        let (entity, id) = row.map_err(|e| format!("read availability overrides: {e}"))?;
+        if let Some(entity) = MenuEntity::parse(&entity) {
+            targets.push((entity, id));
+        }
+    }
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn.execute(
+            "INSERT INTO menu_cache (id, cache_key, data, version, updated_at)
+             VALUES ('m1', 'subcategories', ?1, 'v1', datetime('now'))",
+            params![json!([
+                { "id": "fries", "category_id": "fried", "is_available": true },
+                { "id": "wings", "category_id": "fried", "is_available": true },
+                { "id": "salad", "category_id": "cold", "is_available": true }
+            ])
+            .to_string()],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn cached_flag(conn: &Connection, id: &str) -> Option<bool> {
+        read_section(conn, "subcategories")
+            .unwrap()
+            .into_iter()
+            .find(|item| item_id(item).as_deref() == Some(id))
+            .and_then(|item| item.get("is_available").and_then(Value::as_bool))
+    }
+
+    #[test]
+    fn parent_category_expands_to_children_and_overrides_apply_locally() {
+        let conn = test_conn();
+        let targets = resolve_targets(
+            &conn,
+            MenuEntity::Subcategory,
+            &["missing".to_string()],
+            Some("fried"),
+        )
+        .unwrap();
+        assert_eq!(targets, vec!["missing", "fries", "wings"]);
+        assert!(resolve_targets(&conn, MenuEntity::Category, &[], Some("fried")).is_err());
+
+        let outcomes = apply_local(
+            &conn,
+            MenuEntity::Subcategory,
+            &targets,
+            false,
+            "op-1",
+            Some("s1"),
+        )
+        .unwrap();
+        assert!(outcomes[0].1.is_err());
+        assert!(outcomes[1].1.is_ok() && outcomes[2].1.is_ok());
+        assert_eq!(cached_flag(&conn, "fries"), Some(false));
+        assert_eq!(cached_flag(&conn, "salad"), Some(true));
+
+        let disabled = disabled_by_operation(&conn, "op-1").unwrap();
+        assert_eq!(
+            disabled,
+            vec![
+                (MenuEntity::Subcategory, "fries".to_string()),
+                (MenuEntity::Subcategory, "wings".to_string()),
+            ]
+        );
+
+        // A later operation takes over the override, so op-1 no longer owns it.
+        apply_local(
+            &conn,
+            MenuEntity::Subcategory,
+            &["wings".to_string()],
+            true,
+            "op-2",
+            None,
+        )
+        .unwrap();
+        assert_eq!(disabled_by_operation(&conn, "op-1").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn sync_payload_keeps_pending_overrides_until_admin_agrees() {
+        let conn = test_conn();
+        apply_local(
+            &conn,
+            MenuEntity::Subcategory,
+            &["fries".to_string(), "wings".to_string()],
+            false,
+            "op-1",
+            None,
+        )
+        .unwrap();
+
+        let mut synced = json!([
+            { "id": "fries", "is_available": true },
+            { "id": "wings", "is_available": false }
+        ]);
+        assert_eq!(
+            overlay_pending(&conn, "subcategories", &mut synced).unwrap(),
+            1
+        );
+        assert_eq!(synced[0]["is_available"], false);
+
+        let pending: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM menu_availability_overrides WHERE remote_status = 'pending'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(pending, 1, "wings matched the admin and is applied");
+        assert_eq!(overlay_pending(&conn, "combos", &mut synced).unwrap(), 0);
+    }
+}
@@ -0,0 +1,591 @@
+//! Offline cache for menu photos.
+//!
+//! After each menu sync, the `image_url` of every category, subcategory and
+//! combo is downloaded into `menu_images/` next to the database — at most
+//! [`MAX_CONCURRENT_DOWNLOADS`] at a time — and the cached menu JSON gains a
+//! `local_image_path` beside the original URL. When the menu itself changed,
+//! cached images are revalidated with `If-None-Match`; an image whose bytes
+//! hash the same is kept as is. Failed downloads (404s, timeouts) are counted
+//! and skipped, never failing the menu sync.
+//!
+//! `menu_image_cache` tracks each file's size and last use; once the
+//! directory exceeds [`MAX_CACHE_BYTES`] the least recently used images are
+//! evicted.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use base64::Engine;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tracing::{debug, info, warn};
+
+use crate::api;
+use crate::db::DbState;
+
+pub const IMAGE_DIR: &str = "menu_images";
+pub const LOCAL_IMAGE_PATH_KEY: &str = "local_image_path";
+
+/// Menu sections whose entries carry an image.
+const IMAGE_SECTIONS: &[&str] = &["categories", "subcategories", "combos"];
+const IMAGE_URL_KEYS: &[&str] = &["image_url", "imageUrl"];
+
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(20);
+/// Larger images are skipped rather than cached.
+const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+/// Total size of `menu_images/` before LRU eviction kicks in.
+pub const MAX_CACHE_BYTES: u64 = 200 * 1024 * 1024;
+/// `menu_get_image` inlines images up to this size as base64.
+const INLINE_IMAGE_BYTES: u64 = 64 * 1024;
+
+pub fn image_dir(db: &DbState) -> PathBuf {
+    db.db_path.with_file_name(IMAGE_DIR)
+}
+
+fn image_url(item: &Value) -> Option<String> {
+    crate::value_str(item, IMAGE_URL_KEYS).filter(|url| {
+        let lower = url.to_ascii_lowercase();
+        lower.starts_with("https://") || lower.starts_with("http://")
+    })
+}
+
+fn read_section(conn: &Connection, section: &str) -> Result<Vec<Value>, String> {
+    let raw: Option<String> = conn
+        .query_row(
+            "SELECT data FROM menu_cache WHERE cache_key = ?1",
+            params![section],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("read menu_cache[{section}]: {e}"))?;
+    Ok(match raw.map(|raw| serde_json::from_str::<Value>(&raw)) {
+        Some(Ok(Value::Array(items))) => items,
+        _ => Vec::new(),
+    })
+}
+
+fn collect_image_urls(conn: &Connection) -> Result<BTreeSet<String>, String> {
+    let mut urls = BTreeSet::new();
+    for section in IMAGE_SECTIONS {
+        urls.extend(read_section(conn, section)?.iter().filter_map(image_url));
+    }
+    Ok(urls)
+}
+
+#[derive(Debug, Clone)]
+struct CachedImage {
+    file_name: String,
+    content_type: Option<String>,
+    etag: Option<String>,
+    content_hash: String,
+    size_bytes: u64,
+}
+
+fn load_index(conn: &Connection) -> Result<HashMap<String, CachedImage>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT url, file_name, content_type, etag, content_hash, size_bytes
+             FROM menu_image_cache",
+        )
+        .map_err(|e| format!("prepare menu_image_cache: {e}"))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                CachedImage {
+                    file_name: row.get(1)?,
+                    content_type: row.get(2)?,
+                    etag: row.get(3)?,
+                    content_hash: row.get(4)?,
+                    size_bytes: row.get::<_, i64>(5)?.max(0) as u64,
+                },
+            ))
+        })
+        .map_err(|e| format!("query menu_image_cache: {e}"))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("read menu_image_cache: {e}"))
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn extension_for(content_type: Option<&str>) -> &'static str {
+    let essence = content_type
+        .unwrap_or_default()
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    match essence.as_str() {
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/png" => "png",
+        "image/webp" => "webp",
+        "image/gif" => "gif",
+        "image/svg+xml" => "svg",
+        "image/avif" => "avif",
+        _ => "img",
+    }
+}
+
+/// Stable file name for `url`: the same URL always maps to the same file.
+fn file_name_for(url: &str, content_type: Option<&str>) -> String {
+    format!(
+        "{}.{}",
+        &sha256_hex(url.as_bytes())[..32],
+        extension_for(content_type)
+    )
+}
+
+enum Fetched {
+    NotModified,
+    Body {
+        bytes: Vec<u8>,
+        etag: Option<String>,
+        content_type: Option<String>,
+    },
+}
+
+async fn fetch_image(url: &str, etag: Option<&str>) -> Result<Fetched, String> {
+    let client = api::shared_client()?;
+    let mut request = client.get(url).timeout(DOWNLOAD_TIMEOUT);
+    if let Some(etag) = etag {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(Fetched::NotModified);
+    }
+    if !status.is_success() {
+        return Err(format!("HTTP {status}"));
+    }
+    if response
+        .content_length()
+        .is_some_and(|len| len > MAX_IMAGE_BYTES as u64)
+    {
+        return Err("image too large".into());
+    }
+    let header = |name: reqwest::header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let etag = header(reqwest::header::ETAG);
+    let content_type = header(reqwest::header::CONTENT_TYPE);
+    let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err("image too large".into());
+    }
+    Ok(Fetched::Body {
+        bytes: bytes.to_vec(),
+        etag,
+        content_type,
+    })
+}
+
+/// Write a downloaded image and record it. Returns `false` when the bytes
+/// match the cached copy and the file was left untouched.
+fn store_image(
+    conn: &Connection,
+    dir: &Path,
+    url: &str,
+    previous: Option<&CachedImage>,
+    bytes: &[u8],
+    etag: Option<&str>,
+    content_type: Option<&str>,
+) -> Result<bool, String> {
+    let now = Utc::now().to_rfc3339();
+    let content_hash = sha256_hex(bytes);
+    if let Some(previous) = previous {
+        if previous.content_hash == content_hash && dir.join(&previous.file_name).exists() {
+            conn.execute(
+                "UPDATE menu_image_cache SET etag = ?1, fetched_at = ?2 WHERE url = ?3",
+                params![etag, now, url],
+            )
+            .map_err(|e| format!("update menu_image_cache: {e}"))?;
+            return Ok(false);
+        }
+    }
+
+    let file_name = file_name_for(url, content_type);
+    let path = dir.join(&file_name);
+    let partial = dir.join(format!("{file_name}.part"));
+    std::fs::write(&partial, bytes).map_err(|e| format!("write {}: {e}", partial.display()))?;
+    std::fs::rename(&partial, &path).map_err(|e| format!("rename {}: {e}", path.display()))?;
+    if let Some(previous) = previous.filter(|previous| previous.file_name != file_name) {
+        let _ = std::fs::remove_file(dir.join(&previous.file_name));
+    }
+    conn.execute(
+        "INSERT INTO menu_image_cache
+            (url, file_name, content_type, etag, content_hash, size_bytes,
+             fetched_at, last_accessed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+         ON CONFLICT(url) DO UPDATE SET
+            file_name = excluded.file_name,
+            content_type = excluded.content_type,
+            etag = excluded.etag,
+            content_hash = excluded.content_hash,
+            size_bytes = excluded.size_bytes,
+            fetched_at = excluded.fetched_at",
+        params![
+            url,
+            file_name,
+            content_type,
+            etag,
+            content_hash,
+            bytes.len() as i64,
+            now
+        ],
+    )
+    .map_err(|e| format!("upsert menu_image_cache: {e}"))?;
+    Ok(true)
+}
+
+/// Delete least recently used images until the cache fits in `cap` bytes.
+/// Returns the number of images evicted.
+fn evict_to_cap(conn: &Connection, dir: &Path, cap: u64) -> Result<usize, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT url, file_name, size_bytes FROM menu_image_cache
+             ORDER BY last_accessed_at ASC, url ASC",
+        )
+        .map_err(|e| format!("prepare eviction: {e}"))?;
+    let entries: Vec<(String, String, u64)> = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get::<_, i64>(2)?.max(0) as u64,
+            ))
+        })
+        .map_err(|e| format!("query eviction: {e}"))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("read eviction: {e}"))?;
+
+    let mut total: u64 = entries.iter().map(|entry| entry.2).sum();
+    let mut evicted = 0;
+    for (url, file_name, size) in entries {
+        if total <= cap {
+            break;
+        }
+        let _ = std::fs::remove_file(dir.join(&file_name));
+        conn.execute("DELETE FROM menu_image_cache WHERE url = ?1", params![url])
+            .map_err(|e| format!("evict menu image: {e}"))?;
+        total = total.saturating_sub(size);
+        evicted += 1;
+    }
+    Ok(evicted)
+}
+
+/// Set or clear `local_image_path` on every cached menu entry so it matches
+/// the image files currently on disk.
+fn annotate_menu_cache(conn: &Connection, dir: &Path) -> Result<(), String> {
+    let index = load_index(conn)?;
+    for section in IMAGE_SECTIONS {
+        let mut items = read_section(conn, section)?;
+        let mut changed = false;
+        for item in items.iter_mut() {
+            let local_path = image_url(item)
+                .and_then(|url| index.get(&url))
+                .map(|cached| dir.join(&cached.file_name))
+                .filter(|path| path.exists())
+                .map(|path| path.to_string_lossy().into_owned());
+            let Some(obj) = item.as_object_mut() else {
+                continue;
+            };
+            let current = obj.get(LOCAL_IMAGE_PATH_KEY).and_then(Value::as_str);
+            if current == local_path.as_deref() {
+                continue;
+            }
+            match local_path {
+                Some(path) => obj.insert(LOCAL_IMAGE_PATH_KEY.into(), json!(path)),
+                None => obj.remove(LOCAL_IMAGE_PATH_KEY),
+            };
+            changed = true;
+        }
+        if changed {
+            let data =
+                serde_json::to_string(&items).map_err(|e| format!("serialize {section}: {e}"))?;
+            conn.execute(
+                "UPDATE menu_cache SET data = ?1 WHERE cache_key = ?2",
+                params![data, section],
+            )
+            .map_err(|e| format!("update menu_cache[{section}]: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Download images referenced by the cached menu. With `revalidate`, images
+/// already cached are re-requested conditionally; otherwise only missing
+/// ones are fetched. Returns a summary for the menu sync result.
+pub async fn sync_images(db: &DbState, revalidate: bool) -> Result<Value, String> {
+    let dir = image_dir(db);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+
+    let (urls, index) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        (collect_image_urls(&conn)?, load_index(&conn)?)
+    };
+    let pending: Vec<(String, Option<CachedImage>)> = urls
+        .into_iter()
+        .filter_map(|url| {
+            let cached = index.get(&url).cloned();
+            let present = cached
+                .as_ref()
+                .is_some_and(|cached| dir.join(&cached.file_name).exists());
+            (revalidate || !present).then_some((url, cached.filter(|_| present)))
+        })
+        .collect();
+
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_DOWNLOADS));
+    let mut set = tokio::task::JoinSet::new();
+    for (url, cached) in pending {
+        let semaphore = semaphore.clone();
+        set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let etag = cached.as_ref().and_then(|cached| cached.etag.clone());
+            let fetched = fetch_image(&url, etag.as_deref()).await;
+            (url, cached, fetched)
+        });
+    }
+    let mut results = Vec::new();
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok(result) => results.push(result),
+            Err(e) => warn!("menu image download task failed: {e}"),
+        }
+    }
+
+    let (mut downloaded, mut unchanged, mut failed) = (0usize, 0usize, 0usize);
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    for (url, cached, fetched) in results {
+        let stored = match fetched {
+            Ok(Fetched::NotModified) => Ok(false),
+            Ok(Fetched::Body {
+                bytes,
+                etag,
+                content_type,
+            }) => store_image(
+                &conn,
+                &dir,
+                &url,
+                cached.as_ref(),
+                &bytes,
+                etag.as_deref(),
+                content_type.as_deref(),
+            ),
+            Err(e) => Err(e),
+        };
+        match stored {
+            Ok(true) => downloaded += 1,
+            Ok(false) => unchanged += 1,
+            Err(error) => {
+                failed += 1;
+                debug!(url = %url, error = %error, "menu image download skipped");
+            }
+        }
+    }
+    let evicted = evict_to_cap(&conn, &dir, MAX_CACHE_BYTES)?;
+    annotate_menu_cache(&conn, &dir)?;
+
+    if downloaded > 0 || failed > 0 || evicted > 0 {
+        info!(
+            downloaded,
+            unchanged, failed, evicted, "menu images: cache refreshed"
+        );
+    }
+    Ok(json!({
+        "downloaded": downloaded,
+        "unchanged": unchanged,
+        "failed": failed,
+        "evicted": evicted,
+    }))
+}
+
+fn find_image_url(conn: &Connection, item_id: &str) -> Result<Option<String>, String> {
+    for section in IMAGE_SECTIONS {
+        let found = read_section(conn, section)?
+            .into_iter()
+            .find(|item| item.get("id").and_then(Value::as_str) == Some(item_id));
+        if let Some(item) = found {
+            return Ok(image_url(&item));
+        }
+    }
+    Ok(None)
+}
+
+/// Cached image for the menu entry `item_id`. Small images are returned
+/// inline as base64 as well as by path. Marks the image as recently used.
+pub fn get_image(db: &DbState, item_id: &str) -> Result<Value, String> {
+    let dir = image_dir(db);
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let Some(url) = find_image_url(&conn, item_id)? else {
+        return Ok(json!({ "id": item_id, "cached": false, "url": null, "path": null }));
+    };
+    let cached = load_index(&conn)?
+        .remove(&url)
+        .filter(|cached| dir.join(&cached.file_name).exists());
+    let Some(cached) = cached else {
+        return Ok(json!({ "id": item_id, "cached": false, "url": url, "path": null }));
+    };
+
+    conn.execute(
+        "UPDATE menu_image_cache SET last_accessed_at = ?1 WHERE url = ?2",
+        params![Utc::now().to_rfc3339(), url],
+    )
+    .map_err(|e| format!("touch menu image: {e}"))?;
+
+    let path = dir.join(&cached.file_name);
+    let data = if cached.size_bytes <= INLINE_IMAGE_BYTES {
+        std::fs::read(&path)
+            .ok()
+            .map(|bytes| base64::engine::general_purpose::STANDARD.encode(bytes))
+    } else {
+        None
+    };
+    Ok(json!({
+        "id": item_id,
+        "cached": true,
+        "url": url,
+        "path": path.to_string_lossy(),
+        "contentType": cached.content_type,
+        "sizeBytes": cached.size_bytes,
+        "data": data,
+    }))
+}
+
+/// Delete every cached image and drop `local_image_path` from the menu.
+pub fn clear_cache(db: &DbState) -> Result<Value, String> {
+    let dir = image_dir(db);
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let index = load_index(&conn)?;
+    let mut freed_bytes = 0u64;
+    for cached in index.values() {
+        if std::fs::remove_file(dir.join(&cached.file_name)).is_ok() {
+            freed_bytes += cached.size_bytes;
+        }
+    }
+    conn.execute("DELETE FROM menu_image_cache", [])
+        .map_err(|e| format!("clear menu_image_cache: {e}"))?;
+    annotate_menu_cache(&conn, &dir)?;
+    info!(
+        removed = index.len(),
+        freed_bytes, "menu images: cache cleared"
+    );
+    Ok(json!({ "removed": index.len(), "freedBytes": freed_bytes }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn temp_db() -> (PathBuf, DbState) {
+        let dir = std::env::temp_dir().join(format!("pos-menu-images-{}", uuid::Uuid::new_v4()));
+        let db = db::init(&dir).expect("init db");
+        std::fs::create_dir_all(image_dir(&db)).unwrap();
+        (dir, db)
+    }
+
+    fn seed_menu(conn: &Connection) {
+        conn.execute_batch(
+            r#"INSERT INTO menu_cache (id, cache_key, data, version, updated_at)
+               VALUES ('m1', 'subcategories',
+                       '[{"id":"s1","image_url":"https://cdn.example/crepe.png"},
+                         {"id":"s2","image_url":"https://cdn.example/waffle.png"},
+                         {"id":"s3","image_url":null}]',
+                       'v1', datetime('now'));"#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn stored_images_are_annotated_and_evicted_least_recent_first() {
+        let (root, db) = temp_db();
+        let dir = image_dir(&db);
+        let conn = db.conn.lock().unwrap();
+        seed_menu(&conn);
+        assert_eq!(collect_image_urls(&conn).unwrap().len(), 2);
+
+        let crepe = "https://cdn.example/crepe.png";
+        let waffle = "https://cdn.example/waffle.png";
+        assert!(store_image(&conn, &dir, crepe, None, b"crepe", None, Some("image/png")).unwrap());
+        assert!(store_image(
+            &conn,
+            &dir,
+            waffle,
+            None,
+            b"waffle",
+            None,
+            Some("image/png")
+        )
+        .unwrap());
+        let cached = load_index(&conn).unwrap().remove(crepe).unwrap();
+        assert!(!store_image(&conn, &dir, crepe, Some(&cached), b"crepe", None, None).unwrap());
+
+        annotate_menu_cache(&conn, &dir).unwrap();
+        let items = read_section(&conn, "subcategories").unwrap();
+        let local = items[0][LOCAL_IMAGE_PATH_KEY].as_str().unwrap();
+        assert!(local.ends_with(".png"));
+        assert_eq!(items[0]["image_url"], crepe);
+        assert!(items[2].get(LOCAL_IMAGE_PATH_KEY).is_none());
+
+        conn.execute(
+            "UPDATE menu_image_cache SET last_accessed_at = '2000-01-01T00:00:00Z' WHERE url = ?1",
+            params![waffle],
+        )
+        .unwrap();
+        assert_eq!(evict_to_cap(&conn, &dir, 5).unwrap(), 1);
+        let index = load_index(&conn).unwrap();
+        assert!(index.contains_key(crepe));
+        assert!(!index.contains_key(waffle));
+        annotate_menu_cache(&conn, &dir).unwrap();
+        let items = read_section(&conn, "subcategories").unwrap();
+        assert!(items[1].get(LOCAL_IMAGE_PATH_KEY).is_none());
+        drop(conn);
+
+        let image = get_image(&db, "s1").unwrap();
+        assert_eq!(image["cached"], true);
+        assert_eq!(
+            image["data"],
+            base64::engine::general_purpose::STANDARD.encode(b"crepe")
+        );
+        assert_eq!(get_image(&db, "s2").unwrap()["cached"], false);
+
+        let cleared = clear_cache(&db).unwrap();
+        assert_eq!(cleared["removed"], 1);
+        assert_eq!(get_image(&db, "s1").unwrap()["cached"], false);
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn file_names_are_stable_per_url() {
+        let a = file_name_for(
+            "https://cdn.example/a.jpg",
+            Some("image/jpeg; charset=binary"),
+        );
+        assert_eq!(
+            a,
+            file_name_for("https://cdn.example/a.jpg", Some("image/jpeg"))
+        );
+        assert!(a.ends_with(".jpg"));
+        assert_ne!(
+            a,
+            file_name_for("https://cdn.example/b.jpg", Some("image/jpeg"))
+        );
+        assert!(file_name_for("https://cdn.example/c", None).ends_with(".img"));
+    }
+}
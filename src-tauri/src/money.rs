@@ -10,6 +10,9 @@
 //! file `D:\The-Small-002\planning\claude\create-a-plan-to-rustling-pretzel.md`
 //! (Wave 4).
 
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::Value;
 use std::iter::Sum;
 use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
 
@@ -64,6 +67,14 @@ impl Cents {
         Self((major * 100.0).round() as i64)
     }
 
+    /// Convert a major-unit float to cents with an explicit [`RoundingRule`].
+    pub fn round_with(major: f64, rule: RoundingRule) -> Self {
+        match rule {
+            RoundingRule::HalfUp => Self::round_half_up(major),
+            RoundingRule::HalfEven => Self::round_half_even(major),
+        }
+    }
+
     /// Convert back to a major-unit float at 2 decimal places.
     ///
     /// Loss-free for all values representable within ±2⁵³ cents. Use
@@ -194,6 +205,220 @@ pub fn serialize_cents_as_f64_dp2<S: serde::Serializer>(
     ser.serialize_f64(value.to_f64_dp2())
 }
 
+/// Rounding applied when an order line is converted to cents.
+///
+/// Lines are rounded once, then summed as integers, so the receipt, the
+/// Z-report and the synced totals all add up the same cents. Configured via
+/// `general.money_rounding` (`half_up` | `half_even`); half-up is the
+/// default because it matches what receipt printers show.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum RoundingRule {
+    #[default]
+    HalfUp,
+    HalfEven,
+}
+
+impl RoundingRule {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "half_up" => Some(Self::HalfUp),
+            "half_even" | "bankers" => Some(Self::HalfEven),
+            _ => None,
+        }
+    }
+
+    pub fn from_settings(conn: &Connection) -> Self {
+        crate::db::get_setting(conn, "general", "money_rounding")
+            .and_then(|raw| Self::parse(&raw))
+            .unwrap_or_default()
+    }
+}
+
+fn item_number(item: &Value, keys: &[&str]) -> Option<f64> {
+    keys.iter()
+        .find_map(|key| item.get(*key).and_then(Value::as_f64))
+        .filter(|value| value.is_finite())
+}
+
+/// Line total of a JSON order item in cents: `total_price` when present,
+/// otherwise `unit_price × quantity`. This is the single place item lines
+/// leave `f64`.
+pub fn item_line_cents(item: &Value, rule: RoundingRule) -> Cents {
+    if let Some(total) = item_number(item, &["total_price", "totalPrice"]) {
+        return Cents::round_with(total, rule);
+    }
+    let quantity = item_number(item, &["quantity"]).unwrap_or(1.0).max(0.0);
+    let unit_price = item_number(item, &["unit_price", "unitPrice", "price"]).unwrap_or(0.0);
+    Cents::round_with(unit_price * quantity, rule)
+}
+
+/// Sum of [`item_line_cents`] over an item array.
+pub fn items_total_cents(items: &[Value], rule: RoundingRule) -> Cents {
+    items.iter().map(|item| item_line_cents(item, rule)).sum()
+}
+
+/// Tenant currency, stored in `general.currency` either as a bare ISO code
+/// (`"EUR"`) or as `{"code": "EUR", "symbol": "€", "decimalPlaces": 2}`.
+///
+/// Amounts are still held in hundredths (see [`Cents`]); `decimal_places`
+/// is a display hint for the renderer and is capped at 2.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrencySettings {
+    pub code: String,
+    pub symbol: String,
+    pub decimal_places: u8,
+}
+
+impl Default for CurrencySettings {
+    fn default() -> Self {
+        Self {
+            code: "EUR".to_string(),
+            symbol: "\u{20AC}".to_string(),
+            decimal_places: 2,
+        }
+    }
+}
+
+fn symbol_for_code(code: &str) -> String {
+    match code {
+        "EUR" => "\u{20AC}".to_string(),
+        "USD" => "$".to_string(),
+        "GBP" => "\u{00A3}".to_string(),
+        other => other.to_string(),
+    }
+}
+
+impl CurrencySettings {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return None;
+        }
+        let Ok(Value::Object(obj)) = serde_json::from_str::<Value>(raw) else {
+            let code = raw.trim_matches('"').trim().to_ascii_uppercase();
+            return (!code.is_empty()).then(|| Self {
+                symbol: symbol_for_code(&code),
+                code,
+                ..Self::default()
+            });
+        };
+        let text = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| obj.get(*key).and_then(Value::as_str))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let code = text(&["code"])
+            .map(|code| code.to_ascii_uppercase())
+            .unwrap_or_else(|| Self::default().code);
+        let symbol = text(&["symbol"]).unwrap_or_else(|| symbol_for_code(&code));
+        let decimal_places = ["decimalPlaces", "decimal_places"]
+            .iter()
+            .find_map(|key| obj.get(*key).and_then(Value::as_u64))
+            .unwrap_or(2)
+            .min(2) as u8;
+        Some(Self {
+            code,
+            symbol,
+            decimal_places,
+        })
+    }
+
+    pub fn from_settings(conn: &Connection) -> Self {
+        crate::db::get_setting(conn, "general", "currency")
+            .and_then(|raw| Self::parse(&raw))
+            .unwrap_or_default()
+    }
+}
+
+/// Which way [`CashRounding`] moves an amount that is not a whole number of
+/// increments.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CashRoundingDirection {
+    /// Closest increment; exact midpoints round up.
+    #[default]
+    Nearest,
+    Up,
+    Down,
+}
+
+impl CashRoundingDirection {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "nearest" | "half_up" => Some(Self::Nearest),
+            "up" => Some(Self::Up),
+            "down" => Some(Self::Down),
+            _ => None,
+        }
+    }
+}
+
+/// Rounding of cash tenders to the smallest coin in circulation. Card and
+/// other tenders always charge the exact amount.
+///
+/// Stored in `payments.cash_rounding` either as a bare increment (`"0.05"`,
+/// rounding to nearest) or as `{"increment": 0.05, "direction": "nearest"}`
+/// with `direction` one of `nearest` | `up` | `down`. Unset, or an increment
+/// of one cent or less, means no rounding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CashRounding {
+    pub increment: Cents,
+    pub direction: CashRoundingDirection,
+}
+
+impl CashRounding {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        let (increment, direction) = match serde_json::from_str::<Value>(raw) {
+            Ok(Value::Object(obj)) => {
+                if obj.get("enabled").and_then(Value::as_bool) == Some(false) {
+                    return None;
+                }
+                let direction = match obj.get("direction").and_then(Value::as_str) {
+                    Some(direction) => CashRoundingDirection::parse(direction)?,
+                    None => CashRoundingDirection::default(),
+                };
+                (obj.get("increment")?.as_f64()?, direction)
+            }
+            Ok(Value::Number(number)) => (number.as_f64()?, CashRoundingDirection::default()),
+            _ => (
+                raw.trim_matches('"').trim().parse::<f64>().ok()?,
+                CashRoundingDirection::default(),
+            ),
+        };
+        let increment = Cents::round_half_even(increment);
+        (increment.as_i64() > 1).then_some(Self {
+            increment,
+            direction,
+        })
+    }
+
+    pub fn from_settings(conn: &Connection) -> Option<Self> {
+        crate::db::get_setting(conn, "payments", "cash_rounding").and_then(|raw| Self::parse(&raw))
+    }
+
+    /// The amount actually collected in cash for an exact `amount`.
+    pub fn round(&self, amount: Cents) -> Cents {
+        let increment = self.increment.as_i64();
+        let remainder = amount.as_i64().rem_euclid(increment);
+        if remainder == 0 {
+            return amount;
+        }
+        let down = amount.as_i64() - remainder;
+        let rounded = match self.direction {
+            CashRoundingDirection::Down => down,
+            CashRoundingDirection::Up => down + increment,
+            CashRoundingDirection::Nearest if remainder * 2 >= increment => down + increment,
+            CashRoundingDirection::Nearest => down,
+        };
+        Cents::new(rounded)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -290,4 +515,142 @@ mod tests {
         assert!(Cents::new(-10) < Cents::new(10));
         assert_eq!(Cents::new(42), Cents::new(42));
     }
+
+    #[test]
+    fn rounding_rule_parses_setting_values() {
+        assert_eq!(RoundingRule::parse("half_up"), Some(RoundingRule::HalfUp));
+        assert_eq!(
+            RoundingRule::parse(" Half-Even "),
+            Some(RoundingRule::HalfEven)
+        );
+        assert_eq!(RoundingRule::parse("bankers"), Some(RoundingRule::HalfEven));
+        assert_eq!(RoundingRule::parse("nearest"), None);
+        assert_eq!(
+            Cents::round_with(0.125, RoundingRule::HalfUp),
+            Cents::new(13)
+        );
+        assert_eq!(
+            Cents::round_with(0.125, RoundingRule::HalfEven),
+            Cents::new(12)
+        );
+    }
+
+    #[test]
+    fn item_line_prefers_total_price_and_rounds_once() {
+        let rule = RoundingRule::HalfUp;
+        let explicit = serde_json::json!({ "total_price": 7.5, "unit_price": 1.0, "quantity": 3 });
+        assert_eq!(item_line_cents(&explicit, rule), Cents::new(750));
+        let derived = serde_json::json!({ "unitPrice": 0.1, "quantity": 3 });
+        assert_eq!(item_line_cents(&derived, rule), Cents::new(30));
+        let bare = serde_json::json!({ "price": 2.35 });
+        assert_eq!(item_line_cents(&bare, rule), Cents::new(235));
+    }
+
+    /// Deterministic xorshift so the property test needs no extra crates.
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    fn summing_random_lines_matches_integer_total() {
+        let mut state = 0x9E37_79B9_7F4A_7C15_u64;
+        for _ in 0..20 {
+            let mut expected = 0_i64;
+            let items: Vec<Value> = (0..1000)
+                .map(|_| {
+                    let unit_cents = (next_random(&mut state) % 5000) as i64;
+                    let quantity = (next_random(&mut state) % 5 + 1) as i64;
+                    expected += unit_cents * quantity;
+                    serde_json::json!({
+                        "unit_price": unit_cents as f64 / 100.0,
+                        "quantity": quantity,
+                    })
+                })
+                .collect();
+            for rule in [RoundingRule::HalfUp, RoundingRule::HalfEven] {
+                assert_eq!(items_total_cents(&items, rule), Cents::new(expected));
+            }
+        }
+    }
+
+    #[test]
+    fn cash_rounding_to_nearest_five_cents() {
+        let rounding = CashRounding::parse("0.05").unwrap();
+        let cases = [
+            (1001, 1000),
+            (1002, 1000),
+            (1003, 1005),
+            (1004, 1005),
+            (1005, 1005),
+            (1006, 1005),
+            (1007, 1005),
+            (1008, 1010),
+            (1009, 1010),
+            (1010, 1010),
+        ];
+        for (exact, expected) in cases {
+            assert_eq!(
+                rounding.round(Cents::new(exact)),
+                Cents::new(expected),
+                "{exact}"
+            );
+        }
+    }
+
+    #[test]
+    fn cash_rounding_up_and_down() {
+        let up = CashRounding::parse(r#"{"increment":0.05,"direction":"up"}"#).unwrap();
+        let down = CashRounding::parse(r#"{"increment":0.05,"direction":"down"}"#).unwrap();
+        for cents in [1001, 1002, 1003, 1004] {
+            assert_eq!(up.round(Cents::new(cents)), Cents::new(1005));
+            assert_eq!(down.round(Cents::new(cents)), Cents::new(1000));
+        }
+        for cents in [1006, 1007, 1008, 1009] {
+            assert_eq!(up.round(Cents::new(cents)), Cents::new(1010));
+            assert_eq!(down.round(Cents::new(cents)), Cents::new(1005));
+        }
+        assert_eq!(up.round(Cents::new(1010)), Cents::new(1010));
+    }
+
+    #[test]
+    fn cash_rounding_setting_values() {
+        assert_eq!(CashRounding::parse(""), None);
+        assert_eq!(CashRounding::parse("0.01"), None);
+        assert_eq!(
+            CashRounding::parse(r#"{"increment":0.05,"enabled":false}"#),
+            None
+        );
+        assert_eq!(
+            CashRounding::parse(r#"{"increment":0.05,"direction":"sideways"}"#),
+            None
+        );
+        let tens = CashRounding::parse(r#"{"increment":0.1}"#).unwrap();
+        assert_eq!(tens.direction, CashRoundingDirection::Nearest);
+        assert_eq!(tens.round(Cents::new(1005)), Cents::new(1010));
+        assert_eq!(tens.round(Cents::new(1004)), Cents::new(1000));
+    }
+
+    #[test]
+    fn currency_settings_parse_code_and_object() {
+        assert_eq!(CurrencySettings::parse(""), None);
+        let usd = CurrencySettings::parse("usd").unwrap();
+        assert_eq!(usd.code, "USD");
+        assert_eq!(usd.symbol, "$");
+        assert_eq!(usd.decimal_places, 2);
+
+        let custom =
+            CurrencySettings::parse(r#"{"code":"huf","symbol":"Ft","decimalPlaces":0}"#).unwrap();
+        assert_eq!(custom.code, "HUF");
+        assert_eq!(custom.symbol, "Ft");
+        assert_eq!(custom.decimal_places, 0);
+        assert_eq!(
+            CurrencySettings::parse(r#"{"code":"EUR","decimal_places":4}"#)
+                .unwrap()
+                .decimal_places,
+            2
+        );
+    }
 }
@@ -0,0 +1,373 @@
+//! OS notifications for events staff should see even when the POS window is
+//! in the background: new remote orders, permanent sync failures, print jobs
+//! that gave up, and order SLA breaches.
+//!
+//! Every emit site goes through [`send`], which applies the settings stored
+//! under the `notifications` category — a master switch, sound, one toggle per
+//! event kind and an optional do-not-disturb window — before handing the
+//! notification to the OS. When the OS refuses (permission denied, no
+//! notification daemon), the notification is emitted to the webview as a
+//! `notification_fallback` event instead so it can be shown in-app.
+
+use chrono::{Local, NaiveTime};
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter};
+use tracing::{debug, warn};
+
+use crate::db::{self, DbState};
+
+const SETTINGS_CATEGORY: &str = "notifications";
+const ENABLED_KEY: &str = "enabled";
+const SOUND_KEY: &str = "sound";
+const DND_ENABLED_KEY: &str = "dnd_enabled";
+const DND_START_KEY: &str = "dnd_start";
+const DND_END_KEY: &str = "dnd_end";
+
+const DEFAULT_DND_START: &str = "22:00";
+const DEFAULT_DND_END: &str = "07:00";
+
+pub const FALLBACK_EVENT: &str = "notification_fallback";
+pub const APP_TITLE: &str = "The Small POS";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    NewRemoteOrder,
+    SyncFailure,
+    PrinterOffline,
+    SlaBreach,
+    /// `show_notification` from the renderer; only the master switch and
+    /// do-not-disturb apply.
+    General,
+    /// `notifications_test`; always delivered.
+    Test,
+}
+
+impl NotificationKind {
+    /// Kinds with their own toggle, in settings order.
+    const TOGGLED: [Self; 4] = [
+        Self::NewRemoteOrder,
+        Self::SyncFailure,
+        Self::PrinterOffline,
+        Self::SlaBreach,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::NewRemoteOrder => "new_remote_order",
+            Self::SyncFailure => "sync_failure",
+            Self::PrinterOffline => "printer_offline",
+            Self::SlaBreach => "sla_breach",
+            Self::General => "general",
+            Self::Test => "test",
+        }
+    }
+
+    /// Key in the `events` object of the settings payload.
+    fn json_key(self) -> &'static str {
+        match self {
+            Self::NewRemoteOrder => "newRemoteOrder",
+            Self::SyncFailure => "syncFailure",
+            Self::PrinterOffline => "printerOffline",
+            Self::SlaBreach => "slaBreach",
+            Self::General => "general",
+            Self::Test => "test",
+        }
+    }
+
+    fn setting_key(self) -> String {
+        format!("event_{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    pub sound: bool,
+    pub new_remote_order: bool,
+    pub sync_failure: bool,
+    pub printer_offline: bool,
+    pub sla_breach: bool,
+    pub dnd_enabled: bool,
+    pub dnd_start: NaiveTime,
+    pub dnd_end: NaiveTime,
+}
+
+fn parse_flag(raw: &str) -> Option<bool> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_hhmm(raw: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(raw.trim(), "%H:%M").ok()
+}
+
+fn default_time(raw: &str) -> NaiveTime {
+    parse_hhmm(raw).expect("default do-not-disturb time is valid")
+}
+
+impl NotificationSettings {
+    pub fn load(conn: &Connection) -> Self {
+        let flag = |key: &str, default: bool| {
+            db::get_setting(conn, SETTINGS_CATEGORY, key)
+                .and_then(|raw| parse_flag(&raw))
+                .unwrap_or(default)
+        };
+        let time = |key: &str, default: &str| {
+            db::get_setting(conn, SETTINGS_CATEGORY, key)
+                .and_then(|raw| parse_hhmm(&raw))
+                .unwrap_or_else(|| default_time(default))
+        };
+        let event = |kind: NotificationKind| flag(&kind.setting_key(), true);
+        Self {
+            enabled: flag(ENABLED_KEY, true),
+            sound: flag(SOUND_KEY, true),
+            new_remote_order: event(NotificationKind::NewRemoteOrder),
+            sync_failure: event(NotificationKind::SyncFailure),
+            printer_offline: event(NotificationKind::PrinterOffline),
+            sla_breach: event(NotificationKind::SlaBreach),
+            dnd_enabled: flag(DND_ENABLED_KEY, false),
+            dnd_start: time(DND_START_KEY, DEFAULT_DND_START),
+            dnd_end: time(DND_END_KEY, DEFAULT_DND_END),
+        }
+    }
+
+    fn event_enabled(&self, kind: NotificationKind) -> bool {
+        match kind {
+            NotificationKind::NewRemoteOrder => self.new_remote_order,
+            NotificationKind::SyncFailure => self.sync_failure,
+            NotificationKind::PrinterOffline => self.printer_offline,
+            NotificationKind::SlaBreach => self.sla_breach,
+            NotificationKind::General | NotificationKind::Test => true,
+        }
+    }
+
+    /// Whether `now` falls inside the do-not-disturb window. A window whose
+    /// end is before its start runs over midnight; equal bounds mean empty.
+    pub fn in_quiet_hours(&self, now: NaiveTime) -> bool {
+        if !self.dnd_enabled || self.dnd_start == self.dnd_end {
+            return false;
+        }
+        if self.dnd_start < self.dnd_end {
+            now >= self.dnd_start && now < self.dnd_end
+        } else {
+            now >= self.dnd_start || now < self.dnd_end
+        }
+    }
+
+    /// Reason a notification of `kind` is not delivered at `now`, if any.
+    fn suppression(&self, kind: NotificationKind, now: NaiveTime) -> Option<&'static str> {
+        if kind == NotificationKind::Test {
+            None
+        } else if !self.enabled {
+            Some("disabled")
+        } else if !self.event_enabled(kind) {
+            Some("event_disabled")
+        } else if self.in_quiet_hours(now) {
+            Some("do_not_disturb")
+        } else {
+            None
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let events: serde_json::Map<String, Value> = NotificationKind::TOGGLED
+            .iter()
+            .map(|kind| {
+                (
+                    kind.json_key().to_string(),
+                    json!(self.event_enabled(*kind)),
+                )
+            })
+            .collect();
+        json!({
+            "enabled": self.enabled,
+            "sound": self.sound,
+            "events": events,
+            "doNotDisturb": {
+                "enabled": self.dnd_enabled,
+                "start": self.dnd_start.format("%H:%M").to_string(),
+                "end": self.dnd_end.format("%H:%M").to_string(),
+            },
+        })
+    }
+}
+
+/// Persist the fields present in `payload` (same shape as
+/// [`NotificationSettings::to_json`]) and return the resulting settings.
+pub fn update_settings(conn: &Connection, payload: &Value) -> Result<NotificationSettings, String> {
+    let save_flag = |key: &str, value: Option<&Value>, field: &str| -> Result<(), String> {
+        match value {
+            None | Some(Value::Null) => Ok(()),
+            Some(Value::Bool(flag)) => db::set_setting(
+                conn,
+                SETTINGS_CATEGORY,
+                key,
+                if *flag { "true" } else { "false" },
+            ),
+            Some(_) => Err(format!("{field} must be a boolean")),
+        }
+    };
+    let save_time = |key: &str, value: Option<&Value>, field: &str| -> Result<(), String> {
+        match value {
+            None | Some(Value::Null) => Ok(()),
+            Some(Value::String(raw)) => {
+                let time = parse_hhmm(raw).ok_or_else(|| format!("{field} must be HH:MM"))?;
+                db::set_setting(
+                    conn,
+                    SETTINGS_CATEGORY,
+                    key,
+                    &time.format("%H:%M").to_string(),
+                )
+            }
+            Some(_) => Err(format!("{field} must be HH:MM")),
+        }
+    };
+
+    save_flag(ENABLED_KEY, payload.get("enabled"), "enabled")?;
+    save_flag(SOUND_KEY, payload.get("sound"), "sound")?;
+    if let Some(events) = payload.get("events") {
+        let events = events.as_object().ok_or("events must be an object")?;
+        for kind in NotificationKind::TOGGLED {
+            save_flag(
+                &kind.setting_key(),
+                events.get(kind.json_key()),
+                &format!("events.{}", kind.json_key()),
+            )?;
+        }
+    }
+    if let Some(dnd) = payload.get("doNotDisturb") {
+        let dnd = dnd.as_object().ok_or("doNotDisturb must be an object")?;
+        save_flag(DND_ENABLED_KEY, dnd.get("enabled"), "doNotDisturb.enabled")?;
+        save_time(DND_START_KEY, dnd.get("start"), "doNotDisturb.start")?;
+        save_time(DND_END_KEY, dnd.get("end"), "doNotDisturb.end")?;
+    }
+    Ok(NotificationSettings::load(conn))
+}
+
+fn show_os_notification(
+    app: &AppHandle,
+    title: &str,
+    body: &str,
+    sound: bool,
+) -> Result<(), String> {
+    use tauri_plugin_notification::{NotificationExt, PermissionState};
+
+    let notification = app.notification();
+    if notification.permission_state().map_err(|e| e.to_string())? == PermissionState::Denied {
+        return Err("OS notifications are not permitted".into());
+    }
+    let mut builder = notification.builder().title(title).body(body);
+    if sound {
+        builder = builder.sound("default");
+    }
+    builder.show().map_err(|e| e.to_string())
+}
+
+/// Deliver a notification of `kind`, honouring the stored settings. Returns
+/// `{ delivered: "os" | "event" | "suppressed", reason? }`.
+pub fn send(
+    app: &AppHandle,
+    db: &DbState,
+    kind: NotificationKind,
+    title: &str,
+    body: &str,
+) -> Value {
+    let settings = match db.conn.lock() {
+        Ok(conn) => NotificationSettings::load(&conn),
+        Err(e) => {
+            warn!(error = %e, "Notification settings unavailable, skipping notification");
+            return json!({ "delivered": "suppressed", "reason": "settings_unavailable" });
+        }
+    };
+    if let Some(reason) = settings.suppression(kind, Local::now().time()) {
+        debug!(kind = kind.as_str(), reason, "Notification suppressed");
+        return json!({ "delivered": "suppressed", "reason": reason });
+    }
+
+    match show_os_notification(app, title, body, settings.sound) {
+        Ok(()) => json!({ "delivered": "os" }),
+        Err(error) => {
+            debug!(kind = kind.as_str(), error = %error, "OS notification unavailable, emitting fallback event");
+            let _ = app.emit(
+                FALLBACK_EVENT,
+                json!({
+                    "kind": kind.as_str(),
+                    "title": title,
+                    "body": body,
+                    "sound": settings.sound,
+                }),
+            );
+            json!({ "delivered": "event", "reason": error })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn quiet_hours_handle_overnight_and_same_day_windows() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        let mut settings = NotificationSettings::load(&conn);
+        assert!(!settings.in_quiet_hours(at(23, 0)));
+
+        settings.dnd_enabled = true;
+        assert!(settings.in_quiet_hours(at(23, 0)));
+        assert!(settings.in_quiet_hours(at(6, 59)));
+        assert!(!settings.in_quiet_hours(at(7, 0)));
+        assert!(!settings.in_quiet_hours(at(12, 0)));
+
+        settings.dnd_start = at(13, 0);
+        settings.dnd_end = at(15, 30);
+        assert!(settings.in_quiet_hours(at(14, 0)));
+        assert!(!settings.in_quiet_hours(at(16, 0)));
+        assert_eq!(
+            settings.suppression(NotificationKind::SlaBreach, at(14, 0)),
+            Some("do_not_disturb")
+        );
+        assert_eq!(
+            settings.suppression(NotificationKind::Test, at(14, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn update_settings_round_trips_and_validates() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        let settings = update_settings(
+            &conn,
+            &json!({
+                "sound": false,
+                "events": { "printerOffline": false },
+                "doNotDisturb": { "enabled": true, "start": "21:30", "end": "06:00" },
+            }),
+        )
+        .unwrap();
+        assert!(settings.enabled);
+        assert!(!settings.sound);
+        assert!(!settings.printer_offline);
+        assert!(settings.sync_failure);
+        assert_eq!(
+            settings.suppression(NotificationKind::PrinterOffline, at(12, 0)),
+            Some("event_disabled")
+        );
+        let json = settings.to_json();
+        assert_eq!(json["events"]["printerOffline"], false);
+        assert_eq!(json["doNotDisturb"]["start"], "21:30");
+
+        assert!(update_settings(&conn, &json!({ "doNotDisturb": { "start": "25:00" } })).is_err());
+        assert!(update_settings(&conn, &json!({ "sound": "loud" })).is_err());
+        assert_eq!(NotificationSettings::load(&conn), settings);
+    }
+}
@@ -0,0 +1,327 @@
+//! First-run onboarding state.
+//!
+//! The wizard walks a new terminal through a fixed list of required steps:
+//! connection code stored, admin dashboard reachable, terminal settings
+//! fetched (which yields the branch), a printer profile configured and an
+//! admin PIN set. Each step reports a machine-readable `errorCode` so the
+//! wizard can point at the actual problem (unreachable admin vs invalid key
+//! vs missing branch) instead of a generic failure.
+//!
+//! `onboarding.completed` is only written once every required step passes.
+
+use chrono::Utc;
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{api, db, storage};
+
+pub const SETTINGS_CATEGORY: &str = "onboarding";
+pub const COMPLETED_KEY: &str = "completed";
+pub const COMPLETED_AT_KEY: &str = "completed_at";
+
+pub const STEP_CONNECTION_CODE: &str = "connection_code";
+pub const STEP_ADMIN_REACHABLE: &str = "admin_reachable";
+pub const STEP_TERMINAL_SETTINGS: &str = "terminal_settings";
+pub const STEP_PRINTER: &str = "printer";
+pub const STEP_ADMIN_PIN: &str = "admin_pin";
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct StepResult {
+    pub id: &'static str,
+    pub complete: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl StepResult {
+    pub fn ok(id: &'static str) -> Self {
+        Self {
+            id,
+            complete: true,
+            error_code: None,
+            error: None,
+        }
+    }
+
+    pub fn failed(id: &'static str, error_code: &'static str, error: impl Into<String>) -> Self {
+        Self {
+            id,
+            complete: false,
+            error_code: Some(error_code),
+            error: Some(error.into()),
+        }
+    }
+}
+
+/// Connection string contents, with whichever fields could be decoded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodedConnectionCode {
+    pub api_key: Option<String>,
+    pub admin_url: Option<String>,
+    pub terminal_id: Option<String>,
+}
+
+pub fn decode_connection_code(raw: &str) -> DecodedConnectionCode {
+    DecodedConnectionCode {
+        api_key: api::extract_api_key_from_connection_string(raw),
+        admin_url: api::extract_admin_url_from_connection_string(raw),
+        terminal_id: api::extract_terminal_id_from_connection_string(raw),
+    }
+}
+
+/// A connection code must carry all three of key, admin URL and terminal id;
+/// anything less is what leaves terminals half-configured.
+pub fn validate_connection_code(decoded: &DecodedConnectionCode) -> StepResult {
+    match (
+        decoded.api_key.as_ref(),
+        decoded.admin_url.as_ref(),
+        decoded.terminal_id.as_ref(),
+    ) {
+        (None, None, None) => StepResult::failed(
+            STEP_CONNECTION_CODE,
+            "invalid_connection_code",
+            "The connection code could not be decoded",
+        ),
+        (None, _, _) => StepResult::failed(
+            STEP_CONNECTION_CODE,
+            "missing_api_key",
+            "The connection code has no API key",
+        ),
+        (_, None, _) => StepResult::failed(
+            STEP_CONNECTION_CODE,
+            "missing_admin_url",
+            "The connection code has no admin dashboard URL",
+        ),
+        (_, _, None) => StepResult::failed(
+            STEP_CONNECTION_CODE,
+            "missing_terminal_id",
+            "The connection code has no terminal id",
+        ),
+        _ => StepResult::ok(STEP_CONNECTION_CODE),
+    }
+}
+
+/// Stored credentials, checked the same way as a freshly decoded code.
+pub fn stored_connection_step() -> (StepResult, Option<(String, String)>) {
+    let decoded = DecodedConnectionCode {
+        api_key: storage::get_credential("pos_api_key").filter(|v| !v.trim().is_empty()),
+        admin_url: storage::get_credential("admin_dashboard_url")
+            .map(|url| api::normalize_admin_url(&url))
+            .filter(|v| !v.trim().is_empty()),
+        terminal_id: storage::get_credential("terminal_id").filter(|v| !v.trim().is_empty()),
+    };
+    let mut step = validate_connection_code(&decoded);
+    if step.error_code == Some("invalid_connection_code") {
+        step = StepResult::failed(
+            STEP_CONNECTION_CODE,
+            "not_configured",
+            "No connection code has been applied",
+        );
+    }
+    let endpoint = match (decoded.admin_url, decoded.api_key) {
+        (Some(url), Some(key)) if step.complete => Some((url, key)),
+        _ => None,
+    };
+    (step, endpoint)
+}
+
+/// Map an admin API error string (see `api::status_error` /
+/// `api::friendly_error`) onto a wizard error code.
+pub fn classify_admin_error(error: &str) -> &'static str {
+    let lower = error.to_ascii_lowercase();
+    if lower.starts_with("circuit_open") {
+        "circuit_open"
+    } else if lower.contains("api key is invalid") || lower.contains("http 401") {
+        "invalid_api_key"
+    } else if lower.contains("not authorized") || lower.contains("http 403") {
+        "terminal_not_authorized"
+    } else if lower.contains("cannot reach")
+        || lower.contains("timed out")
+        || lower.contains("network error")
+    {
+        "admin_unreachable"
+    } else if lower.contains("invalid admin dashboard url")
+        || lower.contains("plain http admin url")
+    {
+        "invalid_admin_url"
+    } else if lower.contains("endpoint not found") || lower.contains("http 404") {
+        "admin_endpoint_not_found"
+    } else if lower.contains("server error") {
+        "admin_server_error"
+    } else {
+        "admin_request_failed"
+    }
+}
+
+pub fn admin_step(id: &'static str, outcome: Result<(), String>) -> StepResult {
+    match outcome {
+        Ok(()) => StepResult::ok(id),
+        Err(error) => StepResult::failed(id, classify_admin_error(&error), error),
+    }
+}
+
+/// Terminal settings count as fetched once the admin has told us the branch.
+pub fn terminal_settings_step(conn: &Connection) -> StepResult {
+    let branch = storage::get_credential("branch_id")
+        .or_else(|| db::get_setting(conn, "terminal", "branch_id"))
+        .filter(|v| !v.trim().is_empty());
+    match branch {
+        Some(_) => StepResult::ok(STEP_TERMINAL_SETTINGS),
+        None => StepResult::failed(
+            STEP_TERMINAL_SETTINGS,
+            "missing_branch",
+            "The admin dashboard has not assigned this terminal to a branch",
+        ),
+    }
+}
+
+pub fn printer_step(conn: &Connection) -> StepResult {
+    let printers: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM printer_profiles WHERE enabled = 1",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+    if printers > 0 {
+        StepResult::ok(STEP_PRINTER)
+    } else {
+        StepResult::failed(
+            STEP_PRINTER,
+            "no_printer",
+            "No enabled printer profile is configured",
+        )
+    }
+}
+
+pub fn admin_pin_step(conn: &Connection) -> StepResult {
+    match db::get_setting(conn, "staff", "admin_pin_hash").filter(|v| !v.trim().is_empty()) {
+        Some(_) => StepResult::ok(STEP_ADMIN_PIN),
+        None => StepResult::failed(STEP_ADMIN_PIN, "admin_pin_not_set", "No admin PIN is set"),
+    }
+}
+
+pub fn is_completed(conn: &Connection) -> bool {
+    crate::print::setting_bool(conn, SETTINGS_CATEGORY, COMPLETED_KEY)
+}
+
+pub fn mark_completed(conn: &Connection) -> Result<(), String> {
+    db::set_setting(conn, SETTINGS_CATEGORY, COMPLETED_KEY, "true")?;
+    db::set_setting(
+        conn,
+        SETTINGS_CATEGORY,
+        COMPLETED_AT_KEY,
+        &Utc::now().to_rfc3339(),
+    )
+}
+
+pub fn all_complete(steps: &[StepResult]) -> bool {
+    steps.iter().all(|step| step.complete)
+}
+
+pub fn state_json(steps: &[StepResult], completed: bool) -> Value {
+    serde_json::json!({
+        "success": true,
+        "completed": completed,
+        "ready": all_complete(steps),
+        "steps": steps,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine as _;
+
+    fn connection_code(payload: Value) -> String {
+        base64::engine::general_purpose::STANDARD.encode(payload.to_string())
+    }
+
+    #[test]
+    fn connection_code_reports_the_missing_field() {
+        let full = decode_connection_code(&connection_code(serde_json::json!({
+            "key": "k-123",
+            "url": "https://admin.example.com/api/",
+            "tid": "term-1",
+        })));
+        assert_eq!(full.admin_url.as_deref(), Some("https://admin.example.com"));
+        assert!(validate_connection_code(&full).complete);
+
+        let no_url = decode_connection_code(&connection_code(serde_json::json!({
+            "key": "k-123",
+            "tid": "term-1",
+        })));
+        assert_eq!(
+            validate_connection_code(&no_url).error_code,
+            Some("missing_admin_url")
+        );
+        assert_eq!(
+            validate_connection_code(&decode_connection_code("not a code")).error_code,
+            Some("invalid_connection_code")
+        );
+    }
+
+    #[test]
+    fn admin_errors_are_classified() {
+        assert_eq!(
+            classify_admin_error("Cannot reach admin dashboard at https://x"),
+            "admin_unreachable"
+        );
+        assert_eq!(
+            classify_admin_error("API key is invalid or expired (HTTP 401)"),
+            "invalid_api_key"
+        );
+        assert_eq!(
+            classify_admin_error("Terminal not authorized (HTTP 403)"),
+            "terminal_not_authorized"
+        );
+        assert_eq!(
+            classify_admin_error("Admin dashboard server error (HTTP 502)"),
+            "admin_server_error"
+        );
+    }
+
+    #[test]
+    fn local_steps_and_completion_flag() {
+        let _fake = crate::tests::fake_keyring::install_empty();
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+
+        assert_eq!(
+            stored_connection_step().0.error_code,
+            Some("not_configured")
+        );
+        assert_eq!(
+            terminal_settings_step(&conn).error_code,
+            Some("missing_branch")
+        );
+        assert_eq!(admin_pin_step(&conn).error_code, Some("admin_pin_not_set"));
+
+        storage::set_credential("pos_api_key", "k-123").unwrap();
+        storage::set_credential("terminal_id", "term-1").unwrap();
+        assert_eq!(
+            stored_connection_step().0.error_code,
+            Some("missing_admin_url")
+        );
+        storage::set_credential("admin_dashboard_url", "https://admin.example.com").unwrap();
+        let (step, endpoint) = stored_connection_step();
+        assert!(step.complete);
+        assert_eq!(
+            endpoint,
+            Some(("https://admin.example.com".to_string(), "k-123".to_string()))
+        );
+
+        db::set_setting(&conn, "terminal", "branch_id", "branch-1").unwrap();
+        db::set_setting(&conn, "staff", "admin_pin_hash", "hash").unwrap();
+        assert!(terminal_settings_step(&conn).complete);
+        assert!(admin_pin_step(&conn).complete);
+
+        assert!(!is_completed(&conn));
+        mark_completed(&conn).unwrap();
+        assert!(is_completed(&conn));
+    }
+}
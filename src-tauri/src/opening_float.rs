@@ -0,0 +1,258 @@
+//! Standing cash float and the opening-float check at shift open.
+//!
+//! The drawer is meant to start every day with the same float, but the
+//! opening amount used to be whatever the cashier typed, and a short float
+//! only showed up as a variance at close. The expected float is
+//! `cash.branch_opening_float` when the admin snapshot carries a branch
+//! override, otherwise the terminal's `cash.default_opening_float`.
+//!
+//! `shift_open` pre-fills a cashier or manager shift with the expected float
+//! when no amount is given. A declared float that differs by more than
+//! `cash.opening_float_tolerance` needs manager approval and a note; the
+//! expected float and the deviation are recorded on the shift either way.
+//! `cash.validate_opening_float = false` turns the approval off for
+//! businesses whose float genuinely varies.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+
+use crate::db;
+use crate::money::Cents;
+
+const SETTINGS_CATEGORY: &str = "cash";
+const DEFAULT_FLOAT_KEY: &str = "default_opening_float";
+/// Written by the admin settings snapshot for the terminal's branch.
+const BRANCH_FLOAT_KEY: &str = "branch_opening_float";
+const TOLERANCE_KEY: &str = "opening_float_tolerance";
+const VALIDATE_KEY: &str = "validate_opening_float";
+
+pub const HISTORY_DAYS: i64 = 30;
+
+fn setting_amount(conn: &Connection, key: &str) -> Option<Cents> {
+    db::get_setting(conn, SETTINGS_CATEGORY, key)
+        .and_then(|raw| raw.trim().parse::<f64>().ok())
+        .filter(|value| value.is_finite() && *value >= 0.0)
+        .map(Cents::round_half_even)
+}
+
+/// The configured float, tolerance and whether deviations need approval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloatConfig {
+    pub expected: Option<Cents>,
+    pub tolerance: Cents,
+    pub validate: bool,
+}
+
+impl FloatConfig {
+    pub fn load(conn: &Connection) -> Self {
+        let validate = db::get_setting(conn, SETTINGS_CATEGORY, VALIDATE_KEY)
+            .map(|raw| {
+                !matches!(
+                    raw.trim().to_ascii_lowercase().as_str(),
+                    "0" | "false" | "no" | "off"
+                )
+            })
+            .unwrap_or(true);
+        Self {
+            expected: setting_amount(conn, BRANCH_FLOAT_KEY)
+                .or_else(|| setting_amount(conn, DEFAULT_FLOAT_KEY)),
+            tolerance: setting_amount(conn, TOLERANCE_KEY).unwrap_or(Cents::ZERO),
+            validate,
+        }
+    }
+
+    pub fn to_json(self) -> Value {
+        json!({
+            "expectedFloat": self.expected.map(Cents::to_f64_dp2),
+            "tolerance": self.tolerance.to_f64_dp2(),
+            "validationEnabled": self.validate,
+        })
+    }
+}
+
+/// How a declared opening float compares to the expected one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloatCheck {
+    pub config: FloatConfig,
+    pub declared: Cents,
+}
+
+impl FloatCheck {
+    pub fn new(config: FloatConfig, declared: Cents) -> Self {
+        Self { config, declared }
+    }
+
+    /// Declared minus expected; `None` when no float is configured.
+    pub fn deviation(&self) -> Option<Cents> {
+        self.config
+            .expected
+            .map(|expected| self.declared - expected)
+    }
+
+    pub fn exceeds_tolerance(&self) -> bool {
+        self.deviation()
+            .is_some_and(|deviation| deviation.abs() > self.config.tolerance)
+    }
+
+    pub fn needs_approval(&self) -> bool {
+        self.config.validate && self.exceeds_tolerance()
+    }
+
+    pub fn to_json(self) -> Value {
+        json!({
+            "expectedFloat": self.config.expected.map(Cents::to_f64_dp2),
+            "declaredFloat": self.declared.to_f64_dp2(),
+            "deviation": self.deviation().map(Cents::to_f64_dp2),
+            "tolerance": self.config.tolerance.to_f64_dp2(),
+            "validationEnabled": self.config.validate,
+            "approvalRequired": self.needs_approval(),
+        })
+    }
+}
+
+#[derive(Default)]
+struct TerminalSummary {
+    shifts: i64,
+    /// Shifts whose float differed from the expected one at all.
+    deviations: i64,
+    approvals: i64,
+    net_deviation: Cents,
+}
+
+/// Opening floats of cashier and manager shifts since `now` minus
+/// [`HISTORY_DAYS`], newest first, with a per-terminal summary.
+pub fn history(
+    conn: &Connection,
+    terminal_id: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Value, String> {
+    let since = (now - Duration::days(HISTORY_DAYS)).to_rfc3339();
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, COALESCE(terminal_id, ''), staff_id, staff_name, check_in_time,
+                    report_date, opening_cash_amount_cents, opening_float_expected_cents,
+                    opening_float_deviation_cents, opening_float_note, opening_float_approved_at
+             FROM staff_shifts
+             WHERE role_type IN ('cashier', 'manager')
+               AND julianday(check_in_time) >= julianday(?1)
+               AND (?2 IS NULL OR terminal_id = ?2)
+             ORDER BY check_in_time DESC",
+        )
+        .map_err(|e| format!("prepare float history: {e}"))?;
+    let rows = stmt
+        .query_map(params![since, terminal_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, Option<i64>>(6)?,
+                row.get::<_, Option<i64>>(7)?,
+                row.get::<_, Option<i64>>(8)?,
+                row.get::<_, Option<String>>(9)?,
+                row.get::<_, Option<String>>(10)?,
+            ))
+        })
+        .map_err(|e| format!("query float history: {e}"))?;
+
+    let mut entries = Vec::new();
+    let mut terminals: BTreeMap<String, TerminalSummary> = BTreeMap::new();
+    for row in rows {
+        let (
+            shift_id,
+            row_terminal,
+            staff_id,
+            staff_name,
+            check_in_time,
+            report_date,
+            opening_cents,
+            expected_cents,
+            deviation_cents,
+            note,
+            approved_at,
+        ) = row.map_err(|e| format!("read float history row: {e}"))?;
+        let deviation = deviation_cents.map(Cents::new);
+        let summary = terminals.entry(row_terminal.clone()).or_default();
+        summary.shifts += 1;
+        if let Some(deviation) = deviation.filter(|value| !value.is_zero()) {
+            summary.deviations += 1;
+            summary.net_deviation += deviation;
+        }
+        if approved_at.is_some() {
+            summary.approvals += 1;
+        }
+        entries.push(json!({
+            "shiftId": shift_id,
+            "terminalId": row_terminal,
+            "staffId": staff_id,
+            "staffName": staff_name,
+            "checkInTime": check_in_time,
+            "reportDate": report_date,
+            "openingFloat": opening_cents.map(|cents| Cents::new(cents).to_f64_dp2()),
+            "expectedFloat": expected_cents.map(|cents| Cents::new(cents).to_f64_dp2()),
+            "deviation": deviation.map(Cents::to_f64_dp2),
+            "note": note,
+            "approvedAt": approved_at,
+        }));
+    }
+
+    let summaries: Vec<Value> = terminals
+        .into_iter()
+        .map(|(terminal, summary)| {
+            json!({
+                "terminalId": terminal,
+                "shifts": summary.shifts,
+                "deviations": summary.deviations,
+                "approvals": summary.approvals,
+                "netDeviation": summary.net_deviation.to_f64_dp2(),
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "days": HISTORY_DAYS,
+        "since": since,
+        "config": FloatConfig::load(conn).to_json(),
+        "terminals": summaries,
+        "entries": entries,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branch_override_wins_and_validation_can_be_switched_off() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+
+        let config = FloatConfig::load(&conn);
+        assert_eq!(config.expected, None);
+        assert!(!FloatCheck::new(config, Cents::new(12_000)).needs_approval());
+
+        db::set_setting(&conn, "cash", DEFAULT_FLOAT_KEY, "150").unwrap();
+        db::set_setting(&conn, "cash", TOLERANCE_KEY, "5").unwrap();
+        let config = FloatConfig::load(&conn);
+        assert_eq!(config.expected, Some(Cents::new(15_000)));
+        let within = FloatCheck::new(config, Cents::new(14_600));
+        assert_eq!(within.deviation(), Some(Cents::new(-400)));
+        assert!(!within.needs_approval());
+        let short = FloatCheck::new(config, Cents::new(12_000));
+        assert!(short.needs_approval());
+        assert_eq!(short.to_json()["deviation"], -30.0);
+
+        db::set_setting(&conn, "cash", BRANCH_FLOAT_KEY, "200").unwrap();
+        assert_eq!(FloatConfig::load(&conn).expected, Some(Cents::new(20_000)));
+
+        db::set_setting(&conn, "cash", VALIDATE_KEY, "false").unwrap();
+        let unchecked = FloatCheck::new(FloatConfig::load(&conn), Cents::new(12_000));
+        assert!(unchecked.exceeds_tolerance());
+        assert!(!unchecked.needs_approval());
+    }
+}
@@ -0,0 +1,451 @@
+//! Open-order aging for the kitchen dashboard.
+//!
+//! [`open_orders_snapshot`] groups the open orders (pending, confirmed,
+//! preparing, ready) into age bands by minutes since `created_at`. The band
+//! edges come from `kitchen.aging_band_minutes` (default `10,20`, giving
+//! `0-10`, `10-20` and `20+`). Each order carries its prep-time target:
+//! `kitchen.prep_target_minutes_<order type>` when set (e.g.
+//! `prep_target_minutes_delivery`), otherwise `kitchen.prep_target_minutes`.
+//!
+//! The dashboard polls the snapshot, so it reads only the columns it needs
+//! through `idx_orders_status_created_at`, and parses the items JSON only
+//! when asked to.
+//!
+//! [`start_sla_monitor`] runs every [`MONITOR_INTERVAL_SECS`] seconds and
+//! emits `order_sla_breached` the first time an open order passes its
+//! target. The breach is stamped into `orders.sla_breached_at`, so each
+//! order alerts at most once, restarts included.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+use tauri::Emitter;
+use tracing::{info, warn};
+
+use crate::db::{self, DbState};
+use crate::notify::{self, NotificationKind};
+
+pub const SETTINGS_CATEGORY: &str = "kitchen";
+pub const AGING_BANDS_KEY: &str = "aging_band_minutes";
+pub const PREP_TARGET_KEY: &str = "prep_target_minutes";
+
+pub const DEFAULT_BAND_EDGES: &[i64] = &[10, 20];
+pub const DEFAULT_PREP_TARGET_MINUTES: i64 = 20;
+
+pub const OPEN_STATUSES: &[&str] = &["pending", "confirmed", "preparing", "ready"];
+pub const SLA_BREACHED_EVENT: &str = "order_sla_breached";
+
+const MONITOR_INTERVAL_SECS: u64 = 30;
+
+/// Band edges in minutes, ascending. Anything that is not a strictly
+/// ascending list of positive integers falls back to the default.
+pub fn parse_band_edges(raw: Option<&str>) -> Vec<i64> {
+    let parsed: Option<Vec<i64>> = raw.and_then(|raw| {
+        raw.split(',')
+            .map(|part| part.trim().parse::<i64>().ok().filter(|v| *v > 0))
+            .collect()
+    });
+    match parsed {
+        Some(edges) if !edges.is_empty() && edges.windows(2).all(|w| w[0] < w[1]) => edges,
+        _ => DEFAULT_BAND_EDGES.to_vec(),
+    }
+}
+
+fn normalize_order_type(order_type: &str) -> String {
+    order_type
+        .trim()
+        .to_ascii_lowercase()
+        .replace(['-', ' '], "_")
+}
+
+/// Prep-time targets: the default plus per-order-type overrides.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrepTargets {
+    pub default_minutes: i64,
+    pub by_type: HashMap<String, i64>,
+}
+
+impl PrepTargets {
+    pub fn load(conn: &Connection) -> Self {
+        let default_minutes = db::get_setting(conn, SETTINGS_CATEGORY, PREP_TARGET_KEY)
+            .and_then(|v| v.trim().parse::<i64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_PREP_TARGET_MINUTES);
+
+        let prefix = format!("{PREP_TARGET_KEY}_");
+        let mut by_type = HashMap::new();
+        let rows = conn
+            .prepare(
+                "SELECT setting_key, setting_value FROM local_settings
+                 WHERE setting_category = ?1 AND setting_key LIKE ?2",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![SETTINGS_CATEGORY, format!("{prefix}%")], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()
+            });
+        match rows {
+            Ok(rows) => {
+                for (key, value) in rows {
+                    let minutes = value
+                        .and_then(|v| v.trim().parse::<i64>().ok())
+                        .filter(|v| *v > 0);
+                    if let (Some(order_type), Some(minutes)) = (key.strip_prefix(&prefix), minutes)
+                    {
+                        by_type.insert(normalize_order_type(order_type), minutes);
+                    }
+                }
+            }
+            Err(e) => warn!(error = %e, "Failed to read per-type prep targets"),
+        }
+
+        Self {
+            default_minutes,
+            by_type,
+        }
+    }
+
+    pub fn for_order_type(&self, order_type: &str) -> i64 {
+        self.by_type
+            .get(&normalize_order_type(order_type))
+            .copied()
+            .unwrap_or(self.default_minutes)
+    }
+}
+
+/// `created_at` is RFC 3339 for orders created by the app and
+/// `YYYY-MM-DD HH:MM:SS` (UTC) for rows defaulted by SQLite.
+fn parse_created_at(raw: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(raw) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(raw, fmt).ok())
+        .map(|naive| naive.and_utc())
+}
+
+fn elapsed_minutes(created_at: &str, now: DateTime<Utc>) -> Option<i64> {
+    parse_created_at(created_at).map(|created| (now - created).num_minutes().max(0))
+}
+
+fn band_index(edges: &[i64], elapsed: i64) -> usize {
+    edges.iter().take_while(|edge| elapsed >= **edge).count()
+}
+
+fn band_label(edges: &[i64], index: usize) -> String {
+    let min = if index == 0 { 0 } else { edges[index - 1] };
+    match edges.get(index) {
+        Some(max) => format!("{min}-{max}"),
+        None => format!("{min}+"),
+    }
+}
+
+struct OpenOrder {
+    id: String,
+    order_number: Option<String>,
+    customer_name: Option<String>,
+    table_number: Option<String>,
+    order_type: String,
+    status: String,
+    created_at: String,
+    sla_breached_at: Option<String>,
+    items: Option<String>,
+}
+
+fn load_open_orders(conn: &Connection, include_items: bool) -> Result<Vec<OpenOrder>, String> {
+    let placeholders = OPEN_STATUSES
+        .iter()
+        .map(|_| "?")
+        .collect::<Vec<_>>()
+        .join(", ");
+    let items_col = if include_items { "items" } else { "NULL" };
+    let sql = format!(
+        "SELECT id, order_number, customer_name, table_number,
+                COALESCE(order_type, 'dine-in'), status, COALESCE(created_at, ''),
+                sla_breached_at, {items_col}
+         FROM orders
+         WHERE status IN ({placeholders}) AND COALESCE(is_ghost, 0) = 0
+         ORDER BY created_at ASC"
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(OPEN_STATUSES.iter()), |row| {
+            Ok(OpenOrder {
+                id: row.get(0)?,
+                order_number: row.get(1)?,
+                customer_name: row.get(2)?,
+                table_number: row.get(3)?,
+                order_type: row.get(4)?,
+                status: row.get(5)?,
+                created_at: row.get(6)?,
+                sla_breached_at: row.get(7)?,
+                items: row.get(8)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Open orders grouped into age bands, oldest first within each band.
+pub fn open_orders_snapshot(
+    conn: &Connection,
+    include_items: bool,
+    now: DateTime<Utc>,
+) -> Result<Value, String> {
+    let edges =
+        parse_band_edges(db::get_setting(conn, SETTINGS_CATEGORY, AGING_BANDS_KEY).as_deref());
+    let targets = PrepTargets::load(conn);
+    let orders = load_open_orders(conn, include_items)?;
+
+    let mut buckets: Vec<Vec<Value>> = vec![Vec::new(); edges.len() + 1];
+    let mut breached_count = 0;
+    for order in &orders {
+        let elapsed = elapsed_minutes(&order.created_at, now).unwrap_or(0);
+        let target = targets.for_order_type(&order.order_type);
+        let breached = elapsed >= target;
+        if breached {
+            breached_count += 1;
+        }
+        let mut entry = json!({
+            "id": order.id,
+            "orderNumber": order.order_number,
+            "customerName": order.customer_name,
+            "tableNumber": order.table_number,
+            "orderType": order.order_type,
+            "status": order.status,
+            "createdAt": order.created_at,
+            "elapsedMinutes": elapsed,
+            "targetMinutes": target,
+            "breached": breached,
+            "slaBreachedAt": order.sla_breached_at,
+        });
+        if include_items {
+            entry["items"] = order
+                .items
+                .as_deref()
+                .and_then(|raw| serde_json::from_str(raw).ok())
+                .unwrap_or_else(|| json!([]));
+        }
+        buckets[band_index(&edges, elapsed)].push(entry);
+    }
+
+    let buckets: Vec<Value> = buckets
+        .into_iter()
+        .enumerate()
+        .map(|(index, orders)| {
+            json!({
+                "label": band_label(&edges, index),
+                "minMinutes": if index == 0 { 0 } else { edges[index - 1] },
+                "maxMinutes": edges.get(index),
+                "orders": orders,
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "generatedAt": now.with_timezone(&Local).to_rfc3339(),
+        "totalOpen": orders.len(),
+        "breachedCount": breached_count,
+        "buckets": buckets,
+    }))
+}
+
+/// Stamp `sla_breached_at` on open orders that have passed their target and
+/// return one event payload per newly breached order.
+pub fn detect_breaches(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<Value>, String> {
+    let targets = PrepTargets::load(conn);
+    let stamp = now.to_rfc3339();
+    let mut breached = Vec::new();
+    for order in load_open_orders(conn, false)? {
+        if order.sla_breached_at.is_some() {
+            continue;
+        }
+        let Some(elapsed) = elapsed_minutes(&order.created_at, now) else {
+            continue;
+        };
+        let target = targets.for_order_type(&order.order_type);
+        if elapsed < target {
+            continue;
+        }
+        let updated = conn
+            .execute(
+                "UPDATE orders SET sla_breached_at = ?1
+                 WHERE id = ?2 AND sla_breached_at IS NULL",
+                params![stamp, order.id],
+            )
+            .map_err(|e| e.to_string())?;
+        if updated == 0 {
+            continue;
+        }
+        breached.push(json!({
+            "orderId": order.id,
+            "orderNumber": order.order_number,
+            "orderType": order.order_type,
+            "status": order.status,
+            "createdAt": order.created_at,
+            "elapsedMinutes": elapsed,
+            "targetMinutes": target,
+            "breachedAt": stamp,
+        }));
+    }
+    Ok(breached)
+}
+
+pub fn start_sla_monitor(
+    app: tauri::AppHandle,
+    db: Arc<DbState>,
+    cancel: tokio_util::sync::CancellationToken,
+) {
+    let cadence = Duration::from_secs(MONITOR_INTERVAL_SECS);
+    tauri::async_runtime::spawn(async move {
+        info!("Order SLA monitor started");
+        loop {
+            let result = match db.conn.lock() {
+                Ok(conn) => detect_breaches(&conn, Utc::now()),
+                Err(e) => Err(e.to_string()),
+            };
+            match result {
+                Ok(breached) => {
+                    for payload in breached {
+                        let order = payload["orderNumber"]
+                            .as_str()
+                            .map(|number| format!("Order #{number}"))
+                            .unwrap_or_else(|| "An order".to_string());
+                        let body = format!(
+                            "{order} has been open for {} minutes",
+                            payload["elapsedMinutes"]
+                        );
+                        let _ = app.emit(SLA_BREACHED_EVENT, payload);
+                        notify::send(
+                            &app,
+                            &db,
+                            NotificationKind::SlaBreach,
+                            "Order running late",
+                            &body,
+                        );
+                    }
+                }
+                Err(error) => warn!(error = %error, "Order SLA check failed"),
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(cadence) => {}
+                _ = cancel.cancelled() => {
+                    info!("Order SLA monitor cancelled");
+                    break;
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()
+    }
+
+    fn insert_order(conn: &Connection, id: &str, order_type: &str, status: &str, age_min: i64) {
+        let created_at = (now() - chrono::Duration::minutes(age_min)).to_rfc3339();
+        conn.execute(
+            "INSERT INTO orders (id, order_number, items, total_amount, status, order_type,
+                                 created_at, updated_at)
+             VALUES (?1, ?1, '[{\"name\":\"Pita\"}]', 10.0, ?2, ?3, ?4, ?4)",
+            params![id, status, order_type, created_at],
+        )
+        .expect("insert order");
+    }
+
+    #[test]
+    fn band_edges_fall_back_on_bad_input() {
+        assert_eq!(parse_band_edges(None), vec![10, 20]);
+        assert_eq!(parse_band_edges(Some("5, 15,30")), vec![5, 15, 30]);
+        assert_eq!(parse_band_edges(Some("20,10")), vec![10, 20]);
+        assert_eq!(parse_band_edges(Some("0,10")), vec![10, 20]);
+        assert_eq!(parse_band_edges(Some("ten")), vec![10, 20]);
+    }
+
+    #[test]
+    fn created_at_accepts_sqlite_and_rfc3339() {
+        let expected = Utc.with_ymd_and_hms(2026, 3, 1, 11, 45, 0).unwrap();
+        assert_eq!(parse_created_at("2026-03-01 11:45:00"), Some(expected));
+        assert_eq!(
+            parse_created_at("2026-03-01T13:45:00+02:00"),
+            Some(expected)
+        );
+        assert_eq!(parse_created_at("garbage"), None);
+    }
+
+    #[test]
+    fn snapshot_buckets_open_orders_by_age() {
+        let conn = test_conn();
+        insert_order(&conn, "fresh", "dine-in", "pending", 3);
+        insert_order(&conn, "mid", "takeaway", "preparing", 12);
+        insert_order(&conn, "old", "delivery", "ready", 45);
+        insert_order(&conn, "done", "dine-in", "completed", 60);
+        db::set_setting(
+            &conn,
+            SETTINGS_CATEGORY,
+            "prep_target_minutes_delivery",
+            "40",
+        )
+        .unwrap();
+
+        let snapshot = open_orders_snapshot(&conn, false, now()).unwrap();
+        assert_eq!(snapshot["totalOpen"], 3);
+        assert_eq!(snapshot["breachedCount"], 1);
+        let buckets = snapshot["buckets"].as_array().unwrap();
+        let labels: Vec<&str> = buckets
+            .iter()
+            .map(|b| b["label"].as_str().unwrap())
+            .collect();
+        assert_eq!(labels, vec!["0-10", "10-20", "20+"]);
+        assert_eq!(buckets[0]["orders"][0]["id"], "fresh");
+        assert_eq!(buckets[1]["orders"][0]["id"], "mid");
+        assert_eq!(buckets[1]["orders"][0]["targetMinutes"], 20);
+        let old = &buckets[2]["orders"][0];
+        assert_eq!(old["elapsedMinutes"], 45);
+        assert_eq!(old["targetMinutes"], 40);
+        assert!(old.get("items").is_none());
+
+        let with_items = open_orders_snapshot(&conn, true, now()).unwrap();
+        assert_eq!(
+            with_items["buckets"][0]["orders"][0]["items"][0]["name"],
+            "Pita"
+        );
+    }
+
+    #[test]
+    fn breach_is_reported_once() {
+        let conn = test_conn();
+        insert_order(&conn, "late", "dine-in", "preparing", 25);
+        insert_order(&conn, "on-time", "dine-in", "pending", 5);
+
+        let first = detect_breaches(&conn, now()).unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0]["orderId"], "late");
+        assert_eq!(first[0]["targetMinutes"], 20);
+        assert!(detect_breaches(&conn, now()).unwrap().is_empty());
+
+        let later = now() + chrono::Duration::minutes(20);
+        let second = detect_breaches(&conn, later).unwrap();
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0]["orderId"], "on-time");
+    }
+}
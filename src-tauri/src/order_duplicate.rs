@@ -0,0 +1,570 @@
+//! "Same as last time": a new order built from a past one.
+//!
+//! [`build_payload`] turns a source order into an `order_create` payload with
+//! the same items, order type and customer details. Every line is checked
+//! against the cached menu and priced from it for the order type, so a
+//! duplicate never carries an old price. Lines whose item is gone or
+//! unavailable are dropped and reported instead of failing the whole
+//! duplicate; so are extras whose ingredient is gone. Open-price (manual)
+//! lines keep their own price, and combo lines are validated and repriced
+//! by [`combos::validate_line`].
+//!
+//! Discounts, payments, tips, the table and the driver are never copied.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Map, Value};
+
+use crate::combos::{self, ComboMenu};
+use crate::money::{Cents, RoundingRule};
+use crate::{value_f64, value_str};
+
+/// Why a source line was left out of the duplicate.
+pub const SKIP_NOT_ON_MENU: &str = "not_on_menu";
+pub const SKIP_UNAVAILABLE: &str = "unavailable";
+pub const SKIP_NO_PRICE: &str = "no_menu_price";
+pub const SKIP_INVALID_COMBO: &str = "invalid_combo";
+
+/// Order columns copied as customer details, with their payload keys.
+const CUSTOMER_FIELDS: &[(&str, &str)] = &[
+    ("customer_name", "customerName"),
+    ("customer_phone", "customerPhone"),
+    ("customer_email", "customerEmail"),
+    ("customer_id", "customerId"),
+];
+
+/// Delivery address columns, copied for delivery orders only.
+const DELIVERY_FIELDS: &[(&str, &str)] = &[
+    ("delivery_address", "deliveryAddress"),
+    ("delivery_city", "deliveryCity"),
+    ("delivery_postal_code", "deliveryPostalCode"),
+    ("delivery_floor", "deliveryFloor"),
+    ("delivery_notes", "deliveryNotes"),
+    ("name_on_ringer", "nameOnRinger"),
+    ("delivery_address_id", "deliveryAddressId"),
+    ("delivery_zone_id", "deliveryZoneId"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicatePlan {
+    pub source_order_id: String,
+    pub source_order_number: Option<String>,
+    pub payload: Value,
+    pub skipped_items: Vec<Value>,
+}
+
+struct MenuSections {
+    subcategories: HashMap<String, Value>,
+    categories: HashMap<String, Value>,
+    ingredients: HashMap<String, Value>,
+}
+
+fn flag_off(entry: &Value, key: &str) -> bool {
+    matches!(entry.get(key), Some(Value::Bool(false)))
+        || matches!(entry.get(key).and_then(Value::as_i64), Some(0))
+}
+
+fn is_unavailable(entry: &Value, categories: &HashMap<String, Value>) -> bool {
+    let category_off = value_str(entry, &["category_id", "categoryId"])
+        .and_then(|id| categories.get(&id))
+        .is_some_and(|category| flag_off(category, "is_active"));
+    flag_off(entry, "is_available") || flag_off(entry, "is_active") || category_off
+}
+
+/// Menu price for the order type: the type's own price, then pickup, then
+/// the base price.
+pub(crate) fn menu_price(entry: &Value, order_type: &str) -> Option<f64> {
+    let pickup = value_f64(entry, &["pickup_price", "base_price", "price"]);
+    match order_type.trim().to_ascii_lowercase().as_str() {
+        "delivery" => value_f64(entry, &["delivery_price"]).or(pickup),
+        "dine-in" | "dine_in" | "dinein" => value_f64(entry, &["dine_in_price"]).or(pickup),
+        _ => pickup,
+    }
+    .filter(|price| price.is_finite() && *price >= 0.0)
+}
+
+fn line_name(line: &Value) -> String {
+    value_str(line, &["name", "menu_item_name", "itemName", "title"])
+        .unwrap_or_else(|| "Item".to_string())
+}
+
+fn skipped(line: &Value, reason: &str, message: String) -> Value {
+    json!({
+        "name": line_name(line),
+        "menuItemId": value_str(line, &["menu_item_id", "menuItemId"]),
+        "comboId": value_str(line, &["combo_id", "comboId"]),
+        "quantity": value_f64(line, &["quantity"]).unwrap_or(1.0),
+        "reason": reason,
+        "message": message,
+    })
+}
+
+pub(crate) fn is_manual(line: &Value) -> bool {
+    ["is_manual", "isManual"]
+        .iter()
+        .any(|key| line.get(*key).and_then(Value::as_bool) == Some(true))
+}
+
+pub(crate) fn is_removal(entry: &Value) -> bool {
+    ["is_without", "isWithout", "without"]
+        .iter()
+        .any(|key| entry.get(*key).and_then(Value::as_bool) == Some(true))
+        || value_str(entry, &["action"]).as_deref() == Some("remove")
+}
+
+fn customization_ingredient_id(entry: &Value) -> Option<String> {
+    value_str(entry, &["ingredient_id", "ingredientId"])
+        .or_else(|| entry.get("ingredient").and_then(|i| value_str(i, &["id"])))
+}
+
+/// Refresh one extra's price from the ingredient cache. `Err` carries the
+/// skip reason when the ingredient is gone or unavailable.
+fn reprice_customization(
+    entry: &mut Value,
+    menu: &MenuSections,
+    order_type: &str,
+) -> Result<f64, &'static str> {
+    let quantity = value_f64(entry, &["quantity"]).unwrap_or(1.0).max(0.0);
+    let Some(ingredient_id) = customization_ingredient_id(entry) else {
+        return Ok(if is_removal(entry) {
+            0.0
+        } else {
+            value_f64(entry, &["price"]).unwrap_or(0.0).max(0.0) * quantity
+        });
+    };
+    let Some(ingredient) = menu.ingredients.get(&ingredient_id) else {
+        return Err(SKIP_NOT_ON_MENU);
+    };
+    if is_removal(entry) {
+        return Ok(0.0);
+    }
+    if is_unavailable(ingredient, &menu.categories) {
+        return Err(SKIP_UNAVAILABLE);
+    }
+    let price = menu_price(ingredient, order_type).unwrap_or(0.0);
+    if let Some(object) = entry.as_object_mut() {
+        object.insert("price".to_string(), json!(price));
+        if let Some(nested) = object.get_mut("ingredient").and_then(Value::as_object_mut) {
+            nested.insert("price".to_string(), json!(price));
+        }
+    }
+    Ok(price * quantity)
+}
+
+/// Reprice the extras of a line, dropping the ones that are gone. Returns
+/// the per-unit extras total.
+fn reprice_customizations(
+    line: &Value,
+    menu: &MenuSections,
+    order_type: &str,
+    skipped_items: &mut Vec<Value>,
+) -> (Option<Value>, f64) {
+    let raw = line.get("customizations").cloned();
+    let raw = match raw {
+        Some(Value::String(text)) => serde_json::from_str::<Value>(&text).ok(),
+        other => other,
+    };
+    let mut extras = 0.0;
+    let mut keep = |entry: &mut Value| match reprice_customization(entry, menu, order_type) {
+        Ok(price) => {
+            extras += price;
+            true
+        }
+        Err(reason) => {
+            let name = value_str(entry, &["name"])
+                .or_else(|| {
+                    entry
+                        .get("ingredient")
+                        .and_then(|i| value_str(i, &["name"]))
+                })
+                .unwrap_or_else(|| "Extra".to_string());
+            skipped_items.push(json!({
+                "name": name,
+                "parentName": line_name(line),
+                "ingredientId": customization_ingredient_id(entry),
+                "reason": reason,
+                "message": format!("'{name}' on '{}' is no longer offered", line_name(line)),
+            }));
+            false
+        }
+    };
+    let customizations = match raw {
+        Some(Value::Array(entries)) => Some(Value::Array(
+            entries
+                .into_iter()
+                .filter_map(|mut entry| keep(&mut entry).then_some(entry))
+                .collect(),
+        )),
+        Some(Value::Object(entries)) => Some(Value::Object(
+            entries
+                .into_iter()
+                .filter_map(|(key, mut entry)| keep(&mut entry).then_some((key, entry)))
+                .collect(),
+        )),
+        _ => None,
+    };
+    (customizations, extras)
+}
+
+fn priced_line(
+    mut line: Map<String, Value>,
+    unit: f64,
+    quantity: f64,
+    rule: RoundingRule,
+) -> Value {
+    let unit = Cents::round_with(unit, rule);
+    let total = Cents::round_with(unit.to_f64_dp2() * quantity, rule);
+    line.insert("quantity".to_string(), json!(quantity));
+    line.insert("price".to_string(), json!(unit.to_f64_dp2()));
+    line.insert("unit_price".to_string(), json!(unit.to_f64_dp2()));
+    line.insert("total_price".to_string(), json!(total.to_f64_dp2()));
+    Value::Object(line)
+}
+
+/// Copy the keys of a source line that describe what was ordered, never
+/// its price, discount or override flags.
+fn base_line(source: &Value, keys: &[&str]) -> Map<String, Value> {
+    keys.iter()
+        .filter_map(|key| {
+            source
+                .get(*key)
+                .filter(|value| !value.is_null())
+                .map(|value| (key.to_string(), value.clone()))
+        })
+        .collect()
+}
+
+const LINE_KEYS: &[&str] = &[
+    "menu_item_id",
+    "menuItemId",
+    "name",
+    "menu_item_name",
+    "category_id",
+    "categoryId",
+    "notes",
+    "course",
+];
+
+fn duplicate_menu_line(
+    source: &Value,
+    menu: &MenuSections,
+    order_type: &str,
+    rule: RoundingRule,
+    skipped_items: &mut Vec<Value>,
+) -> Option<Value> {
+    let quantity = value_f64(source, &["quantity"]).unwrap_or(1.0).max(1.0);
+    if is_manual(source) {
+        let unit = value_f64(source, &["unit_price", "unitPrice", "price"]).unwrap_or(0.0);
+        let mut line = base_line(source, LINE_KEYS);
+        line.insert("is_manual".to_string(), Value::Bool(true));
+        return Some(priced_line(line, unit, quantity, rule));
+    }
+    let Some(menu_item_id) = value_str(source, &["menu_item_id", "menuItemId"]) else {
+        skipped_items.push(skipped(
+            source,
+            SKIP_NOT_ON_MENU,
+            format!("'{}' has no menu item", line_name(source)),
+        ));
+        return None;
+    };
+    let Some(entry) = menu.subcategories.get(&menu_item_id) else {
+        skipped_items.push(skipped(
+            source,
+            SKIP_NOT_ON_MENU,
+            format!("'{}' is no longer on the menu", line_name(source)),
+        ));
+        return None;
+    };
+    if is_unavailable(entry, &menu.categories) {
+        skipped_items.push(skipped(
+            source,
+            SKIP_UNAVAILABLE,
+            format!("'{}' is unavailable", line_name(source)),
+        ));
+        return None;
+    }
+    let Some(base_price) = menu_price(entry, order_type) else {
+        skipped_items.push(skipped(
+            source,
+            SKIP_NO_PRICE,
+            format!("'{}' has no menu price for {order_type}", line_name(source)),
+        ));
+        return None;
+    };
+
+    let (customizations, extras) = reprice_customizations(source, menu, order_type, skipped_items);
+    let mut line = base_line(source, LINE_KEYS);
+    if let Some(name) = value_str(entry, &["name"]) {
+        line.insert("name".to_string(), json!(name));
+    }
+    if let Some(customizations) = customizations {
+        line.insert("customizations".to_string(), customizations);
+    }
+    Some(priced_line(line, base_price + extras, quantity, rule))
+}
+
+fn duplicate_combo_line(
+    source: &Value,
+    combo_menu: &ComboMenu,
+    order_type: &str,
+    now: DateTime<Local>,
+    rule: RoundingRule,
+    skipped_items: &mut Vec<Value>,
+) -> Option<Value> {
+    let mut line = Value::Object(base_line(
+        source,
+        &[
+            "combo_id",
+            "comboId",
+            "combo_items",
+            "comboItems",
+            "name",
+            "quantity",
+            "notes",
+            "course",
+        ],
+    ));
+    match combos::validate_line(combo_menu, &mut line, order_type, now, rule) {
+        Ok(_) => {
+            if let Some(object) = line.as_object_mut() {
+                // Prices were left off on purpose, so the fix-up markers
+                // would read as a correction of the cashier's price.
+                object.remove("normalized");
+                object.remove("normalizedFromTotal");
+            }
+            Some(line)
+        }
+        Err(violations) => {
+            let message = violations
+                .first()
+                .and_then(|v| value_str(v, &["message"]))
+                .unwrap_or_else(|| format!("Combo '{}' is invalid", line_name(source)));
+            skipped_items.push(skipped(source, SKIP_INVALID_COMBO, message));
+            None
+        }
+    }
+}
+
+type SourceOrder = (Option<String>, String, String, HashMap<String, String>);
+
+fn load_source(conn: &Connection, order_id: &str) -> Result<SourceOrder, String> {
+    let columns: Vec<&str> = CUSTOMER_FIELDS
+        .iter()
+        .chain(DELIVERY_FIELDS)
+        .map(|(column, _)| *column)
+        .collect();
+    let sql = format!(
+        "SELECT order_number, COALESCE(items, '[]'), COALESCE(order_type, 'pickup'), {}
+         FROM orders WHERE id = ?1",
+        columns.join(", ")
+    );
+    conn.query_row(&sql, params![order_id], |row| {
+        let mut fields = HashMap::new();
+        for (index, column) in columns.iter().enumerate() {
+            if let Some(value) = row.get::<_, Option<String>>(index + 3)? {
+                if !value.trim().is_empty() {
+                    fields.insert(column.to_string(), value);
+                }
+            }
+        }
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?, fields))
+    })
+    .optional()
+    .map_err(|e| format!("load source order: {e}"))?
+    .ok_or_else(|| format!("Order not found: {order_id}"))
+}
+
+/// Build the `order_create` payload for a duplicate of `order_id` (a local
+/// id). Fails only when the source is missing, the menu was never synced,
+/// or none of its lines can be sold any more.
+pub fn build_payload(
+    conn: &Connection,
+    order_id: &str,
+    now: DateTime<Local>,
+) -> Result<DuplicatePlan, String> {
+    let (order_number, items_json, order_type, fields) = load_source(conn, order_id)?;
+    let menu = MenuSections {
+        subcategories: combos::read_section(conn, "subcategories"),
+        categories: combos::read_section(conn, "categories"),
+        ingredients: combos::read_section(conn, "ingredients"),
+    };
+    if menu.subcategories.is_empty() {
+        return Err("Menu is not synced yet; cannot price a duplicate order".into());
+    }
+    let combo_menu = ComboMenu::load(conn);
+    let rule = RoundingRule::from_settings(conn);
+
+    let source_items: Vec<Value> = serde_json::from_str::<Value>(&items_json)
+        .ok()
+        .and_then(|value| value.as_array().cloned())
+        .unwrap_or_default();
+    let mut skipped_items = Vec::new();
+    let items: Vec<Value> = source_items
+        .iter()
+        .filter_map(|source| {
+            if combos::is_combo_line(source) {
+                duplicate_combo_line(
+                    source,
+                    &combo_menu,
+                    &order_type,
+                    now,
+                    rule,
+                    &mut skipped_items,
+                )
+            } else {
+                duplicate_menu_line(source, &menu, &order_type, rule, &mut skipped_items)
+            }
+        })
+        .collect();
+    if items.is_empty() {
+        return Err(format!(
+            "None of the items on order {} are available on the current menu",
+            order_number.as_deref().unwrap_or(order_id)
+        ));
+    }
+
+    let mut payload = Map::new();
+    payload.insert("orderType".to_string(), json!(order_type));
+    payload.insert("items".to_string(), Value::Array(items));
+    let is_delivery = order_type.eq_ignore_ascii_case("delivery");
+    for (column, key) in CUSTOMER_FIELDS.iter().chain(DELIVERY_FIELDS) {
+        if !is_delivery && DELIVERY_FIELDS.iter().any(|(c, _)| c == column) {
+            continue;
+        }
+        if let Some(value) = fields.get(*column) {
+            payload.insert(key.to_string(), json!(value));
+        }
+    }
+
+    Ok(DuplicatePlan {
+        source_order_id: order_id.to_string(),
+        source_order_number: order_number,
+        payload: Value::Object(payload),
+        skipped_items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn cache(conn: &Connection, key: &str, data: Value) {
+        conn.execute(
+            "INSERT INTO menu_cache (cache_key, data, updated_at) VALUES (?1, ?2, datetime('now'))",
+            params![key, data.to_string()],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn duplicate_reprices_from_menu_and_reports_dropped_lines() {
+        let conn = test_conn();
+        cache(
+            &conn,
+            "categories",
+            json!([{ "id": "cat-1", "name": "Mains", "is_active": true }]),
+        );
+        cache(
+            &conn,
+            "subcategories",
+            json!([
+                { "id": "burger", "name": "Burger", "category_id": "cat-1",
+                  "base_price": 9.0, "delivery_price": 10.0, "is_available": true },
+                { "id": "fries", "name": "Fries", "category_id": "cat-1",
+                  "base_price": 3.0, "is_available": false }
+            ]),
+        );
+        cache(
+            &conn,
+            "ingredients",
+            json!([
+                { "id": "cheese", "name": "Cheese", "price": 1.0, "is_available": true },
+                { "id": "bacon", "name": "Bacon", "price": 1.5, "is_available": false }
+            ]),
+        );
+        conn.execute(
+            "INSERT INTO orders (id, order_number, items, total_amount, status, order_type,
+                                 customer_name, customer_phone, delivery_address, driver_id,
+                                 discount_amount, created_at, updated_at)
+             VALUES ('src-1', 'ORD-7', ?1, 20.0, 'completed', 'delivery',
+                     'Maria', '6900000000', 'Main St 1', 'driver-1', 2.0,
+                     datetime('now'), datetime('now'))",
+            params![json!([
+                { "menu_item_id": "burger", "name": "Burger", "quantity": 2,
+                  "unit_price": 7.0, "total_price": 14.0, "discount": 1.0,
+                  "customizations": [
+                      { "ingredient": { "id": "cheese", "name": "Cheese", "price": 0.5 }, "quantity": 1 },
+                      { "ingredient": { "id": "bacon", "name": "Bacon", "price": 1.0 }, "quantity": 1 }
+                  ] },
+                { "menu_item_id": "fries", "name": "Fries", "quantity": 1, "unit_price": 3.0 },
+                { "menu_item_id": "gone", "name": "Old special", "quantity": 1, "unit_price": 5.0 },
+                { "is_manual": true, "name": "Open price", "quantity": 1, "unit_price": 4.0 }
+            ])
+            .to_string()],
+        )
+        .unwrap();
+
+        let plan = build_payload(&conn, "src-1", Local::now()).unwrap();
+        let payload = &plan.payload;
+        assert_eq!(payload["orderType"], "delivery");
+        assert_eq!(payload["customerName"], "Maria");
+        assert_eq!(payload["deliveryAddress"], "Main St 1");
+        assert!(payload.get("driverId").is_none());
+        assert!(payload.get("discountAmount").is_none());
+
+        let items = payload["items"].as_array().unwrap();
+        assert_eq!(items.len(), 2);
+        // Delivery price 10 + cheese 1, bacon dropped.
+        assert_eq!(items[0]["unit_price"], 11.0);
+        assert_eq!(items[0]["total_price"], 22.0);
+        assert!(items[0].get("discount").is_none());
+        assert_eq!(items[0]["customizations"].as_array().unwrap().len(), 1);
+        assert_eq!(items[1]["name"], "Open price");
+        assert_eq!(items[1]["total_price"], 4.0);
+
+        let reasons: Vec<(&str, &str)> = plan
+            .skipped_items
+            .iter()
+            .map(|s| (s["name"].as_str().unwrap(), s["reason"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                ("Bacon", SKIP_UNAVAILABLE),
+                ("Fries", SKIP_UNAVAILABLE),
+                ("Old special", SKIP_NOT_ON_MENU),
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_fails_when_nothing_can_be_sold() {
+        let conn = test_conn();
+        conn.execute(
+            "INSERT INTO orders (id, items, total_amount, status, order_type, created_at, updated_at)
+             VALUES ('src-2', ?1, 5.0, 'completed', 'pickup', datetime('now'), datetime('now'))",
+            params![json!([{ "menu_item_id": "gone", "name": "Gone", "quantity": 1 }]).to_string()],
+        )
+        .unwrap();
+        assert!(build_payload(&conn, "src-2", Local::now())
+            .unwrap_err()
+            .contains("not synced"));
+        cache(
+            &conn,
+            "subcategories",
+            json!([{ "id": "other", "name": "Other", "base_price": 1.0 }]),
+        );
+        assert!(build_payload(&conn, "src-2", Local::now())
+            .unwrap_err()
+            .contains("None of the items"));
+        assert!(build_payload(&conn, "missing", Local::now()).is_err());
+    }
+}
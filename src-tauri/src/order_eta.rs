@@ -0,0 +1,309 @@
+//! Estimated-time changes after an order was approved.
+//!
+//! `order_update_eta` moves an order's `estimated_time` (minutes from
+//! confirmation) to a new value or by a delta. Each change is recorded on
+//! the order timeline as an `eta_updated` event with the old and new value,
+//! the reason code and the acting staff member, so the timeline is the ETA
+//! history. The change is also queued as its own `order_eta_updates` sync
+//! row typed `eta_update`, so the server can tell the customer instead of
+//! seeing a generic order update.
+//!
+//! An ETA may not be cut below the minutes already elapsed since the order
+//! was confirmed: the customer would be told the order is late on arrival.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::order_events;
+use crate::reasons::{self, ReasonAction, ResolvedReason};
+use crate::{value_i64, value_str};
+
+/// `parity_sync_queue.table_name` of an ETA change.
+pub const SYNC_ENTITY: &str = "order_eta_updates";
+/// `type` of the synced payload.
+pub const SYNC_TYPE: &str = "eta_update";
+/// Longest ETA accepted, in minutes.
+pub const MAX_ETA_MINUTES: i64 = 24 * 60;
+
+/// Statuses whose ETA can no longer change.
+const CLOSED_STATUSES: [&str; 4] = ["completed", "delivered", "cancelled", "refunded"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Adjustment {
+    /// New ETA in minutes.
+    Set(i64),
+    /// Minutes added to (or, negative, taken off) the current ETA.
+    Delta(i64),
+}
+
+impl Adjustment {
+    /// `estimatedTime` sets the ETA; `deltaMinutes` moves it.
+    pub fn from_payload(payload: &Value) -> Result<Self, String> {
+        if let Some(minutes) = value_i64(payload, &["estimatedTime", "estimated_time"]) {
+            return Ok(Self::Set(minutes));
+        }
+        match value_i64(payload, &["deltaMinutes", "delta_minutes"]) {
+            Some(0) => Err("deltaMinutes must not be zero".into()),
+            Some(delta) => Ok(Self::Delta(delta)),
+            None => Err("Missing estimatedTime or deltaMinutes".into()),
+        }
+    }
+
+    fn apply(self, current: Option<i64>) -> i64 {
+        match self {
+            Self::Set(minutes) => minutes,
+            Self::Delta(delta) => current.unwrap_or(0) + delta,
+        }
+    }
+}
+
+/// Resolve the reason for an ETA change; the code is required.
+pub fn reason(conn: &Connection, payload: &Value) -> Result<ResolvedReason, String> {
+    reasons::require(
+        conn,
+        ReasonAction::EtaChange,
+        value_str(payload, &["reasonCode", "reason_code"]).as_deref(),
+        value_str(payload, &["reason", "notes"]).as_deref(),
+    )
+}
+
+struct OrderEta {
+    status: String,
+    estimated_time: Option<i64>,
+    confirmed_at: String,
+    supabase_id: Option<String>,
+}
+
+fn minutes_since(start: &str, now: DateTime<Utc>) -> i64 {
+    DateTime::parse_from_rfc3339(start)
+        .map(|at| (now - at.with_timezone(&Utc)).num_minutes().max(0))
+        .unwrap_or(0)
+}
+
+/// Change one order's ETA, record it on the timeline and queue the typed
+/// sync row. The caller owns the surrounding transaction.
+pub fn update(
+    conn: &Connection,
+    order_id: &str,
+    adjustment: Adjustment,
+    reason: &ResolvedReason,
+    actor: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Value, String> {
+    let order = conn
+        .query_row(
+            "SELECT status, estimated_time, COALESCE(confirmed_at, created_at), supabase_id
+             FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| {
+                Ok(OrderEta {
+                    status: row.get(0)?,
+                    estimated_time: row.get(1)?,
+                    confirmed_at: row.get(2)?,
+                    supabase_id: row.get(3)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| format!("load order eta: {e}"))?
+        .ok_or_else(|| format!("Order not found: {order_id}"))?;
+    if CLOSED_STATUSES.contains(&order.status.as_str()) {
+        return Err(format!(
+            "Order {order_id} is {}; its ETA can no longer change",
+            order.status
+        ));
+    }
+
+    let previous = order.estimated_time;
+    let estimated_time = adjustment.apply(previous);
+    if !(1..=MAX_ETA_MINUTES).contains(&estimated_time) {
+        return Err(format!(
+            "ETA must be between 1 and {MAX_ETA_MINUTES} minutes, got {estimated_time}"
+        ));
+    }
+    let elapsed = minutes_since(&order.confirmed_at, now);
+    if previous.is_some_and(|previous| estimated_time < previous) && estimated_time < elapsed {
+        return Err(format!(
+            "ETA of {estimated_time} min for order {order_id} is below the {elapsed} min already elapsed"
+        ));
+    }
+
+    let changed_at = now.to_rfc3339();
+    conn.execute(
+        "UPDATE orders SET estimated_time = ?1, updated_at = ?2 WHERE id = ?3",
+        params![estimated_time, changed_at, order_id],
+    )
+    .map_err(|e| format!("update order eta: {e}"))?;
+
+    let delta = previous.map(|previous| estimated_time - previous);
+    order_events::append(
+        conn,
+        order_id,
+        order_events::ETA_UPDATED,
+        actor,
+        json!({
+            "from": previous,
+            "to": estimated_time,
+            "deltaMinutes": delta,
+            "reasonCode": reason.code,
+            "reason": reason.text,
+        }),
+    );
+
+    let payload = json!({
+        "type": SYNC_TYPE,
+        "orderId": order_id,
+        "supabaseId": order.supabase_id,
+        "previousEstimatedTime": previous,
+        "estimatedTime": estimated_time,
+        "estimated_time": estimated_time,
+        "deltaMinutes": delta,
+        "reasonCode": reason.code,
+        "reason": reason.text,
+        "staffId": actor,
+        "changedAt": changed_at,
+    });
+    crate::sync_queue::enqueue_payload_item(
+        conn,
+        SYNC_ENTITY,
+        &Uuid::new_v4().to_string(),
+        "INSERT",
+        &payload,
+        Some(0),
+        Some(SYNC_ENTITY),
+        None,
+        None,
+    )
+    .map_err(|e| format!("enqueue eta update sync: {e}"))?;
+
+    Ok(json!({
+        "orderId": order_id,
+        "previousEstimatedTime": previous,
+        "estimatedTime": estimated_time,
+        "deltaMinutes": delta,
+        "elapsedMinutes": elapsed,
+        "reasonCode": reason.code,
+        "changedAt": changed_at,
+    }))
+}
+
+/// Apply the same adjustment to every order, stopping at the first one that
+/// is rejected. Run inside one transaction so a rejection leaves all the
+/// orders as they were.
+pub fn update_many(
+    conn: &Connection,
+    order_ids: &[String],
+    adjustment: Adjustment,
+    reason: &ResolvedReason,
+    actor: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Vec<Value>, String> {
+    order_ids
+        .iter()
+        .map(|order_id| update(conn, order_id, adjustment, reason, actor, now))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn insert_order(conn: &Connection, id: &str, confirmed_at: &str, eta: i64) {
+        conn.execute(
+            "INSERT INTO orders (id, items, total_amount, status, estimated_time, confirmed_at,
+                                 sync_status, created_at, updated_at)
+             VALUES (?1, '[]', 10.0, 'preparing', ?2, ?3, 'synced', ?3, ?3)",
+            params![id, eta, confirmed_at],
+        )
+        .unwrap();
+    }
+
+    fn reason(conn: &Connection) -> ResolvedReason {
+        super::reason(conn, &json!({ "reasonCode": "kitchen_backlog" })).unwrap()
+    }
+
+    #[test]
+    fn eta_change_is_recorded_on_the_timeline_and_queued_as_eta_update() {
+        let conn = test_conn();
+        let now = Utc::now();
+        let confirmed = (now - Duration::minutes(20)).to_rfc3339();
+        insert_order(&conn, "order-1", &confirmed, 25);
+        let reason = reason(&conn);
+
+        let changed = update(
+            &conn,
+            "order-1",
+            Adjustment::Delta(15),
+            &reason,
+            Some("staff-1"),
+            now,
+        )
+        .unwrap();
+        assert_eq!(changed["previousEstimatedTime"], 25);
+        assert_eq!(changed["estimatedTime"], 40);
+
+        let timeline = order_events::timeline(&conn, "order-1").unwrap();
+        let event = timeline.last().unwrap();
+        assert_eq!(event["eventType"], order_events::ETA_UPDATED);
+        assert_eq!(event["actorStaffId"], "staff-1");
+        assert_eq!(event["summary"]["from"], 25);
+        assert_eq!(event["summary"]["to"], 40);
+        assert_eq!(event["summary"]["reasonCode"], "kitchen_backlog");
+
+        let data: String = conn
+            .query_row(
+                "SELECT data FROM parity_sync_queue WHERE table_name = ?1",
+                params![SYNC_ENTITY],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let data: Value = serde_json::from_str(&data).unwrap();
+        assert_eq!(data["type"], SYNC_TYPE);
+        assert_eq!(data["estimatedTime"], 40);
+
+        let cut = update(&conn, "order-1", Adjustment::Set(15), &reason, None, now);
+        assert!(cut.unwrap_err().contains("already elapsed"));
+        assert!(update(&conn, "order-1", Adjustment::Set(22), &reason, None, now).is_ok());
+    }
+
+    #[test]
+    fn bulk_update_rolls_back_when_one_order_is_rejected() {
+        let conn = test_conn();
+        let now = Utc::now();
+        let confirmed = (now - Duration::minutes(30)).to_rfc3339();
+        insert_order(&conn, "order-a", &confirmed, 50);
+        insert_order(&conn, "order-b", &confirmed, 35);
+        let reason = reason(&conn);
+        let ids = vec!["order-a".to_string(), "order-b".to_string()];
+
+        conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+        let result = update_many(&conn, &ids, Adjustment::Delta(-10), &reason, None, now);
+        assert!(
+            result.is_err(),
+            "order-b would drop to 25 of 30 elapsed minutes"
+        );
+        conn.execute_batch("ROLLBACK").unwrap();
+
+        let etas: Vec<i64> = conn
+            .prepare("SELECT estimated_time FROM orders ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(etas, vec![50, 35]);
+
+        let changed = update_many(&conn, &ids, Adjustment::Delta(15), &reason, None, now).unwrap();
+        assert_eq!(changed.len(), 2);
+        assert_eq!(changed[1]["estimatedTime"], 50);
+    }
+}
@@ -0,0 +1,166 @@
+//! Per-order activity timeline.
+//!
+//! Order mutations append a row to `order_events` so support can answer
+//! "what happened to this order?": status changes, item edits, driver
+//! assignment, payments, refunds, print jobs, course fires, bill splits,
+//! returns, price refreshes, allergy flag updates, service charge waivers,
+//! ETA changes and duplication from an earlier order, each with the acting
+//! staff member, the terminal and a small JSON summary. The table has no
+//! foreign key to `orders`, so events survive order deletion for audit.
+//!
+//! [`append`] never fails from the caller's point of view — a timeline write
+//! must not break the order flow it describes — and only logs on error.
+
+use rusqlite::{params, Connection};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::storage;
+
+pub const STATUS_CHANGED: &str = "status_changed";
+pub const ITEMS_EDITED: &str = "items_edited";
+pub const APPROVED: &str = "approved";
+pub const AUTO_APPROVED: &str = "auto_approved";
+pub const DECLINED: &str = "declined";
+pub const DRIVER_ASSIGNED: &str = "driver_assigned";
+pub const PAYMENT_RECORDED: &str = "payment_recorded";
+pub const PAYMENT_VOIDED: &str = "payment_voided";
+pub const REFUND_RECORDED: &str = "refund_recorded";
+pub const PRINT_ENQUEUED: &str = "print_enqueued";
+pub const RECEIPT_REPRINTED: &str = "receipt_reprinted";
+pub const RECEIPT_DELIVERY_QUEUED: &str = "receipt_delivery_queued";
+pub const RECEIPT_DELIVERED: &str = "receipt_delivered";
+pub const COURSE_FIRED: &str = "course_fired";
+pub const DUPLICATED_FROM: &str = "duplicated_from";
+pub const BILL_SPLIT: &str = "bill_split";
+pub const RETURN_CREATED: &str = "return_created";
+pub const PRICES_REFRESHED: &str = "prices_refreshed";
+pub const ALLERGY_FLAGS_UPDATED: &str = "allergy_flags_updated";
+pub const TAB_OPENED: &str = "tab_opened";
+pub const TAB_CLOSED: &str = "tab_closed";
+pub const TAB_SHIFT_CLOSE_OVERRIDDEN: &str = "tab_shift_close_overridden";
+pub const SERVICE_CHARGE_WAIVED: &str = "service_charge_waived";
+pub const ETA_UPDATED: &str = "eta_updated";
+
+/// Append one event to the order's timeline. Errors are logged, not returned.
+pub fn append(
+    conn: &Connection,
+    order_id: &str,
+    event_type: &str,
+    actor_staff_id: Option<&str>,
+    summary: Value,
+) {
+    let terminal_id = storage::get_credential("terminal_id");
+    let actor_staff_id = actor_staff_id.map(str::trim).filter(|id| !id.is_empty());
+    if let Err(e) = conn.execute(
+        "INSERT INTO order_events
+            (order_id, event_type, actor_staff_id, terminal_id, summary, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))",
+        params![
+            order_id,
+            event_type,
+            actor_staff_id,
+            terminal_id,
+            summary.to_string()
+        ],
+    ) {
+        warn!(order_id = %order_id, event_type = %event_type, error = %e, "order event append failed");
+    }
+}
+
+/// Append an event for the order that owns `payment_id`.
+pub fn append_for_payment(
+    conn: &Connection,
+    payment_id: &str,
+    event_type: &str,
+    actor_staff_id: Option<&str>,
+    summary: Value,
+) {
+    match conn.query_row(
+        "SELECT order_id FROM order_payments WHERE id = ?1",
+        params![payment_id],
+        |row| row.get::<_, String>(0),
+    ) {
+        Ok(order_id) => append(conn, &order_id, event_type, actor_staff_id, summary),
+        Err(e) => {
+            warn!(payment_id = %payment_id, event_type = %event_type, error = %e, "order event append skipped: payment not found");
+        }
+    }
+}
+
+/// The order's timeline, oldest first.
+pub fn timeline(conn: &Connection, order_id: &str) -> Result<Vec<Value>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, event_type, actor_staff_id, terminal_id, summary, created_at
+             FROM order_events WHERE order_id = ?1 ORDER BY id ASC",
+        )
+        .map_err(|e| format!("read order events: {e}"))?;
+    let rows = stmt
+        .query_map(params![order_id], |row| {
+            let summary: String = row.get(4)?;
+            Ok(serde_json::json!({
+                "id": row.get::<_, i64>(0)?,
+                "orderId": order_id,
+                "eventType": row.get::<_, String>(1)?,
+                "actorStaffId": row.get::<_, Option<String>>(2)?,
+                "terminalId": row.get::<_, Option<String>>(3)?,
+                "summary": serde_json::from_str::<Value>(&summary).unwrap_or(Value::Null),
+                "createdAt": row.get::<_, String>(5)?,
+            }))
+        })
+        .map_err(|e| format!("read order events: {e}"))?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("read order events: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeline_is_ordered_and_survives_order_deletion() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn.execute(
+            "INSERT INTO orders (id, items, total_amount, status, created_at, updated_at)
+             VALUES ('ord-1', '[]', 10.0, 'pending', datetime('now'), datetime('now'))",
+            [],
+        )
+        .unwrap();
+
+        append(
+            &conn,
+            "ord-1",
+            STATUS_CHANGED,
+            Some("staff-1"),
+            serde_json::json!({ "from": "pending", "to": "preparing" }),
+        );
+        append(
+            &conn,
+            "ord-1",
+            PRINT_ENQUEUED,
+            None,
+            serde_json::json!({ "entityType": "kitchen_ticket" }),
+        );
+        conn.execute("DELETE FROM orders WHERE id = 'ord-1'", [])
+            .unwrap();
+
+        let events = timeline(&conn, "ord-1").unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["eventType"], STATUS_CHANGED);
+        assert_eq!(events[0]["actorStaffId"], "staff-1");
+        assert_eq!(events[0]["summary"]["to"], "preparing");
+        assert_eq!(events[1]["eventType"], PRINT_ENQUEUED);
+        assert!(events[1]["actorStaffId"].is_null());
+    }
+
+    #[test]
+    fn append_logs_instead_of_failing() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        // No schema: the insert fails and must not panic or propagate.
+        append(&conn, "ord-1", STATUS_CHANGED, None, Value::Null);
+        append_for_payment(&conn, "pay-1", PAYMENT_VOIDED, None, Value::Null);
+        assert!(timeline(&conn, "ord-1").is_err());
+    }
+}
@@ -0,0 +1,445 @@
+//! Cross-terminal order edit locks.
+//!
+//! Two terminals on the same branch can open the same dine-in ticket; without
+//! a lock the last `order_update_items` wins silently. A terminal takes a
+//! short-lived lock when staff open an order for editing, and the mutating
+//! order/payment commands refuse to run while a *different* terminal holds a
+//! fresh one. Locks are announced to the admin API through the parity queue
+//! (`order_locks` entity) and mirrored back from other terminals via
+//! realtime. Every lock carries an `expires_at`; an expired row is treated as
+//! absent, so a crashed terminal cannot hold an order hostage.
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{db, resolve_order_id, storage, sync_queue};
+
+pub const LOCKED_ERROR_CODE: &str = "order_locked";
+const DEFAULT_LOCK_TTL_SECS: i64 = 120;
+const MIN_LOCK_TTL_SECS: i64 = 15;
+const MAX_LOCK_TTL_SECS: i64 = 1800;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderLock {
+    pub order_id: String,
+    pub terminal_id: String,
+    pub staff_id: Option<String>,
+    pub acquired_at: String,
+    pub expires_at: String,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AcquireOutcome {
+    Acquired(OrderLock),
+    /// Another terminal already holds a fresh lock.
+    Held(OrderLock),
+}
+
+fn parse_ts(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|ts| ts.with_timezone(&Utc))
+}
+
+fn is_fresh(lock: &OrderLock, now: DateTime<Utc>) -> bool {
+    parse_ts(&lock.expires_at).is_some_and(|expires_at| expires_at > now)
+}
+
+pub fn lock_ttl(conn: &Connection) -> ChronoDuration {
+    let secs = db::get_setting(conn, "orders", "lock_ttl_secs")
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .unwrap_or(DEFAULT_LOCK_TTL_SECS)
+        .clamp(MIN_LOCK_TTL_SECS, MAX_LOCK_TTL_SECS);
+    ChronoDuration::seconds(secs)
+}
+
+/// This terminal's id, as used for lock ownership.
+pub fn local_terminal_id() -> String {
+    storage::get_credential("terminal_id").unwrap_or_default()
+}
+
+fn read_lock(conn: &Connection, order_id: &str) -> Result<Option<OrderLock>, String> {
+    conn.query_row(
+        "SELECT order_id, terminal_id, staff_id, acquired_at, expires_at, source
+         FROM order_locks WHERE order_id = ?1",
+        params![order_id],
+        |row| {
+            Ok(OrderLock {
+                order_id: row.get(0)?,
+                terminal_id: row.get(1)?,
+                staff_id: row.get(2)?,
+                acquired_at: row.get(3)?,
+                expires_at: row.get(4)?,
+                source: row.get(5)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("read order lock: {e}"))
+}
+
+/// The current lock on `order_id`, dropping the row if it has expired.
+pub fn current_lock(
+    conn: &Connection,
+    order_id: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<OrderLock>, String> {
+    match read_lock(conn, order_id)? {
+        Some(lock) if is_fresh(&lock, now) => Ok(Some(lock)),
+        Some(_) => {
+            conn.execute(
+                "DELETE FROM order_locks WHERE order_id = ?1",
+                params![order_id],
+            )
+            .map_err(|e| format!("purge expired order lock: {e}"))?;
+            Ok(None)
+        }
+        None => Ok(None),
+    }
+}
+
+/// A fresh lock held by a terminal other than `terminal_id`, if any.
+pub fn blocking_lock(
+    conn: &Connection,
+    order_id: &str,
+    terminal_id: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<OrderLock>, String> {
+    Ok(current_lock(conn, order_id, now)?.filter(|lock| lock.terminal_id != terminal_id))
+}
+
+pub fn acquire_lock(
+    conn: &Connection,
+    order_id: &str,
+    staff_id: Option<&str>,
+    terminal_id: &str,
+    ttl: ChronoDuration,
+    now: DateTime<Utc>,
+) -> Result<AcquireOutcome, String> {
+    if let Some(lock) = blocking_lock(conn, order_id, terminal_id, now)? {
+        return Ok(AcquireOutcome::Held(lock));
+    }
+    // Re-acquiring our own lock refreshes it but keeps the original
+    // acquisition time.
+    let acquired_at = read_lock(conn, order_id)?
+        .filter(|lock| lock.terminal_id == terminal_id)
+        .map(|lock| lock.acquired_at)
+        .unwrap_or_else(|| now.to_rfc3339());
+    let lock = OrderLock {
+        order_id: order_id.to_string(),
+        terminal_id: terminal_id.to_string(),
+        staff_id: staff_id.map(str::to_string),
+        acquired_at,
+        expires_at: (now + ttl).to_rfc3339(),
+        source: "local".to_string(),
+    };
+    conn.execute(
+        "INSERT INTO order_locks (order_id, terminal_id, staff_id, acquired_at, expires_at, source)
+         VALUES (?1, ?2, ?3, ?4, ?5, 'local')
+         ON CONFLICT(order_id) DO UPDATE SET
+             terminal_id = excluded.terminal_id,
+             staff_id = excluded.staff_id,
+             acquired_at = excluded.acquired_at,
+             expires_at = excluded.expires_at,
+             source = 'local'",
+        params![
+            lock.order_id,
+            lock.terminal_id,
+            lock.staff_id,
+            lock.acquired_at,
+            lock.expires_at
+        ],
+    )
+    .map_err(|e| format!("acquire order lock: {e}"))?;
+    Ok(AcquireOutcome::Acquired(lock))
+}
+
+/// Release this terminal's lock. Locks held by other terminals are left
+/// alone; returns whether a row was removed.
+pub fn release_lock(conn: &Connection, order_id: &str, terminal_id: &str) -> Result<bool, String> {
+    let removed = conn
+        .execute(
+            "DELETE FROM order_locks WHERE order_id = ?1 AND terminal_id = ?2",
+            params![order_id, terminal_id],
+        )
+        .map_err(|e| format!("release order lock: {e}"))?;
+    Ok(removed > 0)
+}
+
+/// Queue the lock (or its release) for the admin API so other terminals
+/// see it. Only the newest state matters, so any unsent row for the same
+/// order is replaced. Orders that never reached the server have nothing to
+/// announce.
+pub fn enqueue_lock_sync(
+    conn: &Connection,
+    order_id: &str,
+    lock: Option<&OrderLock>,
+    terminal_id: &str,
+) -> Result<(), String> {
+    let remote_id: Option<String> = conn
+        .query_row(
+            "SELECT NULLIF(TRIM(COALESCE(supabase_id, '')), '') FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("resolve remote order id for lock: {e}"))?
+        .flatten();
+    let Some(remote_id) = remote_id else {
+        return Ok(());
+    };
+
+    sync_queue::clear_unsynced_items(conn, "order_locks", &remote_id)?;
+    let branch_id = storage::get_credential("branch_id");
+    let (operation, payload) = match lock {
+        Some(lock) => (
+            "INSERT",
+            json!({
+                "order_id": remote_id,
+                "branch_id": branch_id,
+                "terminal_id": lock.terminal_id,
+                "staff_id": lock.staff_id,
+                "acquired_at": lock.acquired_at,
+                "expires_at": lock.expires_at,
+            }),
+        ),
+        None => (
+            "DELETE",
+            json!({
+                "order_id": remote_id,
+                "branch_id": branch_id,
+                "terminal_id": terminal_id,
+            }),
+        ),
+    };
+    sync_queue::enqueue_payload_item(
+        conn,
+        "order_locks",
+        &remote_id,
+        operation,
+        &payload,
+        Some(0),
+        Some("orders"),
+        Some("server-wins"),
+        Some(1),
+    )?;
+    Ok(())
+}
+
+/// Mirror a lock row announced by another terminal (realtime
+/// `order_locks` change). `deleted` clears the mirrored row. Returns the
+/// local order id when something changed.
+pub fn apply_remote_lock(
+    conn: &Connection,
+    record: &Value,
+    deleted: bool,
+    local_terminal_id: &str,
+) -> Result<Option<String>, String> {
+    let field = |key: &str| {
+        record
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let (Some(remote_order_id), Some(terminal_id)) = (field("order_id"), field("terminal_id"))
+    else {
+        return Ok(None);
+    };
+    // Our own locks echo back through realtime; the local row is the
+    // source of truth for those.
+    if terminal_id == local_terminal_id {
+        return Ok(None);
+    }
+    let Some(order_id) = resolve_order_id(conn, &remote_order_id) else {
+        return Ok(None);
+    };
+
+    let changed = if deleted {
+        conn.execute(
+            "DELETE FROM order_locks WHERE order_id = ?1 AND terminal_id = ?2",
+            params![order_id, terminal_id],
+        )
+        .map_err(|e| format!("clear remote order lock: {e}"))?
+    } else {
+        let Some(expires_at) = field("expires_at").filter(|value| parse_ts(value).is_some()) else {
+            return Ok(None);
+        };
+        let acquired_at = field("acquired_at").unwrap_or_else(|| Utc::now().to_rfc3339());
+        conn.execute(
+            "INSERT INTO order_locks (order_id, terminal_id, staff_id, acquired_at, expires_at, source)
+             VALUES (?1, ?2, ?3, ?4, ?5, 'remote')
+             ON CONFLICT(order_id) DO UPDATE SET
+                 terminal_id = excluded.terminal_id,
+                 staff_id = excluded.staff_id,
+                 acquired_at = excluded.acquired_at,
+                 expires_at = excluded.expires_at,
+                 source = 'remote'",
+            params![order_id, terminal_id, field("staff_id"), acquired_at, expires_at],
+        )
+        .map_err(|e| format!("mirror remote order lock: {e}"))?
+    };
+    Ok((changed > 0).then_some(order_id))
+}
+
+/// Structured rejection returned by mutating commands when another
+/// terminal holds the order.
+pub fn build_locked_response(lock: &OrderLock) -> Value {
+    let error = format!(
+        "Order is being edited on terminal {} until {}",
+        lock.terminal_id, lock.expires_at
+    );
+    json!({
+        "success": false,
+        "errorCode": LOCKED_ERROR_CODE,
+        "error": error,
+        "message": error,
+        "lockedBy": lock,
+    })
+}
+
+/// Guard for mutating commands: `Ok(Some(response))` means the caller must
+/// return `response` instead of applying the change.
+pub fn guard_order_unlocked(conn: &Connection, order_id: &str) -> Result<Option<Value>, String> {
+    Ok(
+        blocking_lock(conn, order_id, &local_terminal_id(), Utc::now())?
+            .as_ref()
+            .map(build_locked_response),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        conn.execute(
+            "INSERT INTO orders (id, supabase_id, items, total_amount, status, created_at, updated_at)
+             VALUES ('ord-1', 'remote-1', '[]', 0, 'pending', datetime('now'), datetime('now'))",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-05-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            + ChronoDuration::seconds(secs)
+    }
+
+    #[test]
+    fn other_terminal_is_blocked_until_ttl_expires() {
+        let conn = test_conn();
+        let ttl = ChronoDuration::seconds(60);
+        let first = acquire_lock(&conn, "ord-1", Some("staff-a"), "term-a", ttl, at(0)).unwrap();
+        assert!(matches!(first, AcquireOutcome::Acquired(_)));
+
+        match acquire_lock(&conn, "ord-1", Some("staff-b"), "term-b", ttl, at(30)).unwrap() {
+            AcquireOutcome::Held(lock) => {
+                assert_eq!(lock.terminal_id, "term-a");
+                assert_eq!(lock.staff_id.as_deref(), Some("staff-a"));
+            }
+            other => panic!("expected held lock, got {other:?}"),
+        }
+        assert!(blocking_lock(&conn, "ord-1", "term-a", at(30))
+            .unwrap()
+            .is_none());
+        assert!(blocking_lock(&conn, "ord-1", "term-b", at(30))
+            .unwrap()
+            .is_some());
+
+        // After the TTL the stale lock is ignored and purged.
+        assert!(blocking_lock(&conn, "ord-1", "term-b", at(61))
+            .unwrap()
+            .is_none());
+        assert!(read_lock(&conn, "ord-1").unwrap().is_none());
+        assert!(matches!(
+            acquire_lock(&conn, "ord-1", None, "term-b", ttl, at(62)).unwrap(),
+            AcquireOutcome::Acquired(_)
+        ));
+    }
+
+    #[test]
+    fn refresh_keeps_acquired_at_and_release_is_owner_only() {
+        let conn = test_conn();
+        let ttl = ChronoDuration::seconds(60);
+        acquire_lock(&conn, "ord-1", None, "term-a", ttl, at(0)).unwrap();
+        let AcquireOutcome::Acquired(refreshed) =
+            acquire_lock(&conn, "ord-1", None, "term-a", ttl, at(40)).unwrap()
+        else {
+            panic!("owner refresh should succeed");
+        };
+        assert_eq!(refreshed.acquired_at, at(0).to_rfc3339());
+        assert_eq!(refreshed.expires_at, at(100).to_rfc3339());
+
+        assert!(!release_lock(&conn, "ord-1", "term-b").unwrap());
+        assert!(release_lock(&conn, "ord-1", "term-a").unwrap());
+        assert!(read_lock(&conn, "ord-1").unwrap().is_none());
+    }
+
+    #[test]
+    fn lock_sync_replaces_unsent_rows() {
+        let conn = test_conn();
+        let ttl = ChronoDuration::seconds(60);
+        let AcquireOutcome::Acquired(lock) =
+            acquire_lock(&conn, "ord-1", None, "term-a", ttl, at(0)).unwrap()
+        else {
+            panic!("acquire should succeed");
+        };
+        enqueue_lock_sync(&conn, "ord-1", Some(&lock), "term-a").unwrap();
+        enqueue_lock_sync(&conn, "ord-1", None, "term-a").unwrap();
+
+        let rows: Vec<(String, String)> = conn
+            .prepare(
+                "SELECT record_id, operation FROM parity_sync_queue
+                 WHERE table_name = 'order_locks'",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(rows, vec![("remote-1".to_string(), "DELETE".to_string())]);
+    }
+
+    #[test]
+    fn remote_locks_are_mirrored_and_own_echoes_ignored() {
+        let conn = test_conn();
+        let record = json!({
+            "order_id": "remote-1",
+            "terminal_id": "term-b",
+            "staff_id": "staff-b",
+            "expires_at": (Utc::now() + ChronoDuration::seconds(60)).to_rfc3339(),
+        });
+        assert_eq!(
+            apply_remote_lock(&conn, &record, false, "term-b").unwrap(),
+            None
+        );
+        assert_eq!(
+            apply_remote_lock(&conn, &record, false, "term-a").unwrap(),
+            Some("ord-1".to_string())
+        );
+        let lock = blocking_lock(&conn, "ord-1", "term-a", Utc::now())
+            .unwrap()
+            .expect("mirrored lock");
+        assert_eq!(lock.source, "remote");
+        let response = build_locked_response(&lock);
+        assert_eq!(response["errorCode"], LOCKED_ERROR_CODE);
+        assert_eq!(response["lockedBy"]["terminalId"], "term-b");
+
+        assert_eq!(
+            apply_remote_lock(&conn, &record, true, "term-a").unwrap(),
+            Some("ord-1".to_string())
+        );
+        assert!(read_lock(&conn, "ord-1").unwrap().is_none());
+    }
+}
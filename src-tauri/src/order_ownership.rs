@@ -832,7 +832,7 @@ pub fn repair_historical_pickup_financial_attribution(
         "SELECT o.id, {financial_expr}
          FROM orders o
          JOIN staff_shifts ss ON ss.id = o.staff_shift_id
-         WHERE (?1 = '' OR o.branch_id = ?1 OR o.branch_id IS NULL)
+         WHERE (o.branch_id = ?1 OR o.branch_id IS NULL)
            AND COALESCE(o.is_ghost, 0) = 0
            AND COALESCE(o.order_type, 'pickup') != 'delivery'
            AND o.staff_shift_id IS NOT NULL
@@ -0,0 +1,578 @@
+//! Orders ingested straight from delivery platforms (Wolt, efood, ...).
+//!
+//! Until now a platform order only reached the terminal when the admin cloud
+//! forwarded it, by which point the platform-specific fields were gone.
+//! `order_ingest_external` takes the platform's own JSON, runs it through the
+//! normalizer for that platform and feeds the result into the regular
+//! create-order pipeline. Wolt has a dedicated normalizer; every other
+//! platform goes through [`normalize_generic`], which accepts a payload
+//! already close to our order shape.
+//!
+//! Orders are deduplicated on `(plugin, external_plugin_order_id)` and the
+//! original JSON is kept in `orders.external_raw_payload` for audit. When such
+//! an order changes status locally, [`enqueue_status_ack`] queues an
+//! `external_order_ack` row carrying the platform-specific callback so the
+//! admin side can acknowledge the order on the platform.
+
+mod wolt;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Map, Value};
+
+use crate::money::Cents;
+
+/// `sync_queue` table name and module type for platform callbacks.
+pub const ACK_ENTITY: &str = "external_order_ack";
+
+/// A platform order mapped onto the create-order payload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizedOrder {
+    pub plugin: String,
+    pub external_id: String,
+    /// Ready for `create_order`; carries `plugin`, `externalPluginOrderId`
+    /// and a `clientRequestId` derived from both.
+    pub payload: Value,
+}
+
+/// Normalize a platform identifier: trimmed, lowercase, and limited to
+/// `[a-z0-9_-]` since it ends up in the `plugin` column and in queue rows.
+pub fn platform_id(raw: &str) -> Result<String, String> {
+    let platform = raw.trim().to_ascii_lowercase();
+    if platform.is_empty() {
+        return Err("Missing platform identifier".into());
+    }
+    if !platform
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!("Invalid platform identifier: {raw}"));
+    }
+    Ok(platform)
+}
+
+/// Map a raw platform payload onto our order structure.
+pub fn normalize(platform: &str, raw: &Value) -> Result<NormalizedOrder, String> {
+    let plugin = platform_id(platform)?;
+    if !raw.is_object() {
+        return Err("External order payload must be a JSON object".into());
+    }
+    let (external_id, mut payload) = match plugin.as_str() {
+        "wolt" => wolt::normalize(raw)?,
+        _ => normalize_generic(raw)?,
+    };
+    if let Some(obj) = payload.as_object_mut() {
+        obj.insert("plugin".into(), Value::String(plugin.clone()));
+        obj.insert(
+            "externalPluginOrderId".into(),
+            Value::String(external_id.clone()),
+        );
+        // Makes `create_order`'s idempotency guard catch a duplicate that
+        // slips past the lookup in `find_existing`.
+        obj.insert(
+            "clientRequestId".into(),
+            Value::String(format!("{plugin}:{external_id}")),
+        );
+    }
+    Ok(NormalizedOrder {
+        plugin,
+        external_id,
+        payload,
+    })
+}
+
+/// Local id of an already ingested order, if any.
+pub fn find_existing(
+    conn: &Connection,
+    plugin: &str,
+    external_id: &str,
+) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT id FROM orders
+         WHERE plugin = ?1 AND external_plugin_order_id = ?2
+         LIMIT 1",
+        params![plugin, external_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("query external order: {e}"))
+}
+
+pub fn store_raw_payload(conn: &Connection, order_id: &str, raw: &Value) -> Result<(), String> {
+    conn.execute(
+        "UPDATE orders SET external_raw_payload = ?1 WHERE id = ?2",
+        params![raw.to_string(), order_id],
+    )
+    .map_err(|e| format!("store external order payload: {e}"))?;
+    Ok(())
+}
+
+/// Queue the platform callback for a status change on an ingested order.
+/// Orders that did not come through `order_ingest_external` (no raw payload)
+/// are left alone: the admin cloud already owns those. Returns whether a row
+/// was queued.
+pub fn enqueue_status_ack(
+    conn: &Connection,
+    order_id: &str,
+    status: &str,
+    cancellation_reason: Option<&str>,
+    estimated_time: Option<i64>,
+) -> Result<bool, String> {
+    let row: Option<(String, String)> = conn
+        .query_row(
+            "SELECT plugin, external_plugin_order_id FROM orders
+             WHERE id = ?1
+               AND plugin IS NOT NULL
+               AND external_plugin_order_id IS NOT NULL
+               AND external_raw_payload IS NOT NULL",
+            params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("query external order identity: {e}"))?;
+    let Some((plugin, external_id)) = row else {
+        return Ok(false);
+    };
+    let Some(payload) = build_ack(
+        &plugin,
+        &external_id,
+        order_id,
+        status,
+        cancellation_reason,
+        estimated_time,
+    ) else {
+        return Ok(false);
+    };
+    crate::sync_queue::enqueue_payload_item(
+        conn,
+        ACK_ENTITY,
+        order_id,
+        "INSERT",
+        &payload,
+        Some(0),
+        Some(ACK_ENTITY),
+        None,
+        None,
+    )?;
+    Ok(true)
+}
+
+/// Platform-specific callback for a local status change, or `None` when the
+/// platform has nothing to hear about this transition.
+pub fn build_ack(
+    plugin: &str,
+    external_id: &str,
+    order_id: &str,
+    status: &str,
+    cancellation_reason: Option<&str>,
+    estimated_time: Option<i64>,
+) -> Option<Value> {
+    let callback = match plugin {
+        "wolt" => wolt::ack_callback(external_id, status, cancellation_reason, estimated_time)?,
+        _ => json!({
+            "status": status,
+            "cancellationReason": cancellation_reason,
+            "estimatedTime": estimated_time,
+        }),
+    };
+    Some(json!({
+        "plugin": plugin,
+        "externalOrderId": external_id,
+        "orderId": order_id,
+        "status": status,
+        "callback": callback,
+    }))
+}
+
+/// Mapper for platforms without a dedicated normalizer. Accepts our own
+/// camelCase/snake_case order keys plus a few common aliases (`customer`
+/// object, `address` object, `total`); amounts are in major units.
+pub fn normalize_generic(raw: &Value) -> Result<(String, Value), String> {
+    let external_id = id_str(
+        raw,
+        &[
+            "externalOrderId",
+            "external_order_id",
+            "orderId",
+            "order_id",
+            "id",
+        ],
+    )
+    .ok_or("External order is missing an id")?;
+
+    let customer = raw.get("customer").unwrap_or(&Value::Null);
+    let address = raw
+        .get("deliveryAddress")
+        .or_else(|| raw.get("delivery_address"))
+        .or_else(|| raw.get("address"))
+        .unwrap_or(&Value::Null);
+
+    let raw_items = raw
+        .get("items")
+        .and_then(Value::as_array)
+        .filter(|items| !items.is_empty())
+        .ok_or("External order has no items")?;
+    let items: Vec<Value> = raw_items
+        .iter()
+        .map(|item| {
+            let quantity = number(item, &["quantity", "count", "qty"]).unwrap_or(1.0);
+            let unit_price = number(item, &["unitPrice", "unit_price", "price"]);
+            let total_price = number(item, &["totalPrice", "total_price"])
+                .or_else(|| unit_price.map(|price| price * quantity));
+            let menu_item_id = id_str(
+                item,
+                &["menuItemId", "menu_item_id", "posId", "pos_id", "sku"],
+            );
+            let mut line = item_line(
+                menu_item_id,
+                crate::value_str(item, &["name", "title"]),
+                quantity,
+                unit_price.or_else(|| total_price.map(|total| total / quantity.max(1.0))),
+                total_price,
+                crate::value_str(item, &["notes", "comment", "instructions"]),
+            );
+            if let Some(modifiers) = item
+                .get("modifiers")
+                .or_else(|| item.get("customizations"))
+                .or_else(|| item.get("options"))
+                .filter(|v| !v.is_null())
+            {
+                line.insert("customizations".into(), modifiers.clone());
+            }
+            Value::Object(line)
+        })
+        .collect();
+
+    let subtotal = number(raw, &["subtotal", "subTotal"])
+        .unwrap_or_else(|| items.iter().filter_map(|i| i["totalPrice"].as_f64()).sum());
+    let delivery_fee = number(raw, &["deliveryFee", "delivery_fee"]).unwrap_or(0.0);
+    let discount = number(raw, &["discountAmount", "discount_amount", "discount"]).unwrap_or(0.0);
+    let service_charge = number(raw, &["serviceCharge", "service_charge", "serviceFee"]);
+    let commission = number(
+        raw,
+        &["platformCommission", "platform_commission", "commission"],
+    );
+    let total = number(raw, &["totalAmount", "total_amount", "total"])
+        .unwrap_or(subtotal + delivery_fee + service_charge.unwrap_or(0.0) - discount);
+
+    let mut payload = Map::new();
+    payload.insert(
+        "orderType".into(),
+        json!(order_type(
+            crate::value_str(raw, &["orderType", "order_type", "type", "fulfillment"]).as_deref()
+        )),
+    );
+    insert_opt(
+        &mut payload,
+        "customerName",
+        crate::value_str(raw, &["customerName", "customer_name"])
+            .or_else(|| crate::value_str(customer, &["name", "fullName"])),
+    );
+    insert_opt(
+        &mut payload,
+        "customerPhone",
+        crate::value_str(raw, &["customerPhone", "customer_phone"])
+            .or_else(|| crate::value_str(customer, &["phone", "phoneNumber"])),
+    );
+    insert_opt(
+        &mut payload,
+        "customerEmail",
+        crate::value_str(raw, &["customerEmail", "customer_email"])
+            .or_else(|| crate::value_str(customer, &["email"])),
+    );
+    if let Some(line) = address.as_str().map(str::trim).filter(|s| !s.is_empty()) {
+        payload.insert("deliveryAddress".into(), json!(line));
+    } else {
+        insert_opt(
+            &mut payload,
+            "deliveryAddress",
+            crate::value_str(address, &["street", "streetAddress", "line1"]),
+        );
+        insert_opt(
+            &mut payload,
+            "deliveryCity",
+            crate::value_str(address, &["city"]),
+        );
+        insert_opt(
+            &mut payload,
+            "deliveryPostalCode",
+            crate::value_str(address, &["postalCode", "postal_code", "zip"]),
+        );
+        insert_opt(
+            &mut payload,
+            "deliveryFloor",
+            crate::value_str(address, &["floor", "apartment"]),
+        );
+        insert_opt(
+            &mut payload,
+            "deliveryNotes",
+            crate::value_str(address, &["notes", "instructions"]),
+        );
+    }
+    insert_opt(
+        &mut payload,
+        "specialInstructions",
+        crate::value_str(raw, &["specialInstructions", "notes", "comment"]),
+    );
+    payload.insert("items".into(), Value::Array(items));
+    payload.insert("subtotal".into(), json!(money(subtotal)));
+    payload.insert("deliveryFee".into(), json!(money(delivery_fee)));
+    payload.insert("discountAmount".into(), json!(money(discount)));
+    if let Some(service_charge) = service_charge {
+        payload.insert("serviceCharge".into(), json!(money(service_charge)));
+    }
+    if let Some(commission) = commission {
+        payload.insert("platformCommission".into(), json!(money(commission)));
+    }
+    payload.insert("totalAmount".into(), json!(money(total)));
+    let paid = raw.get("paid").and_then(Value::as_bool).unwrap_or_else(|| {
+        crate::value_str(raw, &["paymentStatus", "payment_status"]).as_deref() == Some("paid")
+    });
+    payload.insert(
+        "paymentStatus".into(),
+        json!(if paid { "paid" } else { "pending" }),
+    );
+    insert_opt(
+        &mut payload,
+        "paymentMethod",
+        crate::value_str(raw, &["paymentMethod", "payment_method"]),
+    );
+    Ok((external_id, Value::Object(payload)))
+}
+
+/// One canonical item line. Lines without a POS menu id are marked manual so
+/// the menu-cache check in `create_order` does not reject them.
+fn item_line(
+    menu_item_id: Option<String>,
+    name: Option<String>,
+    quantity: f64,
+    unit_price: Option<f64>,
+    total_price: Option<f64>,
+    notes: Option<String>,
+) -> Map<String, Value> {
+    let mut line = Map::new();
+    match menu_item_id {
+        Some(id) => {
+            line.insert("menuItemId".into(), json!(id));
+        }
+        None => {
+            line.insert("isManual".into(), json!(true));
+        }
+    }
+    line.insert(
+        "name".into(),
+        json!(name.unwrap_or_else(|| "External item".into())),
+    );
+    line.insert("quantity".into(), json!(quantity));
+    if let Some(price) = unit_price {
+        line.insert("unitPrice".into(), json!(money(price)));
+    }
+    if let Some(total) = total_price {
+        line.insert("totalPrice".into(), json!(money(total)));
+    }
+    insert_opt(&mut line, "notes", notes);
+    line
+}
+
+fn order_type(raw: Option<&str>) -> &'static str {
+    match raw
+        .map(|s| s.trim().to_ascii_lowercase())
+        .unwrap_or_default()
+        .as_str()
+    {
+        "pickup" | "takeaway" | "take-away" | "collection" => "pickup",
+        "dine-in" | "dine_in" | "dinein" | "eatin" | "eat-in" => "dine-in",
+        _ => "delivery",
+    }
+}
+
+fn insert_opt(map: &mut Map<String, Value>, key: &str, value: Option<String>) {
+    if let Some(value) = value {
+        map.insert(key.to_string(), Value::String(value));
+    }
+}
+
+/// Ids arrive as strings on some platforms and integers on others.
+fn id_str(v: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| match v.get(*key)? {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+fn number(v: &Value, keys: &[&str]) -> Option<f64> {
+    keys.iter().find_map(|key| match v.get(*key)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    })
+}
+
+fn money(major: f64) -> f64 {
+    Cents::round_half_even(major).to_f64_dp2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Value {
+        let raw = match name {
+            "wolt" => include_str!("order_plugins/fixtures/wolt_order.json"),
+            "generic" => include_str!("order_plugins/fixtures/generic_order.json"),
+            _ => unreachable!(),
+        };
+        serde_json::from_str(raw).expect("fixture parses")
+    }
+
+    #[test]
+    fn wolt_fixture_normalizes_to_canonical_order() {
+        let order = normalize("Wolt", &fixture("wolt")).expect("normalize");
+        assert_eq!(order.plugin, "wolt");
+        assert_eq!(order.external_id, "64f1c0a2e5b7d1a9c3f2e811");
+
+        let p = &order.payload;
+        assert_eq!(p["plugin"], "wolt");
+        assert_eq!(p["externalPluginOrderId"], "64f1c0a2e5b7d1a9c3f2e811");
+        assert_eq!(p["clientRequestId"], "wolt:64f1c0a2e5b7d1a9c3f2e811");
+        assert_eq!(p["orderType"], "delivery");
+        assert_eq!(p["customerName"], "Maria P.");
+        assert_eq!(p["customerPhone"], "+306900000000");
+        assert_eq!(p["deliveryAddress"], "Ermou 12");
+        assert_eq!(p["deliveryCity"], "Athens");
+        assert_eq!(p["deliveryPostalCode"], "10563");
+        assert_eq!(p["deliveryFloor"], "3rd floor");
+        assert_eq!(p["specialInstructions"], "Wolt W-4F2A: Ring twice");
+        assert_eq!(p["subtotal"], 21.0);
+        assert_eq!(p["deliveryFee"], 2.5);
+        assert_eq!(p["totalAmount"], 23.5);
+        assert_eq!(p["paymentStatus"], "paid");
+
+        let items = p["items"].as_array().expect("items");
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0]["menuItemId"],
+            "11111111-1111-4111-8111-111111111111"
+        );
+        assert_eq!(items[0]["quantity"], 2.0);
+        assert_eq!(items[0]["unitPrice"], 9.0);
+        assert_eq!(items[0]["totalPrice"], 18.0);
+        assert_eq!(items[0]["customizations"][0]["name"], "Extra cheese");
+        assert_eq!(items[0]["customizations"][0]["price"], 1.0);
+        assert_eq!(items[1]["isManual"], true);
+        assert!(items[1].get("menuItemId").is_none());
+        assert_eq!(items[1]["totalPrice"], 3.0);
+
+        // The canonical payload must pass the same checks as a till order.
+        let mut payload = order.payload.clone();
+        crate::sync::order_schema::validate_order(
+            &mut payload,
+            crate::sync::order_schema::ValidationMode::Standard,
+        )
+        .expect("canonical payload validates");
+    }
+
+    #[test]
+    fn generic_fixture_normalizes_and_derives_totals() {
+        let order = normalize("efood", &fixture("generic")).expect("normalize");
+        assert_eq!(order.plugin, "efood");
+        assert_eq!(order.external_id, "998877");
+
+        let p = &order.payload;
+        assert_eq!(p["orderType"], "pickup");
+        assert_eq!(p["customerName"], "Nikos");
+        assert_eq!(p["customerEmail"], "nikos@example.com");
+        assert_eq!(p["specialInstructions"], "No onions");
+        assert_eq!(p["items"][0]["menuItemId"], "sku-souvlaki");
+        assert_eq!(p["items"][0]["totalPrice"], 7.0);
+        assert_eq!(p["items"][1]["isManual"], true);
+        assert_eq!(p["items"][1]["unitPrice"], 1.5);
+        assert_eq!(p["subtotal"], 8.5);
+        assert_eq!(p["totalAmount"], 8.5);
+        assert_eq!(p["paymentStatus"], "pending");
+        assert!(p.get("serviceCharge").is_none());
+        assert!(p.get("platformCommission").is_none());
+
+        let mut raw = fixture("generic");
+        raw["serviceFee"] = json!("0.50");
+        raw["commission"] = json!(2.55);
+        let p = normalize("efood", &raw).expect("normalize").payload;
+        assert_eq!(p["serviceCharge"], 0.5);
+        assert_eq!(p["platformCommission"], 2.55);
+        assert_eq!(p["totalAmount"], 9.0);
+    }
+
+    #[test]
+    fn normalize_rejects_bad_input() {
+        assert!(normalize("", &fixture("generic")).is_err());
+        assert!(normalize("wo lt", &fixture("wolt")).is_err());
+        assert!(normalize("wolt", &json!([])).is_err());
+        assert!(normalize("wolt", &json!({ "items": [] })).is_err());
+        assert!(normalize("efood", &json!({ "id": "1" })).is_err());
+    }
+
+    #[test]
+    fn wolt_ack_maps_local_statuses_to_platform_actions() {
+        let ack = |status: &str| build_ack("wolt", "ext-1", "local-1", status, None, Some(20));
+        assert_eq!(ack("confirmed").unwrap()["callback"]["action"], "accept");
+        assert_eq!(
+            ack("confirmed").unwrap()["callback"]["body"]["preparation_time_minutes"],
+            20
+        );
+        assert_eq!(ack("ready").unwrap()["callback"]["action"], "ready");
+        assert_eq!(ack("delivered").unwrap()["callback"]["action"], "delivered");
+        assert!(ack("out_for_delivery").is_none());
+
+        let reject = build_ack(
+            "wolt",
+            "ext-1",
+            "local-1",
+            "cancelled",
+            Some("Closed"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(reject["callback"]["action"], "reject");
+        assert_eq!(reject["callback"]["path"], "/orders/ext-1/reject");
+        assert_eq!(reject["callback"]["body"]["reason"], "Closed");
+
+        let generic = build_ack("efood", "9", "local-2", "preparing", None, None).unwrap();
+        assert_eq!(generic["plugin"], "efood");
+        assert_eq!(generic["callback"]["status"], "preparing");
+    }
+
+    #[test]
+    fn status_ack_is_queued_only_for_ingested_orders() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations_for_test(&conn);
+        crate::sync_queue::create_tables(&conn).unwrap();
+        for (id, raw) in [("o-ingested", Some("{}")), ("o-forwarded", None)] {
+            conn.execute(
+                "INSERT INTO orders (id, items, total_amount, status, order_type,
+                    created_at, updated_at, plugin, external_plugin_order_id,
+                    external_raw_payload)
+                 VALUES (?1, '[]', 10.0, 'pending', 'delivery',
+                    datetime('now'), datetime('now'), 'wolt', ?1, ?2)",
+                params![id, raw],
+            )
+            .unwrap();
+        }
+
+        assert!(enqueue_status_ack(&conn, "o-ingested", "confirmed", None, None).unwrap());
+        assert!(!enqueue_status_ack(&conn, "o-forwarded", "confirmed", None, None).unwrap());
+        assert!(!enqueue_status_ack(&conn, "o-ingested", "out_for_delivery", None, None).unwrap());
+
+        let (table, module, data): (String, String, String) = conn
+            .query_row(
+                "SELECT table_name, module_type, data FROM parity_sync_queue",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(table, ACK_ENTITY);
+        assert_eq!(module, ACK_ENTITY);
+        let data: Value = serde_json::from_str(&data).unwrap();
+        assert_eq!(data["externalOrderId"], "o-ingested");
+        assert_eq!(data["callback"]["action"], "accept");
+    }
+}
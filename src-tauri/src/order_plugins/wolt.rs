@@ -0,0 +1,169 @@
+//! Wolt order JSON (`/orders/{id}` on the merchant API).
+//!
+//! Amounts are objects in minor units (`{"amount": 2350, "currency": "EUR"}`),
+//! items carry the POS id we registered with Wolt as `pos_id`, and the order
+//! is always paid on the platform. Status callbacks are the merchant API's
+//! `accept` / `reject` / `ready` / `delivered` actions.
+
+use serde_json::{json, Map, Value};
+
+use super::{id_str, insert_opt, item_line, money, order_type};
+
+pub(super) fn normalize(raw: &Value) -> Result<(String, Value), String> {
+    let external_id = id_str(raw, &["id"]).ok_or("Wolt order is missing an id")?;
+    let delivery = raw.get("delivery").unwrap_or(&Value::Null);
+    let location = delivery.get("location").unwrap_or(&Value::Null);
+
+    let raw_items = raw
+        .get("items")
+        .and_then(Value::as_array)
+        .filter(|items| !items.is_empty())
+        .ok_or("Wolt order has no items")?;
+    let items: Vec<Value> = raw_items
+        .iter()
+        .map(|item| {
+            let quantity = item.get("count").and_then(Value::as_f64).unwrap_or(1.0);
+            let unit_price = amount(item.get("unit_price"))
+                .or_else(|| amount(item.get("base_price")));
+            let total_price = amount(item.get("total_price"))
+                .or_else(|| unit_price.map(|price| price * quantity));
+            let mut line = item_line(
+                id_str(item, &["pos_id"]),
+                crate::value_str(item, &["name"]),
+                quantity,
+                unit_price,
+                total_price,
+                None,
+            );
+            let options: Vec<Value> = item
+                .get("options")
+                .and_then(Value::as_array)
+                .map(|options| {
+                    options
+                        .iter()
+                        .map(|option| {
+                            json!({
+                                "name": crate::value_str(option, &["value", "name"]),
+                                "group": crate::value_str(option, &["name"]),
+                                "quantity": option.get("count").and_then(Value::as_f64).unwrap_or(1.0),
+                                "price": amount(option.get("price")).map(money).unwrap_or(0.0),
+                                "ingredientId": id_str(option, &["pos_id"]),
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            if !options.is_empty() {
+                line.insert("customizations".into(), Value::Array(options));
+            }
+            Value::Object(line)
+        })
+        .collect();
+
+    let subtotal = amount(raw.get("items_price"))
+        .unwrap_or_else(|| items.iter().filter_map(|i| i["totalPrice"].as_f64()).sum());
+    let delivery_fee = amount(delivery.get("fee")).unwrap_or(0.0);
+    let total = amount(raw.get("price")).unwrap_or(subtotal + delivery_fee);
+    let discount = (subtotal + delivery_fee - total).max(0.0);
+
+    let mut payload = Map::new();
+    payload.insert(
+        "orderType".into(),
+        json!(order_type(crate::value_str(delivery, &["type"]).as_deref())),
+    );
+    insert_opt(
+        &mut payload,
+        "customerName",
+        crate::value_str(raw, &["consumer_name"]),
+    );
+    insert_opt(
+        &mut payload,
+        "customerPhone",
+        crate::value_str(raw, &["consumer_phone_number"]),
+    );
+    insert_opt(
+        &mut payload,
+        "deliveryAddress",
+        crate::value_str(location, &["street_address"]),
+    );
+    insert_opt(
+        &mut payload,
+        "deliveryCity",
+        crate::value_str(location, &["city"]),
+    );
+    insert_opt(
+        &mut payload,
+        "deliveryPostalCode",
+        crate::value_str(location, &["post_code"]),
+    );
+    insert_opt(
+        &mut payload,
+        "deliveryFloor",
+        crate::value_str(location, &["apartment"]),
+    );
+    if let Some(coordinates) = location.get("coordinates") {
+        if let (Some(lat), Some(lon)) = (
+            coordinates.get("lat").and_then(Value::as_f64),
+            coordinates.get("lon").and_then(Value::as_f64),
+        ) {
+            payload.insert("deliveryLatitude".into(), json!(lat));
+            payload.insert("deliveryLongitude".into(), json!(lon));
+        }
+    }
+    // Couriers quote Wolt's order number, so keep it in front of staff.
+    let instructions = match (
+        crate::value_str(raw, &["order_number"]),
+        crate::value_str(raw, &["consumer_comment"]),
+    ) {
+        (Some(number), Some(comment)) => Some(format!("Wolt {number}: {comment}")),
+        (Some(number), None) => Some(format!("Wolt {number}")),
+        (None, comment) => comment,
+    };
+    insert_opt(&mut payload, "specialInstructions", instructions);
+    payload.insert("items".into(), Value::Array(items));
+    payload.insert("subtotal".into(), json!(money(subtotal)));
+    payload.insert("deliveryFee".into(), json!(money(delivery_fee)));
+    payload.insert("discountAmount".into(), json!(money(discount)));
+    payload.insert("totalAmount".into(), json!(money(total)));
+    payload.insert("paymentStatus".into(), json!("paid"));
+    payload.insert("paymentMethod".into(), json!("other"));
+    Ok((external_id, Value::Object(payload)))
+}
+
+/// Merchant API call for a local status change. Wolt couriers handle the
+/// leg between pickup and drop-off, so `out_for_delivery` has no callback.
+pub(super) fn ack_callback(
+    external_id: &str,
+    status: &str,
+    cancellation_reason: Option<&str>,
+    estimated_time: Option<i64>,
+) -> Option<Value> {
+    let (action, body) = match status {
+        "confirmed" | "preparing" => (
+            "accept",
+            match estimated_time {
+                Some(minutes) => json!({ "preparation_time_minutes": minutes }),
+                None => json!({}),
+            },
+        ),
+        "ready" => ("ready", json!({})),
+        "delivered" | "completed" => ("delivered", json!({})),
+        "cancelled" => (
+            "reject",
+            json!({ "reason": cancellation_reason.unwrap_or("Cancelled by venue") }),
+        ),
+        _ => return None,
+    };
+    Some(json!({
+        "action": action,
+        "method": "PUT",
+        "path": format!("/orders/{external_id}/{action}"),
+        "body": body,
+    }))
+}
+
+/// `{"amount": <minor units>}` as major units.
+fn amount(value: Option<&Value>) -> Option<f64> {
+    let minor = value?.get("amount")?.as_f64()?;
+    Some(minor / 100.0)
+}
@@ -0,0 +1,585 @@
+//! Data isolation when a terminal is re-paired to a different organization.
+//!
+//! The organization that owns the local orders, customers and receipt
+//! numbering is remembered in `local_settings` (`system` /
+//! `data_organization_id`). Unlike the `terminal` identity settings it
+//! survives `clear_derived_terminal_context`, so a re-pair cannot silently
+//! inherit the previous organization's data.
+//!
+//! When the admin settings response names a different organization,
+//! [`observe`] holds the new id in a pending change instead of storing it.
+//! While a change is pending the sync loop refuses to run
+//! ([`SYNC_BLOCKED_ERROR`]) and staff cannot log in, until
+//! `organization_change_confirm` either archives and wipes the old data
+//! ([`Resolution::Wipe`]) or, after an admin PIN, re-tags it to the new
+//! organization ([`Resolution::Retag`]). Every step is written to
+//! `recovery_action_log` with both organization ids.
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{db, retention};
+
+pub const REQUIRED_EVENT: &str = "organization_change_required";
+pub const RESOLVED_EVENT: &str = "organization_change_resolved";
+pub const ARCHIVE_DIR: &str = "organization_change";
+pub const SYNC_BLOCKED_ERROR: &str = "ORGANIZATION_CHANGE_UNCONFIRMED: this terminal was paired \
+     to a different organization; confirm the change before syncing";
+pub const LOGIN_BLOCKED_ERROR: &str =
+    "This terminal was paired to a different organization. An administrator must confirm \
+     what happens to the existing data before anyone can log in.";
+
+const SETTINGS_CATEGORY: &str = "system";
+const DATA_ORGANIZATION_KEY: &str = "data_organization_id";
+const PENDING_KEY: &str = "pending_organization_change";
+const AUDIT_ACTION_ID: &str = "organization_change";
+
+/// Organization-scoped tables exported before a wipe, on top of orders, their
+/// payment rows and the shift tables.
+const ARCHIVED_EXTRA_TABLES: &[&str] = &[
+    "staff_shifts",
+    "cash_drawer_sessions",
+    "shift_expenses",
+    "drawer_transactions",
+    "z_reports",
+    "loyalty_transactions",
+    "loyalty_customers",
+    "caller_id_log",
+    "reservations",
+    "receipt_deliveries",
+    "fiscal_documents",
+    "fiscal_receipt_series",
+    "fiscal_sequence_counters",
+];
+
+/// Tables a wipe empties beyond what `clear_operational_data_inner` clears.
+const WIPED_TABLES: &[&str] = &[
+    "loyalty_transactions",
+    "loyalty_customers",
+    "loyalty_settings",
+    "caller_id_log",
+    "reservations",
+    "receipt_deliveries",
+    "fiscal_documents",
+    "fiscal_receipt_series",
+    "fiscal_sequence_counters",
+    "remote_cache",
+];
+
+/// Settings holding receipt numbering or cached customers, dropped by a wipe.
+const WIPED_SETTINGS: &[(&str, &str)] =
+    &[("orders", "order_counter"), ("local", "customer_cache_v1")];
+
+/// Tables whose rows carry the organization and move with a re-tag.
+const RETAGGED_TABLES: &[&str] = &[
+    "orders",
+    "loyalty_transactions",
+    "caller_id_log",
+    "reservations",
+    "parity_sync_queue",
+];
+
+/// Caches of the old organization's server data; a re-tag drops them and
+/// lets the new organization's data be fetched again.
+const RETAG_DROPPED_CACHES: &[&str] = &["loyalty_customers", "loyalty_settings"];
+
+/// How a pending organization change is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Wipe,
+    Retag,
+}
+
+impl Resolution {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Wipe => "wipe",
+            Self::Retag => "retag",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "wipe" | "archive_and_wipe" | "archive" => Some(Self::Wipe),
+            "retag" | "migrate" => Some(Self::Retag),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingChange {
+    pub previous_organization_id: String,
+    pub new_organization_id: String,
+    pub source: String,
+    pub detected_at: String,
+}
+
+impl PendingChange {
+    /// Renderer view; organization ids are masked.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "previousOrganizationId": crate::mask_terminal_id(&self.previous_organization_id),
+            "newOrganizationId": crate::mask_terminal_id(&self.new_organization_id),
+            "source": self.source,
+            "detectedAt": self.detected_at,
+        })
+    }
+
+    fn audit_details(&self) -> Value {
+        json!({
+            "previousOrganizationId": self.previous_organization_id,
+            "newOrganizationId": self.new_organization_id,
+            "source": self.source,
+            "detectedAt": self.detected_at,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Observation {
+    /// The organization matches the one that owns the local data.
+    Unchanged,
+    /// No organization owned the local data yet; this one now does.
+    Adopted,
+    /// A different organization; held until confirmed.
+    Pending(PendingChange),
+}
+
+/// The organization owning the local data. Installs that predate the anchor
+/// fall back to the stored terminal organization, then to the newest order.
+pub fn data_organization_id(conn: &Connection) -> Option<String> {
+    let non_empty = |value: Option<String>| {
+        value
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    non_empty(db::get_setting(
+        conn,
+        SETTINGS_CATEGORY,
+        DATA_ORGANIZATION_KEY,
+    ))
+    .or_else(|| non_empty(db::get_setting(conn, "terminal", "organization_id")))
+    .or_else(|| {
+        non_empty(
+            conn.query_row(
+                "SELECT organization_id FROM orders
+                 WHERE TRIM(COALESCE(organization_id, '')) != ''
+                 ORDER BY created_at DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .ok(),
+        )
+    })
+}
+
+pub fn pending(conn: &Connection) -> Option<PendingChange> {
+    db::get_setting(conn, SETTINGS_CATEGORY, PENDING_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+}
+
+fn store_pending(conn: &Connection, change: &PendingChange) -> Result<(), String> {
+    let raw = serde_json::to_string(change).map_err(|e| format!("serialize change: {e}"))?;
+    db::set_setting(conn, SETTINGS_CATEGORY, PENDING_KEY, &raw)
+}
+
+/// Check an organization id reported by the admin dashboard against the one
+/// owning the local data. Only [`Observation::Unchanged`] and
+/// [`Observation::Adopted`] mean the caller may store it.
+pub fn observe(
+    conn: &Connection,
+    organization_id: &str,
+    source: &str,
+    now: DateTime<Utc>,
+) -> Result<Observation, String> {
+    let incoming = organization_id.trim();
+    if incoming.is_empty() {
+        return Ok(Observation::Unchanged);
+    }
+
+    if let Some(mut change) = pending(conn) {
+        if change.previous_organization_id == incoming {
+            db::delete_setting(conn, SETTINGS_CATEGORY, PENDING_KEY)?;
+            record(
+                conn,
+                "reverted",
+                &change,
+                "Terminal paired back to the organization that owns the local data",
+                None,
+                json!({}),
+            )?;
+            return Ok(Observation::Unchanged);
+        }
+        if change.new_organization_id != incoming {
+            change.new_organization_id = incoming.to_string();
+            change.source = source.to_string();
+            change.detected_at = now.to_rfc3339();
+            store_pending(conn, &change)?;
+            detected(conn, &change)?;
+        }
+        return Ok(Observation::Pending(change));
+    }
+
+    match data_organization_id(conn) {
+        Some(current) if current != incoming => {
+            let change = PendingChange {
+                previous_organization_id: current,
+                new_organization_id: incoming.to_string(),
+                source: source.to_string(),
+                detected_at: now.to_rfc3339(),
+            };
+            store_pending(conn, &change)?;
+            detected(conn, &change)?;
+            Ok(Observation::Pending(change))
+        }
+        current => {
+            db::set_setting(conn, SETTINGS_CATEGORY, DATA_ORGANIZATION_KEY, incoming)?;
+            Ok(if current.is_some() {
+                Observation::Unchanged
+            } else {
+                Observation::Adopted
+            })
+        }
+    }
+}
+
+fn detected(conn: &Connection, change: &PendingChange) -> Result<(), String> {
+    tracing::warn!(
+        previous_organization_id = %crate::mask_terminal_id(&change.previous_organization_id),
+        new_organization_id = %crate::mask_terminal_id(&change.new_organization_id),
+        source = %change.source,
+        "Terminal paired to a different organization; holding sync until the change is confirmed"
+    );
+    record(
+        conn,
+        "detected",
+        change,
+        "Terminal paired to a different organization; sync and login are blocked",
+        None,
+        json!({}),
+    )
+}
+
+/// Fail with [`SYNC_BLOCKED_ERROR`] while an organization change is pending,
+/// so the previous organization's data never reaches the new one's API.
+pub fn ensure_sync_allowed(conn: &Connection) -> Result<(), String> {
+    match pending(conn) {
+        Some(_) => Err(SYNC_BLOCKED_ERROR.to_string()),
+        None => Ok(()),
+    }
+}
+
+fn archived_tables() -> Vec<&'static str> {
+    let mut tables = vec!["orders"];
+    tables.extend_from_slice(retention::ORDER_CASCADED_TABLES);
+    tables.extend_from_slice(ARCHIVED_EXTRA_TABLES);
+    tables
+}
+
+/// Export the previous organization's data before a wipe.
+pub fn archive(conn: &Connection, archive_dir: &Path, date: &str) -> Result<Vec<String>, String> {
+    retention::export_tables(conn, archive_dir, &archived_tables(), date)
+}
+
+fn in_transaction<T>(
+    conn: &Connection,
+    what: &str,
+    body: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    conn.execute_batch("BEGIN IMMEDIATE")
+        .map_err(|e| format!("begin {what}: {e}"))?;
+    match body() {
+        Ok(value) => {
+            conn.execute_batch("COMMIT")
+                .map_err(|e| format!("commit {what}: {e}"))?;
+            Ok(value)
+        }
+        Err(error) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            Err(error)
+        }
+    }
+}
+
+/// Drop the organization-scoped data and receipt numbering that
+/// `clear_operational_data_inner` leaves behind.
+pub fn wipe(conn: &Connection) -> Result<(), String> {
+    in_transaction(conn, "organization wipe", || {
+        for table in WIPED_TABLES.iter().copied() {
+            if !retention::table_exists(conn, table) {
+                continue;
+            }
+            conn.execute(&format!("DELETE FROM {table}"), [])
+                .map_err(|e| format!("wipe {table}: {e}"))?;
+        }
+        for (category, key) in WIPED_SETTINGS.iter().copied() {
+            db::delete_setting(conn, category, key)?;
+        }
+        Ok(())
+    })
+}
+
+/// Move the local data to the new organization. Rows without an
+/// organization are claimed too. Returns the row counts per table.
+pub fn retag(conn: &Connection, change: &PendingChange) -> Result<Value, String> {
+    let old = change.previous_organization_id.as_str();
+    let new = change.new_organization_id.as_str();
+    in_transaction(conn, "organization re-tag", || {
+        let mut counts = serde_json::Map::new();
+        for table in RETAGGED_TABLES.iter().copied() {
+            if !retention::table_exists(conn, table) {
+                continue;
+            }
+            let updated = conn
+                .execute(
+                    &format!(
+                        "UPDATE {table} SET organization_id = ?2
+                         WHERE organization_id = ?1 OR TRIM(COALESCE(organization_id, '')) = ''"
+                    ),
+                    params![old, new],
+                )
+                .map_err(|e| format!("re-tag {table}: {e}"))?;
+            counts.insert(table.to_string(), json!(updated));
+        }
+        let mut payloads = 0;
+        for (table, column) in [("sync_queue", "payload"), ("parity_sync_queue", "data")] {
+            if !retention::table_exists(conn, table) {
+                continue;
+            }
+            for path in ["$.organizationId", "$.organization_id"] {
+                payloads += conn
+                    .execute(
+                        &format!(
+                            "UPDATE {table} SET {column} = json_replace({column}, '{path}', ?2)
+                             WHERE json_valid({column})
+                               AND json_extract({column}, '{path}') = ?1"
+                        ),
+                        params![old, new],
+                    )
+                    .map_err(|e| format!("re-tag {table} payloads: {e}"))?;
+            }
+        }
+        counts.insert("queuedPayloads".into(), json!(payloads));
+        for table in RETAG_DROPPED_CACHES.iter().copied() {
+            if retention::table_exists(conn, table) {
+                conn.execute(
+                    &format!("DELETE FROM {table} WHERE organization_id = ?1"),
+                    params![old],
+                )
+                .map_err(|e| format!("drop {table} cache: {e}"))?;
+            }
+        }
+        Ok(Value::Object(counts))
+    })
+}
+
+/// Make the new organization the owner of the local data, clear the pending
+/// change and audit the resolution.
+pub fn finish(
+    conn: &Connection,
+    change: &PendingChange,
+    resolution: Resolution,
+    actor_staff_id: Option<&str>,
+    details: Value,
+) -> Result<(), String> {
+    db::set_setting(
+        conn,
+        SETTINGS_CATEGORY,
+        DATA_ORGANIZATION_KEY,
+        &change.new_organization_id,
+    )?;
+    db::set_setting(
+        conn,
+        "terminal",
+        "organization_id",
+        &change.new_organization_id,
+    )?;
+    db::delete_setting(conn, SETTINGS_CATEGORY, PENDING_KEY)?;
+    let message = match resolution {
+        Resolution::Wipe => "Previous organization's data archived and wiped",
+        Resolution::Retag => "Local data re-tagged to the new organization",
+    };
+    record(
+        conn,
+        resolution.as_str(),
+        change,
+        message,
+        actor_staff_id,
+        details,
+    )
+}
+
+/// Write one step to `recovery_action_log`, with both organization ids.
+pub fn record(
+    conn: &Connection,
+    step: &str,
+    change: &PendingChange,
+    message: &str,
+    actor_staff_id: Option<&str>,
+    details: Value,
+) -> Result<(), String> {
+    let mut payload = change.audit_details();
+    if let (Some(map), Value::Object(details)) = (payload.as_object_mut(), details) {
+        map.extend(details);
+    }
+    conn.execute(
+        "INSERT INTO recovery_action_log (
+            id, action_id, issue_code, entity_type, entity_id, success, message,
+            actor_staff_id, payload_json, created_at
+         ) VALUES (?1, ?2, ?3, 'organization', ?4, 1, ?5, ?6, ?7, ?8)",
+        params![
+            Uuid::new_v4().to_string(),
+            AUDIT_ACTION_ID,
+            step,
+            change.new_organization_id,
+            message,
+            actor_staff_id,
+            payload.to_string(),
+            Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(|e| format!("record organization change {step} in audit log: {e}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn audit_steps(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT issue_code FROM recovery_action_log
+                 WHERE action_id = 'organization_change' ORDER BY rowid",
+            )
+            .unwrap();
+        let rows = stmt.query_map([], |row| row.get(0)).unwrap();
+        rows.map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn a_different_organization_is_held_and_blocks_sync_until_reverted() {
+        let conn = test_conn();
+        let now = Utc::now();
+        assert_eq!(
+            observe(&conn, "org-a", "test", now).unwrap(),
+            Observation::Adopted
+        );
+        assert_eq!(
+            observe(&conn, "org-a", "test", now).unwrap(),
+            Observation::Unchanged
+        );
+        ensure_sync_allowed(&conn).unwrap();
+
+        let Observation::Pending(change) = observe(&conn, "org-b", "test", now).unwrap() else {
+            panic!("expected a pending change");
+        };
+        assert_eq!(change.previous_organization_id, "org-a");
+        assert_eq!(change.new_organization_id, "org-b");
+        assert_eq!(data_organization_id(&conn).as_deref(), Some("org-a"));
+        assert_eq!(ensure_sync_allowed(&conn).unwrap_err(), SYNC_BLOCKED_ERROR);
+        assert_eq!(change.to_json()["newOrganizationId"], "***rg-b");
+
+        assert_eq!(
+            observe(&conn, "org-a", "test", now).unwrap(),
+            Observation::Unchanged
+        );
+        assert!(pending(&conn).is_none());
+        assert_eq!(audit_steps(&conn), vec!["detected", "reverted"]);
+    }
+
+    #[test]
+    fn wipe_drops_customers_and_receipt_numbering() {
+        let conn = test_conn();
+        conn.execute_batch(
+            "INSERT INTO loyalty_customers (id, user_profile_id, organization_id, points_balance)
+             VALUES ('lc-1', 'profile-1', 'org-a', 10);
+             INSERT INTO fiscal_sequence_counters (branch_id, business_day_iso, last_seq)
+             VALUES ('branch-1', '2026-10-01', 41);",
+        )
+        .unwrap();
+        db::set_setting(&conn, "orders", "order_counter", "41").unwrap();
+        db::set_setting(&conn, "local", "customer_cache_v1", "[]").unwrap();
+        observe(&conn, "org-a", "test", Utc::now()).unwrap();
+        let Observation::Pending(change) = observe(&conn, "org-b", "test", Utc::now()).unwrap()
+        else {
+            panic!("expected a pending change");
+        };
+
+        wipe(&conn).unwrap();
+        finish(&conn, &change, Resolution::Wipe, None, json!({})).unwrap();
+
+        let count = |table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        assert_eq!(count("loyalty_customers"), 0);
+        assert_eq!(count("fiscal_sequence_counters"), 0);
+        assert!(db::get_setting(&conn, "orders", "order_counter").is_none());
+        assert!(db::get_setting(&conn, "local", "customer_cache_v1").is_none());
+        assert_eq!(data_organization_id(&conn).as_deref(), Some("org-b"));
+        ensure_sync_allowed(&conn).unwrap();
+        assert_eq!(audit_steps(&conn), vec!["detected", "wipe"]);
+    }
+
+    #[test]
+    fn retag_moves_orders_and_queued_payloads_to_the_new_organization() {
+        let conn = test_conn();
+        conn.execute_batch(
+            "INSERT INTO orders (id, items, total_amount, status, order_type, organization_id, created_at, updated_at)
+             VALUES ('ord-1', '[]', 10.0, 'completed', 'takeaway', 'org-a', datetime('now'), datetime('now')),
+                    ('ord-2', '[]', 12.0, 'completed', 'takeaway', NULL, datetime('now'), datetime('now'));
+             INSERT INTO sync_queue (entity_type, entity_id, operation, payload, idempotency_key)
+             VALUES ('order', 'ord-1', 'insert', '{\"organizationId\":\"org-a\"}', 'k-1');",
+        )
+        .unwrap();
+        let Observation::Pending(change) = observe(&conn, "org-b", "test", Utc::now()).unwrap()
+        else {
+            panic!("an order of org-a should anchor the data");
+        };
+
+        let counts = retag(&conn, &change).unwrap();
+        finish(
+            &conn,
+            &change,
+            Resolution::Retag,
+            Some("admin-1"),
+            counts.clone(),
+        )
+        .unwrap();
+
+        assert_eq!(counts["orders"], 2);
+        assert_eq!(counts["queuedPayloads"], 1);
+        let orgs: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM orders WHERE organization_id = 'org-b'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(orgs, 2);
+        let payload: String = conn
+            .query_row("SELECT payload FROM sync_queue", [], |row| row.get(0))
+            .unwrap();
+        assert!(payload.contains("org-b"), "{payload}");
+        assert_eq!(
+            db::get_setting(&conn, "terminal", "organization_id").as_deref(),
+            Some("org-b")
+        );
+    }
+}
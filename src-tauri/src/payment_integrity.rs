@@ -323,7 +323,7 @@ pub fn load_branch_window_payment_blockers(
         "{} FROM orders o
          WHERE {order_financial_expr} {operator} ?1
            AND (?2 IS NULL OR {order_financial_expr} <= ?2)
-           AND (?3 = '' OR o.branch_id = ?3 OR o.branch_id IS NULL)
+           AND (o.branch_id = ?3 OR o.branch_id IS NULL)
            AND COALESCE(o.is_ghost, 0) = 0
            AND o.status NOT IN ('cancelled', 'canceled', 'refunded')
            AND NOT {open_table_tab_expr}
@@ -413,10 +413,7 @@ mod tests {
         )
         .expect("pragma setup");
         db::run_migrations_for_test(&conn);
-        crate::db::DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        }
+        crate::db::DbState::new(conn, std::path::PathBuf::from(":memory:"))
     }
 
     #[test]
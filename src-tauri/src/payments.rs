@@ -11,10 +11,10 @@ use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::db::DbState;
-use crate::money::Cents;
+use crate::money::{CashRounding, Cents};
 use crate::{
     business_day, order_ownership, payment_integrity, print, printers, receipt_renderer,
-    resolve_order_id, shifts,
+    resolve_order_id, shifts, storage,
 };
 
 fn load_payment_items_for_payment(
@@ -398,6 +398,14 @@ pub(crate) struct PaymentRecordInput {
     pub requested_tip_recipient_staff_id: Option<String>,
     pub requested_tip_recipient_staff_shift_id: Option<String>,
     pub collected_by: Option<String>,
+    /// Caller confirmed that paying more than the outstanding balance is
+    /// intended; the excess is folded into change or tip.
+    pub allow_overpayment: bool,
+    /// Cash physically collected when `payments.cash_rounding` applies;
+    /// `amount` stays the exact amount settled against the order.
+    pub rounded_amount: Option<f64>,
+    /// Check of a split bill this payment settles (see [`crate::checks`]).
+    pub check_id: Option<String>,
     items: Vec<PaymentItemInput>,
 }
 
@@ -413,6 +421,10 @@ pub(crate) struct PaymentInsertOptions {
     pub sync_order_owner_with_payment: bool,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
+    /// Issue a Greek fiscal receipt for the payment (no-op unless enabled
+    /// in `fiscalization.gr`). Off for mirrors of remote payments, which
+    /// were fiscalised by the terminal that took them.
+    pub issue_fiscal_document: bool,
 }
 
 impl PaymentInsertOptions {
@@ -428,6 +440,7 @@ impl PaymentInsertOptions {
             sync_order_owner_with_payment: true,
             created_at: None,
             updated_at: None,
+            issue_fiscal_document: true,
         }
     }
 
@@ -443,6 +456,7 @@ impl PaymentInsertOptions {
             sync_order_owner_with_payment: true,
             created_at: None,
             updated_at: None,
+            issue_fiscal_document: false,
         }
     }
 }
@@ -453,6 +467,7 @@ pub(crate) struct RecordedPayment {
     pub payment_origin: String,
     pub sync_status: String,
     pub sync_state: String,
+    pub fiscal_document_number: Option<String>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -663,6 +678,16 @@ pub(crate) fn build_payment_record_input(payload: &Value) -> Result<PaymentRecor
         requested_tip_recipient_staff_shift_id: str_field(payload, "tipRecipientStaffShiftId")
             .or_else(|| str_field(payload, "tip_recipient_staff_shift_id")),
         collected_by,
+        allow_overpayment: payload
+            .get("allowOverpayment")
+            .or_else(|| payload.get("allow_overpayment"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        rounded_amount: None,
+        check_id: str_field(payload, "checkId")
+            .or_else(|| str_field(payload, "check_id"))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty()),
         items: parse_payment_items(payload),
     })
 }
@@ -671,35 +696,59 @@ pub(crate) fn load_net_paid_for_order(
     conn: &rusqlite::Connection,
     order_id: &str,
 ) -> Result<f64, String> {
+    load_net_paid_cents(conn, "order_id", order_id)
+        .map(|c| Cents::new(c).to_f64_dp2())
+        .map_err(|e| format!("load net paid for order {order_id}: {e}"))
+}
+
+/// Net paid against one check of a split bill (see [`crate::checks`]).
+pub(crate) fn load_net_paid_for_check(
+    conn: &rusqlite::Connection,
+    check_id: &str,
+) -> Result<Cents, String> {
+    load_net_paid_cents(conn, "check_id", check_id)
+        .map(Cents::new)
+        .map_err(|e| format!("load net paid for check {check_id}: {e}"))
+}
+
+/// Completed payments minus their refunds, in cents, for the payments whose
+/// `key_column` (`order_id` or `check_id`) equals `key`.
+fn load_net_paid_cents(
+    conn: &rusqlite::Connection,
+    key_column: &str,
+    key: &str,
+) -> rusqlite::Result<i64> {
+    debug_assert!(matches!(key_column, "order_id" | "check_id"));
     conn.query_row(
         // W4b: aggregate using cents-with-real-fallback shim. The shim
         // (`COALESCE(*_cents, CAST(ROUND(*_real * 100) AS INTEGER))`)
         // tolerates any row whose cents was never populated (pre-W4c
         // production rows or test fixtures). 4e removes the shim when
         // the REAL columns are dropped.
-        "SELECT COALESCE(SUM(
-            CASE
-                WHEN COALESCE(op.amount_cents, CAST(ROUND(op.amount * 100) AS INTEGER), 0)
-                     > COALESCE(refunds.refunded_cents, 0)
-                    THEN COALESCE(op.amount_cents, CAST(ROUND(op.amount * 100) AS INTEGER), 0)
-                         - COALESCE(refunds.refunded_cents, 0)
-                ELSE 0
-            END
-        ), 0)
-         FROM order_payments op
-         LEFT JOIN (
-             SELECT payment_id,
-                    SUM(COALESCE(amount_cents, CAST(ROUND(amount * 100) AS INTEGER))) AS refunded_cents
-             FROM payment_adjustments
-             WHERE adjustment_type = 'refund'
-             GROUP BY payment_id
-         ) refunds ON refunds.payment_id = op.id
-         WHERE op.order_id = ?1
-           AND op.status = 'completed'",
-        params![order_id],
-        |row| row.get::<_, i64>(0).map(|c| Cents::new(c).to_f64_dp2()),
+        &format!(
+            "SELECT COALESCE(SUM(
+                CASE
+                    WHEN COALESCE(op.amount_cents, CAST(ROUND(op.amount * 100) AS INTEGER), 0)
+                         > COALESCE(refunds.refunded_cents, 0)
+                        THEN COALESCE(op.amount_cents, CAST(ROUND(op.amount * 100) AS INTEGER), 0)
+                             - COALESCE(refunds.refunded_cents, 0)
+                    ELSE 0
+                END
+            ), 0)
+             FROM order_payments op
+             LEFT JOIN (
+                 SELECT payment_id,
+                        SUM(COALESCE(amount_cents, CAST(ROUND(amount * 100) AS INTEGER))) AS refunded_cents
+                 FROM payment_adjustments
+                 WHERE adjustment_type = 'refund'
+                 GROUP BY payment_id
+             ) refunds ON refunds.payment_id = op.id
+             WHERE op.{key_column} = ?1
+               AND op.status = 'completed'"
+        ),
+        params![key],
+        |row| row.get::<_, i64>(0),
     )
-    .map_err(|e| format!("load net paid for order {order_id}: {e}"))
 }
 
 pub(crate) fn load_order_payment_balance_snapshot(
@@ -724,6 +773,142 @@ pub(crate) fn load_order_payment_balance_snapshot(
     })
 }
 
+/// Balance the payment settles against: its check when it names one,
+/// otherwise the whole order.
+fn load_payment_balance_snapshot(
+    conn: &Connection,
+    input: &PaymentRecordInput,
+) -> Result<OrderPaymentBalanceSnapshot, String> {
+    match input.check_id.as_deref() {
+        Some(check_id) => crate::checks::balance_snapshot(conn, &input.order_id, check_id),
+        None => load_order_payment_balance_snapshot(conn, &input.order_id),
+    }
+}
+
+/// Balance view of an order for deposit flows: `paid` is every completed
+/// payment, `refunded` what has been given back on them, and `remaining`
+/// what is still owed.
+pub(crate) fn load_order_balance(conn: &Connection, order_id: &str) -> Result<Value, String> {
+    let (total_cents, payment_status): (i64, String) = conn
+        .query_row(
+            "SELECT COALESCE(total_amount_cents, CAST(ROUND(total_amount * 100) AS INTEGER), 0),
+                    COALESCE(payment_status, 'pending')
+             FROM orders
+             WHERE id = ?1",
+            params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| format!("Order not found: {order_id}"))?;
+    let (paid_cents, refunded_cents): (i64, i64) = conn
+        .query_row(
+            "SELECT
+                COALESCE((SELECT SUM(COALESCE(amount_cents, CAST(ROUND(amount * 100) AS INTEGER), 0))
+                          FROM order_payments
+                          WHERE order_id = ?1 AND status = 'completed'), 0),
+                COALESCE((SELECT SUM(COALESCE(pa.amount_cents, CAST(ROUND(pa.amount * 100) AS INTEGER), 0))
+                          FROM payment_adjustments pa
+                          JOIN order_payments op ON op.id = pa.payment_id
+                          WHERE op.order_id = ?1
+                            AND op.status = 'completed'
+                            AND pa.adjustment_type = 'refund'), 0)",
+            params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("load order balance {order_id}: {e}"))?;
+    let net_paid = Cents::round_half_even(load_net_paid_for_order(conn, order_id)?);
+    let total = Cents::new(total_cents);
+    let remaining = if net_paid >= total {
+        Cents::ZERO
+    } else {
+        total - net_paid
+    };
+    Ok(serde_json::json!({
+        "orderId": order_id,
+        "total": total.to_f64_dp2(),
+        "paid": Cents::new(paid_cents).to_f64_dp2(),
+        "refunded": Cents::new(refunded_cents).to_f64_dp2(),
+        "netPaid": net_paid.to_f64_dp2(),
+        "remaining": remaining.to_f64_dp2(),
+        "paymentStatus": payment_status,
+    }))
+}
+
+/// Where the excess of an allowed overpayment goes, from
+/// `orders.overpayment_handling`: `change` (default) hands it back, `tip`
+/// keeps it as a tip. Only cash can give change, so other methods always
+/// book the excess as tip.
+fn overpayment_handling(conn: &Connection, method: &str) -> &'static str {
+    let configured = crate::db::get_setting(conn, "orders", "overpayment_handling")
+        .map(|value| value.trim().to_ascii_lowercase());
+    if method == "cash" && configured.as_deref() != Some("tip") {
+        "change"
+    } else {
+        "tip"
+    }
+}
+
+/// Trim an explicitly allowed overpayment down to the outstanding balance
+/// and move the excess into change or tip, so the payment row never
+/// settles more than the order owes. Returns the excess and where it went.
+fn apply_allowed_overpayment(
+    conn: &Connection,
+    input: &mut PaymentRecordInput,
+) -> Result<Option<(f64, &'static str)>, String> {
+    if !input.allow_overpayment {
+        return Ok(None);
+    }
+    let snapshot = load_payment_balance_snapshot(conn, input)?;
+    let amount = Cents::round_half_even(input.amount);
+    let outstanding = Cents::round_half_even(snapshot.outstanding_amount);
+    if amount <= outstanding {
+        return Ok(None);
+    }
+    if !outstanding.is_positive() {
+        return Err(match input.check_id.as_deref() {
+            Some(check_id) => format!("Check {check_id} is already fully paid"),
+            None => format!("Order {} is already fully paid", input.order_id),
+        });
+    }
+    let excess = (amount - outstanding).to_f64_dp2();
+    let handling = overpayment_handling(conn, &input.method);
+    if handling == "change" {
+        input.cash_received = Some(input.cash_received.unwrap_or(input.amount));
+        input.change_given = Some(
+            (Cents::round_half_even(input.change_given.unwrap_or(0.0))
+                + Cents::round_half_even(excess))
+            .to_f64_dp2(),
+        );
+    } else {
+        input.tip_amount = (Cents::round_half_even(input.tip_amount)
+            + Cents::round_half_even(excess))
+        .to_f64_dp2();
+    }
+    input.amount = outstanding.to_f64_dp2();
+    Ok(Some((excess, handling)))
+}
+
+/// Round a cash tender per `payments.cash_rounding`. The payment still
+/// settles the exact amount against the order; the cash collected and the
+/// change handed back follow the rounded amount. Split tenders are separate
+/// payments, so only their cash portion is rounded.
+fn apply_cash_rounding(conn: &Connection, input: &mut PaymentRecordInput) {
+    if input.method != "cash" {
+        return;
+    }
+    let Some(rounding) = CashRounding::from_settings(conn) else {
+        return;
+    };
+    let rounded = rounding.round(Cents::round_half_even(input.amount));
+    input.rounded_amount = Some(rounded.to_f64_dp2());
+    if let Some(received) = input.cash_received {
+        input.change_given = Some(
+            (Cents::round_half_even(received) - rounded)
+                .max(Cents::ZERO)
+                .to_f64_dp2(),
+        );
+    }
+}
+
 fn should_enforce_local_outstanding_guard(
     input: &PaymentRecordInput,
     options: &PaymentInsertOptions,
@@ -752,7 +937,14 @@ fn validate_payment_amount_against_outstanding(
         return Ok(());
     }
 
-    let snapshot = load_order_payment_balance_snapshot(conn, &input.order_id)?;
+    if input.check_id.is_none() && !crate::checks::load_for_order(conn, &input.order_id)?.is_empty()
+    {
+        return Err(format!(
+            "Order {} is split into checks; pass checkId to pay one of them",
+            input.order_id
+        ));
+    }
+    let snapshot = load_payment_balance_snapshot(conn, input)?;
     // W4e: integer-cent comparison. The half-cent epsilon that the float
     // path required (Wave 2a C3) goes away because integer comparison
     // is exact by construction. Both sides round half-even at the
@@ -760,11 +952,14 @@ fn validate_payment_amount_against_outstanding(
     let input_amount_cents = Cents::round_half_even(input.amount).as_i64();
     let outstanding_cents = Cents::round_half_even(snapshot.outstanding_amount).as_i64();
     if input_amount_cents > outstanding_cents {
+        let target = match input.check_id.as_deref() {
+            Some(check_id) => format!("check {check_id} of order {}", input.order_id),
+            None => format!("order {}", input.order_id),
+        };
         return Err(format!(
-            "Payment amount {:.2} exceeds outstanding balance {:.2} for order {} (total {:.2}, settled {:.2})",
+            "Payment amount {:.2} exceeds outstanding balance {:.2} for {target} (total {:.2}, settled {:.2}); pass allowOverpayment to record the excess as change or tip",
             input.amount,
             snapshot.outstanding_amount,
-            input.order_id,
             snapshot.order_total,
             snapshot.net_paid,
         ));
@@ -828,9 +1023,15 @@ pub(crate) fn recompute_order_payment_state(
     // aggregation drift, and integer cents have neither.
     let total_paid_cents = Cents::round_half_even(total_paid).as_i64();
     let order_total_cents = Cents::round_half_even(order_total).as_i64();
+    // A split order is only paid once every one of its checks is.
+    let all_checks_paid = crate::checks::refresh_statuses(conn, order_id, now)?.unwrap_or(true);
     let new_payment_status = if total_paid_cents <= 0 {
-        "pending"
-    } else if total_paid_cents >= order_total_cents {
+        if crate::tabs::is_open(conn, order_id) {
+            crate::tabs::PAYMENT_STATUS_TAB_OPEN
+        } else {
+            "pending"
+        }
+    } else if all_checks_paid && total_paid_cents >= order_total_cents {
         "paid"
     } else {
         "partially_paid"
@@ -1204,6 +1405,11 @@ pub(crate) fn record_payment_in_connection(
         .map(|v| Cents::round_half_even(v).as_i64());
     let discount_amount_cents = Cents::round_half_even(input.discount_amount).as_i64();
     let tip_amount_cents = Cents::round_half_even(input.tip_amount).as_i64();
+    let rounded_amount_cents = input
+        .rounded_amount
+        .map(|v| Cents::round_half_even(v).as_i64());
+    let rounding_delta_cents = rounded_amount_cents.map(|rounded| rounded - amount_cents);
+    let rounding_delta = rounding_delta_cents.map(|c| Cents::new(c).to_f64_dp2());
     conn.execute(
         "INSERT INTO order_payments (
             id, order_id, method, amount, amount_cents, currency, status,
@@ -1213,11 +1419,13 @@ pub(crate) fn record_payment_in_connection(
             tip_recipient_staff_id, tip_recipient_staff_shift_id,
             payment_origin, terminal_device_id,
             remote_payment_id, staff_id, staff_shift_id, sync_status,
-            sync_state, created_at, updated_at
+            sync_state, created_at, updated_at,
+            rounded_amount, rounded_amount_cents, rounding_delta, rounding_delta_cents,
+            check_id
         ) VALUES (
             ?1, ?2, ?3, ?4, ?5, ?6, 'completed', ?7, ?8, ?9, ?10,
             ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21,
-            ?22, ?23, ?24, ?25, ?26, ?27
+            ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32
         )",
         params![
             payment_id,
@@ -1247,6 +1455,11 @@ pub(crate) fn record_payment_in_connection(
             sync_state,
             created_at,
             updated_at,
+            input.rounded_amount,
+            rounded_amount_cents,
+            rounding_delta,
+            rounding_delta_cents,
+            input.check_id,
         ],
     )
     .map_err(|e| format!("insert payment: {e}"))?;
@@ -1272,6 +1485,21 @@ pub(crate) fn record_payment_in_connection(
         .map_err(|e| format!("insert payment item: {e}"))?;
     }
 
+    let fiscal_document_number = if options.issue_fiscal_document {
+        let issuing_terminal = storage::get_credential("terminal_id")
+            .filter(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| terminal_id.clone());
+        crate::fiscal::documents::issue_payment_receipt(
+            conn,
+            &payment_id,
+            &issuing_terminal,
+            &branch_id,
+        )?
+        .map(|document| document.document_number)
+    } else {
+        None
+    };
+
     recompute_order_payment_state(conn, &input.order_id, &updated_at, &payment_id)?;
 
     if order_type.eq_ignore_ascii_case("delivery")
@@ -1336,13 +1564,24 @@ pub(crate) fn record_payment_in_connection(
         if let Some(ref sid) = resolved_shift_id {
             if input.method == "cash" {
                 // W4c dual-write: mirror total_cash_sales onto cents.
+                // Cash rounding is its own drawer line so cash sales keep
+                // matching the orders they settled.
                 conn.execute(
                     "UPDATE cash_drawer_sessions SET
                         total_cash_sales = COALESCE(total_cash_sales, 0) + ?1,
                         total_cash_sales_cents = COALESCE(total_cash_sales_cents, 0) + ?2,
+                        total_cash_rounding = COALESCE(total_cash_rounding, 0) + ?5,
+                        total_cash_rounding_cents = COALESCE(total_cash_rounding_cents, 0) + ?6,
                         updated_at = ?3
                      WHERE staff_shift_id = ?4",
-                    params![input.amount, amount_cents, updated_at, sid],
+                    params![
+                        input.amount,
+                        amount_cents,
+                        updated_at,
+                        sid,
+                        rounding_delta.unwrap_or(0.0),
+                        rounding_delta_cents.unwrap_or(0)
+                    ],
                 )
                 .map_err(|e| format!("update drawer cash_sales: {e}"))?;
             } else if input.method == "card" {
@@ -1391,6 +1630,10 @@ pub(crate) fn record_payment_in_connection(
             "changeGiven": input.change_given,
             "change_given_cents": input.change_given
                 .map(|v| Cents::round_half_even(v).as_i64()),
+            "roundedAmount": input.rounded_amount,
+            "rounded_amount_cents": rounded_amount_cents,
+            "roundingDelta": rounding_delta,
+            "rounding_delta_cents": rounding_delta_cents,
             "transactionRef": input.transaction_ref,
             "discountAmount": input.discount_amount,
             "discount_amount_cents": Cents::round_half_even(input.discount_amount).as_i64(),
@@ -1400,6 +1643,8 @@ pub(crate) fn record_payment_in_connection(
             "table_session_id": input.table_session_id,
             "seatNumber": input.seat_number,
             "seat_number": input.seat_number,
+            "checkId": input.check_id,
+            "check_id": input.check_id,
             "collectedBy": input.collected_by,
             "staffId": resolved_staff_id,
             "staffShiftId": resolved_shift_id,
@@ -1432,6 +1677,7 @@ pub(crate) fn record_payment_in_connection(
         payment_origin: input.payment_origin.clone(),
         sync_status: options.sync_status.clone(),
         sync_state,
+        fiscal_document_number,
     })
 }
 
@@ -1461,7 +1707,12 @@ pub fn record_payment(db: &DbState, payload: &Value) -> Result<Value, String> {
     conn.execute_batch("BEGIN IMMEDIATE")
         .map_err(|e| format!("begin transaction: {e}"))?;
 
-    let recorded = match record_payment_in_connection(&conn, &input, &options) {
+    let attempt = apply_allowed_overpayment(&conn, &mut input).and_then(|overpayment| {
+        apply_cash_rounding(&conn, &mut input);
+        record_payment_in_connection(&conn, &input, &options)
+            .map(|recorded| (recorded, overpayment))
+    });
+    let (recorded, overpayment) = match attempt {
         Ok(recorded) => {
             conn.execute_batch("COMMIT")
                 .map_err(|e| format!("commit: {e}"))?;
@@ -1479,6 +1730,19 @@ pub fn record_payment(db: &DbState, payload: &Value) -> Result<Value, String> {
         amount = %input.amount,
         "Payment recorded"
     );
+    crate::order_events::append(
+        &conn,
+        &input.order_id,
+        crate::order_events::PAYMENT_RECORDED,
+        input.requested_staff_id.as_deref(),
+        serde_json::json!({
+            "paymentId": recorded.payment_id,
+            "method": input.method,
+            "amount": input.amount,
+            "checkId": input.check_id,
+            "fiscalDocumentNumber": recorded.fiscal_document_number,
+        }),
+    );
 
     Ok(serde_json::json!({
         "success": true,
@@ -1486,6 +1750,20 @@ pub fn record_payment(db: &DbState, payload: &Value) -> Result<Value, String> {
         "paymentOrigin": recorded.payment_origin,
         "syncStatus": recorded.sync_status,
         "syncState": recorded.sync_state,
+        "fiscalDocumentNumber": recorded.fiscal_document_number,
+        "checkId": input.check_id,
+        "overpayment": overpayment.map(|(excess, handling)| serde_json::json!({
+            "excess": excess,
+            "recordedAs": handling,
+        })),
+        "cashRounding": input.rounded_amount.map(|rounded| serde_json::json!({
+            "exactAmount": input.amount,
+            "roundedAmount": rounded,
+            "roundingDelta": (Cents::round_half_even(rounded)
+                - Cents::round_half_even(input.amount))
+            .to_f64_dp2(),
+            "changeGiven": input.change_given,
+        })),
         "message": format!("Payment of {:.2} recorded", input.amount),
     }))
 }
@@ -2147,6 +2425,7 @@ pub fn update_payment_method(
 pub fn void_payment(
     db: &DbState,
     payment_id: &str,
+    reason_code: &str,
     reason: &str,
     voided_by: Option<&str>,
     voided_by_shift_id: Option<&str>,
@@ -2154,6 +2433,7 @@ pub fn void_payment(
     crate::refunds::void_payment_with_adjustment(
         db,
         payment_id,
+        reason_code,
         reason,
         voided_by,
         voided_by_shift_id,
@@ -2357,11 +2637,12 @@ pub fn get_receipt_preview(db: &DbState, order_id: &str) -> Result<Value, String
     let layout = print::resolve_layout_config(db, &profile, "order_receipt")?;
 
     // Render using the canonical receipt renderer
-    let html = receipt_renderer::render_html(&doc, &layout);
+    let preview = receipt_renderer::render_preview(&doc, &layout);
 
     Ok(serde_json::json!({
         "success": true,
-        "html": html,
+        "html": preview.html,
+        "text": preview.text,
     }))
 }
 
@@ -2416,10 +2697,7 @@ mod tests {
         )
         .expect("pragma setup");
         db::run_migrations_for_test(&conn);
-        DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        }
+        DbState::new(conn, std::path::PathBuf::from(":memory:"))
     }
 
     #[test]
@@ -2517,6 +2795,91 @@ mod tests {
         );
     }
 
+    #[test]
+    fn fiscal_documents_follow_payment_refund_and_void() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        crate::db::set_setting(&conn, "fiscalization.gr", "documents_enabled", "true").unwrap();
+        conn.execute(
+            "INSERT INTO orders (id, items, total_amount, total_amount_cents, status, sync_status, terminal_id, created_at, updated_at)
+             VALUES ('ord-gr', '[]', 30.0, 3000, 'pending', 'pending', 'term-gr', datetime('now'), datetime('now'))",
+            [],
+        )
+        .expect("insert order");
+        drop(conn);
+
+        let first = record_payment(
+            &db,
+            &serde_json::json!({ "orderId": "ord-gr", "method": "cash", "amount": 20.0 }),
+        )
+        .expect("record first payment");
+        assert!(first["fiscalDocumentNumber"]
+            .as_str()
+            .is_some_and(|number| number.starts_with("R-")));
+        let second = record_payment(
+            &db,
+            &serde_json::json!({ "orderId": "ord-gr", "method": "card", "amount": 10.0 }),
+        )
+        .expect("record second payment");
+        assert!(second["fiscalDocumentNumber"]
+            .as_str()
+            .is_some_and(|number| number.starts_with("R-")));
+
+        let refund = crate::refunds::refund_payment(
+            &db,
+            &serde_json::json!({
+                "paymentId": first["paymentId"],
+                "amount": 5.0,
+                "reasonCode": "other",
+                "reason": "Returned",
+            }),
+        )
+        .expect("refund");
+        assert!(refund["fiscalDocumentNumber"]
+            .as_str()
+            .is_some_and(|number| number.starts_with("C-")));
+
+        void_payment(
+            &db,
+            second["paymentId"].as_str().unwrap(),
+            "wrong_tender",
+            "Wrong card",
+            None,
+            None,
+        )
+        .expect("void");
+
+        let conn = db.conn.lock().unwrap();
+        let documents = crate::fiscal::documents::list_order_documents(&conn, "ord-gr").unwrap();
+        // Other tests swap the global terminal credential, so check the
+        // series per (terminal, series) instead of fixed numbers.
+        let summary: Vec<(String, i64)> = documents
+            .iter()
+            .map(|doc| (doc.document_type.clone(), doc.gross_cents))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("receipt".to_string(), 2000),
+                ("receipt".to_string(), 1000),
+                ("refund".to_string(), -500),
+                ("cancellation".to_string(), -1000),
+            ]
+        );
+        let mut series: std::collections::HashMap<(String, String), Vec<i64>> =
+            std::collections::HashMap::new();
+        for doc in &documents {
+            series
+                .entry((doc.terminal_id.clone(), doc.series.clone()))
+                .or_default()
+                .push(doc.number);
+        }
+        for numbers in series.values() {
+            let expected: Vec<i64> = (1..=numbers.len() as i64).collect();
+            assert_eq!(numbers, &expected, "series must be gap-free");
+        }
+    }
+
     #[test]
     fn test_record_payment_and_query() {
         let db = test_db();
@@ -2948,6 +3311,71 @@ mod tests {
         assert_eq!(payment_count, 1);
     }
 
+    #[test]
+    fn deposit_marks_order_partially_paid_and_overpayment_needs_explicit_flag() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO orders (
+                id, items, total_amount, total_amount_cents, status, payment_status, sync_status, created_at, updated_at
+             ) VALUES (
+                'ord-catering', '[]', 200.0, 20000, 'confirmed', 'pending', 'pending',
+                datetime('now'), datetime('now')
+             )",
+            [],
+        )
+        .expect("insert catering order");
+        drop(conn);
+
+        record_payment(
+            &db,
+            &serde_json::json!({ "orderId": "ord-catering", "method": "card", "amount": 60.0 }),
+        )
+        .expect("record deposit");
+        {
+            let conn = db.conn.lock().unwrap();
+            let balance = load_order_balance(&conn, "ord-catering").expect("load balance");
+            assert_eq!(balance["paymentStatus"], "partially_paid");
+            assert_eq!(balance["paid"], 60.0);
+            assert_eq!(balance["refunded"], 0.0);
+            assert_eq!(balance["remaining"], 140.0);
+        }
+
+        let error = record_payment(
+            &db,
+            &serde_json::json!({ "orderId": "ord-catering", "method": "cash", "amount": 150.0 }),
+        )
+        .expect_err("overpaying without the flag is rejected");
+        assert!(error.contains("allowOverpayment"));
+
+        let result = record_payment(
+            &db,
+            &serde_json::json!({
+                "orderId": "ord-catering",
+                "method": "cash",
+                "amount": 150.0,
+                "allowOverpayment": true
+            }),
+        )
+        .expect("overpayment with flag is recorded");
+        assert_eq!(result["overpayment"]["excess"], 10.0);
+        assert_eq!(result["overpayment"]["recordedAs"], "change");
+
+        let conn = db.conn.lock().unwrap();
+        let (amount, received, change): (f64, f64, f64) = conn
+            .query_row(
+                "SELECT amount, cash_received, change_given FROM order_payments
+                 WHERE order_id = 'ord-catering' AND method = 'cash'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .expect("load cash payment");
+        assert_eq!((amount, received, change), (140.0, 150.0, 10.0));
+        let balance = load_order_balance(&conn, "ord-catering").expect("load balance");
+        assert_eq!(balance["paymentStatus"], "paid");
+        assert_eq!(balance["remaining"], 0.0);
+    }
+
     #[test]
     fn test_sync_reconstructed_payment_bypasses_local_outstanding_guard() {
         let db = test_db();
@@ -3503,7 +3931,8 @@ mod tests {
         let void_result = void_payment(
             &db,
             &payment_id,
-            "Customer changed mind",
+            "customer_changed_mind",
+            "",
             Some("staff-1"),
             None,
         )
@@ -3612,6 +4041,171 @@ mod tests {
         assert_eq!(card_sales, 0.0, "total_card_sales should remain 0.0");
     }
 
+    fn seed_cashier_drawer(conn: &Connection, shift_id: &str) {
+        conn.execute(
+            "INSERT INTO staff_shifts (id, staff_id, role_type, check_in_time, status, sync_status, created_at, updated_at)
+             VALUES (?1, 'staff-round', 'cashier', datetime('now'), 'active', 'pending', datetime('now'), datetime('now'))",
+            params![shift_id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO cash_drawer_sessions (id, staff_shift_id, cashier_id, branch_id, terminal_id, opening_amount, opening_amount_cents, opened_at, created_at, updated_at)
+             VALUES (?1, ?1, 'staff-round', 'b1', 't1', 0.0, 0, datetime('now'), datetime('now'), datetime('now'))",
+            params![shift_id],
+        )
+        .unwrap();
+    }
+
+    fn seed_round_order(conn: &Connection, order_id: &str, shift_id: &str, total_cents: i64) {
+        conn.execute(
+            "INSERT INTO orders (id, items, total_amount, total_amount_cents, status, sync_status, staff_shift_id, created_at, updated_at)
+             VALUES (?1, '[]', ?2, ?3, 'pending', 'pending', ?4, datetime('now'), datetime('now'))",
+            params![
+                order_id,
+                Cents::new(total_cents).to_f64_dp2(),
+                total_cents,
+                shift_id
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_cash_rounding_records_exact_and_rounded_amounts() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        crate::db::set_setting(&conn, "payments", "cash_rounding", "0.05").unwrap();
+        seed_cashier_drawer(&conn, "shift-round");
+        // (exact total, collected in cash) for totals ending in .01-.04, .06-.09
+        let cases = [
+            (1001, 1000),
+            (1002, 1000),
+            (1003, 1005),
+            (1004, 1005),
+            (1006, 1005),
+            (1007, 1005),
+            (1008, 1010),
+            (1009, 1010),
+        ];
+        for (exact, _) in cases {
+            seed_round_order(&conn, &format!("ord-round-{exact}"), "shift-round", exact);
+        }
+        drop(conn);
+
+        for (exact, rounded) in cases {
+            let result = record_payment(
+                &db,
+                &serde_json::json!({
+                    "orderId": format!("ord-round-{exact}"),
+                    "method": "cash",
+                    "amount": Cents::new(exact).to_f64_dp2(),
+                    "cashReceived": 20.0,
+                    "staffShiftId": "shift-round",
+                }),
+            )
+            .expect("record rounded cash payment");
+            assert_eq!(
+                result["cashRounding"]["roundedAmount"],
+                Cents::new(rounded).to_f64_dp2()
+            );
+
+            let conn = db.conn.lock().unwrap();
+            let (amount_cents, rounded_cents, delta_cents, change_cents, payment_status): (
+                i64,
+                i64,
+                i64,
+                i64,
+                String,
+            ) = conn
+                .query_row(
+                    "SELECT op.amount_cents, op.rounded_amount_cents, op.rounding_delta_cents,
+                            op.change_given_cents, o.payment_status
+                     FROM order_payments op JOIN orders o ON o.id = op.order_id
+                     WHERE op.order_id = ?1",
+                    params![format!("ord-round-{exact}")],
+                    |row| {
+                        Ok((
+                            row.get(0)?,
+                            row.get(1)?,
+                            row.get(2)?,
+                            row.get(3)?,
+                            row.get(4)?,
+                        ))
+                    },
+                )
+                .unwrap();
+            assert_eq!(amount_cents, exact, "payment settles the exact total");
+            assert_eq!(rounded_cents, rounded, "{exact}");
+            assert_eq!(delta_cents, rounded - exact, "{exact}");
+            assert_eq!(change_cents, 2000 - rounded, "{exact}");
+            assert_eq!(payment_status, "paid", "{exact}");
+        }
+
+        let conn = db.conn.lock().unwrap();
+        let (cash_sales_cents, rounding_cents): (i64, i64) = conn
+            .query_row(
+                "SELECT total_cash_sales_cents, total_cash_rounding_cents
+                 FROM cash_drawer_sessions WHERE staff_shift_id = 'shift-round'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        let exact_total: i64 = cases.iter().map(|(exact, _)| exact).sum();
+        let rounded_total: i64 = cases.iter().map(|(_, rounded)| rounded).sum();
+        assert_eq!(cash_sales_cents, exact_total);
+        assert_eq!(rounding_cents, rounded_total - exact_total);
+    }
+
+    #[test]
+    fn test_cash_rounding_applies_only_to_cash_portion_of_split() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        crate::db::set_setting(
+            &conn,
+            "payments",
+            "cash_rounding",
+            r#"{"increment":0.05,"direction":"up"}"#,
+        )
+        .unwrap();
+        seed_cashier_drawer(&conn, "shift-split-round");
+        seed_round_order(&conn, "ord-split-round", "shift-split-round", 2003);
+        drop(conn);
+
+        for (method, amount) in [("card", 10.01), ("cash", 10.02)] {
+            record_payment(
+                &db,
+                &serde_json::json!({
+                    "orderId": "ord-split-round",
+                    "method": method,
+                    "amount": amount,
+                    "staffShiftId": "shift-split-round",
+                }),
+            )
+            .expect("record split tender");
+        }
+
+        let conn = db.conn.lock().unwrap();
+        let rows: Vec<(String, i64, Option<i64>, Option<i64>)> = conn
+            .prepare(
+                "SELECT method, amount_cents, rounded_amount_cents, rounding_delta_cents
+                 FROM order_payments WHERE order_id = 'ord-split-round' ORDER BY method",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                ("card".to_string(), 1001, None, None),
+                ("cash".to_string(), 1002, Some(1005), Some(3)),
+            ]
+        );
+    }
+
     #[test]
     fn test_record_payment_updates_drawer_card_sales() {
         let db = test_db();
@@ -4227,6 +4821,9 @@ mod tests {
         assert!(html.contains("Cash"));
         assert!(html.contains("20.00")); // received
         assert!(html.contains("5.50")); // change
+        let text = result["text"].as_str().unwrap();
+        assert!(text.contains("ORD-001"));
+        assert!(text.contains("Burger"));
     }
 
     #[test]
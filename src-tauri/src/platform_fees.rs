@@ -0,0 +1,305 @@
+//! Per-order service charges and delivery platform commissions.
+//!
+//! `service_charge` is billed to the customer: it is part of the order
+//! total and printed on the receipt. `platform_commission` is what a
+//! delivery platform (the order's `plugin`) keeps out of the payout; it is
+//! stored for reconciliation only and never printed. Orders that carry
+//! neither keep the column defaults of zero.
+
+use rusqlite::{params, Connection};
+use serde_json::{json, Map, Value};
+
+use crate::money::Cents;
+
+const SERVICE_CHARGE_KEYS: &[&str] = &["serviceCharge", "service_charge"];
+const PLATFORM_COMMISSION_KEYS: &[&str] = &["platformCommission", "platform_commission"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OrderCharges {
+    pub service_charge: f64,
+    pub platform_commission: f64,
+}
+
+/// First usable amount under `keys`. Numeric strings are accepted; negative
+/// or non-finite values count as absent.
+fn amount(payload: &Value, keys: &[&str]) -> Option<f64> {
+    keys.iter()
+        .filter_map(|key| payload.get(*key))
+        .find_map(|value| match value {
+            Value::Number(number) => number.as_f64(),
+            Value::String(raw) => raw.trim().parse::<f64>().ok(),
+            _ => None,
+        })
+        .filter(|value| value.is_finite() && *value >= 0.0)
+}
+
+impl OrderCharges {
+    /// Charges carried by an order payload, or `None` when it mentions
+    /// neither field so callers can leave stored values untouched.
+    pub fn from_payload(payload: &Value) -> Option<Self> {
+        let service_charge = amount(payload, SERVICE_CHARGE_KEYS);
+        let platform_commission = amount(payload, PLATFORM_COMMISSION_KEYS);
+        if service_charge.is_none() && platform_commission.is_none() {
+            return None;
+        }
+        Some(Self {
+            service_charge: Cents::round_half_even(service_charge.unwrap_or(0.0)).to_f64_dp2(),
+            platform_commission: Cents::round_half_even(platform_commission.unwrap_or(0.0))
+                .to_f64_dp2(),
+        })
+    }
+
+    pub fn service_charge_cents(&self) -> Cents {
+        Cents::round_half_even(self.service_charge)
+    }
+
+    pub fn platform_commission_cents(&self) -> Cents {
+        Cents::round_half_even(self.platform_commission)
+    }
+
+    /// Write both amounts onto an existing order row.
+    pub fn store(&self, conn: &Connection, order_id: &str) -> Result<(), String> {
+        conn.execute(
+            "UPDATE orders SET
+                 service_charge = ?2, service_charge_cents = ?3,
+                 platform_commission = ?4, platform_commission_cents = ?5
+             WHERE id = ?1",
+            params![
+                order_id,
+                self.service_charge,
+                self.service_charge_cents().as_i64(),
+                self.platform_commission,
+                self.platform_commission_cents().as_i64(),
+            ],
+        )
+        .map_err(|e| format!("store order charges: {e}"))?;
+        Ok(())
+    }
+
+    /// Add both amounts to an outgoing order sync payload in the camelCase
+    /// and snake_case spellings the admin accepts.
+    pub fn write_sync_fields(&self, obj: &mut Map<String, Value>) {
+        for (keys, amount, cents) in [
+            (
+                SERVICE_CHARGE_KEYS,
+                self.service_charge,
+                self.service_charge_cents(),
+            ),
+            (
+                PLATFORM_COMMISSION_KEYS,
+                self.platform_commission,
+                self.platform_commission_cents(),
+            ),
+        ] {
+            for key in keys {
+                obj.insert((*key).to_string(), json!(amount));
+            }
+            obj.insert(format!("{}_cents", keys[1]), json!(cents.as_i64()));
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PlatformReconciliation {
+    pub plugin: String,
+    pub order_count: i64,
+    pub gross_cents: i64,
+    pub service_charge_cents: i64,
+    pub commission_cents: i64,
+}
+
+impl PlatformReconciliation {
+    /// What the platform should pay out: the gross less its commission.
+    pub fn net_payout_cents(&self) -> i64 {
+        self.gross_cents - self.commission_cents
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "plugin": self.plugin,
+            "orderCount": self.order_count,
+            "gross": Cents::from(self.gross_cents).to_f64_dp2(),
+            "serviceCharges": Cents::from(self.service_charge_cents).to_f64_dp2(),
+            "commissions": Cents::from(self.commission_cents).to_f64_dp2(),
+            "netExpectedPayout": Cents::from(self.net_payout_cents()).to_f64_dp2(),
+        })
+    }
+}
+
+fn cents_sql(column: &str) -> String {
+    format!("COALESCE({column}_cents, CAST(ROUND(COALESCE({column}, 0) * 100) AS INTEGER), 0)")
+}
+
+/// Platform orders created in `[start_at, end_at)` grouped by `plugin`.
+/// Cancelled and ghost orders are left out; `branch_id` is the resolved
+/// report scope, so an empty id only matches unbranched orders.
+pub fn reconciliation(
+    conn: &Connection,
+    branch_id: &str,
+    start_at: &str,
+    end_at: &str,
+) -> Result<Vec<PlatformReconciliation>, String> {
+    let order_range = crate::business_day::timestamp_in_range_sql("o.created_at", "?2", "?3");
+    let total = cents_sql("o.total_amount");
+    let service_charge = cents_sql("o.service_charge");
+    let commission = cents_sql("o.platform_commission");
+    let sql = format!(
+        "SELECT LOWER(TRIM(o.plugin)), COUNT(*), COALESCE(SUM({total}), 0),
+                COALESCE(SUM({service_charge}), 0), COALESCE(SUM({commission}), 0)
+         FROM orders o
+         WHERE COALESCE(o.branch_id, '') = ?1
+           AND TRIM(COALESCE(o.plugin, '')) <> ''
+           AND COALESCE(o.is_ghost, 0) = 0
+           AND {order_range}
+           AND LOWER(TRIM(COALESCE(o.status, ''))) NOT IN ('cancelled', 'canceled')
+         GROUP BY 1
+         ORDER BY 1"
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("prepare platform reconciliation: {e}"))?;
+    let rows = stmt
+        .query_map(params![branch_id, start_at, end_at], |row| {
+            Ok(PlatformReconciliation {
+                plugin: row.get(0)?,
+                order_count: row.get(1)?,
+                gross_cents: row.get(2)?,
+                service_charge_cents: row.get(3)?,
+                commission_cents: row.get(4)?,
+            })
+        })
+        .map_err(|e| format!("query platform reconciliation: {e}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read platform reconciliation: {e}"))
+}
+
+/// Report body: one entry per platform plus the overall totals.
+pub fn reconciliation_to_json(
+    platforms: &[PlatformReconciliation],
+    date_from: &str,
+    date_to: &str,
+) -> Value {
+    let totals = platforms.iter().fold(
+        PlatformReconciliation {
+            plugin: "total".to_string(),
+            ..Default::default()
+        },
+        |mut acc, platform| {
+            acc.order_count += platform.order_count;
+            acc.gross_cents += platform.gross_cents;
+            acc.service_charge_cents += platform.service_charge_cents;
+            acc.commission_cents += platform.commission_cents;
+            acc
+        },
+    );
+    json!({
+        "dateFrom": date_from,
+        "dateTo": date_to,
+        "platforms": platforms.iter().map(PlatformReconciliation::to_json).collect::<Vec<_>>(),
+        "totals": totals.to_json(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn insert_order(conn: &Connection, id: &str, plugin: Option<&str>, status: &str, total: f64) {
+        conn.execute(
+            "INSERT INTO orders (
+                 id, items, total_amount, total_amount_cents, status, order_type, plugin,
+                 branch_id, sync_status, created_at, updated_at
+             ) VALUES (?1, '[]', ?2, ?3, ?4, 'delivery', ?5, 'branch-1', 'pending',
+                       '2026-05-04T12:00:00Z', '2026-05-04T12:00:00Z')",
+            params![
+                id,
+                total,
+                Cents::round_half_even(total).as_i64(),
+                status,
+                plugin
+            ],
+        )
+        .expect("insert order");
+    }
+
+    #[test]
+    fn payload_charges_default_to_absent_and_round_to_cents() {
+        assert_eq!(
+            OrderCharges::from_payload(&json!({ "totalAmount": 10 })),
+            None
+        );
+        let charges = OrderCharges::from_payload(&json!({
+            "serviceCharge": "1.256",
+            "platform_commission": -3,
+        }))
+        .unwrap();
+        assert_eq!(charges.service_charge, 1.26);
+        assert_eq!(charges.platform_commission, 0.0);
+
+        let mut obj = Map::new();
+        charges.write_sync_fields(&mut obj);
+        assert_eq!(obj["service_charge_cents"], json!(126));
+        assert_eq!(obj["platformCommission"], json!(0.0));
+    }
+
+    #[test]
+    fn reconciliation_groups_platform_orders_and_skips_cancelled() {
+        let conn = test_conn();
+        insert_order(&conn, "w-1", Some("wolt"), "completed", 20.0);
+        insert_order(&conn, "w-2", Some("Wolt"), "delivered", 12.5);
+        insert_order(&conn, "w-3", Some("wolt"), "cancelled", 99.0);
+        insert_order(&conn, "e-1", Some("efood"), "completed", 30.0);
+        insert_order(&conn, "pos-1", None, "completed", 8.0);
+        OrderCharges {
+            service_charge: 0.0,
+            platform_commission: 5.0,
+        }
+        .store(&conn, "w-1")
+        .unwrap();
+        OrderCharges {
+            service_charge: 1.5,
+            platform_commission: 3.1,
+        }
+        .store(&conn, "w-2")
+        .unwrap();
+
+        let platforms = reconciliation(
+            &conn,
+            "branch-1",
+            "2026-05-04T00:00:00Z",
+            "2026-05-05T00:00:00Z",
+        )
+        .unwrap();
+        assert_eq!(platforms.len(), 2);
+        assert_eq!(platforms[0].plugin, "efood");
+        assert_eq!(platforms[0].commission_cents, 0);
+        assert_eq!(platforms[0].net_payout_cents(), 3000);
+        let wolt = &platforms[1];
+        assert_eq!(wolt.order_count, 2);
+        assert_eq!(wolt.gross_cents, 3250);
+        assert_eq!(wolt.service_charge_cents, 150);
+        assert_eq!(wolt.commission_cents, 810);
+        assert_eq!(wolt.net_payout_cents(), 2440);
+
+        let report = reconciliation_to_json(&platforms, "2026-05-04", "2026-05-04");
+        assert_eq!(report["totals"]["orderCount"], json!(3));
+        assert_eq!(report["totals"]["netExpectedPayout"], json!(54.4));
+
+        for other_scope in ["branch-2", ""] {
+            assert!(reconciliation(
+                &conn,
+                other_scope,
+                "2026-05-04T00:00:00Z",
+                "2026-05-05T00:00:00Z"
+            )
+            .unwrap()
+            .is_empty());
+        }
+    }
+}
@@ -0,0 +1,472 @@
+//! Prep-time estimates learned from completed orders.
+//!
+//! Every order stamps `confirmed_at` when it is first approved and
+//! `ready_at` when it first reaches `ready`. At that point
+//! [`record_ready`] folds the confirmed → ready minutes into
+//! `prep_time_stats`: one rolling mean and variance for all orders, one per
+//! order type and one per menu category on the order. Each scope is an
+//! exact running average for its first [`WINDOW`] samples and an
+//! exponential average weighted like the last [`WINDOW`] after that, so an
+//! update is a single row read and write however much history there is.
+//! Samples above `kitchen.prep_time_outlier_cap_minutes` (default 120), or
+//! not positive, are ignored.
+//!
+//! [`estimate`] starts from the order type's mean (the overall mean while
+//! that type has fewer than [`MIN_SAMPLES`]), scales it by the slowest
+//! category on the order relative to the overall mean, and adds
+//! `kitchen.prep_time_load_minutes` (default 2) per order already
+//! preparing. The band is ±1.28 standard deviations (roughly 80%). With no
+//! usable history it falls back to the kitchen prep target from
+//! `order_aging` with a ±25% band and `confidence: "none"`.
+
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+
+use crate::db;
+use crate::order_aging::{self, PrepTargets};
+use crate::value_str;
+
+const SETTINGS_CATEGORY: &str = order_aging::SETTINGS_CATEGORY;
+const OUTLIER_CAP_KEY: &str = "prep_time_outlier_cap_minutes";
+const LOAD_MINUTES_KEY: &str = "prep_time_load_minutes";
+
+pub const DEFAULT_OUTLIER_CAP_MINUTES: f64 = 120.0;
+pub const DEFAULT_LOAD_MINUTES: f64 = 2.0;
+
+/// Samples the rolling statistics are weighted over.
+pub const WINDOW: i64 = 200;
+/// Samples a scope needs before estimates lean on it.
+pub const MIN_SAMPLES: i64 = 5;
+
+const SCOPE_ALL: &str = "all";
+const SCOPE_ORDER_TYPE: &str = "order_type";
+const SCOPE_CATEGORY: &str = "category";
+const ALL_KEY: &str = "*";
+
+/// z for a two-sided ~80% band.
+const BAND_Z: f64 = 1.28;
+const DEFAULT_BAND_RATIO: f64 = 0.25;
+/// How far the category mix may stretch or shrink the base estimate.
+const MIX_FACTOR_RANGE: (f64, f64) = (0.5, 2.0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RollingStat {
+    pub samples: i64,
+    pub mean: f64,
+    pub variance: f64,
+}
+
+impl RollingStat {
+    /// Fold one sample in (Welford's update with a weight floor of
+    /// `1 / WINDOW`).
+    pub fn push(self, minutes: f64) -> Self {
+        let samples = self.samples + 1;
+        let alpha = 1.0 / samples.min(WINDOW) as f64;
+        let diff = minutes - self.mean;
+        Self {
+            samples,
+            mean: self.mean + alpha * diff,
+            variance: (1.0 - alpha) * (self.variance + alpha * diff * diff),
+        }
+    }
+
+    fn std_dev(&self) -> f64 {
+        self.variance.max(0.0).sqrt()
+    }
+
+    fn usable(&self) -> bool {
+        self.samples >= MIN_SAMPLES && self.mean > 0.0
+    }
+}
+
+fn normalize(raw: &str) -> String {
+    raw.trim().to_ascii_lowercase().replace(['-', ' '], "_")
+}
+
+fn setting_minutes(conn: &Connection, key: &str, default: f64) -> f64 {
+    db::get_setting(conn, SETTINGS_CATEGORY, key)
+        .and_then(|raw| raw.trim().parse::<f64>().ok())
+        .filter(|value| value.is_finite() && *value >= 0.0)
+        .unwrap_or(default)
+}
+
+fn load_stat(conn: &Connection, scope: &str, key: &str) -> Result<RollingStat, String> {
+    conn.query_row(
+        "SELECT samples, mean_minutes, variance FROM prep_time_stats
+         WHERE scope = ?1 AND scope_key = ?2",
+        params![scope, key],
+        |row| {
+            Ok(RollingStat {
+                samples: row.get(0)?,
+                mean: row.get(1)?,
+                variance: row.get(2)?,
+            })
+        },
+    )
+    .optional()
+    .map(Option::unwrap_or_default)
+    .map_err(|e| format!("load prep time stats {scope}/{key}: {e}"))
+}
+
+fn push_stat(conn: &Connection, scope: &str, key: &str, minutes: f64) -> Result<(), String> {
+    let next = load_stat(conn, scope, key)?.push(minutes);
+    conn.execute(
+        "INSERT INTO prep_time_stats (scope, scope_key, samples, mean_minutes, variance, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(scope, scope_key) DO UPDATE SET
+            samples = excluded.samples,
+            mean_minutes = excluded.mean_minutes,
+            variance = excluded.variance,
+            updated_at = excluded.updated_at",
+        params![
+            scope,
+            key,
+            next.samples,
+            next.mean,
+            next.variance,
+            Utc::now().to_rfc3339()
+        ],
+    )
+    .map_err(|e| format!("update prep time stats {scope}/{key}: {e}"))?;
+    Ok(())
+}
+
+/// Distinct menu categories of an item list: the line's own
+/// `category_id`, else the cached menu item's.
+fn item_categories(conn: &Connection, items: &[Value]) -> Vec<String> {
+    let mut menu_items: Option<HashMap<String, Value>> = None;
+    let mut categories = BTreeSet::new();
+    for item in items {
+        let category = value_str(item, &["category_id", "categoryId"]).or_else(|| {
+            let menu_item_id = value_str(item, &["menu_item_id", "menuItemId"])?;
+            menu_items
+                .get_or_insert_with(|| crate::combos::read_section(conn, "subcategories"))
+                .get(&menu_item_id)
+                .and_then(|entry| value_str(entry, &["category_id", "categoryId"]))
+        });
+        if let Some(category) = category {
+            categories.insert(category);
+        }
+    }
+    categories.into_iter().collect()
+}
+
+fn parse_items(raw: &str) -> Vec<Value> {
+    serde_json::from_str::<Value>(raw)
+        .ok()
+        .and_then(|value| value.as_array().cloned())
+        .unwrap_or_default()
+}
+
+/// Stamp `confirmed_at` the first time an order is approved or starts
+/// preparing.
+pub fn mark_confirmed(conn: &Connection, order_id: &str, at: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE orders SET confirmed_at = COALESCE(confirmed_at, ?1) WHERE id = ?2",
+        params![at, order_id],
+    )
+    .map_err(|e| format!("stamp confirmed_at: {e}"))?;
+    Ok(())
+}
+
+struct ReadyOrder {
+    confirmed_at: Option<String>,
+    ready_at: Option<String>,
+    order_type: String,
+    items: String,
+    is_ghost: bool,
+}
+
+/// Stamp `ready_at` and fold the order's prep time into the statistics.
+/// Only the first time an order reaches `ready` counts. Returns the minutes
+/// recorded, or `None` when the sample was not usable.
+pub fn record_ready(
+    conn: &Connection,
+    order_id: &str,
+    at: DateTime<Utc>,
+) -> Result<Option<f64>, String> {
+    let row = conn
+        .query_row(
+            "SELECT confirmed_at, ready_at, COALESCE(order_type, ''), COALESCE(items, '[]'),
+                    COALESCE(is_ghost, 0) != 0
+             FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| {
+                Ok(ReadyOrder {
+                    confirmed_at: row.get(0)?,
+                    ready_at: row.get(1)?,
+                    order_type: row.get(2)?,
+                    items: row.get(3)?,
+                    is_ghost: row.get(4)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| format!("load order for prep time: {e}"))?;
+    let Some(ReadyOrder {
+        confirmed_at,
+        ready_at,
+        order_type,
+        items,
+        is_ghost,
+    }) = row
+    else {
+        return Ok(None);
+    };
+    if ready_at.is_some() {
+        return Ok(None);
+    }
+    conn.execute(
+        "UPDATE orders SET ready_at = ?1 WHERE id = ?2 AND ready_at IS NULL",
+        params![at.to_rfc3339(), order_id],
+    )
+    .map_err(|e| format!("stamp ready_at: {e}"))?;
+
+    let Some(confirmed) = confirmed_at
+        .as_deref()
+        .and_then(|raw| DateTime::parse_from_rfc3339(raw.trim()).ok())
+    else {
+        return Ok(None);
+    };
+    let minutes = (at - confirmed.with_timezone(&Utc)).num_seconds() as f64 / 60.0;
+    let cap = setting_minutes(conn, OUTLIER_CAP_KEY, DEFAULT_OUTLIER_CAP_MINUTES);
+    if is_ghost || minutes <= 0.0 || minutes > cap {
+        tracing::debug!(order_id = %order_id, minutes, cap, "Prep time sample ignored");
+        return Ok(None);
+    }
+
+    push_stat(conn, SCOPE_ALL, ALL_KEY, minutes)?;
+    let order_type = normalize(&order_type);
+    if !order_type.is_empty() {
+        push_stat(conn, SCOPE_ORDER_TYPE, &order_type, minutes)?;
+    }
+    for category in item_categories(conn, &parse_items(&items)) {
+        push_stat(conn, SCOPE_CATEGORY, &category, minutes)?;
+    }
+    Ok(Some(minutes))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrepEstimate {
+    pub minutes: i64,
+    pub low: i64,
+    pub high: i64,
+    /// `none` (no history), `low`, `medium` or `high`.
+    pub confidence: &'static str,
+    /// Scope the base came from: `order_type`, `all` or `default`.
+    pub basis: &'static str,
+    pub samples: i64,
+    pub load: i64,
+}
+
+impl PrepEstimate {
+    /// Whether the estimate comes from recorded orders rather than the
+    /// configured prep target.
+    pub fn is_learned(&self) -> bool {
+        self.basis != "default"
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "minutes": self.minutes,
+            "low": self.low,
+            "high": self.high,
+            "confidence": self.confidence,
+            "basis": self.basis,
+            "samples": self.samples,
+            "load": self.load,
+        })
+    }
+}
+
+/// Orders currently in `preparing`, other than `exclude`.
+pub fn preparing_load(conn: &Connection, exclude: Option<&str>) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM orders
+         WHERE status = 'preparing' AND COALESCE(is_ghost, 0) = 0
+           AND (?1 IS NULL OR id != ?1)",
+        params![exclude],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("count preparing orders: {e}"))
+}
+
+/// Suggested prep minutes for an item list, given how many orders are
+/// already preparing.
+pub fn estimate(
+    conn: &Connection,
+    order_type: &str,
+    items: &[Value],
+    load: i64,
+) -> Result<PrepEstimate, String> {
+    let load = load.max(0);
+    let load_minutes = setting_minutes(conn, LOAD_MINUTES_KEY, DEFAULT_LOAD_MINUTES);
+    let overall = load_stat(conn, SCOPE_ALL, ALL_KEY)?;
+    let by_type = load_stat(conn, SCOPE_ORDER_TYPE, &normalize(order_type))?;
+    let (base, basis) = if by_type.usable() {
+        (by_type, "order_type")
+    } else if overall.usable() {
+        (overall, "all")
+    } else {
+        let target = PrepTargets::load(conn).for_order_type(order_type) as f64;
+        let minutes = target + load as f64 * load_minutes;
+        let band = minutes * DEFAULT_BAND_RATIO;
+        return Ok(PrepEstimate {
+            minutes: minutes.round().max(1.0) as i64,
+            low: (minutes - band).round().max(1.0) as i64,
+            high: (minutes + band).round().max(1.0) as i64,
+            confidence: "none",
+            basis: "default",
+            samples: 0,
+            load,
+        });
+    };
+
+    let mut mix_factor: Option<f64> = None;
+    if overall.usable() {
+        for category in item_categories(conn, items) {
+            let stat = load_stat(conn, SCOPE_CATEGORY, &category)?;
+            if stat.usable() {
+                let ratio = stat.mean / overall.mean;
+                mix_factor = Some(mix_factor.map_or(ratio, |current| current.max(ratio)));
+            }
+        }
+    }
+    let factor = mix_factor
+        .unwrap_or(1.0)
+        .clamp(MIX_FACTOR_RANGE.0, MIX_FACTOR_RANGE.1);
+
+    let minutes = base.mean * factor + load as f64 * load_minutes;
+    let band = (BAND_Z * base.std_dev() * factor).max(1.0);
+    Ok(PrepEstimate {
+        minutes: minutes.round().max(1.0) as i64,
+        low: (minutes - band).round().max(1.0) as i64,
+        high: (minutes + band).round().max(1.0) as i64,
+        confidence: match base.samples {
+            n if n < 20 => "low",
+            n if n < 100 => "medium",
+            _ => "high",
+        },
+        basis,
+        samples: base.samples,
+        load,
+    })
+}
+
+/// [`estimate`] for a stored order, with the current preparing load.
+pub fn estimate_for_order(conn: &Connection, order_id: &str) -> Result<PrepEstimate, String> {
+    let (order_type, items): (String, String) = conn
+        .query_row(
+            "SELECT COALESCE(order_type, ''), COALESCE(items, '[]') FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("load order for prep estimate: {e}"))?;
+    let load = preparing_load(conn, Some(order_id))?;
+    estimate(conn, &order_type, &parse_items(&items), load)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn confirmed_order(
+        conn: &Connection,
+        id: &str,
+        order_type: &str,
+        items: &str,
+    ) -> DateTime<Utc> {
+        let confirmed = Utc::now() - Duration::hours(1);
+        conn.execute(
+            "INSERT INTO orders (id, items, total_amount, status, order_type, confirmed_at,
+                                 created_at, updated_at)
+             VALUES (?1, ?2, 10.0, 'preparing', ?3, ?4, ?4, ?4)",
+            params![id, items, order_type, confirmed.to_rfc3339()],
+        )
+        .expect("insert order");
+        confirmed
+    }
+
+    #[test]
+    fn rolling_stat_is_exact_until_the_window_fills() {
+        let stat = [10.0, 20.0, 30.0]
+            .into_iter()
+            .fold(RollingStat::default(), RollingStat::push);
+        assert_eq!(stat.samples, 3);
+        assert!((stat.mean - 20.0).abs() < 1e-9);
+        assert!((stat.variance - 200.0 / 3.0).abs() < 1e-9);
+
+        let mut full = RollingStat {
+            samples: WINDOW,
+            mean: 10.0,
+            variance: 0.0,
+        };
+        full = full.push(210.0);
+        assert!((full.mean - 11.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ready_orders_feed_stats_once_and_skip_outliers() {
+        let conn = test_conn();
+        let items = r#"[{"name":"Pizza","category_id":"pizza"}]"#;
+        for (index, minutes) in [18, 20, 22, 20, 20].into_iter().enumerate() {
+            let id = format!("o-{index}");
+            let confirmed = confirmed_order(&conn, &id, "delivery", items);
+            let recorded =
+                record_ready(&conn, &id, confirmed + Duration::minutes(minutes)).unwrap();
+            assert_eq!(recorded, Some(minutes as f64));
+            // A second trip through `ready` does not count again.
+            assert_eq!(
+                record_ready(&conn, &id, confirmed + Duration::minutes(90)).unwrap(),
+                None
+            );
+        }
+        let outlier = confirmed_order(&conn, "slow", "delivery", items);
+        assert_eq!(
+            record_ready(&conn, "slow", outlier + Duration::minutes(300)).unwrap(),
+            None
+        );
+
+        let stat = load_stat(&conn, SCOPE_ORDER_TYPE, "delivery").unwrap();
+        assert_eq!(stat.samples, 5);
+        assert!((stat.mean - 20.0).abs() < 1e-9);
+        assert_eq!(
+            load_stat(&conn, SCOPE_CATEGORY, "pizza").unwrap().samples,
+            5
+        );
+        assert_eq!(load_stat(&conn, SCOPE_ALL, ALL_KEY).unwrap().samples, 5);
+    }
+
+    #[test]
+    fn estimate_uses_history_load_and_falls_back_to_target() {
+        let conn = test_conn();
+        let fallback = estimate(&conn, "pickup", &[], 0).unwrap();
+        assert_eq!(fallback.basis, "default");
+        assert_eq!(fallback.minutes, order_aging::DEFAULT_PREP_TARGET_MINUTES);
+        assert!(!fallback.is_learned());
+
+        for index in 0..5 {
+            let id = format!("o-{index}");
+            let confirmed = confirmed_order(&conn, &id, "pickup", "[]");
+            record_ready(&conn, &id, confirmed + Duration::minutes(10)).unwrap();
+        }
+        let learned = estimate(&conn, "pickup", &[], 3).unwrap();
+        assert_eq!(learned.basis, "order_type");
+        assert_eq!(learned.confidence, "low");
+        assert_eq!(learned.minutes, 16);
+        assert_eq!((learned.low, learned.high), (15, 17));
+
+        // Delivery has no history of its own, so it leans on all orders.
+        assert_eq!(estimate(&conn, "delivery", &[], 0).unwrap().basis, "all");
+    }
+}
@@ -0,0 +1,793 @@
+//! Time-based pricing rules ("20% off coffee 16:00–18:00").
+//!
+//! A rule has a schedule (ISO days of week, 1 = Monday, and a local time
+//! window), a scope (menu category, subcategory and item ids) and an
+//! adjustment: a percentage off or a fixed unit price. Rules are stored in
+//! `pricing_rules`; admin rules arrive with the menu sync and replace the
+//! previous admin set, local rules are managed with `pricing_rules_set`.
+//!
+//! [`apply_to_items`] prices order lines at the order's creation time. A
+//! matched line gets its new unit price and a `pricing_rule` marker with the
+//! rule id, receipt label and the price it replaced. Marked lines are
+//! locked: later edits never re-evaluate them, and combo re-pricing keeps
+//! the recorded adjustment ([`locked_unit_price`]). When several rules
+//! match a line the lowest resulting price wins (largest discount); on a
+//! tie the rule listed first wins. A rule never raises a price.
+//!
+//! A window whose end is before its start runs past midnight and belongs to
+//! the day it starts on: a Friday 22:00–02:00 rule is active early Saturday
+//! morning, not early Friday.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime, NaiveTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::money::{self, Cents, RoundingRule};
+use crate::value_str;
+
+/// Order line key carrying the applied rule.
+pub const LINE_MARKER_KEY: &str = "pricing_rule";
+
+pub const SOURCE_LOCAL: &str = "local";
+pub const SOURCE_ADMIN: &str = "admin";
+
+const TIME_FORMAT: &str = "%H:%M";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustmentType {
+    Percent,
+    #[serde(alias = "fixed", alias = "fixedPrice")]
+    FixedPrice,
+}
+
+impl AdjustmentType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AdjustmentType::Percent => "percent",
+            AdjustmentType::FixedPrice => "fixed_price",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim() {
+            "percent" => Some(AdjustmentType::Percent),
+            "fixed_price" => Some(AdjustmentType::FixedPrice),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingRule {
+    pub id: String,
+    pub name: String,
+    /// Receipt text; defaults to the name plus the adjustment.
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// ISO weekdays, 1 = Monday … 7 = Sunday (0 is read as Sunday). Empty
+    /// means every day.
+    #[serde(default, alias = "days_of_week")]
+    pub days_of_week: Vec<u32>,
+    /// `HH:MM` local time; both or neither.
+    #[serde(default, alias = "start_time")]
+    pub start_time: Option<String>,
+    #[serde(default, alias = "end_time")]
+    pub end_time: Option<String>,
+    #[serde(default, alias = "category_ids")]
+    pub category_ids: Vec<String>,
+    #[serde(default, alias = "subcategory_ids")]
+    pub subcategory_ids: Vec<String>,
+    #[serde(default, alias = "item_ids")]
+    pub item_ids: Vec<String>,
+    #[serde(alias = "adjustment_type")]
+    pub adjustment_type: AdjustmentType,
+    #[serde(alias = "adjustment_value")]
+    pub adjustment_value: f64,
+    #[serde(default = "default_source")]
+    pub source: String,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_source() -> String {
+    SOURCE_LOCAL.to_string()
+}
+
+fn parse_time(raw: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(raw.trim(), TIME_FORMAT)
+        .map_err(|_| format!("Invalid time '{}', expected HH:MM", raw.trim()))
+}
+
+fn clean_ids(ids: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    ids.iter()
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty() && seen.insert(id.clone()))
+        .collect()
+}
+
+impl PricingRule {
+    /// Check and normalize a rule before saving.
+    pub fn validate(mut self) -> Result<Self, String> {
+        self.id = self.id.trim().to_string();
+        self.name = self.name.trim().to_string();
+        if self.id.is_empty() {
+            return Err("Pricing rules need an id".into());
+        }
+        if self.name.is_empty() {
+            return Err(format!("Pricing rule '{}' needs a name", self.id));
+        }
+        self.label = self
+            .label
+            .map(|label| label.trim().to_string())
+            .filter(|label| !label.is_empty());
+        let mut days = Vec::new();
+        for day in &self.days_of_week {
+            let day = if *day == 0 { 7 } else { *day };
+            if day > 7 {
+                return Err(format!(
+                    "Pricing rule '{}': day {day} is not a weekday (1-7)",
+                    self.id
+                ));
+            }
+            if !days.contains(&day) {
+                days.push(day);
+            }
+        }
+        days.sort_unstable();
+        self.days_of_week = days;
+        match (&self.start_time, &self.end_time) {
+            (Some(start), Some(end)) => {
+                let (start, end) = (parse_time(start), parse_time(end));
+                let (start, end) = start
+                    .and_then(|start| end.map(|end| (start, end)))
+                    .map_err(|e| format!("Pricing rule '{}': {e}", self.id))?;
+                if start == end {
+                    return Err(format!(
+                        "Pricing rule '{}': the time window is empty",
+                        self.id
+                    ));
+                }
+                self.start_time = Some(start.format(TIME_FORMAT).to_string());
+                self.end_time = Some(end.format(TIME_FORMAT).to_string());
+            }
+            (None, None) => {}
+            _ => {
+                return Err(format!(
+                    "Pricing rule '{}': set both startTime and endTime, or neither",
+                    self.id
+                ))
+            }
+        }
+        self.category_ids = clean_ids(&self.category_ids);
+        self.subcategory_ids = clean_ids(&self.subcategory_ids);
+        self.item_ids = clean_ids(&self.item_ids);
+        if self.category_ids.is_empty()
+            && self.subcategory_ids.is_empty()
+            && self.item_ids.is_empty()
+        {
+            return Err(format!(
+                "Pricing rule '{}' needs at least one category, subcategory or item",
+                self.id
+            ));
+        }
+        let value = self.adjustment_value;
+        let valid = match self.adjustment_type {
+            AdjustmentType::Percent => value.is_finite() && value > 0.0 && value <= 100.0,
+            AdjustmentType::FixedPrice => value.is_finite() && value >= 0.0,
+        };
+        if !valid {
+            return Err(format!(
+                "Pricing rule '{}': invalid {} adjustment {value}",
+                self.id,
+                self.adjustment_type.as_str()
+            ));
+        }
+        if self.source != SOURCE_ADMIN {
+            self.source = SOURCE_LOCAL.to_string();
+        }
+        Ok(self)
+    }
+
+    fn window(&self) -> Option<(NaiveTime, NaiveTime)> {
+        let start = parse_time(self.start_time.as_deref()?).ok()?;
+        let end = parse_time(self.end_time.as_deref()?).ok()?;
+        Some((start, end))
+    }
+
+    fn runs_on(&self, day: u32) -> bool {
+        self.days_of_week.is_empty() || self.days_of_week.contains(&day)
+    }
+
+    /// Whether the rule is on at a local date and time.
+    pub fn is_active_at(&self, at: NaiveDateTime) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let day = at.weekday().number_from_monday();
+        let time = at.time();
+        match self.window() {
+            None => self.runs_on(day),
+            Some((start, end)) if start < end => self.runs_on(day) && start <= time && time < end,
+            Some((start, end)) => {
+                let previous_day = (at - Duration::days(1)).weekday().number_from_monday();
+                (time >= start && self.runs_on(day)) || (time < end && self.runs_on(previous_day))
+            }
+        }
+    }
+
+    fn applies_to(&self, scope: &LineScope) -> bool {
+        let hit = |ids: &[String], id: &Option<String>| {
+            id.as_ref()
+                .is_some_and(|id| ids.iter().any(|rule_id| rule_id == id))
+        };
+        scope.item_ids.iter().any(|id| self.item_ids.contains(id))
+            || hit(&self.subcategory_ids, &scope.subcategory_id)
+            || hit(&self.category_ids, &scope.category_id)
+    }
+
+    /// Unit price after the adjustment. Never above `unit`.
+    pub fn adjusted_unit(&self, unit: Cents, rounding: RoundingRule) -> Cents {
+        adjust(self.adjustment_type, self.adjustment_value, unit, rounding)
+    }
+
+    pub fn receipt_label(&self) -> String {
+        if let Some(label) = &self.label {
+            return label.clone();
+        }
+        match self.adjustment_type {
+            AdjustmentType::Percent => {
+                format!("{} -{}%", self.name, format_number(self.adjustment_value))
+            }
+            AdjustmentType::FixedPrice => {
+                format!("{} {:.2}", self.name, self.adjustment_value)
+            }
+        }
+    }
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value}")
+    }
+}
+
+fn adjust(kind: AdjustmentType, value: f64, unit: Cents, rounding: RoundingRule) -> Cents {
+    let adjusted = match kind {
+        AdjustmentType::Percent => {
+            Cents::round_with(unit.to_f64_dp2() * (100.0 - value) / 100.0, rounding)
+        }
+        AdjustmentType::FixedPrice => Cents::round_with(value, rounding),
+    };
+    adjusted.min(unit).max(Cents::ZERO)
+}
+
+fn ids_json(ids: &[String]) -> String {
+    serde_json::to_string(ids).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn ids_from_json<T: serde::de::DeserializeOwned>(raw: &str) -> Vec<T> {
+    serde_json::from_str(raw).unwrap_or_default()
+}
+
+/// Every stored rule, admin rules first, each group by name.
+pub fn list(conn: &Connection) -> Result<Vec<PricingRule>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, label, enabled, days_of_week, start_time, end_time,
+                    category_ids, subcategory_ids, item_ids, adjustment_type,
+                    adjustment_value, source
+             FROM pricing_rules
+             ORDER BY CASE source WHEN 'admin' THEN 0 ELSE 1 END, name, id",
+        )
+        .map_err(|e| format!("prepare pricing rules: {e}"))?;
+    let rows = stmt
+        .query_map([], |row| {
+            let adjustment_type: String = row.get(10)?;
+            Ok(PricingRule {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                label: row.get(2)?,
+                enabled: row.get::<_, i64>(3)? != 0,
+                days_of_week: ids_from_json(&row.get::<_, String>(4)?),
+                start_time: row.get(5)?,
+                end_time: row.get(6)?,
+                category_ids: ids_from_json(&row.get::<_, String>(7)?),
+                subcategory_ids: ids_from_json(&row.get::<_, String>(8)?),
+                item_ids: ids_from_json(&row.get::<_, String>(9)?),
+                adjustment_type: AdjustmentType::parse(&adjustment_type)
+                    .unwrap_or(AdjustmentType::Percent),
+                adjustment_value: row.get(11)?,
+                source: row.get(12)?,
+            })
+        })
+        .map_err(|e| format!("query pricing rules: {e}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read pricing rules: {e}"))
+}
+
+fn insert(
+    conn: &Connection,
+    rule: &PricingRule,
+    actor_staff_id: Option<&str>,
+    now: &str,
+) -> Result<(), String> {
+    let value_cents = (rule.adjustment_type == AdjustmentType::FixedPrice)
+        .then(|| Cents::round_half_even(rule.adjustment_value).as_i64());
+    conn.execute(
+        "INSERT INTO pricing_rules (
+             id, name, label, enabled, days_of_week, start_time, end_time,
+             category_ids, subcategory_ids, item_ids, adjustment_type,
+             adjustment_value, adjustment_value_cents, source, updated_by,
+             created_at, updated_at
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?16)",
+        params![
+            rule.id,
+            rule.name,
+            rule.label,
+            rule.enabled as i64,
+            serde_json::to_string(&rule.days_of_week).unwrap_or_else(|_| "[]".to_string()),
+            rule.start_time,
+            rule.end_time,
+            ids_json(&rule.category_ids),
+            ids_json(&rule.subcategory_ids),
+            ids_json(&rule.item_ids),
+            rule.adjustment_type.as_str(),
+            rule.adjustment_value,
+            value_cents,
+            rule.source,
+            actor_staff_id,
+            now,
+        ],
+    )
+    .map_err(|e| format!("insert pricing rule {}: {e}", rule.id))?;
+    Ok(())
+}
+
+/// Replace every rule of one source. Ids must be unique across sources, so
+/// a local rule cannot shadow an admin one.
+fn replace_source(
+    conn: &Connection,
+    source: &str,
+    rules: Vec<PricingRule>,
+    actor_staff_id: Option<&str>,
+) -> Result<Vec<PricingRule>, String> {
+    let mut seen = HashSet::new();
+    let mut validated = Vec::with_capacity(rules.len());
+    for rule in rules {
+        let rule = PricingRule {
+            source: source.to_string(),
+            ..rule
+        }
+        .validate()?;
+        if !seen.insert(rule.id.clone()) {
+            return Err(format!("Duplicate pricing rule id '{}'", rule.id));
+        }
+        validated.push(rule);
+    }
+    let taken: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM pricing_rules
+             WHERE source != ?1 AND id IN (SELECT value FROM json_each(?2))",
+            params![source, ids_json(&seen.into_iter().collect::<Vec<_>>())],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("check pricing rule ids: {e}"))?;
+    if taken > 0 {
+        return Err("A pricing rule id is already used by another rule source".into());
+    }
+
+    let now = Utc::now().to_rfc3339();
+    conn.execute_batch("SAVEPOINT pricing_rules_replace")
+        .map_err(|e| format!("begin pricing rules replace: {e}"))?;
+    let result = (|| {
+        conn.execute(
+            "DELETE FROM pricing_rules WHERE source = ?1",
+            params![source],
+        )
+        .map_err(|e| format!("clear {source} pricing rules: {e}"))?;
+        for rule in &validated {
+            insert(conn, rule, actor_staff_id, &now)?;
+        }
+        Ok(())
+    })();
+    let finish = if result.is_ok() {
+        "RELEASE pricing_rules_replace"
+    } else {
+        "ROLLBACK TO pricing_rules_replace; RELEASE pricing_rules_replace"
+    };
+    conn.execute_batch(finish)
+        .map_err(|e| format!("finish pricing rules replace: {e}"))?;
+    result.map(|_| validated)
+}
+
+/// Replace the terminal's own rules. Admin rules are left alone.
+pub fn set_local(
+    conn: &Connection,
+    rules: Vec<PricingRule>,
+    actor_staff_id: Option<&str>,
+) -> Result<Vec<PricingRule>, String> {
+    replace_source(conn, SOURCE_LOCAL, rules, actor_staff_id)
+}
+
+/// Store the `pricing_rules` section of an admin menu sync. Entries that
+/// fail validation are skipped with a count so one bad rule does not drop
+/// the rest.
+pub fn store_remote(conn: &Connection, entries: &[Value]) -> Result<usize, String> {
+    let mut rules = Vec::new();
+    let mut skipped = 0;
+    for entry in entries {
+        match serde_json::from_value::<PricingRule>(entry.clone())
+            .map_err(|e| e.to_string())
+            .and_then(|rule| {
+                PricingRule {
+                    source: SOURCE_ADMIN.to_string(),
+                    ..rule
+                }
+                .validate()
+            }) {
+            Ok(rule) => rules.push(rule),
+            Err(error) => {
+                skipped += 1;
+                tracing::warn!(error = %error, "Skipping invalid admin pricing rule");
+            }
+        }
+    }
+    let stored = replace_source(conn, SOURCE_ADMIN, rules, None)?;
+    if skipped > 0 {
+        tracing::warn!(
+            skipped,
+            stored = stored.len(),
+            "Admin pricing rules partly stored"
+        );
+    }
+    Ok(stored.len())
+}
+
+/// Ids an order line can be matched on.
+#[derive(Debug, Default)]
+struct LineScope {
+    item_ids: Vec<String>,
+    subcategory_id: Option<String>,
+    category_id: Option<String>,
+}
+
+impl LineScope {
+    /// Menu items live in the `subcategories` menu section, so a line's
+    /// menu item id doubles as its subcategory id, and its category comes
+    /// from the line or, failing that, the cached menu entry.
+    fn of(line: &Value, menu_items: &HashMap<String, Value>) -> Self {
+        let menu_item_id = value_str(line, &["menu_item_id", "menuItemId"]);
+        let item_ids = [
+            menu_item_id.clone(),
+            value_str(line, &["combo_id", "comboId"]),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let subcategory_id =
+            value_str(line, &["subcategory_id", "subcategoryId"]).or(menu_item_id.clone());
+        let category_id = value_str(line, &["category_id", "categoryId"]).or_else(|| {
+            menu_item_id
+                .and_then(|id| menu_items.get(&id))
+                .and_then(|entry| value_str(entry, &["category_id", "categoryId"]))
+        });
+        Self {
+            item_ids,
+            subcategory_id,
+            category_id,
+        }
+    }
+}
+
+fn is_manual(line: &Value) -> bool {
+    ["is_manual", "isManual"]
+        .iter()
+        .any(|key| line.get(*key).and_then(Value::as_bool) == Some(true))
+}
+
+pub fn is_locked(line: &Value) -> bool {
+    line.get(LINE_MARKER_KEY).is_some_and(Value::is_object)
+}
+
+fn line_quantity(line: &Value) -> f64 {
+    crate::value_f64(line, &["quantity"])
+        .unwrap_or(1.0)
+        .max(0.0)
+}
+
+pub(crate) fn line_unit(line: &Value, rounding: RoundingRule) -> Option<Cents> {
+    if let Some(unit) = crate::value_f64(line, &["unit_price", "unitPrice", "price"]) {
+        return Some(Cents::round_with(unit, rounding));
+    }
+    let quantity = line_quantity(line);
+    (quantity > 0.0).then(|| {
+        Cents::round_with(
+            money::item_line_cents(line, rounding).to_f64_dp2() / quantity,
+            rounding,
+        )
+    })
+}
+
+pub(crate) fn set_line_price(line: &mut Value, unit: Cents, rounding: RoundingRule) {
+    let total = Cents::round_with(unit.to_f64_dp2() * line_quantity(line), rounding);
+    let Some(object) = line.as_object_mut() else {
+        return;
+    };
+    let mut unit_set = false;
+    for key in ["unit_price", "unitPrice", "price"] {
+        if object.contains_key(key) {
+            object.insert(key.to_string(), json!(unit.to_f64_dp2()));
+            unit_set = true;
+        }
+    }
+    if !unit_set {
+        object.insert("unit_price".to_string(), json!(unit.to_f64_dp2()));
+    }
+    for key in ["total_price", "totalPrice"] {
+        if object.contains_key(key) {
+            object.insert(key.to_string(), json!(total.to_f64_dp2()));
+        }
+    }
+}
+
+/// Rules on at `at` (local time), in the order they are listed.
+pub fn active_rules(conn: &Connection, at: NaiveDateTime) -> Result<Vec<PricingRule>, String> {
+    Ok(list(conn)?
+        .into_iter()
+        .filter(|rule| rule.is_active_at(at))
+        .collect())
+}
+
+/// Price unlocked lines with the rules active at `at`, the order's creation
+/// time. Returns the change in the items total (zero or negative).
+pub fn apply_to_items(
+    conn: &Connection,
+    items: &mut [Value],
+    at: DateTime<Local>,
+) -> Result<Cents, String> {
+    let rules = active_rules(conn, at.naive_local())?;
+    if rules.is_empty() || items.is_empty() {
+        return Ok(Cents::ZERO);
+    }
+    let menu_items = crate::combos::read_section(conn, "subcategories");
+    let rounding = RoundingRule::from_settings(conn);
+    Ok(apply_rules(&rules, &menu_items, items, rounding))
+}
+
+fn apply_rules(
+    rules: &[PricingRule],
+    menu_items: &HashMap<String, Value>,
+    items: &mut [Value],
+    rounding: RoundingRule,
+) -> Cents {
+    let mut delta = Cents::ZERO;
+    for line in items.iter_mut() {
+        if is_locked(line) || is_manual(line) {
+            continue;
+        }
+        let Some(unit) = line_unit(line, rounding) else {
+            continue;
+        };
+        let scope = LineScope::of(line, menu_items);
+        let mut best: Option<(&PricingRule, Cents)> = None;
+        for rule in rules.iter().filter(|rule| rule.applies_to(&scope)) {
+            let price = rule.adjusted_unit(unit, rounding);
+            if price < best.map_or(unit, |(_, best_price)| best_price) {
+                best = Some((rule, price));
+            }
+        }
+        let Some((rule, price)) = best else {
+            continue;
+        };
+        let before = money::item_line_cents(line, rounding);
+        set_line_price(line, price, rounding);
+        if let Some(object) = line.as_object_mut() {
+            object.insert(
+                LINE_MARKER_KEY.to_string(),
+                json!({
+                    "id": rule.id,
+                    "name": rule.name,
+                    "label": rule.receipt_label(),
+                    "adjustmentType": rule.adjustment_type.as_str(),
+                    "adjustmentValue": rule.adjustment_value,
+                    "originalUnitPrice": unit.to_f64_dp2(),
+                }),
+            );
+        }
+        delta += money::item_line_cents(line, rounding) - before;
+    }
+    delta
+}
+
+/// Unit price of a line re-priced from the menu (combo price fix-ups):
+/// the recorded adjustment is applied again so a locked discount survives.
+pub fn locked_unit_price(line: &Value, unit: Cents, rounding: RoundingRule) -> Cents {
+    let Some(marker) = line.get(LINE_MARKER_KEY).filter(|m| m.is_object()) else {
+        return unit;
+    };
+    let kind = value_str(marker, &["adjustmentType"]).and_then(|raw| AdjustmentType::parse(&raw));
+    let value = crate::value_f64(marker, &["adjustmentValue"]);
+    match kind.zip(value) {
+        Some((kind, value)) => adjust(kind, value, unit, rounding),
+        None => unit,
+    }
+}
+
+/// Receipt text for a priced line, e.g. "Happy Hour -20%".
+pub fn line_label(line: &Value) -> Option<String> {
+    line.get(LINE_MARKER_KEY)
+        .and_then(|marker| value_str(marker, &["label", "name"]))
+}
+
+/// Keep the markers of existing lines on an edit that resends them without
+/// one, so the locked price is not discounted a second time. `matches`
+/// decides whether an incoming line is the same line as a stored one.
+pub fn carry_over_markers(
+    existing: &[Value],
+    incoming: &mut [Value],
+    matches: impl Fn(&Value, &Value) -> bool,
+) {
+    let mut used = vec![false; existing.len()];
+    for line in incoming.iter_mut() {
+        if is_locked(line) {
+            continue;
+        }
+        let found = existing
+            .iter()
+            .enumerate()
+            .find(|(index, stored)| !used[*index] && is_locked(stored) && matches(stored, line));
+        if let Some((index, stored)) = found {
+            used[index] = true;
+            if let (Some(object), Some(marker)) =
+                (line.as_object_mut(), stored.get(LINE_MARKER_KEY))
+            {
+                object.insert(LINE_MARKER_KEY.to_string(), marker.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeZone};
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn rule(value: Value) -> PricingRule {
+        serde_json::from_value::<PricingRule>(value)
+            .unwrap()
+            .validate()
+            .unwrap()
+    }
+
+    fn at(date: (i32, u32, u32), time: &str) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(date.0, date.1, date.2)
+            .unwrap()
+            .and_time(parse_time(time).unwrap())
+    }
+
+    #[test]
+    fn window_past_midnight_belongs_to_the_day_it_starts() {
+        // 2026-05-08 is a Friday.
+        let late = rule(json!({
+            "id": "late", "name": "Late Night", "daysOfWeek": [5],
+            "startTime": "22:00", "endTime": "02:00",
+            "itemIds": ["beer"], "adjustmentType": "percent", "adjustmentValue": 50,
+        }));
+        assert!(late.is_active_at(at((2026, 5, 8), "23:30")));
+        assert!(late.is_active_at(at((2026, 5, 9), "01:59")));
+        assert!(!late.is_active_at(at((2026, 5, 9), "02:00")));
+        assert!(!late.is_active_at(at((2026, 5, 8), "01:00")));
+        assert!(!late.is_active_at(at((2026, 5, 9), "23:00")));
+    }
+
+    #[test]
+    fn validate_rejects_bad_rules() {
+        let parse = |value: Value| {
+            serde_json::from_value::<PricingRule>(value)
+                .unwrap()
+                .validate()
+        };
+        let base = json!({
+            "id": " hh ", "name": "Happy Hour", "daysOfWeek": [0, 1, 1],
+            "categoryIds": ["coffee"], "adjustmentType": "percent", "adjustmentValue": 20,
+        });
+        let valid = parse(base.clone()).unwrap();
+        assert_eq!(valid.id, "hh");
+        assert_eq!(valid.days_of_week, vec![1, 7]);
+        assert_eq!(valid.receipt_label(), "Happy Hour -20%");
+
+        let with = |key: &str, value: Value| {
+            let mut rule = base.clone();
+            rule[key] = value;
+            parse(rule)
+        };
+        assert!(with("adjustmentValue", json!(120)).is_err());
+        assert!(with("categoryIds", json!([])).is_err());
+        assert!(with("startTime", json!("16:00")).is_err());
+        assert!(with("daysOfWeek", json!([8])).is_err());
+    }
+
+    #[test]
+    fn largest_discount_wins_and_priced_lines_stay_locked() {
+        let conn = test_conn();
+        conn.execute(
+            "INSERT INTO menu_cache (id, cache_key, data, version, updated_at)
+             VALUES ('m-1', 'subcategories', ?1, 'v1', datetime('now'))",
+            params![json!([
+                { "id": "espresso", "name": "Espresso", "category_id": "coffee" },
+                { "id": "cake", "name": "Cake", "category_id": "desserts" },
+            ])
+            .to_string()],
+        )
+        .unwrap();
+        set_local(
+            &conn,
+            vec![
+                rule(json!({
+                    "id": "hh", "name": "Happy Hour", "startTime": "16:00", "endTime": "18:00",
+                    "categoryIds": ["coffee"], "adjustmentType": "percent", "adjustmentValue": 20,
+                })),
+                rule(json!({
+                    "id": "espresso-2", "name": "Espresso deal", "label": "Espresso 2.00",
+                    "startTime": "16:00", "endTime": "18:00",
+                    "itemIds": ["espresso"], "adjustmentType": "fixed_price", "adjustmentValue": 2,
+                })),
+            ],
+            Some("mgr-1"),
+        )
+        .unwrap();
+
+        let mut items = vec![
+            json!({ "menu_item_id": "espresso", "quantity": 2, "unit_price": 3.0, "total_price": 6.0 }),
+            json!({ "menu_item_id": "cake", "quantity": 1, "unit_price": 4.0 }),
+            json!({ "name": "Latte", "category_id": "coffee", "quantity": 1, "unit_price": 4.0 }),
+        ];
+        let local = |time: &str| {
+            Local
+                .from_local_datetime(&at((2026, 5, 8), time))
+                .single()
+                .unwrap()
+        };
+        let delta = apply_to_items(&conn, &mut items, local("16:30")).unwrap();
+        assert_eq!(delta, Cents::new(-280));
+        assert_eq!(items[0]["unit_price"], json!(2.0));
+        assert_eq!(items[0]["total_price"], json!(4.0));
+        assert_eq!(items[0][LINE_MARKER_KEY]["id"], json!("espresso-2"));
+        assert!(!is_locked(&items[1]));
+        assert_eq!(items[2]["unit_price"], json!(3.2));
+        assert_eq!(line_label(&items[2]).as_deref(), Some("Happy Hour -20%"));
+
+        // Locked lines are never priced again, even inside another window.
+        let again = apply_to_items(&conn, &mut items, local("17:00")).unwrap();
+        assert_eq!(again, Cents::ZERO);
+        assert_eq!(
+            locked_unit_price(&items[2], Cents::new(500), RoundingRule::default()),
+            Cents::new(400)
+        );
+
+        let mut outside = vec![json!({ "menu_item_id": "espresso", "unit_price": 3.0 })];
+        assert_eq!(
+            apply_to_items(&conn, &mut outside, local("18:00")).unwrap(),
+            Cents::ZERO
+        );
+        assert!(!is_locked(&outside[0]));
+    }
+}
@@ -15,19 +15,25 @@ use std::time::Duration;
 use base64::Engine as _;
 use chrono::Utc;
 
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension};
 use serde_json::Value;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::db::{self, DbState};
 use crate::drawer;
+use crate::drawer_transactions;
+use crate::labels;
+use crate::notify::{self, NotificationKind};
+use crate::print_schedule;
+use crate::printer_watchdog;
 use crate::printers;
+use crate::receipt_copies;
 use crate::receipt_renderer::{
     self, AdjustmentLine, ClassicCustomerRenderMode, CommandProfile, DeliverySlipMode, FontType,
     HeaderEmphasis, KitchenTicketDoc, LayoutConfig, LayoutDensity, OrderReceiptDoc, PaymentLine,
     ReceiptCustomizationLine, ReceiptDocument, ReceiptEmulationMode, ReceiptItem, ReceiptTemplate,
-    ShiftCheckoutDoc, TotalsLine, ZReportDoc, PAYMENT_DETAIL_AMOUNT_UNKNOWN,
+    ShiftCheckoutDoc, TaxBreakdownLine, TotalsLine, ZReportDoc, PAYMENT_DETAIL_AMOUNT_UNKNOWN,
 };
 
 // ---------------------------------------------------------------------------
@@ -211,6 +217,26 @@ pub fn enqueue_print_job_with_payload(
     entity_id: &str,
     printer_profile_id: Option<&str>,
     entity_payload_json: Option<&Value>,
+) -> Result<Value, String> {
+    enqueue_print_job_at(
+        db,
+        entity_type,
+        entity_id,
+        printer_profile_id,
+        entity_payload_json,
+        None,
+    )
+}
+
+/// Create a print job that the worker leaves alone until `not_before`.
+/// `None` prints as soon as the worker sees the row.
+pub fn enqueue_print_job_at(
+    db: &DbState,
+    entity_type: &str,
+    entity_id: &str,
+    printer_profile_id: Option<&str>,
+    entity_payload_json: Option<&Value>,
+    not_before: Option<chrono::DateTime<Utc>>,
 ) -> Result<Value, String> {
     if entity_type != "order_receipt"
         && entity_type != "kitchen_ticket"
@@ -219,26 +245,83 @@ pub fn enqueue_print_job_with_payload(
         && entity_type != "delivery_slip"
         && entity_type != "test_print"
         && entity_type != "split_receipt"
+        && entity_type != "check_receipt"
         && entity_type != "order_completed_receipt"
         && entity_type != "order_canceled_receipt"
+        && entity_type != labels::LABEL_ENTITY_TYPE
+        && entity_type != drawer_transactions::PRINT_ENTITY_TYPE
     {
         return Err(format!(
-            "Invalid entity_type: {entity_type}. Must be order_receipt, kitchen_ticket, shift_checkout, z_report, delivery_slip, test_print, split_receipt, order_completed_receipt, or order_canceled_receipt"
+            "Invalid entity_type: {entity_type}. Must be order_receipt, kitchen_ticket, shift_checkout, z_report, delivery_slip, test_print, split_receipt, check_receipt, order_completed_receipt, order_canceled_receipt, order_label, or drawer_transaction"
         ));
     }
 
+    // The print worker only reads the live database, so a job written to the
+    // training database would never print. Report it as printed instead.
+    if db.read(|conn| Ok(crate::training::is_training_connection(conn)))? {
+        return Ok(crate::training::simulated_success(serde_json::json!({
+            "jobId": format!("training-{}", Uuid::new_v4()),
+            "message": "Print simulated in training mode",
+        })));
+    }
+
+    // Resolve before locking: profile lookups take the connection lock.
+    // A scheduled job checks the paper when it comes due, not now.
+    let deferral = if not_before.is_some() {
+        None
+    } else {
+        paper_out_deferral(db, entity_type, printer_profile_id)
+    };
+
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
-    // Idempotency: reject if a pending/printing job already exists for this entity
-    let existing: Option<String> = conn
-        .query_row(
-            "SELECT id FROM print_jobs
-             WHERE entity_type = ?1 AND entity_id = ?2
-               AND status IN ('pending', 'printing')",
-            params![entity_type, entity_id],
+    let payload_string =
+        entity_payload_json.and_then(|payload| serde_json::to_string(payload).ok());
+
+    // Immediate jobs only collide with jobs that are already due; a scheduled
+    // job only collides with one scheduled for the same time.
+    let now_time = Utc::now();
+    let (not_before_str, due_cmp) = match not_before {
+        Some(at) => (print_schedule::format_time(at), "="),
+        None => (print_schedule::IMMEDIATE.to_string(), "<="),
+    };
+    let due_by = match not_before {
+        Some(_) => not_before_str.clone(),
+        None => print_schedule::format_time(now_time),
+    };
+
+    // Idempotency: reject if a pending/printing job already exists for this entity.
+    // Kitchen tickets also compare the payload so a course fire ticket is not
+    // swallowed by an earlier ticket for the same order that is still queued;
+    // so do receipt copies, which differ only in their copy number.
+    let compares_payload =
+        entity_type == "kitchen_ticket" || receipt_copies::is_copy_payload(entity_payload_json);
+    let existing: Option<String> = if compares_payload {
+        conn.query_row(
+            &format!(
+                "SELECT id FROM print_jobs
+                 WHERE entity_type = ?1 AND entity_id = ?2
+                   AND status IN ('pending', 'printing', 'deferred')
+                   AND not_before {due_cmp} ?4
+                   AND entity_payload_json IS ?3"
+            ),
+            params![entity_type, entity_id, payload_string, due_by],
             |row| row.get(0),
         )
-        .ok();
+        .ok()
+    } else {
+        conn.query_row(
+            &format!(
+                "SELECT id FROM print_jobs
+                 WHERE entity_type = ?1 AND entity_id = ?2
+                   AND status IN ('pending', 'printing', 'deferred')
+                   AND not_before {due_cmp} ?3"
+            ),
+            params![entity_type, entity_id, due_by],
+            |row| row.get(0),
+        )
+        .ok()
+    };
 
     if let Some(existing_id) = existing {
         return Ok(serde_json::json!({
@@ -250,26 +333,100 @@ pub fn enqueue_print_job_with_payload(
     }
 
     let job_id = Uuid::new_v4().to_string();
-    let now = Utc::now().to_rfc3339();
-    let payload_string =
-        entity_payload_json.and_then(|payload| serde_json::to_string(payload).ok());
+    let now = now_time.to_rfc3339();
+
+    // A deferred job is pinned to the printer it is waiting for, so the
+    // watchdog can release it when that printer has paper again.
+    let (status, job_profile_id, warning_code, warning_message) = match &deferral {
+        Some((profile_id, reason)) => (
+            printer_watchdog::DEFERRED_STATUS,
+            Some(profile_id.as_str()),
+            Some(printer_watchdog::PAPER_OUT_WARNING_CODE),
+            Some(reason.as_str()),
+        ),
+        None => ("pending", printer_profile_id, None, None),
+    };
 
     conn.execute(
         "INSERT INTO print_jobs (id, entity_type, entity_id, entity_payload_json, printer_profile_id,
-                                 status, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, 'pending', ?6, ?6)",
+                                 status, warning_code, warning_message, not_before, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)",
         params![
             job_id,
             entity_type,
             entity_id,
             payload_string,
-            printer_profile_id,
+            job_profile_id,
+            status,
+            warning_code,
+            warning_message,
+            not_before_str,
             now
         ],
     )
     .map_err(|e| format!("enqueue print job: {e}"))?;
 
-    info!(job_id = %job_id, entity_type = %entity_type, entity_id = %entity_id, "Print job enqueued");
+    info!(job_id = %job_id, entity_type = %entity_type, entity_id = %entity_id, status, "Print job enqueued");
+    let summary = match not_before {
+        Some(_) => serde_json::json!({
+            "jobId": job_id,
+            "entityType": entity_type,
+            "notBefore": not_before_str,
+        }),
+        None => serde_json::json!({ "jobId": job_id, "entityType": entity_type }),
+    };
+    match entity_type {
+        "order_receipt"
+        | "kitchen_ticket"
+        | "delivery_slip"
+        | "order_completed_receipt"
+        | "order_canceled_receipt"
+        | labels::LABEL_ENTITY_TYPE => crate::order_events::append(
+            &conn,
+            entity_id,
+            crate::order_events::PRINT_ENQUEUED,
+            None,
+            summary,
+        ),
+        "split_receipt" => crate::order_events::append_for_payment(
+            &conn,
+            entity_id,
+            crate::order_events::PRINT_ENQUEUED,
+            None,
+            summary,
+        ),
+        "check_receipt" => {
+            if let Ok(Some(check)) = crate::checks::load(&conn, entity_id) {
+                crate::order_events::append(
+                    &conn,
+                    &check.order_id,
+                    crate::order_events::PRINT_ENQUEUED,
+                    None,
+                    summary,
+                );
+            }
+        }
+        _ => {}
+    }
+
+    if let Some((_, reason)) = deferral {
+        return Ok(serde_json::json!({
+            "success": true,
+            "jobId": job_id,
+            "deferred": true,
+            "message": reason,
+        }));
+    }
+
+    if not_before.is_some() {
+        return Ok(serde_json::json!({
+            "success": true,
+            "jobId": job_id,
+            "scheduled": true,
+            "notBefore": not_before_str,
+            "message": "Print job scheduled",
+        }));
+    }
 
     Ok(serde_json::json!({
         "success": true,
@@ -278,6 +435,149 @@ pub fn enqueue_print_job_with_payload(
     }))
 }
 
+/// The printer profile and reason to hold a new job back, when the printer
+/// it would print on last reported paper-out. Label printers are not
+/// polled.
+fn paper_out_deferral(
+    db: &DbState,
+    entity_type: &str,
+    printer_profile_id: Option<&str>,
+) -> Option<(String, String)> {
+    if entity_type == labels::LABEL_ENTITY_TYPE || !printer_watchdog::any_paper_out() {
+        return None;
+    }
+    let profile = printers::resolve_printer_profile_for_role(
+        db,
+        printer_profile_id,
+        Some(dispatch_role(entity_type)),
+    )
+    .ok()
+    .flatten()?;
+    let profile_id = profile.get("id").and_then(Value::as_str)?;
+    let name = profile
+        .get("name")
+        .and_then(Value::as_str)
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or(profile_id);
+    let reason = printer_watchdog::deferral_reason(profile_id, name)?;
+    Some((profile_id.to_string(), reason))
+}
+
+/// The most recent customer receipt job printed for an order.
+#[derive(Debug, Clone)]
+pub struct ReceiptJobRef {
+    pub job_id: String,
+    pub entity_type: String,
+    pub created_at: String,
+}
+
+pub fn latest_receipt_job(
+    conn: &rusqlite::Connection,
+    order_id: &str,
+) -> Result<Option<ReceiptJobRef>, String> {
+    conn.query_row(
+        "SELECT id, entity_type, created_at FROM print_jobs
+         WHERE entity_id = ?1
+           AND entity_type IN ('order_receipt', 'order_completed_receipt')
+         ORDER BY created_at DESC, rowid DESC
+         LIMIT 1",
+        params![order_id],
+        |row| {
+            Ok(ReceiptJobRef {
+                job_id: row.get(0)?,
+                entity_type: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("load latest receipt job: {e}"))
+}
+
+/// Whether reprinting `job` needs manager approval: only when
+/// `receipt.reprint_requires_approval_for_past_days` is on and the original
+/// was printed before today (local time).
+pub fn receipt_reprint_needs_approval(conn: &rusqlite::Connection, job: &ReceiptJobRef) -> bool {
+    if !setting_bool(conn, "receipt", "reprint_requires_approval_for_past_days") {
+        return false;
+    }
+    let today = chrono::Local::now().date_naive();
+    chrono::DateTime::parse_from_rfc3339(&job.created_at)
+        .map(|printed| printed.with_timezone(&chrono::Local).date_naive() < today)
+        .unwrap_or(true)
+}
+
+/// Enqueue a duplicate of the order's latest receipt. The new job carries the
+/// next reprint number so the renderer prints a "DUPLICATE #n" marker, and
+/// the reprint is recorded on the order timeline with the requesting staff.
+pub fn enqueue_receipt_reprint(
+    db: &DbState,
+    order_id: &str,
+    staff_id: Option<&str>,
+) -> Result<Value, String> {
+    let (latest, reprint_count) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let latest = latest_receipt_job(&conn, order_id)?
+            .ok_or_else(|| "No receipt has been printed for this order".to_string())?;
+        let previous: i64 = conn
+            .query_row(
+                "SELECT COALESCE(MAX(reprint_count), 0) FROM print_jobs
+                 WHERE entity_id = ?1
+                   AND entity_type IN ('order_receipt', 'order_completed_receipt')",
+                params![order_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("load reprint count: {e}"))?;
+        (latest, previous + 1)
+    };
+
+    let reprinted_at = Utc::now().to_rfc3339();
+    let payload = serde_json::json!({
+        "reprintCount": reprint_count,
+        "reprintedAt": reprinted_at,
+        "reprintedBy": staff_id,
+        "sourceJobId": latest.job_id,
+    });
+    let result =
+        enqueue_print_job_with_payload(db, &latest.entity_type, order_id, None, Some(&payload))?;
+    if result.get("duplicate").and_then(Value::as_bool) == Some(true) {
+        // A copy is already waiting in the queue; don't burn a number on it.
+        return Ok(result);
+    }
+    let job_id = result
+        .get("jobId")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE print_jobs SET reprint_count = ?1 WHERE id = ?2",
+        params![reprint_count, job_id],
+    )
+    .map_err(|e| format!("record reprint count: {e}"))?;
+    crate::order_events::append(
+        &conn,
+        order_id,
+        crate::order_events::RECEIPT_REPRINTED,
+        staff_id,
+        serde_json::json!({
+            "jobId": job_id,
+            "sourceJobId": latest.job_id,
+            "reprintCount": reprint_count,
+        }),
+    );
+    info!(order_id = %order_id, job_id = %job_id, reprint_count, "Receipt reprint enqueued");
+
+    Ok(serde_json::json!({
+        "success": true,
+        "jobId": job_id,
+        "reprintCount": reprint_count,
+        "reprintedAt": reprinted_at,
+        "message": "Receipt reprint enqueued",
+    }))
+}
+
 // ---------------------------------------------------------------------------
 // Query
 // ---------------------------------------------------------------------------
@@ -296,13 +596,18 @@ pub fn list_print_jobs_with_filters(
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
     let row_mapper = |row: &rusqlite::Row<'_>| {
+        let status: String = row.get(5)?;
+        let remediation = (status == "failed")
+            .then(|| crate::remediation::lookup(crate::remediation::PRINTER_JOB_FAILED))
+            .flatten()
+            .map(crate::remediation::to_json);
         Ok(serde_json::json!({
             "id": row.get::<_, String>(0)?,
             "entityType": row.get::<_, String>(1)?,
             "entityId": row.get::<_, String>(2)?,
             "entityPayloadJson": row.get::<_, Option<String>>(3)?,
             "printerProfileId": row.get::<_, Option<String>>(4)?,
-            "status": row.get::<_, String>(5)?,
+            "status": status,
             "outputPath": row.get::<_, Option<String>>(6)?,
             "retryCount": row.get::<_, i32>(7)?,
             "maxRetries": row.get::<_, i32>(8)?,
@@ -313,6 +618,7 @@ pub fn list_print_jobs_with_filters(
             "lastAttemptAt": row.get::<_, Option<String>>(13)?,
             "createdAt": row.get::<_, String>(14)?,
             "updatedAt": row.get::<_, String>(15)?,
+            "remediation": remediation,
         }))
     };
 
@@ -420,7 +726,7 @@ pub fn cancel_print_job(db: &DbState, job_id: &str) -> Result<Value, String> {
                  warning_code = 'operator_cancelled',
                  warning_message = 'Print job cancelled from the print queue',
                  updated_at = datetime('now')
-             WHERE id = ?1 AND status IN ('pending', 'printing')",
+             WHERE id = ?1 AND status IN ('pending', 'printing', 'deferred')",
             params![job_id],
         )
         .map_err(|e| e.to_string())?;
@@ -437,10 +743,16 @@ pub fn cancel_print_jobs(
             values
                 .iter()
                 .map(|value| value.trim().to_ascii_lowercase())
-                .filter(|value| matches!(value.as_str(), "pending" | "printing"))
+                .filter(|value| matches!(value.as_str(), "pending" | "printing" | "deferred"))
                 .collect()
         })
-        .unwrap_or_else(|| vec!["pending".to_string(), "printing".to_string()]);
+        .unwrap_or_else(|| {
+            vec![
+                "pending".to_string(),
+                "printing".to_string(),
+                "deferred".to_string(),
+            ]
+        });
 
     if statuses.is_empty() {
         return Err("No cancellable print job statuses were provided".into());
@@ -456,7 +768,7 @@ pub fn cancel_print_jobs(
                 .prepare(
                     "SELECT id FROM print_jobs
                      WHERE COALESCE(printer_profile_id, '') = COALESCE(?1, '')
-                       AND status IN ('pending', 'printing')",
+                       AND status IN ('pending', 'printing', 'deferred')",
                 )
                 .map_err(|e| e.to_string())?;
             let job_ids: Vec<String> = stmt
@@ -485,7 +797,7 @@ pub fn cancel_print_jobs(
                                  warning_code = 'operator_cancelled',
                                  warning_message = 'Print jobs cancelled from the print queue',
                                  updated_at = ?1
-                             WHERE id = ?2 AND status IN ('pending', 'printing')",
+                             WHERE id = ?2 AND status IN ('pending', 'printing', 'deferred')",
                             params![now, job_id],
                         )
                         .map_err(|e| e.to_string())?;
@@ -670,36 +982,26 @@ fn lock_conn_recovering(db: &DbState) -> std::sync::MutexGuard<'_, rusqlite::Con
 /// pending rows fill the whole window, so every healthy printer's newer jobs
 /// are silently starved and never printed. Jobs with a NULL profile are always
 /// eligible.
+///
+/// Jobs scheduled for later are skipped by `not_before`. The plain
+/// `not_before <= ?` comparison lets SQLite walk the `(status, not_before)`
+/// index, so a backlog of future jobs is never scanned.
 fn select_ready_pending_jobs(
     conn: &rusqlite::Connection,
     now_str: &str,
     paused_profiles: &std::collections::HashSet<String>,
     limit: usize,
 ) -> Result<Vec<(String, String, String, Option<String>, Option<String>)>, String> {
-    let mut sql = String::from(
-        "SELECT id, entity_type, entity_id, entity_payload_json, printer_profile_id FROM print_jobs
-         WHERE status = 'pending'
-           AND (next_retry_at IS NULL OR julianday(next_retry_at) <= julianday(?1))",
-    );
-
     let paused: Vec<&String> = paused_profiles.iter().collect();
-    if !paused.is_empty() {
-        // ?1 is now_str; paused ids bind starting at ?2.
-        let placeholders = (0..paused.len())
-            .map(|i| format!("?{}", i + 2))
-            .collect::<Vec<_>>()
-            .join(", ");
-        sql.push_str(&format!(
-            " AND (printer_profile_id IS NULL OR printer_profile_id NOT IN ({placeholders}))"
-        ));
-    }
-    sql.push_str(&format!(" ORDER BY created_at ASC LIMIT {limit}"));
+    let sql = ready_pending_jobs_sql(paused.len(), limit);
 
     let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
 
-    // Positional binds: ?1 = now_str, ?2.. = paused profile ids.
-    let mut binds: Vec<String> = Vec::with_capacity(1 + paused.len());
+    // Positional binds: ?1 = now_str, ?2 = due-by, ?3.. = paused profile ids.
+    let due_by = print_schedule::normalize(now_str).unwrap_or_else(|| now_str.to_string());
+    let mut binds: Vec<String> = Vec::with_capacity(2 + paused.len());
     binds.push(now_str.to_string());
+    binds.push(due_by);
     for profile in &paused {
         binds.push((*profile).clone());
     }
@@ -720,6 +1022,27 @@ fn select_ready_pending_jobs(
     Ok(rows)
 }
 
+fn ready_pending_jobs_sql(paused_count: usize, limit: usize) -> String {
+    let mut sql = String::from(
+        "SELECT id, entity_type, entity_id, entity_payload_json, printer_profile_id FROM print_jobs
+         WHERE status = 'pending'
+           AND not_before <= ?2
+           AND (next_retry_at IS NULL OR julianday(next_retry_at) <= julianday(?1))",
+    );
+    if paused_count > 0 {
+        // ?1 is now_str, ?2 its `not_before` form; paused ids bind from ?3.
+        let placeholders = (0..paused_count)
+            .map(|i| format!("?{}", i + 3))
+            .collect::<Vec<_>>()
+            .join(", ");
+        sql.push_str(&format!(
+            " AND (printer_profile_id IS NULL OR printer_profile_id NOT IN ({placeholders}))"
+        ));
+    }
+    sql.push_str(&format!(" ORDER BY created_at ASC LIMIT {limit}"));
+    sql
+}
+
 /// Run a blocking hardware-dispatch closure under a hard wall-clock timeout.
 ///
 /// `print_raw_to_windows` (the default Windows spooler transport) has no timeout
@@ -1093,12 +1416,14 @@ fn parse_item_total(item: &Value) -> f64 {
 #[derive(Debug, Default, Clone)]
 struct MenuSubcategoryEntry {
     name: String,
+    /// `name_<lang>` for the print language, when the menu has one.
+    localized_name: Option<String>,
     category_id: Option<String>,
     category_name: Option<String>,
 }
 
 #[derive(Debug, Default, Clone)]
-struct MenuCategoryLookup {
+pub(crate) struct MenuCategoryLookup {
     categories_by_id: HashMap<String, String>,
     subcategories_by_id: HashMap<String, MenuSubcategoryEntry>,
 }
@@ -1132,12 +1457,55 @@ fn parse_cached_menu_section(conn: &rusqlite::Connection, key: &str) -> Vec<Valu
         .unwrap_or_default()
 }
 
-fn build_menu_category_lookup(conn: &rusqlite::Connection) -> MenuCategoryLookup {
+/// Item name in the print language: the line's own `name_<lang>`, then the
+/// menu's localized name for its `menu_item_id`, then the name stored on the
+/// line.
+pub(crate) fn localized_item_name(
+    item: &Value,
+    language: &str,
+    lookup: &MenuCategoryLookup,
+) -> String {
+    let localized_key = format!("name_{language}");
+    text_from_keys(item, &[localized_key.as_str()])
+        .or_else(|| {
+            text_from_keys(item, &["menu_item_id", "menuItemId"])
+                .and_then(|id| normalized_lookup_key(&id))
+                .and_then(|key| lookup.subcategories_by_id.get(&key))
+                .and_then(|entry| entry.localized_name.clone())
+        })
+        .or_else(|| text_from_keys(item, &["name", "itemName", "menu_item_name", "title"]))
+        .unwrap_or_else(|| "Item".to_string())
+}
+
+/// Language for a print: the job's override when given, else the
+/// `general.language` setting, else English.
+pub(crate) fn print_language(conn: &rusqlite::Connection, job_override: Option<&str>) -> String {
+    job_override
+        .map(str::to_string)
+        .or_else(|| setting_text(conn, "general", "language"))
+        .map(|raw| receipt_renderer::normalize_receipt_language(&raw))
+        .unwrap_or_else(|| "en".to_string())
+}
+
+/// Per-job language override carried in a print job payload.
+fn payload_language(payload: Option<&Value>) -> Option<String> {
+    payload.and_then(|payload| object_text_field(payload, &["language", "lang", "locale"]))
+}
+
+/// Category and subcategory names from the cached menu, preferring the
+/// `name_<lang>` field for `language`.
+pub(crate) fn build_menu_category_lookup(
+    conn: &rusqlite::Connection,
+    language: &str,
+) -> MenuCategoryLookup {
     let mut lookup = MenuCategoryLookup::default();
+    let localized_key = format!("name_{language}");
 
     for category in parse_cached_menu_section(conn, "categories") {
         let id = text_from_keys(&category, &["id", "category_id", "categoryId"]);
-        let name = text_from_keys(&category, &["name", "name_el", "name_en", "title", "label"]);
+        let name = text_from_keys(&category, &[localized_key.as_str()]).or_else(|| {
+            text_from_keys(&category, &["name", "name_el", "name_en", "title", "label"])
+        });
         if let (Some(id), Some(name)) = (id, name) {
             if let Some(key) = normalized_lookup_key(&id) {
                 lookup.categories_by_id.insert(key, name);
@@ -1171,12 +1539,14 @@ fn build_menu_category_lookup(conn: &rusqlite::Connection) -> MenuCategoryLookup
             ],
         );
         let category_name = text_from_keys(&subcategory, &["category_name", "categoryName"]);
+        let localized_name = text_from_keys(&subcategory, &[localized_key.as_str()]);
         if let (Some(id), Some(name)) = (id, name) {
             if let Some(key) = normalized_lookup_key(&id) {
                 lookup.subcategories_by_id.insert(
                     key,
                     MenuSubcategoryEntry {
-                        name,
+                        localized_name: localized_name.clone(),
+                        name: localized_name.unwrap_or(name),
                         category_id,
                         category_name,
                     },
@@ -1318,6 +1688,18 @@ fn title_case_payment_method(input: &str) -> Option<String> {
     }
 }
 
+/// "Rounding" line for a cash tender rounded per `payments.cash_rounding`:
+/// the signed difference between the cash collected and the exact amount.
+fn push_rounding_line(payments: &mut Vec<PaymentLine>, rounding_delta: Option<f64>) {
+    if let Some(delta) = rounding_delta.filter(|delta| delta.abs() >= 0.005) {
+        payments.push(PaymentLine {
+            label: "Rounding".to_string(),
+            amount: delta,
+            detail: None,
+        });
+    }
+}
+
 fn fallback_payment_line_from_order_snapshot(
     payment_method: &str,
     payment_status: &str,
@@ -1387,6 +1769,19 @@ fn build_item_note_text(item: &Value) -> Option<String> {
     }
 }
 
+/// Item note for customer receipts: the line's notes plus the pricing rule
+/// it was sold under ("Happy Hour -20%").
+fn build_receipt_item_note_text(item: &Value) -> Option<String> {
+    let note = build_item_note_text(item);
+    match crate::pricing_rules::line_label(item) {
+        Some(label) => Some(match note {
+            Some(note) => format!("{note} | {label}"),
+            None => label,
+        }),
+        None => note,
+    }
+}
+
 pub fn resolve_layout_config(
     db: &DbState,
     profile: &Value,
@@ -1449,13 +1844,18 @@ pub fn resolve_layout_config(
         .or_else(|| setting_text(&conn, "terminal", "store_phone"));
     let currency_symbol = setting_text(&conn, "receipt", "currency_symbol")
         .or_else(|| setting_text(&conn, "organization", "currency_symbol"))
+        .or_else(|| {
+            setting_text(&conn, "general", "currency")
+                .and_then(|raw| crate::money::CurrencySettings::parse(&raw))
+                .map(|currency| format!(" {}", currency.symbol))
+        })
         .or_else(|| {
             // Default currency symbol based on language when not explicitly set
             let lang = setting_text(&conn, "general", "language").unwrap_or_default();
-            match lang.as_str() {
-                "el" | "de" | "fr" | "it" | "es" | "pt" | "nl" => Some(" \u{20AC}".to_string()),
-                _ => None,
-            }
+            receipt_renderer::language_uses_decimal_comma(
+                &receipt_renderer::normalize_receipt_language(&lang),
+            )
+            .then(|| " \u{20AC}".to_string())
         })
         .unwrap_or_default();
     let vat_number = setting_text(&conn, "organization", "vat_number")
@@ -1658,7 +2058,9 @@ pub fn resolve_layout_config(
         requested_header_emphasis
     };
 
-    let app_language = setting_text(&conn, "general", "language").unwrap_or_default();
+    let app_language = setting_text(&conn, "general", "language")
+        .map(|raw| receipt_renderer::normalize_receipt_language(&raw))
+        .unwrap_or_default();
     // Known brands (Star, Epson) support logo raster even if the profile
     // hasn't been verified yet.  Only suppress logo for truly unknown printers
     // where we can't be sure the firmware handles raster images.
@@ -1775,7 +2177,7 @@ pub fn resolve_layout_config(
         _ => 400,
     };
 
-    Ok(LayoutConfig {
+    let mut layout = LayoutConfig {
         paper_width: crate::escpos::PaperWidth::from_mm(paper_mm),
         template,
         command_profile,
@@ -1801,10 +2203,7 @@ pub fn resolve_layout_config(
         layout_density,
         header_emphasis,
         layout_density_scale,
-        decimal_comma: matches!(
-            app_language.as_str(),
-            "el" | "de" | "fr" | "it" | "es" | "pt" | "nl"
-        ),
+        decimal_comma: receipt_renderer::language_uses_decimal_comma(&app_language),
         classic_customer_render_mode,
         emulation_mode,
         printable_width_dots,
@@ -1813,7 +2212,12 @@ pub fn resolve_layout_config(
         text_scale,
         logo_scale,
         body_font_weight,
-    })
+    };
+    receipt_renderer::apply_training_marker(
+        &mut layout,
+        crate::training::is_training_connection(&conn),
+    );
+    Ok(layout)
 }
 
 fn paper_logo_max_width_dots(paper: crate::escpos::PaperWidth) -> u32 {
@@ -2549,7 +2953,16 @@ fn kiosk_context_note_from_metadata(raw: &str) -> Option<String> {
 }
 
 pub fn build_order_receipt_doc(db: &DbState, order_id: &str) -> Result<OrderReceiptDoc, String> {
+    build_order_receipt_doc_in_language(db, order_id, None)
+}
+
+fn build_order_receipt_doc_in_language(
+    db: &DbState,
+    order_id: &str,
+    language: Option<&str>,
+) -> Result<OrderReceiptDoc, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let language = print_language(&conn, language);
     // W6: `orders.payment_method` was dropped in v55. Derive the method
     // from completed `order_payments` rows via the canonical helper. For
     // orders with no completed payment rows, `derive_payment_method`
@@ -2573,7 +2986,7 @@ pub fn build_order_receipt_doc(db: &DbState, order_id: &str) -> Result<OrderRece
                     COALESCE(delivery_notes, ''), COALESCE(special_instructions, ''),
                     COALESCE(payment_status, ''),
                     COALESCE(payment_transaction_id, ''),
-                    COALESCE(ghost_metadata, '')
+                    COALESCE(ghost_metadata, ''), COALESCE(service_charge, 0)
              FROM orders WHERE id = ?1",
             params![order_id],
             |row| {
@@ -2606,6 +3019,7 @@ pub fn build_order_receipt_doc(db: &DbState, order_id: &str) -> Result<OrderRece
                     row.get::<_, String>(25)?,
                     row.get::<_, String>(26)?,
                     row.get::<_, String>(27)?,
+                    row.get::<_, f64>(28)?,
                 ))
             },
         )
@@ -2639,9 +3053,10 @@ pub fn build_order_receipt_doc(db: &DbState, order_id: &str) -> Result<OrderRece
         payment_status,
         payment_transaction_id,
         ghost_metadata,
+        service_charge,
     ) = order;
     let payment_method = derived_payment_method;
-    let menu_lookup = build_menu_category_lookup(&conn);
+    let menu_lookup = build_menu_category_lookup(&conn, &language);
 
     let items: Vec<ReceiptItem> = serde_json::from_str::<Value>(&items_json)
         .ok()
@@ -2651,21 +3066,15 @@ pub fn build_order_receipt_doc(db: &DbState, order_id: &str) -> Result<OrderRece
         .map(|item| {
             let category_fields = resolve_item_category_fields(&item, &menu_lookup);
             ReceiptItem {
-                name: item
-                    .get("name")
-                    .or_else(|| item.get("itemName"))
-                    .or_else(|| item.get("menu_item_name"))
-                    .or_else(|| item.get("title"))
-                    .and_then(Value::as_str)
-                    .unwrap_or("Item")
-                    .to_string(),
+                name: localized_item_name(&item, &language, &menu_lookup),
                 quantity: item.get("quantity").and_then(parse_number).unwrap_or(1.0),
                 total: parse_item_total(&item),
                 category_name: category_fields.category_name,
                 subcategory_name: category_fields.subcategory_name,
                 category_path: category_fields.category_path,
-                note: build_item_note_text(&item),
+                note: build_receipt_item_note_text(&item),
                 customizations: parse_item_customizations(&item),
+                allergy_flags: Vec::new(),
             }
         })
         .collect();
@@ -2678,7 +3087,7 @@ pub fn build_order_receipt_doc(db: &DbState, order_id: &str) -> Result<OrderRece
 
     let effective_discount = discount_amount.max(0.0);
     let computed_subtotal =
-        total_amount - tax_amount - delivery_fee - tip_amount + effective_discount;
+        total_amount - tax_amount - delivery_fee - service_charge - tip_amount + effective_discount;
     let display_subtotal = if computed_subtotal.is_finite() && computed_subtotal > 0.0 {
         computed_subtotal
     } else {
@@ -2720,6 +3129,25 @@ pub fn build_order_receipt_doc(db: &DbState, order_id: &str) -> Result<OrderRece
             discount_percent: None,
         });
     }
+    // Platform commission is internal accounting and never printed.
+    if service_charge > 0.0 {
+        // An automatic charge prints its captured rate; platform charges
+        // carry none.
+        let service_charge_rate: Option<f64> = conn
+            .query_row(
+                "SELECT service_charge_rate FROM orders WHERE id = ?1",
+                params![order_id],
+                |row| row.get(0),
+            )
+            .ok()
+            .flatten();
+        totals.push(TotalsLine {
+            label: "Service charge".to_string(),
+            amount: service_charge,
+            emphasize: false,
+            discount_percent: service_charge_rate,
+        });
+    }
     if tip_amount > 0.0 {
         totals.push(TotalsLine {
             label: "Tip".to_string(),
@@ -2735,62 +3163,24 @@ pub fn build_order_receipt_doc(db: &DbState, order_id: &str) -> Result<OrderRece
         discount_percent: None,
     });
 
-    let mut payments_stmt = conn
-        .prepare(
-            "SELECT COALESCE(method, ''), COALESCE(amount, 0), cash_received, change_given, COALESCE(transaction_ref, '')
-             FROM order_payments
-             WHERE order_id = ?1 AND status = 'completed'
-             ORDER BY created_at ASC",
-        )
-        .map_err(|e| format!("prepare payments: {e}"))?;
-
-    type PaymentRow = (String, f64, Option<f64>, Option<f64>, String);
-    let payment_rows: Vec<PaymentRow> = payments_stmt
-        .query_map(params![order_id], |row| {
-            Ok((
-                row.get(0)?,
-                row.get(1)?,
-                row.get(2)?,
-                row.get(3)?,
-                row.get(4)?,
-            ))
-        })
-        .map_err(|e| format!("query payments: {e}"))?
-        .filter_map(|r| r.ok())
-        .collect();
-
-    let mut payments = Vec::new();
-    let mut masked_card = None;
-    for (method, amount, cash_received, change_given, transaction_ref) in payment_rows {
-        let label = match method.as_str() {
-            "cash" => "Cash",
-            "card" => "Card",
-            _ => "Other",
-        };
-        let normalized_amount = if method == "cash" {
-            cash_received
-                .filter(|received| *received > 0.0)
-                .unwrap_or(amount)
-        } else {
-            amount
-        };
-        payments.push(PaymentLine {
-            label: label.to_string(),
-            amount: normalized_amount,
-            detail: None,
-        });
-        if let Some(change) = change_given {
-            if change > 0.0 {
+    let (mut payments, mut masked_card) = load_receipt_payment_lines(&conn, "order_id", order_id)?;
+    // Deposits: a partially paid order shows what was taken so far and
+    // what is still owed instead of reading as settled.
+    if !payments.is_empty() {
+        if let Ok(balance) = crate::payments::load_order_payment_balance_snapshot(&conn, order_id) {
+            if balance.net_paid > 0.0 && balance.outstanding_amount >= 0.005 {
                 payments.push(PaymentLine {
-                    label: "Change".to_string(),
-                    amount: change,
+                    label: "Deposit received".to_string(),
+                    amount: balance.net_paid,
+                    detail: None,
+                });
+                payments.push(PaymentLine {
+                    label: "Balance due".to_string(),
+                    amount: balance.outstanding_amount,
                     detail: None,
                 });
             }
         }
-        if masked_card.is_none() && method == "card" {
-            masked_card = extract_masked_card_reference(&transaction_ref);
-        }
     }
     if payments.is_empty() {
         if let Some(payment) = fallback_payment_line_from_order_snapshot(
@@ -2862,21 +3252,194 @@ pub fn build_order_receipt_doc(db: &DbState, order_id: &str) -> Result<OrderRece
         totals,
         payments,
         adjustments,
+        tax_breakdown: load_receipt_tax_breakdown(&conn, order_id),
+        fiscal_document_numbers: load_receipt_fiscal_numbers(&conn, order_id, None),
         masked_card,
         order_notes,
         status_label: None,
         cancellation_reason: None,
+        reprint_count: 0,
+        reprinted_at: None,
+        merchant_copy: false,
+        return_of_order_number: load_return_of_order_number(&conn, order_id),
     })
 }
 
+/// Order number of the sale a return order refunds; `None` for ordinary
+/// orders (and for exchange orders, which link to the return, not a sale).
+fn load_return_of_order_number(conn: &rusqlite::Connection, order_id: &str) -> Option<String> {
+    conn.query_row(
+        "SELECT original.order_number
+         FROM orders ret
+         JOIN orders original ON original.id = ret.related_order_id
+         WHERE ret.id = ?1 AND COALESCE(ret.is_return, 0) = 1",
+        params![order_id],
+        |row| row.get::<_, Option<String>>(0),
+    )
+    .ok()
+    .flatten()
+    .filter(|number| !number.trim().is_empty())
+}
+
+/// Per-rate tax lines stored on the order when it was rung up.
+fn load_receipt_tax_breakdown(
+    conn: &rusqlite::Connection,
+    order_id: &str,
+) -> Vec<TaxBreakdownLine> {
+    let raw: Option<String> = conn
+        .query_row(
+            "SELECT tax_breakdown FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| row.get(0),
+        )
+        .ok()
+        .flatten();
+    receipt_tax_lines(crate::tax::parse_breakdown(raw.as_deref()))
+}
+
+fn receipt_tax_lines(lines: Vec<crate::tax::TaxBreakdownLine>) -> Vec<TaxBreakdownLine> {
+    lines
+        .into_iter()
+        .map(|line| TaxBreakdownLine {
+            rate: line.rate_percent(),
+            net: crate::money::Cents::new(line.net_cents).to_f64_dp2(),
+            tax: crate::money::Cents::new(line.tax_cents).to_f64_dp2(),
+            gross: crate::money::Cents::new(line.gross_cents).to_f64_dp2(),
+            label: line.label,
+        })
+        .collect()
+}
+
+/// Tender lines for the completed payments whose `key_column` (`order_id`
+/// or `check_id`) equals `key`, oldest first, plus the first masked card
+/// reference among them.
+fn load_receipt_payment_lines(
+    conn: &rusqlite::Connection,
+    key_column: &str,
+    key: &str,
+) -> Result<(Vec<PaymentLine>, Option<String>), String> {
+    debug_assert!(matches!(key_column, "order_id" | "check_id"));
+    let mut payments_stmt = conn
+        .prepare(&format!(
+            "SELECT COALESCE(method, ''), COALESCE(amount, 0), cash_received, change_given, COALESCE(transaction_ref, ''),
+                    rounded_amount, rounding_delta
+             FROM order_payments
+             WHERE {key_column} = ?1 AND status = 'completed'
+             ORDER BY created_at ASC"
+        ))
+        .map_err(|e| format!("prepare payments: {e}"))?;
+
+    type PaymentRow = (
+        String,
+        f64,
+        Option<f64>,
+        Option<f64>,
+        String,
+        Option<f64>,
+        Option<f64>,
+    );
+    let payment_rows: Vec<PaymentRow> = payments_stmt
+        .query_map(params![key], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                row.get(6)?,
+            ))
+        })
+        .map_err(|e| format!("query payments: {e}"))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut payments = Vec::new();
+    let mut masked_card = None;
+    for (
+        method,
+        amount,
+        cash_received,
+        change_given,
+        transaction_ref,
+        rounded_amount,
+        rounding_delta,
+    ) in payment_rows
+    {
+        let label = match method.as_str() {
+            "cash" => "Cash",
+            "card" => "Card",
+            _ => "Other",
+        };
+        let normalized_amount = if method == "cash" {
+            cash_received
+                .filter(|received| *received > 0.0)
+                .or(rounded_amount)
+                .unwrap_or(amount)
+        } else {
+            amount
+        };
+        push_rounding_line(&mut payments, rounding_delta);
+        payments.push(PaymentLine {
+            label: label.to_string(),
+            amount: normalized_amount,
+            detail: None,
+        });
+        if let Some(change) = change_given {
+            if change > 0.0 {
+                payments.push(PaymentLine {
+                    label: "Change".to_string(),
+                    amount: change,
+                    detail: None,
+                });
+            }
+        }
+        if masked_card.is_none() && method == "card" {
+            masked_card = extract_masked_card_reference(&transaction_ref);
+        }
+    }
+    Ok((payments, masked_card))
+}
+
+/// Numbers of the live (not cancelled) fiscal receipts for an order, or
+/// for a single payment of it.
+fn load_receipt_fiscal_numbers(
+    conn: &rusqlite::Connection,
+    order_id: &str,
+    payment_id: Option<&str>,
+) -> Vec<String> {
+    crate::fiscal::documents::list_order_documents(conn, order_id)
+        .map(|documents| {
+            let cancelled: HashSet<String> = documents
+                .iter()
+                .filter_map(|document| document.cancels_document_id.clone())
+                .collect();
+            documents
+                .into_iter()
+                .filter(|document| document.document_type == "receipt")
+                .filter(|document| !cancelled.contains(&document.id))
+                .filter(|document| {
+                    payment_id.map_or(true, |id| document.payment_id.as_deref() == Some(id))
+                })
+                .map(|document| document.document_number)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// Build a receipt document for a single split payment.
 ///
 /// The `payment_id` identifies which payment to print. If payment_items
 /// exist for this payment, only those items are shown. Otherwise all order
 /// items are included with a "Split Payment" header. Only the single
 /// payment line is shown.
-fn build_split_receipt_doc(db: &DbState, payment_id: &str) -> Result<OrderReceiptDoc, String> {
+fn build_split_receipt_doc(
+    db: &DbState,
+    payment_id: &str,
+    language: Option<&str>,
+) -> Result<OrderReceiptDoc, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let language = print_language(&conn, language);
 
     // Load the payment record
     let (
@@ -2887,6 +3450,8 @@ fn build_split_receipt_doc(db: &DbState, payment_id: &str) -> Result<OrderReceip
         change_given,
         transaction_ref,
         discount_amount,
+        rounded_amount,
+        rounding_delta,
     ): (
         String,
         String,
@@ -2895,11 +3460,13 @@ fn build_split_receipt_doc(db: &DbState, payment_id: &str) -> Result<OrderReceip
         Option<f64>,
         String,
         f64,
+        Option<f64>,
+        Option<f64>,
     ) = conn
         .query_row(
             "SELECT order_id, COALESCE(method, ''), COALESCE(amount, 0),
                     cash_received, change_given, COALESCE(transaction_ref, ''),
-                    COALESCE(discount_amount, 0)
+                    COALESCE(discount_amount, 0), rounded_amount, rounding_delta
              FROM order_payments WHERE id = ?1 AND status = 'completed'",
             params![payment_id],
             |row| {
@@ -2911,6 +3478,8 @@ fn build_split_receipt_doc(db: &DbState, payment_id: &str) -> Result<OrderReceip
                     row.get(4)?,
                     row.get(5)?,
                     row.get(6)?,
+                    row.get(7)?,
+                    row.get(8)?,
                 ))
             },
         )
@@ -2977,7 +3546,7 @@ fn build_split_receipt_doc(db: &DbState, payment_id: &str) -> Result<OrderReceip
         .filter_map(|r| r.ok())
         .collect();
 
-    let menu_lookup = build_menu_category_lookup(&conn);
+    let menu_lookup = build_menu_category_lookup(&conn, &language);
 
     // Build items list: payment_items if present, otherwise all order items
     let items: Vec<ReceiptItem> = if !payment_items.is_empty() {
@@ -2992,6 +3561,7 @@ fn build_split_receipt_doc(db: &DbState, payment_id: &str) -> Result<OrderReceip
                 category_path: None,
                 note: None,
                 customizations: Vec::new(),
+                allergy_flags: Vec::new(),
             })
             .collect()
     } else {
@@ -3004,21 +3574,15 @@ fn build_split_receipt_doc(db: &DbState, payment_id: &str) -> Result<OrderReceip
             .map(|item| {
                 let category_fields = resolve_item_category_fields(&item, &menu_lookup);
                 ReceiptItem {
-                    name: item
-                        .get("name")
-                        .or_else(|| item.get("itemName"))
-                        .or_else(|| item.get("menu_item_name"))
-                        .or_else(|| item.get("title"))
-                        .and_then(Value::as_str)
-                        .unwrap_or("Item")
-                        .to_string(),
+                    name: localized_item_name(&item, &language, &menu_lookup),
                     quantity: item.get("quantity").and_then(parse_number).unwrap_or(1.0),
                     total: parse_item_total(&item),
                     category_name: category_fields.category_name,
                     subcategory_name: category_fields.subcategory_name,
                     category_path: category_fields.category_path,
-                    note: build_item_note_text(&item),
+                    note: build_receipt_item_note_text(&item),
                     customizations: parse_item_customizations(&item),
+                    allergy_flags: Vec::new(),
                 }
             })
             .collect()
@@ -3058,15 +3622,20 @@ fn build_split_receipt_doc(db: &DbState, payment_id: &str) -> Result<OrderReceip
         _ => "Other",
     };
     let normalized_amount = if method == "cash" {
-        cash_received.filter(|r| *r > 0.0).unwrap_or(amount)
+        cash_received
+            .filter(|r| *r > 0.0)
+            .or(rounded_amount)
+            .unwrap_or(amount)
     } else {
         amount
     };
-    let mut payments = vec![PaymentLine {
+    let mut payments = Vec::new();
+    push_rounding_line(&mut payments, rounding_delta);
+    payments.push(PaymentLine {
         label: label.to_string(),
         amount: normalized_amount,
         detail: None,
-    }];
+    });
     if let Some(change) = change_given {
         if change > 0.0 {
             payments.push(PaymentLine {
@@ -3116,15 +3685,171 @@ fn build_split_receipt_doc(db: &DbState, payment_id: &str) -> Result<OrderReceip
         totals,
         payments,
         adjustments: Vec::new(),
+        tax_breakdown: Vec::new(),
+        fiscal_document_numbers: load_receipt_fiscal_numbers(&conn, &order_id, Some(payment_id)),
         masked_card,
         order_notes,
         status_label: None,
         cancellation_reason: None,
+        reprint_count: 0,
+        reprinted_at: None,
+        merchant_copy: false,
+        return_of_order_number: None,
     })
 }
 
-fn build_kitchen_ticket_doc(db: &DbState, order_id: &str) -> Result<KitchenTicketDoc, String> {
+/// Receipt for one check of a split bill (see [`crate::checks`]): the order
+/// receipt narrowed to the check's items — an even split keeps them all —
+/// with the check's own totals, tax lines and payments.
+fn build_check_receipt_doc(
+    db: &DbState,
+    check_id: &str,
+    language: Option<&str>,
+) -> Result<OrderReceiptDoc, String> {
+    let (check, paid, payments, masked_card, adjustments, fiscal_document_numbers) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let check = crate::checks::load(&conn, check_id)?
+            .ok_or_else(|| format!("Check not found: {check_id}"))?;
+        let paid = crate::payments::load_net_paid_for_check(&conn, check_id)?;
+        let (payments, masked_card) = load_receipt_payment_lines(&conn, "check_id", check_id)?;
+        let mut adjustments_stmt = conn
+            .prepare(
+                "SELECT COALESCE(pa.adjustment_type, ''), COALESCE(pa.amount, 0), COALESCE(pa.reason, '')
+                 FROM payment_adjustments pa
+                 JOIN order_payments op ON op.id = pa.payment_id
+                 WHERE op.check_id = ?1
+                 ORDER BY pa.created_at ASC",
+            )
+            .map_err(|e| format!("prepare check adjustments: {e}"))?;
+        let rows: Vec<(String, f64, String)> = adjustments_stmt
+            .query_map(params![check_id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .map_err(|e| format!("query check adjustments: {e}"))?
+            .filter_map(|r| r.ok())
+            .collect();
+        let adjustments: Vec<AdjustmentLine> = rows
+            .into_iter()
+            .map(|(kind, amount, reason)| AdjustmentLine {
+                label: match kind.as_str() {
+                    "void" => "Void",
+                    "refund" => "Refund",
+                    _ => "Adjustment",
+                }
+                .to_string(),
+                amount,
+                reason: non_empty_text(&reason),
+            })
+            .collect();
+        let mut payment_stmt = conn
+            .prepare("SELECT id FROM order_payments WHERE check_id = ?1 ORDER BY created_at ASC")
+            .map_err(|e| format!("prepare check payments: {e}"))?;
+        let payment_ids: Vec<String> = payment_stmt
+            .query_map(params![check_id], |row| row.get(0))
+            .map_err(|e| format!("query check payments: {e}"))?
+            .filter_map(|r| r.ok())
+            .collect();
+        let fiscal_document_numbers: Vec<String> = payment_ids
+            .iter()
+            .flat_map(|payment_id| {
+                load_receipt_fiscal_numbers(&conn, &check.order_id, Some(payment_id))
+            })
+            .collect();
+        (
+            check,
+            paid,
+            payments,
+            masked_card,
+            adjustments,
+            fiscal_document_numbers,
+        )
+    };
+
+    let mut doc = build_order_receipt_doc_in_language(db, &check.order_id, language)?;
+    if check.split_mode == "items" {
+        doc.items = doc
+            .items
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| check.item_indexes.contains(index))
+            .map(|(_, item)| item)
+            .collect();
+    }
+
+    let subtotal = crate::money::Cents::new(check.subtotal_cents);
+    let adjustment = crate::money::Cents::new(check.adjustment_cents);
+    let total = crate::money::Cents::new(check.total_cents);
+    // Exclusive tax is whatever the total holds beyond items and the
+    // check's share of order-level charges.
+    let added_tax = total - subtotal - adjustment;
+    let mut totals = vec![TotalsLine {
+        label: "Subtotal".to_string(),
+        amount: subtotal.to_f64_dp2(),
+        emphasize: false,
+        discount_percent: None,
+    }];
+    if added_tax.is_positive() {
+        totals.push(TotalsLine {
+            label: "Tax".to_string(),
+            amount: added_tax.to_f64_dp2(),
+            emphasize: false,
+            discount_percent: None,
+        });
+    }
+    if !adjustment.is_zero() {
+        totals.push(TotalsLine {
+            label: "Adjustments".to_string(),
+            amount: adjustment.to_f64_dp2(),
+            emphasize: false,
+            discount_percent: None,
+        });
+    }
+    totals.push(TotalsLine {
+        label: "TOTAL".to_string(),
+        amount: total.to_f64_dp2(),
+        emphasize: true,
+        discount_percent: None,
+    });
+
+    let mut payments = payments;
+    if paid.is_positive() && paid < total {
+        payments.push(PaymentLine {
+            label: "Balance due".to_string(),
+            amount: (total - paid).to_f64_dp2(),
+            detail: None,
+        });
+    }
+
+    let mut order_notes = vec![format!(
+        "Check {} of {}",
+        check.check_number, check.check_count
+    )];
+    if check.split_mode == "even" {
+        order_notes.push(format!("Split evenly {} ways", check.check_count));
+    }
+    order_notes.append(&mut doc.order_notes);
+
+    doc.totals = totals;
+    doc.payments = payments;
+    doc.masked_card = masked_card;
+    doc.adjustments = adjustments;
+    doc.tax_breakdown = receipt_tax_lines(check.tax_breakdown);
+    doc.fiscal_document_numbers = fiscal_document_numbers;
+    doc.order_notes = order_notes;
+    Ok(doc)
+}
+
+/// Kitchen ticket for an order. A course fire payload (see
+/// [`crate::courses::ticket_payload`]) limits the items to the fired courses
+/// and replaces the title with the fire header; an allergy amendment (see
+/// [`crate::allergens::amendment_payload`]) limits them to the flagged lines.
+fn build_kitchen_ticket_doc(
+    db: &DbState,
+    order_id: &str,
+    payload: Option<&Value>,
+) -> Result<KitchenTicketDoc, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let language = print_language(&conn, payload_language(payload).as_deref());
     let (
         order_number,
         order_type,
@@ -3142,6 +3867,8 @@ fn build_kitchen_ticket_doc(db: &DbState, order_id: &str) -> Result<KitchenTicke
         customer_name,
         customer_phone,
         ghost_metadata,
+        kitchen_notes,
+        allergy_flags,
     ) = conn
         .query_row(
             "SELECT COALESCE(order_number, ''), COALESCE(order_type, ''), COALESCE(created_at, ''),
@@ -3150,7 +3877,8 @@ fn build_kitchen_ticket_doc(db: &DbState, order_id: &str) -> Result<KitchenTicke
                     COALESCE(delivery_city, ''), COALESCE(delivery_postal_code, ''),
                     COALESCE(delivery_floor, ''), COALESCE(name_on_ringer, ''),
                     COALESCE(driver_name, ''), COALESCE(customer_name, ''),
-                    COALESCE(customer_phone, ''), COALESCE(ghost_metadata, '')
+                    COALESCE(customer_phone, ''), COALESCE(ghost_metadata, ''),
+                    COALESCE(kitchen_notes, ''), allergy_flags
              FROM orders WHERE id = ?1",
             params![order_id],
             |row| {
@@ -3171,28 +3899,37 @@ fn build_kitchen_ticket_doc(db: &DbState, order_id: &str) -> Result<KitchenTicke
                     row.get::<_, String>(13)?,
                     row.get::<_, String>(14)?,
                     row.get::<_, String>(15)?,
+                    row.get::<_, String>(16)?,
+                    row.get::<_, Option<String>>(17)?,
                 ))
             },
         )
         .map_err(|_| format!("Order not found: {order_id}"))?;
-    let menu_lookup = build_menu_category_lookup(&conn);
+    let menu_lookup = build_menu_category_lookup(&conn, &language);
+    let fired_courses = crate::courses::ticket_courses(payload);
+    let amended_items = crate::allergens::amendment_items(payload);
+    let course_header = payload
+        .and_then(|payload| payload.get("courseHeader"))
+        .and_then(Value::as_str)
+        .and_then(non_empty_text);
 
     let items: Vec<ReceiptItem> = serde_json::from_str::<Value>(&items_json)
         .ok()
         .and_then(|value| value.as_array().cloned())
         .unwrap_or_default()
         .into_iter()
-        .map(|item| {
+        .enumerate()
+        .filter(|(index, item)| {
+            fired_courses.as_ref().map_or(true, |courses| {
+                courses.contains(&crate::courses::item_course(item))
+            }) && amended_items
+                .as_ref()
+                .map_or(true, |indexes| indexes.contains(index))
+        })
+        .map(|(_, item)| {
             let category_fields = resolve_item_category_fields(&item, &menu_lookup);
             ReceiptItem {
-                name: item
-                    .get("name")
-                    .or_else(|| item.get("itemName"))
-                    .or_else(|| item.get("menu_item_name"))
-                    .or_else(|| item.get("title"))
-                    .and_then(Value::as_str)
-                    .unwrap_or("Item")
-                    .to_string(),
+                name: localized_item_name(&item, &language, &menu_lookup),
                 quantity: item.get("quantity").and_then(parse_number).unwrap_or(1.0),
                 total: parse_item_total(&item),
                 category_name: category_fields.category_name,
@@ -3200,6 +3937,7 @@ fn build_kitchen_ticket_doc(db: &DbState, order_id: &str) -> Result<KitchenTicke
                 category_path: category_fields.category_path,
                 note: build_item_note_text(&item),
                 customizations: parse_item_customizations(&item),
+                allergy_flags: crate::allergens::flags(&item),
             }
         })
         .collect();
@@ -3274,6 +4012,10 @@ fn build_kitchen_ticket_doc(db: &DbState, order_id: &str) -> Result<KitchenTicke
         } else {
             Some(customer_phone)
         },
+        course_header,
+        kitchen_notes: non_empty_text(&kitchen_notes),
+        allergy_flags: crate::allergens::parse_stored(allergy_flags.as_deref()),
+        allergy_update: amended_items.is_some(),
         items,
     })
 }
@@ -3486,6 +4228,15 @@ fn build_shift_checkout_doc(
         cash_sales,
         card_sales,
         cash_drops: number_from_paths(&cash_drawer, &["/cash_drops", "/cashDrops"]).unwrap_or(0.0),
+        cash_rounding: number_from_paths(
+            &cash_drawer,
+            &[
+                "/total_cash_rounding",
+                "/totalCashRounding",
+                "/cashRounding",
+            ],
+        )
+        .unwrap_or(0.0),
         driver_cash_given: number_from_paths(
             &cash_drawer,
             &["/driver_cash_given", "/driverCashGiven"],
@@ -3932,6 +4683,8 @@ fn build_z_report_doc_from_payload(db: &DbState, payload: &Value, entity_id: &st
     .unwrap_or(0.0);
     let cash_drops =
         number_from_paths(payload, &["/cashDrawer/totalCashDrops", "/cashDrops"]).unwrap_or(0.0);
+    let cash_rounding =
+        number_from_paths(payload, &["/cashDrawer/cashRounding", "/cashRounding"]).unwrap_or(0.0);
     let driver_cash_given = number_from_paths(
         payload,
         &["/cashDrawer/driverCashGiven", "/driverCashGiven"],
@@ -4006,6 +4759,7 @@ fn build_z_report_doc_from_payload(db: &DbState, payload: &Value, entity_id: &st
         closing_cash,
         expected_cash,
         cash_drops,
+        cash_rounding,
         driver_cash_given,
         driver_cash_returned,
         staff_payments_total,
@@ -4181,6 +4935,10 @@ fn build_z_report_doc(db: &DbState, z_report_id: &str) -> Result<ZReportDoc, Str
             .pointer("/cashDrawer/totalCashDrops")
             .and_then(|v| v.as_f64())
             .unwrap_or(0.0),
+        cash_rounding: rj
+            .pointer("/cashDrawer/cashRounding")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0),
         driver_cash_given: rj
             .pointer("/cashDrawer/driverCashGiven")
             .and_then(|v| v.as_f64())
@@ -4279,13 +5037,19 @@ fn build_document_for_job(
 ) -> Result<ReceiptDocument, String> {
     let payload =
         payload_json.and_then(|raw_payload| serde_json::from_str::<Value>(raw_payload).ok());
+    let job_language = payload_language(payload.as_ref());
+    let language = job_language.as_deref();
 
     match entity_type {
-        "order_receipt" => Ok(ReceiptDocument::OrderReceipt(build_order_receipt_doc(
-            db, entity_id,
-        )?)),
+        "order_receipt" => {
+            let mut doc = build_order_receipt_doc_in_language(db, entity_id, language)?;
+            apply_reprint_payload(&mut doc, payload.as_ref());
+            Ok(ReceiptDocument::OrderReceipt(doc))
+        }
         "kitchen_ticket" => Ok(ReceiptDocument::KitchenTicket(build_kitchen_ticket_doc(
-            db, entity_id,
+            db,
+            entity_id,
+            payload.as_ref(),
         )?)),
         "shift_checkout" => Ok(ReceiptDocument::ShiftCheckout(build_shift_checkout_doc(
             db,
@@ -4301,7 +5065,7 @@ fn build_document_for_job(
             Ok(ReceiptDocument::ZReport(build_z_report_doc(db, entity_id)?))
         }
         "delivery_slip" => {
-            let mut doc = build_order_receipt_doc(db, entity_id)?;
+            let mut doc = build_order_receipt_doc_in_language(db, entity_id, language)?;
             if let Some(payload) = payload.as_ref() {
                 if let Some(mode) = object_text_field(payload, &["slip_mode", "slipMode"]) {
                     doc.delivery_slip_mode = if mode.eq_ignore_ascii_case("assign_driver") {
@@ -4322,17 +5086,29 @@ fn build_document_for_job(
         }
         "split_receipt" => {
             // entity_id is the payment_id for split receipts
-            let doc = build_split_receipt_doc(db, entity_id)?;
+            let doc = build_split_receipt_doc(db, entity_id, language)?;
+            Ok(ReceiptDocument::OrderReceipt(doc))
+        }
+        "check_receipt" => {
+            // entity_id is the order_checks id
+            let doc = build_check_receipt_doc(db, entity_id, language)?;
             Ok(ReceiptDocument::OrderReceipt(doc))
         }
         "order_completed_receipt" => {
-            let mut doc = build_order_receipt_doc(db, entity_id)?;
-            doc.status_label = Some("\u{2713} COMPLETED".to_string());
+            let mut doc = build_order_receipt_doc_in_language(db, entity_id, language)?;
+            doc.status_label = Some(format!(
+                "\u{2713} {}",
+                receipt_renderer::receipt_label(&status_label_language(db, language), "COMPLETED")
+            ));
+            apply_reprint_payload(&mut doc, payload.as_ref());
             Ok(ReceiptDocument::OrderReceipt(doc))
         }
         "order_canceled_receipt" => {
-            let mut doc = build_order_receipt_doc(db, entity_id)?;
-            doc.status_label = Some("\u{2717} CANCELED".to_string());
+            let mut doc = build_order_receipt_doc_in_language(db, entity_id, language)?;
+            doc.status_label = Some(format!(
+                "\u{2717} {}",
+                receipt_renderer::receipt_label(&status_label_language(db, language), "CANCELED")
+            ));
             if let Some(payload) = payload.as_ref() {
                 doc.cancellation_reason = payload
                     .get("cancellationReason")
@@ -4345,6 +5121,31 @@ fn build_document_for_job(
     }
 }
 
+fn status_label_language(db: &DbState, job_language: Option<&str>) -> String {
+    match db.conn.lock() {
+        Ok(conn) => print_language(&conn, job_language),
+        Err(_) => "en".to_string(),
+    }
+}
+
+/// Copy the duplicate counter and timestamp from a reprint job payload, and
+/// the merchant-copy flag from a policy copy.
+fn apply_reprint_payload(doc: &mut OrderReceiptDoc, payload: Option<&Value>) {
+    let Some(payload) = payload else {
+        return;
+    };
+    doc.merchant_copy = payload
+        .get(receipt_copies::MERCHANT_COPY_FIELD)
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    doc.reprint_count = payload
+        .get("reprintCount")
+        .and_then(Value::as_u64)
+        .map(|count| count.min(u32::MAX as u64) as u32)
+        .unwrap_or(0);
+    doc.reprinted_at = object_text_field(payload, &["reprintedAt", "reprinted_at"]);
+}
+
 /// Reject a path segment that could escape the receipts directory or
 /// contain filesystem-hostile characters. POS entity ids (UUIDs, order
 /// ids, z-report ids) are machine-generated and always match
@@ -4562,6 +5363,69 @@ fn generate_shift_checkout_file(
 // Hardware dispatch
 // ---------------------------------------------------------------------------
 
+fn dispatch_role(entity_type: &str) -> &'static str {
+    match entity_type {
+        "kitchen_ticket" => "kitchen",
+        labels::LABEL_ENTITY_TYPE => labels::LABEL_PROFILE_TYPE,
+        "order_receipt"
+        | "shift_checkout"
+        | "z_report"
+        | drawer_transactions::PRINT_ENTITY_TYPE => "receipt",
+        _ => "receipt",
+    }
+}
+
+/// Layout for `document` on `profile`, shared by dispatch and preview.
+/// `language` is the print job's override, if it carries one.
+fn resolve_document_layout(
+    db: &DbState,
+    profile: &Value,
+    entity_type: &str,
+    document: &ReceiptDocument,
+    language: Option<&str>,
+) -> Result<LayoutConfig, String> {
+    let mut layout = resolve_layout_config(db, profile, entity_type)?;
+    if let Some(language) = language {
+        receipt_renderer::apply_language_override(&mut layout, language);
+    }
+    receipt_renderer::apply_reprint_marker(&mut layout, document);
+    let training = db.read(|conn| Ok(crate::training::is_training_connection(conn)))?;
+    receipt_renderer::apply_training_marker(&mut layout, training);
+    Ok(layout)
+}
+
+/// Render what a print of `entity_type` for `entity_id` would produce,
+/// without enqueueing a job. The document, printer profile and layout are
+/// resolved exactly as the print worker resolves them, and the text comes
+/// from the same ESC/POS render, so preview and paper cannot drift apart.
+/// Without a configured printer the default layout is used.
+pub fn preview_document(
+    db: &DbState,
+    entity_type: &str,
+    entity_id: &str,
+    job_profile_id: Option<&str>,
+) -> Result<Value, String> {
+    let document = build_document_for_job(db, entity_type, entity_id, None)?;
+    let profile = printers::resolve_printer_profile_for_role(
+        db,
+        job_profile_id,
+        Some(dispatch_role(entity_type)),
+    )?
+    .unwrap_or_else(|| serde_json::json!({}));
+    let layout = resolve_document_layout(db, &profile, entity_type, &document, None)?;
+    let preview = receipt_renderer::render_preview(&document, &layout);
+    Ok(serde_json::json!({
+        "success": true,
+        "entityType": entity_type,
+        "entityId": entity_id,
+        "text": preview.text,
+        "html": preview.html,
+        "bodyMode": preview.body_mode.as_str(),
+        "paperWidthChars": layout.paper_width.chars(),
+        "warnings": preview.warnings,
+    }))
+}
+
 /// Attempt to send a receipt file to a hardware printer.
 ///
 /// Returns the resolved profile (if any) so the caller can pass it to the
@@ -4571,12 +5435,9 @@ fn dispatch_to_printer(
     entity_type: &str,
     job_profile_id: Option<&str>,
     document: &ReceiptDocument,
+    language: Option<&str>,
 ) -> Result<(Value, Vec<receipt_renderer::RenderWarning>), String> {
-    let role = match entity_type {
-        "kitchen_ticket" => "kitchen",
-        "order_receipt" | "shift_checkout" | "z_report" => "receipt",
-        _ => "receipt",
-    };
+    let role = dispatch_role(entity_type);
     let profile = printers::resolve_printer_profile_for_role(db, job_profile_id, Some(role))?;
 
     let profile = match profile {
@@ -4599,7 +5460,7 @@ fn dispatch_to_printer(
         .get("cutPaper")
         .and_then(|v| v.as_bool())
         .unwrap_or(true);
-    let layout = resolve_layout_config(db, &profile, entity_type)?;
+    let layout = resolve_document_layout(db, &profile, entity_type, document, language)?;
     let (brand_source, branch_source, address_source, phone_source) = match db.conn.lock() {
         Ok(conn) => resolve_header_sources(&conn),
         Err(_) => (
@@ -4707,27 +5568,132 @@ fn dispatch_to_printer(
                 "delivery_slip" => "POS Delivery Slip",
                 _ => "POS Receipt",
             };
-            // Watchdog: the Windows spooler transport (`print_raw_to_windows`) has no
-            // timeout of its own, and this runs inside PRINT_PROCESSOR_LOCK while the
-            // worker awaits the tick — so a jammed spooler would freeze the whole queue
-            // until restart. Bound the send; a timeout fails the job closed (unknown
-            // state, no auto-resend) and lets the queue keep serving other jobs.
-            let target = printers::resolve_printer_target(&profile)?;
-            let bytes = std::mem::take(&mut rendered.bytes);
-            let doc = doc_name.to_string();
-            let dispatch_outcome = run_dispatch_with_timeout(DISPATCH_TIMEOUT, move || {
-                printers::print_raw_for_target(&target, &bytes, &doc)
-            });
-            let _dispatch = match dispatch_outcome {
-                Ok(inner) => inner?,
-                Err(timeout_err) => return Err(timeout_err),
-            };
+            send_raw_with_watchdog(&profile, std::mem::take(&mut rendered.bytes), doc_name)?;
             Ok((profile, rendered.warnings))
         }
         other => Err(format!("Unsupported driver_type: {other}")),
     }
 }
 
+/// Send raw bytes to the profile's printer under `DISPATCH_TIMEOUT`.
+///
+/// Watchdog: the Windows spooler transport (`print_raw_to_windows`) has no
+/// timeout of its own, and this runs inside PRINT_PROCESSOR_LOCK while the
+/// worker awaits the tick — so a jammed spooler would freeze the whole queue
+/// until restart. Bound the send; a timeout fails the job closed (unknown
+/// state, no auto-resend) and lets the queue keep serving other jobs.
+fn send_raw_with_watchdog(profile: &Value, bytes: Vec<u8>, doc_name: &str) -> Result<(), String> {
+    let target = printers::resolve_printer_target(profile)?;
+    let doc = doc_name.to_string();
+    let dispatch_outcome = run_dispatch_with_timeout(DISPATCH_TIMEOUT, move || {
+        printers::print_raw_for_target(&target, &bytes, &doc)
+    });
+    match dispatch_outcome {
+        Ok(inner) => inner.map(|_| ()),
+        Err(timeout_err) => Err(timeout_err),
+    }
+}
+
+/// Render and send an `order_label` job. Labels do not go through
+/// `ReceiptDocument`: they have no store header or footer, only resolve
+/// `label` profiles, and take their layout from the label template.
+/// Returns the written HTML path and any render warnings.
+fn print_label_job(
+    db: &DbState,
+    data_dir: &Path,
+    order_id: &str,
+    payload_json: Option<&str>,
+    job_profile_id: Option<&str>,
+) -> Result<(String, Vec<receipt_renderer::RenderWarning>), String> {
+    let payload = payload_json.and_then(|raw| serde_json::from_str::<Value>(raw).ok());
+    let options = labels::LabelOptions::from_payload(payload.as_ref());
+    let profile =
+        printers::resolve_printer_profile_for_type(db, job_profile_id, labels::LABEL_PROFILE_TYPE)?
+            .ok_or_else(|| {
+                format!(
+                    "No hardware printer profile resolved for entity type {}",
+                    labels::LABEL_ENTITY_TYPE
+                )
+            })?;
+    let mut layout = resolve_layout_config(db, &profile, labels::LABEL_ENTITY_TYPE)?;
+    if let Some(language) = options.language.as_deref() {
+        receipt_renderer::apply_language_override(&mut layout, language);
+    }
+    let pages = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        labels::labels_for_job(&conn, order_id, &options, layout.paper_width)?
+    };
+
+    let html = labels::render_label_html(&pages);
+    let path = write_print_html_file(data_dir, labels::LABEL_ENTITY_TYPE, order_id, &html)?;
+
+    let driver_type = profile["driverType"].as_str().unwrap_or("windows");
+    if driver_type != "windows" && driver_type != "escpos" {
+        return Err(format!("Unsupported driver_type: {driver_type}"));
+    }
+    let should_cut = profile
+        .get("cutPaper")
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+    let rendered = labels::render_label_escpos(&pages, &layout, should_cut);
+    info!(
+        order_id = %order_id,
+        labels = pages.len(),
+        escpos_bytes = rendered.bytes.len(),
+        "Dispatch: bag labels rendered"
+    );
+    send_raw_with_watchdog(&profile, rendered.bytes, "POS Bag Labels")?;
+    Ok((path, rendered.warnings))
+}
+
+/// Render and send a `drawer_transaction` chit. Like bag labels the chit
+/// bypasses `ReceiptDocument`; it prints on the receipt printer with the
+/// receipt layout. Returns the written HTML path and any render warnings.
+fn print_drawer_chit_job(
+    db: &DbState,
+    data_dir: &Path,
+    transaction_id: &str,
+    job_profile_id: Option<&str>,
+) -> Result<(String, Vec<receipt_renderer::RenderWarning>), String> {
+    let profile = printers::resolve_printer_profile_for_type(db, job_profile_id, "receipt")?
+        .ok_or_else(|| {
+            format!(
+                "No hardware printer profile resolved for entity type {}",
+                drawer_transactions::PRINT_ENTITY_TYPE
+            )
+        })?;
+    let layout = resolve_layout_config(db, &profile, drawer_transactions::PRINT_ENTITY_TYPE)?;
+    let lines = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        drawer_transactions::chit_lines(&conn, transaction_id, &layout.currency_symbol)?
+    };
+
+    let html = drawer_transactions::render_chit_html(&lines);
+    let path = write_print_html_file(
+        data_dir,
+        drawer_transactions::PRINT_ENTITY_TYPE,
+        transaction_id,
+        &html,
+    )?;
+
+    let driver_type = profile["driverType"].as_str().unwrap_or("windows");
+    if driver_type != "windows" && driver_type != "escpos" {
+        return Err(format!("Unsupported driver_type: {driver_type}"));
+    }
+    let should_cut = profile
+        .get("cutPaper")
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+    let rendered = drawer_transactions::render_chit_escpos(&lines, &layout, should_cut);
+    info!(
+        transaction_id = %transaction_id,
+        escpos_bytes = rendered.bytes.len(),
+        "Dispatch: drawer chit rendered"
+    );
+    send_raw_with_watchdog(&profile, rendered.bytes, "POS Drawer Chit")?;
+    Ok((path, rendered.warnings))
+}
+
 // ---------------------------------------------------------------------------
 // Background print worker
 // ---------------------------------------------------------------------------
@@ -4794,22 +5760,8 @@ pub fn recover_stale_printing_jobs(db: &DbState) -> Result<usize, String> {
         );
     }
 
-    // Purge old failed/completed jobs (older than 24 hours) to prevent queue bloat
-    let purged = conn
-        .execute(
-            "DELETE FROM print_jobs
-             WHERE status IN ('failed', 'printed', 'dispatched', 'cancelled')
-               AND julianday(?1) - julianday(updated_at) > 1.0",
-            params![now],
-        )
-        .unwrap_or(0);
-
-    if purged > 0 {
-        info!(
-            count = purged,
-            "Purged old completed/failed print jobs (>24h)"
-        );
-    }
+    // Finished jobs are archived and purged by the retention pass
+    // (`retention.print_jobs_days`), not deleted here.
 
     Ok(affected)
 }
@@ -4887,6 +5839,49 @@ pub fn process_pending_jobs(db: &DbState, data_dir: &Path) -> Result<usize, Stri
                     }
                 }
 
+                if entity_type == labels::LABEL_ENTITY_TYPE
+                    || entity_type == drawer_transactions::PRINT_ENTITY_TYPE
+                {
+                    let result = if entity_type == labels::LABEL_ENTITY_TYPE {
+                        print_label_job(
+                            db,
+                            data_dir,
+                            &entity_id,
+                            payload_json.as_deref(),
+                            profile_id.as_deref(),
+                        )
+                    } else {
+                        print_drawer_chit_job(db, data_dir, &entity_id, profile_id.as_deref())
+                    };
+                    match result {
+                        Ok((path, render_warnings)) => {
+                            if let Err(e) = mark_print_job_dispatched(db, &job_id, &path) {
+                                error!(job_id = %job_id, error = %e, "Failed to mark print job as dispatched");
+                            } else if !render_warnings.is_empty() {
+                                let combined = render_warnings
+                                    .iter()
+                                    .map(|warning| warning.message.clone())
+                                    .collect::<Vec<String>>()
+                                    .join(" | ");
+                                let _ =
+                                    set_print_job_warning(db, &job_id, "render_warning", &combined);
+                            }
+                        }
+                        Err(error) => {
+                            warn!(job_id = %job_id, entity_type = %entity_type, error = %error, "Raw print job failed");
+                            let mark_result = if is_non_retryable_print_error(&error) {
+                                mark_print_job_failed_non_retryable(db, &job_id, &error)
+                            } else {
+                                mark_print_job_failed(db, &job_id, &error)
+                            };
+                            if let Err(e) = mark_result {
+                                error!(job_id = %job_id, error = %e, "Failed to mark print job as failed");
+                            }
+                        }
+                    }
+                    return Ok(());
+                }
+
                 let document = match build_document_for_job(
                     db,
                     &entity_type,
@@ -4922,8 +5917,15 @@ pub fn process_pending_jobs(db: &DbState, data_dir: &Path) -> Result<usize, Stri
                 .ok()
                 .flatten()
                 .unwrap_or_else(|| serde_json::json!({}));
-                let html_layout =
+                let job_language = payload_json
+                    .as_deref()
+                    .and_then(|raw| serde_json::from_str::<Value>(raw).ok())
+                    .and_then(|payload| payload_language(Some(&payload)));
+                let mut html_layout =
                     resolve_layout_config(db, &html_profile, &entity_type).unwrap_or_default();
+                if let Some(language) = job_language.as_deref() {
+                    receipt_renderer::apply_language_override(&mut html_layout, language);
+                }
                 let html = receipt_renderer::render_html(&document, &html_layout);
                 let path = match write_print_html_file(data_dir, &entity_type, &entity_id, &html) {
                     Ok(path) => path,
@@ -4936,7 +5938,13 @@ pub fn process_pending_jobs(db: &DbState, data_dir: &Path) -> Result<usize, Stri
                 };
 
                 // Try to dispatch to hardware printer from structured render path.
-                match dispatch_to_printer(db, &entity_type, profile_id.as_deref(), &document) {
+                match dispatch_to_printer(
+                    db,
+                    &entity_type,
+                    profile_id.as_deref(),
+                    &document,
+                    job_language.as_deref(),
+                ) {
                     Ok((resolved_profile, render_warnings)) => {
                         if let Err(e) = mark_print_job_dispatched(db, &job_id, &path) {
                             error!(job_id = %job_id, error = %e, "Failed to mark print job as dispatched");
@@ -5048,11 +6056,32 @@ pub fn spawn_pending_job_processing(app: tauri::AppHandle, data_dir: PathBuf, co
     });
 }
 
+/// Jobs that ran out of retries with `updated_at` in `(since, until]`, and the
+/// most recent of their errors.
+fn failed_jobs_between(
+    conn: &rusqlite::Connection,
+    since: &str,
+    until: &str,
+) -> Result<(i64, Option<String>), String> {
+    conn.query_row(
+        "SELECT COUNT(*),
+                (SELECT last_error FROM print_jobs
+                 WHERE status = 'failed' AND updated_at > ?1 AND updated_at <= ?2
+                 ORDER BY updated_at DESC LIMIT 1)
+         FROM print_jobs
+         WHERE status = 'failed' AND updated_at > ?1 AND updated_at <= ?2",
+        params![since, until],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .map_err(|e| format!("count failed print jobs: {e}"))
+}
+
 /// Start the background print worker loop.
 ///
 /// Runs every `interval_secs` seconds, processes pending print jobs.
 /// Emits a `print-worker-alert` Tauri event when consecutive failures exceed
-/// the threshold, and resets the counter on any successful tick.
+/// the threshold, and resets the counter on any successful tick. Jobs that
+/// give up during a tick raise a printer-offline notification.
 pub fn start_print_worker(
     db: Arc<DbState>,
     app_handle: tauri::AppHandle,
@@ -5065,6 +6094,7 @@ pub fn start_print_worker(
     tauri::async_runtime::spawn(async move {
         let interval = tokio::time::Duration::from_secs(interval_secs);
         let mut consecutive_failures: u32 = 0;
+        let mut failures_checked_at = Utc::now().to_rfc3339();
         loop {
             tokio::select! {
                 _ = tokio::time::sleep(interval) => {}
@@ -5133,6 +6163,31 @@ pub fn start_print_worker(
                     }),
                 );
             }
+
+            let checked_at = Utc::now().to_rfc3339();
+            let newly_failed = db
+                .conn
+                .lock()
+                .map_err(|e| e.to_string())
+                .and_then(|conn| failed_jobs_between(&conn, &failures_checked_at, &checked_at));
+            failures_checked_at = checked_at;
+            match newly_failed {
+                Ok((count, last_error)) if count > 0 => {
+                    let body = match last_error {
+                        Some(error) => format!("{count} print job(s) failed: {error}"),
+                        None => format!("{count} print job(s) failed"),
+                    };
+                    notify::send(
+                        &app_handle,
+                        &db,
+                        NotificationKind::PrinterOffline,
+                        "Printer problem",
+                        &body,
+                    );
+                }
+                Ok(_) => {}
+                Err(error) => warn!(error = %error, "Failed to check for failed print jobs"),
+            }
         }
     });
 
@@ -5191,10 +6246,7 @@ mod tests {
         )
         .expect("pragma setup");
         db::run_migrations_for_test(&conn);
-        DbState {
-            conn: Mutex::new(conn),
-            db_path: PathBuf::from(":memory:"),
-        }
+        DbState::new(conn, PathBuf::from(":memory:"))
     }
 
     fn insert_receipt_order(conn: &Connection, order_id: &str, order_number: &str, total: f64) {
@@ -6459,6 +7511,33 @@ mod tests {
         assert_eq!(discount_line.discount_percent, Some(10.0));
     }
 
+    #[test]
+    fn test_build_order_receipt_doc_prints_service_charge_but_not_commission() {
+        let db = test_db();
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO orders (
+                    id, order_number, items, total_amount, total_amount_cents, subtotal, subtotal_cents, status, order_type,
+                    service_charge, service_charge_cents, platform_commission, platform_commission_cents,
+                    plugin, sync_status, created_at, updated_at
+                 ) VALUES (
+                    'ord-service-charge', 'ORD-SVC-1', '[]', 22.00, 2200, 20.00, 2000, 'completed', 'delivery',
+                    2.00, 200, 4.40, 440, 'wolt', 'pending', datetime('now'), datetime('now')
+                 )",
+                [],
+            )
+            .unwrap();
+        }
+
+        let doc = build_order_receipt_doc(&db, "ord-service-charge").unwrap();
+        let labels: Vec<&str> = doc.totals.iter().map(|line| line.label.as_str()).collect();
+        assert_eq!(labels, vec!["Subtotal", "Service charge", "TOTAL"]);
+        assert!((doc.totals[0].amount - 20.00).abs() < 0.001);
+        assert!((doc.totals[1].amount - 2.00).abs() < 0.001);
+        assert!((doc.totals[2].amount - 22.00).abs() < 0.001);
+    }
+
     #[test]
     fn test_build_order_receipt_doc_collects_item_and_order_notes() {
         let db = test_db();
@@ -6530,6 +7609,55 @@ mod tests {
         assert_eq!(first_item.category_path.as_deref(), Some("ΓΛΥΚΑ > Βάφλα"));
     }
 
+    #[test]
+    fn test_receipt_item_names_follow_print_language() {
+        let db = test_db();
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO menu_cache (cache_key, data, updated_at) VALUES (?1, ?2, datetime('now'))",
+                params![
+                    "subcategories",
+                    r#"[{"id":"sub-waffle","name":"Βάφλα","name_en":"Waffle","name_el":"Βάφλα"}]"#
+                ],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO orders (
+                    id, order_number, items, total_amount, total_amount_cents, subtotal, subtotal_cents, status, order_type,
+                    sync_status, created_at, updated_at
+                 ) VALUES (
+                    'ord-lang', 'ORD-LANG-1', ?1, 8.80, 880, 8.80, 880, 'completed', 'pickup',
+                    'pending', datetime('now'), datetime('now')
+                 )",
+                params![r#"[{"menu_item_id":"sub-waffle","name":"Βάφλα","quantity":1,"total_price":8.8}]"#],
+            )
+            .unwrap();
+            db::set_setting(&conn, "general", "language", "en-US").unwrap();
+            assert_eq!(print_language(&conn, None), "en");
+            assert_eq!(print_language(&conn, Some("el_GR")), "el");
+        }
+
+        let doc = build_order_receipt_doc(&db, "ord-lang").unwrap();
+        assert_eq!(doc.items[0].name, "Waffle");
+        let doc = build_order_receipt_doc_in_language(&db, "ord-lang", Some("el")).unwrap();
+        assert_eq!(doc.items[0].name, "Βάφλα");
+
+        let ReceiptDocument::OrderReceipt(completed) = build_document_for_job(
+            &db,
+            "order_completed_receipt",
+            "ord-lang",
+            Some(r#"{"language":"el"}"#),
+        )
+        .unwrap() else {
+            panic!("expected an order receipt");
+        };
+        assert_eq!(
+            completed.status_label.as_deref(),
+            Some("\u{2713} ΟΛΟΚΛΗΡΩΘΗΚΕ")
+        );
+    }
+
     #[test]
     fn test_resolve_layout_config_uses_restaurant_name_as_branch_subtitle_fallback() {
         let db = test_db();
@@ -7029,6 +8157,187 @@ mod tests {
             .contains("\"date\""));
     }
 
+    #[test]
+    fn test_enqueue_defers_jobs_for_printer_out_of_paper() {
+        let db = test_db();
+        let created = printers::create_printer_profile(
+            &db,
+            &serde_json::json!({ "name": "Front", "printerName": "POS-80" }),
+        )
+        .unwrap();
+        let profile_id = created["profileId"].as_str().unwrap().to_string();
+        printer_watchdog::set_status_for_test(
+            &profile_id,
+            Some(printer_watchdog::PrinterHealth {
+                online: false,
+                paper_out: true,
+                paper_near_end: false,
+                cover_open: false,
+                detail: None,
+                checked_at: Utc::now().to_rfc3339(),
+            }),
+        );
+
+        let result =
+            enqueue_print_job(&db, "order_receipt", "ord-paper", Some(profile_id.as_str()))
+                .unwrap();
+        assert_eq!(result["deferred"], true);
+        assert!(result["message"]
+            .as_str()
+            .unwrap_or_default()
+            .contains("out of paper"));
+        let job_id = result["jobId"].as_str().unwrap().to_string();
+
+        // Pressing print again does not queue a second copy.
+        let dup = enqueue_print_job(&db, "order_receipt", "ord-paper", Some(profile_id.as_str()))
+            .unwrap();
+        assert_eq!(dup["duplicate"], true);
+
+        {
+            let conn = db.conn.lock().unwrap();
+            let (status, warning): (String, Option<String>) = conn
+                .query_row(
+                    "SELECT status, warning_code FROM print_jobs WHERE id = ?1",
+                    params![job_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap();
+            assert_eq!(status, "deferred");
+            assert_eq!(warning.as_deref(), Some("printer_paper_out"));
+            let ready =
+                select_ready_pending_jobs(&conn, &Utc::now().to_rfc3339(), &HashSet::new(), 10)
+                    .unwrap();
+            assert!(ready.is_empty(), "deferred jobs must not be dispatched");
+        }
+
+        printer_watchdog::set_status_for_test(&profile_id, None);
+        let cancelled = cancel_print_job(&db, &job_id).unwrap();
+        assert_eq!(cancelled["success"], true);
+    }
+
+    #[test]
+    fn test_scheduled_job_waits_for_not_before() {
+        let db = test_db();
+        let at = Utc::now() + chrono::Duration::minutes(30);
+        let scheduled =
+            enqueue_print_job_at(&db, "kitchen_ticket", "ord-later", None, None, Some(at)).unwrap();
+        assert_eq!(scheduled["scheduled"], true);
+        let job_id = scheduled["jobId"].as_str().unwrap().to_string();
+
+        // An immediate ticket for the same order is not swallowed by it.
+        let immediate = enqueue_print_job(&db, "kitchen_ticket", "ord-later", None).unwrap();
+        assert_ne!(immediate["jobId"].as_str(), Some(job_id.as_str()));
+
+        let conn = db.conn.lock().unwrap();
+        let ready = select_ready_pending_jobs(&conn, &Utc::now().to_rfc3339(), &HashSet::new(), 10)
+            .unwrap();
+        assert!(ready.iter().all(|(id, ..)| id != &job_id));
+        assert_eq!(ready.len(), 1);
+
+        let listed = print_schedule::list_scheduled(&conn, Utc::now()).unwrap();
+        assert_eq!(listed[0]["jobId"], job_id);
+        assert_eq!(listed[0]["notBefore"], print_schedule::format_time(at));
+
+        let later = (at + chrono::Duration::seconds(1)).to_rfc3339();
+        let ready = select_ready_pending_jobs(&conn, &later, &HashSet::new(), 10).unwrap();
+        assert!(ready.iter().any(|(id, ..)| id == &job_id));
+    }
+
+    #[test]
+    fn test_cancelling_order_cancels_its_scheduled_jobs() {
+        let db = test_db();
+        {
+            let conn = db.conn.lock().unwrap();
+            insert_receipt_order(&conn, "ord-sched", "S-1", 12.0);
+        }
+        let at = Utc::now() + chrono::Duration::hours(1);
+        let scheduled =
+            enqueue_print_job_at(&db, "order_receipt", "ord-sched", None, None, Some(at)).unwrap();
+        let immediate = enqueue_print_job(&db, "kitchen_ticket", "ord-sched", None).unwrap();
+
+        let conn = db.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE orders SET status = 'cancelled' WHERE id = 'ord-sched'",
+            [],
+        )
+        .unwrap();
+        let job_state = |id: &Value| -> (String, Option<String>) {
+            conn.query_row(
+                "SELECT status, cancel_reason FROM print_jobs WHERE id = ?1",
+                params![id.as_str().unwrap()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            job_state(&scheduled["jobId"]),
+            ("cancelled".to_string(), Some("order_cancelled".to_string()))
+        );
+        assert_eq!(
+            job_state(&immediate["jobId"]),
+            ("pending".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn test_cancel_scheduled_job_refuses_jobs_sent_to_printer() {
+        let db = test_db();
+        let at = Utc::now() + chrono::Duration::minutes(10);
+        let sent = enqueue_print_job(&db, "order_receipt", "ord-sent", None).unwrap();
+        let waiting =
+            enqueue_print_job_at(&db, "order_receipt", "ord-wait", None, None, Some(at)).unwrap();
+
+        let conn = db.conn.lock().unwrap();
+        let sent_id = sent["jobId"].as_str().unwrap();
+        conn.execute(
+            "UPDATE print_jobs SET status = 'printing' WHERE id = ?1",
+            params![sent_id],
+        )
+        .unwrap();
+        let err =
+            print_schedule::cancel(&conn, sent_id, Some("staff-1"), None, Utc::now()).unwrap_err();
+        assert!(err.contains("already sent"), "{err}");
+
+        let waiting_id = waiting["jobId"].as_str().unwrap();
+        let result = print_schedule::cancel(
+            &conn,
+            waiting_id,
+            Some("staff-1"),
+            Some("guest left"),
+            Utc::now(),
+        )
+        .unwrap();
+        assert_eq!(result["previousStatus"], "pending");
+        let (status, by, reason): (String, Option<String>, Option<String>) = conn
+            .query_row(
+                "SELECT status, cancelled_by, cancel_reason FROM print_jobs WHERE id = ?1",
+                params![waiting_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(status, "cancelled");
+        assert_eq!(by.as_deref(), Some("staff-1"));
+        assert_eq!(reason.as_deref(), Some("guest left"));
+    }
+
+    #[test]
+    fn test_ready_jobs_query_uses_status_not_before_index() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        let sql = format!("EXPLAIN QUERY PLAN {}", ready_pending_jobs_sql(1, 10));
+        let mut stmt = conn.prepare(&sql).unwrap();
+        let plan: Vec<String> = stmt
+            .query_map(params!["now", "now", "paused"], |row| row.get(3))
+            .unwrap()
+            .filter_map(|row| row.ok())
+            .collect();
+        assert!(
+            plan.iter()
+                .any(|step| step.contains("idx_print_jobs_status_not_before")),
+            "{plan:?}"
+        );
+    }
+
     #[test]
     fn test_mark_dispatched() {
         let db = test_db();
@@ -7059,6 +8368,7 @@ mod tests {
         assert_eq!(arr[0]["retryCount"], 1);
         assert_eq!(arr[0]["status"], "pending");
         assert_eq!(arr[0]["lastError"], "printer offline");
+        assert!(arr[0]["remediation"].is_null());
 
         // Second failure
         mark_print_job_failed(&db, job_id, "still offline").unwrap();
@@ -7073,6 +8383,7 @@ mod tests {
         let arr = jobs.as_array().unwrap();
         assert_eq!(arr[0]["retryCount"], 3);
         assert_eq!(arr[0]["status"], "failed");
+        assert_eq!(arr[0]["remediation"]["action"], "open_printer_settings");
     }
 
     #[test]
@@ -7769,6 +9080,170 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_receipt_reprint_increments_counter_and_marks_duplicate() {
+        let db = test_db();
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO orders (id, order_number, items, total_amount, total_amount_cents, subtotal, subtotal_cents, status, order_type, sync_status, created_at, updated_at)
+                 VALUES ('ord-dup', 'ORD-DUP', '[]', 10.0, 1000, 10.0, 1000, 'completed', 'dine-in', 'pending', datetime('now'), datetime('now'))",
+                [],
+            )
+            .unwrap();
+        }
+        assert!(enqueue_receipt_reprint(&db, "ord-dup", Some("staff-1"))
+            .unwrap_err()
+            .contains("No receipt"));
+
+        enqueue_print_job(&db, "order_receipt", "ord-dup", None).unwrap();
+        let mark_printed = |db: &DbState| {
+            db.conn
+                .lock()
+                .unwrap()
+                .execute(
+                    "UPDATE print_jobs SET status = 'dispatched' WHERE entity_id = 'ord-dup'",
+                    [],
+                )
+                .unwrap();
+        };
+        mark_printed(&db);
+
+        let first = enqueue_receipt_reprint(&db, "ord-dup", Some("staff-1")).unwrap();
+        assert_eq!(first["reprintCount"], 1);
+        // A second request while the copy is still queued reuses that job.
+        let queued = enqueue_receipt_reprint(&db, "ord-dup", Some("staff-1")).unwrap();
+        assert_eq!(queued["duplicate"], true);
+        mark_printed(&db);
+
+        let second = enqueue_receipt_reprint(&db, "ord-dup", Some("staff-2")).unwrap();
+        assert_eq!(second["reprintCount"], 2);
+        let job_id = second["jobId"].as_str().unwrap().to_string();
+
+        let (payload, stored_count, reprint_events): (Option<String>, i64, i64) = {
+            let conn = db.conn.lock().unwrap();
+            let (payload, count) = conn
+                .query_row(
+                    "SELECT entity_payload_json, reprint_count FROM print_jobs WHERE id = ?1",
+                    params![job_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap();
+            let events = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM order_events
+                     WHERE order_id = 'ord-dup' AND event_type = 'receipt_reprinted'
+                       AND actor_staff_id IN ('staff-1', 'staff-2')",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            (payload, count, events)
+        };
+        assert_eq!(stored_count, 2);
+        assert_eq!(reprint_events, 2);
+
+        let document =
+            build_document_for_job(&db, "order_receipt", "ord-dup", payload.as_deref()).unwrap();
+        match &document {
+            ReceiptDocument::OrderReceipt(doc) => {
+                assert_eq!(doc.reprint_count, 2);
+                assert!(doc.reprinted_at.is_some());
+            }
+            _ => panic!("expected OrderReceipt"),
+        }
+        let mut layout = LayoutConfig::default();
+        receipt_renderer::apply_reprint_marker(&mut layout, &document);
+        assert!(layout
+            .copy_label
+            .as_deref()
+            .unwrap_or("")
+            .starts_with("DUPLICATE #2"));
+        assert!(receipt_renderer::render_html(&document, &layout).contains("DUPLICATE #2"));
+    }
+
+    #[test]
+    fn merchant_copy_receipt_prints_marker_and_signature_line() {
+        let db = test_db();
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO orders (id, order_number, items, total_amount, total_amount_cents, subtotal, subtotal_cents, status, order_type, sync_status, created_at, updated_at)
+                 VALUES ('ord-card', 'ORD-CARD', '[]', 10.0, 1000, 10.0, 1000, 'completed', 'pickup', 'pending', datetime('now'), datetime('now'))",
+                [],
+            )
+            .unwrap();
+        }
+        let payload = serde_json::json!({ "receiptCopy": 2, "merchantCopy": true });
+        let document =
+            build_document_for_job(&db, "order_receipt", "ord-card", Some(&payload.to_string()))
+                .unwrap();
+        let mut layout = LayoutConfig::default();
+        receipt_renderer::apply_reprint_marker(&mut layout, &document);
+        assert_eq!(layout.copy_label.as_deref(), Some("MERCHANT COPY"));
+        let preview = receipt_renderer::render_preview(&document, &layout);
+        assert!(preview.text.contains("Signature: ___"), "{}", preview.text);
+        assert!(receipt_renderer::render_html(&document, &layout).contains("MERCHANT COPY"));
+
+        let customer = build_document_for_job(
+            &db,
+            "order_receipt",
+            "ord-card",
+            Some(&serde_json::json!({ "receiptCopy": 1, "merchantCopy": false }).to_string()),
+        )
+        .unwrap();
+        let mut layout = LayoutConfig::default();
+        receipt_renderer::apply_reprint_marker(&mut layout, &customer);
+        assert!(layout.copy_label.is_none());
+        assert!(!receipt_renderer::render_preview(&customer, &layout)
+            .text
+            .contains("Signature"));
+    }
+
+    #[test]
+    fn return_order_receipt_shows_return_banner_with_original_number() {
+        let db = test_db();
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO orders (id, order_number, items, total_amount, total_amount_cents, subtotal, subtotal_cents, status, order_type, sync_status, created_at, updated_at)
+                 VALUES ('ord-sale', 'ORD-SALE', '[]', 10.0, 1000, 10.0, 1000, 'completed', 'takeaway', 'pending', datetime('now'), datetime('now'))",
+                [],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO orders (id, order_number, items, total_amount, total_amount_cents, subtotal, subtotal_cents, status, order_type, related_order_id, is_return, sync_status, created_at, updated_at)
+                 VALUES ('ord-ret', 'ORD-RET', '[{\"name\":\"Soup\",\"quantity\":1,\"unit_price\":-4.0,\"total_price\":-4.0}]', -4.0, -400, -4.0, -400, 'completed', 'takeaway', 'ord-sale', 1, 'pending', datetime('now'), datetime('now'))",
+                [],
+            )
+            .unwrap();
+        }
+
+        let document = build_document_for_job(&db, "order_receipt", "ord-ret", None).unwrap();
+        match &document {
+            ReceiptDocument::OrderReceipt(doc) => {
+                assert_eq!(doc.return_of_order_number.as_deref(), Some("ORD-SALE"));
+            }
+            _ => panic!("expected OrderReceipt"),
+        }
+        let mut layout = LayoutConfig::default();
+        receipt_renderer::apply_reprint_marker(&mut layout, &document);
+        assert_eq!(
+            layout.copy_label.as_deref(),
+            Some("RETURN  Original order #ORD-SALE")
+        );
+        let html = receipt_renderer::render_html(&document, &layout);
+        assert!(html.contains("status-banner return"));
+        assert!(html.contains("Original order #ORD-SALE"));
+
+        // The sale itself prints as usual.
+        let sale = build_document_for_job(&db, "order_receipt", "ord-sale", None).unwrap();
+        match sale {
+            ReceiptDocument::OrderReceipt(doc) => assert!(doc.return_of_order_number.is_none()),
+            _ => panic!("expected OrderReceipt"),
+        }
+    }
+
     #[test]
     fn test_canceled_receipt_includes_reason() {
         let db = test_db();
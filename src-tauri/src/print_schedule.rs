@@ -0,0 +1,173 @@
+//! Deferred print jobs.
+//!
+//! Scheduled orders and course firing enqueue print jobs that must wait for
+//! a later time. Such a job carries a `not_before` timestamp and stays
+//! `pending` until it is due; the print worker only picks up jobs whose
+//! `not_before` has passed, so waiting is not a failure and does not touch
+//! the retry counters. Jobs that print straight away keep [`IMMEDIATE`].
+//!
+//! `not_before` is stored as millisecond UTC text in the same shape as
+//! SQLite's `strftime('%Y-%m-%dT%H:%M:%fZ', 'now')`, so the worker and the
+//! order-cancel trigger (migration v113) compare it as plain text through
+//! the `(status, not_before)` index.
+//!
+//! A job can be cancelled until it is handed to the printer. Cancelling an
+//! order cancels its jobs that are still waiting for their time.
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+
+/// `not_before` of a job that prints as soon as the worker sees it.
+pub const IMMEDIATE: &str = "1970-01-01T00:00:00.000Z";
+pub const CANCELLED_WARNING_CODE: &str = "operator_cancelled";
+/// Longest a job may be pushed out by `delayMinutes`.
+pub const MAX_DELAY_MINS: i64 = 7 * 24 * 60;
+
+pub fn format_time(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Canonical form of an RFC 3339 timestamp, or `None` if it does not parse.
+pub fn normalize(value: &str) -> Option<String> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .ok()
+        .map(|at| format_time(at.with_timezone(&Utc)))
+}
+
+/// Trigger time from a submit payload: `notBefore` (RFC 3339) or
+/// `delayMinutes` from `now`. `None` prints immediately, as does a time
+/// that has already passed.
+pub fn from_payload(payload: &Value, now: DateTime<Utc>) -> Result<Option<DateTime<Utc>>, String> {
+    let at = if let Some(raw) = crate::value_str(payload, &["notBefore", "not_before"]) {
+        DateTime::parse_from_rfc3339(raw.trim())
+            .map(|at| at.with_timezone(&Utc))
+            .map_err(|e| format!("Invalid notBefore '{raw}': {e}"))?
+    } else if let Some(minutes) = crate::value_i64(payload, &["delayMinutes", "delay_minutes"]) {
+        if !(0..=MAX_DELAY_MINS).contains(&minutes) {
+            return Err(format!(
+                "delayMinutes must be between 0 and {MAX_DELAY_MINS}"
+            ));
+        }
+        now + Duration::minutes(minutes)
+    } else {
+        return Ok(None);
+    };
+    Ok((at > now).then_some(at))
+}
+
+/// Cancel a job that has not been handed to the printer yet, recording who
+/// cancelled it and why.
+pub fn cancel(
+    conn: &Connection,
+    job_id: &str,
+    actor: Option<&str>,
+    reason: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Value, String> {
+    let status: Option<String> = conn
+        .query_row(
+            "SELECT status FROM print_jobs WHERE id = ?1",
+            params![job_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("load print job: {e}"))?;
+    let Some(status) = status else {
+        return Err(format!("Print job not found: {job_id}"));
+    };
+    match status.as_str() {
+        "pending" | "deferred" => {}
+        "printing" | "dispatched" | "printed" => {
+            return Err("Print job was already sent to the printer".into());
+        }
+        other => return Err(format!("Print job is {other} and cannot be cancelled")),
+    }
+
+    let reason = reason
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty())
+        .unwrap_or("operator_cancelled");
+    let now = format_time(now);
+    // The status guard keeps a job the worker claimed in the meantime.
+    let affected = conn
+        .execute(
+            "UPDATE print_jobs
+             SET status = 'cancelled',
+                 warning_code = ?2,
+                 warning_message = 'Print job cancelled before printing',
+                 cancelled_at = ?3,
+                 cancelled_by = ?4,
+                 cancel_reason = ?5,
+                 updated_at = ?3
+             WHERE id = ?1 AND status IN ('pending', 'deferred')",
+            params![job_id, CANCELLED_WARNING_CODE, now, actor, reason],
+        )
+        .map_err(|e| format!("cancel print job: {e}"))?;
+    if affected == 0 {
+        return Err("Print job was already sent to the printer".into());
+    }
+    Ok(json!({
+        "success": true,
+        "jobId": job_id,
+        "previousStatus": status,
+        "cancelledAt": now,
+        "cancelledBy": actor,
+        "cancelReason": reason,
+    }))
+}
+
+/// Jobs still waiting for their trigger time, soonest first.
+pub fn list_scheduled(conn: &Connection, now: DateTime<Utc>) -> Result<Value, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, entity_type, entity_id, printer_profile_id, status, not_before, created_at
+             FROM print_jobs
+             WHERE status IN ('pending', 'deferred') AND not_before > ?1
+             ORDER BY not_before ASC, created_at ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let jobs = stmt
+        .query_map(params![format_time(now)], |row| {
+            Ok(json!({
+                "jobId": row.get::<_, String>(0)?,
+                "entityType": row.get::<_, String>(1)?,
+                "entityId": row.get::<_, String>(2)?,
+                "printerProfileId": row.get::<_, Option<String>>(3)?,
+                "status": row.get::<_, String>(4)?,
+                "notBefore": row.get::<_, String>(5)?,
+                "createdAt": row.get::<_, String>(6)?,
+            }))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(Value::Array(jobs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_schedules_only_future_times() {
+        let now = DateTime::parse_from_rfc3339("2026-10-16T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(from_payload(&json!({}), now).unwrap(), None);
+        assert_eq!(
+            from_payload(&json!({ "delayMinutes": 15 }), now).unwrap(),
+            Some(now + Duration::minutes(15))
+        );
+        assert_eq!(
+            from_payload(&json!({ "notBefore": "2026-10-16T11:00:00Z" }), now).unwrap(),
+            None
+        );
+        assert!(from_payload(&json!({ "notBefore": "tomorrow" }), now).is_err());
+        assert!(from_payload(&json!({ "delayMinutes": -5 }), now).is_err());
+        assert_eq!(
+            normalize("2026-10-16T14:30:00+02:00").as_deref(),
+            Some("2026-10-16T12:30:00.000Z")
+        );
+    }
+}
@@ -0,0 +1,618 @@
+//! Hardware status polling for receipt printers.
+//!
+//! The connection probe in `printer_get_status` only says whether the
+//! printer answers on its port; a printer with an empty roll answers fine
+//! and then swallows every receipt. When `printing.status_poll_enabled` is
+//! on, the watchdog asks each profile's printer for its real state every
+//! `printing.status_poll_interval_secs`:
+//!
+//! - raw TCP (ESC/POS network) printers: `DLE EOT` real-time status
+//! - Windows queue printers: the spooler's `PRINTER_INFO_6` status word
+//!
+//! Serial printers are not polled; their port is held by the print worker.
+//! The last reading per profile is kept in memory. Print jobs for a
+//! profile that last reported paper-out are stored as `deferred` and go
+//! back to `pending` once paper is loaded.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::Utc;
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::db::{self, DbState};
+use crate::printers::{self, ResolvedPrinterTarget};
+
+pub const SETTINGS_CATEGORY: &str = "printing";
+/// `printing.status_poll_enabled`: off unless set.
+pub const POLL_ENABLED_KEY: &str = "status_poll_enabled";
+/// `printing.status_poll_interval_secs`, clamped to
+/// [`MIN_POLL_INTERVAL_SECS`]..=[`MAX_POLL_INTERVAL_SECS`].
+pub const POLL_INTERVAL_KEY: &str = "status_poll_interval_secs";
+pub const DEFAULT_POLL_INTERVAL_SECS: u64 = 30;
+pub const MIN_POLL_INTERVAL_SECS: u64 = 5;
+pub const MAX_POLL_INTERVAL_SECS: u64 = 600;
+
+pub const DEFERRED_STATUS: &str = "deferred";
+pub const PAPER_OUT_WARNING_CODE: &str = "printer_paper_out";
+
+const STATUS_QUERY_TIMEOUT_MS: u64 = 1500;
+
+static STATUSES: Mutex<BTreeMap<String, PrinterHealth>> = Mutex::new(BTreeMap::new());
+
+/// Last hardware reading for one printer profile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrinterHealth {
+    pub online: bool,
+    pub paper_out: bool,
+    pub paper_near_end: bool,
+    pub cover_open: bool,
+    pub detail: Option<String>,
+    pub checked_at: String,
+}
+
+impl PrinterHealth {
+    pub fn offline(detail: impl Into<String>) -> Self {
+        Self {
+            online: false,
+            paper_out: false,
+            paper_near_end: false,
+            cover_open: false,
+            detail: Some(detail.into()),
+            checked_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Same printer state, ignoring when it was read and the free-text
+    /// detail.
+    fn same_state(&self, other: &Self) -> bool {
+        self.online == other.online
+            && self.paper_out == other.paper_out
+            && self.paper_near_end == other.paper_near_end
+            && self.cover_open == other.cover_open
+    }
+
+    /// `printer_get_status` error code, most urgent first.
+    pub fn error_code(&self) -> Option<&'static str> {
+        if self.paper_out {
+            Some("paper_out")
+        } else if self.cover_open {
+            Some("cover_open")
+        } else if !self.online {
+            Some("offline")
+        } else {
+            None
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+}
+
+/// Reads hardware status from a printer. Returns `None` for targets the
+/// transport cannot poll.
+pub trait StatusTransport: Send + Sync {
+    fn query(&self, target: &ResolvedPrinterTarget) -> Option<PrinterHealth>;
+}
+
+/// `DLE EOT` over raw TCP and the spooler status for Windows queues.
+pub struct HardwareTransport;
+
+impl StatusTransport for HardwareTransport {
+    fn query(&self, target: &ResolvedPrinterTarget) -> Option<PrinterHealth> {
+        match target {
+            ResolvedPrinterTarget::RawTcp { host, port } => {
+                Some(query_escpos_tcp(host, *port).unwrap_or_else(PrinterHealth::offline))
+            }
+            ResolvedPrinterTarget::WindowsQueue { printer_name } => Some(
+                query_spooler_status(printer_name)
+                    .map(|status| health_from_spooler_status(status, Utc::now().to_rfc3339()))
+                    .unwrap_or_else(PrinterHealth::offline),
+            ),
+            ResolvedPrinterTarget::SerialPort { .. } => None,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ESC/POS real-time status
+// ---------------------------------------------------------------------------
+
+/// `DLE EOT 1`: printer status.
+const DLE_EOT_PRINTER: [u8; 3] = [0x10, 0x04, 0x01];
+/// `DLE EOT 2`: offline cause.
+const DLE_EOT_OFFLINE: [u8; 3] = [0x10, 0x04, 0x02];
+/// `DLE EOT 4`: roll paper sensor.
+const DLE_EOT_PAPER: [u8; 3] = [0x10, 0x04, 0x04];
+
+/// Status bytes always have bits 1 and 4 set and bits 0 and 7 clear;
+/// anything else is not a reply to `DLE EOT`.
+fn is_status_byte(byte: u8) -> bool {
+    byte & 0x93 == 0x12
+}
+
+/// Health from the three `DLE EOT` replies. A missing or malformed reply
+/// leaves the flags it carries cleared: a printer that accepts the
+/// connection but does not implement the command is treated as online.
+pub fn parse_escpos_status(
+    printer: Option<u8>,
+    offline_cause: Option<u8>,
+    paper: Option<u8>,
+    checked_at: String,
+) -> PrinterHealth {
+    let printer = printer.filter(|byte| is_status_byte(*byte));
+    let offline_cause = offline_cause.filter(|byte| is_status_byte(*byte));
+    let paper = paper.filter(|byte| is_status_byte(*byte));
+
+    let reports_offline = printer.is_some_and(|byte| byte & 0x08 != 0);
+    let cover_open = offline_cause.is_some_and(|byte| byte & 0x04 != 0);
+    let paper_out = offline_cause.is_some_and(|byte| byte & 0x20 != 0)
+        || paper.is_some_and(|byte| byte & 0x60 != 0);
+    let paper_near_end = paper.is_some_and(|byte| byte & 0x0C != 0);
+
+    let detail = if printer.is_none() && offline_cause.is_none() && paper.is_none() {
+        Some("Printer did not answer the status request".to_string())
+    } else {
+        None
+    };
+
+    PrinterHealth {
+        online: !reports_offline,
+        paper_out,
+        paper_near_end,
+        cover_open,
+        detail,
+        checked_at,
+    }
+}
+
+fn query_escpos_tcp(host: &str, port: u16) -> Result<PrinterHealth, String> {
+    use std::net::ToSocketAddrs;
+
+    let timeout = Duration::from_millis(STATUS_QUERY_TIMEOUT_MS);
+    let target = format!("{host}:{port}");
+    let addr = target
+        .to_socket_addrs()
+        .map_err(|e| format!("Resolve printer {target}: {e}"))?
+        .next()
+        .ok_or_else(|| format!("No socket addresses resolved for {target}"))?;
+    let mut stream = std::net::TcpStream::connect_timeout(&addr, timeout)
+        .map_err(|e| format!("Printer {target} is not reachable: {e}"))?;
+    let _ = stream.set_read_timeout(Some(timeout));
+    let _ = stream.set_write_timeout(Some(timeout));
+    let _ = stream.set_nodelay(true);
+
+    let mut ask = |command: &[u8]| -> Result<Option<u8>, String> {
+        stream
+            .write_all(command)
+            .map_err(|e| format!("Send status request to {target}: {e}"))?;
+        let mut reply = [0u8; 1];
+        Ok(match stream.read(&mut reply) {
+            Ok(1) => Some(reply[0]),
+            _ => None,
+        })
+    };
+    let printer = ask(&DLE_EOT_PRINTER)?;
+    // A printer that ignored the first request will ignore the others;
+    // skip two more read timeouts.
+    let (offline_cause, paper) = if printer.is_some() {
+        (ask(&DLE_EOT_OFFLINE)?, ask(&DLE_EOT_PAPER)?)
+    } else {
+        (None, None)
+    };
+    let _ = stream.shutdown(std::net::Shutdown::Both);
+
+    Ok(parse_escpos_status(
+        printer,
+        offline_cause,
+        paper,
+        Utc::now().to_rfc3339(),
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// Windows spooler status
+// ---------------------------------------------------------------------------
+
+const PRINTER_STATUS_PAUSED: u32 = 0x0000_0001;
+const PRINTER_STATUS_ERROR: u32 = 0x0000_0002;
+const PRINTER_STATUS_PAPER_OUT: u32 = 0x0000_0010;
+const PRINTER_STATUS_OFFLINE: u32 = 0x0000_0080;
+const PRINTER_STATUS_NOT_AVAILABLE: u32 = 0x0000_1000;
+const PRINTER_STATUS_DOOR_OPEN: u32 = 0x0040_0000;
+
+pub fn health_from_spooler_status(status: u32, checked_at: String) -> PrinterHealth {
+    let offline = status
+        & (PRINTER_STATUS_OFFLINE | PRINTER_STATUS_NOT_AVAILABLE | PRINTER_STATUS_PAUSED)
+        != 0;
+    PrinterHealth {
+        online: !offline,
+        paper_out: status & PRINTER_STATUS_PAPER_OUT != 0,
+        paper_near_end: false,
+        cover_open: status & PRINTER_STATUS_DOOR_OPEN != 0,
+        detail: (status & PRINTER_STATUS_ERROR != 0)
+            .then(|| format!("Spooler reports an error (status 0x{status:08x})")),
+        checked_at,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn query_spooler_status(printer_name: &str) -> Result<u32, String> {
+    use std::ptr;
+
+    #[allow(clippy::upper_case_acronyms)]
+    type HANDLE = *mut std::ffi::c_void;
+
+    #[link(name = "winspool")]
+    extern "system" {
+        fn OpenPrinterW(
+            pPrinterName: *const u16,
+            phPrinter: *mut HANDLE,
+            pDefault: *const u8,
+        ) -> i32;
+        fn GetPrinterW(
+            hPrinter: HANDLE,
+            Level: u32,
+            pPrinter: *mut u8,
+            cbBuf: u32,
+            pcbNeeded: *mut u32,
+        ) -> i32;
+        fn ClosePrinter(hPrinter: HANDLE) -> i32;
+    }
+
+    let wide_name: Vec<u16> = printer_name
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut h_printer: HANDLE = ptr::null_mut();
+    let ok = unsafe { OpenPrinterW(wide_name.as_ptr(), &mut h_printer, ptr::null()) };
+    if ok == 0 || h_printer.is_null() {
+        return Err(format!(
+            "Printer spool is not reachable for \"{printer_name}\""
+        ));
+    }
+
+    // PRINTER_INFO_6 is a single DWORD status.
+    let mut status: u32 = 0;
+    let mut needed: u32 = 0;
+    let ok = unsafe {
+        GetPrinterW(
+            h_printer,
+            6,
+            &mut status as *mut u32 as *mut u8,
+            std::mem::size_of::<u32>() as u32,
+            &mut needed,
+        )
+    };
+    unsafe {
+        ClosePrinter(h_printer);
+    }
+    if ok == 0 {
+        return Err(format!(
+            "Could not read spooler status for \"{printer_name}\""
+        ));
+    }
+    Ok(status)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn query_spooler_status(_printer_name: &str) -> Result<u32, String> {
+    Err("Printer spool status is only available on Windows".into())
+}
+
+// ---------------------------------------------------------------------------
+// Settings and last known status
+// ---------------------------------------------------------------------------
+
+pub fn poll_enabled(conn: &Connection) -> bool {
+    crate::print::setting_bool(conn, SETTINGS_CATEGORY, POLL_ENABLED_KEY)
+}
+
+pub fn poll_interval(conn: &Connection) -> Duration {
+    let secs = db::get_setting(conn, SETTINGS_CATEGORY, POLL_INTERVAL_KEY)
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_POLL_INTERVAL_SECS)
+        .clamp(MIN_POLL_INTERVAL_SECS, MAX_POLL_INTERVAL_SECS);
+    Duration::from_secs(secs)
+}
+
+pub fn last_status(profile_id: &str) -> Option<PrinterHealth> {
+    STATUSES
+        .lock()
+        .ok()
+        .and_then(|statuses| statuses.get(profile_id).cloned())
+}
+
+pub fn any_paper_out() -> bool {
+    STATUSES
+        .lock()
+        .map(|statuses| statuses.values().any(|health| health.paper_out))
+        .unwrap_or(false)
+}
+
+pub fn snapshot_json() -> Value {
+    let statuses = STATUSES.lock().map(|s| s.clone()).unwrap_or_default();
+    serde_json::to_value(statuses).unwrap_or(Value::Null)
+}
+
+/// Why a job for `profile_id` should wait instead of printing now, if the
+/// printer last reported it has no paper.
+pub fn deferral_reason(profile_id: &str, printer_name: &str) -> Option<String> {
+    last_status(profile_id)
+        .filter(|health| health.paper_out)
+        .map(|_| {
+            format!(
+                "Printer \"{printer_name}\" is out of paper; the job will print when paper is loaded"
+            )
+        })
+}
+
+/// Move `deferred` jobs back to `pending` unless their printer is one of
+/// `paper_out`. An empty list releases everything.
+pub fn release_deferred_jobs(conn: &Connection, paper_out: &[String]) -> Result<usize, String> {
+    let mut sql = String::from(
+        "UPDATE print_jobs
+         SET status = 'pending',
+             warning_code = NULL,
+             warning_message = NULL,
+             updated_at = ?1
+         WHERE status = 'deferred'",
+    );
+    if !paper_out.is_empty() {
+        let placeholders = (0..paper_out.len())
+            .map(|i| format!("?{}", i + 2))
+            .collect::<Vec<_>>()
+            .join(", ");
+        sql.push_str(&format!(
+            " AND (printer_profile_id IS NULL OR printer_profile_id NOT IN ({placeholders}))"
+        ));
+    }
+    let mut binds = vec![Utc::now().to_rfc3339()];
+    binds.extend(paper_out.iter().cloned());
+    conn.execute(&sql, rusqlite::params_from_iter(binds.iter()))
+        .map_err(|e| format!("release deferred print jobs: {e}"))
+}
+
+/// A change in a profile's hardware state found by [`poll_once`].
+#[derive(Debug, Clone)]
+pub struct StatusTransition {
+    pub profile_id: String,
+    pub previous: Option<PrinterHealth>,
+    pub current: PrinterHealth,
+}
+
+/// Poll every enabled profile once and record the readings. Returns the
+/// profiles whose state changed since the previous poll.
+///
+/// Deferred jobs are released for any printer not reporting paper-out,
+/// which covers paper being loaded as well as a profile that was
+/// disabled, deleted or moved to a transport that cannot be polled.
+pub fn poll_once(
+    db: &DbState,
+    transport: &dyn StatusTransport,
+) -> Result<Vec<StatusTransition>, String> {
+    let profiles = printers::list_printer_profiles(db)?;
+    let mut known = Vec::new();
+    let mut readings = BTreeMap::new();
+    for profile in profiles.as_array().into_iter().flatten() {
+        let Some(profile_id) = profile.get("id").and_then(Value::as_str) else {
+            continue;
+        };
+        known.push(profile_id.to_string());
+        if !profile
+            .get("enabled")
+            .and_then(Value::as_bool)
+            .unwrap_or(true)
+        {
+            continue;
+        }
+        let Ok(target) = printers::resolve_printer_target(profile) else {
+            continue;
+        };
+        if let Some(health) = transport.query(&target) {
+            readings.insert(profile_id.to_string(), health);
+        }
+    }
+
+    let mut transitions = Vec::new();
+    let paper_out: Vec<String> = {
+        let mut statuses = STATUSES.lock().map_err(|e| e.to_string())?;
+        for profile_id in &known {
+            let before = statuses.remove(profile_id);
+            let Some(current) = readings.remove(profile_id) else {
+                continue;
+            };
+            if !before.as_ref().is_some_and(|b| b.same_state(&current)) {
+                transitions.push(StatusTransition {
+                    profile_id: profile_id.clone(),
+                    previous: before,
+                    current: current.clone(),
+                });
+            }
+            statuses.insert(profile_id.clone(), current);
+        }
+        statuses
+            .iter()
+            .filter(|(_, health)| health.paper_out)
+            .map(|(profile_id, _)| profile_id.clone())
+            .collect()
+    };
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let released = release_deferred_jobs(&conn, &paper_out)?;
+    if released > 0 {
+        info!(
+            released,
+            "Printer paper restored; deferred print jobs released"
+        );
+    }
+
+    Ok(transitions)
+}
+
+/// Drop the reading for a deleted profile.
+pub fn forget(profile_id: &str) {
+    if let Ok(mut statuses) = STATUSES.lock() {
+        statuses.remove(profile_id);
+    }
+}
+
+/// Forget every reading and release all deferred jobs. Used when polling
+/// is switched off, so nothing waits on a status that will never update.
+pub fn clear(db: &DbState) -> Result<(), String> {
+    let had_statuses = {
+        let mut statuses = STATUSES.lock().map_err(|e| e.to_string())?;
+        let had = !statuses.is_empty();
+        statuses.clear();
+        had
+    };
+    if had_statuses {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let released = release_deferred_jobs(&conn, &[])?;
+        if released > 0 {
+            warn!(
+                released,
+                "Printer status polling disabled; deferred print jobs released"
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) fn set_status_for_test(profile_id: &str, health: Option<PrinterHealth>) {
+    let mut statuses = STATUSES.lock().unwrap();
+    match health {
+        Some(health) => statuses.insert(profile_id.to_string(), health),
+        None => statuses.remove(profile_id),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    /// Canned readings keyed by target label.
+    struct MockTransport(Mutex<HashMap<String, PrinterHealth>>);
+
+    impl MockTransport {
+        fn set(&self, label: &str, health: PrinterHealth) {
+            self.0.lock().unwrap().insert(label.to_string(), health);
+        }
+    }
+
+    impl StatusTransport for MockTransport {
+        fn query(&self, target: &ResolvedPrinterTarget) -> Option<PrinterHealth> {
+            self.0.lock().unwrap().get(&target.label()).cloned()
+        }
+    }
+
+    fn health(online: bool, paper_out: bool) -> PrinterHealth {
+        PrinterHealth {
+            online,
+            paper_out,
+            paper_near_end: false,
+            cover_open: false,
+            detail: None,
+            checked_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    #[test]
+    fn escpos_and_spooler_status_bits_map_to_flags() {
+        let now = || "2026-10-16T10:00:00Z".to_string();
+        // Online, cover closed, paper present.
+        let ok = parse_escpos_status(Some(0x12), Some(0x12), Some(0x12), now());
+        assert!(ok.online && !ok.paper_out && !ok.cover_open && !ok.paper_near_end);
+
+        // Offline because the paper ran out, with the roll sensor agreeing.
+        let empty = parse_escpos_status(Some(0x1A), Some(0x32), Some(0x72), now());
+        assert!(!empty.online && empty.paper_out && !empty.cover_open);
+
+        let cover = parse_escpos_status(Some(0x1A), Some(0x16), Some(0x12), now());
+        assert!(cover.cover_open && !cover.paper_out);
+
+        let near_end = parse_escpos_status(Some(0x12), Some(0x12), Some(0x1E), now());
+        assert!(near_end.paper_near_end && !near_end.paper_out);
+
+        // Silence or garbage: reachable, nothing known.
+        let silent = parse_escpos_status(None, None, None, now());
+        assert!(silent.online && !silent.paper_out && silent.detail.is_some());
+        assert!(!parse_escpos_status(Some(0xFF), Some(0xFF), Some(0xFF), now()).paper_out);
+
+        let spooler =
+            health_from_spooler_status(PRINTER_STATUS_PAPER_OUT | PRINTER_STATUS_DOOR_OPEN, now());
+        assert!(spooler.online && spooler.paper_out && spooler.cover_open);
+        assert!(!health_from_spooler_status(PRINTER_STATUS_OFFLINE, now()).online);
+    }
+
+    #[test]
+    fn poll_reports_transitions_and_releases_deferred_jobs_when_paper_returns() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        let db = DbState::new(conn, PathBuf::from(":memory:"));
+        let created = printers::create_printer_profile(
+            &db,
+            &serde_json::json!({
+                "name": "Front",
+                "printerName": "Front",
+                "printerType": "network",
+                "connectionJson": "{\"type\":\"network\",\"ip\":\"10.0.0.91\",\"port\":9100}"
+            }),
+        )
+        .unwrap();
+        let profile_id = created["profileId"].as_str().unwrap().to_string();
+        db.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO print_jobs (id, entity_type, entity_id, printer_profile_id, status,
+                                         warning_code, created_at, updated_at)
+                 VALUES ('wd-j1', 'order_receipt', 'ord-1', ?1, 'deferred',
+                         'printer_paper_out', datetime('now'), datetime('now'))",
+                [&profile_id],
+            )
+            .unwrap();
+        let transport = MockTransport(Mutex::new(HashMap::new()));
+        let label = "10.0.0.91:9100";
+
+        transport.set(label, health(false, true));
+        let first = poll_once(&db, &transport).unwrap();
+        let first = first.iter().find(|t| t.profile_id == profile_id).unwrap();
+        assert!(first.previous.is_none() && first.current.paper_out);
+        assert!(deferral_reason(&profile_id, "Front").is_some());
+
+        // Same state again: no transition, job still waiting.
+        let again = poll_once(&db, &transport).unwrap();
+        assert!(!again.iter().any(|t| t.profile_id == profile_id));
+
+        transport.set(label, health(true, false));
+        let loaded = poll_once(&db, &transport).unwrap();
+        assert!(loaded
+            .iter()
+            .any(|t| t.profile_id == profile_id && t.previous.as_ref().unwrap().paper_out));
+        assert!(deferral_reason(&profile_id, "Front").is_none());
+
+        let conn = db.conn.lock().unwrap();
+        let (status, warning): (String, Option<String>) = conn
+            .query_row(
+                "SELECT status, warning_code FROM print_jobs WHERE id = 'wd-j1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(status, "pending");
+        assert_eq!(warning, None);
+        drop(conn);
+        forget(&profile_id);
+    }
+}
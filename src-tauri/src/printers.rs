@@ -1627,9 +1627,49 @@ fn non_empty_str(value: Option<&str>) -> Option<String> {
         .map(ToString::to_string)
 }
 
+/// Printer profile types. Each type has its own default profile.
+pub const PROFILE_TYPES: &[&str] = &["receipt", "kitchen", "label"];
+
+fn parse_profile_type(value: Option<&Value>) -> Result<Option<String>, String> {
+    let Some(raw) = value.and_then(Value::as_str).map(str::trim) else {
+        return Ok(None);
+    };
+    let normalized = raw.to_ascii_lowercase();
+    if PROFILE_TYPES.contains(&normalized.as_str()) {
+        Ok(Some(normalized))
+    } else {
+        Err(format!(
+            "Invalid profile_type: {raw}. Must be 'receipt', 'kitchen', or 'label'"
+        ))
+    }
+}
+
+/// `local_settings` key holding the default profile id for `profile_type`.
+/// Receipt keeps the pre-v84 key so older readers still find it.
+fn default_profile_setting_key(profile_type: &str) -> String {
+    if profile_type == "receipt" {
+        "default_printer_profile_id".to_string()
+    } else {
+        format!("default_{profile_type}_printer_profile_id")
+    }
+}
+
+fn profile_type_locked(conn: &rusqlite::Connection, profile_id: &str) -> Result<String, String> {
+    conn.query_row(
+        "SELECT profile_type FROM printer_profiles WHERE id = ?1",
+        params![profile_id],
+        |row| row.get::<_, String>(0),
+    )
+    .map_err(|e| format!("Printer profile {profile_id} not found: {e}"))
+}
+
 fn set_default_profile_locked(conn: &rusqlite::Connection, profile_id: &str) -> Result<(), String> {
-    conn.execute("UPDATE printer_profiles SET is_default = 0", [])
-        .map_err(|e| format!("clear existing default printer flags: {e}"))?;
+    let profile_type = profile_type_locked(conn, profile_id)?;
+    conn.execute(
+        "UPDATE printer_profiles SET is_default = 0 WHERE profile_type = ?1",
+        params![profile_type],
+    )
+    .map_err(|e| format!("clear existing default printer flags: {e}"))?;
     conn.execute(
         "UPDATE printer_profiles
          SET is_default = 1, updated_at = ?1
@@ -1637,25 +1677,65 @@ fn set_default_profile_locked(conn: &rusqlite::Connection, profile_id: &str) ->
         params![Utc::now().to_rfc3339(), profile_id],
     )
     .map_err(|e| format!("set printer default flag: {e}"))?;
-    db::set_setting(conn, "printer", "default_printer_profile_id", profile_id)?;
+    db::set_setting(
+        conn,
+        "printer",
+        &default_profile_setting_key(&profile_type),
+        profile_id,
+    )?;
     Ok(())
 }
 
-fn clear_default_profile_locked(conn: &rusqlite::Connection) -> Result<(), String> {
-    conn.execute("UPDATE printer_profiles SET is_default = 0", [])
-        .map_err(|e| format!("clear default profile flags: {e}"))?;
+fn clear_default_profile_locked(
+    conn: &rusqlite::Connection,
+    profile_type: &str,
+) -> Result<(), String> {
+    conn.execute(
+        "UPDATE printer_profiles SET is_default = 0 WHERE profile_type = ?1",
+        params![profile_type],
+    )
+    .map_err(|e| format!("clear default profile flags: {e}"))?;
     conn.execute(
         "DELETE FROM local_settings
          WHERE setting_category = 'printer'
-           AND setting_key = 'default_printer_profile_id'",
-        [],
+           AND setting_key = ?1",
+        params![default_profile_setting_key(profile_type)],
     )
     .map_err(|e| format!("clear default printer setting: {e}"))?;
     Ok(())
 }
 
-fn get_default_profile_id_from_setting(conn: &rusqlite::Connection) -> Option<String> {
-    db::get_setting(conn, "printer", "default_printer_profile_id").and_then(|id| {
+/// After `profile_id` stops being the default of `profile_type` (flag
+/// cleared, type changed, or deleted), point the setting at whichever
+/// profile of that type still carries the flag, or drop it.
+fn repoint_default_setting_locked(
+    conn: &rusqlite::Connection,
+    profile_type: &str,
+    profile_id: &str,
+) -> Result<(), String> {
+    if get_default_profile_id_from_setting(conn, profile_type).as_deref() != Some(profile_id) {
+        return Ok(());
+    }
+    let key = default_profile_setting_key(profile_type);
+    if let Some(other_default) = get_default_profile_id_from_column(conn, profile_type) {
+        db::set_setting(conn, "printer", &key, &other_default)?;
+    } else {
+        conn.execute(
+            "DELETE FROM local_settings
+             WHERE setting_category = 'printer'
+               AND setting_key = ?1",
+            params![key],
+        )
+        .map_err(|e| format!("clear default printer setting: {e}"))?;
+    }
+    Ok(())
+}
+
+fn get_default_profile_id_from_setting(
+    conn: &rusqlite::Connection,
+    profile_type: &str,
+) -> Option<String> {
+    db::get_setting(conn, "printer", &default_profile_setting_key(profile_type)).and_then(|id| {
         let trimmed = id.trim().to_string();
         if trimmed.is_empty() {
             None
@@ -1665,13 +1745,16 @@ fn get_default_profile_id_from_setting(conn: &rusqlite::Connection) -> Option<St
     })
 }
 
-fn get_default_profile_id_from_column(conn: &rusqlite::Connection) -> Option<String> {
+fn get_default_profile_id_from_column(
+    conn: &rusqlite::Connection,
+    profile_type: &str,
+) -> Option<String> {
     conn.query_row(
         "SELECT id FROM printer_profiles
-         WHERE is_default = 1
+         WHERE is_default = 1 AND profile_type = ?1
          ORDER BY updated_at DESC, created_at DESC
          LIMIT 1",
-        [],
+        params![profile_type],
         |row| row.get::<_, String>(0),
     )
     .ok()
@@ -1754,6 +1837,12 @@ pub fn create_printer_profile(db: &DbState, profile: &Value) -> Result<Value, St
         .filter(|v| !v.is_empty())
         .unwrap_or("receipt")
         .to_string();
+    let profile_type = parse_profile_type(
+        profile
+            .get("profileType")
+            .or_else(|| profile.get("profile_type")),
+    )?
+    .unwrap_or_else(|| "receipt".to_string());
     let is_default = profile
         .get("isDefault")
         .or_else(|| profile.get("is_default"))
@@ -1872,9 +1961,9 @@ pub fn create_printer_profile(db: &DbState, profile: &Value) -> Result<Value, St
                                        fallback_printer_id, connection_json,
                                        escpos_code_page,
                                        font_type, layout_density, header_emphasis,
-                                       created_at, updated_at)
+                                       profile_type, created_at, updated_at)
          VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12,
-                 ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?26)",
+                 ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?27)",
         params![
             id,
             &name,
@@ -1901,6 +1990,7 @@ pub fn create_printer_profile(db: &DbState, profile: &Value) -> Result<Value, St
             font_type,
             layout_density,
             header_emphasis,
+            profile_type,
             now,
         ],
     )
@@ -1928,11 +2018,15 @@ pub fn update_printer_profile(db: &DbState, profile: &Value) -> Result<Value, St
 
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let now = Utc::now().to_rfc3339();
-    let (current_role, current_connection_json): (String, Option<String>) = conn
+    let (current_role, current_connection_json, current_profile_type): (
+        String,
+        Option<String>,
+        String,
+    ) = conn
         .query_row(
-            "SELECT role, connection_json FROM printer_profiles WHERE id = ?1",
+            "SELECT role, connection_json, profile_type FROM printer_profiles WHERE id = ?1",
             [id],
-            |row| Ok((row.get(0)?, row.get(1)?)),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
         )
         .map_err(|e| format!("load current printer profile: {e}"))?;
 
@@ -1945,6 +2039,12 @@ pub fn update_printer_profile(db: &DbState, profile: &Value) -> Result<Value, St
         .and_then(|v| v.as_str())
         .map(|value| value.trim().to_string())
         .filter(|value| !value.is_empty());
+    let requested_profile_type = parse_profile_type(
+        profile
+            .get("profileType")
+            .or_else(|| profile.get("profile_type")),
+    )?
+    .filter(|value| *value != current_profile_type);
 
     if let Some(raw) = profile.get("name").and_then(|v| v.as_str()) {
         let v = raw.trim();
@@ -2051,6 +2151,13 @@ pub fn update_printer_profile(db: &DbState, profile: &Value) -> Result<Value, St
         sets.push("role = ?");
         vals.push(Box::new(v.to_string()));
     }
+    if let Some(v) = requested_profile_type.as_deref() {
+        // The default flag belongs to the old type; the profile has to be
+        // made default again under its new type.
+        sets.push("profile_type = ?");
+        vals.push(Box::new(v.to_string()));
+        sets.push("is_default = 0");
+    }
     if let Some(v) = profile
         .get("isDefault")
         .or_else(|| profile.get("is_default"))
@@ -2202,6 +2309,10 @@ pub fn update_printer_profile(db: &DbState, profile: &Value) -> Result<Value, St
         }
     }
 
+    if requested_profile_type.is_some() {
+        repoint_default_setting_locked(&conn, &current_profile_type, id)?;
+    }
+
     match requested_default {
         Some(true) => {
             set_default_profile_locked(&conn, id)?;
@@ -2213,24 +2324,10 @@ pub fn update_printer_profile(db: &DbState, profile: &Value) -> Result<Value, St
             )
             .map_err(|e| format!("clear printer default flag: {e}"))?;
 
-            if get_default_profile_id_from_setting(&conn).as_deref() == Some(id) {
-                if let Some(other_default) = get_default_profile_id_from_column(&conn) {
-                    db::set_setting(
-                        &conn,
-                        "printer",
-                        "default_printer_profile_id",
-                        &other_default,
-                    )?;
-                } else {
-                    conn.execute(
-                        "DELETE FROM local_settings
-                         WHERE setting_category = 'printer'
-                           AND setting_key = 'default_printer_profile_id'",
-                        [],
-                    )
-                    .map_err(|e| format!("clear default printer setting: {e}"))?;
-                }
-            }
+            let profile_type = requested_profile_type
+                .as_deref()
+                .unwrap_or(current_profile_type.as_str());
+            repoint_default_setting_locked(&conn, profile_type, id)?;
         }
         None => {}
     }
@@ -2253,7 +2350,7 @@ pub fn list_printer_profiles(db: &DbState) -> Result<Value, String> {
                     character_set, greek_render_mode, receipt_template,
                     fallback_printer_id, connection_json,
                     escpos_code_page,
-                    font_type, layout_density, header_emphasis
+                    font_type, layout_density, header_emphasis, profile_type
              FROM printer_profiles ORDER BY created_at ASC",
         )
         .map_err(|e| e.to_string())?;
@@ -2288,6 +2385,7 @@ pub fn list_printer_profiles(db: &DbState) -> Result<Value, String> {
                 "fontType": row.get::<_, String>(24)?,
                 "layoutDensity": row.get::<_, String>(25)?,
                 "headerEmphasis": row.get::<_, String>(26)?,
+                "profileType": row.get::<_, String>(27)?,
             }))
         })
         .map_err(|e| e.to_string())?
@@ -2310,7 +2408,7 @@ pub fn get_printer_profile(db: &DbState, profile_id: &str) -> Result<Value, Stri
                 character_set, greek_render_mode, receipt_template,
                 fallback_printer_id, connection_json,
                 escpos_code_page,
-                font_type, layout_density, header_emphasis
+                font_type, layout_density, header_emphasis, profile_type
          FROM printer_profiles WHERE id = ?1",
         params![profile_id],
         |row| {
@@ -2342,6 +2440,7 @@ pub fn get_printer_profile(db: &DbState, profile_id: &str) -> Result<Value, Stri
                 "fontType": row.get::<_, String>(24)?,
                 "layoutDensity": row.get::<_, String>(25)?,
                 "headerEmphasis": row.get::<_, String>(26)?,
+                "profileType": row.get::<_, String>(27)?,
             }))
         },
     )
@@ -2352,11 +2451,7 @@ pub fn get_printer_profile(db: &DbState, profile_id: &str) -> Result<Value, Stri
 pub fn delete_printer_profile(db: &DbState, profile_id: &str) -> Result<Value, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
-    let was_default_setting = get_default_profile_id_from_setting(&conn)
-        .as_deref()
-        .map(|id| id == profile_id)
-        .unwrap_or(false);
-
+    let profile_type = profile_type_locked(&conn, profile_id)?;
     let affected = conn
         .execute(
             "DELETE FROM printer_profiles WHERE id = ?1",
@@ -2369,30 +2464,15 @@ pub fn delete_printer_profile(db: &DbState, profile_id: &str) -> Result<Value, S
     }
 
     // Keep local setting and is_default source-of-truth in sync after delete.
-    if was_default_setting {
-        if let Some(other_default) = get_default_profile_id_from_column(&conn) {
-            db::set_setting(
-                &conn,
-                "printer",
-                "default_printer_profile_id",
-                &other_default,
-            )?;
-        } else {
-            conn.execute(
-                "DELETE FROM local_settings
-                 WHERE setting_category = 'printer'
-                   AND setting_key = 'default_printer_profile_id'",
-                [],
-            )
-            .map_err(|e| format!("clear default printer setting after delete: {e}"))?;
-        }
-    }
+    repoint_default_setting_locked(&conn, &profile_type, profile_id)?;
+    crate::printer_watchdog::forget(profile_id);
 
     info!(id = %profile_id, "Printer profile deleted");
     Ok(serde_json::json!({ "success": true }))
 }
 
-/// Set the default printer profile ID in local_settings.
+/// Make `profile_id` the default of its profile type. Defaults of other
+/// types are left alone.
 pub fn set_default_printer_profile(db: &DbState, profile_id: &str) -> Result<Value, String> {
     // Verify profile exists
     let _ = get_printer_profile(db, profile_id)?;
@@ -2404,15 +2484,25 @@ pub fn set_default_printer_profile(db: &DbState, profile_id: &str) -> Result<Val
     Ok(serde_json::json!({ "success": true }))
 }
 
-/// Get the default printer profile (full profile object or null).
+/// Get the default receipt printer profile (full profile object or null).
 pub fn get_default_printer_profile(db: &DbState) -> Result<Value, String> {
+    get_default_printer_profile_for_type(db, "receipt")
+}
+
+/// Get the default profile of `profile_type` (full profile object or null).
+pub fn get_default_printer_profile_for_type(
+    db: &DbState,
+    profile_type: &str,
+) -> Result<Value, String> {
     let selected_id = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        if let Some(id) = get_default_profile_id_from_setting(&conn) {
+        if let Some(id) = get_default_profile_id_from_setting(&conn, profile_type) {
             let exists: bool = conn
                 .query_row(
-                    "SELECT EXISTS(SELECT 1 FROM printer_profiles WHERE id = ?1)",
-                    params![id.clone()],
+                    "SELECT EXISTS(
+                        SELECT 1 FROM printer_profiles WHERE id = ?1 AND profile_type = ?2
+                     )",
+                    params![id.clone(), profile_type],
                     |row| row.get(0),
                 )
                 .unwrap_or(false);
@@ -2427,9 +2517,14 @@ pub fn get_default_printer_profile(db: &DbState) -> Result<Value, String> {
             None
         }
         .or_else(|| {
-            let column_default = get_default_profile_id_from_column(&conn);
+            let column_default = get_default_profile_id_from_column(&conn, profile_type);
             if let Some(ref id) = column_default {
-                let _ = db::set_setting(&conn, "printer", "default_printer_profile_id", id);
+                let _ = db::set_setting(
+                    &conn,
+                    "printer",
+                    &default_profile_setting_key(profile_type),
+                    id,
+                );
             }
             column_default
         })
@@ -2439,7 +2534,7 @@ pub fn get_default_printer_profile(db: &DbState) -> Result<Value, String> {
         get_printer_profile(db, &id)
     } else {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        let _ = clear_default_profile_locked(&conn);
+        let _ = clear_default_profile_locked(&conn, profile_type);
         Ok(Value::Null)
     }
 }
@@ -2543,6 +2638,52 @@ pub fn resolve_printer_profile_for_role(
     resolve_any_enabled_profile(db)
 }
 
+/// Resolve a profile of `profile_type` for a print job.
+///
+/// Priority: explicit job profile id, the type's enabled default, then the
+/// first enabled profile of that type. Unlike role resolution there is no
+/// fallback to other types; a label job has nowhere sensible to go on a
+/// receipt printer.
+pub fn resolve_printer_profile_for_type(
+    db: &DbState,
+    job_profile_id: Option<&str>,
+    profile_type: &str,
+) -> Result<Option<Value>, String> {
+    if let Some(id) = job_profile_id.filter(|id| !id.is_empty()) {
+        return get_printer_profile(db, id)
+            .map(Some)
+            .map_err(|e| format!("Job printer profile not found: {e}"));
+    }
+
+    let default_profile = get_default_printer_profile_for_type(db, profile_type)?;
+    if !default_profile.is_null()
+        && default_profile
+            .get("enabled")
+            .and_then(Value::as_bool)
+            .unwrap_or(true)
+    {
+        return Ok(Some(default_profile));
+    }
+
+    let selected_id = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT id
+             FROM printer_profiles
+             WHERE profile_type = ?1 AND enabled = 1
+             ORDER BY updated_at DESC, created_at ASC
+             LIMIT 1",
+            params![profile_type],
+            |row| row.get::<_, String>(0),
+        )
+        .ok()
+    };
+    match selected_id {
+        Some(id) => get_printer_profile(db, &id).map(Some),
+        None => Ok(None),
+    }
+}
+
 /// Reprint a failed print job by resetting its status and retry counters.
 pub fn reprint_job(db: &DbState, job_id: &str) -> Result<Value, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
@@ -2599,10 +2740,7 @@ mod tests {
         )
         .expect("pragma setup");
         db::run_migrations_for_test(&conn);
-        DbState {
-            conn: Mutex::new(conn),
-            db_path: PathBuf::from(":memory:"),
-        }
+        DbState::new(conn, PathBuf::from(":memory:"))
     }
 
     #[test]
@@ -3390,6 +3528,97 @@ mod tests {
         assert_ne!(p1_id, p2_id);
     }
 
+    #[test]
+    fn test_each_profile_type_keeps_its_own_default() {
+        let db = test_db();
+
+        let receipt = create_printer_profile(
+            &db,
+            &serde_json::json!({
+                "name": "Receipt",
+                "printerName": "ReceiptPrinter",
+                "isDefault": true,
+            }),
+        )
+        .unwrap();
+        let receipt_id = receipt["profileId"].as_str().unwrap().to_string();
+        let label = create_printer_profile(
+            &db,
+            &serde_json::json!({
+                "name": "Bag labels",
+                "printerName": "LabelPrinter",
+                "paperWidthMm": 58,
+                "profileType": "label",
+            }),
+        )
+        .unwrap();
+        let label_id = label["profileId"].as_str().unwrap().to_string();
+
+        set_default_printer_profile(&db, &label_id).unwrap();
+
+        assert_eq!(get_default_printer_profile(&db).unwrap()["id"], receipt_id);
+        let label_default = get_default_printer_profile_for_type(&db, "label").unwrap();
+        assert_eq!(label_default["id"], label_id);
+        assert_eq!(label_default["profileType"], "label");
+        assert_eq!(
+            get_printer_profile(&db, &receipt_id).unwrap()["profileType"],
+            "receipt"
+        );
+
+        // Moving the label profile to another type drops its default flag.
+        update_printer_profile(
+            &db,
+            &serde_json::json!({ "id": label_id, "profileType": "kitchen" }),
+        )
+        .unwrap();
+        assert!(get_default_printer_profile_for_type(&db, "label")
+            .unwrap()
+            .is_null());
+        assert!(get_default_printer_profile_for_type(&db, "kitchen")
+            .unwrap()
+            .is_null());
+        assert_eq!(get_default_printer_profile(&db).unwrap()["id"], receipt_id);
+
+        let err = update_printer_profile(
+            &db,
+            &serde_json::json!({ "id": label_id, "profileType": "sticker" }),
+        )
+        .unwrap_err();
+        assert!(err.contains("Invalid profile_type"));
+    }
+
+    #[test]
+    fn test_resolve_printer_profile_for_type_does_not_cross_types() {
+        let db = test_db();
+
+        create_printer_profile(
+            &db,
+            &serde_json::json!({
+                "name": "Receipt",
+                "printerName": "ReceiptPrinter",
+                "isDefault": true,
+            }),
+        )
+        .unwrap();
+        assert!(resolve_printer_profile_for_type(&db, None, "label")
+            .unwrap()
+            .is_none());
+
+        let label = create_printer_profile(
+            &db,
+            &serde_json::json!({
+                "name": "Labels",
+                "printerName": "LabelPrinter",
+                "profileType": "label",
+            }),
+        )
+        .unwrap();
+        let resolved = resolve_printer_profile_for_type(&db, None, "label")
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved["id"], label["profileId"]);
+    }
+
     #[test]
     fn test_resolve_printer_profile_for_role_prefers_role_then_falls_back() {
         let db = test_db();
@@ -0,0 +1,706 @@
+//! Encrypted terminal provisioning files.
+//!
+//! A provisioning file carries what an operator needs to bring a replacement
+//! or additional terminal up in the same state as an existing one: the
+//! keyring credentials, the operator-tunable `local_settings` categories,
+//! printer profiles and ECR devices. The bundle is serialised to JSON,
+//! encrypted with AES-256-GCM under a key derived from a passphrase with
+//! Argon2id, and written as a small JSON envelope that records the KDF
+//! parameters, salt and nonce next to the ciphertext.
+//!
+//! Nothing in this module logs bundle contents; credentials are zeroized
+//! when a bundle is dropped.
+
+use std::collections::BTreeMap;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{db, printers, storage, terminal_helpers};
+
+pub const FILE_FORMAT: &str = "the-small-pos/provisioning";
+pub const FILE_VERSION: u32 = 1;
+pub const MIN_PASSPHRASE_CHARS: usize = 8;
+
+const KDF_ALGORITHM: &str = "argon2id";
+const CIPHER_ALGORITHM: &str = "aes-256-gcm";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Upper bounds for KDF parameters read from a file, so a crafted envelope
+/// cannot make the import spin or allocate without limit.
+const MAX_KDF_MEMORY_KIB: u32 = 256 * 1024;
+const MAX_KDF_ITERATIONS: u32 = 10;
+const MAX_KDF_PARALLELISM: u32 = 4;
+
+/// Keyring entries carried by a provisioning file. The renderer session blob
+/// is deliberately excluded: it belongs to the exporting terminal's user.
+pub const CREDENTIAL_KEYS: &[&str] = &[
+    "admin_dashboard_url",
+    "terminal_id",
+    "pos_api_key",
+    "branch_id",
+    "organization_id",
+    "business_type",
+    "supabase_url",
+    "supabase_anon_key",
+    "ghost_mode_feature_enabled",
+    storage::KEY_CALLERID_SIP_PASSWORD,
+];
+
+/// `local_settings` categories carried by a provisioning file. Terminal
+/// identity lives in the keyring and is mirrored back into the `terminal`
+/// category by the credential setter, so that category is not exported.
+pub const SETTING_CATEGORIES: &[&str] = &[
+    "general",
+    "tax",
+    "receipt",
+    "receipt_actions",
+    "printer",
+    "restaurant",
+    "orders",
+    "ui",
+    "fiscalization.gr",
+    "eod",
+    "inventory",
+];
+
+/// Argon2id cost parameters recorded in the envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfCost {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfCost {
+    fn default() -> Self {
+        Self {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvisioningBundle {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: String,
+    #[serde(default)]
+    pub credentials: BTreeMap<String, String>,
+    #[serde(default)]
+    pub settings: BTreeMap<String, BTreeMap<String, String>>,
+    #[serde(default)]
+    pub printer_profiles: Vec<Value>,
+    #[serde(default)]
+    pub ecr_devices: Vec<Value>,
+}
+
+/// Lists credential names only, so a stray `{:?}` cannot leak secrets.
+impl std::fmt::Debug for ProvisioningBundle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProvisioningBundle")
+            .field("format", &self.format)
+            .field("version", &self.version)
+            .field("exported_at", &self.exported_at)
+            .field("credentials", &self.credentials.keys().collect::<Vec<_>>())
+            .field("settings", &self.settings.keys().collect::<Vec<_>>())
+            .field("printer_profiles", &self.printer_profiles.len())
+            .field("ecr_devices", &self.ecr_devices.len())
+            .finish()
+    }
+}
+
+impl Drop for ProvisioningBundle {
+    fn drop(&mut self) {
+        for value in self.credentials.values_mut() {
+            value.zeroize();
+        }
+    }
+}
+
+impl ProvisioningBundle {
+    /// Check the bundle shape before anything is written back.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.format != FILE_FORMAT {
+            return Err(format!("Unsupported provisioning format: {}", self.format));
+        }
+        if self.version == 0 || self.version > FILE_VERSION {
+            return Err(format!(
+                "Unsupported provisioning version: {}",
+                self.version
+            ));
+        }
+        for key in self.credentials.keys() {
+            if !CREDENTIAL_KEYS.contains(&key.as_str()) {
+                return Err(format!("Unknown credential in provisioning file: {key}"));
+            }
+        }
+        for required in ["terminal_id", "pos_api_key"] {
+            if self
+                .credentials
+                .get(required)
+                .map_or(true, |v| v.trim().is_empty())
+            {
+                return Err(format!("Provisioning file is missing {required}"));
+            }
+        }
+        for (category, values) in &self.settings {
+            if !SETTING_CATEGORIES.contains(&category.as_str()) {
+                return Err(format!(
+                    "Unsupported settings category in provisioning file: {category}"
+                ));
+            }
+            if let Some(key) = values
+                .keys()
+                .find(|key| terminal_helpers::is_sensitive_terminal_setting(key))
+            {
+                return Err(format!(
+                    "Provisioning file must not carry secret setting {category}.{key}"
+                ));
+            }
+        }
+        for profile in &self.printer_profiles {
+            let name = profile.get("name").and_then(Value::as_str).unwrap_or("");
+            if !profile.is_object() || name.trim().is_empty() {
+                return Err("Provisioning file contains an invalid printer profile".into());
+            }
+        }
+        for device in &self.ecr_devices {
+            let id = device.get("id").and_then(Value::as_str).unwrap_or("");
+            if !device.is_object() || id.trim().is_empty() {
+                return Err("Provisioning file contains an invalid ECR device".into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Payload in the shape `storage::update_terminal_credentials` expects.
+    pub fn credentials_payload(&self) -> Value {
+        let mut payload = serde_json::Map::new();
+        for (key, field) in [
+            ("terminal_id", "terminalId"),
+            ("pos_api_key", "apiKey"),
+            ("admin_dashboard_url", "adminDashboardUrl"),
+            ("branch_id", "branchId"),
+            ("organization_id", "organizationId"),
+            ("supabase_url", "supabaseUrl"),
+            ("supabase_anon_key", "supabaseAnonKey"),
+            ("ghost_mode_feature_enabled", "ghostModeFeatureEnabled"),
+        ] {
+            if let Some(value) = self.credentials.get(key) {
+                payload.insert(field.to_string(), Value::String(value.clone()));
+            }
+        }
+        Value::Object(payload)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KdfEnvelope {
+    algorithm: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    format: String,
+    version: u32,
+    kdf: KdfEnvelope,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Read every exported credential from the OS keyring.
+pub fn credentials_from_keyring() -> BTreeMap<String, String> {
+    CREDENTIAL_KEYS
+        .iter()
+        .filter_map(|key| {
+            storage::get_credential(key)
+                .filter(|value| !value.trim().is_empty())
+                .map(|value| (key.to_string(), value))
+        })
+        .collect()
+}
+
+/// Gather settings, printer profiles and ECR devices into a bundle.
+pub fn collect_bundle(
+    db: &db::DbState,
+    credentials: BTreeMap<String, String>,
+) -> Result<ProvisioningBundle, String> {
+    let (settings, ecr_devices) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let settings = collect_settings(&conn)?;
+        let devices = db::ecr_list_devices(&conn)
+            .into_iter()
+            .map(portable_ecr_device)
+            .collect::<Vec<_>>();
+        (settings, devices)
+    };
+    let printer_profiles = match printers::list_printer_profiles(db)? {
+        Value::Array(rows) => rows,
+        _ => Vec::new(),
+    };
+    Ok(ProvisioningBundle {
+        format: FILE_FORMAT.to_string(),
+        version: FILE_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        credentials,
+        settings,
+        printer_profiles,
+        ecr_devices,
+    })
+}
+
+fn collect_settings(
+    conn: &Connection,
+) -> Result<BTreeMap<String, BTreeMap<String, String>>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT setting_category, setting_key, setting_value
+             FROM local_settings ORDER BY setting_category, setting_key",
+        )
+        .map_err(|e| format!("read settings: {e}"))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .map_err(|e| format!("read settings: {e}"))?;
+    let mut settings: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    for row in rows {
+        let (category, key, value) = row.map_err(|e| format!("read settings: {e}"))?;
+        if !SETTING_CATEGORIES.contains(&category.as_str())
+            || terminal_helpers::is_sensitive_terminal_setting(&key)
+        {
+            continue;
+        }
+        settings.entry(category).or_default().insert(key, value);
+    }
+    Ok(settings)
+}
+
+/// Turn an `ecr_devices` row into the shape `db::ecr_insert_device` reads:
+/// JSON columns parsed, flags as booleans, runtime status dropped.
+fn portable_ecr_device(row: Value) -> Value {
+    let Value::Object(mut map) = row else {
+        return row;
+    };
+    for key in ["connectionDetails", "taxRates", "settings"] {
+        if let Some(Value::String(raw)) = map.get(key) {
+            if let Ok(parsed) = serde_json::from_str::<Value>(raw) {
+                map.insert(key.to_string(), parsed);
+            }
+        }
+    }
+    for key in ["isDefault", "enabled"] {
+        if let Some(flag) = map.get(key).and_then(Value::as_i64) {
+            map.insert(key.to_string(), Value::Bool(flag != 0));
+        }
+    }
+    for key in [
+        "status",
+        "lastConnectedAt",
+        "lastError",
+        "createdAt",
+        "updatedAt",
+    ] {
+        map.remove(key);
+    }
+    Value::Object(map)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], cost: KdfCost) -> Result<Zeroizing<[u8; 32]>, String> {
+    let params = argon2::Params::new(cost.memory_kib, cost.iterations, cost.parallelism, Some(32))
+        .map_err(|e| format!("Invalid key derivation parameters: {e}"))?;
+    let argon = argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+    let mut key = Zeroizing::new([0u8; 32]);
+    argon
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+        .map_err(|e| format!("Key derivation failed: {e}"))?;
+    Ok(key)
+}
+
+/// Associated data binding the ciphertext to the envelope header, so the
+/// format and version cannot be swapped without failing authentication.
+fn header_aad(version: u32) -> Vec<u8> {
+    format!("{FILE_FORMAT};v{version}").into_bytes()
+}
+
+/// Encrypt a bundle with the default Argon2id cost.
+pub fn seal(bundle: &ProvisioningBundle, passphrase: &str) -> Result<String, String> {
+    seal_with_cost(bundle, passphrase, KdfCost::default())
+}
+
+pub fn seal_with_cost(
+    bundle: &ProvisioningBundle,
+    passphrase: &str,
+    cost: KdfCost,
+) -> Result<String, String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(format!(
+            "Passphrase must be at least {MIN_PASSPHRASE_CHARS} characters"
+        ));
+    }
+    let plaintext =
+        Zeroizing::new(serde_json::to_vec(bundle).map_err(|e| format!("serialize bundle: {e}"))?);
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_key(passphrase, &salt, cost)?;
+    let cipher = Aes256Gcm::new_from_slice(key.as_ref()).map_err(|e| e.to_string())?;
+    let aad = header_aad(FILE_VERSION);
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &plaintext,
+                aad: &aad,
+            },
+        )
+        .map_err(|_| "Encryption failed".to_string())?;
+
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let envelope = Envelope {
+        format: FILE_FORMAT.to_string(),
+        version: FILE_VERSION,
+        kdf: KdfEnvelope {
+            algorithm: KDF_ALGORITHM.to_string(),
+            memory_kib: cost.memory_kib,
+            iterations: cost.iterations,
+            parallelism: cost.parallelism,
+            salt: b64.encode(salt),
+        },
+        cipher: CIPHER_ALGORITHM.to_string(),
+        nonce: b64.encode(nonce),
+        ciphertext: b64.encode(ciphertext),
+    };
+    serde_json::to_string_pretty(&envelope).map_err(|e| format!("serialize envelope: {e}"))
+}
+
+/// Decrypt and validate a provisioning file.
+pub fn open(contents: &str, passphrase: &str) -> Result<ProvisioningBundle, String> {
+    let envelope: Envelope = serde_json::from_str(contents)
+        .map_err(|_| "Not a provisioning file (unreadable envelope)".to_string())?;
+    if envelope.format != FILE_FORMAT {
+        return Err(format!(
+            "Unsupported provisioning format: {}",
+            envelope.format
+        ));
+    }
+    if envelope.version == 0 || envelope.version > FILE_VERSION {
+        return Err(format!(
+            "Unsupported provisioning version: {}",
+            envelope.version
+        ));
+    }
+    if envelope.kdf.algorithm != KDF_ALGORITHM || envelope.cipher != CIPHER_ALGORITHM {
+        return Err("Unsupported provisioning encryption scheme".into());
+    }
+    let cost = KdfCost {
+        memory_kib: envelope.kdf.memory_kib,
+        iterations: envelope.kdf.iterations,
+        parallelism: envelope.kdf.parallelism,
+    };
+    if cost.memory_kib > MAX_KDF_MEMORY_KIB
+        || cost.iterations > MAX_KDF_ITERATIONS
+        || cost.parallelism > MAX_KDF_PARALLELISM
+    {
+        return Err("Provisioning key derivation parameters are out of range".into());
+    }
+
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let salt = b64
+        .decode(&envelope.kdf.salt)
+        .map_err(|_| "Provisioning file salt is malformed".to_string())?;
+    let nonce = b64
+        .decode(&envelope.nonce)
+        .ok()
+        .filter(|nonce| nonce.len() == NONCE_LEN)
+        .ok_or("Provisioning file nonce is malformed")?;
+    let ciphertext = b64
+        .decode(&envelope.ciphertext)
+        .map_err(|_| "Provisioning file ciphertext is malformed".to_string())?;
+
+    let key = derive_key(passphrase, &salt, cost)?;
+    let cipher = Aes256Gcm::new_from_slice(key.as_ref()).map_err(|e| e.to_string())?;
+    let aad = header_aad(envelope.version);
+    let plaintext = Zeroizing::new(
+        cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| {
+                "Provisioning file could not be decrypted (wrong passphrase or damaged file)"
+                    .to_string()
+            })?,
+    );
+    let bundle: ProvisioningBundle = serde_json::from_slice(&plaintext)
+        .map_err(|_| "Provisioning file content is malformed".to_string())?;
+    if bundle.version != envelope.version {
+        return Err("Provisioning file version mismatch".into());
+    }
+    bundle.validate()?;
+    Ok(bundle)
+}
+
+/// Whether this terminal currently has an active shift. Imports are refused
+/// while one is open so a shift never straddles two configurations.
+pub fn has_open_shift(conn: &Connection) -> bool {
+    conn.query_row(
+        "SELECT COUNT(*) FROM staff_shifts WHERE status = 'active'",
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+    .unwrap_or(false)
+}
+
+/// Write settings, printer profiles and ECR devices from a validated bundle
+/// through the regular setters. Credentials are applied separately by the
+/// caller so they go through the keyring setter. Returns per-section counts.
+pub fn apply_bundle(db: &db::DbState, bundle: &ProvisioningBundle) -> Result<Value, String> {
+    let mut setting_count = 0usize;
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        for (category, values) in &bundle.settings {
+            for (key, value) in values {
+                db::set_setting(&conn, category, key, value)?;
+                setting_count += 1;
+            }
+        }
+        for device in &bundle.ecr_devices {
+            let id = device.get("id").and_then(Value::as_str).unwrap_or_default();
+            if db::ecr_get_device(&conn, id).is_some() {
+                db::ecr_update_device(&conn, id, device)?;
+            } else {
+                db::ecr_insert_device(&conn, device)?;
+            }
+        }
+    }
+
+    // Profiles keep their id when it already exists here; new ones get a
+    // fresh id from the setter, so fallback references are remapped after.
+    let mut id_map: BTreeMap<String, String> = BTreeMap::new();
+    for profile in &bundle.printer_profiles {
+        let source_id = profile
+            .get("id")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let exists = !source_id.is_empty() && printers::get_printer_profile(db, &source_id).is_ok();
+        let mut fields = profile.clone();
+        if let Some(map) = fields.as_object_mut() {
+            map.remove("fallbackPrinterId");
+            map.remove("fallback_printer_id");
+        }
+        if exists {
+            printers::update_printer_profile(db, &fields)?;
+            id_map.insert(source_id.clone(), source_id);
+        } else {
+            let created = printers::create_printer_profile(db, &fields)?;
+            if let Some(new_id) = created.get("profileId").and_then(Value::as_str) {
+                id_map.insert(source_id, new_id.to_string());
+            }
+        }
+    }
+    for profile in &bundle.printer_profiles {
+        let source_id = profile
+            .get("id")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let fallback = profile
+            .get("fallbackPrinterId")
+            .and_then(Value::as_str)
+            .and_then(|id| id_map.get(id));
+        if let (Some(target), Some(fallback)) = (id_map.get(source_id), fallback) {
+            printers::update_printer_profile(
+                db,
+                &serde_json::json!({ "id": target, "fallbackPrinterId": fallback }),
+            )?;
+        }
+    }
+
+    Ok(serde_json::json!({
+        "credentials": bundle.credentials.len(),
+        "settings": setting_count,
+        "printerProfiles": bundle.printer_profiles.len(),
+        "ecrDevices": bundle.ecr_devices.len(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    const TEST_COST: KdfCost = KdfCost {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    fn test_db() -> db::DbState {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        db::run_migrations_for_test(&conn);
+        db::DbState::new(conn, std::path::PathBuf::from(":memory:"))
+    }
+
+    fn credentials() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("terminal_id".to_string(), "terminal-a".to_string()),
+            ("pos_api_key".to_string(), "secret-api-key".to_string()),
+            (
+                "admin_dashboard_url".to_string(),
+                "https://admin.example.com".to_string(),
+            ),
+        ])
+    }
+
+    fn seed_source(db: &db::DbState) {
+        {
+            let conn = db.conn.lock().unwrap();
+            db::set_setting(&conn, "general", "currency", "EUR").unwrap();
+            db::set_setting(&conn, "receipt", "footer", "Thanks").unwrap();
+            db::set_setting(&conn, "terminal", "terminal_id", "terminal-a").unwrap();
+            db::set_setting(&conn, "general", "sync_token", "leak").unwrap();
+            db::ecr_insert_device(
+                &conn,
+                &serde_json::json!({
+                    "id": "ecr-1",
+                    "name": "Counter terminal",
+                    "connectionType": "network",
+                    "connectionDetails": { "host": "10.0.0.9", "port": 20007 },
+                    "isDefault": true,
+                }),
+            )
+            .unwrap();
+        }
+        let kitchen = printers::create_printer_profile(
+            db,
+            &serde_json::json!({ "name": "Kitchen", "printerName": "EPSON-K" }),
+        )
+        .unwrap();
+        let kitchen_id = kitchen["profileId"].as_str().unwrap().to_string();
+        printers::create_printer_profile(
+            db,
+            &serde_json::json!({
+                "name": "Front",
+                "printerName": "EPSON-F",
+                "fallbackPrinterId": kitchen_id,
+            }),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn bundle_round_trips_through_encrypted_file() {
+        let source = test_db();
+        seed_source(&source);
+        let bundle = collect_bundle(&source, credentials()).unwrap();
+        assert!(!bundle.settings.contains_key("terminal"));
+        assert!(!bundle.settings["general"].contains_key("sync_token"));
+        assert_eq!(bundle.ecr_devices[0]["connectionDetails"]["port"], 20007);
+        assert_eq!(bundle.ecr_devices[0]["isDefault"], true);
+
+        let file = seal_with_cost(&bundle, "correct horse", TEST_COST).unwrap();
+        assert!(!file.contains("secret-api-key"));
+        assert!(!file.contains("EPSON-K"));
+
+        let opened = open(&file, "correct horse").unwrap();
+        assert!(opened == bundle);
+        assert_eq!(opened.credentials_payload()["apiKey"], "secret-api-key");
+
+        let target = test_db();
+        let summary = apply_bundle(&target, &opened).unwrap();
+        assert_eq!(summary["printerProfiles"], 2);
+        let conn = target.conn.lock().unwrap();
+        assert_eq!(
+            db::get_setting(&conn, "receipt", "footer").as_deref(),
+            Some("Thanks")
+        );
+        let device = db::ecr_get_device(&conn, "ecr-1").expect("device imported");
+        assert_eq!(device["isDefault"], 1);
+        assert_eq!(
+            serde_json::from_str::<Value>(device["connectionDetails"].as_str().unwrap()).unwrap()
+                ["host"],
+            "10.0.0.9"
+        );
+        let (front_fallback, kitchen_id): (String, String) = conn
+            .query_row(
+                "SELECT f.fallback_printer_id, k.id FROM printer_profiles f
+                 JOIN printer_profiles k ON k.name = 'Kitchen' WHERE f.name = 'Front'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(front_fallback, kitchen_id);
+    }
+
+    #[test]
+    fn open_rejects_wrong_passphrase_tampering_and_bad_versions() {
+        let bundle = collect_bundle(&test_db(), credentials()).unwrap();
+        assert!(seal_with_cost(&bundle, "short", TEST_COST).is_err());
+        let file = seal_with_cost(&bundle, "correct horse", TEST_COST).unwrap();
+
+        let err = open(&file, "wrong horse!").unwrap_err();
+        assert!(err.contains("could not be decrypted"));
+
+        let mut envelope: Value = serde_json::from_str(&file).unwrap();
+        envelope["version"] = serde_json::json!(2);
+        assert!(open(&envelope.to_string(), "correct horse")
+            .unwrap_err()
+            .contains("Unsupported provisioning version"));
+
+        let mut envelope: Value = serde_json::from_str(&file).unwrap();
+        envelope["kdf"]["memoryKib"] = serde_json::json!(MAX_KDF_MEMORY_KIB + 1);
+        assert!(open(&envelope.to_string(), "correct horse").is_err());
+
+        assert!(open("{\"hello\":1}", "correct horse").is_err());
+
+        let mut missing_key = bundle.clone();
+        missing_key.credentials.remove("pos_api_key");
+        let file = seal_with_cost(&missing_key, "correct horse", TEST_COST).unwrap();
+        assert!(open(&file, "correct horse")
+            .unwrap_err()
+            .contains("missing pos_api_key"));
+    }
+
+    #[test]
+    fn open_shift_is_detected() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        assert!(!has_open_shift(&conn));
+        conn.execute(
+            "INSERT INTO staff_shifts (id, staff_id, role_type, check_in_time, status, created_at, updated_at)
+             VALUES ('s1', 'staff-1', 'cashier', datetime('now'), 'active', datetime('now'), datetime('now'))",
+            [],
+        )
+        .unwrap();
+        assert!(has_open_shift(&conn));
+    }
+}
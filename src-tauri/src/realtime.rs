@@ -0,0 +1,689 @@
+//! Supabase Realtime subscription for remote orders.
+//!
+//! The sync loop reconciles remote orders on a polling interval, which means
+//! a web/app order can take up to a full cycle to appear on the terminal.
+//! This module keeps a Phoenix-protocol websocket open against
+//! `/realtime/v1/websocket`, joins a `postgres_changes` channel for the
+//! terminal's branch, and applies INSERT/UPDATE rows as soon as they arrive.
+//!
+//! Remote rows are applied through the same paths the command layer and the
+//! reconciler use (`persist_remote_order` / `apply_remote_order_snapshot`),
+//! so the realtime feed is purely a latency optimisation — polling remains
+//! the source of truth whenever the socket is down.
+
+use chrono::Utc;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::commands;
+use crate::db::DbState;
+use crate::notify::{self, NotificationKind};
+use crate::order_locks;
+use crate::storage;
+use crate::sync::{self, RemoteOrderSnapshotOutcome};
+
+/// Phoenix heartbeat cadence; Supabase drops sockets after ~60s of silence.
+const HEARTBEAT_INTERVAL_SECS: u64 = 25;
+/// Reconnect when nothing (not even a heartbeat reply) arrives for this long.
+const STALE_SOCKET_SECS: u64 = 70;
+/// Poll interval while credentials are missing.
+const UNCONFIGURED_RETRY_SECS: u64 = 30;
+const BACKOFF_BASE_SECS: u64 = 1;
+const BACKOFF_MAX_SECS: u64 = 60;
+const ORDERS_TOPIC: &str = "realtime:public:orders";
+
+// ---------------------------------------------------------------------------
+// Shared state
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Default)]
+struct RealtimeStatus {
+    state: &'static str,
+    connected_at: Option<String>,
+    last_message_at: Option<String>,
+    last_event_at: Option<String>,
+    last_error: Option<String>,
+    reconnect_attempts: u32,
+    events_received: u64,
+}
+
+/// Tauri managed state for the realtime subscription.
+///
+/// Holds the status snapshot reported to the UI and a `Notify` used to force
+/// a reconnect when terminal credentials change.
+pub struct RealtimeState {
+    status: Mutex<RealtimeStatus>,
+    restart: Notify,
+    ref_counter: AtomicU64,
+}
+
+impl Default for RealtimeState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RealtimeState {
+    pub fn new() -> Self {
+        Self {
+            status: Mutex::new(RealtimeStatus {
+                state: "disabled",
+                ..Default::default()
+            }),
+            restart: Notify::new(),
+            ref_counter: AtomicU64::new(1),
+        }
+    }
+
+    /// Drop the current socket (if any) and reconnect with fresh credentials.
+    pub fn request_restart(&self) {
+        self.restart.notify_one();
+    }
+
+    pub fn status_json(&self) -> Value {
+        let status = self.status.lock().map(|s| s.clone()).unwrap_or_default();
+        json!({
+            "connected": status.state == "subscribed",
+            "state": status.state,
+            "connectedAt": status.connected_at,
+            "lastMessageAt": status.last_message_at,
+            "lastEventAt": status.last_event_at,
+            "lastError": status.last_error,
+            "reconnectAttempts": status.reconnect_attempts,
+            "eventsReceived": status.events_received,
+        })
+    }
+
+    fn next_ref(&self) -> String {
+        self.ref_counter.fetch_add(1, Ordering::Relaxed).to_string()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut RealtimeStatus)) {
+        if let Ok(mut status) = self.status.lock() {
+            f(&mut status);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Protocol helpers
+// ---------------------------------------------------------------------------
+
+/// Build the websocket URL from the Supabase project URL.
+pub(crate) fn build_realtime_url(supabase_url: &str, anon_key: &str) -> Result<String, String> {
+    let trimmed = supabase_url.trim().trim_end_matches('/');
+    let host = if let Some(rest) = trimmed.strip_prefix("https://") {
+        format!("wss://{rest}")
+    } else if let Some(rest) = trimmed.strip_prefix("http://") {
+        format!("ws://{rest}")
+    } else {
+        return Err(format!("Unsupported Supabase URL scheme: {trimmed}"));
+    };
+    let key: String = url::form_urlencoded::byte_serialize(anon_key.trim().as_bytes()).collect();
+    Ok(format!(
+        "{host}/realtime/v1/websocket?apikey={key}&vsn=1.0.0"
+    ))
+}
+
+/// Phoenix `phx_join` for INSERT/UPDATE changes on the branch's orders,
+/// plus every change to its order edit locks.
+pub(crate) fn build_join_message(branch_id: &str, anon_key: &str, msg_ref: &str) -> Value {
+    let filter = format!("branch_id=eq.{branch_id}");
+    json!({
+        "topic": ORDERS_TOPIC,
+        "event": "phx_join",
+        "payload": {
+            "config": {
+                "broadcast": { "self": false },
+                "presence": { "key": "" },
+                "postgres_changes": [
+                    { "event": "INSERT", "schema": "public", "table": "orders", "filter": filter },
+                    { "event": "UPDATE", "schema": "public", "table": "orders", "filter": filter },
+                    { "event": "*", "schema": "public", "table": "order_locks", "filter": filter },
+                ],
+            },
+            "access_token": anon_key,
+        },
+        "ref": msg_ref,
+        "join_ref": msg_ref,
+    })
+}
+
+pub(crate) fn build_heartbeat_message(msg_ref: &str) -> Value {
+    json!({
+        "topic": "phoenix",
+        "event": "heartbeat",
+        "payload": {},
+        "ref": msg_ref,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum RealtimeMessage {
+    OrderInserted(Value),
+    OrderUpdated(Value),
+    OrderLockChanged { record: Value, deleted: bool },
+    JoinOk,
+    JoinError(String),
+    ChannelError(String),
+    Other,
+}
+
+/// Classify an incoming Phoenix frame.
+pub(crate) fn parse_realtime_message(raw: &str) -> RealtimeMessage {
+    let Ok(msg) = serde_json::from_str::<Value>(raw) else {
+        return RealtimeMessage::Other;
+    };
+    let topic = msg.get("topic").and_then(Value::as_str).unwrap_or("");
+    let event = msg.get("event").and_then(Value::as_str).unwrap_or("");
+    let payload = msg.get("payload").cloned().unwrap_or(Value::Null);
+
+    match event {
+        "phx_reply" if topic == ORDERS_TOPIC => {
+            let status = payload.get("status").and_then(Value::as_str).unwrap_or("");
+            if status == "ok" {
+                RealtimeMessage::JoinOk
+            } else {
+                let reason = payload
+                    .pointer("/response/reason")
+                    .and_then(Value::as_str)
+                    .unwrap_or(status)
+                    .to_string();
+                RealtimeMessage::JoinError(reason)
+            }
+        }
+        "phx_error" | "phx_close" if topic == ORDERS_TOPIC => {
+            RealtimeMessage::ChannelError(event.to_string())
+        }
+        "system" if topic == ORDERS_TOPIC => {
+            if payload.get("status").and_then(Value::as_str) == Some("error") {
+                let message = payload
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("channel error")
+                    .to_string();
+                RealtimeMessage::ChannelError(message)
+            } else {
+                RealtimeMessage::Other
+            }
+        }
+        "postgres_changes" => {
+            let data = payload.get("data").cloned().unwrap_or(Value::Null);
+            let change = data.get("type").and_then(Value::as_str).unwrap_or("");
+            if data.get("table").and_then(Value::as_str) == Some("order_locks") {
+                let deleted = change == "DELETE";
+                let key = if deleted { "old_record" } else { "record" };
+                return match data.get(key).filter(|r| r.is_object()) {
+                    Some(record) => RealtimeMessage::OrderLockChanged {
+                        record: record.clone(),
+                        deleted,
+                    },
+                    None => RealtimeMessage::Other,
+                };
+            }
+            let Some(record) = data.get("record").filter(|r| r.is_object()).cloned() else {
+                return RealtimeMessage::Other;
+            };
+            match change {
+                "INSERT" => RealtimeMessage::OrderInserted(record),
+                "UPDATE" => RealtimeMessage::OrderUpdated(record),
+                _ => RealtimeMessage::Other,
+            }
+        }
+        _ => RealtimeMessage::Other,
+    }
+}
+
+/// Exponential backoff (1s, 2s, 4s … capped at 60s) with up to 25% jitter
+/// so a fleet of terminals doesn't reconnect in lockstep after an outage.
+pub(crate) fn reconnect_delay(attempt: u32, jitter_seed: u64) -> Duration {
+    let exp = BACKOFF_BASE_SECS.saturating_mul(1u64 << attempt.min(6));
+    let base_ms = exp.min(BACKOFF_MAX_SECS) * 1000;
+    let jitter_ms = if base_ms >= 4 {
+        jitter_seed % (base_ms / 4)
+    } else {
+        0
+    };
+    Duration::from_millis(base_ms + jitter_ms)
+}
+
+fn jitter_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0)
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+// ---------------------------------------------------------------------------
+// Event application
+// ---------------------------------------------------------------------------
+
+fn apply_order_insert(app: &AppHandle, db: &DbState, record: &Value) {
+    match commands::orders::persist_remote_order(db, app, record) {
+        Ok(result) => {
+            debug!(result = %result, "Realtime: applied remote order insert");
+            if result.get("orderId").is_some() {
+                let order_number = crate::value_str(record, &["order_number", "orderNumber"])
+                    .map(|number| format!("Order #{number}"))
+                    .unwrap_or_else(|| "An order".to_string());
+                notify::send(
+                    app,
+                    db,
+                    NotificationKind::NewRemoteOrder,
+                    "New online order",
+                    &format!("{order_number} has arrived"),
+                );
+            }
+        }
+        Err(e) => warn!("Realtime: failed to persist remote order insert: {e}"),
+    }
+}
+
+fn apply_order_update(app: &AppHandle, db: &DbState, record: &Value) {
+    match sync::apply_remote_order_snapshot(db, record) {
+        Ok(RemoteOrderSnapshotOutcome::Missing) => apply_order_insert(app, db, record),
+        Ok(RemoteOrderSnapshotOutcome::Unchanged { local_id }) => {
+            debug!(order_id = %local_id, "Realtime: remote order update skipped by snapshot guards");
+        }
+        Ok(RemoteOrderSnapshotOutcome::Updated {
+            local_id,
+            previous_status,
+            status,
+        }) => {
+            let _ = app.emit(
+                "order_realtime_update",
+                json!({ "orderId": local_id, "source": "realtime" }),
+            );
+            if status.is_some() && status != previous_status {
+                let _ = app.emit(
+                    "order_status_updated",
+                    json!({
+                        "orderId": local_id,
+                        "status": status,
+                        "previousStatus": previous_status,
+                        "source": "realtime",
+                    }),
+                );
+            }
+        }
+        Err(e) => warn!("Realtime: failed to apply remote order update: {e}"),
+    }
+}
+
+fn apply_order_lock_change(app: &AppHandle, db: &DbState, record: &Value, deleted: bool) {
+    let terminal_id = order_locks::local_terminal_id();
+    let applied = db.conn.lock().map_err(|e| e.to_string()).and_then(|conn| {
+        let order_id = order_locks::apply_remote_lock(&conn, record, deleted, &terminal_id)?;
+        let lock = match &order_id {
+            Some(order_id) => order_locks::current_lock(&conn, order_id, Utc::now())?,
+            None => None,
+        };
+        Ok(order_id.map(|order_id| (order_id, lock)))
+    });
+    match applied {
+        Ok(Some((order_id, lock))) => {
+            let _ = app.emit(
+                "order_lock_changed",
+                json!({
+                    "orderId": order_id,
+                    "locked": lock.is_some(),
+                    "lock": lock,
+                    "source": "realtime",
+                }),
+            );
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Realtime: failed to apply order lock change: {e}"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Background loop
+// ---------------------------------------------------------------------------
+
+enum SessionEnd {
+    Cancelled,
+    Restart,
+    Failed(String),
+}
+
+async fn run_session(
+    app: &AppHandle,
+    db: &DbState,
+    state: &RealtimeState,
+    cancel: &CancellationToken,
+    url: &str,
+    branch_id: &str,
+    anon_key: &str,
+) -> SessionEnd {
+    let (socket, _) = match tokio_tungstenite::connect_async(url).await {
+        Ok(conn) => conn,
+        Err(e) => return SessionEnd::Failed(format!("connect failed: {e}")),
+    };
+    let (mut sink, mut stream) = socket.split();
+
+    let join = build_join_message(branch_id, anon_key, &state.next_ref());
+    if let Err(e) = sink.send(Message::Text(join.to_string())).await {
+        return SessionEnd::Failed(format!("join send failed: {e}"));
+    }
+
+    let mut heartbeat = tokio::time::interval(Duration::from_secs(HEARTBEAT_INTERVAL_SECS));
+    heartbeat.tick().await;
+    let mut last_message = Instant::now();
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                let _ = sink.close().await;
+                return SessionEnd::Cancelled;
+            }
+            _ = state.restart.notified() => {
+                let _ = sink.close().await;
+                return SessionEnd::Restart;
+            }
+            _ = heartbeat.tick() => {
+                if last_message.elapsed() > Duration::from_secs(STALE_SOCKET_SECS) {
+                    let _ = sink.close().await;
+                    return SessionEnd::Failed("socket stale (no messages)".to_string());
+                }
+                let beat = build_heartbeat_message(&state.next_ref());
+                if let Err(e) = sink.send(Message::Text(beat.to_string())).await {
+                    return SessionEnd::Failed(format!("heartbeat send failed: {e}"));
+                }
+            }
+            frame = stream.next() => {
+                let text = match frame {
+                    None => return SessionEnd::Failed("socket closed by server".to_string()),
+                    Some(Err(e)) => return SessionEnd::Failed(format!("socket error: {e}")),
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) => {
+                        return SessionEnd::Failed("socket closed by server".to_string());
+                    }
+                    Some(Ok(_)) => {
+                        last_message = Instant::now();
+                        continue;
+                    }
+                };
+                last_message = Instant::now();
+                let now = Utc::now().to_rfc3339();
+                state.update(|s| s.last_message_at = Some(now.clone()));
+
+                match parse_realtime_message(&text) {
+                    RealtimeMessage::JoinOk => {
+                        info!(branch_id = %branch_id, "Realtime: subscribed to remote order changes");
+                        state.update(|s| {
+                            s.state = "subscribed";
+                            s.connected_at = Some(now.clone());
+                            s.reconnect_attempts = 0;
+                            s.last_error = None;
+                        });
+                        let _ = app.emit("realtime_status", state.status_json());
+                    }
+                    RealtimeMessage::JoinError(reason) => {
+                        let _ = sink.close().await;
+                        return SessionEnd::Failed(format!("join rejected: {reason}"));
+                    }
+                    RealtimeMessage::ChannelError(reason) => {
+                        let _ = sink.close().await;
+                        return SessionEnd::Failed(format!("channel error: {reason}"));
+                    }
+                    RealtimeMessage::OrderInserted(record) => {
+                        state.update(|s| {
+                            s.events_received += 1;
+                            s.last_event_at = Some(now.clone());
+                        });
+                        apply_order_insert(app, db, &record);
+                    }
+                    RealtimeMessage::OrderUpdated(record) => {
+                        state.update(|s| {
+                            s.events_received += 1;
+                            s.last_event_at = Some(now.clone());
+                        });
+                        apply_order_update(app, db, &record);
+                    }
+                    RealtimeMessage::OrderLockChanged { record, deleted } => {
+                        state.update(|s| {
+                            s.events_received += 1;
+                            s.last_event_at = Some(now.clone());
+                        });
+                        apply_order_lock_change(app, db, &record, deleted);
+                    }
+                    RealtimeMessage::Other => {}
+                }
+            }
+        }
+    }
+}
+
+/// Start the realtime subscription loop.
+///
+/// Runs until `cancel` fires. While the terminal is unconfigured the loop
+/// idles; once connected it reconnects with jittered exponential backoff
+/// and restarts immediately when `RealtimeState::request_restart` is called.
+pub fn start_realtime_subscription(
+    app: AppHandle,
+    db: Arc<DbState>,
+    state: Arc<RealtimeState>,
+    cancel: CancellationToken,
+) {
+    tauri::async_runtime::spawn(async move {
+        info!("Realtime order subscription started");
+        loop {
+            if cancel.is_cancelled() {
+                break;
+            }
+
+            let supabase_url = non_empty(storage::get_credential("supabase_url"));
+            let anon_key = non_empty(storage::get_credential("supabase_anon_key"));
+            let branch_id = non_empty(storage::get_credential("branch_id"));
+            let (Some(supabase_url), Some(anon_key), Some(branch_id)) =
+                (supabase_url, anon_key, branch_id)
+            else {
+                state.update(|s| s.state = "disabled");
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_secs(UNCONFIGURED_RETRY_SECS)) => {}
+                    _ = state.restart.notified() => {}
+                    _ = cancel.cancelled() => break,
+                }
+                continue;
+            };
+
+            let url = match build_realtime_url(&supabase_url, &anon_key) {
+                Ok(url) => url,
+                Err(e) => {
+                    warn!("Realtime: {e}");
+                    state.update(|s| {
+                        s.state = "disabled";
+                        s.last_error = Some(e);
+                    });
+                    tokio::select! {
+                        _ = tokio::time::sleep(Duration::from_secs(UNCONFIGURED_RETRY_SECS)) => {}
+                        _ = state.restart.notified() => {}
+                        _ = cancel.cancelled() => break,
+                    }
+                    continue;
+                }
+            };
+
+            state.update(|s| s.state = "connecting");
+            let outcome =
+                run_session(&app, &db, &state, &cancel, &url, &branch_id, &anon_key).await;
+            match outcome {
+                SessionEnd::Cancelled => break,
+                SessionEnd::Restart => {
+                    info!("Realtime: restarting subscription");
+                    state.update(|s| {
+                        s.state = "connecting";
+                        s.reconnect_attempts = 0;
+                    });
+                }
+                SessionEnd::Failed(error) => {
+                    let attempts = state
+                        .status
+                        .lock()
+                        .map(|s| s.reconnect_attempts)
+                        .unwrap_or(0);
+                    let delay = reconnect_delay(attempts, jitter_seed());
+                    warn!(
+                        attempt = attempts + 1,
+                        delay_ms = delay.as_millis() as u64,
+                        "Realtime: {error}; reconnecting"
+                    );
+                    state.update(|s| {
+                        s.state = "reconnecting";
+                        s.last_error = Some(error);
+                        s.reconnect_attempts = s.reconnect_attempts.saturating_add(1);
+                    });
+                    let _ = app.emit("realtime_status", state.status_json());
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = state.restart.notified() => {}
+                        _ = cancel.cancelled() => break,
+                    }
+                }
+            }
+        }
+        state.update(|s| s.state = "disabled");
+        info!("Realtime order subscription stopped");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn realtime_url_swaps_scheme_and_encodes_key() {
+        let url = build_realtime_url("https://abc.supabase.co/", "key+/=").unwrap();
+        assert_eq!(
+            url,
+            "wss://abc.supabase.co/realtime/v1/websocket?apikey=key%2B%2F%3D&vsn=1.0.0"
+        );
+        assert!(build_realtime_url("http://localhost:54321", "k")
+            .unwrap()
+            .starts_with("ws://localhost:54321/realtime/v1/websocket"));
+        assert!(build_realtime_url("ftp://x", "k").is_err());
+    }
+
+    #[test]
+    fn join_message_filters_orders_by_branch() {
+        let msg = build_join_message("branch-1", "anon", "7");
+        assert_eq!(msg["topic"], ORDERS_TOPIC);
+        assert_eq!(msg["event"], "phx_join");
+        assert_eq!(msg["ref"], "7");
+        let changes = msg["payload"]["config"]["postgres_changes"]
+            .as_array()
+            .unwrap();
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0]["event"], "INSERT");
+        assert_eq!(changes[1]["event"], "UPDATE");
+        assert_eq!(changes[0]["table"], "orders");
+        assert_eq!(changes[1]["table"], "orders");
+        assert_eq!(changes[2]["table"], "order_locks");
+        assert_eq!(changes[2]["event"], "*");
+        for change in changes {
+            assert_eq!(change["filter"], "branch_id=eq.branch-1");
+        }
+    }
+
+    #[test]
+    fn parses_postgres_change_frames() {
+        let insert = json!({
+            "topic": ORDERS_TOPIC,
+            "event": "postgres_changes",
+            "payload": { "data": { "type": "INSERT", "record": { "id": "r1", "status": "pending" } } },
+        });
+        assert_eq!(
+            parse_realtime_message(&insert.to_string()),
+            RealtimeMessage::OrderInserted(json!({ "id": "r1", "status": "pending" }))
+        );
+
+        let update = json!({
+            "topic": ORDERS_TOPIC,
+            "event": "postgres_changes",
+            "payload": { "data": { "type": "UPDATE", "record": { "id": "r1", "status": "ready" } } },
+        });
+        assert!(matches!(
+            parse_realtime_message(&update.to_string()),
+            RealtimeMessage::OrderUpdated(_)
+        ));
+
+        let delete = json!({
+            "topic": ORDERS_TOPIC,
+            "event": "postgres_changes",
+            "payload": { "data": { "type": "DELETE", "old_record": { "id": "r1" } } },
+        });
+        assert_eq!(
+            parse_realtime_message(&delete.to_string()),
+            RealtimeMessage::Other
+        );
+
+        let lock_delete = json!({
+            "topic": ORDERS_TOPIC,
+            "event": "postgres_changes",
+            "payload": { "data": {
+                "table": "order_locks",
+                "type": "DELETE",
+                "old_record": { "order_id": "r1", "terminal_id": "t2" },
+            } },
+        });
+        assert_eq!(
+            parse_realtime_message(&lock_delete.to_string()),
+            RealtimeMessage::OrderLockChanged {
+                record: json!({ "order_id": "r1", "terminal_id": "t2" }),
+                deleted: true,
+            }
+        );
+    }
+
+    #[test]
+    fn parses_join_replies_and_errors() {
+        let ok = json!({ "topic": ORDERS_TOPIC, "event": "phx_reply", "payload": { "status": "ok", "response": {} } });
+        assert_eq!(
+            parse_realtime_message(&ok.to_string()),
+            RealtimeMessage::JoinOk
+        );
+
+        let err = json!({
+            "topic": ORDERS_TOPIC,
+            "event": "phx_reply",
+            "payload": { "status": "error", "response": { "reason": "unauthorized" } },
+        });
+        assert_eq!(
+            parse_realtime_message(&err.to_string()),
+            RealtimeMessage::JoinError("unauthorized".to_string())
+        );
+
+        // Heartbeat replies arrive on the "phoenix" topic and are not join acks.
+        let beat =
+            json!({ "topic": "phoenix", "event": "phx_reply", "payload": { "status": "ok" } });
+        assert_eq!(
+            parse_realtime_message(&beat.to_string()),
+            RealtimeMessage::Other
+        );
+        assert_eq!(parse_realtime_message("not json"), RealtimeMessage::Other);
+    }
+
+    #[test]
+    fn reconnect_delay_grows_and_caps() {
+        assert_eq!(reconnect_delay(0, 0), Duration::from_secs(1));
+        assert_eq!(reconnect_delay(3, 0), Duration::from_secs(8));
+        assert_eq!(reconnect_delay(20, 0), Duration::from_secs(60));
+        let jittered = reconnect_delay(20, u64::MAX);
+        assert!(jittered >= Duration::from_secs(60) && jittered < Duration::from_secs(75));
+    }
+}
@@ -0,0 +1,400 @@
+//! Configurable reason codes for voids, refunds, comps, order declines,
+//! drawer opens, service charge waivers, drawer cash-in / cash-out and
+//! order ETA changes.
+//!
+//! Each action keeps its own list in `local_settings` (category `reasons`,
+//! key = action) as a JSON array of `{code, label, active}`. Migration v93
+//! seeds the defaults below so upgraded terminals have a usable list before
+//! anyone opens the settings screen. Retired codes stay in the list with
+//! `active: false` so historical rows still resolve to a label; only active
+//! codes are accepted for new entries.
+
+use std::collections::HashSet;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::db;
+
+const SETTINGS_CATEGORY: &str = "reasons";
+const MAX_CODE_LEN: usize = 48;
+
+/// Action types that carry a reason code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReasonAction {
+    Void,
+    Refund,
+    Comp,
+    OrderDecline,
+    DrawerOpen,
+    ServiceChargeWaiver,
+    DrawerCashIn,
+    DrawerCashOut,
+    EtaChange,
+}
+
+impl ReasonAction {
+    pub const ALL: [ReasonAction; 9] = [
+        ReasonAction::Void,
+        ReasonAction::Refund,
+        ReasonAction::Comp,
+        ReasonAction::OrderDecline,
+        ReasonAction::DrawerOpen,
+        ReasonAction::ServiceChargeWaiver,
+        ReasonAction::DrawerCashIn,
+        ReasonAction::DrawerCashOut,
+        ReasonAction::EtaChange,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReasonAction::Void => "void",
+            ReasonAction::Refund => "refund",
+            ReasonAction::Comp => "comp",
+            ReasonAction::OrderDecline => "order_decline",
+            ReasonAction::DrawerOpen => "drawer_open",
+            ReasonAction::ServiceChargeWaiver => "service_charge_waiver",
+            ReasonAction::DrawerCashIn => "drawer_cash_in",
+            ReasonAction::DrawerCashOut => "drawer_cash_out",
+            ReasonAction::EtaChange => "eta_change",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        let normalized = raw.trim().to_ascii_lowercase().replace('-', "_");
+        Self::ALL
+            .into_iter()
+            .find(|action| action.as_str() == normalized)
+    }
+
+    fn defaults(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            ReasonAction::Void => &[
+                ("wrong_item", "Wrong item rung up"),
+                ("wrong_tender", "Wrong payment method"),
+                ("duplicate_payment", "Duplicate payment"),
+                ("customer_changed_mind", "Customer changed mind"),
+                ("test_transaction", "Test transaction"),
+                ("other", "Other"),
+            ],
+            ReasonAction::Refund => &[
+                ("quality_issue", "Food quality issue"),
+                ("wrong_order", "Wrong order delivered"),
+                ("missing_item", "Missing item"),
+                ("late_delivery", "Late delivery"),
+                ("overcharge", "Customer overcharged"),
+                ("other", "Other"),
+            ],
+            ReasonAction::Comp => &[
+                ("service_recovery", "Service recovery"),
+                ("manager_comp", "Manager comp"),
+                ("staff_meal", "Staff meal"),
+                ("loyalty_goodwill", "Regular customer goodwill"),
+                ("promotion", "Promotion"),
+                ("other", "Other"),
+            ],
+            ReasonAction::OrderDecline => &[
+                ("item_unavailable", "Item unavailable"),
+                ("kitchen_busy", "Kitchen too busy"),
+                ("outside_delivery_area", "Outside delivery area"),
+                ("closing_soon", "Closing soon"),
+                ("suspected_fraud", "Suspected fraud"),
+                ("other", "Other"),
+            ],
+            ReasonAction::DrawerOpen => &[
+                ("make_change", "Make change"),
+                ("cash_drop", "Cash drop"),
+                ("float_count", "Count float"),
+                ("no_sale", "No sale"),
+                ("other", "Other"),
+            ],
+            ReasonAction::ServiceChargeWaiver => &[
+                ("service_complaint", "Service complaint"),
+                ("long_wait", "Long wait"),
+                ("large_party_agreement", "Agreed with the party in advance"),
+                ("regular_customer", "Regular customer goodwill"),
+                ("other", "Other"),
+            ],
+            ReasonAction::DrawerCashIn => &[
+                ("change_run", "Change from the bank"),
+                ("float_top_up", "Float top-up"),
+                ("safe_transfer", "Cash from the safe"),
+                ("other", "Other"),
+            ],
+            ReasonAction::DrawerCashOut => &[
+                ("safe_drop", "Safe drop"),
+                ("bank_deposit", "Bank deposit"),
+                ("excess_cash", "Excess cash removed"),
+                ("other", "Other"),
+            ],
+            ReasonAction::EtaChange => &[
+                ("kitchen_backlog", "Kitchen running behind"),
+                ("driver_delay", "Driver delayed"),
+                ("item_delay", "Item takes longer"),
+                ("customer_request", "Customer request"),
+                ("ahead_of_schedule", "Ahead of schedule"),
+                ("other", "Other"),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReasonCode {
+    pub code: String,
+    pub label: String,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// The reason a caller supplied, resolved against the action's list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedReason {
+    pub code: String,
+    pub label: String,
+    /// Free text if the caller gave any, otherwise the code's label. This is
+    /// what goes into the legacy free-text reason columns.
+    pub text: String,
+}
+
+fn default_list(action: ReasonAction) -> Vec<ReasonCode> {
+    action
+        .defaults()
+        .iter()
+        .map(|(code, label)| ReasonCode {
+            code: (*code).to_string(),
+            label: (*label).to_string(),
+            active: true,
+        })
+        .collect()
+}
+
+/// The configured list for `action`, falling back to the defaults when the
+/// setting is missing or unreadable.
+pub fn list(conn: &Connection, action: ReasonAction) -> Vec<ReasonCode> {
+    db::get_setting(conn, SETTINGS_CATEGORY, action.as_str())
+        .and_then(|raw| serde_json::from_str::<Vec<ReasonCode>>(&raw).ok())
+        .filter(|codes| !codes.is_empty())
+        .unwrap_or_else(|| default_list(action))
+}
+
+pub fn normalize_code(raw: &str) -> String {
+    raw.trim().to_ascii_lowercase().replace([' ', '-'], "_")
+}
+
+fn validate_list(action: ReasonAction, codes: Vec<ReasonCode>) -> Result<Vec<ReasonCode>, String> {
+    let mut seen = HashSet::new();
+    let mut cleaned = Vec::with_capacity(codes.len());
+    for entry in codes {
+        let code = normalize_code(&entry.code);
+        if code.is_empty() {
+            return Err(format!("{} reason codes cannot be empty", action.as_str()));
+        }
+        if code.len() > MAX_CODE_LEN || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(format!(
+                "Invalid {} reason code '{}': use letters, digits and underscores (max {MAX_CODE_LEN})",
+                action.as_str(),
+                entry.code.trim()
+            ));
+        }
+        if !seen.insert(code.clone()) {
+            return Err(format!(
+                "Duplicate {} reason code '{code}'",
+                action.as_str()
+            ));
+        }
+        let label = entry.label.trim();
+        cleaned.push(ReasonCode {
+            label: if label.is_empty() {
+                code.clone()
+            } else {
+                label.to_string()
+            },
+            code,
+            active: entry.active,
+        });
+    }
+    if !cleaned.iter().any(|entry| entry.active) {
+        return Err(format!(
+            "At least one {} reason code must stay active",
+            action.as_str()
+        ));
+    }
+    Ok(cleaned)
+}
+
+/// Replace the list for `action`. Codes are normalized to snake_case; at
+/// least one must be active, since every guarded action needs one.
+pub fn set(
+    conn: &Connection,
+    action: ReasonAction,
+    codes: Vec<ReasonCode>,
+    actor_staff_id: Option<&str>,
+) -> Result<Vec<ReasonCode>, String> {
+    let cleaned = validate_list(action, codes)?;
+    let value = serde_json::to_string(&cleaned).map_err(|e| e.to_string())?;
+    db::set_setting_with_source(
+        conn,
+        SETTINGS_CATEGORY,
+        action.as_str(),
+        &value,
+        "reasons_set",
+        actor_staff_id,
+    )?;
+    Ok(cleaned)
+}
+
+/// Write the default list for every action that has none yet.
+pub fn seed_defaults(conn: &Connection) -> Result<(), String> {
+    for action in ReasonAction::ALL {
+        if db::get_setting(conn, SETTINGS_CATEGORY, action.as_str()).is_some() {
+            continue;
+        }
+        let value = serde_json::to_string(&default_list(action)).map_err(|e| e.to_string())?;
+        db::set_setting(conn, SETTINGS_CATEGORY, action.as_str(), &value)?;
+    }
+    Ok(())
+}
+
+/// Resolve a caller's reason code for `action`. The code is required and must
+/// be active in the current list; `free_text` is optional detail.
+pub fn require(
+    conn: &Connection,
+    action: ReasonAction,
+    code: Option<&str>,
+    free_text: Option<&str>,
+) -> Result<ResolvedReason, String> {
+    let code = code.map(normalize_code).unwrap_or_default();
+    if code.is_empty() {
+        return Err(format!("A {} reason code is required", action.as_str()));
+    }
+    let entry = list(conn, action)
+        .into_iter()
+        .find(|entry| entry.code == code && entry.active)
+        .ok_or_else(|| format!("Unknown {} reason code '{code}'", action.as_str()))?;
+    let text = free_text
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| entry.label.clone());
+    Ok(ResolvedReason {
+        code: entry.code,
+        label: entry.label,
+        text,
+    })
+}
+
+/// Label for a stored code, including retired ones. Unknown or missing
+/// codes come back as-is so reports never drop a row.
+pub fn label_for(conn: &Connection, action: ReasonAction, code: Option<&str>) -> String {
+    match code {
+        Some(code) => list(conn, action)
+            .into_iter()
+            .find(|entry| entry.code == code)
+            .map(|entry| entry.label)
+            .unwrap_or_else(|| code.to_string()),
+        None => "Uncoded".to_string(),
+    }
+}
+
+/// All lists keyed by action, as returned by `reasons_get`.
+pub fn all_to_json(conn: &Connection) -> Value {
+    let mut lists = serde_json::Map::new();
+    for action in ReasonAction::ALL {
+        lists.insert(action.as_str().to_string(), json!(list(conn, action)));
+    }
+    Value::Object(lists)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    #[test]
+    fn migration_seeds_defaults_and_require_resolves_codes() {
+        let conn = test_conn();
+        for action in ReasonAction::ALL {
+            assert!(db::get_setting(&conn, SETTINGS_CATEGORY, action.as_str()).is_some());
+        }
+
+        let resolved =
+            require(&conn, ReasonAction::Void, Some("Wrong-Item"), None).expect("default code");
+        assert_eq!(resolved.code, "wrong_item");
+        assert_eq!(resolved.text, "Wrong item rung up");
+
+        let with_text = require(
+            &conn,
+            ReasonAction::Refund,
+            Some("quality_issue"),
+            Some("  cold pizza "),
+        )
+        .unwrap();
+        assert_eq!(with_text.text, "cold pizza");
+
+        let missing = require(&conn, ReasonAction::Void, None, Some("asdf")).unwrap_err();
+        assert!(missing.contains("required"), "{missing}");
+        let unknown = require(&conn, ReasonAction::Void, Some("asdf"), None).unwrap_err();
+        assert!(unknown.contains("Unknown void reason code"), "{unknown}");
+    }
+
+    #[test]
+    fn set_validates_and_inactive_codes_are_rejected_but_keep_labels() {
+        let conn = test_conn();
+        let codes = vec![
+            ReasonCode {
+                code: "Burnt Food".into(),
+                label: "Burnt".into(),
+                active: true,
+            },
+            ReasonCode {
+                code: "wrong_item".into(),
+                label: "Wrong item rung up".into(),
+                active: false,
+            },
+        ];
+        let saved = set(&conn, ReasonAction::Void, codes, Some("staff-1")).unwrap();
+        assert_eq!(saved[0].code, "burnt_food");
+
+        assert!(require(&conn, ReasonAction::Void, Some("burnt_food"), None).is_ok());
+        assert!(require(&conn, ReasonAction::Void, Some("wrong_item"), None).is_err());
+        assert_eq!(
+            label_for(&conn, ReasonAction::Void, Some("wrong_item")),
+            "Wrong item rung up"
+        );
+        // Other actions keep their own lists.
+        assert!(require(&conn, ReasonAction::Refund, Some("wrong_order"), None).is_ok());
+
+        let duplicate = vec![
+            ReasonCode {
+                code: "a".into(),
+                label: "A".into(),
+                active: true,
+            },
+            ReasonCode {
+                code: "A".into(),
+                label: "A again".into(),
+                active: true,
+            },
+        ];
+        assert!(set(&conn, ReasonAction::Comp, duplicate, None).is_err());
+        let none_active = vec![ReasonCode {
+            code: "a".into(),
+            label: "A".into(),
+            active: false,
+        }];
+        assert!(set(&conn, ReasonAction::Comp, none_active, None).is_err());
+        assert!(ReasonAction::parse("order-decline") == Some(ReasonAction::OrderDecline));
+    }
+}
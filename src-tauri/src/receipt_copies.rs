@@ -0,0 +1,302 @@
+//! Receipt copies printed automatically after a payment.
+//!
+//! `print.copy_policy` in `local_settings` is an ordered list of rules. Each
+//! rule matches a payment method and an order type (`*` matches any), and
+//! optionally only payments below an amount. The first matching rule sets
+//! how many copies of the order receipt `payment_record` prints and whether
+//! the last of them is the merchant copy, printed with a "MERCHANT COPY"
+//! marker and a signature line. With no matching rule nothing is printed,
+//! so an empty policy leaves printing as it was.
+//!
+//! ```json
+//! { "rules": [
+//!     { "method": "cash", "belowAmount": 5.0, "copies": 0 },
+//!     { "method": "card", "copies": 2, "merchantCopy": true },
+//!     { "method": "*", "copies": 1 }
+//! ] }
+//! ```
+//!
+//! Rules may only name the methods `payment_record` accepts; a settings
+//! write naming any other method is rejected.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::db::{self, DbState};
+
+pub const SETTINGS_CATEGORY: &str = "print";
+pub const POLICY_KEY: &str = "copy_policy";
+/// Print job payload field carrying the copy number (1-based).
+pub const COPY_FIELD: &str = "receiptCopy";
+/// Print job payload field set on the merchant copy.
+pub const MERCHANT_COPY_FIELD: &str = "merchantCopy";
+/// Payment methods a rule may name besides [`ANY`].
+pub const KNOWN_METHODS: [&str; 3] = ["cash", "card", "room_charge"];
+/// Matches every method or order type.
+pub const ANY: &str = "*";
+/// Most copies a single rule may print.
+pub const MAX_COPIES: u32 = 5;
+
+fn any() -> String {
+    ANY.to_string()
+}
+
+fn normalize(raw: &str) -> String {
+    raw.trim().to_ascii_lowercase().replace('_', "-")
+}
+
+fn normalize_method(raw: &str) -> String {
+    raw.trim().to_ascii_lowercase()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyRule {
+    #[serde(default = "any")]
+    pub method: String,
+    #[serde(default = "any", alias = "order_type")]
+    pub order_type: String,
+    /// Only payments strictly below this amount match.
+    #[serde(default, alias = "below_amount")]
+    pub below_amount: Option<f64>,
+    /// Receipts printed in total, merchant copy included.
+    pub copies: u32,
+    #[serde(default, alias = "merchant_copy")]
+    pub merchant_copy: bool,
+}
+
+impl CopyRule {
+    fn matches(&self, method: &str, order_type: &str, amount: f64) -> bool {
+        (self.method == ANY || normalize_method(&self.method) == normalize_method(method))
+            && (self.order_type == ANY || normalize(&self.order_type) == normalize(order_type))
+            && !self.below_amount.is_some_and(|limit| amount >= limit)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CopyPolicy {
+    #[serde(default)]
+    pub rules: Vec<CopyRule>,
+}
+
+impl CopyPolicy {
+    /// Parse and validate the stored JSON.
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let policy: Self =
+            serde_json::from_str(raw).map_err(|e| format!("Invalid receipt copy policy: {e}"))?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (index, rule) in self.rules.iter().enumerate() {
+            let position = index + 1;
+            let method = normalize_method(&rule.method);
+            if method != ANY && !KNOWN_METHODS.contains(&method.as_str()) {
+                return Err(format!(
+                    "Receipt copy rule {position} names unknown payment method '{}'; expected one of {} or {ANY}",
+                    rule.method,
+                    KNOWN_METHODS.join(", ")
+                ));
+            }
+            if rule.order_type.trim().is_empty() {
+                return Err(format!(
+                    "Receipt copy rule {position} has an empty order type"
+                ));
+            }
+            if rule.copies > MAX_COPIES {
+                return Err(format!(
+                    "Receipt copy rule {position} prints {} copies; at most {MAX_COPIES} are allowed",
+                    rule.copies
+                ));
+            }
+            if rule.merchant_copy && rule.copies == 0 {
+                return Err(format!(
+                    "Receipt copy rule {position} asks for a merchant copy but prints no copies"
+                ));
+            }
+            if rule
+                .below_amount
+                .is_some_and(|limit| !limit.is_finite() || limit <= 0.0)
+            {
+                return Err(format!(
+                    "Receipt copy rule {position} needs a positive belowAmount"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The first rule matching this payment, if any.
+    pub fn rule_for(&self, method: &str, order_type: &str, amount: f64) -> Option<&CopyRule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(method, order_type, amount))
+    }
+}
+
+/// The stored policy; an unreadable one counts as empty.
+pub fn load_policy(conn: &Connection) -> CopyPolicy {
+    db::get_setting(conn, SETTINGS_CATEGORY, POLICY_KEY)
+        .and_then(|raw| CopyPolicy::parse(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// Reject a settings write of an invalid `print.copy_policy`. Other keys,
+/// and clearing the policy, pass.
+pub fn validate_setting(category: &str, key: &str, value: &str) -> Result<(), String> {
+    if category != SETTINGS_CATEGORY || key != POLICY_KEY || value.trim().is_empty() {
+        return Ok(());
+    }
+    CopyPolicy::parse(value).map(|_| ())
+}
+
+/// Whether a print job payload is a policy copy.
+pub fn is_copy_payload(payload: Option<&Value>) -> bool {
+    payload.is_some_and(|payload| payload.get(COPY_FIELD).is_some())
+}
+
+/// Print job payloads for the copies `rule` asks for; the merchant copy is
+/// the last one.
+fn copy_payloads(rule: &CopyRule, payment_id: &str) -> Vec<Value> {
+    (1..=rule.copies)
+        .map(|copy| {
+            json!({
+                COPY_FIELD: copy,
+                "receiptCopies": rule.copies,
+                MERCHANT_COPY_FIELD: rule.merchant_copy && copy == rule.copies,
+                "paymentId": payment_id,
+            })
+        })
+        .collect()
+}
+
+/// Enqueue the receipt copies the policy asks for after a payment on
+/// `order_id`. Ghost orders never print. Returns the enqueued jobs.
+pub fn enqueue_after_payment(
+    db: &DbState,
+    order_id: &str,
+    payment_id: &str,
+    method: &str,
+    amount: f64,
+) -> Result<Vec<Value>, String> {
+    let rule = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let order: Option<(String, bool)> = conn
+            .query_row(
+                "SELECT COALESCE(order_type, ''), COALESCE(is_ghost, 0) FROM orders WHERE id = ?1",
+                params![order_id],
+                |row| Ok((row.get(0)?, row.get::<_, i64>(1)? != 0)),
+            )
+            .optional()
+            .map_err(|e| format!("load order for receipt copies: {e}"))?;
+        let Some((order_type, false)) = order else {
+            return Ok(Vec::new());
+        };
+        load_policy(&conn)
+            .rule_for(method, &order_type, amount)
+            .cloned()
+    };
+    let Some(rule) = rule else {
+        return Ok(Vec::new());
+    };
+
+    copy_payloads(&rule, payment_id)
+        .iter()
+        .map(|payload| {
+            crate::print::enqueue_print_job_with_payload(
+                db,
+                "order_receipt",
+                order_id,
+                None,
+                Some(payload),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn policy(raw: Value) -> CopyPolicy {
+        CopyPolicy::parse(&raw.to_string()).unwrap()
+    }
+
+    #[test]
+    fn first_matching_rule_sets_copies_and_unknown_methods_are_rejected() {
+        let policy = policy(json!({ "rules": [
+            { "method": "cash", "belowAmount": 5.0, "copies": 0 },
+            { "method": "card", "copies": 2, "merchantCopy": true },
+            { "method": "*", "orderType": "delivery", "copies": 1 },
+        ] }));
+        assert_eq!(policy.rule_for("cash", "pickup", 4.5).unwrap().copies, 0);
+        assert!(policy.rule_for("cash", "pickup", 5.0).is_none());
+        assert_eq!(policy.rule_for("cash", "delivery", 12.0).unwrap().copies, 1);
+        let card = policy.rule_for("card", "dine_in", 3.0).unwrap();
+        assert_eq!((card.copies, card.merchant_copy), (2, true));
+
+        let error = validate_setting(
+            SETTINGS_CATEGORY,
+            POLICY_KEY,
+            &json!({ "rules": [{ "method": "voucher", "copies": 1 }] }).to_string(),
+        )
+        .unwrap_err();
+        assert!(error.contains("unknown payment method 'voucher'"));
+        assert!(validate_setting(
+            SETTINGS_CATEGORY,
+            POLICY_KEY,
+            &json!({ "rules": [{ "method": "cash", "copies": 0, "merchantCopy": true }] })
+                .to_string(),
+        )
+        .is_err());
+        assert!(validate_setting("print", "other_key", "not json").is_ok());
+    }
+
+    #[test]
+    fn card_payment_enqueues_customer_and_merchant_copies() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        conn.execute(
+            "INSERT INTO orders (id, items, total_amount, status, order_type, sync_status,
+                                 created_at, updated_at)
+             VALUES ('order-1', '[]', 20.0, 'completed', 'pickup', 'pending',
+                     datetime('now'), datetime('now'))",
+            [],
+        )
+        .unwrap();
+        db::set_setting(
+            &conn,
+            SETTINGS_CATEGORY,
+            POLICY_KEY,
+            &json!({ "rules": [{ "method": "card", "copies": 2, "merchantCopy": true }] })
+                .to_string(),
+        )
+        .unwrap();
+        let db = DbState::new(conn, PathBuf::from(":memory:"));
+
+        let jobs = enqueue_after_payment(&db, "order-1", "pay-1", "card", 20.0).unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert!(jobs.iter().all(|job| job.get("duplicate").is_none()));
+        assert!(enqueue_after_payment(&db, "order-1", "pay-2", "cash", 20.0)
+            .unwrap()
+            .is_empty());
+
+        let conn = db.conn.lock().unwrap();
+        let payloads: Vec<Value> = conn
+            .prepare(
+                "SELECT entity_payload_json FROM print_jobs
+                 WHERE entity_type = 'order_receipt' AND entity_id = 'order-1'
+                 ORDER BY json_extract(entity_payload_json, '$.receiptCopy')",
+            )
+            .unwrap()
+            .query_map([], |row| row.get::<_, String>(0))
+            .unwrap()
+            .map(|raw| serde_json::from_str(&raw.unwrap()).unwrap())
+            .collect();
+        assert_eq!(payloads[0][MERCHANT_COPY_FIELD], false);
+        assert_eq!(payloads[1][MERCHANT_COPY_FIELD], true);
+    }
+}
@@ -0,0 +1,515 @@
+//! E-receipt delivery (email / SMS) through the admin API.
+//!
+//! `receipt_send` snapshots the structured receipt of an order into
+//! `receipt_deliveries` and tries to POST it to `/api/pos/receipts/send`
+//! right away. When the admin API is unreachable the row stays `pending`
+//! with an exponential `next_retry_at`, and the background worker started
+//! from `lib.rs` picks it up again; after [`MAX_ATTEMPTS`] it is parked as
+//! `failed`.
+//!
+//! Each row carries an idempotency key of order + destination + local day,
+//! so a double-tap (or a second request the same day) returns the existing
+//! delivery instead of sending twice. The same key is sent to the admin API
+//! so it can deduplicate retries whose response was lost.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{Local, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::DbState;
+use crate::{normalize_phone, order_events, payments, print};
+
+pub const RECEIPT_SEND_PATH: &str = "/api/pos/receipts/send";
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_SENT: &str = "sent";
+pub const STATUS_FAILED: &str = "failed";
+
+/// Attempts before a delivery is parked as `failed`.
+pub const MAX_ATTEMPTS: i64 = 8;
+const BASE_BACKOFF_SECS: i64 = 30;
+const MAX_BACKOFF_SECS: i64 = 3600;
+const RETRY_BATCH_SIZE: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Email,
+    Sms,
+}
+
+impl Channel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Email => "email",
+            Self::Sms => "sms",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "email" | "e-mail" => Some(Self::Email),
+            "sms" | "phone" => Some(Self::Sms),
+            _ => None,
+        }
+    }
+}
+
+/// Validate the destination and normalize it for the chosen channel. When no
+/// channel is given it is inferred: anything with an `@` is an email.
+pub fn parse_destination(
+    channel: Option<&str>,
+    destination: &str,
+) -> Result<(Channel, String), String> {
+    let destination = destination.trim();
+    if destination.is_empty() {
+        return Err("Missing receipt destination".into());
+    }
+    let channel = match channel.map(str::trim).filter(|raw| !raw.is_empty()) {
+        Some(raw) => {
+            Channel::parse(raw).ok_or_else(|| format!("Unknown receipt channel: {raw}"))?
+        }
+        None if destination.contains('@') => Channel::Email,
+        None => Channel::Sms,
+    };
+    match channel {
+        Channel::Email => {
+            let email = destination.to_ascii_lowercase();
+            if is_valid_email(&email) {
+                Ok((channel, email))
+            } else {
+                Err(format!("Invalid email address: {destination}"))
+            }
+        }
+        Channel::Sms => {
+            let phone = normalize_phone(destination);
+            if (7..=15).contains(&phone.len()) {
+                Ok((channel, phone))
+            } else {
+                Err(format!("Invalid phone number: {destination}"))
+            }
+        }
+    }
+}
+
+fn is_valid_email(email: &str) -> bool {
+    if email.len() > 254 || email.chars().any(char::is_whitespace) {
+        return false;
+    }
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !domain.contains("..")
+}
+
+/// Order + channel + destination + local day.
+pub fn idempotency_key(order_id: &str, channel: Channel, destination: &str) -> String {
+    format!(
+        "receipt:{order_id}:{}:{destination}:{}",
+        channel.as_str(),
+        Local::now().format("%Y-%m-%d")
+    )
+}
+
+/// Seconds to wait after the `attempts`-th failure: 30s doubling, capped at
+/// an hour.
+fn backoff_secs(attempts: i64) -> i64 {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    BASE_BACKOFF_SECS
+        .saturating_mul(1_i64 << exponent)
+        .min(MAX_BACKOFF_SECS)
+}
+
+fn delivery_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Value> {
+    Ok(serde_json::json!({
+        "id": row.get::<_, String>(0)?,
+        "orderId": row.get::<_, String>(1)?,
+        "channel": row.get::<_, String>(2)?,
+        "destination": row.get::<_, String>(3)?,
+        "status": row.get::<_, String>(4)?,
+        "attempts": row.get::<_, i64>(5)?,
+        "nextRetryAt": row.get::<_, Option<String>>(6)?,
+        "lastError": row.get::<_, Option<String>>(7)?,
+        "requestedBy": row.get::<_, Option<String>>(8)?,
+        "sentAt": row.get::<_, Option<String>>(9)?,
+        "createdAt": row.get::<_, String>(10)?,
+        "updatedAt": row.get::<_, String>(11)?,
+    }))
+}
+
+const DELIVERY_COLUMNS: &str = "id, order_id, channel, destination, status, attempts,
+    next_retry_at, last_error, requested_by, sent_at, created_at, updated_at";
+
+pub fn get_delivery(conn: &Connection, delivery_id: &str) -> Result<Option<Value>, String> {
+    conn.query_row(
+        &format!("SELECT {DELIVERY_COLUMNS} FROM receipt_deliveries WHERE id = ?1"),
+        params![delivery_id],
+        delivery_from_row,
+    )
+    .optional()
+    .map_err(|e| format!("load receipt delivery: {e}"))
+}
+
+/// Newest first, optionally narrowed to one order and/or status.
+pub fn list_deliveries(
+    conn: &Connection,
+    order_id: Option<&str>,
+    status: Option<&str>,
+    limit: i64,
+) -> Result<Vec<Value>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {DELIVERY_COLUMNS} FROM receipt_deliveries
+             WHERE (?1 IS NULL OR order_id = ?1)
+               AND (?2 IS NULL OR status = ?2)
+             ORDER BY created_at DESC, rowid DESC
+             LIMIT ?3"
+        ))
+        .map_err(|e| format!("prepare receipt deliveries: {e}"))?;
+    let rows = stmt
+        .query_map(
+            params![order_id, status, limit.clamp(1, 500)],
+            delivery_from_row,
+        )
+        .map_err(|e| format!("query receipt deliveries: {e}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read receipt deliveries: {e}"))
+}
+
+/// Snapshot the order's receipt and queue it. Returns the delivery and
+/// whether a new row was created (`false` when the idempotency key already
+/// exists, e.g. on a double-tap).
+pub fn enqueue_delivery(
+    db: &DbState,
+    order_id: &str,
+    channel: Channel,
+    destination: &str,
+    requested_by: Option<&str>,
+) -> Result<(Value, bool), String> {
+    let key = idempotency_key(order_id, channel, destination);
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let existing: Option<String> = conn
+            .query_row(
+                "SELECT id FROM receipt_deliveries WHERE idempotency_key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("check receipt delivery: {e}"))?;
+        if let Some(existing_id) = existing {
+            let delivery = get_delivery(&conn, &existing_id)?
+                .ok_or_else(|| format!("Receipt delivery not found: {existing_id}"))?;
+            return Ok((delivery, false));
+        }
+    }
+
+    // Same structured document and HTML the receipt preview shows.
+    let receipt = print::build_order_receipt_doc(db, order_id)?;
+    let preview = payments::get_receipt_preview(db, order_id)?;
+    let payload = serde_json::json!({
+        "orderId": order_id,
+        "channel": channel.as_str(),
+        "destination": destination,
+        "idempotencyKey": key,
+        "receipt": receipt,
+        "html": preview.get("html").cloned().unwrap_or(Value::Null),
+    });
+
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO receipt_deliveries (
+             id, order_id, channel, destination, idempotency_key, payload_json,
+             status, attempts, next_retry_at, requested_by, created_at, updated_at
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'pending', 0, ?7, ?8, ?7, ?7)",
+        params![
+            id,
+            order_id,
+            channel.as_str(),
+            destination,
+            key,
+            payload.to_string(),
+            now,
+            requested_by,
+        ],
+    )
+    .map_err(|e| format!("queue receipt delivery: {e}"))?;
+    order_events::append(
+        &conn,
+        order_id,
+        order_events::RECEIPT_DELIVERY_QUEUED,
+        requested_by,
+        serde_json::json!({
+            "deliveryId": id,
+            "channel": channel.as_str(),
+            "destination": destination,
+        }),
+    );
+    let delivery =
+        get_delivery(&conn, &id)?.ok_or_else(|| format!("Receipt delivery not found: {id}"))?;
+    Ok((delivery, true))
+}
+
+pub fn mark_sent(conn: &Connection, delivery_id: &str) -> Result<(), String> {
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "UPDATE receipt_deliveries
+         SET status = ?1, attempts = attempts + 1, sent_at = ?2,
+             next_retry_at = NULL, last_error = NULL, updated_at = ?2
+         WHERE id = ?3",
+        params![STATUS_SENT, now, delivery_id],
+    )
+    .map_err(|e| format!("mark receipt delivery sent: {e}"))?;
+
+    let (order_id, channel, destination): (String, String, String) = conn
+        .query_row(
+            "SELECT order_id, channel, destination FROM receipt_deliveries WHERE id = ?1",
+            params![delivery_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| format!("load receipt delivery: {e}"))?;
+    order_events::append(
+        conn,
+        &order_id,
+        order_events::RECEIPT_DELIVERED,
+        None,
+        serde_json::json!({
+            "deliveryId": delivery_id,
+            "channel": channel,
+            "destination": destination,
+        }),
+    );
+    Ok(())
+}
+
+/// Record a failed attempt: back off, or park as `failed` once
+/// [`MAX_ATTEMPTS`] is reached.
+pub fn mark_attempt_failed(
+    conn: &Connection,
+    delivery_id: &str,
+    error: &str,
+) -> Result<(), String> {
+    let attempts: i64 = conn
+        .query_row(
+            "SELECT attempts FROM receipt_deliveries WHERE id = ?1",
+            params![delivery_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("load receipt delivery: {e}"))?;
+    let attempts = attempts + 1;
+    let now = Utc::now();
+    let (status, next_retry_at) = if attempts >= MAX_ATTEMPTS {
+        (STATUS_FAILED, None)
+    } else {
+        let next = now + chrono::Duration::seconds(backoff_secs(attempts));
+        (STATUS_PENDING, Some(next.to_rfc3339()))
+    };
+    conn.execute(
+        "UPDATE receipt_deliveries
+         SET status = ?1, attempts = ?2, next_retry_at = ?3, last_error = ?4, updated_at = ?5
+         WHERE id = ?6",
+        params![
+            status,
+            attempts,
+            next_retry_at,
+            error,
+            now.to_rfc3339(),
+            delivery_id
+        ],
+    )
+    .map_err(|e| format!("mark receipt delivery failed: {e}"))?;
+    Ok(())
+}
+
+/// Pending deliveries whose backoff has elapsed, oldest first.
+pub fn due_delivery_ids(conn: &Connection, limit: usize) -> Result<Vec<String>, String> {
+    let now = Utc::now().to_rfc3339();
+    let mut stmt = conn
+        .prepare(
+            "SELECT id FROM receipt_deliveries
+             WHERE status = 'pending' AND (next_retry_at IS NULL OR next_retry_at <= ?1)
+             ORDER BY created_at ASC
+             LIMIT ?2",
+        )
+        .map_err(|e| format!("prepare due receipt deliveries: {e}"))?;
+    let rows = stmt
+        .query_map(params![now, limit as i64], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("query due receipt deliveries: {e}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read due receipt deliveries: {e}"))
+}
+
+/// POST one delivery to the admin API and record the outcome. Returns the
+/// updated row.
+pub async fn attempt_delivery(db: &DbState, delivery_id: &str) -> Result<Value, String> {
+    let payload: Value = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let raw: String = conn
+            .query_row(
+                "SELECT payload_json FROM receipt_deliveries WHERE id = ?1",
+                params![delivery_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("load receipt delivery payload: {e}"))?;
+        serde_json::from_str(&raw).map_err(|e| format!("parse receipt delivery payload: {e}"))?
+    };
+
+    let outcome = crate::admin_fetch(Some(db), RECEIPT_SEND_PATH, "POST", Some(payload)).await;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    match outcome {
+        Ok(_) => {
+            mark_sent(&conn, delivery_id)?;
+            info!(delivery_id = %delivery_id, "Receipt delivered");
+        }
+        Err(error) => {
+            mark_attempt_failed(&conn, delivery_id, &error)?;
+            warn!(delivery_id = %delivery_id, error = %error, "Receipt delivery failed");
+        }
+    }
+    get_delivery(&conn, delivery_id)?
+        .ok_or_else(|| format!("Receipt delivery not found: {delivery_id}"))
+}
+
+/// Retry due deliveries on a fixed cadence until cancelled.
+pub fn start_retry_worker(
+    db: Arc<DbState>,
+    interval_secs: u64,
+    cancel: tokio_util::sync::CancellationToken,
+) {
+    let cadence = Duration::from_secs(interval_secs.max(10));
+    tauri::async_runtime::spawn(async move {
+        info!(
+            interval_secs = cadence.as_secs(),
+            "Receipt delivery worker started"
+        );
+        loop {
+            let due = match db.conn.lock() {
+                Ok(conn) => due_delivery_ids(&conn, RETRY_BATCH_SIZE).unwrap_or_else(|error| {
+                    warn!(error = %error, "Receipt delivery worker query failed");
+                    Vec::new()
+                }),
+                Err(error) => {
+                    warn!(error = %error, "Receipt delivery worker could not lock db");
+                    Vec::new()
+                }
+            };
+            for delivery_id in due {
+                if let Err(error) = attempt_delivery(db.as_ref(), &delivery_id).await {
+                    warn!(delivery_id = %delivery_id, error = %error, "Receipt delivery retry failed");
+                }
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(cadence) => {}
+                _ = cancel.cancelled() => {
+                    info!("Receipt delivery worker cancelled");
+                    break;
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    fn test_db() -> DbState {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        db::run_migrations_for_test(&conn);
+        conn.execute(
+            "INSERT INTO orders (id, order_number, items, total_amount, total_amount_cents, subtotal, subtotal_cents, status, order_type, sync_status, created_at, updated_at)
+             VALUES ('ord-e', 'ORD-E', '[]', 10.0, 1000, 10.0, 1000, 'completed', 'pickup', 'pending', datetime('now'), datetime('now'))",
+            [],
+        )
+        .expect("insert order");
+        DbState::new(conn, PathBuf::from(":memory:"))
+    }
+
+    #[test]
+    fn parse_destination_validates_email_and_normalizes_phone() {
+        assert_eq!(
+            parse_destination(None, " Maria@Example.com ").unwrap(),
+            (Channel::Email, "maria@example.com".to_string())
+        );
+        assert_eq!(
+            parse_destination(Some("sms"), "+30 691-234-5678").unwrap(),
+            (Channel::Sms, "306912345678".to_string())
+        );
+        assert!(parse_destination(Some("email"), "maria@example").is_err());
+        assert!(parse_destination(None, "maria@@example.com").is_err());
+        assert!(parse_destination(Some("sms"), "12-34").is_err());
+        assert!(parse_destination(Some("fax"), "12345678").is_err());
+    }
+
+    #[test]
+    fn enqueue_is_idempotent_and_failures_back_off_until_parked() {
+        let db = test_db();
+        let (first, created) =
+            enqueue_delivery(&db, "ord-e", Channel::Email, "a@b.co", Some("staff-1")).unwrap();
+        assert!(created);
+        let id = first["id"].as_str().unwrap().to_string();
+        let (again, created_again) =
+            enqueue_delivery(&db, "ord-e", Channel::Email, "a@b.co", Some("staff-1")).unwrap();
+        assert!(!created_again);
+        assert_eq!(again["id"], first["id"]);
+
+        let conn = db.conn.lock().unwrap();
+        let payload: Value = conn
+            .query_row(
+                "SELECT payload_json FROM receipt_deliveries WHERE id = ?1",
+                params![id],
+                |row| row.get::<_, String>(0),
+            )
+            .map(|raw| serde_json::from_str(&raw).unwrap())
+            .unwrap();
+        assert_eq!(payload["receipt"]["order_number"], "ORD-E");
+        assert!(payload["html"].as_str().is_some());
+        assert_eq!(due_delivery_ids(&conn, 10).unwrap(), vec![id.clone()]);
+
+        mark_attempt_failed(&conn, &id, "offline").unwrap();
+        let delivery = get_delivery(&conn, &id).unwrap().unwrap();
+        assert_eq!(delivery["status"], STATUS_PENDING);
+        assert_eq!(delivery["attempts"], 1);
+        assert!(due_delivery_ids(&conn, 10).unwrap().is_empty());
+
+        for _ in 1..MAX_ATTEMPTS {
+            mark_attempt_failed(&conn, &id, "offline").unwrap();
+        }
+        let delivery = get_delivery(&conn, &id).unwrap().unwrap();
+        assert_eq!(delivery["status"], STATUS_FAILED);
+        assert!(delivery["nextRetryAt"].is_null());
+
+        let queued_events: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM order_events
+                 WHERE order_id = 'ord-e' AND event_type = 'receipt_delivery_queued'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(queued_events, 1);
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps_at_an_hour() {
+        assert_eq!(backoff_secs(1), 30);
+        assert_eq!(backoff_secs(2), 60);
+        assert_eq!(backoff_secs(4), 240);
+        assert_eq!(backoff_secs(12), MAX_BACKOFF_SECS);
+    }
+}
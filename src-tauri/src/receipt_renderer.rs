@@ -162,6 +162,9 @@ pub struct ReceiptItem {
     pub note: Option<String>,
     #[serde(default)]
     pub customizations: Vec<ReceiptCustomizationLine>,
+    /// Allergen keys; kitchen tickets print them under the item.
+    #[serde(default)]
+    pub allergy_flags: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -174,6 +177,16 @@ pub struct TotalsLine {
     pub discount_percent: Option<f64>,
 }
 
+/// One tax rate's share of the order, as captured at sale time.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TaxBreakdownLine {
+    pub label: String,
+    pub rate: f64,
+    pub net: f64,
+    pub tax: f64,
+    pub gross: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PaymentLine {
     pub label: String,
@@ -238,6 +251,12 @@ pub struct OrderReceiptDoc {
     pub payments: Vec<PaymentLine>,
     #[serde(default)]
     pub adjustments: Vec<AdjustmentLine>,
+    /// Per-rate net/tax/gross lines; empty when the order has no breakdown.
+    #[serde(default)]
+    pub tax_breakdown: Vec<TaxBreakdownLine>,
+    /// Fiscal receipt numbers (Greek series) covering this receipt.
+    #[serde(default)]
+    pub fiscal_document_numbers: Vec<String>,
     #[serde(default)]
     pub masked_card: Option<String>,
     #[serde(default)]
@@ -249,6 +268,20 @@ pub struct OrderReceiptDoc {
     /// Cancellation reason shown under the CANCELED banner.
     #[serde(default)]
     pub cancellation_reason: Option<String>,
+    /// 0 for the original print; n for the n-th duplicate of this receipt.
+    #[serde(default)]
+    pub reprint_count: u32,
+    /// When the duplicate was requested (RFC 3339), shown next to the marker.
+    #[serde(default)]
+    pub reprinted_at: Option<String>,
+    /// Merchant copy of a payment receipt: "MERCHANT COPY" marker and a
+    /// signature line.
+    #[serde(default)]
+    pub merchant_copy: bool,
+    /// Number of the order this return order gives money back on; `Some`
+    /// switches the receipt to the RETURN layout.
+    #[serde(default)]
+    pub return_of_order_number: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -279,6 +312,18 @@ pub struct KitchenTicketDoc {
     pub customer_name: Option<String>,
     #[serde(default)]
     pub customer_phone: Option<String>,
+    /// Replaces the "KITCHEN TICKET" title on course fire tickets.
+    #[serde(default)]
+    pub course_header: Option<String>,
+    /// Order-level note for the kitchen, printed emphasised.
+    #[serde(default)]
+    pub kitchen_notes: Option<String>,
+    /// Order-level allergen keys, printed emphasised above the items.
+    #[serde(default)]
+    pub allergy_flags: Vec<String>,
+    /// Allergy amendment: `items` holds only the newly flagged lines.
+    #[serde(default)]
+    pub allergy_update: bool,
     #[serde(default)]
     pub items: Vec<ReceiptItem>,
 }
@@ -320,6 +365,9 @@ pub struct ShiftCheckoutDoc {
     pub card_sales: f64,
     #[serde(default)]
     pub cash_drops: f64,
+    /// Net cash rounding taken into the drawer (`payments.cash_rounding`).
+    #[serde(default)]
+    pub cash_rounding: f64,
     #[serde(default)]
     pub driver_cash_given: f64,
     #[serde(default)]
@@ -425,6 +473,9 @@ pub struct ZReportDoc {
     pub expected_cash: f64,
     #[serde(default)]
     pub cash_drops: f64,
+    /// Net cash rounding taken into the drawer (`payments.cash_rounding`).
+    #[serde(default)]
+    pub cash_rounding: f64,
     #[serde(default)]
     pub driver_cash_given: f64,
     #[serde(default)]
@@ -550,6 +601,7 @@ pub fn receipt_label<'a>(lang: &str, key: &'a str) -> &'a str {
     match lang {
         "el" => match key {
             "Order" => "\u{03A0}\u{03B1}\u{03C1}\u{03B1}\u{03B3}\u{03B3}\u{03B5}\u{03BB}\u{03AF}\u{03B1}",
+            "Receipt No" => "\u{0391}\u{03C1}. \u{0391}\u{03C0}\u{03CC}\u{03B4}\u{03B5}\u{03B9}\u{03BE}\u{03B7}\u{03C2}",
             "Type" => "\u{03A4}\u{03CD}\u{03C0}\u{03BF}\u{03C2}",
             "Date" => "\u{0397}\u{03BC}/\u{03BD}\u{03AF}\u{03B1}",
             "Table" => "\u{03A4}\u{03C1}\u{03B1}\u{03C0}\u{03AD}\u{03B6}\u{03B9}",
@@ -570,13 +622,25 @@ pub fn receipt_label<'a>(lang: &str, key: &'a str) -> &'a str {
             "Tax" => "\u{03A6}\u{03A0}\u{0391}",
             "Delivery" => "\u{039C}\u{03B5}\u{03C4}\u{03B1}\u{03C6}\u{03BF}\u{03C1}\u{03B9}\u{03BA}\u{03AC}",
             "Tip" => "\u{03A6}\u{03B9}\u{03BB}\u{03BF}\u{03B4}\u{03CE}\u{03C1}\u{03B7}\u{03BC}\u{03B1}",
+            "Service charge" => "Χρέωση υπηρεσίας",
             "TOTAL" => "\u{03A3}\u{03A5}\u{039D}\u{039F}\u{039B}\u{039F}",
+            "Total" => "Σύνολο",
             "PAYMENT" => "\u{03A0}\u{039B}\u{0397}\u{03A1}\u{03A9}\u{039C}\u{0397}",
             "METHOD" => "\u{03A4}\u{03C1}\u{03CC}\u{03C0}\u{03BF}\u{03C2}",
             "Cash" => "\u{039C}\u{03B5}\u{03C4}\u{03C1}\u{03B7}\u{03C4}\u{03AC}",
             "Card" => "\u{039A}\u{03AC}\u{03C1}\u{03C4}\u{03B1}",
             "Received" => "\u{0395}\u{03B9}\u{03C3}\u{03C0}\u{03C1}\u{03AC}\u{03C7}\u{03B8}\u{03B7}\u{03BA}\u{03B5}",
             "Change" => "\u{03A1}\u{03AD}\u{03C3}\u{03C4}\u{03B1}",
+            "Rounding" => "\u{03A3}\u{03C4}\u{03C1}\u{03BF}\u{03B3}\u{03B3}\u{03C5}\u{03BB}\u{03BF}\u{03C0}\u{03BF}\u{03AF}\u{03B7}\u{03C3}\u{03B7}",
+            "Deposit received" => "\u{03A0}\u{03C1}\u{03BF}\u{03BA}\u{03B1}\u{03C4}\u{03B1}\u{03B2}\u{03BF}\u{03BB}\u{03AE}",
+            "Balance due" => "\u{03A5}\u{03C0}\u{03CC}\u{03BB}\u{03BF}\u{03B9}\u{03C0}\u{03BF}",
+            "DUPLICATE" => "\u{0391}\u{039D}\u{03A4}\u{0399}\u{0393}\u{03A1}\u{0391}\u{03A6}\u{039F}",
+            "MERCHANT COPY" => "ΑΝΤΙΓΡΑΦΟ ΕΜΠΟΡΟΥ",
+            "Signature" => "Υπογραφή",
+            "RETURN" => "ΕΠΙΣΤΡΟΦΗ",
+            "Original order" => "Αρχική παραγγελία",
+            "COMPLETED" => "ΟΛΟΚΛΗΡΩΘΗΚΕ",
+            "CANCELED" => "ΑΚΥΡΩΘΗΚΕ",
             "Other" => "\u{0386}\u{03BB}\u{03BB}\u{03BF}",
             "ADJUSTMENTS" => "\u{03A0}\u{03A1}\u{039F}\u{03A3}\u{0391}\u{03A1}\u{039C}\u{039F}\u{0393}\u{0395}\u{03A3}",
             "Void" => "\u{0391}\u{03BA}\u{03CD}\u{03C1}\u{03C9}\u{03C3}\u{03B7}",
@@ -647,6 +711,7 @@ pub fn receipt_label<'a>(lang: &str, key: &'a str) -> &'a str {
             "Drawer" => "Ταμείο",
             "Card Sales" => "Πωλήσεις Κάρτας",
             "Cash Drops" => "Αποσύρσεις Μετρητών",
+            "Cash Rounding" => "Στρογγυλοποίηση Μετρητών",
             "Driver Given" => "Δόθηκαν σε Οδηγό",
             "Driver Returned" => "Επιστράφηκαν από Οδηγό",
             "Transferred Staff" => "Μεταφερμένο Προσωπικό",
@@ -676,6 +741,7 @@ pub fn receipt_label<'a>(lang: &str, key: &'a str) -> &'a str {
         },
         "de" => match key {
             "Order" => "Bestellung",
+            "Receipt No" => "Beleg-Nr.",
             "Type" => "Typ",
             "Date" => "Datum",
             "Table" => "Tisch",
@@ -696,13 +762,23 @@ pub fn receipt_label<'a>(lang: &str, key: &'a str) -> &'a str {
             "Tax" => "MwSt",
             "Delivery" => "Lieferung",
             "Tip" => "Trinkgeld",
+            "Service charge" => "Servicegebuehr",
             "TOTAL" => "GESAMT",
+            "Total" => "Gesamt",
             "PAYMENT" => "ZAHLUNG",
             "METHOD" => "METHODE",
             "Cash" => "Bar",
             "Card" => "Karte",
             "Received" => "Erhalten",
             "Change" => "Wechselgeld",
+            "Rounding" => "Rundung",
+            "Deposit received" => "Anzahlung erhalten",
+            "Balance due" => "Restbetrag",
+            "DUPLICATE" => "DUPLIKAT",
+            "MERCHANT COPY" => "HÄNDLERBELEG",
+            "Signature" => "Unterschrift",
+            "RETURN" => "R\u{00DC}CKGABE",
+            "Original order" => "Urspr\u{00FC}ngliche Bestellung",
             "Other" => "Andere",
             "ADJUSTMENTS" => "KORREKTUREN",
             "Void" => "Storno",
@@ -772,6 +848,7 @@ pub fn receipt_label<'a>(lang: &str, key: &'a str) -> &'a str {
             "Drawer" => "Kasse",
             "Card Sales" => "Kartenumsatz",
             "Cash Drops" => "Barentnahmen",
+            "Cash Rounding" => "Barrundung",
             "Driver Given" => "Fahrer ausgezahlt",
             "Driver Returned" => "Vom Fahrer retour",
             "Transferred Staff" => "Übertragenes Personal",
@@ -801,6 +878,7 @@ pub fn receipt_label<'a>(lang: &str, key: &'a str) -> &'a str {
         },
         "fr" => match key {
             "Order" => "Commande",
+            "Receipt No" => "N\u{00B0} de re\u{00E7}u",
             "Type" => "Type",
             "Date" => "Date",
             "Table" => "Table",
@@ -821,6 +899,7 @@ pub fn receipt_label<'a>(lang: &str, key: &'a str) -> &'a str {
             "Tax" => "TVA",
             "Delivery" => "Livraison",
             "Tip" => "Pourboire",
+            "Service charge" => "Frais de service",
             "TOTAL" => "TOTAL",
             "PAYMENT" => "PAIEMENT",
             "METHOD" => "MODE",
@@ -828,6 +907,14 @@ pub fn receipt_label<'a>(lang: &str, key: &'a str) -> &'a str {
             "Card" => "Carte",
             "Received" => "Recu",
             "Change" => "Monnaie",
+            "Rounding" => "Arrondi",
+            "Deposit received" => "Acompte re\u{00E7}u",
+            "Balance due" => "Solde restant",
+            "DUPLICATE" => "DUPLICATA",
+            "MERCHANT COPY" => "COPIE COMMERÇANT",
+            "Signature" => "Signature",
+            "RETURN" => "RETOUR",
+            "Original order" => "Commande d'origine",
             "Other" => "Autre",
             "ADJUSTMENTS" => "AJUSTEMENTS",
             "Void" => "Annulation",
@@ -897,6 +984,7 @@ pub fn receipt_label<'a>(lang: &str, key: &'a str) -> &'a str {
             "Drawer" => "Caisse",
             "Card Sales" => "Ventes carte",
             "Cash Drops" => "Sorties especes",
+            "Cash Rounding" => "Arrondi especes",
             "Driver Given" => "Donne au livreur",
             "Driver Returned" => "Rendu par livreur",
             "Transferred Staff" => "Personnel transfere",
@@ -926,6 +1014,7 @@ pub fn receipt_label<'a>(lang: &str, key: &'a str) -> &'a str {
         },
         "it" => match key {
             "Order" => "Ordine",
+            "Receipt No" => "N. ricevuta",
             "Type" => "Tipo",
             "Date" => "Data",
             "Table" => "Tavolo",
@@ -946,13 +1035,23 @@ pub fn receipt_label<'a>(lang: &str, key: &'a str) -> &'a str {
             "Tax" => "IVA",
             "Delivery" => "Consegna",
             "Tip" => "Mancia",
+            "Service charge" => "Costo del servizio",
             "TOTAL" => "TOTALE",
+            "Total" => "Totale",
             "PAYMENT" => "PAGAMENTO",
             "METHOD" => "METODO",
             "Cash" => "Contanti",
             "Card" => "Carta",
             "Received" => "Ricevuto",
             "Change" => "Resto",
+            "Rounding" => "Arrotondamento",
+            "Deposit received" => "Acconto ricevuto",
+            "Balance due" => "Saldo residuo",
+            "DUPLICATE" => "DUPLICATO",
+            "MERCHANT COPY" => "COPIA ESERCENTE",
+            "Signature" => "Firma",
+            "RETURN" => "RESO",
+            "Original order" => "Ordine originale",
             "Other" => "Altro",
             "ADJUSTMENTS" => "RETTIFICHE",
             "Void" => "Annullamento",
@@ -1022,6 +1121,7 @@ pub fn receipt_label<'a>(lang: &str, key: &'a str) -> &'a str {
             "Drawer" => "Cassa",
             "Card Sales" => "Vendite carta",
             "Cash Drops" => "Prelievi contanti",
+            "Cash Rounding" => "Arrotondamento contanti",
             "Driver Given" => "Dato al corriere",
             "Driver Returned" => "Reso dal corriere",
             "Transferred Staff" => "Personale trasferito",
@@ -1145,6 +1245,16 @@ fn z_report_shift_line(doc: &ZReportDoc, lang: &str) -> Option<(String, String)>
         .map(|count| (receipt_label(lang, "Shifts").to_string(), count.to_string()))
 }
 
+/// Kitchen ticket title: the course fire header when set, otherwise the
+/// translated "KITCHEN TICKET".
+fn kitchen_ticket_title<'a>(doc: &'a KitchenTicketDoc, lang: &str) -> &'a str {
+    doc.course_header
+        .as_deref()
+        .map(str::trim)
+        .filter(|header| !header.is_empty())
+        .unwrap_or_else(|| receipt_label(lang, "KITCHEN TICKET"))
+}
+
 /// Translate an order type string (e.g. "pickup", "delivery", "dine_in", "takeaway")
 /// to the configured receipt language. Returns an owned uppercase string.
 fn translate_order_type(lang: &str, order_type: &str) -> String {
@@ -1194,6 +1304,8 @@ pub struct EscPosRender {
     pub bytes: Vec<u8>,
     pub warnings: Vec<RenderWarning>,
     pub body_mode: EscPosBodyMode,
+    /// Plain-text copy of the printed lines. Empty for raster-exact bodies.
+    pub text: String,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -1202,7 +1314,26 @@ pub enum EscPosBodyMode {
     RasterExact,
 }
 
-fn esc(input: &str) -> String {
+impl EscPosBodyMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::RasterExact => "raster_exact",
+        }
+    }
+}
+
+/// On-screen preview of a document: the fixed-width text the printer gets
+/// and the HTML rendering, both from the print path's renderers.
+#[derive(Debug, Clone)]
+pub struct ReceiptPreview {
+    pub text: String,
+    pub html: String,
+    pub body_mode: EscPosBodyMode,
+    pub warnings: Vec<RenderWarning>,
+}
+
+pub(crate) fn esc(input: &str) -> String {
     input
         .replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -1267,6 +1398,12 @@ fn money_with_currency_locale(value: f64, symbol: &str, comma: bool) -> String {
     }
 }
 
+/// Drawer line that can go either way, e.g. cash rounding: `+0.02` / `-0.03`.
+fn signed_amount(value: f64, format: impl Fn(f64) -> String) -> String {
+    let sign = if value < 0.0 { "-" } else { "+" };
+    format!("{sign}{}", format(value.abs()))
+}
+
 fn is_known_euro_character_set(character_set: &str) -> bool {
     matches!(
         character_set.trim().to_ascii_uppercase().as_str(),
@@ -1641,6 +1778,13 @@ fn order_note_lines(doc: &OrderReceiptDoc) -> Vec<String> {
     lines
 }
 
+fn kitchen_notes_text(doc: &KitchenTicketDoc) -> Option<&str> {
+    doc.kitchen_notes
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
 fn kitchen_order_note_lines(doc: &KitchenTicketDoc) -> Vec<String> {
     let mut lines: Vec<String> = Vec::new();
     push_unique_line(&mut lines, doc.delivery_notes.as_deref());
@@ -1670,9 +1814,41 @@ fn format_discount_percent(percent: f64) -> String {
     }
 }
 
+/// Label/amount rows for the per-rate tax breakdown: net, tax and gross for
+/// each rate, e.g. `Net 13% Food`.
+fn tax_breakdown_rows(lang: &str, doc: &OrderReceiptDoc) -> Vec<(String, f64)> {
+    let mut rows = Vec::new();
+    for line in &doc.tax_breakdown {
+        let rate = format_discount_percent(line.rate);
+        let suffix = if line.label.trim().is_empty() {
+            rate
+        } else {
+            format!("{rate} {}", line.label.trim())
+        };
+        for (key, amount) in [("Net", line.net), ("Tax", line.tax), ("Gross", line.gross)] {
+            rows.push((format!("{} {suffix}", receipt_label(lang, key)), amount));
+        }
+    }
+    rows
+}
+
+fn fiscal_number_rows(lang: &str, doc: &OrderReceiptDoc) -> Vec<(String, String)> {
+    doc.fiscal_document_numbers
+        .iter()
+        .map(|number| {
+            (
+                receipt_label(lang, "Receipt No").to_string(),
+                number.clone(),
+            )
+        })
+        .collect()
+}
+
 fn total_label_text(lang: &str, total: &TotalsLine) -> String {
     let base = receipt_label(lang, &total.label);
-    if total.label.eq_ignore_ascii_case("discount") {
+    if total.label.eq_ignore_ascii_case("discount")
+        || total.label.eq_ignore_ascii_case("service charge")
+    {
         if let Some(percent) = total.discount_percent.filter(|value| *value > 0.0) {
             return format!("{base} ({})", format_discount_percent(percent));
         }
@@ -1695,7 +1871,10 @@ fn customization_display(
     }
     if include_price {
         if let Some(price) = customization.price.filter(|value| *value > 0.0) {
-            line.push_str(&format!(" (+{})", money(price)));
+            line.push_str(&format!(
+                " (+{})",
+                money_locale(price, language_uses_decimal_comma(lang))
+            ));
         }
     }
     line
@@ -2037,7 +2216,7 @@ fn wrap(text: &str, width: usize) -> Vec<String> {
     out
 }
 
-fn apply_character_set(
+pub(crate) fn apply_character_set(
     builder: &mut EscPosBuilder,
     character_set: &str,
     greek_render_mode: Option<&str>,
@@ -2158,6 +2337,38 @@ pub fn language_to_character_set(language: &str) -> &'static str {
     }
 }
 
+/// Two-letter receipt language for a language setting such as `el-GR` or
+/// `en_US`. Empty values fall back to English.
+pub fn normalize_receipt_language(raw: &str) -> String {
+    let lang = raw
+        .trim()
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if lang.is_empty() {
+        "en".to_string()
+    } else {
+        lang
+    }
+}
+
+/// Languages whose receipts print amounts as `12,50`.
+pub fn language_uses_decimal_comma(language: &str) -> bool {
+    matches!(language, "el" | "de" | "fr" | "it" | "es" | "pt" | "nl")
+}
+
+/// Switch a resolved layout to a per-job language. The character set only
+/// follows when the profile left it at the PC437 default.
+pub fn apply_language_override(cfg: &mut LayoutConfig, language: &str) {
+    let language = normalize_receipt_language(language);
+    if cfg.character_set == "PC437_USA" && language != "en" {
+        cfg.character_set = language_to_character_set(&language).to_string();
+    }
+    cfg.decimal_comma = language_uses_decimal_comma(&language);
+    cfg.language = language;
+}
+
 /// Resolve the ESC/POS code page number for a (brand, character_set) pair.
 ///
 /// Different printer brands assign different numbers to the same code page
@@ -2358,12 +2569,15 @@ body {{ background: #2a2a2a; display: flex; justify-content: center; padding: 32
 .line strong {{ font-size: 11px; }}
 .section {{ margin-top: 8px; border-top: 1px dashed #999; padding-top: 6px; }}
 .note {{ color: #666; font-size: 9px; }}
+.allergy {{ background: #000; color: #fff; font-size: 13px; font-weight: 700; padding: 2px 4px; margin: 2px 0; }}
 .center {{ text-align: center; }}
 
 /* Status banner (completed / canceled receipts) */
 .status-banner {{ text-align: center; padding: 6px 0; margin-bottom: 10px; font-weight: 700; font-size: 13px; letter-spacing: 1px; border-radius: 4px; }}
 .status-banner.completed {{ background: #e6f4ea; color: #1a7a34; border: 1px solid #a8d5b5; }}
 .status-banner.canceled {{ background: #fce8e8; color: #b00020; border: 1px solid #f5b8b8; }}
+.status-banner.duplicate {{ background: #fff; color: #000; border: 2px dashed #000; }}
+.status-banner.return {{ background: #fff4e5; color: #8a4b00; border: 1px solid #f0c48a; }}
 .status-banner .cancel-reason {{ font-weight: 400; font-size: 10px; margin-top: 3px; }}
 </style>
 </head>
@@ -2423,6 +2637,127 @@ fn build_status_banner_html(doc: &OrderReceiptDoc) -> String {
     format!("<div class=\"status-banner {css_class}\"><div>{label}</div>{reason_html}</div>")
 }
 
+/// "DUPLICATE #n  DD/MM/YYYY HH:MM" for reprinted order receipts; `None` for
+/// the original print.
+pub fn reprint_marker_line(doc: &OrderReceiptDoc, lang: &str) -> Option<String> {
+    if doc.reprint_count == 0 {
+        return None;
+    }
+    let mut line = format!(
+        "{} #{}",
+        receipt_label(lang, "DUPLICATE"),
+        doc.reprint_count
+    );
+    if let Some(at) = doc.reprinted_at.as_deref().filter(|at| !at.is_empty()) {
+        line.push_str("  ");
+        line.push_str(&format_datetime_human(at));
+    }
+    Some(line)
+}
+
+/// "MERCHANT COPY" for the merchant copy of a payment receipt.
+pub fn merchant_copy_marker_line(doc: &OrderReceiptDoc, lang: &str) -> Option<String> {
+    doc.merchant_copy
+        .then(|| receipt_label(lang, "MERCHANT COPY").to_string())
+}
+
+/// "Signature: ____" printed above the footer of a merchant copy, `width`
+/// characters wide.
+fn merchant_signature_line(doc: &OrderReceiptDoc, lang: &str, width: usize) -> Option<String> {
+    if !doc.merchant_copy {
+        return None;
+    }
+    let label = format!("{}: ", receipt_label(lang, "Signature"));
+    let rule = width.saturating_sub(label.chars().count()).max(8);
+    Some(format!("{label}{}", "_".repeat(rule)))
+}
+
+/// "RETURN  Original order #ORD-…" for return orders; `None` otherwise.
+pub fn return_marker_line(doc: &OrderReceiptDoc, lang: &str) -> Option<String> {
+    let original = doc
+        .return_of_order_number
+        .as_deref()
+        .map(str::trim)
+        .filter(|number| !number.is_empty())?;
+    Some(format!(
+        "{}  {} #{}",
+        receipt_label(lang, "RETURN"),
+        receipt_label(lang, "Original order"),
+        original
+    ))
+}
+
+/// Replace the layout copy label with the merchant-copy, return and
+/// duplicate markers so the ESC/POS and raster paths print them under the
+/// store header.
+pub fn apply_reprint_marker(cfg: &mut LayoutConfig, document: &ReceiptDocument) {
+    if let ReceiptDocument::OrderReceipt(doc) = document {
+        let markers = [
+            merchant_copy_marker_line(doc, &cfg.language),
+            return_marker_line(doc, &cfg.language),
+            reprint_marker_line(doc, &cfg.language),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        if !markers.is_empty() {
+            cfg.copy_label = Some(markers.join(" / "));
+        }
+    }
+}
+
+/// Printed in place of the copy label on everything rendered in training
+/// mode.
+pub const TRAINING_MARKER: &str = "TRAINING — NOT A RECEIPT";
+
+/// Stamp `cfg` with [`TRAINING_MARKER`] when the document comes from the
+/// training database. Runs after every other copy-label rule so nothing can
+/// hide the stamp.
+pub fn apply_training_marker(cfg: &mut LayoutConfig, training: bool) {
+    if training {
+        cfg.copy_label = Some(TRAINING_MARKER.to_string());
+    }
+}
+
+fn build_return_banner_html(doc: &OrderReceiptDoc, lang: &str) -> String {
+    let Some(original) = doc
+        .return_of_order_number
+        .as_deref()
+        .map(str::trim)
+        .filter(|number| !number.is_empty())
+    else {
+        return String::new();
+    };
+    format!(
+        "<div class=\"status-banner return\"><div>{}</div><div class=\"cancel-reason\">{} #{}</div></div>",
+        esc(receipt_label(lang, "RETURN")),
+        esc(receipt_label(lang, "Original order")),
+        esc(original)
+    )
+}
+
+fn build_duplicate_banner_html(doc: &OrderReceiptDoc, lang: &str) -> String {
+    reprint_marker_line(doc, lang)
+        .map(|line| {
+            format!(
+                "<div class=\"status-banner duplicate\"><div>{}</div></div>",
+                esc(&line)
+            )
+        })
+        .unwrap_or_default()
+}
+
+fn build_merchant_copy_banner_html(doc: &OrderReceiptDoc, lang: &str) -> String {
+    merchant_copy_marker_line(doc, lang)
+        .map(|line| {
+            format!(
+                "<div class=\"status-banner duplicate\"><div>{}</div></div>",
+                esc(&line)
+            )
+        })
+        .unwrap_or_default()
+}
+
 pub fn render_html(document: &ReceiptDocument, cfg: &LayoutConfig) -> String {
     let is_modern = cfg.template == ReceiptTemplate::Modern;
     let lang = cfg.language.as_str();
@@ -2436,6 +2771,9 @@ pub fn render_html(document: &ReceiptDocument, cfg: &LayoutConfig) -> String {
             let mut body = String::new();
             let banner = build_status_banner_html(doc);
             body.push_str(&banner);
+            body.push_str(&build_return_banner_html(doc, lang));
+            body.push_str(&build_merchant_copy_banner_html(doc, lang));
+            body.push_str(&build_duplicate_banner_html(doc, lang));
             append_html_header_block(&mut body, cfg, lang, cfg.show_logo);
 
             if is_modern {
@@ -2592,6 +2930,25 @@ pub fn render_html(document: &ReceiptDocument, cfg: &LayoutConfig) -> String {
                     }
                 }
                 body.push_str("</table>");
+                let tax_rows = tax_breakdown_rows(lang, doc);
+                if !tax_rows.is_empty() {
+                    body.push_str("<hr class=\"thin\"><table>");
+                    for (label, amount) in &tax_rows {
+                        body.push_str(&format!(
+                            "<tr><td class=\"dim\">{}</td><td class=\"r\">{}</td></tr>",
+                            esc(label),
+                            money(*amount)
+                        ));
+                    }
+                    body.push_str("</table>");
+                }
+                for (label, number) in fiscal_number_rows(lang, doc) {
+                    body.push_str(&format!(
+                        "<div class=\"center\">{}: {}</div>",
+                        esc(&label),
+                        esc(&number)
+                    ));
+                }
 
                 // Payments
                 body.push_str("<hr class=\"thin\">");
@@ -2813,6 +3170,20 @@ pub fn render_html(document: &ReceiptDocument, cfg: &LayoutConfig) -> String {
                         ));
                     }
                 }
+                for (label, amount) in tax_breakdown_rows(lang, doc) {
+                    body.push_str(&format!(
+                        "<tr><td class=\"dim\">{}</td><td class=\"r\">{}</td></tr>",
+                        esc(&label),
+                        money(amount)
+                    ));
+                }
+                for (label, number) in fiscal_number_rows(lang, doc) {
+                    body.push_str(&format!(
+                        "<tr><td class=\"dim\">{}</td><td class=\"r\">{}</td></tr>",
+                        esc(&label),
+                        esc(&number)
+                    ));
+                }
                 body.push_str("</table>");
 
                 // Payments
@@ -2901,6 +3272,13 @@ pub fn render_html(document: &ReceiptDocument, cfg: &LayoutConfig) -> String {
                 }
             }
 
+            if let Some(signature) = merchant_signature_line(doc, lang, 32) {
+                body.push_str(&format!(
+                    "<div style=\"margin-top:24px\">{}</div>",
+                    esc(&signature)
+                ));
+            }
+
             // Footer
             let footer = cfg.footer_text.as_deref().unwrap_or("Thank you");
             let translated_footer = receipt_label(lang, footer);
@@ -2918,7 +3296,7 @@ pub fn render_html(document: &ReceiptDocument, cfg: &LayoutConfig) -> String {
             // Title
             body.push_str(&format!(
                 "<div class=\"center\"><strong>{}</strong></div>",
-                esc(receipt_label(lang, "KITCHEN TICKET"))
+                esc(kitchen_ticket_title(doc, lang))
             ));
             // Order info
             body.push_str(&format!(
@@ -3055,6 +3433,16 @@ pub fn render_html(document: &ReceiptDocument, cfg: &LayoutConfig) -> String {
                 }
                 body.push_str("</div>");
             }
+            if let Some(label) = crate::allergens::ticket_label(&doc.allergy_flags) {
+                body.push_str(&format!("<div class=\"allergy\">{}</div>", esc(&label)));
+            }
+            if let Some(note) = kitchen_notes_text(doc) {
+                body.push_str(&format!(
+                    "<div class=\"section\"><strong>{}: {}</strong></div>",
+                    esc(receipt_label(lang, "Note")),
+                    esc(note)
+                ));
+            }
             let order_notes = kitchen_order_note_lines(doc);
             if !order_notes.is_empty() {
                 body.push_str("<div class=\"section\">");
@@ -3097,6 +3485,9 @@ pub fn render_html(document: &ReceiptDocument, cfg: &LayoutConfig) -> String {
                         qty(item.quantity),
                         esc(&item.name)
                     ));
+                    if let Some(label) = crate::allergens::ticket_label(&item.allergy_flags) {
+                        body.push_str(&format!("<div class=\"allergy\">{}</div>", esc(&label)));
+                    }
                     append_customizations_html(&mut body, item, lang);
                     if let Some(note) = item
                         .note
@@ -3109,7 +3500,7 @@ pub fn render_html(document: &ReceiptDocument, cfg: &LayoutConfig) -> String {
                 }
             }
             body.push_str("</div>");
-            html_shell(receipt_label(lang, "KITCHEN TICKET"), &body, cfg)
+            html_shell(kitchen_ticket_title(doc, lang), &body, cfg)
         }
         ReceiptDocument::DeliverySlip(doc) => {
             let lang = cfg.language.as_str();
@@ -3369,6 +3760,13 @@ pub fn render_html(document: &ReceiptDocument, cfg: &LayoutConfig) -> String {
                         esc(receipt_label(lang, "Expenses")),
                         money(doc.total_expenses),
                     ));
+                    if doc.cash_rounding != 0.0 {
+                        body.push_str(&format!(
+                            "<div class=\"line\"><span>{}</span><span>{}</span></div>",
+                            esc(receipt_label(lang, "Cash Rounding")),
+                            signed_amount(doc.cash_rounding, money),
+                        ));
+                    }
                     if doc.cash_drops > 0.0 {
                         body.push_str(&format!(
                             "<div class=\"line\"><span>{}</span><span>-{}</span></div>",
@@ -3752,6 +4150,13 @@ pub fn render_html(document: &ReceiptDocument, cfg: &LayoutConfig) -> String {
                     money(doc.expenses_total),
                 ));
             }
+            if doc.cash_rounding != 0.0 {
+                body.push_str(&format!(
+                    "<div class=\"line\"><span>{}</span><span>{}</span></div>",
+                    esc(receipt_label(lang, "Cash Rounding")),
+                    signed_amount(doc.cash_rounding, money),
+                ));
+            }
             if doc.cash_drops > 0.0 {
                 body.push_str(&format!(
                     "<div class=\"line\"><span>{}</span><span>-{}</span></div>",
@@ -3935,6 +4340,24 @@ fn emit_wrapped(builder: &mut EscPosBuilder, text: &str, width: usize) {
     }
 }
 
+/// Allergy warning: bold, double-height and inverted so it stands out from
+/// the item lines around it.
+fn emit_allergy_alert(builder: &mut EscPosBuilder, text: &str, width: usize, star: bool) {
+    builder.bold(true).double_height();
+    if star {
+        builder.star_reverse(true);
+    } else {
+        builder.reverse(true);
+    }
+    emit_wrapped(builder, text, width);
+    if star {
+        builder.star_reverse(false);
+    } else {
+        builder.reverse(false);
+    }
+    builder.normal_size().bold(false);
+}
+
 fn wrap_centered_header(text: &str, width: usize) -> Vec<String> {
     let value = text.trim();
     if value.is_empty() {
@@ -5068,6 +5491,16 @@ impl TtfReceiptComposer {
         self.y += banner_h + self.preset.small_gap;
     }
 
+    /// Reverse banner wrapped to the content width, one bar per line.
+    fn draw_reverse_wrapped(&mut self, text: &str) {
+        let style = self.preset.banner_style;
+        let max_width = self.content_width - self.preset.banner_padding_y * 2;
+        let lines = wrap_pixels(text, max_width, |line| self.text_width(line, style));
+        for line in lines {
+            self.draw_reverse_banner(&line);
+        }
+    }
+
     fn add_gap(&mut self, dots: i32) {
         self.y += dots.max(0);
     }
@@ -5889,6 +6322,12 @@ fn render_classic_customer_raster_exact_ttf(
         );
         canvas.draw_rule();
     }
+    for (label, amount) in tax_breakdown_rows(lang, doc) {
+        canvas.draw_pair(&label, &money_locale(amount, comma), preset.subtotal_style);
+    }
+    for (label, number) in fiscal_number_rows(lang, doc) {
+        canvas.draw_pair(&label, &number, preset.subtotal_style);
+    }
 
     if let Some(method_label) = method_only_payment_label(doc, lang) {
         canvas.add_gap(preset.small_gap);
@@ -5915,6 +6354,12 @@ fn render_classic_customer_raster_exact_ttf(
         }
     }
 
+    let signature_width = canvas.stars_for_width(preset.payment_style);
+    if let Some(signature) = merchant_signature_line(doc, lang, signature_width) {
+        canvas.add_gap(preset.medium_gap);
+        canvas.draw_text_line(&signature, BitmapAlign::Left, preset.payment_style);
+    }
+
     if let Some(footer) = cfg
         .footer_text
         .as_deref()
@@ -6179,6 +6624,17 @@ fn render_classic_customer_raster_exact_bitmap(
             );
         }
     }
+    for (label, amount) in tax_breakdown_rows(lang, doc) {
+        canvas.draw_pair_body(
+            &label,
+            &money_locale(amount, comma),
+            false,
+            canvas.normal_scale,
+        );
+    }
+    for (label, number) in fiscal_number_rows(lang, doc) {
+        canvas.draw_pair_body(&label, &number, false, canvas.normal_scale);
+    }
 
     if let Some(method_label) = method_only_payment_label(doc, lang) {
         canvas.add_spacer(1);
@@ -6222,6 +6678,11 @@ fn render_classic_customer_raster_exact_bitmap(
         }
     }
 
+    if let Some(signature) = merchant_signature_line(doc, lang, 24) {
+        canvas.add_spacer(1);
+        canvas.draw_text_line(&signature, BitmapAlign::Left, false, canvas.normal_scale, 0);
+    }
+
     if let Some(footer) = cfg
         .footer_text
         .as_deref()
@@ -6406,7 +6867,7 @@ fn render_classic_non_customer_raster_exact_ttf(
 
     match document {
         ReceiptDocument::KitchenTicket(doc) => {
-            let title = receipt_label(lang, "KITCHEN TICKET").to_uppercase();
+            let title = kitchen_ticket_title(doc, lang).to_uppercase();
             canvas.draw_reverse_banner(&title);
             let order_type_display = translate_order_type(lang, &doc.order_type);
             canvas.draw_text_line(
@@ -6460,6 +6921,16 @@ fn render_classic_non_customer_raster_exact_ttf(
                     preset.meta_style,
                 );
             }
+            if let Some(label) = crate::allergens::ticket_label(&doc.allergy_flags) {
+                canvas.draw_reverse_wrapped(&label);
+            }
+            if let Some(note) = kitchen_notes_text(doc) {
+                canvas.draw_wrapped(
+                    &format!("{}: {note}", receipt_label(lang, "Note")),
+                    BitmapAlign::Left,
+                    preset.total_style,
+                );
+            }
             canvas.draw_rule();
             canvas.draw_text_line(
                 &receipt_label(lang, "ITEMS").to_uppercase(),
@@ -6487,6 +6958,9 @@ fn render_classic_non_customer_raster_exact_ttf(
                         &money_locale(item.total, comma),
                         preset.item_style,
                     );
+                    if let Some(label) = crate::allergens::ticket_label(&item.allergy_flags) {
+                        canvas.draw_reverse_wrapped(&label);
+                    }
                     let (with_items, without_items) = split_customizations(item);
                     for customization in with_items {
                         canvas.draw_wrapped(
@@ -6633,6 +7107,15 @@ fn render_classic_non_customer_raster_exact_ttf(
                         ),
                         preset.item_style,
                     );
+                    if doc.cash_rounding != 0.0 {
+                        canvas.draw_pair(
+                            &format!("{}:", receipt_label(lang, "Cash Rounding")),
+                            &signed_amount(doc.cash_rounding, |amount| {
+                                money_with_currency_locale(amount, &cur, comma)
+                            }),
+                            preset.item_style,
+                        );
+                    }
                     if doc.cash_drops > 0.0 {
                         canvas.draw_pair(
                             &format!("{}:", receipt_label(lang, "Cash Drops")),
@@ -7062,6 +7545,15 @@ fn render_classic_non_customer_raster_exact_ttf(
                     preset.item_style,
                 );
             }
+            if doc.cash_rounding != 0.0 {
+                canvas.draw_pair(
+                    &format!("{}:", receipt_label(lang, "Cash Rounding")),
+                    &signed_amount(doc.cash_rounding, |amount| {
+                        money_with_currency_locale(amount, &cur, comma)
+                    }),
+                    preset.item_style,
+                );
+            }
             if doc.cash_drops > 0.0 {
                 canvas.draw_pair(
                     &format!("{}:", receipt_label(lang, "Cash Drops")),
@@ -7221,6 +7713,7 @@ pub fn render_escpos(document: &ReceiptDocument, cfg: &LayoutConfig) -> EscPosRe
                     bytes,
                     warnings,
                     body_mode: EscPosBodyMode::RasterExact,
+                    text: String::new(),
                 };
             }
             Err(err) => warnings.push(RenderWarning {
@@ -7692,6 +8185,12 @@ pub fn render_escpos(document: &ReceiptDocument, cfg: &LayoutConfig) -> EscPosRe
                     }
                 }
             }
+            for (label, amount) in tax_breakdown_rows(lang, doc) {
+                emit_pair(&mut builder, &label, &money_locale(amount, comma), width);
+            }
+            for (label, number) in fiscal_number_rows(lang, doc) {
+                emit_pair(&mut builder, &label, &number, width);
+            }
             if style.modern {
                 // Modern: dash rule separator before payments
                 emit_rule(&mut builder, width, '-');
@@ -7787,7 +8286,7 @@ pub fn render_escpos(document: &ReceiptDocument, cfg: &LayoutConfig) -> EscPosRe
             }
         }
         ReceiptDocument::KitchenTicket(doc) => {
-            let title = receipt_label(lang, "KITCHEN TICKET");
+            let title = kitchen_ticket_title(doc, lang);
             let display_date = format_datetime_human(&doc.created_at);
             let order_type_display = translate_order_type(lang, &doc.order_type);
             if style.modern {
@@ -7953,6 +8452,18 @@ pub fn render_escpos(document: &ReceiptDocument, cfg: &LayoutConfig) -> EscPosRe
                     );
                 }
             }
+            if let Some(label) = crate::allergens::ticket_label(&doc.allergy_flags) {
+                emit_allergy_alert(&mut builder, &label, width, use_star_commands);
+            }
+            if let Some(note) = kitchen_notes_text(doc) {
+                builder.bold(true);
+                emit_wrapped(
+                    &mut builder,
+                    &format!("{}: {note}", receipt_label(lang, "Note")),
+                    width,
+                );
+                builder.bold(false);
+            }
             emit_section_header(&mut builder, receipt_label(lang, "ITEMS"), style, width);
             if doc.items.is_empty() {
                 builder.text(receipt_label(lang, "No items")).lf();
@@ -7969,6 +8480,9 @@ pub fn render_escpos(document: &ReceiptDocument, cfg: &LayoutConfig) -> EscPosRe
                         width,
                         style,
                     );
+                    if let Some(label) = crate::allergens::ticket_label(&item.allergy_flags) {
+                        emit_allergy_alert(&mut builder, &label, width, use_star_commands);
+                    }
                     emit_item_customizations_escpos(&mut builder, item, width, lang);
                     if let Some(note) = item
                         .note
@@ -8130,6 +8644,12 @@ pub fn render_escpos(document: &ReceiptDocument, cfg: &LayoutConfig) -> EscPosRe
                     emit_pair(&mut builder, &label_text, &val, width);
                 }
             }
+            for (label, amount) in tax_breakdown_rows(lang, doc) {
+                emit_pair(&mut builder, &label, &money_locale(amount, comma), width);
+            }
+            for (label, number) in fiscal_number_rows(lang, doc) {
+                emit_pair(&mut builder, &label, &number, width);
+            }
             let delivery_method_only_payment = method_only_payment_label(doc, lang);
             if delivery_method_only_payment.is_some()
                 || !doc.payments.is_empty()
@@ -8313,6 +8833,14 @@ pub fn render_escpos(document: &ReceiptDocument, cfg: &LayoutConfig) -> EscPosRe
                         &format!("-{}", money_locale(doc.total_expenses, comma)),
                         width,
                     );
+                    if doc.cash_rounding != 0.0 {
+                        emit_pair(
+                            &mut builder,
+                            receipt_label(lang, "Cash Rounding"),
+                            &signed_amount(doc.cash_rounding, |amount| money_locale(amount, comma)),
+                            width,
+                        );
+                    }
                     if doc.cash_drops > 0.0 {
                         emit_pair(
                             &mut builder,
@@ -8807,6 +9335,14 @@ pub fn render_escpos(document: &ReceiptDocument, cfg: &LayoutConfig) -> EscPosRe
                     width,
                 );
             }
+            if doc.cash_rounding != 0.0 {
+                emit_pair(
+                    &mut builder,
+                    receipt_label(lang, "Cash Rounding"),
+                    &signed_amount(doc.cash_rounding, |amount| money_locale(amount, comma)),
+                    width,
+                );
+            }
             if doc.cash_drops > 0.0 {
                 emit_pair(
                     &mut builder,
@@ -8892,6 +9428,12 @@ pub fn render_escpos(document: &ReceiptDocument, cfg: &LayoutConfig) -> EscPosRe
         }
     }
 
+    if let ReceiptDocument::OrderReceipt(doc) = document {
+        if let Some(signature) = merchant_signature_line(doc, lang, width) {
+            builder.left().lf().lf().text(&signature).lf();
+        }
+    }
+
     if let Some(footer) = cfg
         .footer_text
         .as_deref()
@@ -8942,12 +9484,37 @@ pub fn render_escpos(document: &ReceiptDocument, cfg: &LayoutConfig) -> EscPosRe
     }
 
     EscPosRender {
+        text: builder.plain_text(),
         bytes: builder.build(),
         warnings,
         body_mode: EscPosBodyMode::Text,
     }
 }
 
+/// Render `document` for preview without printing anything. The text is
+/// taken from [`render_escpos`], so it matches the paper line for line. A
+/// raster-exact body has no text form; its text falls back to the
+/// text-mode layout of the same template.
+pub fn render_preview(document: &ReceiptDocument, cfg: &LayoutConfig) -> ReceiptPreview {
+    let rendered = render_escpos(document, cfg);
+    let text = match rendered.body_mode {
+        EscPosBodyMode::Text => rendered.text,
+        EscPosBodyMode::RasterExact => {
+            let text_cfg = LayoutConfig {
+                classic_customer_render_mode: ClassicCustomerRenderMode::Text,
+                ..cfg.clone()
+            };
+            render_escpos(document, &text_cfg).text
+        }
+    };
+    ReceiptPreview {
+        text,
+        html: render_html(document, cfg),
+        body_mode: rendered.body_mode,
+        warnings: rendered.warnings,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -9646,6 +10213,17 @@ mod tests {
         };
         assert_eq!(total_label_text("en", &line), "Discount (10%)");
         assert_eq!(total_label_text("el", &line), "Έκπτωση (10%)");
+
+        let service_charge = TotalsLine {
+            label: "Service charge".to_string(),
+            amount: 2.5,
+            emphasize: false,
+            discount_percent: Some(12.5),
+        };
+        assert_eq!(
+            total_label_text("en", &service_charge),
+            "Service charge (12.5%)"
+        );
     }
 
     #[test]
@@ -10121,6 +10699,22 @@ mod tests {
         assert!(text.contains("KITCHEN TICKET"));
     }
 
+    #[test]
+    fn course_header_replaces_kitchen_ticket_title() {
+        let doc = ReceiptDocument::KitchenTicket(KitchenTicketDoc {
+            order_number: "KT-COURSE-1".to_string(),
+            order_type: "dine-in".to_string(),
+            created_at: "2026-02-24T10:00:00Z".to_string(),
+            course_header: Some("FIRE: MAINS".to_string()),
+            ..KitchenTicketDoc::default()
+        });
+        let text = String::from_utf8_lossy(&render_escpos(&doc, &LayoutConfig::default()).bytes)
+            .to_string();
+        assert!(text.contains("FIRE: MAINS"));
+        assert!(!text.contains("KITCHEN TICKET"));
+        assert!(render_html(&doc, &LayoutConfig::default()).contains("FIRE: MAINS"));
+    }
+
     #[test]
     fn classic_zreport_header_keeps_legacy_org_line_behavior() {
         let cfg = LayoutConfig {
@@ -10263,6 +10857,23 @@ mod tests {
         assert!(variance < closing);
     }
 
+    #[test]
+    fn language_override_sets_decimal_comma_and_character_set() {
+        assert_eq!(normalize_receipt_language(" EL_gr "), "el");
+        assert_eq!(normalize_receipt_language(""), "en");
+        let mut cfg = LayoutConfig {
+            character_set: "PC850_MULTILINGUAL".to_string(),
+            ..LayoutConfig::default()
+        };
+        apply_language_override(&mut cfg, "el");
+        assert!(cfg.decimal_comma);
+        assert_eq!(cfg.character_set, "PC850_MULTILINGUAL");
+        apply_language_override(&mut cfg, "en");
+        assert!(!cfg.decimal_comma);
+        assert_eq!(cfg.language, "en");
+        assert_eq!(receipt_label("el", "COMPLETED"), "ΟΛΟΚΛΗΡΩΘΗΚΕ");
+    }
+
     #[test]
     fn receipt_label_translates_shift_and_zreport_terms() {
         assert_eq!(receipt_label("el", "SHIFT CHECKOUT"), "ΚΛΕΙΣΙΜΟ ΒΑΡΔΙΑΣ");
@@ -11577,4 +12188,308 @@ mod tests {
             "expected logo fallback warning when logo is enabled without a source"
         );
     }
+
+    #[test]
+    fn tax_breakdown_prints_net_tax_and_gross_per_rate() {
+        let doc = ReceiptDocument::OrderReceipt(OrderReceiptDoc {
+            order_number: "T-1".to_string(),
+            order_type: "pickup".to_string(),
+            created_at: "2026-03-09T18:00:00Z".to_string(),
+            tax_breakdown: vec![
+                TaxBreakdownLine {
+                    label: "Food".to_string(),
+                    rate: 13.0,
+                    net: 10.0,
+                    tax: 1.3,
+                    gross: 11.3,
+                },
+                TaxBreakdownLine {
+                    label: "Alcohol".to_string(),
+                    rate: 24.0,
+                    net: 10.0,
+                    tax: 2.4,
+                    gross: 12.4,
+                },
+            ],
+            ..OrderReceiptDoc::default()
+        });
+
+        for template in [ReceiptTemplate::Classic, ReceiptTemplate::Modern] {
+            let cfg = LayoutConfig {
+                template,
+                command_profile: CommandProfile::SafeText,
+                ..LayoutConfig::default()
+            };
+            let text = String::from_utf8_lossy(&render_escpos(&doc, &cfg).bytes).to_string();
+            assert!(text.contains("Net 13% Food"), "{text}");
+            assert!(text.contains("Tax 24% Alcohol"), "{text}");
+            assert!(text.contains("Gross 24% Alcohol"), "{text}");
+            assert!(text.contains("12.40"), "{text}");
+
+            let html = render_html(&doc, &cfg);
+            assert!(html.contains("Gross 13% Food"));
+        }
+    }
+
+    fn preview_order_doc() -> OrderReceiptDoc {
+        OrderReceiptDoc {
+            order_id: "ord-1".to_string(),
+            order_number: "ORD-0042".to_string(),
+            order_type: "dine-in".to_string(),
+            created_at: "2026-02-24 13:05".to_string(),
+            table_number: Some("7".to_string()),
+            customer_name: Some("Γιώργος Παπαδόπουλος".to_string()),
+            items: vec![
+                ReceiptItem {
+                    name: "Σουβλάκι χοιρινό".to_string(),
+                    quantity: 2.0,
+                    total: 9.0,
+                    customizations: vec![
+                        ReceiptCustomizationLine {
+                            name: "Τζατζίκι".to_string(),
+                            quantity: 1.0,
+                            price: Some(0.5),
+                            ..ReceiptCustomizationLine::default()
+                        },
+                        ReceiptCustomizationLine {
+                            name: "Onion".to_string(),
+                            quantity: 1.0,
+                            is_without: true,
+                            ..ReceiptCustomizationLine::default()
+                        },
+                    ],
+                    ..ReceiptItem::default()
+                },
+                ReceiptItem {
+                    name: "Greek Salad".to_string(),
+                    quantity: 1.0,
+                    total: 7.5,
+                    note: Some("Dressing on the side".to_string()),
+                    ..ReceiptItem::default()
+                },
+            ],
+            totals: vec![
+                TotalsLine {
+                    label: "Subtotal".to_string(),
+                    amount: 16.5,
+                    ..TotalsLine::default()
+                },
+                TotalsLine {
+                    label: "Discount".to_string(),
+                    amount: -1.65,
+                    discount_percent: Some(10.0),
+                    ..TotalsLine::default()
+                },
+                TotalsLine {
+                    label: "Total".to_string(),
+                    amount: 14.85,
+                    emphasize: true,
+                    ..TotalsLine::default()
+                },
+            ],
+            payments: vec![
+                PaymentLine {
+                    label: "Cash".to_string(),
+                    amount: 10.0,
+                    ..PaymentLine::default()
+                },
+                PaymentLine {
+                    label: "Card".to_string(),
+                    amount: 4.85,
+                    ..PaymentLine::default()
+                },
+            ],
+            ..OrderReceiptDoc::default()
+        }
+    }
+
+    fn greek_preview_cfg() -> LayoutConfig {
+        LayoutConfig {
+            character_set: "PC737_GREEK".to_string(),
+            currency_symbol: "€".to_string(),
+            ..LayoutConfig::default()
+        }
+    }
+
+    #[test]
+    fn preview_text_snapshot_for_order_receipt() {
+        let document = ReceiptDocument::OrderReceipt(preview_order_doc());
+        let preview = render_preview(&document, &greek_preview_cfg());
+        let expected = "
+------------------------------------------------
+                  [ DINE-IN ]
+Order                                  #ORD-0042
+Date                            2026-02-24 13:05
+Table                                          7
+Customer                    Γιώργος Παπαδόπουλος
+------------------------------------------------
+                     ORDER
+2x Σουβλάκι χοιρινό                         9.00
++ Τζατζίκι (+0.50)
+- Without
+- Onion
+1x Greek Salad                              7.50
+Note: Dressing on the side
+------------------------------------------------
+Subtotal                                   16.50
+Discount (10%)                             -1.65
+Total                                     14.85€
+------------------------------------------------
+Cash                                       10.00
+Card                                        4.85
+* * * * * * * * * * * * * * * * * * * * * * * *
+                   Thank you
+* * * * * * * * * * * * * * * * * * * * * * * *
+";
+        assert_eq!(preview.text, expected);
+        assert_eq!(preview.body_mode, EscPosBodyMode::Text);
+        assert!(preview.html.contains("Σουβλάκι χοιρινό"));
+        assert_eq!(
+            preview.text,
+            render_escpos(&document, &greek_preview_cfg()).text
+        );
+    }
+
+    #[test]
+    fn preview_text_snapshot_for_greek_order_receipt() {
+        let document = ReceiptDocument::OrderReceipt(preview_order_doc());
+        let mut cfg = LayoutConfig {
+            currency_symbol: "€".to_string(),
+            ..LayoutConfig::default()
+        };
+        apply_language_override(&mut cfg, "el-GR");
+        assert_eq!(cfg.character_set, "PC737_GREEK");
+        let preview = render_preview(&document, &cfg);
+        let expected = "
+------------------------------------------------
+                  [ ΕΠΙΤΟΠΟΥ ]
+Παραγγελία                             #ORD-0042
+Ημ/νία                          2026-02-24 13:05
+Τραπέζι                                        7
+Πελάτης                     Γιώργος Παπαδόπουλος
+------------------------------------------------
+                   Παραγγελία
+2x Σουβλάκι χοιρινό                         9,00
++ Τζατζίκι (+0,50)
+- Χωρίς
+- Onion
+1x Greek Salad                              7,50
+Σημείωση: Dressing on the side
+------------------------------------------------
+Υποσύνολο                                  16,50
+Έκπτωση (10%)                              -1,65
+Σύνολο                                    14,85€
+------------------------------------------------
+Μετρητά                                    10,00
+Κάρτα                                       4,85
+* * * * * * * * * * * * * * * * * * * * * * * *
+                  Ευχαριστούμε
+* * * * * * * * * * * * * * * * * * * * * * * *
+";
+        assert_eq!(preview.text, expected);
+    }
+
+    #[test]
+    fn preview_text_snapshot_for_kitchen_ticket() {
+        let order = preview_order_doc();
+        let document = ReceiptDocument::KitchenTicket(KitchenTicketDoc {
+            order_id: order.order_id,
+            order_number: order.order_number,
+            order_type: order.order_type,
+            created_at: order.created_at,
+            table_number: order.table_number,
+            items: order.items,
+            ..KitchenTicketDoc::default()
+        });
+        let preview = render_preview(&document, &greek_preview_cfg());
+        let expected = "
+---------------- KITCHEN TICKET ----------------
+                Order #ORD-0042
+                 Type: DINE-IN
+             Date: 2026-02-24 13:05
+------------------------------------------------
+Table                                          7
+------------------------------------------------
+                     ITEMS
+2 x Σουβλάκι χοιρινό
++ Τζατζίκι (+0.50)
+- Without
+- Onion
+1 x Greek Salad
+Note: Dressing on the side
+* * * * * * * * * * * * * * * * * * * * * * * *
+                   Thank you
+* * * * * * * * * * * * * * * * * * * * * * * *
+";
+        assert_eq!(preview.text, expected);
+    }
+
+    #[test]
+    fn kitchen_ticket_emphasises_allergy_flags_under_the_item() {
+        let mut order = preview_order_doc();
+        order.items[1].allergy_flags = vec!["gluten".to_string(), "milk".to_string()];
+        let document = ReceiptDocument::KitchenTicket(KitchenTicketDoc {
+            order_id: order.order_id,
+            order_number: order.order_number,
+            order_type: order.order_type,
+            created_at: order.created_at,
+            kitchen_notes: Some("Birthday, bring cake last".to_string()),
+            allergy_flags: vec!["peanuts".to_string()],
+            items: order.items,
+            ..KitchenTicketDoc::default()
+        });
+        let cfg = greek_preview_cfg();
+        let preview = render_preview(&document, &cfg);
+        let lines: Vec<&str> = preview.text.lines().collect();
+        let order_flag = lines
+            .iter()
+            .position(|line| *line == "[ALLERGY: PEANUTS]")
+            .expect("order flags printed");
+        let items_header = lines
+            .iter()
+            .position(|line| line.trim() == "ITEMS")
+            .unwrap();
+        assert!(order_flag < items_header, "{}", preview.text);
+        assert!(preview.text.contains("Note: Birthday, bring cake last"));
+        let item = lines
+            .iter()
+            .position(|line| *line == "1 x Greek Salad")
+            .unwrap();
+        assert_eq!(lines[item + 1], "[ALLERGY: GLUTEN, MILK]");
+
+        let bytes = render_escpos(&document, &cfg).bytes;
+        // GS ! 0x01 (double height) and GS B 1 (reverse) around the flags.
+        assert!(bytes.windows(3).any(|w| w == [0x1D, 0x21, 0x01]));
+        assert!(bytes.windows(3).any(|w| w == [0x1D, 0x42, 0x01]));
+        assert!(preview
+            .html
+            .contains("<div class=\"allergy\">[ALLERGY: GLUTEN, MILK]</div>"));
+    }
+
+    #[test]
+    fn preview_text_shows_unprintable_characters_as_the_printer_does() {
+        let document = ReceiptDocument::OrderReceipt(preview_order_doc());
+        let preview = render_preview(&document, &LayoutConfig::default());
+        assert!(
+            preview.text.contains("???????? ???????"),
+            "{}",
+            preview.text
+        );
+        assert!(!preview.text.contains('Σ'));
+        assert!(preview.html.contains("Σουβλάκι χοιρινό"));
+    }
+
+    #[test]
+    fn raster_exact_preview_falls_back_to_text_layout() {
+        let document = ReceiptDocument::OrderReceipt(preview_order_doc());
+        let cfg = LayoutConfig {
+            template: ReceiptTemplate::Classic,
+            classic_customer_render_mode: ClassicCustomerRenderMode::RasterExact,
+            ..greek_preview_cfg()
+        };
+        let preview = render_preview(&document, &cfg);
+        assert_eq!(preview.body_mode, EscPosBodyMode::RasterExact);
+        assert!(render_escpos(&document, &cfg).text.is_empty());
+        assert!(preview.text.contains("Σουβλάκι"), "{}", preview.text);
+    }
 }
@@ -1467,7 +1467,7 @@ fn sqlite_value_to_string(value: ValueRef<'_>) -> String {
     }
 }
 
-fn csv_escape(value: &str) -> String {
+pub(crate) fn csv_escape(value: &str) -> String {
     let needs_quotes =
         value.contains(',') || value.contains('"') || value.contains('\n') || value.contains('\r');
     if !needs_quotes {
@@ -16,8 +16,9 @@ use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::db::DbState;
-use crate::money::Cents;
+use crate::money::{CashRounding, Cents};
 use crate::payments;
+use crate::reasons::{self, ReasonAction};
 use crate::storage;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -176,6 +177,7 @@ fn build_adjustment_queue_payload(
     adjustment_type: &str,
     amount: f64,
     reason: &str,
+    reason_code: Option<&str>,
     staff_id: Option<&str>,
     staff_shift_id: Option<&str>,
     terminal_id: &str,
@@ -217,6 +219,12 @@ fn build_adjustment_queue_payload(
         Value::from(Cents::round_half_even(amount).as_i64()),
     );
     payload.insert("reason".to_string(), Value::String(reason.to_string()));
+    if let Some(reason_code) = reason_code {
+        payload.insert(
+            "reasonCode".to_string(),
+            Value::String(reason_code.to_string()),
+        );
+    }
     payload.insert(
         "terminalId".to_string(),
         Value::String(terminal_id.to_string()),
@@ -271,6 +279,49 @@ fn build_adjustment_queue_payload(
 // at :170) stay — they have other live callers at :463, :569, :737, :883
 // inside the refund-path entry points.
 
+/// Terminal whose fiscal series a refund/void document is numbered in:
+/// this terminal, or the order's terminal when credentials are missing.
+/// Rounding on cash handed back from the drawer (paid out minus exact). A
+/// refund that completes a rounded payment undoes whatever rounding the
+/// payment's earlier refunds left, so the drawer's rounding line returns to
+/// zero; partial refunds are rounded like any other cash tender.
+fn cash_refund_rounding(
+    conn: &Connection,
+    payment_id: &str,
+    amount: Cents,
+    fully_refunded: bool,
+) -> Result<Option<Cents>, String> {
+    if fully_refunded {
+        let (payment_delta, prior_refund_delta): (Option<i64>, i64) = conn
+            .query_row(
+                "SELECT op.rounding_delta_cents,
+                        (SELECT COALESCE(SUM(pa.rounding_delta_cents), 0)
+                         FROM payment_adjustments pa
+                         WHERE pa.payment_id = op.id AND pa.adjustment_type = 'refund')
+                 FROM order_payments op WHERE op.id = ?1",
+                params![payment_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| format!("load payment rounding: {e}"))?;
+        if let Some(payment_delta) = payment_delta {
+            return Ok(Some(Cents::new(payment_delta - prior_refund_delta)));
+        }
+    }
+    Ok(CashRounding::from_settings(conn).map(|rounding| rounding.round(amount) - amount))
+}
+
+fn fiscal_terminal_id(conn: &Connection, order_id: &str, terminal_id: &str) -> String {
+    if !terminal_id.trim().is_empty() {
+        return terminal_id.to_string();
+    }
+    conn.query_row(
+        "SELECT COALESCE(terminal_id, '') FROM orders WHERE id = ?1",
+        params![order_id],
+        |row| row.get(0),
+    )
+    .unwrap_or_default()
+}
+
 pub(crate) fn refund_payment_in_connection(
     conn: &Connection,
     payload: &Value,
@@ -282,7 +333,6 @@ pub(crate) fn refund_payment_in_connection(
     if amount <= 0.0 {
         return Err("Refund amount must be positive".into());
     }
-    let reason = str_field(payload, "reason").ok_or("Missing reason")?;
     let requested_idempotency_key = str_field(payload, "idempotencyKey")
         .or_else(|| str_field(payload, "idempotency_key"))
         .or_else(|| str_field(payload, "clientRequestId"))
@@ -307,6 +357,24 @@ pub(crate) fn refund_payment_in_connection(
             .or_else(|| str_field(payload, "adjustment_context"))
             .as_deref(),
     );
+    // Edit-settlement refunds are the system rebalancing an edited order,
+    // not a refund anyone chose, so they keep their free-text reason only.
+    let requested_reason_code =
+        str_field(payload, "reasonCode").or_else(|| str_field(payload, "reason_code"));
+    let free_reason = str_field(payload, "reason");
+    let (reason, reason_code) = if adjustment_context == AdjustmentContext::EditSettlement
+        && requested_reason_code.is_none()
+    {
+        (free_reason.ok_or("Missing reason")?, None)
+    } else {
+        let resolved = reasons::require(
+            conn,
+            ReasonAction::Refund,
+            requested_reason_code.as_deref(),
+            free_reason.as_deref(),
+        )?;
+        (resolved.text, Some(resolved.code))
+    };
 
     let (
         order_id,
@@ -399,11 +467,12 @@ pub(crate) fn refund_payment_in_connection(
         )
         .unwrap_or(0.0);
 
-    let remaining = original_amount - prior_refunds;
     // W4e: integer-cent comparison. Half-cent epsilon no longer needed.
-    if Cents::round_half_even(amount).as_i64() > Cents::round_half_even(remaining).as_i64() {
+    let remaining = Cents::round_half_even(original_amount) - Cents::round_half_even(prior_refunds);
+    if Cents::round_half_even(amount) > remaining {
         return Err(format!(
-            "Refund amount {amount:.2} exceeds remaining balance {remaining:.2}"
+            "Refund amount {amount:.2} exceeds remaining balance {:.2}",
+            remaining.to_f64_dp2()
         ));
     }
 
@@ -423,10 +492,9 @@ pub(crate) fn refund_payment_in_connection(
 
     let adjustment_id = Uuid::new_v4().to_string();
     let now = Utc::now().to_rfc3339();
-    let new_total_refunds = prior_refunds + amount;
+    let new_total_refunds = Cents::round_half_even(prior_refunds) + Cents::round_half_even(amount);
     // W4e: integer-cent equality replaces the float-distance epsilon.
-    let is_fully_refunded = Cents::round_half_even(new_total_refunds).as_i64()
-        == Cents::round_half_even(original_amount).as_i64();
+    let is_fully_refunded = new_total_refunds == Cents::round_half_even(original_amount);
     let order_shift_id: Option<String> = conn
         .query_row(
             "SELECT staff_shift_id FROM orders WHERE id = ?1",
@@ -448,12 +516,34 @@ pub(crate) fn refund_payment_in_connection(
 
     // W4c dual-write: populate `amount_cents` alongside REAL `amount`.
     let amount_cents = Cents::round_half_even(amount).as_i64();
+    let rounding_delta_cents = if cash_handler == Some(CashHandler::CashierDrawer) {
+        cash_refund_rounding(
+            conn,
+            &payment_id,
+            Cents::new(amount_cents),
+            is_fully_refunded,
+        )?
+        .map(Cents::as_i64)
+    } else {
+        None
+    };
+    let rounding_delta = rounding_delta_cents.map(|c| Cents::new(c).to_f64_dp2());
+    // The refund's proportional share of the order's service charge. An
+    // edit-settlement refund follows an item edit that already re-priced
+    // the charge, so it carries none.
+    let service_charge_share = if adjustment_context == AdjustmentContext::EditSettlement {
+        Cents::ZERO
+    } else {
+        crate::service_charge::refund_share(conn, &order_id, Cents::new(amount_cents))?
+    };
     conn.execute(
         "INSERT INTO payment_adjustments (
             id, payment_id, order_id, adjustment_type, amount, amount_cents,
             reason, staff_id, staff_shift_id, sync_state, refund_method, cash_handler,
-            adjustment_context, idempotency_key, created_at, updated_at
-        ) VALUES (?1, ?2, ?3, 'refund', ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?14)",
+            adjustment_context, idempotency_key, created_at, updated_at,
+            rounding_delta, rounding_delta_cents, reason_code,
+            service_charge, service_charge_cents
+        ) VALUES (?1, ?2, ?3, 'refund', ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?14, ?15, ?16, ?17, ?18, ?19)",
         params![
             adjustment_id,
             payment_id,
@@ -469,6 +559,11 @@ pub(crate) fn refund_payment_in_connection(
             adjustment_context.as_str(),
             client_idempotency_key,
             now,
+            rounding_delta,
+            rounding_delta_cents,
+            reason_code,
+            service_charge_share.to_f64_dp2(),
+            service_charge_share.as_i64(),
         ],
     )
     .map_err(|e| format!("insert adjustment: {e}"))?;
@@ -492,9 +587,18 @@ pub(crate) fn refund_payment_in_connection(
                     "UPDATE cash_drawer_sessions SET
                         total_refunds = COALESCE(total_refunds, 0) + ?1,
                         total_refunds_cents = COALESCE(total_refunds_cents, 0) + ?2,
+                        total_cash_rounding = COALESCE(total_cash_rounding, 0) - ?5,
+                        total_cash_rounding_cents = COALESCE(total_cash_rounding_cents, 0) - ?6,
                         updated_at = ?3
                      WHERE staff_shift_id = ?4",
-                    params![amount, amount_cents, now, sid],
+                    params![
+                        amount,
+                        amount_cents,
+                        now,
+                        sid,
+                        rounding_delta.unwrap_or(0.0),
+                        rounding_delta_cents.unwrap_or(0)
+                    ],
                 )
                 .map_err(|e| format!("update drawer refunds: {e}"))?;
             }
@@ -572,6 +676,12 @@ pub(crate) fn refund_payment_in_connection(
 
     let terminal_id = storage::get_credential("terminal_id").unwrap_or_default();
     let branch_id = storage::get_credential("branch_id").unwrap_or_default();
+    let fiscal_document = crate::fiscal::documents::issue_refund(
+        conn,
+        &adjustment_id,
+        &fiscal_terminal_id(conn, &order_id, &terminal_id),
+        &branch_id,
+    )?;
     let sync_payload = build_adjustment_queue_payload(
         &adjustment_id,
         &payment_id,
@@ -580,6 +690,7 @@ pub(crate) fn refund_payment_in_connection(
         "refund",
         amount,
         &reason,
+        reason_code.as_deref(),
         resolved_staff_id.as_deref(),
         resolved_staff_shift_id.as_deref(),
         &terminal_id,
@@ -592,8 +703,18 @@ pub(crate) fn refund_payment_in_connection(
 
     payments::recompute_order_payment_state(conn, &order_id, &now, &payment_id)?;
 
-    let sync_payload_value = serde_json::from_str::<Value>(&sync_payload)
+    let mut sync_payload_value = serde_json::from_str::<Value>(&sync_payload)
         .map_err(|e| format!("parse adjustment payload: {e}"))?;
+    if let Value::Object(obj) = &mut sync_payload_value {
+        obj.insert(
+            "serviceCharge".to_string(),
+            serde_json::json!(service_charge_share.to_f64_dp2()),
+        );
+        obj.insert(
+            "service_charge_cents".to_string(),
+            serde_json::json!(service_charge_share.as_i64()),
+        );
+    }
     crate::sync_queue::enqueue_payload_item(
         conn,
         "payment_adjustments",
@@ -618,16 +739,44 @@ pub(crate) fn refund_payment_in_connection(
             .map_err(|e| format!("refresh parent payment settlement proof sync: {e}"))?;
     }
 
+    // Optional restock: `restock: true` returns everything the order's sale
+    // deduction took; `restockItems` (order-item shape) returns only those
+    // lines. Capped at what was deducted either way.
+    let restock_items = payload
+        .get("restockItems")
+        .or_else(|| payload.get("restock_items"))
+        .and_then(Value::as_array);
+    let restocked_ingredients = if restock_items.is_some()
+        || payload.get("restock").and_then(Value::as_bool) == Some(true)
+    {
+        crate::inventory::restock_for_refund(
+            conn,
+            &order_id,
+            restock_items.map(Vec::as_slice),
+            &adjustment_id,
+            resolved_staff_id.as_deref(),
+        )?
+    } else {
+        0
+    };
+
     Ok(serde_json::json!({
         "success": true,
         "adjustmentId": adjustment_id,
         "paymentId": payment_id,
         "amount": amount,
-        "remainingBalance": original_amount - new_total_refunds,
+        "restockedIngredients": restocked_ingredients,
+        "remainingBalance": (Cents::round_half_even(original_amount) - new_total_refunds).to_f64_dp2(),
         "fullyRefunded": is_fully_refunded,
+        "roundingDelta": rounding_delta,
+        "serviceChargeRefunded": service_charge_share.to_f64_dp2(),
+        "cashPaidOut": rounding_delta_cents
+            .map(|delta| Cents::new(amount_cents + delta).to_f64_dp2()),
         "refundMethod": refund_method.as_str(),
         "cashHandler": cash_handler.map(CashHandler::as_str),
         "adjustmentContext": adjustment_context.as_str(),
+        "reasonCode": reason_code,
+        "fiscalDocumentNumber": fiscal_document.map(|document| document.document_number),
         "message": format!("Refund of {amount:.2} recorded"),
     }))
 }
@@ -669,6 +818,20 @@ pub fn refund_payment(db: &DbState, payload: &Value) -> Result<Value, String> {
                 amount = %amount,
                 "Refund recorded"
             );
+            crate::order_events::append_for_payment(
+                &conn,
+                payment_id,
+                crate::order_events::REFUND_RECORDED,
+                str_field(payload, "staffId")
+                    .or_else(|| str_field(payload, "staff_id"))
+                    .as_deref(),
+                serde_json::json!({
+                    "paymentId": payment_id,
+                    "adjustmentId": adjustment_id,
+                    "amount": amount,
+                    "reason": str_field(payload, "reason"),
+                }),
+            );
             Ok(value)
         }
         Err(e) => {
@@ -687,14 +850,22 @@ pub fn refund_payment(db: &DbState, payload: &Value) -> Result<Value, String> {
 /// This extends the existing `payments::void_payment` flow by also writing
 /// an adjustment record for the full amount, providing a unified audit trail
 /// for both voids and refunds.
+///
+/// `reason_code` must be active in the void reason list; `reason` is optional
+/// free text and falls back to the code's label.
 pub fn void_payment_with_adjustment(
     db: &DbState,
     payment_id: &str,
+    reason_code: &str,
     reason: &str,
     staff_id: Option<&str>,
     staff_shift_id: Option<&str>,
 ) -> Result<Value, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let resolved_reason =
+        reasons::require(&conn, ReasonAction::Void, Some(reason_code), Some(reason))?;
+    let reason = resolved_reason.text.as_str();
+    let reason_code = resolved_reason.code.as_str();
 
     // Fetch the payment in any state so we can return a precise, typed error
     // rather than a misleading "not found". A completed payment is the only
@@ -845,13 +1016,21 @@ pub fn void_payment_with_adjustment(
             "waiting_parent"
         };
 
+        let rounding_delta_cents: i64 = conn
+            .query_row(
+                "SELECT COALESCE(rounding_delta_cents, 0) FROM order_payments WHERE id = ?1",
+                params![payment_id],
+                |row| row.get(0),
+            )
+            .unwrap_or(0);
+
         // Mark payment as voided
         conn.execute(
             "UPDATE order_payments SET
                 status = 'voided', voided_at = ?1, voided_by = ?2,
-                void_reason = ?3, sync_status = 'pending', updated_at = ?1
+                void_reason = ?3, void_reason_code = ?5, sync_status = 'pending', updated_at = ?1
              WHERE id = ?4",
-            params![now, resolved_staff_id, reason, payment_id],
+            params![now, resolved_staff_id, reason, payment_id, reason_code],
         )
         .map_err(|e| format!("void payment: {e}"))?;
 
@@ -862,8 +1041,9 @@ pub fn void_payment_with_adjustment(
         conn.execute(
             "INSERT INTO payment_adjustments (
                 id, payment_id, order_id, adjustment_type, amount, amount_cents,
-                reason, staff_id, staff_shift_id, sync_state, created_at, updated_at
-            ) VALUES (?1, ?2, ?3, 'void', ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)",
+                reason, staff_id, staff_shift_id, sync_state, created_at, updated_at,
+                reason_code
+            ) VALUES (?1, ?2, ?3, 'void', ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10, ?11)",
             params![
                 adjustment_id,
                 payment_id,
@@ -875,6 +1055,7 @@ pub fn void_payment_with_adjustment(
                 resolved_staff_shift_id,
                 initial_sync_state,
                 now,
+                reason_code,
             ],
         )
         .map_err(|e| format!("insert void adjustment: {e}"))?;
@@ -893,13 +1074,23 @@ pub fn void_payment_with_adjustment(
         if let Some(ref sid) = order_shift_id {
             if pay_method == "cash" {
                 // W4c dual-write: clamp the cents sibling alongside REAL.
+                // The payment's cash rounding goes back with it.
                 conn.execute(
                     "UPDATE cash_drawer_sessions SET
                         total_cash_sales = MAX(COALESCE(total_cash_sales, 0) - ?1, 0),
                         total_cash_sales_cents = MAX(COALESCE(total_cash_sales_cents, 0) - ?2, 0),
+                        total_cash_rounding = COALESCE(total_cash_rounding, 0) - ?5,
+                        total_cash_rounding_cents = COALESCE(total_cash_rounding_cents, 0) - ?6,
                         updated_at = ?3
                      WHERE staff_shift_id = ?4",
-                    params![amount, void_amount_cents, now, sid],
+                    params![
+                        amount,
+                        void_amount_cents,
+                        now,
+                        sid,
+                        Cents::new(rounding_delta_cents).to_f64_dp2(),
+                        rounding_delta_cents
+                    ],
                 )
                 .map_err(|e| format!("reverse drawer cash_sales: {e}"))?;
             } else if pay_method == "card" {
@@ -920,6 +1111,11 @@ pub fn void_payment_with_adjustment(
         // Enqueue adjustment sync
         let terminal_id = storage::get_credential("terminal_id").unwrap_or_default();
         let branch_id = storage::get_credential("branch_id").unwrap_or_default();
+        crate::fiscal::documents::cancel_payment_receipt(
+            &conn,
+            payment_id,
+            &fiscal_terminal_id(&conn, &order_id, &terminal_id),
+        )?;
         let adj_payload = build_adjustment_queue_payload(
             &adjustment_id,
             payment_id,
@@ -928,6 +1124,7 @@ pub fn void_payment_with_adjustment(
             "void",
             amount,
             reason,
+            Some(reason_code),
             resolved_staff_id.as_deref(),
             resolved_staff_shift_id.as_deref(),
             &terminal_id,
@@ -973,11 +1170,26 @@ pub fn void_payment_with_adjustment(
         reason = %reason,
         "Payment voided with adjustment"
     );
+    crate::order_events::append(
+        &conn,
+        &order_id,
+        crate::order_events::PAYMENT_VOIDED,
+        staff_id,
+        serde_json::json!({
+            "paymentId": payment_id,
+            "adjustmentId": adjustment_id,
+            "method": pay_method,
+            "amount": amount,
+            "reason": reason,
+            "reasonCode": reason_code,
+        }),
+    );
 
     Ok(serde_json::json!({
         "success": true,
         "paymentId": payment_id,
         "adjustmentId": adjustment_id,
+        "reasonCode": reason_code,
         "message": "Payment voided",
     }))
 }
@@ -997,7 +1209,7 @@ pub fn list_order_adjustments(db: &DbState, order_id: &str) -> Result<Value, Str
                     COALESCE(amount_cents, CAST(ROUND(amount * 100) AS INTEGER), 0),
                     reason, staff_id, staff_shift_id, sync_state, sync_last_error,
                     refund_method, cash_handler, adjustment_context,
-                    created_at, updated_at
+                    created_at, updated_at, reason_code
              FROM payment_adjustments
              WHERE order_id = ?1
              ORDER BY created_at DESC",
@@ -1025,6 +1237,7 @@ pub fn list_order_adjustments(db: &DbState, order_id: &str) -> Result<Value, Str
                 "adjustmentContext": row.get::<_, Option<String>>(12)?,
                 "createdAt": row.get::<_, String>(13)?,
                 "updatedAt": row.get::<_, String>(14)?,
+                "reasonCode": row.get::<_, Option<String>>(15)?,
             }))
         })
         .map_err(|e| e.to_string())?;
@@ -1150,10 +1363,7 @@ mod tests {
         )
         .expect("pragma setup");
         db::run_migrations_for_test(&conn);
-        DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        }
+        DbState::new(conn, std::path::PathBuf::from(":memory:"))
     }
 
     /// Insert a test order + payment and return (order_id, payment_id).
@@ -1189,6 +1399,7 @@ mod tests {
         let payload = serde_json::json!({
             "paymentId": pay_id,
             "amount": 15.0,
+            "reasonCode": "other",
             "reason": "Item returned",
         });
         let result = refund_payment(&db, &payload).unwrap();
@@ -1288,6 +1499,7 @@ mod tests {
         let payload = serde_json::json!({
             "paymentId": pay_id,
             "amount": 15.0,
+            "reasonCode": "other",
             "reason": "Item returned",
             "clientRequestId": "client-refund-1",
         });
@@ -1318,6 +1530,7 @@ mod tests {
         let payload = serde_json::json!({
             "paymentId": pay_id,
             "amount": 5.0,
+            "reasonCode": "other",
             "reason": "Queue payload check",
         });
         refund_payment(&db, &payload).unwrap();
@@ -1353,6 +1566,7 @@ mod tests {
         let payload = serde_json::json!({
             "paymentId": pay_id,
             "amount": 30.0,
+            "reasonCode": "other",
             "reason": "Full refund",
         });
         let result = refund_payment(&db, &payload).unwrap();
@@ -1377,11 +1591,11 @@ mod tests {
         let pay_id = seed_order_and_payment(&db, "ord-r3", 20.0);
 
         // First refund: 15
-        let p1 = serde_json::json!({ "paymentId": pay_id, "amount": 15.0, "reason": "Partial" });
+        let p1 = serde_json::json!({ "paymentId": pay_id, "amount": 15.0, "reasonCode": "other", "reason": "Partial" });
         refund_payment(&db, &p1).unwrap();
 
         // Second refund: 10 — exceeds remaining 5
-        let p2 = serde_json::json!({ "paymentId": pay_id, "amount": 10.0, "reason": "Too much" });
+        let p2 = serde_json::json!({ "paymentId": pay_id, "amount": 10.0, "reasonCode": "other", "reason": "Too much" });
         let err = refund_payment(&db, &p2).unwrap_err();
         assert!(err.contains("exceeds remaining balance"));
     }
@@ -1392,10 +1606,11 @@ mod tests {
         let pay_id = seed_order_and_payment(&db, "ord-r4", 25.0);
 
         // Void the payment first
-        void_payment_with_adjustment(&db, &pay_id, "Wrong order", None, None).unwrap();
+        void_payment_with_adjustment(&db, &pay_id, "wrong_item", "Wrong order", None, None)
+            .unwrap();
 
         // Try to refund — should fail
-        let payload = serde_json::json!({ "paymentId": pay_id, "amount": 10.0, "reason": "test" });
+        let payload = serde_json::json!({ "paymentId": pay_id, "amount": 10.0, "reasonCode": "other", "reason": "test" });
         let err = refund_payment(&db, &payload).unwrap_err();
         assert!(err.contains("voided"));
     }
@@ -1405,9 +1620,15 @@ mod tests {
         let db = test_db();
         let pay_id = seed_order_and_payment(&db, "ord-v1", 40.0);
 
-        let result =
-            void_payment_with_adjustment(&db, &pay_id, "Customer complaint", Some("staff-1"), None)
-                .unwrap();
+        let result = void_payment_with_adjustment(
+            &db,
+            &pay_id,
+            "wrong_item",
+            "Customer complaint",
+            Some("staff-1"),
+            None,
+        )
+        .unwrap();
         assert_eq!(result["success"], true);
         assert!(result["adjustmentId"].as_str().is_some());
 
@@ -1468,9 +1689,9 @@ mod tests {
         let pay_id = seed_order_and_payment(&db, "ord-la", 100.0);
 
         // Two refunds
-        let p1 = serde_json::json!({ "paymentId": pay_id, "amount": 20.0, "reason": "Item 1" });
+        let p1 = serde_json::json!({ "paymentId": pay_id, "amount": 20.0, "reasonCode": "other", "reason": "Item 1" });
         refund_payment(&db, &p1).unwrap();
-        let p2 = serde_json::json!({ "paymentId": pay_id, "amount": 30.0, "reason": "Item 2" });
+        let p2 = serde_json::json!({ "paymentId": pay_id, "amount": 30.0, "reasonCode": "other", "reason": "Item 2" });
         refund_payment(&db, &p2).unwrap();
 
         let result = list_order_adjustments(&db, "ord-la").unwrap();
@@ -1493,7 +1714,7 @@ mod tests {
         assert_eq!(b1["balance"], 60.0);
 
         // Refund 25
-        let p = serde_json::json!({ "paymentId": pay_id, "amount": 25.0, "reason": "Partial" });
+        let p = serde_json::json!({ "paymentId": pay_id, "amount": 25.0, "reasonCode": "other", "reason": "Partial" });
         refund_payment(&db, &p).unwrap();
 
         let b2 = get_payment_balance(&db, &pay_id).unwrap();
@@ -1507,7 +1728,7 @@ mod tests {
         let db = test_db();
         let pay_id = seed_order_and_payment(&db, "ord-gbv", 45.0);
 
-        void_payment_with_adjustment(&db, &pay_id, "Cancelled", None, None).unwrap();
+        void_payment_with_adjustment(&db, &pay_id, "wrong_item", "Cancelled", None, None).unwrap();
 
         let b = get_payment_balance(&db, &pay_id).unwrap();
         assert_eq!(b["balance"], 0.0);
@@ -1592,6 +1813,7 @@ mod tests {
         let payload = serde_json::json!({
             "paymentId": "pay-wp",
             "amount": 5.0,
+            "reasonCode": "other",
             "reason": "Partial refund before sync",
         });
         let result = refund_payment(&db, &payload).unwrap();
@@ -1654,7 +1876,8 @@ mod tests {
         drop(conn);
 
         // Void the payment
-        void_payment_with_adjustment(&db, "pay-vr", "Wrong order", None, None).unwrap();
+        void_payment_with_adjustment(&db, "pay-vr", "wrong_item", "Wrong order", None, None)
+            .unwrap();
 
         // Verify drawer cash_sales was reversed
         let conn = db.conn.lock().unwrap();
@@ -1718,6 +1941,7 @@ mod tests {
         let payload = serde_json::json!({
             "paymentId": "pay-rdr",
             "amount": 15.0,
+            "reasonCode": "other",
             "reason": "Item returned",
         });
         refund_payment(&db, &payload).unwrap();
@@ -1744,6 +1968,75 @@ mod tests {
         assert_eq!(cash_sales, 50.0, "total_cash_sales should remain 50.0");
     }
 
+    #[test]
+    fn test_full_refund_of_rounded_cash_payment_reverses_rounding() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        crate::db::set_setting(&conn, "payments", "cash_rounding", "0.05").unwrap();
+
+        // 10.03 exact, 10.05 collected: the drawer holds a +0.02 rounding line.
+        conn.execute(
+            "INSERT INTO staff_shifts (id, staff_id, role_type, check_in_time, status, sync_status, created_at, updated_at)
+             VALUES ('shift-rnd', 'staff-rnd', 'cashier', datetime('now'), 'active', 'pending', datetime('now'), datetime('now'))",
+            [],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO cash_drawer_sessions (id, staff_shift_id, cashier_id, branch_id, terminal_id,
+                                                opening_amount, opening_amount_cents,
+                                                total_cash_sales, total_cash_sales_cents,
+                                                total_cash_rounding, total_cash_rounding_cents,
+                                                opened_at, created_at, updated_at)
+             VALUES ('cd-rnd', 'shift-rnd', 'staff-rnd', 'b1', 't1', 0.0, 0, 10.03, 1003, 0.02, 2,
+                     datetime('now'), datetime('now'), datetime('now'))",
+            [],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO orders (id, items, total_amount, total_amount_cents, status, sync_status, staff_shift_id, supabase_id, created_at, updated_at)
+             VALUES ('ord-rnd', '[]', 10.03, 1003, 'completed', 'synced', 'shift-rnd', 'sup-rnd', datetime('now'), datetime('now'))",
+            [],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO order_payments (id, order_id, method, amount, amount_cents,
+                                         rounded_amount, rounded_amount_cents, rounding_delta, rounding_delta_cents,
+                                         status, staff_shift_id, sync_status, sync_state, created_at, updated_at)
+             VALUES ('pay-rnd', 'ord-rnd', 'cash', 10.03, 1003, 10.05, 1005, 0.02, 2,
+                     'completed', 'shift-rnd', 'synced', 'applied', datetime('now'), datetime('now'))",
+            [],
+        ).unwrap();
+        drop(conn);
+
+        let payload = serde_json::json!({
+            "paymentId": "pay-rnd",
+            "amount": 10.03,
+            "reasonCode": "other",
+            "reason": "Order cancelled",
+        });
+        let result = refund_payment(&db, &payload).unwrap();
+        assert_eq!(result["fullyRefunded"], true);
+        assert_eq!(result["roundingDelta"], 0.02);
+        assert_eq!(result["cashPaidOut"], 10.05);
+
+        let conn = db.conn.lock().unwrap();
+        let adjustment_delta: i64 = conn
+            .query_row(
+                "SELECT rounding_delta_cents FROM payment_adjustments WHERE payment_id = 'pay-rnd'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(adjustment_delta, 2);
+        let (refunds_cents, rounding_cents): (i64, i64) = conn
+            .query_row(
+                "SELECT total_refunds_cents, total_cash_rounding_cents
+                 FROM cash_drawer_sessions WHERE staff_shift_id = 'shift-rnd'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(refunds_cents, 1003);
+        assert_eq!(rounding_cents, 0, "rounding nets out after a full refund");
+    }
+
     #[test]
     fn test_multiple_refunds_then_void_rejected() {
         let db = test_db();
@@ -1751,15 +2044,16 @@ mod tests {
 
         // Refund 20 — payment stays status='completed' (fully-refunded threshold
         // not yet reached) but now has a non-zero prior refund total.
-        let p = serde_json::json!({ "paymentId": pay_id, "amount": 20.0, "reason": "Partial" });
+        let p = serde_json::json!({ "paymentId": pay_id, "amount": 20.0, "reasonCode": "other", "reason": "Partial" });
         refund_payment(&db, &p).unwrap();
 
         // Void must now be rejected: the payment is partially materialized via
         // the prior refund, so voiding (which reverses the full sale) would
         // double-count the already-paid-out refund. The correct operator flow
         // is to process the remaining 30.0 as another refund, not a void.
-        let err = void_payment_with_adjustment(&db, &pay_id, "Cancel rest", None, None)
-            .expect_err("void of partially-refunded payment must be rejected");
+        let err =
+            void_payment_with_adjustment(&db, &pay_id, "wrong_item", "Cancel rest", None, None)
+                .expect_err("void of partially-refunded payment must be rejected");
         assert!(
             err.contains("prior refunds"),
             "error should mention prior refunds, got: {err}"
@@ -1834,6 +2128,7 @@ mod tests {
         let payload = serde_json::json!({
             "paymentId": "pay-adjustment-staff",
             "amount": 5.0,
+            "reasonCode": "other",
             "reason": "Operator correction",
             "staffId": "STF0008",
             "staffShiftId": staff_shift_id,
@@ -1915,6 +2210,7 @@ mod tests {
         void_payment_with_adjustment(
             &db,
             "pay-void-adjustment-staff",
+            "other",
             "Operator correction",
             Some("STF0008"),
             Some(staff_shift_id),
@@ -1959,6 +2255,7 @@ mod tests {
         let payload = serde_json::json!({
             "paymentId": pay_id,
             "amount": 10.0,
+            "reasonCode": "other",
             "reason": "Operator correction",
             "staffId": "STF0008",
             "staffShiftId": "shift-not-a-uuid",
@@ -0,0 +1,248 @@
+//! What staff can do about common failures.
+//!
+//! An error message such as "Terminal not configured: missing API key"
+//! tells support what broke but leaves the cashier stuck. Failures that
+//! have a known fix carry a `remediation` object next to the message:
+//!
+//! ```json
+//! { "code": "TERMINAL_NOT_CONFIGURED",
+//!   "messageKey": "errors.remediation.terminalNotConfigured",
+//!   "action": "open_onboarding" }
+//! ```
+//!
+//! `messageKey` is a renderer i18n key and `action` one of
+//! [`SuggestedAction`], which the renderer shows as a button. Every code
+//! is listed once in [`REGISTRY`]; [`for_message`] is the single place
+//! error text is matched, and the tests check that every code it (or any
+//! other caller) can produce has an entry.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Button the renderer offers next to the error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestedAction {
+    OpenOnboarding,
+    RetrySync,
+    CheckNetwork,
+    ContactSupport,
+    OpenPrinterSettings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Remediation {
+    pub code: &'static str,
+    pub message_key: &'static str,
+    pub action: SuggestedAction,
+}
+
+pub const TERMINAL_NOT_CONFIGURED: &str = "TERMINAL_NOT_CONFIGURED";
+pub const TERMINAL_AUTH_FAILED: &str = "TERMINAL_AUTH_FAILED";
+pub const TERMINAL_INACTIVE: &str = "TERMINAL_INACTIVE";
+pub const ADMIN_UNREACHABLE: &str = "ADMIN_UNREACHABLE";
+pub const SYNC_CIRCUIT_OPEN: &str = "SYNC_CIRCUIT_OPEN";
+pub const SUPABASE_NOT_CONFIGURED: &str = "SUPABASE_NOT_CONFIGURED";
+pub const PRINTER_JOB_FAILED: &str = "PRINTER_JOB_FAILED";
+pub const DATABASE_BUSY: &str = "DATABASE_BUSY";
+
+pub const REGISTRY: &[Remediation] = &[
+    Remediation {
+        code: TERMINAL_NOT_CONFIGURED,
+        message_key: "errors.remediation.terminalNotConfigured",
+        action: SuggestedAction::OpenOnboarding,
+    },
+    Remediation {
+        code: TERMINAL_AUTH_FAILED,
+        message_key: "errors.remediation.terminalAuthFailed",
+        action: SuggestedAction::OpenOnboarding,
+    },
+    Remediation {
+        code: TERMINAL_INACTIVE,
+        message_key: "errors.remediation.terminalInactive",
+        action: SuggestedAction::ContactSupport,
+    },
+    Remediation {
+        code: ADMIN_UNREACHABLE,
+        message_key: "errors.remediation.adminUnreachable",
+        action: SuggestedAction::CheckNetwork,
+    },
+    Remediation {
+        code: SYNC_CIRCUIT_OPEN,
+        message_key: "errors.remediation.syncCircuitOpen",
+        action: SuggestedAction::CheckNetwork,
+    },
+    Remediation {
+        code: SUPABASE_NOT_CONFIGURED,
+        message_key: "errors.remediation.supabaseNotConfigured",
+        action: SuggestedAction::RetrySync,
+    },
+    Remediation {
+        code: PRINTER_JOB_FAILED,
+        message_key: "errors.remediation.printerJobFailed",
+        action: SuggestedAction::OpenPrinterSettings,
+    },
+    Remediation {
+        code: DATABASE_BUSY,
+        message_key: "errors.remediation.databaseBusy",
+        action: SuggestedAction::ContactSupport,
+    },
+];
+
+pub fn lookup(code: &str) -> Option<&'static Remediation> {
+    REGISTRY.iter().find(|entry| entry.code == code)
+}
+
+/// Remediation code for an error message, if it is one we know how to
+/// fix. Order matters: "Terminal not configured: missing terminal_id" is
+/// also a terminal-auth message, but onboarding is the fix.
+pub fn code_for_message(message: &str) -> Option<&'static str> {
+    let lower = message.trim().to_ascii_lowercase();
+    if lower.starts_with("terminal not configured")
+        || lower.starts_with("terminal is not configured")
+        || lower.ends_with("credential not configured")
+        || lower.starts_with("api key not configured")
+        || lower.starts_with("admin url not configured")
+    {
+        Some(TERMINAL_NOT_CONFIGURED)
+    } else if lower.starts_with("circuit_open") {
+        Some(SYNC_CIRCUIT_OPEN)
+    } else if crate::is_terminal_auth_failure(message) {
+        let inactive = crate::terminal_auth_failure_code(message)
+            .map(|code| code == "terminal_inactive")
+            .unwrap_or_else(|| lower.contains("terminal is inactive"));
+        Some(if inactive {
+            TERMINAL_INACTIVE
+        } else {
+            TERMINAL_AUTH_FAILED
+        })
+    } else if lower.starts_with("supabase not configured") {
+        Some(SUPABASE_NOT_CONFIGURED)
+    } else if lower.contains("database is locked")
+        || lower.contains("database table is locked")
+        || lower.contains("database is busy")
+    {
+        Some(DATABASE_BUSY)
+    } else if lower.starts_with("cannot reach admin dashboard")
+        || lower.starts_with("network error communicating with")
+        || (lower.starts_with("connection to ") && lower.ends_with(" timed out"))
+    {
+        Some(ADMIN_UNREACHABLE)
+    } else {
+        None
+    }
+}
+
+pub fn for_message(message: &str) -> Option<&'static Remediation> {
+    code_for_message(message).and_then(lookup)
+}
+
+/// Add `remediation` to a `{ success: false, error }` style response when
+/// the message has one. Leaves other responses untouched.
+pub fn attach(response: &mut Value, message: &str) {
+    let Some(remediation) = for_message(message) else {
+        return;
+    };
+    if let Some(map) = response.as_object_mut() {
+        map.insert("remediation".to_string(), to_json(remediation));
+    }
+}
+
+pub fn to_json(remediation: &Remediation) -> Value {
+    serde_json::to_value(remediation).unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// One message per rule in `code_for_message`, plus the codes callers
+    /// use directly. A new code must be added here and to `REGISTRY`.
+    const EMITTED: &[(&str, &str)] = &[
+        (
+            "Terminal not configured: missing API key",
+            TERMINAL_NOT_CONFIGURED,
+        ),
+        (
+            "Terminal not configured: missing terminal_id",
+            TERMINAL_NOT_CONFIGURED,
+        ),
+        (
+            "pos_api_key credential not configured",
+            TERMINAL_NOT_CONFIGURED,
+        ),
+        ("Admin URL not configured", TERMINAL_NOT_CONFIGURED),
+        (
+            "circuit_open: Cannot reach admin dashboard after 5 consecutive failures; retrying in 30s",
+            SYNC_CIRCUIT_OPEN,
+        ),
+        ("API key is invalid or expired", TERMINAL_AUTH_FAILED),
+        (
+            r#"Terminal is inactive (HTTP 401): {"success":false,"code":"terminal_inactive"}"#,
+            TERMINAL_INACTIVE,
+        ),
+        ("Supabase not configured: missing URL", SUPABASE_NOT_CONFIGURED),
+        ("database is locked", DATABASE_BUSY),
+        (
+            "Cannot reach admin dashboard at https://admin",
+            ADMIN_UNREACHABLE,
+        ),
+        ("Connection to https://admin timed out", ADMIN_UNREACHABLE),
+    ];
+
+    /// Codes passed to `lookup` by name: print jobs, `PosError` and the
+    /// sync status payload.
+    const DIRECT: &[&str] = &[
+        PRINTER_JOB_FAILED,
+        TERMINAL_AUTH_FAILED,
+        TERMINAL_INACTIVE,
+        TERMINAL_NOT_CONFIGURED,
+        SYNC_CIRCUIT_OPEN,
+    ];
+
+    #[test]
+    fn every_emitted_code_has_a_registry_entry() {
+        for (message, code) in EMITTED {
+            assert_eq!(code_for_message(message), Some(*code), "{message}");
+            assert!(lookup(code).is_some(), "{code} has no remediation entry");
+        }
+        for code in DIRECT {
+            assert!(lookup(code).is_some(), "{code} has no remediation entry");
+        }
+        let emitted: HashSet<&str> = EMITTED
+            .iter()
+            .map(|(_, code)| *code)
+            .chain(DIRECT.iter().copied())
+            .collect();
+        let mut seen = HashSet::new();
+        for entry in REGISTRY {
+            assert!(seen.insert(entry.code), "duplicate entry {}", entry.code);
+            assert!(
+                emitted.contains(entry.code),
+                "{} is never emitted",
+                entry.code
+            );
+            assert!(entry.message_key.starts_with("errors.remediation."));
+        }
+    }
+
+    #[test]
+    fn unknown_messages_get_no_hint_and_hints_serialize_for_the_renderer() {
+        assert_eq!(for_message("Order not found: ord-1"), None);
+        let mut response = serde_json::json!({ "success": false, "error": "boom" });
+        attach(&mut response, "boom");
+        assert!(response.get("remediation").is_none());
+
+        attach(&mut response, "Terminal not configured: missing admin URL");
+        assert_eq!(
+            response["remediation"],
+            serde_json::json!({
+                "code": "TERMINAL_NOT_CONFIGURED",
+                "messageKey": "errors.remediation.terminalNotConfigured",
+                "action": "open_onboarding"
+            })
+        );
+    }
+}
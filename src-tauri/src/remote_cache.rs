@@ -0,0 +1,284 @@
+//! Read-through cache for the admin GET proxies (`sync_fetch_suppliers`,
+//! `sync_fetch_tables`, `sync_fetch_reservations`, `sync_fetch_analytics`,
+//! `sync_fetch_rooms`).
+//!
+//! Responses are stored in `remote_cache` keyed by path plus normalized
+//! query, with a TTL per resource. A fresh entry is served without touching
+//! the network; a stale one is served immediately while the proxy refreshes
+//! it in the background, and any entry is served when the admin API cannot
+//! be reached. Served responses carry `fromCache: true` and `cachedAt`.
+//!
+//! The table is bounded by entry count and total body size; the least
+//! recently read entries are evicted first. Mutations invalidate by path
+//! prefix.
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+
+pub const MAX_ENTRIES: i64 = 200;
+pub const MAX_TOTAL_BYTES: i64 = 8 * 1024 * 1024;
+
+/// Resource name for a cache key: the first segment after `/api/pos/`.
+pub fn resource_for_path(path: &str) -> String {
+    let base = path.split('?').next().unwrap_or_default();
+    base.strip_prefix("/api/pos/")
+        .unwrap_or(base)
+        .split('/')
+        .find(|segment| !segment.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// How long a cached response counts as fresh.
+pub fn ttl_secs(resource: &str) -> i64 {
+    match resource {
+        "suppliers" => 30 * 60,
+        "analytics" => 5 * 60,
+        "tables" | "reservations" | "rooms" => 60,
+        _ => 2 * 60,
+    }
+}
+
+/// Cache key for a full request path: the path without a trailing slash and
+/// the query pairs sorted, so option order and empty filters do not split
+/// the cache.
+pub fn cache_key(full_path: &str) -> String {
+    let (base, query) = full_path.split_once('?').unwrap_or((full_path, ""));
+    let base = match base.trim_end_matches('/') {
+        "" => "/",
+        trimmed => trimmed,
+    };
+    let mut pairs: Vec<(String, String)> = url::form_urlencoded::parse(query.as_bytes())
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if pairs.is_empty() {
+        return base.to_string();
+    }
+    pairs.sort();
+    let serialized = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .finish();
+    format!("{base}?{serialized}")
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedResponse {
+    pub body: Value,
+    pub cached_at: String,
+    pub fresh: bool,
+}
+
+/// Cached response for `key`, if any. Marks the entry as read for LRU.
+pub fn lookup(
+    conn: &Connection,
+    key: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<CachedResponse>, String> {
+    let row: Option<(String, String, String)> = conn
+        .query_row(
+            "SELECT body, cached_at, expires_at FROM remote_cache WHERE cache_key = ?1",
+            params![key],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| format!("read remote cache: {e}"))?;
+    let Some((body, cached_at, expires_at)) = row else {
+        return Ok(None);
+    };
+    let Ok(body) = serde_json::from_str::<Value>(&body) else {
+        conn.execute(
+            "DELETE FROM remote_cache WHERE cache_key = ?1",
+            params![key],
+        )
+        .map_err(|e| format!("drop unreadable remote cache entry: {e}"))?;
+        return Ok(None);
+    };
+    conn.execute(
+        "UPDATE remote_cache SET last_accessed_at = ?2 WHERE cache_key = ?1",
+        params![key, timestamp(now)],
+    )
+    .map_err(|e| format!("touch remote cache entry: {e}"))?;
+    let fresh = DateTime::parse_from_rfc3339(&expires_at)
+        .map(|expires| now < expires.with_timezone(&Utc))
+        .unwrap_or(false);
+    Ok(Some(CachedResponse {
+        body,
+        cached_at,
+        fresh,
+    }))
+}
+
+/// Store a response under `key` and evict down to the size bounds.
+pub fn store(conn: &Connection, key: &str, body: &Value, now: DateTime<Utc>) -> Result<(), String> {
+    let path = key.split('?').next().unwrap_or(key);
+    let resource = resource_for_path(path);
+    let body = body.to_string();
+    let expires_at = now + Duration::seconds(ttl_secs(&resource));
+    conn.execute(
+        "INSERT INTO remote_cache (
+             cache_key, path, resource, body, size_bytes, cached_at, expires_at, last_accessed_at
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?6)
+         ON CONFLICT(cache_key) DO UPDATE SET
+             body = excluded.body,
+             size_bytes = excluded.size_bytes,
+             cached_at = excluded.cached_at,
+             expires_at = excluded.expires_at,
+             last_accessed_at = excluded.last_accessed_at",
+        params![
+            key,
+            path,
+            resource,
+            body,
+            body.len() as i64,
+            timestamp(now),
+            timestamp(expires_at),
+        ],
+    )
+    .map_err(|e| format!("write remote cache: {e}"))?;
+    evict(conn, MAX_ENTRIES, MAX_TOTAL_BYTES)?;
+    Ok(())
+}
+
+/// Drop least recently read entries until both bounds hold. Returns how
+/// many entries were evicted.
+fn evict(conn: &Connection, max_entries: i64, max_bytes: i64) -> Result<usize, String> {
+    let mut evicted = 0;
+    loop {
+        let (count, bytes): (i64, i64) = conn
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(size_bytes), 0) FROM remote_cache",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| format!("measure remote cache: {e}"))?;
+        // Always keep the newest entry, even when it alone exceeds the byte
+        // bound.
+        if count <= 1 || (count <= max_entries && bytes <= max_bytes) {
+            return Ok(evicted);
+        }
+        conn.execute(
+            "DELETE FROM remote_cache WHERE cache_key = (
+                 SELECT cache_key FROM remote_cache
+                 ORDER BY last_accessed_at ASC, cached_at ASC
+                 LIMIT 1
+             )",
+            [],
+        )
+        .map_err(|e| format!("evict remote cache entry: {e}"))?;
+        evicted += 1;
+    }
+}
+
+/// Drop every entry whose path starts with `prefix`. Returns how many were
+/// removed.
+pub fn invalidate_prefix(conn: &Connection, prefix: &str) -> Result<usize, String> {
+    conn.execute(
+        "DELETE FROM remote_cache WHERE substr(path, 1, length(?1)) = ?1",
+        params![prefix],
+    )
+    .map_err(|e| format!("invalidate remote cache: {e}"))
+}
+
+/// Tag a proxy response with where it came from. Non-object responses are
+/// wrapped as `{ success, data }` so the marker has somewhere to go.
+pub fn mark(body: Value, cached_at: Option<&str>, stale: bool) -> Value {
+    let mut body = match body {
+        Value::Object(map) => Value::Object(map),
+        other => serde_json::json!({ "success": true, "data": other }),
+    };
+    if let Some(map) = body.as_object_mut() {
+        map.insert("fromCache".to_string(), Value::Bool(cached_at.is_some()));
+        if let Some(cached_at) = cached_at {
+            map.insert("cachedAt".to_string(), Value::String(cached_at.to_string()));
+            map.insert("stale".to_string(), Value::Bool(stale));
+        }
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    #[test]
+    fn cache_key_normalizes_query_order_and_empty_values() {
+        assert_eq!(
+            cache_key("/api/pos/suppliers/?search=&b=2&a=1"),
+            cache_key("/api/pos/suppliers?a=1&b=2")
+        );
+        assert_eq!(cache_key("/api/pos/suppliers"), "/api/pos/suppliers");
+        assert_eq!(resource_for_path("/api/pos/rooms/room-1?x=1"), "rooms");
+    }
+
+    #[test]
+    fn entries_go_stale_after_their_ttl() {
+        let conn = test_conn();
+        let now = Utc::now();
+        let key = cache_key("/api/pos/rooms");
+        store(&conn, &key, &json!({ "rooms": [] }), now).unwrap();
+
+        let hit = lookup(&conn, &key, now + Duration::seconds(30))
+            .unwrap()
+            .unwrap();
+        assert!(hit.fresh);
+        assert_eq!(hit.body, json!({ "rooms": [] }));
+        assert_eq!(hit.cached_at, timestamp(now));
+
+        let stale = lookup(&conn, &key, now + Duration::seconds(61))
+            .unwrap()
+            .unwrap();
+        assert!(!stale.fresh);
+
+        let marked = mark(stale.body, Some(&stale.cached_at), true);
+        assert_eq!(marked["fromCache"], true);
+        assert_eq!(marked["stale"], true);
+        assert_eq!(mark(json!([1]), None, false)["data"], json!([1]));
+    }
+
+    #[test]
+    fn eviction_drops_least_recently_read_and_invalidation_matches_prefix() {
+        let conn = test_conn();
+        let start = Utc::now();
+        for (offset, path) in ["/api/pos/rooms", "/api/pos/suppliers", "/api/pos/tables"]
+            .iter()
+            .enumerate()
+        {
+            store(
+                &conn,
+                path,
+                &json!({ "path": path }),
+                start + Duration::seconds(offset as i64),
+            )
+            .unwrap();
+        }
+        // Reading rooms makes suppliers the least recently used entry.
+        lookup(&conn, "/api/pos/rooms", start + Duration::seconds(10)).unwrap();
+        assert_eq!(evict(&conn, 2, MAX_TOTAL_BYTES).unwrap(), 1);
+        assert!(lookup(&conn, "/api/pos/suppliers", start)
+            .unwrap()
+            .is_none());
+
+        store(
+            &conn,
+            &cache_key("/api/pos/rooms?floor=1"),
+            &json!({}),
+            start,
+        )
+        .unwrap();
+        assert_eq!(invalidate_prefix(&conn, "/api/pos/rooms").unwrap(), 2);
+        assert!(lookup(&conn, "/api/pos/tables", start).unwrap().is_some());
+    }
+}
@@ -0,0 +1,624 @@
+//! Shared exporter for report tables.
+//!
+//! Every exportable report is reduced to a [`ReportTable`]: column
+//! definitions plus one row of JSON cells per line. `reports_export` builds
+//! the table for the requested report and hands it to [`write_export`], so
+//! file naming, encoding and number formatting are the same whichever report
+//! is exported. Files land in `exports/` next to the database (the app data
+//! directory) as `<report>_<branch>_<range>.<ext>`; re-exporting the same
+//! report and range replaces the earlier file.
+//!
+//! CSV starts with a UTF-8 BOM so spreadsheet apps pick the right encoding.
+//! When the terminal language writes decimals with a comma (`12,50`), cells
+//! are separated with `;`, which is what spreadsheet apps in those locales
+//! expect. XLSX keeps numbers numeric and JSON carries the raw cell values,
+//! so neither is localised.
+
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::db::DbState;
+
+pub const EXPORTS_DIR_NAME: &str = "exports";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnKind {
+    Text,
+    Integer,
+    Decimal,
+    Money,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    /// Field read from a report row object by [`ReportTable::from_objects`].
+    pub key: &'static str,
+    /// Header written to the file.
+    pub label: &'static str,
+    pub kind: ColumnKind,
+}
+
+impl Column {
+    pub const fn text(key: &'static str, label: &'static str) -> Self {
+        Self {
+            key,
+            label,
+            kind: ColumnKind::Text,
+        }
+    }
+
+    pub const fn integer(key: &'static str, label: &'static str) -> Self {
+        Self {
+            key,
+            label,
+            kind: ColumnKind::Integer,
+        }
+    }
+
+    pub const fn decimal(key: &'static str, label: &'static str) -> Self {
+        Self {
+            key,
+            label,
+            kind: ColumnKind::Decimal,
+        }
+    }
+
+    pub const fn money(key: &'static str, label: &'static str) -> Self {
+        Self {
+            key,
+            label,
+            kind: ColumnKind::Money,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReportTable {
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl ReportTable {
+    pub fn new(columns: &[Column]) -> Self {
+        Self {
+            columns: columns.to_vec(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Table whose rows are the given report objects, one cell per column
+    /// `key`. Missing fields become empty cells.
+    pub fn from_objects<'a>(
+        columns: &[Column],
+        objects: impl IntoIterator<Item = &'a Value>,
+    ) -> Self {
+        let mut table = Self::new(columns);
+        for object in objects {
+            let row = table
+                .columns
+                .iter()
+                .map(|column| object.get(column.key).cloned().unwrap_or(Value::Null))
+                .collect();
+            table.rows.push(row);
+        }
+        table
+    }
+
+    pub fn push(&mut self, row: Vec<Value>) {
+        debug_assert_eq!(row.len(), self.columns.len());
+        self.rows.push(row);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn parse(raw: Option<&str>) -> Result<Self, String> {
+        match raw.map(str::trim).map(str::to_ascii_lowercase).as_deref() {
+            None | Some("") | Some("csv") => Ok(Self::Csv),
+            Some("json") => Ok(Self::Json),
+            Some("xlsx") => Ok(Self::Xlsx),
+            Some(other) => Err(format!("Unsupported export format: {other}")),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Xlsx => "xlsx",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Prefix CSV output with a UTF-8 byte order mark.
+    pub bom: bool,
+    /// Write decimals as `12,50` and separate CSV cells with `;`.
+    pub decimal_comma: bool,
+}
+
+impl ExportOptions {
+    /// Options for files written for the terminal's `language`.
+    pub fn for_language(language: &str) -> Self {
+        Self {
+            bom: true,
+            decimal_comma: crate::receipt_renderer::language_uses_decimal_comma(language),
+        }
+    }
+
+    fn delimiter(self) -> char {
+        if self.decimal_comma {
+            ';'
+        } else {
+            ','
+        }
+    }
+}
+
+/// Report, branch and range an export is named after.
+#[derive(Debug, Clone, Copy)]
+pub struct ExportName<'a> {
+    pub report: &'a str,
+    pub branch_id: &'a str,
+    pub range: &'a str,
+}
+
+impl ExportName<'_> {
+    /// `<report>_<branch>_<range>.<ext>`, with an empty branch written as
+    /// `all` and anything but ASCII letters, digits, `-` and `_` replaced.
+    pub fn file_name(&self, format: ExportFormat) -> String {
+        let branch = if self.branch_id.trim().is_empty() {
+            "all"
+        } else {
+            self.branch_id
+        };
+        format!(
+            "{}_{}_{}.{}",
+            file_name_part(self.report),
+            file_name_part(branch),
+            file_name_part(self.range),
+            format.extension()
+        )
+    }
+}
+
+fn file_name_part(raw: &str) -> String {
+    raw.trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// `from` alone for a single day, otherwise `from_to`.
+pub fn range_label(from: &str, to: &str) -> String {
+    if from == to {
+        from.to_string()
+    } else {
+        format!("{from}_{to}")
+    }
+}
+
+fn number_text(value: &Value, kind: ColumnKind, options: &ExportOptions) -> String {
+    let text = match (kind, value) {
+        (ColumnKind::Money, Value::Number(n)) => format!("{:.2}", n.as_f64().unwrap_or(0.0)),
+        (ColumnKind::Integer, Value::Number(n)) => n
+            .as_i64()
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| format!("{}", n.as_f64().unwrap_or(0.0).round())),
+        (_, Value::Number(n)) => n
+            .as_f64()
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| n.to_string()),
+        _ => return String::new(),
+    };
+    if options.decimal_comma {
+        text.replace('.', ",")
+    } else {
+        text
+    }
+}
+
+fn cell_text(value: &Value, kind: ColumnKind, options: &ExportOptions) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Bool(flag) => flag.to_string(),
+        Value::Number(_) => number_text(value, kind, options),
+        other => other.to_string(),
+    }
+}
+
+fn csv_cell(text: &str, delimiter: char) -> String {
+    if text.contains(delimiter) || text.contains(['"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn render_csv(table: &ReportTable, options: &ExportOptions) -> String {
+    let delimiter = options.delimiter();
+    let separator = delimiter.to_string();
+    let mut out = String::new();
+    if options.bom {
+        out.push('\u{feff}');
+    }
+    let header: Vec<String> = table
+        .columns
+        .iter()
+        .map(|column| csv_cell(column.label, delimiter))
+        .collect();
+    out.push_str(&header.join(&separator));
+    out.push('\n');
+    for row in &table.rows {
+        let cells: Vec<String> = table
+            .columns
+            .iter()
+            .zip(row)
+            .map(|(column, value)| csv_cell(&cell_text(value, column.kind, options), delimiter))
+            .collect();
+        out.push_str(&cells.join(&separator));
+        out.push('\n');
+    }
+    out
+}
+
+pub fn render_json(table: &ReportTable, name: &ExportName<'_>) -> Value {
+    let rows: Vec<Value> = table
+        .rows
+        .iter()
+        .map(|row| {
+            let object: Map<String, Value> = table
+                .columns
+                .iter()
+                .zip(row)
+                .map(|(column, value)| (column.label.to_string(), value.clone()))
+                .collect();
+            Value::Object(object)
+        })
+        .collect();
+    json!({
+        "report": name.report,
+        "branchId": name.branch_id,
+        "range": name.range,
+        "columns": table
+            .columns
+            .iter()
+            .map(|column| json!({ "key": column.label, "kind": column.kind }))
+            .collect::<Vec<_>>(),
+        "rows": rows,
+    })
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Spreadsheet column letters for a zero-based index (`0` -> `A`, `26` -> `AA`).
+fn column_letters(mut index: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap_or_default()
+}
+
+fn xlsx_cell(reference: &str, value: &Value, kind: ColumnKind) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Number(n) if kind != ColumnKind::Text => {
+            let number = match kind {
+                ColumnKind::Money => format!("{:.2}", n.as_f64().unwrap_or(0.0)),
+                _ => n.to_string(),
+            };
+            format!("<c r=\"{reference}\"><v>{number}</v></c>")
+        }
+        other => {
+            let text = match other {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            format!(
+                "<c r=\"{reference}\" t=\"inlineStr\"><is><t xml:space=\"preserve\">{}</t></is></c>",
+                xml_escape(&text)
+            )
+        }
+    }
+}
+
+fn xlsx_sheet(table: &ReportTable) -> String {
+    let mut sheet = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\
+         <worksheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\"><sheetData>",
+    );
+    let header = table
+        .columns
+        .iter()
+        .map(|column| Value::String(column.label.to_string()))
+        .collect::<Vec<_>>();
+    let header_kinds = vec![ColumnKind::Text; table.columns.len()];
+    let body_kinds: Vec<ColumnKind> = table.columns.iter().map(|column| column.kind).collect();
+    let lines = std::iter::once((&header, &header_kinds))
+        .chain(table.rows.iter().map(|row| (row, &body_kinds)));
+    for (row_index, (row, kinds)) in lines.enumerate() {
+        let row_number = row_index + 1;
+        sheet.push_str(&format!("<row r=\"{row_number}\">"));
+        for (column_index, (value, kind)) in row.iter().zip(kinds).enumerate() {
+            let reference = format!("{}{row_number}", column_letters(column_index));
+            sheet.push_str(&xlsx_cell(&reference, value, *kind));
+        }
+        sheet.push_str("</row>");
+    }
+    sheet.push_str("</sheetData></worksheet>");
+    sheet
+}
+
+/// Minimal single-sheet workbook using inline strings, which every
+/// spreadsheet app reads without a shared-strings part or styles.
+pub fn render_xlsx(table: &ReportTable, sheet_name: &str) -> Result<Vec<u8>, String> {
+    let sheet_name: String = xml_escape(sheet_name).chars().take(31).collect();
+    let parts = [
+        (
+            "[Content_Types].xml",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\
+             <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+             <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+             <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+             <Override PartName=\"/xl/workbook.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml\"/>\
+             <Override PartName=\"/xl/worksheets/sheet1.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml\"/>\
+             </Types>"
+                .to_string(),
+        ),
+        (
+            "_rels/.rels",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+             <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" Target=\"xl/workbook.xml\"/>\
+             </Relationships>"
+                .to_string(),
+        ),
+        (
+            "xl/workbook.xml",
+            format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\
+                 <workbook xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" \
+                 xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">\
+                 <sheets><sheet name=\"{sheet_name}\" sheetId=\"1\" r:id=\"rId1\"/></sheets></workbook>"
+            ),
+        ),
+        (
+            "xl/_rels/workbook.xml.rels",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+             <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet\" Target=\"worksheets/sheet1.xml\"/>\
+             </Relationships>"
+                .to_string(),
+        ),
+        ("xl/worksheets/sheet1.xml", xlsx_sheet(table)),
+    ];
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let zip_options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for (path, content) in parts {
+        zip.start_file(path, zip_options)
+            .map_err(|e| format!("start xlsx part {path}: {e}"))?;
+        zip.write_all(content.as_bytes())
+            .map_err(|e| format!("write xlsx part {path}: {e}"))?;
+    }
+    zip.finish()
+        .map(Cursor::into_inner)
+        .map_err(|e| format!("finish xlsx: {e}"))
+}
+
+pub fn exports_dir(db: &DbState) -> Result<PathBuf, String> {
+    db.db_path
+        .parent()
+        .map(|dir| dir.join(EXPORTS_DIR_NAME))
+        .ok_or_else(|| "database path does not have a parent directory".to_string())
+}
+
+/// Render `table` as `format` and write it into `dir`. Returns the file's
+/// name, path, size and row count.
+pub fn write_export(
+    dir: &Path,
+    table: &ReportTable,
+    name: &ExportName<'_>,
+    format: ExportFormat,
+    options: &ExportOptions,
+) -> Result<Value, String> {
+    let bytes = match format {
+        ExportFormat::Csv => render_csv(table, options).into_bytes(),
+        ExportFormat::Json => serde_json::to_vec_pretty(&render_json(table, name))
+            .map_err(|e| format!("serialize export: {e}"))?,
+        ExportFormat::Xlsx => render_xlsx(table, name.report)?,
+    };
+    fs::create_dir_all(dir).map_err(|e| format!("create exports directory: {e}"))?;
+    let file_name = name.file_name(format);
+    let path = dir.join(&file_name);
+    // Write next to the target and rename so a half-written file never
+    // shows up in `exports_list`.
+    let temp_path = dir.join(format!(".{file_name}.tmp"));
+    fs::write(&temp_path, &bytes).map_err(|e| format!("write export: {e}"))?;
+    fs::rename(&temp_path, &path).map_err(|e| format!("finalize export: {e}"))?;
+    Ok(json!({
+        "fileName": file_name,
+        "path": path.to_string_lossy(),
+        "format": format.extension(),
+        "sizeBytes": bytes.len(),
+        "rowCount": table.rows.len(),
+    }))
+}
+
+/// Files in `dir`, newest first. A missing directory lists as empty.
+pub fn list_exports(dir: &Path) -> Result<Vec<Value>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("read exports directory: {e}")),
+    };
+    let mut files: Vec<(Option<DateTime<Utc>>, Value)> = Vec::new();
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() || file_name.starts_with('.') {
+            continue;
+        }
+        let modified = metadata.modified().ok().map(DateTime::<Utc>::from);
+        let format = Path::new(&file_name)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string());
+        files.push((
+            modified,
+            json!({
+                "fileName": file_name,
+                "path": entry.path().to_string_lossy(),
+                "format": format,
+                "sizeBytes": metadata.len(),
+                "modifiedAt": modified.map(|at| at.to_rfc3339()),
+            }),
+        ));
+    }
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    Ok(files.into_iter().map(|(_, file)| file).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    const COLUMNS: &[Column] = &[
+        Column::text("name", "name"),
+        Column::integer("count", "count"),
+        Column::money("total", "total"),
+        Column::decimal("share", "share_percent"),
+    ];
+
+    fn sample() -> ReportTable {
+        let rows = [
+            json!({ "name": "Café, \"bar\"", "count": 2, "total": 12.5, "share": 62.5 }),
+            json!({ "name": "Kiosk", "count": 1, "total": 7.0 }),
+        ];
+        ReportTable::from_objects(COLUMNS, rows.iter())
+    }
+
+    #[test]
+    fn csv_follows_locale_separator_and_bom() {
+        let table = sample();
+        let plain = render_csv(&table, &ExportOptions::default());
+        assert_eq!(
+            plain,
+            "name,count,total,share_percent\n\"Café, \"\"bar\"\"\",2,12.50,62.5\nKiosk,1,7.00,\n"
+        );
+
+        let greek = render_csv(&table, &ExportOptions::for_language("el"));
+        assert!(greek.starts_with('\u{feff}'));
+        let mut lines = greek.trim_start_matches('\u{feff}').lines();
+        assert_eq!(lines.next(), Some("name;count;total;share_percent"));
+        assert_eq!(lines.next(), Some("\"Café, \"\"bar\"\"\";2;12,50;62,5"));
+        assert_eq!(lines.next(), Some("Kiosk;1;7,00;"));
+    }
+
+    #[test]
+    fn file_names_are_sanitized_and_scoped_to_branch_and_range() {
+        let name = ExportName {
+            report: "daily_summary",
+            branch_id: "",
+            range: &range_label("2026-05-04", "2026-05-04"),
+        };
+        assert_eq!(
+            name.file_name(ExportFormat::Csv),
+            "daily_summary_all_2026-05-04.csv"
+        );
+        let name = ExportName {
+            report: "labor",
+            branch_id: "branch/../1",
+            range: &range_label("2026-05-01", "2026-05-07"),
+        };
+        assert_eq!(
+            name.file_name(ExportFormat::Xlsx),
+            "labor_branch----1_2026-05-01_2026-05-07.xlsx"
+        );
+        assert_eq!(ExportFormat::parse(Some(" XLSX ")), Ok(ExportFormat::Xlsx));
+        assert!(ExportFormat::parse(Some("pdf")).is_err());
+    }
+
+    #[test]
+    fn xlsx_is_a_workbook_with_numeric_cells() {
+        let bytes = render_xlsx(&sample(), "daily_summary").unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut sheet = String::new();
+        archive
+            .by_name("xl/worksheets/sheet1.xml")
+            .unwrap()
+            .read_to_string(&mut sheet)
+            .unwrap();
+        assert!(sheet.contains("<c r=\"C2\"><v>12.50</v></c>"));
+        assert!(sheet.contains("Café, &quot;bar&quot;"));
+        assert!(!sheet.contains("D3"), "missing cells are left out");
+        assert!(archive.by_name("[Content_Types].xml").is_ok());
+        assert_eq!(column_letters(0), "A");
+        assert_eq!(column_letters(27), "AB");
+    }
+
+    #[test]
+    fn written_exports_are_listed_newest_first() {
+        let dir = std::env::temp_dir().join(format!("pos-exports-{}", uuid::Uuid::new_v4()));
+        assert!(list_exports(&dir).unwrap().is_empty());
+        let name = ExportName {
+            report: "zreport",
+            branch_id: "branch-1",
+            range: "2026-05-04",
+        };
+        let written = write_export(
+            &dir,
+            &sample(),
+            &name,
+            ExportFormat::Json,
+            &ExportOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(written["fileName"], "zreport_branch-1_2026-05-04.json");
+        assert_eq!(written["rowCount"], 2);
+        let content: Value = serde_json::from_slice(
+            &fs::read(dir.join("zreport_branch-1_2026-05-04.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(content["rows"][1]["total"], 7.0);
+
+        let listed = list_exports(&dir).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0]["format"], "json");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
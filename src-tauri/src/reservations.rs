@@ -0,0 +1,746 @@
+//! Local reservations cache for the hostess screen.
+//!
+//! Reservations used to be read straight from the admin API, so the screen
+//! went blank whenever the connection dropped. They are now kept in the
+//! `reservations` table, refreshed by the sync cycle (at most once a minute)
+//! and on demand. Check-ins and no-shows are written locally first and queued
+//! to `parity_sync_queue` under the `hospitality` module with the `manual`
+//! conflict strategy, the same path `offline_reservation_update` uses, so a
+//! server-side 409 lands in `conflict_audit_log` for operator review.
+//!
+//! A refresh never overwrites a reservation that still has a local change in
+//! the queue. When the server copy was edited after the local change was
+//! made, the remote version is logged to `conflict_audit_log` as a `manual`
+//! conflict and the local row is kept until the queue drains.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::db::DbState;
+use crate::sync_queue;
+
+pub const TABLE: &str = "reservations";
+pub const MODULE_TYPE: &str = "hospitality";
+pub const CONFLICT_STRATEGY: &str = "manual";
+pub const UPDATED_EVENT: &str = "reservation_updated";
+
+pub const STATUS_SEATED: &str = "seated";
+pub const STATUS_NO_SHOW: &str = "no_show";
+/// Statuses that no longer count towards expected covers.
+const INACTIVE_STATUSES: &[&str] = &["cancelled", "no_show", "completed"];
+
+const LIST_PATH: &str = "/api/pos/reservations";
+const REFRESH_INTERVAL_MS: i64 = 60_000;
+static LAST_REFRESH_MS: AtomicI64 = AtomicI64::new(0);
+
+const DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+const SELECT_COLUMNS: &str = "id, organization_id, branch_id, reservation_number, customer_id,
+    customer_name, customer_phone, customer_email, party_size, reservation_datetime,
+    duration_minutes, table_id, status, special_requests, notes, order_id, seated_at,
+    no_show_at, remote_updated_at, version, synced_at, updated_at";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyOutcome {
+    Inserted,
+    Updated,
+    /// A local change is still queued; the remote copy was not applied.
+    KeptLocal,
+    /// As `KeptLocal`, and the remote copy was logged as a conflict.
+    Conflict,
+    /// No id or no usable date/time.
+    Skipped,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RefreshSummary {
+    pub inserted: usize,
+    pub updated: usize,
+    pub kept_local: usize,
+    pub conflicts: usize,
+    pub skipped: usize,
+}
+
+impl RefreshSummary {
+    fn record(&mut self, outcome: ApplyOutcome) {
+        match outcome {
+            ApplyOutcome::Inserted => self.inserted += 1,
+            ApplyOutcome::Updated => self.updated += 1,
+            ApplyOutcome::KeptLocal => self.kept_local += 1,
+            ApplyOutcome::Conflict => self.conflicts += 1,
+            ApplyOutcome::Skipped => self.skipped += 1,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "inserted": self.inserted,
+            "updated": self.updated,
+            "keptLocal": self.kept_local,
+            "conflicts": self.conflicts,
+            "skipped": self.skipped,
+        })
+    }
+}
+
+fn text(v: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| match v.get(*key)? {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+/// Reservation start as local wall-clock time. Accepts an RFC 3339
+/// timestamp, a naive `reservation_datetime`, or separate date and time
+/// fields.
+fn local_datetime(reservation: &Value) -> Option<String> {
+    let parse = |raw: &str| -> Option<NaiveDateTime> {
+        if let Ok(ts) = DateTime::parse_from_rfc3339(raw) {
+            return Some(ts.with_timezone(&Local).naive_local());
+        }
+        [
+            "%Y-%m-%dT%H:%M:%S%.f",
+            "%Y-%m-%dT%H:%M",
+            "%Y-%m-%d %H:%M:%S",
+        ]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok())
+    };
+    let parsed = text(
+        reservation,
+        &["reservation_datetime", "reservationDatetime"],
+    )
+    .and_then(|raw| parse(&raw))
+    .or_else(|| {
+        let date = text(reservation, &["reservation_date", "reservationDate"])?;
+        let time = text(reservation, &["reservation_time", "reservationTime"])?;
+        let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()?;
+        let time = NaiveTime::parse_from_str(&time, "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(&time, "%H:%M"))
+            .ok()?;
+        Some(date.and_time(time))
+    })?;
+    Some(parsed.format(DATETIME_FORMAT).to_string())
+}
+
+fn has_outstanding_queue(conn: &Connection, id: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS(
+            SELECT 1 FROM parity_sync_queue
+            WHERE table_name = ?1
+              AND record_id = ?2
+              AND status IN ('pending', 'processing', 'failed', 'conflict')
+        )",
+        params![TABLE, id],
+        |row| row.get::<_, bool>(0),
+    )
+    .map_err(|e| format!("query reservation queue: {e}"))
+}
+
+/// Insert or overwrite the local row from a reservation object in the admin
+/// API shape.
+fn upsert(
+    conn: &Connection,
+    id: &str,
+    datetime: &str,
+    reservation: &Value,
+    remote_updated_at: Option<&str>,
+    now: &str,
+) -> Result<usize, String> {
+    let party_size = reservation
+        .get("party_size")
+        .or_else(|| reservation.get("partySize"))
+        .and_then(Value::as_i64)
+        .unwrap_or(2);
+    let duration = reservation
+        .get("duration_minutes")
+        .or_else(|| reservation.get("durationMinutes"))
+        .and_then(Value::as_i64);
+    conn.execute(
+        "INSERT INTO reservations (
+            id, organization_id, branch_id, reservation_number, customer_id,
+            customer_name, customer_phone, customer_email, party_size,
+            reservation_datetime, duration_minutes, table_id, status,
+            special_requests, notes, order_id, seated_at, remote_updated_at,
+            version, payload_json, synced_at, updated_at
+         ) VALUES (
+            ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13,
+            ?14, ?15, ?16, ?17, ?18, 1, ?19, ?20, ?21
+         )
+         ON CONFLICT(id) DO UPDATE SET
+            organization_id = excluded.organization_id,
+            branch_id = excluded.branch_id,
+            reservation_number = excluded.reservation_number,
+            customer_id = excluded.customer_id,
+            customer_name = excluded.customer_name,
+            customer_phone = excluded.customer_phone,
+            customer_email = excluded.customer_email,
+            party_size = excluded.party_size,
+            reservation_datetime = excluded.reservation_datetime,
+            duration_minutes = excluded.duration_minutes,
+            table_id = excluded.table_id,
+            status = excluded.status,
+            special_requests = excluded.special_requests,
+            notes = excluded.notes,
+            order_id = COALESCE(excluded.order_id, reservations.order_id),
+            seated_at = COALESCE(excluded.seated_at, reservations.seated_at),
+            remote_updated_at = COALESCE(excluded.remote_updated_at, reservations.remote_updated_at),
+            conflict_remote_updated_at = NULL,
+            version = reservations.version + 1,
+            payload_json = excluded.payload_json,
+            synced_at = COALESCE(excluded.synced_at, reservations.synced_at),
+            updated_at = excluded.updated_at",
+        params![
+            id,
+            text(reservation, &["organization_id", "organizationId"]),
+            text(reservation, &["branch_id", "branchId"]),
+            text(reservation, &["reservation_number", "reservationNumber"]),
+            text(reservation, &["customer_id", "customerId"]),
+            text(reservation, &["customer_name", "customerName"]),
+            text(reservation, &["customer_phone", "customerPhone"]),
+            text(reservation, &["customer_email", "customerEmail"]),
+            party_size,
+            datetime,
+            duration,
+            text(reservation, &["table_id", "tableId"]),
+            text(reservation, &["status"]).unwrap_or_else(|| "pending".into()),
+            text(reservation, &["special_requests", "specialRequests"]),
+            text(reservation, &["notes"]),
+            text(reservation, &["order_id", "orderId"]),
+            text(reservation, &["seated_at", "seatedAt"]),
+            remote_updated_at,
+            reservation.to_string(),
+            remote_updated_at.map(|_| now),
+            now,
+        ],
+    )
+    .map_err(|e| format!("upsert reservation: {e}"))
+}
+
+/// Apply one reservation from an admin pull.
+pub fn apply_remote(
+    conn: &Connection,
+    reservation: &Value,
+    now: &str,
+) -> Result<ApplyOutcome, String> {
+    let Some(id) = text(reservation, &["id"]) else {
+        return Ok(ApplyOutcome::Skipped);
+    };
+    let Some(datetime) = local_datetime(reservation) else {
+        warn!(reservation_id = %id, "Skipping reservation without a usable date/time");
+        return Ok(ApplyOutcome::Skipped);
+    };
+    let remote_updated_at = text(reservation, &["updated_at", "updatedAt"]);
+
+    let local: Option<(Option<String>, Option<String>, i64)> = conn
+        .query_row(
+            "SELECT remote_updated_at, conflict_remote_updated_at, version
+             FROM reservations WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| format!("read local reservation: {e}"))?;
+
+    if let Some((base_updated_at, logged_conflict_at, version)) = local {
+        if has_outstanding_queue(conn, &id)? {
+            let remote_changed =
+                remote_updated_at.is_some() && remote_updated_at != base_updated_at;
+            if !remote_changed || remote_updated_at == logged_conflict_at {
+                return Ok(ApplyOutcome::KeptLocal);
+            }
+            sync_queue::log_conflict(
+                conn,
+                "UPDATE",
+                &id,
+                TABLE,
+                version,
+                reservation
+                    .get("version")
+                    .and_then(Value::as_i64)
+                    .unwrap_or(version + 1),
+                &reservation.to_string(),
+                CONFLICT_STRATEGY,
+                false,
+                false,
+            )?;
+            conn.execute(
+                "UPDATE reservations SET conflict_remote_updated_at = ?1 WHERE id = ?2",
+                params![remote_updated_at, id],
+            )
+            .map_err(|e| format!("record reservation conflict: {e}"))?;
+            warn!(
+                reservation_id = %id,
+                "Reservation changed remotely while a local change is queued; logged conflict"
+            );
+            return Ok(ApplyOutcome::Conflict);
+        }
+        upsert(
+            conn,
+            &id,
+            &datetime,
+            reservation,
+            remote_updated_at.as_deref(),
+            now,
+        )?;
+        return Ok(ApplyOutcome::Updated);
+    }
+
+    upsert(
+        conn,
+        &id,
+        &datetime,
+        reservation,
+        remote_updated_at.as_deref(),
+        now,
+    )?;
+    Ok(ApplyOutcome::Inserted)
+}
+
+/// Store a reservation created or edited on this terminal. Its change is
+/// already in the queue, so the row is written as-is.
+pub fn store_local(conn: &Connection, reservation: &Value) -> Result<(), String> {
+    let id = text(reservation, &["id"]).ok_or("Missing reservation id")?;
+    let datetime = local_datetime(reservation).ok_or("Missing reservation date/time")?;
+    upsert(
+        conn,
+        &id,
+        &datetime,
+        reservation,
+        None,
+        &Utc::now().to_rfc3339(),
+    )?;
+    Ok(())
+}
+
+/// Reservation lists come back bare, under `reservations`, or wrapped in
+/// `data`.
+fn extract_list(response: &Value) -> Vec<Value> {
+    let candidates = [
+        Some(response),
+        response.get("reservations"),
+        response.get("data"),
+        response.get("data").and_then(|d| d.get("reservations")),
+    ];
+    candidates
+        .into_iter()
+        .flatten()
+        .find_map(|v| v.as_array().cloned())
+        .unwrap_or_default()
+}
+
+pub fn apply_remote_list(conn: &Connection, response: &Value) -> Result<RefreshSummary, String> {
+    let now = Utc::now().to_rfc3339();
+    let mut summary = RefreshSummary::default();
+    for reservation in extract_list(response) {
+        summary.record(apply_remote(conn, &reservation, &now)?);
+    }
+    Ok(summary)
+}
+
+/// Pull reservations from the admin API into the local table.
+pub async fn refresh(db: &DbState) -> Result<RefreshSummary, String> {
+    let response = crate::admin_fetch(Some(db), LIST_PATH, "GET", None).await?;
+    LAST_REFRESH_MS.store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    let summary = db.write(|conn| {
+        conn.execute_batch("BEGIN IMMEDIATE")
+            .map_err(|e| format!("begin reservation refresh: {e}"))?;
+        match apply_remote_list(conn, &response) {
+            Ok(summary) => {
+                conn.execute_batch("COMMIT")
+                    .map_err(|e| format!("commit reservation refresh: {e}"))?;
+                Ok(summary)
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    })?;
+    if summary != RefreshSummary::default() {
+        info!(
+            inserted = summary.inserted,
+            updated = summary.updated,
+            kept_local = summary.kept_local,
+            conflicts = summary.conflicts,
+            "Reservations refreshed"
+        );
+    }
+    Ok(summary)
+}
+
+/// `refresh`, unless one ran within the last minute. Called from the sync
+/// cycle; failures are logged and never fail the cycle.
+pub async fn refresh_if_due(db: &DbState) {
+    let now_ms = Utc::now().timestamp_millis();
+    if now_ms - LAST_REFRESH_MS.load(Ordering::Relaxed) < REFRESH_INTERVAL_MS {
+        return;
+    }
+    LAST_REFRESH_MS.store(now_ms, Ordering::Relaxed);
+    if let Err(e) = refresh(db).await {
+        warn!(error = %e, "Reservation refresh failed");
+    }
+}
+
+fn row_to_json(row: &rusqlite::Row) -> rusqlite::Result<Value> {
+    Ok(json!({
+        "id": row.get::<_, String>(0)?,
+        "organization_id": row.get::<_, Option<String>>(1)?,
+        "branch_id": row.get::<_, Option<String>>(2)?,
+        "reservation_number": row.get::<_, Option<String>>(3)?,
+        "customer_id": row.get::<_, Option<String>>(4)?,
+        "customer_name": row.get::<_, Option<String>>(5)?,
+        "customer_phone": row.get::<_, Option<String>>(6)?,
+        "customer_email": row.get::<_, Option<String>>(7)?,
+        "party_size": row.get::<_, i64>(8)?,
+        "reservation_datetime": row.get::<_, String>(9)?,
+        "duration_minutes": row.get::<_, Option<i64>>(10)?,
+        "table_id": row.get::<_, Option<String>>(11)?,
+        "status": row.get::<_, String>(12)?,
+        "special_requests": row.get::<_, Option<String>>(13)?,
+        "notes": row.get::<_, Option<String>>(14)?,
+        "order_id": row.get::<_, Option<String>>(15)?,
+        "seated_at": row.get::<_, Option<String>>(16)?,
+        "no_show_at": row.get::<_, Option<String>>(17)?,
+        "remote_updated_at": row.get::<_, Option<String>>(18)?,
+        "version": row.get::<_, i64>(19)?,
+        "synced_at": row.get::<_, Option<String>>(20)?,
+        "updated_at": row.get::<_, String>(21)?,
+    }))
+}
+
+pub fn get(conn: &Connection, id: &str) -> Result<Option<Value>, String> {
+    conn.query_row(
+        &format!("SELECT {SELECT_COLUMNS} FROM reservations WHERE id = ?1"),
+        params![id],
+        row_to_json,
+    )
+    .optional()
+    .map_err(|e| format!("read reservation: {e}"))
+}
+
+/// Local `[from, to)` window, as `reservation_datetime` strings. `date`
+/// defaults to today; `from_time` / `to_time` (`HH:MM`) narrow the day.
+pub fn day_window(
+    date: Option<&str>,
+    from_time: Option<&str>,
+    to_time: Option<&str>,
+) -> Result<(String, String), String> {
+    let day = match date {
+        Some(raw) => NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date: {raw}"))?,
+        None => Local::now().date_naive(),
+    };
+    let time = |raw: &str| {
+        NaiveTime::parse_from_str(raw.trim(), "%H:%M").map_err(|_| format!("Invalid time: {raw}"))
+    };
+    let from = match from_time {
+        Some(raw) => day.and_time(time(raw)?),
+        None => day.and_time(NaiveTime::MIN),
+    };
+    let to = match to_time {
+        Some(raw) => day.and_time(time(raw)?),
+        None => (day + Duration::days(1)).and_time(NaiveTime::MIN),
+    };
+    if to <= from {
+        return Err("Time window end must be after its start".into());
+    }
+    Ok((
+        from.format(DATETIME_FORMAT).to_string(),
+        to.format(DATETIME_FORMAT).to_string(),
+    ))
+}
+
+pub fn list_window(
+    conn: &Connection,
+    branch_id: Option<&str>,
+    from: &str,
+    to: &str,
+) -> Result<Vec<Value>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM reservations
+             WHERE reservation_datetime >= ?1
+               AND reservation_datetime < ?2
+               AND (?3 IS NULL OR branch_id IS NULL OR branch_id = ?3)
+             ORDER BY reservation_datetime ASC, id ASC"
+        ))
+        .map_err(|e| format!("prepare reservation list: {e}"))?;
+    let rows = stmt
+        .query_map(params![from, to, branch_id], row_to_json)
+        .map_err(|e| format!("query reservation list: {e}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read reservation list: {e}"))
+}
+
+/// Expected covers per hour, skipping cancelled, no-show and completed
+/// reservations.
+pub fn covers_by_hour(reservations: &[Value]) -> Vec<Value> {
+    let mut hours: BTreeMap<String, (i64, i64)> = BTreeMap::new();
+    for reservation in reservations {
+        let status = reservation["status"].as_str().unwrap_or("");
+        if INACTIVE_STATUSES.contains(&status) {
+            continue;
+        }
+        let Some(hour) = reservation["reservation_datetime"]
+            .as_str()
+            .and_then(|dt| dt.get(11..13))
+        else {
+            continue;
+        };
+        let entry = hours.entry(format!("{hour}:00")).or_default();
+        entry.0 += reservation["party_size"].as_i64().unwrap_or(0);
+        entry.1 += 1;
+    }
+    hours
+        .into_iter()
+        .map(|(hour, (covers, count))| {
+            json!({ "hour": hour, "covers": covers, "reservations": count })
+        })
+        .collect()
+}
+
+/// Queue a local status change for the admin. The payload carries the
+/// server `updated_at` the change was based on so the server can reject it
+/// with a conflict when the reservation moved on in the meantime.
+fn queue_change(conn: &Connection, reservation: &Value, changes: Value) -> Result<String, String> {
+    let id = reservation["id"].as_str().unwrap_or_default();
+    let mut payload = changes;
+    if let Some(obj) = payload.as_object_mut() {
+        obj.insert("id".into(), json!(id));
+        obj.insert("updated_at".into(), reservation["updated_at"].clone());
+        obj.insert(
+            "expected_updated_at".into(),
+            reservation["remote_updated_at"].clone(),
+        );
+    }
+    sync_queue::enqueue_payload_item(
+        conn,
+        TABLE,
+        id,
+        "UPDATE",
+        &payload,
+        Some(0),
+        Some(MODULE_TYPE),
+        Some(CONFLICT_STRATEGY),
+        reservation["version"].as_i64(),
+    )
+}
+
+fn require(conn: &Connection, id: &str) -> Result<Value, String> {
+    get(conn, id)?.ok_or_else(|| format!("Reservation not found: {id}"))
+}
+
+/// Mark the guests as arrived. `table_id` and `order_id` are linked when
+/// given. Returns the updated reservation and the queue row id.
+pub fn check_in(
+    conn: &Connection,
+    id: &str,
+    table_id: Option<&str>,
+    order_id: Option<&str>,
+    now: &str,
+) -> Result<(Value, String), String> {
+    let current = require(conn, id)?;
+    let status = current["status"].as_str().unwrap_or("");
+    if status == STATUS_SEATED {
+        return Err("Reservation is already checked in".into());
+    }
+    if INACTIVE_STATUSES.contains(&status) {
+        return Err(format!("Cannot check in a reservation that is {status}"));
+    }
+    conn.execute(
+        "UPDATE reservations
+         SET status = ?1,
+             seated_at = ?2,
+             table_id = COALESCE(?3, table_id),
+             order_id = COALESCE(?4, order_id),
+             version = version + 1,
+             updated_at = ?2
+         WHERE id = ?5",
+        params![STATUS_SEATED, now, table_id, order_id, id],
+    )
+    .map_err(|e| format!("check in reservation: {e}"))?;
+    let updated = require(conn, id)?;
+    let queue_id = queue_change(
+        conn,
+        &updated,
+        json!({
+            "status": STATUS_SEATED,
+            "seated_at": now,
+            "table_id": updated["table_id"],
+            "order_id": updated["order_id"],
+        }),
+    )?;
+    Ok((updated, queue_id))
+}
+
+pub fn mark_no_show(conn: &Connection, id: &str, now: &str) -> Result<(Value, String), String> {
+    let current = require(conn, id)?;
+    let status = current["status"].as_str().unwrap_or("");
+    if status == STATUS_SEATED || INACTIVE_STATUSES.contains(&status) {
+        return Err(format!(
+            "Cannot mark a reservation that is {status} as no-show"
+        ));
+    }
+    conn.execute(
+        "UPDATE reservations
+         SET status = ?1, no_show_at = ?2, version = version + 1, updated_at = ?2
+         WHERE id = ?3",
+        params![STATUS_NO_SHOW, now, id],
+    )
+    .map_err(|e| format!("mark reservation no-show: {e}"))?;
+    let updated = require(conn, id)?;
+    let queue_id = queue_change(
+        conn,
+        &updated,
+        json!({ "status": STATUS_NO_SHOW, "no_show_at": now }),
+    )?;
+    Ok((updated, queue_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations_for_test(&conn);
+        sync_queue::create_tables(&conn).unwrap();
+        conn
+    }
+
+    fn remote(id: &str, time: &str, party: i64, status: &str, updated_at: &str) -> Value {
+        json!({
+            "id": id,
+            "customer_name": format!("Guest {id}"),
+            "party_size": party,
+            "reservation_date": "2026-03-01",
+            "reservation_time": time,
+            "status": status,
+            "updated_at": updated_at,
+        })
+    }
+
+    #[test]
+    fn window_listing_and_covers_per_hour() {
+        let conn = test_conn();
+        let list = json!({ "reservations": [
+            remote("r1", "19:00", 4, "confirmed", "t1"),
+            remote("r2", "19:30", 2, "confirmed", "t1"),
+            remote("r3", "20:15", 6, "cancelled", "t1"),
+            remote("r4", "21:00", 3, "pending", "t1"),
+            json!({ "id": "r5", "party_size": 2 }),
+        ]});
+        let summary = apply_remote_list(&conn, &list).unwrap();
+        assert_eq!(summary.inserted, 4);
+        assert_eq!(summary.skipped, 1);
+
+        let (from, to) = day_window(Some("2026-03-01"), None, None).unwrap();
+        let day = list_window(&conn, None, &from, &to).unwrap();
+        assert_eq!(day.len(), 4);
+        assert_eq!(day[0]["reservation_datetime"], "2026-03-01T19:00:00");
+
+        let covers = covers_by_hour(&day);
+        assert_eq!(
+            covers,
+            vec![
+                json!({ "hour": "19:00", "covers": 6, "reservations": 2 }),
+                json!({ "hour": "21:00", "covers": 3, "reservations": 1 }),
+            ]
+        );
+
+        let (from, to) = day_window(Some("2026-03-01"), Some("19:15"), Some("21:00")).unwrap();
+        let narrowed = list_window(&conn, None, &from, &to).unwrap();
+        let ids: Vec<&str> = narrowed.iter().map(|r| r["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec!["r2", "r3"]);
+
+        assert!(day_window(Some("2026-03-01"), Some("20:00"), Some("19:00")).is_err());
+    }
+
+    #[test]
+    fn check_in_and_no_show_queue_changes() {
+        let conn = test_conn();
+        apply_remote(&conn, &remote("r1", "19:00", 4, "confirmed", "t1"), "now").unwrap();
+        apply_remote(&conn, &remote("r2", "19:00", 2, "confirmed", "t1"), "now").unwrap();
+
+        let (seated, _) =
+            check_in(&conn, "r1", Some("table-7"), None, "2026-03-01T19:02:00Z").unwrap();
+        assert_eq!(seated["status"], STATUS_SEATED);
+        assert_eq!(seated["table_id"], "table-7");
+        assert!(check_in(&conn, "r1", None, None, "later").is_err());
+
+        let (no_show, _) = mark_no_show(&conn, "r2", "2026-03-01T19:30:00Z").unwrap();
+        assert_eq!(no_show["status"], STATUS_NO_SHOW);
+        assert!(check_in(&conn, "r2", None, None, "later").is_err());
+
+        let queued: Vec<(String, String, String, String)> = conn
+            .prepare(
+                "SELECT record_id, module_type, conflict_strategy, data
+                 FROM parity_sync_queue WHERE table_name = 'reservations'
+                 ORDER BY record_id",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(queued.len(), 2);
+        assert_eq!(queued[0].1, MODULE_TYPE);
+        assert_eq!(queued[0].2, CONFLICT_STRATEGY);
+        let data: Value = serde_json::from_str(&queued[0].3).unwrap();
+        assert_eq!(data["status"], STATUS_SEATED);
+        assert_eq!(data["table_id"], "table-7");
+        assert_eq!(data["expected_updated_at"], "t1");
+    }
+
+    #[test]
+    fn remote_edit_after_local_check_in_is_logged_not_applied() {
+        let conn = test_conn();
+        apply_remote(&conn, &remote("r1", "19:00", 4, "confirmed", "t1"), "now").unwrap();
+        check_in(&conn, "r1", None, None, "2026-03-01T19:02:00Z").unwrap();
+
+        // Same server copy as before the check-in: nothing to report.
+        let outcome =
+            apply_remote(&conn, &remote("r1", "19:00", 4, "confirmed", "t1"), "now").unwrap();
+        assert_eq!(outcome, ApplyOutcome::KeptLocal);
+
+        let edited = remote("r1", "19:00", 8, "confirmed", "t2");
+        assert_eq!(
+            apply_remote(&conn, &edited, "now").unwrap(),
+            ApplyOutcome::Conflict
+        );
+        assert_eq!(
+            apply_remote(&conn, &edited, "now").unwrap(),
+            ApplyOutcome::KeptLocal
+        );
+        let local = get(&conn, "r1").unwrap().unwrap();
+        assert_eq!(local["status"], STATUS_SEATED);
+        assert_eq!(local["party_size"], 4);
+
+        let (entity_type, resolution): (String, String) = conn
+            .query_row(
+                "SELECT entity_type, resolution FROM conflict_audit_log WHERE entity_id = 'r1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(entity_type, TABLE);
+        assert_eq!(resolution, CONFLICT_STRATEGY);
+
+        // Once the queue drains the server copy wins again.
+        conn.execute("DELETE FROM parity_sync_queue", []).unwrap();
+        assert_eq!(
+            apply_remote(&conn, &edited, "now").unwrap(),
+            ApplyOutcome::Updated
+        );
+        assert_eq!(get(&conn, "r1").unwrap().unwrap()["party_size"], 8);
+    }
+}
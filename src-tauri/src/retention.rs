@@ -0,0 +1,886 @@
+//! Data retention.
+//!
+//! Operational history is kept for a configurable number of days per
+//! category (`retention.*` settings). A nightly pass, or `retention_run_now`,
+//! first exports every row due for deletion into a gzip-compressed NDJSON
+//! file under `<app_data>/archives/` (one file per table per day), reads the
+//! file back to confirm the row count, and only then deletes. Each table is
+//! handled in its own transaction, so a failed export leaves it untouched
+//! and stops the pass. Nothing runs while free disk space is below
+//! [`MIN_FREE_BYTES`].
+//!
+//! The scheduler also prunes expired `idempotency_cache` responses on every
+//! tick; those are disposable and are not archived.
+
+use std::fs;
+use std::io::{BufRead, BufReader, BufWriter, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, Timelike, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::Emitter;
+use tracing::{info, warn};
+
+use crate::db::{self, DbState};
+use crate::idempotency;
+
+pub const SETTINGS_CATEGORY: &str = "retention";
+const ENABLED_KEY: &str = "enabled";
+const ORDERS_DAYS_KEY: &str = "orders_days";
+const PRINT_JOBS_DAYS_KEY: &str = "print_jobs_days";
+const AUDIT_LOG_DAYS_KEY: &str = "audit_log_days";
+const SYNC_HISTORY_DAYS_KEY: &str = "sync_history_days";
+const LAST_RUN_DATE_KEY: &str = "last_run_date";
+
+pub const ARCHIVES_DIR_NAME: &str = "archives";
+pub const PROGRESS_EVENT: &str = "retention_progress";
+
+pub const TRIGGER_SCHEDULED: &str = "scheduled";
+pub const TRIGGER_MANUAL: &str = "manual";
+
+/// Refuse to archive or delete with less free space than this on the
+/// archive volume.
+pub const MIN_FREE_BYTES: u64 = 256 * 1024 * 1024;
+const MIN_RETENTION_DAYS: u32 = 1;
+const MAX_RETENTION_DAYS: u32 = 3650;
+/// Local hour after which the nightly pass becomes due.
+const NIGHTLY_RUN_HOUR: u32 = 3;
+const SCHEDULER_INTERVAL_SECS: u64 = 10 * 60;
+const ARCHIVE_SUFFIX: &str = ".ndjson.gz";
+const PARTIAL_SUFFIX: &str = ".partial";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPolicy {
+    pub enabled: bool,
+    pub orders_days: u32,
+    pub print_jobs_days: u32,
+    pub audit_log_days: u32,
+    pub sync_history_days: u32,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            orders_days: 90,
+            print_jobs_days: 7,
+            audit_log_days: 180,
+            sync_history_days: 90,
+        }
+    }
+}
+
+/// Partial policy update from the UI; absent fields keep their value.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPolicyUpdate {
+    pub enabled: Option<bool>,
+    #[serde(alias = "orders_days")]
+    pub orders_days: Option<u32>,
+    #[serde(alias = "print_jobs_days")]
+    pub print_jobs_days: Option<u32>,
+    #[serde(alias = "audit_log_days")]
+    pub audit_log_days: Option<u32>,
+    #[serde(alias = "sync_history_days")]
+    pub sync_history_days: Option<u32>,
+}
+
+fn days_setting(conn: &Connection, key: &str, default: u32) -> u32 {
+    db::get_setting(conn, SETTINGS_CATEGORY, key)
+        .and_then(|raw| raw.trim().parse::<u32>().ok())
+        .filter(|days| (MIN_RETENTION_DAYS..=MAX_RETENTION_DAYS).contains(days))
+        .unwrap_or(default)
+}
+
+pub fn load_policy(conn: &Connection) -> RetentionPolicy {
+    let defaults = RetentionPolicy::default();
+    RetentionPolicy {
+        enabled: db::get_setting(conn, SETTINGS_CATEGORY, ENABLED_KEY)
+            .map(|_| crate::print::setting_bool(conn, SETTINGS_CATEGORY, ENABLED_KEY))
+            .unwrap_or(defaults.enabled),
+        orders_days: days_setting(conn, ORDERS_DAYS_KEY, defaults.orders_days),
+        print_jobs_days: days_setting(conn, PRINT_JOBS_DAYS_KEY, defaults.print_jobs_days),
+        audit_log_days: days_setting(conn, AUDIT_LOG_DAYS_KEY, defaults.audit_log_days),
+        sync_history_days: days_setting(conn, SYNC_HISTORY_DAYS_KEY, defaults.sync_history_days),
+    }
+}
+
+/// Apply `update` on top of the stored policy and persist the result.
+pub fn update_policy(
+    conn: &Connection,
+    update: &RetentionPolicyUpdate,
+) -> Result<RetentionPolicy, String> {
+    let current = load_policy(conn);
+    let policy = RetentionPolicy {
+        enabled: update.enabled.unwrap_or(current.enabled),
+        orders_days: update.orders_days.unwrap_or(current.orders_days),
+        print_jobs_days: update.print_jobs_days.unwrap_or(current.print_jobs_days),
+        audit_log_days: update.audit_log_days.unwrap_or(current.audit_log_days),
+        sync_history_days: update
+            .sync_history_days
+            .unwrap_or(current.sync_history_days),
+    };
+    for (name, days) in [
+        ("ordersDays", policy.orders_days),
+        ("printJobsDays", policy.print_jobs_days),
+        ("auditLogDays", policy.audit_log_days),
+        ("syncHistoryDays", policy.sync_history_days),
+    ] {
+        if !(MIN_RETENTION_DAYS..=MAX_RETENTION_DAYS).contains(&days) {
+            return Err(format!(
+                "{name} must be between {MIN_RETENTION_DAYS} and {MAX_RETENTION_DAYS} days"
+            ));
+        }
+    }
+
+    let enabled = if policy.enabled { "true" } else { "false" };
+    db::set_setting(conn, SETTINGS_CATEGORY, ENABLED_KEY, enabled)?;
+    for (key, days) in [
+        (ORDERS_DAYS_KEY, policy.orders_days),
+        (PRINT_JOBS_DAYS_KEY, policy.print_jobs_days),
+        (AUDIT_LOG_DAYS_KEY, policy.audit_log_days),
+        (SYNC_HISTORY_DAYS_KEY, policy.sync_history_days),
+    ] {
+        db::set_setting(conn, SETTINGS_CATEGORY, key, &days.to_string())?;
+    }
+    Ok(policy)
+}
+
+pub fn last_run_date(conn: &Connection) -> Option<NaiveDate> {
+    db::get_setting(conn, SETTINGS_CATEGORY, LAST_RUN_DATE_KEY)
+        .and_then(|raw| NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d").ok())
+}
+
+/// Whether the nightly pass should run at local time `now`.
+pub fn nightly_run_due(
+    policy: &RetentionPolicy,
+    now: DateTime<Local>,
+    last_run: Option<NaiveDate>,
+) -> bool {
+    policy.enabled
+        && now.hour() >= NIGHTLY_RUN_HOUR
+        && last_run.map_or(true, |last| last < now.date_naive())
+}
+
+pub fn archives_dir(db: &DbState) -> Result<PathBuf, String> {
+    db.db_path
+        .parent()
+        .map(|dir| dir.join(ARCHIVES_DIR_NAME))
+        .ok_or_else(|| "database path does not have a parent directory".to_string())
+}
+
+/// Create the archive directory and make sure its volume has room.
+pub fn ensure_archive_space(archive_dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(archive_dir).map_err(|e| format!("create archive directory: {e}"))?;
+    let available =
+        fs4::available_space(archive_dir).map_err(|e| format!("check archive disk space: {e}"))?;
+    if available < MIN_FREE_BYTES {
+        return Err(format!(
+            "Only {} MB free on the archive volume; at least {} MB is required",
+            available / (1024 * 1024),
+            MIN_FREE_BYTES / (1024 * 1024)
+        ));
+    }
+    Ok(())
+}
+
+/// One table swept by the retention pass.
+pub struct PurgeTarget {
+    pub category: &'static str,
+    pub table: &'static str,
+    /// Age column, qualified with the `t` alias.
+    pub timestamp_column: &'static str,
+    /// Extra predicate over `t` that rows must also satisfy to be purged.
+    pub filter: String,
+    /// Tables whose rows are removed with the main row by `ON DELETE
+    /// CASCADE` through `order_id`; they are archived alongside it.
+    pub cascaded: &'static [&'static str],
+}
+
+pub const ORDER_CASCADED_TABLES: &[&str] = &[
+    "order_payments",
+    "payment_adjustments",
+    "payment_items",
+    "driver_earnings",
+];
+
+/// Orders eligible for deletion regardless of age: never a live table tab.
+pub fn orders_target(filter: &str) -> PurgeTarget {
+    let open_table_tab = crate::business_day::open_unsettled_table_tab_expr("t");
+    PurgeTarget {
+        category: "orders",
+        table: "orders",
+        timestamp_column: "t.created_at",
+        filter: format!("({filter}) AND NOT {open_table_tab}"),
+        cascaded: ORDER_CASCADED_TABLES,
+    }
+}
+
+fn policy_targets(policy: &RetentionPolicy, now: DateTime<Utc>) -> Vec<(PurgeTarget, String)> {
+    let cutoff = |days: u32| (now - chrono::Duration::days(i64::from(days))).to_rfc3339();
+    let orders_cutoff = cutoff(policy.orders_days);
+    let print_cutoff = cutoff(policy.print_jobs_days);
+    let audit_cutoff = cutoff(policy.audit_log_days);
+    let sync_cutoff = cutoff(policy.sync_history_days);
+    vec![
+        (
+            // Only finished orders the server already has.
+            orders_target(
+                "t.sync_status = 'synced'
+                 AND LOWER(COALESCE(t.status, '')) IN
+                     ('completed', 'delivered', 'cancelled', 'canceled', 'refunded', 'declined')",
+            ),
+            orders_cutoff,
+        ),
+        (
+            PurgeTarget {
+                category: "print_jobs",
+                table: "print_jobs",
+                timestamp_column: "t.created_at",
+                filter: "t.status IN ('failed', 'printed', 'dispatched', 'cancelled')".into(),
+                cascaded: &[],
+            },
+            print_cutoff,
+        ),
+        (
+            PurgeTarget {
+                category: "audit_log",
+                table: "order_events",
+                timestamp_column: "t.created_at",
+                filter: "1 = 1".into(),
+                cascaded: &[],
+            },
+            audit_cutoff.clone(),
+        ),
+        (
+            PurgeTarget {
+                category: "audit_log",
+                table: "settings_history",
+                timestamp_column: "t.changed_at",
+                filter: "1 = 1".into(),
+                cascaded: &[],
+            },
+            audit_cutoff.clone(),
+        ),
+        (
+            PurgeTarget {
+                category: "audit_log",
+                table: "recovery_action_log",
+                timestamp_column: "t.created_at",
+                filter: "1 = 1".into(),
+                cascaded: &[],
+            },
+            audit_cutoff,
+        ),
+        (
+            // Monetary conflicts stay until an operator has reviewed them.
+            PurgeTarget {
+                category: "sync_history",
+                table: "conflict_audit_log",
+                timestamp_column: "t.timestamp",
+                filter: "NOT (t.is_monetary = 1 AND t.reviewed_by_operator = 0)".into(),
+                cascaded: &[],
+            },
+            sync_cutoff,
+        ),
+    ]
+}
+
+pub(crate) fn table_exists(conn: &Connection, table: &str) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        params![table],
+        |_| Ok(()),
+    )
+    .is_ok()
+}
+
+pub(crate) fn sqlite_value_to_json(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(value) => json!(value),
+        ValueRef::Real(value) => serde_json::Number::from_f64(value)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        ValueRef::Text(value) => Value::String(String::from_utf8_lossy(value).to_string()),
+        ValueRef::Blob(value) => {
+            use base64::Engine as _;
+            json!({ "$blob": base64::engine::general_purpose::STANDARD.encode(value) })
+        }
+    }
+}
+
+fn archive_path(archive_dir: &Path, table: &str, date: &str) -> PathBuf {
+    let mut candidate = archive_dir.join(format!("{table}-{date}{ARCHIVE_SUFFIX}"));
+    let mut run = 2;
+    while candidate.exists() {
+        candidate = archive_dir.join(format!("{table}-{date}-{run}{ARCHIVE_SUFFIX}"));
+        run += 1;
+    }
+    candidate
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(PARTIAL_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Count the JSON lines in a gzip NDJSON file, failing on any line that
+/// does not parse.
+fn count_archived_rows(path: &Path) -> Result<usize, String> {
+    let file = fs::File::open(path).map_err(|e| format!("open archive for verify: {e}"))?;
+    let mut rows = 0;
+    for line in BufReader::new(MultiGzDecoder::new(file)).lines() {
+        let line = line.map_err(|e| format!("read archive for verify: {e}"))?;
+        serde_json::from_str::<Value>(&line)
+            .map_err(|e| format!("archive line {} is not valid JSON: {e}", rows + 1))?;
+        rows += 1;
+    }
+    Ok(rows)
+}
+
+/// Export the rows selected by `sql` into a new archive file and verify it.
+/// Returns the final path and row count; nothing is left behind on error.
+fn write_archive(
+    conn: &Connection,
+    archive_dir: &Path,
+    table: &str,
+    date: &str,
+    sql: &str,
+) -> Result<(PathBuf, usize), String> {
+    let path = archive_path(archive_dir, table, date);
+    let partial = partial_path(&path);
+    let result = (|| {
+        let mut stmt = conn
+            .prepare(sql)
+            .map_err(|e| format!("prepare {table} archive: {e}"))?;
+        let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+        let file =
+            fs::File::create(&partial).map_err(|e| format!("create {table} archive: {e}"))?;
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        let mut rows = stmt
+            .query([])
+            .map_err(|e| format!("query {table} archive: {e}"))?;
+        let mut written = 0;
+        while let Some(row) = rows.next().map_err(|e| format!("read {table} row: {e}"))? {
+            let mut object = serde_json::Map::with_capacity(columns.len());
+            for (index, column) in columns.iter().enumerate() {
+                let value = row
+                    .get_ref(index)
+                    .map_err(|e| format!("read {table}.{column}: {e}"))?;
+                object.insert(column.clone(), sqlite_value_to_json(value));
+            }
+            serde_json::to_writer(&mut encoder, &Value::Object(object))
+                .and_then(|_| encoder.write_all(b"\n").map_err(serde_json::Error::io))
+                .map_err(|e| format!("write {table} archive: {e}"))?;
+            written += 1;
+        }
+        let file = encoder
+            .finish()
+            .and_then(|writer| writer.into_inner().map_err(|e| e.into_error()))
+            .map_err(|e| format!("finish {table} archive: {e}"))?;
+        file.sync_all()
+            .map_err(|e| format!("sync {table} archive: {e}"))?;
+        drop(file);
+
+        let verified = count_archived_rows(&partial)?;
+        if verified != written {
+            return Err(format!(
+                "{table} archive verification failed: wrote {written} rows, read back {verified}"
+            ));
+        }
+        fs::rename(&partial, &path).map_err(|e| format!("finalize {table} archive: {e}"))?;
+        Ok(written)
+    })();
+
+    match result {
+        Ok(rows) => Ok((path, rows)),
+        Err(error) => {
+            let _ = fs::remove_file(&partial);
+            Err(error)
+        }
+    }
+}
+
+/// Archive, then delete, every row of `target` older than `cutoff_at`, in
+/// one transaction. Returns the archived files and the main-table count.
+pub fn purge_with_archive(
+    conn: &Connection,
+    archive_dir: &Path,
+    target: &PurgeTarget,
+    cutoff_at: &str,
+    archive_date: &str,
+) -> Result<(Vec<String>, usize), String> {
+    let table = target.table;
+    if !table_exists(conn, table) {
+        return Ok((Vec::new(), 0));
+    }
+
+    conn.execute_batch("BEGIN IMMEDIATE")
+        .map_err(|e| format!("begin {table} retention: {e}"))?;
+    let mut files: Vec<PathBuf> = Vec::new();
+    let result = (|| {
+        conn.execute_batch(
+            "DROP TABLE IF EXISTS temp.retention_rowids;
+             CREATE TEMP TABLE retention_rowids (id INTEGER PRIMARY KEY);",
+        )
+        .map_err(|e| format!("create retention scratch table: {e}"))?;
+        let selected = conn
+            .execute(
+                &format!(
+                    "INSERT INTO temp.retention_rowids (id)
+                     SELECT t.rowid FROM {table} t
+                     WHERE julianday({ts}) < julianday(?1)
+                       AND {filter}",
+                    ts = target.timestamp_column,
+                    filter = target.filter,
+                ),
+                params![cutoff_at],
+            )
+            .map_err(|e| format!("select {table} rows due: {e}"))?;
+        if selected == 0 {
+            return Ok(0);
+        }
+
+        let selected_rows =
+            format!("SELECT * FROM {table} WHERE rowid IN (SELECT id FROM temp.retention_rowids)");
+        for cascaded in target.cascaded.iter().copied() {
+            if !table_exists(conn, cascaded) {
+                continue;
+            }
+            let sql = format!(
+                "SELECT * FROM {cascaded} WHERE order_id IN (
+                    SELECT id FROM {table} WHERE rowid IN (SELECT id FROM temp.retention_rowids)
+                 )"
+            );
+            let (path, rows) = write_archive(conn, archive_dir, cascaded, archive_date, &sql)?;
+            if rows == 0 {
+                let _ = fs::remove_file(&path);
+            } else {
+                files.push(path);
+            }
+        }
+        let (path, archived) =
+            write_archive(conn, archive_dir, table, archive_date, &selected_rows)?;
+        files.push(path);
+        if archived != selected {
+            return Err(format!(
+                "{table} archive holds {archived} rows but {selected} were selected"
+            ));
+        }
+
+        let deleted = conn
+            .execute(
+                &format!(
+                    "DELETE FROM {table} WHERE rowid IN (SELECT id FROM temp.retention_rowids)"
+                ),
+                [],
+            )
+            .map_err(|e| format!("delete archived {table} rows: {e}"))?;
+        if deleted != selected {
+            return Err(format!(
+                "{table} delete removed {deleted} rows but {selected} were archived"
+            ));
+        }
+        Ok(deleted)
+    })();
+
+    let _ = conn.execute_batch("DROP TABLE IF EXISTS temp.retention_rowids");
+    let committed = result.and_then(|deleted| {
+        conn.execute_batch("COMMIT")
+            .map_err(|e| format!("commit {table} retention: {e}"))
+            .map(|_| deleted)
+    });
+    match committed {
+        Ok(deleted) => Ok((
+            files
+                .iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect(),
+            deleted,
+        )),
+        Err(error) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            for path in files {
+                let _ = fs::remove_file(path);
+            }
+            Err(error)
+        }
+    }
+}
+
+/// Export every row of `tables` into archive files without deleting
+/// anything, for operations that are about to wipe them. Missing and empty
+/// tables produce no file. Returns the archived file paths.
+pub fn export_tables(
+    conn: &Connection,
+    archive_dir: &Path,
+    tables: &[&str],
+    archive_date: &str,
+) -> Result<Vec<String>, String> {
+    ensure_archive_space(archive_dir)?;
+    let mut files = Vec::new();
+    for table in tables.iter().copied() {
+        if !table_exists(conn, table) {
+            continue;
+        }
+        if let Some(path) = export_query(
+            conn,
+            archive_dir,
+            table,
+            archive_date,
+            &format!("SELECT * FROM {table}"),
+        )? {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Export the rows `sql` selects from `table` into one archive file. An
+/// empty result produces no file. The caller makes sure the directory
+/// exists and has room.
+pub fn export_query(
+    conn: &Connection,
+    archive_dir: &Path,
+    table: &str,
+    archive_date: &str,
+    sql: &str,
+) -> Result<Option<String>, String> {
+    let (path, rows) = write_archive(conn, archive_dir, table, archive_date, sql)?;
+    if rows == 0 {
+        let _ = fs::remove_file(&path);
+        return Ok(None);
+    }
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// Run one retention pass with the stored policy. `progress` receives a
+/// payload per table and a final `completed` one.
+pub fn run(db: &DbState, trigger: &str, progress: &dyn Fn(&Value)) -> Result<Value, String> {
+    let archive_dir = archives_dir(db)?;
+    if let Err(error) = ensure_archive_space(&archive_dir) {
+        warn!(error = %error, "Retention pass refused");
+        return Ok(json!({
+            "success": false,
+            "errorCode": "archive_unavailable",
+            "error": error,
+        }));
+    }
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let policy = load_policy(&conn);
+    let now = Utc::now();
+    let archive_date = Local::now().format("%Y-%m-%d").to_string();
+    let targets = policy_targets(&policy, now);
+    let total = targets.len();
+    let mut tables = Vec::with_capacity(total);
+
+    for (index, (target, cutoff_at)) in targets.iter().enumerate() {
+        match purge_with_archive(&conn, &archive_dir, target, cutoff_at, &archive_date) {
+            Ok((files, deleted)) => {
+                let entry = json!({
+                    "stage": "table",
+                    "category": target.category,
+                    "table": target.table,
+                    "cutoffAt": cutoff_at,
+                    "deleted": deleted,
+                    "files": files,
+                    "index": index + 1,
+                    "total": total,
+                });
+                progress(&entry);
+                tables.push(entry);
+            }
+            Err(error) => {
+                warn!(table = target.table, error = %error, "Retention pass stopped");
+                let failure = json!({
+                    "stage": "failed",
+                    "category": target.category,
+                    "table": target.table,
+                    "error": error,
+                    "index": index + 1,
+                    "total": total,
+                });
+                progress(&failure);
+                return Ok(json!({
+                    "success": false,
+                    "errorCode": "archive_failed",
+                    "error": error,
+                    "tables": tables,
+                }));
+            }
+        }
+    }
+
+    db::set_setting(&conn, SETTINGS_CATEGORY, LAST_RUN_DATE_KEY, &archive_date)?;
+    let deleted: usize = tables
+        .iter()
+        .filter_map(|entry| entry["deleted"].as_u64())
+        .sum::<u64>() as usize;
+    info!(trigger, deleted, "Retention pass completed");
+    let summary = json!({
+        "success": true,
+        "trigger": trigger,
+        "deleted": deleted,
+        "archiveDir": archive_dir.to_string_lossy(),
+        "tables": tables,
+    });
+    progress(&json!({ "stage": "completed", "summary": summary }));
+    Ok(summary)
+}
+
+/// Archive files, newest first.
+pub fn list_archives(archive_dir: &Path) -> Result<Vec<Value>, String> {
+    let entries = match fs::read_dir(archive_dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(format!("read archive directory: {error}")),
+    };
+    let mut archives = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(stem) = name.strip_suffix(ARCHIVE_SUFFIX) else {
+            continue;
+        };
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        // `<table>-<YYYY-MM-DD>[-<run>]`
+        let (table, date) = stem
+            .char_indices()
+            .filter(|(_, c)| *c == '-')
+            .find_map(|(index, _)| {
+                let date = stem.get(index + 1..index + 11)?;
+                NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .ok()
+                    .map(|_| (&stem[..index], date))
+            })
+            .unwrap_or((stem, ""));
+        let modified_at = metadata
+            .modified()
+            .ok()
+            .map(|time| DateTime::<Utc>::from(time).to_rfc3339());
+        archives.push(json!({
+            "name": name,
+            "table": table,
+            "date": date,
+            "sizeBytes": metadata.len(),
+            "modifiedAt": modified_at,
+            "path": entry.path().to_string_lossy(),
+        }));
+    }
+    archives.sort_by(|a, b| {
+        b["date"]
+            .as_str()
+            .cmp(&a["date"].as_str())
+            .then_with(|| a["name"].as_str().cmp(&b["name"].as_str()))
+    });
+    Ok(archives)
+}
+
+pub fn start_retention_scheduler(
+    app: tauri::AppHandle,
+    db: Arc<DbState>,
+    cancel: tokio_util::sync::CancellationToken,
+) {
+    let cadence = Duration::from_secs(SCHEDULER_INTERVAL_SECS);
+    tauri::async_runtime::spawn(async move {
+        info!("Retention scheduler started");
+        loop {
+            if let Ok(conn) = db.conn.lock() {
+                match idempotency::prune_expired(&conn, Utc::now()) {
+                    Ok(0) => {}
+                    Ok(pruned) => info!(pruned, "Pruned expired idempotency cache entries"),
+                    Err(error) => warn!(error = %error, "Idempotency cache prune failed"),
+                }
+            }
+
+            let due = db.conn.lock().ok().is_some_and(|conn| {
+                nightly_run_due(&load_policy(&conn), Local::now(), last_run_date(&conn))
+            });
+            if due {
+                let emit = |payload: &Value| {
+                    let _ = app.emit(PROGRESS_EVENT, payload);
+                };
+                if let Err(error) = run(db.as_ref(), TRIGGER_SCHEDULED, &emit) {
+                    warn!(error = %error, "Retention pass failed");
+                }
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(cadence) => {}
+                _ = cancel.cancelled() => {
+                    info!("Retention scheduler cancelled");
+                    break;
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn insert_order(conn: &Connection, id: &str, sync_status: &str, created_at: &str) {
+        conn.execute(
+            "INSERT INTO orders (id, order_number, items, total_amount, total_amount_cents,
+                                 status, order_type, sync_status, created_at, updated_at)
+             VALUES (?1, ?1, '[]', 10.0, 1000, 'completed', 'takeaway', ?2, ?3, ?3)",
+            params![id, sync_status, created_at],
+        )
+        .expect("insert order");
+    }
+
+    #[test]
+    fn purge_archives_orders_and_cascaded_rows_before_deleting() {
+        let conn = test_conn();
+        let dir = crate::tests::harness::TempDir::new();
+        insert_order(&conn, "ord-old", "synced", "2026-01-01T10:00:00Z");
+        insert_order(&conn, "ord-unsynced", "pending", "2026-01-01T11:00:00Z");
+        insert_order(&conn, "ord-recent", "synced", "2026-06-01T10:00:00Z");
+        conn.execute(
+            "INSERT INTO order_payments (id, order_id, method, amount, amount_cents, status, created_at, updated_at)
+             VALUES ('pay-old', 'ord-old', 'cash', 10.0, 1000, 'completed', '2026-01-01T10:01:00Z', '2026-01-01T10:01:00Z')",
+            [],
+        )
+        .expect("insert payment");
+
+        let policy = RetentionPolicy {
+            orders_days: 30,
+            ..RetentionPolicy::default()
+        };
+        let now = Utc.with_ymd_and_hms(2026, 6, 15, 0, 0, 0).unwrap();
+        let (target, cutoff) = policy_targets(&policy, now).remove(0);
+        let (files, deleted) =
+            purge_with_archive(&conn, dir.path(), &target, &cutoff, "2026-06-15").expect("purge");
+
+        assert_eq!(deleted, 1);
+        assert_eq!(files.len(), 2, "payments and orders archives");
+        let orders_archive = dir.path().join("orders-2026-06-15.ndjson.gz");
+        assert_eq!(count_archived_rows(&orders_archive).unwrap(), 1);
+        assert_eq!(
+            count_archived_rows(&dir.path().join("order_payments-2026-06-15.ndjson.gz")).unwrap(),
+            1
+        );
+        let remaining: Vec<String> = conn
+            .prepare("SELECT id FROM orders ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(remaining, vec!["ord-recent", "ord-unsynced"]);
+        let payments: i64 = conn
+            .query_row("SELECT COUNT(*) FROM order_payments", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(payments, 0);
+
+        let listed = list_archives(dir.path()).expect("list");
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0]["table"], "order_payments");
+        assert_eq!(listed[0]["date"], "2026-06-15");
+    }
+
+    #[test]
+    fn every_policy_target_matches_the_migrated_schema() {
+        let conn = test_conn();
+        let dir = crate::tests::harness::TempDir::new();
+        conn.execute(
+            "INSERT INTO settings_history (setting_category, setting_key, new_value, changed_at)
+             VALUES ('printer', 'width', '80', '2020-01-01 00:00:00')",
+            [],
+        )
+        .expect("insert settings history");
+
+        let mut deleted_by_table = Vec::new();
+        for (target, cutoff) in policy_targets(&RetentionPolicy::default(), Utc::now()) {
+            let (_, deleted) =
+                purge_with_archive(&conn, dir.path(), &target, &cutoff, "2026-06-15")
+                    .unwrap_or_else(|e| panic!("{}: {e}", target.table));
+            deleted_by_table.push((target.table, deleted));
+        }
+        assert!(deleted_by_table.contains(&("settings_history", 1)));
+    }
+
+    #[test]
+    fn purge_keeps_rows_when_archive_cannot_be_written() {
+        let conn = test_conn();
+        let dir = crate::tests::harness::TempDir::new();
+        insert_order(&conn, "ord-old", "synced", "2026-01-01T10:00:00Z");
+        let missing_dir = dir.path().join("missing");
+
+        let target = orders_target("1 = 1");
+        let error = purge_with_archive(
+            &conn,
+            &missing_dir,
+            &target,
+            "2026-02-01T00:00:00Z",
+            "2026-06-15",
+        )
+        .expect_err("archive write must fail");
+
+        assert!(error.contains("archive"), "{error}");
+        let orders: i64 = conn
+            .query_row("SELECT COUNT(*) FROM orders", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(orders, 1, "nothing is deleted without an archive");
+        assert!(conn.is_autocommit(), "transaction rolled back");
+    }
+
+    #[test]
+    fn policy_update_validates_and_nightly_run_fires_once_per_day() {
+        let conn = test_conn();
+        assert_eq!(load_policy(&conn), RetentionPolicy::default());
+
+        let error = update_policy(
+            &conn,
+            &RetentionPolicyUpdate {
+                orders_days: Some(0),
+                ..Default::default()
+            },
+        )
+        .expect_err("zero days rejected");
+        assert!(error.contains("ordersDays"));
+
+        let policy = update_policy(
+            &conn,
+            &RetentionPolicyUpdate {
+                print_jobs_days: Some(3),
+                ..Default::default()
+            },
+        )
+        .expect("update");
+        assert_eq!(policy.print_jobs_days, 3);
+        assert_eq!(load_policy(&conn), policy);
+
+        let after_run_hour = Local.with_ymd_and_hms(2026, 6, 15, 3, 30, 0).unwrap();
+        let before_run_hour = Local.with_ymd_and_hms(2026, 6, 15, 1, 0, 0).unwrap();
+        let yesterday = NaiveDate::from_ymd_opt(2026, 6, 14);
+        assert!(nightly_run_due(&policy, after_run_hour, yesterday));
+        assert!(!nightly_run_due(&policy, before_run_hour, yesterday));
+        assert!(!nightly_run_due(
+            &policy,
+            after_run_hour,
+            Some(after_run_hour.date_naive())
+        ));
+    }
+}
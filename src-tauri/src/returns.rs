@@ -0,0 +1,888 @@
+//! Returns and exchanges: money given back on a sale, as a linked order.
+//!
+//! A return is an order of its own (`is_return = 1`) whose lines are the
+//! returned quantities of the sale's lines at negative amounts, pointing at
+//! the sale through `related_order_id`. Lines are named by their index in
+//! the sale's items JSON, as with split checks, and every return line keeps
+//! a `returnOf` marker; what was already returned is read back from those
+//! markers, so no line can go back more times than it was sold.
+//!
+//! The money goes back on the sale's own payments, oldest first, through
+//! [`refunds::refund_payment_in_connection`] in the same transaction as the
+//! return order. Those refunds carry `return_order_id`: reports count the
+//! returned value once, through the return order's negative total, and skip
+//! them as refunds.
+//!
+//! As with split checks, whatever the sale total holds beyond its items
+//! (discounts, fees, tip, exclusive tax) is spread by item value, and the
+//! return that takes back the last of the items gives back exactly what the
+//! sale's total has left, so partial returns always add up to the total.
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::money::{self, Cents, RoundingRule};
+use crate::tax::{self, MenuTaxLookup};
+use crate::{order_events, refunds, storage, value_f64, value_i64, value_str};
+
+/// Quantities closer than this are the same quantity.
+const QTY_EPSILON: f64 = 1e-6;
+
+/// One line of the sale to take back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReturnLine {
+    pub line_index: usize,
+    pub quantity: f64,
+}
+
+/// What a sale line can still be returned for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReturnableLine {
+    pub line_index: usize,
+    pub name: String,
+    pub sold: f64,
+    pub returned: f64,
+}
+
+impl ReturnableLine {
+    pub fn available(&self) -> f64 {
+        (self.sold - self.returned).max(0.0)
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "lineIndex": self.line_index,
+            "name": self.name,
+            "sold": self.sold,
+            "returned": self.returned,
+            "available": self.available(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReturnRequest<'a> {
+    pub original_order_id: &'a str,
+    pub lines: &'a [ReturnLine],
+    pub reason_code: Option<&'a str>,
+    pub reason: Option<&'a str>,
+    pub staff_id: Option<&'a str>,
+    pub staff_shift_id: Option<&'a str>,
+    /// Put the returned lines' ingredients back in stock.
+    pub restock: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreatedReturn {
+    pub return_order_id: String,
+    pub order_number: String,
+    pub original_order_id: String,
+    pub original_order_number: Option<String>,
+    pub refund_cents: i64,
+    pub adjustment_ids: Vec<String>,
+    pub items: Vec<Value>,
+}
+
+impl CreatedReturn {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "returnOrderId": self.return_order_id,
+            "orderNumber": self.order_number,
+            "originalOrderId": self.original_order_id,
+            "originalOrderNumber": self.original_order_number,
+            "refundAmount": Cents::new(self.refund_cents).to_f64_dp2(),
+            "adjustmentIds": self.adjustment_ids,
+            "items": self.items,
+        })
+    }
+}
+
+/// `items: [{ lineIndex, quantity }]`; a line named twice is returned once
+/// with the quantities added up.
+pub fn parse_lines(payload: &Value) -> Result<Vec<ReturnLine>, String> {
+    let entries = ["items", "lines", "returnItems", "return_items"]
+        .iter()
+        .find_map(|key| payload.get(*key).and_then(Value::as_array))
+        .ok_or("Missing items to return")?;
+    let mut lines: Vec<ReturnLine> = Vec::new();
+    for entry in entries {
+        let line_index = value_i64(entry, &["lineIndex", "line_index", "index"])
+            .filter(|index| *index >= 0)
+            .ok_or("Each returned item needs its lineIndex on the original order")?
+            as usize;
+        let quantity = value_f64(entry, &["quantity", "qty"]).unwrap_or(1.0);
+        if !quantity.is_finite() || quantity <= 0.0 {
+            return Err(format!(
+                "Invalid return quantity {quantity} for line {line_index}"
+            ));
+        }
+        match lines.iter_mut().find(|line| line.line_index == line_index) {
+            Some(line) => line.quantity += quantity,
+            None => lines.push(ReturnLine {
+                line_index,
+                quantity,
+            }),
+        }
+    }
+    if lines.is_empty() {
+        return Err("Missing items to return".into());
+    }
+    Ok(lines)
+}
+
+fn parse_items(raw: &str) -> Vec<Value> {
+    serde_json::from_str::<Value>(raw)
+        .ok()
+        .and_then(|value| value.as_array().cloned())
+        .unwrap_or_default()
+}
+
+fn line_quantity(item: &Value) -> f64 {
+    value_f64(item, &["quantity"]).unwrap_or(1.0).max(0.0)
+}
+
+fn line_name(item: &Value, index: usize) -> String {
+    value_str(item, &["name", "menuItemName", "menu_item_name", "title"])
+        .unwrap_or_else(|| format!("Item {}", index + 1))
+}
+
+fn format_quantity(quantity: f64) -> String {
+    if (quantity - quantity.round()).abs() < QTY_EPSILON {
+        format!("{}", quantity.round() as i64)
+    } else {
+        format!("{quantity:.3}")
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    }
+}
+
+/// Quantity already taken back per sale line, from earlier live returns.
+fn returned_quantities(conn: &Connection, order_id: &str) -> Result<Vec<(usize, f64)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT COALESCE(items, '[]') FROM orders
+             WHERE related_order_id = ?1
+               AND COALESCE(is_return, 0) = 1
+               AND status NOT IN ('cancelled', 'canceled')",
+        )
+        .map_err(|e| format!("prepare earlier returns: {e}"))?;
+    let rows = stmt
+        .query_map(params![order_id], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("query earlier returns: {e}"))?;
+    let mut returned: Vec<(usize, f64)> = Vec::new();
+    for raw in rows.flatten() {
+        for item in parse_items(&raw) {
+            let Some(index) = item
+                .get("returnOf")
+                .and_then(|marker| value_i64(marker, &["lineIndex"]))
+                .filter(|index| *index >= 0)
+            else {
+                continue;
+            };
+            let quantity = line_quantity(&item);
+            match returned
+                .iter_mut()
+                .find(|(line, _)| *line == index as usize)
+            {
+                Some((_, total)) => *total += quantity,
+                None => returned.push((index as usize, quantity)),
+            }
+        }
+    }
+    Ok(returned)
+}
+
+struct OriginalOrder {
+    order_number: Option<String>,
+    status: String,
+    is_return: bool,
+    items: Vec<Value>,
+    total_cents: i64,
+}
+
+fn load_original(conn: &Connection, order_id: &str) -> Result<OriginalOrder, String> {
+    conn.query_row(
+        "SELECT order_number, COALESCE(status, ''), COALESCE(is_return, 0),
+                COALESCE(items, '[]'),
+                COALESCE(NULLIF(total_amount_cents, 0), CAST(ROUND(total_amount * 100) AS INTEGER), 0)
+         FROM orders WHERE id = ?1",
+        params![order_id],
+        |row| {
+            Ok(OriginalOrder {
+                order_number: row.get(0)?,
+                status: row.get(1)?,
+                is_return: row.get::<_, i64>(2)? != 0,
+                items: parse_items(&row.get::<_, String>(3)?),
+                total_cents: row.get(4)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("load order for return: {e}"))?
+    .ok_or_else(|| format!("Order not found: {order_id}"))
+}
+
+fn returnable_from(
+    conn: &Connection,
+    order_id: &str,
+    items: &[Value],
+) -> Result<Vec<ReturnableLine>, String> {
+    let returned = returned_quantities(conn, order_id)?;
+    Ok(items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| ReturnableLine {
+            line_index: index,
+            name: line_name(item, index),
+            sold: line_quantity(item),
+            returned: returned
+                .iter()
+                .find(|(line, _)| *line == index)
+                .map(|(_, quantity)| *quantity)
+                .unwrap_or(0.0),
+        })
+        .collect())
+}
+
+/// Every line of the sale with what is left to return on it.
+pub fn returnable_lines(conn: &Connection, order_id: &str) -> Result<Vec<ReturnableLine>, String> {
+    let original = load_original(conn, order_id)?;
+    returnable_from(conn, order_id, &original.items)
+}
+
+fn describe_available(lines: &[ReturnableLine]) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            format!(
+                "#{} {} ×{}",
+                line.line_index,
+                line.name,
+                format_quantity(line.available())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Reject lines that do not exist or ask for more than is left, naming
+/// what can still be returned.
+pub fn validate_lines(available: &[ReturnableLine], lines: &[ReturnLine]) -> Result<(), String> {
+    for line in lines {
+        let Some(sold) = available.get(line.line_index) else {
+            return Err(format!(
+                "Order has no item at line {}. Available to return: {}",
+                line.line_index,
+                describe_available(available)
+            ));
+        };
+        if line.quantity > sold.available() + QTY_EPSILON {
+            return Err(format!(
+                "Cannot return {} × {}: only {} left of {} sold. Available to return: {}",
+                format_quantity(line.quantity),
+                sold.name,
+                format_quantity(sold.available()),
+                format_quantity(sold.sold),
+                describe_available(available)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Completed payments on the order with what each has left to refund,
+/// oldest first.
+fn refundable_payments(conn: &Connection, order_id: &str) -> Result<Vec<(String, i64)>, String> {
+    let mut stmt = conn
+        .prepare(
+            // W4b: cents-with-real-fallback shim (removed in 4e).
+            "SELECT op.id,
+                    COALESCE(op.amount_cents, CAST(ROUND(op.amount * 100) AS INTEGER), 0)
+                    - COALESCE((
+                        SELECT SUM(COALESCE(pa.amount_cents, CAST(ROUND(pa.amount * 100) AS INTEGER)))
+                        FROM payment_adjustments pa
+                        WHERE pa.payment_id = op.id AND pa.adjustment_type = 'refund'
+                    ), 0)
+             FROM order_payments op
+             WHERE op.order_id = ?1 AND op.status = 'completed'
+             ORDER BY op.created_at, op.id",
+        )
+        .map_err(|e| format!("prepare refundable payments: {e}"))?;
+    let rows = stmt
+        .query_map(params![order_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })
+        .map_err(|e| format!("query refundable payments: {e}"))?;
+    Ok(rows
+        .flatten()
+        .filter(|(_, remaining)| *remaining > 0)
+        .collect())
+}
+
+/// The shift the return is rung up on: the one given, the staff member's
+/// active shift, or the sale's own.
+fn resolve_shift(
+    conn: &Connection,
+    request: &ReturnRequest<'_>,
+    original_shift: Option<String>,
+) -> Option<String> {
+    if let Some(shift_id) = request
+        .staff_shift_id
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+    {
+        return Some(shift_id.to_string());
+    }
+    request
+        .staff_id
+        .and_then(|staff_id| {
+            conn.query_row(
+                "SELECT id FROM staff_shifts WHERE staff_id = ?1 AND status = 'active'
+                 ORDER BY check_in_time DESC LIMIT 1",
+                params![staff_id],
+                |row| row.get::<_, String>(0),
+            )
+            .ok()
+        })
+        .or(original_shift)
+}
+
+/// Negative copy of a sale line for `quantity` of it worth `value_cents`.
+fn return_item(
+    original_order_id: &str,
+    source: &Value,
+    line_index: usize,
+    quantity: f64,
+    value_cents: i64,
+) -> Value {
+    let mut item = source.clone();
+    if let Value::Object(obj) = &mut item {
+        let total = Cents::new(-value_cents).to_f64_dp2();
+        let unit = Cents::round_half_even(total / quantity).to_f64_dp2();
+        obj.insert("quantity".into(), json!(quantity));
+        for key in ["unit_price", "unitPrice", "price"] {
+            if obj.contains_key(key) {
+                obj.insert(key.into(), json!(unit));
+            }
+        }
+        obj.insert("unit_price".into(), json!(unit));
+        obj.remove("totalPrice");
+        obj.insert("total_price".into(), json!(total));
+        obj.insert(
+            "returnOf".into(),
+            json!({ "orderId": original_order_id, "lineIndex": line_index }),
+        );
+    }
+    item
+}
+
+/// Record a return against `request.original_order_id` and refund it on
+/// the sale's payments, all in one transaction.
+pub fn create_return(
+    conn: &Connection,
+    request: &ReturnRequest<'_>,
+) -> Result<CreatedReturn, String> {
+    conn.execute_batch("BEGIN IMMEDIATE")
+        .map_err(|e| format!("begin return transaction: {e}"))?;
+    match create_return_in_tx(conn, request) {
+        Ok(created) => {
+            conn.execute_batch("COMMIT")
+                .map_err(|e| format!("commit return: {e}"))?;
+            Ok(created)
+        }
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            Err(e)
+        }
+    }
+}
+
+fn create_return_in_tx(
+    conn: &Connection,
+    request: &ReturnRequest<'_>,
+) -> Result<CreatedReturn, String> {
+    let order_id = request.original_order_id;
+    let original = load_original(conn, order_id)?;
+    if original.is_return {
+        return Err("A return order cannot itself be returned".into());
+    }
+    if matches!(original.status.as_str(), "cancelled" | "canceled") {
+        return Err(format!(
+            "Cannot return items from cancelled order {order_id}"
+        ));
+    }
+    if request.lines.is_empty() {
+        return Err("Missing items to return".into());
+    }
+    let available = returnable_from(conn, order_id, &original.items)?;
+    validate_lines(&available, request.lines)?;
+
+    // Item value of what goes back, then its share of the sale total.
+    let rule = RoundingRule::from_settings(conn);
+    let line_cents: Vec<i64> = original
+        .items
+        .iter()
+        .map(|item| money::item_line_cents(item, rule).as_i64())
+        .collect();
+    let items_total: i64 = line_cents.iter().sum();
+    if items_total <= 0 {
+        return Err(format!("Order {order_id} has no item value to refund"));
+    }
+    let values: Vec<i64> = request
+        .lines
+        .iter()
+        .map(|line| {
+            let sold = available[line.line_index].sold;
+            let whole = line_cents[line.line_index];
+            if sold <= QTY_EPSILON || (line.quantity - sold).abs() < QTY_EPSILON {
+                whole
+            } else {
+                Cents::round_half_even(Cents::new(whole).to_f64_dp2() * line.quantity / sold)
+                    .as_i64()
+            }
+        })
+        .collect();
+    let returns_everything_left = available.iter().all(|sold| {
+        let taken = request
+            .lines
+            .iter()
+            .find(|line| line.line_index == sold.line_index)
+            .map(|line| line.quantity)
+            .unwrap_or(0.0);
+        sold.available() - taken < QTY_EPSILON
+    });
+    let already_returned: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(-COALESCE(total_amount_cents, CAST(ROUND(total_amount * 100) AS INTEGER), 0)), 0)
+             FROM orders
+             WHERE related_order_id = ?1
+               AND COALESCE(is_return, 0) = 1
+               AND status NOT IN ('cancelled', 'canceled')",
+            params![order_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("sum earlier returns: {e}"))?;
+    let refund_cents = if returns_everything_left {
+        original.total_cents - already_returned
+    } else {
+        let value: i64 = values.iter().sum();
+        (i128::from(original.total_cents) * i128::from(value) / i128::from(items_total)) as i64
+    }
+    .max(0);
+
+    let payments = refundable_payments(conn, order_id)?;
+    let refundable: i64 = payments.iter().map(|(_, remaining)| *remaining).sum();
+    if refund_cents > refundable {
+        return Err(format!(
+            "Return of {:.2} exceeds the {:.2} left to refund on order {}",
+            Cents::new(refund_cents).to_f64_dp2(),
+            Cents::new(refundable).to_f64_dp2(),
+            original.order_number.as_deref().unwrap_or(order_id)
+        ));
+    }
+
+    let items: Vec<Value> = request
+        .lines
+        .iter()
+        .zip(&values)
+        .map(|(line, value)| {
+            return_item(
+                order_id,
+                &original.items[line.line_index],
+                line.line_index,
+                line.quantity,
+                *value,
+            )
+        })
+        .collect();
+    let breakdown = tax::compute_breakdown(
+        &tax::load_config(conn),
+        &MenuTaxLookup::load(conn),
+        &items,
+        rule,
+    );
+    let tax_cents: i64 = breakdown.iter().map(|line| line.tax_cents).sum();
+    let subtotal_cents = -values.iter().sum::<i64>();
+    let total_cents = -refund_cents;
+
+    let (customer_name, customer_phone, customer_email, customer_id, order_type): (
+        Option<String>,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+    ) = conn
+        .query_row(
+            "SELECT customer_name, customer_phone, customer_email, customer_id, order_type
+             FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            },
+        )
+        .map_err(|e| format!("load order customer for return: {e}"))?;
+    let (branch_id, organization_id, original_shift, remote_order_id): (
+        Option<String>,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+    ) = conn
+        .query_row(
+            "SELECT branch_id, organization_id, staff_shift_id, supabase_id
+             FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| format!("load order scope for return: {e}"))?;
+    let staff_shift_id = resolve_shift(conn, request, original_shift);
+    let terminal_id = storage::get_credential("terminal_id").filter(|id| !id.trim().is_empty());
+
+    let return_order_id = Uuid::new_v4().to_string();
+    let order_number = crate::sync::next_order_number(conn);
+    let now = Utc::now().to_rfc3339();
+    let items_json = serde_json::to_string(&items).map_err(|e| e.to_string())?;
+    let breakdown_json =
+        serde_json::to_string(&breakdown).map_err(|e| format!("serialize tax breakdown: {e}"))?;
+    conn.execute(
+        "INSERT INTO orders (
+            id, order_number, display_order_number, customer_name, customer_phone,
+            customer_email, customer_id, items, total_amount, total_amount_cents,
+            subtotal, subtotal_cents, tax_amount, tax_amount_cents, tax_breakdown,
+            status, order_type, created_at, updated_at, sync_status, payment_status,
+            staff_shift_id, staff_id, version, terminal_id, branch_id, organization_id,
+            related_order_id, is_return
+        ) VALUES (?1, ?2, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14,
+                  'completed', ?15, ?16, ?16, 'pending', 'refunded',
+                  ?17, ?18, 1, ?19, ?20, ?21, ?22, 1)",
+        params![
+            return_order_id,
+            order_number,
+            customer_name,
+            customer_phone,
+            customer_email,
+            customer_id,
+            items_json,
+            Cents::new(total_cents).to_f64_dp2(),
+            total_cents,
+            Cents::new(subtotal_cents).to_f64_dp2(),
+            subtotal_cents,
+            Cents::new(tax_cents).to_f64_dp2(),
+            tax_cents,
+            breakdown_json,
+            order_type,
+            now,
+            staff_shift_id,
+            request.staff_id,
+            terminal_id,
+            branch_id,
+            organization_id,
+            order_id,
+        ],
+    )
+    .map_err(|e| format!("insert return order: {e}"))?;
+
+    // Oldest payment first; the restock rides on the first refund only.
+    let restock_items: Vec<Value> = request
+        .lines
+        .iter()
+        .map(|line| {
+            let mut item = original.items[line.line_index].clone();
+            if let Value::Object(obj) = &mut item {
+                obj.insert("quantity".into(), json!(line.quantity));
+            }
+            item
+        })
+        .collect();
+    let mut left = refund_cents;
+    let mut adjustment_ids = Vec::new();
+    for (payment_id, remaining) in payments {
+        if left <= 0 {
+            break;
+        }
+        let amount = left.min(remaining);
+        let mut payload = json!({
+            "paymentId": payment_id,
+            "amount": Cents::new(amount).to_f64_dp2(),
+            "reasonCode": request.reason_code,
+            "reason": request.reason,
+            "staffId": request.staff_id,
+            "staffShiftId": staff_shift_id,
+            "idempotencyKey": format!("return:{return_order_id}:{payment_id}"),
+        });
+        if request.restock && adjustment_ids.is_empty() {
+            payload["restockItems"] = Value::Array(restock_items.clone());
+        }
+        let refund = refunds::refund_payment_in_connection(conn, &payload)?;
+        let adjustment_id = value_str(&refund, &["adjustmentId"])
+            .ok_or("Refund did not return an adjustment id")?;
+        conn.execute(
+            "UPDATE payment_adjustments SET return_order_id = ?1 WHERE id = ?2",
+            params![return_order_id, adjustment_id],
+        )
+        .map_err(|e| format!("link refund to return: {e}"))?;
+        order_events::append(
+            conn,
+            order_id,
+            order_events::REFUND_RECORDED,
+            request.staff_id,
+            json!({
+                "paymentId": payment_id,
+                "adjustmentId": adjustment_id,
+                "amount": Cents::new(amount).to_f64_dp2(),
+                "reason": request.reason,
+                "returnOrderId": return_order_id,
+            }),
+        );
+        adjustment_ids.push(adjustment_id);
+        left -= amount;
+    }
+
+    let sync_payload = json!({
+        "orderId": return_order_id,
+        "orderNumber": order_number,
+        "order_number": order_number,
+        "displayOrderNumber": order_number,
+        "orderType": order_type,
+        "status": "completed",
+        "paymentStatus": "refunded",
+        "items": items,
+        "subtotal": Cents::new(subtotal_cents).to_f64_dp2(),
+        "taxAmount": Cents::new(tax_cents).to_f64_dp2(),
+        "totalAmount": Cents::new(total_cents).to_f64_dp2(),
+        "customerName": customer_name,
+        "customerPhone": customer_phone,
+        "customerId": customer_id,
+        "staffId": request.staff_id,
+        "staffShiftId": staff_shift_id,
+        "terminalId": terminal_id,
+        "branchId": branch_id,
+        "organizationId": organization_id,
+        "isReturn": true,
+        "relatedOrderId": order_id,
+        "relatedRemoteOrderId": remote_order_id,
+        "createdAt": now,
+    });
+    crate::sync_queue::enqueue_payload_item(
+        conn,
+        "orders",
+        &return_order_id,
+        "INSERT",
+        &sync_payload,
+        Some(1),
+        Some("orders"),
+        Some("server-wins"),
+        Some(1),
+    )
+    .map_err(|e| format!("enqueue return order sync: {e}"))?;
+
+    let summary = json!({
+        "returnOrderId": return_order_id,
+        "returnOrderNumber": order_number,
+        "originalOrderId": order_id,
+        "originalOrderNumber": original.order_number,
+        "refundAmount": Cents::new(refund_cents).to_f64_dp2(),
+        "lines": request.lines.iter().map(|line| json!({
+            "lineIndex": line.line_index,
+            "quantity": line.quantity,
+        })).collect::<Vec<_>>(),
+    });
+    order_events::append(
+        conn,
+        order_id,
+        order_events::RETURN_CREATED,
+        request.staff_id,
+        summary.clone(),
+    );
+    order_events::append(
+        conn,
+        &return_order_id,
+        order_events::RETURN_CREATED,
+        request.staff_id,
+        summary,
+    );
+
+    Ok(CreatedReturn {
+        return_order_id,
+        order_number,
+        original_order_id: order_id.to_string(),
+        original_order_number: original.order_number,
+        refund_cents,
+        adjustment_ids,
+        items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    /// 2 × burger at 8.00 and 1 × fries at 4.00, paid 20.00 in cash.
+    fn seed_sale(conn: &Connection) {
+        let items = json!([
+            { "name": "Burger", "quantity": 2, "unit_price": 8.0, "total_price": 16.0 },
+            { "name": "Fries", "quantity": 1, "unit_price": 4.0, "total_price": 4.0 },
+        ]);
+        conn.execute(
+            "INSERT INTO orders (id, order_number, items, total_amount, total_amount_cents,
+                 status, order_type, sync_status, created_at, updated_at)
+             VALUES ('sale-1', 'ORD-SALE-1', ?1, 20.0, 2000, 'completed', 'takeaway',
+                     'pending', datetime('now'), datetime('now'))",
+            params![items.to_string()],
+        )
+        .expect("insert sale");
+        conn.execute(
+            "INSERT INTO order_payments (id, order_id, method, amount, amount_cents,
+                 sync_status, sync_state, created_at, updated_at)
+             VALUES ('pay-1', 'sale-1', 'cash', 20.0, 2000, 'synced', 'applied',
+                     datetime('now'), datetime('now'))",
+            [],
+        )
+        .expect("insert payment");
+    }
+
+    fn request<'a>(lines: &'a [ReturnLine]) -> ReturnRequest<'a> {
+        ReturnRequest {
+            original_order_id: "sale-1",
+            lines,
+            reason_code: Some("other"),
+            reason: Some("Wrong size"),
+            ..ReturnRequest::default()
+        }
+    }
+
+    #[test]
+    fn parse_lines_merges_repeated_lines_and_rejects_bad_quantities() {
+        let lines = parse_lines(&json!({
+            "items": [
+                { "lineIndex": 0, "quantity": 1 },
+                { "line_index": 0 },
+                { "lineIndex": 2, "quantity": 3 },
+            ]
+        }))
+        .unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                ReturnLine {
+                    line_index: 0,
+                    quantity: 2.0
+                },
+                ReturnLine {
+                    line_index: 2,
+                    quantity: 3.0
+                },
+            ]
+        );
+        assert!(parse_lines(&json!({ "items": [{ "quantity": 1 }] })).is_err());
+        assert!(parse_lines(&json!({ "items": [{ "lineIndex": 0, "quantity": 0 }] })).is_err());
+        assert!(parse_lines(&json!({})).is_err());
+    }
+
+    #[test]
+    fn return_creates_linked_negative_order_and_refunds_the_sale() {
+        let conn = test_conn();
+        seed_sale(&conn);
+
+        let lines = [ReturnLine {
+            line_index: 0,
+            quantity: 1.0,
+        }];
+        let created = create_return(&conn, &request(&lines)).unwrap();
+        assert_eq!(created.refund_cents, 800);
+        assert_eq!(created.adjustment_ids.len(), 1);
+
+        let (total_cents, related, is_return, items): (i64, String, i64, String) = conn
+            .query_row(
+                "SELECT total_amount_cents, related_order_id, is_return, items
+                 FROM orders WHERE id = ?1",
+                params![created.return_order_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(total_cents, -800);
+        assert_eq!(related, "sale-1");
+        assert_eq!(is_return, 1);
+        let items = parse_items(&items);
+        assert_eq!(items[0]["total_price"], json!(-8.0));
+        assert_eq!(items[0]["returnOf"]["lineIndex"], json!(0));
+
+        let (amount_cents, return_order_id): (i64, String) = conn
+            .query_row(
+                "SELECT amount_cents, return_order_id FROM payment_adjustments
+                 WHERE order_id = 'sale-1' AND adjustment_type = 'refund'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(amount_cents, 800);
+        assert_eq!(return_order_id, created.return_order_id);
+
+        let available = returnable_lines(&conn, "sale-1").unwrap();
+        assert_eq!(available[0].available(), 1.0);
+        assert_eq!(available[1].available(), 1.0);
+    }
+
+    #[test]
+    fn returning_more_than_sold_lists_what_is_left_and_writes_nothing() {
+        let conn = test_conn();
+        seed_sale(&conn);
+        let first = [ReturnLine {
+            line_index: 0,
+            quantity: 1.0,
+        }];
+        create_return(&conn, &request(&first)).unwrap();
+
+        let too_many = [ReturnLine {
+            line_index: 0,
+            quantity: 2.0,
+        }];
+        let err = create_return(&conn, &request(&too_many)).unwrap_err();
+        assert!(err.contains("only 1 left of 2 sold"), "{err}");
+        assert!(err.contains("#0 Burger ×1, #1 Fries ×1"), "{err}");
+        let returns: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM orders WHERE COALESCE(is_return, 0) = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(returns, 1);
+
+        // The last of the items gives back exactly what the total has left.
+        let rest = [
+            ReturnLine {
+                line_index: 0,
+                quantity: 1.0,
+            },
+            ReturnLine {
+                line_index: 1,
+                quantity: 1.0,
+            },
+        ];
+        let last = create_return(&conn, &request(&rest)).unwrap();
+        assert_eq!(last.refund_cents, 1200);
+        let status: String = conn
+            .query_row(
+                "SELECT status FROM order_payments WHERE id = 'pay-1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(status, "refunded");
+    }
+}
@@ -0,0 +1,831 @@
+//! Local cache of the staff schedule and the clock-in check against it.
+//!
+//! Scheduled shifts live in Supabase (`salon_staff_shifts`). They used to be
+//! fetched on every schedule view and never compared with actual clock-ins,
+//! so staff could clock in hours early without anyone noticing. They are now
+//! kept in `scheduled_shifts`, refreshed by the sync cycle (at most every 15
+//! minutes) and whenever the schedule screen asks for a range.
+//!
+//! `check_clock_in` matches a clock-in against the staff member's scheduled
+//! shift for today. Clocking in more than `shifts.clock_in_early_tolerance_minutes`
+//! (default 15) before the scheduled start, or with no scheduled shift at
+//! all, needs manager approval. The check only enforces while the cache is
+//! trustworthy: when it has never been refreshed, holds nothing for today, or
+//! is older than `shifts.schedule_max_age_hours` (default 24), the clock-in
+//! is allowed and flagged `unverified` instead.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::db::{self, DbState};
+use crate::supabase::SupabaseQuery;
+
+pub const REMOTE_TABLE: &str = "salon_staff_shifts";
+
+const SETTINGS_CATEGORY: &str = "shifts";
+const SYNCED_AT_KEY: &str = "schedule_synced_at";
+const EARLY_TOLERANCE_KEY: &str = "clock_in_early_tolerance_minutes";
+const MAX_AGE_KEY: &str = "schedule_max_age_hours";
+const DEFAULT_EARLY_TOLERANCE_MINUTES: i64 = 15;
+const DEFAULT_MAX_AGE_HOURS: i64 = 24;
+
+/// The sync cycle refreshes yesterday through two weeks ahead.
+const REFRESH_DAYS_BEHIND: i64 = 1;
+const REFRESH_DAYS_AHEAD: i64 = 14;
+const REFRESH_INTERVAL_MS: i64 = 15 * 60_000;
+static LAST_REFRESH_MS: AtomicI64 = AtomicI64::new(0);
+
+/// Scheduled shifts with these statuses are ignored when matching clock-ins.
+const INACTIVE_STATUSES: &[&str] = &["cancelled", "canceled"];
+
+const SELECT_COLUMNS: &str = "id, staff_id, branch_id, start_time, end_time, break_start,
+    break_end, status, notes, staff_name, staff_code";
+
+/// Supabase query for a branch's scheduled shifts, with the staff join the
+/// schedule screen shows.
+pub fn remote_query(branch_id: &str) -> SupabaseQuery {
+    SupabaseQuery::new(REMOTE_TABLE)
+        .select(
+            "id,staff_id,branch_id,start_time,end_time,break_start,break_end,status,notes,staff(id,first_name,last_name,staff_code)",
+        )
+        .filter("branch_id", format!("eq.{branch_id}"))
+        .order("start_time.asc")
+}
+
+fn utc_string(ts: DateTime<Utc>) -> String {
+    ts.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Parse a schedule bound or timestamp. Accepts RFC 3339, a naive date-time
+/// (taken as UTC, as PostgREST does) or a bare date (UTC midnight).
+pub fn parse_timestamp(raw: &str) -> Option<DateTime<Utc>> {
+    let raw = raw.trim();
+    if let Ok(ts) = DateTime::parse_from_rfc3339(raw) {
+        return Some(ts.with_timezone(&Utc));
+    }
+    if let Some(naive) = [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok())
+    {
+        return Some(Utc.from_utc_datetime(&naive));
+    }
+    NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|naive| Utc.from_utc_datetime(&naive))
+}
+
+/// `[start, end]` of the local calendar day `date`, in UTC.
+pub fn local_day_bounds(date: NaiveDate) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    let at = |h, m, s| {
+        Local
+            .from_local_datetime(&date.and_hms_opt(h, m, s)?)
+            .earliest()
+            .map(|ts| ts.with_timezone(&Utc))
+    };
+    let start = at(0, 0, 0).ok_or("Failed to compute local start of day")?;
+    let end = at(23, 59, 59).ok_or("Failed to compute local end of day")?;
+    Ok((start, end))
+}
+
+fn text(v: &Value, key: &str) -> Option<String> {
+    match v.get(key)? {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn timestamp_text(v: &Value, key: &str) -> Option<String> {
+    text(v, key)
+        .and_then(|raw| parse_timestamp(&raw))
+        .map(utc_string)
+}
+
+/// Name and code from the `staff(...)` join, which PostgREST returns as an
+/// object or a one-element array.
+fn staff_identity(row: &Value) -> (Option<String>, Option<String>) {
+    let staff = match row.get("staff") {
+        Some(Value::Array(arr)) => arr.first(),
+        other => other,
+    };
+    let Some(staff) = staff.filter(|s| s.is_object()) else {
+        return (None, None);
+    };
+    let first = text(staff, "first_name").unwrap_or_default();
+    let last = text(staff, "last_name").unwrap_or_default();
+    let name = format!("{first} {last}").trim().to_string();
+    (
+        Some(name).filter(|name| !name.is_empty()),
+        text(staff, "staff_code"),
+    )
+}
+
+/// Replace the cached scheduled shifts whose start falls in `[from, to]`
+/// with the rows just fetched for that window. `staff_id` narrows the
+/// replacement to one staff member; only a whole-branch refresh marks the
+/// cache as fresh. Returns the number of rows stored.
+pub fn store_remote(
+    conn: &Connection,
+    branch_id: &str,
+    staff_id: Option<&str>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    rows: &[Value],
+    now: &str,
+) -> Result<usize, String> {
+    conn.execute(
+        "DELETE FROM scheduled_shifts
+         WHERE branch_id = ?1 AND start_time >= ?2 AND start_time <= ?3
+           AND (?4 IS NULL OR staff_id = ?4)",
+        params![branch_id, utc_string(from), utc_string(to), staff_id],
+    )
+    .map_err(|e| format!("clear scheduled shifts: {e}"))?;
+
+    let mut stored = 0;
+    for row in rows {
+        let (Some(id), Some(row_staff_id), Some(start_time)) = (
+            text(row, "id"),
+            text(row, "staff_id"),
+            timestamp_text(row, "start_time"),
+        ) else {
+            warn!(row = %row, "Skipping scheduled shift without id, staff or start time");
+            continue;
+        };
+        let (staff_name, staff_code) = staff_identity(row);
+        conn.execute(
+            "INSERT INTO scheduled_shifts (
+                id, staff_id, branch_id, start_time, end_time, break_start, break_end,
+                status, notes, staff_name, staff_code, synced_at
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+             ON CONFLICT(id) DO UPDATE SET
+                staff_id = excluded.staff_id,
+                branch_id = excluded.branch_id,
+                start_time = excluded.start_time,
+                end_time = excluded.end_time,
+                break_start = excluded.break_start,
+                break_end = excluded.break_end,
+                status = excluded.status,
+                notes = excluded.notes,
+                staff_name = excluded.staff_name,
+                staff_code = excluded.staff_code,
+                synced_at = excluded.synced_at",
+            params![
+                id,
+                row_staff_id,
+                text(row, "branch_id").unwrap_or_else(|| branch_id.to_string()),
+                start_time,
+                timestamp_text(row, "end_time"),
+                timestamp_text(row, "break_start"),
+                timestamp_text(row, "break_end"),
+                text(row, "status"),
+                text(row, "notes"),
+                staff_name,
+                staff_code,
+                now,
+            ],
+        )
+        .map_err(|e| format!("store scheduled shift {id}: {e}"))?;
+        stored += 1;
+    }
+
+    if staff_id.is_none() {
+        db::set_setting(conn, SETTINGS_CATEGORY, SYNCED_AT_KEY, now)?;
+    }
+    Ok(stored)
+}
+
+fn row_to_json(row: &rusqlite::Row) -> rusqlite::Result<Value> {
+    let staff_name: Option<String> = row.get(9)?;
+    Ok(json!({
+        "id": row.get::<_, String>(0)?,
+        "staffId": row.get::<_, String>(1)?,
+        "branchId": row.get::<_, String>(2)?,
+        "startTime": row.get::<_, String>(3)?,
+        "endTime": row.get::<_, Option<String>>(4)?,
+        "breakStart": row.get::<_, Option<String>>(5)?,
+        "breakEnd": row.get::<_, Option<String>>(6)?,
+        "status": row.get::<_, Option<String>>(7)?,
+        "notes": row.get::<_, Option<String>>(8)?,
+        "staffName": staff_name.unwrap_or_else(|| "Unknown".to_string()),
+        "staffCode": row.get::<_, Option<String>>(10)?.unwrap_or_default(),
+    }))
+}
+
+/// Cached scheduled shifts starting in `[from, to]`, oldest first.
+pub fn list(
+    conn: &Connection,
+    branch_id: Option<&str>,
+    staff_id: Option<&str>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<Value>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM scheduled_shifts
+             WHERE start_time >= ?1 AND start_time <= ?2
+               AND (?3 IS NULL OR branch_id = ?3)
+               AND (?4 IS NULL OR staff_id = ?4)
+             ORDER BY start_time ASC, id ASC"
+        ))
+        .map_err(|e| format!("prepare scheduled shifts: {e}"))?;
+    let rows = stmt
+        .query_map(
+            params![utc_string(from), utc_string(to), branch_id, staff_id],
+            row_to_json,
+        )
+        .map_err(|e| format!("query scheduled shifts: {e}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("read scheduled shift: {e}"))
+}
+
+/// When the whole-branch schedule was last refreshed, if ever.
+pub fn synced_at(conn: &Connection) -> Option<DateTime<Utc>> {
+    db::get_setting(conn, SETTINGS_CATEGORY, SYNCED_AT_KEY).and_then(|raw| parse_timestamp(&raw))
+}
+
+/// Fetch yesterday through two weeks ahead for `branch_id` and replace that
+/// window in the cache.
+pub async fn refresh(db: &DbState, branch_id: &str) -> Result<usize, String> {
+    let today = Local::now().date_naive();
+    let (from, _) = local_day_bounds(today - Duration::days(REFRESH_DAYS_BEHIND))?;
+    let (_, to) = local_day_bounds(today + Duration::days(REFRESH_DAYS_AHEAD))?;
+    let rows = remote_query(branch_id)
+        .filter("start_time", format!("gte.{}", utc_string(from)))
+        .filter("start_time", format!("lte.{}", utc_string(to)))
+        .fetch_all()
+        .await?;
+    LAST_REFRESH_MS.store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    let now = Utc::now().to_rfc3339();
+    let stored = db.write(|conn| {
+        conn.execute_batch("BEGIN IMMEDIATE")
+            .map_err(|e| format!("begin schedule refresh: {e}"))?;
+        match store_remote(conn, branch_id, None, from, to, &rows, &now) {
+            Ok(stored) => {
+                conn.execute_batch("COMMIT")
+                    .map_err(|e| format!("commit schedule refresh: {e}"))?;
+                Ok(stored)
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    })?;
+    info!(branch_id = %branch_id, stored, "Staff schedule refreshed");
+    Ok(stored)
+}
+
+/// `refresh`, unless one ran within the last 15 minutes. Called from the
+/// sync cycle; failures are logged and never fail the cycle.
+pub async fn refresh_if_due(db: &DbState, branch_id: &str) {
+    let now_ms = Utc::now().timestamp_millis();
+    if now_ms - LAST_REFRESH_MS.load(Ordering::Relaxed) < REFRESH_INTERVAL_MS {
+        return;
+    }
+    LAST_REFRESH_MS.store(now_ms, Ordering::Relaxed);
+    if let Err(e) = refresh(db, branch_id).await {
+        warn!(error = %e, "Staff schedule refresh failed");
+    }
+}
+
+fn setting_i64(conn: &Connection, key: &str, default: i64) -> i64 {
+    db::get_setting(conn, SETTINGS_CATEGORY, key)
+        .and_then(|raw| raw.trim().parse::<i64>().ok())
+        .filter(|value| *value >= 0)
+        .unwrap_or(default)
+}
+
+/// Whether the cache can be trusted to say who is scheduled today.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheState {
+    Fresh,
+    /// Never refreshed, or nothing scheduled for the branch today.
+    Empty,
+    /// Last refreshed longer ago than `shifts.schedule_max_age_hours`.
+    Stale,
+}
+
+impl CacheState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Fresh => "fresh",
+            Self::Empty => "empty",
+            Self::Stale => "stale",
+        }
+    }
+}
+
+/// How a clock-in compares to the staff member's schedule for today.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockInCheck {
+    pub scheduled_shift_id: Option<String>,
+    pub scheduled_start: Option<String>,
+    pub scheduled_end: Option<String>,
+    pub early_minutes: i64,
+    pub late_minutes: i64,
+    pub unscheduled: bool,
+    pub cache: CacheState,
+    pub tolerance_minutes: i64,
+}
+
+impl ClockInCheck {
+    /// Early beyond the tolerance, or unscheduled, against a usable cache.
+    pub fn needs_approval(&self) -> bool {
+        self.cache == CacheState::Fresh
+            && (self.unscheduled || self.early_minutes > self.tolerance_minutes)
+    }
+
+    /// `unverified` when the cache could not be trusted; otherwise
+    /// `unscheduled`, `early` (beyond the tolerance), `late` or `on_time`.
+    pub fn status(&self) -> &'static str {
+        if self.cache != CacheState::Fresh {
+            "unverified"
+        } else if self.unscheduled {
+            "unscheduled"
+        } else if self.early_minutes > self.tolerance_minutes {
+            "early"
+        } else if self.late_minutes > 0 {
+            "late"
+        } else {
+            "on_time"
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let warning = match self.cache {
+            CacheState::Fresh => None,
+            CacheState::Empty => Some("Schedule not available; clock-in was not checked"),
+            CacheState::Stale => Some("Schedule is out of date; clock-in was not checked"),
+        };
+        json!({
+            "status": self.status(),
+            "scheduledShiftId": self.scheduled_shift_id,
+            "scheduledStart": self.scheduled_start,
+            "scheduledEnd": self.scheduled_end,
+            "earlyMinutes": self.early_minutes,
+            "lateMinutes": self.late_minutes,
+            "unscheduled": self.unscheduled,
+            "toleranceMinutes": self.tolerance_minutes,
+            "cacheState": self.cache.as_str(),
+            "approvalRequired": self.needs_approval(),
+            "warning": warning,
+        })
+    }
+}
+
+/// Match a clock-in at `now` against `staff_id`'s scheduled shifts for the
+/// local day. With more than one (split shifts), the one starting closest to
+/// `now` wins.
+pub fn check_clock_in(
+    conn: &Connection,
+    branch_id: &str,
+    staff_id: &str,
+    now: DateTime<Utc>,
+) -> Result<ClockInCheck, String> {
+    let tolerance_minutes = setting_i64(conn, EARLY_TOLERANCE_KEY, DEFAULT_EARLY_TOLERANCE_MINUTES);
+    let max_age = Duration::hours(setting_i64(conn, MAX_AGE_KEY, DEFAULT_MAX_AGE_HOURS));
+    let (day_start, day_end) = local_day_bounds(now.with_timezone(&Local).date_naive())?;
+
+    let today = list(conn, Some(branch_id), None, day_start, day_end)?;
+    let cache = match synced_at(conn) {
+        None => CacheState::Empty,
+        Some(_) if today.is_empty() => CacheState::Empty,
+        Some(at) if now - at > max_age => CacheState::Stale,
+        Some(_) => CacheState::Fresh,
+    };
+
+    let scheduled = today
+        .iter()
+        .filter(|shift| shift["staffId"].as_str() == Some(staff_id))
+        .filter(|shift| {
+            !shift["status"]
+                .as_str()
+                .is_some_and(|status| INACTIVE_STATUSES.contains(&status))
+        })
+        .filter_map(|shift| {
+            let start = parse_timestamp(shift["startTime"].as_str()?)?;
+            Some((shift, start))
+        })
+        .min_by_key(|(_, start)| (*start - now).num_seconds().abs());
+
+    let (early_minutes, late_minutes) = scheduled
+        .map(|(_, start)| {
+            let offset = (start - now).num_minutes();
+            (offset.max(0), (-offset).max(0))
+        })
+        .unwrap_or((0, 0));
+    Ok(ClockInCheck {
+        scheduled_shift_id: scheduled.and_then(|(shift, _)| text(shift, "id")),
+        scheduled_start: scheduled.and_then(|(shift, _)| text(shift, "startTime")),
+        scheduled_end: scheduled.and_then(|(shift, _)| text(shift, "endTime")),
+        early_minutes,
+        late_minutes,
+        unscheduled: scheduled.is_none(),
+        cache,
+        tolerance_minutes,
+    })
+}
+
+fn minutes_between(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Option<i64> {
+    Some((to? - from?).num_minutes())
+}
+
+#[derive(Default)]
+struct StaffAdherence {
+    staff_name: Option<String>,
+    scheduled: i64,
+    worked: i64,
+    no_shows: i64,
+    unscheduled: i64,
+    early_clock_ins: i64,
+    late_clock_ins: i64,
+    scheduled_minutes: i64,
+    actual_minutes: i64,
+    shifts: Vec<Value>,
+}
+
+/// Per-staff scheduled vs actual times for scheduled shifts starting in
+/// `[from, to]`, plus clock-ins in that range that matched no scheduled
+/// shift. A clock-in is matched through the `scheduled_shift_id` recorded at
+/// check-in; older shifts fall back to the same staff member's nearest
+/// scheduled start within 12 hours. Variances are in minutes, positive when
+/// late.
+pub fn adherence(
+    conn: &Connection,
+    branch_id: Option<&str>,
+    staff_id: Option<&str>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<Value, String> {
+    let scheduled = list(conn, branch_id, staff_id, from, to)?;
+
+    // Clock-ins can sit a few hours either side of the scheduled window.
+    let margin = Duration::hours(12);
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, staff_id, staff_name, role_type, check_in_time, check_out_time,
+                    status, scheduled_shift_id, schedule_check_status
+             FROM staff_shifts
+             WHERE check_in_time >= ?1 AND check_in_time <= ?2
+               AND (?3 IS NULL OR branch_id = ?3)
+               AND (?4 IS NULL OR staff_id = ?4)
+             ORDER BY check_in_time ASC",
+        )
+        .map_err(|e| format!("prepare adherence shifts: {e}"))?;
+    let actual: Vec<Value> = stmt
+        .query_map(
+            params![
+                (from - margin).to_rfc3339(),
+                (to + margin).to_rfc3339(),
+                branch_id,
+                staff_id
+            ],
+            |row| {
+                Ok(json!({
+                    "id": row.get::<_, String>(0)?,
+                    "staffId": row.get::<_, String>(1)?,
+                    "staffName": row.get::<_, Option<String>>(2)?,
+                    "roleType": row.get::<_, String>(3)?,
+                    "checkInTime": row.get::<_, String>(4)?,
+                    "checkOutTime": row.get::<_, Option<String>>(5)?,
+                    "status": row.get::<_, String>(6)?,
+                    "scheduledShiftId": row.get::<_, Option<String>>(7)?,
+                    "scheduleCheckStatus": row.get::<_, Option<String>>(8)?,
+                }))
+            },
+        )
+        .map_err(|e| format!("query adherence shifts: {e}"))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("read adherence shift: {e}"))?;
+
+    let ts = |v: &Value, key: &str| v[key].as_str().and_then(parse_timestamp);
+    let mut used: HashSet<String> = HashSet::new();
+    let mut by_staff: BTreeMap<String, StaffAdherence> = BTreeMap::new();
+
+    for shift in scheduled.iter().filter(|shift| {
+        !shift["status"]
+            .as_str()
+            .is_some_and(|status| INACTIVE_STATUSES.contains(&status))
+    }) {
+        let shift_id = shift["id"].as_str().unwrap_or_default();
+        let shift_staff = shift["staffId"].as_str().unwrap_or_default();
+        let start = ts(shift, "startTime");
+        let end = ts(shift, "endTime");
+        let linked = actual.iter().find(|a| {
+            a["scheduledShiftId"].as_str() == Some(shift_id)
+                && !used.contains(a["id"].as_str().unwrap_or_default())
+        });
+        let matched = linked.or_else(|| {
+            let start = start?;
+            actual
+                .iter()
+                .filter(|a| {
+                    a["staffId"].as_str() == Some(shift_staff)
+                        && a["scheduledShiftId"].is_null()
+                        && !used.contains(a["id"].as_str().unwrap_or_default())
+                })
+                .filter_map(|a| Some((a, (ts(a, "checkInTime")? - start).num_minutes().abs())))
+                .filter(|(_, distance)| *distance <= margin.num_minutes())
+                .min_by_key(|(_, distance)| *distance)
+                .map(|(a, _)| a)
+        });
+        if let Some(a) = matched {
+            used.insert(a["id"].as_str().unwrap_or_default().to_string());
+        }
+
+        let entry = by_staff.entry(shift_staff.to_string()).or_default();
+        entry.staff_name = entry
+            .staff_name
+            .take()
+            .or_else(|| shift["staffName"].as_str().map(str::to_string));
+        entry.scheduled += 1;
+        entry.scheduled_minutes += minutes_between(start, end).unwrap_or(0).max(0);
+
+        let check_in = matched.and_then(|a| ts(a, "checkInTime"));
+        let check_out = matched.and_then(|a| ts(a, "checkOutTime"));
+        let start_variance = minutes_between(start, check_in);
+        let end_variance = minutes_between(end, check_out);
+        let status = match (matched, start_variance) {
+            (Some(_), Some(variance)) if variance < 0 => "early",
+            (Some(_), Some(variance)) if variance > 0 => "late",
+            (Some(_), _) => "on_time",
+            (None, _) if start.is_some_and(|start| start <= now) => "no_show",
+            (None, _) => "upcoming",
+        };
+        match status {
+            "early" => entry.early_clock_ins += 1,
+            "late" => entry.late_clock_ins += 1,
+            "no_show" => entry.no_shows += 1,
+            _ => {}
+        }
+        if matched.is_some() {
+            entry.worked += 1;
+            entry.actual_minutes += minutes_between(check_in, check_out).unwrap_or(0).max(0);
+        }
+        entry.shifts.push(json!({
+            "scheduledShiftId": shift_id,
+            "shiftId": matched.map(|a| a["id"].clone()),
+            "scheduledStart": shift["startTime"],
+            "scheduledEnd": shift["endTime"],
+            "actualCheckIn": matched.map(|a| a["checkInTime"].clone()),
+            "actualCheckOut": matched.map(|a| a["checkOutTime"].clone()),
+            "startVarianceMinutes": start_variance,
+            "endVarianceMinutes": end_variance,
+            "status": status,
+        }));
+    }
+
+    for a in &actual {
+        let id = a["id"].as_str().unwrap_or_default();
+        let check_in = ts(a, "checkInTime");
+        if used.contains(id) || !check_in.is_some_and(|at| at >= from && at <= to) {
+            continue;
+        }
+        let entry = by_staff
+            .entry(a["staffId"].as_str().unwrap_or_default().to_string())
+            .or_default();
+        entry.staff_name = entry
+            .staff_name
+            .take()
+            .or_else(|| a["staffName"].as_str().map(str::to_string));
+        entry.unscheduled += 1;
+        entry.actual_minutes += minutes_between(check_in, ts(a, "checkOutTime"))
+            .unwrap_or(0)
+            .max(0);
+        entry.shifts.push(json!({
+            "scheduledShiftId": Value::Null,
+            "shiftId": id,
+            "scheduledStart": Value::Null,
+            "scheduledEnd": Value::Null,
+            "actualCheckIn": a["checkInTime"],
+            "actualCheckOut": a["checkOutTime"],
+            "startVarianceMinutes": Value::Null,
+            "endVarianceMinutes": Value::Null,
+            "status": "unscheduled",
+        }));
+    }
+
+    let staff: Vec<Value> = by_staff
+        .into_iter()
+        .map(|(staff_id, entry)| {
+            json!({
+                "staffId": staff_id,
+                "staffName": entry.staff_name,
+                "scheduledShifts": entry.scheduled,
+                "workedShifts": entry.worked,
+                "noShows": entry.no_shows,
+                "unscheduledShifts": entry.unscheduled,
+                "earlyClockIns": entry.early_clock_ins,
+                "lateClockIns": entry.late_clock_ins,
+                "scheduledMinutes": entry.scheduled_minutes,
+                "actualMinutes": entry.actual_minutes,
+                "shifts": entry.shifts,
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "from": utc_string(from),
+        "to": utc_string(to),
+        "scheduleSyncedAt": synced_at(conn).map(utc_string),
+        "staff": staff,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn scheduled(id: &str, staff_id: &str, start: DateTime<Utc>, hours: i64) -> Value {
+        json!({
+            "id": id,
+            "staff_id": staff_id,
+            "branch_id": "branch-1",
+            "start_time": start.to_rfc3339(),
+            "end_time": (start + Duration::hours(hours)).to_rfc3339(),
+            "status": "scheduled",
+            "staff": [{ "first_name": "Maria", "last_name": "K", "staff_code": "M1" }],
+        })
+    }
+
+    fn store_today(conn: &Connection, rows: &[Value], synced_at: DateTime<Utc>) {
+        let (from, to) = local_day_bounds(Local::now().date_naive()).unwrap();
+        store_remote(
+            conn,
+            "branch-1",
+            None,
+            from - Duration::days(1),
+            to + Duration::days(1),
+            rows,
+            &synced_at.to_rfc3339(),
+        )
+        .unwrap();
+    }
+
+    /// A local noon today, so early/late offsets never cross midnight.
+    fn noon() -> DateTime<Utc> {
+        let date = Local::now().date_naive();
+        Local
+            .from_local_datetime(&date.and_hms_opt(12, 0, 0).unwrap())
+            .earliest()
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn clock_in_is_unverified_without_a_usable_cache() {
+        let conn = test_conn();
+        let now = noon();
+        let check = check_clock_in(&conn, "branch-1", "staff-1", now).unwrap();
+        assert_eq!(check.cache, CacheState::Empty);
+        assert!(check.unscheduled);
+        assert!(!check.needs_approval());
+        assert_eq!(check.status(), "unverified");
+
+        store_today(
+            &conn,
+            &[scheduled("s1", "staff-2", now, 8)],
+            now - Duration::hours(30),
+        );
+        let check = check_clock_in(&conn, "branch-1", "staff-1", now).unwrap();
+        assert_eq!(check.cache, CacheState::Stale);
+        assert!(!check.needs_approval());
+    }
+
+    #[test]
+    fn clock_in_needs_approval_when_early_or_unscheduled() {
+        let conn = test_conn();
+        let now = noon();
+        store_today(
+            &conn,
+            &[
+                scheduled("s-early", "staff-early", now + Duration::minutes(45), 6),
+                scheduled("s-ok", "staff-ok", now + Duration::minutes(10), 6),
+                scheduled("s-late", "staff-late", now - Duration::minutes(20), 6),
+            ],
+            now,
+        );
+
+        let early = check_clock_in(&conn, "branch-1", "staff-early", now).unwrap();
+        assert_eq!(early.scheduled_shift_id.as_deref(), Some("s-early"));
+        assert_eq!(early.early_minutes, 45);
+        assert!(early.needs_approval());
+        assert_eq!(early.status(), "early");
+
+        let within = check_clock_in(&conn, "branch-1", "staff-ok", now).unwrap();
+        assert_eq!(within.early_minutes, 10);
+        assert!(!within.needs_approval());
+        assert_eq!(within.status(), "on_time");
+
+        let late = check_clock_in(&conn, "branch-1", "staff-late", now).unwrap();
+        assert_eq!(late.late_minutes, 20);
+        assert!(!late.needs_approval());
+        assert_eq!(late.status(), "late");
+
+        let walk_in = check_clock_in(&conn, "branch-1", "staff-none", now).unwrap();
+        assert!(walk_in.unscheduled);
+        assert!(walk_in.needs_approval());
+
+        db::set_setting(&conn, "shifts", "clock_in_early_tolerance_minutes", "60").unwrap();
+        let relaxed = check_clock_in(&conn, "branch-1", "staff-early", now).unwrap();
+        assert!(!relaxed.needs_approval());
+    }
+
+    #[test]
+    fn refresh_window_replaces_removed_shifts() {
+        let conn = test_conn();
+        let now = noon();
+        store_today(
+            &conn,
+            &[
+                scheduled("s1", "staff-1", now, 4),
+                scheduled("s2", "staff-2", now, 4),
+            ],
+            now,
+        );
+        store_today(&conn, &[scheduled("s1", "staff-1", now, 4)], now);
+
+        let (from, to) = local_day_bounds(now.with_timezone(&Local).date_naive()).unwrap();
+        let cached = list(&conn, Some("branch-1"), None, from, to).unwrap();
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[0]["id"], "s1");
+        assert_eq!(cached[0]["staffName"], "Maria K");
+        assert_eq!(cached[0]["staffCode"], "M1");
+    }
+
+    #[test]
+    fn adherence_reports_scheduled_vs_actual() {
+        let conn = test_conn();
+        let now = noon();
+        let start = now - Duration::hours(4);
+        store_today(
+            &conn,
+            &[
+                scheduled("s-worked", "staff-1", start, 3),
+                scheduled("s-missed", "staff-2", start, 3),
+            ],
+            now,
+        );
+        for (id, staff_id, check_in, check_out, scheduled_shift_id) in [
+            (
+                "shift-1",
+                "staff-1",
+                start + Duration::minutes(7),
+                Some(start + Duration::hours(3)),
+                Some("s-worked"),
+            ),
+            ("shift-3", "staff-3", start, None, None),
+        ] {
+            conn.execute(
+                "INSERT INTO staff_shifts (id, staff_id, branch_id, role_type, check_in_time, check_out_time,
+                                           status, scheduled_shift_id, sync_status, created_at, updated_at)
+                 VALUES (?1, ?2, 'branch-1', 'server', ?3, ?4, 'active', ?5, 'pending', ?3, ?3)",
+                params![
+                    id,
+                    staff_id,
+                    check_in.to_rfc3339(),
+                    check_out.map(|at| at.to_rfc3339()),
+                    scheduled_shift_id
+                ],
+            )
+            .unwrap();
+        }
+
+        let (from, to) = local_day_bounds(now.with_timezone(&Local).date_naive()).unwrap();
+        let report = adherence(&conn, Some("branch-1"), None, from, to, now).unwrap();
+        let staff = report["staff"].as_array().unwrap();
+        assert_eq!(staff.len(), 3);
+
+        assert_eq!(staff[0]["staffId"], "staff-1");
+        assert_eq!(staff[0]["lateClockIns"], 1);
+        assert_eq!(staff[0]["scheduledMinutes"], 180);
+        assert_eq!(staff[0]["actualMinutes"], 173);
+        assert_eq!(staff[0]["shifts"][0]["startVarianceMinutes"], 7);
+        assert_eq!(staff[0]["shifts"][0]["endVarianceMinutes"], 0);
+
+        assert_eq!(staff[1]["staffId"], "staff-2");
+        assert_eq!(staff[1]["noShows"], 1);
+        assert_eq!(staff[1]["shifts"][0]["status"], "no_show");
+
+        assert_eq!(staff[2]["staffId"], "staff-3");
+        assert_eq!(staff[2]["unscheduledShifts"], 1);
+        assert_eq!(staff[2]["shifts"][0]["status"], "unscheduled");
+    }
+}
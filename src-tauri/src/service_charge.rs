@@ -0,0 +1,619 @@
+//! Automatic percentage service charge on qualifying orders.
+//!
+//! The configuration lives in `local_settings` (`service_charge.config`) as
+//! JSON: the `percentage`, the `orderTypes` it applies to (dine-in by
+//! default), whether it is computed on the item total before or after the
+//! order discount, and whether it is `taxable`. A taxable charge is taxed at
+//! the default rate like an item line: under exclusive tax its tax is added
+//! on top, under inclusive tax it is part of the charge.
+//!
+//! The rate, basis and taxability are captured on the order when the charge
+//! is applied, and item edits re-price it at that captured rate, so a later
+//! configuration change never touches existing orders. An order that already
+//! carries an explicit `serviceCharge` (delivery platforms) keeps it.
+//! `order_waive_service_charge` removes the charge from one order with a
+//! manager PIN and a reason code; refunds carry a proportional share of the
+//! charge in `payment_adjustments.service_charge_cents`.
+
+use rusqlite::{params, Connection, ToSql};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::db;
+use crate::money::{Cents, RoundingRule};
+use crate::reasons::ResolvedReason;
+use crate::{order_events, tax};
+
+const SETTINGS_CATEGORY: &str = "service_charge";
+const CONFIG_KEY: &str = "config";
+
+fn default_order_types() -> Vec<String> {
+    vec!["dine-in".to_string()]
+}
+
+fn normalize_order_type(raw: &str) -> String {
+    raw.trim().to_ascii_lowercase().replace('_', "-")
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceChargeConfig {
+    /// Percentage, e.g. `10.0`; zero disables the charge.
+    #[serde(default)]
+    pub percentage: f64,
+    #[serde(default = "default_order_types", alias = "order_types")]
+    pub order_types: Vec<String>,
+    /// Compute on the item total before the order discount is taken off.
+    #[serde(default, alias = "apply_before_discount")]
+    pub apply_before_discount: bool,
+    #[serde(default)]
+    pub taxable: bool,
+}
+
+impl Default for ServiceChargeConfig {
+    fn default() -> Self {
+        Self {
+            percentage: 0.0,
+            order_types: default_order_types(),
+            apply_before_discount: false,
+            taxable: false,
+        }
+    }
+}
+
+impl ServiceChargeConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.percentage.is_finite() || !(0.0..=100.0).contains(&self.percentage) {
+            return Err("Service charge must be between 0 and 100 percent".into());
+        }
+        if self.percentage > 0.0 && self.order_types.is_empty() {
+            return Err("Service charge needs at least one order type".into());
+        }
+        if self.order_types.iter().any(|kind| kind.trim().is_empty()) {
+            return Err("Service charge order types cannot be empty".into());
+        }
+        Ok(())
+    }
+
+    pub fn applies_to(&self, order_type: &str) -> bool {
+        let order_type = normalize_order_type(order_type);
+        self.percentage > 0.0
+            && self
+                .order_types
+                .iter()
+                .any(|kind| normalize_order_type(kind) == order_type)
+    }
+}
+
+pub fn load_config(conn: &Connection) -> ServiceChargeConfig {
+    db::get_setting(conn, SETTINGS_CATEGORY, CONFIG_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_config(conn: &Connection, config: &ServiceChargeConfig) -> Result<(), String> {
+    config.validate()?;
+    let mut config = config.clone();
+    config.order_types = config
+        .order_types
+        .iter()
+        .map(|kind| normalize_order_type(kind))
+        .collect();
+    let raw =
+        serde_json::to_string(&config).map_err(|e| format!("serialize service charge: {e}"))?;
+    db::set_setting(conn, SETTINGS_CATEGORY, CONFIG_KEY, &raw)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Basis {
+    BeforeDiscount,
+    AfterDiscount,
+}
+
+impl Basis {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::BeforeDiscount => "before_discount",
+            Self::AfterDiscount => "after_discount",
+        }
+    }
+
+    fn parse(raw: &str) -> Self {
+        if raw == "before_discount" {
+            Self::BeforeDiscount
+        } else {
+            Self::AfterDiscount
+        }
+    }
+}
+
+/// The terms a charge was applied under, as captured on the order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AppliedCharge {
+    pub rate: f64,
+    pub basis: Basis,
+    pub taxable: bool,
+}
+
+impl AppliedCharge {
+    /// The charge for a new order of `order_type`, if the configuration
+    /// covers it.
+    pub fn for_new_order(conn: &Connection, order_type: &str) -> Option<Self> {
+        let config = load_config(conn);
+        config.applies_to(order_type).then_some(Self {
+            rate: config.percentage,
+            basis: if config.apply_before_discount {
+                Basis::BeforeDiscount
+            } else {
+                Basis::AfterDiscount
+            },
+            taxable: config.taxable,
+        })
+    }
+
+    /// The charge captured on an order, unless none was applied or it was
+    /// waived.
+    pub fn load(conn: &Connection, order_id: &str) -> Result<Option<Self>, String> {
+        match conn.query_row(
+            "SELECT service_charge_rate, COALESCE(service_charge_basis, ''),
+                    COALESCE(service_charge_taxable, 0)
+             FROM orders
+             WHERE id = ?1 AND service_charge_rate IS NOT NULL
+               AND service_charge_waived_at IS NULL",
+            params![order_id],
+            |row| {
+                Ok(Self {
+                    rate: row.get(0)?,
+                    basis: Basis::parse(&row.get::<_, String>(1)?),
+                    taxable: row.get::<_, i64>(2)? != 0,
+                })
+            },
+        ) {
+            Ok(charge) => Ok(Some(charge)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(format!("load service charge: {e}")),
+        }
+    }
+
+    pub fn amount(&self, items_total: Cents, discount: Cents) -> Cents {
+        let base = match self.basis {
+            Basis::BeforeDiscount => items_total,
+            Basis::AfterDiscount => items_total - discount,
+        }
+        .max(Cents::ZERO);
+        Cents::round_with(base.to_f64_dp2() * self.rate / 100.0, RoundingRule::HalfUp)
+    }
+
+    /// Write the charge and its terms onto an order row.
+    pub fn store(&self, conn: &Connection, order_id: &str, amount: Cents) -> Result<(), String> {
+        conn.execute(
+            "UPDATE orders SET
+                 service_charge = ?2, service_charge_cents = ?3, service_charge_rate = ?4,
+                 service_charge_basis = ?5, service_charge_taxable = ?6
+             WHERE id = ?1",
+            params![
+                order_id,
+                amount.to_f64_dp2(),
+                amount.as_i64(),
+                self.rate,
+                self.basis.as_str(),
+                self.taxable,
+            ],
+        )
+        .map_err(|e| format!("store service charge: {e}"))?;
+        Ok(())
+    }
+
+    /// Add the charge and its terms to an outgoing order sync payload.
+    pub fn write_sync_fields(&self, obj: &mut Map<String, Value>, amount: Cents) {
+        for key in ["serviceCharge", "service_charge"] {
+            obj.insert(key.to_string(), json!(amount.to_f64_dp2()));
+        }
+        obj.insert("service_charge_cents".to_string(), json!(amount.as_i64()));
+        for key in ["serviceChargeRate", "service_charge_rate"] {
+            obj.insert(key.to_string(), json!(self.rate));
+        }
+        for key in ["serviceChargeTaxable", "service_charge_taxable"] {
+            obj.insert(key.to_string(), json!(self.taxable));
+        }
+    }
+}
+
+/// The charge as an order line, for the tax breakdown.
+pub fn charge_line(amount: Cents) -> Value {
+    json!({
+        "name": "Service charge",
+        "quantity": 1,
+        "total_price": amount.to_f64_dp2(),
+    })
+}
+
+/// What a charge adds to the order total: the charge itself, plus its tax
+/// when it is taxable and tax is exclusive.
+pub fn total_effect(conn: &Connection, amount: Cents, taxable: bool) -> Cents {
+    if !taxable || amount.is_zero() {
+        return amount;
+    }
+    let lines = tax::compute_breakdown(
+        &tax::load_config(conn),
+        &tax::MenuTaxLookup::default(),
+        &[charge_line(amount)],
+        RoundingRule::from_settings(conn),
+    );
+    if lines.is_empty() {
+        return amount;
+    }
+    lines.iter().map(|line| Cents::new(line.gross_cents)).sum()
+}
+
+/// The stored charge as a line for [`tax::capture_order_breakdown`], when
+/// it is taxable.
+pub fn taxable_line(conn: &Connection, order_id: &str) -> Option<Value> {
+    conn.query_row(
+        "SELECT COALESCE(service_charge_cents, 0) FROM orders
+         WHERE id = ?1 AND COALESCE(service_charge_taxable, 0) = 1",
+        params![order_id],
+        |row| row.get::<_, i64>(0),
+    )
+    .ok()
+    .filter(|cents| *cents > 0)
+    .map(|cents| charge_line(Cents::new(cents)))
+}
+
+/// A charge re-priced for new order items at the order's captured rate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Repriced {
+    pub charge: AppliedCharge,
+    pub previous: Cents,
+    pub amount: Cents,
+    /// What the new charge adds to the order total, its tax included.
+    pub effect: Cents,
+    /// Change in the order total against the previous charge.
+    pub total_delta: Cents,
+    /// Change in the order's exclusive tax.
+    pub tax_delta: Cents,
+}
+
+impl Repriced {
+    /// Store the new amount and move the order's tax by the charge's share.
+    /// The caller writes the order total.
+    pub fn store(&self, conn: &Connection, order_id: &str) -> Result<(), String> {
+        self.charge.store(conn, order_id, self.amount)?;
+        if !self.tax_delta.is_zero() {
+            conn.execute(
+                "UPDATE orders SET
+                     tax_amount = ROUND(COALESCE(tax_amount, 0) + ?2, 2),
+                     tax_amount_cents = COALESCE(tax_amount_cents, CAST(ROUND(COALESCE(tax_amount, 0) * 100) AS INTEGER)) + ?3
+                 WHERE id = ?1",
+                params![order_id, self.tax_delta.to_f64_dp2(), self.tax_delta.as_i64()],
+            )
+            .map_err(|e| format!("store service charge tax: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Re-price the order's charge for `items_total`. `discount` defaults to
+/// the order's stored discount.
+pub fn reprice(
+    conn: &Connection,
+    order_id: &str,
+    items_total: Cents,
+    discount: Option<Cents>,
+) -> Result<Option<Repriced>, String> {
+    let Some(charge) = AppliedCharge::load(conn, order_id)? else {
+        return Ok(None);
+    };
+    let (previous, stored_discount): (i64, i64) = conn
+        .query_row(
+            "SELECT COALESCE(service_charge_cents, 0),
+                    COALESCE(discount_amount_cents, CAST(ROUND(COALESCE(discount_amount, 0) * 100) AS INTEGER))
+             FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("load service charge amount: {e}"))?;
+    let previous = Cents::new(previous);
+    let amount = charge.amount(items_total, discount.unwrap_or(Cents::new(stored_discount)));
+    let previous_effect = total_effect(conn, previous, charge.taxable);
+    let effect = total_effect(conn, amount, charge.taxable);
+    Ok(Some(Repriced {
+        charge,
+        previous,
+        amount,
+        effect,
+        total_delta: effect - previous_effect,
+        tax_delta: (effect - amount) - (previous_effect - previous),
+    }))
+}
+
+/// Remove the charge from an unpaid order and record the waiver on the
+/// order timeline.
+pub fn waive(
+    conn: &Connection,
+    order_id: &str,
+    reason: &ResolvedReason,
+    manager_staff_id: Option<&str>,
+    now: &str,
+) -> Result<Value, String> {
+    let row = conn.query_row(
+        "SELECT COALESCE(service_charge_cents, CAST(ROUND(COALESCE(service_charge, 0) * 100) AS INTEGER)),
+                COALESCE(service_charge_taxable, 0), service_charge_waived_at,
+                COALESCE(payment_status, 'pending'),
+                COALESCE(total_amount_cents, CAST(ROUND(total_amount * 100) AS INTEGER), 0),
+                COALESCE(tax_amount_cents, CAST(ROUND(COALESCE(tax_amount, 0) * 100) AS INTEGER)),
+                COALESCE(items, '[]')
+         FROM orders WHERE id = ?1",
+        params![order_id],
+        |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)? != 0,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, i64>(5)?,
+                row.get::<_, String>(6)?,
+            ))
+        },
+    );
+    let (charge_cents, taxable, waived_at, payment_status, total_cents, tax_cents, items) =
+        match row {
+            Ok(row) => row,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Err("Order not found".into()),
+            Err(e) => return Err(format!("load order service charge: {e}")),
+        };
+    if waived_at.is_some() {
+        return Err("The service charge on this order was already waived".into());
+    }
+    if charge_cents <= 0 {
+        return Err("This order has no service charge".into());
+    }
+    if payment_status.eq_ignore_ascii_case("paid") {
+        return Err("The order is already paid; refund the service charge instead".into());
+    }
+
+    let amount = Cents::new(charge_cents);
+    let effect = total_effect(conn, amount, taxable);
+    let total = (Cents::new(total_cents) - effect).max(Cents::ZERO);
+    let tax_amount = (Cents::new(tax_cents) - (effect - amount)).max(Cents::ZERO);
+    conn.execute(
+        "UPDATE orders SET
+             total_amount = ?2, total_amount_cents = ?3,
+             tax_amount = ?4, tax_amount_cents = ?5,
+             service_charge = 0, service_charge_cents = 0,
+             service_charge_waived = ?6, service_charge_waived_cents = ?7,
+             service_charge_waived_at = ?8, service_charge_waiver_reason = ?9,
+             service_charge_waived_by = ?10,
+             sync_status = 'pending', updated_at = ?8
+         WHERE id = ?1",
+        params![
+            order_id,
+            total.to_f64_dp2(),
+            total.as_i64(),
+            tax_amount.to_f64_dp2(),
+            tax_amount.as_i64(),
+            amount.to_f64_dp2(),
+            amount.as_i64(),
+            now,
+            reason.code,
+            manager_staff_id,
+        ],
+    )
+    .map_err(|e| format!("waive service charge: {e}"))?;
+    let items: Vec<Value> = serde_json::from_str(&items).unwrap_or_default();
+    tax::capture_order_breakdown(conn, order_id, &items)?;
+    order_events::append(
+        conn,
+        order_id,
+        order_events::SERVICE_CHARGE_WAIVED,
+        manager_staff_id,
+        json!({
+            "amount": amount.to_f64_dp2(),
+            "reasonCode": reason.code,
+            "reason": reason.text,
+            "total": total.to_f64_dp2(),
+        }),
+    );
+    Ok(json!({
+        "waived": amount.to_f64_dp2(),
+        "waived_cents": amount.as_i64(),
+        "totalAmount": total.to_f64_dp2(),
+        "taxAmount": tax_amount.to_f64_dp2(),
+        "reasonCode": reason.code,
+    }))
+}
+
+/// The share of the order's service charge a refund of `refund` returns.
+/// Shares are taken on the cumulative refunded amount, so several partial
+/// refunds add up to the whole charge without drifting by rounding.
+pub fn refund_share(conn: &Connection, order_id: &str, refund: Cents) -> Result<Cents, String> {
+    let (charge, total, refunded, shared): (i64, i64, i64, i64) = conn
+        .query_row(
+            "SELECT COALESCE(o.service_charge_cents, CAST(ROUND(COALESCE(o.service_charge, 0) * 100) AS INTEGER)),
+                    COALESCE(o.total_amount_cents, CAST(ROUND(o.total_amount * 100) AS INTEGER), 0),
+                    (SELECT COALESCE(SUM(COALESCE(pa.amount_cents, CAST(ROUND(pa.amount * 100) AS INTEGER))), 0)
+                     FROM payment_adjustments pa
+                     WHERE pa.order_id = o.id AND pa.adjustment_type = 'refund'
+                       AND COALESCE(pa.adjustment_context, '') != 'edit_settlement'),
+                    (SELECT COALESCE(SUM(pa.service_charge_cents), 0)
+                     FROM payment_adjustments pa
+                     WHERE pa.order_id = o.id AND pa.adjustment_type = 'refund')
+             FROM orders o WHERE o.id = ?1",
+            params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| format!("load service charge refund share: {e}"))?;
+    if charge <= 0 || total <= 0 {
+        return Ok(Cents::ZERO);
+    }
+    let refunded = (refunded + refund.as_i64()).min(total) as i128;
+    let cumulative = (refunded * charge as i128 + total as i128 / 2) / total as i128;
+    Ok(Cents::new(
+        (cumulative as i64 - shared).clamp(0, charge - shared),
+    ))
+}
+
+/// Service charges for a Z-report scope, kept apart from sales.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ServiceChargeTotals {
+    pub orders: i64,
+    pub charged_cents: i64,
+    pub refunded_cents: i64,
+    pub waived_orders: i64,
+    pub waived_cents: i64,
+}
+
+impl ServiceChargeTotals {
+    /// Sum over the orders selected by `scope`, a `FROM orders o WHERE ...`
+    /// clause bound to `params`.
+    pub fn collect(conn: &Connection, scope: &str, params: &[&dyn ToSql]) -> Self {
+        let sql = format!(
+            "SELECT COUNT(CASE WHEN COALESCE(o.service_charge_cents, 0) > 0 THEN 1 END),
+                    COALESCE(SUM(COALESCE(o.service_charge_cents, CAST(ROUND(COALESCE(o.service_charge, 0) * 100) AS INTEGER))), 0),
+                    COALESCE(SUM((SELECT SUM(pa.service_charge_cents) FROM payment_adjustments pa
+                                   WHERE pa.order_id = o.id AND pa.adjustment_type = 'refund')), 0),
+                    COUNT(o.service_charge_waived_at),
+                    COALESCE(SUM(o.service_charge_waived_cents), 0)
+             {scope}"
+        );
+        conn.query_row(&sql, params, |row| {
+            Ok(Self {
+                orders: row.get(0)?,
+                charged_cents: row.get(1)?,
+                refunded_cents: row.get(2)?,
+                waived_orders: row.get(3)?,
+                waived_cents: row.get(4)?,
+            })
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn collected_cents(self) -> i64 {
+        self.charged_cents - self.refunded_cents
+    }
+
+    pub fn to_json(self) -> Value {
+        json!({
+            "orders": self.orders,
+            "charged": Cents::new(self.charged_cents).to_f64_dp2(),
+            "charged_cents": self.charged_cents,
+            "refunded": Cents::new(self.refunded_cents).to_f64_dp2(),
+            "refunded_cents": self.refunded_cents,
+            "collected": Cents::new(self.collected_cents()).to_f64_dp2(),
+            "collected_cents": self.collected_cents(),
+            "waivedOrders": self.waived_orders,
+            "waived": Cents::new(self.waived_cents).to_f64_dp2(),
+            "waived_cents": self.waived_cents,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn insert_order(conn: &Connection, id: &str, total: f64, charge: f64) {
+        conn.execute(
+            "INSERT INTO orders (id, items, total_amount, status, order_type, sync_status,
+                                 service_charge, service_charge_cents, service_charge_rate,
+                                 service_charge_basis, created_at, updated_at)
+             VALUES (?1, '[{\"name\":\"Moussaka\",\"quantity\":1,\"total_price\":20.0}]', ?2,
+                     'pending', 'dine-in', 'pending', ?3, ?4, 10.0, 'after_discount',
+                     datetime('now'), datetime('now'))",
+            params![id, total, charge, Cents::round_half_even(charge).as_i64()],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn config_applies_to_listed_order_types_and_captured_rate_survives_changes() {
+        let conn = test_conn();
+        assert!(AppliedCharge::for_new_order(&conn, "dine-in").is_none());
+
+        save_config(
+            &conn,
+            &ServiceChargeConfig {
+                percentage: 10.0,
+                order_types: vec!["Dine_In".into()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(AppliedCharge::for_new_order(&conn, "takeaway").is_none());
+        let charge = AppliedCharge::for_new_order(&conn, "dine-in").unwrap();
+        assert_eq!(
+            charge.amount(Cents::new(2_000), Cents::new(500)),
+            Cents::new(150)
+        );
+        let before_discount = AppliedCharge {
+            basis: Basis::BeforeDiscount,
+            ..charge
+        };
+        assert_eq!(
+            before_discount.amount(Cents::new(2_000), Cents::new(500)),
+            Cents::new(200)
+        );
+
+        insert_order(&conn, "ord-1", 22.0, 2.0);
+        save_config(
+            &conn,
+            &ServiceChargeConfig {
+                percentage: 15.0,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let repriced = reprice(&conn, "ord-1", Cents::new(3_000), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(repriced.amount, Cents::new(300));
+        assert_eq!(repriced.total_delta, Cents::new(100));
+    }
+
+    #[test]
+    fn waiver_removes_the_charge_and_refunds_share_it_proportionally() {
+        let conn = test_conn();
+        insert_order(&conn, "ord-1", 22.0, 2.0);
+        let reason = ResolvedReason {
+            code: "service_complaint".into(),
+            label: "Service complaint".into(),
+            text: "Slow service".into(),
+        };
+        let waived = waive(
+            &conn,
+            "ord-1",
+            &reason,
+            Some("manager-1"),
+            "2026-10-16T12:00:00Z",
+        )
+        .unwrap();
+        assert_eq!(waived["totalAmount"], 20.0);
+        assert!(waive(&conn, "ord-1", &reason, None, "2026-10-16T12:01:00Z").is_err());
+        assert!(AppliedCharge::load(&conn, "ord-1").unwrap().is_none());
+        let events: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM order_events WHERE order_id = 'ord-1' AND event_type = ?1",
+                params![order_events::SERVICE_CHARGE_WAIVED],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(events, 1);
+
+        insert_order(&conn, "ord-2", 33.0, 3.0);
+        assert_eq!(
+            refund_share(&conn, "ord-2", Cents::new(1_100)).unwrap(),
+            Cents::new(100)
+        );
+        assert_eq!(
+            refund_share(&conn, "ord-2", Cents::new(3_300)).unwrap(),
+            Cents::new(300)
+        );
+    }
+}
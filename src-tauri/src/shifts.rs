@@ -17,7 +17,10 @@ use uuid::Uuid;
 
 use crate::db::DbState;
 use crate::money::Cents;
-use crate::{business_day, order_ownership, payment_integrity, storage, sync_queue};
+use crate::{
+    business_day, drawer_transactions, opening_float, order_ownership, payment_integrity, schedule,
+    storage, sync_queue,
+};
 
 #[derive(Debug)]
 struct CheckInEligibility {
@@ -136,6 +139,22 @@ fn load_checkout_payment_blockers_with_auto_repair(
 /// Creates a `staff_shifts` row and, for cashier roles, a matching
 /// `cash_drawer_sessions` row. Returns error if staff already has an active shift.
 pub fn open_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
+    open_shift_with_manager_approval(db, payload, false)
+}
+
+/// Open a shift, checking the clock-in against the staff member's scheduled
+/// shift for today (see `schedule::check_clock_in`) and a cashier or
+/// manager's opening float against the standing float (see
+/// `opening_float`). A clock-in too far ahead of the scheduled start, with
+/// nothing scheduled, or with a float outside the tolerance is rejected with
+/// `approvalRequired` unless `manager_approved` says a manager has already
+/// signed it off; an approved float deviation also needs `openingFloatNote`.
+/// The outcome is recorded on the shift row either way.
+pub fn open_shift_with_manager_approval(
+    db: &DbState,
+    payload: &Value,
+    manager_approved: bool,
+) -> Result<Value, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
     let normalize_runtime_identity = |value: String| -> Option<String> {
@@ -200,16 +219,30 @@ pub fn open_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
     let requested_opening_cash = num_field(payload, "openingCash")
         .or_else(|| num_field(payload, "opening_cash"))
         .or_else(|| num_field(payload, "startingAmount"))
-        .or_else(|| num_field(payload, "starting_amount"))
-        .unwrap_or(0.0);
+        .or_else(|| num_field(payload, "starting_amount"));
+    let owns_drawer = role_type == "cashier" || role_type == "manager";
+    let float_config = opening_float::FloatConfig::load(&conn);
     let opening_cash = if is_non_financial_shift_role(&role_type) {
         0.0
-    } else {
+    } else if owns_drawer {
+        // An omitted amount is pre-filled with the standing float.
         requested_opening_cash
+            .or_else(|| float_config.expected.map(Cents::to_f64_dp2))
+            .unwrap_or(0.0)
+    } else {
+        requested_opening_cash.unwrap_or(0.0)
     };
+    let float_check = owns_drawer.then(|| {
+        opening_float::FloatCheck::new(float_config, Cents::round_half_even(opening_cash))
+    });
+    let float_note = str_field(payload, "openingFloatNote")
+        .or_else(|| str_field(payload, "opening_float_note"))
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
 
     let shift_id = Uuid::new_v4().to_string();
-    let now = Utc::now().to_rfc3339();
+    let now_utc = Utc::now();
+    let now = now_utc.to_rfc3339();
 
     // Wave 2a C2: open the transaction FIRST. The duplicate-shift check
     // used to run here (lines 186–199 on HEAD) outside any transaction.
@@ -222,7 +255,7 @@ pub fn open_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
     conn.execute_batch("BEGIN IMMEDIATE")
         .map_err(|e| format!("begin transaction: {e}"))?;
 
-    let result = (|| -> Result<(), String> {
+    let result = (|| -> Result<Result<schedule::ClockInCheck, Value>, String> {
         // Re-check inside the transaction: this is the authoritative
         // guard. Any concurrent writer that beat us to `BEGIN IMMEDIATE`
         // has already committed their INSERT, so their row is visible.
@@ -251,6 +284,48 @@ pub fn open_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
             );
         }
 
+        let schedule_check = schedule::check_clock_in(&conn, &branch_id, &staff_id, now_utc)?;
+        let float_needs_approval = float_check.is_some_and(|check| check.needs_approval());
+        let float_json = float_check.map(|check| check.to_json());
+        if !manager_approved && (schedule_check.needs_approval() || float_needs_approval) {
+            let mut messages = Vec::new();
+            if schedule_check.needs_approval() {
+                messages.push(if schedule_check.unscheduled {
+                    "No scheduled shift today. A manager must approve this clock-in.".to_string()
+                } else {
+                    format!(
+                        "Clocking in {} minutes before the scheduled start. A manager must approve this clock-in.",
+                        schedule_check.early_minutes
+                    )
+                });
+            }
+            if let Some(check) = float_check.filter(|check| check.needs_approval()) {
+                messages.push(opening_float_message(&check));
+            }
+            return Ok(Err(serde_json::json!({
+                "success": false,
+                "approvalRequired": true,
+                "noteRequired": float_needs_approval && float_note.is_none(),
+                "error": messages.join(" "),
+                "schedule": schedule_check.to_json(),
+                "openingFloat": float_json,
+            })));
+        }
+        if float_needs_approval && float_note.is_none() {
+            return Ok(Err(serde_json::json!({
+                "success": false,
+                "noteRequired": true,
+                "error": "A note explaining the opening float difference is required.",
+                "schedule": schedule_check.to_json(),
+                "openingFloat": float_json,
+            })));
+        }
+        let schedule_approved_at =
+            (schedule_check.needs_approval() && manager_approved).then(|| now.clone());
+        let float_approved_at = float_needs_approval.then(|| now.clone());
+        let float_expected = float_check.and_then(|check| check.config.expected);
+        let float_deviation = float_check.and_then(|check| check.deviation());
+
         let responsible_cashier_assignment = if role_returns_cash(&role_type) {
             find_active_cashier_assignment(&conn, &branch_id, &terminal_id)?
         } else {
@@ -275,8 +350,16 @@ pub fn open_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
                 check_in_time, report_date, period_start_at,
                 opening_cash_amount, opening_cash_amount_cents,
                 status, calculation_version, transferred_to_cashier_shift_id,
+                scheduled_shift_id, scheduled_start, scheduled_end,
+                clock_in_early_minutes, clock_in_late_minutes, is_unscheduled,
+                schedule_check_status, schedule_approved_at,
+                opening_float_expected, opening_float_expected_cents,
+                opening_float_deviation, opening_float_deviation_cents,
+                opening_float_note, opening_float_approved_at,
                 sync_status, created_at, updated_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 'active', 2, ?12, 'pending', ?13, ?13)",
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 'active', 2, ?12,
+                      ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21,
+                      ?22, ?23, ?24, ?25, ?26, ?27, 'pending', ?13, ?13)",
             params![
                 shift_id,
                 staff_id,
@@ -291,6 +374,20 @@ pub fn open_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
                 opening_cash_cents,
                 responsible_cashier_shift_id,
                 now,
+                schedule_check.scheduled_shift_id,
+                schedule_check.scheduled_start,
+                schedule_check.scheduled_end,
+                schedule_check.early_minutes,
+                schedule_check.late_minutes,
+                schedule_check.unscheduled,
+                schedule_check.status(),
+                schedule_approved_at,
+                float_expected.map(Cents::to_f64_dp2),
+                float_expected.map(Cents::as_i64),
+                float_deviation.map(Cents::to_f64_dp2),
+                float_deviation.map(Cents::as_i64),
+                float_note,
+                float_approved_at,
             ],
         )
         .map_err(|e| format!("insert shift: {e}"))?;
@@ -368,7 +465,7 @@ pub fn open_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
         // (same admin endpoint the legacy drain hit). idempotency_key is now
         // read from staff_shifts.idempotency_key (v47/v49) instead of the
         // volatile per-enqueue UUID (C17).
-        let sync_payload = build_shift_open_sync_payload(
+        let mut sync_payload = build_shift_open_sync_payload(
             &shift_id,
             &staff_id,
             staff_name.as_deref(),
@@ -387,6 +484,18 @@ pub fn open_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
                 .as_ref()
                 .map(|(_, drawer_id)| drawer_id.as_str()),
         );
+        sync_payload["scheduledShiftId"] = serde_json::json!(schedule_check.scheduled_shift_id);
+        sync_payload["clockInEarlyMinutes"] = serde_json::json!(schedule_check.early_minutes);
+        sync_payload["clockInLateMinutes"] = serde_json::json!(schedule_check.late_minutes);
+        sync_payload["isUnscheduled"] = serde_json::json!(schedule_check.unscheduled);
+        sync_payload["scheduleCheckStatus"] = serde_json::json!(schedule_check.status());
+        sync_payload["scheduleApprovedAt"] = serde_json::json!(schedule_approved_at);
+        sync_payload["openingFloatExpected"] =
+            serde_json::json!(float_expected.map(Cents::to_f64_dp2));
+        sync_payload["openingFloatDeviation"] =
+            serde_json::json!(float_deviation.map(Cents::to_f64_dp2));
+        sync_payload["openingFloatNote"] = serde_json::json!(float_note);
+        sync_payload["openingFloatApprovedAt"] = serde_json::json!(float_approved_at);
 
         sync_queue::enqueue_payload_item(
             &conn,
@@ -401,29 +510,51 @@ pub fn open_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
         )
         .map_err(|e| format!("enqueue shift sync: {e}"))?;
 
-        Ok(())
+        Ok(Ok(schedule_check))
     })();
 
-    match result {
-        Ok(()) => {
+    let schedule_check = match result {
+        Ok(Ok(schedule_check)) => {
             conn.execute_batch("COMMIT")
                 .map_err(|e| format!("commit: {e}"))?;
+            schedule_check
+        }
+        Ok(Err(rejection)) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            return Ok(rejection);
         }
         Err(e) => {
             let _ = conn.execute_batch("ROLLBACK");
             return Err(e);
         }
-    }
+    };
 
-    info!(shift_id = %shift_id, staff_id = %staff_id, role = %role_type, "Shift opened");
+    info!(
+        shift_id = %shift_id,
+        staff_id = %staff_id,
+        role = %role_type,
+        schedule = schedule_check.status(),
+        "Shift opened"
+    );
 
     Ok(serde_json::json!({
         "success": true,
         "shiftId": shift_id,
-        "message": format!("Shift opened for {} ({})", staff_id, role_type)
+        "message": format!("Shift opened for {} ({})", staff_id, role_type),
+        "schedule": schedule_check.to_json(),
+        "openingFloat": float_check.map(|check| check.to_json()),
     }))
 }
 
+fn opening_float_message(check: &opening_float::FloatCheck) -> String {
+    format!(
+        "Opening float {:.2} differs from the expected {:.2} by {:+.2}. A manager must approve it with a note.",
+        check.declared.to_f64_dp2(),
+        check.config.expected.unwrap_or(Cents::ZERO).to_f64_dp2(),
+        check.deviation().unwrap_or(Cents::ZERO).to_f64_dp2(),
+    )
+}
+
 fn build_shift_open_sync_payload(
     shift_id: &str,
     staff_id: &str,
@@ -497,7 +628,8 @@ fn load_cash_drawer_snapshot_for_shift(
                 COALESCE(driver_cash_returned_cents, CAST(ROUND(driver_cash_returned * 100) AS INTEGER), 0),
                 COALESCE(total_staff_payments_cents, CAST(ROUND(total_staff_payments * 100) AS INTEGER), 0),
                 opened_at, closed_at, reconciled,
-                reconciled_at, reconciled_by
+                reconciled_at, reconciled_by,
+                COALESCE(cash_in_cents, CAST(ROUND(cash_in * 100) AS INTEGER), 0)
          FROM cash_drawer_sessions
          WHERE staff_shift_id = ?1",
         params![shift_id],
@@ -516,6 +648,7 @@ fn load_cash_drawer_snapshot_for_shift(
             let driver_cash_given_cents = row.get::<_, i64>(11)?;
             let driver_cash_returned_cents = row.get::<_, i64>(12)?;
             let total_staff_payments_cents = row.get::<_, i64>(13)?;
+            let cash_in_cents = row.get::<_, i64>(19)?;
             Ok(serde_json::json!({
                 "id": row.get::<_, String>(0)?,
                 "cashierId": row.get::<_, Option<String>>(1)?,
@@ -537,6 +670,8 @@ fn load_cash_drawer_snapshot_for_shift(
                 "total_expenses_cents": total_expenses_cents,
                 "cashDrops": Cents::new(cash_drops_cents).to_f64_dp2(),
                 "cash_drops_cents": cash_drops_cents,
+                "cashIn": Cents::new(cash_in_cents).to_f64_dp2(),
+                "cash_in_cents": cash_in_cents,
                 "driverCashGiven": Cents::new(driver_cash_given_cents).to_f64_dp2(),
                 "driver_cash_given_cents": driver_cash_given_cents,
                 "driverCashReturned": Cents::new(driver_cash_returned_cents).to_f64_dp2(),
@@ -559,10 +694,51 @@ fn load_cash_drawer_snapshot_for_shift(
 // Close shift
 // ---------------------------------------------------------------------------
 
+/// Net cash rounding a cashier drawer took in during a shift window, in
+/// cents: the rounding on cash payments, less the rounding on cash refunds
+/// paid out of the drawer. A rounded payment that was later refunded in full
+/// nets to zero.
+fn compute_shift_cash_rounding_cents(
+    conn: &Connection,
+    shift_id: &str,
+    from: &str,
+    to: &str,
+) -> i64 {
+    let order_financial_expr = business_day::order_financial_timestamp_expr("o");
+    conn.query_row(
+        &format!(
+            "SELECT
+                (SELECT COALESCE(SUM(op.rounding_delta_cents), 0)
+                 FROM orders o
+                 JOIN order_payments op ON op.order_id = o.id
+                 WHERE COALESCE(op.staff_shift_id, o.staff_shift_id) = ?1
+                   AND op.method = 'cash'
+                   AND op.status IN ('completed', 'refunded')
+                   AND COALESCE(o.is_ghost, 0) = 0
+                   AND {order_financial_expr} >= ?2
+                   AND {order_financial_expr} <= ?3)
+              - (SELECT COALESCE(SUM(pa.rounding_delta_cents), 0)
+                 FROM orders o
+                 JOIN payment_adjustments pa ON pa.order_id = o.id
+                 LEFT JOIN order_payments op ON op.id = pa.payment_id
+                 WHERE COALESCE(op.staff_shift_id, o.staff_shift_id) = ?1
+                   AND pa.adjustment_type = 'refund'
+                   AND pa.cash_handler = 'cashier_drawer'
+                   AND COALESCE(o.is_ghost, 0) = 0
+                   AND {order_financial_expr} >= ?2
+                   AND {order_financial_expr} <= ?3)"
+        ),
+        params![shift_id, from, to],
+        |row| row.get(0),
+    )
+    .unwrap_or(0)
+}
+
 /// Close an active shift. Calculates expected cash and variance.
 ///
-/// For cashier/manager: expected = opening + cash_sales - refunds - expenses
-///   - deducted_staff_payments - drops - driver_cash_given + driver_cash_returned
+/// For cashier/manager: expected = opening + cash_sales + cash_rounding - refunds
+///   - expenses - deducted_staff_payments - drops - driver_cash_given
+///   + driver_cash_returned
 ///     For driver/server: expected = opening + cash_collected - expenses
 ///
 /// For calculation_version >= 2, recorded staff payouts for the cashier shift are
@@ -571,9 +747,19 @@ pub fn close_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
     let shift_id = str_field(payload, "shiftId")
         .or_else(|| str_field(payload, "shift_id"))
         .ok_or("Missing shiftId")?;
-    let closing_cash = num_field(payload, "closingCash")
-        .or_else(|| num_field(payload, "closing_cash"))
-        .ok_or("Missing closingCash")?;
+    // Unattended closes (end-of-day automation) have no counted cash; they
+    // record the computed expected amount so the shift closes with zero
+    // variance instead of a fabricated count.
+    let closing_cash_from_expected = payload
+        .get("closingCashFromExpected")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let closing_cash =
+        match num_field(payload, "closingCash").or_else(|| num_field(payload, "closing_cash")) {
+            Some(amount) => amount,
+            None if closing_cash_from_expected => 0.0,
+            None => return Err("Missing closingCash".into()),
+        };
     let raw_closed_by = str_field(payload, "closedBy").or_else(|| str_field(payload, "closed_by"));
     let closed_by = sanitize_database_uuid(raw_closed_by.clone());
     let payment_amount =
@@ -727,7 +913,7 @@ pub fn close_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
         }
     }
 
-    let result = (|| -> Result<(f64, f64), String> {
+    let result = (|| -> Result<(f64, f64, f64), String> {
         #[allow(clippy::needless_late_init)]
         let expected: f64;
         let mut returned_cash_target: Option<(String, String, f64)> = None;
@@ -884,6 +1070,8 @@ pub fn close_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
                     |row| row.get(0),
                 )
                 .unwrap_or(0.0);
+            let reconciled_cash_rounding_cents =
+                compute_shift_cash_rounding_cents(&conn, &shift_id, &shift_check_in_time, &now);
 
             // Write reconciled values to cash_drawer_sessions (W4c dual-write).
             let reconciled_cash_sales_cents =
@@ -901,6 +1089,7 @@ pub fn close_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
                 total_refunds = ?5, total_refunds_cents = ?6,
                 total_expenses = ?7, total_expenses_cents = ?8,
                 total_staff_payments = ?9, total_staff_payments_cents = ?10,
+                total_cash_rounding = ?13, total_cash_rounding_cents = ?14,
                 updated_at = ?11
              WHERE staff_shift_id = ?12",
                 params![
@@ -916,6 +1105,8 @@ pub fn close_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
                     reconciled_staff_payments_cents,
                     now,
                     shift_id,
+                    Cents::new(reconciled_cash_rounding_cents).to_f64_dp2(),
+                    reconciled_cash_rounding_cents,
                 ],
             )
             .map_err(|e| format!("reconcile drawer totals: {e}"))?;
@@ -931,7 +1122,9 @@ pub fn close_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
                         COALESCE(cash_drops_cents, CAST(ROUND(cash_drops * 100) AS INTEGER), 0),
                         COALESCE(driver_cash_given_cents, CAST(ROUND(driver_cash_given * 100) AS INTEGER), 0),
                         COALESCE(driver_cash_returned_cents, CAST(ROUND(driver_cash_returned * 100) AS INTEGER), 0),
-                        COALESCE(total_staff_payments_cents, CAST(ROUND(total_staff_payments * 100) AS INTEGER), 0)
+                        COALESCE(total_staff_payments_cents, CAST(ROUND(total_staff_payments * 100) AS INTEGER), 0),
+                        COALESCE(total_cash_rounding_cents, CAST(ROUND(total_cash_rounding * 100) AS INTEGER), 0),
+                        COALESCE(cash_in_cents, CAST(ROUND(cash_in * 100) AS INTEGER), 0)
                  FROM cash_drawer_sessions WHERE staff_shift_id = ?1",
                     params![shift_id],
                     |row| {
@@ -943,10 +1136,12 @@ pub fn close_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
                             Cents::new(row.get::<_, i64>(4).unwrap_or(0)).to_f64_dp2(),
                             Cents::new(row.get::<_, i64>(5).unwrap_or(0)).to_f64_dp2(),
                             Cents::new(row.get::<_, i64>(6).unwrap_or(0)).to_f64_dp2(),
+                            Cents::new(row.get::<_, i64>(7).unwrap_or(0)).to_f64_dp2(),
+                            Cents::new(row.get::<_, i64>(8).unwrap_or(0)).to_f64_dp2(),
                         ))
                     },
                 )
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0));
+                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0));
 
             let (
                 cash_sales,
@@ -956,6 +1151,8 @@ pub fn close_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
                 driver_given,
                 driver_returned,
                 staff_payments,
+                cash_rounding,
+                cash_in,
             ) = drawer;
             let deducted_staff_payments = if calc_version >= 2 {
                 let recorded_staff_payouts: f64 = conn
@@ -993,7 +1190,7 @@ pub fn close_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
                 );
             }
 
-            expected = opening_cash + cash_sales
+            expected = opening_cash + cash_sales + cash_rounding + cash_in
                 - refunds
                 - expenses
                 - deducted_staff_payments
@@ -1029,6 +1226,11 @@ pub fn close_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
             expected = opening_cash + cash_collected - expenses;
         }
 
+        let closing_cash_to_persist = if closing_cash_from_expected && !is_non_financial_role {
+            expected
+        } else {
+            closing_cash_to_persist
+        };
         let variance = if is_non_financial_role {
             0.0
         } else {
@@ -1262,11 +1464,11 @@ pub fn close_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
             )?;
         }
 
-        Ok((expected, variance))
+        Ok((expected, variance, closing_cash_to_persist))
     })();
 
     match result {
-        Ok((expected, variance)) => {
+        Ok((expected, variance, closing)) => {
             conn.execute_batch("COMMIT")
                 .map_err(|e| format!("commit: {e}"))?;
 
@@ -1277,7 +1479,7 @@ pub fn close_shift(db: &DbState, payload: &Value) -> Result<Value, String> {
                 "shiftId": shift_id,
                 "variance": variance,
                 "expected": expected,
-                "closing": closing_cash,
+                "closing": if closing_cash_from_expected { closing } else { closing_cash },
                 "message": format!("Shift closed. Variance: {:.2}", variance)
             }))
         }
@@ -1577,7 +1779,9 @@ pub fn get_shift_summary(db: &DbState, shift_id: &str) -> Result<Value, String>
                     COALESCE(driver_cash_given_cents, CAST(ROUND(driver_cash_given * 100) AS INTEGER), 0),
                     COALESCE(driver_cash_returned_cents, CAST(ROUND(driver_cash_returned * 100) AS INTEGER), 0),
                     COALESCE(total_staff_payments_cents, CAST(ROUND(total_staff_payments * 100) AS INTEGER), 0),
-                    opened_at, closed_at, reconciled
+                    opened_at, closed_at, reconciled,
+                    COALESCE(total_cash_rounding_cents, CAST(ROUND(total_cash_rounding * 100) AS INTEGER), 0),
+                    COALESCE(cash_in_cents, CAST(ROUND(cash_in * 100) AS INTEGER), 0)
              FROM cash_drawer_sessions WHERE staff_shift_id = ?1",
             params![shift_id],
             |row| {
@@ -1598,6 +1802,8 @@ pub fn get_shift_summary(db: &DbState, shift_id: &str) -> Result<Value, String>
                     "opened_at": row.get::<_, String>(13)?,
                     "closed_at": row.get::<_, Option<String>>(14)?,
                     "reconciled": row.get::<_, i64>(15)? != 0,
+                    "total_cash_rounding": Cents::new(row.get::<_, i64>(16)?).to_f64_dp2(),
+                    "cash_in": Cents::new(row.get::<_, i64>(17)?).to_f64_dp2(),
                 }))
             },
         )
@@ -1998,10 +2204,13 @@ pub fn get_shift_summary(db: &DbState, shift_id: &str) -> Result<Value, String>
     let orders_count = overall["totalCount"].as_i64().unwrap_or(0);
     let sales_amount = overall["totalAmount"].as_f64().unwrap_or(0.0);
 
+    let drawer_transactions = drawer_transactions::list_for_shift(&conn, shift_id)?;
+
     let mut result = serde_json::json!({
         "shift": shift,
         "cashDrawer": cash_drawer,
         "expenses": expense_items,
+        "drawerTransactions": drawer_transactions,
         "totalExpenses": total_expenses,
         "breakdown": breakdown,
         "canceledOrders": canceled_orders,
@@ -2350,63 +2559,7 @@ pub fn delete_expense(db: &DbState, payload: &Value) -> Result<Value, String> {
 // Staff payment management
 // ---------------------------------------------------------------------------
 
-pub(crate) fn ensure_staff_payments_table(conn: &Connection) -> Result<(), String> {
-    conn.execute_batch(
-        "
-        CREATE TABLE IF NOT EXISTS staff_payments (
-            id TEXT PRIMARY KEY,
-            cashier_shift_id TEXT NOT NULL,
-            paid_to_staff_id TEXT NOT NULL,
-            amount REAL NOT NULL,
-            payment_type TEXT NOT NULL DEFAULT 'wage',
-            notes TEXT,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
-        );
-        CREATE INDEX IF NOT EXISTS idx_staff_payments_cashier_shift_id
-            ON staff_payments(cashier_shift_id);
-        CREATE INDEX IF NOT EXISTS idx_staff_payments_paid_to_staff_id
-            ON staff_payments(paid_to_staff_id);
-        CREATE INDEX IF NOT EXISTS idx_staff_payments_created_at
-            ON staff_payments(created_at);
-        ",
-    )
-    .map_err(|e| format!("ensure staff_payments table: {e}"))?;
-
-    let has_updated_at: bool = conn
-        .query_row(
-            "SELECT EXISTS(
-                SELECT 1
-                FROM pragma_table_info('staff_payments')
-                WHERE name = 'updated_at'
-            )",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or(false);
-
-    if !has_updated_at {
-        conn.execute(
-            "ALTER TABLE staff_payments
-             ADD COLUMN updated_at TEXT",
-            [],
-        )
-        .map_err(|e| format!("add staff_payments.updated_at: {e}"))?;
-    }
-
-    conn.execute(
-        "UPDATE staff_payments
-         SET updated_at = COALESCE(NULLIF(trim(updated_at), ''), created_at)
-         WHERE updated_at IS NULL OR trim(updated_at) = ''",
-        [],
-    )
-    .map_err(|e| format!("backfill staff_payments.updated_at: {e}"))?;
-
-    Ok(())
-}
-
 fn compute_staff_payments_total(conn: &Connection, cashier_shift_id: &str) -> Result<f64, String> {
-    ensure_staff_payments_table(conn)?;
     conn.query_row(
         "SELECT COALESCE(SUM(amount), 0)
          FROM staff_payments
@@ -2570,6 +2723,9 @@ pub(crate) fn recompute_closed_cashier_shift_financial_snapshot(
         .unwrap_or(0.0);
 
     let reconciled_staff_payments = compute_staff_payments_total(conn, shift_id)?;
+    let reconciled_cash_rounding_cents =
+        compute_shift_cash_rounding_cents(conn, shift_id, &check_in_time, &check_out_time);
+    let reconciled_cash_rounding = Cents::new(reconciled_cash_rounding_cents).to_f64_dp2();
 
     // W4c dual-write: reconciled drawer totals mirror onto cents siblings.
     let reconciled_cash_sales_cents = Cents::round_half_even(reconciled_cash_sales).as_i64();
@@ -2586,6 +2742,7 @@ pub(crate) fn recompute_closed_cashier_shift_financial_snapshot(
                 total_refunds = ?5, total_refunds_cents = ?6,
                 total_expenses = ?7, total_expenses_cents = ?8,
                 total_staff_payments = ?9, total_staff_payments_cents = ?10,
+                total_cash_rounding = ?13, total_cash_rounding_cents = ?14,
                 updated_at = ?11
              WHERE staff_shift_id = ?12",
             params![
@@ -2601,6 +2758,8 @@ pub(crate) fn recompute_closed_cashier_shift_financial_snapshot(
                 reconciled_staff_payments_cents,
                 written_at,
                 shift_id,
+                reconciled_cash_rounding,
+                reconciled_cash_rounding_cents,
             ],
         )
         .map_err(|e| format!("recompute closed drawer totals: {e}"))?;
@@ -2612,11 +2771,12 @@ pub(crate) fn recompute_closed_cashier_shift_financial_snapshot(
     }
 
     // W4b-ii: cents-with-real-fallback shim (removed in 4e).
-    let (cash_drops, driver_cash_given, driver_cash_returned): (f64, f64, f64) = conn
+    let (cash_drops, driver_cash_given, driver_cash_returned, cash_in): (f64, f64, f64, f64) = conn
         .query_row(
             "SELECT COALESCE(cash_drops_cents, CAST(ROUND(cash_drops * 100) AS INTEGER), 0),
                     COALESCE(driver_cash_given_cents, CAST(ROUND(driver_cash_given * 100) AS INTEGER), 0),
-                    COALESCE(driver_cash_returned_cents, CAST(ROUND(driver_cash_returned * 100) AS INTEGER), 0)
+                    COALESCE(driver_cash_returned_cents, CAST(ROUND(driver_cash_returned * 100) AS INTEGER), 0),
+                    COALESCE(cash_in_cents, CAST(ROUND(cash_in * 100) AS INTEGER), 0)
              FROM cash_drawer_sessions
              WHERE staff_shift_id = ?1",
             params![shift_id],
@@ -2625,6 +2785,7 @@ pub(crate) fn recompute_closed_cashier_shift_financial_snapshot(
                     Cents::new(row.get::<_, i64>(0)?).to_f64_dp2(),
                     Cents::new(row.get::<_, i64>(1)?).to_f64_dp2(),
                     Cents::new(row.get::<_, i64>(2)?).to_f64_dp2(),
+                    Cents::new(row.get::<_, i64>(3)?).to_f64_dp2(),
                 ))
             },
         )
@@ -2639,7 +2800,7 @@ pub(crate) fn recompute_closed_cashier_shift_financial_snapshot(
     let inherited_driver_expected_returns =
         compute_inherited_cash_staff_expected_returns(conn, shift_id, &check_in_time)?;
 
-    let expected = opening_cash + reconciled_cash_sales
+    let expected = opening_cash + reconciled_cash_sales + reconciled_cash_rounding + cash_in
         - reconciled_refunds
         - reconciled_expenses
         - deducted_staff_payments
@@ -3013,8 +3174,6 @@ fn reconcile_cashier_shift_after_staff_payment_mutation(
 
 pub fn record_staff_payment(db: &DbState, payload: &Value) -> Result<Value, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    ensure_staff_payments_table(&conn)?;
-
     let cashier_shift_id = str_field(payload, "cashierShiftId")
         .or_else(|| str_field(payload, "cashier_shift_id"))
         .ok_or("Missing cashierShiftId")?;
@@ -3056,14 +3215,15 @@ pub fn record_staff_payment(db: &DbState, payload: &Value) -> Result<Value, Stri
     let result = (|| -> Result<(), String> {
         conn.execute(
             "INSERT INTO staff_payments (
-                id, cashier_shift_id, paid_to_staff_id, amount, payment_type,
+                id, cashier_shift_id, paid_to_staff_id, amount, amount_cents, payment_type,
                 notes, created_at, updated_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)",
             params![
                 payment_id,
                 cashier_shift_id,
                 paid_to_staff_id,
                 amount,
+                Cents::round_half_even(amount).as_i64(),
                 payment_type,
                 notes,
                 now,
@@ -3107,8 +3267,6 @@ pub fn record_staff_payment(db: &DbState, payload: &Value) -> Result<Value, Stri
 
 pub fn update_staff_payment(db: &DbState, payload: &Value) -> Result<Value, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    ensure_staff_payments_table(&conn)?;
-
     let payment_id = str_field(payload, "paymentId")
         .or_else(|| str_field(payload, "payment_id"))
         .or_else(|| str_field(payload, "id"))
@@ -3158,13 +3316,15 @@ pub fn update_staff_payment(db: &DbState, payload: &Value) -> Result<Value, Stri
             "UPDATE staff_payments
              SET paid_to_staff_id = ?1,
                  amount = ?2,
-                 payment_type = ?3,
-                 notes = ?4,
-                 updated_at = ?5
-             WHERE id = ?6",
+                 amount_cents = ?3,
+                 payment_type = ?4,
+                 notes = ?5,
+                 updated_at = ?6
+             WHERE id = ?7",
             params![
                 paid_to_staff_id,
                 amount,
+                Cents::round_half_even(amount).as_i64(),
                 payment_type,
                 notes,
                 now,
@@ -3209,8 +3369,6 @@ pub fn update_staff_payment(db: &DbState, payload: &Value) -> Result<Value, Stri
 
 pub fn delete_staff_payment(db: &DbState, payload: &Value) -> Result<Value, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    ensure_staff_payments_table(&conn)?;
-
     let payment_id = str_field(payload, "paymentId")
         .or_else(|| str_field(payload, "payment_id"))
         .or_else(|| str_field(payload, "id"))
@@ -3300,8 +3458,8 @@ fn resolve_check_in_eligibility(
                 SELECT 1
                 FROM staff_shifts
                 WHERE role_type = 'cashier'
-                  AND (?1 = '' OR branch_id = ?1 OR branch_id IS NULL)
-                  AND (?2 = '' OR terminal_id = ?2 OR terminal_id IS NULL)
+                  AND (branch_id = ?1 OR branch_id IS NULL)
+                  AND (terminal_id = ?2 OR terminal_id IS NULL)
                   AND check_in_time >= ?3
             )",
             params![branch_id, terminal_id, business_day_start_at.as_str()],
@@ -4085,8 +4243,6 @@ fn load_cashier_staff_payments(
     conn: &rusqlite::Connection,
     cashier_shift_id: &str,
 ) -> Result<Vec<Value>, String> {
-    ensure_staff_payments_table(conn)?;
-
     let mut stmt = conn
         .prepare(
             "SELECT sp.id, sp.cashier_shift_id, sp.paid_to_staff_id, sp.amount, sp.payment_type,
@@ -4388,10 +4544,7 @@ mod tests {
         )
         .expect("pragma setup");
         db::run_migrations_for_test(&conn);
-        DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        }
+        DbState::new(conn, std::path::PathBuf::from(":memory:"))
     }
 
     #[test]
@@ -4752,6 +4905,110 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_shift_open_requires_approval_for_unscheduled_clock_in() {
+        let _fake = crate::tests::fake_keyring::install_empty();
+        let db = test_db();
+        set_business_day_start(&db, "2026-03-22T08:00:00Z");
+        {
+            let conn = db.conn.lock().unwrap();
+            let (day_start, day_end) =
+                schedule::local_day_bounds(chrono::Local::now().date_naive()).unwrap();
+            schedule::store_remote(
+                &conn,
+                "branch-1",
+                None,
+                day_start,
+                day_end,
+                &[serde_json::json!({
+                    "id": "sched-other",
+                    "staff_id": "cashier-2",
+                    "start_time": (day_start + chrono::Duration::hours(12)).to_rfc3339(),
+                })],
+                &Utc::now().to_rfc3339(),
+            )
+            .unwrap();
+        }
+        let payload = serde_json::json!({
+            "staffId": "cashier-1",
+            "branchId": "branch-1",
+            "terminalId": "term-1",
+            "roleType": "cashier",
+        });
+
+        let rejected = open_shift(&db, &payload).expect("rejection is a response");
+        assert_eq!(rejected["success"], false);
+        assert_eq!(rejected["approvalRequired"], true);
+        assert_eq!(rejected["schedule"]["status"], "unscheduled");
+        let open_count: i64 = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM staff_shifts", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(open_count, 0, "no shift is opened without approval");
+
+        let approved = open_shift_with_manager_approval(&db, &payload, true).unwrap();
+        assert_eq!(approved["success"], true);
+        let conn = db.conn.lock().unwrap();
+        let (unscheduled, status, approved_at): (i64, String, Option<String>) = conn
+            .query_row(
+                "SELECT is_unscheduled, schedule_check_status, schedule_approved_at
+                 FROM staff_shifts WHERE id = ?1",
+                params![approved["shiftId"].as_str().unwrap()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(unscheduled, 1);
+        assert_eq!(status, "unscheduled");
+        assert!(approved_at.is_some());
+    }
+
+    #[test]
+    fn test_shift_open_short_float_needs_approval_and_note() {
+        let _fake = crate::tests::fake_keyring::install_empty();
+        let db = test_db();
+        set_business_day_start(&db, "2026-03-22T08:00:00Z");
+        {
+            let conn = db.conn.lock().unwrap();
+            db::set_setting(&conn, "cash", "default_opening_float", "150").unwrap();
+        }
+        let mut payload = serde_json::json!({
+            "staffId": "cashier-1",
+            "branchId": "branch-1",
+            "terminalId": "term-1",
+            "roleType": "cashier",
+            "openingCash": 120.0,
+        });
+
+        let rejected = open_shift(&db, &payload).expect("rejection is a response");
+        assert_eq!(rejected["success"], false);
+        assert_eq!(rejected["approvalRequired"], true);
+        assert_eq!(rejected["openingFloat"]["deviation"], -30.0);
+
+        let unexplained = open_shift_with_manager_approval(&db, &payload, true).unwrap();
+        assert_eq!(unexplained["success"], false);
+        assert_eq!(unexplained["noteRequired"], true);
+
+        payload["openingFloatNote"] = serde_json::json!("Bank run before open");
+        let approved = open_shift_with_manager_approval(&db, &payload, true).unwrap();
+        assert_eq!(approved["success"], true);
+        let conn = db.conn.lock().unwrap();
+        let (expected, deviation, note, approved_at): (i64, i64, String, Option<String>) = conn
+            .query_row(
+                "SELECT opening_float_expected_cents, opening_float_deviation_cents,
+                        opening_float_note, opening_float_approved_at
+                 FROM staff_shifts WHERE id = ?1",
+                params![approved["shiftId"].as_str().unwrap()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(expected, 15_000);
+        assert_eq!(deviation, -3_000);
+        assert_eq!(note, "Bank run before open");
+        assert!(approved_at.is_some());
+    }
+
     #[test]
     fn test_shift_open_allows_cashier_as_first_shift_of_business_day() {
         let _fake = crate::tests::fake_keyring::install_empty();
@@ -4972,6 +5229,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cashier_close_from_expected_cash_records_zero_variance() {
+        let db = test_db();
+
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO staff_shifts (
+                    id, staff_id, role_type, branch_id, terminal_id,
+                    check_in_time, opening_cash_amount, opening_cash_amount_cents,
+                    status, calculation_version,
+                    sync_status, created_at, updated_at
+                 ) VALUES (
+                    'cashier-auto', 'cashier-1', 'cashier', 'branch-1', 'term-1',
+                    '2026-03-18T08:00:00Z', 80.0, 8000, 'active', 2, 'pending',
+                    '2026-03-18T08:00:00Z', '2026-03-18T08:00:00Z'
+                 )",
+                [],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO cash_drawer_sessions (
+                    id, staff_shift_id, cashier_id, branch_id, terminal_id,
+                    opening_amount, opening_amount_cents, opened_at, created_at, updated_at
+                 ) VALUES (
+                    'drawer-auto', 'cashier-auto', 'cashier-1', 'branch-1', 'term-1',
+                    80.0, 8000, '2026-03-18T08:00:00Z', '2026-03-18T08:00:00Z', '2026-03-18T08:00:00Z'
+                 )",
+                [],
+            )
+            .unwrap();
+        }
+
+        assert!(close_shift(&db, &serde_json::json!({ "shiftId": "cashier-auto" })).is_err());
+
+        let result = close_shift(
+            &db,
+            &serde_json::json!({
+                "shiftId": "cashier-auto",
+                "closingCashFromExpected": true,
+            }),
+        )
+        .expect("unattended close should use the expected amount");
+        assert_eq!(result["success"], true);
+        assert_eq!(result["closing"], 80.0);
+        assert_eq!(result["variance"], 0.0);
+
+        let conn = db.conn.lock().unwrap();
+        let (closing_cents, variance_cents): (i64, i64) = conn
+            .query_row(
+                "SELECT closing_cash_amount_cents, cash_variance_cents
+                 FROM staff_shifts WHERE id = 'cashier-auto'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(closing_cents, 8000);
+        assert_eq!(variance_cents, 0);
+    }
+
     #[test]
     fn test_cashier_close_v2_deducts_all_staff_payouts_from_drawer() {
         let db = test_db();
@@ -5123,7 +5440,6 @@ mod tests {
             .unwrap();
             conn.execute_batch("PRAGMA ignore_check_constraints = OFF;")
                 .unwrap();
-            ensure_staff_payments_table(&conn).unwrap();
             conn.execute(
                 "INSERT INTO staff_payments (
                     id, cashier_shift_id, paid_to_staff_id, amount, payment_type, created_at, updated_at
@@ -6391,7 +6707,6 @@ mod tests {
         let conn = db.conn.lock().unwrap();
         let created_at = "2026-03-26T10:00:00Z";
 
-        ensure_staff_payments_table(&conn).unwrap();
         // W4e Step 0: dual-populate (100/18 → 10000/1800). staff_payments
         // table is excluded from cents migration per migrate_v54 docstring.
         conn.execute(
@@ -6531,7 +6846,6 @@ mod tests {
         let conn = db.conn.lock().unwrap();
         let created_at = "2026-03-26T10:00:00Z";
 
-        ensure_staff_payments_table(&conn).unwrap();
         // W4e Step 0: dual-populate (100/18 → 10000/1800).
         conn.execute(
             "INSERT INTO staff_shifts (
@@ -6646,7 +6960,6 @@ mod tests {
         let conn = db.conn.lock().unwrap();
         let created_at = "2026-03-26T10:00:00Z";
 
-        ensure_staff_payments_table(&conn).unwrap();
         // W4e Step 0: dual-populate every monetary column (100/80/80/0/20 → 10000/8000/8000/0/2000).
         conn.execute(
             "INSERT INTO staff_shifts (
@@ -6776,7 +7089,6 @@ mod tests {
         let conn = db.conn.lock().unwrap();
         let created_at = "2026-03-26T10:00:00Z";
 
-        ensure_staff_payments_table(&conn).unwrap();
         // W4e Step 0: dual-populate (100/80/80/0/20 → 10000/8000/8000/0/2000).
         conn.execute(
             "INSERT INTO staff_shifts (
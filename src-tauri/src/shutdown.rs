@@ -0,0 +1,242 @@
+//! Graceful shutdown sequence.
+//!
+//! `app_shutdown` used to call `app.exit(0)` straight away, which could cut a
+//! sync HTTP request off mid-flight and leave its `sync_queue` rows stuck in
+//! `in_progress`. The sequence here stops the background workers, waits (up
+//! to `system.shutdown_grace_secs`) for any running sync cycle to finish,
+//! hands interrupted rows back to the queue, flushes any buffered order
+//! events, checkpoints the SQLite WAL and only then lets the caller exit. Each step is broadcast as
+//! `app_shutdown_progress` so the renderer can show "finishing sync…".
+
+use chrono::Utc;
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::db::{self, DbState};
+use crate::event_batcher;
+use crate::sync::{self, SyncState};
+
+const DEFAULT_GRACE_PERIOD_SECS: u64 = 15;
+const MAX_GRACE_PERIOD_SECS: u64 = 120;
+const SYNC_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Default)]
+struct Inner {
+    phase: Option<&'static str>,
+    started_at: Option<String>,
+    force: bool,
+}
+
+/// Tauri managed state reporting whether a shutdown is under way.
+#[derive(Default)]
+pub struct ShutdownState {
+    shutting_down: AtomicBool,
+    inner: Mutex<Inner>,
+}
+
+impl ShutdownState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flip the shutting-down flag. Returns `false` when a shutdown was
+    /// already in progress so a second request does not run the sequence
+    /// twice.
+    fn begin(&self, force: bool) -> bool {
+        if self.shutting_down.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.started_at = Some(Utc::now().to_rfc3339());
+            inner.force = force;
+        }
+        true
+    }
+
+    fn set_phase(&self, phase: &'static str) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.phase = Some(phase);
+        }
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    pub fn status_json(&self) -> Value {
+        let inner = self.inner.lock();
+        let (phase, started_at, force) = match inner.as_deref() {
+            Ok(inner) => (inner.phase, inner.started_at.clone(), inner.force),
+            Err(_) => (None, None, false),
+        };
+        json!({
+            "shuttingDown": self.is_shutting_down(),
+            "phase": phase,
+            "startedAt": started_at,
+            "force": force,
+        })
+    }
+}
+
+fn grace_period(conn: &Connection) -> Duration {
+    let secs = db::get_setting(conn, "system", "shutdown_grace_secs")
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_GRACE_PERIOD_SECS)
+        .min(MAX_GRACE_PERIOD_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Fold the WAL back into the main database file so the next launch (or a
+/// file-level backup taken while the app is closed) sees a single file.
+fn checkpoint_wal(conn: &Connection) -> Result<(), String> {
+    let (busy, _log, _checkpointed): (i64, i64, i64) = conn
+        .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .map_err(|e| format!("wal checkpoint: {e}"))?;
+    if busy != 0 {
+        return Err("wal checkpoint could not complete: database busy".into());
+    }
+    Ok(())
+}
+
+fn emit_progress(app: &AppHandle, state: &ShutdownState, phase: &'static str, extra: Value) {
+    state.set_phase(phase);
+    let mut payload = json!({ "phase": phase });
+    if let (Some(payload), Value::Object(extra)) = (payload.as_object_mut(), extra) {
+        payload.extend(extra);
+    }
+    let _ = app.emit("app_shutdown_progress", payload);
+}
+
+/// Run the shutdown steps. Returns `false` when another shutdown already
+/// owns the sequence; the caller should not exit in that case.
+pub async fn run_shutdown_sequence(app: &AppHandle, force: bool) -> bool {
+    let state = app.state::<ShutdownState>();
+    if !state.begin(force) {
+        info!("Shutdown already in progress");
+        return false;
+    }
+    let db = app.state::<DbState>();
+    let sync_state = app.state::<Arc<SyncState>>();
+
+    emit_progress(app, &state, "stopping_workers", json!({ "force": force }));
+    sync_state.is_running.store(false, Ordering::SeqCst);
+    if let Some(token) = app.try_state::<CancellationToken>() {
+        token.cancel();
+    }
+
+    if force {
+        info!("Forced shutdown: skipping in-flight sync wait");
+    } else if sync_state.is_cycle_in_flight() {
+        let grace = db
+            .conn
+            .lock()
+            .map(|conn| grace_period(&conn))
+            .unwrap_or(Duration::from_secs(DEFAULT_GRACE_PERIOD_SECS));
+        emit_progress(
+            app,
+            &state,
+            "finishing_sync",
+            json!({ "graceSecs": grace.as_secs() }),
+        );
+        let started = Instant::now();
+        while sync_state.is_cycle_in_flight() && started.elapsed() < grace {
+            tokio::time::sleep(SYNC_POLL_INTERVAL).await;
+        }
+        if sync_state.is_cycle_in_flight() {
+            warn!(
+                grace_secs = grace.as_secs(),
+                "Sync cycle still running after shutdown grace period"
+            );
+        }
+    }
+
+    emit_progress(app, &state, "releasing_sync_queue", json!({}));
+    match sync::release_in_progress_sync_rows(&db) {
+        Ok(0) => {}
+        Ok(released) => info!(released, "Released in-progress sync rows for shutdown"),
+        Err(error) => warn!(error = %error, "Failed to release in-progress sync rows"),
+    }
+
+    event_batcher::flush(app);
+
+    emit_progress(app, &state, "checkpointing_database", json!({}));
+    match db.conn.lock() {
+        Ok(conn) => {
+            if let Err(error) = checkpoint_wal(&conn) {
+                warn!(error = %error, "Shutdown WAL checkpoint failed");
+            }
+        }
+        Err(error) => warn!(error = %error, "Shutdown could not lock database"),
+    }
+
+    emit_progress(app, &state, "exiting", json!({}));
+    info!("Shutdown sequence complete");
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn begin_only_succeeds_once() {
+        let state = ShutdownState::new();
+        assert_eq!(state.status_json()["shuttingDown"], json!(false));
+        assert!(state.begin(false));
+        assert!(!state.begin(true));
+        state.set_phase("finishing_sync");
+        let status = state.status_json();
+        assert_eq!(status["shuttingDown"], json!(true));
+        assert_eq!(status["phase"], json!("finishing_sync"));
+        assert_eq!(status["force"], json!(false));
+        assert!(status["startedAt"].is_string());
+    }
+
+    #[test]
+    fn grace_period_reads_and_caps_setting() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE local_settings (
+                setting_category TEXT, setting_key TEXT, setting_value TEXT
+             );",
+        )
+        .unwrap();
+        assert_eq!(
+            grace_period(&conn),
+            Duration::from_secs(DEFAULT_GRACE_PERIOD_SECS)
+        );
+        conn.execute(
+            "INSERT INTO local_settings VALUES ('system', 'shutdown_grace_secs', '900')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(
+            grace_period(&conn),
+            Duration::from_secs(MAX_GRACE_PERIOD_SECS)
+        );
+    }
+
+    #[test]
+    fn checkpoint_wal_succeeds_on_idle_database() {
+        let dir = std::env::temp_dir().join(format!("shutdown-wal-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let conn = Connection::open(dir.join("pos.db")).unwrap();
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE t (v INTEGER);
+             INSERT INTO t VALUES (1);",
+        )
+        .unwrap();
+        checkpoint_wal(&conn).unwrap();
+        drop(conn);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
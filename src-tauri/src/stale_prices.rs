@@ -0,0 +1,468 @@
+//! Menu price changes against orders that are still open.
+//!
+//! Open and held orders keep the prices they were rung up at, so a price
+//! change from head office mid-day only surfaced at payment. A menu sync now
+//! computes a [`PriceDelta`] — the items and combos whose price fields
+//! changed between the cached menu and the synced payload — and
+//! [`flag_open_orders`] marks the affected lines of open, unpaid orders with
+//! `price_stale: true` and the new `menu_price`, without changing what the
+//! order charges.
+//!
+//! [`reprice_order`] is the cashier's side: it prices every line from the
+//! current cache and reports a before/after diff; `order_refresh_prices`
+//! only writes it back once confirmed. Lines locked by a pricing rule keep
+//! their adjustment on the new price, open-price lines keep theirs, and an
+//! order with a completed payment is never touched.
+
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::combos;
+use crate::money::{self, Cents, RoundingRule};
+use crate::order_duplicate;
+use crate::pricing_rules;
+use crate::{value_f64, value_str};
+
+/// Line flag set when the menu price moved after the line was rung up.
+pub const STALE_KEY: &str = "price_stale";
+/// New menu unit price recorded next to [`STALE_KEY`].
+pub const MENU_PRICE_KEY: &str = "menu_price";
+
+/// Price fields of menu items and combos; a change in any of them counts.
+const PRICE_KEYS: &[&str] = &[
+    "price",
+    "base_price",
+    "pickup_price",
+    "delivery_price",
+    "dine_in_price",
+];
+
+/// Orders still open with nothing paid on them.
+const OPEN_UNPAID_FILTER: &str = "LOWER(COALESCE(status, '')) NOT IN
+        ('completed', 'delivered', 'cancelled', 'voided', 'refunded')
+   AND LOWER(COALESCE(payment_status, 'pending')) NOT IN
+        ('paid', 'partially_paid', 'refunded')
+   AND NOT EXISTS (
+        SELECT 1 FROM order_payments op
+        WHERE op.order_id = orders.id AND op.status = 'completed'
+   )";
+
+/// Menu entries whose price changed in a sync, keyed by id, with their
+/// synced definition.
+#[derive(Debug, Default)]
+pub struct PriceDelta {
+    pub items: HashMap<String, Value>,
+    pub combos: HashMap<String, Value>,
+}
+
+fn price_changed(cached: &Value, synced: &Value) -> bool {
+    PRICE_KEYS.iter().any(|key| {
+        value_f64(cached, &[*key]).map(Cents::round_half_even)
+            != value_f64(synced, &[*key]).map(Cents::round_half_even)
+    })
+}
+
+fn section_delta(
+    cached: &HashMap<String, Value>,
+    synced: Option<&Value>,
+) -> HashMap<String, Value> {
+    synced
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let id = value_str(entry, &["id"])?.trim().to_string();
+            let before = cached.get(&id)?;
+            price_changed(before, entry).then(|| (id, entry.clone()))
+        })
+        .collect()
+}
+
+impl PriceDelta {
+    /// Compare the cached menu with a synced payload before it replaces the
+    /// cache. Entries new to the menu are not price changes.
+    pub fn between(conn: &Connection, data: &Value) -> Self {
+        Self {
+            items: section_delta(
+                &combos::read_section(conn, "subcategories"),
+                data.get("subcategories"),
+            ),
+            combos: section_delta(&combos::read_section(conn, "combos"), data.get("combos")),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty() && self.combos.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len() + self.combos.len()
+    }
+}
+
+fn extras_per_unit(line: &Value) -> f64 {
+    let raw = match line.get("customizations") {
+        Some(Value::String(text)) => serde_json::from_str::<Value>(text).ok(),
+        other => other.cloned(),
+    };
+    let entries: Vec<Value> = match raw {
+        Some(Value::Array(entries)) => entries,
+        Some(Value::Object(entries)) => entries.into_iter().map(|(_, entry)| entry).collect(),
+        _ => Vec::new(),
+    };
+    entries
+        .iter()
+        .filter(|entry| !order_duplicate::is_removal(entry))
+        .map(|entry| {
+            let quantity = value_f64(entry, &["quantity"]).unwrap_or(1.0).max(0.0);
+            let price = value_f64(entry, &["price"])
+                .or_else(|| {
+                    entry
+                        .get("ingredient")
+                        .and_then(|ingredient| value_f64(ingredient, &["price"]))
+                })
+                .unwrap_or(0.0)
+                .max(0.0);
+            price * quantity
+        })
+        .sum()
+}
+
+/// Unit price `line` gets from the given menu entries: the item's price for
+/// the order type plus the extras on the line, or the combo price plus paid
+/// upgrades, with a locked pricing-rule adjustment applied again. `None`
+/// for open-price lines and lines whose entry is not in the maps.
+fn menu_unit(
+    line: &Value,
+    items: &HashMap<String, Value>,
+    combo_entries: &HashMap<String, Value>,
+    order_type: &str,
+    rule: RoundingRule,
+) -> Option<Cents> {
+    if order_duplicate::is_manual(line) {
+        return None;
+    }
+    if let Some(combo_id) = combos::combo_id(line) {
+        let combo = combo_entries.get(&combo_id)?;
+        return combos::menu_unit_price(combo, line, order_type, rule);
+    }
+    let menu_item_id = value_str(line, &["menu_item_id", "menuItemId"])?;
+    let entry = items.get(menu_item_id.trim())?;
+    let base = order_duplicate::menu_price(entry, order_type)?;
+    let unit = Cents::round_with(base + extras_per_unit(line), rule);
+    Some(pricing_rules::locked_unit_price(line, unit, rule))
+}
+
+fn clear_flag(line: &mut Value) -> bool {
+    line.as_object_mut().is_some_and(|object| {
+        let had = object.remove(STALE_KEY).is_some();
+        object.remove(MENU_PRICE_KEY);
+        had
+    })
+}
+
+fn parse_items(items_json: &str) -> Vec<Value> {
+    serde_json::from_str::<Value>(items_json)
+        .ok()
+        .and_then(|value| value.as_array().cloned())
+        .unwrap_or_default()
+}
+
+/// Mark the lines of open, unpaid orders that `delta` re-prices. Lines
+/// already at the new price are left alone. Returns the flagged order ids.
+pub fn flag_open_orders(conn: &Connection, delta: &PriceDelta) -> Result<Vec<String>, String> {
+    if delta.is_empty() {
+        return Ok(Vec::new());
+    }
+    let rule = RoundingRule::from_settings(conn);
+    let orders: Vec<(String, String, String)> = {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT id, COALESCE(items, '[]'), COALESCE(order_type, 'pickup')
+                 FROM orders WHERE {OPEN_UNPAID_FILTER}"
+            ))
+            .map_err(|e| format!("prepare open orders: {e}"))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| format!("query open orders: {e}"))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("read open orders: {e}"))?
+    };
+
+    let mut flagged = Vec::new();
+    for (order_id, items_json, order_type) in orders {
+        let mut items = parse_items(&items_json);
+        let mut changed = false;
+        for line in items.iter_mut() {
+            let Some(unit) = menu_unit(line, &delta.items, &delta.combos, &order_type, rule) else {
+                continue;
+            };
+            if pricing_rules::line_unit(line, rule) == Some(unit) {
+                continue;
+            }
+            if let Some(object) = line.as_object_mut() {
+                object.insert(STALE_KEY.to_string(), Value::Bool(true));
+                object.insert(MENU_PRICE_KEY.to_string(), json!(unit.to_f64_dp2()));
+                changed = true;
+            }
+        }
+        if !changed {
+            continue;
+        }
+        let items_json =
+            serde_json::to_string(&items).map_err(|e| format!("serialize items: {e}"))?;
+        conn.execute(
+            "UPDATE orders SET items = ?1 WHERE id = ?2",
+            params![items_json, order_id],
+        )
+        .map_err(|e| format!("flag stale prices: {e}"))?;
+        flagged.push(order_id);
+    }
+    Ok(flagged)
+}
+
+/// One line whose price a refresh changes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineChange {
+    pub index: usize,
+    pub name: String,
+    pub menu_item_id: Option<String>,
+    pub combo_id: Option<String>,
+    pub quantity: f64,
+    pub unit_before: f64,
+    pub unit_after: f64,
+    pub total_before: f64,
+    pub total_after: f64,
+}
+
+/// An order's lines priced from the current menu, not yet saved.
+#[derive(Debug, Clone)]
+pub struct PriceRefresh {
+    pub order_type: String,
+    pub items: Vec<Value>,
+    pub changes: Vec<LineChange>,
+    pub items_before: Cents,
+    pub items_after: Cents,
+    /// Whether any line carried a stale flag (cleared in `items`).
+    pub had_flags: bool,
+}
+
+impl PriceRefresh {
+    pub fn delta(&self) -> Cents {
+        self.items_after - self.items_before
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "orderType": self.order_type,
+            "changes": self.changes,
+            "itemsBefore": self.items_before.to_f64_dp2(),
+            "itemsAfter": self.items_after.to_f64_dp2(),
+            "delta": self.delta().to_f64_dp2(),
+        })
+    }
+}
+
+/// Price every line of `order_id` (a local id) from the cached menu. Fails
+/// when the order is paid or partly paid, split into checks, or the menu
+/// was never synced.
+pub fn reprice_order(conn: &Connection, order_id: &str) -> Result<PriceRefresh, String> {
+    let (items_json, order_type, open): (String, String, bool) = conn
+        .query_row(
+            &format!(
+                "SELECT COALESCE(items, '[]'), COALESCE(order_type, 'pickup'),
+                        CASE WHEN {OPEN_UNPAID_FILTER} THEN 1 ELSE 0 END
+                 FROM orders WHERE id = ?1"
+            ),
+            params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, i64>(2)? != 0)),
+        )
+        .optional()
+        .map_err(|e| format!("load order for price refresh: {e}"))?
+        .ok_or_else(|| format!("Order not found: {order_id}"))?;
+    if !open {
+        return Err("Only open, unpaid orders can have their prices refreshed".into());
+    }
+    if !crate::checks::load_for_order(conn, order_id)?.is_empty() {
+        return Err("Order is split into checks; prices cannot be refreshed".into());
+    }
+    let menu_items = combos::read_section(conn, "subcategories");
+    if menu_items.is_empty() {
+        return Err("Menu is not synced yet; cannot refresh prices".into());
+    }
+    let combo_entries = combos::read_section(conn, "combos");
+    let rule = RoundingRule::from_settings(conn);
+
+    let mut items = parse_items(&items_json);
+    let items_before = money::items_total_cents(&items, rule);
+    let mut changes = Vec::new();
+    let mut had_flags = false;
+    for (index, line) in items.iter_mut().enumerate() {
+        had_flags |= clear_flag(line);
+        let Some(unit) = menu_unit(line, &menu_items, &combo_entries, &order_type, rule) else {
+            continue;
+        };
+        let unit_before = pricing_rules::line_unit(line, rule);
+        if unit_before == Some(unit) {
+            continue;
+        }
+        let total_before = money::item_line_cents(line, rule);
+        pricing_rules::set_line_price(line, unit, rule);
+        changes.push(LineChange {
+            index,
+            name: value_str(line, &["name", "menu_item_name", "itemName"])
+                .unwrap_or_else(|| "Item".to_string()),
+            menu_item_id: value_str(line, &["menu_item_id", "menuItemId"]),
+            combo_id: combos::combo_id(line),
+            quantity: value_f64(line, &["quantity"]).unwrap_or(1.0),
+            unit_before: unit_before.unwrap_or(Cents::ZERO).to_f64_dp2(),
+            unit_after: unit.to_f64_dp2(),
+            total_before: total_before.to_f64_dp2(),
+            total_after: money::item_line_cents(line, rule).to_f64_dp2(),
+        });
+    }
+    let items_after = money::items_total_cents(&items, rule);
+    Ok(PriceRefresh {
+        order_type,
+        items,
+        changes,
+        items_before,
+        items_after,
+        had_flags,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn cache_section(conn: &Connection, key: &str, data: Value) {
+        conn.execute(
+            "INSERT INTO menu_cache (id, cache_key, data, version, updated_at)
+             VALUES (?1, ?1, ?2, 'v1', datetime('now'))
+             ON CONFLICT(cache_key) DO UPDATE SET data = excluded.data",
+            params![key, data.to_string()],
+        )
+        .unwrap();
+    }
+
+    fn insert_order(conn: &Connection, id: &str, payment_status: &str, items: Value) {
+        conn.execute(
+            "INSERT INTO orders (id, items, total_amount, status, payment_status, order_type,
+                                 created_at, updated_at)
+             VALUES (?1, ?2, 10.0, 'pending', ?3, 'pickup', datetime('now'), datetime('now'))",
+            params![id, items.to_string(), payment_status],
+        )
+        .unwrap();
+    }
+
+    fn stored_items(conn: &Connection, id: &str) -> Vec<Value> {
+        let raw: String = conn
+            .query_row(
+                "SELECT items FROM orders WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        parse_items(&raw)
+    }
+
+    fn pizza_line() -> Value {
+        json!({
+            "menu_item_id": "pizza",
+            "name": "Pizza",
+            "quantity": 2,
+            "unit_price": 10.0,
+            "total_price": 20.0,
+            "customizations": [{ "name": "Olives", "price": 0.5, "quantity": 1 }]
+        })
+    }
+
+    #[test]
+    fn delta_counts_only_price_changes_of_known_entries() {
+        let conn = test_conn();
+        cache_section(
+            &conn,
+            "subcategories",
+            json!([
+                { "id": "pizza", "name": "Pizza", "pickup_price": 9.5 },
+                { "id": "salad", "name": "Salad", "pickup_price": 6.0 }
+            ]),
+        );
+        let delta = PriceDelta::between(
+            &conn,
+            &json!({
+                "subcategories": [
+                    { "id": "pizza", "name": "Pizza", "pickup_price": 11.0 },
+                    { "id": "salad", "name": "Green salad", "pickup_price": 6.0 },
+                    { "id": "pasta", "name": "Pasta", "pickup_price": 8.0 }
+                ]
+            }),
+        );
+        assert_eq!(delta.len(), 1);
+        assert!(delta.items.contains_key("pizza"));
+    }
+
+    #[test]
+    fn flags_open_unpaid_orders_but_never_paid_ones() {
+        let conn = test_conn();
+        insert_order(&conn, "open", "pending", json!([pizza_line()]));
+        insert_order(&conn, "paid", "paid", json!([pizza_line()]));
+        let delta = PriceDelta {
+            items: HashMap::from([(
+                "pizza".to_string(),
+                json!({ "id": "pizza", "pickup_price": 11.0 }),
+            )]),
+            combos: HashMap::new(),
+        };
+
+        assert_eq!(flag_open_orders(&conn, &delta).unwrap(), vec!["open"]);
+        let open = stored_items(&conn, "open");
+        assert_eq!(open[0][STALE_KEY], json!(true));
+        assert_eq!(open[0][MENU_PRICE_KEY], json!(11.5));
+        assert_eq!(open[0]["unit_price"], json!(10.0));
+        assert!(stored_items(&conn, "paid")[0].get(STALE_KEY).is_none());
+    }
+
+    #[test]
+    fn reprice_keeps_locked_rule_adjustment_and_refuses_paid_orders() {
+        let conn = test_conn();
+        cache_section(
+            &conn,
+            "subcategories",
+            json!([{ "id": "pizza", "pickup_price": 12.0 }]),
+        );
+        let mut locked = pizza_line();
+        locked[STALE_KEY] = json!(true);
+        locked[pricing_rules::LINE_MARKER_KEY] = json!({
+            "adjustmentType": "percent",
+            "adjustmentValue": 50.0
+        });
+        let manual =
+            json!({ "name": "Open food", "is_manual": true, "unit_price": 3.0, "quantity": 1 });
+        insert_order(&conn, "open", "pending", json!([locked, manual]));
+        insert_order(&conn, "paid", "paid", json!([pizza_line()]));
+
+        let refresh = reprice_order(&conn, "open").unwrap();
+        assert!(refresh.had_flags);
+        assert_eq!(refresh.changes.len(), 1);
+        assert_eq!(refresh.changes[0].unit_after, 6.25);
+        assert_eq!(refresh.items[0]["total_price"], json!(12.5));
+        assert!(refresh.items[0].get(STALE_KEY).is_none());
+        assert_eq!(refresh.items[1]["unit_price"], json!(3.0));
+        assert_eq!(refresh.delta(), Cents::new(-750));
+
+        assert!(reprice_order(&conn, "paid").is_err());
+    }
+}
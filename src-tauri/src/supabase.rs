@@ -0,0 +1,485 @@
+//! Read-only PostgREST client for the few tables the POS still reads
+//! straight from Supabase (order items, scheduled shifts, the customer
+//! fallback sync).
+//!
+//! The old `fetch_supabase_rows` sent one GET and returned whatever came
+//! back, so a caller could silently stop at PostgREST's row limit and every
+//! failure was an opaque string. [`SupabaseQuery`] pages with the `Range`
+//! header and `Prefer: count=exact`, follows the reported total until the
+//! table is exhausted (up to a hard cap), and returns a [`SupabaseError`]
+//! that tells auth failures apart from transient ones. 5xx responses and
+//! transport failures are retried a couple of times before giving up;
+//! 401/403 are handed to [`handle_auth_failure`] by callers that hold an
+//! `AppHandle`.
+//!
+//! Requests are logged with the table and filter only. The anon key and the
+//! terminal API key travel in headers and are never logged.
+
+use reqwest::header::{HeaderMap, CONTENT_RANGE};
+use reqwest::{StatusCode, Url};
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::error::PosError;
+use crate::{db, storage};
+
+pub const DEFAULT_PAGE_SIZE: usize = 1_000;
+pub const DEFAULT_MAX_ROWS: usize = 50_000;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SupabaseError {
+    #[error("Supabase not configured: {0}")]
+    NotConfigured(String),
+    /// 401/403: the anon key or the terminal credentials were rejected.
+    #[error("Supabase rejected terminal credentials (HTTP {status}): {body}")]
+    Auth { status: u16, body: String },
+    /// 5xx: worth retrying later.
+    #[error("Supabase unavailable (HTTP {status}): {body}")]
+    Unavailable { status: u16, body: String },
+    #[error("Supabase request failed: {0}")]
+    Network(String),
+    #[error("Supabase error (HTTP {status}): {body}")]
+    Rejected { status: u16, body: String },
+    #[error("Supabase JSON parse error: {0}")]
+    Parse(String),
+    #[error("{table} pagination exceeded safety limit of {cap} rows")]
+    RowCapExceeded { table: String, cap: usize },
+}
+
+impl SupabaseError {
+    pub fn is_auth(&self) -> bool {
+        matches!(self, Self::Auth { .. })
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Unavailable { .. } | Self::Network(_))
+    }
+
+    fn from_status(status: StatusCode, body: String) -> Self {
+        let status_code = status.as_u16();
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Self::Auth {
+                status: status_code,
+                body,
+            },
+            status if status.is_server_error() => Self::Unavailable {
+                status: status_code,
+                body,
+            },
+            _ => Self::Rejected {
+                status: status_code,
+                body,
+            },
+        }
+    }
+}
+
+impl From<SupabaseError> for String {
+    fn from(error: SupabaseError) -> Self {
+        error.to_string()
+    }
+}
+
+impl From<SupabaseError> for PosError {
+    fn from(error: SupabaseError) -> Self {
+        let message = error.to_string();
+        match error {
+            SupabaseError::Auth { .. } => Self::TerminalAuth(message),
+            SupabaseError::Unavailable { .. } | SupabaseError::Network(_) => {
+                Self::NetworkError(message)
+            }
+            _ => Self::Internal(message),
+        }
+    }
+}
+
+/// Route a 401/403 through the same reset flow the admin API uses. Other
+/// errors are left to the caller.
+pub(crate) fn handle_auth_failure(
+    db: Option<&db::DbState>,
+    app: &tauri::AppHandle,
+    source: &str,
+    error: &SupabaseError,
+) {
+    if error.is_auth() {
+        crate::handle_invalid_terminal_credentials(db, app, source, &error.to_string());
+    }
+}
+
+/// One page of rows plus the total reported by `Content-Range`, when the
+/// server sent one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SupabasePage {
+    pub rows: Vec<Value>,
+    pub total: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SupabaseQuery {
+    table: String,
+    params: Vec<(String, String)>,
+    page_size: usize,
+    max_rows: usize,
+}
+
+impl SupabaseQuery {
+    pub fn new(table: &str) -> Self {
+        Self {
+            table: table.to_string(),
+            params: Vec::new(),
+            page_size: DEFAULT_PAGE_SIZE,
+            max_rows: DEFAULT_MAX_ROWS,
+        }
+    }
+
+    pub fn select(self, columns: &str) -> Self {
+        self.param("select", columns)
+    }
+
+    /// PostgREST filter, e.g. `filter("branch_id", format!("eq.{id}"))`.
+    /// The same column may be filtered more than once.
+    pub fn filter(self, column: &str, expression: impl Into<String>) -> Self {
+        self.param(column, expression)
+    }
+
+    /// PostgREST ordering, e.g. `"start_time.asc"`.
+    pub fn order(self, spec: &str) -> Self {
+        self.param("order", spec)
+    }
+
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Hard cap for [`fetch_all`](Self::fetch_all); more rows is an error.
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    fn param(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.params.push((key.to_string(), value.into()));
+        self
+    }
+
+    /// Query parameters except `select`, for logs.
+    fn filter_summary(&self) -> String {
+        self.params
+            .iter()
+            .filter(|(key, _)| key != "select")
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// Fetch rows `offset .. offset + limit` together with the exact total.
+    /// In training mode the network is never touched and the page is empty.
+    pub async fn fetch_page(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<SupabasePage, SupabaseError> {
+        if crate::training::is_active() {
+            return Ok(SupabasePage {
+                rows: Vec::new(),
+                total: Some(0),
+            });
+        }
+        let mut attempt = 1;
+        loop {
+            match self.send(offset, limit).await {
+                Err(error) if error.is_retryable() && attempt < MAX_ATTEMPTS => {
+                    tracing::warn!(
+                        table = %self.table,
+                        filter = %self.filter_summary(),
+                        attempt,
+                        error = %error,
+                        "Supabase query failed; retrying"
+                    );
+                    tokio::time::sleep(RETRY_BACKOFF * attempt).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Page through the whole result set. Stops once the reported total is
+    /// reached, or on a short page when the server sent no total, and fails
+    /// with [`SupabaseError::RowCapExceeded`] past `max_rows`.
+    pub async fn fetch_all(&self) -> Result<Vec<Value>, SupabaseError> {
+        let mut rows = Vec::new();
+        loop {
+            let page = self.fetch_page(rows.len(), self.page_size).await?;
+            let page_len = page.rows.len();
+            rows.extend(page.rows);
+
+            let exhausted = match page.total {
+                Some(total) => page_len == 0 || rows.len() >= total,
+                None => page_len < self.page_size,
+            };
+            if exhausted {
+                break;
+            }
+            if rows.len() >= self.max_rows {
+                return Err(SupabaseError::RowCapExceeded {
+                    table: self.table.clone(),
+                    cap: self.max_rows,
+                });
+            }
+        }
+        tracing::debug!(
+            table = %self.table,
+            filter = %self.filter_summary(),
+            rows = rows.len(),
+            "Supabase query complete"
+        );
+        Ok(rows)
+    }
+
+    async fn send(&self, offset: usize, limit: usize) -> Result<SupabasePage, SupabaseError> {
+        let supabase_url = storage::get_credential("supabase_url")
+            .ok_or_else(|| SupabaseError::NotConfigured("missing URL".into()))?;
+        let supabase_key = storage::get_credential("supabase_anon_key")
+            .ok_or_else(|| SupabaseError::NotConfigured("missing anon key".into()))?;
+
+        let base = supabase_url.trim_end_matches('/');
+        let mut url = Url::parse(&format!("{base}/rest/v1/{}", self.table))
+            .map_err(|e| SupabaseError::NotConfigured(format!("invalid URL: {e}")))?;
+        {
+            let mut qp = url.query_pairs_mut();
+            for (key, value) in &self.params {
+                qp.append_pair(key, value);
+            }
+        }
+
+        tracing::debug!(
+            table = %self.table,
+            filter = %self.filter_summary(),
+            offset,
+            limit,
+            "Supabase query"
+        );
+
+        let client = crate::api::shared_client().map_err(SupabaseError::Network)?;
+        let mut request = client
+            .get(url)
+            .timeout(REQUEST_TIMEOUT)
+            .header("apikey", &supabase_key)
+            .header("Authorization", format!("Bearer {supabase_key}"))
+            .header("Content-Type", "application/json")
+            .header("Range-Unit", "items")
+            .header("Range", format!("{offset}-{}", offset + limit.max(1) - 1))
+            .header("Prefer", "count=exact")
+            .header(
+                crate::correlation::HEADER,
+                crate::correlation::current_or_new(),
+            );
+
+        if let Some(terminal_id) = storage::get_credential("terminal_id")
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+        {
+            request = request.header("x-terminal-id", terminal_id);
+        }
+        if let Some(api_key) = storage::get_credential("pos_api_key")
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+        {
+            request = request.header("x-pos-api-key", api_key);
+        }
+
+        let resp = request
+            .send()
+            .await
+            .map_err(|e| SupabaseError::Network(e.to_string()))?;
+        let status = resp.status();
+        let total = content_range_total(resp.headers());
+        if status == StatusCode::RANGE_NOT_SATISFIABLE {
+            // Offset past the end of the table.
+            return Ok(SupabasePage {
+                rows: Vec::new(),
+                total,
+            });
+        }
+        if !status.is_success() {
+            // Wave 9 H4: keep body-read failures visible instead of
+            // collapsing them to an empty string.
+            let body = resp
+                .text()
+                .await
+                .unwrap_or_else(|e| format!("<failed to read body: {e}>"));
+            let error = SupabaseError::from_status(status, body);
+            tracing::warn!(
+                table = %self.table,
+                filter = %self.filter_summary(),
+                status = status.as_u16(),
+                "Supabase query rejected"
+            );
+            return Err(error);
+        }
+
+        let body = resp
+            .json::<Value>()
+            .await
+            .map_err(|e| SupabaseError::Parse(e.to_string()))?;
+        let rows = match body {
+            Value::Array(rows) => rows,
+            Value::Null => Vec::new(),
+            other => vec![other],
+        };
+        Ok(SupabasePage { rows, total })
+    }
+}
+
+/// Total from `Content-Range: 0-999/5432`; `None` for `*` or no header.
+fn content_range_total(headers: &HeaderMap) -> Option<usize> {
+    headers
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit_once('/')?
+        .1
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::fake_http::{MockResponse, MockServer};
+    use crate::tests::fake_keyring;
+    use serde_json::json;
+
+    fn seed_credentials(server: &MockServer) -> fake_keyring::Guard {
+        fake_keyring::install_seeded([
+            ("supabase_url", server.url.as_str()),
+            ("supabase_anon_key", "anon-secret"),
+            ("terminal_id", "term-1"),
+            ("pos_api_key", "pos-secret"),
+        ])
+    }
+
+    fn rows(range: std::ops::Range<usize>) -> String {
+        json!(range.map(|id| json!({ "id": id })).collect::<Vec<_>>()).to_string()
+    }
+
+    #[tokio::test]
+    async fn fetch_all_pages_until_reported_total() {
+        let server = MockServer::scripted(vec![
+            MockResponse::json(206, rows(0..2)).with_header("Content-Range", "0-1/5"),
+            MockResponse::json(206, rows(2..4)).with_header("Content-Range", "2-3/5"),
+            MockResponse::json(206, rows(4..5)).with_header("Content-Range", "4-4/5"),
+        ]);
+        let _keyring = seed_credentials(&server);
+
+        let all = SupabaseQuery::new("salon_staff_shifts")
+            .select("id")
+            .filter("branch_id", "eq.b-1")
+            .order("start_time.asc")
+            .page_size(2)
+            .fetch_all()
+            .await
+            .expect("fetch all");
+
+        assert_eq!(all.len(), 5);
+        assert_eq!(all[4], json!({ "id": 4 }));
+        let recorded = server.recorded();
+        let ranges: Vec<_> = recorded.iter().map(|r| r.header("range")).collect();
+        assert_eq!(ranges, vec![Some("0-1"), Some("2-3"), Some("4-5")]);
+        assert_eq!(recorded[0].header("prefer"), Some("count=exact"));
+        assert!(recorded[0].path.starts_with("/rest/v1/salon_staff_shifts?"));
+        assert!(recorded[0].path.contains("branch_id=eq.b-1"));
+        assert!(recorded[0].path.contains("order=start_time.asc"));
+        assert_eq!(recorded[0].header("x-pos-api-key"), Some("pos-secret"));
+    }
+
+    #[tokio::test]
+    async fn fetch_all_without_total_stops_on_short_page_and_enforces_cap() {
+        let server = MockServer::scripted(vec![
+            MockResponse::json(200, rows(0..2)),
+            MockResponse::json(200, rows(2..3)),
+        ]);
+        let _keyring = seed_credentials(&server);
+        let query = SupabaseQuery::new("order_items").page_size(2);
+        assert_eq!(query.fetch_all().await.unwrap().len(), 3);
+
+        let capped = MockServer::new(rows(0..2));
+        let _keyring = seed_credentials(&capped);
+        let error = SupabaseQuery::new("customers")
+            .page_size(2)
+            .max_rows(4)
+            .fetch_all()
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            SupabaseError::RowCapExceeded {
+                table: "customers".into(),
+                cap: 4
+            }
+        );
+        assert_eq!(capped.count(), 2);
+    }
+
+    #[tokio::test]
+    async fn auth_failures_are_typed_and_not_retried() {
+        let server = MockServer::scripted(vec![MockResponse::json(
+            401,
+            r#"{"message":"JWT expired"}"#,
+        )]);
+        let _keyring = seed_credentials(&server);
+
+        let error = SupabaseQuery::new("order_items")
+            .fetch_all()
+            .await
+            .unwrap_err();
+        assert!(error.is_auth(), "{error:?}");
+        assert!(!error.is_retryable());
+        assert_eq!(PosError::from(error).code(), "TERMINAL_AUTH");
+        assert_eq!(server.count(), 1);
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried_then_reported_as_transient() {
+        let server = MockServer::scripted(vec![
+            MockResponse::json(503, "{}"),
+            MockResponse::json(200, rows(0..1)),
+        ]);
+        let _keyring = seed_credentials(&server);
+        let rows = SupabaseQuery::new("menu_items").fetch_all().await.unwrap();
+        assert_eq!(rows, vec![json!({ "id": 0 })]);
+        assert_eq!(server.count(), 2);
+
+        let down = MockServer::scripted(vec![MockResponse::json(500, "boom")]);
+        let _keyring = seed_credentials(&down);
+        let error = SupabaseQuery::new("menu_items")
+            .fetch_all()
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            SupabaseError::Unavailable {
+                status: 500,
+                body: "boom".into()
+            }
+        );
+        assert_eq!(PosError::from(error).code(), "NETWORK_ERROR");
+        assert_eq!(down.count(), MAX_ATTEMPTS as usize);
+    }
+
+    #[test]
+    fn content_range_total_handles_unknown_totals() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(content_range_total(&headers), None);
+        headers.insert(CONTENT_RANGE, "0-9/42".parse().unwrap());
+        assert_eq!(content_range_total(&headers), Some(42));
+        headers.insert(CONTENT_RANGE, "0-9/*".parse().unwrap());
+        assert_eq!(content_range_total(&headers), None);
+    }
+}
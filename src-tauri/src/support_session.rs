@@ -0,0 +1,275 @@
+//! Read-only remote support sessions.
+//!
+//! Support used to need a screen-sharing tool to look at a terminal's orders
+//! and sync state. `support_session_start` (admin, behind the SystemControl
+//! PIN confirmation) opens a session for [`DEFAULT_DURATION_MINS`] and hands
+//! out a one-time token. `support_get_snapshot` trades that token for the
+//! redacted diagnostics snapshot plus the next token, so a token that was
+//! already used, belongs to an ended session or outlived the session fails.
+//!
+//! Only one session exists at a time and it lives in memory: a restart ends
+//! it. Every start, snapshot, rejected token and end is written to
+//! `recovery_action_log`, and the end of a session, early or by expiry,
+//! emits [`ENDED_EVENT`].
+
+use std::sync::{Mutex, OnceLock};
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+pub const ENDED_EVENT: &str = "support_session_ended";
+pub const DEFAULT_DURATION_MINS: i64 = 30;
+pub const MAX_DURATION_MINS: i64 = 120;
+const AUDIT_ACTION_ID: &str = "support_session";
+
+/// Why a session ended, as reported in [`ENDED_EVENT`] and the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndReason {
+    Ended,
+    Expired,
+    Replaced,
+}
+
+impl EndReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ended => "ended",
+            Self::Expired => "expired",
+            Self::Replaced => "replaced",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub id: String,
+    pub started_by: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub snapshots: u32,
+    token: String,
+}
+
+impl Session {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "sessionId": self.id,
+            "startedBy": self.started_by,
+            "startedAt": self.started_at.to_rfc3339(),
+            "expiresAt": self.expires_at.to_rfc3339(),
+            "snapshots": self.snapshots,
+        })
+    }
+}
+
+fn active() -> &'static Mutex<Option<Session>> {
+    static ACTIVE: OnceLock<Mutex<Option<Session>>> = OnceLock::new();
+    ACTIVE.get_or_init(Default::default)
+}
+
+fn new_token() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// Session length from the `durationMinutes` payload field, clamped to
+/// 1..=[`MAX_DURATION_MINS`].
+pub fn duration_from_payload(payload: Option<&Value>) -> Duration {
+    let minutes = payload
+        .and_then(|payload| {
+            payload
+                .get("durationMinutes")
+                .or_else(|| payload.get("duration_minutes"))
+        })
+        .and_then(Value::as_i64)
+        .unwrap_or(DEFAULT_DURATION_MINS)
+        .clamp(1, MAX_DURATION_MINS);
+    Duration::minutes(minutes)
+}
+
+/// Open a session. Returns it with its first token, and the session it
+/// replaced, if one was still open.
+pub fn start(
+    started_by: Option<&str>,
+    duration: Duration,
+    now: DateTime<Utc>,
+) -> Result<(Session, String, Option<Session>), String> {
+    let token = new_token();
+    let session = Session {
+        id: Uuid::new_v4().to_string(),
+        started_by: started_by.map(str::to_string),
+        started_at: now,
+        expires_at: now + duration,
+        snapshots: 0,
+        token: token.clone(),
+    };
+    let mut active = active().lock().map_err(|e| e.to_string())?;
+    let replaced = active.replace(session.clone());
+    Ok((session, token, replaced))
+}
+
+/// Why a token was refused. `Expired` carries the session it ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenError {
+    Missing,
+    Expired(Session),
+    Invalid,
+}
+
+impl TokenError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::Missing => "support_get_snapshot requires a support session token",
+            Self::Expired(_) => "Support session has expired; start a new one",
+            Self::Invalid => "Unknown or already used support session token",
+        }
+    }
+}
+
+/// Use up `token` and issue the next one. Returns the session after the
+/// snapshot is counted, and the next token.
+pub fn consume(token: Option<&str>, now: DateTime<Utc>) -> Result<(Session, String), TokenError> {
+    let token = token
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .ok_or(TokenError::Missing)?;
+    let mut active = active().lock().map_err(|_| TokenError::Invalid)?;
+    let Some(session) = active.as_mut().filter(|session| session.token == token) else {
+        return Err(TokenError::Invalid);
+    };
+    if session.expires_at <= now {
+        let expired = active.take().expect("matched above");
+        return Err(TokenError::Expired(expired));
+    }
+    session.token = new_token();
+    session.snapshots += 1;
+    Ok((session.clone(), session.token.clone()))
+}
+
+/// End the open session, if any. With `session_id`, only that session.
+pub fn end(session_id: Option<&str>) -> Result<Option<Session>, String> {
+    let mut active = active().lock().map_err(|e| e.to_string())?;
+    let matches = active.as_ref().is_some_and(|session| match session_id {
+        Some(id) => session.id == id,
+        None => true,
+    });
+    Ok(if matches { active.take() } else { None })
+}
+
+/// End `session_id` if it is still open and past its expiry.
+pub fn expire(session_id: &str, now: DateTime<Utc>) -> Result<Option<Session>, String> {
+    let mut active = active().lock().map_err(|e| e.to_string())?;
+    let due = active
+        .as_ref()
+        .is_some_and(|session| session.id == session_id && session.expires_at <= now);
+    Ok(if due { active.take() } else { None })
+}
+
+pub fn current() -> Option<Session> {
+    active().lock().ok().and_then(|active| active.clone())
+}
+
+/// Write one session step to `recovery_action_log`.
+pub fn record(
+    conn: &Connection,
+    step: &str,
+    session_id: Option<&str>,
+    success: bool,
+    message: &str,
+    actor_staff_id: Option<&str>,
+    details: Value,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO recovery_action_log (
+            id, action_id, issue_code, entity_type, entity_id, success, message,
+            actor_staff_id, payload_json, created_at
+         ) VALUES (?1, ?2, ?3, 'support_session', ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            Uuid::new_v4().to_string(),
+            AUDIT_ACTION_ID,
+            step,
+            session_id,
+            success,
+            message,
+            actor_staff_id,
+            details.to_string(),
+            Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(|e| format!("record support session {step}: {e}"))?;
+    Ok(())
+}
+
+/// Audit the end of `session` and build the [`ENDED_EVENT`] payload.
+pub fn record_end(
+    conn: &Connection,
+    session: &Session,
+    reason: EndReason,
+    actor_staff_id: Option<&str>,
+) -> Result<Value, String> {
+    let payload = json!({
+        "sessionId": session.id,
+        "reason": reason.as_str(),
+        "snapshots": session.snapshots,
+        "endedAt": Utc::now().to_rfc3339(),
+    });
+    record(
+        conn,
+        "end",
+        Some(&session.id),
+        true,
+        &format!(
+            "Support session {} after {} snapshot(s)",
+            reason.as_str(),
+            session.snapshots
+        ),
+        actor_staff_id,
+        payload.clone(),
+    )?;
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_roll_per_snapshot_and_fail_when_reused_or_expired() {
+        let now = Utc::now();
+        assert_eq!(consume(None, now).unwrap_err(), TokenError::Missing);
+
+        let (session, first, _) = start(Some("admin-1"), Duration::minutes(30), now).unwrap();
+        let (counted, second) = consume(Some(&first), now).unwrap();
+        assert_eq!(counted.snapshots, 1);
+        assert_ne!(first, second);
+        assert_eq!(consume(Some(&first), now).unwrap_err(), TokenError::Invalid);
+
+        let (_, third, replaced) = start(None, Duration::minutes(30), now).unwrap();
+        assert_eq!(replaced.map(|old| old.id), Some(session.id));
+        assert_eq!(
+            consume(Some(&second), now).unwrap_err(),
+            TokenError::Invalid
+        );
+
+        let later = now + Duration::minutes(31);
+        assert!(matches!(
+            consume(Some(&third), later),
+            Err(TokenError::Expired(_))
+        ));
+        assert!(current().is_none());
+    }
+
+    #[test]
+    fn duration_defaults_to_thirty_minutes_and_is_clamped() {
+        assert_eq!(duration_from_payload(None), Duration::minutes(30));
+        assert_eq!(
+            duration_from_payload(Some(&json!({ "durationMinutes": 600 }))),
+            Duration::minutes(MAX_DURATION_MINS)
+        );
+        assert_eq!(
+            duration_from_payload(Some(&json!({ "duration_minutes": 0 }))),
+            Duration::minutes(1)
+        );
+    }
+}
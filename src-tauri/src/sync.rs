@@ -56,7 +56,7 @@ use rusqlite::{params, Connection, OptionalExtension};
 use serde_json::{Map, Value};
 use std::collections::{HashMap, HashSet};
 use std::future::Future;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Emitter};
@@ -71,8 +71,11 @@ use crate::business_day;
 use crate::can_transition_locally;
 use crate::db;
 use crate::db::DbState;
-use crate::money::Cents;
+use crate::error::PosError;
+use crate::event_batcher;
+use crate::money::{self, Cents, RoundingRule};
 use crate::normalize_status_for_storage;
+use crate::notify::{self, NotificationKind};
 use crate::order_ownership;
 use crate::payments;
 use crate::print;
@@ -89,6 +92,8 @@ use crate::terminal_helpers::{
 };
 use crate::APP_START_EPOCH;
 
+pub mod order_schema;
+
 const ADMIN_API_CACHE_PREFIX: &str = "admin_api_get::";
 const POS_INTEGRATIONS_PATH: &str = "/api/pos/integrations";
 
@@ -120,6 +125,34 @@ pub(crate) struct OrderDirectSyncResponse {
     pub error: Option<String>,
 }
 
+/// Individual result in a batched push response (`/api/pos/orders/sync`,
+/// `/api/pos/payments/sync`). Matched back to queue rows by
+/// `idempotency_key`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PushBatchResultItem {
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    #[serde(default)]
+    pub success: Option<bool>,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub server_id: Option<String>,
+}
+
+/// Response from a batched push endpoint.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct PushBatchSyncResponse {
+    #[serde(default)]
+    pub results: Vec<PushBatchResultItem>,
+}
+
 /// Individual result item in a financial batch sync response.
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
@@ -202,6 +235,11 @@ pub(crate) struct ZReportSyncResponse {
 
 const CLOSEOUT_SYNC_DRAIN_MAX_PASSES: usize = 4;
 const STALE_INFLIGHT_SYNC_LEASE_SECS: i64 = 120;
+/// Appended to `last_error` when a row whose claim lease expired goes back
+/// to the queue.
+const STALE_RECOVERY_NOTE: &str = "Recovered stale in_progress claim after lease expiry";
+/// `local_settings` key (category `sync`) counting recovered stale claims.
+const SYNC_RECOVERED_COUNT_KEY: &str = "recovered_count";
 const CLOSEOUT_SYNC_BLOCKER_SUMMARY_LIMIT: i64 = 5;
 const ADJUSTMENT_BLOCKER_PARENT_PAYMENT_NOT_SYNCED: &str = "parent_payment_not_synced";
 const ADJUSTMENT_BLOCKER_PARENT_PAYMENT_MISSING_CANONICAL_REMOTE_ID: &str =
@@ -242,6 +280,30 @@ pub struct SyncBlockerDetail {
     pub payment_updated_at: Option<String>,
 }
 
+/// Push-side counters for one sync pass, reported in `sync_complete`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncPushStats {
+    pub batches_sent: usize,
+    pub items_synced: usize,
+    pub items_failed: usize,
+}
+
+impl SyncPushStats {
+    fn absorb(&mut self, other: SyncPushStats) {
+        self.batches_sent += other.batches_sent;
+        self.items_synced += other.items_synced;
+        self.items_failed += other.items_failed;
+    }
+}
+
+/// Result of one `run_sync_cycle`: total progress (pulls, repairs and
+/// pushes) plus the push counters.
+#[derive(Debug, Clone, Copy, Default)]
+struct SyncCycleOutcome {
+    progress: usize,
+    push: SyncPushStats,
+}
+
 #[derive(Debug, Clone, Default, PartialEq)]
 pub struct UnsyncedSyncQueueSnapshot {
     pub count: i64,
@@ -303,30 +365,6 @@ enum AdjustmentCanonicalPaymentResolution {
 // Auth failure detection
 // ---------------------------------------------------------------------------
 
-fn is_terminal_auth_failure(error: &str) -> bool {
-    if let Some(code) = crate::terminal_auth_failure_code(error) {
-        return matches!(
-            code.as_str(),
-            "terminal_not_found"
-                | "terminal_inactive"
-                | "invalid_terminal_api_key"
-                | "terminal_identity_mismatch"
-                | "per_terminal_auth_required"
-                | "missing_terminal_id"
-        );
-    }
-
-    let lower = error.to_lowercase();
-    lower.contains("invalid api key for terminal")
-        || lower.contains("terminal identity mismatch")
-        || lower.contains("per-terminal authentication required")
-        || lower.contains("missing terminal_id")
-        || lower.contains("api key is invalid or expired")
-        || lower.contains("terminal not authorized")
-        || lower.contains("terminal not found or inactive")
-        || lower.contains("terminal is inactive")
-}
-
 fn is_non_authoritative_terminal_lookup_miss(error: &str) -> bool {
     if crate::terminal_auth_failure_code(error).is_some() {
         return false;
@@ -363,6 +401,18 @@ pub struct SyncState {
     pub is_running: Arc<AtomicBool>,
     pub last_sync: Arc<std::sync::Mutex<Option<String>>>,
     remote_auth_pause: Arc<std::sync::Mutex<RemoteAuthPauseState>>,
+    sync_now: Arc<tokio::sync::Notify>,
+    cycles_in_flight: Arc<AtomicUsize>,
+}
+
+/// Marks a sync cycle as in flight for as long as it is held, so shutdown
+/// can wait for the HTTP round-trips to finish before exiting.
+struct SyncCycleGuard(Arc<AtomicUsize>);
+
+impl Drop for SyncCycleGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -432,6 +482,10 @@ const SYNC_BOOTSTRAP_MODE_EMPTY_DB: &str = "bootstrap_empty_db";
 #[allow(dead_code)]
 const ORDER_DIRECT_FALLBACK_QUEUE_AGE_SEC: i64 = 600;
 const SYNC_LOG_DEDUPE_COOLDOWN_SECS: i64 = 120;
+/// Queue rows claimed per sync cycle.
+const SYNC_PUSH_CLAIM_LIMIT: usize = 100;
+/// Most orders or payments sent in one batched push request.
+const SYNC_PUSH_BATCH_SIZE: usize = 50;
 pub(crate) const HISTORICAL_Z_REPORT_CONFLICT_PREFIX: &str = "historical_z_report_conflict:";
 const Z_REPORT_FINALIZED_BOUND_CONFLICT_MESSAGE: &str =
     "Finalized Z-report period bounds cannot be changed without a rebuild flow";
@@ -683,9 +737,27 @@ impl SyncState {
             is_running: Arc::new(AtomicBool::new(false)),
             last_sync: Arc::new(std::sync::Mutex::new(None)),
             remote_auth_pause: Arc::new(std::sync::Mutex::new(RemoteAuthPauseState::default())),
+            sync_now: Arc::new(tokio::sync::Notify::new()),
+            cycles_in_flight: Arc::new(AtomicUsize::new(0)),
         }
     }
 
+    /// Whether a sync cycle (background or manually triggered) is running.
+    pub fn is_cycle_in_flight(&self) -> bool {
+        self.cycles_in_flight.load(Ordering::SeqCst) > 0
+    }
+
+    fn begin_cycle(&self) -> SyncCycleGuard {
+        self.cycles_in_flight.fetch_add(1, Ordering::SeqCst);
+        SyncCycleGuard(self.cycles_in_flight.clone())
+    }
+
+    /// Wake the background sync loop so it runs a cycle immediately instead
+    /// of waiting out the rest of its interval.
+    pub fn request_sync_now(&self) {
+        self.sync_now.notify_one();
+    }
+
     pub fn remote_auth_snapshot(&self) -> RemoteAuthPauseState {
         self.remote_auth_pause
             .lock()
@@ -863,17 +935,18 @@ async fn run_sync_cycle_with_auth_guard(
     sync_state: &SyncState,
     app: &AppHandle,
     source: &str,
-) -> RemoteAuthExecutionOutcome<usize> {
+) -> RemoteAuthExecutionOutcome<SyncCycleOutcome> {
+    let _cycle = sync_state.begin_cycle();
     let mut repair_attempted = false;
 
     loop {
         match run_sync_cycle(db, app).await {
-            Ok(synced) => {
+            Ok(cycle) => {
                 sync_state.clear_remote_auth_pause();
-                return RemoteAuthExecutionOutcome::Success(synced);
+                return RemoteAuthExecutionOutcome::Success(cycle);
             }
             Err(error) => {
-                if !is_terminal_auth_failure(&error) {
+                if !PosError::from(error.as_str()).is_terminal_auth() {
                     return RemoteAuthExecutionOutcome::Failed(error);
                 }
 
@@ -928,7 +1001,7 @@ async fn send_terminal_heartbeat_with_auth_guard(
                 return RemoteAuthExecutionOutcome::Success(sent);
             }
             Err(error) => {
-                if !is_terminal_auth_failure(&error) {
+                if !PosError::from(error.as_str()).is_terminal_auth() {
                     return RemoteAuthExecutionOutcome::Failed(error);
                 }
 
@@ -977,7 +1050,7 @@ async fn send_terminal_heartbeat_with_auth_guard(
 /// Uses `local_settings` (category='orders', key='order_counter') as a
 /// persistent counter. The counter is reset to 0 when a Z-report is generated
 /// via `submit_z_report()`.
-fn next_order_number(conn: &rusqlite::Connection) -> String {
+pub(crate) fn next_order_number(conn: &rusqlite::Connection) -> String {
     let today = chrono::Local::now();
     let date_display = today.format("%d%m%Y").to_string();
 
@@ -1372,17 +1445,35 @@ pub fn create_order(db: &DbState, payload: &Value) -> Result<Value, String> {
         .get("items")
         .map(|v| serde_json::to_string(v).unwrap_or_else(|_| "[]".to_string()))
         .unwrap_or_else(|| "[]".to_string());
+    // Totals sent by the renderer win; otherwise derive them from the item
+    // lines, rounded per line and summed in cents.
+    let items_total = payload
+        .get("items")
+        .and_then(Value::as_array)
+        .map(|lines| money::items_total_cents(lines, RoundingRule::from_settings(&conn)));
+    let tax_amount = num_field(payload, "taxAmount")
+        .or_else(|| num_field(payload, "tax_amount"))
+        .unwrap_or(0.0);
+    let charges = crate::platform_fees::OrderCharges::from_payload(payload);
+    let service_charge = charges.unwrap_or_default().service_charge_cents();
     let total_amount = num_field(payload, "totalAmount")
         .or_else(|| num_field(payload, "total_amount"))
+        .or_else(|| {
+            items_total.map(|total| {
+                (total + Cents::round_half_even(tax_amount) + service_charge).to_f64_dp2()
+            })
+        })
         .unwrap_or(0.0);
-    let tax_amount = num_field(payload, "taxAmount")
-        .or_else(|| num_field(payload, "tax_amount"))
+    let subtotal = num_field(payload, "subtotal")
+        .or_else(|| items_total.map(Cents::to_f64_dp2))
         .unwrap_or(0.0);
-    let subtotal = num_field(payload, "subtotal").unwrap_or(0.0);
     let status = str_field(payload, "status").unwrap_or_else(|| "pending".to_string());
     let order_type = str_field(payload, "orderType")
         .or_else(|| str_field(payload, "order_type"))
         .unwrap_or_else(|| "dine-in".to_string());
+    let source = str_field(payload, "source")
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
     let table_number =
         str_field(payload, "tableNumber").or_else(|| str_field(payload, "table_number"));
     let table_id = str_field(payload, "tableId").or_else(|| str_field(payload, "table_id"));
@@ -1429,6 +1520,14 @@ pub fn create_order(db: &DbState, payload: &Value) -> Result<Value, String> {
     if let Some(ref v) = special_instructions {
         validate_string_length("special_instructions", v, 2000)?;
     }
+    // Structured kitchen notes and allergy flags; the flags were checked
+    // against the allergen list by the order schema.
+    let kitchen_notes =
+        str_field(payload, "kitchenNotes").or_else(|| str_field(payload, "kitchen_notes"));
+    if let Some(ref v) = kitchen_notes {
+        validate_string_length("kitchen_notes", v, 2000)?;
+    }
+    let allergy_flags = crate::allergens::flags(payload);
     if let Some(ref v) = delivery_address {
         validate_string_length("delivery_address", v, 500)?;
     }
@@ -1484,6 +1583,28 @@ pub fn create_order(db: &DbState, payload: &Value) -> Result<Value, String> {
     let discount_amount = num_field(payload, "discountAmount")
         .or_else(|| num_field(payload, "discount_amount"))
         .unwrap_or(0.0);
+    // The configured service charge only applies when the payload carries
+    // no service charge of its own (delivery platforms send theirs). It is
+    // added on top of the renderer's totals, with its tax when taxable.
+    let auto_service_charge = match (charges.is_none(), items_total) {
+        (true, Some(items_total)) => {
+            crate::service_charge::AppliedCharge::for_new_order(&conn, &order_type).map(|charge| {
+                let amount = charge.amount(items_total, Cents::round_half_even(discount_amount));
+                (charge, amount)
+            })
+        }
+        _ => None,
+    };
+    let (total_amount, tax_amount) = match auto_service_charge {
+        Some((charge, amount)) => {
+            let effect = crate::service_charge::total_effect(&conn, amount, charge.taxable);
+            (
+                (Cents::round_half_even(total_amount) + effect).to_f64_dp2(),
+                (Cents::round_half_even(tax_amount) + effect - amount).to_f64_dp2(),
+            )
+        }
+        None => (total_amount, tax_amount),
+    };
     let tip_amount = num_field(payload, "tipAmount")
         .or_else(|| num_field(payload, "tip_amount"))
         .unwrap_or(0.0);
@@ -1492,6 +1613,8 @@ pub fn create_order(db: &DbState, payload: &Value) -> Result<Value, String> {
         .or_else(|| num_field(payload, "delivery_fee"))
         .unwrap_or(0.0);
     let plugin = str_field(payload, "plugin");
+    let external_plugin_order_id = str_field(payload, "externalPluginOrderId")
+        .or_else(|| str_field(payload, "external_plugin_order_id"));
     let is_ghost = payload
         .get("is_ghost")
         .or_else(|| payload.get("isGhost"))
@@ -1553,6 +1676,13 @@ pub fn create_order(db: &DbState, payload: &Value) -> Result<Value, String> {
         requested_staff_shift_id.as_deref(),
         requested_staff_id.as_deref(),
     )?;
+    // The shift stays with the cashier drawer, but the order is attributed to
+    // whoever is signed in on the terminal when it was rung up. Driver-owned
+    // delivery orders keep the driver as owner.
+    let resolved_staff_id = match str_field(payload, "sessionStaffId") {
+        Some(session_staff_id) if driver_id.is_none() => Some(session_staff_id),
+        _ => resolved_staff_id,
+    };
     let (owner_terminal_id, source_terminal_id) = current_order_terminal_scope_for_insert(&conn);
 
     // Wrap order + sync_queue inserts in a transaction to prevent
@@ -1579,7 +1709,8 @@ pub fn create_order(db: &DbState, payload: &Value) -> Result<Value, String> {
             source_terminal_id, branch_id, organization_id, plugin, tax_rate,
             delivery_fee, client_request_id, is_ghost, ghost_source, ghost_metadata,
             delivery_address_id, delivery_latitude, delivery_longitude,
-            delivery_address_fingerprint, delivery_zone_id, receipt_number
+            delivery_address_fingerprint, delivery_zone_id, receipt_number, source,
+            external_plugin_order_id
         ) VALUES (
             ?1, ?2, ?3, ?4, ?5, ?6, ?7,
             ?8, ?9, ?10, ?11, ?12,
@@ -1591,7 +1722,8 @@ pub fn create_order(db: &DbState, payload: &Value) -> Result<Value, String> {
             ?34, ?35, 1, ?36, ?37,
             ?38, ?39, ?40, ?41, ?42,
             ?43, ?44, ?45, ?46, ?47,
-            ?48, ?49, ?50, ?51, ?52, ?53
+            ?48, ?49, ?50, ?51, ?52, ?53, ?54,
+            ?55
         )",
         params![
             &order_id,
@@ -1647,12 +1779,50 @@ pub fn create_order(db: &DbState, payload: &Value) -> Result<Value, String> {
             &delivery_address_fingerprint,
             &delivery_zone_id,
             &receipt_number,
+            &source,
+            &external_plugin_order_id,
         ],
     )
     .map_err(|e| {
         let _ = conn.execute_batch("ROLLBACK");
         format!("insert order: {e}")
     })?;
+    if let Some(charges) = charges.as_ref() {
+        charges.store(&conn, &order_id).inspect_err(|_| {
+            let _ = conn.execute_batch("ROLLBACK");
+        })?;
+    }
+    if let Some((charge, amount)) = auto_service_charge {
+        charge.store(&conn, &order_id, amount).inspect_err(|_| {
+            let _ = conn.execute_batch("ROLLBACK");
+        })?;
+    }
+    let item_lines = payload
+        .get("items")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let captured_tax = crate::tax::capture_order_breakdown(&conn, &order_id, &item_lines)
+        .inspect_err(|_| {
+            let _ = conn.execute_batch("ROLLBACK");
+        })?;
+    let totals_derived = auto_service_charge.is_some()
+        || captured_tax.total != Cents::round_half_even(total_amount)
+        || captured_tax.tax != Cents::round_half_even(tax_amount);
+    let total_amount = captured_tax.total.to_f64_dp2();
+    let tax_amount = captured_tax.tax.to_f64_dp2();
+    if kitchen_notes.is_some() || !allergy_flags.is_empty() {
+        let allergy_flags_json =
+            (!allergy_flags.is_empty()).then(|| serde_json::json!(allergy_flags).to_string());
+        conn.execute(
+            "UPDATE orders SET kitchen_notes = ?1, allergy_flags = ?2 WHERE id = ?3",
+            params![kitchen_notes, allergy_flags_json, order_id],
+        )
+        .map_err(|e| {
+            let _ = conn.execute_batch("ROLLBACK");
+            format!("store kitchen notes: {e}")
+        })?;
+    }
 
     if let Some(initial_payment_payload) = initial_payment_payload.clone() {
         let mut enriched_initial_payment = initial_payment_payload;
@@ -1694,6 +1864,20 @@ pub fn create_order(db: &DbState, payload: &Value) -> Result<Value, String> {
     if let Value::Object(obj) = &mut sync_data {
         obj.remove("initialPayment");
         obj.remove("initial_payment");
+        if let Some(charges) = charges.as_ref() {
+            charges.write_sync_fields(obj);
+        }
+        if let Some((charge, amount)) = auto_service_charge {
+            charge.write_sync_fields(obj, amount);
+        }
+        if totals_derived {
+            for key in ["totalAmount", "total_amount"] {
+                obj.insert(key.to_string(), serde_json::json!(total_amount));
+            }
+            for key in ["taxAmount", "tax_amount"] {
+                obj.insert(key.to_string(), serde_json::json!(tax_amount));
+            }
+        }
         obj.entry("orderId".to_string())
             .or_insert_with(|| Value::String(order_id.clone()));
         if !terminal_id.trim().is_empty() {
@@ -1767,6 +1951,15 @@ pub fn create_order(db: &DbState, payload: &Value) -> Result<Value, String> {
             obj.insert("guestCount".to_string(), Value::from(value));
             obj.insert("guest_count".to_string(), Value::from(value));
         }
+        if let Some(value) = kitchen_notes.as_ref() {
+            obj.insert("kitchenNotes".to_string(), Value::String(value.clone()));
+            obj.insert("kitchen_notes".to_string(), Value::String(value.clone()));
+        }
+        obj.insert("allergyFlags".to_string(), serde_json::json!(allergy_flags));
+        obj.insert(
+            "allergy_flags".to_string(),
+            serde_json::json!(allergy_flags),
+        );
         // Ensure the Rust-generated order number is synced to admin
         if let Some(ref num) = order_number {
             obj.insert("orderNumber".to_string(), Value::String(num.clone()));
@@ -2125,8 +2318,11 @@ pub(crate) fn remote_order_visible_to_current_terminal(
 
 /// Get all orders, most recent first.
 pub fn get_all_orders(db: &DbState) -> Result<Vec<Value>, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let visibility_scope = load_order_terminal_visibility_scope(&conn);
+    db.read(query_all_orders)
+}
+
+fn query_all_orders(conn: &Connection) -> Result<Vec<Value>, String> {
+    let visibility_scope = load_order_terminal_visibility_scope(conn);
     // W6: `orders.payment_method` was dropped in v55. The SELECT keeps
     // the same column ordering (`paymentMethod` stays at index 25)
     // by substituting a derive subquery that matches
@@ -2166,7 +2362,8 @@ pub fn get_all_orders(db: &DbState) -> Result<Vec<Value>, String> {
                         FROM order_payments op
                         WHERE op.order_id = orders.id
                           AND op.status = 'completed'
-                    ), 0)
+                    ), 0),
+                    kitchen_notes, allergy_flags
              FROM orders
              WHERE COALESCE(is_ghost, 0) = 0
              ORDER BY created_at ASC",
@@ -2192,6 +2389,8 @@ pub fn get_all_orders(db: &DbState) -> Result<Vec<Value>, String> {
                 })
                 .unwrap_or(Value::Null);
             let is_ghost = row.get::<_, Option<i64>>(42)?.unwrap_or(0) != 0;
+            let allergy_flags =
+                crate::allergens::parse_stored(row.get::<_, Option<String>>(63)?.as_deref());
 
             Ok(serde_json::json!({
                 "id": row.get::<_, Option<String>>(0)?,
@@ -2283,6 +2482,10 @@ pub fn get_all_orders(db: &DbState) -> Result<Vec<Value>, String> {
                 "guest_count": row.get::<_, Option<i64>>(60)?,
                 "paidTotal": row.get::<_, f64>(61)?,
                 "paid_total": row.get::<_, f64>(61)?,
+                "kitchenNotes": row.get::<_, Option<String>>(62)?,
+                "kitchen_notes": row.get::<_, Option<String>>(62)?,
+                "allergyFlags": allergy_flags,
+                "allergy_flags": allergy_flags,
             }))
         })
         .map_err(|e| e.to_string())?;
@@ -2322,8 +2525,10 @@ pub fn get_all_orders(db: &DbState) -> Result<Vec<Value>, String> {
 
 /// Get a single order by ID.
 pub fn get_order_by_id(db: &DbState, id: &str) -> Result<Value, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    db.read(|conn| query_order_by_id(conn, id))
+}
 
+fn query_order_by_id(conn: &Connection, id: &str) -> Result<Value, String> {
     // W6: `orders.payment_method` was dropped in v55. Derive subquery
     // slotted in at the same position so downstream row indices stay
     // aligned with `get_all_orders`. See that function for semantic
@@ -2360,7 +2565,8 @@ pub fn get_order_by_id(db: &DbState, id: &str) -> Result<Value, String> {
                     FROM order_payments op
                     WHERE op.order_id = orders.id
                       AND op.status = 'completed'
-                ), 0)
+                ), 0),
+                kitchen_notes, allergy_flags
         FROM orders WHERE id = ?1",
         params![id],
         |row| {
@@ -2381,6 +2587,9 @@ pub fn get_order_by_id(db: &DbState, id: &str) -> Result<Value, String> {
                 .unwrap_or(Value::Null);
             let is_ghost = row.get::<_, Option<i64>>(42)?.unwrap_or(0) != 0;
             let ghost_source: Option<String> = row.get(43)?;
+            let allergy_flags = crate::allergens::parse_stored(
+                row.get::<_, Option<String>>(61)?.as_deref(),
+            );
 
             Ok(serde_json::json!({
                 "id": row.get::<_, Option<String>>(0)?,
@@ -2467,6 +2676,10 @@ pub fn get_order_by_id(db: &DbState, id: &str) -> Result<Value, String> {
                 "guest_count": row.get::<_, Option<i64>>(58)?,
                 "paidTotal": row.get::<_, f64>(59)?,
                 "paid_total": row.get::<_, f64>(59)?,
+                "kitchenNotes": row.get::<_, Option<String>>(60)?,
+                "kitchen_notes": row.get::<_, Option<String>>(60)?,
+                "allergyFlags": allergy_flags,
+                "allergy_flags": allergy_flags,
             }))
         },
     );
@@ -2732,11 +2945,15 @@ pub fn get_sync_status(db: &DbState, sync_state: &SyncState) -> Result<Value, St
     let financial_stats = collect_financial_sync_stats(&conn);
     let last_queue_failure = extract_last_queue_failure_snapshot(&conn).map(|s| s.to_json());
     let historical_z_report_conflicts = count_historical_z_report_conflicts(&conn);
+    let recovered_stale_items = recovered_stale_count(&conn);
+    let pending_order_items = count_sync_queue_rows(&conn, &["order"], PENDING_SYNC_STATES);
+    let failed_order_items = count_sync_queue_rows(&conn, &["order"], FAILED_SYNC_STATES);
 
     let is_online = storage::is_configured();
     let last_sync = sync_state.last_sync.lock().ok().and_then(|g| g.clone());
     let pending_total = pending + queued_remote;
     let remote_auth_pause = sync_state.remote_auth_snapshot();
+    let remediation = sync_status_remediation(&remote_auth_pause);
     let mut payload = serde_json::json!({
         "isOnline": is_online,
         "lastSync": last_sync,
@@ -2755,12 +2972,21 @@ pub fn get_sync_status(db: &DbState, sync_state: &SyncState) -> Result<Value, St
         "oldestNextRetryAt": oldest_next_retry_at,
         "lastQueueFailure": last_queue_failure,
         "historicalZReportConflicts": historical_z_report_conflicts,
+        "recoveredStaleItems": recovered_stale_items,
+        "pendingOrderItems": pending_order_items,
+        "failedOrderItems": failed_order_items,
         "pendingPaymentItems": financial_stats.pending_payment_items(),
         "failedPaymentItems": financial_stats.failed_payment_items(),
         "financialStats": financial_stats.to_json(),
     });
 
     if let Some(map) = payload.as_object_mut() {
+        map.insert("remediation".to_string(), remediation);
+        crate::sync_backlog::insert_status_fields(&conn, map);
+        map.insert(
+            "adminApiCircuit".to_string(),
+            serde_json::to_value(api::admin_breaker_snapshot()).unwrap_or(Value::Null),
+        );
         map.insert(
             "remoteAuthPaused".to_string(),
             Value::Bool(remote_auth_pause.remote_auth_paused),
@@ -2814,6 +3040,11 @@ pub fn get_financial_stats(db: &DbState) -> Result<Value, String> {
 
 /// Quick network check: HEAD request to admin URL.
 pub async fn check_network_status() -> Value {
+    check_network_status_with_timeout(Duration::from_secs(10)).await
+}
+
+/// Probe the admin `/api/health` endpoint with an explicit request timeout.
+pub(crate) async fn check_network_status_with_timeout(timeout: Duration) -> Value {
     let admin_url = match storage::get_credential("admin_dashboard_url") {
         Some(url) => url,
         None => return serde_json::json!({ "isOnline": false }),
@@ -2829,18 +3060,16 @@ pub async fn check_network_status() -> Value {
     // Switched HEAD -> GET so this probe matches the path used by
     // `api::test_connectivity`. Some upstream proxies and Next.js auto-HEAD
     // shims occasionally hiccup on HEAD even when GET is healthy, which
-    // showed up as a flickering "Disconnected" badge. Timeout bumped from
-    // 5s -> 10s to absorb cold-start / GC pauses without flipping state.
-    let client = match reqwest::Client::builder()
-        .timeout(Duration::from_secs(10))
-        .build()
-    {
+    // showed up as a flickering "Disconnected" badge. Default timeout bumped
+    // from 5s -> 10s to absorb cold-start / GC pauses without flipping state.
+    let client = match api::shared_client() {
         Ok(c) => c,
         Err(_) => return serde_json::json!({ "isOnline": false }),
     };
 
     match client
         .get(&health_url)
+        .timeout(timeout)
         .header("X-POS-API-Key", api_key.as_str())
         .send()
         .await
@@ -3113,9 +3342,30 @@ fn cleanup_stale_syncing_adjustments(db: &DbState) -> Result<usize, String> {
     Ok(updated)
 }
 
+/// Hand every `in_progress` row back to the queue during a clean shutdown.
+/// Unlike [`requeue_stale_in_progress_sync_rows`] this ignores the lease and
+/// does not burn a retry: the row was interrupted by us, not by a crash.
+pub(crate) fn release_in_progress_sync_rows(db: &DbState) -> Result<usize, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE sync_queue
+         SET status = 'pending',
+             next_retry_at = NULL,
+             lease_expires_at = NULL,
+             updated_at = datetime('now')
+         WHERE status = 'in_progress'",
+        [],
+    )
+    .map_err(|e| format!("release in-progress sync rows: {e}"))
+}
+
+/// Requeue `in_progress` rows whose claim lease has expired. Rows claimed
+/// before leases existed have no `lease_expires_at` and fall back to
+/// `updated_at` plus the lease window. Each recovered row gets a note in
+/// `last_error` and bumps the `recovered_count` metric.
 fn requeue_stale_in_progress_sync_rows(db: &DbState) -> Result<usize, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let lease_modifier = format!("-{} seconds", STALE_INFLIGHT_SYNC_LEASE_SECS);
+    let lease_modifier = format!("+{} seconds", STALE_INFLIGHT_SYNC_LEASE_SECS);
 
     let stale_rows: Vec<(i64, String, String)> = {
         let mut stmt = conn
@@ -3123,8 +3373,10 @@ fn requeue_stale_in_progress_sync_rows(db: &DbState) -> Result<usize, String> {
                 "SELECT id, entity_type, entity_id
                  FROM sync_queue
                  WHERE status = 'in_progress'
-                   AND julianday(COALESCE(updated_at, created_at))
-                       <= julianday('now', ?1)",
+                   AND julianday(COALESCE(
+                           lease_expires_at,
+                           datetime(COALESCE(updated_at, created_at), ?1)
+                       )) <= julianday('now')",
             )
             .map_err(|e| format!("prepare stale in-progress selector: {e}"))?;
 
@@ -3170,16 +3422,20 @@ fn requeue_stale_in_progress_sync_rows(db: &DbState) -> Result<usize, String> {
                  last_error = CASE
                      WHEN retry_count + 1 >= max_retries
                          THEN 'Stale in-progress row exhausted max retries after lease expiry'
-                     ELSE last_error
+                     ELSE COALESCE(NULLIF(last_error, '') || ' | ', '') || ?2
                  END,
                  next_retry_at = NULL,
+                 lease_expires_at = NULL,
                  updated_at = datetime('now')
              WHERE status = 'in_progress'
-               AND julianday(COALESCE(updated_at, created_at))
-                   <= julianday('now', ?1)",
-            params![lease_modifier.as_str()],
+               AND julianday(COALESCE(
+                       lease_expires_at,
+                       datetime(COALESCE(updated_at, created_at), ?1)
+                   )) <= julianday('now')",
+            params![lease_modifier.as_str(), STALE_RECOVERY_NOTE],
         )
         .map_err(|e| format!("requeue stale in-progress rows: {e}"))?;
+    bump_recovered_stale_count(&conn, requeued)?;
 
     for (queue_id, entity_type, entity_id) in stale_rows.iter() {
         let recovered_row = conn
@@ -3262,6 +3518,59 @@ fn requeue_stale_in_progress_sync_rows(db: &DbState) -> Result<usize, String> {
     Ok(requeued)
 }
 
+/// Add `recovered` to the persisted stale-claim recovery counter and return
+/// the new total.
+fn bump_recovered_stale_count(conn: &Connection, recovered: usize) -> Result<i64, String> {
+    if recovered > 0 {
+        conn.execute(
+            "INSERT INTO local_settings (setting_category, setting_key, setting_value, updated_at)
+             VALUES ('sync', ?1, ?2, datetime('now'))
+             ON CONFLICT(setting_category, setting_key) DO UPDATE SET
+                setting_value = CAST(CAST(setting_value AS INTEGER) + ?2 AS TEXT),
+                updated_at = excluded.updated_at",
+            params![SYNC_RECOVERED_COUNT_KEY, recovered as i64],
+        )
+        .map_err(|e| format!("bump sync recovered_count: {e}"))?;
+    }
+    Ok(recovered_stale_count(conn))
+}
+
+fn recovered_stale_count(conn: &Connection) -> i64 {
+    db::get_setting(conn, "sync", SYNC_RECOVERED_COUNT_KEY)
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .unwrap_or(0)
+}
+
+/// Startup pass: requeue rows a previous run left `in_progress` past their
+/// lease (typically a crash between claim and completion) and tell the UI
+/// how many came back.
+fn recover_stale_sync_items_on_startup(db: &DbState, app: &AppHandle) {
+    match requeue_stale_in_progress_sync_rows(db) {
+        Ok(0) => {}
+        Ok(count) => {
+            let recovered_total = db
+                .conn
+                .lock()
+                .map(|conn| recovered_stale_count(&conn))
+                .unwrap_or(0);
+            info!(
+                count,
+                recovered_total, "Recovered stale in-progress sync rows on startup"
+            );
+            let _ = app.emit(
+                "sync_recovered_stale_items",
+                serde_json::json!({
+                    "count": count,
+                    "recoveredTotal": recovered_total,
+                }),
+            );
+        }
+        Err(error) => {
+            warn!(error = %error, "Startup sync recovery failed to requeue stale in-progress rows");
+        }
+    }
+}
+
 fn has_actionable_remote_sync_work(db: &DbState) -> Result<bool, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let actionable: Option<i64> = conn
@@ -3326,7 +3635,7 @@ fn resolve_heartbeat_platform() -> Option<&'static str> {
     }
 }
 
-fn compute_uptime_seconds() -> u64 {
+pub(crate) fn compute_uptime_seconds() -> u64 {
     let started_at = APP_START_EPOCH.load(Ordering::Relaxed);
     if started_at == 0 {
         return 0;
@@ -3581,8 +3890,11 @@ pub fn start_sync_loop(
     // Mark as running
     is_running.store(true, Ordering::SeqCst);
 
+    let sync_now = sync_state.sync_now.clone();
+
     tauri::async_runtime::spawn(async move {
         info!("Sync loop started (interval: {interval_secs}s)");
+        recover_stale_sync_items_on_startup(&db, &app);
         let mut previous_network_online: Option<bool> = None;
         // Hysteresis: a single failed probe shouldn't flip the UI badge to
         // offline. Only flip after `OFFLINE_FLIP_THRESHOLD` consecutive
@@ -3593,6 +3905,8 @@ pub fn start_sync_loop(
         // crosses the warning threshold, then keep emitting the renderer
         // event every tick while it stays there.
         let mut parity_capacity_warning_active = false;
+        // Same edge tracking for old unsent financial rows.
+        let mut backlog_warning_active = false;
 
         loop {
             if cancel.is_cancelled() || !is_running.load(Ordering::SeqCst) {
@@ -3602,6 +3916,9 @@ pub fn start_sync_loop(
 
             tokio::select! {
                 _ = tokio::time::sleep(Duration::from_secs(interval_secs)) => {}
+                _ = sync_now.notified() => {
+                    debug!("Sync loop woken early");
+                }
                 _ = cancel.cancelled() => {
                     info!("Sync loop cancelled");
                     break;
@@ -3612,8 +3929,8 @@ pub fn start_sync_loop(
                 break;
             }
 
-            // Emit network status every cycle so renderer indicators can
-            // stay event-driven without command polling.
+            // `network_status` events are owned by the connectivity watchdog
+            // (emitted on transitions only); this probe just gates the cycle.
             let network_status = check_network_status().await;
             let raw_probe_online = network_status
                 .get("isOnline")
@@ -3634,18 +3951,13 @@ pub fn start_sync_loop(
             } else {
                 consecutive_offline_probes < OFFLINE_FLIP_THRESHOLD
             };
-            let network_status_for_ui = if raw_probe_online == network_is_online {
-                network_status
-            } else {
-                serde_json::json!({ "isOnline": network_is_online })
-            };
-            let _ = app.emit("network_status", &network_status_for_ui);
 
             // Parity-queue capacity early warning. Runs on every tick --
             // including offline and auth-paused ticks, which is exactly when
             // the backlog grows -- so staff see "sync backlog growing" long
             // before the enqueue cap fail-closes domain writes at checkout.
             emit_parity_queue_capacity_warning(&db, &app, &mut parity_capacity_warning_active);
+            emit_sync_backlog_warning(&db, &app, &mut backlog_warning_active);
 
             // If terminal is not configured yet, still emit sync status so
             // UI state remains consistent.
@@ -3664,6 +3976,30 @@ pub fn start_sync_loop(
                 continue;
             }
 
+            // A terminal re-paired to another organization must not send the
+            // previous organization's data until the change is confirmed.
+            let organization_blocked = db
+                .conn
+                .lock()
+                .map(|conn| crate::organization_change::ensure_sync_allowed(&conn).is_err())
+                .unwrap_or(false);
+            if organization_blocked {
+                let status = get_sync_status_for_event(&db, sync_state.as_ref(), network_is_online);
+                let _ = app.emit("sync_status", &status);
+                let _ = app.emit("sync-status-changed", &status);
+                continue;
+            }
+
+            // Admin requests are simulated in training mode, so a cycle now
+            // would mark live rows synced without sending them. Hold the live
+            // queue until training ends.
+            if crate::training::is_active() {
+                let status = get_sync_status_for_event(&db, sync_state.as_ref(), network_is_online);
+                let _ = app.emit("sync_status", &status);
+                let _ = app.emit("sync-status-changed", &status);
+                continue;
+            }
+
             let recovery_summary = run_recurring_sync_recovery(&db);
             let actionable_remote_work = match has_actionable_remote_sync_work(&db) {
                 Ok(has_work) => has_work,
@@ -3701,7 +4037,8 @@ pub fn start_sync_loop(
 
             match run_sync_cycle_with_auth_guard(&db, sync_state.as_ref(), &app, "sync_loop").await
             {
-                RemoteAuthExecutionOutcome::Success(synced) => {
+                RemoteAuthExecutionOutcome::Success(cycle) => {
+                    let synced = cycle.progress;
                     if synced > 0 {
                         info!("Sync cycle complete: {synced} items synced");
                     }
@@ -4280,19 +4617,32 @@ where
     Ok(state)
 }
 
+/// A forced sync on the training database pushes nothing: training orders
+/// and payments never leave the terminal.
+fn force_sync_suppressed(db: &DbState) -> Result<bool, String> {
+    db.read(|conn| Ok(crate::training::is_training_connection(conn)))
+}
+
 async fn force_sync_once(
     db: &DbState,
     sync_state: &SyncState,
     app: &AppHandle,
-) -> Result<usize, String> {
+) -> Result<SyncCycleOutcome, String> {
     if !storage::is_configured() {
         return Err("Terminal not configured".into());
     }
+    if force_sync_suppressed(db)? {
+        return Ok(SyncCycleOutcome {
+            progress: 0,
+            push: SyncPushStats::default(),
+        });
+    }
 
     let _ = run_recurring_sync_recovery(db);
 
     match run_sync_cycle_with_auth_guard(db, sync_state, app, "force_sync").await {
-        RemoteAuthExecutionOutcome::Success(synced) => {
+        RemoteAuthExecutionOutcome::Success(cycle) => {
+            let synced = cycle.progress;
             let parity_synced = force_parity_sync_once(db, app).await?;
             let total_synced = synced + parity_synced;
             info!(
@@ -4304,7 +4654,10 @@ async fn force_sync_once(
                 *guard = Some(Utc::now().to_rfc3339());
             }
 
-            Ok(total_synced)
+            Ok(SyncCycleOutcome {
+                progress: total_synced,
+                push: cycle.push,
+            })
         }
         RemoteAuthExecutionOutcome::Paused(error) => Err(error),
         RemoteAuthExecutionOutcome::Reset(error) => Err(error),
@@ -4325,6 +4678,16 @@ async fn force_parity_sync_once(db: &DbState, app: &AppHandle) -> Result<usize,
     let result = sync_queue::process_queue(&db.conn, admin_url.as_str(), api_key.as_str()).await?;
     for dead_letter in &result.monetary_dead_letters {
         let _ = app.emit("sync:dead-letter:monetary", dead_letter);
+        notify::send(
+            app,
+            db,
+            NotificationKind::SyncFailure,
+            "Sync failed",
+            &format!(
+                "A {} could not be synced and needs attention: {}",
+                dead_letter.entity_type, dead_letter.error_message
+            ),
+        );
     }
 
     if result.failed > 0 || result.conflicts > 0 {
@@ -4389,14 +4752,58 @@ fn emit_parity_queue_capacity_warning(db: &DbState, app: &AppHandle, warning_act
     }
 }
 
-/// Trigger an immediate sync cycle (called by `sync_force`).
+/// Emit `sync_backlog_warning` while the oldest unsent financial row is
+/// older than `sync.backlog_warning_minutes`, and a single `null` when it
+/// clears.
+fn emit_sync_backlog_warning(db: &DbState, app: &AppHandle, warning_active: &mut bool) {
+    let warning = {
+        let conn = match db.conn.lock() {
+            Ok(conn) => conn,
+            Err(_) => return,
+        };
+        match crate::sync_backlog::cached_entity_backlog(&conn) {
+            Ok(entities) => crate::sync_backlog::financial_warning(
+                &entities,
+                crate::sync_backlog::warning_minutes(&conn),
+            ),
+            Err(error) => {
+                warn!(error = %error, "Failed to inspect sync backlog age");
+                return;
+            }
+        }
+    };
+
+    match warning {
+        Some(warning) => {
+            if !*warning_active {
+                warn!(
+                    entity = warning["entity"].as_str().unwrap_or_default(),
+                    age_seconds = warning["ageSeconds"].as_i64().unwrap_or_default(),
+                    "Financial sync backlog older than the warning threshold"
+                );
+            }
+            *warning_active = true;
+            let _ = app.emit(crate::sync_backlog::WARNING_EVENT, &warning);
+        }
+        None => {
+            if *warning_active {
+                info!("Financial sync backlog back below the age warning threshold");
+                let _ = app.emit(crate::sync_backlog::WARNING_EVENT, Value::Null);
+            }
+            *warning_active = false;
+        }
+    }
+}
+
+/// Trigger an immediate sync cycle (called by `sync_force`). Returns the
+/// push counters for the `sync_complete` event.
 pub async fn force_sync(
     db: &DbState,
     sync_state: &SyncState,
     app: &AppHandle,
-) -> Result<(), String> {
-    let _ = force_sync_once(db, sync_state, app).await?;
-    Ok(())
+) -> Result<SyncPushStats, String> {
+    let cycle = force_sync_once(db, sync_state, app).await?;
+    Ok(cycle.push)
 }
 
 pub async fn force_sync_until_closeout_stable(
@@ -4406,7 +4813,11 @@ pub async fn force_sync_until_closeout_stable(
 ) -> Result<CloseoutSyncDrainState, String> {
     let state = drain_sync_until_closeout_stable(
         CLOSEOUT_SYNC_DRAIN_MAX_PASSES,
-        || force_sync_once(db, sync_state, app),
+        || async {
+            force_sync_once(db, sync_state, app)
+                .await
+                .map(|cycle| cycle.progress)
+        },
         || capture_unsynced_sync_queue_snapshot(db),
     )
     .await?;
@@ -5512,6 +5923,7 @@ struct DirectOrderFallbackOutcome {
     synced_queue_ids: HashSet<i64>,
     permanent_failures: HashMap<i64, String>,
     transient_failures: HashMap<i64, String>,
+    batches_sent: usize,
 }
 
 impl DirectOrderFallbackOutcome {
@@ -5537,6 +5949,76 @@ impl DirectOrderFallbackOutcome {
         ids.extend(self.transient_failures.keys());
         ids
     }
+
+    fn push_stats(&self) -> SyncPushStats {
+        SyncPushStats {
+            batches_sent: self.batches_sent,
+            items_synced: self.synced_queue_ids.len(),
+            items_failed: self.permanent_failures.len() + self.transient_failures.len(),
+        }
+    }
+}
+
+/// How the server answered one item of a batched push.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PushBatchItemOutcome {
+    Accepted { remote_id: Option<String> },
+    Rejected(String),
+}
+
+/// Map a batched push response to per-item outcomes keyed by idempotency
+/// key. Items the response does not mention are absent from the map.
+fn map_push_batch_results(resp: &Value) -> HashMap<String, PushBatchItemOutcome> {
+    let typed: PushBatchSyncResponse = serde_json::from_value(resp.clone()).unwrap_or_default();
+    typed
+        .results
+        .into_iter()
+        .filter_map(|item| {
+            let key = item.idempotency_key?.trim().to_string();
+            if key.is_empty() {
+                return None;
+            }
+            let status = item.status.as_deref().unwrap_or("").to_ascii_lowercase();
+            let accepted = item.error.is_none()
+                && (item.success == Some(true)
+                    || matches!(
+                        status.as_str(),
+                        "ok" | "synced" | "created" | "updated" | "duplicate" | "skipped"
+                    ));
+            let outcome = if accepted {
+                PushBatchItemOutcome::Accepted {
+                    remote_id: item
+                        .id
+                        .or(item.server_id)
+                        .map(|id| id.trim().to_string())
+                        .filter(|id| !id.is_empty()),
+                }
+            } else {
+                PushBatchItemOutcome::Rejected(
+                    item.error
+                        .or(item.message)
+                        .unwrap_or_else(|| format!("rejected in batch (status: {status})")),
+                )
+            };
+            Some((key, outcome))
+        })
+        .collect()
+}
+
+/// Whether a whole batched push failed for a reason that would fail each
+/// item alone too (network, timeout, 5xx, backpressure). Anything else —
+/// a 4xx on the batch, or an admin without the batch endpoint — falls back
+/// to single-item pushes.
+fn is_retryable_batch_push_error(error: &str) -> bool {
+    let lower = error.to_lowercase();
+    is_backpressure_error(error)
+        || lower.contains("network error")
+        || lower.contains("cannot reach admin dashboard")
+        || lower.contains("timed out")
+        || lower.contains("timeout")
+        || lower.contains("server error")
+        || lower.contains("http 5")
+        || lower.contains("connection refused")
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -7069,6 +7551,41 @@ fn attach_kiosk_payment_method_to_metadata(
     Some(metadata.to_string())
 }
 
+/// Item lines of a pulled order as JSON for the `orders.items` column,
+/// passed through the lenient order schema so numeric strings from older
+/// servers are stored as numbers.
+fn remote_order_items_json(remote_order: &Value) -> Option<String> {
+    let raw = remote_order
+        .get("items")
+        .or_else(|| remote_order.get("order_items"))
+        .or_else(|| remote_order.get("orderItems"))?;
+    let mut items = match raw {
+        Value::String(text) => match serde_json::from_str::<Value>(text) {
+            Ok(parsed) => parsed,
+            Err(_) => return Some(text.clone()),
+        },
+        other => other.clone(),
+    };
+    if let Some(lines) = items.as_array_mut() {
+        if let Ok(validated) =
+            order_schema::validate_items(lines, order_schema::ValidationMode::Lenient)
+        {
+            if !validated.warnings.is_empty() {
+                warn!(
+                    remote_order_id = str_any(remote_order, &["id"]).unwrap_or_default(),
+                    fields = ?validated
+                        .warnings
+                        .iter()
+                        .map(|issue| issue.field.as_str())
+                        .collect::<Vec<_>>(),
+                    "Coerced invalid item fields in pulled order"
+                );
+            }
+        }
+    }
+    Some(serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string()))
+}
+
 fn materialize_remote_order(
     conn: &rusqlite::Connection,
     remote_order: &Value,
@@ -7109,15 +7626,7 @@ fn materialize_remote_order(
         .map(ToString::to_string);
     let local_id = client_order_id.unwrap_or_else(|| Uuid::new_v4().to_string());
 
-    let items_json = match remote_order
-        .get("items")
-        .or_else(|| remote_order.get("order_items"))
-        .or_else(|| remote_order.get("orderItems"))
-    {
-        Some(Value::String(raw)) => raw.clone(),
-        Some(value) => serde_json::to_string(value).unwrap_or_else(|_| "[]".to_string()),
-        None => "[]".to_string(),
-    };
+    let items_json = remote_order_items_json(remote_order).unwrap_or_else(|| "[]".to_string());
 
     let order_number = str_any(remote_order, &["order_number", "orderNumber"]);
     let display_order_number = str_any(
@@ -7212,7 +7721,7 @@ fn materialize_remote_order(
     // materializer preserves the inbound `payment_method` only in the
     // downstream sync replay (if any), not on the local row.
     let _ = &payment_method;
-    conn.execute(
+    let inserted = conn.execute(
         "INSERT INTO orders (
             id, order_number, display_order_number, customer_name, customer_phone, customer_email, customer_id,
             items, total_amount, tax_amount, subtotal, status,
@@ -7238,7 +7747,9 @@ fn materialize_remote_order(
             ?39, ?40, ?41, ?42, ?43,
             ?44, ?45, ?46, ?47,
             ?48, ?49, ?50, ?51
-        )",
+        )
+        ON CONFLICT(supabase_id) WHERE supabase_id IS NOT NULL AND supabase_id <> ''
+        DO NOTHING",
         params![
             local_id,
             order_number,
@@ -7294,6 +7805,20 @@ fn materialize_remote_order(
         ],
     )
     .map_err(|e| format!("materialize remote order: {e}"))?;
+    if inserted == 0 {
+        // A remote save stored the same order after the lookup above.
+        return conn
+            .query_row(
+                "SELECT id FROM orders WHERE supabase_id = ?1",
+                params![remote_id],
+                |row| row.get(0),
+            )
+            .map(Some)
+            .map_err(|e| format!("resolve concurrently stored remote order: {e}"));
+    }
+    if let Some(charges) = crate::platform_fees::OrderCharges::from_payload(remote_order) {
+        charges.store(conn, &local_id)?;
+    }
 
     Ok(Some(local_id))
 }
@@ -9623,8 +10148,11 @@ async fn reconcile_remote_orders(
                         .unwrap_or(0);
                     if deleted > 0 {
                         reconciled += 1;
-                        let _ =
-                            app.emit("order_deleted", serde_json::json!({ "orderId": local_id }));
+                        event_batcher::emit(
+                            app,
+                            "order_deleted",
+                            serde_json::json!({ "orderId": local_id }),
+                        );
                         info!(
                             remote_id = %remote_id,
                             local_id = %local_id,
@@ -9880,16 +10408,18 @@ async fn reconcile_remote_orders(
 
         for (local_id, status_event) in reconciled_order_events {
             if let Ok(order_json) = get_order_by_id(db, &local_id) {
-                let _ = app.emit("order_realtime_update", order_json);
+                event_batcher::emit(app, "order_realtime_update", order_json);
             } else {
-                let _ = app.emit(
+                event_batcher::emit(
+                    app,
                     "order_realtime_update",
                     serde_json::json!({ "orderId": local_id.clone() }),
                 );
             }
 
             if let Some(ref new_status) = status_event {
-                let _ = app.emit(
+                event_batcher::emit(
+                    app,
                     "order_status_updated",
                     serde_json::json!({
                         "orderId": local_id.clone(),
@@ -9975,10 +10505,11 @@ async fn reconcile_remote_orders(
                 if is_ghost || payment_method == "pending" {
                     skip_auto_print = true;
                 }
-                let _ = app.emit("order_created", order_json.clone());
-                let _ = app.emit("order_realtime_update", order_json);
+                event_batcher::emit(app, "order_created", order_json.clone());
+                event_batcher::emit(app, "order_realtime_update", order_json);
             } else {
-                let _ = app.emit(
+                event_batcher::emit(
+                    app,
                     "order_created",
                     serde_json::json!({ "orderId": local_id.clone() }),
                 );
@@ -10707,13 +11238,70 @@ fn attach_remote_order_identity_to_local_order(
     Ok(Some(remote_order_id))
 }
 
-fn sync_remote_order_snapshot_into_local(
-    conn: &Connection,
-    local_order_id: &str,
-    remote_order: &Value,
-    repaired_at: &str,
-) -> Result<usize, String> {
-    let remote_order_id = str_any(remote_order, &["id"]);
+/// Result of applying one pushed remote order row to the local cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RemoteOrderSnapshotOutcome {
+    /// No local row matches the remote identity; the caller should insert it.
+    Missing,
+    /// A local row exists but the snapshot was skipped (stale, outstanding
+    /// local queue, or outside the terminal scope).
+    Unchanged { local_id: String },
+    /// The local row was overwritten with the remote snapshot.
+    Updated {
+        local_id: String,
+        previous_status: Option<String>,
+        status: Option<String>,
+    },
+}
+
+/// Apply a single remote order row (e.g. from the realtime subscription)
+/// through the same guarded snapshot path the sync reconciler uses, so a
+/// pushed update can never clobber outstanding local edits.
+pub(crate) fn apply_remote_order_snapshot(
+    db: &DbState,
+    remote_order: &Value,
+) -> Result<RemoteOrderSnapshotOutcome, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let Some(local_id) = resolve_local_order_id(&conn, remote_order) else {
+        return Ok(RemoteOrderSnapshotOutcome::Missing);
+    };
+    let previous_status: Option<String> = conn
+        .query_row(
+            "SELECT status FROM orders WHERE id = ?1",
+            params![local_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("read local order status: {e}"))?
+        .flatten();
+    let now = Utc::now().to_rfc3339();
+    let updated = sync_remote_order_snapshot_into_local(&conn, &local_id, remote_order, &now)?;
+    if updated == 0 {
+        return Ok(RemoteOrderSnapshotOutcome::Unchanged { local_id });
+    }
+    let status: Option<String> = conn
+        .query_row(
+            "SELECT status FROM orders WHERE id = ?1",
+            params![local_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("read local order status: {e}"))?
+        .flatten();
+    Ok(RemoteOrderSnapshotOutcome::Updated {
+        local_id,
+        previous_status,
+        status,
+    })
+}
+
+fn sync_remote_order_snapshot_into_local(
+    conn: &Connection,
+    local_order_id: &str,
+    remote_order: &Value,
+    repaired_at: &str,
+) -> Result<usize, String> {
+    let remote_order_id = str_any(remote_order, &["id"]);
     if !remote_order_visible_to_current_terminal(conn, remote_order)? {
         debug!(
             local_order_id = %local_order_id,
@@ -10723,14 +11311,7 @@ fn sync_remote_order_snapshot_into_local(
         return Ok(0);
     }
     let order_number = str_any(remote_order, &["order_number", "orderNumber"]);
-    let items_json = remote_order
-        .get("items")
-        .or_else(|| remote_order.get("order_items"))
-        .or_else(|| remote_order.get("orderItems"))
-        .map(|items| match items {
-            Value::String(raw) => raw.clone(),
-            other => serde_json::to_string(other).unwrap_or_else(|_| "[]".to_string()),
-        });
+    let items_json = remote_order_items_json(remote_order);
     let total_amount = num_any(remote_order, &["total_amount", "totalAmount"]);
     let tax_amount = num_any(remote_order, &["tax_amount", "taxAmount"]);
     let subtotal = num_any(remote_order, &["subtotal"]);
@@ -10885,7 +11466,7 @@ fn sync_remote_order_snapshot_into_local(
              discount_amount_cents = COALESCE(CAST(ROUND(?25 * 100) AS INTEGER), discount_amount_cents),
              tip_amount = COALESCE(?26, tip_amount),
              tip_amount_cents = COALESCE(CAST(ROUND(?26 * 100) AS INTEGER), tip_amount_cents),
-             version = COALESCE(?27, version),
+             version = MAX(COALESCE(?27, 0), COALESCE(version, 1) + 1),
              terminal_id = COALESCE(?28, terminal_id),
              owner_terminal_id = COALESCE(?29, owner_terminal_id),
              source_terminal_id = COALESCE(?30, source_terminal_id),
@@ -10957,6 +11538,11 @@ fn sync_remote_order_snapshot_into_local(
         ],
     )
     .map_err(|e| format!("sync remote order snapshot into local cache: {e}"))?;
+    if updated > 0 {
+        if let Some(charges) = crate::platform_fees::OrderCharges::from_payload(remote_order) {
+            charges.store(conn, local_order_id)?;
+        }
+    }
 
     // W6: inbound remote `payment_method` is consumed by sync payload
     // construction upstream but is no longer persisted locally (column
@@ -11671,15 +12257,19 @@ async fn recover_payment_total_conflicts(
 ///
 /// Orders and shifts are synced to separate endpoints so a failure in one
 /// category does not block the other.
-async fn run_sync_cycle(db: &DbState, app: &AppHandle) -> Result<usize, String> {
+async fn run_sync_cycle(db: &DbState, app: &AppHandle) -> Result<SyncCycleOutcome, String> {
     let admin_url = match storage::get_credential("admin_dashboard_url") {
         Some(url) => url,
-        None => return Ok(0),
+        None => return Ok(SyncCycleOutcome::default()),
     };
     let api_key = match load_zeroized_pos_api_key_optional() {
         Some(k) => k,
-        None => return Ok(0),
+        None => return Ok(SyncCycleOutcome::default()),
     };
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        crate::organization_change::ensure_sync_allowed(&conn)?;
+    }
     let terminal_id = storage::get_credential("terminal_id").unwrap_or_default();
     let branch_id = storage::get_credential("branch_id").unwrap_or_default();
 
@@ -11830,6 +12420,11 @@ async fn run_sync_cycle(db: &DbState, app: &AppHandle) -> Result<usize, String>
     total_progress += receipt_updates;
 
     let reconciled_orders = reconcile_remote_orders(db, &admin_url, &api_key, app).await?;
+    crate::sync_backlog::record_pull();
+    crate::reservations::refresh_if_due(db).await;
+    if !branch_id.is_empty() {
+        crate::schedule::refresh_if_due(db, &branch_id).await;
+    }
     total_progress += reconciled_orders.reconciled;
     if let Err(error) = finalize_sync_bootstrap_mode_after_remote_catchup(db, &reconciled_orders) {
         warn!(error = %error, "Failed to clear sync bootstrap mode after remote catch-up");
@@ -11853,11 +12448,15 @@ async fn run_sync_cycle(db: &DbState, app: &AppHandle) -> Result<usize, String>
 
     let pending_items = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        claim_pending_sync_items(&conn, 25)?
+        claim_pending_sync_items(&conn, SYNC_PUSH_CLAIM_LIMIT)?
     };
+    let progress_before_push = total_progress;
 
     if pending_items.is_empty() {
-        return Ok(total_progress);
+        return Ok(SyncCycleOutcome {
+            progress: total_progress,
+            push: SyncPushStats::default(),
+        });
     }
 
     // Partition by entity_type
@@ -11881,6 +12480,7 @@ async fn run_sync_cycle(db: &DbState, app: &AppHandle) -> Result<usize, String>
     }
 
     let mut had_non_backpressure_failure = false;
+    let mut push = SyncPushStats::default();
 
     // Sync orders — use direct API (POST /api/pos/orders) as primary path
     // for insert operations. The queue-based endpoint (/api/pos/orders/sync)
@@ -11891,6 +12491,7 @@ async fn run_sync_cycle(db: &DbState, app: &AppHandle) -> Result<usize, String>
             .await
         {
             Ok(direct_outcome) => {
+                push.absorb(direct_outcome.push_stats());
                 if !direct_outcome.synced_queue_ids.is_empty() {
                     total_progress += direct_outcome.synced_queue_ids.len();
                     info!(
@@ -11938,6 +12539,7 @@ async fn run_sync_cycle(db: &DbState, app: &AppHandle) -> Result<usize, String>
                 // orders to be dead-lettered. Instead, let the normal retry
                 // mechanism schedule retries on the direct API path.
                 warn!(error = %e, "Direct order API failed, scheduling retry (no queue fallback)");
+                push.items_failed += order_items.len();
                 let empty = DirectOrderFallbackOutcome::default();
                 if mark_order_batch_failures(db, &order_items, &e, &empty)? {
                     had_non_backpressure_failure = true;
@@ -11948,10 +12550,14 @@ async fn run_sync_cycle(db: &DbState, app: &AppHandle) -> Result<usize, String>
 
     // Sync shifts
     if !shift_items.is_empty() {
+        push.batches_sent += 1;
         match sync_shift_batch(&admin_url, &api_key, &terminal_id, &branch_id, &shift_items).await {
             Ok(shift_outcome) => {
-                total_progress +=
+                let synced =
                     mark_synced_shift_items(db, &shift_items, &shift_outcome.synced_shift_ids)?;
+                total_progress += synced;
+                push.items_synced += synced;
+                push.items_failed += shift_outcome.failed_shift_ids.len();
 
                 if mark_failed_shift_items(db, &shift_items, &shift_outcome.failed_shift_ids)? {
                     had_non_backpressure_failure = true;
@@ -11971,6 +12577,7 @@ async fn run_sync_cycle(db: &DbState, app: &AppHandle) -> Result<usize, String>
             }
             Err(e) => {
                 warn!("Shift sync failed: {e}");
+                push.items_failed += shift_items.len();
                 let outcome = mark_batch_failed(db, &shift_items, &e)?;
                 if !outcome.backpressure_deferred {
                     had_non_backpressure_failure = true;
@@ -11980,6 +12587,7 @@ async fn run_sync_cycle(db: &DbState, app: &AppHandle) -> Result<usize, String>
     }
 
     if !financial_items.is_empty() {
+        push.batches_sent += 1;
         match sync_financial_batch(
             &admin_url,
             &api_key,
@@ -11992,12 +12600,14 @@ async fn run_sync_cycle(db: &DbState, app: &AppHandle) -> Result<usize, String>
         {
             Ok(outcome) => {
                 total_progress += outcome.synced;
+                push.items_synced += outcome.synced;
                 if outcome.had_non_backpressure_failure {
                     had_non_backpressure_failure = true;
                 }
             }
             Err(e) => {
                 warn!("Financial sync failed: {e}");
+                push.items_failed += financial_items.len();
                 let outcome = mark_batch_failed(db, &financial_items, &e)?;
                 if !outcome.backpressure_deferred {
                     had_non_backpressure_failure = true;
@@ -12006,10 +12616,17 @@ async fn run_sync_cycle(db: &DbState, app: &AppHandle) -> Result<usize, String>
         }
     }
 
-    // Sync payments (individually to /api/pos/payments)
+    // Sync payments (batched to /api/pos/payments/sync, singly on rejection)
     if !payment_items.is_empty() {
-        let synced =
-            sync_payment_items(&admin_url, &api_key, &terminal_id, db, &payment_items).await;
+        let synced = sync_payment_items(
+            &admin_url,
+            &api_key,
+            &terminal_id,
+            db,
+            &payment_items,
+            &mut push,
+        )
+        .await;
         total_progress += synced;
     }
 
@@ -12050,8 +12667,14 @@ async fn run_sync_cycle(db: &DbState, app: &AppHandle) -> Result<usize, String>
     if total_progress == 0 && !pending_items.is_empty() && had_non_backpressure_failure {
         return Err("All sync batches failed".into());
     }
+    if total_progress > progress_before_push {
+        crate::sync_backlog::record_push();
+    }
 
-    Ok(total_progress)
+    Ok(SyncCycleOutcome {
+        progress: total_progress,
+        push,
+    })
 }
 
 fn mark_synced_shift_items(
@@ -12133,6 +12756,13 @@ fn claim_pending_sync_items(conn: &Connection, limit: usize) -> Result<Vec<SyncI
     // ids whose DB state did not match, relying on the 120s stale-lease
     // recovery to heal the divergence. With BEGIN IMMEDIATE both sides
     // commit together or neither does.
+    //
+    // Each claim is leased for `STALE_INFLIGHT_SYNC_LEASE_SECS`. A row still
+    // `in_progress` after its lease expired belongs to a cycle that died, so
+    // it is claimable again; reclaiming burns a retry like the recovery
+    // pass does. Rows claimed before leases existed have no lease and are
+    // left to `requeue_stale_in_progress_sync_rows`.
+    let lease_modifier = format!("+{} seconds", STALE_INFLIGHT_SYNC_LEASE_SECS);
     conn.execute_batch("BEGIN IMMEDIATE")
         .map_err(|e| format!("begin claim transaction: {e}"))?;
 
@@ -12140,14 +12770,23 @@ fn claim_pending_sync_items(conn: &Connection, limit: usize) -> Result<Vec<SyncI
         let mut stmt = conn
             .prepare(
                 "SELECT id, entity_type, entity_id, operation, payload, idempotency_key,
-                        retry_count, max_retries, next_retry_at,
+                        retry_count + CASE WHEN status = 'in_progress' THEN 1 ELSE 0 END,
+                        max_retries, next_retry_at,
                         COALESCE(retry_delay_ms, 5000), remote_receipt_id
                  FROM sync_queue
-                 WHERE status = 'pending'
-                   AND retry_count < max_retries
-                   AND (
-                        next_retry_at IS NULL
-                        OR julianday(next_retry_at) <= julianday('now')
+                 WHERE (
+                        status = 'pending'
+                        AND retry_count < max_retries
+                        AND (
+                             next_retry_at IS NULL
+                             OR julianday(next_retry_at) <= julianday('now')
+                        )
+                   )
+                   OR (
+                        status = 'in_progress'
+                        AND retry_count + 1 < max_retries
+                        AND lease_expires_at IS NOT NULL
+                        AND julianday(lease_expires_at) <= julianday('now')
                    )
                  ORDER BY COALESCE(next_retry_at, created_at) ASC, created_at ASC
                  LIMIT ?1",
@@ -12181,13 +12820,48 @@ fn claim_pending_sync_items(conn: &Connection, limit: usize) -> Result<Vec<SyncI
         if !items.is_empty() {
             let ids: Vec<i64> = items.iter().map(|item| item.0).collect();
             let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+            let reclaimed: usize =
+                conn.query_row(
+                    &format!(
+                        "SELECT COUNT(*) FROM sync_queue
+                         WHERE id IN ({placeholders}) AND status = 'in_progress'"
+                    ),
+                    rusqlite::params_from_iter(&ids),
+                    |row| row.get::<_, i64>(0),
+                )
+                .map_err(|e| format!("count reclaimed leases: {e}"))? as usize;
             let update_sql = format!(
                 "UPDATE sync_queue
-                 SET status = 'in_progress', updated_at = datetime('now')
-                 WHERE id IN ({placeholders}) AND status = 'pending'"
+                 SET retry_count = retry_count
+                         + CASE WHEN status = 'in_progress' THEN 1 ELSE 0 END,
+                     last_error = CASE
+                         WHEN status = 'in_progress'
+                             THEN COALESCE(NULLIF(last_error, '') || ' | ', '') || ?1
+                         ELSE last_error
+                     END,
+                     status = 'in_progress',
+                     lease_expires_at = datetime('now', ?2),
+                     updated_at = datetime('now')
+                 WHERE id IN ({placeholders})
+                   AND (
+                        status = 'pending'
+                        OR (
+                            status = 'in_progress'
+                            AND julianday(lease_expires_at) <= julianday('now')
+                        )
+                   )"
             );
-            conn.execute(&update_sql, rusqlite::params_from_iter(&ids))
+            let mut update_params: Vec<rusqlite::types::Value> = vec![
+                STALE_RECOVERY_NOTE.to_string().into(),
+                lease_modifier.clone().into(),
+            ];
+            update_params.extend(ids.iter().map(|id| rusqlite::types::Value::from(*id)));
+            conn.execute(&update_sql, rusqlite::params_from_iter(update_params))
                 .map_err(|e| format!("bulk claim update: {e}"))?;
+            if reclaimed > 0 {
+                bump_recovered_stale_count(conn, reclaimed)?;
+                info!(reclaimed, "Reclaimed sync rows with expired leases");
+            }
         }
 
         Ok(items)
@@ -12850,7 +13524,8 @@ fn normalize_order_items_for_sync(items: &Value) -> Vec<Value> {
             "unit_price": unit_price,
             "total_price": total_price,
             "customizations": customizations,
-            "notes": notes
+            "notes": notes,
+            "allergy_flags": crate::allergens::flags(&item)
         }));
     }
     normalized
@@ -12889,14 +13564,13 @@ fn build_normalized_order_operation(
         .unwrap_or_else(|| Value::Array(vec![]));
     let items = normalize_order_items_for_sync(&raw_items);
 
+    let rounding = db.read(|conn| Ok(RoundingRule::from_settings(conn)))?;
     let items_subtotal = items
         .iter()
-        .map(|item| {
-            item.get("total_price")
-                .and_then(Value::as_f64)
-                .unwrap_or(0.0)
-        })
-        .sum::<f64>();
+        .filter_map(|item| item.get("total_price").and_then(Value::as_f64))
+        .map(|line| Cents::round_with(line, rounding))
+        .sum::<Cents>()
+        .to_f64_dp2();
     let subtotal = num_any(source, &["subtotal"])
         .or_else(|| num_any(&payload_data, &["subtotal"]))
         .unwrap_or(items_subtotal)
@@ -13072,6 +13746,11 @@ fn build_normalized_order_operation(
             None
         });
 
+    let mut allergy_flags = crate::allergens::flags(source);
+    if allergy_flags.is_empty() {
+        allergy_flags = crate::allergens::flags(&payload_data);
+    }
+
     let data = serde_json::json!({
         "order_number": str_any(source, &["orderNumber", "order_number"]).or_else(|| str_any(&payload_data, &["orderNumber", "order_number"])),
         "customer_id": customer_id,
@@ -13096,6 +13775,8 @@ fn build_normalized_order_operation(
         "delivery_fee": delivery_fee,
         "notes": notes,
         "special_instructions": special_instructions,
+        "kitchen_notes": str_any(source, &["kitchenNotes", "kitchen_notes"]).or_else(|| str_any(&payload_data, &["kitchenNotes", "kitchen_notes"])),
+        "allergy_flags": allergy_flags,
         "cancellation_reason": cancellation_reason,
         "delivery_address": str_any(source, &["deliveryAddress", "delivery_address"]).or_else(|| str_any(&payload_data, &["deliveryAddress", "delivery_address"])),
         "delivery_city": str_any(source, &["deliveryCity", "delivery_city"]).or_else(|| str_any(&payload_data, &["deliveryCity", "delivery_city"])),
@@ -13147,6 +13828,8 @@ fn copy_order_update_payload_fields(payload_value: &Value, body: &mut Value) {
         ("tableId", "table_id"),
         ("tableSessionId", "table_session_id"),
         ("guestCount", "guest_count"),
+        ("kitchenNotes", "kitchen_notes"),
+        ("allergyFlags", "allergy_flags"),
         ("cancellationReason", "cancellation_reason"),
         ("cancelledAt", "cancelled_at"),
     ] {
@@ -13257,9 +13940,10 @@ async fn sync_order_batch_via_direct_api(
     items: &[&SyncItem],
 ) -> Result<DirectOrderFallbackOutcome, String> {
     let mut outcome = DirectOrderFallbackOutcome::default();
+    let mut prepared: Vec<PreparedOrderPush> = Vec::new();
 
     for item in items {
-        let (queue_id, _etype, entity_id, operation, payload, _idem, _ret, _max, _, _, _) = item;
+        let (queue_id, _etype, entity_id, operation, payload, idem, _ret, _max, _, _, _) = item;
         let operation_name = operation.trim().to_lowercase();
         let local_order = get_order_by_id(db, entity_id).unwrap_or_else(|e| {
             warn!(order_id = %entity_id, "get_order_by_id fallback: {e}");
@@ -13379,6 +14063,7 @@ async fn sync_order_batch_via_direct_api(
                 "category_name": raw_item.get("category_name").cloned().unwrap_or(Value::Null),
                 "customizations": raw_item.get("customizations").cloned().unwrap_or(Value::Null),
                 "notes": raw_item.get("notes").cloned().unwrap_or(Value::Null),
+                "allergy_flags": crate::allergens::flags(&raw_item),
             }));
         }
 
@@ -13463,6 +14148,8 @@ async fn sync_order_batch_via_direct_api(
             "coupon_discount_amount": num_any(&data, &["coupon_discount_amount"]),
             "delivery_fee": num_any(&data, &["delivery_fee"]),
             "notes": str_any(&data, &["notes"]),
+            "kitchen_notes": str_any(&data, &["kitchen_notes", "kitchenNotes"]),
+            "allergy_flags": crate::allergens::flags(&data),
             "customer_id": str_any(&data, &["customer_id", "customerId"]),
             "customer_name": str_any(&data, &["customer_name"]),
             "customer_email": str_any(&data, &["customer_email", "customerEmail"]),
@@ -13484,78 +14171,104 @@ async fn sync_order_batch_via_direct_api(
             "ghost_metadata": data.get("ghost_metadata").or_else(|| data.pointer("/data/ghost_metadata")),
         });
 
-        match api::fetch_from_admin(admin_url, api_key, "/api/pos/orders", "POST", Some(body)).await
-        {
-            Ok(resp) => {
-                let remote_id = resp
-                    .pointer("/data/id")
-                    .and_then(Value::as_str)
-                    .or_else(|| resp.get("id").and_then(Value::as_str))
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty());
-
-                let Some(remote_id) = remote_id else {
-                    outcome.record_transient_failure(
-                        *queue_id,
-                        "Transient direct fallback failure: response missing order id".to_string(),
-                    );
-                    warn!(
-                        queue_id = *queue_id,
-                        entity_id = %entity_id,
-                        "Direct order fallback response missing order id"
-                    );
-                    continue;
-                };
+        prepared.push(PreparedOrderPush {
+            queue_id: *queue_id,
+            entity_id: entity_id.clone(),
+            operation_name,
+            idempotency_key: idem.clone(),
+            items_sent: direct_items.len(),
+            order_type: order_type_normalized,
+            payment_method: payment_method_normalized,
+            body,
+        });
+    }
 
-                mark_order_synced_via_direct_fallback(db, *queue_id, entity_id, &remote_id)?;
-                outcome.record_synced(*queue_id);
+    for chunk in prepared.chunks(SYNC_PUSH_BATCH_SIZE) {
+        if let [single] = chunk {
+            push_single_direct_order(db, admin_url, api_key, single, &mut outcome).await?;
+            continue;
+        }
 
-                info!(
-                    queue_id = *queue_id,
-                    entity_id = %entity_id,
-                    source_operation = %operation_name,
-                    remote_order_id = %remote_id,
-                    "Order synced via direct API fallback"
+        let operations: Vec<Value> = chunk
+            .iter()
+            .map(|order| {
+                serde_json::json!({
+                    "operation": "insert",
+                    "idempotency_key": order.idempotency_key,
+                    "client_order_id": order.entity_id,
+                    "data": order.body,
+                })
+            })
+            .collect();
+        outcome.batches_sent += 1;
+        let batch = api::fetch_from_admin(
+            admin_url,
+            api_key,
+            "/api/pos/orders/sync",
+            "POST",
+            Some(serde_json::json!({ "mode": "direct", "operations": operations })),
+        )
+        .await;
+        let results = match batch {
+            Ok(resp) => map_push_batch_results(&resp),
+            Err(error) if is_retryable_batch_push_error(&error) => {
+                warn!(
+                    batch_size = chunk.len(),
+                    error = %error,
+                    "Batched order push failed; items will retry next cycle"
                 );
-            }
-            Err(error) => {
-                let order_preview = build_order_preview(db, entity_id);
-                if is_permanent_order_sync_error(&error) {
-                    outcome.record_permanent_failure(
-                        *queue_id,
-                        format!("Permanent direct fallback failure: {error}"),
-                    );
-                    error!(
-                        queue_id = *queue_id,
-                        entity_id = %entity_id,
-                        error = %error,
-                        order_preview = ?order_preview,
-                        items_sent = direct_items.len(),
-                        order_type = %order_type_normalized,
-                        payment_method = %payment_method_normalized,
-                        "Direct order sync PERMANENT failure — order will not retry"
-                    );
-                } else if is_transient_order_sync_error(&error) {
+                for order in chunk {
                     outcome.record_transient_failure(
-                        *queue_id,
+                        order.queue_id,
                         format!("Transient direct fallback failure: {error}"),
                     );
-                    warn!(
-                        queue_id = *queue_id,
-                        entity_id = %entity_id,
-                        error = %error,
-                        order_preview = ?order_preview,
-                        items_sent = direct_items.len(),
-                        order_type = %order_type_normalized,
-                        payment_method = %payment_method_normalized,
-                        "Direct order sync transient failure — will retry"
-                    );
-                } else {
-                    outcome.record_transient_failure(
-                        *queue_id,
-                        format!("Transient direct fallback failure: {error}"),
+                }
+                continue;
+            }
+            Err(error) => {
+                warn!(
+                    batch_size = chunk.len(),
+                    error = %error,
+                    "Batched order push rejected; falling back to single-order pushes"
+                );
+                HashMap::new()
+            }
+        };
+
+        for order in chunk {
+            match results.get(&order.idempotency_key) {
+                Some(PushBatchItemOutcome::Accepted {
+                    remote_id: Some(remote_id),
+                }) => {
+                    mark_order_synced_via_direct_fallback(
+                        db,
+                        order.queue_id,
+                        &order.entity_id,
+                        remote_id,
+                    )?;
+                    outcome.record_synced(order.queue_id);
+                    info!(
+                        queue_id = order.queue_id,
+                        entity_id = %order.entity_id,
+                        source_operation = %order.operation_name,
+                        remote_order_id = %remote_id,
+                        "Order synced via batched direct API"
                     );
                 }
+                // Rejected in the batch, or not confirmed with a remote id:
+                // push it alone so one poison order cannot hold back the
+                // rest and its own error is classified as before.
+                other => {
+                    if let Some(PushBatchItemOutcome::Rejected(reason)) = other {
+                        debug!(
+                            queue_id = order.queue_id,
+                            entity_id = %order.entity_id,
+                            reason = %reason,
+                            "Order rejected in batch; retrying as a single push"
+                        );
+                    }
+                    push_single_direct_order(db, admin_url, api_key, order, &mut outcome).await?
+                }
             }
         }
     }
@@ -13759,6 +14472,116 @@ async fn sync_order_batch_via_direct_api(
     Ok(outcome)
 }
 
+/// A normalized order insert ready for `POST /api/pos/orders`, or for one
+/// slot of a batched `POST /api/pos/orders/sync`.
+struct PreparedOrderPush {
+    queue_id: i64,
+    entity_id: String,
+    operation_name: String,
+    idempotency_key: String,
+    items_sent: usize,
+    order_type: String,
+    payment_method: String,
+    body: Value,
+}
+
+/// Push one order through the single-order endpoint and record the result.
+async fn push_single_direct_order(
+    db: &DbState,
+    admin_url: &str,
+    api_key: &str,
+    order: &PreparedOrderPush,
+    outcome: &mut DirectOrderFallbackOutcome,
+) -> Result<(), String> {
+    match api::fetch_from_admin(
+        admin_url,
+        api_key,
+        "/api/pos/orders",
+        "POST",
+        Some(order.body.clone()),
+    )
+    .await
+    {
+        Ok(resp) => {
+            let remote_id = resp
+                .pointer("/data/id")
+                .and_then(Value::as_str)
+                .or_else(|| resp.get("id").and_then(Value::as_str))
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty());
+
+            let Some(remote_id) = remote_id else {
+                outcome.record_transient_failure(
+                    order.queue_id,
+                    "Transient direct fallback failure: response missing order id".to_string(),
+                );
+                warn!(
+                    queue_id = order.queue_id,
+                    entity_id = %order.entity_id,
+                    "Direct order fallback response missing order id"
+                );
+                return Ok(());
+            };
+
+            mark_order_synced_via_direct_fallback(
+                db,
+                order.queue_id,
+                &order.entity_id,
+                &remote_id,
+            )?;
+            outcome.record_synced(order.queue_id);
+
+            info!(
+                queue_id = order.queue_id,
+                entity_id = %order.entity_id,
+                source_operation = %order.operation_name,
+                remote_order_id = %remote_id,
+                "Order synced via direct API fallback"
+            );
+        }
+        Err(error) => {
+            let order_preview = build_order_preview(db, &order.entity_id);
+            if is_permanent_order_sync_error(&error) {
+                outcome.record_permanent_failure(
+                    order.queue_id,
+                    format!("Permanent direct fallback failure: {error}"),
+                );
+                error!(
+                    queue_id = order.queue_id,
+                    entity_id = %order.entity_id,
+                    error = %error,
+                    order_preview = ?order_preview,
+                    items_sent = order.items_sent,
+                    order_type = %order.order_type,
+                    payment_method = %order.payment_method,
+                    "Direct order sync PERMANENT failure — order will not retry"
+                );
+            } else if is_transient_order_sync_error(&error) {
+                outcome.record_transient_failure(
+                    order.queue_id,
+                    format!("Transient direct fallback failure: {error}"),
+                );
+                warn!(
+                    queue_id = order.queue_id,
+                    entity_id = %order.entity_id,
+                    error = %error,
+                    order_preview = ?order_preview,
+                    items_sent = order.items_sent,
+                    order_type = %order.order_type,
+                    payment_method = %order.payment_method,
+                    "Direct order sync transient failure — will retry"
+                );
+            } else {
+                outcome.record_transient_failure(
+                    order.queue_id,
+                    format!("Transient direct fallback failure: {error}"),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
 /// POST a batch of normalized order sync items to `/api/pos/orders/sync`.
 ///
 /// Returns the server response JSON so the caller can extract
@@ -15146,8 +15969,10 @@ async fn sync_payment_items(
     terminal_id: &str,
     db: &DbState,
     items: &[&SyncItem],
+    stats: &mut SyncPushStats,
 ) -> usize {
     let mut synced = 0;
+    let mut prepared: Vec<PreparedPaymentPush> = Vec::new();
 
     for item in items {
         let (
@@ -15455,250 +16280,379 @@ async fn sync_payment_items(
         // sync_state = 'syncing'` used to live here; it was redundant
         // with the atomic claim and has been removed.
 
-        match api::fetch_from_admin(admin_url, api_key, "/api/pos/payments", "POST", Some(body))
-            .await
-        {
-            Ok(resp) => {
-                let typed: Option<PaymentSyncResponse> = serde_json::from_value(resp.clone()).ok();
-                let remote_payment_id = typed
-                    .as_ref()
-                    .and_then(|value| value.payment_id.clone())
-                    .or_else(|| {
-                        resp.get("payment_id")
-                            .or_else(|| resp.get("id"))
-                            .and_then(Value::as_str)
-                            .map(|value| value.to_string())
-                    });
-                let now = Utc::now().to_rfc3339();
-                // Wave 3 C4: both the `order_payments` row update and the
-                // `sync_queue` row update are routed through
-                // `mark_local_payment_applied`, which wraps them in a
-                // SAVEPOINT. Previously the two `conn.execute` calls were
-                // siblings with no savepoint — a process crash between
-                // them could leave the queue row at `status = 'synced'`
-                // while the payment's `sync_state` remained `'syncing'`,
-                // and stale-lease recovery would not pick up a row
-                // already marked synced. The payment was effectively
-                // stuck forever. SAVEPOINT makes the two writes atomic
-                // even when running without an enclosing transaction.
-                let local_apply_result = match db.conn.lock() {
-                    Ok(conn) => mark_local_payment_applied(
-                        &conn,
-                        entity_id,
-                        &now,
-                        remote_payment_id.as_deref(),
-                    ),
-                    Err(err) => Err(format!("db lock: {err}")),
-                };
-                if let Err(err) = local_apply_result {
-                    warn!(
-                        payment_id = %entity_id,
-                        queue_row_id = id,
-                        error = %err,
-                        "Payment sync: local apply failed after successful HTTP POST; keeping row retryable"
-                    );
-                    if let Ok(conn) = db.conn.lock() {
-                        let _ = conn.execute(
-                            "UPDATE sync_queue
-                             SET status = 'pending',
-                                 last_error = ?1,
-                                 next_retry_at = NULL,
-                                 updated_at = datetime('now')
-                             WHERE id = ?2",
-                            params![
-                                format!("Local apply failed after remote success: {err}"),
-                                id
-                            ],
-                        );
-                    }
-                    continue;
+        prepared.push(PreparedPaymentPush {
+            queue_id: *id,
+            payment_id: entity_id.clone(),
+            local_order_id: local_order_id.to_string(),
+            idempotency_key: idem_key.clone(),
+            retry_count: *retry_count,
+            max_retries: *max_retries,
+            body,
+        });
+    }
+
+    let mut tally = |result: ItemPushOutcome, stats: &mut SyncPushStats| match result {
+        ItemPushOutcome::Synced => {
+            synced += 1;
+            stats.items_synced += 1;
+        }
+        ItemPushOutcome::Failed => stats.items_failed += 1,
+        ItemPushOutcome::Deferred => {}
+    };
+
+    for chunk in prepared.chunks(SYNC_PUSH_BATCH_SIZE) {
+        if let [single] = chunk {
+            tally(
+                push_single_payment(admin_url, api_key, db, single).await,
+                stats,
+            );
+            continue;
+        }
+
+        stats.batches_sent += 1;
+        let payments: Vec<&Value> = chunk.iter().map(|payment| &payment.body).collect();
+        let batch = api::fetch_from_admin(
+            admin_url,
+            api_key,
+            "/api/pos/payments/sync",
+            "POST",
+            Some(serde_json::json!({ "terminal_id": terminal_id, "payments": payments })),
+        )
+        .await;
+        let results = match batch {
+            Ok(resp) => map_push_batch_results(&resp),
+            Err(error) if is_retryable_batch_push_error(&error) => {
+                warn!(
+                    batch_size = chunk.len(),
+                    error = %error,
+                    "Batched payment push failed; payments will retry"
+                );
+                for payment in chunk {
+                    record_payment_push_failure(db, payment, &error);
+                    tally(ItemPushOutcome::Failed, stats);
                 }
-                synced += 1;
+                continue;
             }
-            Err(e) => {
-                if is_payment_total_conflict_error(&e) {
-                    match reconcile_remote_payments_for_local_order_with_context(
-                        db,
-                        admin_url,
-                        api_key,
-                        local_order_id,
-                    )
-                    .await
-                    {
-                        Ok(outcome) => {
-                            let reconciled_queue_rows =
-                                match reconcile_applied_payment_queue_rows_for_order(
-                                    db,
-                                    local_order_id,
-                                ) {
-                                    Ok(reconciled) => reconciled,
-                                    Err(reconcile_error) => {
-                                        warn!(
-                                            payment_id = %entity_id,
-                                            order_id = %local_order_id,
-                                            error = %reconcile_error,
-                                            "Failed to reconcile applied payment queue rows after canonical remote payment recovery"
-                                        );
-                                        0
-                                    }
-                                };
-
-                            let resolved_from_remote_state = db.conn.lock().ok().and_then(|conn| {
-                                payment_has_unsynced_queue_rows(&conn, entity_id)
-                                    .ok()
-                                    .map(|has_unsynced_rows| !has_unsynced_rows)
-                            });
-
-                            if matches!(resolved_from_remote_state, Some(true)) {
-                                info!(
-                                    payment_id = %entity_id,
-                                    order_id = %local_order_id,
-                                    mirrored_remote_payments = outcome.changed,
-                                    reconciled_queue_rows,
-                                    "Payment sync conflict resolved from canonical remote payment state"
-                                );
-                                synced += 1;
-                                continue;
-                            }
-                        }
-                        Err(recovery_error) => {
-                            warn!(
-                                payment_id = %entity_id,
-                                order_id = %local_order_id,
-                                error = %recovery_error,
-                                "Payment conflict recovery failed"
-                            );
-                        }
+            Err(error) => {
+                warn!(
+                    batch_size = chunk.len(),
+                    error = %error,
+                    "Batched payment push rejected; falling back to single-payment pushes"
+                );
+                HashMap::new()
+            }
+        };
+
+        for payment in chunk {
+            let result = match results.get(&payment.idempotency_key) {
+                Some(PushBatchItemOutcome::Accepted { remote_id }) => {
+                    apply_synced_payment(db, payment, remote_id.as_deref())
+                }
+                // Rejected in the batch or not mentioned: push it alone so
+                // the conflict recovery below runs for that payment only.
+                other => {
+                    if let Some(PushBatchItemOutcome::Rejected(reason)) = other {
+                        debug!(
+                            payment_id = %payment.payment_id,
+                            reason = %reason,
+                            "Payment rejected in batch; retrying as a single push"
+                        );
                     }
+                    push_single_payment(admin_url, api_key, db, payment).await
+                }
+            };
+            tally(result, stats);
+        }
+    }
+
+    synced
+}
 
-                    match resolve_duplicate_payment_total_conflict(db, entity_id) {
-                        Ok(Some(canonical_payment_id)) => {
+/// A claimed payment with its `/api/pos/payments` body, ready to push alone
+/// or as one entry of a batched `POST /api/pos/payments/sync`.
+struct PreparedPaymentPush {
+    queue_id: i64,
+    payment_id: String,
+    local_order_id: String,
+    idempotency_key: String,
+    retry_count: i64,
+    max_retries: i64,
+    body: Value,
+}
+
+/// Final state of one pushed queue item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemPushOutcome {
+    Synced,
+    Deferred,
+    Failed,
+}
+
+/// Apply a server-accepted payment locally. A local failure keeps the row
+/// retryable rather than counting it synced.
+fn apply_synced_payment(
+    db: &DbState,
+    payment: &PreparedPaymentPush,
+    remote_payment_id: Option<&str>,
+) -> ItemPushOutcome {
+    let id = payment.queue_id;
+    let entity_id = payment.payment_id.as_str();
+    let now = Utc::now().to_rfc3339();
+    // Wave 3 C4: both the `order_payments` row update and the
+    // `sync_queue` row update are routed through
+    // `mark_local_payment_applied`, which wraps them in a
+    // SAVEPOINT. Previously the two `conn.execute` calls were
+    // siblings with no savepoint — a process crash between
+    // them could leave the queue row at `status = 'synced'`
+    // while the payment's `sync_state` remained `'syncing'`,
+    // and stale-lease recovery would not pick up a row
+    // already marked synced. The payment was effectively
+    // stuck forever. SAVEPOINT makes the two writes atomic
+    // even when running without an enclosing transaction.
+    let local_apply_result = match db.conn.lock() {
+        Ok(conn) => mark_local_payment_applied(&conn, entity_id, &now, remote_payment_id),
+        Err(err) => Err(format!("db lock: {err}")),
+    };
+    if let Err(err) = local_apply_result {
+        warn!(
+            payment_id = %entity_id,
+            queue_row_id = id,
+            error = %err,
+            "Payment sync: local apply failed after successful HTTP POST; keeping row retryable"
+        );
+        if let Ok(conn) = db.conn.lock() {
+            let _ = conn.execute(
+                "UPDATE sync_queue
+                     SET status = 'pending',
+                         last_error = ?1,
+                         next_retry_at = NULL,
+                         updated_at = datetime('now')
+                     WHERE id = ?2",
+                params![
+                    format!("Local apply failed after remote success: {err}"),
+                    id
+                ],
+            );
+        }
+        return ItemPushOutcome::Deferred;
+    }
+    ItemPushOutcome::Synced
+}
+
+/// Push one payment through `/api/pos/payments`, running the total-conflict
+/// recovery on rejection.
+async fn push_single_payment(
+    admin_url: &str,
+    api_key: &str,
+    db: &DbState,
+    payment: &PreparedPaymentPush,
+) -> ItemPushOutcome {
+    let entity_id = payment.payment_id.as_str();
+    let local_order_id = payment.local_order_id.as_str();
+    match api::fetch_from_admin(
+        admin_url,
+        api_key,
+        "/api/pos/payments",
+        "POST",
+        Some(payment.body.clone()),
+    )
+    .await
+    {
+        Ok(resp) => {
+            let typed: Option<PaymentSyncResponse> = serde_json::from_value(resp.clone()).ok();
+            let remote_payment_id = typed
+                .as_ref()
+                .and_then(|value| value.payment_id.clone())
+                .or_else(|| {
+                    resp.get("payment_id")
+                        .or_else(|| resp.get("id"))
+                        .and_then(Value::as_str)
+                        .map(|value| value.to_string())
+                });
+            apply_synced_payment(db, payment, remote_payment_id.as_deref())
+        }
+        Err(e) => {
+            if is_payment_total_conflict_error(&e) {
+                match reconcile_remote_payments_for_local_order_with_context(
+                    db,
+                    admin_url,
+                    api_key,
+                    local_order_id,
+                )
+                .await
+                {
+                    Ok(outcome) => {
+                        let reconciled_queue_rows =
+                            match reconcile_applied_payment_queue_rows_for_order(db, local_order_id)
+                            {
+                                Ok(reconciled) => reconciled,
+                                Err(reconcile_error) => {
+                                    warn!(
+                                        payment_id = %entity_id,
+                                        order_id = %local_order_id,
+                                        error = %reconcile_error,
+                                        "Failed to reconcile applied payment queue rows after canonical remote payment recovery"
+                                    );
+                                    0
+                                }
+                            };
+
+                        let resolved_from_remote_state = db.conn.lock().ok().and_then(|conn| {
+                            payment_has_unsynced_queue_rows(&conn, entity_id)
+                                .ok()
+                                .map(|has_unsynced_rows| !has_unsynced_rows)
+                        });
+
+                        if matches!(resolved_from_remote_state, Some(true)) {
                             info!(
                                 payment_id = %entity_id,
                                 order_id = %local_order_id,
-                                canonical_payment_id = %canonical_payment_id,
-                                "Payment sync conflict resolved by voiding stale duplicate local payment"
-                            );
-                            synced += 1;
-                            continue;
-                        }
-                        Ok(None) => {}
-                        Err(resolve_error) => {
-                            warn!(
-                                payment_id = %entity_id,
-                                order_id = %local_order_id,
-                                error = %resolve_error,
-                                "Failed to resolve stale duplicate local payment conflict"
+                                mirrored_remote_payments = outcome.changed,
+                                reconciled_queue_rows,
+                                "Payment sync conflict resolved from canonical remote payment state"
                             );
+                            return ItemPushOutcome::Synced;
                         }
                     }
+                    Err(recovery_error) => {
+                        warn!(
+                            payment_id = %entity_id,
+                            order_id = %local_order_id,
+                            error = %recovery_error,
+                            "Payment conflict recovery failed"
+                        );
+                    }
+                }
 
-                    let repaired_tax_inflated_total = {
-                        let repaired_at = Utc::now().to_rfc3339();
-                        match db.conn.lock() {
-                            Ok(conn) => repair_tax_inflated_local_order_total_for_payment_conflict(
-                                &conn,
-                                local_order_id,
-                                entity_id,
-                                &repaired_at,
-                            ),
-                            Err(err) => Err(format!("db lock: {err}")),
-                        }
-                    };
-                    match repaired_tax_inflated_total {
-                        Ok(Some(repair)) => {
-                            info!(
-                                payment_id = %repair.payment_id,
-                                order_id = %repair.order_id,
-                                previous_total_cents = repair.previous_total_cents,
-                                repaired_total_cents = repair.repaired_total_cents,
-                                voided_payment_cents = repair.voided_payment_cents,
-                                "Payment sync conflict resolved by correcting tax-inflated local order total"
-                            );
-                            synced += 1;
-                            continue;
-                        }
-                        Ok(None) => {}
-                        Err(resolve_error) => {
-                            warn!(
-                                payment_id = %entity_id,
-                                order_id = %local_order_id,
-                                error = %resolve_error,
-                                "Failed to correct tax-inflated local order total after payment conflict"
-                            );
-                        }
+                match resolve_duplicate_payment_total_conflict(db, entity_id) {
+                    Ok(Some(canonical_payment_id)) => {
+                        info!(
+                            payment_id = %entity_id,
+                            order_id = %local_order_id,
+                            canonical_payment_id = %canonical_payment_id,
+                            "Payment sync conflict resolved by voiding stale duplicate local payment"
+                        );
+                        return ItemPushOutcome::Synced;
+                    }
+                    Ok(None) => {}
+                    Err(resolve_error) => {
+                        warn!(
+                            payment_id = %entity_id,
+                            order_id = %local_order_id,
+                            error = %resolve_error,
+                            "Failed to resolve stale duplicate local payment conflict"
+                        );
                     }
+                }
 
-                    match defer_payment_total_conflict_while_parent_order_lags(db, entity_id, &e) {
-                        Ok(Some(deferred)) => {
-                            info!(
-                                payment_id = %entity_id,
-                                order_id = %deferred.order_id,
-                                amount = deferred.payment_amount,
-                                local_total = deferred.local_total,
-                                remote_total = deferred.remote_total,
-                                queued_order_update = deferred.queued_order_update,
-                                "Payment sync conflict deferred while parent order update catches up"
-                            );
-                            continue;
-                        }
-                        Ok(None) => {}
-                        Err(resolve_error) => {
-                            warn!(
-                                payment_id = %entity_id,
-                                order_id = %local_order_id,
-                                error = %resolve_error,
-                                "Failed to defer payment total conflict behind parent order update"
-                            );
-                        }
+                let repaired_tax_inflated_total = {
+                    let repaired_at = Utc::now().to_rfc3339();
+                    match db.conn.lock() {
+                        Ok(conn) => repair_tax_inflated_local_order_total_for_payment_conflict(
+                            &conn,
+                            local_order_id,
+                            entity_id,
+                            &repaired_at,
+                        ),
+                        Err(err) => Err(format!("db lock: {err}")),
+                    }
+                };
+                match repaired_tax_inflated_total {
+                    Ok(Some(repair)) => {
+                        info!(
+                            payment_id = %repair.payment_id,
+                            order_id = %repair.order_id,
+                            previous_total_cents = repair.previous_total_cents,
+                            repaired_total_cents = repair.repaired_total_cents,
+                            voided_payment_cents = repair.voided_payment_cents,
+                            "Payment sync conflict resolved by correcting tax-inflated local order total"
+                        );
+                        return ItemPushOutcome::Synced;
                     }
+                    Ok(None) => {}
+                    Err(resolve_error) => {
+                        warn!(
+                            payment_id = %entity_id,
+                            order_id = %local_order_id,
+                            error = %resolve_error,
+                            "Failed to correct tax-inflated local order total after payment conflict"
+                        );
+                    }
+                }
 
-                    match resolve_stale_local_payment_total_conflict(db, entity_id) {
-                        Ok(Some(resolution)) => {
-                            info!(
-                                payment_id = %entity_id,
-                                order_id = %resolution.order_id,
-                                amount = resolution.amount,
-                                outstanding_before = resolution.outstanding_before,
-                                "Payment sync conflict resolved by voiding stale unsynced local overpay"
-                            );
-                            synced += 1;
-                            continue;
-                        }
-                        Ok(None) => {}
-                        Err(resolve_error) => {
-                            warn!(
-                                payment_id = %entity_id,
-                                order_id = %local_order_id,
-                                error = %resolve_error,
-                                "Failed to resolve stale unsynced local overpay payment conflict"
-                            );
-                        }
+                match defer_payment_total_conflict_while_parent_order_lags(db, entity_id, &e) {
+                    Ok(Some(deferred)) => {
+                        info!(
+                            payment_id = %entity_id,
+                            order_id = %deferred.order_id,
+                            amount = deferred.payment_amount,
+                            local_total = deferred.local_total,
+                            remote_total = deferred.remote_total,
+                            queued_order_update = deferred.queued_order_update,
+                            "Payment sync conflict deferred while parent order update catches up"
+                        );
+                        return ItemPushOutcome::Deferred;
+                    }
+                    Ok(None) => {}
+                    Err(resolve_error) => {
+                        warn!(
+                            payment_id = %entity_id,
+                            order_id = %local_order_id,
+                            error = %resolve_error,
+                            "Failed to defer payment total conflict behind parent order update"
+                        );
                     }
                 }
 
-                warn!(payment_id = %entity_id, error = %e, "Payment sync failed");
-                if let Ok(conn) = db.conn.lock() {
-                    let new_retry = retry_count + 1;
-                    let (queue_status, pay_state) = if new_retry >= *max_retries {
-                        ("failed", "failed")
-                    } else {
-                        ("pending", "pending")
-                    };
-                    let _ = conn.execute(
-                        "UPDATE sync_queue SET status = ?1, retry_count = ?2, last_error = ?3, updated_at = datetime('now') WHERE id = ?4",
-                        params![queue_status, new_retry, e, id],
-                    );
-                    let _ = conn.execute(
-                        "UPDATE order_payments SET sync_state = ?1, sync_retry_count = ?2, sync_last_error = ?3, updated_at = datetime('now') WHERE id = ?4",
-                        params![pay_state, new_retry, e, entity_id],
-                    );
+                match resolve_stale_local_payment_total_conflict(db, entity_id) {
+                    Ok(Some(resolution)) => {
+                        info!(
+                            payment_id = %entity_id,
+                            order_id = %resolution.order_id,
+                            amount = resolution.amount,
+                            outstanding_before = resolution.outstanding_before,
+                            "Payment sync conflict resolved by voiding stale unsynced local overpay"
+                        );
+                        return ItemPushOutcome::Synced;
+                    }
+                    Ok(None) => {}
+                    Err(resolve_error) => {
+                        warn!(
+                            payment_id = %entity_id,
+                            order_id = %local_order_id,
+                            error = %resolve_error,
+                            "Failed to resolve stale unsynced local overpay payment conflict"
+                        );
+                    }
                 }
             }
+
+            warn!(payment_id = %entity_id, error = %e, "Payment sync failed");
+            record_payment_push_failure(db, payment, &e);
+            ItemPushOutcome::Failed
         }
     }
+}
 
-    synced
+/// Count a failed push against the payment's retry budget, dead-lettering
+/// it once the budget is spent.
+fn record_payment_push_failure(db: &DbState, payment: &PreparedPaymentPush, error: &str) {
+    if let Ok(conn) = db.conn.lock() {
+        let new_retry = payment.retry_count + 1;
+        let (queue_status, pay_state) = if new_retry >= payment.max_retries {
+            ("failed", "failed")
+        } else {
+            ("pending", "pending")
+        };
+        let _ = conn.execute(
+            "UPDATE sync_queue SET status = ?1, retry_count = ?2, last_error = ?3, updated_at = datetime('now') WHERE id = ?4",
+            params![queue_status, new_retry, error, payment.queue_id],
+        );
+        let _ = conn.execute(
+            "UPDATE order_payments SET sync_state = ?1, sync_retry_count = ?2, sync_last_error = ?3, updated_at = datetime('now') WHERE id = ?4",
+            params![pay_state, new_retry, error, payment.payment_id],
+        );
+    }
 }
 
 /// Sync payment adjustment items to `/api/pos/payments/adjustments/sync`.
@@ -16345,7 +17299,8 @@ pub(crate) async fn dispatch_pending_payments_for_test(
         return 0;
     }
     let refs: Vec<&SyncItem> = items.iter().collect();
-    sync_payment_items(admin_url, api_key, terminal_id, db, &refs).await
+    let mut stats = SyncPushStats::default();
+    sync_payment_items(admin_url, api_key, terminal_id, db, &refs, &mut stats).await
 }
 
 /// Test-only entry point: load every pending `entity_type = 'z_report'`
@@ -16673,6 +17628,30 @@ fn mark_order_item_retry_or_fail(db: &DbState, item: &SyncItem, error: &str) ->
 }
 
 /// Build sync status JSON for event emission (avoids needing SyncState ref).
+/// Hint for the sync indicator: onboarding when the terminal has no
+/// credentials or its auth is paused, the network when the admin circuit
+/// is open. `null` while sync is healthy.
+fn sync_status_remediation(remote_auth_pause: &RemoteAuthPauseState) -> Value {
+    let code = if !storage::is_configured() {
+        Some(crate::remediation::TERMINAL_NOT_CONFIGURED)
+    } else if remote_auth_pause.remote_auth_paused {
+        Some(
+            if remote_auth_pause.remote_auth_code.as_deref() == Some("terminal_inactive") {
+                crate::remediation::TERMINAL_INACTIVE
+            } else {
+                crate::remediation::TERMINAL_AUTH_FAILED
+            },
+        )
+    } else if api::admin_breaker_snapshot().state == api::BreakerState::Open {
+        Some(crate::remediation::SYNC_CIRCUIT_OPEN)
+    } else {
+        None
+    };
+    code.and_then(crate::remediation::lookup)
+        .map(crate::remediation::to_json)
+        .unwrap_or(Value::Null)
+}
+
 fn get_sync_status_for_event(db: &DbState, sync_state: &SyncState, is_online: bool) -> Value {
     let (
         pending,
@@ -16771,6 +17750,7 @@ fn get_sync_status_for_event(db: &DbState, sync_state: &SyncState, is_online: bo
     let last = sync_state.last_sync.lock().ok().and_then(|g| g.clone());
     let pending_total = pending + queued_remote;
     let remote_auth_pause = sync_state.remote_auth_snapshot();
+    let remediation = sync_status_remediation(&remote_auth_pause);
     let mut payload = serde_json::json!({
         "isOnline": is_online,
         "lastSync": last,
@@ -16795,6 +17775,10 @@ fn get_sync_status_for_event(db: &DbState, sync_state: &SyncState, is_online: bo
     });
 
     if let Some(map) = payload.as_object_mut() {
+        map.insert("remediation".to_string(), remediation);
+        if let Ok(conn) = db.conn.lock() {
+            crate::sync_backlog::insert_status_fields(&conn, map);
+        }
         map.insert(
             "remoteAuthPaused".to_string(),
             Value::Bool(remote_auth_pause.remote_auth_paused),
@@ -18478,10 +19462,7 @@ mod tests {
         db::run_migrations_for_test(&conn);
         db::set_setting(&conn, "terminal", "__ignore_keyring", "1")
             .expect("disable keyring reads for sync tests");
-        DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        }
+        DbState::new(conn, std::path::PathBuf::from(":memory:"))
     }
 
     fn seed_active_cashier(db: &DbState, branch_id: &str, terminal_id: &str) {
@@ -19237,19 +20218,77 @@ mod tests {
             .get("orderId")
             .and_then(Value::as_str)
             .expect("order id");
-
-        let conn = db.conn.lock().unwrap();
-        let queued_count: i64 = conn
+
+        let conn = db.conn.lock().unwrap();
+        let queued_count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM print_jobs
+                 WHERE entity_type = 'order_receipt'
+                   AND entity_id = ?1
+                   AND status = 'pending'",
+                params![order_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(queued_count, 1);
+    }
+
+    #[test]
+    fn test_order_totals_and_sync_subtotal_follow_configured_rounding() {
+        let db = test_db();
+        seed_active_cashier(&db, "branch-rounding", "terminal-rounding");
+        {
+            let conn = db.conn.lock().unwrap();
+            db::set_setting(&conn, "general", "money_rounding", "half_even").unwrap();
+        }
+        // 0.125 and 0.375 sit on a half cent: half-even keeps 12 + 38 cents
+        // where half-up would make it 13 + 38.
+        let items = serde_json::json!([
+            { "name": "Half", "quantity": 1, "totalPrice": 0.125 },
+            { "name": "Half", "quantity": 1, "totalPrice": 0.375 },
+            { "name": "Whole", "quantity": 2, "price": 1.5 }
+        ]);
+        let payload = serde_json::json!({
+            "branchId": "branch-rounding",
+            "terminalId": "terminal-rounding",
+            "items": items,
+            "status": "pending",
+            "orderType": "pickup"
+        });
+
+        let created = create_order(&db, &payload).expect("create order");
+        let order_id = created
+            .get("orderId")
+            .and_then(Value::as_str)
+            .expect("order id");
+        let (subtotal, stored_items): (f64, String) = db
+            .conn
+            .lock()
+            .unwrap()
             .query_row(
-                "SELECT COUNT(*) FROM print_jobs
-                 WHERE entity_type = 'order_receipt'
-                   AND entity_id = ?1
-                   AND status = 'pending'",
+                "SELECT subtotal, items FROM orders WHERE id = ?1",
                 params![order_id],
-                |row| row.get(0),
+                |row| Ok((row.get(0)?, row.get(1)?)),
             )
             .unwrap();
-        assert_eq!(queued_count, 1);
+        assert!((subtotal - 3.5).abs() < 1e-9);
+        assert_eq!(
+            crate::parse_item_totals(&stored_items, RoundingRule::HalfEven).0,
+            3.5
+        );
+
+        let normalized = build_normalized_order_operation(
+            &db,
+            "ord-not-local",
+            "insert",
+            &serde_json::json!({ "items": items }),
+            "branch-rounding",
+        )
+        .unwrap();
+        assert_eq!(
+            normalized.pointer("/data/subtotal").and_then(Value::as_f64),
+            Some(3.5)
+        );
     }
 
     #[test]
@@ -22002,6 +23041,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_sync_status_counts_pending_and_failed_order_items() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        for (entity_type, entity_id, status) in [
+            ("order", "ord-pending", "pending"),
+            ("order", "ord-deferred", "deferred"),
+            ("order", "ord-failed", "failed"),
+            ("payment", "pay-failed", "failed"),
+        ] {
+            conn.execute(
+                "INSERT INTO sync_queue
+                 (entity_type, entity_id, operation, payload, idempotency_key, status, retry_count, max_retries)
+                 VALUES (?1, ?2, 'insert', '{}', ?3, ?4, 0, 3)",
+                params![entity_type, entity_id, format!("idem-{entity_id}"), status],
+            )
+            .unwrap();
+        }
+        drop(conn);
+
+        let sync_state = SyncState::new();
+        let status = get_sync_status(&db, &sync_state).expect("status");
+        assert_eq!(status["pendingOrderItems"], 2);
+        assert_eq!(
+            status["failedOrderItems"], 1,
+            "the failed payment row is not an order item"
+        );
+    }
+
     #[test]
     fn test_get_sync_status_parks_historical_z_report_conflicts_separately() {
         let db = test_db();
@@ -22428,6 +23496,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_force_sync_pushes_nothing_from_training_database() {
+        let dir = std::env::temp_dir().join(format!("pos-sync-training-{}", uuid::Uuid::new_v4()));
+        let db = db::init(&dir).expect("init live db");
+        assert!(!force_sync_suppressed(&db).unwrap());
+
+        let training_path = crate::training::training_db_path(&db);
+        let live = db
+            .swap_connections(db.open_sibling(&training_path).unwrap())
+            .unwrap();
+        assert!(force_sync_suppressed(&db).unwrap());
+        {
+            let conn = db.conn.lock().unwrap();
+            sync_queue::enqueue(
+                &conn,
+                &sync_queue::EnqueueInput {
+                    table_name: "orders".to_string(),
+                    record_id: "ord-training".to_string(),
+                    operation: "INSERT".to_string(),
+                    data: serde_json::json!({ "id": "ord-training", "total_amount": 12.5 })
+                        .to_string(),
+                    organization_id: "org-1".to_string(),
+                    priority: None,
+                    module_type: Some("orders".to_string()),
+                    conflict_strategy: None,
+                    version: None,
+                },
+            )
+            .unwrap();
+        }
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let result = tauri::async_runtime::block_on(sync_queue::process_queue(
+            &db.conn, &base_url, "api-key",
+        ))
+        .expect("process training queue");
+        assert!(result.success);
+        assert_eq!(result.processed, 0);
+        assert!(listener.accept().is_err(), "no request may reach the admin");
+        let pending: i64 = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT COUNT(*) FROM parity_sync_queue WHERE status = 'pending'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(pending, 1);
+
+        drop(db.swap_connections(live).unwrap());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
     #[test]
     fn test_mark_batch_failed_backpressure_defers_without_retry_increment() {
         let db = test_db();
@@ -22844,6 +23969,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_build_normalized_order_operation_carries_kitchen_notes_and_allergy_flags() {
+        let db = test_db();
+        let fallback_branch_id = Uuid::new_v4().to_string();
+        let conn = db.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO orders (
+                 id, items, total_amount, status, order_type, kitchen_notes, allergy_flags,
+                 sync_status, created_at, updated_at
+             ) VALUES (
+                 'ord-allergy', ?1, 9.0, 'pending', 'dine-in', 'Birthday table', '[\"peanuts\"]',
+                 'pending', datetime('now'), datetime('now')
+             )",
+            params![r#"[{"name":"Pasta","quantity":1,"price":9.0,"allergyFlags":["gluten"]}]"#],
+        )
+        .unwrap();
+        drop(conn);
+
+        let normalized = build_normalized_order_operation(
+            &db,
+            "ord-allergy",
+            "insert",
+            &serde_json::json!({}),
+            &fallback_branch_id,
+        )
+        .unwrap();
+        assert_eq!(
+            normalized.pointer("/data/kitchen_notes"),
+            Some(&serde_json::json!("Birthday table"))
+        );
+        assert_eq!(
+            normalized.pointer("/data/allergy_flags"),
+            Some(&serde_json::json!(["peanuts"]))
+        );
+        assert_eq!(
+            normalized.pointer("/items/0/allergy_flags"),
+            Some(&serde_json::json!(["gluten"]))
+        );
+    }
+
     #[test]
     fn test_copy_order_update_payload_fields_forwards_cancellation_reason() {
         let payload = serde_json::json!({
@@ -24756,6 +25921,49 @@ mod tests {
         assert_eq!(status, "pending");
     }
 
+    #[test]
+    fn test_release_in_progress_sync_rows_keeps_retry_budget() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO sync_queue (
+                 entity_type, entity_id, operation, payload, idempotency_key,
+                 status, retry_count, created_at, updated_at
+             ) VALUES (
+                 'order', 'ord-inflight', 'insert', '{}', 'order:ord-inflight',
+                 'in_progress', 1, datetime('now'), datetime('now')
+             )",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        assert_eq!(release_in_progress_sync_rows(&db).unwrap(), 1);
+
+        let conn = db.conn.lock().unwrap();
+        let (status, retry_count): (String, i64) = conn
+            .query_row(
+                "SELECT status, retry_count FROM sync_queue WHERE entity_id = 'ord-inflight'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(status, "pending");
+        assert_eq!(retry_count, 1);
+    }
+
+    #[test]
+    fn test_sync_cycle_guard_tracks_in_flight_cycles() {
+        let state = SyncState::new();
+        assert!(!state.is_cycle_in_flight());
+        let first = state.begin_cycle();
+        let second = state.begin_cycle();
+        drop(first);
+        assert!(state.is_cycle_in_flight());
+        drop(second);
+        assert!(!state.is_cycle_in_flight());
+    }
+
     #[test]
     fn test_requeue_stale_in_progress_sync_rows_keeps_fresh_rows_in_progress() {
         let db = test_db();
@@ -24874,6 +26082,110 @@ mod tests {
         );
     }
 
+    fn insert_pending_order_row(conn: &Connection, entity_id: &str) {
+        conn.execute(
+            "INSERT INTO sync_queue (
+                 entity_type, entity_id, operation, payload, idempotency_key,
+                 status, created_at, updated_at
+             ) VALUES (
+                 'order', ?1, 'insert', '{}', 'order:' || ?1,
+                 'pending', datetime('now', '-60 seconds'), datetime('now', '-60 seconds')
+             )",
+            params![entity_id],
+        )
+        .unwrap();
+    }
+
+    fn load_queue_row(conn: &Connection, entity_id: &str) -> (String, i64, Option<String>) {
+        conn.query_row(
+            "SELECT status, retry_count, last_error FROM sync_queue WHERE entity_id = ?1",
+            params![entity_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_claim_leases_rows_and_reclaims_after_crash_once_lease_expires() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        insert_pending_order_row(&conn, "ord-crashed");
+
+        // First cycle claims the row, then "crashes" before completing it.
+        let claimed = claim_pending_sync_items(&conn, 25).unwrap();
+        assert_eq!(claimed.len(), 1);
+        let lease_secs: i64 = conn
+            .query_row(
+                "SELECT CAST((julianday(lease_expires_at) - julianday('now')) * 86400 AS INTEGER)
+                 FROM sync_queue WHERE entity_id = 'ord-crashed'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(lease_secs > 100 && lease_secs <= STALE_INFLIGHT_SYNC_LEASE_SECS);
+
+        // A second cycle inside the lease leaves it alone.
+        assert!(claim_pending_sync_items(&conn, 25).unwrap().is_empty());
+
+        // Once the lease has expired the row is claimable again and the
+        // reclaim burns a retry.
+        conn.execute(
+            "UPDATE sync_queue SET lease_expires_at = datetime('now', '-1 seconds')
+             WHERE entity_id = 'ord-crashed'",
+            [],
+        )
+        .unwrap();
+        let reclaimed = claim_pending_sync_items(&conn, 25).unwrap();
+        assert_eq!(reclaimed.len(), 1);
+        assert_eq!(reclaimed[0].2, "ord-crashed");
+        assert_eq!(reclaimed[0].6, 1);
+
+        let (status, retry_count, last_error) = load_queue_row(&conn, "ord-crashed");
+        assert_eq!(status, "in_progress");
+        assert_eq!(retry_count, 1);
+        assert_eq!(last_error.as_deref(), Some(STALE_RECOVERY_NOTE));
+        assert_eq!(recovered_stale_count(&conn), 1);
+    }
+
+    #[test]
+    fn test_requeue_recovers_expired_lease_with_note_and_metric() {
+        let db = test_db();
+        let conn = db.conn.lock().unwrap();
+        insert_pending_order_row(&conn, "ord-expired");
+        insert_pending_order_row(&conn, "ord-live");
+        claim_pending_sync_items(&conn, 25).unwrap();
+        conn.execute(
+            "UPDATE sync_queue
+             SET lease_expires_at = datetime('now', '-5 seconds'),
+                 last_error = 'HTTP 503'
+             WHERE entity_id = 'ord-expired'",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        assert_eq!(requeue_stale_in_progress_sync_rows(&db).unwrap(), 1);
+
+        let conn = db.conn.lock().unwrap();
+        let (status, retry_count, last_error) = load_queue_row(&conn, "ord-expired");
+        assert_eq!(status, "pending");
+        assert_eq!(retry_count, 1);
+        assert_eq!(
+            last_error.as_deref(),
+            Some(format!("HTTP 503 | {STALE_RECOVERY_NOTE}").as_str())
+        );
+        let lease: Option<String> = conn
+            .query_row(
+                "SELECT lease_expires_at FROM sync_queue WHERE entity_id = 'ord-expired'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(lease.is_none());
+        assert_eq!(load_queue_row(&conn, "ord-live").0, "in_progress");
+        assert_eq!(recovered_stale_count(&conn), 1);
+    }
+
     #[test]
     fn test_requeue_retryable_failed_shift_rows_resets_retryable_shift() {
         let db = test_db();
@@ -27758,15 +29070,173 @@ mod tests {
         let item = load_sync_item(&db, queue_id);
         let items = vec![&item];
         let api_key = r#"{"key":"test-key","tid":"term-table-sync"}"#;
+        let mut stats = SyncPushStats::default();
         let synced = tauri::async_runtime::block_on(sync_payment_items(
             &mock_url,
             api_key,
             "term-table-sync",
             &db,
             &items,
+            &mut stats,
         ));
         assert_eq!(synced, 1);
+        assert_eq!(
+            stats,
+            SyncPushStats {
+                batches_sent: 0,
+                items_synced: 1,
+                items_failed: 0,
+            }
+        );
+        server_handle.join().expect("join mock server");
+    }
+
+    #[test]
+    fn map_push_batch_results_keys_outcomes_by_idempotency_key() {
+        let results = map_push_batch_results(&serde_json::json!({
+            "results": [
+                { "idempotency_key": "pay:a", "success": true, "id": "remote-a" },
+                { "idempotency_key": "pay:b", "status": "duplicate", "server_id": "remote-b" },
+                { "idempotency_key": "pay:c", "success": false, "error": "Payment exceeds order total" },
+                { "idempotency_key": "pay:d", "status": "synced", "error": "late conflict" },
+                { "success": true, "id": "remote-without-key" }
+            ]
+        }));
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(
+            results.get("pay:a"),
+            Some(&PushBatchItemOutcome::Accepted {
+                remote_id: Some("remote-a".to_string())
+            })
+        );
+        assert_eq!(
+            results.get("pay:b"),
+            Some(&PushBatchItemOutcome::Accepted {
+                remote_id: Some("remote-b".to_string())
+            })
+        );
+        assert_eq!(
+            results.get("pay:c"),
+            Some(&PushBatchItemOutcome::Rejected(
+                "Payment exceeds order total".to_string()
+            ))
+        );
+        assert!(matches!(
+            results.get("pay:d"),
+            Some(PushBatchItemOutcome::Rejected(_))
+        ));
+    }
+
+    #[test]
+    fn payment_batch_push_retries_rejected_item_singly() {
+        let db = test_db();
+        let now = "2026-05-21T07:26:26Z";
+        let queue_ids: Vec<i64> = {
+            let conn = db.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO orders (
+                     id, supabase_id, order_number, items, total_amount, total_amount_cents,
+                     status, payment_status, sync_status, created_at, updated_at
+                 ) VALUES (
+                     'ord-batch-pay', 'remote-ord-batch-pay', 'ORD-BATCH-PAY', '[]', 20.0, 2000,
+                     'completed', 'paid', 'synced', ?1, ?1
+                 )",
+                params![now],
+            )
+            .unwrap();
+            ["pay-batch-a", "pay-batch-b"]
+                .iter()
+                .map(|payment_id| {
+                    conn.execute(
+                        "INSERT INTO order_payments (
+                             id, order_id, method, amount, amount_cents, status,
+                             sync_status, sync_state, created_at, updated_at
+                         ) VALUES (
+                             ?1, 'ord-batch-pay', 'cash', 10.0, 1000, 'completed',
+                             'pending', 'pending', ?2, ?2
+                         )",
+                        params![payment_id, now],
+                    )
+                    .unwrap();
+                    let payload = serde_json::json!({
+                        "paymentId": payment_id,
+                        "orderId": "ord-batch-pay",
+                        "method": "cash",
+                        "amount": 10.0,
+                        "amount_cents": 1000
+                    })
+                    .to_string();
+                    conn.execute(
+                        "INSERT INTO sync_queue (
+                             entity_type, entity_id, operation, payload, idempotency_key, status,
+                             created_at, updated_at
+                         ) VALUES ('payment', ?1, 'insert', ?2, ?3, 'pending', ?4, ?4)",
+                        params![payment_id, payload, format!("payment:{payment_id}"), now],
+                    )
+                    .unwrap();
+                    conn.last_insert_rowid()
+                })
+                .collect()
+        };
+
+        let (mock_url, server_handle) = spawn_json_sequence_server(vec![
+            (
+                "/api/pos/payments/sync".to_string(),
+                serde_json::json!({
+                    "results": [
+                        { "idempotency_key": "payment:pay-batch-a", "success": true, "id": "remote-pay-batch-a" },
+                        { "idempotency_key": "payment:pay-batch-b", "success": false, "error": "Temporary lock" }
+                    ]
+                })
+                .to_string(),
+            ),
+            (
+                "/api/pos/payments".to_string(),
+                r#"{"success":true,"payment_id":"remote-pay-batch-b"}"#.to_string(),
+            ),
+        ]);
+        let loaded: Vec<SyncItem> = queue_ids
+            .iter()
+            .map(|id| load_sync_item(&db, *id))
+            .collect();
+        let items: Vec<&SyncItem> = loaded.iter().collect();
+        let api_key = r#"{"key":"test-key","tid":"term-batch-pay"}"#;
+        let mut stats = SyncPushStats::default();
+        let synced = tauri::async_runtime::block_on(sync_payment_items(
+            &mock_url,
+            api_key,
+            "term-batch-pay",
+            &db,
+            &items,
+            &mut stats,
+        ));
         server_handle.join().expect("join mock server");
+
+        assert_eq!(synced, 2);
+        assert_eq!(
+            stats,
+            SyncPushStats {
+                batches_sent: 1,
+                items_synced: 2,
+                items_failed: 0,
+            }
+        );
+        let conn = db.conn.lock().unwrap();
+        for (payment_id, remote_id) in [
+            ("pay-batch-a", "remote-pay-batch-a"),
+            ("pay-batch-b", "remote-pay-batch-b"),
+        ] {
+            let (sync_state, stored_remote_id): (String, Option<String>) = conn
+                .query_row(
+                    "SELECT sync_state, remote_payment_id FROM order_payments WHERE id = ?1",
+                    params![payment_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap();
+            assert_eq!(sync_state, "applied");
+            assert_eq!(stored_remote_id.as_deref(), Some(remote_id));
+        }
     }
 
     #[test]
@@ -0,0 +1,885 @@
+//! Order payload schema.
+//!
+//! `order_create` and `order_update_items` used to store whatever JSON the
+//! renderer sent; a string quantity or a line without a price only failed
+//! later in the Z-report or on the sync push. Payloads are now checked here
+//! first and every invalid field is reported at once, keyed by its path
+//! (`items[2].quantity`).
+//!
+//! Both camelCase and snake_case spellings are accepted. Unknown keys on
+//! item lines are reported as warnings, or rejected when the
+//! `orders.strict_payload_validation` setting is on. Top-level order keys are
+//! not checked for unknowns: `create_order` reads dozens of optional fields
+//! that are not part of this schema.
+//!
+//! Remote order pulls go through [`ValidationMode::Lenient`], which coerces
+//! numeric strings, drops values that cannot be coerced and never rejects.
+//!
+//! Allergy flags are only type-checked here; [`check_allergy_flags`] checks
+//! them against the configured allergen list (see `crate::allergens`).
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub const STRICT_SETTING_CATEGORY: &str = "orders";
+pub const STRICT_SETTING_KEY: &str = "strict_payload_validation";
+
+/// Highest course number an item line may carry. Lines without a course
+/// belong to course 1.
+pub const MAX_COURSE: u32 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// Invalid fields reject; unknown item keys are warnings.
+    Standard,
+    /// Unknown item keys reject as well.
+    Strict,
+    /// Coerce what can be coerced, drop the rest, report everything as
+    /// warnings.
+    Lenient,
+}
+
+impl ValidationMode {
+    /// Mode for payloads arriving over IPC.
+    pub fn for_ipc(conn: &Connection) -> Self {
+        if crate::print::setting_bool(conn, STRICT_SETTING_CATEGORY, STRICT_SETTING_KEY) {
+            Self::Strict
+        } else {
+            Self::Standard
+        }
+    }
+}
+
+/// One problem with one field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldIssue {
+    pub field: String,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl FieldIssue {
+    fn new(field: impl Into<String>, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderItemPayload {
+    #[serde(default, alias = "menu_item_id")]
+    pub menu_item_id: Option<String>,
+    #[serde(default, alias = "menu_item_name", alias = "menuItemName")]
+    pub name: Option<String>,
+    pub quantity: f64,
+    #[serde(default, alias = "unit_price", alias = "price")]
+    pub unit_price: Option<f64>,
+    #[serde(default, alias = "total_price")]
+    pub total_price: Option<f64>,
+    #[serde(default, alias = "customizations")]
+    pub modifiers: Option<Value>,
+    #[serde(default, alias = "special_instructions", alias = "specialInstructions")]
+    pub notes: Option<String>,
+    #[serde(default = "default_course")]
+    pub course: u32,
+    #[serde(default, alias = "allergy_flags")]
+    pub allergy_flags: Vec<String>,
+}
+
+fn default_course() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderCustomerPayload {
+    #[serde(default, alias = "customer_id")]
+    pub customer_id: Option<String>,
+    #[serde(default, alias = "customer_name")]
+    pub customer_name: Option<String>,
+    #[serde(default, alias = "customer_phone")]
+    pub customer_phone: Option<String>,
+    #[serde(default, alias = "customer_email")]
+    pub customer_email: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderTotalsPayload {
+    #[serde(default)]
+    pub subtotal: Option<f64>,
+    #[serde(default, alias = "tax_amount")]
+    pub tax_amount: Option<f64>,
+    #[serde(default, alias = "total_amount")]
+    pub total_amount: Option<f64>,
+    #[serde(default, alias = "discount_amount")]
+    pub discount_amount: Option<f64>,
+    #[serde(default, alias = "delivery_fee")]
+    pub delivery_fee: Option<f64>,
+    #[serde(default, alias = "service_charge")]
+    pub service_charge: Option<f64>,
+    #[serde(default, alias = "platform_commission")]
+    pub platform_commission: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderPayload {
+    #[serde(default)]
+    pub items: Vec<OrderItemPayload>,
+    #[serde(default, alias = "order_type")]
+    pub order_type: Option<String>,
+    #[serde(flatten)]
+    pub customer: OrderCustomerPayload,
+    #[serde(flatten)]
+    pub totals: OrderTotalsPayload,
+    #[serde(default, alias = "kitchen_notes")]
+    pub kitchen_notes: Option<String>,
+    #[serde(default, alias = "allergy_flags")]
+    pub allergy_flags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validated<T> {
+    pub value: T,
+    pub warnings: Vec<FieldIssue>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Text,
+    Number,
+    Quantity,
+    Collection,
+    Course,
+    /// Array of strings; lenient mode splits a comma-separated string.
+    Flags,
+}
+
+struct FieldSpec {
+    /// Key used for the typed struct.
+    canonical: &'static str,
+    /// Accepted spellings, canonical first. The first one present wins.
+    keys: &'static [&'static str],
+    kind: Kind,
+}
+
+const ITEM_FIELDS: &[FieldSpec] = &[
+    FieldSpec {
+        canonical: "menuItemId",
+        keys: &["menuItemId", "menu_item_id"],
+        kind: Kind::Text,
+    },
+    FieldSpec {
+        canonical: "name",
+        keys: &["name", "menu_item_name", "menuItemName"],
+        kind: Kind::Text,
+    },
+    FieldSpec {
+        canonical: "quantity",
+        keys: &["quantity"],
+        kind: Kind::Quantity,
+    },
+    FieldSpec {
+        canonical: "unitPrice",
+        keys: &["unitPrice", "unit_price", "price"],
+        kind: Kind::Number,
+    },
+    FieldSpec {
+        canonical: "totalPrice",
+        keys: &["totalPrice", "total_price"],
+        kind: Kind::Number,
+    },
+    FieldSpec {
+        canonical: "modifiers",
+        keys: &["modifiers", "customizations"],
+        kind: Kind::Collection,
+    },
+    FieldSpec {
+        canonical: "notes",
+        keys: &["notes", "specialInstructions", "special_instructions"],
+        kind: Kind::Text,
+    },
+    FieldSpec {
+        canonical: "course",
+        keys: &["course"],
+        kind: Kind::Course,
+    },
+    FieldSpec {
+        canonical: "allergyFlags",
+        keys: &["allergyFlags", "allergy_flags"],
+        kind: Kind::Flags,
+    },
+];
+
+/// Item keys the renderer, combo normalisation and fiscal code attach that
+/// are not part of the typed item but are not "unknown" either.
+const PASSTHROUGH_ITEM_KEYS: &[&str] = &[
+    "id",
+    "instructions",
+    "options",
+    "selectedIngredients",
+    "ingredients",
+    "originalUnitPrice",
+    "original_unit_price",
+    "originalPrice",
+    "isPriceOverridden",
+    "is_price_overridden",
+    "appliedPriceType",
+    "priceTierLabel",
+    "basePrice",
+    "categoryId",
+    "category_id",
+    "categoryName",
+    "category_name",
+    "subcategoryName",
+    "subcategory_name",
+    "categoryPath",
+    "category_path",
+    "isManual",
+    "is_manual",
+    "comboId",
+    "combo_id",
+    "comboItems",
+    "combo_items",
+    "normalized",
+    "normalizedFromTotal",
+    "vatCategoryCode",
+    "vat_category_code",
+    "priceIncludesVat",
+    "price_includes_vat",
+    "taxExemptionReason",
+    "tax_exemption_reason",
+    "fiscalDocumentProfile",
+    "fiscal_document_profile",
+];
+
+const ORDER_FIELDS: &[FieldSpec] = &[
+    FieldSpec {
+        canonical: "orderType",
+        keys: &["orderType", "order_type"],
+        kind: Kind::Text,
+    },
+    FieldSpec {
+        canonical: "customerId",
+        keys: &["customerId", "customer_id"],
+        kind: Kind::Text,
+    },
+    FieldSpec {
+        canonical: "customerName",
+        keys: &["customerName", "customer_name"],
+        kind: Kind::Text,
+    },
+    FieldSpec {
+        canonical: "customerPhone",
+        keys: &["customerPhone", "customer_phone"],
+        kind: Kind::Text,
+    },
+    FieldSpec {
+        canonical: "customerEmail",
+        keys: &["customerEmail", "customer_email"],
+        kind: Kind::Text,
+    },
+    FieldSpec {
+        canonical: "subtotal",
+        keys: &["subtotal"],
+        kind: Kind::Number,
+    },
+    FieldSpec {
+        canonical: "taxAmount",
+        keys: &["taxAmount", "tax_amount"],
+        kind: Kind::Number,
+    },
+    FieldSpec {
+        canonical: "totalAmount",
+        keys: &["totalAmount", "total_amount"],
+        kind: Kind::Number,
+    },
+    FieldSpec {
+        canonical: "discountAmount",
+        keys: &["discountAmount", "discount_amount"],
+        kind: Kind::Number,
+    },
+    FieldSpec {
+        canonical: "deliveryFee",
+        keys: &["deliveryFee", "delivery_fee"],
+        kind: Kind::Number,
+    },
+    FieldSpec {
+        canonical: "serviceCharge",
+        keys: &["serviceCharge", "service_charge"],
+        kind: Kind::Number,
+    },
+    FieldSpec {
+        canonical: "platformCommission",
+        keys: &["platformCommission", "platform_commission"],
+        kind: Kind::Number,
+    },
+    FieldSpec {
+        canonical: "kitchenNotes",
+        keys: &["kitchenNotes", "kitchen_notes"],
+        kind: Kind::Text,
+    },
+    FieldSpec {
+        canonical: "allergyFlags",
+        keys: &["allergyFlags", "allergy_flags"],
+        kind: Kind::Flags,
+    },
+];
+
+fn kind_name(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Text => "a string",
+        Kind::Number | Kind::Quantity | Kind::Course => "a number",
+        Kind::Collection => "an array or object",
+        Kind::Flags => "an array of strings",
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Lenient coercion of a value that failed the type check.
+fn coerce(value: &Value, kind: Kind) -> Option<Value> {
+    match (kind, value) {
+        (Kind::Number | Kind::Quantity | Kind::Course, Value::String(raw)) => raw
+            .trim()
+            .replace(',', ".")
+            .parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite())
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number),
+        (Kind::Text, Value::Number(_) | Value::Bool(_)) => Some(Value::String(value.to_string())),
+        (Kind::Flags, Value::String(raw)) => Some(Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|flag| !flag.is_empty())
+                .map(|flag| Value::String(flag.to_string()))
+                .collect(),
+        )),
+        (Kind::Flags, Value::Array(values)) => Some(Value::Array(
+            values
+                .iter()
+                .filter(|flag| flag.is_string())
+                .cloned()
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
+fn type_matches(value: &Value, kind: Kind) -> bool {
+    match kind {
+        Kind::Text => value.is_string(),
+        Kind::Number | Kind::Quantity | Kind::Course => value.as_f64().is_some_and(f64::is_finite),
+        Kind::Collection => value.is_array() || value.is_object(),
+        Kind::Flags => value
+            .as_array()
+            .is_some_and(|flags| flags.iter().all(Value::is_string)),
+    }
+}
+
+/// Check every spelling of every field in `object`, collecting issues into
+/// `issues`. In lenient mode bad values are coerced or removed in place.
+/// Returns the canonical object for serde.
+fn check_fields(
+    object: &mut Map<String, Value>,
+    specs: &[FieldSpec],
+    prefix: &str,
+    mode: ValidationMode,
+    issues: &mut Vec<FieldIssue>,
+) -> Map<String, Value> {
+    let mut canonical = Map::new();
+    for spec in specs {
+        for key in spec.keys {
+            let Some(value) = object.get(*key) else {
+                continue;
+            };
+            if value.is_null() {
+                continue;
+            }
+            let path = format!("{prefix}{key}");
+            let mut value = value.clone();
+            if !type_matches(&value, spec.kind) {
+                let issue = FieldIssue::new(
+                    &path,
+                    "type_mismatch",
+                    format!(
+                        "expected {}, got {}",
+                        kind_name(spec.kind),
+                        json_type(&value)
+                    ),
+                );
+                issues.push(issue);
+                if mode != ValidationMode::Lenient {
+                    continue;
+                }
+                match coerce(&value, spec.kind) {
+                    Some(coerced) => value = coerced,
+                    None => {
+                        object.remove(*key);
+                        continue;
+                    }
+                }
+                object.insert((*key).to_string(), value.clone());
+            }
+            if spec.kind == Kind::Quantity && value.as_f64().is_some_and(|q| q < 0.0) {
+                issues.push(FieldIssue::new(
+                    &path,
+                    "negative_quantity",
+                    "quantity must not be negative",
+                ));
+            }
+            if spec.kind == Kind::Course {
+                match value
+                    .as_f64()
+                    .filter(|n| n.fract() == 0.0 && (1.0..=f64::from(MAX_COURSE)).contains(n))
+                {
+                    Some(course) => value = Value::from(course as u32),
+                    None => {
+                        issues.push(FieldIssue::new(
+                            &path,
+                            "invalid_course",
+                            format!("course must be a whole number from 1 to {MAX_COURSE}"),
+                        ));
+                        if mode == ValidationMode::Lenient {
+                            object.remove(*key);
+                        }
+                        continue;
+                    }
+                }
+            }
+            canonical.entry(spec.canonical.to_string()).or_insert(value);
+        }
+    }
+    canonical
+}
+
+fn check_item(
+    item: &mut Value,
+    index: usize,
+    mode: ValidationMode,
+    errors: &mut Vec<FieldIssue>,
+    warnings: &mut Vec<FieldIssue>,
+) -> Option<OrderItemPayload> {
+    let prefix = format!("items[{index}].");
+    let Some(object) = item.as_object_mut() else {
+        let issue = FieldIssue::new(
+            format!("items[{index}]"),
+            "type_mismatch",
+            format!("expected an object, got {}", json_type(item)),
+        );
+        match mode {
+            ValidationMode::Lenient => warnings.push(issue),
+            _ => errors.push(issue),
+        }
+        return None;
+    };
+
+    // Missing means no spelling was sent at all; a present but invalid value
+    // is already reported as a type mismatch.
+    let present = |keys: &[&str]| {
+        keys.iter()
+            .any(|key| object.get(*key).is_some_and(|value| !value.is_null()))
+    };
+    let has_quantity = present(&["quantity"]);
+    let has_price = present(&[
+        "unitPrice",
+        "unit_price",
+        "price",
+        "totalPrice",
+        "total_price",
+    ]);
+
+    let mut issues = Vec::new();
+    let mut canonical = check_fields(object, ITEM_FIELDS, &prefix, mode, &mut issues);
+    if !has_quantity {
+        issues.push(FieldIssue::new(
+            format!("{prefix}quantity"),
+            "missing_field",
+            "quantity is required",
+        ));
+    }
+    // Lenient mode dropped what it could not coerce; fall back to the same
+    // default quantity the money helpers use.
+    canonical
+        .entry("quantity".to_string())
+        .or_insert_with(|| Value::from(1.0));
+    if !has_price {
+        issues.push(FieldIssue::new(
+            format!("{prefix}unitPrice"),
+            "missing_field",
+            "a unit price or total price is required",
+        ));
+    }
+    match mode {
+        ValidationMode::Lenient => warnings.append(&mut issues),
+        _ => errors.append(&mut issues),
+    }
+
+    // Remote rows carry server columns (`order_id`, `created_at`, ...);
+    // unknown keys only matter for what the renderer sends.
+    // Remote rows carry server columns (`order_id`, `created_at`, ...), so
+    // unknown keys are only reported for what the renderer sends.
+    let unknown_keys = object.keys().filter(|key| {
+        mode != ValidationMode::Lenient
+            && !ITEM_FIELDS
+                .iter()
+                .any(|spec| spec.keys.contains(&key.as_str()))
+            && !PASSTHROUGH_ITEM_KEYS.contains(&key.as_str())
+    });
+    for key in unknown_keys {
+        let issue = FieldIssue::new(
+            format!("{prefix}{key}"),
+            "unknown_key",
+            "unknown item field",
+        );
+        match mode {
+            ValidationMode::Strict => errors.push(issue),
+            _ => warnings.push(issue),
+        }
+    }
+
+    serde_json::from_value(Value::Object(canonical)).ok()
+}
+
+/// Validate order item lines. In lenient mode the lines are coerced in
+/// place and this never fails.
+pub fn validate_items(
+    items: &mut [Value],
+    mode: ValidationMode,
+) -> Result<Validated<Vec<OrderItemPayload>>, Vec<FieldIssue>> {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    let typed: Vec<OrderItemPayload> = items
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(index, item)| check_item(item, index, mode, &mut errors, &mut warnings))
+        .collect();
+    if errors.is_empty() {
+        Ok(Validated {
+            value: typed,
+            warnings,
+        })
+    } else {
+        Err(errors)
+    }
+}
+
+/// Validate a whole order payload (items, customer fields and totals).
+pub fn validate_order(
+    payload: &mut Value,
+    mode: ValidationMode,
+) -> Result<Validated<OrderPayload>, Vec<FieldIssue>> {
+    let lenient = mode == ValidationMode::Lenient;
+    let Some(object) = payload.as_object_mut() else {
+        return if lenient {
+            Ok(Validated {
+                value: OrderPayload::default(),
+                warnings: Vec::new(),
+            })
+        } else {
+            Err(vec![FieldIssue::new(
+                "",
+                "type_mismatch",
+                "expected an order object",
+            )])
+        };
+    };
+
+    let mut issues = Vec::new();
+    let canonical = check_fields(object, ORDER_FIELDS, "", mode, &mut issues);
+    let (mut errors, mut warnings) = if lenient {
+        (Vec::new(), issues)
+    } else {
+        (issues, Vec::new())
+    };
+
+    let mut items = Vec::new();
+    match object.get_mut("items") {
+        None | Some(Value::Null) => {}
+        Some(Value::Array(lines)) => match validate_items(lines, mode) {
+            Ok(validated) => {
+                items = validated.value;
+                warnings.extend(validated.warnings);
+            }
+            Err(item_errors) => errors.extend(item_errors),
+        },
+        Some(other) => {
+            let issue = FieldIssue::new(
+                "items",
+                "type_mismatch",
+                format!("expected an array, got {}", json_type(other)),
+            );
+            if lenient {
+                warnings.push(issue);
+            } else {
+                errors.push(issue);
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut order: OrderPayload =
+        serde_json::from_value(Value::Object(canonical)).unwrap_or_default();
+    order.items = items;
+    Ok(Validated {
+        value: order,
+        warnings,
+    })
+}
+
+/// Check order-level and item-level allergy flags against `allowed`,
+/// normalising the flags in place (trimmed, lowercase, deduplicated) and
+/// writing them back under `allergyFlags`. Returns one issue per flag that
+/// is not on the list. Type errors are left to [`validate_order`].
+pub fn check_allergy_flags(payload: &mut Value, allowed: &[String]) -> Vec<FieldIssue> {
+    let mut issues = Vec::new();
+    if let Some(object) = payload.as_object_mut() {
+        check_flag_keys(object, "", allowed, &mut issues);
+        if let Some(Value::Array(items)) = object.get_mut("items") {
+            issues.extend(check_item_allergy_flags(items, allowed));
+        }
+    }
+    issues
+}
+
+/// [`check_allergy_flags`] for item lines alone.
+pub fn check_item_allergy_flags(items: &mut [Value], allowed: &[String]) -> Vec<FieldIssue> {
+    let mut issues = Vec::new();
+    for (index, item) in items.iter_mut().enumerate() {
+        if let Some(item) = item.as_object_mut() {
+            check_flag_keys(item, &format!("items[{index}]."), allowed, &mut issues);
+        }
+    }
+    issues
+}
+
+fn check_flag_keys(
+    object: &mut Map<String, Value>,
+    prefix: &str,
+    allowed: &[String],
+    issues: &mut Vec<FieldIssue>,
+) {
+    let Some(key) = ["allergyFlags", "allergy_flags"]
+        .into_iter()
+        .find(|key| object.get(*key).is_some_and(Value::is_array))
+    else {
+        return;
+    };
+    let raw = object.remove(key).unwrap_or_default();
+    object.remove("allergy_flags");
+    let mut flags: Vec<String> = Vec::new();
+    for (index, flag) in raw.as_array().into_iter().flatten().enumerate() {
+        let Some(flag) = flag.as_str().map(|flag| flag.trim().to_lowercase()) else {
+            continue;
+        };
+        if flag.is_empty() || flags.contains(&flag) {
+            continue;
+        }
+        if !allowed.contains(&flag) {
+            issues.push(FieldIssue::new(
+                format!("{prefix}{key}[{index}]"),
+                "unknown_allergen",
+                format!("'{flag}' is not on the allergen list"),
+            ));
+            continue;
+        }
+        flags.push(flag);
+    }
+    object.insert("allergyFlags".to_string(), Value::from(flags));
+}
+
+/// Structured rejection returned by order commands.
+pub fn rejection_response(issues: &[FieldIssue]) -> Value {
+    let fields: Vec<&str> = issues.iter().map(|issue| issue.field.as_str()).collect();
+    serde_json::json!({
+        "success": false,
+        "errorCode": "invalid_order_payload",
+        "error": format!("Invalid order payload: {}", fields.join(", ")),
+        "issues": issues,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(issues: &[FieldIssue]) -> Vec<(String, &'static str)> {
+        issues
+            .iter()
+            .map(|issue| (issue.field.clone(), issue.code))
+            .collect()
+    }
+
+    #[test]
+    fn reports_every_invalid_field() {
+        let mut payload = serde_json::json!({
+            "customer_name": 42,
+            "totalAmount": "12.50",
+            "items": [
+                { "name": "Coffee", "quantity": "2", "unit_price": 2.5 },
+                { "name": "Tea", "quantity": -1, "price": 1.5 },
+                { "name": "Cake", "quantity": 1 },
+                "not-an-item"
+            ]
+        });
+        let issues = validate_order(&mut payload, ValidationMode::Standard).unwrap_err();
+        assert_eq!(
+            codes(&issues),
+            vec![
+                ("customer_name".to_string(), "type_mismatch"),
+                ("totalAmount".to_string(), "type_mismatch"),
+                ("items[0].quantity".to_string(), "type_mismatch"),
+                ("items[1].quantity".to_string(), "negative_quantity"),
+                ("items[2].unitPrice".to_string(), "missing_field"),
+                ("items[3]".to_string(), "type_mismatch"),
+            ]
+        );
+    }
+
+    #[test]
+    fn accepts_both_spellings_and_warns_on_unknown_keys() {
+        let mut payload = serde_json::json!({
+            "orderType": "pickup",
+            "customer_phone": "+30 210 000",
+            "items": [
+                { "menu_item_id": "m-1", "name": "Coffee", "quantity": 2,
+                  "price": 2.5, "unit_price": 2.5, "totalPrice": 5.0,
+                  "customizations": [], "isManual": false, "glitter": true }
+            ]
+        });
+        let validated = validate_order(&mut payload, ValidationMode::Standard).unwrap();
+        let order = validated.value;
+        assert_eq!(order.order_type.as_deref(), Some("pickup"));
+        assert_eq!(
+            order.customer.customer_phone.as_deref(),
+            Some("+30 210 000")
+        );
+        assert_eq!(order.items[0].menu_item_id.as_deref(), Some("m-1"));
+        assert_eq!(order.items[0].unit_price, Some(2.5));
+        assert_eq!(
+            codes(&validated.warnings),
+            vec![("items[0].glitter".to_string(), "unknown_key")]
+        );
+
+        let strict = validate_order(&mut payload, ValidationMode::Strict).unwrap_err();
+        assert_eq!(
+            codes(&strict),
+            vec![("items[0].glitter".to_string(), "unknown_key")]
+        );
+    }
+
+    #[test]
+    fn lenient_mode_coerces_and_never_rejects() {
+        let mut items = vec![
+            serde_json::json!({ "name": "Coffee", "quantity": "2", "unitPrice": "2,50" }),
+            serde_json::json!({ "name": "Tea", "quantity": "lots", "price": 1.5 }),
+        ];
+        let validated = validate_items(&mut items, ValidationMode::Lenient).unwrap();
+        assert_eq!(items[0]["quantity"], serde_json::json!(2.0));
+        assert_eq!(items[0]["unitPrice"], serde_json::json!(2.5));
+        assert!(items[1].get("quantity").is_none());
+        assert_eq!(validated.value.len(), 2);
+        assert_eq!(validated.value[1].quantity, 1.0);
+        assert!(!validated.warnings.is_empty());
+    }
+
+    #[test]
+    fn course_defaults_to_one_and_must_be_a_whole_number() {
+        let mut items = vec![
+            serde_json::json!({ "name": "Soup", "quantity": 1, "price": 4.0 }),
+            serde_json::json!({ "name": "Steak", "quantity": 1, "price": 18.0, "course": 2 }),
+        ];
+        let validated = validate_items(&mut items, ValidationMode::Standard).unwrap();
+        assert_eq!(validated.value[0].course, 1);
+        assert_eq!(validated.value[1].course, 2);
+
+        let mut bad = vec![
+            serde_json::json!({ "name": "A", "quantity": 1, "price": 1.0, "course": 0 }),
+            serde_json::json!({ "name": "B", "quantity": 1, "price": 1.0, "course": 1.5 }),
+            serde_json::json!({ "name": "C", "quantity": 1, "price": 1.0, "course": "mains" }),
+        ];
+        let issues = validate_items(&mut bad, ValidationMode::Standard).unwrap_err();
+        assert_eq!(
+            codes(&issues),
+            vec![
+                ("items[0].course".to_string(), "invalid_course"),
+                ("items[1].course".to_string(), "invalid_course"),
+                ("items[2].course".to_string(), "type_mismatch"),
+            ]
+        );
+
+        let mut remote = vec![
+            serde_json::json!({ "name": "D", "quantity": 1, "price": 1.0, "course": "3" }),
+            serde_json::json!({ "name": "E", "quantity": 1, "price": 1.0, "course": -2 }),
+        ];
+        let validated = validate_items(&mut remote, ValidationMode::Lenient).unwrap();
+        assert_eq!(validated.value[0].course, 3);
+        assert_eq!(validated.value[1].course, 1);
+        assert!(remote[1].get("course").is_none());
+    }
+
+    #[test]
+    fn allergy_flags_are_typed_normalised_and_checked_against_the_list() {
+        let allowed: Vec<String> = ["gluten", "milk", "peanuts"]
+            .iter()
+            .map(|flag| flag.to_string())
+            .collect();
+        let mut payload = serde_json::json!({
+            "kitchenNotes": "Birthday table",
+            "allergy_flags": [" Peanuts ", "peanuts"],
+            "items": [
+                { "name": "Pasta", "quantity": 1, "price": 9.0,
+                  "allergyFlags": ["Gluten", "shellfish"] }
+            ]
+        });
+        let validated = validate_order(&mut payload, ValidationMode::Strict).unwrap();
+        assert_eq!(
+            validated.value.kitchen_notes.as_deref(),
+            Some("Birthday table")
+        );
+        assert_eq!(
+            validated.value.items[0].allergy_flags,
+            vec!["Gluten", "shellfish"]
+        );
+
+        let issues = check_allergy_flags(&mut payload, &allowed);
+        assert_eq!(
+            codes(&issues),
+            vec![("items[0].allergyFlags[1]".to_string(), "unknown_allergen")]
+        );
+        assert_eq!(payload["allergyFlags"], serde_json::json!(["peanuts"]));
+        assert!(payload.get("allergy_flags").is_none());
+        assert_eq!(
+            payload["items"][0]["allergyFlags"],
+            serde_json::json!(["gluten"])
+        );
+
+        let mut bad = serde_json::json!({ "allergyFlags": "gluten" });
+        let issues = validate_order(&mut bad, ValidationMode::Standard).unwrap_err();
+        assert_eq!(
+            codes(&issues),
+            vec![("allergyFlags".to_string(), "type_mismatch")]
+        );
+        let lenient = validate_order(&mut bad, ValidationMode::Lenient).unwrap();
+        assert_eq!(lenient.value.allergy_flags, vec!["gluten"]);
+    }
+}
@@ -0,0 +1,379 @@
+//! Per-entity view of the sync backlog for `sync_get_status`.
+//!
+//! "12 pending" says nothing about whether those rows are menu overrides or
+//! a payment from three hours ago. [`entity_backlog`] groups both queues by
+//! entity in one pass each and reports, per entity:
+//!
+//! - `pending`: waiting to be sent, never failed
+//! - `inProgress`: claimed by the push running now
+//! - `failed`: failed at least once, retry scheduled (parity conflicts too)
+//! - `dead`: retries exhausted; needs an operator
+//!
+//! plus the age of the oldest unsent row and the most recent failure. The
+//! dashboard polls the status every few seconds, so the grouped query is
+//! cached for [`CACHE_TTL`].
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::db;
+
+pub const WARNING_EVENT: &str = "sync_backlog_warning";
+/// `sync.backlog_warning_minutes`: age of the oldest unsent financial row
+/// that raises [`WARNING_EVENT`]. `0` turns the warning off.
+pub const WARNING_MINUTES_KEY: &str = "backlog_warning_minutes";
+pub const DEFAULT_WARNING_MINUTES: i64 = 30;
+pub const CACHE_TTL: Duration = Duration::from_secs(5);
+
+static CACHE: Mutex<Option<(Instant, Vec<EntityBacklog>)>> = Mutex::new(None);
+static LAST_PUSH_AT: Mutex<Option<String>> = Mutex::new(None);
+static LAST_PULL_AT: Mutex<Option<String>> = Mutex::new(None);
+
+/// Separates the failure timestamp from its error in the one-pass
+/// "latest failure" aggregate.
+const FAILURE_SEPARATOR: char = '\u{1f}';
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityBacklog {
+    pub entity: String,
+    pub financial: bool,
+    pub pending: i64,
+    pub in_progress: i64,
+    pub failed: i64,
+    pub dead: i64,
+    pub oldest_pending_at: Option<String>,
+    pub oldest_pending_age_secs: Option<i64>,
+    pub last_failure_at: Option<String>,
+    pub last_failure_error: Option<String>,
+}
+
+/// Entity name for a `sync_queue.entity_type` or `parity_sync_queue.table_name`.
+fn entity_group(raw: &str) -> &str {
+    match raw {
+        "order" | "orders" => "orders",
+        "payment" | "payments" | "order_payment" | "order_payments" => "payments",
+        "payment_adjustment" | "payment_adjustments" => "adjustments",
+        "staff_payment" | "staff_payments" => "staff_payments",
+        "driver_earning" | "driver_earnings" => "driver_earnings",
+        "shift_expense" | "shift_expenses" => "shift_expenses",
+        "drawer_transaction" | "drawer_transactions" => "drawer_transactions",
+        "shift" | "shifts" | "staff_shift" | "staff_shifts" => "shifts",
+        "z_report" | "z_reports" => "z_reports",
+        other if other.starts_with("inventory") => "inventory",
+        other => other,
+    }
+}
+
+/// Entities that carry money; their age drives [`WARNING_EVENT`].
+fn is_financial(entity: &str) -> bool {
+    matches!(
+        entity,
+        "payments"
+            | "adjustments"
+            | "staff_payments"
+            | "driver_earnings"
+            | "shift_expenses"
+            | "drawer_transactions"
+            | "shifts"
+            | "z_reports"
+    )
+}
+
+/// Group both queues by entity. One grouped scan of each queue over the
+/// unsynced rows only.
+pub fn entity_backlog(conn: &Connection) -> Result<Vec<EntityBacklog>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT entity_type,
+                    SUM(CASE WHEN status IN ('pending', 'queued_remote', 'deferred', 'waiting_parent')
+                              AND last_error IS NULL THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN status = 'in_progress' THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN status IN ('pending', 'queued_remote', 'deferred', 'waiting_parent')
+                              AND last_error IS NOT NULL THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN status = 'failed' THEN 1 ELSE 0 END),
+                    MIN(CASE WHEN status <> 'failed' THEN created_at END),
+                    MAX(CASE WHEN last_error IS NOT NULL
+                             THEN strftime('%Y-%m-%dT%H:%M:%SZ', COALESCE(updated_at, created_at))
+                                  || char(31) || last_error END)
+             FROM sync_queue
+             WHERE status IN ('pending', 'queued_remote', 'deferred', 'waiting_parent',
+                              'in_progress', 'failed')
+             GROUP BY entity_type
+             UNION ALL
+             SELECT table_name,
+                    SUM(CASE WHEN status = 'pending' AND error_message IS NULL THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN status = 'processing' THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN status = 'conflict'
+                              OR (status = 'pending' AND error_message IS NOT NULL)
+                             THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN status = 'failed' THEN 1 ELSE 0 END),
+                    MIN(CASE WHEN status IN ('pending', 'processing') THEN created_at END),
+                    MAX(CASE WHEN error_message IS NOT NULL
+                             THEN strftime('%Y-%m-%dT%H:%M:%SZ', COALESCE(last_attempt, created_at))
+                                  || char(31) || error_message END)
+             FROM parity_sync_queue
+             GROUP BY table_name",
+        )
+        .map_err(|e| format!("prepare sync backlog: {e}"))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                [
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, i64>(4)?,
+                ],
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, Option<String>>(6)?,
+            ))
+        })
+        .map_err(|e| format!("query sync backlog: {e}"))?;
+
+    let mut grouped: BTreeMap<String, EntityBacklog> = BTreeMap::new();
+    for row in rows {
+        let (raw, [pending, in_progress, failed, dead], oldest, last_failure) =
+            row.map_err(|e| format!("read sync backlog: {e}"))?;
+        let entity = entity_group(&raw).to_string();
+        let entry = grouped
+            .entry(entity.clone())
+            .or_insert_with(|| EntityBacklog {
+                financial: is_financial(&entity),
+                entity,
+                ..EntityBacklog::default()
+            });
+        entry.pending += pending;
+        entry.in_progress += in_progress;
+        entry.failed += failed;
+        entry.dead += dead;
+        if let Some(oldest) = oldest {
+            if entry
+                .oldest_pending_at
+                .as_deref()
+                .map_or(true, |current| age_secs(&oldest) > age_secs(current))
+            {
+                entry.oldest_pending_age_secs = age_secs(&oldest);
+                entry.oldest_pending_at = Some(oldest);
+            }
+        }
+        if let Some((at, error)) = last_failure
+            .as_deref()
+            .and_then(|raw| raw.split_once(FAILURE_SEPARATOR))
+        {
+            if entry
+                .last_failure_at
+                .as_deref()
+                .map_or(true, |current| at > current)
+            {
+                entry.last_failure_at = Some(at.to_string());
+                entry.last_failure_error = Some(error.to_string());
+            }
+        }
+    }
+    Ok(grouped
+        .into_values()
+        .filter(|entry| entry.pending + entry.in_progress + entry.failed + entry.dead > 0)
+        .collect())
+}
+
+/// Seconds since a queue timestamp, which is either SQLite's
+/// `datetime('now')` form or RFC 3339.
+fn age_secs(timestamp: &str) -> Option<i64> {
+    let parsed = chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|at| at.with_timezone(&Utc))
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S")
+                .map(|at| at.and_utc())
+        })
+        .ok()?;
+    Some((Utc::now() - parsed).num_seconds().max(0))
+}
+
+/// [`entity_backlog`], reused for [`CACHE_TTL`].
+pub fn cached_entity_backlog(conn: &Connection) -> Result<Vec<EntityBacklog>, String> {
+    if let Ok(cache) = CACHE.lock() {
+        if let Some((at, entities)) = cache.as_ref() {
+            if at.elapsed() < CACHE_TTL {
+                return Ok(entities.clone());
+            }
+        }
+    }
+    let entities = entity_backlog(conn)?;
+    if let Ok(mut cache) = CACHE.lock() {
+        *cache = Some((Instant::now(), entities.clone()));
+    }
+    Ok(entities)
+}
+
+/// Note a push that delivered at least one row.
+pub fn record_push() {
+    if let Ok(mut last) = LAST_PUSH_AT.lock() {
+        *last = Some(Utc::now().to_rfc3339());
+    }
+}
+
+/// Note a successful pull of remote changes.
+pub fn record_pull() {
+    if let Ok(mut last) = LAST_PULL_AT.lock() {
+        *last = Some(Utc::now().to_rfc3339());
+    }
+}
+
+fn last_at(slot: &Mutex<Option<String>>) -> Option<String> {
+    slot.lock().ok().and_then(|last| last.clone())
+}
+
+/// Add `entityBacklog`, `lastPushAt` and `lastPullAt` to a sync status
+/// payload.
+pub fn insert_status_fields(conn: &Connection, status: &mut serde_json::Map<String, Value>) {
+    let entities = cached_entity_backlog(conn).unwrap_or_else(|error| {
+        tracing::warn!(error = %error, "Failed to read sync backlog breakdown");
+        Vec::new()
+    });
+    status.insert("entityBacklog".to_string(), to_json(&entities));
+    status.insert("lastPushAt".to_string(), json!(last_at(&LAST_PUSH_AT)));
+    status.insert("lastPullAt".to_string(), json!(last_at(&LAST_PULL_AT)));
+}
+
+/// Entities keyed by name.
+pub fn to_json(entities: &[EntityBacklog]) -> Value {
+    Value::Object(
+        entities
+            .iter()
+            .map(|entry| {
+                (
+                    entry.entity.clone(),
+                    serde_json::to_value(entry).unwrap_or(Value::Null),
+                )
+            })
+            .collect(),
+    )
+}
+
+pub fn warning_minutes(conn: &Connection) -> i64 {
+    db::get_setting(conn, "sync", WARNING_MINUTES_KEY)
+        .and_then(|raw| raw.trim().parse::<i64>().ok())
+        .filter(|minutes| *minutes >= 0)
+        .unwrap_or(DEFAULT_WARNING_MINUTES)
+}
+
+/// The [`WARNING_EVENT`] payload when the oldest unsent financial row is
+/// older than `threshold_minutes`, otherwise `None`.
+pub fn financial_warning(entities: &[EntityBacklog], threshold_minutes: i64) -> Option<Value> {
+    if threshold_minutes <= 0 {
+        return None;
+    }
+    let oldest = entities
+        .iter()
+        .filter(|entry| entry.financial)
+        .filter_map(|entry| entry.oldest_pending_age_secs.map(|age| (age, entry)))
+        .max_by_key(|(age, _)| *age)?;
+    let (age, entry) = oldest;
+    (age > threshold_minutes * 60).then(|| {
+        json!({
+            "entity": entry.entity,
+            "oldestPendingAt": entry.oldest_pending_at,
+            "ageSeconds": age,
+            "thresholdMinutes": threshold_minutes,
+            "pending": entry.pending + entry.in_progress + entry.failed,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn insert_legacy(
+        conn: &Connection,
+        entity_type: &str,
+        status: &str,
+        age: &str,
+        error: Option<&str>,
+    ) {
+        conn.execute(
+            "INSERT INTO sync_queue (
+                 entity_type, entity_id, operation, payload, idempotency_key,
+                 status, retry_count, max_retries, last_error, created_at, updated_at
+             ) VALUES (?1, 'e', 'insert', '{}', lower(hex(randomblob(8))), ?2, 0, 5, ?3,
+                       datetime('now', ?4), datetime('now', ?4))",
+            rusqlite::params![entity_type, status, error, age],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn backlog_groups_entities_across_both_queues() {
+        let conn = test_conn();
+        insert_legacy(&conn, "payment", "pending", "-3 hours", None);
+        insert_legacy(
+            &conn,
+            "order_payment",
+            "pending",
+            "-10 minutes",
+            Some("HTTP 503"),
+        );
+        insert_legacy(
+            &conn,
+            "payment",
+            "failed",
+            "-2 hours",
+            Some("HTTP 422: invalid amount"),
+        );
+        insert_legacy(&conn, "order", "in_progress", "-1 minutes", None);
+        insert_legacy(&conn, "order", "synced", "-5 hours", None);
+        conn.execute(
+            "INSERT INTO parity_sync_queue
+                 (id, table_name, record_id, operation, data, organization_id, created_at,
+                  status, error_message, last_attempt)
+             VALUES ('q1', 'inventory_movements', 'm1', 'INSERT', '{}', 'org',
+                     datetime('now', '-20 minutes'), 'failed', 'HTTP 500', datetime('now'))",
+            [],
+        )
+        .unwrap();
+
+        let entities = entity_backlog(&conn).unwrap();
+        let names: Vec<&str> = entities.iter().map(|e| e.entity.as_str()).collect();
+        assert_eq!(names, vec!["inventory", "orders", "payments"]);
+
+        let payments = &entities[2];
+        assert!(payments.financial);
+        assert_eq!(
+            (
+                payments.pending,
+                payments.in_progress,
+                payments.failed,
+                payments.dead
+            ),
+            (1, 0, 1, 1)
+        );
+        let age = payments.oldest_pending_age_secs.unwrap();
+        assert!((3 * 3600 - 5..=3 * 3600 + 5).contains(&age), "{age}");
+        assert_eq!(payments.last_failure_error.as_deref(), Some("HTTP 503"));
+
+        assert_eq!(entities[0].dead, 1);
+        assert_eq!(entities[0].oldest_pending_age_secs, None);
+        assert_eq!(entities[1].in_progress, 1, "synced rows are not backlog");
+
+        let warning = financial_warning(&entities, 120).unwrap();
+        assert_eq!(warning["entity"], "payments");
+        assert_eq!(warning["pending"], 2);
+        assert!(financial_warning(&entities, 240).is_none());
+        assert!(financial_warning(&entities, 0).is_none());
+        assert_eq!(warning_minutes(&conn), DEFAULT_WARNING_MINUTES);
+    }
+}
@@ -202,6 +202,10 @@ pub struct SyncQueueItem {
     /// reclaimed (lease expired) and the success ack is silently dropped.
     pub claim_generation: i64,
     pub status: String,
+    /// Correlation id of the command that enqueued the row; sent as
+    /// `X-Correlation-Id` when the row is pushed.
+    #[serde(default)]
+    pub correlation_id: Option<String>,
 }
 
 /// Input for enqueueing a new item (fields auto-populated by the queue).
@@ -522,7 +526,8 @@ pub fn create_tables(conn: &Connection) -> Result<(), String> {
             -- claim. See `project_w10_h8_claim_generation_deferred.md`.
             claim_generation INTEGER NOT NULL DEFAULT 0,
             status          TEXT NOT NULL DEFAULT 'pending'
-                CHECK (status IN ('pending', 'processing', 'failed', 'conflict'))
+                CHECK (status IN ('pending', 'processing', 'failed', 'conflict')),
+            correlation_id  TEXT
         );
 
         CREATE INDEX IF NOT EXISTS idx_parity_sq_priority_created
@@ -570,7 +575,7 @@ pub fn create_tables(conn: &Connection) -> Result<(), String> {
 /// and would fall back to a full-table scan.
 pub fn capacity_usage(conn: &Connection) -> Result<QueueCapacityUsage, String> {
     let mut stmt = conn
-        .prepare(
+        .prepare_cached(
             "SELECT status, COUNT(*)
              FROM parity_sync_queue
              WHERE status IN ('pending', 'processing', 'conflict')
@@ -675,14 +680,17 @@ pub fn enqueue(conn: &Connection, input: &EnqueueInput) -> Result<String, String
     let module_type = input.module_type.as_deref().unwrap_or("orders");
     let conflict_strategy = input.conflict_strategy.as_deref().unwrap_or("server-wins");
     let version = input.version.unwrap_or(1);
+    let correlation_id = crate::correlation::current();
 
-    conn.execute(
+    conn.prepare_cached(
         "INSERT INTO parity_sync_queue
             (id, table_name, record_id, operation, data, organization_id,
              created_at, attempts, retry_delay_ms, priority, module_type,
-             conflict_strategy, version, status)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, ?8, ?9, ?10, ?11, ?12, 'pending')",
-        params![
+             conflict_strategy, version, status, correlation_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, ?8, ?9, ?10, ?11, ?12, 'pending', ?13)",
+    )
+    .and_then(|mut stmt| {
+        stmt.execute(params![
             id,
             input.table_name,
             input.record_id,
@@ -695,8 +703,9 @@ pub fn enqueue(conn: &Connection, input: &EnqueueInput) -> Result<String, String
             module_type,
             conflict_strategy,
             version,
-        ],
-    )
+            correlation_id,
+        ])
+    })
     .map_err(|e| format!("sync_queue enqueue: {e}"))?;
 
     info!(
@@ -2922,7 +2931,7 @@ pub fn dequeue(conn: &Connection) -> Result<Option<SyncQueueItem>, String> {
             "SELECT id, table_name, record_id, operation, data, organization_id,
                     created_at, attempts, last_attempt, error_message, next_retry_at,
                     retry_delay_ms, priority, module_type, conflict_strategy, version,
-                    claim_generation, status
+                    claim_generation, status, correlation_id
              FROM parity_sync_queue
              WHERE status = 'pending'
                AND (next_retry_at IS NULL OR next_retry_at <= ?1)
@@ -2949,6 +2958,7 @@ pub fn dequeue(conn: &Connection) -> Result<Option<SyncQueueItem>, String> {
                     version: row.get(15)?,
                     claim_generation: row.get(16)?,
                     status: row.get(17)?,
+                    correlation_id: row.get(18)?,
                 })
             },
         )
@@ -3101,7 +3111,7 @@ pub fn peek(conn: &Connection) -> Result<Option<SyncQueueItem>, String> {
         "SELECT id, table_name, record_id, operation, data, organization_id,
                 created_at, attempts, last_attempt, error_message, next_retry_at,
                 retry_delay_ms, priority, module_type, conflict_strategy, version,
-                claim_generation, status
+                claim_generation, status, correlation_id
          FROM parity_sync_queue
          WHERE status = 'pending'
            AND (next_retry_at IS NULL OR next_retry_at <= ?1)
@@ -3128,6 +3138,7 @@ pub fn peek(conn: &Connection) -> Result<Option<SyncQueueItem>, String> {
                 version: row.get(15)?,
                 claim_generation: row.get(16)?,
                 status: row.get(17)?,
+                correlation_id: row.get(18)?,
             })
         },
     )
@@ -3280,7 +3291,7 @@ pub fn list_actionable_items(
         "SELECT id, table_name, record_id, operation, data, organization_id,
                 created_at, attempts, last_attempt, error_message, next_retry_at,
                 retry_delay_ms, priority, module_type, conflict_strategy, version,
-                claim_generation, status
+                claim_generation, status, correlation_id
          FROM parity_sync_queue
          WHERE status IN ('pending', 'processing', 'failed', 'conflict')
            AND module_type = ?1
@@ -3298,7 +3309,7 @@ pub fn list_actionable_items(
         "SELECT id, table_name, record_id, operation, data, organization_id,
                 created_at, attempts, last_attempt, error_message, next_retry_at,
                 retry_delay_ms, priority, module_type, conflict_strategy, version,
-                claim_generation, status
+                claim_generation, status, correlation_id
          FROM parity_sync_queue
          WHERE status IN ('pending', 'processing', 'failed', 'conflict')
          ORDER BY
@@ -3337,6 +3348,7 @@ pub fn list_actionable_items(
             version: row.get(15)?,
             claim_generation: row.get(16)?,
             status: row.get(17)?,
+            correlation_id: row.get(18)?,
         })
     };
 
@@ -3951,7 +3963,11 @@ fn prepare_request(conn: &Connection, item: &SyncQueueItem) -> Result<RequestPre
             prepare_adjustment_request(conn, item, &payload, terminal_id.as_str())
         }
         "staff_shifts" => prepare_shift_request(conn, item, &payload, terminal_id.as_str()),
-        "driver_earnings" | "driver_earning" | "shift_expenses" | "staff_payments" => {
+        "driver_earnings"
+        | "driver_earning"
+        | "shift_expenses"
+        | "staff_payments"
+        | "drawer_transactions" => {
             prepare_financial_request(conn, item, &payload, terminal_id.as_str())
         }
         "loyalty_transactions" => {
@@ -4768,6 +4784,8 @@ fn prepare_order_request(
         "driver_name",
         false,
     );
+    // Local row version, used by admin to detect stale edits.
+    copy_payload_field(&mut body, payload, &["version"], "version", false);
     for (camel, snake) in [
         ("totalAmount", "total_amount"),
         ("subtotal", "subtotal"),
@@ -5148,6 +5166,7 @@ fn financial_entity_type(table_name: &str) -> &str {
         "driver_earnings" => "driver_earning",
         "shift_expenses" => "shift_expense",
         "staff_payments" => "staff_payment",
+        "drawer_transactions" => "drawer_transaction",
         other => other,
     }
 }
@@ -6016,6 +6035,21 @@ pub async fn process_queue(
     // Check for age warnings before processing
     {
         let db = conn.lock().map_err(|e| format!("lock: {e}"))?;
+        crate::organization_change::ensure_sync_allowed(&db)?;
+        // Training rows never leave the terminal: report an empty pass.
+        if crate::training::is_training_connection(&db) {
+            let telemetry =
+                SyncTelemetryBuilder::new(started_at, get_length(&db)?).finish(&db, 0, 0, 0)?;
+            return Ok(SyncResult {
+                success: true,
+                processed: 0,
+                failed: 0,
+                conflicts: 0,
+                errors: Vec::new(),
+                monetary_dead_letters: Vec::new(),
+                telemetry,
+            });
+        }
         let _ = check_age_warnings(&db);
         let _ = recover_stale_processing_items(&db)?;
         let _ = cleanup_superseded_synced_order_status_updates(&db)?;
@@ -6059,10 +6093,7 @@ pub async fn process_queue(
         queue_depth_before = get_length(&db)?;
     }
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
-        .build()
-        .map_err(|e| format!("sync_queue client build: {e}"))?;
+    let client = crate::api::shared_client()?;
     let mut processed: i64 = 0;
     let mut failed: i64 = 0;
     let mut conflicts: i64 = 0;
@@ -6143,11 +6174,24 @@ pub async fn process_queue(
             request_spec.endpoint
         );
 
+        // Rows enqueued outside any command (or before v81) get a fresh id
+        // per push attempt.
+        let correlation_id = item
+            .correlation_id
+            .clone()
+            .unwrap_or_else(crate::correlation::new_id);
+        debug!(
+            item_id = %item.id,
+            correlation_id = %correlation_id,
+            "Pushing parity item"
+        );
         let mut request = client
             .request(request_spec.method.clone(), &url)
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
             .header("x-pos-api-key", api_key)
             .header("x-terminal-id", request_spec.terminal_id.as_str())
-            .header("Content-Type", "application/json");
+            .header("Content-Type", "application/json")
+            .header(crate::correlation::HEADER, correlation_id.as_str());
 
         if let Some(body) = request_spec.body.as_ref() {
             request = request.body(body.clone());
@@ -6234,7 +6278,7 @@ pub async fn process_queue(
                     );
                 } else if is_replay_conflict_response(status, &response_body, &item) {
                     let server_record = fetch_server_record(
-                        &client,
+                        client,
                         api_base_url,
                         api_key,
                         request_spec.terminal_id.as_str(),
@@ -6372,9 +6416,11 @@ pub async fn process_queue(
                         );
                         let mut fallback_request = client
                             .request(fallback_spec.method.clone(), &fallback_url)
+                            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
                             .header("x-pos-api-key", api_key)
                             .header("x-terminal-id", fallback_spec.terminal_id.as_str())
-                            .header("Content-Type", "application/json");
+                            .header("Content-Type", "application/json")
+                            .header(crate::correlation::HEADER, correlation_id.as_str());
 
                         if let Some(body) = fallback_spec.body.as_ref() {
                             fallback_request = fallback_request.body(body.clone());
@@ -6494,6 +6540,9 @@ pub async fn process_queue(
         let db = conn.lock().map_err(|e| format!("lock: {e}"))?;
         telemetry.finish(&db, processed, failed, conflicts)?
     };
+    if processed > 0 {
+        crate::sync_backlog::record_push();
+    }
 
     Ok(SyncResult {
         success,
@@ -6537,6 +6586,7 @@ fn resolve_endpoint(item: &SyncQueueItem) -> String {
 fn resolve_special_entity_endpoint(item: &SyncQueueItem) -> Option<String> {
     match item.table_name.as_str() {
         "inventory_adjustments" => Some("/api/pos/inventory".to_string()),
+        "inventory_movements" => Some("/api/pos/inventory/movements".to_string()),
         "coupons" => Some(match item.operation.as_str() {
             "INSERT" => "/api/pos/coupons".to_string(),
             _ => format!("/api/pos/coupons/{}", item.record_id),
@@ -6545,6 +6595,9 @@ fn resolve_special_entity_endpoint(item: &SyncQueueItem) -> Option<String> {
         "menu_subcategories" => Some(format!("/api/pos/sync/subcategories/{}", item.record_id)),
         "menu_ingredients" => Some(format!("/api/pos/sync/ingredients/{}", item.record_id)),
         "menu_combos" => Some(format!("/api/menu/combos/{}", item.record_id)),
+        "external_order_ack" => Some("/api/pos/external-orders/ack".to_string()),
+        "order_eta_updates" => Some("/api/pos/orders/eta".to_string()),
+        "customer_erasures" => Some("/api/pos/privacy/erasures".to_string()),
         "reservations" => Some(match item.operation.as_str() {
             "INSERT" => "/api/pos/reservations".to_string(),
             _ => format!("/api/pos/reservations/{}", item.record_id),
@@ -6587,6 +6640,7 @@ fn resolve_special_entity_endpoint(item: &SyncQueueItem) -> Option<String> {
             Some(format!("/api/pos/rooms/{room_id}/checkin"))
         }
         "products" => Some(format!("/api/pos/products/{}", item.record_id)),
+        "order_locks" => Some(format!("/api/pos/orders/{}/lock", item.record_id)),
         _ => None,
     }
 }
@@ -6691,9 +6745,11 @@ fn resolve_financial_endpoint(item: &SyncQueueItem) -> String {
     match item.table_name.as_str() {
         "payments" => "/api/pos/payments".to_string(),
         "payment_adjustments" => "/api/pos/payments/adjustments/sync".to_string(),
-        "driver_earnings" | "driver_earning" | "shift_expenses" | "staff_payments" => {
-            "/api/pos/financial/sync".to_string()
-        }
+        "driver_earnings"
+        | "driver_earning"
+        | "shift_expenses"
+        | "staff_payments"
+        | "drawer_transactions" => "/api/pos/financial/sync".to_string(),
         _ => "/api/pos/financial/sync".to_string(),
     }
 }
@@ -6797,9 +6853,16 @@ async fn fetch_server_record(
 
     let response = client
         .get(&endpoint)
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
         .header("x-pos-api-key", api_key)
         .header("x-terminal-id", terminal_id)
         .header("Content-Type", "application/json")
+        .header(
+            crate::correlation::HEADER,
+            item.correlation_id
+                .clone()
+                .unwrap_or_else(crate::correlation::new_id),
+        )
         .send()
         .await
         .ok()?;
@@ -6920,6 +6983,7 @@ mod tests {
             version: 1,
             claim_generation: 0,
             status: "pending".to_string(),
+            correlation_id: None,
         }
     }
 
@@ -6992,6 +7056,27 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn enqueue_records_current_correlation_id() {
+        let conn = test_connection();
+        crate::correlation::scope("corr-enqueue".to_string(), async {
+            enqueue(&conn, &capacity_enqueue_input("order-corr")).expect("enqueue");
+        })
+        .await;
+        enqueue(&conn, &capacity_enqueue_input("order-plain")).expect("enqueue");
+
+        let first = dequeue(&conn).expect("dequeue").expect("first item");
+        let second = dequeue(&conn).expect("dequeue").expect("second item");
+        let by_record = |record: &str| {
+            [&first, &second]
+                .into_iter()
+                .find(|item| item.record_id == record)
+                .and_then(|item| item.correlation_id.clone())
+        };
+        assert_eq!(by_record("order-corr").as_deref(), Some("corr-enqueue"));
+        assert_eq!(by_record("order-plain"), None);
+    }
+
     /// Bulk-insert rows directly so capacity tests do not pay the
     /// per-`enqueue` capacity COUNT + info! log 5,000 times.
     fn insert_raw_queue_rows(conn: &Connection, count: i64, status: &str) {
@@ -7839,8 +7924,29 @@ mod tests {
             "table-1",
             serde_json::json!({ "status": "occupied" }),
         );
+        let order_lock_item = queue_item(
+            "order_locks",
+            "DELETE",
+            "remote-order-1",
+            serde_json::json!({ "order_id": "remote-order-1", "terminal_id": "term-1" }),
+        );
 
+        assert_eq!(
+            resolve_endpoint(&order_lock_item),
+            "/api/pos/orders/remote-order-1/lock"
+        );
+        assert_eq!(resolve_http_method(&order_lock_item), Method::DELETE);
         assert_eq!(resolve_endpoint(&inventory_item), "/api/pos/inventory");
+        let inventory_movement = queue_item(
+            "inventory_movements",
+            "INSERT",
+            "mov-1",
+            serde_json::json!({ "id": "mov-1", "movement_type": "sale" }),
+        );
+        assert_eq!(
+            resolve_endpoint(&inventory_movement),
+            "/api/pos/inventory/movements"
+        );
         assert_eq!(resolve_endpoint(&coupon_insert), "/api/pos/coupons");
         assert_eq!(
             resolve_endpoint(&coupon_update),
@@ -8145,6 +8251,7 @@ mod tests {
                 "orderId": "order-fiscal-receipt",
                 "status": "completed",
                 "fiscalReceiptNumber": "FISC-000123",
+                "version": 4,
             }),
         );
         let payload = serde_json::from_str::<Value>(&item.data).expect("parse payload");
@@ -8162,6 +8269,7 @@ mod tests {
             body.get("fiscal_receipt_number").and_then(Value::as_str),
             Some("FISC-000123")
         );
+        assert_eq!(body.get("version").and_then(Value::as_i64), Some(4));
     }
 
     #[test]
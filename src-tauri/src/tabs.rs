@@ -0,0 +1,490 @@
+//! Bar tabs: an order held open against a card and settled in one capture.
+//!
+//! Opening a tab pre-authorizes a hold on the card through the ECR (the
+//! `payments.tab_hold_amount` setting, 50.00 by default) or, when the
+//! driver cannot pre-authorize, records a card reference typed in by the
+//! operator. The order reads `payment_status = 'tab_open'` until something
+//! is paid on it. Closing a tab captures the outstanding balance against the
+//! hold ([`capture_plan`] picks the ECR steps from the driver's
+//! capabilities), records the card payment, and the acquirer releases the
+//! unused part of the hold.
+//!
+//! The ECR exchange itself happens in the command layer; this module owns
+//! the `order_tabs` rows.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Value};
+
+use crate::db;
+use crate::ecr::protocol::EcrCapabilities;
+use crate::money::Cents;
+use crate::order_events;
+
+pub const MODE_PRE_AUTH: &str = "pre_auth";
+pub const MODE_MANUAL: &str = "manual";
+pub const PAYMENT_STATUS_TAB_OPEN: &str = "tab_open";
+pub const UPDATED_EVENT: &str = "tab_updated";
+
+const HOLD_SETTING_CATEGORY: &str = "payments";
+const HOLD_SETTING_KEY: &str = "tab_hold_amount";
+const DEFAULT_HOLD_AMOUNT: f64 = 50.0;
+
+/// An open tab.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tab {
+    pub id: String,
+    pub order_id: String,
+    pub mode: String,
+    pub device_id: Option<String>,
+    pub hold_amount_cents: i64,
+    pub card_reference: Option<String>,
+    pub card_last_four: Option<String>,
+    pub authorization_code: Option<String>,
+    pub terminal_reference: Option<String>,
+    pub ecr_transaction_id: Option<String>,
+    pub opened_at: String,
+}
+
+impl Tab {
+    /// The reference the terminal needs to complete or void the hold.
+    pub fn original_transaction_id(&self) -> Option<String> {
+        self.terminal_reference
+            .clone()
+            .or_else(|| self.authorization_code.clone())
+            .or_else(|| self.ecr_transaction_id.clone())
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "orderId": self.order_id,
+            "mode": self.mode,
+            "deviceId": self.device_id,
+            "holdAmount": Cents::new(self.hold_amount_cents).to_f64_dp2(),
+            "cardReference": self.card_reference,
+            "cardLastFour": self.card_last_four,
+            "authorizationCode": self.authorization_code,
+            "terminalReference": self.terminal_reference,
+            "openedAt": self.opened_at,
+        })
+    }
+}
+
+/// One ECR step of closing a pre-authorized tab.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureStep {
+    /// Raise the hold by this many cents.
+    Increment(i64),
+    /// Complete the pre-authorization for this many cents; the acquirer
+    /// releases whatever is left of the hold.
+    Complete(i64),
+    /// Nothing to capture: void the pre-authorization to release the hold.
+    Release,
+}
+
+/// The configured hold for new tabs.
+pub fn hold_amount(conn: &Connection) -> f64 {
+    db::get_setting(conn, HOLD_SETTING_CATEGORY, HOLD_SETTING_KEY)
+        .and_then(|raw| raw.trim().parse::<f64>().ok())
+        .filter(|amount| amount.is_finite() && *amount > 0.0)
+        .unwrap_or(DEFAULT_HOLD_AMOUNT)
+}
+
+/// ECR steps that capture `amount_cents` against a hold of `hold_cents`.
+///
+/// A total above the hold needs an incremental authorization first; a
+/// driver without one gets an error telling the operator to take the
+/// difference as a separate payment, which brings the balance back under
+/// the hold.
+pub fn capture_plan(
+    hold_cents: i64,
+    amount_cents: i64,
+    capabilities: EcrCapabilities,
+    device_id: &str,
+) -> Result<Vec<CaptureStep>, String> {
+    if amount_cents <= 0 {
+        if !capabilities.void {
+            return Err(format!(
+                "ECR device '{device_id}' cannot void a pre-authorization; release the hold on the terminal"
+            ));
+        }
+        return Ok(vec![CaptureStep::Release]);
+    }
+    if !capabilities.pre_auth_completion {
+        return Err(format!(
+            "ECR device '{device_id}' cannot complete a pre-authorization"
+        ));
+    }
+    if amount_cents <= hold_cents {
+        return Ok(vec![CaptureStep::Complete(amount_cents)]);
+    }
+    if !capabilities.incremental_auth {
+        return Err(format!(
+            "Tab total {:.2} exceeds the pre-authorized {:.2} and ECR device '{device_id}' cannot raise the hold; record a payment for the difference of {:.2} first",
+            Cents::new(amount_cents).to_f64_dp2(),
+            Cents::new(hold_cents).to_f64_dp2(),
+            Cents::new(amount_cents - hold_cents).to_f64_dp2(),
+        ));
+    }
+    Ok(vec![
+        CaptureStep::Increment(amount_cents - hold_cents),
+        CaptureStep::Complete(amount_cents),
+    ])
+}
+
+/// Refuse orders that cannot take a tab, before any hold is placed.
+pub fn ensure_can_open(conn: &Connection, order_id: &str) -> Result<(), String> {
+    let (status, payment_status): (String, String) = conn
+        .query_row(
+            "SELECT COALESCE(status, ''), COALESCE(payment_status, 'pending')
+             FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("load order for tab: {e}"))?
+        .ok_or_else(|| format!("Order not found: {order_id}"))?;
+    if matches!(status.as_str(), "cancelled" | "canceled" | "refunded") {
+        return Err(format!("Cannot open a tab on a {status} order"));
+    }
+    if payment_status == "paid" {
+        return Err("Cannot open a tab on a paid order".into());
+    }
+    if load_open(conn, order_id)?.is_some() {
+        return Err("This order already has an open tab".into());
+    }
+    Ok(())
+}
+
+/// Store a newly opened tab and mark its order `tab_open`.
+pub fn open(conn: &Connection, tab: &Tab, opened_by: Option<&str>) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO order_tabs (
+            id, order_id, branch_id, terminal_id, mode, device_id,
+            hold_amount, hold_amount_cents, card_reference, card_last_four,
+            authorization_code, terminal_reference, ecr_transaction_id,
+            status, opened_by, opened_at, updated_at
+         )
+         SELECT ?1, id, branch_id, terminal_id, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
+                'open', ?11, ?12, ?12
+         FROM orders WHERE id = ?13",
+        params![
+            tab.id,
+            tab.mode,
+            tab.device_id,
+            Cents::new(tab.hold_amount_cents).to_f64_dp2(),
+            tab.hold_amount_cents,
+            tab.card_reference,
+            tab.card_last_four,
+            tab.authorization_code,
+            tab.terminal_reference,
+            tab.ecr_transaction_id,
+            opened_by,
+            tab.opened_at,
+            tab.order_id,
+        ],
+    )
+    .map_err(|e| format!("insert order tab: {e}"))?;
+    conn.execute(
+        "UPDATE orders SET payment_status = ?1, updated_at = ?2
+         WHERE id = ?3 AND COALESCE(payment_status, 'pending') = 'pending'",
+        params![PAYMENT_STATUS_TAB_OPEN, tab.opened_at, tab.order_id],
+    )
+    .map_err(|e| format!("mark order tab_open: {e}"))?;
+    order_events::append(
+        conn,
+        &tab.order_id,
+        order_events::TAB_OPENED,
+        opened_by,
+        json!({
+            "tabId": tab.id,
+            "mode": tab.mode,
+            "holdAmount": Cents::new(tab.hold_amount_cents).to_f64_dp2(),
+        }),
+    );
+    Ok(())
+}
+
+/// The open tab on an order, if any.
+pub fn load_open(conn: &Connection, order_id: &str) -> Result<Option<Tab>, String> {
+    conn.query_row(
+        "SELECT id, order_id, mode, device_id, hold_amount_cents, card_reference,
+                card_last_four, authorization_code, terminal_reference,
+                ecr_transaction_id, opened_at
+         FROM order_tabs WHERE order_id = ?1 AND status = 'open'",
+        params![order_id],
+        |row| {
+            Ok(Tab {
+                id: row.get(0)?,
+                order_id: row.get(1)?,
+                mode: row.get(2)?,
+                device_id: row.get(3)?,
+                hold_amount_cents: row.get(4)?,
+                card_reference: row.get(5)?,
+                card_last_four: row.get(6)?,
+                authorization_code: row.get(7)?,
+                terminal_reference: row.get(8)?,
+                ecr_transaction_id: row.get(9)?,
+                opened_at: row.get(10)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("load open tab: {e}"))
+}
+
+/// Whether the order has an open tab. A missing table reads as no tab.
+pub fn is_open(conn: &Connection, order_id: &str) -> bool {
+    matches!(load_open(conn, order_id), Ok(Some(_)))
+}
+
+/// Mark a tab captured (or released, when `captured_cents` is zero).
+pub fn close(
+    conn: &Connection,
+    tab: &Tab,
+    captured_cents: i64,
+    payment_id: Option<&str>,
+    closed_by: Option<&str>,
+    now: &str,
+) -> Result<(), String> {
+    let changed = conn
+        .execute(
+            "UPDATE order_tabs SET
+                status = 'closed',
+                captured_amount = ?1,
+                captured_amount_cents = ?2,
+                payment_id = ?3,
+                closed_by = ?4,
+                closed_at = ?5,
+                updated_at = ?5
+             WHERE id = ?6 AND status = 'open'",
+            params![
+                Cents::new(captured_cents).to_f64_dp2(),
+                captured_cents,
+                payment_id,
+                closed_by,
+                now,
+                tab.id,
+            ],
+        )
+        .map_err(|e| format!("close order tab: {e}"))?;
+    if changed == 0 {
+        return Err("Tab is no longer open".into());
+    }
+    conn.execute(
+        "UPDATE orders SET payment_status = 'pending', updated_at = ?1
+         WHERE id = ?2 AND payment_status = ?3",
+        params![now, tab.order_id, PAYMENT_STATUS_TAB_OPEN],
+    )
+    .map_err(|e| format!("clear order tab_open: {e}"))?;
+    order_events::append(
+        conn,
+        &tab.order_id,
+        order_events::TAB_CLOSED,
+        closed_by,
+        json!({
+            "tabId": tab.id,
+            "capturedAmount": Cents::new(captured_cents).to_f64_dp2(),
+            "releasedAmount": Cents::new((tab.hold_amount_cents - captured_cents).max(0))
+                .to_f64_dp2(),
+            "paymentId": payment_id,
+        }),
+    );
+    Ok(())
+}
+
+/// Open tabs, oldest first, with their order and age in minutes.
+pub fn list_open(
+    conn: &Connection,
+    branch_id: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Vec<Value>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.order_id, t.mode, t.device_id, t.hold_amount_cents,
+                    t.card_reference, t.card_last_four, t.authorization_code,
+                    t.terminal_reference, t.ecr_transaction_id, t.opened_at,
+                    COALESCE(o.display_order_number, o.order_number), o.table_number,
+                    COALESCE(o.total_amount_cents, CAST(ROUND(o.total_amount * 100) AS INTEGER), 0)
+             FROM order_tabs t
+             JOIN orders o ON o.id = t.order_id
+             WHERE t.status = 'open' AND (?1 IS NULL OR t.branch_id = ?1)
+             ORDER BY t.opened_at, t.id",
+        )
+        .map_err(|e| format!("prepare open tabs: {e}"))?;
+    let rows = stmt
+        .query_map(params![branch_id], |row| {
+            let tab = Tab {
+                id: row.get(0)?,
+                order_id: row.get(1)?,
+                mode: row.get(2)?,
+                device_id: row.get(3)?,
+                hold_amount_cents: row.get(4)?,
+                card_reference: row.get(5)?,
+                card_last_four: row.get(6)?,
+                authorization_code: row.get(7)?,
+                terminal_reference: row.get(8)?,
+                ecr_transaction_id: row.get(9)?,
+                opened_at: row.get(10)?,
+            };
+            let order_number: Option<String> = row.get(11)?;
+            let table_number: Option<String> = row.get(12)?;
+            let total_cents: i64 = row.get(13)?;
+            Ok((tab, order_number, table_number, total_cents))
+        })
+        .map_err(|e| format!("query open tabs: {e}"))?;
+
+    let mut tabs = Vec::new();
+    for row in rows {
+        let (tab, order_number, table_number, total_cents) =
+            row.map_err(|e| format!("read open tab: {e}"))?;
+        let age_minutes = DateTime::parse_from_rfc3339(&tab.opened_at)
+            .map(|opened| (now - opened.with_timezone(&Utc)).num_minutes().max(0))
+            .unwrap_or(0);
+        let mut value = tab.to_json();
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("orderNumber".into(), json!(order_number));
+            obj.insert("tableNumber".into(), json!(table_number));
+            obj.insert(
+                "orderTotal".into(),
+                json!(Cents::new(total_cents).to_f64_dp2()),
+            );
+            obj.insert("ageMinutes".into(), json!(age_minutes));
+        }
+        tabs.push(value);
+    }
+    Ok(tabs)
+}
+
+/// Audit a manager closing a shift over open tabs: one event per tab's
+/// order naming the shift and the approving manager.
+pub fn record_shift_close_override(
+    conn: &Connection,
+    open_tabs: &[Value],
+    shift_id: &str,
+    manager_staff_id: Option<&str>,
+) {
+    for tab in open_tabs {
+        let Some(order_id) = tab.get("orderId").and_then(Value::as_str) else {
+            continue;
+        };
+        order_events::append(
+            conn,
+            order_id,
+            order_events::TAB_SHIFT_CLOSE_OVERRIDDEN,
+            manager_staff_id,
+            json!({
+                "tabId": tab.get("id"),
+                "shiftId": shift_id,
+                "ageMinutes": tab.get("ageMinutes"),
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pre_auth_tab(order_id: &str, hold_cents: i64) -> Tab {
+        Tab {
+            id: format!("tab-{order_id}"),
+            order_id: order_id.into(),
+            mode: MODE_PRE_AUTH.into(),
+            device_id: Some("dev-1".into()),
+            hold_amount_cents: hold_cents,
+            card_reference: None,
+            card_last_four: Some("4242".into()),
+            authorization_code: Some("AUTH1".into()),
+            terminal_reference: Some("REF1".into()),
+            ecr_transaction_id: Some("txn-1".into()),
+            opened_at: "2026-10-15T20:00:00+00:00".into(),
+        }
+    }
+
+    #[test]
+    fn capture_plan_follows_driver_capabilities() {
+        let completion_only = EcrCapabilities {
+            pre_auth: true,
+            pre_auth_completion: true,
+            ..EcrCapabilities::default()
+        };
+        assert_eq!(
+            capture_plan(5000, 3250, completion_only, "dev-1"),
+            Ok(vec![CaptureStep::Complete(3250)])
+        );
+        let err = capture_plan(5000, 6000, completion_only, "dev-1").unwrap_err();
+        assert!(err.contains("difference of 10.00"), "{err}");
+        assert!(capture_plan(5000, 0, completion_only, "dev-1").is_err());
+
+        let full = EcrCapabilities {
+            incremental_auth: true,
+            void: true,
+            ..completion_only
+        };
+        assert_eq!(
+            capture_plan(5000, 6000, full, "dev-1"),
+            Ok(vec![
+                CaptureStep::Increment(1000),
+                CaptureStep::Complete(6000)
+            ])
+        );
+        assert_eq!(
+            capture_plan(5000, 0, full, "dev-1"),
+            Ok(vec![CaptureStep::Release])
+        );
+        assert!(capture_plan(5000, 100, EcrCapabilities::default(), "dev-1").is_err());
+    }
+
+    #[test]
+    fn open_list_and_close_track_order_status() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations_for_test(&conn);
+        conn.execute(
+            "INSERT INTO orders (id, order_number, items, total_amount, total_amount_cents,
+                                 status, payment_status, branch_id, created_at, updated_at)
+             VALUES ('ord-1', 'ORD-1', '[]', 42.5, 4250, 'pending', 'pending', 'branch-1',
+                     datetime('now'), datetime('now'))",
+            [],
+        )
+        .unwrap();
+        assert_eq!(hold_amount(&conn), DEFAULT_HOLD_AMOUNT);
+
+        ensure_can_open(&conn, "ord-1").unwrap();
+        let tab = pre_auth_tab("ord-1", 5000);
+        open(&conn, &tab, Some("staff-1")).unwrap();
+        assert!(ensure_can_open(&conn, "ord-1").is_err());
+        assert!(is_open(&conn, "ord-1"));
+        let payment_status: String = conn
+            .query_row(
+                "SELECT payment_status FROM orders WHERE id = 'ord-1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(payment_status, PAYMENT_STATUS_TAB_OPEN);
+
+        let now = DateTime::parse_from_rfc3339("2026-10-15T21:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let listed = list_open(&conn, Some("branch-1"), now).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0]["ageMinutes"], json!(90));
+        assert_eq!(listed[0]["orderTotal"], json!(42.5));
+        assert!(list_open(&conn, Some("branch-2"), now).unwrap().is_empty());
+
+        close(
+            &conn,
+            &tab,
+            4250,
+            Some("pay-1"),
+            None,
+            "2026-10-15T21:30:00Z",
+        )
+        .unwrap();
+        assert!(!is_open(&conn, "ord-1"));
+        assert!(close(&conn, &tab, 4250, None, None, "2026-10-15T21:31:00Z").is_err());
+        assert!(list_open(&conn, None, now).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,609 @@
+//! Per-rate tax configuration and order tax breakdowns.
+//!
+//! The configuration lives in `local_settings`:
+//! - `tax.config` — JSON with the list of rates (`id`, `label`, `rate` in
+//!   percent), the default rate and per-category / per-item assignments;
+//! - `general.tax_mode` — `inclusive` (menu prices already contain tax) or
+//!   `exclusive` (tax is added on top).
+//!
+//! A menu item can also carry its own `tax_rate_id` through the menu sync,
+//! which wins over the local mappings. The breakdown is computed once when
+//! an order is created or its items are edited and stored on
+//! `orders.tax_breakdown`, so later rate changes never rewrite existing
+//! orders.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::db;
+use crate::money::{self, Cents, RoundingRule};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaxMode {
+    #[default]
+    Inclusive,
+    Exclusive,
+}
+
+impl TaxMode {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "inclusive" | "incl" | "gross" => Some(Self::Inclusive),
+            "exclusive" | "excl" | "net" => Some(Self::Exclusive),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Inclusive => "inclusive",
+            Self::Exclusive => "exclusive",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaxRate {
+    pub id: String,
+    #[serde(default)]
+    pub label: String,
+    /// Percentage, e.g. `13.0` for 13% VAT.
+    pub rate: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaxConfig {
+    #[serde(default)]
+    pub mode: TaxMode,
+    #[serde(default)]
+    pub rates: Vec<TaxRate>,
+    #[serde(default, alias = "default_rate_id")]
+    pub default_rate_id: Option<String>,
+    /// Menu category id → rate id.
+    #[serde(default, alias = "category_rates")]
+    pub category_rates: BTreeMap<String, String>,
+    /// Menu item (subcategory) id → rate id.
+    #[serde(default, alias = "item_rates")]
+    pub item_rates: BTreeMap<String, String>,
+}
+
+impl TaxConfig {
+    fn rate(&self, id: &str) -> Option<&TaxRate> {
+        let id = id.trim();
+        self.rates.iter().find(|rate| rate.id == id)
+    }
+
+    /// Build the single-rate configuration that mirrors the legacy
+    /// `general.tax_rate` setting.
+    pub fn from_legacy_rate(rate: Option<f64>) -> Self {
+        match rate.filter(|rate| rate.is_finite() && *rate > 0.0) {
+            Some(rate) => Self {
+                rates: vec![TaxRate {
+                    id: "standard".to_string(),
+                    label: "Standard".to_string(),
+                    rate,
+                }],
+                default_rate_id: Some("standard".to_string()),
+                ..Self::default()
+            },
+            None => Self::default(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let mut seen = std::collections::HashSet::new();
+        for rate in &self.rates {
+            if rate.id.trim().is_empty() {
+                return Err("Tax rate id cannot be empty".into());
+            }
+            if !seen.insert(rate.id.as_str()) {
+                return Err(format!("Duplicate tax rate id: {}", rate.id));
+            }
+            if !rate.rate.is_finite() || !(0.0..=100.0).contains(&rate.rate) {
+                return Err(format!(
+                    "Tax rate {} must be between 0 and 100 percent",
+                    rate.id
+                ));
+            }
+        }
+        let assigned = self
+            .default_rate_id
+            .iter()
+            .chain(self.category_rates.values())
+            .chain(self.item_rates.values());
+        for rate_id in assigned {
+            if self.rate(rate_id).is_none() {
+                return Err(format!("Unknown tax rate id: {rate_id}"));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn load_config(conn: &Connection) -> TaxConfig {
+    let mut config = db::get_setting(conn, "tax", "config")
+        .and_then(|raw| serde_json::from_str::<TaxConfig>(&raw).ok())
+        .unwrap_or_else(|| {
+            let legacy = db::get_setting(conn, "general", "tax_rate")
+                .and_then(|raw| raw.trim().parse::<f64>().ok());
+            TaxConfig::from_legacy_rate(legacy)
+        });
+    config.mode = db::get_setting(conn, "general", "tax_mode")
+        .and_then(|raw| TaxMode::parse(&raw))
+        .unwrap_or_default();
+    config
+}
+
+pub fn save_config(conn: &Connection, config: &TaxConfig) -> Result<(), String> {
+    config.validate()?;
+    let raw = serde_json::to_string(config).map_err(|e| format!("serialize tax config: {e}"))?;
+    db::set_setting(conn, "tax", "config", &raw)?;
+    db::set_setting(conn, "general", "tax_mode", config.mode.as_str())?;
+    // Keep the legacy single-rate setting pointing at the default rate for
+    // callers that still read it.
+    if let Some(rate) = config
+        .default_rate_id
+        .as_deref()
+        .and_then(|id| config.rate(id))
+    {
+        db::set_setting(conn, "general", "tax_rate", &rate.rate.to_string())?;
+    }
+    Ok(())
+}
+
+/// One rate's share of an order, captured at sale time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaxBreakdownLine {
+    pub rate_id: String,
+    pub label: String,
+    /// Rate in basis points (1300 = 13%), so lines compare exactly.
+    pub rate_bps: i64,
+    pub net_cents: i64,
+    pub tax_cents: i64,
+    pub gross_cents: i64,
+}
+
+impl TaxBreakdownLine {
+    pub fn rate_percent(&self) -> f64 {
+        self.rate_bps as f64 / 100.0
+    }
+}
+
+/// Menu-sync data the rate resolution needs: each menu item's category and
+/// any rate id the admin attached to it.
+#[derive(Debug, Default)]
+pub struct MenuTaxLookup {
+    category_by_item: HashMap<String, String>,
+    rate_by_item: HashMap<String, String>,
+}
+
+fn text(value: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .find_map(|key| value.get(*key).and_then(Value::as_str))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+impl MenuTaxLookup {
+    pub fn load(conn: &Connection) -> Self {
+        let mut lookup = Self::default();
+        let raw: Option<String> = conn
+            .query_row(
+                "SELECT data FROM menu_cache WHERE cache_key = 'subcategories'",
+                [],
+                |row| row.get(0),
+            )
+            .ok();
+        let entries = raw
+            .and_then(|data| serde_json::from_str::<Value>(&data).ok())
+            .and_then(|value| value.as_array().cloned())
+            .unwrap_or_default();
+        for entry in entries {
+            let Some(id) = text(&entry, &["id", "subcategory_id", "menu_item_id"]) else {
+                continue;
+            };
+            if let Some(category) = text(&entry, &["category_id", "categoryId"]) {
+                lookup.category_by_item.insert(id.clone(), category);
+            }
+            if let Some(rate_id) = text(&entry, &["tax_rate_id", "taxRateId"]) {
+                lookup.rate_by_item.insert(id, rate_id);
+            }
+        }
+        lookup
+    }
+}
+
+fn resolve_rate<'a>(
+    config: &'a TaxConfig,
+    menu: &MenuTaxLookup,
+    item: &Value,
+) -> Option<&'a TaxRate> {
+    let menu_item_id = text(item, &["menu_item_id", "menuItemId"]);
+    let category_id = text(item, &["category_id", "categoryId"]).or_else(|| {
+        menu_item_id
+            .as_deref()
+            .and_then(|id| menu.category_by_item.get(id).cloned())
+    });
+    text(item, &["tax_rate_id", "taxRateId"])
+        .and_then(|id| config.rate(&id))
+        .or_else(|| {
+            let id = menu_item_id.as_deref()?;
+            menu.rate_by_item
+                .get(id)
+                .or_else(|| config.item_rates.get(id))
+                .and_then(|rate_id| config.rate(rate_id))
+        })
+        .or_else(|| {
+            category_id
+                .as_deref()
+                .and_then(|id| config.category_rates.get(id))
+                .and_then(|rate_id| config.rate(rate_id))
+        })
+        .or_else(|| {
+            config
+                .default_rate_id
+                .as_deref()
+                .and_then(|id| config.rate(id))
+        })
+}
+
+/// Split order lines by tax rate. Lines are rounded once (see
+/// [`money::item_line_cents`]), summed per rate, and tax is rounded once
+/// per rate. Lines without a resolvable rate are left out.
+pub fn compute_breakdown(
+    config: &TaxConfig,
+    menu: &MenuTaxLookup,
+    items: &[Value],
+    rule: RoundingRule,
+) -> Vec<TaxBreakdownLine> {
+    let mut grouped: Vec<(&TaxRate, Cents)> = Vec::new();
+    for item in items {
+        let Some(rate) = resolve_rate(config, menu, item) else {
+            continue;
+        };
+        let amount = money::item_line_cents(item, rule);
+        match grouped
+            .iter_mut()
+            .find(|(existing, _)| existing.id == rate.id)
+        {
+            Some((_, total)) => *total += amount,
+            None => grouped.push((rate, amount)),
+        }
+    }
+
+    grouped
+        .into_iter()
+        .map(|(rate, amount)| {
+            let amount_major = amount.to_f64_dp2();
+            let (net, tax, gross) = match config.mode {
+                TaxMode::Inclusive => {
+                    let tax = Cents::round_with(
+                        amount_major * rate.rate / (100.0 + rate.rate),
+                        RoundingRule::HalfUp,
+                    );
+                    (amount - tax, tax, amount)
+                }
+                TaxMode::Exclusive => {
+                    let tax =
+                        Cents::round_with(amount_major * rate.rate / 100.0, RoundingRule::HalfUp);
+                    (amount, tax, amount + tax)
+                }
+            };
+            TaxBreakdownLine {
+                rate_id: rate.id.clone(),
+                label: if rate.label.trim().is_empty() {
+                    rate.id.clone()
+                } else {
+                    rate.label.clone()
+                },
+                rate_bps: (rate.rate * 100.0).round() as i64,
+                net_cents: net.as_i64(),
+                tax_cents: tax.as_i64(),
+                gross_cents: gross.as_i64(),
+            }
+        })
+        .collect()
+}
+
+/// An order's breakdown with the tax and total stored alongside it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedTax {
+    pub lines: Vec<TaxBreakdownLine>,
+    pub tax: Cents,
+    pub total: Cents,
+}
+
+/// Compute and store the breakdown for an order from its current items,
+/// plus its service charge when that is taxable.
+///
+/// Once any line resolves to a rate, the order's tax is the breakdown's
+/// tax. In exclusive mode the total moves by the same difference: the
+/// stored total is the net prices plus the stored tax, so swapping the tax
+/// keeps every other offset (fees, discounts, tips) intact. Without a
+/// resolvable rate the stored tax and total are left alone.
+pub fn capture_order_breakdown(
+    conn: &Connection,
+    order_id: &str,
+    items: &[Value],
+) -> Result<CapturedTax, String> {
+    let config = load_config(conn);
+    let mut items = items.to_vec();
+    items.extend(crate::service_charge::taxable_line(conn, order_id));
+    let lines = compute_breakdown(
+        &config,
+        &MenuTaxLookup::load(conn),
+        &items,
+        RoundingRule::from_settings(conn),
+    );
+    let (stored_total, stored_tax): (f64, f64) = conn
+        .query_row(
+            "SELECT COALESCE(total_amount, 0), COALESCE(tax_amount, 0)
+             FROM orders WHERE id = ?1",
+            params![order_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("load order totals for tax: {e}"))?;
+    let stored_total = Cents::round_half_even(stored_total);
+    let stored_tax = Cents::round_half_even(stored_tax);
+    let (tax, total) = if lines.is_empty() {
+        (stored_tax, stored_total)
+    } else {
+        let tax: Cents = lines.iter().map(|line| Cents::new(line.tax_cents)).sum();
+        let total = match config.mode {
+            TaxMode::Inclusive => stored_total,
+            TaxMode::Exclusive => (stored_total - stored_tax + tax).max(Cents::ZERO),
+        };
+        (tax, total)
+    };
+
+    let raw = serde_json::to_string(&lines).map_err(|e| format!("serialize tax breakdown: {e}"))?;
+    conn.execute(
+        "UPDATE orders SET
+             tax_breakdown = ?1,
+             tax_amount = ?2, tax_amount_cents = ?3,
+             total_amount = ?4, total_amount_cents = ?5
+         WHERE id = ?6",
+        params![
+            raw,
+            tax.to_f64_dp2(),
+            tax.as_i64(),
+            total.to_f64_dp2(),
+            total.as_i64(),
+            order_id
+        ],
+    )
+    .map_err(|e| format!("store tax breakdown: {e}"))?;
+    Ok(CapturedTax { lines, tax, total })
+}
+
+pub fn parse_breakdown(raw: Option<&str>) -> Vec<TaxBreakdownLine> {
+    raw.and_then(|raw| serde_json::from_str(raw).ok())
+        .unwrap_or_default()
+}
+
+/// Sum stored breakdowns per rate. A rate id whose percentage changed
+/// mid-day stays split into one line per percentage.
+pub fn aggregate_breakdowns<'a>(
+    breakdowns: impl IntoIterator<Item = &'a str>,
+) -> Vec<TaxBreakdownLine> {
+    let mut totals: Vec<TaxBreakdownLine> = Vec::new();
+    for line in breakdowns
+        .into_iter()
+        .flat_map(|raw| parse_breakdown(Some(raw)))
+    {
+        match totals
+            .iter_mut()
+            .find(|total| total.rate_id == line.rate_id && total.rate_bps == line.rate_bps)
+        {
+            Some(total) => {
+                total.net_cents += line.net_cents;
+                total.tax_cents += line.tax_cents;
+                total.gross_cents += line.gross_cents;
+            }
+            None => totals.push(line),
+        }
+    }
+    totals.sort_by(|a, b| a.rate_bps.cmp(&b.rate_bps).then(a.rate_id.cmp(&b.rate_id)));
+    totals
+}
+
+/// JSON shape used in command responses and the Z-report: the stored cents
+/// plus major-unit floats for display.
+pub fn breakdown_json(lines: &[TaxBreakdownLine]) -> Value {
+    Value::Array(
+        lines
+            .iter()
+            .map(|line| {
+                serde_json::json!({
+                    "rateId": line.rate_id,
+                    "label": line.label,
+                    "rate": line.rate_percent(),
+                    "net": Cents::new(line.net_cents).to_f64_dp2(),
+                    "net_cents": line.net_cents,
+                    "tax": Cents::new(line.tax_cents).to_f64_dp2(),
+                    "tax_cents": line.tax_cents,
+                    "gross": Cents::new(line.gross_cents).to_f64_dp2(),
+                    "gross_cents": line.gross_cents,
+                })
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_rate_config(mode: TaxMode) -> TaxConfig {
+        TaxConfig {
+            mode,
+            rates: vec![
+                TaxRate {
+                    id: "food".into(),
+                    label: "Food".into(),
+                    rate: 13.0,
+                },
+                TaxRate {
+                    id: "alcohol".into(),
+                    label: "Alcohol".into(),
+                    rate: 24.0,
+                },
+            ],
+            default_rate_id: Some("food".into()),
+            category_rates: BTreeMap::from([("cat-drinks".to_string(), "alcohol".to_string())]),
+            item_rates: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn inclusive_breakdown_splits_by_category() {
+        let config = two_rate_config(TaxMode::Inclusive);
+        let items = vec![
+            serde_json::json!({ "name": "Souvlaki", "total_price": 11.30 }),
+            serde_json::json!({ "name": "Beer", "category_id": "cat-drinks", "unit_price": 6.20, "quantity": 2 }),
+        ];
+        let lines = compute_breakdown(
+            &config,
+            &MenuTaxLookup::default(),
+            &items,
+            RoundingRule::HalfUp,
+        );
+        assert_eq!(lines.len(), 2);
+        let food = &lines[0];
+        assert_eq!((food.rate_id.as_str(), food.rate_bps), ("food", 1300));
+        assert_eq!(
+            (food.net_cents, food.tax_cents, food.gross_cents),
+            (1000, 130, 1130)
+        );
+        let alcohol = &lines[1];
+        assert_eq!(
+            (alcohol.net_cents, alcohol.tax_cents, alcohol.gross_cents),
+            (1000, 240, 1240)
+        );
+    }
+
+    #[test]
+    fn exclusive_breakdown_adds_tax_on_top() {
+        let config = two_rate_config(TaxMode::Exclusive);
+        let items = vec![
+            serde_json::json!({ "total_price": 10.0, "taxRateId": "alcohol" }),
+            serde_json::json!({ "total_price": 5.0, "menu_item_id": "wine" }),
+        ];
+        let mut menu = MenuTaxLookup::default();
+        menu.category_by_item
+            .insert("wine".to_string(), "cat-drinks".to_string());
+        let lines = compute_breakdown(&config, &menu, &items, RoundingRule::HalfUp);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(
+            (lines[0].net_cents, lines[0].tax_cents, lines[0].gross_cents),
+            (1500, 360, 1860)
+        );
+    }
+
+    #[test]
+    fn captured_breakdown_sets_order_tax_and_exclusive_total() {
+        for (mode, price, total_before, expected_total_cents) in [
+            (TaxMode::Inclusive, 11.30, 11.30, 1130),
+            // Exclusive: 10.00 net plus a 2.50 delivery fee, sent without tax.
+            (TaxMode::Exclusive, 10.00, 12.50, 1380),
+        ] {
+            let conn = Connection::open_in_memory().unwrap();
+            db::run_migrations_for_test(&conn);
+            save_config(&conn, &two_rate_config(mode)).unwrap();
+            let items = vec![serde_json::json!({ "name": "Souvlaki", "total_price": price })];
+            conn.execute(
+                "INSERT INTO orders (id, items, total_amount, tax_amount, status, created_at, updated_at)
+                 VALUES ('order-1', ?1, ?2, 0, 'pending', datetime('now'), datetime('now'))",
+                params![Value::Array(items.clone()).to_string(), total_before],
+            )
+            .unwrap();
+
+            let captured = capture_order_breakdown(&conn, "order-1", &items).unwrap();
+            assert_eq!(captured.tax, Cents::new(130), "{mode:?}");
+            assert_eq!(captured.total, Cents::new(expected_total_cents), "{mode:?}");
+            // Capturing again swaps the tax for itself.
+            let again = capture_order_breakdown(&conn, "order-1", &items).unwrap();
+            assert_eq!(again, captured, "{mode:?}");
+
+            let stored: (f64, i64, f64, i64) = conn
+                .query_row(
+                    "SELECT tax_amount, tax_amount_cents, total_amount, total_amount_cents
+                     FROM orders WHERE id = 'order-1'",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )
+                .unwrap();
+            assert_eq!(stored.1, 130, "{mode:?}");
+            assert_eq!(stored.3, expected_total_cents, "{mode:?}");
+            assert!((stored.0 - 1.30).abs() < 0.001, "{mode:?}");
+            assert!(
+                (stored.2 - expected_total_cents as f64 / 100.0).abs() < 0.001,
+                "{mode:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn validate_rejects_unknown_assignments_and_bad_rates() {
+        let mut config = two_rate_config(TaxMode::Inclusive);
+        assert!(config.validate().is_ok());
+        config
+            .item_rates
+            .insert("item-1".to_string(), "missing".to_string());
+        assert!(config.validate().unwrap_err().contains("missing"));
+        config.item_rates.clear();
+        config.rates[1].rate = 140.0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn aggregate_keeps_mid_day_rate_changes_apart() {
+        let before = serde_json::to_string(&vec![TaxBreakdownLine {
+            rate_id: "food".into(),
+            label: "Food".into(),
+            rate_bps: 1300,
+            net_cents: 1000,
+            tax_cents: 130,
+            gross_cents: 1130,
+        }])
+        .unwrap();
+        let after = serde_json::to_string(&vec![TaxBreakdownLine {
+            rate_id: "food".into(),
+            label: "Food".into(),
+            rate_bps: 2400,
+            net_cents: 500,
+            tax_cents: 120,
+            gross_cents: 620,
+        }])
+        .unwrap();
+        let totals = aggregate_breakdowns([before.as_str(), before.as_str(), after.as_str()]);
+        assert_eq!(totals.len(), 2);
+        assert_eq!((totals[0].rate_bps, totals[0].tax_cents), (1300, 260));
+        assert_eq!((totals[1].rate_bps, totals[1].tax_cents), (2400, 120));
+    }
+
+    #[test]
+    fn load_config_falls_back_to_legacy_rate() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE local_settings (
+                setting_category TEXT, setting_key TEXT, setting_value TEXT
+             );
+             INSERT INTO local_settings VALUES ('general', 'tax_rate', '24');
+             INSERT INTO local_settings VALUES ('general', 'tax_mode', 'exclusive');",
+        )
+        .unwrap();
+        let config = load_config(&conn);
+        assert_eq!(config.mode, TaxMode::Exclusive);
+        assert_eq!(config.rates.len(), 1);
+        assert_eq!(config.rates[0].rate, 24.0);
+        assert_eq!(config.default_rate_id.as_deref(), Some("standard"));
+    }
+}
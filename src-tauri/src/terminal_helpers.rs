@@ -452,6 +452,24 @@ pub(crate) fn cache_terminal_settings_snapshot(
         }
     }
 
+    if let Some(envelope) = resp
+        .get("admin_proxy_allowlist")
+        .or_else(|| resp.get("adminProxyAllowlist"))
+        .filter(|value| !value.is_null())
+    {
+        match crate::admin_proxy::store_extensions(&conn, envelope) {
+            Ok(entries) => {
+                tracing::info!(entries, "Stored admin proxy allowlist extension");
+                updated.push(format!(
+                    "{}.{}",
+                    crate::admin_proxy::SETTINGS_CATEGORY,
+                    crate::admin_proxy::EXTENSIONS_KEY
+                ));
+            }
+            Err(e) => warn!(error = %e, "Rejected admin proxy allowlist extension"),
+        }
+    }
+
     if let Some(branch_name) =
         nested_value_str(resp, &["/branch_info/name", "/branch_info/display_name"])
     {
@@ -889,23 +907,28 @@ pub(crate) fn purge_hydrated_terminal_credentials_from_local_settings(db: &db::D
     }
 }
 
+/// Credential keys mirrored between the OS keyring and `local_settings`
+/// (category `terminal`) under the same name, in hydration order.
+pub(crate) const HYDRATED_CREDENTIAL_KEYS: &[&str] = &[
+    "terminal_id",
+    "pos_api_key",
+    "admin_dashboard_url",
+    "branch_id",
+    "organization_id",
+    "business_type",
+    "supabase_url",
+    "supabase_anon_key",
+    "ghost_mode_feature_enabled",
+];
+
+/// Copy non-empty `local_settings` credentials into the keyring. A
+/// `local_settings` value wins over a differing keyring value; see
+/// `credential_validation` for reporting and repairing such disagreements.
 pub(crate) fn hydrate_terminal_credentials_from_local_settings(db: &db::DbState) {
     // Keep keyring credentials aligned with local_settings values used by Electron
     // compatibility paths.
-    let mappings = [
-        ("terminal_id", "terminal_id"),
-        ("pos_api_key", "pos_api_key"),
-        ("admin_dashboard_url", "admin_dashboard_url"),
-        ("branch_id", "branch_id"),
-        ("organization_id", "organization_id"),
-        ("business_type", "business_type"),
-        ("supabase_url", "supabase_url"),
-        ("supabase_anon_key", "supabase_anon_key"),
-        ("ghost_mode_feature_enabled", "ghost_mode_feature_enabled"),
-    ];
-
-    for (credential_key, setting_key) in mappings {
-        if let Some(value) = read_local_setting(db, "terminal", setting_key) {
+    for &credential_key in HYDRATED_CREDENTIAL_KEYS {
+        if let Some(value) = read_local_setting(db, "terminal", credential_key) {
             let trimmed = value.trim();
             if !trimmed.is_empty() {
                 if credential_key == "terminal_id" && trimmed == "terminal-001" {
@@ -1263,10 +1286,7 @@ mod tests {
         )
         .expect("pragma setup");
         db::run_migrations_for_test(&conn);
-        db::DbState {
-            conn: Mutex::new(conn),
-            db_path: PathBuf::from(":memory:"),
-        }
+        db::DbState::new(conn, PathBuf::from(":memory:"))
     }
 
     const TEST_CREDENTIAL_KEYS: &[&str] = &[
@@ -0,0 +1,778 @@
+//! Re-pairing a terminal whose credentials were invalidated.
+//!
+//! When the admin revokes a terminal, `handle_invalid_terminal_credentials`
+//! clears the API key but leaves the old terminal's unsynced work behind.
+//! Re-pairing runs in three steps:
+//!
+//! 1. `terminal_repair_begin` quarantines that work: open or unsynced orders
+//!    are listed in `terminal_repair_quarantine`, and their unsent rows in
+//!    `sync_queue` / `parity_sync_queue` are moved there so the sync loop
+//!    cannot send them under the new identity. It then applies and validates
+//!    the new connection code.
+//! 2. `terminal_repair_migrate` rewrites the quarantined orders and queue
+//!    payloads to the new terminal and branch and requeues them, or
+//! 3. `terminal_repair_discard` archives the quarantined rows under the
+//!    recovery root and deletes them.
+//!
+//! Every step is written to `recovery_action_log` and announced with
+//! [`PROGRESS_EVENT`]. Neither the log nor the quarantine ever holds an API
+//! key: only terminal and branch ids and row counts are recorded.
+
+use std::path::Path;
+
+use chrono::{DateTime, Local, Utc};
+use rusqlite::{params, params_from_iter, types::Value as SqlValue, Connection, OptionalExtension};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::retention;
+
+pub const PROGRESS_EVENT: &str = "terminal_repair_progress";
+pub const ARCHIVE_DIR: &str = "terminal_repair";
+const AUDIT_ACTION_ID: &str = "terminal_repair";
+
+pub const STATUS_QUARANTINED: &str = "quarantined";
+pub const STATUS_VALIDATED: &str = "validated";
+
+/// Orders the old terminal still owes the server, or that are still open.
+const QUARANTINED_ORDERS_FILTER: &str = "COALESCE(sync_status, 'pending') != 'synced'
+    OR LOWER(COALESCE(status, '')) NOT IN
+        ('completed', 'cancelled', 'canceled', 'voided', 'delivered', 'refunded')";
+
+/// Queue tables drained into the quarantine, with the statuses that mean
+/// "not yet accepted by the server" and the column holding the payload.
+struct QueueTable {
+    table: &'static str,
+    unsent_filter: &'static str,
+    payload_column: &'static str,
+}
+
+const QUEUE_TABLES: &[QueueTable] = &[
+    QueueTable {
+        table: "sync_queue",
+        unsent_filter: "status IN ('pending', 'in_progress', 'deferred', 'failed')",
+        payload_column: "payload",
+    },
+    QueueTable {
+        table: "parity_sync_queue",
+        unsent_filter: "status IN ('pending', 'processing', 'failed', 'conflict')",
+        payload_column: "data",
+    },
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairSession {
+    pub id: String,
+    pub status: String,
+    pub old_terminal_id: Option<String>,
+    pub old_branch_id: Option<String>,
+    pub new_terminal_id: Option<String>,
+    pub new_branch_id: Option<String>,
+    pub order_count: i64,
+    pub queue_count: i64,
+}
+
+impl RepairSession {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "repairId": self.id,
+            "status": self.status,
+            "oldTerminalId": self.old_terminal_id,
+            "oldBranchId": self.old_branch_id,
+            "newTerminalId": self.new_terminal_id,
+            "newBranchId": self.new_branch_id,
+            "orders": self.order_count,
+            "queueItems": self.queue_count,
+        })
+    }
+}
+
+fn timestamp(now: DateTime<Utc>) -> String {
+    now.to_rfc3339()
+}
+
+const SESSION_COLUMNS: &str = "id, status, old_terminal_id, old_branch_id, new_terminal_id,
+    new_branch_id, order_count, queue_count";
+
+fn session_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<RepairSession> {
+    Ok(RepairSession {
+        id: row.get(0)?,
+        status: row.get(1)?,
+        old_terminal_id: row.get(2)?,
+        old_branch_id: row.get(3)?,
+        new_terminal_id: row.get(4)?,
+        new_branch_id: row.get(5)?,
+        order_count: row.get(6)?,
+        queue_count: row.get(7)?,
+    })
+}
+
+/// The re-pairing that is still waiting for migrate or discard, if any.
+pub fn active_session(conn: &Connection) -> Result<Option<RepairSession>, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {SESSION_COLUMNS} FROM terminal_repair_sessions
+             WHERE status IN ('quarantined', 'validated')
+             ORDER BY created_at DESC LIMIT 1"
+        ),
+        [],
+        session_from_row,
+    )
+    .optional()
+    .map_err(|e| format!("load active terminal repair: {e}"))
+}
+
+pub fn load_session(conn: &Connection, repair_id: &str) -> Result<RepairSession, String> {
+    conn.query_row(
+        &format!("SELECT {SESSION_COLUMNS} FROM terminal_repair_sessions WHERE id = ?1"),
+        params![repair_id],
+        session_from_row,
+    )
+    .optional()
+    .map_err(|e| format!("load terminal repair {repair_id}: {e}"))?
+    .ok_or_else(|| format!("Terminal repair {repair_id} not found"))
+}
+
+/// The session named by `repair_id`, or the active one when none is given.
+pub fn resolve_session(
+    conn: &Connection,
+    repair_id: Option<&str>,
+) -> Result<RepairSession, String> {
+    match repair_id.map(str::trim).filter(|id| !id.is_empty()) {
+        Some(id) => load_session(conn, id),
+        None => active_session(conn)?.ok_or_else(|| "No terminal repair is in progress".into()),
+    }
+}
+
+fn in_transaction<T>(
+    conn: &Connection,
+    what: &str,
+    body: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    conn.execute_batch("BEGIN IMMEDIATE")
+        .map_err(|e| format!("begin {what}: {e}"))?;
+    match body() {
+        Ok(value) => {
+            conn.execute_batch("COMMIT")
+                .map_err(|e| format!("commit {what}: {e}"))?;
+            Ok(value)
+        }
+        Err(error) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            Err(error)
+        }
+    }
+}
+
+fn row_to_json(row: &rusqlite::Row<'_>, columns: &[String]) -> rusqlite::Result<Value> {
+    let mut object = Map::with_capacity(columns.len());
+    for (index, column) in columns.iter().enumerate() {
+        object.insert(
+            column.clone(),
+            retention::sqlite_value_to_json(row.get_ref(index)?),
+        );
+    }
+    Ok(Value::Object(object))
+}
+
+/// Move the unsent rows of one queue table into the quarantine. Returns how
+/// many rows moved.
+fn quarantine_queue_rows(
+    conn: &Connection,
+    repair_id: &str,
+    queue: &QueueTable,
+    now: &str,
+) -> Result<i64, String> {
+    let table = queue.table;
+    let rows: Vec<(String, String)> = {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT * FROM {table} WHERE {}",
+                queue.unsent_filter
+            ))
+            .map_err(|e| format!("prepare {table} quarantine: {e}"))?;
+        let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+        let id_index = columns
+            .iter()
+            .position(|column| column == "id")
+            .ok_or_else(|| format!("{table} has no id column"))?;
+        let rows = stmt
+            .query_map([], |row| {
+                let id = match row.get_ref(id_index)? {
+                    rusqlite::types::ValueRef::Integer(id) => id.to_string(),
+                    other => other.as_str().unwrap_or_default().to_string(),
+                };
+                Ok((id, row_to_json(row, &columns)?.to_string()))
+            })
+            .map_err(|e| format!("read {table} rows to quarantine: {e}"))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("read {table} row to quarantine: {e}"))?
+    };
+    for (id, row_json) in &rows {
+        conn.execute(
+            "INSERT OR REPLACE INTO terminal_repair_quarantine
+                 (repair_id, source_table, row_id, row_json, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![repair_id, table, id, row_json, now],
+        )
+        .map_err(|e| format!("quarantine {table} row: {e}"))?;
+        conn.execute(
+            &format!("DELETE FROM {table} WHERE CAST(id AS TEXT) = ?1"),
+            params![id],
+        )
+        .map_err(|e| format!("remove quarantined {table} row: {e}"))?;
+    }
+    Ok(rows.len() as i64)
+}
+
+fn count_quarantined(conn: &Connection, repair_id: &str, orders: bool) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM terminal_repair_quarantine
+         WHERE repair_id = ?1 AND (source_table = 'orders') = ?2",
+        params![repair_id, orders],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("count quarantined rows: {e}"))
+}
+
+/// Quarantine the old terminal's open and unsynced work. Joins the active
+/// session when a previous attempt did not finish, so running `begin` again
+/// picks up anything queued since.
+pub fn quarantine(
+    conn: &Connection,
+    old_terminal_id: Option<&str>,
+    old_branch_id: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<RepairSession, String> {
+    let now = timestamp(now);
+    let repair_id = in_transaction(conn, "terminal repair quarantine", || {
+        let repair_id = match active_session(conn)? {
+            Some(session) => session.id,
+            None => {
+                let id = Uuid::new_v4().to_string();
+                conn.execute(
+                    "INSERT INTO terminal_repair_sessions (
+                         id, status, old_terminal_id, old_branch_id, created_at, updated_at
+                     ) VALUES (?1, 'quarantined', ?2, ?3, ?4, ?4)",
+                    params![id, old_terminal_id, old_branch_id, now],
+                )
+                .map_err(|e| format!("create terminal repair: {e}"))?;
+                id
+            }
+        };
+        conn.execute(
+            &format!(
+                "INSERT OR IGNORE INTO terminal_repair_quarantine
+                     (repair_id, source_table, row_id, row_json, created_at)
+                 SELECT ?1, 'orders', id, NULL, ?2 FROM orders
+                 WHERE {QUARANTINED_ORDERS_FILTER}"
+            ),
+            params![repair_id, now],
+        )
+        .map_err(|e| format!("quarantine orders: {e}"))?;
+        for queue in QUEUE_TABLES {
+            quarantine_queue_rows(conn, &repair_id, queue, &now)?;
+        }
+        conn.execute(
+            "UPDATE terminal_repair_sessions
+             SET order_count = ?2, queue_count = ?3, updated_at = ?4
+             WHERE id = ?1",
+            params![
+                repair_id,
+                count_quarantined(conn, &repair_id, true)?,
+                count_quarantined(conn, &repair_id, false)?,
+                now
+            ],
+        )
+        .map_err(|e| format!("update terminal repair counts: {e}"))?;
+        Ok(repair_id)
+    })?;
+    load_session(conn, &repair_id)
+}
+
+/// Record the identity the new connection code resolved to.
+pub fn mark_validated(
+    conn: &Connection,
+    repair_id: &str,
+    new_terminal_id: &str,
+    new_branch_id: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<RepairSession, String> {
+    let updated = conn
+        .execute(
+            "UPDATE terminal_repair_sessions
+             SET status = 'validated', new_terminal_id = ?2, new_branch_id = ?3, updated_at = ?4
+             WHERE id = ?1 AND status IN ('quarantined', 'validated')",
+            params![repair_id, new_terminal_id, new_branch_id, timestamp(now)],
+        )
+        .map_err(|e| format!("mark terminal repair validated: {e}"))?;
+    if updated == 0 {
+        return Err(format!("Terminal repair {repair_id} is already finished"));
+    }
+    load_session(conn, repair_id)
+}
+
+/// Point top-level terminal/branch fields of a queued payload at the new
+/// identity. Payloads that are not JSON objects are left alone.
+fn rewrite_payload_identity(payload: &str, terminal_id: &str, branch_id: Option<&str>) -> String {
+    let Ok(Value::Object(mut object)) = serde_json::from_str::<Value>(payload) else {
+        return payload.to_string();
+    };
+    for key in ["terminalId", "terminal_id"] {
+        if object.contains_key(key) {
+            object.insert(key.to_string(), json!(terminal_id));
+        }
+    }
+    if let Some(branch_id) = branch_id {
+        for key in ["branchId", "branch_id"] {
+            if object.contains_key(key) {
+                object.insert(key.to_string(), json!(branch_id));
+            }
+        }
+    }
+    Value::Object(object).to_string()
+}
+
+fn json_to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(flag) => SqlValue::Integer(i64::from(*flag)),
+        Value::Number(number) => number
+            .as_i64()
+            .map(SqlValue::Integer)
+            .or_else(|| number.as_f64().map(SqlValue::Real))
+            .unwrap_or(SqlValue::Null),
+        Value::String(text) => SqlValue::Text(text.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+/// Reset a quarantined queue row so the sync loop treats it as new work
+/// under the new identity.
+fn requeued_row(
+    queue: &QueueTable,
+    mut row: Map<String, Value>,
+    session: &RepairSession,
+    terminal_id: &str,
+    organization_id: Option<&str>,
+    now: &str,
+) -> Map<String, Value> {
+    if let Some(Value::String(payload)) = row.get(queue.payload_column) {
+        let rewritten =
+            rewrite_payload_identity(payload, terminal_id, session.new_branch_id.as_deref());
+        row.insert(queue.payload_column.to_string(), json!(rewritten));
+    }
+    let mut set = |column: &str, value: Value| {
+        if row.contains_key(column) {
+            row.insert(column.to_string(), value);
+        }
+    };
+    set("status", json!("pending"));
+    set("next_retry_at", Value::Null);
+    match queue.table {
+        "sync_queue" => {
+            set("retry_count", json!(0));
+            set("last_error", Value::Null);
+            set("lease_expires_at", Value::Null);
+            set("updated_at", json!(now));
+        }
+        _ => {
+            set("attempts", json!(0));
+            set("last_attempt", Value::Null);
+            set("error_message", Value::Null);
+            if let Some(organization_id) = organization_id {
+                set("organization_id", json!(organization_id));
+            }
+        }
+    }
+    row
+}
+
+/// Re-associate the quarantined orders with the validated terminal and
+/// branch and put their queue rows back. Returns how many orders and queue
+/// rows were migrated.
+pub fn migrate(
+    conn: &Connection,
+    repair_id: &str,
+    organization_id: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<(usize, usize), String> {
+    let session = load_session(conn, repair_id)?;
+    if session.status != STATUS_VALIDATED {
+        return Err(format!(
+            "Terminal repair {repair_id} is {}; validate a new connection code first",
+            session.status
+        ));
+    }
+    let terminal_id = session
+        .new_terminal_id
+        .clone()
+        .ok_or("Terminal repair has no new terminal id")?;
+    let now = timestamp(now);
+    in_transaction(conn, "terminal repair migrate", || {
+        let orders = conn
+            .execute(
+                "UPDATE orders SET
+                     terminal_id = ?2,
+                     branch_id = COALESCE(?3, branch_id),
+                     owner_terminal_id = CASE
+                         WHEN owner_terminal_id IS NOT NULL AND owner_terminal_id = ?4 THEN ?2
+                         ELSE owner_terminal_id END,
+                     source_terminal_id = CASE
+                         WHEN source_terminal_id IS NOT NULL AND source_terminal_id = ?4 THEN ?2
+                         ELSE source_terminal_id END,
+                     sync_status = CASE
+                         WHEN COALESCE(sync_status, 'pending') = 'synced' THEN sync_status
+                         ELSE 'pending' END,
+                     updated_at = ?5
+                 WHERE id IN (
+                     SELECT row_id FROM terminal_repair_quarantine
+                     WHERE repair_id = ?1 AND source_table = 'orders'
+                 )",
+                params![
+                    repair_id,
+                    terminal_id,
+                    session.new_branch_id,
+                    session.old_terminal_id,
+                    now
+                ],
+            )
+            .map_err(|e| format!("re-associate quarantined orders: {e}"))?;
+
+        let mut requeued = 0;
+        for queue in QUEUE_TABLES {
+            let rows: Vec<String> = {
+                let mut stmt = conn
+                    .prepare(
+                        "SELECT row_json FROM terminal_repair_quarantine
+                         WHERE repair_id = ?1 AND source_table = ?2",
+                    )
+                    .map_err(|e| format!("prepare quarantined {} rows: {e}", queue.table))?;
+                let rows = stmt
+                    .query_map(params![repair_id, queue.table], |row| row.get(0))
+                    .map_err(|e| format!("read quarantined {} rows: {e}", queue.table))?;
+                rows.collect::<Result<_, _>>()
+                    .map_err(|e| format!("read quarantined {} row: {e}", queue.table))?
+            };
+            for raw in rows {
+                let Ok(Value::Object(row)) = serde_json::from_str::<Value>(&raw) else {
+                    return Err(format!("Quarantined {} row is unreadable", queue.table));
+                };
+                let row = requeued_row(queue, row, &session, &terminal_id, organization_id, &now);
+                let columns: Vec<&String> = row.keys().collect();
+                let placeholders = (1..=columns.len())
+                    .map(|index| format!("?{index}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let column_list = columns
+                    .iter()
+                    .map(|column| column.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                conn.execute(
+                    &format!(
+                        "INSERT INTO {} ({column_list}) VALUES ({placeholders})",
+                        queue.table
+                    ),
+                    params_from_iter(row.values().map(json_to_sql)),
+                )
+                .map_err(|e| format!("requeue quarantined {} row: {e}", queue.table))?;
+                requeued += 1;
+            }
+        }
+
+        conn.execute(
+            "DELETE FROM terminal_repair_quarantine WHERE repair_id = ?1",
+            params![repair_id],
+        )
+        .map_err(|e| format!("clear terminal repair quarantine: {e}"))?;
+        conn.execute(
+            "UPDATE terminal_repair_sessions
+             SET status = 'migrated', updated_at = ?2, completed_at = ?2
+             WHERE id = ?1",
+            params![repair_id, now],
+        )
+        .map_err(|e| format!("mark terminal repair migrated: {e}"))?;
+        Ok((orders, requeued))
+    })
+}
+
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Archive the quarantined orders (with their payment rows) and queue rows
+/// into `archive_dir`, then delete them. Returns the archive files and how
+/// many orders were dropped.
+pub fn discard(
+    conn: &Connection,
+    repair_id: &str,
+    archive_dir: &Path,
+    now: DateTime<Utc>,
+) -> Result<(Vec<String>, usize), String> {
+    let session = load_session(conn, repair_id)?;
+    if !matches!(
+        session.status.as_str(),
+        STATUS_QUARANTINED | STATUS_VALIDATED
+    ) {
+        return Err(format!(
+            "Terminal repair {repair_id} is already {}",
+            session.status
+        ));
+    }
+    retention::ensure_archive_space(archive_dir)?;
+    let date = Local::now().format("%Y-%m-%d").to_string();
+    let order_ids = format!(
+        "SELECT row_id FROM terminal_repair_quarantine
+         WHERE repair_id = {} AND source_table = 'orders'",
+        quote(repair_id)
+    );
+    let mut files = Vec::new();
+    let result = in_transaction(conn, "terminal repair discard", || {
+        for table in retention::ORDER_CASCADED_TABLES.iter().copied() {
+            if !retention::table_exists(conn, table) {
+                continue;
+            }
+            let sql = format!("SELECT * FROM {table} WHERE order_id IN ({order_ids})");
+            if let Some(path) = retention::export_query(conn, archive_dir, table, &date, &sql)? {
+                files.push(path);
+            }
+        }
+        let sql = format!("SELECT * FROM orders WHERE id IN ({order_ids})");
+        if let Some(path) = retention::export_query(conn, archive_dir, "orders", &date, &sql)? {
+            files.push(path);
+        }
+        let sql = format!(
+            "SELECT * FROM terminal_repair_quarantine
+             WHERE repair_id = {} AND source_table != 'orders'",
+            quote(repair_id)
+        );
+        if let Some(path) =
+            retention::export_query(conn, archive_dir, "terminal_repair_quarantine", &date, &sql)?
+        {
+            files.push(path);
+        }
+
+        let orders = conn
+            .execute(&format!("DELETE FROM orders WHERE id IN ({order_ids})"), [])
+            .map_err(|e| format!("delete quarantined orders: {e}"))?;
+        conn.execute(
+            "DELETE FROM terminal_repair_quarantine WHERE repair_id = ?1",
+            params![repair_id],
+        )
+        .map_err(|e| format!("clear terminal repair quarantine: {e}"))?;
+        conn.execute(
+            "UPDATE terminal_repair_sessions
+             SET status = 'discarded', updated_at = ?2, completed_at = ?2
+             WHERE id = ?1",
+            params![repair_id, timestamp(now)],
+        )
+        .map_err(|e| format!("mark terminal repair discarded: {e}"))?;
+        Ok(orders)
+    });
+    match result {
+        Ok(orders) => Ok((files, orders)),
+        Err(error) => {
+            for path in &files {
+                let _ = std::fs::remove_file(path);
+            }
+            Err(error)
+        }
+    }
+}
+
+/// Record one re-pairing step in `recovery_action_log`.
+pub fn record(
+    conn: &Connection,
+    repair_id: Option<&str>,
+    step: &str,
+    success: bool,
+    message: &str,
+    actor_staff_id: Option<&str>,
+    details: Value,
+) -> Result<(), String> {
+    let payload = json!({
+        "repairId": repair_id,
+        "step": step,
+        "details": details,
+    });
+    conn.execute(
+        "INSERT INTO recovery_action_log (
+            id, action_id, issue_code, entity_type, entity_id, success, message,
+            actor_staff_id, payload_json, created_at
+         ) VALUES (?1, ?2, ?3, 'terminal_repair', ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            Uuid::new_v4().to_string(),
+            AUDIT_ACTION_ID,
+            step,
+            repair_id,
+            success,
+            message,
+            actor_staff_id,
+            payload.to_string(),
+            Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(|e| format!("record terminal repair {step} in audit log: {e}"))?;
+    Ok(())
+}
+
+/// Payload for [`PROGRESS_EVENT`].
+pub fn progress(step: &str, session: Option<&RepairSession>, extra: Value) -> Value {
+    let mut payload = json!({ "step": step });
+    if let (Some(map), Some(session)) = (payload.as_object_mut(), session) {
+        map.insert("repairId".into(), json!(session.id));
+        map.insert("status".into(), json!(session.status));
+    }
+    if let (Some(map), Value::Object(extra)) = (payload.as_object_mut(), extra) {
+        map.extend(extra);
+    }
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        crate::db::run_migrations_for_test(&conn);
+        conn
+    }
+
+    fn seed(conn: &Connection) {
+        conn.execute_batch(
+            "INSERT INTO orders (id, items, total_amount, status, order_type, sync_status,
+                                 terminal_id, branch_id, created_at, updated_at)
+             VALUES ('ord-open', '[]', 10.0, 'pending', 'takeaway', 'synced',
+                     'term-old', 'branch-old', datetime('now'), datetime('now')),
+                    ('ord-unsynced', '[]', 12.0, 'completed', 'takeaway', 'pending',
+                     'term-old', 'branch-old', datetime('now'), datetime('now')),
+                    ('ord-done', '[]', 8.0, 'completed', 'takeaway', 'synced',
+                     'term-old', 'branch-old', datetime('now'), datetime('now'));
+             INSERT INTO order_payments (id, order_id, method, amount, status, created_at, updated_at)
+             VALUES ('pay-1', 'ord-unsynced', 'cash', 12.0, 'completed', datetime('now'), datetime('now'));
+             INSERT INTO sync_queue (entity_type, entity_id, operation, payload, idempotency_key, status, retry_count)
+             VALUES ('order', 'ord-unsynced', 'update',
+                     '{\"terminalId\":\"term-old\",\"branchId\":\"branch-old\"}', 'idem-1', 'failed', 4),
+                    ('order', 'ord-done', 'update', '{}', 'idem-2', 'synced', 0);",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn quarantine_holds_open_and_unsynced_work_and_migrate_requeues_it() {
+        let conn = test_conn();
+        seed(&conn);
+        let now = Utc::now();
+
+        let session = quarantine(&conn, Some("term-old"), Some("branch-old"), now).unwrap();
+        assert_eq!(session.status, STATUS_QUARANTINED);
+        assert_eq!(session.order_count, 2);
+        assert_eq!(session.queue_count, 1);
+        let unsent: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sync_queue WHERE status != 'synced'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(unsent, 0, "quarantined rows must leave the live queue");
+
+        // Migrating before a new code is validated is refused.
+        assert!(migrate(&conn, &session.id, None, now).is_err());
+        mark_validated(&conn, &session.id, "term-new", Some("branch-new"), now).unwrap();
+        assert_eq!(migrate(&conn, &session.id, None, now).unwrap(), (2, 1));
+
+        let (terminal, branch, sync_status): (String, String, String) = conn
+            .query_row(
+                "SELECT terminal_id, branch_id, sync_status FROM orders WHERE id = 'ord-unsynced'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            (terminal.as_str(), branch.as_str(), sync_status.as_str()),
+            ("term-new", "branch-new", "pending")
+        );
+        let untouched: String = conn
+            .query_row(
+                "SELECT terminal_id FROM orders WHERE id = 'ord-done'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(untouched, "term-old");
+
+        let (status, retries, payload): (String, i64, String) = conn
+            .query_row(
+                "SELECT status, retry_count, payload FROM sync_queue WHERE idempotency_key = 'idem-1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((status.as_str(), retries), ("pending", 0));
+        let payload: Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!(payload["terminalId"], "term-new");
+        assert_eq!(payload["branchId"], "branch-new");
+        assert_eq!(load_session(&conn, &session.id).unwrap().status, "migrated");
+        assert!(active_session(&conn).unwrap().is_none());
+    }
+
+    #[test]
+    fn discard_archives_then_drops_quarantined_rows() {
+        let conn = test_conn();
+        seed(&conn);
+        let now = Utc::now();
+        let session = quarantine(&conn, Some("term-old"), None, now).unwrap();
+        let dir = std::env::temp_dir().join(format!("terminal-repair-{}", Uuid::new_v4()));
+
+        let (files, orders) = discard(&conn, &session.id, &dir, now).unwrap();
+        assert_eq!(orders, 2);
+        assert_eq!(files.len(), 3, "{files:?}");
+        let remaining: i64 = conn
+            .query_row("SELECT COUNT(*) FROM orders", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(remaining, 1);
+        let payments: i64 = conn
+            .query_row("SELECT COUNT(*) FROM order_payments", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(payments, 0);
+        assert!(discard(&conn, &session.id, &dir, now).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+
+        record(
+            &conn,
+            Some(&session.id),
+            "discard",
+            true,
+            "discarded",
+            Some("admin-1"),
+            json!({ "orders": orders }),
+        )
+        .unwrap();
+        let logged: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM recovery_action_log WHERE action_id = 'terminal_repair'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(logged, 1);
+    }
+
+    #[test]
+    fn payload_rewrite_only_touches_existing_identity_fields() {
+        let rewritten: Value = serde_json::from_str(&rewrite_payload_identity(
+            r#"{"terminal_id":"old","total":5}"#,
+            "new",
+            Some("branch-new"),
+        ))
+        .unwrap();
+        assert_eq!(rewritten, json!({ "terminal_id": "new", "total": 5 }));
+        assert_eq!(
+            rewrite_payload_identity("not json", "new", None),
+            "not json"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Order lookups stay responsive while a Z-report runs.
+//!
+//! # Test shape
+//!
+//! `zreport_generate` and `order_get_by_id` both hand their work to
+//! `DbState::run_blocking`, so neither parks a Tokio worker on SQLite.
+//! The Z-report holds the write connection for its whole rollover; the
+//! order lookups read from the WAL read pool. This test seeds a shift
+//! large enough that the rollover takes a noticeable moment, starts it,
+//! fires 50 concurrent lookups through the same helpers the commands use,
+//! and asserts the p95 lookup latency stays bounded.
+
+use std::time::{Duration, Instant};
+
+use rusqlite::params;
+
+use crate::commands::orders::load_order_for_ipc;
+use crate::tests::harness::TestDb;
+use crate::zreport;
+
+const SHIFT_ID: &str = "shift-offload-1";
+const ORDER_COUNT: usize = 2000;
+const CONCURRENT_LOOKUPS: usize = 50;
+const P95_BOUND: Duration = Duration::from_secs(2);
+
+fn seed_closed_shift_with_orders(td: &TestDb) {
+    let mut conn = td.state.conn.lock().expect("lock db");
+    let tx = conn.transaction().expect("begin seed");
+    let now = "2026-02-16T18:00:00Z";
+    tx.execute(
+        "INSERT INTO staff_shifts (
+            id, staff_id, staff_name, branch_id, terminal_id, role_type,
+            opening_cash_amount, opening_cash_amount_cents,
+            check_in_time, check_out_time, status, calculation_version,
+            sync_status, created_at, updated_at
+         ) VALUES (
+            ?1, 'staff-1', 'John', 'branch-1', 'term-1', 'cashier',
+            200.0, 20000, '2026-02-16T09:00:00Z', ?2, 'closed', 2,
+            'pending', ?2, ?2
+         )",
+        params![SHIFT_ID, now],
+    )
+    .expect("insert shift");
+    for i in 0..ORDER_COUNT {
+        tx.execute(
+            "INSERT INTO orders (
+                id, order_number, items, total_amount, total_amount_cents, status, order_type,
+                payment_status, staff_shift_id, sync_status, created_at, updated_at
+             ) VALUES (?1, ?2, '[]', 12.5, 1250, 'completed', 'dine-in',
+                'paid', ?3, 'pending', ?4, ?4)",
+            params![format!("ord-offload-{i}"), format!("#{i}"), SHIFT_ID, now],
+        )
+        .expect("insert order");
+        tx.execute(
+            "INSERT INTO order_payments (
+                id, order_id, method, amount, amount_cents, status, staff_shift_id,
+                currency, created_at, updated_at
+             ) VALUES (?1, ?2, 'cash', 12.5, 1250, 'completed', ?3, 'EUR', ?4, ?4)",
+            params![
+                format!("pay-offload-{i}"),
+                format!("ord-offload-{i}"),
+                SHIFT_ID,
+                now
+            ],
+        )
+        .expect("insert payment");
+    }
+    tx.commit().expect("commit seed");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn order_lookups_stay_bounded_while_zreport_runs() {
+    let td = TestDb::open();
+    seed_closed_shift_with_orders(&td);
+    let db = td.state.clone();
+
+    let payload = serde_json::json!({ "shiftId": SHIFT_ID });
+    let zreport_db = db.clone();
+    let zreport_task = tokio::spawn(async move {
+        zreport_db
+            .run_blocking(move |db| zreport::generate_z_report(db, &payload))
+            .await
+    });
+
+    let lookups: Vec<_> = (0..CONCURRENT_LOOKUPS)
+        .map(|i| {
+            let db = db.clone();
+            let order_id = format!("ord-offload-{}", i * (ORDER_COUNT / CONCURRENT_LOOKUPS));
+            tokio::spawn(async move {
+                let started = Instant::now();
+                let order = db
+                    .run_blocking(move |db| load_order_for_ipc(db, &order_id, false))
+                    .await;
+                (started.elapsed(), order)
+            })
+        })
+        .collect();
+
+    let mut latencies = Vec::with_capacity(CONCURRENT_LOOKUPS);
+    for lookup in lookups {
+        let (elapsed, order) = lookup.await.expect("lookup task");
+        order.expect("order lookup succeeds during z-report");
+        latencies.push(elapsed);
+    }
+
+    let report = zreport_task
+        .await
+        .expect("z-report task")
+        .expect("z-report generates");
+    assert_eq!(report["success"], true);
+
+    latencies.sort();
+    let p95 = latencies[(CONCURRENT_LOOKUPS * 95).div_ceil(100) - 1];
+    assert!(
+        p95 <= P95_BOUND,
+        "p95 order lookup latency {p95:?} exceeded {P95_BOUND:?} while a z-report ran"
+    );
+}
@@ -30,8 +30,9 @@
 //!   clients that emit `Content-Length` (reqwest does), the body is
 //!   intact.
 //! - Header parsing records lowercase keys only.
-//! - The server responds the SAME body to every request. For per-request
-//!   scripted responses use `sync.rs::spawn_json_sequence_server`.
+//! - `MockServer::new` responds the SAME body to every request.
+//!   `MockServer::scripted` walks a list of [`MockResponse`]s (status,
+//!   extra headers, body) and repeats the last one once exhausted.
 
 use std::collections::HashMap;
 use std::io::{Read, Write};
@@ -68,6 +69,53 @@ impl RecordedRequest {
     }
 }
 
+/// One scripted reply for [`MockServer::scripted`].
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl MockResponse {
+    pub fn json(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    fn render(&self) -> String {
+        let reason = match self.status {
+            200 => "OK",
+            206 => "Partial Content",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            500 => "Internal Server Error",
+            503 => "Service Unavailable",
+            _ => "Status",
+        };
+        let extra: String = self
+            .headers
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}\r\n"))
+            .collect();
+        format!(
+            "HTTP/1.1 {} {reason}\r\nContent-Type: application/json\r\n{extra}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.body.len(),
+            self.body
+        )
+    }
+}
+
 /// A non-blocking mock HTTP server that records every inbound request.
 pub struct MockServer {
     /// The `http://127.0.0.1:<port>` URL the client should connect to.
@@ -83,7 +131,13 @@ impl MockServer {
     /// (as a `200 OK` JSON). The caller keeps the returned handle alive
     /// for the duration of the test; dropping it stops the server.
     pub fn new(response_body: impl Into<String>) -> Self {
-        let response_body = response_body.into();
+        Self::scripted(vec![MockResponse::json(200, response_body)])
+    }
+
+    /// Spawn a server that answers the n-th request with `responses[n]`,
+    /// repeating the last entry once the script runs out.
+    pub fn scripted(responses: Vec<MockResponse>) -> Self {
+        assert!(!responses.is_empty(), "scripted mock needs a response");
         let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
         listener
             .set_nonblocking(true)
@@ -109,16 +163,13 @@ impl MockServer {
                         let raw = String::from_utf8_lossy(&buf[..n]).to_string();
                         let recorded = parse_request(&raw);
 
-                        recorder_for_thread
-                            .lock()
-                            .expect("lock recorder")
-                            .push(recorded);
+                        let index = {
+                            let mut recorder = recorder_for_thread.lock().expect("lock recorder");
+                            recorder.push(recorded);
+                            recorder.len() - 1
+                        };
 
-                        let response = format!(
-                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
-                            response_body.len(),
-                            response_body
-                        );
+                        let response = responses[index.min(responses.len() - 1)].render();
                         let _ = stream.write_all(response.as_bytes());
                     }
                     Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
@@ -140,7 +191,6 @@ impl MockServer {
     }
 
     /// Current count of recorded requests.
-    #[allow(dead_code)] // G8/G13/G14 parity tests (W7 remaining deferral) will consume this.
     pub fn count(&self) -> usize {
         self.recorder.lock().expect("lock recorder").len()
     }
@@ -30,5 +30,8 @@ mod parity_g14;
 mod parity_g7;
 mod parity_g8;
 
+// Blocking-pool offload: reads stay responsive while a Z-report runs.
+mod blocking_offload;
+
 // W4c — temporary dual-write smoke test. Removed in 4e.
 mod w4c_dual_write_smoke;
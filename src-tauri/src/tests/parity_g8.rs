@@ -34,10 +34,11 @@
 //!   7. Spawn a `MockServer`, install `fake_keyring`, and drive
 //!      `sync::dispatch_pending_payments_for_test` (a `#[cfg(test)]
 //!      pub(crate)` wrapper around the private `sync_payment_items`).
-//!   8. Assert `server.count() == 5` and every recorded request's
-//!      body carries its expected `payment:<id>` key at
-//!      `body.idempotency_key` (top-level — `sync_payment_items`
-//!      does not use the financial `items[0]` envelope).
+//!   8. Assert the server saw one batched `POST /api/pos/payments/sync`
+//!      whose `payments[]` carries each expected `payment:<id>` key
+//!      exactly once at `idempotency_key` (top-level per payment —
+//!      `sync_payment_items` does not use the financial `items[0]`
+//!      envelope).
 //!   9. Assert every queue row moved to `status = 'synced'`.
 
 use serde_json::Value;
@@ -155,7 +156,19 @@ async fn parity_g8_payment_offline_restart_sync_exactly_once() {
     }
 
     // ---------- Step 7: spawn MockServer + drive dispatch ----------
-    let server = MockServer::new(r#"{"success":true,"data":{"id":"remote-pay-id"}}"#);
+    let batch_results: Vec<Value> = PAYMENTS
+        .iter()
+        .map(|(pay_id, key)| {
+            serde_json::json!({
+                "idempotency_key": key,
+                "success": true,
+                "id": format!("remote-{pay_id}"),
+            })
+        })
+        .collect();
+    let server = MockServer::new(
+        serde_json::json!({ "success": true, "results": batch_results }).to_string(),
+    );
     let _kr = fake_keyring::install_seeded([
         ("admin_url", server.url.as_str()),
         ("api_key", "test-api-key-g8"),
@@ -174,23 +187,27 @@ async fn parity_g8_payment_offline_restart_sync_exactly_once() {
     assert_eq!(synced, 5, "all 5 payments dispatched");
     assert_eq!(
         server.count(),
-        5,
-        "server received exactly 5 POSTs (exactly-once)"
+        1,
+        "server received exactly one batched POST (exactly-once)"
     );
 
-    // Every request is a POST to /api/pos/payments with
-    // `idempotency_key` at body top-level (see sync.rs:12192).
+    // The batch is a POST to /api/pos/payments/sync whose payments each
+    // carry `idempotency_key` at the payment body's top level.
     let recorded = server.recorded();
     let mut recorded_keys: Vec<String> = recorded
         .iter()
-        .filter_map(|req| {
+        .flat_map(|req| {
             assert_eq!(req.method, "POST");
-            assert_eq!(req.path, "/api/pos/payments");
-            req.json_body().and_then(|body| {
-                body.get("idempotency_key")
-                    .and_then(Value::as_str)
-                    .map(String::from)
-            })
+            assert_eq!(req.path, "/api/pos/payments/sync");
+            req.json_body()
+                .and_then(|body| body.get("payments").and_then(Value::as_array).cloned())
+                .unwrap_or_default()
+        })
+        .filter_map(|payment| {
+            payment
+                .get("idempotency_key")
+                .and_then(Value::as_str)
+                .map(String::from)
         })
         .collect();
     recorded_keys.sort();
@@ -34,10 +34,7 @@ fn test_db() -> DbState {
     )
     .expect("pragma setup");
     db::run_migrations_for_test(&conn);
-    DbState {
-        conn: std::sync::Mutex::new(conn),
-        db_path: std::path::PathBuf::from(":memory:"),
-    }
+    DbState::new(conn, std::path::PathBuf::from(":memory:"))
 }
 
 /// Assert that the cents column equals `Cents::round_half_even(real).as_i64()`
@@ -166,6 +163,7 @@ fn w4c_record_refund_dual_writes_payment_adjustments_amount() {
         &serde_json::json!({
             "paymentId": payment_id,
             "amount": 7.50,
+            "reasonCode": "other",
             "reason": "test refund",
             "refundMethod": "cash",
             "cashHandler": "cashier_drawer",
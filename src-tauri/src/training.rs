@@ -0,0 +1,263 @@
+//! Training (demo) mode.
+//!
+//! While active, the managed [`DbState`] points at `pos-training.db` next to
+//! the live database: same schema, seeded with the live menu cache, settings
+//! and printer profiles so the till looks like the real one. The print
+//! worker and the sync loop keep their own connections to the live
+//! database, so they never see training rows; the sync loop holds the live
+//! queue until training ends.
+//!
+//! External side effects are suppressed and return a simulated success
+//! carrying `training: true`: every admin request (`api::fetch_from_admin`
+//! and the `admin_fetch` command), Supabase queries and ECR transactions.
+//! Sync and print jobs on the training database go nowhere — a forced sync
+//! or queue pass pushes nothing and print jobs are not written.
+//! `_meta.training` reports the mode on every correlated response. Previews
+//! rendered from the training database are stamped by the receipt renderer.
+//! Entering or leaving is refused while a real shift is open.
+//!
+//! The flag is process-wide and not persisted: a restart always comes back on
+//! the live database. Anything that must follow the database a caller works
+//! on, rather than the mode, checks [`is_training_connection`].
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::db::{DbConnections, DbState};
+
+pub const TRAINING_DB_FILE: &str = "pos-training.db";
+pub const MODE_CHANGED_EVENT: &str = "training_mode_changed";
+
+/// Tables copied from the live database on entry.
+const MIRRORED_TABLES: &[&str] = &[
+    "menu_cache",
+    "local_settings",
+    "printer_profiles",
+    "branches",
+];
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Live connections parked while training mode owns the managed state.
+static LIVE: Mutex<Option<DbConnections>> = Mutex::new(None);
+
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::SeqCst)
+}
+
+/// Stand-in result for a side effect suppressed in training mode.
+pub fn simulated_success(extra: Value) -> Value {
+    let mut response = json!({ "success": true, "training": true });
+    if let (Some(obj), Value::Object(extra)) = (response.as_object_mut(), extra) {
+        obj.extend(extra);
+    }
+    response
+}
+
+pub fn training_db_path(db: &DbState) -> PathBuf {
+    db.db_path.with_file_name(TRAINING_DB_FILE)
+}
+
+/// Whether `conn` is open on the training database. The live connections
+/// held by background workers never are, whatever the mode.
+pub fn is_training_connection(conn: &Connection) -> bool {
+    conn.path()
+        .and_then(|path| Path::new(path).file_name())
+        .is_some_and(|name| name == TRAINING_DB_FILE)
+}
+
+fn has_open_shift(conn: &Connection) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM staff_shifts WHERE status = 'active')",
+        [],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("check open shifts: {e}"))
+}
+
+/// Replace the training copies of [`MIRRORED_TABLES`] with the live rows.
+fn mirror_reference_data(training: &Connection, live_path: &Path) -> Result<(), String> {
+    training
+        .execute(
+            "ATTACH DATABASE ?1 AS live",
+            [live_path.to_string_lossy().as_ref()],
+        )
+        .map_err(|e| format!("attach live database: {e}"))?;
+    let copied = MIRRORED_TABLES.iter().try_for_each(|table| {
+        training
+            .execute_batch(&format!(
+                "DELETE FROM main.{table};
+                 INSERT INTO main.{table} SELECT * FROM live.{table};"
+            ))
+            .map_err(|e| format!("copy {table}: {e}"))
+    });
+    if let Err(e) = training.execute_batch("DETACH DATABASE live") {
+        warn!(error = %e, "Failed to detach live database from training database");
+    }
+    copied
+}
+
+fn remove_training_files(path: &Path) -> Result<(), String> {
+    for suffix in ["", "-wal", "-shm"] {
+        let file = PathBuf::from(format!("{}{suffix}", path.display()));
+        match std::fs::remove_file(&file) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("remove {}: {e}", file.display())),
+        }
+    }
+    Ok(())
+}
+
+/// Switch the managed state over to the training database. Returns its path.
+pub fn enter(db: &DbState) -> Result<PathBuf, String> {
+    let mut live = LIVE.lock().map_err(|e| e.to_string())?;
+    if live.is_some() {
+        return Err("Training mode is already active".into());
+    }
+    if db.write(has_open_shift)? {
+        return Err("Close the open shift before entering training mode".into());
+    }
+
+    let path = training_db_path(db);
+    let training = db.open_sibling(&path)?;
+    mirror_reference_data(&training.writer, &db.db_path)?;
+    *live = Some(db.swap_connections(training)?);
+    ACTIVE.store(true, Ordering::SeqCst);
+    info!(path = %path.display(), "Training mode entered");
+    Ok(path)
+}
+
+/// Switch the managed state back to the live database, optionally deleting
+/// the training database afterwards.
+pub fn exit(db: &DbState, wipe: bool) -> Result<(), String> {
+    let mut live = LIVE.lock().map_err(|e| e.to_string())?;
+    let Some(stashed) = live.as_ref() else {
+        return Err("Training mode is not active".into());
+    };
+    if has_open_shift(&stashed.writer)? {
+        return Err("Close the open shift before leaving training mode".into());
+    }
+
+    let stashed = live.take().ok_or("Training mode is not active")?;
+    let training = db.swap_connections(stashed)?;
+    ACTIVE.store(false, Ordering::SeqCst);
+    drop(training);
+    if wipe {
+        remove_training_files(&training_db_path(db))?;
+    }
+    info!(wiped = wipe, "Training mode exited");
+    Ok(())
+}
+
+pub fn status(db: &DbState) -> Value {
+    json!({
+        "active": is_active(),
+        "trainingDbPath": training_db_path(db).to_string_lossy(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn temp_live_db() -> (PathBuf, DbState) {
+        let dir = std::env::temp_dir().join(format!("pos-training-{}", uuid::Uuid::new_v4()));
+        let db = db::init(&dir).expect("init live db");
+        (dir, db)
+    }
+
+    #[test]
+    fn enter_refused_while_real_shift_open() {
+        let (dir, db) = temp_live_db();
+        db.conn
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO staff_shifts (id, staff_id, role_type, check_in_time, status,
+                                           created_at, updated_at)
+                 VALUES ('sh-1', 'st-1', 'cashier', '2026-05-03T08:00:00Z', 'active',
+                         '2026-05-03T08:00:00Z', '2026-05-03T08:00:00Z');",
+            )
+            .unwrap();
+        let error = enter(&db).unwrap_err();
+        assert!(error.contains("open shift"));
+        assert!(!is_active());
+        assert!(!training_db_path(&db).exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn swapped_state_sees_menu_but_not_live_orders() {
+        let (dir, db) = temp_live_db();
+        db.conn
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO menu_cache (cache_key, data) VALUES ('categories', '[]');
+                 INSERT INTO orders (id, items, total_amount, status, created_at, updated_at)
+                 VALUES ('o-1', '[]', 10.0, 'completed', '2026-05-03T08:00:00Z',
+                         '2026-05-03T08:00:00Z');",
+            )
+            .unwrap();
+
+        let path = training_db_path(&db);
+        let training = db.open_sibling(&path).unwrap();
+        mirror_reference_data(&training.writer, &db.db_path).unwrap();
+        let live = db.swap_connections(training).unwrap();
+
+        let count = |sql: &str| {
+            db.read(|conn| {
+                conn.query_row(sql, [], |row| row.get::<_, i64>(0))
+                    .map_err(|e| e.to_string())
+            })
+            .unwrap()
+        };
+        assert_eq!(count("SELECT COUNT(*) FROM menu_cache"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM orders"), 0);
+
+        drop(db.swap_connections(live).unwrap());
+        assert_eq!(count("SELECT COUNT(*) FROM orders"), 1);
+        remove_training_files(&path).unwrap();
+        assert!(!path.exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn prints_follow_the_database_not_the_mode() {
+        let (dir, db) = temp_live_db();
+        let layout = |db: &DbState| {
+            crate::print::resolve_layout_config(db, &json!({}), "order_receipt").unwrap()
+        };
+        let marker = Some(crate::receipt_renderer::TRAINING_MARKER.to_string());
+        assert_ne!(layout(&db).copy_label, marker);
+
+        let path = training_db_path(&db);
+        let live = db
+            .swap_connections(db.open_sibling(&path).unwrap())
+            .unwrap();
+        assert_eq!(layout(&db).copy_label, marker);
+
+        let queued = crate::print::enqueue_print_job(&db, "order_receipt", "o-1", None).unwrap();
+        assert_eq!(queued["success"], true);
+        assert_eq!(queued["training"], true);
+        let jobs: i64 = db
+            .read(|conn| {
+                conn.query_row("SELECT COUNT(*) FROM print_jobs", [], |row| row.get(0))
+                    .map_err(|e| e.to_string())
+            })
+            .unwrap();
+        assert_eq!(jobs, 0);
+
+        drop(db.swap_connections(live).unwrap());
+        assert_ne!(layout(&db).copy_label, marker);
+        remove_training_files(&path).unwrap();
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -18,8 +18,22 @@ use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::db::{self, DbState};
-use crate::money::Cents;
-use crate::{business_day, order_ownership, payment_integrity, storage, sync_queue};
+use crate::drawer_transactions::DrawerTransactionTotals;
+use crate::money::{Cents, CurrencySettings};
+use crate::reasons::{self, ReasonAction};
+use crate::service_charge::ServiceChargeTotals;
+use crate::{business_day, order_ownership, payment_integrity, storage, sync_queue, tax};
+
+/// Add major-unit amounts in cents so derived totals (net sales, day total)
+/// carry no float residue. Inputs are already 2-dp values read from `_cents`
+/// columns, so the round trip is exact.
+fn sum_major_cents(values: impl IntoIterator<Item = f64>) -> f64 {
+    values
+        .into_iter()
+        .map(Cents::round_half_even)
+        .sum::<Cents>()
+        .to_f64_dp2()
+}
 
 // ---------------------------------------------------------------------------
 // Period filtering (Gap 9)
@@ -598,9 +612,9 @@ pub(crate) fn unsettled_payment_blockers(
     db: &DbState,
     payload: &Value,
 ) -> Result<Vec<UnsettledPaymentBlocker>, String> {
-    let branch_id = str_field(payload, "branchId")
-        .or_else(|| str_field(payload, "branch_id"))
-        .unwrap_or_else(|| storage::get_credential("branch_id").unwrap_or_default());
+    let branch_id = crate::branches::report_scope(
+        str_field(payload, "branchId").or_else(|| str_field(payload, "branch_id")),
+    );
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let _ = order_ownership::repair_historical_pickup_financial_attribution(
         &conn,
@@ -652,9 +666,9 @@ pub(crate) fn get_closeout_readiness_snapshot(
     db: &DbState,
     payload: &Value,
 ) -> Result<Value, String> {
-    let branch_id = str_field(payload, "branchId")
-        .or_else(|| str_field(payload, "branch_id"))
-        .unwrap_or_else(|| storage::get_credential("branch_id").unwrap_or_default());
+    let branch_id = crate::branches::report_scope(
+        str_field(payload, "branchId").or_else(|| str_field(payload, "branch_id")),
+    );
 
     let (window, active_staff_blockers, payment_blockers, last_z_report) = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
@@ -934,9 +948,9 @@ fn load_existing_local_z_report_response(
         return Ok(None);
     };
 
-    let branch_id = str_field(payload, "branchId")
-        .or_else(|| str_field(payload, "branch_id"))
-        .unwrap_or_else(|| storage::get_credential("branch_id").unwrap_or_default());
+    let branch_id = crate::branches::report_scope(
+        str_field(payload, "branchId").or_else(|| str_field(payload, "branch_id")),
+    );
 
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let current_window = resolve_current_z_report_window(&conn, branch_id.as_str());
@@ -1287,10 +1301,6 @@ fn display_staff_name(shift: &ReportStaffShift) -> String {
         .unwrap_or_else(|| shift.staff_id.clone())
 }
 
-fn ensure_staff_payments_table(conn: &Connection) {
-    let _ = crate::shifts::ensure_staff_payments_table(conn);
-}
-
 fn drawer_money_cents_expr(alias: Option<&str>, column: &str) -> String {
     let column_ref = alias
         .map(|alias| format!("{alias}.{column}"))
@@ -1313,6 +1323,8 @@ fn drawer_expected_cents_expr(alias: Option<&str>) -> String {
             CAST(ROUND({expected_amount} * 100) AS INTEGER),
             {opening}
               + {cash_sales}
+              + {cash_rounding}
+              + {cash_in}
               - {refunds}
               - {expenses}
               - {staff_payments}
@@ -1324,6 +1336,8 @@ fn drawer_expected_cents_expr(alias: Option<&str>) -> String {
         expected_amount = col("expected_amount"),
         opening = drawer_money_cents_expr(alias, "opening_amount"),
         cash_sales = drawer_money_cents_expr(alias, "total_cash_sales"),
+        cash_rounding = drawer_money_cents_expr(alias, "total_cash_rounding"),
+        cash_in = drawer_money_cents_expr(alias, "cash_in"),
         refunds = drawer_money_cents_expr(alias, "total_refunds"),
         expenses = drawer_money_cents_expr(alias, "total_expenses"),
         staff_payments = drawer_money_cents_expr(alias, "total_staff_payments"),
@@ -1368,8 +1382,6 @@ fn load_staff_payment_items(
     conn: &Connection,
     shift: &ReportStaffShift,
 ) -> Result<Vec<Value>, String> {
-    ensure_staff_payments_table(conn);
-
     if matches!(shift.role_type.as_str(), "cashier" | "manager") {
         let mut stmt = conn
             .prepare(
@@ -1471,7 +1483,8 @@ fn load_staff_drawer_snapshot(conn: &Connection, shift_id: &str) -> Result<Optio
                 COALESCE(cash_drops_cents, CAST(ROUND(cash_drops * 100) AS INTEGER), 0),
                 COALESCE(driver_cash_returned_cents, CAST(ROUND(driver_cash_returned * 100) AS INTEGER), 0),
                 COALESCE(driver_cash_given_cents, CAST(ROUND(driver_cash_given * 100) AS INTEGER), 0),
-                COALESCE(total_staff_payments_cents, CAST(ROUND(total_staff_payments * 100) AS INTEGER), 0)
+                COALESCE(total_staff_payments_cents, CAST(ROUND(total_staff_payments * 100) AS INTEGER), 0),
+                COALESCE(cash_in_cents, CAST(ROUND(cash_in * 100) AS INTEGER), 0)
          FROM cash_drawer_sessions
          WHERE staff_shift_id = ?1"
         ),
@@ -1488,6 +1501,7 @@ fn load_staff_drawer_snapshot(conn: &Connection, shift_id: &str) -> Result<Optio
                 "driverCashReturned": Cents::new(row.get::<_, i64>(7).unwrap_or(0)).to_f64_dp2(),
                 "driverCashGiven": Cents::new(row.get::<_, i64>(8).unwrap_or(0)).to_f64_dp2(),
                 "staffPayments": Cents::new(row.get::<_, i64>(9).unwrap_or(0)).to_f64_dp2(),
+                "cashIn": Cents::new(row.get::<_, i64>(10).unwrap_or(0)).to_f64_dp2(),
             }))
         },
     )
@@ -1518,12 +1532,13 @@ fn load_drawer_rows_for_period(
                     COALESCE(cds.driver_cash_returned_cents, CAST(ROUND(cds.driver_cash_returned * 100) AS INTEGER), 0),
                     COALESCE(cds.cash_drops_cents, CAST(ROUND(cds.cash_drops * 100) AS INTEGER), 0),
                     COALESCE(cds.total_staff_payments_cents, CAST(ROUND(cds.total_staff_payments * 100) AS INTEGER), 0),
-                    cds.opened_at, cds.closed_at, cds.reconciled
+                    cds.opened_at, cds.closed_at, cds.reconciled,
+                    COALESCE(cds.cash_in_cents, CAST(ROUND(cds.cash_in * 100) AS INTEGER), 0)
              FROM cash_drawer_sessions cds
              LEFT JOIN staff_shifts ss ON ss.id = cds.staff_shift_id
              WHERE {opened_at_predicate}
                AND (?2 IS NULL OR cds.opened_at <= ?2)
-               AND (?3 = '' OR cds.branch_id = ?3 OR cds.branch_id IS NULL)
+               AND (cds.branch_id = ?3 OR cds.branch_id IS NULL)
               ORDER BY cds.opened_at ASC"
         ))
         .map_err(|e| format!("prepare drawer rows for period: {e}"))?;
@@ -1547,6 +1562,7 @@ fn load_drawer_rows_for_period(
                 "openedAt": row.get::<_, Option<String>>(13)?,
                 "closedAt": row.get::<_, Option<String>>(14)?,
                 "reconciled": row.get::<_, i64>(15).unwrap_or(0) != 0,
+                "cashIn": Cents::new(row.get::<_, i64>(16).unwrap_or(0)).to_f64_dp2(),
             }))
         })
         .map_err(|e| format!("query drawer rows for period: {e}"))?
@@ -1593,7 +1609,8 @@ fn load_drawer_rows_for_shift(conn: &Connection, shift_id: &str) -> Result<Vec<V
                     COALESCE(cds.driver_cash_returned_cents, CAST(ROUND(cds.driver_cash_returned * 100) AS INTEGER), 0),
                     COALESCE(cds.cash_drops_cents, CAST(ROUND(cds.cash_drops * 100) AS INTEGER), 0),
                     COALESCE(cds.total_staff_payments_cents, CAST(ROUND(cds.total_staff_payments * 100) AS INTEGER), 0),
-                    cds.opened_at, cds.closed_at, cds.reconciled
+                    cds.opened_at, cds.closed_at, cds.reconciled,
+                    COALESCE(cds.cash_in_cents, CAST(ROUND(cds.cash_in * 100) AS INTEGER), 0)
              FROM cash_drawer_sessions cds
              LEFT JOIN staff_shifts ss ON ss.id = cds.staff_shift_id
              WHERE cds.staff_shift_id = ?1
@@ -1620,6 +1637,7 @@ fn load_drawer_rows_for_shift(conn: &Connection, shift_id: &str) -> Result<Vec<V
                 "openedAt": row.get::<_, Option<String>>(13)?,
                 "closedAt": row.get::<_, Option<String>>(14)?,
                 "reconciled": row.get::<_, i64>(15).unwrap_or(0) != 0,
+                "cashIn": Cents::new(row.get::<_, i64>(16).unwrap_or(0)).to_f64_dp2(),
             }))
         })
         .map_err(|e| format!("query drawer rows for shift: {e}"))?
@@ -1652,7 +1670,7 @@ fn load_sales_by_type_for_period(
          JOIN orders o ON o.id = op.order_id
          WHERE {financial_predicate}
            AND (?2 IS NULL OR {financial_expr} <= ?2)
-           AND (?3 = '' OR o.branch_id = ?3 OR o.branch_id IS NULL)
+           AND (o.branch_id = ?3 OR o.branch_id IS NULL)
            AND op.status = 'completed'
            AND COALESCE(o.is_ghost, 0) = 0
            AND o.status NOT IN ('cancelled', 'canceled', 'refunded')
@@ -1775,11 +1793,12 @@ fn load_non_driver_order_totals(
     // aggregates — a live never-settled tab's money was not collected on
     // this shift and must not appear in the staff section either.
     let staff_open_tab = business_day::open_unsettled_table_tab_expr("o");
+    let staff_deposit_due = business_day::deposit_balance_due_cents_expr("o");
     // W4b-iii: cents-with-real-fallback shim (removed in 4e).
     let order_scope_sql = format!(
         "SELECT COUNT(*), COALESCE(SUM(order_total_cents), 0)
          FROM (
-            SELECT o.id, MAX(COALESCE(o.total_amount_cents, CAST(ROUND(o.total_amount * 100) AS INTEGER), 0)) AS order_total_cents
+            SELECT o.id, MAX(COALESCE(o.total_amount_cents, CAST(ROUND(o.total_amount * 100) AS INTEGER), 0) - {staff_deposit_due}) AS order_total_cents
             FROM orders o
             LEFT JOIN order_payments op ON op.order_id = o.id
             WHERE COALESCE(op.staff_shift_id, o.staff_shift_id) = ?1
@@ -2383,6 +2402,48 @@ fn build_driver_summary(staff_reports: &[Value], unsettled_counts: &HashMap<Stri
 ///
 /// **Idempotent:** If a z_report already exists for this shift, returns the
 /// existing one without creating a duplicate.
+/// Refund and void totals plus their breakdown by reason code. Rows come
+/// from `(adjustment_type, reason_code, count, amount_cents)` groups;
+/// adjustments recorded before reason codes existed show up as `uncoded`.
+fn collect_adjustment_totals(
+    conn: &Connection,
+    rows: impl Iterator<Item = (String, Option<String>, i64, i64)>,
+) -> (f64, f64, Vec<Value>) {
+    let mut refunds_cents = 0_i64;
+    let mut voids_cents = 0_i64;
+    let mut by_reason = Vec::new();
+    for (adj_type, reason_code, count, amount_cents) in rows {
+        let action = match adj_type.as_str() {
+            "refund" => {
+                refunds_cents += amount_cents;
+                ReasonAction::Refund
+            }
+            "void" => {
+                voids_cents += amount_cents;
+                ReasonAction::Void
+            }
+            _ => {
+                warn!("Unknown adjustment type: {adj_type}");
+                continue;
+            }
+        };
+        let total = Cents::new(amount_cents).to_f64_dp2();
+        by_reason.push(serde_json::json!({
+            "type": adj_type,
+            "reasonCode": reason_code.as_deref().unwrap_or("uncoded"),
+            "label": reasons::label_for(conn, action, reason_code.as_deref()),
+            "count": count,
+            "total": total,
+            "total_cents": amount_cents,
+        }));
+    }
+    (
+        Cents::new(refunds_cents).to_f64_dp2(),
+        Cents::new(voids_cents).to_f64_dp2(),
+        by_reason,
+    )
+}
+
 pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
@@ -2506,8 +2567,7 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
         .clone()
         .unwrap_or_else(|| storage::get_credential("terminal_id").unwrap_or_default());
     let terminal_name = resolve_terminal_display_name(&conn, None);
-    let branch_id =
-        shift_branch_id.unwrap_or_else(|| storage::get_credential("branch_id").unwrap_or_default());
+    let branch_id = crate::branches::report_scope(shift_branch_id);
 
     // --- Aggregate data from the shift ---
 
@@ -2527,10 +2587,12 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
     // Gap review P0-03: exclude live never-settled table tabs from shift gross —
     // same rule as the date-based aggregates, or the single-shift Z reports
     // uncollected tab money as revenue.
+    // Deposit orders count only what was actually taken so far.
     let single_shift_open_tab = business_day::open_unsettled_table_tab_expr("orders");
+    let single_shift_deposit_due = business_day::deposit_balance_due_cents_expr("orders");
     let single_shift_order_agg_sql = format!(
         "SELECT COUNT(*) as cnt,
-                COALESCE(SUM(total_amount + COALESCE(discount_amount, 0)), 0) as gross,
+                COALESCE(SUM(total_amount + COALESCE(discount_amount, 0) - {single_shift_deposit_due} / 100.0), 0) as gross,
                 COALESCE(SUM(discount_amount), 0) as discounts,
                 COALESCE(SUM(tip_amount), 0) as tips
          FROM orders
@@ -2552,6 +2614,40 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
 
     let (total_orders, gross_sales, discounts_total, tips_total) = order_agg;
 
+    // Per-rate tax, summed from the breakdowns captured at sale time.
+    let single_shift_tax_sql = format!(
+        "SELECT tax_breakdown
+         FROM orders
+         WHERE staff_shift_id = ?1
+           AND tax_breakdown IS NOT NULL
+           AND COALESCE(is_ghost, 0) = 0
+           AND status NOT IN ('cancelled', 'canceled')
+           AND NOT {single_shift_open_tab}"
+    );
+    let tax_breakdowns: Vec<String> = conn
+        .prepare(&single_shift_tax_sql)
+        .and_then(|mut stmt| {
+            stmt.query_map(params![shift_id], |row| row.get::<_, String>(0))?
+                .collect()
+        })
+        .unwrap_or_default();
+    let tax_breakdown = tax::aggregate_breakdowns(tax_breakdowns.iter().map(String::as_str));
+    let single_shift_service_charge_scope = format!(
+        "FROM orders o
+         WHERE o.staff_shift_id = ?1
+           AND COALESCE(o.is_ghost, 0) = 0
+           AND o.status NOT IN ('cancelled', 'canceled')
+           AND NOT {}",
+        business_day::open_unsettled_table_tab_expr("o")
+    );
+    let service_charges =
+        ServiceChargeTotals::collect(&conn, &single_shift_service_charge_scope, &[&shift_id]);
+    let drawer_transactions = DrawerTransactionTotals::collect(
+        &conn,
+        "FROM drawer_transactions dt WHERE dt.staff_shift_id = ?1",
+        &[&shift_id],
+    );
+
     // Payments: breakdown by method
     let mut pay_stmt = conn
         .prepare(
@@ -2568,9 +2664,9 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
         )
         .map_err(|e| format!("prepare payment query: {e}"))?;
 
-    let mut cash_sales = 0.0_f64;
-    let mut card_sales = 0.0_f64;
-    let mut other_sales = 0.0_f64;
+    let mut cash_sales = Cents::ZERO;
+    let mut card_sales = Cents::ZERO;
+    let mut other_sales = Cents::ZERO;
     let mut cash_count = 0_i64;
     let mut card_count = 0_i64;
     let mut other_count = 0_i64;
@@ -2580,7 +2676,7 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, i64>(1)?,
-                Cents::new(row.get::<_, i64>(2)?).to_f64_dp2(),
+                Cents::new(row.get::<_, i64>(2)?),
             ))
         })
         .map_err(|e| format!("query payments: {e}"))?;
@@ -2602,6 +2698,13 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
             }
         }
     }
+    // Totals stay in cents until here; everything below reads major units.
+    let day_total = (cash_sales + card_sales + other_sales).to_f64_dp2();
+    let (cash_sales, card_sales, other_sales) = (
+        cash_sales.to_f64_dp2(),
+        card_sales.to_f64_dp2(),
+        other_sales.to_f64_dp2(),
+    );
 
     // Adjustments: refunds and voids.
     //
@@ -2613,7 +2716,7 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
     let mut adj_stmt = conn
         .prepare(
             // W4b-iii: cents-with-real-fallback shim (removed in 4e).
-            "SELECT pa.adjustment_type,
+            "SELECT pa.adjustment_type, pa.reason_code, COUNT(*),
                     COALESCE(SUM(COALESCE(pa.amount_cents, CAST(ROUND(pa.amount * 100) AS INTEGER))), 0)
              FROM payment_adjustments pa
              JOIN order_payments op ON pa.payment_id = op.id
@@ -2621,30 +2724,19 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
              WHERE COALESCE(op.staff_shift_id, o.staff_shift_id) = ?1
                AND COALESCE(o.is_ghost, 0) = 0
                AND o.status NOT IN ('cancelled', 'canceled')
-             GROUP BY pa.adjustment_type",
+               AND pa.return_order_id IS NULL
+             GROUP BY pa.adjustment_type, pa.reason_code
+             ORDER BY pa.adjustment_type, pa.reason_code",
         )
         .map_err(|e| format!("prepare adjustment query: {e}"))?;
 
-    let mut refunds_total = 0.0_f64;
-    let mut voids_total = 0.0_f64;
-
     let adj_rows = adj_stmt
         .query_map(params![shift_id], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                Cents::new(row.get::<_, i64>(1)?).to_f64_dp2(),
-            ))
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
         })
         .map_err(|e| format!("query adjustments: {e}"))?;
-
-    for row in adj_rows.flatten() {
-        let (adj_type, amount) = row;
-        match adj_type.as_str() {
-            "refund" => refunds_total = amount,
-            "void" => voids_total = amount,
-            _ => warn!("Unknown adjustment type: {adj_type}"),
-        }
-    }
+    let (refunds_total, voids_total, adjustments_by_reason) =
+        collect_adjustment_totals(&conn, adj_rows.flatten());
 
     // Expenses
     // W4b-iii: cents-with-real-fallback shim (removed in 4e).
@@ -2703,7 +2795,9 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
                 COALESCE(driver_cash_given_cents, CAST(ROUND(driver_cash_given * 100) AS INTEGER), 0),
                 COALESCE(driver_cash_returned_cents, CAST(ROUND(driver_cash_returned * 100) AS INTEGER), 0),
                 reconciled,
-                COALESCE(total_staff_payments_cents, CAST(ROUND(total_staff_payments * 100) AS INTEGER), 0)
+                COALESCE(total_staff_payments_cents, CAST(ROUND(total_staff_payments * 100) AS INTEGER), 0),
+                COALESCE(total_cash_rounding_cents, CAST(ROUND(total_cash_rounding * 100) AS INTEGER), 0),
+                COALESCE(cash_in_cents, CAST(ROUND(cash_in * 100) AS INTEGER), 0)
              FROM cash_drawer_sessions WHERE staff_shift_id = ?1",
             params![shift_id],
             |row| {
@@ -2725,6 +2819,8 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
                     "driverCashReturned": Cents::new(row.get::<_, i64>(10).unwrap_or(0)).to_f64_dp2(),
                     "unreconciledCount": if reconciled { 0 } else { 1 },
                     "staffPaymentsTotal": Cents::new(row.get::<_, i64>(12).unwrap_or(0)).to_f64_dp2(),
+                    "cashRounding": Cents::new(row.get::<_, i64>(13).unwrap_or(0)).to_f64_dp2(),
+                    "totalCashIn": Cents::new(row.get::<_, i64>(14).unwrap_or(0)).to_f64_dp2(),
                 }))
             },
         )
@@ -2782,6 +2878,7 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
             "closing": closing_cash,
             "expected": expected_cash,
             "totalCashDrops": 0.0,
+            "totalCashIn": 0.0,
             "driverCashGiven": 0.0,
             "driverCashReturned": 0.0,
             "staffPaymentsTotal": 0.0,
@@ -2826,6 +2923,7 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
                     SUM(COALESCE(amount_cents, CAST(ROUND(amount * 100) AS INTEGER))) AS refund_sum_cents
              FROM payment_adjustments
              WHERE adjustment_type = 'refund'
+               AND return_order_id IS NULL
              GROUP BY order_id
          ) r ON r.order_id = o.id
          WHERE o.staff_shift_id = ?1
@@ -2839,18 +2937,18 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
         .map_err(|e| format!("prepare order_type query: {e}"))?;
 
     let mut dine_in_orders = 0_i64;
-    let mut dine_in_sales = 0.0_f64;
+    let mut dine_in_sales = Cents::ZERO;
     let mut takeaway_orders = 0_i64;
-    let mut takeaway_sales = 0.0_f64;
+    let mut takeaway_sales = Cents::ZERO;
     let mut delivery_orders = 0_i64;
-    let mut delivery_sales = 0.0_f64;
+    let mut delivery_sales = Cents::ZERO;
 
     let ot_rows = ot_stmt
         .query_map(params![shift_id], |row| {
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, i64>(1)?,
-                Cents::new(row.get::<_, i64>(2)?).to_f64_dp2(),
+                Cents::new(row.get::<_, i64>(2)?),
             ))
         })
         .map_err(|e| format!("query order_type: {e}"))?;
@@ -2877,6 +2975,11 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
             }
         }
     }
+    let (dine_in_sales, takeaway_sales, delivery_sales) = (
+        dine_in_sales.to_f64_dp2(),
+        takeaway_sales.to_f64_dp2(),
+        delivery_sales.to_f64_dp2(),
+    );
 
     // Staff payments total (from staff_payments table if it exists)
     let staff_payments_total: f64 = conn
@@ -2907,7 +3010,7 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
     // excludes voided payments). Gross is an order-side figure; voids adjust it
     // down to money actually recognized. A prior review flagged this as a possible
     // double-deduction — it isn't.
-    let net_sales = gross_sales - refunds_total - voids_total - discounts_total;
+    let net_sales = sum_major_cents([gross_sales, -refunds_total, -voids_total, -discounts_total]);
     let opening = opening_cash;
     let closing = closing_cash.unwrap_or(0.0);
     let expected = expected_cash.unwrap_or(0.0);
@@ -2977,10 +3080,13 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
     // W4d-iv additive emission: every monetary float key (top-level and
     // nested) carries a `_cents` integer sibling so admin-dashboard can
     // read either shape during the bake window.
-    let total_sales = gross_sales - discounts_total;
-    let day_total = cash_sales + card_sales + other_sales;
+    let total_sales = sum_major_cents([gross_sales, -discounts_total]);
     let mut report_json = serde_json::json!({
         "date": report_date,
+        "currency": CurrencySettings::from_settings(&conn),
+        "taxBreakdown": tax::breakdown_json(&tax_breakdown),
+        "serviceCharges": service_charges.to_json(),
+        "drawerTransactions": drawer_transactions.to_json(),
         "shifts": shift_counts,
         "sales": {
             "totalOrders": total_orders,
@@ -3037,6 +3143,7 @@ pub fn generate_z_report(db: &DbState, payload: &Value) -> Result<Value, String>
             "totalOrders": total_orders,
         },
         "staffReports": staff_reports,
+        "adjustmentsByReason": adjustments_by_reason,
     });
     canonicalize_report_json_period(&mut report_json, period_start, period_end);
 
@@ -3370,9 +3477,9 @@ fn get_end_of_day_status_at(
 ) -> Result<Value, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
-    let branch_id = str_field(payload, "branchId")
-        .or_else(|| str_field(payload, "branch_id"))
-        .unwrap_or_else(|| storage::get_credential("branch_id").unwrap_or_default());
+    let branch_id = crate::branches::report_scope(
+        str_field(payload, "branchId").or_else(|| str_field(payload, "branch_id")),
+    );
 
     let now_rfc3339 = now.to_rfc3339();
     let _ = order_ownership::repair_historical_pickup_financial_attribution(
@@ -3468,9 +3575,9 @@ fn build_z_report_for_date(
 ) -> Result<BuiltDateZReport, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
-    let branch_id = str_field(payload, "branchId")
-        .or_else(|| str_field(payload, "branch_id"))
-        .unwrap_or_else(|| storage::get_credential("branch_id").unwrap_or_default());
+    let branch_id = crate::branches::report_scope(
+        str_field(payload, "branchId").or_else(|| str_field(payload, "branch_id")),
+    );
     let now = Utc::now().to_rfc3339();
     let _ = order_ownership::repair_historical_pickup_financial_attribution(
         &conn,
@@ -3554,17 +3661,20 @@ fn build_z_report_for_date(
     // still exist here — but their money was never collected, so they must not
     // be reported as revenue. They are counted on the day they are settled.
     let open_table_tab = business_day::open_unsettled_table_tab_expr("o");
+    // Deposit orders count only what was actually taken so far.
+    let deposit_due = business_day::deposit_balance_due_cents_expr("o");
     let order_agg_sql = format!(
         // W4b-iii: cents-with-real-fallback shim (removed in 4e).
         "SELECT COUNT(*) as cnt,
                 COALESCE(SUM(COALESCE(o.total_amount_cents, CAST(ROUND(o.total_amount * 100) AS INTEGER))
-                             + COALESCE(o.discount_amount_cents, CAST(ROUND(o.discount_amount * 100) AS INTEGER), 0)), 0) as gross_cents,
+                             + COALESCE(o.discount_amount_cents, CAST(ROUND(o.discount_amount * 100) AS INTEGER), 0)
+                             - {deposit_due}), 0) as gross_cents,
                 COALESCE(SUM(COALESCE(o.discount_amount_cents, CAST(ROUND(o.discount_amount * 100) AS INTEGER))), 0) as discounts_cents,
                 COALESCE(SUM(COALESCE(o.tip_amount_cents, CAST(ROUND(o.tip_amount * 100) AS INTEGER))), 0) as tips_cents
          FROM orders o
          WHERE {financial_predicate}
            AND (?2 IS NULL OR {financial_expr} <= ?2)
-           AND (?3 = '' OR o.branch_id = ?3 OR o.branch_id IS NULL)
+           AND (o.branch_id = ?3 OR o.branch_id IS NULL)
            AND COALESCE(o.is_ghost, 0) = 0
            AND o.status NOT IN ('cancelled', 'canceled')
            AND NOT {open_table_tab}"
@@ -3586,6 +3696,57 @@ fn build_z_report_for_date(
 
     let (total_orders, gross_sales, discounts_total, tips_total) = order_agg;
 
+    // --- Per-rate tax across all shifts ---
+    let tax_scope_sql = format!(
+        "SELECT o.tax_breakdown
+         FROM orders o
+         WHERE {financial_predicate}
+           AND (?2 IS NULL OR {financial_expr} <= ?2)
+           AND (o.branch_id = ?3 OR o.branch_id IS NULL)
+           AND o.tax_breakdown IS NOT NULL
+           AND COALESCE(o.is_ghost, 0) = 0
+           AND o.status NOT IN ('cancelled', 'canceled')
+           AND NOT {open_table_tab}"
+    );
+    let tax_breakdowns: Vec<String> = conn
+        .prepare(&tax_scope_sql)
+        .and_then(|mut stmt| {
+            stmt.query_map(params![period_start, cutoff_param, branch_id], |row| {
+                row.get::<_, String>(0)
+            })?
+            .collect()
+        })
+        .unwrap_or_default();
+    let tax_breakdown = tax::aggregate_breakdowns(tax_breakdowns.iter().map(String::as_str));
+    let service_charge_scope = format!(
+        "FROM orders o
+         WHERE {financial_predicate}
+           AND (?2 IS NULL OR {financial_expr} <= ?2)
+           AND (o.branch_id = ?3 OR o.branch_id IS NULL)
+           AND COALESCE(o.is_ghost, 0) = 0
+           AND o.status NOT IN ('cancelled', 'canceled')
+           AND NOT {open_table_tab}"
+    );
+    let service_charges = ServiceChargeTotals::collect(
+        &conn,
+        &service_charge_scope,
+        &[&period_start, &cutoff_param, &branch_id],
+    );
+    // Scoped like the drawer aggregate, by the session's opening time.
+    let drawer_transaction_scope = format!(
+        "FROM drawer_transactions dt
+         JOIN cash_drawer_sessions cds ON cds.id = dt.cash_drawer_session_id
+         WHERE {}
+           AND (?2 IS NULL OR cds.opened_at <= ?2)
+           AND (cds.branch_id = ?3 OR cds.branch_id IS NULL)",
+        lower_bound_mode.sql_predicate("cds.opened_at", "?1")
+    );
+    let drawer_transactions = DrawerTransactionTotals::collect(
+        &conn,
+        &drawer_transaction_scope,
+        &[&period_start, &cutoff_param, &branch_id],
+    );
+
     // --- Payments: breakdown by method across all shifts ---
     let payment_scope_expr = business_day::order_financial_timestamp_expr("o");
     let payment_scope_predicate = lower_bound_mode.sql_predicate(&payment_scope_expr, "?1");
@@ -3597,7 +3758,7 @@ fn build_z_report_for_date(
          JOIN orders o ON o.id = op.order_id
          WHERE {payment_scope_predicate}
            AND (?2 IS NULL OR {payment_scope_expr} <= ?2)
-           AND (?3 = '' OR o.branch_id = ?3 OR o.branch_id IS NULL)
+           AND (o.branch_id = ?3 OR o.branch_id IS NULL)
            AND op.status = 'completed'
            AND COALESCE(o.is_ghost, 0) = 0
            AND o.status NOT IN ('cancelled', 'canceled', 'refunded')
@@ -3607,9 +3768,9 @@ fn build_z_report_for_date(
         .prepare(&payment_scope_sql)
         .map_err(|e| format!("prepare payment query: {e}"))?;
 
-    let mut cash_sales = 0.0_f64;
-    let mut card_sales = 0.0_f64;
-    let mut other_sales = 0.0_f64;
+    let mut cash_sales = Cents::ZERO;
+    let mut card_sales = Cents::ZERO;
+    let mut other_sales = Cents::ZERO;
     let mut cash_count = 0_i64;
     let mut card_count = 0_i64;
     let mut other_count = 0_i64;
@@ -3619,7 +3780,7 @@ fn build_z_report_for_date(
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, i64>(1)?,
-                Cents::new(row.get::<_, i64>(2)?).to_f64_dp2(),
+                Cents::new(row.get::<_, i64>(2)?),
             ))
         })
         .map_err(|e| format!("query payments: {e}"))?;
@@ -3641,47 +3802,43 @@ fn build_z_report_for_date(
             }
         }
     }
+    // Totals stay in cents until here; everything below reads major units.
+    let day_total = (cash_sales + card_sales + other_sales).to_f64_dp2();
+    let (cash_sales, card_sales, other_sales) = (
+        cash_sales.to_f64_dp2(),
+        card_sales.to_f64_dp2(),
+        other_sales.to_f64_dp2(),
+    );
 
     // --- Adjustments: refunds and voids across all shifts ---
     let adjustment_scope_expr = business_day::order_financial_timestamp_expr("o");
     let adjustment_scope_predicate = lower_bound_mode.sql_predicate(&adjustment_scope_expr, "?1");
     let adjustment_scope_sql = format!(
         // W4b-iii: cents-with-real-fallback shim (removed in 4e).
-        "SELECT pa.adjustment_type,
+        "SELECT pa.adjustment_type, pa.reason_code, COUNT(*),
                 COALESCE(SUM(COALESCE(pa.amount_cents, CAST(ROUND(pa.amount * 100) AS INTEGER))), 0)
          FROM payment_adjustments pa
          JOIN orders o ON o.id = pa.order_id
          WHERE {adjustment_scope_predicate}
            AND (?2 IS NULL OR {adjustment_scope_expr} <= ?2)
-           AND (?3 = '' OR o.branch_id = ?3 OR o.branch_id IS NULL)
+           AND (o.branch_id = ?3 OR o.branch_id IS NULL)
            AND COALESCE(o.is_ghost, 0) = 0
            AND o.status NOT IN ('cancelled', 'canceled', 'refunded')
-         GROUP BY pa.adjustment_type"
+           AND pa.return_order_id IS NULL
+         GROUP BY pa.adjustment_type, pa.reason_code
+         ORDER BY pa.adjustment_type, pa.reason_code"
     );
     let mut adj_stmt = conn
         .prepare(&adjustment_scope_sql)
         .map_err(|e| format!("prepare adjustment query: {e}"))?;
 
-    let mut refunds_total = 0.0_f64;
-    let mut voids_total = 0.0_f64;
-
     let adj_rows = adj_stmt
         .query_map(params![period_start, cutoff_param, branch_id], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                Cents::new(row.get::<_, i64>(1)?).to_f64_dp2(),
-            ))
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
         })
         .map_err(|e| format!("query adjustments: {e}"))?;
-
-    for row in adj_rows.flatten() {
-        let (adj_type, amount) = row;
-        match adj_type.as_str() {
-            "refund" => refunds_total = amount,
-            "void" => voids_total = amount,
-            _ => warn!("Unknown adjustment type: {adj_type}"),
-        }
-    }
+    let (refunds_total, voids_total, adjustments_by_reason) =
+        collect_adjustment_totals(&conn, adj_rows.flatten());
 
     // --- Expenses (excluding staff_payment type) across all shifts ---
     // W4b-iii: cents-with-real-fallback shim (removed in 4e).
@@ -3692,7 +3849,7 @@ fn build_z_report_for_date(
                  FROM shift_expenses
                  WHERE {}
                    AND (?2 IS NULL OR created_at <= ?2)
-                   AND (?3 = '' OR branch_id = ?3 OR branch_id IS NULL)
+                   AND (branch_id = ?3 OR branch_id IS NULL)
                    AND (expense_type IS NULL OR expense_type != 'staff_payment')",
                 lower_bound_mode.sql_predicate("created_at", "?1")
             ),
@@ -3712,7 +3869,7 @@ fn build_z_report_for_date(
              LEFT JOIN staff_shifts ss ON ss.id = se.staff_shift_id
              WHERE {}
                AND (?2 IS NULL OR se.created_at <= ?2)
-               AND (?3 = '' OR se.branch_id = ?3 OR se.branch_id IS NULL)
+               AND (se.branch_id = ?3 OR se.branch_id IS NULL)
                AND (se.expense_type IS NULL OR se.expense_type != 'staff_payment')
              ORDER BY se.created_at ASC",
             lower_bound_mode.sql_predicate("se.created_at", "?1")
@@ -3753,11 +3910,13 @@ fn build_z_report_for_date(
                     COALESCE(SUM(COALESCE(driver_cash_given_cents, CAST(ROUND(driver_cash_given * 100) AS INTEGER))), 0),
                     COALESCE(SUM(COALESCE(driver_cash_returned_cents, CAST(ROUND(driver_cash_returned * 100) AS INTEGER))), 0),
                     SUM(CASE WHEN (reconciled = 0 OR reconciled IS NULL) THEN 1 ELSE 0 END),
-                    COALESCE(SUM(COALESCE(total_staff_payments_cents, CAST(ROUND(total_staff_payments * 100) AS INTEGER))), 0)
+                    COALESCE(SUM(COALESCE(total_staff_payments_cents, CAST(ROUND(total_staff_payments * 100) AS INTEGER))), 0),
+                    COALESCE(SUM(COALESCE(total_cash_rounding_cents, CAST(ROUND(total_cash_rounding * 100) AS INTEGER))), 0),
+                    COALESCE(SUM(COALESCE(cash_in_cents, CAST(ROUND(cash_in * 100) AS INTEGER))), 0)
              FROM cash_drawer_sessions
              WHERE {}
                AND (?2 IS NULL OR opened_at <= ?2)
-               AND (?3 = '' OR branch_id = ?3 OR branch_id IS NULL)",
+               AND (branch_id = ?3 OR branch_id IS NULL)",
                 lower_bound_mode.sql_predicate("opened_at", "?1")
             ),
             params![period_start, cutoff_param, branch_id],
@@ -3776,6 +3935,8 @@ fn build_z_report_for_date(
                     "driverCashReturned": Cents::new(row.get::<_, i64>(10)?).to_f64_dp2(),
                     "unreconciledCount": row.get::<_, i64>(11)?,
                     "staffPaymentsTotal": Cents::new(row.get::<_, i64>(12)?).to_f64_dp2(),
+                    "cashRounding": Cents::new(row.get::<_, i64>(13)?).to_f64_dp2(),
+                    "totalCashIn": Cents::new(row.get::<_, i64>(14)?).to_f64_dp2(),
                 }))
             },
         )
@@ -3819,7 +3980,7 @@ fn build_z_report_for_date(
          FROM orders o
          WHERE {order_type_scope_predicate}
            AND (?2 IS NULL OR {order_type_scope_expr} <= ?2)
-           AND (?3 = '' OR o.branch_id = ?3 OR o.branch_id IS NULL)
+           AND (o.branch_id = ?3 OR o.branch_id IS NULL)
            AND COALESCE(o.is_ghost, 0) = 0
            AND o.status NOT IN ('cancelled', 'canceled')
            AND NOT {order_type_open_tab}
@@ -3830,18 +3991,18 @@ fn build_z_report_for_date(
         .map_err(|e| format!("prepare order_type query: {e}"))?;
 
     let mut dine_in_orders = 0_i64;
-    let mut dine_in_sales = 0.0_f64;
+    let mut dine_in_sales = Cents::ZERO;
     let mut takeaway_orders = 0_i64;
-    let mut takeaway_sales = 0.0_f64;
+    let mut takeaway_sales = Cents::ZERO;
     let mut delivery_orders = 0_i64;
-    let mut delivery_sales = 0.0_f64;
+    let mut delivery_sales = Cents::ZERO;
 
     let ot_rows = ot_stmt
         .query_map(params![period_start, cutoff_param, branch_id], |row| {
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, i64>(1)?,
-                Cents::new(row.get::<_, i64>(2)?).to_f64_dp2(),
+                Cents::new(row.get::<_, i64>(2)?),
             ))
         })
         .map_err(|e| format!("query order_type: {e}"))?;
@@ -3867,6 +4028,11 @@ fn build_z_report_for_date(
             }
         }
     }
+    let (dine_in_sales, takeaway_sales, delivery_sales) = (
+        dine_in_sales.to_f64_dp2(),
+        takeaway_sales.to_f64_dp2(),
+        delivery_sales.to_f64_dp2(),
+    );
 
     // --- Staff payments total across all shifts ---
     let staff_payments_total: f64 = conn
@@ -3877,7 +4043,7 @@ fn build_z_report_for_date(
                  LEFT JOIN staff_shifts ss ON ss.id = sp.cashier_shift_id
                  WHERE {}
                    AND (?2 IS NULL OR sp.created_at <= ?2)
-                   AND (?3 = '' OR ss.branch_id = ?3 OR ss.branch_id IS NULL)",
+                   AND (ss.branch_id = ?3 OR ss.branch_id IS NULL)",
                 lower_bound_mode.sql_predicate("sp.created_at", "?1")
             ),
             params![period_start, cutoff_param, branch_id],
@@ -3891,7 +4057,7 @@ fn build_z_report_for_date(
              FROM shift_expenses
              WHERE {}
                AND (?2 IS NULL OR created_at <= ?2)
-               AND (?3 = '' OR branch_id = ?3 OR branch_id IS NULL)
+               AND (branch_id = ?3 OR branch_id IS NULL)
                AND status = 'pending'
                AND (expense_type IS NULL OR expense_type != 'staff_payment')",
                 lower_bound_mode.sql_predicate("created_at", "?1")
@@ -3905,11 +4071,11 @@ fn build_z_report_for_date(
     // See single-shift path for the rationale: gross_sales is order-level
     // (orders.total_amount, NOT cash_sales + card_sales), so subtracting
     // voids_total does not double-count against payment-level figures.
-    let net_sales = gross_sales - refunds_total - voids_total - discounts_total;
+    let net_sales = sum_major_cents([gross_sales, -refunds_total, -voids_total, -discounts_total]);
 
     // Sum opening/closing/variance across all cashier shifts
-    let total_opening: f64 = shifts.iter().map(|s| s.opening_cash).sum();
-    let total_closing: f64 = shifts.iter().map(|s| s.closing_cash.unwrap_or(0.0)).sum();
+    let total_opening = sum_major_cents(shifts.iter().map(|s| s.opening_cash));
+    let total_closing = sum_major_cents(shifts.iter().map(|s| s.closing_cash.unwrap_or(0.0)));
     let total_expected: f64 = shifts.iter().map(|s| s.expected_cash.unwrap_or(0.0)).sum();
     let total_variance: f64 = shifts.iter().map(|s| s.cash_variance.unwrap_or(0.0)).sum();
 
@@ -3920,6 +4086,7 @@ fn build_z_report_for_date(
             "closing": total_closing,
             "expected": total_expected,
             "totalCashDrops": 0.0,
+            "totalCashIn": 0.0,
             "driverCashGiven": 0.0,
             "driverCashReturned": 0.0,
             "unreconciledCount": 0,
@@ -4011,10 +4178,13 @@ fn build_z_report_for_date(
     // Build Electron-compatible report_json.
     // W4d-iv additive emission: every monetary float key carries a `_cents`
     // sibling. Mirrors the single-shift body at line 2844.
-    let total_sales = gross_sales - discounts_total;
-    let day_total = cash_sales + card_sales + other_sales;
+    let total_sales = sum_major_cents([gross_sales, -discounts_total]);
     let mut report_json = serde_json::json!({
         "date": date,
+        "currency": CurrencySettings::from_settings(&conn),
+        "taxBreakdown": tax::breakdown_json(&tax_breakdown),
+        "serviceCharges": service_charges.to_json(),
+        "drawerTransactions": drawer_transactions.to_json(),
         "shifts": {
             "total": shifts_total,
             "cashier": shifts_cashier,
@@ -4076,6 +4246,7 @@ fn build_z_report_for_date(
             "totalOrders": total_orders,
         },
         "staffReports": staff_reports,
+        "adjustmentsByReason": adjustments_by_reason,
     });
     canonicalize_report_json_period(&mut report_json, period_start.as_str(), period_end.as_str());
 
@@ -4391,9 +4562,9 @@ pub(crate) fn prepare_z_report_submission(
     db: &DbState,
     payload: &Value,
 ) -> Result<PreparedZReportSubmission, String> {
-    let branch_id = str_field(payload, "branchId")
-        .or_else(|| str_field(payload, "branch_id"))
-        .unwrap_or_else(|| storage::get_credential("branch_id").unwrap_or_default());
+    let branch_id = crate::branches::report_scope(
+        str_field(payload, "branchId").or_else(|| str_field(payload, "branch_id")),
+    );
     let window = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
         let _ = order_ownership::repair_historical_pickup_financial_attribution(
@@ -5132,10 +5303,15 @@ mod tests {
         )
         .expect("set pragmas");
         db::run_migrations_for_test(&conn);
-        DbState {
-            conn: std::sync::Mutex::new(conn),
-            db_path: std::path::PathBuf::from(":memory:"),
-        }
+        DbState::new(conn, std::path::PathBuf::from(":memory:"))
+    }
+
+    #[test]
+    fn sum_major_cents_has_no_float_residue() {
+        assert_eq!(sum_major_cents([0.1, 0.2]), 0.3);
+        assert_eq!(sum_major_cents([100.0, -0.1, -0.2, -99.7]), 0.0);
+        let lines = std::iter::repeat(0.01).take(1000);
+        assert_eq!(sum_major_cents(lines), 10.0);
     }
 
     fn local_datetime(
@@ -5268,8 +5444,8 @@ mod tests {
 
         // Insert a refund adjustment on payment 1 (10.0 → 1000).
         conn.execute(
-            "INSERT INTO payment_adjustments (id, payment_id, order_id, adjustment_type, amount, amount_cents, reason, sync_state, created_at, updated_at)
-             VALUES ('adj-1', 'pay-1', 'ord-1', 'refund', 10.0, 1000, 'wrong item', 'pending', ?1, ?1)",
+            "INSERT INTO payment_adjustments (id, payment_id, order_id, adjustment_type, amount, amount_cents, reason, reason_code, sync_state, created_at, updated_at)
+             VALUES ('adj-1', 'pay-1', 'ord-1', 'refund', 10.0, 1000, 'wrong item', 'wrong_order', 'pending', ?1, ?1)",
             params![now],
         ).expect("insert adjustment");
 
@@ -5331,6 +5507,13 @@ mod tests {
         assert_eq!(staff_reports[0]["orders"]["cashAmount"], 60.0);
         assert_eq!(staff_reports[0]["orders"]["cardAmount"], 40.0);
         assert_eq!(staff_reports[0]["returnedToDrawerAmount"], 235.0);
+        let by_reason = report_json["adjustmentsByReason"].as_array().unwrap();
+        assert_eq!(by_reason.len(), 1);
+        assert_eq!(by_reason[0]["type"], "refund");
+        assert_eq!(by_reason[0]["reasonCode"], "wrong_order");
+        assert_eq!(by_reason[0]["label"], "Wrong order delivered");
+        assert_eq!(by_reason[0]["count"], 1);
+        assert_eq!(by_reason[0]["total"], 10.0);
 
         // Verify z_reports table has 1 row
         let conn = db.conn.lock().unwrap();
@@ -5350,6 +5533,40 @@ mod tests {
         assert_eq!(sq_count, 1);
     }
 
+    #[test]
+    fn return_order_nets_sales_without_counting_its_refund_again() {
+        let db = test_db();
+        let shift_id = seed_closed_shift(&db);
+        {
+            let conn = db.conn.lock().unwrap();
+            // A 5.00 return on ord-2, refunded on its cash payment.
+            conn.execute(
+                "INSERT INTO orders (
+                    id, order_number, items, total_amount, total_amount_cents, status, order_type,
+                    payment_status, staff_shift_id, related_order_id, is_return,
+                    sync_status, created_at, updated_at
+                 ) VALUES ('ret-1', '#4', '[]', -5.0, -500, 'completed', 'dine-in',
+                    'refunded', ?1, 'ord-2', 1, 'pending', '2026-02-16T12:00:00Z',
+                    '2026-02-16T12:00:00Z')",
+                params![shift_id],
+            )
+            .expect("insert return order");
+            conn.execute(
+                "INSERT INTO payment_adjustments (id, payment_id, order_id, adjustment_type, amount, amount_cents, reason, reason_code, sync_state, return_order_id, created_at, updated_at)
+                 VALUES ('adj-ret', 'pay-2', 'ord-2', 'refund', 5.0, 500, 'returned', 'other', 'pending', 'ret-1', '2026-02-16T12:00:00Z', '2026-02-16T12:00:00Z')",
+                [],
+            )
+            .expect("insert return refund");
+        }
+
+        let result = generate_z_report(&db, &serde_json::json!({ "shiftId": shift_id }))
+            .expect("generate should succeed");
+        let report = &result["report"];
+        assert_eq!(report["grossSales"], 95.0);
+        assert_eq!(report["refundsTotal"], 10.0);
+        assert_eq!(report["netSales"], 85.0);
+    }
+
     #[test]
     fn test_generate_z_report_prefers_stored_shift_report_date() {
         let db = test_db();
@@ -5987,7 +6204,6 @@ mod tests {
 
         {
             let conn = db.conn.lock().unwrap();
-            ensure_staff_payments_table(&conn);
             conn.execute(
                 "INSERT INTO staff_shifts (
                     id, staff_id, staff_name, branch_id, terminal_id, role_type,
@@ -6219,7 +6435,8 @@ mod tests {
             .expect("preview reportJson object");
         assert_eq!(report_json["shifts"]["total"], 1);
         assert_eq!(report_json["sales"]["totalOrders"], 1);
-        assert_eq!(report_json["sales"]["totalSales"], 22.0);
+        // Partially paid: only the 13.00 actually received counts as sales.
+        assert_eq!(report_json["sales"]["totalSales"], 13.0);
         assert_eq!(report_json["sales"]["cashSales"], 13.0);
         assert_eq!(report_json["cashDrawer"]["expected"], 113.0);
         assert_eq!(report_json["cashDrawer"]["moneyInDrawer"], 113.0);
@@ -6236,7 +6453,7 @@ mod tests {
         assert_eq!(staff_reports[0]["shiftStatus"], "active");
         assert_eq!(staff_reports[0]["orders"]["count"], 1);
         assert_eq!(staff_reports[0]["orders"]["cashAmount"], 13.0);
-        assert_eq!(staff_reports[0]["orders"]["totalAmount"], 22.0);
+        assert_eq!(staff_reports[0]["orders"]["totalAmount"], 13.0);
         assert_eq!(staff_reports[0]["drawer"]["expected"], 113.0);
         assert_eq!(
             staff_reports[0]["ordersDetails"][0]["orderNumber"],
@@ -7112,6 +7329,39 @@ mod tests {
         assert_eq!(report["totalOrders"], 3);
     }
 
+    #[test]
+    fn test_generate_z_report_counts_only_received_deposit_for_partially_paid_order() {
+        let db = test_db();
+        let shift_id = seed_closed_shift(&db);
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO orders (
+                    id, order_number, items, total_amount, total_amount_cents, status, order_type,
+                    payment_status, staff_shift_id, sync_status, created_at, updated_at
+                 ) VALUES ('ord-deposit', '#dep', '[]', 200.0, 20000, 'confirmed', 'pickup',
+                    'partially_paid', ?1, 'pending', '2026-02-16T12:00:00Z', '2026-02-16T12:00:00Z')",
+                params![shift_id],
+            )
+            .expect("insert deposit order");
+            conn.execute(
+                "INSERT INTO order_payments (id, order_id, method, amount, amount_cents, status, staff_shift_id, currency, created_at, updated_at)
+                 VALUES ('pay-dep', 'ord-deposit', 'card', 60.0, 6000, 'completed', ?1, 'EUR', '2026-02-16T12:00:00Z', '2026-02-16T12:00:00Z')",
+                params![shift_id],
+            )
+            .expect("insert deposit payment");
+        }
+
+        let result =
+            generate_z_report(&db, &serde_json::json!({ "shiftId": shift_id })).expect("generate");
+        let report = &result["report"];
+        assert_eq!(
+            report["grossSales"], 160.0,
+            "a deposit order contributes the 60 received, not its 200 total"
+        );
+        assert_eq!(report["cardSales"], 100.0);
+    }
+
     // Review round 2: a CANCELLED unpaid table order is dead history, not a
     // live tab — it must remain deletable or it accumulates forever.
     #[test]
@@ -7234,7 +7484,6 @@ mod tests {
 
         {
             let conn = db.conn.lock().unwrap();
-            ensure_staff_payments_table(&conn);
             conn.execute(
                 "INSERT INTO staff_payments (
                     id, cashier_shift_id, paid_to_staff_id, amount, payment_type, notes, created_at
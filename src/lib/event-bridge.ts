@@ -30,6 +30,9 @@ const EVENT_MAP: Record<string, string> = {
   'terminal_enabled': 'terminal-enabled',
   'app_reset': 'app:reset',
   'terminal_auth_paused': 'terminal-auth-paused',
+  'terminal_repair_progress': 'terminal-repair:progress',
+  'organization_change_required': 'organization-change:required',
+  'organization_change_resolved': 'organization-change:resolved',
 
   // --- Auto-updater events ---
   'update_checking': 'update-checking',
@@ -45,6 +48,8 @@ const EVENT_MAP: Record<string, string> = {
   'order_created': 'order-created',
   'order_deleted': 'order-deleted',
   'order_payment_updated': 'order-payment-updated',
+  'order_prices_stale': 'order-prices-stale',
+  'dispatch_updated': 'dispatch-updated',
 
   // --- Customer events ---
   'customer_created': 'customer-created',
@@ -53,6 +58,8 @@ const EVENT_MAP: Record<string, string> = {
   'customer_realtime_update': 'customer-realtime-update',
   'customer_sync_conflict': 'customer-sync-conflict',
   'customer_conflict_resolved': 'customer-conflict-resolved',
+  'customer_import_progress': 'customer-import-progress',
+  'customer_import_completed': 'customer-import-completed',
 
   // --- Conflict and retry events ---
   'order_sync_conflict': 'order-sync-conflict',
@@ -65,6 +72,9 @@ const EVENT_MAP: Record<string, string> = {
   // Parity-queue capacity early warning (~80% of the fail-closed enqueue
   // cap); payload is the backend `QueueCapacityWarning` struct.
   'sync:queue-capacity-warning': 'sync:queue-capacity-warning',
+  // Oldest unsent financial row older than `sync.backlog_warning_minutes`;
+  // `null` once it clears.
+  'sync_backlog_warning': 'sync:backlog-warning',
   'network_status': 'network:status',
   'settings_update': 'settings:update',
   'staff_permission_update': 'staff:permission-update',
@@ -75,6 +85,7 @@ const EVENT_MAP: Record<string, string> = {
 
   // --- Shift events ---
   'shift_updated': 'shift-updated',
+  'tab_updated': 'tab:updated',
 
   // --- Database health ---
   'database_health_update': 'database-health-update',
@@ -82,9 +93,13 @@ const EVENT_MAP: Record<string, string> = {
   // --- Terminal settings ---
   'terminal_settings_updated': 'terminal-settings-updated',
   'terminal_credentials_updated': 'terminal-credentials-updated',
+  // Startup report from `credentials_validate` when keyring and
+  // local_settings disagree; payload carries masked values only.
+  'credentials_inconsistent': 'credentials-inconsistent',
 
   // --- Session management ---
   'session_timeout': 'session-timeout',
+  'terminal_locked': 'terminal-locked',
 
   // --- Window state ---
   'window_state_changed': 'window-state-changed',
@@ -93,6 +108,7 @@ const EVENT_MAP: Record<string, string> = {
   'menu_sync': 'menu:sync',
   'menu_check_for_updates': 'menu:check-for-updates',
   'menu_version_checked': 'menu:version-checked',
+  'favorites_updated': 'favorites:updated',
 
   // --- Screen capture ---
   'screen_capture_start': 'screen-capture:start',
@@ -140,6 +156,19 @@ const CHANNEL_TO_TAURI_EVENT = Object.entries(EVENT_MAP).reduce<Record<string, s
   {}
 );
 
+/**
+ * Events the backend coalesces during bulk work (see
+ * `src-tauri/src/event_batcher.rs`). A burst arrives as `<event>_batch`
+ * carrying an array of the individual payloads; the bridge fans it back out
+ * in order so channel listeners always see one payload per callback.
+ */
+const BATCHED_TAURI_EVENTS = new Set<string>([
+  'order_realtime_update',
+  'order_created',
+  'order_status_updated',
+  'order_deleted',
+]);
+
 const listenersByChannel = new Map<string, Set<EventCallback>>();
 const unlistenByChannel = new Map<string, UnlistenFn>();
 const pendingAttachByChannel = new Map<string, Promise<void>>();
@@ -166,9 +195,21 @@ async function attachChannelListener(channel: string): Promise<void> {
 
   const attachPromise = (async () => {
     const { listen } = await import('@tauri-apps/api/event');
-    const unlisten = await listen<any>(tauriEvent, (event) => {
+    const unlistenSingle = await listen<any>(tauriEvent, (event) => {
       dispatch(channel, event.payload);
     });
+    const unlistenBatch = BATCHED_TAURI_EVENTS.has(tauriEvent)
+      ? await listen<unknown>(`${tauriEvent}_batch`, (event) => {
+          if (!Array.isArray(event.payload)) return;
+          for (const payload of event.payload) {
+            dispatch(channel, payload);
+          }
+        })
+      : null;
+    const unlisten = () => {
+      unlistenSingle();
+      unlistenBatch?.();
+    };
 
     const listeners = listenersByChannel.get(channel);
     if (!listeners || listeners.size === 0) {
@@ -21,6 +21,8 @@ export {
   resetBridge,
   createBridge,
   TauriBridge,
+  PosIpcError,
+  isPosErrorPayload,
   CHANNEL_MAP,
   type PlatformBridge,
   type IpcResult,
@@ -69,6 +71,8 @@ export type {
   PrivilegedActionConfirmResponse,
   PrivilegedActionErrorPayload,
   PrivilegedActionScope,
+  PosErrorCode,
+  PosErrorPayload,
   StaffCheckInPinVerifyRequest,
   StaffCheckInPinVerifyResponse,
   EndOfDayStatusResponse,
@@ -19,6 +19,11 @@ import type {
   PrivilegedActionConfirmResponse,
   ResetStartResponse,
   ResetStatus,
+  DestructiveOperation,
+  DestructiveOpPrepareResponse,
+  DestructiveOpConfirmation,
+  TerminalRepairBeginResponse,
+  TerminalRepairFinishResponse,
   StaffCheckInPinVerifyRequest,
   StaffCheckInPinVerifyResponse,
   DiagnosticsAboutInfo,
@@ -53,6 +58,42 @@ import type {
   TerminalRuntimeConfig,
   ZReportSubmitResponse,
 } from "./ipc-contracts";
+import type { PosErrorPayload } from "./ipc-contracts";
+
+// ============================================================================
+// Typed command errors
+// ============================================================================
+
+/**
+ * Rejection from a command that returns a typed `PosError`. Extends `Error`
+ * so existing `err.message` / `String(err)` call sites keep showing the same
+ * text they did when commands rejected with a plain string.
+ */
+export class PosIpcError extends Error implements PosErrorPayload {
+  readonly code: string;
+  readonly details: Record<string, unknown> | null;
+
+  constructor(payload: PosErrorPayload) {
+    super(payload.message);
+    this.name = "PosIpcError";
+    this.code = payload.code;
+    this.details = payload.details ?? null;
+  }
+
+  override toString(): string {
+    return this.message;
+  }
+}
+
+export function isPosErrorPayload(value: unknown): value is PosErrorPayload {
+  if (!value || typeof value !== "object") return false;
+  const candidate = value as Record<string, unknown>;
+  return (
+    typeof candidate.code === "string" &&
+    typeof candidate.message === "string" &&
+    "details" in candidate
+  );
+}
 
 // ============================================================================
 // Payload & Response Types
@@ -829,6 +870,22 @@ export interface SyncStatus {
     lastError: string;
     classification: "backpressure" | "transient" | "permanent" | "unknown";
   } | null;
+  entityBacklog?: Record<string, SyncEntityBacklog>;
+  lastPushAt?: string | null;
+  lastPullAt?: string | null;
+}
+
+export interface SyncEntityBacklog {
+  entity: string;
+  financial: boolean;
+  pending: number;
+  inProgress: number;
+  failed: number;
+  dead: number;
+  oldestPendingAt: string | null;
+  oldestPendingAgeSecs: number | null;
+  lastFailureAt: string | null;
+  lastFailureError: string | null;
 }
 
 export interface NetworkStatus {
@@ -1246,6 +1303,13 @@ export interface PlatformBridge {
     validateSession(): Promise<SessionValidationResponse>;
     hasPermission(permission: string): Promise<boolean>;
     getSessionStats(): Promise<any>;
+    lockTerminal(): Promise<any>;
+    switchUser(request: {
+      pin: string;
+      sessionId?: string;
+      staffId?: string;
+      branchId?: string;
+    }): Promise<AuthLoginResponse>;
     setupPin(request: AuthSetupPinInput): Promise<IpcResult>;
     confirmPrivilegedAction(
       request: PrivilegedActionConfirmRequest,
@@ -1387,7 +1451,7 @@ export interface PlatformBridge {
     ): Promise<IpcResult>;
     forceSyncRetry(orderId: string): Promise<IpcResult>;
     getRetryInfo(orderId: string): Promise<any>;
-    clearAll(): Promise<IpcResult>;
+    clearAll(confirmation: DestructiveOpConfirmation): Promise<IpcResult>;
   };
 
   // -- Payments --------------------------------------------------------------
@@ -1431,7 +1495,7 @@ export interface PlatformBridge {
     clearAll(): Promise<IpcResult>;
     clearFailed(): Promise<IpcResult>;
     clearOldOrders(): Promise<IpcResult>;
-    clearAllOrders(): Promise<IpcResult>;
+    clearAllOrders(confirmation: DestructiveOpConfirmation): Promise<IpcResult>;
     cleanupDeletedOrders(): Promise<IpcResult>;
     getFinancialStats(): Promise<any>;
     getFailedFinancialItems(limit?: number): Promise<SyncFinancialQueueItem[]>;
@@ -1486,6 +1550,19 @@ export interface PlatformBridge {
       data?: any,
     ): Promise<IpcResult>;
     getConflicts(filters?: any): Promise<any[]>;
+    importCsv(options: {
+      path?: string;
+      csv?: string;
+      columns?: Record<string, string>;
+      duplicateStrategy?: "skip" | "update" | "create_duplicate";
+      dryRun?: boolean;
+      delimiter?: string;
+      branchId?: string;
+    }): Promise<any>;
+    exportData(
+      target: string | { customerId?: string; phone?: string },
+    ): Promise<any>;
+    erase(target: string | { customerId?: string; phone?: string }): Promise<any>;
   };
 
   // -- Settings --------------------------------------------------------------
@@ -1516,8 +1593,27 @@ export interface PlatformBridge {
     ): Promise<IpcResult>;
     isConfigured(): Promise<SettingsConfiguredResponse>;
     getResetStatus(): Promise<ResetStatus | null>;
-    factoryReset(): Promise<ResetStartResponse>;
+    prepareDestructiveOp(
+      operation: DestructiveOperation,
+    ): Promise<DestructiveOpPrepareResponse>;
+    factoryReset(
+      confirmation: DestructiveOpConfirmation,
+    ): Promise<ResetStartResponse>;
     emergencyReset(): Promise<ResetStartResponse>;
+    beginTerminalRepair(
+      connectionCode: string,
+    ): Promise<TerminalRepairBeginResponse>;
+    migrateTerminalRepair(
+      repairId?: string,
+    ): Promise<TerminalRepairFinishResponse>;
+    discardTerminalRepair(
+      repairId?: string,
+    ): Promise<TerminalRepairFinishResponse>;
+    getOrganizationChange(): Promise<IpcResult>;
+    confirmOrganizationChange(params: {
+      action: "wipe" | "retag";
+      adminPin?: string;
+    }): Promise<IpcResult>;
   };
 
   // -- Terminal config -------------------------------------------------------
@@ -1716,6 +1812,12 @@ export interface PlatformBridge {
       branchId: string;
       date?: string;
     }): Promise<ZReportSubmitResponse>;
+    getMargin(params: {
+      branchId?: string;
+      dateFrom?: string;
+      dateTo?: string;
+      varianceThresholdPercent?: number;
+    }): Promise<any>;
   };
 
   // -- Menu ------------------------------------------------------------------
@@ -1731,6 +1833,13 @@ export interface PlatformBridge {
     updateIngredient(id: string, updates: any): Promise<IpcResult>;
     updateCombo(id: string, updates: any): Promise<IpcResult>;
     triggerCheckForUpdates(): Promise<void>;
+    getFavoritesGrid(params?: { staffId?: string }): Promise<any>;
+    setFavoritesGrid(params: { grid: any; staffId?: string }): Promise<any>;
+    autofillFavorites(params?: {
+      rows?: number;
+      cols?: number;
+      branchId?: string;
+    }): Promise<any>;
   };
 
   // -- Printer ---------------------------------------------------------------
@@ -1882,6 +1991,8 @@ export interface PlatformBridge {
     saveConfig(config: any): Promise<{ success: boolean }>;
     getConfig(): Promise<any>;
     testConnection(config?: any): Promise<{ success: boolean; message: string; reasonCode?: string }>;
+    configure(config: any): Promise<{ success: boolean; config: any; status: any }>;
+    simulateCall(call: any): Promise<any>;
   };
 
   // -- Loyalty ---------------------------------------------------------------
@@ -2072,6 +2183,30 @@ export interface PlatformBridge {
     }>;
   };
 
+  // -- Bar tabs --------------------------------------------------------------
+  tabs: {
+    open(params: {
+      orderId: string;
+      holdAmount?: number;
+      deviceId?: string;
+      cardReference?: string;
+      cardLastFour?: string;
+      manual?: boolean;
+      currency?: string;
+      staffId?: string;
+      idempotencyKey?: string;
+    }): Promise<IpcResult>;
+    close(params: {
+      orderId: string;
+      tipAmount?: number;
+      currency?: string;
+      staffId?: string;
+      staffShiftId?: string;
+      idempotencyKey?: string;
+    }): Promise<IpcResult>;
+    listOpen(branchId?: string): Promise<IpcResult>;
+  };
+
   // -- Diagnostics -----------------------------------------------------------
   diagnostics: {
     getAbout(): Promise<DiagnosticsAboutInfo>;
@@ -2137,6 +2272,8 @@ export const CHANNEL_MAP: Record<string, string> = {
   "auth:validate-session": "auth.validateSession",
   "auth:has-permission": "auth.hasPermission",
   "auth:get-session-stats": "auth.getSessionStats",
+  "auth:lock-terminal": "auth.lockTerminal",
+  "auth:switch-user": "auth.switchUser",
   "auth:setup-pin": "auth.setupPin",
   "auth:confirm-privileged-action": "auth.confirmPrivilegedAction",
   // Wave 11 L: `auth:secure-session-*` channels were invoked from the
@@ -2166,6 +2303,9 @@ export const CHANNEL_MAP: Record<string, string> = {
   "order:get-by-id": "orders.getById",
   "order:create": "orders.create",
   "order:create-with-initial-payment": "orders.createWithInitialPayment",
+  "order:create-return": "orders.createReturn",
+  "order:estimate-prep-time": "orders.estimatePrepTime",
+  "order:refresh-prices": "orders.refreshPrices",
   "order:update-status": "orders.updateStatus",
   "order:update-items": "orders.updateItems",
   "order:update-customer-info": "orders.updateCustomerInfo",
@@ -2249,6 +2389,9 @@ export const CHANNEL_MAP: Record<string, string> = {
   "customer:delete-address": "customers.deleteAddress",
   "customer:resolve-conflict": "customers.resolveConflict",
   "customer:get-conflicts": "customers.getConflicts",
+  "customer:import-csv": "customers.importCsv",
+  "customer:export-data": "customers.exportData",
+  "customer:erase": "customers.erase",
 
   // Settings
   "get-settings": "settings.get",
@@ -2270,8 +2413,14 @@ export const CHANNEL_MAP: Record<string, string> = {
   "settings:clear-connection": "settings.clearConnection",
   "settings:update-terminal-credentials": "settings.updateTerminalCredentials",
   "settings:is-configured": "settings.isConfigured",
+  "destructive-op:prepare": "settings.prepareDestructiveOp",
   "settings:factory-reset": "settings.factoryReset",
   "settings:emergency-reset": "settings.emergencyReset",
+  "terminal-repair:begin": "settings.beginTerminalRepair",
+  "terminal-repair:migrate": "settings.migrateTerminalRepair",
+  "terminal-repair:discard": "settings.discardTerminalRepair",
+  "organization-change:status": "settings.getOrganizationChange",
+  "organization-change:confirm": "settings.confirmOrganizationChange",
 
   // Terminal config
   "terminal-config:get-settings": "terminalConfig.getSettings",
@@ -2317,6 +2466,9 @@ export const CHANNEL_MAP: Record<string, string> = {
   "driver:get-earnings": "drivers.getEarnings",
   "driver:get-shift-summary": "drivers.getShiftSummary",
   "driver:get-active": "drivers.getActive",
+  "driver:start-run": "drivers.startRun",
+  "driver:complete-run": "drivers.completeRun",
+  "dispatch:get-board": "drivers.getDispatchBoard",
 
   // Delivery zones
   "delivery-zone:track-validation": "deliveryZones.trackValidation",
@@ -2337,6 +2489,7 @@ export const CHANNEL_MAP: Record<string, string> = {
   "report:print-z-report": "reports.printZReport",
   "report:submit-z-report": "reports.submitZReport",
   "report:resolve-payment-blocker": "reports.resolvePaymentBlocker",
+  "reports:get-margin": "reports.getMargin",
 
   // Product dashboard metrics
   // Direct-invoke channels (no bridge method). See comment above
@@ -2356,6 +2509,9 @@ export const CHANNEL_MAP: Record<string, string> = {
   "menu:update-ingredient": "menu.updateIngredient",
   "menu:update-combo": "menu.updateCombo",
   "menu:trigger-check-for-updates": "menu.triggerCheckForUpdates",
+  "favorites:get-grid": "menu.getFavoritesGrid",
+  "favorites:set-grid": "menu.setFavoritesGrid",
+  "favorites:autofill": "menu.autofillFavorites",
 
   // Printer
   "printer:list-system-printers": "printer.listSystemPrinters",
@@ -2446,6 +2602,8 @@ export const CHANNEL_MAP: Record<string, string> = {
   "callerid:save-config": "callerid.saveConfig",
   "callerid:get-config": "callerid.getConfig",
   "callerid:test": "callerid.testConnection",
+  "callerid:configure": "callerid.configure",
+  "callerid:simulate-call": "callerid.simulateCall",
 
   // Loyalty
   "loyalty:get-settings": "loyalty.getSettings",
@@ -2528,6 +2686,11 @@ export const CHANNEL_MAP: Record<string, string> = {
   "refund:list-order-adjustments": "refunds.listOrderAdjustments",
   "refund:get-payment-balance": "refunds.getPaymentBalance",
 
+  // Bar tabs
+  "tab:open": "tabs.open",
+  "tab:close": "tabs.close",
+  "tab:list-open": "tabs.listOpen",
+
   // Diagnostics
   "diagnostics:get-about": "diagnostics.getAbout",
   "diagnostics:get-system-health": "diagnostics.getSystemHealth",
@@ -2589,7 +2752,11 @@ export class TauriBridge implements PlatformBridge {
   constructor() {
     this.tauriInvoke = async (cmd: string, args?: Record<string, unknown>) => {
       const { invoke } = await import("@tauri-apps/api/core");
-      return invoke(cmd, args);
+      try {
+        return await invoke(cmd, args);
+      } catch (error) {
+        throw isPosErrorPayload(error) ? new PosIpcError(error) : error;
+      }
     };
   }
 
@@ -2702,6 +2869,13 @@ export class TauriBridge implements PlatformBridge {
     validateSession: () => this.inv("auth:validate-session"),
     hasPermission: (p: string) => this.inv("auth:has-permission", p),
     getSessionStats: () => this.inv("auth:get-session-stats"),
+    lockTerminal: () => this.inv("auth:lock-terminal"),
+    switchUser: (request: {
+      pin: string;
+      sessionId?: string;
+      staffId?: string;
+      branchId?: string;
+    }) => this.inv("auth:switch-user", request),
     setupPin: (request: AuthSetupPinInput) =>
       this.inv("auth:setup-pin", buildAuthSetupPinArg(request)),
     confirmPrivilegedAction: (request: PrivilegedActionConfirmRequest) =>
@@ -2747,6 +2921,24 @@ export class TauriBridge implements PlatformBridge {
     create: (p: CreateOrderPayload) => this.inv("order:create", p),
     createWithInitialPayment: (p: CreateOrderPayload) =>
       this.inv("order:create-with-initial-payment", p),
+    createReturn: (p: {
+      originalOrderId: string;
+      items: Array<{ lineIndex: number; quantity: number }>;
+      reasonCode: string;
+      reason?: string;
+      restock?: boolean;
+      exchangeItems?: any[];
+    }) => this.inv("order:create-return", p),
+    estimatePrepTime: (p: {
+      items: any[];
+      orderType?: string;
+      openOrders?: number;
+    }) => this.inv("order:estimate-prep-time", p),
+    refreshPrices: (p: {
+      orderId: string;
+      confirm?: boolean;
+      expectedVersion?: number;
+    }) => this.inv("order:refresh-prices", p),
     updateStatus: (
       id: string,
       s: string,
@@ -2813,7 +3005,8 @@ export class TauriBridge implements PlatformBridge {
       this.inv("orders:resolve-conflict", cid, s, d),
     forceSyncRetry: (id: string) => this.inv("orders:force-sync-retry", id),
     getRetryInfo: (id: string) => this.inv("orders:get-retry-info", id),
-    clearAll: () => this.inv("orders:clear-all"),
+    clearAll: (c: DestructiveOpConfirmation) =>
+      this.inv("orders:clear-all", c),
   };
 
   payments = {
@@ -2860,7 +3053,8 @@ export class TauriBridge implements PlatformBridge {
     clearAll: () => this.inv("sync:clear-all"),
     clearFailed: () => this.inv("sync:clear-failed"),
     clearOldOrders: () => this.inv("sync:clear-old-orders"),
-    clearAllOrders: () => this.inv("sync:clear-all-orders"),
+    clearAllOrders: (c: DestructiveOpConfirmation) =>
+      this.inv("sync:clear-all-orders", c),
     cleanupDeletedOrders: () => this.inv("sync:cleanup-deleted-orders"),
     getFinancialStats: () => this.inv("sync:get-financial-stats"),
     getFailedFinancialItems: async (limit?: number) =>
@@ -2917,6 +3111,9 @@ export class TauriBridge implements PlatformBridge {
     resolveConflict: (cid: string, s: string, d?: any) =>
       this.inv("customer:resolve-conflict", cid, s, d),
     getConflicts: (f?: any) => this.inv("customer:get-conflicts", f),
+    importCsv: (options: any) => this.inv("customer:import-csv", options),
+    exportData: (target: any) => this.inv("customer:export-data", target),
+    erase: (target: any) => this.inv("customer:erase", target),
   };
 
   settings = {
@@ -2947,8 +3144,22 @@ export class TauriBridge implements PlatformBridge {
       this.inv("settings:update-terminal-credentials", p),
     isConfigured: () => this.inv("settings:is-configured"),
     getResetStatus: () => this.inv("settings:get-reset-status"),
-    factoryReset: () => this.inv("settings:factory-reset"),
+    prepareDestructiveOp: (op: DestructiveOperation) =>
+      this.inv("destructive-op:prepare", op),
+    factoryReset: (c: DestructiveOpConfirmation) =>
+      this.inv("settings:factory-reset", c),
     emergencyReset: () => this.inv("settings:emergency-reset"),
+    beginTerminalRepair: (connectionCode: string) =>
+      this.inv("terminal-repair:begin", { connectionCode }),
+    migrateTerminalRepair: (repairId?: string) =>
+      this.inv("terminal-repair:migrate", { repairId }),
+    discardTerminalRepair: (repairId?: string) =>
+      this.inv("terminal-repair:discard", { repairId }),
+    getOrganizationChange: () => this.inv("organization-change:status"),
+    confirmOrganizationChange: (params: {
+      action: "wipe" | "retag";
+      adminPin?: string;
+    }) => this.inv("organization-change:confirm", params),
   };
 
   terminalConfig = {
@@ -3107,6 +3318,14 @@ export class TauriBridge implements PlatformBridge {
     getEarnings: (id: string) => this.inv("driver:get-earnings", id),
     getShiftSummary: (id: string) => this.inv("driver:get-shift-summary", id),
     getActive: (branchId: string) => this.inv("driver:get-active", branchId),
+    startRun: (p: { driverId: string; orderIds: string[] }) =>
+      this.inv("driver:start-run", p),
+    completeRun: (p: {
+      runId: string;
+      orders?: Array<{ orderId: string; cashCollected?: number }>;
+    }) => this.inv("driver:complete-run", p),
+    getDispatchBoard: (branchId?: string) =>
+      this.inv("dispatch:get-board", branchId ? { branchId } : {}),
   };
 
   deliveryZones = {
@@ -3160,6 +3379,12 @@ export class TauriBridge implements PlatformBridge {
       this.inv("report:resolve-payment-blocker", p),
     submitZReport: (p: { branchId: string; date?: string }) =>
       this.inv("report:submit-z-report", p),
+    getMargin: (p: {
+      branchId?: string;
+      dateFrom?: string;
+      dateTo?: string;
+      varianceThresholdPercent?: number;
+    }) => this.inv("reports:get-margin", p),
   };
 
   menu = {
@@ -3178,6 +3403,15 @@ export class TauriBridge implements PlatformBridge {
       this.inv("menu:update-ingredient", id, u),
     updateCombo: (id: string, u: any) => this.inv("menu:update-combo", id, u),
     triggerCheckForUpdates: () => this.inv("menu:trigger-check-for-updates"),
+    getFavoritesGrid: (params?: { staffId?: string }) =>
+      this.inv("favorites:get-grid", params ?? {}),
+    setFavoritesGrid: (params: { grid: any; staffId?: string }) =>
+      this.inv("favorites:set-grid", params),
+    autofillFavorites: (params?: {
+      rows?: number;
+      cols?: number;
+      branchId?: string;
+    }) => this.inv("favorites:autofill", params ?? {}),
   };
 
   printer = {
@@ -3335,6 +3569,8 @@ export class TauriBridge implements PlatformBridge {
     saveConfig: (config: any) => this.inv("callerid:save-config", config),
     getConfig: () => this.inv("callerid:get-config"),
     testConnection: (config?: any) => this.inv("callerid:test", config),
+    configure: (config: any) => this.inv("callerid:configure", config),
+    simulateCall: (call: any) => this.inv("callerid:simulate-call", call),
   };
 
   loyalty = {
@@ -3499,6 +3735,30 @@ export class TauriBridge implements PlatformBridge {
       this.inv("refund:get-payment-balance", paymentId),
   };
 
+  tabs = {
+    open: (params: {
+      orderId: string;
+      holdAmount?: number;
+      deviceId?: string;
+      cardReference?: string;
+      cardLastFour?: string;
+      manual?: boolean;
+      currency?: string;
+      staffId?: string;
+      idempotencyKey?: string;
+    }) => this.inv("tab:open", params),
+    close: (params: {
+      orderId: string;
+      tipAmount?: number;
+      currency?: string;
+      staffId?: string;
+      staffShiftId?: string;
+      idempotencyKey?: string;
+    }) => this.inv("tab:close", params),
+    listOpen: (branchId?: string) =>
+      this.inv("tab:list-open", branchId ? { branchId } : {}),
+  };
+
   diagnostics = {
     getAbout: () => this.inv("diagnostics:get-about"),
     getSystemHealth: () => this.inv("diagnostics:get-system-health"),
@@ -3,6 +3,50 @@
  * These types are intentionally runtime-agnostic and reusable by any caller.
  */
 
+// -- Errors ------------------------------------------------------------------
+
+/**
+ * Serialized `PosError` (src-tauri/src/error.rs). Branch on `code`, show
+ * `message`. The code list is documented in that module; codes are only
+ * ever added, never renamed.
+ */
+export type PosErrorCode =
+  | 'NOT_FOUND'
+  | 'VALIDATION'
+  | 'UNAUTHORIZED'
+  | 'CONFLICT'
+  | 'NETWORK_ERROR'
+  | 'TERMINAL_AUTH'
+  | 'DATABASE'
+  | 'INTERNAL'
+  | 'KIOSK_MODE'
+  | 'UNSUPPORTED'
+  | 'UNAVAILABLE';
+
+export type RemediationAction =
+  | 'open_onboarding'
+  | 'retry_sync'
+  | 'check_network'
+  | 'contact_support'
+  | 'open_printer_settings';
+
+/** What staff can do about a failure; see `src-tauri/src/remediation.rs`. */
+export interface Remediation {
+  code: string;
+  /** i18n key under `errors.remediation`. */
+  messageKey: string;
+  action: RemediationAction;
+}
+
+export interface PosErrorPayload {
+  code: PosErrorCode | string;
+  message: string;
+  /** `{ field }` for VALIDATION, `{ reason }` for TERMINAL_AUTH, else null. */
+  details: Record<string, unknown> | null;
+  /** Present only when the failure has a known fix. */
+  remediation?: Remediation;
+}
+
 // -- Auth --------------------------------------------------------------------
 
 export interface AuthSetupPinRequest {
@@ -82,6 +126,70 @@ export interface ResetStartResponse {
   error?: string;
 }
 
+/** Commands that need a `destructive_op_prepare` token before they run. */
+export type DestructiveOperation =
+  | 'orders_clear_all'
+  | 'sync_clear_all_orders'
+  | 'settings_factory_reset';
+
+export interface DestructiveOpImpact {
+  orders: number;
+  unsyncedOrders: number;
+  pendingPayments: number;
+  openShifts: number;
+  pendingSyncItems: number;
+}
+
+export interface DestructiveOpPrepareResponse {
+  success: boolean;
+  operation: DestructiveOperation;
+  /** One-time token, valid for two minutes and only for `operation`. */
+  confirmationToken: string;
+  expiresAt: string;
+  impact: DestructiveOpImpact;
+}
+
+export interface DestructiveOpConfirmation {
+  confirmationToken: string;
+  /** Skip the pre-delete archive export. Recorded in the audit log. */
+  skipBackup?: boolean;
+}
+
+/** A re-pairing of a terminal whose credentials were invalidated. */
+export interface TerminalRepairSession {
+  repairId: string;
+  status: 'quarantined' | 'validated' | 'migrated' | 'discarded';
+  oldTerminalId: string | null;
+  oldBranchId: string | null;
+  newTerminalId: string | null;
+  newBranchId: string | null;
+  /** Quarantined open or unsynced orders. */
+  orders: number;
+  /** Quarantined sync queue rows. */
+  queueItems: number;
+}
+
+export interface TerminalRepairStep {
+  id: string;
+  complete: boolean;
+  errorCode?: string;
+  error?: string;
+}
+
+export interface TerminalRepairBeginResponse {
+  success: boolean;
+  steps: TerminalRepairStep[];
+  repair: TerminalRepairSession;
+}
+
+export interface TerminalRepairFinishResponse {
+  success: boolean;
+  orders: number;
+  queueItems?: number;
+  archiveFiles?: string[];
+  repair: TerminalRepairSession;
+}
+
 export interface ResetStatus {
   operationId: string;
   mode: string;
@@ -494,6 +602,8 @@ export interface DiagnosticsSyncStatusSummary {
   oldestNextRetryAt?: string | null;
   lastQueueFailure?: Record<string, unknown> | null;
   historicalZReportConflicts: number;
+  pendingOrderItems?: number;
+  failedOrderItems?: number;
   pendingPaymentItems: number;
   failedPaymentItems: number;
   financialStats?: Record<string, unknown>;
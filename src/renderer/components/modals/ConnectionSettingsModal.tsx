@@ -34,6 +34,7 @@ import {
 import { getErrorMessage } from '../../utils/privileged-actions';
 import {
   getResetStartingMessage,
+  prepareDestructiveOp,
   startResetAction,
 } from '../../utils/reset-actions';
 
@@ -790,7 +791,11 @@ const ConnectionSettingsModal: React.FC<Props> = ({ isOpen, onClose, initialSect
     try {
       const result = await runWithPrivilegedConfirmation({
         scope: 'system_control',
-        action: () => startResetAction(() => bridge.settings.factoryReset(), t),
+        action: async () => {
+          const confirmation = await prepareDestructiveOp('settings_factory_reset', t)
+          if (!confirmation) return { success: false, cancelled: true }
+          return startResetAction(() => bridge.settings.factoryReset(confirmation), t)
+        },
         title: t('settings.database.factoryResetPinTitle', 'Confirm factory reset'),
         subtitle: t(
           'settings.database.factoryResetPinSubtitle',
@@ -798,6 +803,9 @@ const ConnectionSettingsModal: React.FC<Props> = ({ isOpen, onClose, initialSect
         ),
       })
 
+      if ((result as any)?.cancelled) {
+        return
+      }
       if (result?.success) {
         resetStarted = true
         // Clear all localStorage
@@ -1887,10 +1895,15 @@ const ConnectionSettingsModal: React.FC<Props> = ({ isOpen, onClose, initialSect
                           // Gap review P0: clear-all-orders now requires SystemControl.
                           const result = await runWithPrivilegedConfirmation({
                             scope: 'system_control',
-                            action: () => bridge.sync.clearAllOrders(),
+                            action: async () => {
+                              const confirmation = await prepareDestructiveOp('sync_clear_all_orders', t)
+                              if (!confirmation) return { success: false, cancelled: true }
+                              return bridge.sync.clearAllOrders(confirmation)
+                            },
                             title: t('settings.database.clearAllOrdersPinTitle', 'Confirm delete all orders'),
                             subtitle: t('settings.database.clearAllOrdersPinSubtitle', 'Enter the admin PIN to permanently delete all orders.'),
                           }) as any
+                          if (result?.cancelled) return
                           if (result?.success) {
                             toast.success(t('settings.database.allOrdersCleared', { count: result.cleared, defaultValue: 'Cleared {{count}} orders' }))
                           } else {
@@ -121,6 +121,9 @@ interface PrinterStatus {
   transportReachable?: boolean
   supportsLogo?: boolean
   supportsCut?: boolean
+  paperOut?: boolean
+  paperNearEnd?: boolean
+  coverOpen?: boolean
 }
 
 interface DiscoveredPrinter {
@@ -1,6 +1,11 @@
 import type { TFunction } from 'i18next';
 import { emitCompatEvent, getBridge } from '../../lib';
-import type { ResetStartResponse, ResetStatus } from '../../lib/ipc-contracts';
+import type {
+  DestructiveOpConfirmation,
+  DestructiveOperation,
+  ResetStartResponse,
+  ResetStatus,
+} from '../../lib/ipc-contracts';
 import { withTimeout } from '../../shared/utils/error-handler';
 
 const RESET_START_TIMEOUT_MS = 4000;
@@ -185,6 +190,34 @@ export async function startResetAction(
   }
 }
 
+/**
+ * First step of a destructive operation: fetch its confirmation token and,
+ * when unsynced work would be lost, make the operator confirm the counts.
+ * Returns null when the operator backs out.
+ */
+export async function prepareDestructiveOp(
+  operation: DestructiveOperation,
+  t: TFunction,
+): Promise<DestructiveOpConfirmation | null> {
+  const prepared = await getBridge().settings.prepareDestructiveOp(operation);
+  const { unsyncedOrders, pendingPayments, openShifts } = prepared.impact;
+  if (unsyncedOrders + pendingPayments + openShifts > 0) {
+    const proceed = window.confirm(
+      t('settings.database.destructiveImpactConfirm', {
+        unsyncedOrders,
+        pendingPayments,
+        openShifts,
+        defaultValue:
+          'This will delete {{unsyncedOrders}} unsynced orders, {{pendingPayments}} unsynced payments and {{openShifts}} open shifts. An archive is written first. Continue?',
+      }),
+    );
+    if (!proceed) {
+      return null;
+    }
+  }
+  return { confirmationToken: prepared.confirmationToken };
+}
+
 export function getResetStartingMessage(t: TFunction): string {
   return t(
     'settings.database.resetStarting',
@@ -413,7 +413,7 @@ test('Round 325: Data section is three zones — recovery, green safe fixes, the
 
   // Expanded region keeps the three destructive actions + their EXACT handlers/confirm chain + red buttons.
   assert.equal((expanded.match(/className=\{DB_DANGER_BTN_MD\}/g) || []).length, 3, 'three destructive buttons share the red class');
-  assert.match(expanded, /bridge\.sync\.clearAllOrders\(\)/);
+  assert.match(expanded, /bridge\.sync\.clearAllOrders\(confirmation\)/);
   assert.match(expanded, /onClick=\{\(\) => setShowClearOperationalConfirm\(true\)\}/);
   assert.match(expanded, /onClick=\{handleClearDatabase\}/);
   assert.match(expanded, /t\('settings\.database\.dangerDeleteOrdersButton'/);